@@ -0,0 +1,146 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Bytes, Json},
+};
+use dto::account::{AccountClosureDisplay, CloseAccountRequest, ReactivateAccountRequest};
+use error::error::ApiError;
+use futures_util::StreamExt;
+use sea_orm::DatabaseConnection;
+use security::token_service::TokenService;
+use security::TokenDenylist;
+use serde_json::json;
+use service::account::AccountService;
+use validator::Validate;
+
+use crate::archive::ExportLimiter;
+use crate::config::AppConfig;
+use crate::guard::{claims, current_player};
+use crate::replicas::ReadReplicas;
+
+#[utoipa::path(
+    get,
+    path = "/v1/account/export",
+    responses(
+        (status = 200, description = "ZIP holding profile.json, games.pgn, messages.json and sessions.json", content_type = "application/zip", body = String),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 429, description = "The caller exported an archive too recently", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[get("/export")]
+pub async fn export_personal_data(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    replicas: web::Data<ReadReplicas>,
+    limiter: web::Data<ExportLimiter>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    let session_owner = match claims(&req) {
+        Ok(claims) => claims.user_id,
+        Err(err) => return err.error_response(),
+    };
+
+    if let Err(wait) = limiter.try_acquire(player.id) {
+        return ApiError::TooManyRequests(format!("Try again in {} seconds", wait)).error_response();
+    }
+
+    let body = AccountService::export(replicas.read(), player, session_owner)
+        .map(|chunk| chunk.map(Bytes::from).map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string())));
+
+    HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(("Content-Disposition", "attachment; filename=\"starkmate-data.zip\""))
+        .streaming(body)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/account/close",
+    request_body = CloseAccountRequest,
+    responses(
+        (status = 200, description = "Account disabled; personal data is removed after the grace period", body = AccountClosureDisplay),
+        (status = 400, description = "Account is already closed", body = InvalidCredentialsResponse),
+        (status = 401, description = "Wrong password or authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[post("/close")]
+pub async fn close_account(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<AppConfig>,
+    payload: Json<CloseAccountRequest>,
+    denylist: Option<web::Data<TokenDenylist>>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    let session_owner = match claims(&req) {
+        Ok(claims) => claims.user_id,
+        Err(err) => return err.error_response(),
+    };
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let grace = chrono::Duration::days(config.account_closure_grace_days as i64);
+    let closure = match AccountService::close(db.get_ref(), &player, &payload.password, grace).await {
+        Ok(closure) => closure,
+        Err(err) => return err.error_response(),
+    };
+
+    // The account is closed either way; sessions that cannot be revoked expire on their own
+    if let Err(e) = TokenService::revoke_player_tokens(db.get_ref(), session_owner).await {
+        log::error!("Failed to revoke tokens of a closed account: {}", e);
+    }
+    if let Some(denylist) = denylist {
+        if let Err(e) = denylist.revoke_account(&player.username).await {
+            log::error!("Failed to revoke access tokens of a closed account: {}", e);
+        }
+    }
+
+    HttpResponse::Ok().json(json!({
+        "message": "Account closed",
+        "data": AccountClosureDisplay::from(closure)
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/auth/reactivate",
+    request_body = ReactivateAccountRequest,
+    responses(
+        (status = 200, description = "Account reopened; log in again to get tokens"),
+        (status = 400, description = "Account is not closed", body = InvalidCredentialsResponse),
+        (status = 401, description = "Invalid credentials", body = InvalidCredentialsResponse),
+        (status = 403, description = "The grace period is over", body = InvalidCredentialsResponse)
+    ),
+    tag = "Authentication"
+)]
+#[post("/reactivate")]
+pub async fn reactivate_account(
+    db: web::Data<DatabaseConnection>,
+    payload: Json<ReactivateAccountRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    match AccountService::reactivate(db.get_ref(), &payload.username, &payload.password).await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "message": "Account reactivated",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
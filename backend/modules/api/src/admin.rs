@@ -0,0 +1,269 @@
+//! Operator commands of `starkmate-admin`, run against the database and
+//! Redis the API servers use, so common tasks need no hand-written SQL.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dto::moderation::PlayerRole;
+use dto::tournaments::CreateTournamentRequest;
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use security::{RedisRevocationStore, TokenDenylist};
+use service::fixtures::{FixtureService, FIXTURE_PASSWORD};
+use service::game_archive::GameArchiveService;
+use service::moderation::ModerationService;
+use service::positions::PositionIndexService;
+use service::replay::ReplayService;
+use service::tournaments::TournamentService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::cache::{CacheTtls, RedisCacheStore, ResponseCache};
+use crate::config::AppConfig;
+
+pub const USAGE: &str = "\
+Usage: starkmate-admin <command>
+
+Commands:
+  create-tournament <request.json> --arbiter <username>
+      Create a tournament from a POST /v1/tournaments request body
+  grant-role <username> <moderator|arbiter|admin>
+      Grant a role to a player
+  revoke-tokens <username>
+      Revoke every access token issued to a player so far
+  reanalyze <game-id>
+      Replay a game against its stored position and index its positions again
+  archive [--older-than-days <days>] [--batch <games>]
+      Move finished games to the archive now, in batches until none are left
+  seed-fixtures
+      Load sample players, games and a tournament; needs ENABLE_FIXTURES=true";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    CreateTournament { request: String, arbiter: String },
+    GrantRole { username: String, role: PlayerRole },
+    RevokeTokens { username: String },
+    Reanalyze { game_id: Uuid },
+    Archive { older_than_days: Option<i64>, batch: Option<u64> },
+    SeedFixtures,
+}
+
+impl Command {
+    /// The command named by `args`, the program name left out.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let name = args.next().ok_or("No command given")?;
+        let (positional, mut options) = split(args)?;
+
+        let command = match (name.as_str(), positional.as_slice()) {
+            ("create-tournament", [request]) => Command::CreateTournament {
+                request: request.clone(),
+                arbiter: options.remove("arbiter").ok_or("create-tournament needs --arbiter")?,
+            },
+            ("grant-role", [username, role]) => Command::GrantRole {
+                username: username.clone(),
+                role: serde_json::from_value(serde_json::Value::String(role.clone()))
+                    .map_err(|_| format!("Unknown role '{}'", role))?,
+            },
+            ("revoke-tokens", [username]) => Command::RevokeTokens { username: username.clone() },
+            ("reanalyze", [game_id]) => Command::Reanalyze {
+                game_id: Uuid::parse_str(game_id).map_err(|_| format!("'{}' is not a game id", game_id))?,
+            },
+            ("archive", []) => Command::Archive {
+                older_than_days: number(options.remove("older-than-days"), "--older-than-days")?,
+                batch: number(options.remove("batch"), "--batch")?,
+            },
+            ("seed-fixtures", []) => Command::SeedFixtures,
+            ("create-tournament" | "grant-role" | "revoke-tokens" | "reanalyze" | "archive" | "seed-fixtures", _) => {
+                return Err(format!("Wrong arguments for {}", name))
+            }
+            _ => return Err(format!("Unknown command '{}'", name)),
+        };
+        if let Some(option) = options.keys().next() {
+            return Err(format!("Unknown option --{} for {}", option, name));
+        }
+        Ok(command)
+    }
+}
+
+/// Positional arguments and `--name value` options.
+fn split(mut args: impl Iterator<Item = String>) -> Result<(Vec<String>, HashMap<String, String>), String> {
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(option) => {
+                let value = args.next().ok_or_else(|| format!("--{} needs a value", option))?;
+                options.insert(option.to_string(), value);
+            }
+            None => positional.push(arg),
+        }
+    }
+    Ok((positional, options))
+}
+
+fn number<T: std::str::FromStr>(value: Option<String>, option: &str) -> Result<Option<T>, String> {
+    value
+        .map(|value| value.parse().map_err(|_| format!("{} must be a number", option)))
+        .transpose()
+}
+
+/// Run `command`, printing what it did.
+pub async fn run(db: &DatabaseConnection, config: &AppConfig, command: Command) -> Result<(), String> {
+    match command {
+        Command::CreateTournament { request, arbiter } => {
+            let body = std::fs::read_to_string(&request).map_err(|e| format!("Cannot read {}: {}", request, e))?;
+            let request: CreateTournamentRequest =
+                serde_json::from_str(&body).map_err(|e| format!("Invalid tournament request: {}", e))?;
+            request.validate().map_err(|errors| ApiError::ValidationError(errors).to_string())?;
+            let arbiter = player(db, &arbiter).await?;
+            let tournament = TournamentService::create(db, arbiter.id, request).await.map_err(|e| e.to_string())?;
+            println!("Created tournament {} ({})", tournament.id, tournament.name);
+        }
+        Command::GrantRole { username, role } => {
+            let player = player(db, &username).await?;
+            let granted = ModerationService::grant_role(db, player.id, role.into(), None)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{} holds the {:?} role", username, granted.role);
+        }
+        Command::RevokeTokens { username } => {
+            // A denylist in this process would not reach the running servers
+            let url = config
+                .token_denylist_redis_url
+                .as_deref()
+                .ok_or("TOKEN_DENYLIST_REDIS_URL must be set to revoke tokens")?;
+            let store = RedisRevocationStore::new(url).map_err(|e| e.to_string())?;
+            player(db, &username).await?;
+            let denylist = TokenDenylist::new(Arc::new(store), jwt_expiration(), Duration::ZERO);
+            denylist.revoke_account(&username).await.map_err(|e| e.to_string())?;
+            println!("Revoked the access tokens of {}", username);
+        }
+        Command::Reanalyze { game_id } => {
+            let verification = ReplayService::verify(db, game_id).await.map_err(|e| e.to_string())?;
+            println!(
+                "Replayed {} plies of {}: {}",
+                verification.plies_replayed,
+                game_id,
+                if verification.consistent { "consistent" } else { "diverged" }
+            );
+            for divergence in &verification.divergences {
+                println!("  {:?} at ply {:?}: {}", divergence.kind, divergence.ply, divergence.detail);
+            }
+
+            let game = GameArchiveService::find(db, game_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| ApiError::NotFound("Game".to_string()).to_string())?;
+            PositionIndexService::reindex_game(db, &game).await.map_err(|e| e.to_string())?;
+            if let Some(cache) = shared_cache(config) {
+                cache.positions_indexed().await;
+            }
+            println!("Indexed the positions of {} again", game_id);
+        }
+        Command::Archive { older_than_days, batch } => {
+            let days = older_than_days.unwrap_or(config.game_archive_after_days);
+            if days <= 0 {
+                return Err("Games must be at least a day old to be archived".to_string());
+            }
+            let batch = batch.unwrap_or(config.game_archive_batch).max(1);
+            let cutoff = chrono::Utc::now().fixed_offset() - chrono::Duration::days(days);
+            let mut archived = 0;
+            loop {
+                let moved = GameArchiveService::run_pass(db, cutoff, batch).await.map_err(|e| e.to_string())?;
+                archived += moved;
+                if moved < batch {
+                    break;
+                }
+            }
+            println!("Archived {} games finished more than {} days ago", archived, days);
+        }
+        Command::SeedFixtures => {
+            if !config.fixtures_enabled {
+                return Err("Fixtures are for development databases; set ENABLE_FIXTURES=true to load them".to_string());
+            }
+            let summary = FixtureService::load(db).await.map_err(|e| e.to_string())?;
+            println!(
+                "Loaded {} players with {} rating points, {} finished and {} ongoing games and tournament {}",
+                summary.players, summary.rating_points, summary.finished_games, summary.ongoing_games, summary.tournament_id
+            );
+            println!("Fixture players sign in with the password '{}'", FIXTURE_PASSWORD);
+        }
+    }
+    Ok(())
+}
+
+async fn player(db: &DatabaseConnection, username: &str) -> Result<db_entity::player::Model, String> {
+    ModerationService::find_player_by_username(db, username)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No player named '{}'", username))
+}
+
+/// Lifetime of access tokens, read like the server reads it.
+fn jwt_expiration() -> u64 {
+    std::env::var("JWT_EXPIRATION_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(3600)
+}
+
+/// The response cache the servers share, when it is kept in Redis; caches
+/// of single instances are out of reach and expire on their own.
+fn shared_cache(config: &AppConfig) -> Option<ResponseCache> {
+    let url = config.cache_redis_url.as_deref()?;
+    match RedisCacheStore::new(url) {
+        Ok(store) => Some(ResponseCache::new(
+            Arc::new(store),
+            CacheTtls {
+                leaderboard: Duration::from_secs(config.cache_leaderboard_secs),
+                standings: Duration::from_secs(config.cache_standings_secs),
+                position_search: Duration::from_secs(config.cache_position_search_secs),
+                analysis: Duration::from_secs(config.cache_analysis_secs),
+            },
+        )),
+        Err(e) => {
+            eprintln!("Invalid CACHE_REDIS_URL '{}': {}; cached responses expire on their own", url, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_commands_are_parsed_with_their_options() {
+        assert_eq!(
+            parse(&["create-tournament", "blitz.json", "--arbiter", "alice"]),
+            Ok(Command::CreateTournament { request: "blitz.json".to_string(), arbiter: "alice".to_string() })
+        );
+        assert_eq!(
+            parse(&["grant-role", "bob", "arbiter"]),
+            Ok(Command::GrantRole { username: "bob".to_string(), role: PlayerRole::Arbiter })
+        );
+        assert_eq!(
+            parse(&["archive", "--batch", "100"]),
+            Ok(Command::Archive { older_than_days: None, batch: Some(100) })
+        );
+        assert_eq!(parse(&["seed-fixtures"]), Ok(Command::SeedFixtures));
+    }
+
+    #[test]
+    fn test_bad_arguments_are_refused() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["drop-database"]).is_err());
+        assert!(parse(&["create-tournament", "blitz.json"]).is_err());
+        assert!(parse(&["grant-role", "bob", "king"]).is_err());
+        assert!(parse(&["reanalyze", "not-a-uuid"]).is_err());
+        assert!(parse(&["archive", "--batch", "many"]).is_err());
+        assert!(parse(&["revoke-tokens", "bob", "--force", "yes"]).is_err());
+        assert!(parse(&["seed-fixtures", "extra"]).is_err());
+    }
+}
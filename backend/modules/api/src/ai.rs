@@ -1,25 +1,67 @@
 use actix_web::{
-    HttpResponse, post,
-    web::Json,
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path},
 };
+use db_entity::player_role::Role;
 use dto::{
-    ai::{AiSuggestionRequest, AiSuggestionResponse, PositionAnalysisRequest, PositionAnalysisResponse},
+    ai::{
+        AiSuggestionRequest, AiSuggestionResponse, AnalysisTier, BotMoveRequest, EngineBenchmarkRequest, PositionAnalysisRequest,
+        PositionAnalysisResponse, StartBotGameRequest,
+    },
     responses::ValidationErrorResponse,
 };
 use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use security::JwtService;
 use serde_json::json;
 use validator::Validate;
 
-use service::engine_service::EngineService;
-use std::env;
+use service::bots::BotService;
+use service::engine_service::{BENCH_DEPTH, EngineError, EngineService, JobPriority, PreparedEngine, engine_display};
+
+use crate::cache::{CacheKey, ResponseCache};
+use crate::config::AppConfig;
+use crate::guard::require_role;
+
+// Callers are anonymous, so the per-user engine limit applies per address
+fn caller_key(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Signed-in players keep more of their search while the engines are busy,
+// and the players named in the config all of it
+fn caller_tier(req: &HttpRequest, jwt_service: &JwtService, config: &AppConfig) -> AnalysisTier {
+    let claims = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(JwtService::extract_token_from_header)
+        .and_then(|token| jwt_service.validate_token(&token).ok());
+    match claims {
+        Some(claims) if claims.guest => AnalysisTier::Guest,
+        Some(claims) if config.premium_analysis_players.contains(&claims.username) => AnalysisTier::Premium,
+        Some(_) => AnalysisTier::Player,
+        None => AnalysisTier::Guest,
+    }
+}
+
+fn unsupported_variant(variant: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ValidationErrorResponse {
+        error: format!("No engine analyses {}", variant),
+        code: 400,
+        details: None,
+    })
+}
 
 #[utoipa::path(
     post,
     path = "/v1/ai/suggest",
     request_body = AiSuggestionRequest,
     responses(
-        (status = 200, description = "AI suggestion generated", body = AiSuggestionResponse),
-        (status = 400, description = "Invalid FEN position", body = ValidationErrorResponse)
+        (status = 200, description = "AI suggestion generated; depth and time are cut back while the engines are busy, less so for signed-in players", body = AiSuggestionResponse),
+        (status = 400, description = "Invalid FEN position, or no engine for the variant", body = ValidationErrorResponse)
     ),
     security(
         ("jwt_auth" = [])
@@ -27,14 +69,22 @@ use std::env;
     tag = "AI"
 )]
 #[post("/suggest")]
-pub async fn get_ai_suggestion(payload: Json<AiSuggestionRequest>) -> HttpResponse {
+pub async fn get_ai_suggestion(
+    req: HttpRequest,
+    engine_service: web::Data<EngineService>,
+    jwt_service: web::Data<JwtService>,
+    config: web::Data<AppConfig>,
+    payload: Json<AiSuggestionRequest>,
+) -> HttpResponse {
     match payload.0.validate() {
         Ok(_) => {
-            let engine_path = env::var("ENGINE_PATH").unwrap_or_else(|_| "stockfish".to_string());
-            let engine_service = EngineService::new(engine_path);
-            
             let start_time = std::time::Instant::now();
             let result = engine_service.get_suggestion(
+                &caller_key(&req),
+                JobPriority::Interactive,
+                caller_tier(&req, &jwt_service, &config),
+                payload.0.variant,
+                payload.0.game_id,
                 &payload.0.fen,
                 payload.0.depth,
                 payload.0.time_limit_ms
@@ -51,6 +101,7 @@ pub async fn get_ai_suggestion(payload: Json<AiSuggestionRequest>) -> HttpRespon
                         computation_time_ms: elapsed,
                     })
                 }
+                Err(EngineError::UnsupportedVariant(variant)) => unsupported_variant(&variant),
                 Err(e) => {
                     log::error!("Engine error in get_ai_suggestion: {}", e);
                     HttpResponse::InternalServerError().json(json!({
@@ -80,8 +131,8 @@ pub async fn get_ai_suggestion(payload: Json<AiSuggestionRequest>) -> HttpRespon
     path = "/v1/ai/analyze",
     request_body = PositionAnalysisRequest,
     responses(
-        (status = 200, description = "Position analysis completed", body = PositionAnalysisResponse),
-        (status = 400, description = "Invalid FEN position", body = ValidationErrorResponse)
+        (status = 200, description = "Position analysis completed; the depth is cut back while the engines are busy, less so for signed-in players", body = PositionAnalysisResponse),
+        (status = 400, description = "Invalid FEN position, or no engine for the variant", body = ValidationErrorResponse)
     ),
     security(
         ("jwt_auth" = [])
@@ -89,21 +140,53 @@ pub async fn get_ai_suggestion(payload: Json<AiSuggestionRequest>) -> HttpRespon
     tag = "AI"
 )]
 #[post("/analyze")]
-pub async fn analyze_position(payload: Json<PositionAnalysisRequest>) -> HttpResponse {
+pub async fn analyze_position(
+    req: HttpRequest,
+    engine_service: web::Data<EngineService>,
+    jwt_service: web::Data<JwtService>,
+    config: web::Data<AppConfig>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<PositionAnalysisRequest>,
+) -> HttpResponse {
     match payload.0.validate() {
         Ok(_) => {
-            let engine_path = env::var("ENGINE_PATH").unwrap_or_else(|_| "stockfish".to_string());
-            let engine_service = EngineService::new(engine_path);
-            
-            match engine_service.analyze_position(&payload.0.fen, payload.0.depth).await {
+            let key = CacheKey::Analysis {
+                variant: payload.0.variant,
+                fen: payload.0.fen.clone(),
+                depth: payload.0.depth,
+            };
+            if let Some(analysis) = cache.get::<PositionAnalysisResponse>(&key).await {
+                return HttpResponse::Ok().json(analysis);
+            }
+
+            let tier = caller_tier(&req, &jwt_service, &config);
+            match engine_service
+                .analyze_position(
+                    &caller_key(&req),
+                    JobPriority::Interactive,
+                    tier,
+                    payload.0.variant,
+                    payload.0.game_id,
+                    &payload.0.fen,
+                    payload.0.depth,
+                )
+                .await
+            {
                 Ok(result) => {
-                    HttpResponse::Ok().json(PositionAnalysisResponse {
+                    // Searches cut back for load are not kept for other callers
+                    let full_depth = result.depth.is_some_and(|depth| depth >= payload.0.depth);
+                    let analysis = PositionAnalysisResponse {
                         evaluation: result.evaluation.unwrap_or(0.0),
                         best_line: result.principal_variation,
                         alternatives: vec![], // Engine trait could be extended for multi-pv
                         position_type: "Analyzed by Engine".to_string(),
-                    })
+                    };
+                    if full_depth {
+                        cache.put(&key, &analysis).await;
+                    }
+                    HttpResponse::Ok().json(analysis)
                 }
+                Err(EngineError::UnsupportedVariant(variant)) => unsupported_variant(&variant),
                 Err(e) => {
                     log::error!("Engine error in analyze_position: {}", e);
                     HttpResponse::InternalServerError().json(json!({
@@ -127,3 +210,203 @@ pub async fn analyze_position(payload: Json<PositionAnalysisRequest>) -> HttpRes
         }
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/v1/ai/queue",
+    responses(
+        (status = 200, description = "Engine slots in use, queued jobs and their wait times by priority", body = EngineQueueStats),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "AI"
+)]
+#[get("")]
+pub async fn get_engine_queue(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    engine_service: web::Data<EngineService>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    match engine_service.queue_stats().await {
+        Ok(queue) => HttpResponse::Ok().json(json!({
+            "message": "Engine queue",
+            "data": { "queue": queue }
+        })),
+        Err(e) => {
+            log::error!("Engine error in get_engine_queue: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/ai/benchmark",
+    request_body = EngineBenchmarkRequest,
+    responses(
+        (status = 200, description = "Time and nodes per position of a fixed set of positions searched to a fixed depth, on the analysis engine and each variant engine", body = Vec<EngineBenchmarkDisplay>),
+        (status = 400, description = "Invalid depth", body = ValidationErrorResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "AI"
+)]
+#[post("")]
+pub async fn run_engine_benchmark(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    engine_service: web::Data<EngineService>,
+    payload: Json<EngineBenchmarkRequest>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let depth = payload.depth.unwrap_or(BENCH_DEPTH);
+    match engine_service.benchmark(depth).await {
+        Ok(engines) => HttpResponse::Ok().json(json!({
+            "message": "Engine benchmark",
+            "data": { "engines": engines }
+        })),
+        Err(e) => {
+            log::error!("Engine error in run_engine_benchmark: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/ai/engines",
+    responses(
+        (status = 200, description = "The analysis engine, the engines for variants, then the match engines by name, as each identified itself; engines that fail to start are left out", body = Vec<EngineDisplay>)
+    ),
+    tag = "AI"
+)]
+#[get("/engines")]
+pub async fn list_engines(engine_service: web::Data<EngineService>, config: web::Data<AppConfig>) -> HttpResponse {
+    let mut engines = Vec::new();
+    match engine_service.engine_info().await {
+        Ok(info) => engines.push(engine_display("analysis", info)),
+        Err(e) => log::error!("Engine error in list_engines: {}", e),
+    }
+    for (variant, engine) in engine_service.variant_engines() {
+        match engine_service.identify(engine).await {
+            Ok(info) => engines.push(engine_display(&format!("analysis-{}", variant.as_str()), info)),
+            Err(e) => log::error!("Engine for {} failed to identify itself: {}", variant.as_str(), e),
+        }
+    }
+
+    let mut names: Vec<&String> = config.match_engines.keys().collect();
+    names.sort();
+    for name in names {
+        let engine = PreparedEngine::from_path(&config.match_engines[name]);
+        match engine_service.identify(&engine).await {
+            Ok(info) => engines.push(engine_display(name, info)),
+            Err(e) => log::error!("Match engine '{}' failed to identify itself: {}", name, e),
+        }
+    }
+
+    HttpResponse::Ok().json(json!({
+        "message": "Engines",
+        "data": { "engines": engines }
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/ai/bots",
+    responses(
+        (status = 200, description = "Bot opponents, weakest first", body = Vec<BotDisplay>)
+    ),
+    tag = "AI"
+)]
+#[get("/bots")]
+pub async fn list_bots() -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "message": "Bots",
+        "data": { "bots": BotService::list() }
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/ai/bots/games",
+    request_body = StartBotGameRequest,
+    responses(
+        (status = 201, description = "Unrated game against the bot; includes the bot's first move when it has White", body = BotGameDisplay),
+        (status = 400, description = "Invalid time control", body = ValidationErrorResponse),
+        (status = 404, description = "Unknown bot", body = NotFoundResponse),
+        (status = 502, description = "The bot's engine failed")
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "AI"
+)]
+#[post("/bots/games")]
+pub async fn start_bot_game(
+    req: HttpRequest,
+    engine_service: web::Data<EngineService>,
+    payload: Json<StartBotGameRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    match BotService::start_game(engine_service.get_ref(), &caller_key(&req), payload.into_inner()).await {
+        Ok(game) => HttpResponse::Created().json(json!({
+            "message": "Game created successfully",
+            "data": { "game": game }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/ai/bots/{id}/move",
+    params(
+        ("id" = String, Path, description = "Bot ID, e.g. casual")
+    ),
+    request_body = BotMoveRequest,
+    responses(
+        (status = 200, description = "The bot's reply", body = BotMoveResponse),
+        (status = 400, description = "Invalid FEN position, or the game is over in it", body = ValidationErrorResponse),
+        (status = 404, description = "Unknown bot", body = NotFoundResponse),
+        (status = 502, description = "The bot's engine failed")
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "AI"
+)]
+#[post("/bots/{id}/move")]
+pub async fn bot_move(
+    req: HttpRequest,
+    id: Path<String>,
+    engine_service: web::Data<EngineService>,
+    payload: Json<BotMoveRequest>,
+) -> HttpResponse {
+    match BotService::next_move(engine_service.get_ref(), &caller_key(&req), &id, &payload.0).await {
+        Ok(chosen) => HttpResponse::Ok().json(chosen),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,47 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get,
+    web::{self, Path},
+};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::analysis::GameAnalysisService;
+use uuid::Uuid;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/{id}/analysis",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Time usage of both players by phase, with their longest thinks and the clock at the mistakes marked in annotations visible to the caller", body = GameAnalysisDisplay),
+        (status = 400, description = "The game is not finished", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("")]
+pub async fn get_game_analysis(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let viewer = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match GameAnalysisService::report(db.get_ref(), id.into_inner(), viewer.id).await {
+        Ok(report) => HttpResponse::Ok().json(json!({
+            "message": "Game analysis retrieved successfully",
+            "data": report
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,153 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, put,
+    web::{self, Json, Path},
+};
+use dto::annotations::SaveAnnotationsRequest;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::annotations::AnnotationService;
+use uuid::Uuid;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/{id}/annotations",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Annotations of the game visible to the caller, their own first", body = Vec<AnnotationDisplay>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("")]
+pub async fn list_annotations(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let viewer = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match AnnotationService::list_visible(db.get_ref(), id.into_inner(), viewer.id).await {
+        Ok(annotations) => HttpResponse::Ok().json(json!({
+            "message": "Annotations found",
+            "data": { "annotations": annotations }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/games/{id}/annotations",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    request_body = SaveAnnotationsRequest,
+    responses(
+        (status = 200, description = "Annotations saved, replacing the caller's earlier ones", body = AnnotationDisplay),
+        (status = 400, description = "Game not finished, unknown ply or illegal variation", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the players of the game can annotate it", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[put("")]
+pub async fn save_annotations(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<SaveAnnotationsRequest>,
+) -> HttpResponse {
+    let author = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match AnnotationService::save(db.get_ref(), id.into_inner(), &author, payload.into_inner()).await {
+        Ok(annotations) => HttpResponse::Ok().json(json!({
+            "message": "Annotations saved",
+            "data": { "annotations": annotations }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/games/{id}/annotations",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "The caller's annotations of the game deleted"),
+        (status = 404, description = "No annotations by the caller on this game", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[delete("")]
+pub async fn delete_annotations(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let author = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match AnnotationService::delete(db.get_ref(), id.into_inner(), author.id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Annotations deleted",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/{id}/annotations/pgn",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "PGN of the game with every annotation visible to the caller merged in", content_type = "application/x-chess-pgn", body = String),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("/pgn")]
+pub async fn export_annotated_pgn(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let viewer = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match AnnotationService::export_pgn(db.get_ref(), id.into_inner(), viewer.id).await {
+        Ok(pgn) => HttpResponse::Ok().content_type("application/x-chess-pgn").body(pgn),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,127 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get,
+    web::{self, Bytes, Path, Query},
+};
+use db_entity::player_role::Role;
+use dto::games::{ArchiveFormat, ArchiveQuery};
+use error::error::ApiError;
+use futures_util::StreamExt;
+use sea_orm::DatabaseConnection;
+use service::archive::ArchiveService;
+use service::moderation::ModerationService;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::guard::current_player;
+use crate::replicas::ReadReplicas;
+
+/// Per-player cooldown between archive exports, shared by every worker.
+#[derive(Debug, Clone)]
+pub struct ExportLimiter {
+    last_export: Arc<Mutex<HashMap<Uuid, Instant>>>,
+    cooldown: Duration,
+}
+
+impl ExportLimiter {
+    pub fn new(cooldown: Duration) -> Self {
+        ExportLimiter {
+            last_export: Arc::new(Mutex::new(HashMap::new())),
+            cooldown,
+        }
+    }
+
+    /// Record an export by `player_id`, or refuse it with the seconds left
+    /// until the next one is allowed.
+    pub fn try_acquire(&self, player_id: Uuid) -> Result<(), u64> {
+        let mut last_export = self.last_export.lock().unwrap();
+        let now = Instant::now();
+        last_export.retain(|_, at| now.duration_since(*at) < self.cooldown);
+        if let Some(at) = last_export.get(&player_id) {
+            let wait = self.cooldown - now.duration_since(*at);
+            return Err(wait.as_secs().max(1));
+        }
+        last_export.insert(player_id, now);
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/players/{id}/export",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid"),
+        ("format" = Option<ArchiveFormat>, Query, description = "pgn (default) or zip")
+    ),
+    responses(
+        (status = 200, description = "Every game of the player, newest first, streamed as PGN or as a ZIP holding games.pgn", content_type = "application/x-chess-pgn", body = String),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the player or an admin can export the games", body = InvalidCredentialsResponse),
+        (status = 429, description = "The caller exported an archive too recently", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[get("")]
+pub async fn export_games(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    replicas: web::Data<ReadReplicas>,
+    limiter: web::Data<ExportLimiter>,
+    id: Path<Uuid>,
+    query: Query<ArchiveQuery>,
+) -> HttpResponse {
+    let caller = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let player_id = id.into_inner();
+    if caller.id != player_id {
+        match ModerationService::has_role(db.get_ref(), caller.id, Role::Admin).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return ApiError::Forbidden("Only the player or an admin can export these games".to_string())
+                    .error_response()
+            }
+            Err(err) => return err.error_response(),
+        }
+    }
+
+    if let Err(wait) = limiter.try_acquire(caller.id) {
+        return ApiError::TooManyRequests(format!("Try again in {} seconds", wait)).error_response();
+    }
+
+    let (content_type, file_name) = match query.format {
+        ArchiveFormat::Pgn => ("application/x-chess-pgn", "games.pgn"),
+        ArchiveFormat::Zip => ("application/zip", "games.zip"),
+    };
+    let body = ArchiveService::export(replicas.read(), player_id, query.format)
+        .map(|chunk| chunk.map(Bytes::from).map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string())));
+
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+        .streaming(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_refuses_until_cooldown_passes() {
+        let limiter = ExportLimiter::new(Duration::from_millis(50));
+        let player = Uuid::new_v4();
+        assert!(limiter.try_acquire(player).is_ok());
+        assert_eq!(limiter.try_acquire(player), Err(1));
+        // Other players are not affected
+        assert!(limiter.try_acquire(Uuid::new_v4()).is_ok());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.try_acquire(player).is_ok());
+    }
+}
@@ -0,0 +1,138 @@
+//! AsyncAPI Module
+//!
+//! Machine-readable description of the game WebSocket in AsyncAPI 2.6,
+//! built from the `ToSchema` derive of `WsMessage` so that it follows the
+//! messages the server actually sends.
+
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+use crate::ws::{WsMessage, PROTOCOL_VERSION};
+
+pub const ASYNCAPI_VERSION: &str = "2.6.0";
+
+/// Media type of the message schemas, which are OpenAPI 3 schemas
+const SCHEMA_FORMAT: &str = "application/vnd.oai.openapi;version=3.0.0";
+
+/// The AsyncAPI document of the game WebSocket.
+pub fn asyncapi_document() -> Value {
+    let (_, schema) = WsMessage::schema();
+    let schema = serde_json::to_value(schema).unwrap_or(Value::Null);
+
+    // One message per variant, named after its `type` tag
+    let mut messages = Map::new();
+    let variants = schema["oneOf"].as_array().cloned().unwrap_or_default();
+    for mut variant in variants {
+        let Some(name) = variant["properties"]["type"]["enum"][0].as_str().map(str::to_string) else {
+            continue;
+        };
+        let description = variant["properties"]["payload"]
+            .as_object_mut()
+            .and_then(|payload| payload.remove("description"));
+        if let Some(properties) = variant["properties"].as_object_mut() {
+            properties.insert("version".to_string(), json!({ "type": "string", "example": PROTOCOL_VERSION }));
+        }
+        if let Some(required) = variant["required"].as_array_mut() {
+            required.push(json!("version"));
+        }
+
+        let mut message = json!({
+            "name": name,
+            "schemaFormat": SCHEMA_FORMAT,
+            "payload": variant,
+        });
+        if let Some(description) = description {
+            message["summary"] = description;
+        }
+        messages.insert(name, message);
+    }
+
+    let refs: Vec<Value> = messages
+        .keys()
+        .map(|name| json!({ "$ref": format!("#/components/messages/{}", name) }))
+        .collect();
+
+    json!({
+        "asyncapi": ASYNCAPI_VERSION,
+        "info": {
+            "title": "XLMate Game WebSocket",
+            "version": PROTOCOL_VERSION,
+            "description": "Live updates of a game. Clients only receive messages; text frames sent by clients are ignored.",
+        },
+        "defaultContentType": "application/json",
+        "channels": {
+            "/ws/{game_id}": {
+                "parameters": {
+                    "game_id": {
+                        "description": "Game to follow",
+                        "schema": { "type": "string" },
+                    },
+                },
+                "subscribe": {
+                    "operationId": "receiveGameMessage",
+                    "message": { "oneOf": refs },
+                },
+                "bindings": {
+                    "ws": {
+                        "method": "GET",
+                        "headers": {
+                            "type": "object",
+                            "properties": {
+                                "Authorization": {
+                                    "type": "string",
+                                    "description": "Bearer {token}",
+                                },
+                            },
+                            "required": ["Authorization"],
+                        },
+                    },
+                },
+            },
+        },
+        "components": {
+            "messages": messages,
+            "securitySchemes": {
+                "jwt_auth": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "bearerFormat": "JWT",
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_message_is_described() {
+        let document = asyncapi_document();
+        let messages = document["components"]["messages"].as_object().unwrap();
+        for name in ["Move", "Clock", "End", "Error"] {
+            let payload = &messages[name]["payload"];
+            assert_eq!(payload["properties"]["type"]["enum"][0], name);
+            assert!(payload["properties"]["version"].is_object());
+        }
+        assert_eq!(
+            document["channels"]["/ws/{game_id}"]["subscribe"]["message"]["oneOf"]
+                .as_array()
+                .unwrap()
+                .len(),
+            messages.len()
+        );
+    }
+
+    #[test]
+    fn test_payload_fields_follow_the_enum() {
+        let document = asyncapi_document();
+        let clock = &document["components"]["messages"]["Clock"]["payload"]["properties"]["payload"];
+        assert!(clock["properties"]["white"].is_object());
+        assert!(clock["properties"]["black"].is_object());
+        assert_eq!(
+            document["components"]["messages"]["Move"]["summary"],
+            "A move was played"
+        );
+    }
+}
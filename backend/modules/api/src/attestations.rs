@@ -0,0 +1,37 @@
+use actix_web::{
+    HttpResponse, get,
+    web::{self, Path},
+};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::attestations::{AttestationChain, AttestationService};
+use uuid::Uuid;
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/{id}/attestation",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Attestation state of the game, checked against the contract once confirmed", body = GameAttestationResponse),
+        (status = 404, description = "Game not attested", body = NotFoundResponse),
+        (status = 502, description = "StarkNet node unreachable")
+    ),
+    tag = "Games"
+)]
+#[get("")]
+pub async fn get_attestation(
+    db: web::Data<DatabaseConnection>,
+    chain: Option<web::Data<dyn AttestationChain>>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let chain = chain.as_ref().map(|chain| chain.get_ref());
+    match AttestationService::verify(db.get_ref(), chain, id.into_inner()).await {
+        Ok(attestation) => HttpResponse::Ok().json(json!({
+            "message": "Attestation found",
+            "data": attestation
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -1,26 +1,52 @@
-use actix_web::{web, HttpResponse, HttpRequest, post, cookie::{Cookie, time::Duration}};
+use actix::Addr;
+use actix_web::{web, HttpResponse, HttpRequest, get, post, cookie::{Cookie, time::Duration}};
 use validator::Validate;
 use std::env;
 use uuid::Uuid;
 
-use dto::auth::{RegisterRequest, LoginRequest, AuthResponse, ErrorResponse, RefreshTokenRequest, RefreshResponse, LogoutResponse};
-use security::{JwtService, TokenService, TokenServiceError};
-use sea_orm::DatabaseConnection;
+use dto::auth::{
+    RegisterRequest, LoginRequest, AuthResponse, ErrorResponse, RefreshTokenRequest, RefreshResponse, LogoutResponse,
+    CaptchaChallengeResponse, WalletChallengeRequest, WalletChallengeResponse, WalletLinkResponse, WalletSignatureRequest,
+};
+use security::{
+    AccountFlags, Captcha, CaptchaChallenge, CaptchaError, JwtService, PlayerProfile, TokenDenylist, TokenService,
+    TokenServiceError, WalletAuth, WalletError,
+};
+use sea_orm::{ActiveEnum, DatabaseConnection};
+use db_entity::moderation_action::ActionKind;
+use error::error::ApiError;
+use service::moderation::ModerationService;
+use service::linking::Fingerprinter;
+use service::rating::RatingService;
+use service::wallets::WalletService;
+
+use crate::guard::{current_player, record_fingerprints, require_captcha};
+use crate::guests::{guest_claims, GuestSessions};
+use crate::ws::{Broadcast, LobbyState, WsMessage};
 
 /// Register a new user
 #[utoipa::path(
     post,
     path = "/v1/auth/register",
     request_body = RegisterRequest,
+    params(
+        ("X-Captcha" = Option<String>, Header, description = "Solution of the challenge from /v1/auth/captcha, when captchas are enabled; not needed with a guest token")
+    ),
     responses(
-        (status = 201, description = "User registered successfully", body = AuthResponse),
-        (status = 400, description = "Validation error", body = ErrorResponse)
+        (status = 201, description = "User registered successfully; registering with a guest token hands the guest's games to the new account", body = AuthResponse),
+        (status = 400, description = "Validation error or missing captcha", body = ErrorResponse),
+        (status = 403, description = "Captcha not solved", body = ErrorResponse)
     ),
     tag = "Authentication"
 )]
 #[post("/register")]
 pub async fn register(
+    req: HttpRequest,
     _db: web::Data<DatabaseConnection>,
+    jwt_service: web::Data<JwtService>,
+    guests: web::Data<GuestSessions>,
+    lobby: web::Data<Addr<LobbyState>>,
+    captcha: Option<web::Data<Captcha>>,
     payload: web::Json<RegisterRequest>,
 ) -> HttpResponse {
     // Validate input
@@ -31,6 +57,34 @@ pub async fn register(
         });
     }
 
+    // Guests solved a captcha when their session started
+    let guest = guest_claims(&req, &jwt_service)
+        .map(|(guest_id, _)| guest_id)
+        .filter(|guest_id| guests.is_active(*guest_id));
+    if guest.is_none() {
+        if let Err(e) = require_captcha(captcha.as_ref(), &req).await {
+            return captcha_error_response(e);
+        }
+    }
+
+    // A guest registering mid-game keeps playing under the new account
+    let migrated_games = match guest {
+        Some(guest_id) => {
+            let games = guests.upgrade(guest_id);
+            for game_id in &games {
+                lobby.do_send(Broadcast {
+                    game_id: game_id.to_string(),
+                    message: WsMessage::GuestRegistered {
+                        guest_id: guest_id.to_string(),
+                        username: payload.username.clone(),
+                    },
+                });
+            }
+            games
+        }
+        None => Vec::new(),
+    };
+
     // For now, return a mock response
     HttpResponse::Created().json(AuthResponse {
         access_token: "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...".to_string(),
@@ -40,26 +94,71 @@ pub async fn register(
         refresh_token_expires_in: 604800,
         user_id: 1,
         username: payload.username.clone(),
+        migrated_games,
     })
 }
 
+/// What to solve before registering or creating a game anonymously
+#[utoipa::path(
+    get,
+    path = "/v1/auth/captcha",
+    responses(
+        (status = 200, description = "A captcha widget to render, a proof of work to compute, or `none`", body = CaptchaChallengeResponse)
+    ),
+    tag = "Authentication"
+)]
+#[get("/captcha")]
+pub async fn captcha_challenge(captcha: Option<web::Data<Captcha>>) -> HttpResponse {
+    let none = CaptchaChallengeResponse {
+        kind: "none".to_string(),
+        provider: None,
+        site_key: None,
+        challenge: None,
+        difficulty: None,
+        expires_at: None,
+    };
+    let response = match captcha.map(|captcha| captcha.challenge()) {
+        None => none,
+        Some(CaptchaChallenge::Widget { provider, site_key }) => CaptchaChallengeResponse {
+            kind: "widget".to_string(),
+            provider: Some(provider.as_str().to_string()),
+            site_key: Some(site_key),
+            ..none
+        },
+        Some(CaptchaChallenge::ProofOfWork { challenge, difficulty, expires_at }) => CaptchaChallengeResponse {
+            kind: "proof_of_work".to_string(),
+            challenge: Some(challenge),
+            difficulty: Some(difficulty),
+            expires_at: Some(expires_at),
+            ..none
+        },
+    };
+    HttpResponse::Ok().json(response)
+}
+
 /// Login with credentials
 #[utoipa::path(
     post,
     path = "/v1/auth/login",
     request_body = LoginRequest,
+    params(
+        ("X-Device-Id" = Option<String>, Header, description = "Install id the client generated for this device; stored only as a keyed hash")
+    ),
     responses(
         (status = 200, description = "Login successful", body = AuthResponse),
         (status = 400, description = "Validation error", body = ErrorResponse),
-        (status = 401, description = "Invalid credentials", body = ErrorResponse)
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Account banned or closed", body = ErrorResponse)
     ),
     tag = "Authentication"
 )]
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     db: web::Data<DatabaseConnection>,
     payload: web::Json<LoginRequest>,
     jwt_service: web::Data<JwtService>,
+    fingerprinter: Option<web::Data<Fingerprinter>>,
 ) -> HttpResponse {
     // Validate input
     if let Err(errors) = payload.validate() {
@@ -69,61 +168,48 @@ pub async fn login(
         });
     }
 
-    // For MVP: mock user with ID 1
-    let user_id = 1;
-    let username = payload.username.clone();
-
-    // Generate access token
-    let access_token = match jwt_service.generate_token(user_id, &username) {
-        Ok(t) => t,
-        Err(_) => {
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                message: "Failed to generate access token".to_string(),
-                code: "TOKEN_ERROR".to_string(),
+    // Banned and closed accounts must not receive fresh tokens
+    let player_id = match ModerationService::find_player_by_username(&db, &payload.username).await {
+        Ok(Some(player)) if !player.is_enabled => {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                message: "Account is closed; reactivate it to log in".to_string(),
+                code: "ACCOUNT_CLOSED".to_string(),
             });
         }
-    };
-
-    // Generate refresh token
-    let family_id = Uuid::new_v4();
-    let refresh_ttl = env::var("REFRESH_TOKEN_TTL_DAYS")
-        .unwrap_or_else(|_| "7".to_string())
-        .parse::<i64>()
-        .unwrap_or(7);
-
-    let refresh_token = match TokenService::generate_refresh_token(&db, user_id, family_id, refresh_ttl).await {
-        Ok(t) => t,
+        Ok(Some(player)) => match ModerationService::ensure_not_banned(&db, player.id).await {
+            Ok(()) => Some(player.id),
+            Err(ApiError::Forbidden(message)) => {
+                return HttpResponse::Forbidden().json(ErrorResponse {
+                    message,
+                    code: "ACCOUNT_BANNED".to_string(),
+                });
+            }
+            Err(e) => {
+                log::error!("Failed to check account status: {}", e);
+                return HttpResponse::InternalServerError().json(ErrorResponse {
+                    message: "Failed to check account status".to_string(),
+                    code: "ACCOUNT_STATUS_ERROR".to_string(),
+                });
+            }
+        },
+        Ok(None) => None,
         Err(e) => {
-            log::error!("Failed to generate refresh token: {}", e);
+            log::error!("Failed to look up account: {}", e);
             return HttpResponse::InternalServerError().json(ErrorResponse {
-                message: "Failed to generate refresh token".to_string(),
-                code: "TOKEN_ERROR".to_string(),
+                message: "Failed to check account status".to_string(),
+                code: "ACCOUNT_STATUS_ERROR".to_string(),
             });
         }
     };
 
-    // Build response with cookie
-    let mut response = HttpResponse::Ok()
-        .json(AuthResponse {
-            access_token,
-            refresh_token: refresh_token.clone(),
-            token_type: "Bearer".to_string(),
-            expires_in: 3600,
-            refresh_token_expires_in: (refresh_ttl * 86400) as usize,
-            user_id,
-            username,
-        });
-
-    // Set HTTP-only secure cookie
-    let cookie = Cookie::build("refresh_token", refresh_token)
-        .http_only(true)
-        .secure(false) // Set to true in production HTTPS
-        .same_site(actix_web::cookie::SameSite::Strict)
-        .max_age(Duration::seconds(refresh_ttl as i64 * 86400))
-        .finish();
+    // For MVP: mock user with ID 1
+    let user_id = 1;
+    let username = payload.username.clone();
+    if let Some(player_id) = player_id {
+        record_fingerprints(&db, fingerprinter.as_ref(), &req, player_id).await;
+    }
 
-    response.add_cookie(&cookie).ok();
-    response
+    token_response(&db, &jwt_service, user_id, username).await
 }
 
 /// Refresh tokens - rotate refresh token and get new access token
@@ -133,7 +219,7 @@ pub async fn login(
     request_body = RefreshTokenRequest,
     responses(
         (status = 200, description = "Token refresh successful", body = RefreshResponse),
-        (status = 401, description = "Invalid or reused refresh token", body = ErrorResponse)
+        (status = 401, description = "Invalid or reused refresh token, or revoked access token", body = ErrorResponse)
     ),
     tag = "Authentication"
 )]
@@ -143,6 +229,7 @@ pub async fn refresh(
     req: HttpRequest,
     payload: Option<web::Json<RefreshTokenRequest>>,
     jwt_service: web::Data<JwtService>,
+    denylist: Option<web::Data<TokenDenylist>>,
 ) -> HttpResponse {
     // Extract refresh token from cookie or request body
     let refresh_token = if let Some(cookie) = req.cookie("refresh_token") {
@@ -196,6 +283,28 @@ pub async fn refresh(
         }
     };
 
+    // A revoked access token cannot be traded for a fresh one
+    if let Some(denylist) = denylist {
+        if denylist.is_revoked(&claims).await {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                message: "Access token has been revoked".to_string(),
+                code: "TOKEN_REVOKED".to_string(),
+            });
+        }
+    }
+
+    // Snapshot the player again, so roles and ratings catch up on refresh
+    let profile = match player_profile(&db, &claims.username).await {
+        Ok(profile) => profile,
+        Err(e) => {
+            log::error!("Failed to load player profile: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to check account status".to_string(),
+                code: "ACCOUNT_STATUS_ERROR".to_string(),
+            });
+        }
+    };
+
     // Verify refresh token and mark as used
     let family_id = match TokenService::verify_and_mark_used(&db, &refresh_token, claims.user_id).await {
         Ok(fid) => fid,
@@ -221,7 +330,7 @@ pub async fn refresh(
     };
 
     // Generate new access token
-    let new_access_token = match jwt_service.generate_token(claims.user_id, &claims.username) {
+    let new_access_token = match sign_access_token(&jwt_service, claims.user_id, &claims.username, profile) {
         Ok(t) => t,
         Err(_) => {
             return HttpResponse::InternalServerError().json(ErrorResponse {
@@ -269,12 +378,12 @@ pub async fn refresh(
     response
 }
 
-/// Logout - revoke all tokens
+/// Logout - revoke the access token and every refresh token
 #[utoipa::path(
     post,
     path = "/v1/auth/logout",
     responses(
-        (status = 200, description = "Logout successful", body = LogoutResponse),
+        (status = 200, description = "Logout successful; the access token is refused from now on", body = LogoutResponse),
         (status = 401, description = "Unauthorized", body = ErrorResponse)
     ),
     tag = "Authentication"
@@ -282,6 +391,8 @@ pub async fn refresh(
 #[post("/logout")]
 pub async fn logout(
     db: web::Data<DatabaseConnection>,
+    jwt_service: web::Data<JwtService>,
+    denylist: Option<web::Data<TokenDenylist>>,
     req: HttpRequest,
 ) -> HttpResponse {
     // Extract user from access token
@@ -303,22 +414,25 @@ pub async fn logout(
         }
     };
 
-    let token = if auth_header.starts_with("Bearer ") {
-        &auth_header[7..]
-    } else {
+    let Some(token) = JwtService::extract_token_from_header(&auth_header) else {
         return HttpResponse::Unauthorized().json(ErrorResponse {
             message: "Invalid authorization format".to_string(),
             code: "INVALID_AUTH_FORMAT".to_string(),
         });
     };
 
-    // We would validate token here, but for now just extract user_id from the request
-    // In a real implementation, we'd use a JWT service to validate and extract claims
-    // For MVP, we'll accept it and revoke for user_id 1
-    let user_id = 1;
+    let claims = match jwt_service.validate_token(&token) {
+        Ok(claims) if !claims.guest => claims,
+        _ => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                message: "Invalid or expired token".to_string(),
+                code: "INVALID_ACCESS_TOKEN".to_string(),
+            });
+        }
+    };
 
-    // Revoke all tokens for this player
-    if let Err(e) = TokenService::revoke_player_tokens(&db, user_id).await {
+    // Revoke all refresh tokens for this player
+    if let Err(e) = TokenService::revoke_player_tokens(&db, claims.user_id).await {
         log::error!("Failed to revoke tokens: {}", e);
         return HttpResponse::InternalServerError().json(ErrorResponse {
             message: "Failed to logout".to_string(),
@@ -326,6 +440,17 @@ pub async fn logout(
         });
     }
 
+    // Refuse the access token too, rather than waiting for it to expire
+    if let Some(denylist) = denylist {
+        if let Err(e) = denylist.revoke_token(&claims).await {
+            log::error!("Failed to revoke access token: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to logout".to_string(),
+                code: "LOGOUT_ERROR".to_string(),
+            });
+        }
+    }
+
     // Clear the refresh token cookie
     let mut response = HttpResponse::Ok()
         .json(LogoutResponse {
@@ -342,3 +467,345 @@ pub async fn logout(
     response.add_cookie(&cookie).ok();
     response
 }
+
+/// Request a nonce to sign with a StarkNet wallet
+#[utoipa::path(
+    post,
+    path = "/v1/auth/wallet/challenge",
+    request_body = WalletChallengeRequest,
+    responses(
+        (status = 200, description = "Typed data to sign, valid once until it expires", body = WalletChallengeResponse),
+        (status = 400, description = "Not an account address", body = ErrorResponse),
+        (status = 503, description = "Wallet sign-in is not configured", body = ErrorResponse)
+    ),
+    tag = "Authentication"
+)]
+#[post("/wallet/challenge")]
+pub async fn wallet_challenge(
+    wallet_auth: Option<web::Data<WalletAuth>>,
+    payload: web::Json<WalletChallengeRequest>,
+) -> HttpResponse {
+    let Some(wallet_auth) = wallet_auth else {
+        return wallet_auth_disabled();
+    };
+    if let Err(errors) = payload.validate() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            message: format!("Validation failed: {:?}", errors),
+            code: "VALIDATION_ERROR".to_string(),
+        });
+    }
+
+    match wallet_auth.challenge(&payload.address) {
+        Ok(challenge) => HttpResponse::Ok().json(WalletChallengeResponse {
+            address: challenge.address,
+            nonce: challenge.nonce,
+            expires_at: challenge.expires_at,
+            typed_data: challenge.typed_data,
+        }),
+        Err(e) => wallet_error_response(e),
+    }
+}
+
+/// Login with a signed wallet challenge
+#[utoipa::path(
+    post,
+    path = "/v1/auth/wallet/login",
+    request_body = WalletSignatureRequest,
+    params(
+        ("X-Device-Id" = Option<String>, Header, description = "Install id the client generated for this device; stored only as a keyed hash")
+    ),
+    responses(
+        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 401, description = "Invalid signature, unknown or expired challenge", body = ErrorResponse),
+        (status = 403, description = "Account banned or closed", body = ErrorResponse),
+        (status = 404, description = "Wallet not linked to an account", body = ErrorResponse),
+        (status = 502, description = "StarkNet node unreachable", body = ErrorResponse)
+    ),
+    tag = "Authentication"
+)]
+#[post("/wallet/login")]
+pub async fn wallet_login(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    jwt_service: web::Data<JwtService>,
+    wallet_auth: Option<web::Data<WalletAuth>>,
+    fingerprinter: Option<web::Data<Fingerprinter>>,
+    payload: web::Json<WalletSignatureRequest>,
+) -> HttpResponse {
+    let Some(wallet_auth) = wallet_auth else {
+        return wallet_auth_disabled();
+    };
+    if let Err(errors) = payload.validate() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            message: format!("Validation failed: {:?}", errors),
+            code: "VALIDATION_ERROR".to_string(),
+        });
+    }
+
+    let address = match wallet_auth.verify(&payload.address, &payload.nonce, &payload.signature).await {
+        Ok(address) => address,
+        Err(e) => return wallet_error_response(e),
+    };
+
+    let player = match WalletService::find_player(&db, &address).await {
+        Ok(Some(player)) => player,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                message: "Wallet is not linked to an account".to_string(),
+                code: "WALLET_NOT_LINKED".to_string(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to look up wallet: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to check account status".to_string(),
+                code: "ACCOUNT_STATUS_ERROR".to_string(),
+            });
+        }
+    };
+
+    // Banned and closed accounts must not receive fresh tokens
+    if !player.is_enabled {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            message: "Account is closed; reactivate it to log in".to_string(),
+            code: "ACCOUNT_CLOSED".to_string(),
+        });
+    }
+    match ModerationService::ensure_not_banned(&db, player.id).await {
+        Ok(()) => {}
+        Err(ApiError::Forbidden(message)) => {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                message,
+                code: "ACCOUNT_BANNED".to_string(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to check account status: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to check account status".to_string(),
+                code: "ACCOUNT_STATUS_ERROR".to_string(),
+            });
+        }
+    }
+
+    record_fingerprints(&db, fingerprinter.as_ref(), &req, player.id).await;
+
+    // Same placeholder user id as password login; the username identifies the player
+    token_response(&db, &jwt_service, 1, player.username).await
+}
+
+/// Link a wallet to the authenticated account by signing a challenge
+#[utoipa::path(
+    post,
+    path = "/v1/auth/wallet/link",
+    request_body = WalletSignatureRequest,
+    responses(
+        (status = 200, description = "Wallet linked; linking it again is a no-op", body = WalletLinkResponse),
+        (status = 400, description = "Wallet linked to another account", body = ErrorResponse),
+        (status = 401, description = "Invalid signature, unknown or expired challenge", body = ErrorResponse),
+        (status = 502, description = "StarkNet node unreachable", body = ErrorResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Authentication"
+)]
+#[post("")]
+pub async fn link_wallet(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    wallet_auth: Option<web::Data<WalletAuth>>,
+    payload: web::Json<WalletSignatureRequest>,
+) -> HttpResponse {
+    let Some(wallet_auth) = wallet_auth else {
+        return wallet_auth_disabled();
+    };
+    let player = match current_player(&db, &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    if let Err(errors) = payload.validate() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            message: format!("Validation failed: {:?}", errors),
+            code: "VALIDATION_ERROR".to_string(),
+        });
+    }
+
+    let address = match wallet_auth.verify(&payload.address, &payload.nonce, &payload.signature).await {
+        Ok(address) => address,
+        Err(e) => return wallet_error_response(e),
+    };
+
+    match WalletService::link(&db, player.id, &address).await {
+        Ok(wallet) => HttpResponse::Ok().json(WalletLinkResponse {
+            address: wallet.address,
+            player_id: wallet.player_id,
+        }),
+        Err(ApiError::BadRequest(message)) => HttpResponse::BadRequest().json(ErrorResponse {
+            message,
+            code: "WALLET_ALREADY_LINKED".to_string(),
+        }),
+        Err(e) => {
+            log::error!("Failed to link wallet: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to link wallet".to_string(),
+                code: "WALLET_LINK_ERROR".to_string(),
+            })
+        }
+    }
+}
+
+fn wallet_auth_disabled() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(ErrorResponse {
+        message: "Wallet sign-in is not configured".to_string(),
+        code: "WALLET_AUTH_DISABLED".to_string(),
+    })
+}
+
+fn wallet_error_response(error: WalletError) -> HttpResponse {
+    let code = match &error {
+        WalletError::InvalidAddress => "INVALID_ADDRESS",
+        WalletError::InvalidSignature => "INVALID_SIGNATURE",
+        WalletError::ChallengeNotFound => "CHALLENGE_NOT_FOUND",
+        WalletError::ChallengeExpired => "CHALLENGE_EXPIRED",
+        WalletError::Node(_) => "WALLET_NODE_ERROR",
+    };
+    let body = ErrorResponse {
+        message: error.to_string(),
+        code: code.to_string(),
+    };
+    match error {
+        WalletError::InvalidAddress => HttpResponse::BadRequest().json(body),
+        WalletError::Node(e) => {
+            log::error!("Failed to verify wallet signature: {}", e);
+            HttpResponse::BadGateway().json(body)
+        }
+        _ => HttpResponse::Unauthorized().json(body),
+    }
+}
+
+fn captcha_error_response(error: CaptchaError) -> HttpResponse {
+    let code = match &error {
+        CaptchaError::Missing => "CAPTCHA_REQUIRED",
+        CaptchaError::Rejected => "CAPTCHA_FAILED",
+        CaptchaError::Expired => "CAPTCHA_EXPIRED",
+        CaptchaError::Provider(_) => "CAPTCHA_PROVIDER_ERROR",
+    };
+    let body = ErrorResponse {
+        message: error.to_string(),
+        code: code.to_string(),
+    };
+    match error {
+        CaptchaError::Missing => HttpResponse::BadRequest().json(body),
+        CaptchaError::Provider(e) => {
+            log::error!("Failed to verify captcha: {}", e);
+            HttpResponse::BadGateway().json(body)
+        }
+        _ => HttpResponse::Forbidden().json(body),
+    }
+}
+
+/// What an access token says about the player named `username`; `None`
+/// when no player has that name.
+async fn player_profile(db: &DatabaseConnection, username: &str) -> Result<Option<PlayerProfile>, ApiError> {
+    let Some(player) = ModerationService::find_player_by_username(db, username).await? else {
+        return Ok(None);
+    };
+    let roles = ModerationService::roles_for(db, player.id).await?;
+    let banned = ModerationService::active_action(db, player.id, ActionKind::Ban).await?.is_some();
+    let ratings = RatingService::current(db, player.id).await?;
+
+    Ok(Some(PlayerProfile {
+        player_id: player.id,
+        roles: roles.into_iter().map(|role| role.to_value()).collect(),
+        // Accounts do not mark bots yet
+        flags: AccountFlags { banned, titled: player.title.is_some(), ..Default::default() },
+        ratings: ratings.into_iter().map(|rating| (rating.category.to_value(), rating.rating)).collect(),
+        title: player.title.map(|title| title.code().to_string()),
+    }))
+}
+
+fn sign_access_token(
+    jwt_service: &JwtService,
+    user_id: i32,
+    username: &str,
+    profile: Option<PlayerProfile>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    match profile {
+        Some(profile) => jwt_service.generate_player_token(user_id, username, profile),
+        None => jwt_service.generate_token(user_id, username),
+    }
+}
+
+/// Access and refresh tokens for a signed-in player, the refresh token
+/// also set as an HTTP-only cookie.
+async fn token_response(
+    db: &DatabaseConnection,
+    jwt_service: &JwtService,
+    user_id: i32,
+    username: String,
+) -> HttpResponse {
+    let profile = match player_profile(db, &username).await {
+        Ok(profile) => profile,
+        Err(e) => {
+            log::error!("Failed to load player profile: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to check account status".to_string(),
+                code: "ACCOUNT_STATUS_ERROR".to_string(),
+            });
+        }
+    };
+
+    // Generate access token
+    let access_token = match sign_access_token(jwt_service, user_id, &username, profile) {
+        Ok(t) => t,
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to generate access token".to_string(),
+                code: "TOKEN_ERROR".to_string(),
+            });
+        }
+    };
+
+    // Generate refresh token
+    let family_id = Uuid::new_v4();
+    let refresh_ttl = env::var("REFRESH_TOKEN_TTL_DAYS")
+        .unwrap_or_else(|_| "7".to_string())
+        .parse::<i64>()
+        .unwrap_or(7);
+
+    let refresh_token = match TokenService::generate_refresh_token(db, user_id, family_id, refresh_ttl).await {
+        Ok(t) => t,
+        Err(e) => {
+            log::error!("Failed to generate refresh token: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to generate refresh token".to_string(),
+                code: "TOKEN_ERROR".to_string(),
+            });
+        }
+    };
+
+    // Build response with cookie
+    let mut response = HttpResponse::Ok()
+        .json(AuthResponse {
+            access_token,
+            refresh_token: refresh_token.clone(),
+            token_type: "Bearer".to_string(),
+            expires_in: 3600,
+            refresh_token_expires_in: (refresh_ttl * 86400) as usize,
+            user_id,
+            username,
+            migrated_games: Vec::new(),
+        });
+
+    // Set HTTP-only secure cookie
+    let cookie = Cookie::build("refresh_token", refresh_token)
+        .http_only(true)
+        .secure(false) // Set to true in production HTTPS
+        .same_site(actix_web::cookie::SameSite::Strict)
+        .max_age(Duration::seconds(refresh_ttl as i64 * 86400))
+        .finish();
+
+    response.add_cookie(&cookie).ok();
+    response
+}
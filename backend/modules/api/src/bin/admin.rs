@@ -0,0 +1,49 @@
+//! Operator tasks against the backend's database, run from the command
+//! line. `starkmate-admin help` lists the commands.
+
+use std::env;
+use std::process::ExitCode;
+
+use api::admin::{self, Command};
+use api::config::AppConfig;
+use dotenv::dotenv;
+use sea_orm::Database;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    let _telemetry = telemetry::init("starkmate-admin", "error");
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || matches!(args[0].as_str(), "help" | "--help" | "-h") {
+        println!("{}", admin::USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let command = match Command::parse(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, admin::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let Ok(database_url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL must be set");
+        return ExitCode::FAILURE;
+    };
+    let db = match Database::connect(&database_url).await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Failed to connect to database: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match admin::run(&db, &AppConfig::from_env(), command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Responses of hot read endpoints, kept for a while so repeated reads skip
+//! the database or the engines.
+//!
+//! Each kind of response has its own TTL, and a TTL of zero leaves it
+//! uncached. Handlers that change the data behind a response drop it
+//! through the hooks on [`ResponseCache`], so the TTL only bounds how stale
+//! a response gets through changes made elsewhere, such as the scheduler. A
+//! failing store is logged and treated as a miss.
+
+use async_trait::async_trait;
+use dto::ai::AnalysisVariant;
+use dto::leaderboards::TimeControlCategory;
+use futures_util::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, AsyncIter, RedisResult};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CacheError(pub String);
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Response cache error: {}", self.0)
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// Where cached responses are kept, as JSON, each for `ttl` seconds.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError>;
+
+    async fn set(&self, key: &str, value: &str, ttl: u64) -> Result<(), CacheError>;
+
+    /// Drop every entry whose key starts with `prefix`.
+    async fn invalidate(&self, prefix: &str) -> Result<(), CacheError>;
+}
+
+/// Responses cached by this process only, for single-instance deployments.
+#[derive(Clone, Default)]
+pub struct MemoryCacheStore {
+    /// Value, and when the entry expires
+    entries: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+}
+
+impl MemoryCacheStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: u64) -> Result<(), CacheError> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(key.to_string(), (value.to_string(), now + Duration::from_secs(ttl)));
+        Ok(())
+    }
+
+    async fn invalidate(&self, prefix: &str) -> Result<(), CacheError> {
+        self.entries.lock().unwrap().retain(|key, _| !key.starts_with(prefix));
+        Ok(())
+    }
+}
+
+/// Responses shared by every API instance through Redis, so a change made
+/// through one instance drops the response everywhere. The connection is
+/// opened on first use and again after an error.
+#[derive(Clone)]
+pub struct RedisCacheStore {
+    client: redis::Client,
+    conn: Arc<tokio::sync::Mutex<Option<MultiplexedConnection>>>,
+}
+
+impl RedisCacheStore {
+    pub fn new(redis_url: &str) -> Result<Self, CacheError> {
+        let client = redis::Client::open(redis_url).map_err(|e| CacheError(e.to_string()))?;
+        Ok(Self {
+            client,
+            conn: Arc::new(tokio::sync::Mutex::new(None)),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<MultiplexedConnection> {
+        let mut conn = self.conn.lock().await;
+        if let Some(conn) = conn.as_ref() {
+            return Ok(conn.clone());
+        }
+        let connected = self.client.get_multiplexed_async_connection().await?;
+        *conn = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// Drop the connection after `result` failed, so the next call reconnects.
+    async fn checked<T>(&self, result: RedisResult<T>) -> Result<T, CacheError> {
+        if result.is_err() {
+            *self.conn.lock().await = None;
+        }
+        result.map_err(|e| CacheError(e.to_string()))
+    }
+
+    async fn delete_matching(conn: &mut MultiplexedConnection, prefix: &str) -> RedisResult<()> {
+        let keys: Vec<String> = {
+            let iter: AsyncIter<String> = conn.scan_match(format!("{}*", prefix)).await?;
+            iter.collect().await
+        };
+        if !keys.is_empty() {
+            conn.del::<_, ()>(keys).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        let result = match self.connection().await {
+            Ok(mut conn) => conn.get(key).await,
+            Err(e) => Err(e),
+        };
+        self.checked(result).await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: u64) -> Result<(), CacheError> {
+        let result = match self.connection().await {
+            Ok(mut conn) => conn.set_ex(key, value, ttl.max(1)).await,
+            Err(e) => Err(e),
+        };
+        self.checked(result).await
+    }
+
+    async fn invalidate(&self, prefix: &str) -> Result<(), CacheError> {
+        let result = match self.connection().await {
+            Ok(mut conn) => Self::delete_matching(&mut conn, prefix).await,
+            Err(e) => Err(e),
+        };
+        self.checked(result).await
+    }
+}
+
+/// How long each kind of response is kept; zero leaves it uncached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheTtls {
+    pub leaderboard: Duration,
+    pub standings: Duration,
+    pub position_search: Duration,
+    pub analysis: Duration,
+}
+
+/// A cached response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheKey {
+    Leaderboard { category: TimeControlCategory, limit: u64 },
+    LeaderboardRank { category: TimeControlCategory, player: Uuid },
+    Standings(Uuid),
+    /// A position search, by its query as JSON
+    PositionSearch(String),
+    /// An analysis that reached the depth asked for
+    Analysis { variant: AnalysisVariant, fen: String, depth: u8 },
+}
+
+impl CacheKey {
+    fn key(&self) -> String {
+        match self {
+            Self::Leaderboard { category, limit } => {
+                format!("{}{}:top:{}", LEADERBOARD_PREFIX, category_name(*category), limit)
+            }
+            Self::LeaderboardRank { category, player } => {
+                format!("{}{}:rank:{}", LEADERBOARD_PREFIX, category_name(*category), player)
+            }
+            Self::Standings(tournament) => format!("{}{}", STANDINGS_PREFIX, tournament),
+            Self::PositionSearch(query) => format!("{}{}", POSITION_SEARCH_PREFIX, query),
+            Self::Analysis { variant, fen, depth } => {
+                format!("{}{}:{}:{}", ANALYSIS_PREFIX, variant.as_str(), depth, fen)
+            }
+        }
+    }
+
+    fn ttl(&self, ttls: &CacheTtls) -> Duration {
+        match self {
+            Self::Leaderboard { .. } | Self::LeaderboardRank { .. } => ttls.leaderboard,
+            Self::Standings(_) => ttls.standings,
+            Self::PositionSearch(_) => ttls.position_search,
+            Self::Analysis { .. } => ttls.analysis,
+        }
+    }
+}
+
+const LEADERBOARD_PREFIX: &str = "cache:leaderboard:";
+const STANDINGS_PREFIX: &str = "cache:standings:";
+const POSITION_SEARCH_PREFIX: &str = "cache:positions:";
+const ANALYSIS_PREFIX: &str = "cache:analysis:";
+
+fn category_name(category: TimeControlCategory) -> &'static str {
+    match category {
+        TimeControlCategory::Bullet => "bullet",
+        TimeControlCategory::Blitz => "blitz",
+        TimeControlCategory::Rapid => "rapid",
+        TimeControlCategory::Classical => "classical",
+    }
+}
+
+/// The configured store with the TTL of each kind of response, shared by
+/// every worker.
+#[derive(Clone)]
+pub struct ResponseCache {
+    store: Arc<dyn CacheStore>,
+    ttls: CacheTtls,
+}
+
+impl ResponseCache {
+    pub fn new(store: Arc<dyn CacheStore>, ttls: CacheTtls) -> Self {
+        Self { store, ttls }
+    }
+
+    /// The response cached under `key`, if there is one.
+    pub async fn get<T: DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        if key.ttl(&self.ttls).is_zero() {
+            return None;
+        }
+        match self.store.get(&key.key()).await {
+            Ok(value) => value.and_then(|value| serde_json::from_str(&value).ok()),
+            Err(e) => {
+                log::error!("Failed to read cached response: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Cache `value` under `key` for the TTL of its kind.
+    pub async fn put<T: Serialize>(&self, key: &CacheKey, value: &T) {
+        let ttl = key.ttl(&self.ttls);
+        if ttl.is_zero() {
+            return;
+        }
+        let Ok(value) = serde_json::to_string(value) else {
+            return;
+        };
+        if let Err(e) = self.store.set(&key.key(), &value, ttl.as_secs().max(1)).await {
+            log::error!("Failed to cache response: {}", e);
+        }
+    }
+
+    /// The leaderboards were rebuilt.
+    pub async fn leaderboards_changed(&self) {
+        self.invalidate(LEADERBOARD_PREFIX).await;
+    }
+
+    /// The registrations, rounds, results or prizes of `tournament` changed.
+    pub async fn tournament_changed(&self, tournament: Uuid) {
+        self.invalidate(&CacheKey::Standings(tournament).key()).await;
+    }
+
+    /// Tournaments were advanced in bulk, e.g. by the scheduler.
+    pub async fn tournaments_changed(&self) {
+        self.invalidate(STANDINGS_PREFIX).await;
+    }
+
+    /// More games were indexed for position search.
+    pub async fn positions_indexed(&self) {
+        self.invalidate(POSITION_SEARCH_PREFIX).await;
+    }
+
+    async fn invalidate(&self, prefix: &str) {
+        if let Err(e) = self.store.invalidate(prefix).await {
+            log::error!("Failed to invalidate cached responses: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_cache(ttl: Duration) -> ResponseCache {
+        let ttls = CacheTtls {
+            leaderboard: ttl,
+            standings: ttl,
+            position_search: ttl,
+            analysis: Duration::ZERO,
+        };
+        ResponseCache::new(Arc::new(MemoryCacheStore::new()), ttls)
+    }
+
+    #[tokio::test]
+    async fn test_cached_responses_are_read_back_until_invalidated() {
+        let cache = memory_cache(Duration::from_secs(60));
+        let blitz = CacheKey::Leaderboard { category: TimeControlCategory::Blitz, limit: 50 };
+        let standings = CacheKey::Standings(Uuid::new_v4());
+        assert_eq!(cache.get::<Vec<i32>>(&blitz).await, None);
+
+        cache.put(&blitz, &vec![1, 2, 3]).await;
+        cache.put(&standings, &vec![4]).await;
+        assert_eq!(cache.get::<Vec<i32>>(&blitz).await, Some(vec![1, 2, 3]));
+
+        cache.leaderboards_changed().await;
+        assert_eq!(cache.get::<Vec<i32>>(&blitz).await, None);
+        assert_eq!(cache.get::<Vec<i32>>(&standings).await, Some(vec![4]));
+
+        cache.tournament_changed(Uuid::new_v4()).await;
+        assert_eq!(cache.get::<Vec<i32>>(&standings).await, Some(vec![4]));
+        cache.tournaments_changed().await;
+        assert_eq!(cache.get::<Vec<i32>>(&standings).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_a_zero_ttl_leaves_responses_uncached() {
+        let cache = memory_cache(Duration::from_secs(60));
+        let analysis = CacheKey::Analysis {
+            variant: AnalysisVariant::Standard,
+            fen: "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".to_string(),
+            depth: 12,
+        };
+        cache.put(&analysis, &0.5).await;
+        assert_eq!(cache.get::<f32>(&analysis).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_misses() {
+        let store = MemoryCacheStore::new();
+        store.set("cache:positions:a", "1", 0).await.unwrap();
+        assert_eq!(store.get("cache:positions:a").await.unwrap(), None);
+        store.set("cache:positions:b", "2", 60).await.unwrap();
+        assert_eq!(store.get("cache:positions:b").await.unwrap(), Some("2".to_string()));
+    }
+}
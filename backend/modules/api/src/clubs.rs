@@ -0,0 +1,498 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, post, put,
+    web::{self, Json, Path, Query},
+};
+use db_entity::ladder_challenge::ChallengeStatus;
+use dto::clubs::{
+    AddMemberRequest, ChallengeQuery, ChallengeRequest, ChallengeResultRequest, ClubDisplay, ClubListQuery,
+    ClubMemberDisplay, CreateClubRequest, LadderChallengeDisplay, RespondChallengeRequest,
+    UpdateMemberRequest,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::clubs::ClubService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs",
+    request_body = CreateClubRequest,
+    responses(
+        (status = 201, description = "Club founded; the caller is its owner and tops the ladder", body = ClubDisplay),
+        (status = 400, description = "Invalid request, or the name is taken", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("")]
+pub async fn create_club(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateClubRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::create(db.get_ref(), player.id, payload.into_inner()).await {
+        Ok(club) => HttpResponse::Created().json(json!({
+            "message": "Club created",
+            "data": ClubDisplay::new(club, 1)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/clubs",
+    params(
+        ("name" = Option<String>, Query, description = "Part of the club name"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of clubs to return")
+    ),
+    responses(
+        (status = 200, description = "Clubs by name", body = Vec<ClubDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[get("")]
+pub async fn list_clubs(db: web::Data<DatabaseConnection>, query: Query<ClubListQuery>) -> HttpResponse {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(50);
+
+    match ClubService::list(db.get_ref(), query.name, limit).await {
+        Ok(clubs) => HttpResponse::Ok().json(json!({
+            "message": "Clubs found",
+            "data": { "clubs": clubs }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/clubs/{id}",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Club page: the club, the top of its ladder, its latest tournaments and open challenges", body = ClubPage),
+        (status = 404, description = "Club not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[get("/{id}")]
+pub async fn get_club(db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    match ClubService::page(db.get_ref(), id.into_inner()).await {
+        Ok(page) => HttpResponse::Ok().json(json!({
+            "message": "Club found",
+            "data": page
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/join",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 201, description = "Joined the club, at the bottom of its ladder", body = ClubMemberDisplay),
+        (status = 400, description = "Already a member", body = InvalidCredentialsResponse),
+        (status = 403, description = "The club is invite-only", body = InvalidCredentialsResponse),
+        (status = 404, description = "Club not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/join")]
+pub async fn join_club(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::join(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(member) => HttpResponse::Created().json(json!({
+            "message": "Joined club",
+            "data": ClubMemberDisplay::new(member, player.username)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/leave",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Left the club; everyone below moves up a place"),
+        (status = 400, description = "The owner cannot leave", body = InvalidCredentialsResponse),
+        (status = 404, description = "Not a member of the club", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/leave")]
+pub async fn leave_club(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::leave(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Left club",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/clubs/{id}/ladder",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "The whole ladder, top first", body = Vec<ClubMemberDisplay>),
+        (status = 404, description = "Club not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[get("/{id}/ladder")]
+pub async fn club_ladder(db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let club = match ClubService::get(db.get_ref(), id.into_inner()).await {
+        Ok(club) => club,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::members(db.get_ref(), club.id, None).await {
+        Ok(members) => HttpResponse::Ok().json(json!({
+            "message": "Ladder found",
+            "data": { "members": members }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/members",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    request_body = AddMemberRequest,
+    responses(
+        (status = 201, description = "Player added at the bottom of the ladder", body = ClubMemberDisplay),
+        (status = 400, description = "Already a member", body = InvalidCredentialsResponse),
+        (status = 403, description = "Club owner or admin required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Club or player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/members")]
+pub async fn add_club_member(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<AddMemberRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let member = match ClubService::add_member(db.get_ref(), id.into_inner(), player.id, payload.player_id).await {
+        Ok(member) => member,
+        Err(err) => return err.error_response(),
+    };
+    match ClubService::display_member(db.get_ref(), member).await {
+        Ok(member) => HttpResponse::Created().json(json!({
+            "message": "Member added",
+            "data": member
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/clubs/{id}/members/{player_id}",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid"),
+        ("player_id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    request_body = UpdateMemberRequest,
+    responses(
+        (status = 200, description = "Member's role changed", body = ClubMemberDisplay),
+        (status = 400, description = "The owner's role cannot change, nor can a second owner be made", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the owner can change roles", body = InvalidCredentialsResponse),
+        (status = 404, description = "Club membership not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[put("/{id}/members/{player_id}")]
+pub async fn update_club_member(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    path: Path<(Uuid, Uuid)>,
+    payload: Json<UpdateMemberRequest>,
+) -> HttpResponse {
+    let (club_id, player_id) = path.into_inner();
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let member = match ClubService::set_role(db.get_ref(), club_id, player.id, player_id, payload.role.into()).await {
+        Ok(member) => member,
+        Err(err) => return err.error_response(),
+    };
+    match ClubService::display_member(db.get_ref(), member).await {
+        Ok(member) => HttpResponse::Ok().json(json!({
+            "message": "Member updated",
+            "data": member
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/clubs/{id}/members/{player_id}",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid"),
+        ("player_id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Member removed; everyone below moves up a place"),
+        (status = 403, description = "The owner can remove anyone else, admins only plain members", body = InvalidCredentialsResponse),
+        (status = 404, description = "Club membership not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[delete("/{id}/members/{player_id}")]
+pub async fn remove_club_member(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    path: Path<(Uuid, Uuid)>,
+) -> HttpResponse {
+    let (club_id, player_id) = path.into_inner();
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::remove_member(db.get_ref(), club_id, player.id, player_id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Member removed",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/clubs/{id}/ladder/challenges",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid"),
+        ("status" = Option<String>, Query, description = "pending (default), accepted, declined or completed"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of challenges to return")
+    ),
+    responses(
+        (status = 200, description = "Ladder challenges, oldest first", body = Vec<LadderChallengeDisplay>),
+        (status = 404, description = "Club not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[get("/{id}/ladder/challenges")]
+pub async fn list_ladder_challenges(
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    query: Query<ChallengeQuery>,
+) -> HttpResponse {
+    let club = match ClubService::get(db.get_ref(), id.into_inner()).await {
+        Ok(club) => club,
+        Err(err) => return err.error_response(),
+    };
+    let status = query.status.map(ChallengeStatus::from).unwrap_or(ChallengeStatus::Pending);
+    let limit = query.limit.unwrap_or(50);
+
+    match ClubService::challenges(db.get_ref(), club.id, status, limit).await {
+        Ok(challenges) => {
+            let challenges: Vec<LadderChallengeDisplay> =
+                challenges.into_iter().map(LadderChallengeDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Ladder challenges found",
+                "data": { "challenges": challenges }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/ladder/challenges",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid")
+    ),
+    request_body = ChallengeRequest,
+    responses(
+        (status = 201, description = "Challenge sent to the defender", body = LadderChallengeDisplay),
+        (status = 400, description = "Defender out of reach, or a challenge is already open", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only club members can challenge", body = InvalidCredentialsResponse),
+        (status = 404, description = "Club or defender's membership not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/ladder/challenges")]
+pub async fn create_ladder_challenge(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<ChallengeRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::challenge(db.get_ref(), id.into_inner(), player.id, payload.defender_id).await {
+        Ok(challenge) => HttpResponse::Created().json(json!({
+            "message": "Challenge sent",
+            "data": LadderChallengeDisplay::from(challenge)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/ladder/challenges/{challenge_id}/respond",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid"),
+        ("challenge_id" = String, Path, description = "Challenge ID in UUID format", format = "uuid")
+    ),
+    request_body = RespondChallengeRequest,
+    responses(
+        (status = 200, description = "Challenge accepted or declined", body = LadderChallengeDisplay),
+        (status = 400, description = "Challenge is no longer pending", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the defender can answer", body = InvalidCredentialsResponse),
+        (status = 404, description = "Challenge not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/ladder/challenges/{challenge_id}/respond")]
+pub async fn respond_ladder_challenge(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    path: Path<(Uuid, Uuid)>,
+    payload: Json<RespondChallengeRequest>,
+) -> HttpResponse {
+    let (club_id, challenge_id) = path.into_inner();
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::respond(db.get_ref(), club_id, challenge_id, player.id, payload.accept).await {
+        Ok(challenge) => HttpResponse::Ok().json(json!({
+            "message": "Challenge answered",
+            "data": LadderChallengeDisplay::from(challenge)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/clubs/{id}/ladder/challenges/{challenge_id}/result",
+    params(
+        ("id" = String, Path, description = "Club ID in UUID format", format = "uuid"),
+        ("challenge_id" = String, Path, description = "Challenge ID in UUID format", format = "uuid")
+    ),
+    request_body = ChallengeResultRequest,
+    responses(
+        (status = 200, description = "Challenge completed; a challenger's win takes the defender's place", body = LadderChallengeDisplay),
+        (status = 400, description = "Challenge not accepted, or the game is unfinished or between other players", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the two players can report", body = InvalidCredentialsResponse),
+        (status = 404, description = "Challenge or game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Clubs"
+)]
+#[post("/{id}/ladder/challenges/{challenge_id}/result")]
+pub async fn report_ladder_result(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    path: Path<(Uuid, Uuid)>,
+    payload: Json<ChallengeResultRequest>,
+) -> HttpResponse {
+    let (club_id, challenge_id) = path.into_inner();
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ClubService::report_result(db.get_ref(), club_id, challenge_id, player.id, payload.game_id).await {
+        Ok(challenge) => HttpResponse::Ok().json(json!({
+            "message": "Challenge result recorded",
+            "data": LadderChallengeDisplay::from(challenge)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
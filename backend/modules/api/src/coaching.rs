@@ -0,0 +1,487 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, post, put,
+    web::{self, Json, Path, Query},
+};
+use db_entity::coaching_assignment::AssignmentStatus;
+use dto::coaching::{
+    AssignmentDisplay, AssignmentQuery, CompleteAssignmentRequest, CompletionDisplay, CreateAssignmentRequest,
+    InviteStudentRequest, UpdateAssignmentRequest,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::coaching::CoachingService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    post,
+    path = "/v1/coaching/students",
+    request_body = InviteStudentRequest,
+    responses(
+        (status = 201, description = "Invitation sent; nothing is shared until the student accepts", body = CoachingLinkDisplay),
+        (status = 400, description = "Already coaching or invited this student, or inviting yourself", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[post("/students")]
+pub async fn invite_student(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<InviteStudentRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::invite(db.get_ref(), player.id, payload.student_id).await {
+        Ok(link) => HttpResponse::Created().json(json!({
+            "message": "Student invited",
+            "data": link
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/students",
+    responses(
+        (status = 200, description = "The caller's students, pending and active, oldest first", body = Vec<CoachingLinkDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/students")]
+pub async fn list_students(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::links(db.get_ref(), player.id, true).await {
+        Ok(links) => HttpResponse::Ok().json(json!({
+            "message": "Students found",
+            "data": { "links": links }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/coaches",
+    responses(
+        (status = 200, description = "The caller's coaches and invitations, oldest first", body = Vec<CoachingLinkDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/coaches")]
+pub async fn list_coaches(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::links(db.get_ref(), player.id, false).await {
+        Ok(links) => HttpResponse::Ok().json(json!({
+            "message": "Coaches found",
+            "data": { "links": links }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/coaching/links/{id}/accept",
+    params(
+        ("id" = String, Path, description = "Coaching link ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Invitation accepted; the coach can set assignments and read the student's games and stats", body = CoachingLinkDisplay),
+        (status = 400, description = "Invitation is no longer pending", body = InvalidCredentialsResponse),
+        (status = 404, description = "No invitation to the caller with this ID", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[post("/links/{id}/accept")]
+pub async fn accept_coaching(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::accept(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(link) => HttpResponse::Ok().json(json!({
+            "message": "Coaching accepted",
+            "data": link
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/coaching/links/{id}",
+    params(
+        ("id" = String, Path, description = "Coaching link ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Link ended, or invitation declined; the coach loses access at once"),
+        (status = 400, description = "Link has already ended", body = InvalidCredentialsResponse),
+        (status = 404, description = "Coaching link not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[delete("/links/{id}")]
+pub async fn end_coaching(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::end(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Coaching ended",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/students/{student_id}",
+    params(
+        ("student_id" = String, Path, description = "Student's player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "The student's stats, drill results, recent games and assignment progress", body = StudentOverview),
+        (status = 403, description = "No active coaching link with this student", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/students/{student_id}")]
+pub async fn student_overview(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    student_id: Path<Uuid>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::overview(db.get_ref(), player.id, student_id.into_inner()).await {
+        Ok(overview) => HttpResponse::Ok().json(json!({
+            "message": "Student found",
+            "data": overview
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/students/{student_id}/assignments",
+    params(
+        ("student_id" = String, Path, description = "Student's player ID in UUID format", format = "uuid"),
+        ("status" = Option<String>, Query, description = "assigned or completed; both when absent"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of assignments to return")
+    ),
+    responses(
+        (status = 200, description = "Assignments the caller set the student, soonest due first", body = Vec<AssignmentDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/students/{student_id}/assignments")]
+pub async fn student_assignments(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    student_id: Path<Uuid>,
+    query: Query<AssignmentQuery>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    let status = query.status.map(AssignmentStatus::from);
+    let limit = query.limit.unwrap_or(50);
+
+    match CoachingService::assignments(db.get_ref(), student_id.into_inner(), Some(player.id), status, limit).await {
+        Ok(assignments) => {
+            let assignments: Vec<AssignmentDisplay> = assignments
+                .into_iter()
+                .map(|assignment| AssignmentDisplay::new(assignment, player.id))
+                .collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Assignments found",
+                "data": { "assignments": assignments }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/coaching/assignments",
+    request_body = CreateAssignmentRequest,
+    responses(
+        (status = 201, description = "Assignment set", body = AssignmentDisplay),
+        (status = 400, description = "Invalid request, or a puzzle line with an illegal move", body = InvalidCredentialsResponse),
+        (status = 403, description = "No active coaching link with this student", body = InvalidCredentialsResponse),
+        (status = 404, description = "Study game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[post("/assignments")]
+pub async fn create_assignment(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateAssignmentRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::create_assignment(db.get_ref(), player.id, payload.into_inner()).await {
+        Ok(assignment) => HttpResponse::Created().json(json!({
+            "message": "Assignment created",
+            "data": AssignmentDisplay::new(assignment, player.id)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/assignments",
+    params(
+        ("status" = Option<String>, Query, description = "assigned or completed; both when absent"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of assignments to return")
+    ),
+    responses(
+        (status = 200, description = "The caller's assignments from all their coaches, soonest due first", body = Vec<AssignmentDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/assignments")]
+pub async fn my_assignments(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<AssignmentQuery>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    let status = query.status.map(AssignmentStatus::from);
+    let limit = query.limit.unwrap_or(50);
+
+    match CoachingService::assignments(db.get_ref(), player.id, None, status, limit).await {
+        Ok(assignments) => {
+            let assignments: Vec<AssignmentDisplay> = assignments
+                .into_iter()
+                .map(|assignment| AssignmentDisplay::new(assignment, player.id))
+                .collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Assignments found",
+                "data": { "assignments": assignments }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/coaching/assignments/{id}",
+    params(
+        ("id" = String, Path, description = "Assignment ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Assignment; the student sees a puzzle's solution once they solved it", body = AssignmentDisplay),
+        (status = 404, description = "Assignment not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[get("/assignments/{id}")]
+pub async fn get_assignment(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::assignment(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(assignment) => HttpResponse::Ok().json(json!({
+            "message": "Assignment found",
+            "data": AssignmentDisplay::new(assignment, player.id)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/coaching/assignments/{id}",
+    params(
+        ("id" = String, Path, description = "Assignment ID in UUID format", format = "uuid")
+    ),
+    request_body = UpdateAssignmentRequest,
+    responses(
+        (status = 200, description = "Assignment updated", body = AssignmentDisplay),
+        (status = 400, description = "Invalid request, or the assignment is completed", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the coach can change an assignment", body = InvalidCredentialsResponse),
+        (status = 404, description = "Assignment not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[put("/assignments/{id}")]
+pub async fn update_assignment(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<UpdateAssignmentRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::update_assignment(db.get_ref(), id.into_inner(), player.id, payload.into_inner()).await {
+        Ok(assignment) => HttpResponse::Ok().json(json!({
+            "message": "Assignment updated",
+            "data": AssignmentDisplay::new(assignment, player.id)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/coaching/assignments/{id}",
+    params(
+        ("id" = String, Path, description = "Assignment ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Assignment deleted"),
+        (status = 403, description = "Only the coach can delete an assignment", body = InvalidCredentialsResponse),
+        (status = 404, description = "Assignment not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[delete("/assignments/{id}")]
+pub async fn delete_assignment(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::delete_assignment(db.get_ref(), id.into_inner(), player.id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Assignment deleted",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/coaching/assignments/{id}/complete",
+    params(
+        ("id" = String, Path, description = "Assignment ID in UUID format", format = "uuid")
+    ),
+    request_body = CompleteAssignmentRequest,
+    responses(
+        (status = 200, description = "Whether the assignment is now complete; a wrong puzzle line counts as an attempt and leaves it open", body = CompletionDisplay),
+        (status = 400, description = "Already completed, or the drill session does not qualify", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the student can complete an assignment, while the link is active", body = InvalidCredentialsResponse),
+        (status = 404, description = "Assignment or training session not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Coaching"
+)]
+#[post("/assignments/{id}/complete")]
+pub async fn complete_assignment(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<CompleteAssignmentRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match CoachingService::complete(db.get_ref(), id.into_inner(), player.id, payload.into_inner()).await {
+        Ok((completed, assignment)) => HttpResponse::Ok().json(json!({
+            "message": if completed { "Assignment completed" } else { "Not solved yet" },
+            "data": CompletionDisplay {
+                completed,
+                assignment: AssignmentDisplay::new(assignment, player.id),
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
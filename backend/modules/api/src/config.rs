@@ -1,16 +1,153 @@
+use service::engine_service::LoadConfig;
+use std::collections::HashMap;
 use std::env;
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
+    /// Read replicas for read-only queries; empty sends every query to the primary
+    pub database_replica_urls: Vec<String>,
+    /// How often read replicas are pinged to take unreachable ones out of rotation
+    pub replica_health_check_secs: u64,
     pub auth_rate_limit_per_sec: u64,
     pub auth_rate_limit_burst: u32,
     pub game_rate_limit_per_sec: u64,
     pub game_rate_limit_burst: u32,
+    pub leaderboard_refresh_secs: u64,
+    /// Age in days after which finished games move to the archive; 0 disables archival
+    pub game_archive_after_days: i64,
+    /// How often finished games are moved to the archive
+    pub game_archive_poll_secs: u64,
+    /// Games moved per archival transaction
+    pub game_archive_batch: u64,
+    /// How often finished games are indexed for position search
+    pub position_index_poll_secs: u64,
+    /// Length of a Glicko-2 rating period; 0 disables inactivity decay
+    pub rating_period_days: i64,
+    /// How often the queue of rating recalculations is checked
+    pub rating_recalculation_poll_secs: u64,
+    /// How often template runs are created and scheduled tournaments advanced
+    pub tournament_scheduler_secs: u64,
+    /// Engine binaries that analyse variants the analysis engine does not,
+    /// by variant
+    pub variant_engines: HashMap<String, String>,
+    /// Engine binaries engine-vs-engine matches may run, by name
+    pub match_engines: HashMap<String, String>,
+    /// How often the queue of engine matches is checked
+    pub engine_match_poll_secs: u64,
+    /// How long responses to requests with an `Idempotency-Key` are kept
+    pub idempotency_ttl_secs: u64,
+    /// Shortest time between two game archive exports by the same player
+    pub archive_export_cooldown_secs: u64,
+    /// Timeout for each request to Lichess or Chess.com during an account import
+    pub import_timeout_secs: u64,
+    /// Engines that may search at the same time for analysis requests
+    pub engine_slots: usize,
+    /// Engine slots kept free of batch analysis for interactive requests
+    pub engine_reserved_interactive_slots: usize,
+    /// Analysis jobs one user may run at the same time
+    pub engine_jobs_per_user: usize,
+    /// JSON manifest of engine binaries and networks to download; unset
+    /// means the engine at `ENGINE_PATH` is used as installed
+    pub engine_assets_manifest: Option<String>,
+    /// Where downloaded engine binaries and networks are cached
+    pub engine_assets_dir: String,
+    /// Engine of the manifest that serves analysis requests
+    pub analysis_engine: String,
+    /// Timeout for downloading one engine binary or network
+    pub engine_assets_timeout_secs: u64,
+    /// gRPC address of a separate engine pool (`engine-server`); unset runs
+    /// the engines in this process
+    pub engine_grpc_url: Option<String>,
+    /// Timeout for each request to the engine pool, queueing included
+    pub engine_grpc_timeout_secs: u64,
+    /// How far analysis is cut back while the engines here are busy; a
+    /// separate engine pool reads its own
+    pub analysis_load: LoadConfig,
+    /// Usernames whose analysis is never cut back for load
+    pub premium_analysis_players: Vec<String>,
+    /// Engines kept running between searches of followed games; 0 starts an
+    /// engine for every search
+    pub engine_sessions: usize,
+    /// How long the engine of a followed game is kept after its last search
+    pub engine_session_idle_secs: u64,
+    /// StarkNet JSON-RPC endpoint; unset disables game attestations
+    pub starknet_rpc_url: Option<String>,
+    /// Account contract that submits attestations
+    pub starknet_account_address: Option<String>,
+    /// Contract that stores the attestations
+    pub starknet_attestation_contract: Option<String>,
+    pub starknet_chain_id: String,
+    /// Highest fee, in wei, one attestation transaction may pay
+    pub starknet_max_fee_wei: u64,
+    /// How often finished games are queued and attestations sent or checked
+    pub attestation_poll_secs: u64,
+    /// How long a wallet sign-in challenge may be answered
+    pub wallet_challenge_ttl_secs: u64,
+    /// How often due webhook deliveries are sent
+    pub webhook_poll_secs: u64,
+    /// Timeout for each request to a webhook URL
+    pub webhook_timeout_secs: u64,
+    /// Days a closed account can be reactivated before it is anonymized
+    pub account_closure_grace_days: u64,
+    /// How often closed accounts past their grace period are anonymized
+    pub account_purge_poll_secs: u64,
+    /// How often ratings, games and fingerprints are scanned for sandbagging,
+    /// win trading and accounts sharing devices
+    pub integrity_scan_secs: u64,
+    /// Longest an accepted game event waits in memory before it is written;
+    /// 0 writes every event at once
+    pub game_autosave_interval_ms: u64,
+    /// Events of one game written as soon as this many are waiting
+    pub game_autosave_max_events: usize,
+    /// Key the network and device fingerprints are hashed with; nothing is
+    /// recorded when unset
+    pub fingerprint_secret: Option<String>,
+    /// Days a fingerprint is kept after it was last seen
+    pub fingerprint_retention_days: i64,
+    /// `none`, `pow`, `hcaptcha` or `turnstile`
+    pub captcha_provider: String,
+    pub captcha_site_key: Option<String>,
+    pub captcha_secret: Option<String>,
+    /// Leading zero bits a proof of work must reach
+    pub captcha_pow_difficulty: u8,
+    /// How long a proof-of-work challenge may be answered
+    pub captcha_pow_ttl_secs: u64,
+    /// How long a guest session lasts
+    pub guest_session_ttl_secs: u64,
+    /// Redis holding revoked access tokens, shared by every instance;
+    /// revocations stay in this process when unset
+    pub token_denylist_redis_url: Option<String>,
+    /// How long a denylist lookup is reused before asking the store again
+    pub token_denylist_cache_secs: u64,
+    /// Redis holding cached responses of hot read endpoints, shared by every
+    /// instance; responses are cached in this process when unset
+    pub cache_redis_url: Option<String>,
+    /// How long leaderboard pages and ranks are cached; 0 disables caching
+    pub cache_leaderboard_secs: u64,
+    /// How long tournament standings are cached; 0 disables caching
+    pub cache_standings_secs: u64,
+    /// How long position search results are cached; 0 disables caching
+    pub cache_position_search_secs: u64,
+    /// How long full-depth position analyses are cached; 0 disables caching
+    pub cache_analysis_secs: u64,
+    /// Whether `starkmate-admin seed-fixtures` may load sample data; never
+    /// set it outside development
+    pub fixtures_enabled: bool,
 }
 
 impl AppConfig {
     pub fn from_env() -> Self {
         Self {
+            database_replica_urls: env::var("DATABASE_REPLICA_URLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            replica_health_check_secs: env::var("REPLICA_HEALTH_CHECK_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
             auth_rate_limit_per_sec: env::var("AUTH_RATE_LIMIT_PER_SEC")
                 .unwrap_or_else(|_| "1".to_string())
                 .parse()
@@ -27,6 +164,219 @@ impl AppConfig {
                 .unwrap_or_else(|_| "20".to_string())
                 .parse()
                 .unwrap_or(20),
+            leaderboard_refresh_secs: env::var("LEADERBOARD_REFRESH_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            game_archive_after_days: env::var("GAME_ARCHIVE_AFTER_DAYS")
+                .unwrap_or_else(|_| "180".to_string())
+                .parse()
+                .unwrap_or(180),
+            game_archive_poll_secs: env::var("GAME_ARCHIVE_POLL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            game_archive_batch: env::var("GAME_ARCHIVE_BATCH")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(500),
+            position_index_poll_secs: env::var("POSITION_INDEX_POLL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            rating_period_days: env::var("RATING_PERIOD_DAYS")
+                .unwrap_or_else(|_| "7".to_string())
+                .parse()
+                .unwrap_or(7),
+            rating_recalculation_poll_secs: env::var("RATING_RECALCULATION_POLL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            tournament_scheduler_secs: env::var("TOURNAMENT_SCHEDULER_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            variant_engines: parse_match_engines(&env::var("VARIANT_ENGINES").unwrap_or_default()),
+            match_engines: parse_match_engines(
+                &env::var("MATCH_ENGINES").unwrap_or_else(|_| {
+                    format!("stockfish={}", env::var("ENGINE_PATH").unwrap_or_else(|_| "stockfish".to_string()))
+                }),
+            ),
+            engine_match_poll_secs: env::var("ENGINE_MATCH_POLL_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            idempotency_ttl_secs: env::var("IDEMPOTENCY_TTL_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .unwrap_or(86400),
+            archive_export_cooldown_secs: env::var("ARCHIVE_EXPORT_COOLDOWN_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            import_timeout_secs: env::var("IMPORT_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            engine_slots: env::var("ENGINE_SLOTS")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .unwrap_or(4),
+            engine_reserved_interactive_slots: env::var("ENGINE_RESERVED_INTERACTIVE_SLOTS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            engine_jobs_per_user: env::var("ENGINE_JOBS_PER_USER")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .unwrap_or(2),
+            engine_assets_manifest: env::var("ENGINE_ASSETS_MANIFEST").ok().filter(|path| !path.is_empty()),
+            engine_assets_dir: env::var("ENGINE_ASSETS_DIR").unwrap_or_else(|_| "engine-assets".to_string()),
+            analysis_engine: env::var("ANALYSIS_ENGINE").unwrap_or_else(|_| "stockfish".to_string()),
+            engine_assets_timeout_secs: env::var("ENGINE_ASSETS_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()
+                .unwrap_or(600),
+            engine_grpc_url: env::var("ENGINE_GRPC_URL").ok().filter(|url| !url.is_empty()),
+            engine_grpc_timeout_secs: env::var("ENGINE_GRPC_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            analysis_load: analysis_load_from_env(),
+            premium_analysis_players: env::var("PREMIUM_ANALYSIS_PLAYERS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|username| username.trim().to_string())
+                .filter(|username| !username.is_empty())
+                .collect(),
+            engine_sessions: env::var("ENGINE_SESSIONS")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .unwrap_or(8),
+            engine_session_idle_secs: env::var("ENGINE_SESSION_IDLE_SECS")
+                .unwrap_or_else(|_| "120".to_string())
+                .parse()
+                .unwrap_or(120),
+            starknet_rpc_url: env::var("STARKNET_RPC_URL").ok().filter(|url| !url.is_empty()),
+            starknet_account_address: env::var("STARKNET_ACCOUNT_ADDRESS").ok().filter(|address| !address.is_empty()),
+            starknet_attestation_contract: env::var("STARKNET_ATTESTATION_CONTRACT")
+                .ok()
+                .filter(|address| !address.is_empty()),
+            starknet_chain_id: env::var("STARKNET_CHAIN_ID").unwrap_or_else(|_| "SN_SEPOLIA".to_string()),
+            starknet_max_fee_wei: env::var("STARKNET_MAX_FEE_WEI")
+                .unwrap_or_else(|_| "1000000000000000".to_string())
+                .parse()
+                .unwrap_or(1_000_000_000_000_000),
+            attestation_poll_secs: env::var("ATTESTATION_POLL_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            wallet_challenge_ttl_secs: env::var("WALLET_CHALLENGE_TTL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            webhook_poll_secs: env::var("WEBHOOK_POLL_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            webhook_timeout_secs: env::var("WEBHOOK_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            account_closure_grace_days: env::var("ACCOUNT_CLOSURE_GRACE_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            account_purge_poll_secs: env::var("ACCOUNT_PURGE_POLL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            integrity_scan_secs: env::var("INTEGRITY_SCAN_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            game_autosave_interval_ms: env::var("GAME_AUTOSAVE_INTERVAL_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            game_autosave_max_events: env::var("GAME_AUTOSAVE_MAX_EVENTS")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .unwrap_or(20),
+            fingerprint_secret: env::var("FINGERPRINT_SECRET").ok().filter(|secret| !secret.is_empty()),
+            fingerprint_retention_days: env::var("FINGERPRINT_RETENTION_DAYS")
+                .unwrap_or_else(|_| "90".to_string())
+                .parse()
+                .unwrap_or(90),
+            captcha_provider: env::var("CAPTCHA_PROVIDER")
+                .unwrap_or_else(|_| "none".to_string())
+                .to_lowercase(),
+            captcha_site_key: env::var("CAPTCHA_SITE_KEY").ok().filter(|key| !key.is_empty()),
+            captcha_secret: env::var("CAPTCHA_SECRET").ok().filter(|secret| !secret.is_empty()),
+            captcha_pow_difficulty: env::var("CAPTCHA_POW_DIFFICULTY")
+                .unwrap_or_else(|_| "18".to_string())
+                .parse()
+                .unwrap_or(18),
+            captcha_pow_ttl_secs: env::var("CAPTCHA_POW_TTL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            guest_session_ttl_secs: env::var("GUEST_SESSION_TTL_SECS")
+                .unwrap_or_else(|_| "7200".to_string())
+                .parse()
+                .unwrap_or(7200),
+            token_denylist_redis_url: env::var("TOKEN_DENYLIST_REDIS_URL").ok().filter(|url| !url.is_empty()),
+            token_denylist_cache_secs: env::var("TOKEN_DENYLIST_CACHE_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            cache_redis_url: env::var("CACHE_REDIS_URL").ok().filter(|url| !url.is_empty()),
+            cache_leaderboard_secs: env::var("CACHE_LEADERBOARD_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            cache_standings_secs: env::var("CACHE_STANDINGS_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            cache_position_search_secs: env::var("CACHE_POSITION_SEARCH_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            cache_analysis_secs: env::var("CACHE_ANALYSIS_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .unwrap_or(86400),
+            fixtures_enabled: env::var("ENABLE_FIXTURES").is_ok_and(|flag| flag == "true" || flag == "1"),
         }
     }
 }
+
+/// `LoadConfig` from the `ANALYSIS_*` variables, with its defaults for
+/// those unset or malformed.
+fn analysis_load_from_env() -> LoadConfig {
+    fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+        env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+    }
+    let defaults = LoadConfig::default();
+    LoadConfig {
+        default_depth: var("ANALYSIS_DEFAULT_DEPTH", defaults.default_depth),
+        min_depth: var("ANALYSIS_MIN_DEPTH", defaults.min_depth),
+        busy: var("ANALYSIS_BUSY_LOAD", defaults.busy),
+        idle: var("ANALYSIS_IDLE_LOAD", defaults.idle),
+        guest_floor: var("ANALYSIS_GUEST_FLOOR", defaults.guest_floor),
+        player_floor: var("ANALYSIS_PLAYER_FLOOR", defaults.player_floor),
+        premium_floor: var("ANALYSIS_PREMIUM_FLOOR", defaults.premium_floor),
+        ..defaults
+    }
+}
+
+/// Parse `name=path` pairs separated by commas, skipping malformed entries.
+fn parse_match_engines(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(name, path)| (name.trim().to_string(), path.trim().to_string()))
+        .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+        .collect()
+}
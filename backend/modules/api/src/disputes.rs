@@ -0,0 +1,150 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::{game_dispute::DisputeStatus, player_role::Role};
+use dto::disputes::{DecideDisputeRequest, DisputeDisplay, DisputeQueueQuery, OpenDisputeRequest};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::disputes::DisputeService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::cache::ResponseCache;
+use crate::guard::{current_player, require_role};
+
+#[utoipa::path(
+    post,
+    path = "/v1/games/{id}/disputes",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    request_body = OpenDisputeRequest,
+    responses(
+        (status = 201, description = "Dispute opened with the game attached as evidence", body = DisputeDisplay),
+        (status = 400, description = "Game not finished, already disputed or not in the given round", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only players of the game can dispute it", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game or tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Disputes"
+)]
+#[post("")]
+pub async fn open_dispute(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<OpenDisputeRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let claimant = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match DisputeService::open(db.get_ref(), claimant.id, id.into_inner(), payload.into_inner()).await {
+        Ok(dispute) => HttpResponse::Created().json(json!({
+            "message": "Dispute opened",
+            "data": DisputeDisplay::from(dispute)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/disputes",
+    params(
+        ("status" = Option<String>, Query, description = "Queue to read: open (default), upheld or overturned"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of disputes to return")
+    ),
+    responses(
+        (status = 200, description = "Arbiter queue", body = Vec<DisputeDisplay>),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Disputes"
+)]
+#[get("")]
+pub async fn list_disputes(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<DisputeQueueQuery>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    let status = query.status.map(DisputeStatus::from).unwrap_or(DisputeStatus::Open);
+    let limit = query.limit.unwrap_or(50);
+
+    match DisputeService::list(db.get_ref(), status, limit).await {
+        Ok(disputes) => {
+            let disputes: Vec<DisputeDisplay> = disputes.into_iter().map(DisputeDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Disputes found",
+                "data": { "disputes": disputes }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/disputes/{id}/decide",
+    params(
+        ("id" = String, Path, description = "Dispute ID in UUID format", format = "uuid")
+    ),
+    request_body = DecideDisputeRequest,
+    responses(
+        (status = 200, description = "Dispute decided; an overturned result is applied to the game, tournament standings and ratings", body = DisputeDisplay),
+        (status = 400, description = "Dispute already decided or result cannot be corrected", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required, and not a player of the game", body = InvalidCredentialsResponse),
+        (status = 404, description = "Dispute not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Disputes"
+)]
+#[post("/{id}/decide")]
+pub async fn decide_dispute(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<DecideDisputeRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let arbiter = match require_role(db.get_ref(), &req, Role::Arbiter).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match DisputeService::decide(db.get_ref(), id.into_inner(), arbiter.id, payload.into_inner()).await {
+        Ok(dispute) => {
+            // An upheld dispute may have corrected a tournament result
+            if let Some(tournament_id) = dispute.tournament_id {
+                cache.tournament_changed(tournament_id).await;
+            }
+            HttpResponse::Ok().json(json!({
+                "message": "Dispute decided",
+                "data": DisputeDisplay::from(dispute)
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
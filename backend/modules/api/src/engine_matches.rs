@@ -0,0 +1,201 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path},
+};
+use db_entity::player_role::Role;
+use dto::engine_matches::CreateEngineMatchRequest;
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::engine_matches::{self, EngineMatchService};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::AppConfig;
+use crate::guard::require_role;
+
+#[utoipa::path(
+    post,
+    path = "/v1/engine-matches",
+    request_body = CreateEngineMatchRequest,
+    responses(
+        (status = 201, description = "Match queued; the server plays queued matches one at a time", body = EngineMatchDisplay),
+        (status = 400, description = "Unknown engine, invalid option, illegal opening or bad SPRT bounds", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Engine Matches"
+)]
+#[post("")]
+pub async fn create_engine_match(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<AppConfig>,
+    payload: Json<CreateEngineMatchRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let admin = match require_role(db.get_ref(), &req, Role::Admin).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let created = EngineMatchService::create(db.get_ref(), admin.id, payload.into_inner(), &config.match_engines)
+        .await
+        .and_then(engine_matches::display);
+    match created {
+        Ok(engine_match) => HttpResponse::Created().json(json!({
+            "message": "Engine match queued",
+            "data": { "match": engine_match }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/engine-matches",
+    responses(
+        (status = 200, description = "All engine matches, newest first", body = Vec<EngineMatchDisplay>),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Engine Matches"
+)]
+#[get("")]
+pub async fn list_engine_matches(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    let matches = EngineMatchService::list(db.get_ref())
+        .await
+        .and_then(|matches| matches.into_iter().map(engine_matches::display).collect::<Result<Vec<_>, _>>());
+    match matches {
+        Ok(matches) => HttpResponse::Ok().json(json!({
+            "message": "Engine matches found",
+            "data": { "matches": matches }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/engine-matches/engines",
+    responses(
+        (status = 200, description = "Names of the engine binaries matches can use", body = Vec<String>),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Engine Matches"
+)]
+#[get("/engines")]
+pub async fn list_match_engines(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    let mut engines: Vec<&String> = config.match_engines.keys().collect();
+    engines.sort();
+    HttpResponse::Ok().json(json!({
+        "message": "Engines found",
+        "data": { "engines": engines }
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/engine-matches/{id}",
+    params(
+        ("id" = String, Path, description = "Engine match ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "The match with its Elo/SPRT summary and every game played, with PGN", body = EngineMatchDisplay),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Engine match not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Engine Matches"
+)]
+#[get("/{id}")]
+pub async fn get_engine_match(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    let id = id.into_inner();
+    let found = async {
+        let model = EngineMatchService::find(db.get_ref(), id).await?;
+        let games = EngineMatchService::games(db.get_ref(), id)
+            .await?
+            .into_iter()
+            .map(|game| engine_matches::game_display(&model, game))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok::<_, ApiError>((engine_matches::display(model)?, games))
+    };
+    match found.await {
+        Ok((engine_match, games)) => HttpResponse::Ok().json(json!({
+            "message": "Engine match found",
+            "data": { "match": engine_match, "games": games }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/engine-matches/{id}/cancel",
+    params(
+        ("id" = String, Path, description = "Engine match ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Match cancelled; a running match stops after its current game", body = EngineMatchDisplay),
+        (status = 400, description = "Match already over", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Engine match not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Engine Matches"
+)]
+#[post("/{id}/cancel")]
+pub async fn cancel_engine_match(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    let cancelled = EngineMatchService::cancel(db.get_ref(), id.into_inner())
+        .await
+        .and_then(engine_matches::display);
+    match cancelled {
+        Ok(engine_match) => HttpResponse::Ok().json(json!({
+            "message": "Engine match cancelled",
+            "data": { "match": engine_match }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
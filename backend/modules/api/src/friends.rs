@@ -0,0 +1,109 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, put,
+    web::{self, Path},
+};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::friends::FriendService;
+use uuid::Uuid;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    get,
+    path = "/v1/friends",
+    responses(
+        (status = 200, description = "Players who can read the caller's friends-only content", body = Vec<FriendDisplay>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[get("")]
+pub async fn list_friends(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match FriendService::list(db.get_ref(), player.id).await {
+        Ok(friends) => HttpResponse::Ok().json(json!({
+            "message": "Friends found",
+            "data": { "friends": friends }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/friends/{player_id}",
+    params(
+        ("player_id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Player added to the caller's friends list"),
+        (status = 400, description = "Cannot add yourself", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[put("/{player_id}")]
+pub async fn add_friend(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    player_id: Path<Uuid>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match FriendService::add(db.get_ref(), player.id, player_id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Friend added",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/friends/{player_id}",
+    params(
+        ("player_id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Player removed from the caller's friends list"),
+        (status = 404, description = "Player is not on the friends list", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[delete("/{player_id}")]
+pub async fn remove_friend(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    player_id: Path<Uuid>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match FriendService::remove(db.get_ref(), player.id, player_id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Friend removed",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,123 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::player_role::Role;
+use dto::games::{GameEvent, GameEventsQuery};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::autosave::MoveBuffer;
+use service::game_events::GameEventService;
+use uuid::Uuid;
+
+use crate::guard::{current_player, require_role};
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/{id}/events",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid"),
+        ("after" = Option<i32>, Query, description = "Return the events after this sequence number (default 0)"),
+        ("limit" = Option<u64>, Query, description = "Number of events per page (default 100, at most 500)")
+    ),
+    responses(
+        (status = 200, description = "Events of the game's log in order, and the state after its latest event", body = GameLogResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("")]
+pub async fn get_game_events(
+    db: web::Data<DatabaseConnection>,
+    buffer: web::Data<MoveBuffer>,
+    id: Path<Uuid>,
+    query: Query<GameEventsQuery>,
+) -> HttpResponse {
+    let game_id = id.into_inner();
+    // Resyncing clients get every accepted event, buffered ones included
+    if let Err(err) = buffer.flush_game(db.get_ref(), game_id).await {
+        log::error!("Failed to write the events of game {}: {}", game_id, err);
+    }
+
+    let query = query.into_inner();
+    match GameEventService::log(db.get_ref(), game_id, query.after.unwrap_or(0), query.limit.unwrap_or(100)).await {
+        Ok(log) => HttpResponse::Ok().json(json!({
+            "message": "Game events found",
+            "data": log
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/games/{id}/events",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    request_body = GameEvent,
+    responses(
+        (status = 200, description = "Event appended; the state after it", body = GameLogState),
+        (status = 400, description = "The game does not allow the event now", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the players may act, each for their own side; clocks and endings other than resignation are set by the server", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[post("")]
+pub async fn append_game_event(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    buffer: web::Data<MoveBuffer>,
+    id: Path<Uuid>,
+    payload: Json<GameEvent>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match buffer.append(db.get_ref(), id.into_inner(), Some(player.id), payload.into_inner()).await {
+        Ok(state) => HttpResponse::Ok().json(json!({
+            "message": "Event recorded",
+            "data": state
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/autosave",
+    responses(
+        (status = 200, description = "Game events waiting in the write-behind buffer, and the batches written since the server started", body = AutosaveStats),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("")]
+pub async fn get_autosave_stats(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    buffer: web::Data<MoveBuffer>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    HttpResponse::Ok().json(json!({
+        "message": "Autosave buffer",
+        "data": buffer.stats()
+    }))
+}
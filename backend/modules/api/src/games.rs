@@ -1,9 +1,9 @@
 use actix_web::{
-    HttpResponse, delete, get, post, put,
+    HttpRequest, HttpResponse, delete, get, post, put,
     web::{self, Json, Path, Query},
 };
 use dto::{
-    games::{CreateGameRequest, GameDisplayDTO, MakeMoveRequest, JoinGameRequest, GameStatus, ListGamesQuery, ImportGameRequest, ImportGameResponse},
+    games::{CreateGameRequest, GameDisplayDTO, GameEvent, GameResult, MakeMoveRequest, JoinGameRequest, GameStatus, ListGamesQuery, ImportGameRequest, ImportGameResponse},
     responses::{InvalidCredentialsResponse, NotFoundResponse},
 };
 use error::error::ApiError;
@@ -13,16 +13,26 @@ use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use sea_orm::DatabaseConnection;
-use service::games::GameService;
+use security::{Captcha, JwtService};
+use service::games::{self as games_service, GameService};
+use service::autosave::MoveBuffer;
+use service::replay::ReplayService;
+use crate::guard::{captcha_error, current_player, has_valid_token, require_captcha};
+use crate::guests::{guest_claims, GuestError, GuestSessions};
+use crate::replicas::ReadReplicas;
 
 #[utoipa::path(
     post,
     path = "/v1/games",
     request_body = CreateGameRequest,
+    params(
+        ("X-Captcha" = Option<String>, Header, description = "Solution of the challenge from /v1/auth/captcha; required without an access token when captchas are enabled")
+    ),
     responses(
-        (status = 201, description = "Game created successfully", body = GameDisplayDTO),
-        (status = 400, description = "Invalid request parameters", body = InvalidCredentialsResponse),
-        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+        (status = 201, description = "Game created successfully; odds games start from the handicap position and are unrated, guest games are always unrated", body = GameDisplayDTO),
+        (status = 400, description = "Invalid request parameters, impossible odds or missing captcha", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized or guest session ended", body = InvalidCredentialsResponse),
+        (status = 403, description = "Captcha not solved, or a guest asked for a rated game", body = InvalidCredentialsResponse)
     ),
     security(
         ("jwt_auth" = [])
@@ -30,17 +40,54 @@ use service::games::GameService;
     tag = "Games"
 )]
 #[post("")]
-pub async fn create_game(payload: Json<CreateGameRequest>) -> HttpResponse {
+pub async fn create_game(
+    req: HttpRequest,
+    jwt_service: web::Data<JwtService>,
+    guests: web::Data<GuestSessions>,
+    captcha: Option<web::Data<Captcha>>,
+    payload: Json<CreateGameRequest>,
+) -> HttpResponse {
+    // Anonymous seeks are what bot farms flood the lobby with
+    if !has_valid_token(&req, &jwt_service) {
+        if let Err(err) = require_captcha(captcha.as_ref(), &req).await {
+            return captcha_error(err).error_response();
+        }
+    }
+
     match payload.0.validate() {
         Ok(_) => {
+            let fen = match games_service::start_fen(payload.0.odds.as_ref()) {
+                Ok(fen) => fen,
+                Err(err) => return err.error_response(),
+            };
+
+            let guest = guest_claims(&req, &jwt_service).map(|(guest_id, _)| guest_id);
+            if guest.is_some() && payload.0.rated == Some(true) {
+                return GuestError::NotCasual.into_api_error().error_response();
+            }
+            let rated = guest.is_none() && payload.0.rated.unwrap_or(true) && payload.0.odds.is_none();
+
+            let id = Uuid::new_v4();
+            if !rated {
+                guests.open_casual_game(id);
+            }
+            if let Some(guest_id) = guest {
+                if let Err(err) = guests.join(guest_id, id) {
+                    return err.into_api_error().error_response();
+                }
+            }
+
             // The real implementation would create a game in the database
             // For now, we'll just return a mock response
             HttpResponse::Created().json(json!({
                 "message": "Game created successfully",
                 "data": {
                     "game": {
-                        "id": Uuid::new_v4(),
-                        "status": "waiting"
+                        "id": id,
+                        "status": "waiting",
+                        "current_fen": fen,
+                        "odds": payload.0.odds,
+                        "rated": rated
                     }
                 }
             }))
@@ -87,8 +134,9 @@ pub async fn get_game(id: Path<Uuid>) -> HttpResponse {
     ),
     request_body = MakeMoveRequest,
     responses(
-        (status = 200, description = "Move made successfully", body = GameDisplayDTO),
-        (status = 400, description = "Invalid move", body = InvalidCredentialsResponse),
+        (status = 200, description = "Move appended to the game's log; the state after it", body = GameLogState),
+        (status = 400, description = "Invalid or illegal move, or not the caller's turn", body = InvalidCredentialsResponse),
+        (status = 403, description = "Only the players of the game can move", body = InvalidCredentialsResponse),
         (status = 404, description = "Game not found", body = NotFoundResponse)
     ),
     security(
@@ -96,22 +144,37 @@ pub async fn get_game(id: Path<Uuid>) -> HttpResponse {
     ),
     tag = "Games"
 )]
-#[put("/{id}/move")]
-pub async fn make_move(id: Path<Uuid>, payload: Json<MakeMoveRequest>) -> HttpResponse {
+#[put("")]
+pub async fn make_move(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    buffer: web::Data<MoveBuffer>,
+    id: Path<Uuid>,
+    payload: Json<MakeMoveRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
     match payload.0.validate() {
         Ok(_) => {
-            // The real implementation would validate and make the move
-            // For now, we'll just return a mock response
-            HttpResponse::Ok().json(json!({
-                "message": "Move made successfully",
-                "data": {
-                    "game": {
-                        "id": id.into_inner(),
-                        "status": "in_progress",
-                        "last_move": payload.0.chess_move
+            let event = GameEvent::Move { notation: payload.0.chess_move.clone(), clock_ms: None };
+            let game_id = id.into_inner();
+            match buffer.append(db.get_ref(), game_id, Some(player.id), event).await {
+                Ok(state) => HttpResponse::Ok().json(json!({
+                    "message": "Move made successfully",
+                    "data": {
+                        "game": {
+                            "id": game_id,
+                            "status": if state.result == GameResult::InProgress { "in_progress" } else { "completed" },
+                            "last_move": state.moves.last()
+                        },
+                        "state": state
                     }
-                }
-            }))
+                })),
+                Err(err) => err.error_response(),
+            }
         }
         Err(errors) => ApiError::ValidationError(errors).error_response(),
     }
@@ -125,6 +188,7 @@ pub async fn make_move(id: Path<Uuid>, payload: Json<MakeMoveRequest>) -> HttpRe
     params(
         ("status" = Option<String>, Query, description = "Filter games by status (waiting, in_progress, completed, aborted)"),
         ("player_id" = Option<String>, Query, description = "Filter games by player ID", format = "uuid"),
+        ("club_id" = Option<String>, Query, description = "Only games with a member of this club in either seat; with status=waiting, the club's lobby", format = "uuid"),
         ("page" = Option<i32>, Query, description = "Page number for pagination"),
         ("limit" = Option<i32>, Query, description = "Number of items per page")
     ),
@@ -139,7 +203,7 @@ pub async fn make_move(id: Path<Uuid>, payload: Json<MakeMoveRequest>) -> HttpRe
 #[get("")]
 pub async fn list_games(
     query: Query<ListGamesQuery>,
-    db: web::Data<DatabaseConnection>,
+    replicas: web::Data<ReadReplicas>,
 ) -> HttpResponse {
     // Parse status string to enum if present
     // Note: The Query struct has String for status, but Service expects Option<GameStatus> or we map it.
@@ -162,11 +226,12 @@ pub async fn list_games(
     let cursor = query.cursor.clone();
 
     match GameService::list_games(
-        db.get_ref(),
+        replicas.read().as_ref(),
         cursor,
         limit,
         query.player_id,
         status_enum,
+        query.club_id,
     ).await {
         Ok((games, next_cursor)) => {
             // Map Entity Models to DTOs
@@ -186,6 +251,8 @@ pub async fn list_games(
                     "status": if g.result.is_some() { "completed" } else { "in_progress" }, // simplified
                     "result": g.result,
                     "current_fen": g.fen,
+                    "odds": games_service::odds_of(&g),
+                    "rated": games_service::is_rated(&g),
                     "time_control": 600, // placeholder as it's not in Game entity directly (duration_sec is there but it's different?)
                     "increment": 0,
                     "created_at": g.created_at,
@@ -222,6 +289,8 @@ pub async fn list_games(
     responses(
         (status = 200, description = "Joined game successfully", body = GameDisplayDTO),
         (status = 400, description = "Cannot join game", body = InvalidCredentialsResponse),
+        (status = 401, description = "Guest session ended", body = InvalidCredentialsResponse),
+        (status = 403, description = "Guests can only join casual games", body = InvalidCredentialsResponse),
         (status = 404, description = "Game not found", body = NotFoundResponse)
     ),
     security(
@@ -230,9 +299,22 @@ pub async fn list_games(
     tag = "Games"
 )]
 #[post("/{id}/join")]
-pub async fn join_game(id: Path<Uuid>, payload: Json<JoinGameRequest>) -> HttpResponse {
+pub async fn join_game(
+    req: HttpRequest,
+    jwt_service: web::Data<JwtService>,
+    guests: web::Data<GuestSessions>,
+    id: Path<Uuid>,
+    payload: Json<JoinGameRequest>,
+) -> HttpResponse {
     match payload.0.validate() {
         Ok(_) => {
+            // Guests only sit at casual games
+            if let Some((guest_id, _)) = guest_claims(&req, &jwt_service) {
+                if let Err(err) = guests.join(guest_id, *id) {
+                    return err.into_api_error().error_response();
+                }
+            }
+
             // The real implementation would add the player to the game
             // For now, we'll just return a mock response
             HttpResponse::Ok().json(json!({
@@ -275,6 +357,30 @@ pub async fn abandon_game(id: Path<Uuid>) -> HttpResponse {
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/games/{id}/verify",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Stored moves replayed; `consistent` is false when the position or result diverges", body = dto::games::GameVerificationResponse),
+        (status = 400, description = "Variant cannot be replayed", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    tag = "Games"
+)]
+#[post("/{id}/verify")]
+pub async fn verify_game(id: Path<Uuid>, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    match ReplayService::verify(db.get_ref(), id.into_inner()).await {
+        Ok(report) => HttpResponse::Ok().json(json!({
+            "message": "Game replayed",
+            "data": report
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/v1/games/import",
@@ -0,0 +1,103 @@
+use actix_web::{web, HttpMessage, HttpRequest};
+use db_entity::{player, player_role::Role};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use security::{Captcha, CaptchaError, Claims, JwtService};
+use service::linking::{Fingerprinter, LinkService};
+use service::moderation::ModerationService;
+use uuid::Uuid;
+
+/// Claims inserted by `JwtAuthMiddleware`.
+pub fn claims(req: &HttpRequest) -> Result<Claims, ApiError> {
+    req.extensions()
+        .get::<Claims>()
+        .cloned()
+        .ok_or_else(|| ApiError::Unauthorized("Missing authentication".to_string()))
+}
+
+/// Resolve the authenticated player and reject banned or closed accounts.
+pub async fn current_player(db: &DatabaseConnection, req: &HttpRequest) -> Result<player::Model, ApiError> {
+    let claims = claims(req)?;
+
+    let player = ModerationService::find_player_by_username(db, &claims.username)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Unknown account".to_string()))?;
+    if !player.is_enabled {
+        return Err(ApiError::Unauthorized("Account is closed".to_string()));
+    }
+
+    ModerationService::ensure_not_banned(db, player.id).await?;
+    Ok(player)
+}
+
+/// Like [`current_player`], but also requires `role`.
+pub async fn require_role(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    role: Role,
+) -> Result<player::Model, ApiError> {
+    let player = current_player(db, req).await?;
+
+    if !ModerationService::has_role(db, player.id, role).await? {
+        return Err(ApiError::Forbidden(format!("{:?} role required", role)));
+    }
+    Ok(player)
+}
+
+/// Header carrying the captcha token or proof of work.
+pub const CAPTCHA_HEADER: &str = "X-Captcha";
+
+/// Check the solution sent in [`CAPTCHA_HEADER`]; passes when captchas are
+/// not configured.
+pub async fn require_captcha(captcha: Option<&web::Data<Captcha>>, req: &HttpRequest) -> Result<(), CaptchaError> {
+    let Some(captcha) = captcha else {
+        return Ok(());
+    };
+    let solution = req.headers().get(CAPTCHA_HEADER).and_then(|value| value.to_str().ok());
+    let remote_ip = req.connection_info().realip_remote_addr().map(str::to_string);
+    captcha.verify(solution, remote_ip.as_deref()).await
+}
+
+/// Header carrying the install id clients generate once per device.
+pub const DEVICE_HEADER: &str = "X-Device-Id";
+
+/// Note the network and device `req` came from against `player_id`, when
+/// fingerprinting is configured. Failures are logged and never turn a
+/// login or connection away.
+pub async fn record_fingerprints(
+    db: &DatabaseConnection,
+    fingerprinter: Option<&web::Data<Fingerprinter>>,
+    req: &HttpRequest,
+    player_id: Uuid,
+) {
+    let Some(fingerprinter) = fingerprinter else {
+        return;
+    };
+    let remote_addr = req.connection_info().realip_remote_addr().map(str::to_string);
+    let device_id = req.headers().get(DEVICE_HEADER).and_then(|value| value.to_str().ok());
+    let fingerprints = fingerprinter.fingerprints(remote_addr.as_deref(), device_id);
+    if let Err(e) = LinkService::record(db, player_id, fingerprints).await {
+        log::error!("Failed to record the fingerprints of {}: {}", player_id, e);
+    }
+}
+
+pub fn captcha_error(error: CaptchaError) -> ApiError {
+    match error {
+        CaptchaError::Missing => ApiError::BadRequest(error.to_string()),
+        CaptchaError::Provider(e) => {
+            log::error!("Failed to verify captcha: {}", e);
+            ApiError::BadGateway("Captcha provider unavailable".to_string())
+        }
+        _ => ApiError::Forbidden(error.to_string()),
+    }
+}
+
+/// Whether `req` carries a valid access token, on routes that also serve
+/// anonymous callers and so have no `JwtAuthMiddleware`.
+pub fn has_valid_token(req: &HttpRequest, jwt_service: &JwtService) -> bool {
+    req.headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(JwtService::extract_token_from_header)
+        .is_some_and(|token| jwt_service.validate_token(&token).is_ok())
+}
@@ -0,0 +1,236 @@
+use actix_web::{HttpRequest, HttpResponse, post, web};
+use chrono::{DateTime, Duration, Utc};
+use dto::auth::{ErrorResponse, GuestSessionResponse};
+use error::error::ApiError;
+use security::{Captcha, Claims, JwtService};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::guard::{captcha_error, require_captcha};
+
+/// How long an open casual game stays joinable by guests
+const CASUAL_GAME_RETENTION_HOURS: i64 = 24;
+
+/// Guest sessions and the casual games they may join, shared by every
+/// worker. Guests have no account, so nothing about them is stored in the
+/// database; a restart ends every guest session.
+#[derive(Debug, Clone)]
+pub struct GuestSessions {
+    ttl: Duration,
+    state: Arc<Mutex<GuestState>>,
+}
+
+#[derive(Debug, Default)]
+struct GuestState {
+    guests: HashMap<Uuid, Guest>,
+    /// Unrated games guests may join, by when they were opened
+    casual_games: HashMap<Uuid, DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct Guest {
+    expires_at: DateTime<Utc>,
+    /// Games the guest created or joined
+    games: HashSet<Uuid>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GuestError {
+    /// Unknown guest, or its session has expired
+    SessionEnded,
+    /// The game is rated, or was never opened to guests
+    NotCasual,
+}
+
+impl GuestError {
+    pub fn into_api_error(self) -> ApiError {
+        match self {
+            GuestError::SessionEnded => ApiError::Unauthorized("Guest session has ended".to_string()),
+            GuestError::NotCasual => ApiError::Forbidden("Guests can only play casual games".to_string()),
+        }
+    }
+}
+
+impl GuestSessions {
+    pub fn new(ttl: Duration) -> Self {
+        GuestSessions {
+            ttl,
+            state: Arc::new(Mutex::new(GuestState::default())),
+        }
+    }
+
+    /// Start a session for a new guest, returning its ID and when it ends.
+    pub fn start(&self) -> (Uuid, DateTime<Utc>) {
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        let expires_at = now + self.ttl;
+
+        let mut state = self.state.lock().unwrap();
+        state.guests.retain(|_, guest| guest.expires_at > now);
+        let oldest = now - Duration::hours(CASUAL_GAME_RETENTION_HOURS);
+        state.casual_games.retain(|_, opened_at| *opened_at > oldest);
+        state.guests.insert(id, Guest { expires_at, games: HashSet::new() });
+        (id, expires_at)
+    }
+
+    /// Whether `guest_id` has a session that has not expired.
+    pub fn is_active(&self, guest_id: Uuid) -> bool {
+        let state = self.state.lock().unwrap();
+        matches!(state.guests.get(&guest_id), Some(guest) if guest.expires_at > Utc::now())
+    }
+
+    /// Let guests join the unrated game `game_id`.
+    pub fn open_casual_game(&self, game_id: Uuid) {
+        self.state.lock().unwrap().casual_games.insert(game_id, Utc::now());
+    }
+
+    /// Seat `guest_id` in `game_id`, which must be an open casual game.
+    pub fn join(&self, guest_id: Uuid, game_id: Uuid) -> Result<(), GuestError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.casual_games.contains_key(&game_id) {
+            return Err(GuestError::NotCasual);
+        }
+        match state.guests.get_mut(&guest_id) {
+            Some(guest) if guest.expires_at > Utc::now() => {
+                guest.games.insert(game_id);
+                Ok(())
+            }
+            _ => Err(GuestError::SessionEnded),
+        }
+    }
+
+    /// End the session of a guest who registered, returning the games they
+    /// were playing so they carry on under the new account.
+    pub fn upgrade(&self, guest_id: Uuid) -> Vec<Uuid> {
+        let guest = self.state.lock().unwrap().guests.remove(&guest_id);
+        let mut games: Vec<Uuid> = guest.map(|guest| guest.games.into_iter().collect()).unwrap_or_default();
+        games.sort();
+        games
+    }
+}
+
+/// Guest claims of the access token sent with `req`, on routes that also
+/// serve guests and so have no `JwtAuthMiddleware`.
+pub fn guest_claims(req: &HttpRequest, jwt_service: &JwtService) -> Option<(Uuid, Claims)> {
+    let claims = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(JwtService::extract_token_from_header)
+        .and_then(|token| jwt_service.validate_token(&token).ok())
+        .filter(|claims| claims.guest)?;
+    let guest_id = Uuid::parse_str(&claims.sub).ok()?;
+    Some((guest_id, claims))
+}
+
+/// Display name of a guest.
+fn guest_name(guest_id: Uuid) -> String {
+    format!("Guest-{}", &guest_id.simple().to_string()[..6])
+}
+
+/// Start a guest session
+#[utoipa::path(
+    post,
+    path = "/v1/auth/guest",
+    params(
+        ("X-Captcha" = Option<String>, Header, description = "Solution of the challenge from /v1/auth/captcha, when captchas are enabled")
+    ),
+    responses(
+        (status = 200, description = "Token for a guest with no account; it can create and join casual games and use the game WebSocket, but not rated play or anything needing an account", body = GuestSessionResponse),
+        (status = 400, description = "Missing captcha", body = ErrorResponse),
+        (status = 403, description = "Captcha not solved", body = ErrorResponse)
+    ),
+    tag = "Authentication"
+)]
+#[post("/guest")]
+pub async fn start_guest_session(
+    req: HttpRequest,
+    config: web::Data<AppConfig>,
+    jwt_service: web::Data<JwtService>,
+    guests: web::Data<GuestSessions>,
+    captcha: Option<web::Data<Captcha>>,
+) -> HttpResponse {
+    if let Err(err) = require_captcha(captcha.as_ref(), &req).await {
+        return captcha_error(err).error_response();
+    }
+
+    let (guest_id, _) = guests.start();
+    let name = guest_name(guest_id);
+    let ttl = config.guest_session_ttl_secs.max(1) as usize;
+    match jwt_service.generate_guest_token(guest_id, &name, ttl) {
+        Ok(access_token) => HttpResponse::Ok().json(GuestSessionResponse {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in: ttl,
+            guest_id,
+            username: name,
+        }),
+        Err(e) => {
+            log::error!("Failed to generate guest token: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                message: "Failed to generate guest token".to_string(),
+                code: "TOKEN_ERROR".to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guests_only_join_open_casual_games() {
+        let guests = GuestSessions::new(Duration::hours(1));
+        let (guest, _) = guests.start();
+        let game = Uuid::new_v4();
+
+        assert_eq!(guests.join(guest, game), Err(GuestError::NotCasual));
+        guests.open_casual_game(game);
+        assert_eq!(guests.join(guest, game), Ok(()));
+        assert_eq!(guests.join(Uuid::new_v4(), game), Err(GuestError::SessionEnded));
+    }
+
+    #[test]
+    fn test_upgrade_hands_over_games_and_ends_the_session() {
+        let guests = GuestSessions::new(Duration::hours(1));
+        let (guest, _) = guests.start();
+        let game = Uuid::new_v4();
+        guests.open_casual_game(game);
+        guests.join(guest, game).unwrap();
+
+        assert_eq!(guests.upgrade(guest), vec![game]);
+        assert!(!guests.is_active(guest));
+        assert!(guests.upgrade(guest).is_empty());
+    }
+
+    #[test]
+    fn test_expired_sessions_are_inactive() {
+        let guests = GuestSessions::new(Duration::seconds(-1));
+        let (guest, _) = guests.start();
+        assert!(!guests.is_active(guest));
+    }
+
+    #[test]
+    fn test_guest_tokens_carry_the_guest_id() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let guest_id = Uuid::new_v4();
+        let token = jwt_service.generate_guest_token(guest_id, &guest_name(guest_id), 60).unwrap();
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+
+        let (id, claims) = guest_claims(&req, &jwt_service).unwrap();
+        assert_eq!(id, guest_id);
+        assert!(claims.username.starts_with("Guest-"));
+
+        // Account tokens are not guest tokens
+        let token = jwt_service.generate_token(1, "magnus").unwrap();
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+        assert!(guest_claims(&req, &jwt_service).is_none());
+    }
+}
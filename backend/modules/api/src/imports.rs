@@ -0,0 +1,242 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::import_quarantine::QuarantineStatus;
+use dto::games::{ImportAccountRequest, ImportJobsQuery, QuarantineQuery, QuarantinedImportDisplay, RetryImportRequest};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::importer::{GameFetcher, ImportService};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    post,
+    path = "/v1/games/import/account",
+    request_body = ImportAccountRequest,
+    responses(
+        (status = 200, description = "Recent games of the account, newest first, stored as imported games; games imported before are skipped", body = ImportAccountResponse),
+        (status = 400, description = "Invalid request, or the site rejected the token", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "No such account on the site", body = NotFoundResponse),
+        (status = 429, description = "The site is limiting requests", body = InvalidCredentialsResponse),
+        (status = 502, description = "The site failed or could not be reached", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[post("")]
+pub async fn import_account(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    fetcher: web::Data<GameFetcher>,
+    payload: Json<ImportAccountRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ImportService::import_account(db.get_ref(), fetcher.get_ref(), player.id, &payload.0).await {
+        Ok(summary) => HttpResponse::Ok().json(json!({
+            "message": "Games imported",
+            "data": { "import": summary }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/import/jobs",
+    params(
+        ("limit" = Option<u64>, Query, description = "Maximum number of jobs to return")
+    ),
+    responses(
+        (status = 200, description = "Your account imports, newest first, with what became of the games fetched", body = Vec<ImportJobDisplay>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("/jobs")]
+pub async fn list_import_jobs(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<ImportJobsQuery>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ImportService::jobs(db.get_ref(), player.id, query.limit.unwrap_or(20)).await {
+        Ok(jobs) => HttpResponse::Ok().json(json!({
+            "message": "Import jobs found",
+            "data": { "jobs": jobs }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/import/jobs/{id}",
+    params(
+        ("id" = String, Path, description = "Import job ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Counts of the import", body = ImportJobDisplay),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "No such import of yours", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("/jobs/{id}")]
+pub async fn get_import_job(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ImportService::job(db.get_ref(), player.id, id.into_inner()).await {
+        Ok(job) => HttpResponse::Ok().json(json!({
+            "message": "Import job found",
+            "data": job
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/games/import/quarantine",
+    params(
+        ("job_id" = Option<String>, Query, description = "Only games of this import job", format = "uuid"),
+        ("status" = Option<String>, Query, description = "Queue to read: pending (default), resolved or dismissed"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of games to return")
+    ),
+    responses(
+        (status = 200, description = "Your fetched games that failed to import, oldest first, with the error and the text", body = Vec<QuarantinedImportDisplay>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[get("/quarantine")]
+pub async fn list_quarantine(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<QuarantineQuery>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let status = query.status.map(QuarantineStatus::from).unwrap_or(QuarantineStatus::Pending);
+    let limit = query.limit.unwrap_or(50);
+
+    match ImportService::quarantine(db.get_ref(), player.id, query.job_id, status, limit).await {
+        Ok(entries) => {
+            let games: Vec<QuarantinedImportDisplay> = entries.into_iter().map(QuarantinedImportDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Quarantined games found",
+                "data": { "games": games }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/games/import/quarantine/{id}/retry",
+    params(
+        ("id" = String, Path, description = "Quarantined game ID in UUID format", format = "uuid")
+    ),
+    request_body = RetryImportRequest,
+    responses(
+        (status = 200, description = "Game imported, or found imported already, and resolved", body = QuarantinedImportDisplay),
+        (status = 400, description = "The game still fails to import; the text and error are kept", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "No such quarantined game of yours", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[post("/quarantine/{id}/retry")]
+pub async fn retry_quarantined(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<RetryImportRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ImportService::retry(db.get_ref(), player.id, id.into_inner(), payload.into_inner().pgn).await {
+        Ok(entry) => HttpResponse::Ok().json(json!({
+            "message": "Game imported",
+            "data": QuarantinedImportDisplay::from(entry)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/games/import/quarantine/{id}/dismiss",
+    params(
+        ("id" = String, Path, description = "Quarantined game ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Game taken out of the queue without importing it", body = QuarantinedImportDisplay),
+        (status = 400, description = "The game is no longer pending", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse),
+        (status = 404, description = "No such quarantined game of yours", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Games"
+)]
+#[post("/quarantine/{id}/dismiss")]
+pub async fn dismiss_quarantined(req: HttpRequest, db: web::Data<DatabaseConnection>, id: Path<Uuid>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ImportService::dismiss(db.get_ref(), player.id, id.into_inner()).await {
+        Ok(entry) => HttpResponse::Ok().json(json!({
+            "message": "Game dismissed",
+            "data": QuarantinedImportDisplay::from(entry)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,98 @@
+use actix_web::{
+    HttpResponse, get,
+    web::{self, Path, Query},
+};
+use dto::leaderboards::{LeaderboardEntryDisplay, LeaderboardQuery, TimeControlCategory};
+use serde_json::json;
+use service::leaderboard::LeaderboardService;
+use uuid::Uuid;
+
+use crate::cache::{CacheKey, ResponseCache};
+use crate::replicas::ReadReplicas;
+
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboards/{time_control}",
+    params(
+        ("time_control" = TimeControlCategory, Path, description = "bullet, blitz, rapid or classical"),
+        ("limit" = Option<u64>, Query, description = "Number of entries to return (max 100)")
+    ),
+    responses(
+        (status = 200, description = "Top rated players", body = Vec<LeaderboardEntryDisplay>)
+    ),
+    tag = "Leaderboards"
+)]
+#[get("/{time_control}")]
+pub async fn get_leaderboard(
+    time_control: Path<TimeControlCategory>,
+    query: Query<LeaderboardQuery>,
+    replicas: web::Data<ReadReplicas>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    let category = time_control.into_inner();
+    let limit = query.limit.unwrap_or(50);
+    let key = CacheKey::Leaderboard { category, limit };
+
+    let entries = match cache.get::<Vec<LeaderboardEntryDisplay>>(&key).await {
+        Some(entries) => entries,
+        None => match LeaderboardService::top(replicas.read().as_ref(), category.into(), limit).await {
+            Ok(rows) => {
+                let entries: Vec<LeaderboardEntryDisplay> = rows
+                    .into_iter()
+                    .map(|(entry, username)| LeaderboardEntryDisplay::new(entry, username))
+                    .collect();
+                cache.put(&key, &entries).await;
+                entries
+            }
+            Err(err) => return err.error_response(),
+        },
+    };
+
+    HttpResponse::Ok().json(json!({
+        "message": "Leaderboard found",
+        "data": {
+            "time_control": category,
+            "entries": entries
+        }
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboards/{time_control}/players/{player_id}",
+    params(
+        ("time_control" = TimeControlCategory, Path, description = "bullet, blitz, rapid or classical"),
+        ("player_id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Player's rank", body = LeaderboardEntryDisplay),
+        (status = 404, description = "Player is not ranked in this time control")
+    ),
+    tag = "Leaderboards"
+)]
+#[get("/{time_control}/players/{player_id}")]
+pub async fn get_player_rank(
+    path: Path<(TimeControlCategory, Uuid)>,
+    replicas: web::Data<ReadReplicas>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    let (category, player_id) = path.into_inner();
+    let key = CacheKey::LeaderboardRank { category, player: player_id };
+
+    let entry = match cache.get::<LeaderboardEntryDisplay>(&key).await {
+        Some(entry) => entry,
+        None => match LeaderboardService::rank_of(replicas.read().as_ref(), category.into(), player_id).await {
+            Ok((entry, username)) => {
+                let entry = LeaderboardEntryDisplay::new(entry, username);
+                cache.put(&key, &entry).await;
+                entry
+            }
+            Err(err) => return err.error_response(),
+        },
+    };
+
+    HttpResponse::Ok().json(json!({
+        "message": "Rank found",
+        "data": entry
+    }))
+}
@@ -1,12 +1,41 @@
 pub mod auth;
 pub mod ai;
 pub mod openapi;
+pub mod asyncapi;
 pub mod ws;
 mod test;
 pub mod config;
+pub mod replicas;
+pub mod cache;
+pub mod request_id;
+pub mod admin;
 pub mod server;
 pub mod players;
 pub mod games;
+pub mod game_events;
+pub mod guard;
+pub mod moderation;
+pub mod disputes;
+pub mod titles;
+pub mod clubs;
+pub mod coaching;
+pub mod analysis;
+pub mod leaderboards;
+pub mod search;
+pub mod ratings;
+pub mod tournaments;
+pub mod tournament_templates;
+pub mod annotations;
+pub mod archive;
+pub mod imports;
+pub mod friends;
+pub mod engine_matches;
+pub mod training;
+pub mod attestations;
+pub mod webhooks;
+pub mod preferences;
+pub mod account;
+pub mod guests;
 
 // Re-export server module for external use
 pub use server::main;
@@ -0,0 +1,503 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::{moderation_action::ActionKind, moderation_report::ReportStatus, player_role::Role};
+use dto::moderation::{
+    CreateReportRequest, FlagGameRequest, GrantRoleRequest, LinkAccountRequest, ModerationActionDisplay,
+    ModerationActionRequest, PlayerRole, ReportDisplay, ReportQueueQuery, ResolveReportRequest,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use security::TokenDenylist;
+use serde_json::json;
+use service::linking::LinkService;
+use service::moderation::ModerationService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::{current_player, require_role};
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/reports",
+    request_body = CreateReportRequest,
+    responses(
+        (status = 201, description = "Report filed", body = ReportDisplay),
+        (status = 400, description = "Invalid report", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Reported player or game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/reports")]
+pub async fn create_report(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateReportRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let reporter = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::create_report(db.get_ref(), reporter.id, payload.into_inner()).await {
+        Ok(report) => HttpResponse::Created().json(json!({
+            "message": "Report filed",
+            "data": ReportDisplay::from(report)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/mod/reports",
+    params(
+        ("status" = Option<String>, Query, description = "Queue to read: open (default), resolved or dismissed"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of reports to return")
+    ),
+    responses(
+        (status = 200, description = "Moderator queue", body = Vec<ReportDisplay>),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[get("/reports")]
+pub async fn list_reports(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<ReportQueueQuery>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Moderator).await {
+        return err.error_response();
+    }
+
+    let status = query.status.map(ReportStatus::from).unwrap_or(ReportStatus::Open);
+    let limit = query.limit.unwrap_or(50);
+
+    match ModerationService::list_reports(db.get_ref(), status, limit).await {
+        Ok(reports) => {
+            let reports: Vec<ReportDisplay> = reports.into_iter().map(ReportDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Reports found",
+                "data": { "reports": reports }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/reports/{id}/resolve",
+    params(
+        ("id" = String, Path, description = "Report ID in UUID format", format = "uuid")
+    ),
+    request_body = ResolveReportRequest,
+    responses(
+        (status = 200, description = "Report closed", body = ReportDisplay),
+        (status = 400, description = "Report already closed", body = InvalidCredentialsResponse),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Report not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/reports/{id}/resolve")]
+pub async fn resolve_report(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<ResolveReportRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::resolve_report(db.get_ref(), id.into_inner(), moderator.id, payload.into_inner()).await {
+        Ok(report) => HttpResponse::Ok().json(json!({
+            "message": "Report closed",
+            "data": ReportDisplay::from(report)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/games/{id}/flag",
+    params(
+        ("id" = String, Path, description = "Game ID in UUID format", format = "uuid")
+    ),
+    request_body = FlagGameRequest,
+    responses(
+        (status = 201, description = "Game flagged for review", body = ReportDisplay),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Game not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/games/{id}/flag")]
+pub async fn flag_game(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<FlagGameRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::flag_game(db.get_ref(), moderator.id, id.into_inner(), payload.into_inner()).await {
+        Ok(report) => HttpResponse::Created().json(json!({
+            "message": "Game flagged for review",
+            "data": ReportDisplay::from(report)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/players/{id}/actions",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    request_body = ModerationActionRequest,
+    responses(
+        (status = 201, description = "Action applied; a ban also revokes the access tokens of the player and of accounts linked to them", body = ModerationActionDisplay),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/players/{id}/actions")]
+pub async fn apply_action(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<ModerationActionRequest>,
+    denylist: Option<web::Data<TokenDenylist>>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::apply_action(db.get_ref(), id.into_inner(), moderator.id, payload.into_inner()).await {
+        Ok(action) => {
+            if action.kind == ActionKind::Ban {
+                if let Some(denylist) = denylist {
+                    revoke_sessions(db.get_ref(), &denylist, action.player_id).await;
+                    match LinkService::linked_ids(db.get_ref(), action.player_id).await {
+                        Ok(linked) => {
+                            for player_id in linked {
+                                revoke_sessions(db.get_ref(), &denylist, player_id).await;
+                            }
+                        }
+                        Err(e) => log::error!("Failed to find the accounts linked to {}: {}", action.player_id, e),
+                    }
+                }
+            }
+            HttpResponse::Created().json(json!({
+                "message": "Action applied",
+                "data": ModerationActionDisplay::from(action)
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+/// Cut off the access tokens a banned player already holds. The ban stands
+/// even if this fails; their tokens then run until they expire.
+async fn revoke_sessions(db: &DatabaseConnection, denylist: &TokenDenylist, player_id: Uuid) {
+    let revoked = match ModerationService::find_player(db, player_id).await {
+        Ok(player) => denylist.revoke_account(&player.username).await.map_err(|e| e.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(e) = revoked {
+        log::error!("Failed to revoke the sessions of banned player {}: {}", player_id, e);
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/mod/players/{id}/actions",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Action history", body = Vec<ModerationActionDisplay>),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[get("/players/{id}/actions")]
+pub async fn list_actions(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Moderator).await {
+        return err.error_response();
+    }
+
+    match ModerationService::list_actions(db.get_ref(), id.into_inner()).await {
+        Ok(actions) => {
+            let actions: Vec<ModerationActionDisplay> =
+                actions.into_iter().map(ModerationActionDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Actions found",
+                "data": { "actions": actions }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/actions/{id}/revoke",
+    params(
+        ("id" = String, Path, description = "Moderation action ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Action revoked", body = ModerationActionDisplay),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Action not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/actions/{id}/revoke")]
+pub async fn revoke_action(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::revoke_action(db.get_ref(), id.into_inner(), moderator.id).await {
+        Ok(action) => HttpResponse::Ok().json(json!({
+            "message": "Action revoked",
+            "data": ModerationActionDisplay::from(action)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/players/{id}/roles",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    request_body = GrantRoleRequest,
+    responses(
+        (status = 200, description = "Role granted"),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/players/{id}/roles")]
+pub async fn grant_role(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<GrantRoleRequest>,
+) -> HttpResponse {
+    let admin = match require_role(db.get_ref(), &req, Role::Admin).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match ModerationService::grant_role(db.get_ref(), id.into_inner(), payload.role.into(), Some(admin.id)).await {
+        Ok(granted) => HttpResponse::Ok().json(json!({
+            "message": "Role granted",
+            "data": {
+                "player_id": granted.player_id,
+                "role": PlayerRole::from(granted.role)
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/mod/players/{id}/links",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Accounts marked as the same person, and unlinked accounts seen on the same devices or networks", body = AccountLinksDisplay),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[get("/players/{id}/links")]
+pub async fn list_account_links(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Moderator).await {
+        return err.error_response();
+    }
+
+    match LinkService::links(db.get_ref(), id.into_inner()).await {
+        Ok(links) => HttpResponse::Ok().json(json!({
+            "message": "Account links found",
+            "data": links
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/players/{id}/links",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    request_body = LinkAccountRequest,
+    responses(
+        (status = 201, description = "Accounts linked; a ban on either now holds for both"),
+        (status = 400, description = "Same account or already linked", body = InvalidCredentialsResponse),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/players/{id}/links")]
+pub async fn link_accounts(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<LinkAccountRequest>,
+    denylist: Option<web::Data<TokenDenylist>>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let player_id = id.into_inner();
+    match LinkService::mark(db.get_ref(), player_id, moderator.id, payload.into_inner()).await {
+        Ok(link) => {
+            // A ban already on one account now holds for the other
+            if let Some(denylist) = denylist {
+                for (banned, other) in [(link.player_id, link.linked_player_id), (link.linked_player_id, link.player_id)] {
+                    match ModerationService::active_action(db.get_ref(), banned, ActionKind::Ban).await {
+                        Ok(Some(_)) => revoke_sessions(db.get_ref(), &denylist, other).await,
+                        Ok(None) => {}
+                        Err(e) => log::error!("Failed to check the bans of {}: {}", banned, e),
+                    }
+                }
+            }
+            HttpResponse::Created().json(json!({
+                "message": "Accounts linked",
+                "data": {
+                    "player_id": player_id,
+                    "linked_player_id": if link.player_id == player_id { link.linked_player_id } else { link.player_id },
+                    "marked_by": link.marked_by,
+                    "note": link.note,
+                    "created_at": link.created_at
+                }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/mod/players/{id}/links/{other_id}",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid"),
+        ("other_id" = String, Path, description = "ID of the linked account in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Link removed; sanctions stay on the account they were applied to"),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Accounts are not linked", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[delete("/players/{id}/links/{other_id}")]
+pub async fn unlink_accounts(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    path: Path<(Uuid, Uuid)>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Moderator).await {
+        return err.error_response();
+    }
+
+    let (player_id, other_id) = path.into_inner();
+    match LinkService::unmark(db.get_ref(), player_id, other_id).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Accounts unlinked"
+        })),
+        Err(err) => err.error_response(),
+    }
+}
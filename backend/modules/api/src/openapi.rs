@@ -1,5 +1,8 @@
 use utoipa::OpenApi;
-use crate::{players, games, auth, ai};
+use crate::{
+    account, ai, analysis, annotations, archive, attestations, auth, clubs, coaching, disputes, engine_matches, friends, game_events, games, guests, imports, leaderboards, moderation,
+    players, preferences, ratings, search, titles, tournament_templates, tournaments, training, webhooks,
+};
 use utoipa::openapi::security::{SecurityScheme, HttpAuthScheme, HttpBuilder};
 use utoipa::Modify;
 
@@ -31,6 +34,9 @@ impl Modify for SecurityAddon {
         players::find_player_by_id,
         players::update_player,
         players::delete_player,
+        players::get_player_stats,
+        ratings::get_rating_history,
+        archive::export_games,
         
         // Game endpoints
         games::create_game,
@@ -39,14 +45,160 @@ impl Modify for SecurityAddon {
         games::list_games,
         games::join_game,
         games::abandon_game,
+        games::verify_game,
+        game_events::get_game_events,
+        game_events::append_game_event,
+        game_events::get_autosave_stats,
+        imports::import_account,
+        imports::list_import_jobs,
+        imports::get_import_job,
+        imports::list_quarantine,
+        imports::retry_quarantined,
+        imports::dismiss_quarantined,
+        annotations::list_annotations,
+        annotations::save_annotations,
+        annotations::delete_annotations,
+        annotations::export_annotated_pgn,
+        analysis::get_game_analysis,
+        attestations::get_attestation,
+
+        // Friends endpoints
+        friends::list_friends,
+        friends::add_friend,
+        friends::remove_friend,
+        // Account settings endpoints
+        preferences::get_preferences,
+        preferences::update_preferences,
+        account::export_personal_data,
+        account::close_account,
+        account::reactivate_account,
+        // Webhook endpoints
+        webhooks::create_webhook,
+        webhooks::list_webhooks,
+        webhooks::delete_webhook,
+        webhooks::list_webhook_deliveries,
+
+        // Engine match endpoints
+        engine_matches::create_engine_match,
+        engine_matches::list_engine_matches,
+        engine_matches::list_match_engines,
+        engine_matches::get_engine_match,
+        engine_matches::cancel_engine_match,
+
+        // Training endpoints
+        training::start_training,
+        training::submit_training,
+        training::get_training_stats,
+        training::get_training_leaderboard,
         
         // Authentication endpoints
         auth::login,
         auth::register,
+        auth::captcha_challenge,
+        guests::start_guest_session,
+        auth::wallet_challenge,
+        auth::wallet_login,
+        auth::link_wallet,
         
         // AI suggestion endpoints
         ai::get_ai_suggestion,
         ai::analyze_position,
+        ai::get_engine_queue,
+        ai::run_engine_benchmark,
+        ai::list_engines,
+        ai::list_bots,
+        ai::start_bot_game,
+        ai::bot_move,
+
+        // Moderation endpoints
+        moderation::create_report,
+        moderation::list_reports,
+        moderation::resolve_report,
+        moderation::flag_game,
+        moderation::apply_action,
+        moderation::list_actions,
+        moderation::revoke_action,
+        moderation::grant_role,
+        moderation::list_account_links,
+        moderation::link_accounts,
+        moderation::unlink_accounts,
+        ratings::reset_season,
+        ratings::recalculate_ratings,
+        ratings::get_recalculation,
+        ratings::void_games,
+
+        // Dispute endpoints
+        disputes::open_dispute,
+        disputes::list_disputes,
+        disputes::decide_dispute,
+
+        // Title verification endpoints
+        titles::submit_title,
+        titles::my_title_verifications,
+        titles::list_title_verifications,
+        titles::decide_title,
+        titles::title_verification_events,
+
+        // Club endpoints
+        clubs::create_club,
+        clubs::list_clubs,
+        clubs::get_club,
+        clubs::join_club,
+        clubs::leave_club,
+        clubs::club_ladder,
+        clubs::add_club_member,
+        clubs::update_club_member,
+        clubs::remove_club_member,
+        clubs::list_ladder_challenges,
+        clubs::create_ladder_challenge,
+        clubs::respond_ladder_challenge,
+        clubs::report_ladder_result,
+
+        // Coaching endpoints
+        coaching::invite_student,
+        coaching::list_students,
+        coaching::list_coaches,
+        coaching::accept_coaching,
+        coaching::end_coaching,
+        coaching::student_overview,
+        coaching::student_assignments,
+        coaching::create_assignment,
+        coaching::my_assignments,
+        coaching::get_assignment,
+        coaching::update_assignment,
+        coaching::delete_assignment,
+        coaching::complete_assignment,
+
+        // Leaderboard endpoints
+        leaderboards::get_leaderboard,
+        leaderboards::get_player_rank,
+
+        // Search endpoints
+        search::search,
+        search::search_position,
+
+        // Tournament endpoints
+        tournaments::create_tournament,
+        tournaments::get_tournament,
+        tournaments::pair_remaining,
+        tournaments::preview_round,
+        tournaments::swap_colors,
+        tournaments::force_pairing,
+        tournaments::record_forfeit,
+        tournaments::request_bye,
+        tournaments::register_player,
+        tournaments::withdraw_player,
+        tournaments::reenter_player,
+        tournaments::import_registrations,
+        tournaments::set_prizes,
+        tournaments::finish_tournament,
+        tournaments::get_standings,
+        tournaments::export_trf,
+        tournaments::validate_trf,
+        tournament_templates::create_template,
+        tournament_templates::list_templates,
+        tournament_templates::update_template,
+        tournament_templates::delete_template,
     ),
     components(
         schemas(
@@ -55,15 +207,53 @@ impl Modify for SecurityAddon {
             dto::players::UpdatePlayer,
             dto::players::DisplayPlayer,
             dto::players::UpdatedPlayer,
+            dto::stats::PlayerStats,
+            dto::stats::ResultRecord,
+            dto::stats::StreakStats,
+            dto::ratings::RatingHistoryQuery,
+            dto::ratings::RatingPoint,
+            dto::ratings::SeasonResetRequest,
+            dto::ratings::RecalculateRatingsRequest,
+            dto::ratings::VoidGamesRequest,
+            dto::ratings::RecalculationStatus,
+            dto::ratings::RatingAuditEntry,
+            dto::ratings::RecalculationDisplay,
             
             // Game schemas
             dto::games::CreateGameRequest,
+            dto::games::PlayerColor,
             dto::games::GameDisplayDTO,
+            dto::games::GameOdds,
+            dto::games::OddsGiver,
+            dto::games::OddsPiece,
+            dto::games::ArchiveFormat,
+            dto::games::ArchiveQuery,
             dto::games::MakeMoveRequest,
             dto::games::JoinGameRequest,
             dto::games::GameStatus,
             dto::games::GameResult,
             dto::games::ListGamesQuery,
+            dto::games::ImportSource,
+            dto::games::ImportAccountRequest,
+            dto::games::ImportAccountResponse,
+            dto::games::ImportFailure,
+            dto::games::ImportJobDisplay,
+            dto::games::ImportJobsQuery,
+            dto::games::QuarantineStatus,
+            dto::games::QuarantineQuery,
+            dto::games::QuarantinedImportDisplay,
+            dto::games::RetryImportRequest,
+            dto::games::AttestationStatus,
+            dto::games::GameAttestationResponse,
+            dto::games::DivergenceKind,
+            dto::games::ReplayDivergence,
+            dto::games::GameVerificationResponse,
+            dto::games::Side,
+            dto::games::GameEvent,
+            dto::games::GameLogState,
+            dto::games::GameEventRecord,
+            dto::games::GameLogResponse,
+            dto::games::AutosaveStats,
             
             // Auth schemas
             dto::auth::LoginRequest,
@@ -71,6 +261,12 @@ impl Modify for SecurityAddon {
             dto::auth::RegisterRequest,
             dto::auth::TokenResponse,
             dto::auth::UserInfo,
+            dto::auth::WalletChallengeRequest,
+            dto::auth::CaptchaChallengeResponse,
+            dto::auth::GuestSessionResponse,
+            dto::auth::WalletChallengeResponse,
+            dto::auth::WalletSignatureRequest,
+            dto::auth::WalletLinkResponse,
             
             // AI schemas
             dto::ai::AiSuggestionRequest,
@@ -78,10 +274,196 @@ impl Modify for SecurityAddon {
             dto::ai::PositionAnalysisRequest,
             dto::ai::PositionAnalysisResponse,
             dto::ai::AlternativeMove,
+            dto::ai::EngineQueueStats,
+            dto::ai::EngineWaitStats,
+            dto::ai::AnalysisVariant,
+            dto::ai::EngineOptionDisplay,
+            dto::ai::EngineDisplay,
+            dto::ai::EngineBenchmarkRequest,
+            dto::ai::BenchmarkPositionDisplay,
+            dto::ai::EngineBenchmarkDisplay,
+            dto::ai::BotDisplay,
+            dto::ai::StartBotGameRequest,
+            dto::ai::BotGameDisplay,
+            dto::ai::BotMoveRequest,
+            dto::ai::BotMoveResponse,
+
+            // Moderation schemas
+            dto::moderation::CreateReportRequest,
+            dto::moderation::FlagGameRequest,
+            dto::moderation::ResolveReportRequest,
+            dto::moderation::ModerationActionRequest,
+            dto::moderation::GrantRoleRequest,
+            dto::moderation::LinkAccountRequest,
+            dto::moderation::AccountLinksDisplay,
+            dto::moderation::LinkedAccountDisplay,
+            dto::moderation::SharedFingerprintDisplay,
+            dto::moderation::ReportQueueQuery,
+            dto::moderation::ReportDisplay,
+            dto::moderation::IntegrityEvidence,
+            dto::moderation::ModerationActionDisplay,
+            dto::moderation::ReportReason,
+            dto::moderation::ReportStatus,
+            dto::moderation::ModerationActionKind,
+            dto::moderation::PlayerRole,
+
+            // Dispute schemas
+            dto::disputes::OpenDisputeRequest,
+            dto::disputes::DecideDisputeRequest,
+            dto::disputes::DisputeQueueQuery,
+            dto::disputes::DisputeEvidence,
+            dto::disputes::DisputeDisplay,
+            dto::disputes::DisputeReason,
+            dto::disputes::DisputeStatus,
+
+            // Title verification schemas
+            dto::titles::SubmitTitleRequest,
+            dto::titles::DecideTitleRequest,
+            dto::titles::TitleQueueQuery,
+            dto::titles::TitleVerificationDisplay,
+            dto::titles::TitleVerificationEventDisplay,
+            dto::titles::ChessTitle,
+            dto::titles::VerificationStatus,
+
+            // Club schemas
+            dto::clubs::CreateClubRequest,
+            dto::clubs::AddMemberRequest,
+            dto::clubs::UpdateMemberRequest,
+            dto::clubs::ClubListQuery,
+            dto::clubs::ChallengeRequest,
+            dto::clubs::RespondChallengeRequest,
+            dto::clubs::ChallengeResultRequest,
+            dto::clubs::ChallengeQuery,
+            dto::clubs::ClubDisplay,
+            dto::clubs::ClubMemberDisplay,
+            dto::clubs::ClubPage,
+            dto::clubs::LadderChallengeDisplay,
+            dto::clubs::ClubRole,
+            dto::clubs::ChallengeStatus,
+
+            // Coaching schemas
+            dto::coaching::InviteStudentRequest,
+            dto::coaching::CreateAssignmentRequest,
+            dto::coaching::UpdateAssignmentRequest,
+            dto::coaching::CompleteAssignmentRequest,
+            dto::coaching::AssignmentQuery,
+            dto::coaching::CoachingLinkDisplay,
+            dto::coaching::AssignmentDisplay,
+            dto::coaching::CompletionDisplay,
+            dto::coaching::StudentGameDisplay,
+            dto::coaching::StudentOverview,
+            dto::coaching::CoachingLinkStatus,
+            dto::coaching::AssignmentKind,
+            dto::coaching::AssignmentStatus,
+            dto::coaching::StudentOutcome,
+
+            // Leaderboard schemas
+            dto::leaderboards::TimeControlCategory,
+            dto::leaderboards::LeaderboardQuery,
+            dto::leaderboards::LeaderboardEntryDisplay,
+
+            // Search schemas
+            dto::search::SearchKind,
+            dto::search::SearchQuery,
+            dto::search::SearchResult,
+            dto::search::PositionSearchQuery,
+            dto::search::PositionMatch,
+
+            // Tournament schemas
+            dto::tournaments::CreateTournamentRequest,
+            dto::tournaments::SwapColorsRequest,
+            dto::tournaments::ForcePairingRequest,
+            dto::tournaments::ForfeitRequest,
+            dto::tournaments::ByeRequest,
+            dto::tournaments::HypotheticalResult,
+            dto::tournaments::PreviewRoundRequest,
+            dto::tournaments::PairingDisplay,
+            dto::tournaments::RoundDisplay,
+            dto::tournaments::TournamentDisplay,
+            dto::tournaments::TournamentFormat,
+            dto::tournaments::TournamentStatus,
+            dto::tournaments::CreateTemplateRequest,
+            dto::tournaments::UpdateTemplateRequest,
+            dto::tournaments::TemplateDisplay,
+            dto::tournaments::ArenaPairing,
+            dto::tournaments::ArenaPairingMode,
+            dto::tournaments::ReentryPolicy,
+            dto::tournaments::Seeding,
+            dto::tournaments::InitialColor,
+            dto::tournaments::PrizeKind,
+            dto::tournaments::Prize,
+            dto::tournaments::SetPrizesRequest,
+            dto::tournaments::StandingDisplay,
+            dto::tournaments::TrophyDisplay,
+            dto::tournaments::ValidateTrfRequest,
+            dto::tournaments::TrfValidationDisplay,
+            dto::tournaments::ImportRegistrationsRequest,
+            dto::tournaments::ImportedRegistrationDisplay,
+            dto::tournaments::RejectedRowDisplay,
+            dto::tournaments::RegistrationImportDisplay,
+
+            // Annotation schemas
+            dto::annotations::AnnotationVisibility,
+            dto::annotations::Nag,
+            dto::annotations::MoveNote,
+            dto::annotations::VariationMove,
+            dto::annotations::SaveAnnotationsRequest,
+            dto::annotations::AnnotationDisplay,
+            dto::annotations::FriendDisplay,
+            // Game analysis schemas
+            dto::analysis::GamePhase,
+            dto::analysis::MistakeSeverity,
+            dto::analysis::MoveTimeDisplay,
+            dto::analysis::PhaseTimeDisplay,
+            dto::analysis::CriticalMoveDisplay,
+            dto::analysis::SideTimeUsage,
+            dto::analysis::TimeUsageReport,
+            dto::analysis::GameAnalysisDisplay,
+            dto::preferences::BoardTheme,
+            dto::preferences::ChatAudience,
+            dto::preferences::PreferencesDisplay,
+            dto::preferences::UpdatePreferencesRequest,
+            dto::account::CloseAccountRequest,
+            dto::account::ReactivateAccountRequest,
+            dto::account::AccountClosureDisplay,
+            dto::webhooks::CreateWebhookRequest,
+            dto::webhooks::WebhookEventType,
+            dto::webhooks::WebhookDisplay,
+            dto::webhooks::WebhookDeliveryStatus,
+            dto::webhooks::WebhookDeliveryDisplay,
+            dto::webhooks::WebhookPayload,
+            dto::webhooks::WebhookEvent,
+            dto::webhooks::GameFinishedData,
+            dto::webhooks::RoundPairedData,
+
+            // Engine match schemas
+            dto::engine_matches::EngineMatchStatus,
+            dto::engine_matches::MatchEngine,
+            dto::engine_matches::AdjudicationRules,
+            dto::engine_matches::SprtRequest,
+            dto::engine_matches::CreateEngineMatchRequest,
+            dto::engine_matches::SprtVerdict,
+            dto::engine_matches::MatchSummaryDisplay,
+            dto::engine_matches::EngineMatchDisplay,
+            dto::engine_matches::EngineMatchGameDisplay,
+
+            // Training schemas
+            dto::training::TrainingDrill,
+            dto::training::VisionPiece,
+            dto::training::Exercise,
+            dto::training::StartTrainingRequest,
+            dto::training::SubmitTrainingRequest,
+            dto::training::TrainingSessionDisplay,
+            dto::training::ExerciseResult,
+            dto::training::TrainingResultDisplay,
+            dto::training::BestScore,
+            dto::training::DrillStats,
+            dto::training::TrainingLeaderboardEntry,
             
             // Response schemas
             dto::responses::PlayerAdded,
             dto::responses::PlayerFound,
+            dto::responses::PlayerFoundBody,
             dto::responses::PlayerUpdated,
             dto::responses::PlayerDeleted,
             dto::responses::InvalidCredentialsResponse,
@@ -94,6 +476,17 @@ impl Modify for SecurityAddon {
         (name = "Games", description = "Game management operations"),
         (name = "Authentication", description = "Authentication operations"),
         (name = "AI", description = "AI suggestion operations"),
+        (name = "Engine Matches", description = "Engine-vs-engine matches for tuning bot strength"),
+        (name = "Training", description = "Coordinate and board-vision drills with personal bests"),
+        (name = "Moderation", description = "Reports, account actions and role management"),
+        (name = "Disputes", description = "Contested game results and arbiter decisions"),
+        (name = "Titles", description = "Titled player verification and moderator review"),
+        (name = "Clubs", description = "Clubs, member roles, challenge ladders and club tournaments"),
+        (name = "Coaching", description = "Coach and student links, shared progress and assignments"),
+        (name = "Leaderboards", description = "Rankings per time control"),
+        (name = "Search", description = "Search players and tournaments by name"),
+        (name = "Tournaments", description = "Swiss and arena tournaments, recurring templates and arbiter round management"),
+        (name = "Webhooks", description = "Signed HTTP callbacks for game and tournament events"),
         (name = "WebSocket", description = "WebSocket communication protocol")
     ),
     info(
@@ -1,6 +1,6 @@
 use actix_web::{
-    HttpResponse, delete, get, post, put,
-    web::{Json, Path},
+    HttpRequest, HttpResponse, delete, get, post, put,
+    web::{self, Json, Path},
 };
 use dto::{
     players::{DisplayPlayer, NewPlayer, UpdatePlayer, UpdatedPlayer},
@@ -10,6 +10,8 @@ use dto::{
     },
 };
 use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use security::Captcha;
 use serde_json::json;
 use validator::Validate;
 
@@ -17,18 +19,34 @@ use service::players::{
     add_player as add_new_player, delete_player as delete_player_by_id,
     find_player_by_id as get_single_player_by_id, update_player as update_player_by_id,
 };
+use service::stats::StatsService;
+use service::trophies::TrophyService;
 use uuid::Uuid;
+use crate::guard::{captcha_error, require_captcha};
+use crate::replicas::ReadReplicas;
 
 #[utoipa::path(
     post,
     path = "/v1/players",
+    params(
+        ("X-Captcha" = Option<String>, Header, description = "Solution of the challenge from /v1/auth/captcha, when captchas are enabled")
+    ),
     responses(
         (status = 200, description = "New player added", body=PlayerAdded),
-        (status = 400, description = "Bad request", body=InvalidCredentialsResponse)
+        (status = 400, description = "Bad request or missing captcha", body=InvalidCredentialsResponse),
+        (status = 403, description = "Captcha not solved", body=InvalidCredentialsResponse)
     )
 )]
 #[post("")]
-pub async fn add_player(payload: Json<NewPlayer>) -> HttpResponse {
+pub async fn add_player(
+    req: HttpRequest,
+    captcha: Option<web::Data<Captcha>>,
+    payload: Json<NewPlayer>,
+) -> HttpResponse {
+    if let Err(err) = require_captcha(captcha.as_ref(), &req).await {
+        return captcha_error(err).error_response();
+    }
+
     match payload.0.validate() {
         Ok(_) => {
             let player = add_new_player(payload.0).await;
@@ -57,14 +75,18 @@ pub async fn add_player(payload: Json<NewPlayer>) -> HttpResponse {
     )
 )]
 #[get("/{id}")]
-pub async fn find_player_by_id(id: Path<Uuid>) -> HttpResponse {
-    let player = get_single_player_by_id(id.into_inner()).await;
+pub async fn find_player_by_id(id: Path<Uuid>, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match get_single_player_by_id(id.into_inner()).await {
+        Ok(plyr) => plyr,
+        Err(err) => return err.error_response(),
+    };
 
-    match player {
-        Ok(plyr) => HttpResponse::Ok().json(json!({
+    match TrophyService::for_player(db.get_ref(), player.id).await {
+        Ok(trophies) => HttpResponse::Ok().json(json!({
             "message":"Player found",
             "data":{
-                "player": DisplayPlayer::from(plyr)
+                "player": DisplayPlayer::from(player),
+                "trophies": trophies
             }
         })),
         Err(err) => err.error_response(),
@@ -123,3 +145,33 @@ pub async fn delete_player(id: Path<Uuid>) -> HttpResponse {
         Err(err) => err.error_response(),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/v1/players/{id}/stats",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format="uuid")
+    ),
+    responses(
+        (status = 200, description = "Player statistics", body = dto::stats::PlayerStats),
+        (status = 404, description = "Not found", body=NotFoundResponse)
+    )
+)]
+#[get("/{id}/stats")]
+pub async fn get_player_stats(id: Path<Uuid>, replicas: web::Data<ReadReplicas>) -> HttpResponse {
+    let player_id = id.into_inner();
+
+    if let Err(err) = get_single_player_by_id(player_id).await {
+        return err.error_response();
+    }
+
+    match StatsService::get(replicas.read().as_ref(), player_id).await {
+        Ok(stats) => HttpResponse::Ok().json(json!({
+            "message":"Player stats found",
+            "data":{
+                "stats": stats
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,72 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, patch,
+    web::{self, Json},
+};
+use dto::preferences::UpdatePreferencesRequest;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::preferences::PreferenceService;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    get,
+    path = "/v1/account/preferences",
+    responses(
+        (status = 200, description = "The caller's settings, defaults included", body = PreferencesDisplay),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[get("/preferences")]
+pub async fn get_preferences(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match PreferenceService::get(db.get_ref(), player.id).await {
+        Ok(preferences) => HttpResponse::Ok().json(json!({
+            "message": "Preferences found",
+            "data": preferences
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/v1/account/preferences",
+    request_body = UpdatePreferencesRequest,
+    responses(
+        (status = 200, description = "Settings changed; all of them after the change", body = PreferencesDisplay),
+        (status = 400, description = "Unknown setting or value", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Players"
+)]
+#[patch("/preferences")]
+pub async fn update_preferences(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<UpdatePreferencesRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match PreferenceService::update(db.get_ref(), player.id, payload.into_inner()).await {
+        Ok(preferences) => HttpResponse::Ok().json(json!({
+            "message": "Preferences updated",
+            "data": preferences
+        })),
+        Err(err) => err.error_response(),
+    }
+}
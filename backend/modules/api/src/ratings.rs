@@ -0,0 +1,237 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::player_role::Role;
+use dto::ratings::{
+    RatingHistoryQuery, RatingPoint, RecalculateRatingsRequest, RecalculationDisplay, SeasonResetRequest,
+    VoidGamesRequest,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::rating::RatingService;
+use service::recalculation::RecalculationService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::require_role;
+use crate::replicas::ReadReplicas;
+
+#[utoipa::path(
+    get,
+    path = "/v1/players/{id}/rating-history",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid"),
+        ("tc" = String, Query, description = "Time control: bullet, blitz, rapid or classical"),
+        ("from" = Option<String>, Query, description = "Only points recorded at or after this time", format = "date-time"),
+        ("to" = Option<String>, Query, description = "Only points recorded at or before this time", format = "date-time"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of points (max 2000)")
+    ),
+    responses(
+        (status = 200, description = "Rating points, oldest first", body = Vec<RatingPoint>)
+    ),
+    tag = "Players"
+)]
+#[get("/{id}/rating-history")]
+pub async fn get_rating_history(
+    id: Path<Uuid>,
+    query: Query<RatingHistoryQuery>,
+    replicas: web::Data<ReadReplicas>,
+) -> HttpResponse {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(500);
+
+    match RatingService::history(
+        replicas.read().as_ref(),
+        id.into_inner(),
+        query.tc.into(),
+        query.from.map(|t| t.fixed_offset()),
+        query.to.map(|t| t.fixed_offset()),
+        limit,
+    )
+    .await
+    {
+        Ok(points) => {
+            let points: Vec<RatingPoint> = points.into_iter().map(RatingPoint::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Rating history found",
+                "data": {
+                    "time_control": query.tc,
+                    "points": points
+                }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/ratings/season-reset",
+    request_body = SeasonResetRequest,
+    responses(
+        (status = 200, description = "Season started"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/ratings/season-reset")]
+pub async fn reset_season(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<SeasonResetRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    let category = payload.time_control.map(Into::into);
+    match RatingService::season_reset(db.get_ref(), category, payload.reset_rd).await {
+        Ok(updated) => HttpResponse::Ok().json(json!({
+            "message": "Season started",
+            "data": { "ratings_updated": updated }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/ratings/recalculations",
+    request_body = RecalculateRatingsRequest,
+    responses(
+        (status = 202, description = "Recalculation queued", body = RecalculationDisplay),
+        (status = 400, description = "Invalid request", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/ratings/recalculations")]
+pub async fn recalculate_ratings(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<RecalculateRatingsRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let admin = match require_role(db.get_ref(), &req, Role::Admin).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let payload = payload.into_inner();
+    match RecalculationService::enqueue(
+        db.get_ref(),
+        payload.player_ids,
+        payload.since.fixed_offset(),
+        payload.reason,
+        Some(admin.id),
+    )
+    .await
+    {
+        Ok(recalculation) => HttpResponse::Accepted().json(json!({
+            "message": "Recalculation queued",
+            "data": RecalculationDisplay::from(recalculation)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/mod/ratings/recalculations/{id}",
+    params(
+        ("id" = String, Path, description = "Recalculation ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Recalculation with its before/after audit", body = RecalculationDisplay),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Recalculation not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[get("/ratings/recalculations/{id}")]
+pub async fn get_recalculation(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    match RecalculationService::get(db.get_ref(), id.into_inner()).await {
+        Ok(recalculation) => HttpResponse::Ok().json(json!({
+            "message": "Recalculation found",
+            "data": RecalculationDisplay::from(recalculation)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/mod/players/{id}/void-games",
+    params(
+        ("id" = String, Path, description = "Player ID in UUID format", format = "uuid")
+    ),
+    request_body = VoidGamesRequest,
+    responses(
+        (status = 202, description = "Games voided and recalculation queued", body = RecalculationDisplay),
+        (status = 400, description = "No finished games to void", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Moderation"
+)]
+#[post("/players/{id}/void-games")]
+pub async fn void_games(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<VoidGamesRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let admin = match require_role(db.get_ref(), &req, Role::Admin).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let payload = payload.into_inner();
+    match RecalculationService::void_games(
+        db.get_ref(),
+        id.into_inner(),
+        payload.since.fixed_offset(),
+        payload.reason,
+        Some(admin.id),
+    )
+    .await
+    {
+        Ok(recalculation) => HttpResponse::Accepted().json(json!({
+            "message": "Games voided",
+            "data": RecalculationDisplay::from(recalculation)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,84 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::Error,
+    http::header::{HeaderName, HeaderValue},
+};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use telemetry::REQUEST_ID_HEADER;
+use tracing::Instrument;
+
+/// Middleware giving each request an id and a span. The id is the caller's
+/// `X-Request-Id` when it is usable, a fresh one otherwise, and is sent
+/// back in the response's `X-Request-Id`. Everything logged while serving
+/// the request, queries and engine calls included, carries it.
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdMiddlewareService { service })
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id =
+            telemetry::request_id_from(req.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()));
+        let span = tracing::info_span!(
+            "request",
+            %request_id,
+            method = %req.method(),
+            path = %req.path(),
+        );
+        let fut = span.in_scope(|| self.service.call(req));
+
+        let served = {
+            let request_id = request_id.clone();
+            async move {
+                let started = Instant::now();
+                let mut res = fut.await?;
+                tracing::info!(
+                    status = res.status().as_u16(),
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "Request served"
+                );
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                }
+                Ok(res)
+            }
+            .instrument(span)
+        };
+        Box::pin(telemetry::with_request_id(request_id, served))
+    }
+}
@@ -0,0 +1,97 @@
+use actix_web::{
+    HttpResponse, get,
+    web::{self, Query},
+};
+use dto::search::{PositionMatch, PositionSearchQuery, SearchQuery};
+use error::error::ApiError;
+use serde_json::json;
+use service::positions::PositionIndexService;
+use service::search::SearchService;
+use validator::Validate;
+
+use crate::cache::{CacheKey, ResponseCache};
+use crate::replicas::ReadReplicas;
+
+#[utoipa::path(
+    get,
+    path = "/v1/search",
+    params(
+        ("q" = String, Query, description = "Text to look for in usernames and tournament names (2 to 100 characters)"),
+        ("kind" = Option<String>, Query, description = "Only search `player` or `tournament` names"),
+        ("limit" = Option<u64>, Query, description = "Number of results to return (max 50)"),
+        ("offset" = Option<u64>, Query, description = "Results to skip, from `next_offset` of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Matching players and tournaments, best matches first", body = Vec<dto::search::SearchResult>),
+        (status = 400, description = "Search text too short or too long", body = InvalidCredentialsResponse)
+    ),
+    tag = "Search"
+)]
+#[get("")]
+pub async fn search(query: Query<SearchQuery>, replicas: web::Data<ReadReplicas>) -> HttpResponse {
+    if let Err(errors) = query.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let limit = query.limit.unwrap_or(20);
+    let offset = query.offset.unwrap_or(0);
+
+    match SearchService::search(replicas.read().as_ref(), &query.q, query.kind, limit, offset).await {
+        Ok((results, next_offset)) => HttpResponse::Ok().json(json!({
+            "message": "Search results found",
+            "data": {
+                "results": results,
+                "next_offset": next_offset
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/search/position",
+    params(
+        ("fen" = Option<String>, Query, description = "Position the games reached, by any move order"),
+        ("material" = Option<String>, Query, description = "Material the games reached, e.g. `KRPkr`: upper case for white, lower case for black"),
+        ("result" = Option<String>, Query, description = "Only games ending white_win, black_win or draw"),
+        ("min_rating" = Option<i32>, Query, description = "Only games where both players were rated at least this much"),
+        ("limit" = Option<u64>, Query, description = "Number of games to return (max 50)"),
+        ("offset" = Option<u64>, Query, description = "Games to skip, from `next_offset` of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Finished games that reached the position, newest first", body = Vec<PositionMatch>),
+        (status = 400, description = "Neither `fen` nor `material` given, or one is invalid", body = InvalidCredentialsResponse)
+    ),
+    tag = "Search"
+)]
+#[get("/position")]
+pub async fn search_position(
+    query: Query<PositionSearchQuery>,
+    replicas: web::Data<ReadReplicas>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    if let Err(errors) = query.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let key = CacheKey::PositionSearch(serde_json::to_string(&query.0).unwrap_or_default());
+    let (games, next_offset) = match cache.get::<(Vec<PositionMatch>, Option<u64>)>(&key).await {
+        Some(page) => page,
+        None => match PositionIndexService::search(replicas.read().as_ref(), &query).await {
+            Ok(page) => {
+                cache.put(&key, &page).await;
+                page
+            }
+            Err(err) => return err.error_response(),
+        },
+    };
+
+    HttpResponse::Ok().json(json!({
+        "message": "Games found",
+        "data": {
+            "games": games,
+            "next_offset": next_offset
+        }
+    }))
+}
@@ -5,18 +5,92 @@ use actix_cors::Cors;
 use dotenv::dotenv;
 use sea_orm::{Database, DatabaseConnection};
 use std::env;
-use security::JwtService;
+use security::{
+    Captcha, CaptchaProvider, IdempotencyMiddleware, IdempotencyStore, JwtAuthMiddleware, JwtService, ProofOfWork,
+    MemoryRevocationStore, RedisRevocationStore, RevocationStore, RpcSignatureVerifier, SignInDomain,
+    SiteVerifyCaptcha, TokenDenylist, WalletAuth,
+};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_redoc::{Redoc, Servable};
 use actix::Actor;
-use crate::players::{add_player, delete_player, find_player_by_id, update_player};
-use crate::games::{create_game, get_game, make_move, list_games, join_game, abandon_game, import_game};
-use crate::auth::{login, register, refresh, logout};
-use crate::ai::{get_ai_suggestion, analyze_position};
+use crate::players::{add_player, delete_player, find_player_by_id, get_player_stats, update_player};
+use crate::games::{create_game, get_game, make_move, list_games, join_game, abandon_game, import_game, verify_game};
+use crate::auth::{captcha_challenge, link_wallet, login, logout, refresh, register, wallet_challenge, wallet_login};
+use crate::ai::{
+    analyze_position, bot_move, get_ai_suggestion, get_engine_queue, list_bots, list_engines, run_engine_benchmark,
+    start_bot_game,
+};
+use crate::moderation::{
+    apply_action, create_report, flag_game, grant_role, link_accounts, list_account_links, list_actions,
+    list_reports, resolve_report, revoke_action, unlink_accounts,
+};
+use crate::disputes::{decide_dispute, list_disputes, open_dispute};
+use crate::titles::{
+    decide_title, list_title_verifications, my_title_verifications, submit_title, title_verification_events,
+};
+use crate::clubs::{
+    add_club_member, club_ladder, create_club, create_ladder_challenge, get_club, join_club, leave_club, list_clubs,
+    list_ladder_challenges, remove_club_member, report_ladder_result, respond_ladder_challenge, update_club_member,
+};
+use crate::coaching::{
+    accept_coaching, complete_assignment, create_assignment, delete_assignment, end_coaching, get_assignment,
+    invite_student, list_coaches, list_students, my_assignments, student_assignments, student_overview,
+    update_assignment,
+};
+use crate::leaderboards::{get_leaderboard, get_player_rank};
+use crate::search::{search, search_position};
+use crate::ratings::{get_rating_history, get_recalculation, recalculate_ratings, reset_season, void_games};
+use crate::tournaments::{
+    create_tournament, export_trf, finish_tournament, force_pairing, get_standings, get_tournament,
+    import_registrations, pair_remaining, preview_round, record_forfeit, reenter_player, register_player, request_bye,
+    set_prizes, swap_colors, validate_trf, withdraw_player,
+};
+use crate::archive::{export_games, ExportLimiter};
+use crate::guests::{start_guest_session, GuestSessions};
+use crate::imports::{
+    dismiss_quarantined, get_import_job, import_account, list_import_jobs, list_quarantine, retry_quarantined,
+};
+use crate::game_events::{append_game_event, get_autosave_stats, get_game_events};
+use crate::analysis::get_game_analysis;
+use crate::annotations::{delete_annotations, export_annotated_pgn, list_annotations, save_annotations};
+use crate::friends::{add_friend, list_friends, remove_friend};
+use crate::engine_matches::{
+    cancel_engine_match, create_engine_match, get_engine_match, list_engine_matches, list_match_engines,
+};
+use crate::tournament_templates::{create_template, delete_template, list_templates, update_template};
+use crate::attestations::get_attestation;
+use crate::training::{get_training_leaderboard, get_training_stats, start_training, submit_training};
+use crate::preferences::{get_preferences, update_preferences};
+use crate::account::{close_account, export_personal_data, reactivate_account};
+use crate::webhooks::{create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks};
 use crate::ws::{LobbyState, ws_route};
+use crate::cache::{CacheStore, CacheTtls, MemoryCacheStore, RedisCacheStore, ResponseCache};
 use crate::config::AppConfig;
+use crate::replicas::ReadReplicas;
+use crate::request_id::RequestIdMiddleware;
 use actix_governor::{Governor, GovernorConfigBuilder};
+use service::attestations::{
+    AttestationChain, AttestationService, FieldElement, NonceManager, StarknetConfig, StarknetRpcClient,
+};
+use service::engine_matches::EngineMatchService;
+use service::positions::{PositionIndexService, INDEX_BATCH};
+use service::game_archive::GameArchiveService;
+use rpc::engine::RemoteEnginePool;
+use service::engine_service::{
+    AnalysisQueue, AssetCache, AssetManifest, EngineService, PreparedEngine, QueueConfig, SessionConfig,
+};
+use service::importer::GameFetcher;
+use service::leaderboard::LeaderboardService;
+use service::rating::RatingService;
+use service::recalculation::RecalculationService;
+use service::tournament_templates::TemplateService;
+use service::webhooks::{WebhookSender, WebhookService};
+use service::account::AccountService;
+use service::autosave::{AutosaveConfig, MoveBuffer};
+use service::integrity::IntegrityService;
+use service::linking::{Fingerprinter, LinkService};
+use service::tournaments::TournamentService;
 
 use crate::openapi::ApiDoc;
 
@@ -37,8 +111,9 @@ pub async fn main() -> std::io::Result<()> {
     // Load environment variables from .env file
     dotenv().ok();
 
-    // Initialize logger
-    env_logger::init();
+    // Logs and spans, with a request id for every request; exported over
+    // OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    let _telemetry = telemetry::init("starkmate-api", "error");
 
     // Load configuration from environment
     let server_addr = env::var("SERVER_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
@@ -78,6 +153,416 @@ pub async fn main() -> std::io::Result<()> {
     // Load AppConfig
     let config = AppConfig::from_env();
 
+    // Route read-only queries to the read replicas, if any are configured
+    let read_replicas = web::Data::new(ReadReplicas::connect(db.clone(), &config.database_replica_urls).await);
+    if !read_replicas.is_empty() {
+        let health_replicas = read_replicas.clone();
+        let health_every = std::time::Duration::from_secs(config.replica_health_check_secs.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(health_every);
+            loop {
+                ticker.tick().await;
+                health_replicas.check_health().await;
+            }
+        });
+    }
+
+    // Responses of hot read endpoints; in Redis when CACHE_REDIS_URL is set,
+    // so a change made through one instance drops them on every instance
+    let cache_store: std::sync::Arc<dyn CacheStore> = match config.cache_redis_url.as_deref() {
+        Some(url) => match RedisCacheStore::new(url) {
+            Ok(store) => std::sync::Arc::new(store),
+            Err(e) => {
+                log::error!("Invalid CACHE_REDIS_URL '{}': {}; responses are cached in this process", url, e);
+                std::sync::Arc::new(MemoryCacheStore::new())
+            }
+        },
+        None => std::sync::Arc::new(MemoryCacheStore::new()),
+    };
+    let response_cache = web::Data::new(ResponseCache::new(
+        cache_store,
+        CacheTtls {
+            leaderboard: std::time::Duration::from_secs(config.cache_leaderboard_secs),
+            standings: std::time::Duration::from_secs(config.cache_standings_secs),
+            position_search: std::time::Duration::from_secs(config.cache_position_search_secs),
+            analysis: std::time::Duration::from_secs(config.cache_analysis_secs),
+        },
+    ));
+
+    // Periodically rebuild the materialized leaderboards
+    let leaderboard_db = db.clone();
+    let leaderboard_cache = response_cache.clone();
+    let leaderboard_every = std::time::Duration::from_secs(config.leaderboard_refresh_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(leaderboard_every);
+        loop {
+            ticker.tick().await;
+            match LeaderboardService::refresh(&leaderboard_db).await {
+                Ok(count) => {
+                    leaderboard_cache.leaderboards_changed().await;
+                    log::debug!("Leaderboards refreshed with {} entries", count)
+                }
+                Err(e) => log::error!("Failed to refresh leaderboards: {}", e),
+            }
+        }
+    });
+
+    // Move old finished games out of the hot game table, batch by batch
+    if config.game_archive_after_days > 0 {
+        let archive_db = db.clone();
+        let archive_after = chrono::Duration::days(config.game_archive_after_days);
+        let archive_batch = config.game_archive_batch.max(1);
+        let archive_every = std::time::Duration::from_secs(config.game_archive_poll_secs.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(archive_every);
+            loop {
+                ticker.tick().await;
+                let cutoff = chrono::Utc::now().fixed_offset() - archive_after;
+                loop {
+                    match GameArchiveService::run_pass(&archive_db, cutoff, archive_batch).await {
+                        Ok(moved) => {
+                            if moved > 0 {
+                                log::info!("Archived {} finished games", moved);
+                            }
+                            if moved < archive_batch {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to archive games: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Index the positions of finished games for position search
+    let position_db = db.clone();
+    let position_cache = response_cache.clone();
+    let position_every = std::time::Duration::from_secs(config.position_index_poll_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(position_every);
+        loop {
+            ticker.tick().await;
+            match PositionIndexService::index_pending(&position_db, INDEX_BATCH).await {
+                Ok(0) => {}
+                Ok(count) => {
+                    position_cache.positions_indexed().await;
+                    log::debug!("Indexed the positions of {} games", count)
+                }
+                Err(e) => log::error!("Failed to index game positions: {}", e),
+            }
+        }
+    });
+
+    // Inflate rating deviations of inactive players once per rating period
+    if config.rating_period_days > 0 {
+        let decay_db = db.clone();
+        let rating_period = chrono::Duration::days(config.rating_period_days);
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                ticker.tick().await;
+                let now = chrono::Utc::now().fixed_offset();
+                match RatingService::apply_inactivity_decay(&decay_db, now, rating_period).await {
+                    Ok(count) => log::debug!("Rating deviation decay applied to {} ratings", count),
+                    Err(e) => log::error!("Failed to apply rating deviation decay: {}", e),
+                }
+            }
+        });
+    }
+
+    // Replay ratings after corrected or voided results, one recalculation at a time
+    let recalculation_db = db.clone();
+    let recalculation_every = std::time::Duration::from_secs(config.rating_recalculation_poll_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(recalculation_every);
+        loop {
+            ticker.tick().await;
+            loop {
+                match RecalculationService::run_next(&recalculation_db).await {
+                    Ok(Some(id)) => log::debug!("Rating recalculation {} done", id),
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::error!("Failed to run rating recalculation: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Create tournaments from templates, then open, start and finish them on time
+    let scheduler_db = db.clone();
+    let scheduler_cache = response_cache.clone();
+    let scheduler_every = std::time::Duration::from_secs(config.tournament_scheduler_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(scheduler_every);
+        loop {
+            ticker.tick().await;
+            let now = chrono::Utc::now().fixed_offset();
+            match TemplateService::run_due(&scheduler_db, now).await {
+                Ok(count) => log::debug!("Scheduled {} tournaments from templates", count),
+                Err(e) => log::error!("Failed to schedule tournaments: {}", e),
+            }
+            match TournamentService::advance_due(&scheduler_db, now).await {
+                Ok(count) => {
+                    if count > 0 {
+                        scheduler_cache.tournaments_changed().await;
+                    }
+                    log::debug!("Advanced {} scheduled tournaments", count)
+                }
+                Err(e) => log::error!("Failed to advance scheduled tournaments: {}", e),
+            }
+        }
+    });
+
+    // Play queued engine-vs-engine matches one at a time, resuming any whose
+    // instance stopped running them
+    let match_db = db.clone();
+    let match_engines = config.match_engines.clone();
+    let match_every = std::time::Duration::from_secs(config.engine_match_poll_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(match_every);
+        loop {
+            ticker.tick().await;
+            match EngineMatchService::requeue_interrupted(&match_db).await {
+                Ok(count) => log::debug!("Requeued {} interrupted engine matches", count),
+                Err(e) => log::error!("Failed to requeue engine matches: {}", e),
+            }
+            loop {
+                match EngineMatchService::run_next(&match_db, &match_engines).await {
+                    Ok(Some(id)) => log::debug!("Engine match {} done", id),
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::error!("Failed to run engine match: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Attest finished rated games on StarkNet when a node and account are configured
+    let attestation_chain = starknet_client(&config);
+    if let Some(chain) = attestation_chain.clone() {
+        let attestation_db = db.clone();
+        let attestation_every = std::time::Duration::from_secs(config.attestation_poll_secs.max(1));
+        actix_web::rt::spawn(async move {
+            let mut nonces = NonceManager::new();
+            let mut ticker = actix_web::rt::time::interval(attestation_every);
+            loop {
+                ticker.tick().await;
+                match AttestationService::run_pass(&attestation_db, chain.as_ref(), &mut nonces).await {
+                    Ok(pass) => log::debug!("Attestation pass: {:?}", pass),
+                    Err(e) => log::error!("Failed to process game attestations: {}", e),
+                }
+            }
+        });
+    }
+
+    // Send queued webhook deliveries and retry failed ones with backoff
+    let webhook_db = db.clone();
+    let webhook_sender = WebhookSender::new(std::time::Duration::from_secs(config.webhook_timeout_secs.max(1)));
+    let webhook_every = std::time::Duration::from_secs(config.webhook_poll_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(webhook_every);
+        loop {
+            ticker.tick().await;
+            match WebhookService::deliver_due(&webhook_db, &webhook_sender).await {
+                Ok(pass) => log::debug!("Webhook pass: {:?}", pass),
+                Err(e) => log::error!("Failed to send webhook deliveries: {}", e),
+            }
+        }
+    });
+
+    // Anonymize closed accounts once their grace period is over
+    let purge_db = db.clone();
+    let purge_every = std::time::Duration::from_secs(config.account_purge_poll_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(purge_every);
+        loop {
+            ticker.tick().await;
+            match AccountService::purge_due(&purge_db, chrono::Utc::now()).await {
+                Ok(pass) => log::debug!("Account purge pass: {:?}", pass),
+                Err(e) => log::error!("Failed to purge closed accounts: {}", e),
+            }
+        }
+    });
+
+    // Queue players whose ratings or results look manipulated, or who share
+    // devices with other accounts, for moderators; then forget fingerprints
+    // past their retention
+    let integrity_db = db.clone();
+    let integrity_every = std::time::Duration::from_secs(config.integrity_scan_secs.max(1));
+    let fingerprint_retention = chrono::Duration::days(config.fingerprint_retention_days.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(integrity_every);
+        loop {
+            ticker.tick().await;
+            let now = chrono::Utc::now().fixed_offset();
+            match IntegrityService::run_pass(&integrity_db, now).await {
+                Ok(pass) => log::debug!("Integrity scan pass: {:?}", pass),
+                Err(e) => log::error!("Failed to scan for rating manipulation: {}", e),
+            }
+            if let Err(e) = LinkService::purge_fingerprints(&integrity_db, now - fingerprint_retention).await {
+                log::error!("Failed to purge old fingerprints: {}", e);
+            }
+        }
+    });
+
+    // Hashed networks and devices of logins and socket connections
+    let fingerprinter = config.fingerprint_secret.as_deref().map(Fingerprinter::new);
+
+    // Captcha or proof of work on registration and anonymous game creation
+    let captcha = match config.captcha_provider.as_str() {
+        "pow" => Some(Captcha::new(std::sync::Arc::new(ProofOfWork::new(
+            config.captcha_pow_difficulty,
+            chrono::Duration::seconds(config.captcha_pow_ttl_secs.max(1) as i64),
+        )))),
+        name => match (CaptchaProvider::parse(name), config.captcha_site_key.clone(), config.captcha_secret.clone()) {
+            (Some(provider), Some(site_key), Some(secret)) => Some(Captcha::new(std::sync::Arc::new(
+                SiteVerifyCaptcha::new(provider, site_key, secret, std::time::Duration::from_secs(10)),
+            ))),
+            (Some(_), _, _) => {
+                log::warn!("CAPTCHA_PROVIDER={} needs CAPTCHA_SITE_KEY and CAPTCHA_SECRET; captchas are off", name);
+                None
+            }
+            _ => None,
+        },
+    };
+
+    // Sign-In with StarkNet; signatures are checked by the account contracts
+    // through the node, so it needs STARKNET_RPC_URL
+    let wallet_auth = config.starknet_rpc_url.clone().map(|rpc_url| {
+        WalletAuth::new(
+            SignInDomain {
+                name: "StarkMate".to_string(),
+                version: "1".to_string(),
+                chain_id: config.starknet_chain_id.clone(),
+            },
+            chrono::Duration::seconds(config.wallet_challenge_ttl_secs.max(1) as i64),
+            std::sync::Arc::new(RpcSignatureVerifier::new(rpc_url, std::time::Duration::from_secs(10))),
+        )
+    });
+
+    // Responses to retried mutations, shared by every worker
+    let idempotency_store =
+        IdempotencyStore::new(std::time::Duration::from_secs(config.idempotency_ttl_secs.max(1)));
+
+    // Game archive exports allowed per player, shared by every worker
+    let export_limiter =
+        ExportLimiter::new(std::time::Duration::from_secs(config.archive_export_cooldown_secs));
+
+    // Access tokens revoked by logout, bans and account closure; in Redis
+    // when TOKEN_DENYLIST_REDIS_URL is set, so every instance sees them
+    let revocation_store: std::sync::Arc<dyn RevocationStore> = match config.token_denylist_redis_url.as_deref() {
+        Some(url) => match RedisRevocationStore::new(url) {
+            Ok(store) => std::sync::Arc::new(store),
+            Err(e) => {
+                log::error!("Invalid TOKEN_DENYLIST_REDIS_URL '{}': {}; revocations stay in this process", url, e);
+                std::sync::Arc::new(MemoryRevocationStore::new())
+            }
+        },
+        None => std::sync::Arc::new(MemoryRevocationStore::new()),
+    };
+    let token_denylist = web::Data::new(TokenDenylist::new(
+        revocation_store,
+        jwt_expiration as u64,
+        std::time::Duration::from_secs(config.token_denylist_cache_secs),
+    ));
+
+    // Guest sessions and the casual games open to them, shared by every worker
+    let guest_sessions =
+        web::Data::new(GuestSessions::new(chrono::Duration::seconds(config.guest_session_ttl_secs.max(1) as i64)));
+
+    // Write-behind buffer for game events, shared by every worker; writes
+    // out what is waiting every GAME_AUTOSAVE_INTERVAL_MS
+    let move_buffer = web::Data::new(MoveBuffer::new(AutosaveConfig {
+        interval: std::time::Duration::from_millis(config.game_autosave_interval_ms),
+        max_buffered: config.game_autosave_max_events,
+    }));
+    if !move_buffer.interval().is_zero() {
+        let autosave_db = db.clone();
+        let autosave_buffer = move_buffer.clone();
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(autosave_buffer.interval());
+            loop {
+                ticker.tick().await;
+                autosave_buffer.flush_due(&autosave_db).await;
+            }
+        });
+    }
+    let shutdown_db = db.clone();
+    let shutdown_buffer = move_buffer.clone();
+
+    // HTTP client for account imports, shared by every worker
+    let game_fetcher = GameFetcher::new(std::time::Duration::from_secs(config.import_timeout_secs.max(1)));
+
+    // Engine pool for analysis requests, shared by every worker: a separate
+    // engine-server when ENGINE_GRPC_URL is set, otherwise engines run here
+    let remote_engines = config.engine_grpc_url.as_ref().and_then(|url| {
+        RemoteEnginePool::connect_lazy(url, std::time::Duration::from_secs(config.engine_grpc_timeout_secs.max(1)))
+            .map_err(|e| log::error!("Invalid ENGINE_GRPC_URL '{}': {}", url, e))
+            .ok()
+    });
+    let engine_service = match remote_engines {
+        Some(pool) => EngineService::remote(std::sync::Arc::new(pool)),
+        None => {
+            // Fetch the analysis engine and its network when a manifest is given,
+            // falling back to the engine installed at ENGINE_PATH
+            let installed_engine = || PreparedEngine::from_path(env::var("ENGINE_PATH").unwrap_or_else(|_| "stockfish".to_string()));
+            let analysis_engine = match &config.engine_assets_manifest {
+                Some(manifest_path) => {
+                    let cache = AssetCache::new(
+                        &config.engine_assets_dir,
+                        std::time::Duration::from_secs(config.engine_assets_timeout_secs.max(1)),
+                    );
+                    let prepared = async {
+                        let manifest = AssetManifest::load(std::path::Path::new(manifest_path)).await?;
+                        cache.prepare(&manifest, &config.analysis_engine).await
+                    };
+                    match prepared.await {
+                        Ok(engine) => engine,
+                        Err(e) => {
+                            log::error!("Failed to prepare engine '{}': {}", config.analysis_engine, e);
+                            installed_engine()
+                        }
+                    }
+                }
+                None => installed_engine(),
+            };
+
+            EngineService::with_queue(
+                analysis_engine,
+                AnalysisQueue::new(QueueConfig {
+                    slots: config.engine_slots,
+                    reserved_interactive: config.engine_reserved_interactive_slots,
+                    per_user_limit: config.engine_jobs_per_user,
+                }),
+            )
+            .with_load(config.analysis_load)
+            .with_sessions(SessionConfig {
+                max_sessions: config.engine_sessions,
+                idle_timeout: std::time::Duration::from_secs(config.engine_session_idle_secs.max(1)),
+            })
+            .with_variant_engines(config.variant_engines.clone())
+        }
+    };
+
+    // Quit the engines of followed games that went quiet
+    let session_engines = engine_service.clone();
+    let session_sweep_every = std::time::Duration::from_secs(config.engine_session_idle_secs.max(1));
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(session_sweep_every);
+        loop {
+            ticker.tick().await;
+            session_engines.sweep_sessions().await;
+        }
+    });
+
     eprintln!("Starting HTTP server on {}", server_addr);
 
     // Define the app factory closure
@@ -85,6 +570,17 @@ pub async fn main() -> std::io::Result<()> {
         let db = db.clone();
         let jwt_service = jwt_service.clone();
         let jwt_secret = jwt_secret.clone();
+        let idempotency_store = idempotency_store.clone();
+        let export_limiter = export_limiter.clone();
+        let game_fetcher = game_fetcher.clone();
+        let engine_service = engine_service.clone();
+        let attestation_chain = attestation_chain.clone();
+        let wallet_auth = wallet_auth.clone();
+        let captcha = captcha.clone();
+        let fingerprinter = fingerprinter.clone();
+        let guest_sessions = guest_sessions.clone();
+        let move_buffer = move_buffer.clone();
+        let token_denylist = token_denylist.clone();
         
         // Configure CORS middleware with environment variables for flexibility
         let cors = {
@@ -125,26 +621,143 @@ pub async fn main() -> std::io::Result<()> {
             .finish()
             .unwrap();
 
-        App::new()
-            // Global middleware
+        let mut app = App::new();
+        if let Some(chain) = attestation_chain {
+            app = app.app_data(web::Data::from(chain));
+        }
+        if let Some(wallet_auth) = wallet_auth {
+            app = app.app_data(web::Data::new(wallet_auth));
+        }
+        if let Some(captcha) = captcha {
+            app = app.app_data(web::Data::new(captcha));
+        }
+        if let Some(fingerprinter) = fingerprinter {
+            app = app.app_data(web::Data::new(fingerprinter));
+        }
+
+        app
+            // Global middleware; CORS wraps the replayed responses too, and
+            // the request id and span wrap everything
+            .wrap(IdempotencyMiddleware::new(idempotency_store))
             .wrap(cors)
+            .wrap(RequestIdMiddleware)
             // App data
             .app_data(web::Data::from(db.clone()))
+            .app_data(read_replicas.clone())
             .app_data(web::Data::new(jwt_service.clone()))
             .app_data(web::Data::new(lobby.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(export_limiter))
+            .app_data(web::Data::new(game_fetcher))
+            .app_data(web::Data::new(engine_service))
+            .app_data(guest_sessions)
+            .app_data(move_buffer)
+            .app_data(token_denylist)
+            .app_data(response_cache.clone())
             // WebSocket route mounting
             .route("/ws/{game_id}", web::get().to(ws_route))
             // Register your routes
             .route("/health", web::get().to(health))
             .route("/", web::get().to(greet))
+            // Game archive export, registered before /v1/players so it is matched first
+            .service(
+                web::scope("/v1/players/{id}/export")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(export_games),
+            )
             // Player routes
             .service(
                 web::scope("/v1/players")
                     .service(add_player)
                     .service(find_player_by_id)
+                    .service(get_player_stats)
+                    .service(get_rating_history)
                     .service(update_player)
                     .service(delete_player),
             )
+            // Friends list routes
+            .service(
+                web::scope("/v1/friends")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(list_friends)
+                    .service(add_friend)
+                    .service(remove_friend),
+            )
+            // Account settings routes
+            .service(
+                web::scope("/v1/account")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(get_preferences)
+                    .service(update_preferences)
+                    .service(export_personal_data)
+                    .service(close_account),
+            )
+            // Webhook subscription routes
+            .service(
+                web::scope("/v1/webhooks")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_webhook)
+                    .service(list_webhooks)
+                    .service(delete_webhook)
+                    .service(list_webhook_deliveries),
+            )
+            // Game annotation routes, registered before /v1/games so they are matched first
+            .service(
+                web::scope("/v1/games/{id}/annotations")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(list_annotations)
+                    .service(save_annotations)
+                    .service(delete_annotations)
+                    .service(export_annotated_pgn),
+            )
+            // Post-game analysis, registered before /v1/games so it is matched first
+            .service(
+                web::scope("/v1/games/{id}/analysis")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(get_game_analysis),
+            )
+            // Result disputes, registered before /v1/games so they are matched first
+            .service(
+                web::scope("/v1/games/{id}/disputes")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(open_dispute),
+            )
+            // Public attestation lookups, registered before /v1/games so they are matched first
+            .service(web::scope("/v1/games/{id}/attestation").service(get_attestation))
+            // Account imports, registered before /v1/games so they are matched first
+            .service(
+                web::scope("/v1/games/import/account")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(import_account),
+            )
+            // Import jobs and the quarantine of games that failed, registered
+            // after the account scope, which it would otherwise shadow
+            .service(
+                web::scope("/v1/games/import")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(list_import_jobs)
+                    .service(get_import_job)
+                    .service(list_quarantine)
+                    .service(retry_quarantined)
+                    .service(dismiss_quarantined),
+            )
+            // Game moves and event logs, registered before /v1/games so they are matched first
+            .service(
+                web::scope("/v1/games/{id}/move")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(make_move),
+            )
+            .service(
+                web::scope("/v1/games/{id}/events")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(get_game_events)
+                    .service(append_game_event),
+            )
+            .service(
+                web::scope("/v1/games/autosave")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(get_autosave_stats),
+            )
             // Game routes
             .service(
                 web::scope("/v1/games")
@@ -153,9 +766,15 @@ pub async fn main() -> std::io::Result<()> {
                     .service(get_game)
                     .service(list_games)
                     .service(join_game)
-                    .service(make_move)
                     .service(abandon_game)
-                    .service(import_game),
+                    .service(import_game)
+                    .service(verify_game),
+            )
+            // Wallet linking, registered before /v1/auth so it is matched first
+            .service(
+                web::scope("/v1/auth/wallet/link")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(link_wallet),
             )
             // Auth routes
             .service(
@@ -165,12 +784,162 @@ pub async fn main() -> std::io::Result<()> {
                     .service(register)
                     .service(refresh)
                     .service(logout)
+                    .service(wallet_challenge)
+                    .service(wallet_login)
+                    .service(captcha_challenge)
+                    .service(reactivate_account)
+                    .service(start_guest_session)
             )
             // AI routes
+            .service(
+                web::scope("/v1/ai/queue")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(get_engine_queue),
+            )
+            .service(
+                web::scope("/v1/ai/benchmark")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(run_engine_benchmark),
+            )
             .service(
                 web::scope("/v1/ai")
                     .service(get_ai_suggestion)
-                    .service(analyze_position),
+                    .service(analyze_position)
+                    .service(list_engines)
+                    .service(list_bots)
+                    .service(start_bot_game)
+                    .service(bot_move),
+            )
+            // Moderation routes
+            .service(
+                web::scope("/v1/mod")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_report)
+                    .service(list_reports)
+                    .service(resolve_report)
+                    .service(flag_game)
+                    .service(apply_action)
+                    .service(list_actions)
+                    .service(revoke_action)
+                    .service(grant_role)
+                    .service(list_account_links)
+                    .service(link_accounts)
+                    .service(unlink_accounts)
+                    .service(reset_season)
+                    .service(recalculate_ratings)
+                    .service(get_recalculation)
+                    .service(void_games),
+            )
+            // Arbiter dispute queue
+            .service(
+                web::scope("/v1/disputes")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(list_disputes)
+                    .service(decide_dispute),
+            )
+            // Titled player verification
+            .service(
+                web::scope("/v1/titles/verifications")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(submit_title)
+                    .service(my_title_verifications)
+                    .service(list_title_verifications)
+                    .service(decide_title)
+                    .service(title_verification_events),
+            )
+            // Clubs and their ladders
+            .service(
+                web::scope("/v1/clubs")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_club)
+                    .service(list_clubs)
+                    .service(get_club)
+                    .service(join_club)
+                    .service(leave_club)
+                    .service(club_ladder)
+                    .service(add_club_member)
+                    .service(update_club_member)
+                    .service(remove_club_member)
+                    .service(list_ladder_challenges)
+                    .service(create_ladder_challenge)
+                    .service(respond_ladder_challenge)
+                    .service(report_ladder_result),
+            )
+            // Coaches, students and assignments
+            .service(
+                web::scope("/v1/coaching")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(invite_student)
+                    .service(list_students)
+                    .service(list_coaches)
+                    .service(accept_coaching)
+                    .service(end_coaching)
+                    .service(student_overview)
+                    .service(student_assignments)
+                    .service(create_assignment)
+                    .service(my_assignments)
+                    .service(get_assignment)
+                    .service(update_assignment)
+                    .service(delete_assignment)
+                    .service(complete_assignment),
+            )
+            // Leaderboard routes
+            .service(
+                web::scope("/v1/leaderboards")
+                    .service(get_player_rank)
+                    .service(get_leaderboard),
+            )
+            // Search routes
+            .service(web::scope("/v1/search").service(search_position).service(search))
+            // Tournament routes
+            .service(
+                web::scope("/v1/tournaments")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_tournament)
+                    .service(get_tournament)
+                    .service(pair_remaining)
+                    .service(preview_round)
+                    .service(swap_colors)
+                    .service(force_pairing)
+                    .service(record_forfeit)
+                    .service(request_bye)
+                    .service(register_player)
+                    .service(withdraw_player)
+                    .service(reenter_player)
+                    .service(import_registrations)
+                    .service(set_prizes)
+                    .service(finish_tournament)
+                    .service(get_standings)
+                    .service(export_trf)
+                    .service(validate_trf),
+            )
+            // Tournament template routes
+            .service(
+                web::scope("/v1/tournament-templates")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_template)
+                    .service(list_templates)
+                    .service(update_template)
+                    .service(delete_template),
+            )
+            // Engine-vs-engine match routes
+            .service(
+                web::scope("/v1/engine-matches")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(create_engine_match)
+                    .service(list_engine_matches)
+                    .service(list_match_engines)
+                    .service(get_engine_match)
+                    .service(cancel_engine_match),
+            )
+            // Training routes
+            .service(
+                web::scope("/v1/training")
+                    .wrap(JwtAuthMiddleware::new(jwt_secret.clone(), jwt_expiration))
+                    .service(start_training)
+                    .service(submit_training)
+                    .service(get_training_stats)
+                    .service(get_training_leaderboard),
             )
             // Swagger UI integration
             .service(
@@ -182,6 +951,10 @@ pub async fn main() -> std::io::Result<()> {
             .service(
                 Redoc::with_url("/api/redoc", openapi.clone())
             )
+            // AsyncAPI description of the WebSocket messages
+            .route("/api/docs/asyncapi.json", web::get().to(|| async {
+                HttpResponse::Ok().json(crate::asyncapi::asyncapi_document())
+            }))
             // WebSocket documentation as static HTML
             .route("/api/docs/websocket", web::get().to(|| async {
                 HttpResponse::Ok()
@@ -199,5 +972,37 @@ pub async fn main() -> std::io::Result<()> {
         }
     }
 
-    server.run().await
+    server.run().await?;
+
+    // Events still buffered when the server stops are written before it exits
+    shutdown_buffer.flush_all(&shutdown_db).await;
+    Ok(())
+}
+
+/// Client for the attestation contract, if every StarkNet setting is present.
+fn starknet_client(config: &AppConfig) -> Option<std::sync::Arc<dyn AttestationChain>> {
+    let rpc_url = config.starknet_rpc_url.clone()?;
+    let felt = |name: &str, value: Option<&str>| {
+        let parsed = value.and_then(|value| FieldElement::from_hex_be(value).ok());
+        if parsed.is_none() {
+            log::error!("{} is missing or not a hex felt; game attestations are disabled", name);
+        }
+        parsed
+    };
+    let starknet_config = StarknetConfig {
+        rpc_url,
+        account_address: felt("STARKNET_ACCOUNT_ADDRESS", config.starknet_account_address.as_deref())?,
+        private_key: felt("STARKNET_PRIVATE_KEY", env::var("STARKNET_PRIVATE_KEY").ok().as_deref())?,
+        contract_address: felt("STARKNET_ATTESTATION_CONTRACT", config.starknet_attestation_contract.as_deref())?,
+        chain_id: config.starknet_chain_id.clone(),
+        max_fee: FieldElement::from(config.starknet_max_fee_wei),
+        timeout: std::time::Duration::from_secs(30),
+    };
+    match StarknetRpcClient::new(starknet_config) {
+        Ok(client) => Some(std::sync::Arc::new(client)),
+        Err(e) => {
+            log::error!("Game attestations are disabled: {}", e);
+            None
+        }
+    }
 }
@@ -0,0 +1,180 @@
+use actix_web::dev::Service;
+use actix_web::{test, web, App, HttpResponse, Responder};
+use futures_util::FutureExt;
+use security::{IdempotencyMiddleware, IdempotencyStore};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn counting_handler(counter: web::Data<Arc<AtomicUsize>>) -> impl Responder {
+    let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+    HttpResponse::Created().json(serde_json::json!({"count": count}))
+}
+
+async fn failing_handler(counter: web::Data<Arc<AtomicUsize>>) -> impl Responder {
+    counter.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::InternalServerError().finish()
+}
+
+async fn slow_handler(counter: web::Data<Arc<AtomicUsize>>) -> impl Responder {
+    counter.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_secs(3600)).await;
+    HttpResponse::Created().finish()
+}
+
+#[actix_web::test]
+async fn test_retried_request_is_replayed() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(counter.clone()))
+            .wrap(IdempotencyMiddleware::new(IdempotencyStore::new(Duration::from_secs(60))))
+            .route("/v1/things", web::post().to(counting_handler))
+            .route("/v1/other", web::post().to(counting_handler)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/v1/things")
+        .insert_header(("Idempotency-Key", "abc-123"))
+        .insert_header(("Authorization", "Bearer mine"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    assert!(resp.headers().get("Idempotent-Replayed").is_none());
+    let first = test::read_body(resp).await;
+
+    // Same key: the stored response comes back and the handler does not run
+    let req = test::TestRequest::post()
+        .uri("/v1/things")
+        .insert_header(("Idempotency-Key", "abc-123"))
+        .insert_header(("Authorization", "Bearer mine"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get("Idempotent-Replayed").unwrap(), "true");
+    assert_eq!(test::read_body(resp).await, first);
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+    // Another caller may use the same key
+    let req = test::TestRequest::post()
+        .uri("/v1/things")
+        .insert_header(("Idempotency-Key", "abc-123"))
+        .insert_header(("Authorization", "Bearer other"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+    // Reusing the key on another endpoint is refused
+    let req = test::TestRequest::post()
+        .uri("/v1/other")
+        .insert_header(("Idempotency-Key", "abc-123"))
+        .insert_header(("Authorization", "Bearer mine"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 422);
+
+    // Requests without a key are never deduplicated
+    for _ in 0..2 {
+        let req = test::TestRequest::post().uri("/v1/things").to_request();
+        test::call_service(&app, req).await;
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), 4);
+}
+
+#[actix_web::test]
+async fn test_server_errors_are_not_stored() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(counter.clone()))
+            .wrap(IdempotencyMiddleware::new(IdempotencyStore::new(Duration::from_secs(60))))
+            .route("/v1/things", web::post().to(failing_handler)),
+    )
+    .await;
+
+    for _ in 0..2 {
+        let req = test::TestRequest::post()
+            .uri("/v1/things")
+            .insert_header(("Idempotency-Key", "retry-me"))
+            .insert_header(("Authorization", "Bearer mine"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 500);
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
+
+#[actix_web::test]
+async fn test_invalid_key_is_rejected() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(counter.clone()))
+            .wrap(IdempotencyMiddleware::new(IdempotencyStore::new(Duration::from_secs(60))))
+            .route("/v1/things", web::post().to(counting_handler)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/v1/things")
+        .insert_header(("Idempotency-Key", "k".repeat(256)))
+        .insert_header(("Authorization", "Bearer mine"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(counter.load(Ordering::SeqCst), 0);
+}
+
+#[actix_web::test]
+async fn test_anonymous_requests_are_not_replayed() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(counter.clone()))
+            .wrap(IdempotencyMiddleware::new(IdempotencyStore::new(Duration::from_secs(60))))
+            .route("/v1/auth/login", web::post().to(counting_handler)),
+    )
+    .await;
+
+    // Two clients logging in with the same key each get their own response
+    for count in 1..=2 {
+        let req = test::TestRequest::post()
+            .uri("/v1/auth/login")
+            .insert_header(("Idempotency-Key", "login"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get("Idempotent-Replayed").is_none());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], count);
+    }
+}
+
+#[actix_web::test]
+async fn test_abandoned_request_releases_its_key() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(counter.clone()))
+            .wrap(IdempotencyMiddleware::new(IdempotencyStore::new(Duration::from_secs(60))))
+            .route("/v1/things", web::post().to(slow_handler)),
+    )
+    .await;
+
+    let request = || {
+        test::TestRequest::post()
+            .uri("/v1/things")
+            .insert_header(("Idempotency-Key", "gone"))
+            .insert_header(("Authorization", "Bearer mine"))
+            .to_request()
+    };
+
+    // The client disconnects while the handler is still running
+    assert!(app.call(request()).now_or_never().is_none());
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+    // The retry runs instead of finding the key still in progress
+    assert!(app.call(request()).now_or_never().is_none());
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
@@ -1,6 +1,12 @@
+#[cfg(test)]
+mod idempotency;
+
 #[cfg(test)]
 mod rate_limit;
 
+#[cfg(test)]
+mod request_id;
+
 #[cfg(test)]
 mod tests {
     use actix_web::{App, dev::Service, http::StatusCode, test, web};
@@ -0,0 +1,36 @@
+use actix_web::{test, web, App, HttpResponse, Responder};
+use crate::request_id::RequestIdMiddleware;
+use uuid::Uuid;
+
+async fn current_id() -> impl Responder {
+    HttpResponse::Ok().body(telemetry::current_request_id().unwrap_or_default())
+}
+
+#[actix_web::test]
+async fn test_request_id_is_echoed_or_generated() {
+    let app = test::init_service(
+        App::new()
+            .wrap(RequestIdMiddleware)
+            .route("/v1/id", web::get().to(current_id)),
+    )
+    .await;
+
+    // The caller's id is kept and visible to the handler
+    let req = test::TestRequest::get()
+        .uri("/v1/id")
+        .insert_header(("X-Request-Id", "client-7"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Request-Id").unwrap(), "client-7");
+    assert_eq!(test::read_body(resp).await, "client-7");
+
+    // An unusable id is replaced with a fresh one
+    let req = test::TestRequest::get()
+        .uri("/v1/id")
+        .insert_header(("X-Request-Id", "no spaces allowed"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let sent = resp.headers().get("X-Request-Id").unwrap().to_str().unwrap().to_string();
+    assert!(Uuid::parse_str(&sent).is_ok());
+    assert_eq!(test::read_body(resp).await, sent);
+}
@@ -0,0 +1,225 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use db_entity::{player_role::Role, title_verification::VerificationStatus};
+use dto::titles::{
+    DecideTitleRequest, SubmitTitleRequest, TitleQueueQuery, TitleVerificationDisplay, TitleVerificationEventDisplay,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::moderation::ModerationService;
+use service::titles::TitleVerificationService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::{current_player, require_role};
+
+#[utoipa::path(
+    post,
+    path = "/v1/titles/verifications",
+    request_body = SubmitTitleRequest,
+    responses(
+        (status = 201, description = "Title submitted for review", body = TitleVerificationDisplay),
+        (status = 400, description = "Invalid request, or a verification is already pending", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Titles"
+)]
+#[post("")]
+pub async fn submit_title(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<SubmitTitleRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TitleVerificationService::submit(db.get_ref(), player.id, payload.into_inner()).await {
+        Ok(verification) => HttpResponse::Created().json(json!({
+            "message": "Title submitted for review",
+            "data": TitleVerificationDisplay::from(verification)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/titles/verifications/mine",
+    responses(
+        (status = 200, description = "The player's title verifications, newest first", body = Vec<TitleVerificationDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Titles"
+)]
+#[get("/mine")]
+pub async fn my_title_verifications(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TitleVerificationService::mine(db.get_ref(), player.id).await {
+        Ok(verifications) => {
+            let verifications: Vec<TitleVerificationDisplay> =
+                verifications.into_iter().map(TitleVerificationDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Title verifications found",
+                "data": { "verifications": verifications }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/titles/verifications",
+    params(
+        ("status" = Option<String>, Query, description = "Queue to read: pending (default), approved, rejected or revoked"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of verifications to return")
+    ),
+    responses(
+        (status = 200, description = "Moderator queue, oldest first", body = Vec<TitleVerificationDisplay>),
+        (status = 403, description = "Moderator role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Titles"
+)]
+#[get("")]
+pub async fn list_title_verifications(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    query: Query<TitleQueueQuery>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Moderator).await {
+        return err.error_response();
+    }
+
+    let status = query.status.map(VerificationStatus::from).unwrap_or(VerificationStatus::Pending);
+    let limit = query.limit.unwrap_or(50);
+
+    match TitleVerificationService::list(db.get_ref(), status, limit).await {
+        Ok(verifications) => {
+            let verifications: Vec<TitleVerificationDisplay> =
+                verifications.into_iter().map(TitleVerificationDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Title verifications found",
+                "data": { "verifications": verifications }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/titles/verifications/{id}/decide",
+    params(
+        ("id" = String, Path, description = "Title verification ID in UUID format", format = "uuid")
+    ),
+    request_body = DecideTitleRequest,
+    responses(
+        (status = 200, description = "Verification decided; approval puts the title and flair on the profile, revocation removes them", body = TitleVerificationDisplay),
+        (status = 400, description = "Status cannot follow the current one", body = InvalidCredentialsResponse),
+        (status = 403, description = "Moderator role required, and not the player's own title", body = InvalidCredentialsResponse),
+        (status = 404, description = "Title verification not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Titles"
+)]
+#[post("/{id}/decide")]
+pub async fn decide_title(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<DecideTitleRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let moderator = match require_role(db.get_ref(), &req, Role::Moderator).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TitleVerificationService::decide(db.get_ref(), id.into_inner(), moderator.id, payload.into_inner()).await {
+        Ok(verification) => HttpResponse::Ok().json(json!({
+            "message": "Title verification decided",
+            "data": TitleVerificationDisplay::from(verification)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/titles/verifications/{id}/events",
+    params(
+        ("id" = String, Path, description = "Title verification ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Audit log of the verification, oldest first", body = Vec<TitleVerificationEventDisplay>),
+        (status = 403, description = "Only the player and moderators can read the log", body = InvalidCredentialsResponse),
+        (status = 404, description = "Title verification not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Titles"
+)]
+#[get("/{id}/events")]
+pub async fn title_verification_events(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    let verification = match TitleVerificationService::get(db.get_ref(), id.into_inner()).await {
+        Ok(verification) => verification,
+        Err(err) => return err.error_response(),
+    };
+    if verification.player_id != player.id {
+        match ModerationService::has_role(db.get_ref(), player.id, Role::Moderator).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return ApiError::Forbidden("Moderator role required".to_string()).error_response();
+            }
+            Err(err) => return err.error_response(),
+        }
+    }
+
+    match TitleVerificationService::events(db.get_ref(), verification.id).await {
+        Ok(events) => {
+            let events: Vec<TitleVerificationEventDisplay> =
+                events.into_iter().map(TitleVerificationEventDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Title verification events found",
+                "data": { "events": events }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,159 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, post, put,
+    web::{self, Json, Path},
+};
+use db_entity::player_role::Role;
+use dto::tournaments::{CreateTemplateRequest, TemplateDisplay, UpdateTemplateRequest};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::tournament_templates::TemplateService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::require_role;
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournament-templates",
+    request_body = CreateTemplateRequest,
+    responses(
+        (status = 201, description = "Template created and scheduled", body = TemplateDisplay),
+        (status = 400, description = "Invalid schedule or missing rounds/duration", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("")]
+pub async fn create_template(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateTemplateRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let admin = match require_role(db.get_ref(), &req, Role::Admin).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TemplateService::create(db.get_ref(), admin.id, payload.into_inner()).await {
+        Ok(template) => HttpResponse::Created().json(json!({
+            "message": "Template created",
+            "data": { "template": TemplateDisplay::from(template) }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tournament-templates",
+    responses(
+        (status = 200, description = "All tournament templates", body = Vec<TemplateDisplay>),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[get("")]
+pub async fn list_templates(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    match TemplateService::list(db.get_ref()).await {
+        Ok(templates) => {
+            let templates: Vec<TemplateDisplay> = templates.into_iter().map(TemplateDisplay::from).collect();
+            HttpResponse::Ok().json(json!({
+                "message": "Templates found",
+                "data": { "templates": templates }
+            }))
+        }
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/tournament-templates/{id}",
+    params(
+        ("id" = String, Path, description = "Template ID in UUID format", format = "uuid")
+    ),
+    request_body = UpdateTemplateRequest,
+    responses(
+        (status = 200, description = "Template updated", body = TemplateDisplay),
+        (status = 400, description = "Invalid schedule or registration window", body = InvalidCredentialsResponse),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Template not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[put("/{id}")]
+pub async fn update_template(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<UpdateTemplateRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    match TemplateService::update(db.get_ref(), id.into_inner(), payload.into_inner()).await {
+        Ok(template) => HttpResponse::Ok().json(json!({
+            "message": "Template updated",
+            "data": { "template": TemplateDisplay::from(template) }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/tournament-templates/{id}",
+    params(
+        ("id" = String, Path, description = "Template ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Template deleted; tournaments it created are kept"),
+        (status = 403, description = "Admin role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Template not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[delete("/{id}")]
+pub async fn delete_template(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Admin).await {
+        return err.error_response();
+    }
+
+    match TemplateService::delete(db.get_ref(), id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Template deleted",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,714 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post, put,
+    web::{self, Json, Path},
+};
+use db_entity::{player_role::Role, tournament};
+use dto::tournaments::{
+    ByeRequest, CreateTournamentRequest, ForcePairingRequest, ForfeitRequest, ImportRegistrationsRequest,
+    PreviewRoundRequest, SetPrizesRequest, StandingDisplay, SwapColorsRequest, ValidateTrfRequest,
+};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::tournaments::{self as tournaments_service, TournamentService};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::cache::{CacheKey, ResponseCache};
+use crate::guard::{current_player, require_role};
+
+fn tournament_response(message: &str, model: Result<tournament::Model, ApiError>) -> HttpResponse {
+    match model.and_then(|m| tournaments_service::display(&m)) {
+        Ok(tournament) => HttpResponse::Ok().json(json!({
+            "message": message,
+            "data": { "tournament": tournament }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+/// As `tournament_response`, once the cached standings of the tournament
+/// that changed are dropped.
+async fn changed_response(
+    cache: &ResponseCache,
+    message: &str,
+    model: Result<tournament::Model, ApiError>,
+) -> HttpResponse {
+    if let Ok(model) = &model {
+        cache.tournament_changed(model.id).await;
+    }
+    tournament_response(message, model)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments",
+    request_body = CreateTournamentRequest,
+    responses(
+        (status = 201, description = "Tournament created", body = TournamentDisplay),
+        (status = 400, description = "Invalid tournament", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Entered player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("")]
+pub async fn create_tournament(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateTournamentRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let arbiter = match require_role(db.get_ref(), &req, Role::Arbiter).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TournamentService::create(db.get_ref(), arbiter.id, payload.into_inner())
+        .await
+        .and_then(|m| tournaments_service::display(&m))
+    {
+        Ok(tournament) => HttpResponse::Created().json(json!({
+            "message": "Tournament created",
+            "data": { "tournament": tournament }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tournaments/{id}",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Tournament with its current round", body = TournamentDisplay),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[get("/{id}")]
+pub async fn get_tournament(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = current_player(db.get_ref(), &req).await {
+        return err.error_response();
+    }
+
+    tournament_response(
+        "Tournament found",
+        TournamentService::get(db.get_ref(), id.into_inner()).await,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/round/pair",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Unpaired players of the current round paired", body = TournamentDisplay),
+        (status = 400, description = "Remaining players cannot be paired", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/round/pair")]
+pub async fn pair_remaining(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Round paired",
+        TournamentService::pair_remaining(db.get_ref(), id.into_inner())
+            .await
+            .map(|(model, _)| model),
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/round/preview",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = PreviewRoundRequest,
+    responses(
+        (status = 200, description = "Pairings the next round would get after the given results; nothing is stored", body = RoundDisplay),
+        (status = 400, description = "Invalid results, no rounds left or the players cannot be paired", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament or player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/round/preview")]
+pub async fn preview_round(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<PreviewRoundRequest>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let results: Vec<_> = payload.results.iter().map(|r| (r.white_player_id, r.result)).collect();
+    match TournamentService::preview_round(db.get_ref(), id.into_inner(), &results).await {
+        Ok(round) => HttpResponse::Ok().json(json!({
+            "message": "Round previewed",
+            "data": { "round": round }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/round/swap-colors",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = SwapColorsRequest,
+    responses(
+        (status = 200, description = "Colors swapped", body = TournamentDisplay),
+        (status = 400, description = "Player is not paired or the game was forfeited", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament or player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/round/swap-colors")]
+pub async fn swap_colors(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<SwapColorsRequest>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Colors swapped",
+        TournamentService::swap_colors(db.get_ref(), id.into_inner(), payload.player_id).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/round/force-pairing",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = ForcePairingRequest,
+    responses(
+        (status = 200, description = "Pairing added to the current round", body = TournamentDisplay),
+        (status = 400, description = "A player is already paired or the players already met", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament or player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/round/force-pairing")]
+pub async fn force_pairing(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<ForcePairingRequest>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Pairing forced",
+        TournamentService::force_pairing(
+            db.get_ref(),
+            id.into_inner(),
+            payload.white_player_id,
+            payload.black_player_id,
+        )
+        .await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/round/forfeit",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = ForfeitRequest,
+    responses(
+        (status = 200, description = "Forfeit recorded", body = TournamentDisplay),
+        (status = 400, description = "Player is not paired or the game was already forfeited", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament or player not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/round/forfeit")]
+pub async fn record_forfeit(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<ForfeitRequest>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Forfeit recorded",
+        TournamentService::record_forfeit(db.get_ref(), id.into_inner(), payload.winner_id).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/byes",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = ByeRequest,
+    responses(
+        (status = 200, description = "Bye requested", body = TournamentDisplay),
+        (status = 400, description = "Round already paired or bye already requested", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found or not entered", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/byes")]
+pub async fn request_bye(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<ByeRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    changed_response(
+        &cache,
+        "Bye requested",
+        TournamentService::request_bye(db.get_ref(), id.into_inner(), player.id, payload.round).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/withdraw",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Withdrawn from the rounds not yet paired", body = TournamentDisplay),
+        (status = 400, description = "Tournament not in progress or already withdrawn", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found or not entered", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/withdraw")]
+pub async fn withdraw_player(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    changed_response(
+        &cache,
+        "Withdrawn",
+        TournamentService::withdraw(db.get_ref(), id.into_inner(), player.id).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/reenter",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Back in for the next pairing", body = TournamentDisplay),
+        (status = 400, description = "Not withdrawn, or the tournament does not allow re-entry", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found or not entered", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/reenter")]
+pub async fn reenter_player(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    changed_response(
+        &cache,
+        "Re-entered",
+        TournamentService::reenter(db.get_ref(), id.into_inner(), player.id).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/register",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Registered for the tournament", body = TournamentDisplay),
+        (status = 400, description = "Registration closed or already registered", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/register")]
+pub async fn register_player(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    changed_response(
+        &cache,
+        "Registered",
+        TournamentService::register(db.get_ref(), id.into_inner(), player.id).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/registrations/import",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = ImportRegistrationsRequest,
+    responses(
+        (status = 200, description = "Participants registered, with the rows that were rejected", body = RegistrationImportDisplay),
+        (status = 400, description = "Registration closed, CSV without a name column or too many rows", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/registrations/import")]
+pub async fn import_registrations(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<ImportRegistrationsRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    let id = id.into_inner();
+    let imported = TournamentService::import_registrations(db.get_ref(), id, &payload.csv)
+        .await
+        .and_then(|(model, report)| Ok((tournaments_service::display(&model)?, report)));
+    if imported.is_ok() {
+        cache.tournament_changed(id).await;
+    }
+    match imported {
+        Ok((tournament, report)) => HttpResponse::Ok().json(json!({
+            "message": "Registrations imported",
+            "data": { "tournament": tournament, "report": report }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/tournaments/{id}/prizes",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    request_body = SetPrizesRequest,
+    responses(
+        (status = 200, description = "Prize structure replaced", body = TournamentDisplay),
+        (status = 400, description = "Invalid prizes or tournament already over", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[put("/{id}/prizes")]
+pub async fn set_prizes(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+    payload: Json<SetPrizesRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Prizes updated",
+        TournamentService::set_prizes(db.get_ref(), id.into_inner(), payload.into_inner().prizes).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/{id}/finish",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Tournament finished and prizes awarded", body = TournamentDisplay),
+        (status = 400, description = "Tournament is not in progress", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/{id}/finish")]
+pub async fn finish_tournament(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    changed_response(
+        &cache,
+        "Tournament finished",
+        TournamentService::finish(db.get_ref(), id.into_inner()).await,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tournaments/{id}/standings",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Standings with tiebreaks and prizes", body = Vec<StandingDisplay>),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[get("/{id}/standings")]
+pub async fn get_standings(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    if let Err(err) = current_player(db.get_ref(), &req).await {
+        return err.error_response();
+    }
+
+    let id = id.into_inner();
+    let key = CacheKey::Standings(id);
+    let standings = match cache.get::<Vec<StandingDisplay>>(&key).await {
+        Some(standings) => standings,
+        None => match TournamentService::get(db.get_ref(), id)
+            .await
+            .and_then(|m| tournaments_service::standings_display(&m))
+        {
+            Ok(standings) => {
+                cache.put(&key, &standings).await;
+                standings
+            }
+            Err(err) => return err.error_response(),
+        },
+    };
+
+    HttpResponse::Ok().json(json!({
+        "message": "Standings found",
+        "data": { "standings": standings }
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tournaments/{id}/trf",
+    params(
+        ("id" = String, Path, description = "Tournament ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "FIDE TRF16 report of the tournament, for submission to the rating officer", content_type = "text/plain", body = String),
+        (status = 400, description = "Arena tournaments cannot be rated", body = InvalidCredentialsResponse),
+        (status = 403, description = "Arbiter role required", body = InvalidCredentialsResponse),
+        (status = 404, description = "Tournament not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[get("/{id}/trf")]
+pub async fn export_trf(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    if let Err(err) = require_role(db.get_ref(), &req, Role::Arbiter).await {
+        return err.error_response();
+    }
+
+    match TournamentService::trf_report(db.get_ref(), id.into_inner()).await {
+        Ok((model, report)) => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.trf\"", model.id)))
+            .body(report),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tournaments/trf/validate",
+    request_body = ValidateTrfRequest,
+    responses(
+        (status = 200, description = "Report read; lists round data that doesn't agree", body = TrfValidationDisplay),
+        (status = 400, description = "Report is not valid TRF16", body = InvalidCredentialsResponse),
+        (status = 401, description = "Unauthorized", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Tournaments"
+)]
+#[post("/trf/validate")]
+pub async fn validate_trf(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<ValidateTrfRequest>,
+) -> HttpResponse {
+    if let Err(err) = current_player(db.get_ref(), &req).await {
+        return err.error_response();
+    }
+    if let Err(errors) = payload.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    match tournaments_service::validate_trf(&payload.report) {
+        Ok(validation) => HttpResponse::Ok().json(json!({
+            "message": "Report validated",
+            "data": { "validation": validation }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
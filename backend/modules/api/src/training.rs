@@ -0,0 +1,153 @@
+use actix_web::{
+    HttpRequest, HttpResponse, get, post,
+    web::{self, Json, Path, Query},
+};
+use dto::leaderboards::LeaderboardQuery;
+use dto::training::{StartTrainingRequest, SubmitTrainingRequest, TrainingDrill};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::training::TrainingService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    post,
+    path = "/v1/training/sessions",
+    request_body = StartTrainingRequest,
+    responses(
+        (status = 201, description = "A new session of random exercises; answers are due within ten minutes", body = TrainingSessionDisplay),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Training"
+)]
+#[post("/sessions")]
+pub async fn start_training(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<StartTrainingRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TrainingService::start(db.get_ref(), player.id, payload.drill).await {
+        Ok(session) => HttpResponse::Created().json(json!({
+            "message": "Training session started",
+            "data": { "session": session }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/training/sessions/{id}/answers",
+    params(
+        ("id" = String, Path, description = "Training session ID in UUID format", format = "uuid")
+    ),
+    request_body = SubmitTrainingRequest,
+    responses(
+        (status = 200, description = "Each answer checked, with the time taken since the session started", body = TrainingResultDisplay),
+        (status = 400, description = "Wrong number of answers, already submitted or expired", body = InvalidCredentialsResponse),
+        (status = 404, description = "Training session not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Training"
+)]
+#[post("/sessions/{id}/answers")]
+pub async fn submit_training(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    payload: Json<SubmitTrainingRequest>,
+) -> HttpResponse {
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TrainingService::submit(db.get_ref(), player.id, id.into_inner(), payload.into_inner().answers).await {
+        Ok(result) => HttpResponse::Ok().json(json!({
+            "message": "Answers checked",
+            "data": { "result": result }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/training/stats",
+    responses(
+        (status = 200, description = "The caller's accuracy, time per exercise and best score on each drill", body = Vec<DrillStats>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Training"
+)]
+#[get("/stats")]
+pub async fn get_training_stats(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match TrainingService::stats(db.get_ref(), player.id).await {
+        Ok(stats) => HttpResponse::Ok().json(json!({
+            "message": "Training stats found",
+            "data": { "drills": stats }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/training/leaderboards/{drill}",
+    params(
+        ("drill" = TrainingDrill, Path, description = "find_square, name_square, square_color or piece_vision"),
+        ("limit" = Option<u64>, Query, description = "Number of entries to return (max 100)")
+    ),
+    responses(
+        (status = 200, description = "Best score of each player: most correct answers, then fastest", body = Vec<TrainingLeaderboardEntry>)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Training"
+)]
+#[get("/leaderboards/{drill}")]
+pub async fn get_training_leaderboard(
+    drill: Path<TrainingDrill>,
+    query: Query<LeaderboardQuery>,
+    db: web::Data<DatabaseConnection>,
+) -> HttpResponse {
+    let drill = drill.into_inner();
+    let limit = query.limit.unwrap_or(50);
+
+    match TrainingService::leaderboard(db.get_ref(), drill, limit).await {
+        Ok(entries) => HttpResponse::Ok().json(json!({
+            "message": "Leaderboard found",
+            "data": {
+                "drill": drill,
+                "entries": entries
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
@@ -0,0 +1,155 @@
+use actix_web::{
+    HttpRequest, HttpResponse, delete, get, post,
+    web::{self, Json, Path, Query},
+};
+use dto::webhooks::{CreateWebhookRequest, WebhookDeliveriesQuery, WebhookDeliveryDisplay, WebhookDisplay};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use service::webhooks::WebhookService;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::guard::current_player;
+
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook registered; the secret is not shown again", body = WebhookDisplay),
+        (status = 400, description = "Invalid URL or secret, or too many webhooks", body = InvalidCredentialsResponse),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+#[post("")]
+pub async fn create_webhook(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    payload: Json<CreateWebhookRequest>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+    if let Err(errors) = payload.0.validate() {
+        return ApiError::ValidationError(errors).error_response();
+    }
+
+    match WebhookService::create(db.get_ref(), player.id, payload.into_inner()).await {
+        Ok(webhook) => HttpResponse::Ok().json(json!({
+            "message": "Webhook registered",
+            "data": WebhookDisplay::from(webhook)
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks",
+    responses(
+        (status = 200, description = "The caller's webhooks", body = Vec<WebhookDisplay>),
+        (status = 401, description = "Authentication required", body = InvalidCredentialsResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+#[get("")]
+pub async fn list_webhooks(req: HttpRequest, db: web::Data<DatabaseConnection>) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match WebhookService::list(db.get_ref(), player.id).await {
+        Ok(webhooks) => HttpResponse::Ok().json(json!({
+            "message": "Webhooks found",
+            "data": {
+                "webhooks": webhooks.into_iter().map(WebhookDisplay::from).collect::<Vec<_>>()
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/webhooks/{id}",
+    params(
+        ("id" = String, Path, description = "Webhook ID in UUID format", format = "uuid")
+    ),
+    responses(
+        (status = 200, description = "Webhook and its delivery log removed"),
+        (status = 404, description = "Webhook not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+#[delete("/{id}")]
+pub async fn delete_webhook(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    match WebhookService::delete(db.get_ref(), player.id, id.into_inner()).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "message": "Webhook removed",
+            "data": {}
+        })),
+        Err(err) => err.error_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks/{id}/deliveries",
+    params(
+        ("id" = String, Path, description = "Webhook ID in UUID format", format = "uuid"),
+        ("limit" = Option<u64>, Query, description = "Number of deliveries to return (default 50, at most 200)")
+    ),
+    responses(
+        (status = 200, description = "Latest deliveries with their attempts and outcome, newest first", body = Vec<WebhookDeliveryDisplay>),
+        (status = 404, description = "Webhook not found", body = NotFoundResponse)
+    ),
+    security(
+        ("jwt_auth" = [])
+    ),
+    tag = "Webhooks"
+)]
+#[get("/{id}/deliveries")]
+pub async fn list_webhook_deliveries(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    id: Path<Uuid>,
+    query: Query<WebhookDeliveriesQuery>,
+) -> HttpResponse {
+    let player = match current_player(db.get_ref(), &req).await {
+        Ok(player) => player,
+        Err(err) => return err.error_response(),
+    };
+
+    let limit = query.limit.unwrap_or(50);
+    match WebhookService::deliveries(db.get_ref(), player.id, id.into_inner(), limit).await {
+        Ok(deliveries) => HttpResponse::Ok().json(json!({
+            "message": "Webhook deliveries found",
+            "data": {
+                "deliveries": deliveries.into_iter().map(WebhookDeliveryDisplay::from).collect::<Vec<_>>()
+            }
+        })),
+        Err(err) => err.error_response(),
+    }
+}
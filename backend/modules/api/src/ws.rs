@@ -6,16 +6,37 @@ use std::collections::{HashMap, HashSet};
 use std::env;
 use security::jwt::Claims;
 use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
-use actix_web::error::ErrorUnauthorized;
+use actix_web::error::{ErrorInternalServerError, ErrorUnauthorized};
+use dto::preferences::PreferencesDisplay;
+use sea_orm::DatabaseConnection;
 use serde_json::{Value, json};
+use service::linking::Fingerprinter;
+use service::moderation::ModerationService;
+use service::preferences::PreferenceService;
+use utoipa::ToSchema;
+
+use crate::guard::record_fingerprints;
+use crate::guests::GuestSessions;
+
+/// Version sent with every message
+pub const PROTOCOL_VERSION: &str = "1.0";
 
 /// Core WebSocket message types
-#[derive(Message, Serialize, Clone, Debug, PartialEq)]
+#[derive(Message, Serialize, Clone, Debug, PartialEq, ToSchema)]
 #[rtype(result = "()")]
 #[serde(tag = "type", content = "payload")]
 pub enum WsMessage {
+    /// First message of every connection: who it is for and the settings
+    /// the server plays their moves by, such as auto-queen
+    Hello { player_id: String, preferences: PreferencesDisplay },
+    /// A guest playing this game registered; their seat now belongs to
+    /// `username`
+    GuestRegistered { guest_id: String, username: String },
+    /// A move was played
     Move { from: String, to: String, san: String, fen: String },
+    /// Remaining time of each side
     Clock { white: u32, black: u32 },
+    /// The game is over
     End   { result: String, final_fen: String },
     Error { code: u16, message: String },
 }
@@ -97,6 +118,8 @@ pub struct WsSession {
     pub game_id: String,
     pub lobby: Addr<LobbyState>,
     hb: std::time::Instant,
+    /// Sent once the connection is up
+    hello: Option<WsMessage>,
 }
 
 impl WsSession {
@@ -127,6 +150,9 @@ impl Actor for WsSession {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         self.hb(ctx);
+        if let Some(hello) = self.hello.take() {
+            ctx.text(versioned(&hello));
+        }
         let addr = ctx.address().recipient();
         self.lobby.do_send(Connect { game_id: self.game_id.clone(), addr });
     }
@@ -162,25 +188,31 @@ impl Handler<WsMessage> for WsSession {
     type Result = ();
 
     fn handle(&mut self, msg: WsMessage, ctx: &mut ws::WebsocketContext<Self>) {
-        // Serialize message and inject version field
-        let mut val = serde_json::to_value(&msg).unwrap();
-        if let Value::Object(ref mut m) = val {
-            m.insert("version".into(), json!("1.0"));
-        }
-        let text = serde_json::to_string(&val).unwrap();
-        ctx.text(text);
+        ctx.text(versioned(&msg));
     }
 }
 
+/// Serialize a message with the version field injected
+fn versioned(msg: &WsMessage) -> String {
+    let mut val = serde_json::to_value(msg).unwrap();
+    if let Value::Object(ref mut m) = val {
+        m.insert("version".into(), json!(PROTOCOL_VERSION));
+    }
+    serde_json::to_string(&val).unwrap()
+}
+
 /// WebSocket route handler with auth
 pub async fn ws_route(
     req: HttpRequest,
     stream: web::Payload,
     lobby: web::Data<Addr<LobbyState>>,
+    db: web::Data<DatabaseConnection>,
+    guests: web::Data<GuestSessions>,
+    fingerprinter: Option<web::Data<Fingerprinter>>,
 ) -> Result<HttpResponse, Error> {
     // Validate JWT token from header
     let auth_header = req.headers().get("Authorization").and_then(|h| h.to_str().ok());
-    if let Some(header) = auth_header {
+    let claims = if let Some(header) = auth_header {
         if !header.starts_with("Bearer ") {
             return Err(ErrorUnauthorized("Invalid authorization token format"));
         }
@@ -188,14 +220,41 @@ pub async fn ws_route(
         let secret = env::var("JWT_SECRET_KEY").unwrap_or_else(|_| "development_secret_key".to_string());
         let validation = Validation::new(Algorithm::HS256);
         decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &validation)
-            .map_err(|_| ErrorUnauthorized("Invalid or expired token"))?;
+            .map_err(|_| ErrorUnauthorized("Invalid or expired token"))?
+            .claims
     } else {
         return Err(ErrorUnauthorized("Missing authorization token"));
-    }
+    };
+
+    // The handshake carries the player's settings, so the client shows the
+    // same behaviour the server applies to their moves. Guests have no
+    // account and play with the defaults.
+    let (player_id, preferences) = if claims.guest {
+        let guest_id = uuid::Uuid::parse_str(&claims.sub).map_err(|_| ErrorUnauthorized("Invalid or expired token"))?;
+        if !guests.is_active(guest_id) {
+            return Err(ErrorUnauthorized("Guest session has ended"));
+        }
+        (guest_id, PreferencesDisplay::default())
+    } else {
+        let player = ModerationService::find_player_by_username(db.get_ref(), &claims.username)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .ok_or_else(|| ErrorUnauthorized("Unknown account"))?;
+        record_fingerprints(db.get_ref(), fingerprinter.as_ref(), &req, player.id).await;
+        let preferences = PreferenceService::get(db.get_ref(), player.id)
+            .await
+            .map_err(ErrorInternalServerError)?;
+        (player.id, preferences)
+    };
 
     let game_id = req.match_info().get("game_id").unwrap_or("").to_string();
     ws::start(
-        WsSession { game_id, lobby: lobby.get_ref().clone(), hb: std::time::Instant::now() },
+        WsSession {
+            game_id,
+            lobby: lobby.get_ref().clone(),
+            hb: std::time::Instant::now(),
+            hello: Some(WsMessage::Hello { player_id: player_id.to_string(), preferences }),
+        },
         &req,
         stream,
     )
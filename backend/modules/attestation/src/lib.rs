@@ -0,0 +1,233 @@
+//! Attestations of finished rated games on StarkNet.
+//!
+//! Each game's players, result and a hash of its PGN are written to an
+//! attestation contract, so anyone can check a result against the chain.
+//! The contract is reached through [`AttestationChain`], implemented over
+//! JSON-RPC by [`rpc::StarknetRpcClient`] and in memory by [`mock::MockChain`].
+//!
+//! The contract is expected to expose
+//! `attest(game_id, white, black, result, pgn_hash)` and
+//! `get_attestation(game_id) -> (white, black, result, pgn_hash)`, all felts,
+//! with zeros for games never attested. Ids are UUIDs read as 128-bit numbers;
+//! results are 1 for a white win, 2 for a black win and 3 for a draw.
+
+use async_trait::async_trait;
+use starknet_core::utils::starknet_keccak;
+use thiserror::Error;
+use uuid::Uuid;
+
+pub mod mock;
+pub mod rpc;
+
+pub use starknet_core::types::FieldElement;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    #[error("StarkNet node unreachable: {0}")]
+    Transport(String),
+    #[error("StarkNet RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("Account nonce is out of date")]
+    InvalidNonce,
+    #[error("Transaction rejected: {0}")]
+    Rejected(String),
+    #[error("Unexpected response from the StarkNet node: {0}")]
+    Malformed(String),
+}
+
+impl ChainError {
+    /// Whether the same transaction may succeed when sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ChainError::Transport(_) | ChainError::Rpc { .. } | ChainError::InvalidNonce)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttestedResult {
+    WhiteWins = 1,
+    BlackWins = 2,
+    Draw = 3,
+}
+
+impl AttestedResult {
+    pub fn code(self) -> i16 {
+        self as i16
+    }
+
+    pub fn from_code(code: i16) -> Option<Self> {
+        match code {
+            1 => Some(AttestedResult::WhiteWins),
+            2 => Some(AttestedResult::BlackWins),
+            3 => Some(AttestedResult::Draw),
+            _ => None,
+        }
+    }
+}
+
+/// What the contract records about one game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    pub game_id: Uuid,
+    pub white: Uuid,
+    pub black: Uuid,
+    pub result: AttestedResult,
+    pub pgn_hash: FieldElement,
+}
+
+impl Attestation {
+    /// Arguments of the contract's `attest` entry point.
+    pub fn calldata(&self) -> Vec<FieldElement> {
+        vec![
+            uuid_to_felt(self.game_id),
+            uuid_to_felt(self.white),
+            uuid_to_felt(self.black),
+            FieldElement::from(self.result as u8),
+            self.pgn_hash,
+        ]
+    }
+
+    /// Read the output of `get_attestation`; `None` for unknown games.
+    pub fn from_call_result(game_id: Uuid, output: &[FieldElement]) -> Result<Option<Self>, ChainError> {
+        let [white, black, result, pgn_hash] = output else {
+            return Err(ChainError::Malformed(format!("expected 4 values, got {}", output.len())));
+        };
+        if *result == FieldElement::ZERO {
+            return Ok(None);
+        }
+        let result = u8::try_from(*result)
+            .ok()
+            .and_then(|code| AttestedResult::from_code(code.into()))
+            .ok_or_else(|| ChainError::Malformed(format!("unknown result {:#x}", result)))?;
+        Ok(Some(Attestation {
+            game_id,
+            white: felt_to_uuid(*white)?,
+            black: felt_to_uuid(*black)?,
+            result,
+            pgn_hash: *pgn_hash,
+        }))
+    }
+}
+
+/// Hash of a game's PGN as stored on chain.
+pub fn pgn_hash(pgn: &str) -> FieldElement {
+    starknet_keccak(pgn.as_bytes())
+}
+
+pub fn uuid_to_felt(id: Uuid) -> FieldElement {
+    FieldElement::from(id.as_u128())
+}
+
+pub fn felt_to_uuid(felt: FieldElement) -> Result<Uuid, ChainError> {
+    u128::try_from(felt)
+        .map(Uuid::from_u128)
+        .map_err(|_| ChainError::Malformed(format!("{:#x} is not a game or player id", felt)))
+}
+
+/// Where a sent transaction stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Received but not yet in a block
+    Pending,
+    Accepted,
+    /// Dropped or reverted, with the reason
+    Rejected(String),
+}
+
+/// The attestation contract, as seen from the account that submits results.
+#[async_trait]
+pub trait AttestationChain: Send + Sync {
+    /// Nonce the account's next transaction must carry.
+    async fn nonce(&self) -> Result<FieldElement, ChainError>;
+
+    /// Send an `attest` transaction, returning its hash.
+    async fn submit(&self, attestation: &Attestation, nonce: FieldElement) -> Result<FieldElement, ChainError>;
+
+    async fn transaction_status(&self, tx_hash: FieldElement) -> Result<TxStatus, ChainError>;
+
+    /// The attestation stored for a game, if any.
+    async fn attestation(&self, game_id: Uuid) -> Result<Option<Attestation>, ChainError>;
+}
+
+/// Local copy of the account nonce, so consecutive transactions need not
+/// wait for each other. It is read from the chain again after any failure,
+/// when it is unknown whether the failed transaction used it up.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: Option<FieldElement>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nonce for the next transaction.
+    pub async fn next(&mut self, chain: &dyn AttestationChain) -> Result<FieldElement, ChainError> {
+        match self.next {
+            Some(nonce) => Ok(nonce),
+            None => {
+                let nonce = chain.nonce().await?;
+                self.next = Some(nonce);
+                Ok(nonce)
+            }
+        }
+    }
+
+    /// The transaction with the current nonce was accepted by the node.
+    pub fn advance(&mut self) {
+        self.next = self.next.map(|nonce| nonce + FieldElement::ONE);
+    }
+
+    /// Forget the nonce after a failure.
+    pub fn reset(&mut self) {
+        self.next = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockChain;
+
+    fn attestation() -> Attestation {
+        Attestation {
+            game_id: Uuid::new_v4(),
+            white: Uuid::new_v4(),
+            black: Uuid::new_v4(),
+            result: AttestedResult::BlackWins,
+            pgn_hash: pgn_hash("1. f3 e5 2. g4 Qh4# 0-1"),
+        }
+    }
+
+    #[test]
+    fn attestations_round_trip_through_felts() {
+        let attestation = attestation();
+        let calldata = attestation.calldata();
+        assert_eq!(calldata.len(), 5);
+        assert_eq!(calldata[3], FieldElement::from(2u8));
+
+        let stored = Attestation::from_call_result(attestation.game_id, &calldata[1..]).unwrap();
+        assert_eq!(stored, Some(attestation.clone()));
+        let empty = [FieldElement::ZERO; 4];
+        assert_eq!(Attestation::from_call_result(attestation.game_id, &empty).unwrap(), None);
+        assert!(Attestation::from_call_result(attestation.game_id, &calldata[..2]).is_err());
+    }
+
+    #[tokio::test]
+    async fn nonces_advance_locally_and_resync_after_failures() {
+        let chain = MockChain::new();
+        let mut nonces = NonceManager::new();
+
+        let first = nonces.next(&chain).await.unwrap();
+        chain.submit(&attestation(), first).await.unwrap();
+        nonces.advance();
+        let second = nonces.next(&chain).await.unwrap();
+        assert_eq!(second, first + FieldElement::ONE);
+
+        // Someone else used the nonce: the stale one is refused until resynced
+        chain.submit(&attestation(), second).await.unwrap();
+        assert_eq!(chain.submit(&attestation(), second).await, Err(ChainError::InvalidNonce));
+        nonces.reset();
+        assert_eq!(nonces.next(&chain).await.unwrap(), second + FieldElement::ONE);
+    }
+}
@@ -0,0 +1,74 @@
+//! In-memory stand-in for the attestation contract.
+
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{Attestation, AttestationChain, ChainError, FieldElement, TxStatus};
+
+#[derive(Default)]
+struct State {
+    nonce: u64,
+    attestations: HashMap<Uuid, Attestation>,
+    transactions: HashMap<FieldElement, TxStatus>,
+    failures: VecDeque<ChainError>,
+}
+
+/// Contract that accepts every transaction at once, unless told to fail.
+#[derive(Default)]
+pub struct MockChain {
+    state: Mutex<State>,
+}
+
+impl MockChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next submissions fail with `errors`, in order.
+    pub fn fail_next(&self, errors: impl IntoIterator<Item = ChainError>) {
+        self.state.lock().unwrap().failures.extend(errors);
+    }
+
+    /// Transactions sent successfully so far.
+    pub fn submitted(&self) -> u64 {
+        self.state.lock().unwrap().nonce
+    }
+}
+
+#[async_trait]
+impl AttestationChain for MockChain {
+    async fn nonce(&self) -> Result<FieldElement, ChainError> {
+        Ok(FieldElement::from(self.state.lock().unwrap().nonce))
+    }
+
+    async fn submit(&self, attestation: &Attestation, nonce: FieldElement) -> Result<FieldElement, ChainError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(error) = state.failures.pop_front() {
+            return Err(error);
+        }
+        if nonce != FieldElement::from(state.nonce) {
+            return Err(ChainError::InvalidNonce);
+        }
+
+        state.nonce += 1;
+        let tx_hash = FieldElement::from(state.nonce) + FieldElement::from(0x1000u64);
+        state.transactions.insert(tx_hash, TxStatus::Accepted);
+        state.attestations.insert(attestation.game_id, attestation.clone());
+        Ok(tx_hash)
+    }
+
+    async fn transaction_status(&self, tx_hash: FieldElement) -> Result<TxStatus, ChainError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .transactions
+            .get(&tx_hash)
+            .cloned()
+            .unwrap_or_else(|| TxStatus::Rejected("unknown transaction".to_string())))
+    }
+
+    async fn attestation(&self, game_id: Uuid) -> Result<Option<Attestation>, ChainError> {
+        Ok(self.state.lock().unwrap().attestations.get(&game_id).cloned())
+    }
+}
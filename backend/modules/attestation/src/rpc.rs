@@ -0,0 +1,283 @@
+//! [`AttestationChain`] over the StarkNet JSON-RPC API (spec 0.6).
+//!
+//! Transactions are v1 invokes from an account contract with the Cairo 1
+//! `__execute__` calldata layout, signed with the account's Stark key.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use starknet_core::crypto::{compute_hash_on_elements, ecdsa_sign};
+use starknet_core::utils::{cairo_short_string_to_felt, get_selector_from_name};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{Attestation, AttestationChain, ChainError, FieldElement, TxStatus};
+
+/// `INVALID_TRANSACTION_NONCE` in the RPC spec.
+const INVALID_NONCE_CODE: i64 = 52;
+
+#[derive(Debug, Clone)]
+pub struct StarknetConfig {
+    pub rpc_url: String,
+    /// Account contract sending the transactions
+    pub account_address: FieldElement,
+    pub private_key: FieldElement,
+    pub contract_address: FieldElement,
+    /// e.g. `SN_MAIN` or `SN_SEPOLIA`
+    pub chain_id: String,
+    /// Highest fee, in wei, one transaction may pay
+    pub max_fee: FieldElement,
+    pub timeout: Duration,
+}
+
+pub struct StarknetRpcClient {
+    config: StarknetConfig,
+    chain_id: FieldElement,
+    client: reqwest::Client,
+    request_id: AtomicU64,
+}
+
+impl StarknetRpcClient {
+    pub fn new(config: StarknetConfig) -> Result<Self, ChainError> {
+        let chain_id = cairo_short_string_to_felt(&config.chain_id)
+            .map_err(|e| ChainError::Malformed(format!("invalid chain id '{}': {}", config.chain_id, e)))?;
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
+        Ok(Self {
+            config,
+            chain_id,
+            client,
+            request_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Calldata of the account's `__execute__` for a single `attest` call.
+    pub fn execute_calldata(&self, attestation: &Attestation) -> Vec<FieldElement> {
+        let args = attestation.calldata();
+        let mut calldata = vec![
+            FieldElement::ONE,
+            self.config.contract_address,
+            selector("attest"),
+            FieldElement::from(args.len()),
+        ];
+        calldata.extend(args);
+        calldata
+    }
+
+    /// Hash an account signs to send `calldata` as a v1 invoke.
+    pub fn invoke_hash(&self, calldata: &[FieldElement], nonce: FieldElement) -> FieldElement {
+        compute_hash_on_elements(&[
+            short_string("invoke"),
+            FieldElement::ONE,
+            self.config.account_address,
+            FieldElement::ZERO,
+            compute_hash_on_elements(calldata),
+            self.config.max_fee,
+            self.chain_id,
+            nonce,
+        ])
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, ChainError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.request_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .client
+            .post(&self.config.rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ChainError::Transport(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| ChainError::Malformed(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            let code = error["code"].as_i64().unwrap_or_default();
+            if code == INVALID_NONCE_CODE {
+                return Err(ChainError::InvalidNonce);
+            }
+            return Err(ChainError::Rpc {
+                code,
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| ChainError::Malformed(format!("{} returned no result", method)))
+    }
+}
+
+#[async_trait]
+impl AttestationChain for StarknetRpcClient {
+    async fn nonce(&self) -> Result<FieldElement, ChainError> {
+        let result = self
+            .request(
+                "starknet_getNonce",
+                json!({"block_id": "pending", "contract_address": hex(self.config.account_address)}),
+            )
+            .await?;
+        parse_felt(&result)
+    }
+
+    async fn submit(&self, attestation: &Attestation, nonce: FieldElement) -> Result<FieldElement, ChainError> {
+        let calldata = self.execute_calldata(attestation);
+        let hash = self.invoke_hash(&calldata, nonce);
+        let signature = ecdsa_sign(&self.config.private_key, &hash)
+            .map_err(|e| ChainError::Rejected(format!("could not sign transaction: {}", e)))?;
+
+        let result = self
+            .request(
+                "starknet_addInvokeTransaction",
+                json!({"invoke_transaction": {
+                    "type": "INVOKE",
+                    "version": "0x1",
+                    "sender_address": hex(self.config.account_address),
+                    "calldata": calldata.into_iter().map(hex).collect::<Vec<_>>(),
+                    "max_fee": hex(self.config.max_fee),
+                    "signature": [hex(signature.r), hex(signature.s)],
+                    "nonce": hex(nonce),
+                }}),
+            )
+            .await?;
+        parse_felt(&result["transaction_hash"])
+    }
+
+    async fn transaction_status(&self, tx_hash: FieldElement) -> Result<TxStatus, ChainError> {
+        let result = self
+            .request("starknet_getTransactionStatus", json!({"transaction_hash": hex(tx_hash)}))
+            .await?;
+        Ok(tx_status(&result))
+    }
+
+    async fn attestation(&self, game_id: Uuid) -> Result<Option<Attestation>, ChainError> {
+        let result = self
+            .request(
+                "starknet_call",
+                json!({
+                    "request": {
+                        "contract_address": hex(self.config.contract_address),
+                        "entry_point_selector": hex(selector("get_attestation")),
+                        "calldata": [hex(crate::uuid_to_felt(game_id))],
+                    },
+                    "block_id": "latest",
+                }),
+            )
+            .await?;
+        let output = result
+            .as_array()
+            .ok_or_else(|| ChainError::Malformed("call result is not a list".to_string()))?
+            .iter()
+            .map(parse_felt)
+            .collect::<Result<Vec<_>, _>>()?;
+        Attestation::from_call_result(game_id, &output)
+    }
+}
+
+fn tx_status(result: &Value) -> TxStatus {
+    match (result["finality_status"].as_str(), result["execution_status"].as_str()) {
+        (Some("REJECTED"), _) => TxStatus::Rejected("rejected by the sequencer".to_string()),
+        (_, Some("REVERTED")) => TxStatus::Rejected(
+            result["failure_reason"]
+                .as_str()
+                .unwrap_or("reverted")
+                .to_string(),
+        ),
+        (Some("ACCEPTED_ON_L2" | "ACCEPTED_ON_L1"), _) => TxStatus::Accepted,
+        _ => TxStatus::Pending,
+    }
+}
+
+fn selector(name: &str) -> FieldElement {
+    get_selector_from_name(name).expect("entry point names are ASCII")
+}
+
+fn short_string(value: &str) -> FieldElement {
+    cairo_short_string_to_felt(value).expect("short strings fit in a felt")
+}
+
+fn hex(felt: FieldElement) -> String {
+    format!("{:#x}", felt)
+}
+
+fn parse_felt(value: &Value) -> Result<FieldElement, ChainError> {
+    value
+        .as_str()
+        .and_then(|value| FieldElement::from_hex_be(value).ok())
+        .ok_or_else(|| ChainError::Malformed(format!("expected a felt, got {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AttestedResult;
+
+    fn client() -> StarknetRpcClient {
+        StarknetRpcClient::new(StarknetConfig {
+            rpc_url: "http://127.0.0.1:9".to_string(),
+            account_address: FieldElement::from(0xacc0u64),
+            private_key: FieldElement::from(0x1234u64),
+            contract_address: FieldElement::from(0xc0deu64),
+            chain_id: "SN_SEPOLIA".to_string(),
+            max_fee: FieldElement::from(1_000_000_000_000_000u64),
+            timeout: Duration::from_secs(1),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn attest_calls_are_wrapped_in_a_single_call_multicall() {
+        let attestation = Attestation {
+            game_id: Uuid::new_v4(),
+            white: Uuid::new_v4(),
+            black: Uuid::new_v4(),
+            result: AttestedResult::Draw,
+            pgn_hash: crate::pgn_hash("1. e4 e5 1/2-1/2"),
+        };
+        let calldata = client().execute_calldata(&attestation);
+        assert_eq!(calldata[..4], [
+            FieldElement::ONE,
+            FieldElement::from(0xc0deu64),
+            selector("attest"),
+            FieldElement::from(5u8),
+        ]);
+        assert_eq!(calldata[4..], attestation.calldata()[..]);
+
+        // The hash commits to the nonce
+        let client = client();
+        assert_ne!(
+            client.invoke_hash(&calldata, FieldElement::ZERO),
+            client.invoke_hash(&calldata, FieldElement::ONE)
+        );
+    }
+
+    #[test]
+    fn transaction_statuses_are_read_from_finality_and_execution() {
+        let status = |value: Value| tx_status(&value);
+        assert_eq!(status(json!({"finality_status": "RECEIVED"})), TxStatus::Pending);
+        assert_eq!(
+            status(json!({"finality_status": "ACCEPTED_ON_L2", "execution_status": "SUCCEEDED"})),
+            TxStatus::Accepted
+        );
+        assert!(matches!(
+            status(json!({"finality_status": "ACCEPTED_ON_L2", "execution_status": "REVERTED"})),
+            TxStatus::Rejected(_)
+        ));
+        assert!(matches!(status(json!({"finality_status": "REJECTED"})), TxStatus::Rejected(_)));
+    }
+
+    #[tokio::test]
+    async fn unreachable_nodes_are_retryable() {
+        let error = client().nonce().await.unwrap_err();
+        assert!(matches!(error, ChainError::Transport(_)));
+        assert!(error.is_retryable());
+    }
+}
@@ -0,0 +1,81 @@
+//! Move generation throughput: `cargo bench -p chess`.
+
+use chess::bitboard::attacks;
+use chess::bitboard::board::{Bitboard, Square};
+use chess::Referee;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shakmaty::{fen::Fen, CastlingMode, Chess, Position};
+
+/// Positions with their node counts at the benchmarked depth.
+const PERFT: [(&str, &str, u32, u64); 3] = [
+    ("start", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, 197_281),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97_862),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674_624),
+];
+
+fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .expect("valid FEN")
+        .into_position(CastlingMode::Standard)
+        .expect("legal position")
+}
+
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for (name, fen, depth, nodes) in PERFT {
+        let pos = position(fen);
+        assert_eq!(shakmaty::perft(&pos, depth), nodes);
+        group.throughput(Throughput::Elements(nodes));
+        group.bench_with_input(BenchmarkId::new(name, depth), &pos, |b, pos| {
+            b.iter(|| shakmaty::perft(black_box(pos), depth))
+        });
+    }
+    group.finish();
+}
+
+fn legal_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("legal_moves");
+    for (name, fen, _, _) in PERFT {
+        let referee = Referee::from_fen(fen).expect("valid FEN");
+        group.throughput(Throughput::Elements(referee.legal_moves().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &referee, |b, referee| {
+            b.iter(|| black_box(referee).legal_moves())
+        });
+    }
+    group.finish();
+}
+
+fn slider_attacks(c: &mut Criterion) {
+    attacks::init();
+    let occupied = Bitboard(u64::from(position(PERFT[1].1).board().occupied()));
+    let squares: Vec<Square> = (0..64).map(|value| Square { value }).collect();
+
+    let mut group = c.benchmark_group("attacks");
+    group.throughput(Throughput::Elements(64));
+    group.bench_function("rook", |b| {
+        b.iter(|| {
+            squares
+                .iter()
+                .fold(Bitboard::EMPTY, |all, &s| all | attacks::rook_attacks(s, black_box(occupied)))
+        })
+    });
+    group.bench_function("bishop", |b| {
+        b.iter(|| {
+            squares
+                .iter()
+                .fold(Bitboard::EMPTY, |all, &s| all | attacks::bishop_attacks(s, black_box(occupied)))
+        })
+    });
+    group.bench_function("queen", |b| {
+        b.iter(|| {
+            squares
+                .iter()
+                .fold(Bitboard::EMPTY, |all, &s| all | attacks::queen_attacks(s, black_box(occupied)))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, perft, legal_moves, slider_attacks);
+criterion_main!(benches);
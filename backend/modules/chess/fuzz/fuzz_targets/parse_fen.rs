@@ -0,0 +1,29 @@
+//! Any FEN must be read as a position or rejected. A position read must
+//! write back out as itself, and the incrementally updated hash of every
+//! move from it must match the hash of the position reached.
+
+#![no_main]
+
+use chess::Referee;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(fen) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(referee) = Referee::from_fen(fen) else {
+        return;
+    };
+    let reread = Referee::from_fen(&referee.fen()).expect("written FEN reads back");
+    assert_eq!(reread.fen(), referee.fen());
+    assert_eq!(reread.zobrist(), referee.zobrist());
+
+    for uci in referee.legal_moves() {
+        let mut next = referee.clone();
+        next.play_uci(&uci).expect("legal move plays");
+        let reached = Referee::from_fen(&next.fen()).expect("written FEN reads back");
+        assert_eq!(next.zobrist(), reached.zobrist(), "hash after {}", uci);
+        next.take_back().expect("move takes back");
+        assert_eq!(next.zobrist(), referee.zobrist());
+    }
+});
@@ -0,0 +1,22 @@
+//! Any upload must come back from the PGN parser as a game or an error,
+//! without panicking or hanging the import.
+
+#![no_main]
+
+use chess::{parse_pgn, validate_game, Referee};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(pgn) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(parsed) = parse_pgn(pgn) else {
+        return;
+    };
+    let Ok(game) = validate_game(&parsed) else {
+        return;
+    };
+    assert_eq!(game.moves.len(), game.ply_count);
+    // Imported games are stored by their final position
+    Referee::from_fen(&game.final_fen).expect("final position reads back");
+});
@@ -0,0 +1,435 @@
+//! Game Annotation Module
+//!
+//! Comments, evaluation markers and alternative lines attached to the moves
+//! of a game, stored as a tree indexed by ply, and PGN export with the
+//! annotations merged into the movetext.
+
+use serde::{Deserialize, Serialize};
+use shakmaty::{san::San, Chess, Position};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::pgn::PgnHeaders;
+
+/// Longest comment accepted on a single move
+pub const MAX_COMMENT_LEN: usize = 2000;
+
+/// Deepest nesting of variations inside variations
+pub const MAX_VARIATION_DEPTH: usize = 8;
+
+/// Width movetext is wrapped at on export
+const PGN_LINE_WIDTH: usize = 79;
+
+/// Errors found when checking annotations against the moves of a game
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AnnotationError {
+    #[error("Ply {0} is not a move of this game")]
+    PlyOutOfRange(u32),
+
+    #[error("Illegal move in variation at ply {ply}: '{move_text}'")]
+    IllegalMove { ply: u32, move_text: String },
+
+    #[error("Empty variation at ply {0}")]
+    EmptyVariation(u32),
+
+    #[error("Comment at ply {0} is longer than {MAX_COMMENT_LEN} characters")]
+    CommentTooLong(u32),
+
+    #[error("Variations are nested more than {MAX_VARIATION_DEPTH} deep")]
+    TooDeep,
+
+    #[error("Illegal move in game at ply {0}")]
+    InvalidGame(u32),
+}
+
+/// Numeric Annotation Glyphs for moves and positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Nag {
+    /// `!`
+    Good,
+    /// `?`
+    Mistake,
+    /// `!!`
+    Brilliant,
+    /// `??`
+    Blunder,
+    /// `!?`
+    Interesting,
+    /// `?!`
+    Dubious,
+    /// `=`
+    Equal,
+    /// `∞`
+    Unclear,
+    /// `⩲`
+    WhiteSlightlyBetter,
+    /// `⩱`
+    BlackSlightlyBetter,
+    /// `±`
+    WhiteBetter,
+    /// `∓`
+    BlackBetter,
+    /// `+-`
+    WhiteWinning,
+    /// `-+`
+    BlackWinning,
+}
+
+impl Nag {
+    /// Code written as `$n` in PGN
+    pub fn code(&self) -> u8 {
+        match self {
+            Nag::Good => 1,
+            Nag::Mistake => 2,
+            Nag::Brilliant => 3,
+            Nag::Blunder => 4,
+            Nag::Interesting => 5,
+            Nag::Dubious => 6,
+            Nag::Equal => 10,
+            Nag::Unclear => 13,
+            Nag::WhiteSlightlyBetter => 14,
+            Nag::BlackSlightlyBetter => 15,
+            Nag::WhiteBetter => 16,
+            Nag::BlackBetter => 17,
+            Nag::WhiteWinning => 18,
+            Nag::BlackWinning => 19,
+        }
+    }
+}
+
+/// Annotations on a single move
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveNote {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nags: Vec<Nag>,
+    /// Lines that could have been played instead of this move
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<Vec<AnnotatedMove>>,
+}
+
+/// A move of a variation with its own annotations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnotatedMove {
+    pub san: String,
+    #[serde(default)]
+    pub note: MoveNote,
+}
+
+/// Annotations of a game keyed by ply, where ply 1 is White's first move
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnotationTree {
+    pub moves: BTreeMap<u32, MoveNote>,
+}
+
+impl MoveNote {
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.nags.is_empty() && self.variations.is_empty()
+    }
+
+    fn merge(&mut self, other: &MoveNote) {
+        self.comment = match (self.comment.take(), &other.comment) {
+            (Some(mine), Some(theirs)) => Some(format!("{} {}", mine, theirs)),
+            (mine, theirs) => mine.or_else(|| theirs.clone()),
+        };
+        for nag in &other.nags {
+            if !self.nags.contains(nag) {
+                self.nags.push(*nag);
+            }
+        }
+        for variation in &other.variations {
+            if !self.variations.contains(variation) {
+                self.variations.push(variation.clone());
+            }
+        }
+    }
+
+    fn prefix_comments(&mut self, prefix: &str) {
+        if let Some(comment) = &mut self.comment {
+            *comment = format!("{}{}", prefix, comment);
+        }
+        for variation in &mut self.variations {
+            for mv in variation {
+                mv.note.prefix_comments(prefix);
+            }
+        }
+    }
+
+    fn validate(&self, before: &Chess, ply: u32, depth: usize) -> Result<(), AnnotationError> {
+        if depth > MAX_VARIATION_DEPTH {
+            return Err(AnnotationError::TooDeep);
+        }
+        if self.comment.as_ref().is_some_and(|c| c.chars().count() > MAX_COMMENT_LEN) {
+            return Err(AnnotationError::CommentTooLong(ply));
+        }
+
+        for variation in &self.variations {
+            if variation.is_empty() {
+                return Err(AnnotationError::EmptyVariation(ply));
+            }
+
+            let mut position = before.clone();
+            for (offset, mv) in variation.iter().enumerate() {
+                let ply = ply + offset as u32;
+                mv.note.validate(&position, ply, depth + 1)?;
+                position = play_san(&position, &mv.san).ok_or_else(|| AnnotationError::IllegalMove {
+                    ply,
+                    move_text: mv.san.clone(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AnnotationTree {
+    pub fn is_empty(&self) -> bool {
+        self.moves.values().all(MoveNote::is_empty)
+    }
+
+    /// Check that every annotated ply exists in `moves` and that every
+    /// variation is legal from the position it branches off.
+    pub fn validate(&self, moves: &[String]) -> Result<(), AnnotationError> {
+        if let Some(ply) = self.moves.keys().find(|ply| **ply == 0 || **ply as usize > moves.len()) {
+            return Err(AnnotationError::PlyOutOfRange(*ply));
+        }
+
+        let mut position = Chess::default();
+        for (index, san) in moves.iter().enumerate() {
+            let ply = index as u32 + 1;
+            if let Some(note) = self.moves.get(&ply) {
+                note.validate(&position, ply, 1)?;
+            }
+            position = play_san(&position, san).ok_or(AnnotationError::InvalidGame(ply))?;
+        }
+        Ok(())
+    }
+
+    /// Add the annotations of `other`: comments are appended, evaluation
+    /// markers and variations not already present are added.
+    pub fn merge(&mut self, other: &AnnotationTree) {
+        for (ply, note) in &other.moves {
+            self.moves.entry(*ply).or_default().merge(note);
+        }
+    }
+
+    /// Prefix every comment, e.g. with the author's name before merging.
+    pub fn prefix_comments(&mut self, prefix: &str) {
+        for note in self.moves.values_mut() {
+            note.prefix_comments(prefix);
+        }
+    }
+}
+
+fn play_san(position: &Chess, san: &str) -> Option<Chess> {
+    let san: San = san.parse().ok()?;
+    let mv = san.to_move(position).ok()?;
+    position.clone().play(&mv).ok()
+}
+
+/// Write a game as PGN with `annotations` merged into the movetext.
+pub fn write_pgn(headers: &PgnHeaders, moves: &[String], annotations: &AnnotationTree) -> String {
+    let result = headers.result.to_pgn_string();
+    let mut out = String::new();
+
+    let roster = [
+        ("Event", headers.event.as_deref().unwrap_or("?")),
+        ("Site", headers.site.as_deref().unwrap_or("?")),
+        ("Date", headers.date.as_deref().unwrap_or("????.??.??")),
+        ("Round", headers.round.as_deref().unwrap_or("?")),
+        ("White", headers.white.as_str()),
+        ("Black", headers.black.as_str()),
+        ("Result", result),
+    ];
+    for (key, value) in roster {
+        out.push_str(&format!("[{} \"{}\"]\n", key, escape_tag(value)));
+    }
+    let mut other: Vec<(&String, &String)> = headers.other.iter().collect();
+    other.sort();
+    for (key, value) in other {
+        out.push_str(&format!("[{} \"{}\"]\n", key, escape_tag(value)));
+    }
+    out.push('\n');
+
+    let mainline: Vec<(&str, Option<&MoveNote>)> = moves
+        .iter()
+        .enumerate()
+        .map(|(index, san)| (san.as_str(), annotations.moves.get(&(index as u32 + 1))))
+        .collect();
+
+    let mut tokens = Vec::new();
+    write_line(&mut tokens, 1, &mainline);
+    tokens.push(result.to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > PGN_LINE_WIDTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+fn write_line(tokens: &mut Vec<String>, first_ply: u32, line: &[(&str, Option<&MoveNote>)]) {
+    let mut needs_number = true;
+
+    for (offset, (san, note)) in line.iter().enumerate() {
+        let ply = first_ply + offset as u32;
+        let number = ply.div_ceil(2);
+        if ply % 2 == 1 {
+            tokens.push(format!("{}.", number));
+        } else if needs_number {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(san.to_string());
+        needs_number = false;
+
+        let Some(note) = note else { continue };
+        for nag in &note.nags {
+            tokens.push(format!("${}", nag.code()));
+        }
+        if let Some(comment) = &note.comment {
+            // `}` would end the comment early
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+            needs_number = true;
+        }
+        for variation in &note.variations {
+            let moves: Vec<(&str, Option<&MoveNote>)> =
+                variation.iter().map(|m| (m.san.as_str(), Some(&m.note))).collect();
+            let mut inner = Vec::new();
+            write_line(&mut inner, ply, &moves);
+            if let Some(first) = inner.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = inner.last_mut() {
+                last.push(')');
+            }
+            tokens.extend(inner);
+            needs_number = true;
+        }
+    }
+}
+
+fn escape_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::{parse_pgn, GameResult};
+
+    fn moves(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn headers() -> PgnHeaders {
+        PgnHeaders {
+            white: "Alice".to_string(),
+            black: "Bob".to_string(),
+            result: GameResult::WhiteWins,
+            ..PgnHeaders::default()
+        }
+    }
+
+    fn variation(line: &str) -> Vec<AnnotatedMove> {
+        moves(line)
+            .into_iter()
+            .map(|san| AnnotatedMove { san, note: MoveNote::default() })
+            .collect()
+    }
+
+    #[test]
+    fn test_writes_comments_nags_and_variations() {
+        let game = moves("e4 e5 Nf3 Nc6");
+        let mut tree = AnnotationTree::default();
+        tree.moves.insert(1, MoveNote {
+            nags: vec![Nag::Good],
+            variations: vec![variation("d4 d5")],
+            ..MoveNote::default()
+        });
+        tree.moves.insert(4, MoveNote {
+            comment: Some("Most common".to_string()),
+            ..MoveNote::default()
+        });
+        tree.validate(&game).unwrap();
+
+        let pgn = write_pgn(&headers(), &game, &tree);
+        assert!(pgn.starts_with("[Event \"?\"]\n"));
+        assert!(pgn.contains("[White \"Alice\"]\n"));
+        assert!(pgn.ends_with("1. e4 $1 (1. d4 d5) 1... e5 2. Nf3 Nc6 {Most common} 1-0\n"));
+
+        // The export reads back as the same game
+        let parsed = parse_pgn(&pgn).unwrap();
+        assert_eq!(parsed.moves, game);
+    }
+
+    #[test]
+    fn test_rejects_illegal_variations_and_unknown_plies() {
+        let game = moves("e4 e5");
+
+        let mut beyond = AnnotationTree::default();
+        beyond.moves.insert(3, MoveNote::default());
+        assert_eq!(beyond.validate(&game), Err(AnnotationError::PlyOutOfRange(3)));
+
+        // Black's alternative must be legal from the position after 1. e4
+        let mut illegal = AnnotationTree::default();
+        illegal.moves.insert(2, MoveNote {
+            variations: vec![variation("c5 e4")],
+            ..MoveNote::default()
+        });
+        assert_eq!(
+            illegal.validate(&game),
+            Err(AnnotationError::IllegalMove { ply: 3, move_text: "e4".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_nested_variation_branches_from_its_own_move() {
+        let game = moves("e4 e5 Nf3");
+        let mut sub = variation("c5 Nf3");
+        sub[1].note.variations.push(variation("c3"));
+
+        let mut tree = AnnotationTree::default();
+        tree.moves.insert(2, MoveNote {
+            variations: vec![sub],
+            ..MoveNote::default()
+        });
+        tree.validate(&game).unwrap();
+
+        let pgn = write_pgn(&headers(), &game, &tree);
+        assert!(pgn.contains("1. e4 e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3 1-0"));
+    }
+
+    #[test]
+    fn test_merge_appends_comments_and_skips_duplicates() {
+        let mut mine = AnnotationTree::default();
+        mine.moves.insert(1, MoveNote {
+            comment: Some("Best by test".to_string()),
+            nags: vec![Nag::Good],
+            ..MoveNote::default()
+        });
+
+        let mut theirs = mine.clone();
+        theirs.prefix_comments("Bob: ");
+        theirs.moves.insert(2, MoveNote {
+            nags: vec![Nag::Equal],
+            ..MoveNote::default()
+        });
+
+        mine.merge(&theirs);
+        assert_eq!(mine.moves[&1].comment.as_deref(), Some("Best by test Bob: Best by test"));
+        assert_eq!(mine.moves[&1].nags, vec![Nag::Good]);
+        assert_eq!(mine.moves[&2].nags, vec![Nag::Equal]);
+    }
+}
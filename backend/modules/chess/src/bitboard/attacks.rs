@@ -0,0 +1,310 @@
+//! Precomputed attack tables.
+//!
+//! Sliding pieces look their attacks up in magic bitboard tables, or with
+//! PEXT on x86_64 builds that enable BMI2 (`-C target-feature=+bmi2`). The
+//! tables are built once, the first time any of them is used; call [`init`]
+//! at startup to pay that cost before the first game instead.
+
+use std::sync::OnceLock;
+
+use super::board::{Bitboard, Color, Square};
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const KNIGHT_STEPS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_STEPS: [(i8, i8); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const RANK_1: u64 = 0xff;
+const RANK_8: u64 = RANK_1 << 56;
+
+static TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+/// Where the attacks of a slider on one square are stored in the shared
+/// table, and how an occupancy maps to an index there.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    #[inline]
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    #[inline]
+    fn index(&self, occupied: u64) -> usize {
+        // SAFETY: the build enables BMI2, so every target CPU has PEXT
+        self.offset + unsafe { std::arch::x86_64::_pext_u64(occupied, self.mask) } as usize
+    }
+}
+
+/// Attacks of every piece from every square.
+pub struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    slides: Vec<u64>,
+    between: Box<[[u64; 64]; 64]>,
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut slides = Vec::new();
+        let rook = slider_magics(&ROOK_DIRECTIONS, &mut slides);
+        let bishop = slider_magics(&BISHOP_DIRECTIONS, &mut slides);
+        let mut tables = AttackTables {
+            knight: std::array::from_fn(|square| step_attacks(square, &KNIGHT_STEPS)),
+            king: std::array::from_fn(|square| step_attacks(square, &KING_STEPS)),
+            pawn: [
+                std::array::from_fn(|square| step_attacks(square, &[(-1, 1), (1, 1)])),
+                std::array::from_fn(|square| step_attacks(square, &[(-1, -1), (1, -1)])),
+            ],
+            rook,
+            bishop,
+            slides,
+            between: Box::new([[0; 64]; 64]),
+        };
+
+        for a in 0..64 {
+            for b in 0..64 {
+                let (from, to) = (1u64 << a, 1u64 << b);
+                tables.between[a][b] = if tables.rook_attacks(a, 0) & to != 0 {
+                    tables.rook_attacks(a, to) & tables.rook_attacks(b, from)
+                } else if tables.bishop_attacks(a, 0) & to != 0 {
+                    tables.bishop_attacks(a, to) & tables.bishop_attacks(b, from)
+                } else {
+                    0
+                };
+            }
+        }
+        tables
+    }
+
+    #[inline]
+    fn rook_attacks(&self, square: usize, occupied: u64) -> u64 {
+        self.slides[self.rook[square].index(occupied)]
+    }
+
+    #[inline]
+    fn bishop_attacks(&self, square: usize, occupied: u64) -> u64 {
+        self.slides[self.bishop[square].index(occupied)]
+    }
+}
+
+/// Build the attack tables now rather than on first use.
+pub fn init() {
+    tables();
+}
+
+/// The attack tables, built on first use.
+pub fn tables() -> &'static AttackTables {
+    TABLES.get_or_init(AttackTables::new)
+}
+
+/// Squares a rook on `square` attacks, stopping at the first occupied
+/// square in each direction.
+#[inline]
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    Bitboard(tables().rook_attacks(square.value as usize, occupied.0))
+}
+
+/// Squares a bishop on `square` attacks, stopping at the first occupied
+/// square in each direction.
+#[inline]
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    Bitboard(tables().bishop_attacks(square.value as usize, occupied.0))
+}
+
+#[inline]
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+#[inline]
+pub fn knight_attacks(square: Square) -> Bitboard {
+    Bitboard(tables().knight[square.value as usize])
+}
+
+#[inline]
+pub fn king_attacks(square: Square) -> Bitboard {
+    Bitboard(tables().king[square.value as usize])
+}
+
+/// Squares a pawn of `color` on `square` captures on.
+#[inline]
+pub fn pawn_attacks(color: Color, square: Square) -> Bitboard {
+    Bitboard(tables().pawn[color as usize][square.value as usize])
+}
+
+/// Squares strictly between `a` and `b`; empty unless they share a rank,
+/// file or diagonal.
+#[inline]
+pub fn between(a: Square, b: Square) -> Bitboard {
+    Bitboard(tables().between[a.value as usize][b.value as usize])
+}
+
+fn step_attacks(square: usize, steps: &[(i8, i8)]) -> u64 {
+    let (file, rank) = ((square % 8) as i8, (square / 8) as i8);
+    steps
+        .iter()
+        .map(|(df, dr)| (file + df, rank + dr))
+        .filter(|(file, rank)| (0..8).contains(file) && (0..8).contains(rank))
+        .fold(0, |attacks, (file, rank)| attacks | 1 << (rank * 8 + file))
+}
+
+/// Slider attacks found by walking each ray; only used to fill the tables.
+fn sliding_attacks(square: usize, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0;
+    for (df, dr) in directions {
+        let (mut file, mut rank) = ((square % 8) as i8 + df, (square / 8) as i8 + dr);
+        while (0..8).contains(&file) && (0..8).contains(&rank) {
+            let bit = 1u64 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            file += df;
+            rank += dr;
+        }
+    }
+    attacks
+}
+
+/// Fill `slides` with the attacks from every square for every occupancy of
+/// the squares that can block them, returning how to look them up.
+fn slider_magics(directions: &[(i8, i8)], slides: &mut Vec<u64>) -> [Magic; 64] {
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    let mut prng = Prng(1_070_372);
+
+    std::array::from_fn(|square| {
+        // Pieces on the edge of the board never block anything behind them
+        let edges = ((RANK_1 | RANK_8) & !(RANK_1 << (8 * (square / 8))))
+            | ((FILE_A | FILE_H) & !(FILE_A << (square % 8)));
+        let mask = sliding_attacks(square, 0, directions) & !edges;
+
+        let mut occupancies = Vec::with_capacity(1 << mask.count_ones());
+        let mut subset = 0u64;
+        loop {
+            occupancies.push((subset, sliding_attacks(square, subset, directions)));
+            subset = subset.wrapping_sub(mask) & mask;
+            if subset == 0 {
+                break;
+            }
+        }
+
+        let offset = slides.len();
+        slides.resize(offset + occupancies.len(), 0);
+
+        #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+        let magic = Magic { mask, magic: 0, shift: 0, offset };
+
+        #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+        let magic = {
+            let shift = 64 - mask.count_ones();
+            // Which attempt last wrote each entry, so a failed candidate's
+            // entries need not be cleared
+            let mut written = vec![0u32; occupancies.len()];
+            let mut attempt = 0;
+            loop {
+                let magic = Magic { mask, magic: prng.sparse(), shift, offset };
+                // Candidates that spread the mask over too few high bits
+                // cannot index the whole table
+                if (mask.wrapping_mul(magic.magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+                attempt += 1;
+                let fits = occupancies.iter().all(|&(occupied, attacks)| {
+                    let index = magic.index(occupied);
+                    if written[index - offset] < attempt {
+                        written[index - offset] = attempt;
+                        slides[index] = attacks;
+                        true
+                    } else {
+                        slides[index] == attacks
+                    }
+                });
+                if fits {
+                    break magic;
+                }
+            }
+        };
+
+        for &(occupied, attacks) in &occupancies {
+            slides[magic.index(occupied)] = attacks;
+        }
+        magic
+    })
+}
+
+/// xorshift64*, seeded so every run finds the same magics.
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+struct Prng(u64);
+
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+impl Prng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(2_685_821_657_736_338_717)
+    }
+
+    /// Random numbers with few bits set make good magic candidates.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        let bytes = name.as_bytes();
+        Square { value: (bytes[1] - b'1') * 8 + (bytes[0] - b'a') }
+    }
+
+    #[test]
+    fn slider_lookups_match_walking_the_rays() {
+        let mut prng = Prng(42);
+        for _ in 0..2_000 {
+            let occupied = prng.next() & prng.next();
+            for value in 0..64u8 {
+                let square = Square { value };
+                assert_eq!(
+                    rook_attacks(square, Bitboard(occupied)).0,
+                    sliding_attacks(value as usize, occupied, &ROOK_DIRECTIONS)
+                );
+                assert_eq!(
+                    bishop_attacks(square, Bitboard(occupied)).0,
+                    sliding_attacks(value as usize, occupied, &BISHOP_DIRECTIONS)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn step_and_between_tables() {
+        assert_eq!(knight_attacks(square("a1")).count(), 2);
+        assert_eq!(knight_attacks(square("d4")).count(), 8);
+        assert_eq!(king_attacks(square("h8")).count(), 3);
+        assert_eq!(pawn_attacks(Color::White, square("a2")), square("b3").bitboard());
+        assert_eq!(pawn_attacks(Color::Black, square("e5")), square("d4").bitboard() | square("f4").bitboard());
+
+        assert_eq!(between(square("a1"), square("d4")), square("b2").bitboard() | square("c3").bitboard());
+        assert_eq!(between(square("e1"), square("e3")), square("e2").bitboard());
+        assert_eq!(between(square("a1"), square("b3")), Bitboard::EMPTY);
+        assert_eq!(between(square("a1"), square("b2")), Bitboard::EMPTY);
+    }
+}
@@ -1,2 +1,3 @@
+pub mod attacks;
 pub mod board;
-pub mod bitboard; 
\ No newline at end of file
+pub mod bitboard;
\ No newline at end of file
@@ -1,6 +1,28 @@
+//! Chess rules shared by the server and, compiled to WebAssembly with the
+//! `wasm` feature, the web frontend. Move generation, FEN and PGN have no
+//! platform dependencies; clocks (`clock`) and random drills (`random`) are
+//! behind default features that browser builds leave out.
+
 pub mod bitboard;
+#[cfg(feature = "clock")]
 pub mod time_control;
+#[cfg(feature = "clock")]
+pub mod time_source;
 pub mod pgn;
+pub mod annotation;
+pub mod referee;
+pub mod odds;
+pub mod training;
+pub mod zobrist;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "clock")]
 pub use time_control::{TimeControl, PlayerClock};
+#[cfg(feature = "clock")]
+pub use time_source::{ManualTime, MonotonicTime, TimeSource};
 pub use pgn::{parse_pgn, validate_game, ParsedGame, ValidatedGame, PgnError, PgnHeaders, GameResult as PgnGameResult};
+pub use annotation::{write_pgn, AnnotatedMove, AnnotationError, AnnotationTree, MoveNote, Nag};
+pub use referee::{Material, PieceCounts, Referee, RefereeError, Termination, UndoToken};
+pub use odds::{Odds, OddsError, OddsGiver, OddsPiece};
+pub use training::{Drill, Exercise, VisionPiece};
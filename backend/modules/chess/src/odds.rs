@@ -0,0 +1,237 @@
+//! Odds Module
+//!
+//! Handicap games for coaching and exhibitions: the stronger side (the
+//! giver) starts without some of its pieces, and the weaker side may play
+//! extra moves before the giver's first reply.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Most extra moves a receiver can be given
+pub const MAX_EXTRA_MOVES: u8 = 3;
+
+/// Written in place of the giver's skipped turns when recording the moves
+pub const PASS: &str = "--";
+
+const STANDARD_BACK_RANK: [char; 8] = ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'];
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum OddsError {
+    #[error("Odds must remove at least one piece or give at least one extra move")]
+    NoOdds,
+
+    #[error("There is no {0:?} left to remove")]
+    PieceUnavailable(OddsPiece),
+
+    #[error("At most {MAX_EXTRA_MOVES} extra moves can be given")]
+    TooManyExtraMoves,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsGiver {
+    White,
+    Black,
+}
+
+/// A piece the giver starts without. Knights, bishops and rooks are taken
+/// from the queenside first; the pawn is the f-pawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsPiece {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+}
+
+impl OddsPiece {
+    /// Files the piece is removed from, in order
+    fn files(self) -> &'static [usize] {
+        match self {
+            OddsPiece::Pawn => &[5],
+            OddsPiece::Knight => &[1, 6],
+            OddsPiece::Bishop => &[2, 5],
+            OddsPiece::Rook => &[0, 7],
+            OddsPiece::Queen => &[3],
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            OddsPiece::Pawn => 'p',
+            OddsPiece::Knight => 'n',
+            OddsPiece::Bishop => 'b',
+            OddsPiece::Rook => 'r',
+            OddsPiece::Queen => 'q',
+        }
+    }
+}
+
+/// Handicap given by one side, e.g. queen odds or pawn and move.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Odds {
+    pub giver: OddsGiver,
+    #[serde(default)]
+    pub removed: Vec<OddsPiece>,
+    /// Moves the receiver plays before the giver's first move, on top of
+    /// their normal turn
+    #[serde(default)]
+    pub extra_moves: u8,
+}
+
+impl Odds {
+    pub fn validate(&self) -> Result<(), OddsError> {
+        self.start_fen().map(|_| ())
+    }
+
+    /// The starting position in FEN.
+    pub fn start_fen(&self) -> Result<String, OddsError> {
+        if self.removed.is_empty() && self.extra_moves == 0 {
+            return Err(OddsError::NoOdds);
+        }
+        if self.extra_moves > MAX_EXTRA_MOVES {
+            return Err(OddsError::TooManyExtraMoves);
+        }
+
+        // The giver's back rank and pawns, in lowercase until written out
+        let mut back_rank = STANDARD_BACK_RANK.map(Some);
+        let mut pawns = [Some('p'); 8];
+        for piece in &self.removed {
+            let rank = if *piece == OddsPiece::Pawn { &mut pawns } else { &mut back_rank };
+            let file = piece
+                .files()
+                .iter()
+                .find(|file| rank[**file] == Some(piece.symbol()))
+                .ok_or(OddsError::PieceUnavailable(*piece))?;
+            rank[*file] = None;
+        }
+
+        let giver_white = self.giver == OddsGiver::White;
+        let row = |rank: &[Option<char>; 8], white: bool| {
+            let mut out = String::new();
+            let mut empty = 0;
+            for square in rank {
+                match square {
+                    Some(symbol) => {
+                        if empty > 0 {
+                            out.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        out.push(if white { symbol.to_ascii_uppercase() } else { *symbol });
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                out.push_str(&empty.to_string());
+            }
+            out
+        };
+        let full_back_rank = STANDARD_BACK_RANK.map(Some);
+        let full_pawns = [Some('p'); 8];
+        let (white_back, white_pawns, black_back, black_pawns) = if giver_white {
+            (&back_rank, &pawns, &full_back_rank, &full_pawns)
+        } else {
+            (&full_back_rank, &full_pawns, &back_rank, &pawns)
+        };
+
+        let mut castling = String::new();
+        let rooks = [('K', white_back, 7), ('Q', white_back, 0), ('k', black_back, 7), ('q', black_back, 0)];
+        for (symbol, back, file) in rooks {
+            if back[file] == Some('r') {
+                castling.push(symbol);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        // Black moving first is the receiver's first extra move
+        let turn = if self.extra_moves > 0 && giver_white { 'b' } else { 'w' };
+
+        Ok(format!(
+            "{}/{}/8/8/8/8/{}/{} {} {} - 0 1",
+            row(black_back, false),
+            row(black_pawns, false),
+            row(white_pawns, true),
+            row(white_back, true),
+            turn,
+            castling
+        ))
+    }
+
+    /// Receiver moves after which the giver's turn is skipped. Black moving
+    /// first already counts as one extra move.
+    pub fn skipped_turns(&self) -> u8 {
+        match self.giver {
+            OddsGiver::White => self.extra_moves.saturating_sub(1),
+            OddsGiver::Black => self.extra_moves,
+        }
+    }
+
+    /// Tags describing the starting position, for PGN export.
+    pub fn pgn_tags(&self) -> Result<HashMap<String, String>, OddsError> {
+        Ok(HashMap::from([
+            ("SetUp".to_string(), "1".to_string()),
+            ("FEN".to_string(), self.start_fen()?),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn odds(giver: OddsGiver, removed: Vec<OddsPiece>, extra_moves: u8) -> Odds {
+        Odds {
+            giver,
+            removed,
+            extra_moves,
+        }
+    }
+
+    #[test]
+    fn removes_material_from_the_giver() {
+        let queen = odds(OddsGiver::White, vec![OddsPiece::Queen], 0);
+        assert_eq!(
+            queen.start_fen().unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+        );
+
+        let rooks = odds(OddsGiver::Black, vec![OddsPiece::Rook, OddsPiece::Knight, OddsPiece::Rook], 0);
+        assert_eq!(
+            rooks.start_fen().unwrap(),
+            "2bqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1"
+        );
+    }
+
+    #[test]
+    fn pawn_and_move_lets_black_start() {
+        let pawn_and_move = odds(OddsGiver::White, vec![OddsPiece::Pawn], 1);
+        assert_eq!(
+            pawn_and_move.start_fen().unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1"
+        );
+        assert_eq!(pawn_and_move.skipped_turns(), 0);
+
+        let two_moves = odds(OddsGiver::Black, Vec::new(), 2);
+        assert!(two_moves.start_fen().unwrap().contains(" w "));
+        assert_eq!(two_moves.skipped_turns(), 2);
+    }
+
+    #[test]
+    fn rejects_impossible_odds() {
+        assert_eq!(odds(OddsGiver::White, Vec::new(), 0).validate(), Err(OddsError::NoOdds));
+        assert_eq!(
+            odds(OddsGiver::White, vec![OddsPiece::Queen, OddsPiece::Queen], 0).validate(),
+            Err(OddsError::PieceUnavailable(OddsPiece::Queen))
+        );
+        assert_eq!(
+            odds(OddsGiver::White, Vec::new(), MAX_EXTRA_MOVES + 1).validate(),
+            Err(OddsError::TooManyExtraMoves)
+        );
+    }
+}
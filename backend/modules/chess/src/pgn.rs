@@ -55,7 +55,7 @@ impl GameResult {
         match s.trim() {
             "1-0" => Ok(GameResult::WhiteWins),
             "0-1" => Ok(GameResult::BlackWins),
-            "1/2-1/2" => Ok(GameResult::Draw),
+            "1/2-1/2" | "½-½" => Ok(GameResult::Draw),
             "*" => Ok(GameResult::Ongoing),
             other => Err(PgnError::InvalidResult(other.to_string())),
         }
@@ -108,20 +108,35 @@ pub struct ValidatedGame {
     pub is_valid: bool,
 }
 
-/// Parse PGN headers from the input string
+/// Longest PGN accepted for one game. Real games are a few kilobytes, so
+/// an upload this size is not one.
+pub const MAX_PGN_LENGTH: usize = 1 << 20;
+
+/// Parse PGN headers from the input string. Tag pairs open the game; the
+/// move text starts at the first line that isn't one.
 fn parse_headers(pgn: &str) -> Result<(PgnHeaders, &str), PgnError> {
-    let header_regex = Regex::new(r#"\[(\w+)\s+"([^"]+)"\]"#).unwrap();
-    
+    let tag_regex = Regex::new(r#"^\[\s*(\w+)\s+"((?:[^"\\\r\n]|\\.)*)"\s*\]"#).unwrap();
+
     let mut headers = PgnHeaders::default();
-    let mut last_header_end = 0;
-    
-    for cap in header_regex.captures_iter(pgn) {
-        let full_match = cap.get(0).unwrap();
-        last_header_end = full_match.end();
-        
+    let mut rest = pgn;
+    loop {
+        rest = rest.trim_start();
+        if !rest.starts_with('[') {
+            break;
+        }
+        let Some(cap) = tag_regex.captures(rest) else {
+            // An unreadable tag is skipped rather than read as moves
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        };
+        rest = &rest[cap.get(0).unwrap().end()..];
+
         let key = cap.get(1).unwrap().as_str();
-        let value = cap.get(2).unwrap().as_str().to_string();
-        
+        let value = unescape(cap.get(2).unwrap().as_str());
+        if value.is_empty() {
+            continue;
+        }
+
         match key.to_lowercase().as_str() {
             "event" => headers.event = Some(value),
             "site" => headers.site = Some(value),
@@ -135,7 +150,7 @@ fn parse_headers(pgn: &str) -> Result<(PgnHeaders, &str), PgnError> {
             }
         }
     }
-    
+
     // Validate required headers
     if headers.white.is_empty() {
         return Err(PgnError::MissingHeader("White".to_string()));
@@ -143,60 +158,110 @@ fn parse_headers(pgn: &str) -> Result<(PgnHeaders, &str), PgnError> {
     if headers.black.is_empty() {
         return Err(PgnError::MissingHeader("Black".to_string()));
     }
-    
-    // Get the move text (everything after headers)
-    let move_text = &pgn[last_header_end..];
-    
-    Ok((headers, move_text))
+
+    Ok((headers, rest))
 }
 
-/// Parse move text into individual SAN moves
+/// A tag value with its `\"` and `\\` escapes undone.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Parse move text into individual SAN moves, leaving out comments,
+/// variations (nested to any depth), escaped lines, NAGs, move numbers,
+/// annotation marks and the result.
 fn parse_moves(move_text: &str) -> Vec<String> {
-    // Remove comments (both curly brace and semicolon style)
-    let without_curly_comments = Regex::new(r"\{[^}]*\}")
-        .unwrap()
-        .replace_all(move_text, " ");
-    let without_semicolon_comments = Regex::new(r";[^\n]*")
-        .unwrap()
-        .replace_all(&without_curly_comments, " ");
-    
-    // Remove NAGs (Numeric Annotation Glyphs like $1, $2, etc.)
-    let without_nags = Regex::new(r"\$\d+")
-        .unwrap()
-        .replace_all(&without_semicolon_comments, " ");
-    
-    // Remove variations (recursive parentheses - simplified, only top-level)
-    let without_variations = Regex::new(r"\([^()]*\)")
-        .unwrap()
-        .replace_all(&without_nags, " ");
-    
-    // Split into tokens
-    let tokens: Vec<&str> = without_variations.split_whitespace().collect();
-    
-    // Filter out move numbers, results, and other non-move tokens
-    let move_number_regex = Regex::new(r"^\d+\.+$").unwrap();
-    let result_regex = Regex::new(r"^(1-0|0-1|1/2-1/2|\*)$").unwrap();
-    
-    tokens
-        .into_iter()
-        .filter(|token| {
-            !move_number_regex.is_match(token) && !result_regex.is_match(token) && !token.is_empty()
-        })
-        .map(|s| s.to_string())
-        .collect()
+    let mut moves = Vec::new();
+    let mut token = String::new();
+    let mut variation_depth = 0usize;
+    let mut line_start = true;
+    let mut chars = move_text.chars();
+
+    while let Some(c) = chars.next() {
+        let starts_line = line_start;
+        line_start = c == '\n';
+        match c {
+            // An unclosed comment runs to the end of the game
+            '{' => {
+                chars.by_ref().find(|&c| c == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+                line_start = true;
+            }
+            '%' if starts_line => {
+                chars.by_ref().find(|&c| c == '\n');
+                line_start = true;
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            c if !c.is_whitespace() => {
+                if variation_depth == 0 {
+                    token.push(c);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        push_move(&mut moves, &token);
+        token.clear();
+    }
+    push_move(&mut moves, &token);
+
+    moves
+}
+
+fn push_move(moves: &mut Vec<String>, token: &str) {
+    if token.starts_with('$') || is_result(token) {
+        return;
+    }
+    // Move numbers may be written onto the move, as in `12.e4` or `12...e5`
+    let digits = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+    let token = match token[digits..].starts_with('.') {
+        true => token[digits..].trim_start_matches('.'),
+        false => token,
+    };
+    let token = token.trim_end_matches(['!', '?']);
+    if token.is_empty() {
+        return;
+    }
+    // Castling is often written with zeros
+    match token.starts_with("0-0") {
+        true => moves.push(token.replace('0', "O")),
+        false => moves.push(token.to_string()),
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "½-½" | "*")
 }
 
 /// Parse a PGN string into a ParsedGame
 pub fn parse_pgn(pgn_string: &str) -> Result<ParsedGame, PgnError> {
-    let pgn = pgn_string.trim();
-    
+    // Files saved by some Windows tools open with a byte order mark
+    let pgn = pgn_string.trim_start_matches('\u{feff}').trim();
+
     if pgn.is_empty() {
         return Err(PgnError::EmptyPgn);
     }
-    
+    if pgn.len() > MAX_PGN_LENGTH {
+        return Err(PgnError::InvalidFormat(format!(
+            "PGN is longer than {} bytes",
+            MAX_PGN_LENGTH
+        )));
+    }
+
     let (headers, move_text) = parse_headers(pgn)?;
     let moves = parse_moves(move_text);
-    
+
     Ok(ParsedGame {
         headers,
         moves,
@@ -219,6 +284,13 @@ pub fn validate_game(parsed: &ParsedGame) -> Result<ValidatedGame, PgnError> {
             move_text: move_san.clone(),
             reason: "Invalid move notation".to_string(),
         })?;
+        if san == San::Null {
+            return Err(PgnError::IllegalMove {
+                move_number,
+                move_text: move_san.clone(),
+                reason: "Null moves are not supported".to_string(),
+            });
+        }
         
         // Try to play the move
         let chess_move = san.to_move(&position).map_err(|_| PgnError::IllegalMove {
@@ -338,4 +410,75 @@ mod tests {
         assert_eq!(GameResult::from_pgn_string("1/2-1/2").unwrap(), GameResult::Draw);
         assert_eq!(GameResult::from_pgn_string("*").unwrap(), GameResult::Ongoing);
     }
+
+    #[test]
+    fn test_byte_order_mark_and_crlf() {
+        let pgn = "\u{feff}[White \"Player1\"]\r\n[Black \"Player2\"]\r\n[Result \"0-1\"]\r\n\r\n1. e4 e5 ; king's pawn\r\n2. Nf3 Nc6 0-1\r\n";
+
+        let parsed = parse_pgn(pgn).unwrap();
+        assert_eq!(parsed.headers.white, "Player1");
+        assert_eq!(parsed.headers.result, GameResult::BlackWins);
+        assert_eq!(parsed.moves, ["e4", "e5", "Nf3", "Nc6"]);
+    }
+
+    #[test]
+    fn test_escaped_quotes_in_tags() {
+        let pgn = r#"[White "O'Kelly, \"The Count\""]
+[Black "C:\\Users\\fritz"]
+
+1. d4 *"#;
+
+        let parsed = parse_pgn(pgn).unwrap();
+        assert_eq!(parsed.headers.white, r#"O'Kelly, "The Count""#);
+        assert_eq!(parsed.headers.black, r"C:\Users\fritz");
+    }
+
+    #[test]
+    fn test_tags_are_only_read_before_the_moves() {
+        let pgn = r#"[White "Player1"]
+[Black "Player2"]
+
+1. e4 {[White "Someone else"] [%clk 0:03:00]} e5 *"#;
+
+        let parsed = parse_pgn(pgn).unwrap();
+        assert_eq!(parsed.headers.white, "Player1");
+        assert_eq!(parsed.moves, ["e4", "e5"]);
+    }
+
+    #[test]
+    fn test_move_text_noise_is_dropped() {
+        let pgn = r#"[White "Player1"]
+[Black "Player2"]
+
+1.e4 e5!? 2.Nf3 (2.f4 exf4 (2...d5 3.exd5) 3.Nf3) 2...Nc6 $1
+%a line escaped from the format
+3.Bb5?! a6 4.0-0 {unclosed (comment 5. Ba4 ½-½"#;
+
+        let parsed = parse_pgn(pgn).unwrap();
+        assert_eq!(parsed.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "O-O"]);
+        assert!(validate_game(&parsed).is_ok());
+    }
+
+    #[test]
+    fn test_null_moves_are_rejected() {
+        let pgn = r#"[White "Player1"]
+[Black "Player2"]
+
+1. e4 -- 2. d4 *"#;
+
+        let parsed = parse_pgn(pgn).unwrap();
+        match validate_game(&parsed) {
+            Err(PgnError::IllegalMove { move_number, move_text, reason }) => {
+                assert_eq!((move_number, move_text.as_str()), (1, "--"));
+                assert_eq!(reason, "Null moves are not supported");
+            }
+            other => panic!("expected an illegal move, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unicode_draw_result() {
+        assert_eq!(GameResult::from_pgn_string("½-½").unwrap(), GameResult::Draw);
+        assert!(matches!(GameResult::from_pgn_string("+/-"), Err(PgnError::InvalidResult(_))));
+    }
 }
@@ -0,0 +1,616 @@
+//! Referee Module
+//!
+//! Keeps track of a game played between engines: applies moves given in UCI
+//! or SAN notation, records the game in SAN and detects when it is over by
+//! the rules (mate, stalemate, insufficient material, fifty moves or
+//! threefold repetition). Odds games start from the handicap position, with
+//! the giver's skipped turns recorded as `--`.
+//!
+//! Moves are taken back without replaying the game: each move keeps the
+//! position before it, while the Zobrist hash and material counts are
+//! updated incrementally in both directions.
+
+use shakmaty::{fen::Fen, san::San, uci::UciMove, CastlingMode, Chess, Color, EnPassantMode, Move, Piece, Position, Role};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::odds::{Odds, OddsError, PASS};
+use crate::pgn::GameResult;
+use crate::zobrist;
+
+/// Longest FEN read. The longest legal positions take under 100 characters;
+/// longer input is rejected before parsing and quoted only this far.
+pub const MAX_FEN_LENGTH: usize = 128;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum RefereeError {
+    #[error("Illegal move '{0}'")]
+    IllegalMove(String),
+
+    #[error("The game is already over")]
+    GameOver,
+
+    #[error("Invalid FEN '{0}'")]
+    InvalidFen(String),
+
+    #[error("Only the last move made can be taken back")]
+    UndoOutOfOrder,
+
+    #[error("No move to take back")]
+    NothingToUndo,
+}
+
+/// Why a game ended by the rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoves,
+    Repetition,
+}
+
+/// Pieces of each kind one side has on the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PieceCounts {
+    pub pawns: u8,
+    pub knights: u8,
+    pub bishops: u8,
+    pub rooks: u8,
+    pub queens: u8,
+    pub kings: u8,
+}
+
+impl PieceCounts {
+    fn get_mut(&mut self, role: Role) -> &mut u8 {
+        match role {
+            Role::Pawn => &mut self.pawns,
+            Role::Knight => &mut self.knights,
+            Role::Bishop => &mut self.bishops,
+            Role::Rook => &mut self.rooks,
+            Role::Queen => &mut self.queens,
+            Role::King => &mut self.kings,
+        }
+    }
+
+    /// Material in pawns, counting minor pieces as 3, rooks as 5 and
+    /// queens as 9.
+    pub fn points(&self) -> u32 {
+        self.pawns as u32 + 3 * (self.knights + self.bishops) as u32 + 5 * self.rooks as u32 + 9 * self.queens as u32
+    }
+}
+
+/// Material of both sides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Material {
+    pub white: PieceCounts,
+    pub black: PieceCounts,
+}
+
+impl Material {
+    fn of(position: &Chess) -> Self {
+        let mut material = Material::default();
+        for color in [Color::White, Color::Black] {
+            for role in Role::ALL {
+                *material.side_mut(color).get_mut(role) = position.board().by_piece(Piece { color, role }).count() as u8;
+            }
+        }
+        material
+    }
+
+    /// Counts of every piece but the kings packed into one number, equal for
+    /// two positions exactly when their material is, so it can be stored and
+    /// searched for.
+    pub fn key(&self) -> u64 {
+        [self.white, self.black]
+            .iter()
+            .flat_map(|side| [side.pawns, side.knights, side.bishops, side.rooks, side.queens])
+            .fold(0, |key, count| key << 4 | count.min(15) as u64)
+    }
+
+    /// Parse a material signature such as `KRPPkr`: one letter per piece,
+    /// upper case for white and lower case for black. Kings may be left out.
+    pub fn from_signature(signature: &str) -> Option<Self> {
+        let mut material = Material::default();
+        material.white.kings = 1;
+        material.black.kings = 1;
+        for c in signature.chars() {
+            let role = Role::from_char(c.to_ascii_lowercase())?;
+            if role == Role::King {
+                continue;
+            }
+            let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+            let count = material.side_mut(color).get_mut(role);
+            *count = count.checked_add(1).filter(|count| *count <= 15)?;
+        }
+        Some(material)
+    }
+
+    fn side_mut(&mut self, color: Color) -> &mut PieceCounts {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    /// Material after `mv` is played by `us`.
+    fn after(mut self, us: Color, mv: &Move) -> Self {
+        match *mv {
+            Move::Normal { capture, promotion, .. } => {
+                if let Some(captured) = capture {
+                    *self.side_mut(!us).get_mut(captured) -= 1;
+                }
+                if let Some(promoted) = promotion {
+                    *self.side_mut(us).get_mut(Role::Pawn) -= 1;
+                    *self.side_mut(us).get_mut(promoted) += 1;
+                }
+            }
+            Move::EnPassant { .. } => *self.side_mut(!us).get_mut(Role::Pawn) -= 1,
+            Move::Castle { .. } => {}
+            Move::Put { role, .. } => *self.side_mut(us).get_mut(role) += 1,
+        }
+        self
+    }
+}
+
+/// Returned by [`Referee::make_move`] and needed to take the move back.
+/// Tokens cannot be copied and are only accepted for the last move still
+/// on the board, so moves are unmade in the reverse order they were made.
+#[derive(Debug, PartialEq, Eq)]
+#[must_use = "the move can only be unmade with its token"]
+pub struct UndoToken {
+    serial: u64,
+}
+
+/// What a move changed, to restore the game before it.
+#[derive(Debug, Clone)]
+struct Undo {
+    serial: u64,
+    position: Chess,
+    hash: u64,
+    material: Material,
+    skipped_turns: u8,
+    moves: usize,
+}
+
+/// A game in progress, starting from the standard or an odds position.
+#[derive(Debug, Clone)]
+pub struct Referee {
+    position: Chess,
+    moves: Vec<String>,
+    /// Receiver moves of an odds game still followed by a skipped turn
+    skipped_turns: u8,
+    /// How often each position occurred, by Zobrist hash
+    seen: HashMap<u64, u32>,
+    hash: u64,
+    material: Material,
+    /// One entry per move on the board, the last move last
+    history: Vec<Undo>,
+    /// Serial number of the next move made
+    next_serial: u64,
+}
+
+impl Default for Referee {
+    fn default() -> Self {
+        Self::at(Chess::default(), 0)
+    }
+}
+
+impl Referee {
+    /// Start a game from an opening line in SAN, e.g. `1. e4 c5 2. Nf3`.
+    /// Move numbers are ignored.
+    pub fn from_opening(line: &str) -> Result<Self, RefereeError> {
+        let mut referee = Self::default();
+        for token in line.split_whitespace().filter(|t| !t.ends_with('.')) {
+            referee.play_san(token)?;
+        }
+        Ok(referee)
+    }
+
+    /// Continue a game from a position in FEN. Repetitions before it are
+    /// unknown.
+    pub fn from_fen(fen: &str) -> Result<Self, RefereeError> {
+        let fen = fen.trim_start_matches('\u{feff}').trim();
+        if fen.len() > MAX_FEN_LENGTH {
+            let start: String = fen.chars().take(MAX_FEN_LENGTH).collect();
+            return Err(RefereeError::InvalidFen(format!("{}...", start)));
+        }
+        let position = fen
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+            .ok_or_else(|| RefereeError::InvalidFen(fen.to_string()))?;
+        Ok(Self::at(position, 0))
+    }
+
+    /// Start an odds game.
+    pub fn from_odds(odds: &Odds) -> Result<Self, OddsError> {
+        let position = odds
+            .start_fen()?
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+            .expect("odds positions are legal");
+        Ok(Self::at(position, odds.skipped_turns()))
+    }
+
+    fn at(position: Chess, skipped_turns: u8) -> Self {
+        let hash = zobrist::hash(&position);
+        Self {
+            material: Material::of(&position),
+            position,
+            moves: Vec::new(),
+            skipped_turns,
+            seen: HashMap::from([(hash, 1)]),
+            hash,
+            history: Vec::new(),
+            next_serial: 0,
+        }
+    }
+
+    /// Moves played so far in SAN.
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    pub fn white_to_move(&self) -> bool {
+        self.position.turn().is_white()
+    }
+
+    pub fn fen(&self) -> String {
+        Fen::from_position(self.position.clone(), EnPassantMode::Legal).to_string()
+    }
+
+    /// Halfmoves since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.position.halfmoves()
+    }
+
+    /// Number of the current full move, starting at 1 and increasing after
+    /// each Black move.
+    pub fn fullmove_number(&self) -> u32 {
+        self.position.fullmoves().get()
+    }
+
+    /// Zobrist hash of the position, equal for positions that are the same
+    /// under the repetition rule.
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    /// Every piece on the board as its square, counted from a1 = 0 to
+    /// h8 = 63, and its FEN letter, uppercase for White.
+    pub fn pieces(&self) -> Vec<(u8, char)> {
+        self.position
+            .board()
+            .iter()
+            .map(|(square, piece)| (u32::from(square) as u8, piece.char()))
+            .collect()
+    }
+
+    /// Legal moves of the side to move in UCI notation; none once the game
+    /// is over.
+    pub fn legal_moves(&self) -> Vec<String> {
+        if self.outcome().is_some() {
+            return Vec::new();
+        }
+        self.position
+            .legal_moves()
+            .iter()
+            .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+            .collect()
+    }
+
+    /// Play a move in UCI notation (`e2e4`, `e7e8q`) and return it in SAN,
+    /// with `+` or `#` for check and mate.
+    pub fn play_uci(&mut self, uci: &str) -> Result<String, RefereeError> {
+        self.ensure_in_progress()?;
+        let illegal = || RefereeError::IllegalMove(uci.to_string());
+        let mv = uci
+            .parse::<UciMove>()
+            .map_err(|_| illegal())?
+            .to_move(&self.position)
+            .map_err(|_| illegal())?;
+        self.play(mv, uci)
+    }
+
+    /// Play a move in SAN notation and return it normalized.
+    pub fn play_san(&mut self, san: &str) -> Result<String, RefereeError> {
+        self.ensure_in_progress()?;
+        let illegal = || RefereeError::IllegalMove(san.to_string());
+        let mv = san
+            .parse::<San>()
+            .map_err(|_| illegal())?
+            .to_move(&self.position)
+            .map_err(|_| illegal())?;
+        self.play(mv, san)
+    }
+
+    /// Play a move in UCI or SAN notation, returning the token that takes it
+    /// back.
+    pub fn make_move(&mut self, notation: &str) -> Result<UndoToken, RefereeError> {
+        match self.play_uci(notation) {
+            Err(RefereeError::IllegalMove(_)) => self.play_san(notation)?,
+            played => played?,
+        };
+        Ok(UndoToken { serial: self.next_serial - 1 })
+    }
+
+    /// Take back the move `token` was returned for. Fails, changing nothing,
+    /// unless it is the last move on the board.
+    pub fn unmake_move(&mut self, token: UndoToken) -> Result<(), RefereeError> {
+        match self.history.last() {
+            Some(undo) if undo.serial == token.serial => self.take_back(),
+            _ => Err(RefereeError::UndoOutOfOrder),
+        }
+    }
+
+    /// Take back the last move, however it was played. Tokens of the moves
+    /// taken back are no longer accepted.
+    pub fn take_back(&mut self) -> Result<(), RefereeError> {
+        let undo = self.history.pop().ok_or(RefereeError::NothingToUndo)?;
+        if let Some(count) = self.seen.get_mut(&self.hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.seen.remove(&self.hash);
+            }
+        }
+        self.position = undo.position;
+        self.hash = undo.hash;
+        self.material = undo.material;
+        self.skipped_turns = undo.skipped_turns;
+        self.moves.truncate(undo.moves);
+        Ok(())
+    }
+
+    /// The result once the game is over by the rules.
+    pub fn outcome(&self) -> Option<(GameResult, Termination)> {
+        if self.position.is_checkmate() {
+            let result = if self.white_to_move() {
+                GameResult::BlackWins
+            } else {
+                GameResult::WhiteWins
+            };
+            return Some((result, Termination::Checkmate));
+        }
+
+        let termination = if self.position.is_stalemate() {
+            Termination::Stalemate
+        } else if self.position.is_insufficient_material() {
+            Termination::InsufficientMaterial
+        } else if self.position.halfmoves() >= 100 {
+            Termination::FiftyMoves
+        } else if self.seen.values().any(|count| *count >= 3) {
+            Termination::Repetition
+        } else {
+            return None;
+        };
+        Some((GameResult::Draw, termination))
+    }
+
+    fn ensure_in_progress(&self) -> Result<(), RefereeError> {
+        match self.outcome() {
+            Some(_) => Err(RefereeError::GameOver),
+            None => Ok(()),
+        }
+    }
+
+    fn play(&mut self, mv: shakmaty::Move, text: &str) -> Result<String, RefereeError> {
+        let illegal = || RefereeError::IllegalMove(text.to_string());
+        let mut san = San::from_move(&self.position, &mv).to_string();
+        let mut position = self.position.clone().play(&mv).map_err(|_| illegal())?;
+        if position.is_checkmate() {
+            san.push('#');
+        } else if position.is_check() {
+            san.push('+');
+        }
+
+        // An extra move may not give check, as the giver's turn is skipped
+        let skip = self.skipped_turns > 0;
+        if skip {
+            if position.is_check() {
+                return Err(illegal());
+            }
+            position = position.swap_turn().map_err(|_| illegal())?;
+            self.skipped_turns -= 1;
+        }
+
+        let hash = zobrist::update(self.hash, &self.position, &mv, &position);
+        let material = self.material.after(self.position.turn(), &mv);
+        let previous = std::mem::replace(&mut self.position, position);
+        self.history.push(Undo {
+            serial: self.next_serial,
+            position: previous,
+            hash: self.hash,
+            material: self.material,
+            skipped_turns: self.skipped_turns + u8::from(skip),
+            moves: self.moves.len(),
+        });
+        self.next_serial += 1;
+        self.hash = hash;
+        self.material = material;
+        self.moves.push(san.clone());
+        if skip {
+            self.moves.push(PASS.to_string());
+        }
+        *self.seen.entry(hash).or_insert(0) += 1;
+        Ok(san)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::odds::{OddsGiver, OddsPiece};
+
+    #[test]
+    fn converts_uci_moves_to_san() {
+        let mut referee = Referee::from_opening("1. e4 e5").unwrap();
+        assert_eq!(referee.play_uci("g1f3").unwrap(), "Nf3");
+        assert_eq!(referee.moves(), ["e4", "e5", "Nf3"]);
+        assert!(!referee.white_to_move());
+        assert_eq!(referee.fullmove_number(), 2);
+        assert_eq!(referee.halfmove_clock(), 1);
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let mut referee = Referee::default();
+        assert_eq!(
+            referee.play_uci("e2e5"),
+            Err(RefereeError::IllegalMove("e2e5".to_string()))
+        );
+        assert!(Referee::from_opening("1. e4 e4").is_err());
+    }
+
+    #[test]
+    fn continues_from_fen() {
+        let mut referee = Referee::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 40").unwrap();
+        assert_eq!(referee.legal_moves().len(), 6);
+        assert!(referee.legal_moves().contains(&"e2e4".to_string()));
+        assert_eq!(referee.play_uci("e2e4").unwrap(), "e4");
+        assert_eq!(referee.fullmove_number(), 40);
+
+        assert!(matches!(Referee::from_fen("not a position"), Err(RefereeError::InvalidFen(_))));
+        assert!(Referee::from_fen("\u{feff}4k3/8/8/8/8/8/4P3/4K3 w - - 0 40\r\n").is_ok());
+        let overlong = format!("4k3/8/8/8/8/8/4P3/4K3 w - - 0 {}", "0".repeat(200));
+        match Referee::from_fen(&overlong) {
+            Err(RefereeError::InvalidFen(quoted)) => assert_eq!(quoted.len(), MAX_FEN_LENGTH + 3),
+            other => panic!("expected InvalidFen, got {:?}", other),
+        }
+        let mated = Referee::from_opening("1. f3 e5 2. g4 Qh4").unwrap();
+        assert!(mated.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn detects_checkmate() {
+        let referee = Referee::from_opening("1. f3 e5 2. g4 Qh4").unwrap();
+        assert_eq!(referee.moves().last().map(String::as_str), Some("Qh4#"));
+        assert_eq!(referee.outcome(), Some((GameResult::BlackWins, Termination::Checkmate)));
+
+        let mut finished = referee.clone();
+        assert_eq!(finished.play_uci("e1f2"), Err(RefereeError::GameOver));
+    }
+
+    #[test]
+    fn odds_games_skip_the_givers_turns() {
+        // Black gives the f-pawn and two extra moves
+        let odds = Odds {
+            giver: OddsGiver::Black,
+            removed: vec![OddsPiece::Pawn],
+            extra_moves: 2,
+        };
+        let mut referee = Referee::from_odds(&odds).unwrap();
+        referee.play_uci("e2e4").unwrap();
+        assert!(referee.white_to_move());
+
+        // Extra moves may not give check
+        assert!(referee.play_uci("d1h5").is_err());
+        referee.play_uci("d2d4").unwrap();
+        assert!(referee.white_to_move());
+        assert_eq!(referee.play_uci("d1h5").unwrap(), "Qh5+");
+        assert!(!referee.white_to_move());
+        assert_eq!(referee.moves(), ["e4", PASS, "d4", PASS, "Qh5+"]);
+        assert!(referee.fen().starts_with("rnbqkbnr/ppppp1pp/"));
+    }
+
+    #[test]
+    fn detects_threefold_repetition() {
+        let mut referee = Referee::default();
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert_eq!(referee.outcome(), None);
+                referee.play_uci(uci).unwrap();
+            }
+        }
+        assert_eq!(referee.outcome(), Some((GameResult::Draw, Termination::Repetition)));
+    }
+
+    #[test]
+    fn incremental_state_matches_recomputing_it() {
+        // Castling both ways, en passant, a capturing promotion and odds
+        let games = [
+            "e2e4 d7d5 e4e5 f7f5 e5f6 g8f6 g1f3 c8g4 f1e2 b8c6 e1g1 d8d6 b1c3 e8c8",
+            "a2a4 b7b5 a4b5 a7a6 b5a6 c8b7 a6b7 g8f6 b7a8q",
+        ];
+        for game in games {
+            let mut referee = Referee::default();
+            let mut tokens = Vec::new();
+            let mut states = Vec::new();
+            for uci in game.split_whitespace() {
+                states.push((referee.fen(), referee.zobrist(), referee.material(), referee.moves().len()));
+                tokens.push(referee.make_move(uci).unwrap());
+                assert_eq!(referee.zobrist(), zobrist::hash(&referee.position), "after {}", uci);
+                assert_eq!(referee.material(), Material::of(&referee.position), "after {}", uci);
+            }
+            while let Some(token) = tokens.pop() {
+                referee.unmake_move(token).unwrap();
+                let (fen, hash, material, moves) = states.pop().unwrap();
+                assert_eq!((referee.fen(), referee.zobrist(), referee.material(), referee.moves().len()), (fen, hash, material, moves));
+            }
+        }
+
+        let odds = Odds { giver: OddsGiver::Black, removed: vec![OddsPiece::Pawn], extra_moves: 1 };
+        let mut referee = Referee::from_odds(&odds).unwrap();
+        let start = referee.fen();
+        let token = referee.make_move("e4").unwrap();
+        assert_eq!(referee.moves(), ["e4", PASS]);
+        assert_eq!(referee.zobrist(), zobrist::hash(&referee.position));
+        referee.unmake_move(token).unwrap();
+        assert_eq!(referee.fen(), start);
+        assert_eq!(referee.material().black.pawns, 7);
+        referee.play_uci("d2d4").unwrap();
+        assert!(referee.white_to_move());
+    }
+
+    #[test]
+    fn material_signature_matches_the_position() {
+        let referee = Referee::from_fen("4k3/8/8/8/8/8/3PP3/R3K3 w - - 0 1").unwrap();
+        let material = Material::from_signature("KRPPk").unwrap();
+        assert_eq!(material, referee.material());
+        assert_eq!(material.key(), referee.material().key());
+        assert_ne!(material.key(), Material::from_signature("KRPkp").unwrap().key());
+        // Kings may be left out
+        assert_eq!(Material::from_signature("RPP"), Some(material));
+        assert_eq!(Material::from_signature("KRx"), None);
+    }
+
+    #[test]
+    fn moves_are_unmade_in_reverse_order_only() {
+        let mut referee = Referee::default();
+        let first = referee.make_move("e2e4").unwrap();
+        let second = referee.make_move("e7e5").unwrap();
+        assert_eq!(referee.unmake_move(first), Err(RefereeError::UndoOutOfOrder));
+        assert_eq!(referee.moves(), ["e4", "e5"]);
+
+        // Tokens of moves taken back another way are no longer accepted,
+        // even once the same move is played again
+        referee.take_back().unwrap();
+        let _again = referee.make_move("e7e5").unwrap();
+        assert_eq!(referee.unmake_move(second), Err(RefereeError::UndoOutOfOrder));
+
+        referee.take_back().unwrap();
+        referee.take_back().unwrap();
+        assert_eq!(referee.take_back(), Err(RefereeError::NothingToUndo));
+        assert_eq!(referee.fen(), Referee::default().fen());
+    }
+
+    #[test]
+    fn repetitions_are_forgotten_when_moves_are_taken_back() {
+        let mut referee = Referee::default();
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                referee.play_uci(uci).unwrap();
+            }
+        }
+        assert_eq!(referee.outcome(), Some((GameResult::Draw, Termination::Repetition)));
+        referee.take_back().unwrap();
+        assert_eq!(referee.outcome(), None);
+        assert_eq!(referee.play_uci("f6g8").unwrap(), "Ng8");
+        assert_eq!(referee.outcome(), Some((GameResult::Draw, Termination::Repetition)));
+    }
+}
@@ -1,4 +1,8 @@
-use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::time_source::{self, TimeSource};
 
 #[derive(Debug, Clone)]
 pub struct TimeControl {
@@ -7,33 +11,52 @@ pub struct TimeControl {
     pub delay: Duration,
 }
 
-#[derive(Debug, Clone)]
+/// One side's clock. The start of the running period is kept as a
+/// `time_source` reading, so a clock can be serialized and picked up by
+/// another process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerClock {
     pub remaining_time: Duration,
-    pub last_move_time: Option<Instant>,
+    pub last_move_ms: Option<u64>,
     pub is_running: bool,
+    #[serde(skip, default = "process_time")]
+    time: Arc<dyn TimeSource>,
+}
+
+fn process_time() -> Arc<dyn TimeSource> {
+    Arc::new(time_source::monotonic().clone())
 }
 
 impl PlayerClock {
     pub fn new(initial_time: Duration) -> Self {
+        Self::with_time_source(initial_time, process_time())
+    }
+
+    /// A clock reading `time` instead of the process's monotonic time.
+    pub fn with_time_source(initial_time: Duration, time: Arc<dyn TimeSource>) -> Self {
         Self {
             remaining_time: initial_time,
-            last_move_time: None,
+            last_move_ms: None,
             is_running: false,
+            time,
         }
     }
 
+    fn elapsed(&self) -> Option<Duration> {
+        let last_move_ms = self.last_move_ms?;
+        Some(Duration::from_millis(self.time.now_ms().saturating_sub(last_move_ms)))
+    }
+
     pub fn start(&mut self) {
         if self.is_running {
             return;
         }
         self.is_running = true;
-        self.last_move_time = Some(Instant::now());
+        self.last_move_ms = Some(self.time.now_ms());
     }
 
     pub fn stop(&mut self) {
-        if let Some(last_move_time) = self.last_move_time {
-            let elapsed = last_move_time.elapsed();
+        if let Some(elapsed) = self.elapsed() {
             self.remaining_time = self.remaining_time.saturating_sub(elapsed);
         }
         self.is_running = false;
@@ -44,8 +67,7 @@ impl PlayerClock {
     }
 
     pub fn apply_delay(&mut self, delay: Duration) {
-        if let Some(last_move_time) = self.last_move_time {
-            let elapsed = last_move_time.elapsed();
+        if let Some(elapsed) = self.elapsed() {
             if elapsed < delay {
                 self.remaining_time += delay - elapsed;
             }
@@ -54,8 +76,8 @@ impl PlayerClock {
 
     pub fn get_real_time_remaining(&self) -> Duration {
         if self.is_running {
-            if let Some(last_move_time) = self.last_move_time {
-                return self.remaining_time.saturating_sub(last_move_time.elapsed());
+            if let Some(elapsed) = self.elapsed() {
+                return self.remaining_time.saturating_sub(elapsed);
             }
         }
         self.remaining_time
@@ -63,7 +85,7 @@ impl PlayerClock {
 
     pub fn set_remaining_time(&mut self, time: Duration) {
         self.remaining_time = time;
-        self.last_move_time = None;
+        self.last_move_ms = None;
         self.is_running = false;
     }
 
@@ -0,0 +1,80 @@
+//! Where game clocks read the time.
+//!
+//! Clock arithmetic must not follow the host clock when it is stepped (by
+//! NTP, or by hand), or a move can take negative time or a whole hour. The
+//! time here is `Instant`-based, so it only moves forward, but is reported
+//! in milliseconds since the Unix epoch as of when the process started:
+//! readings can be stored, sent to clients and read back by another server,
+//! off only by how far the hosts' wall clocks were apart at startup.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime};
+
+pub trait TimeSource: fmt::Debug + Send + Sync {
+    /// Milliseconds since the Unix epoch, never less than a previous reading
+    fn now_ms(&self) -> u64;
+}
+
+/// The host's monotonic clock, anchored to its wall clock once.
+#[derive(Debug, Clone)]
+pub struct MonotonicTime {
+    anchor_ms: u64,
+    anchor: Instant,
+}
+
+impl MonotonicTime {
+    pub fn new() -> Self {
+        let anchor_ms = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_millis() as u64)
+            .unwrap_or(0);
+        Self { anchor_ms, anchor: Instant::now() }
+    }
+}
+
+impl Default for MonotonicTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for MonotonicTime {
+    fn now_ms(&self) -> u64 {
+        self.anchor_ms + self.anchor.elapsed().as_millis() as u64
+    }
+}
+
+/// A time that only moves when told to, for tests.
+#[derive(Debug, Default)]
+pub struct ManualTime {
+    now_ms: AtomicU64,
+}
+
+impl ManualTime {
+    pub fn new(now_ms: u64) -> Self {
+        Self { now_ms: AtomicU64::new(now_ms) }
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now_ms(&self) -> u64 {
+        self.now_ms.load(Ordering::SeqCst)
+    }
+}
+
+static PROCESS_TIME: OnceLock<MonotonicTime> = OnceLock::new();
+
+/// The process-wide monotonic time, anchored on first use.
+pub fn monotonic() -> &'static MonotonicTime {
+    PROCESS_TIME.get_or_init(MonotonicTime::new)
+}
+
+pub fn now_ms() -> u64 {
+    monotonic().now_ms()
+}
@@ -0,0 +1,201 @@
+//! Training Module
+//!
+//! Coordinate and board-vision drills played on an otherwise empty board:
+//! generation of random exercises and checking of the answers.
+
+#[cfg(feature = "random")]
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shakmaty::{attacks, Bitboard, Square};
+
+/// Most pawns placed in the way of a piece in vision exercises
+pub const MAX_BLOCKERS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Drill {
+    /// Click the named square
+    FindSquare,
+    /// Name the highlighted square
+    NameSquare,
+    /// Tell whether a square is light or dark
+    SquareColor,
+    /// List every square a piece attacks
+    PieceVision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionPiece {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+#[cfg(feature = "random")]
+const VISION_PIECES: [VisionPiece; 5] = [
+    VisionPiece::Knight,
+    VisionPiece::Bishop,
+    VisionPiece::Rook,
+    VisionPiece::Queen,
+    VisionPiece::King,
+];
+
+/// One question of a drill. Blockers are opponent pawns: the piece attacks
+/// their squares but not the squares behind them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exercise {
+    /// The square asked about, or the square of the piece
+    pub square: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub piece: Option<VisionPiece>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blockers: Vec<String>,
+}
+
+impl Drill {
+    #[cfg(feature = "random")]
+    pub fn generate<R: Rng>(self, rng: &mut R) -> Exercise {
+        let square = Square::new(rng.gen_range(0..64));
+        if self != Drill::PieceVision {
+            return Exercise {
+                square: square.to_string(),
+                piece: None,
+                blockers: Vec::new(),
+            };
+        }
+
+        let piece = VISION_PIECES[rng.gen_range(0..VISION_PIECES.len())];
+        // Only sliding pieces are stopped by blockers; put them on its lines
+        let mut blockers = Vec::new();
+        if matches!(piece, VisionPiece::Bishop | VisionPiece::Rook | VisionPiece::Queen) {
+            let mut lines: Vec<Square> = attacked(piece, square, Bitboard(0)).into_iter().collect();
+            for _ in 0..rng.gen_range(0..=MAX_BLOCKERS) {
+                blockers.push(lines.swap_remove(rng.gen_range(0..lines.len())).to_string());
+            }
+        }
+
+        Exercise {
+            square: square.to_string(),
+            piece: Some(piece),
+            blockers,
+        }
+    }
+
+    /// The expected answer: a square name, `light` or `dark`, or the attacked
+    /// squares from a1 to h8 separated by spaces. `None` for exercises that
+    /// do not belong to this drill.
+    pub fn solution(self, exercise: &Exercise) -> Option<String> {
+        let square: Square = exercise.square.parse().ok()?;
+        match self {
+            Drill::FindSquare | Drill::NameSquare => Some(square.to_string()),
+            Drill::SquareColor => Some(if square.is_light() { "light" } else { "dark" }.to_string()),
+            Drill::PieceVision => {
+                let squares = vision(exercise)?;
+                Some(squares.into_iter().map(|sq| sq.to_string()).collect::<Vec<_>>().join(" "))
+            }
+        }
+    }
+
+    /// Whether `answer` solves `exercise`. Case and, for vision exercises,
+    /// the order of the squares and their separators do not matter.
+    pub fn check(self, exercise: &Exercise, answer: &str) -> bool {
+        if self == Drill::PieceVision {
+            let given = answer
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|token| !token.is_empty())
+                .try_fold(Bitboard(0), |squares, token| {
+                    token
+                        .to_ascii_lowercase()
+                        .parse::<Square>()
+                        .ok()
+                        .map(|sq| squares | Bitboard::from_square(sq))
+                });
+            return given.is_some() && given == vision(exercise);
+        }
+        self.solution(exercise)
+            .is_some_and(|solution| solution.eq_ignore_ascii_case(answer.trim()))
+    }
+}
+
+fn vision(exercise: &Exercise) -> Option<Bitboard> {
+    let square: Square = exercise.square.parse().ok()?;
+    let occupied = exercise
+        .blockers
+        .iter()
+        .try_fold(Bitboard(0), |squares, blocker| {
+            blocker.parse::<Square>().ok().map(|sq| squares | Bitboard::from_square(sq))
+        })?;
+    Some(attacked(exercise.piece?, square, occupied))
+}
+
+fn attacked(piece: VisionPiece, square: Square, occupied: Bitboard) -> Bitboard {
+    match piece {
+        VisionPiece::Knight => attacks::knight_attacks(square),
+        VisionPiece::Bishop => attacks::bishop_attacks(square, occupied),
+        VisionPiece::Rook => attacks::rook_attacks(square, occupied),
+        VisionPiece::Queen => attacks::queen_attacks(square, occupied),
+        VisionPiece::King => attacks::king_attacks(square),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vision_exercise(square: &str, piece: VisionPiece, blockers: &[&str]) -> Exercise {
+        Exercise {
+            square: square.to_string(),
+            piece: Some(piece),
+            blockers: blockers.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn checks_coordinates_and_colors() {
+        let exercise = Exercise {
+            square: "e4".to_string(),
+            piece: None,
+            blockers: Vec::new(),
+        };
+        assert!(Drill::FindSquare.check(&exercise, "e4"));
+        assert!(Drill::NameSquare.check(&exercise, " E4 "));
+        assert!(!Drill::NameSquare.check(&exercise, "e5"));
+        assert_eq!(Drill::SquareColor.solution(&exercise).as_deref(), Some("light"));
+        assert!(Drill::SquareColor.check(&exercise, "Light"));
+        assert!(!Drill::SquareColor.check(&exercise, "dark"));
+    }
+
+    #[test]
+    fn blockers_stop_sliding_pieces() {
+        let rook = vision_exercise("a1", VisionPiece::Rook, &["a3", "c1"]);
+        assert_eq!(Drill::PieceVision.solution(&rook).as_deref(), Some("b1 c1 a2 a3"));
+        assert!(Drill::PieceVision.check(&rook, "a3, a2,b1 C1"));
+        assert!(!Drill::PieceVision.check(&rook, "a2 a3 b1"));
+        assert!(!Drill::PieceVision.check(&rook, "a2 a3 b1 c1 z9"));
+
+        let knight = vision_exercise("a1", VisionPiece::Knight, &[]);
+        assert!(Drill::PieceVision.check(&knight, "b3 c2"));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn generated_exercises_can_be_solved() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for drill in [Drill::FindSquare, Drill::NameSquare, Drill::SquareColor, Drill::PieceVision] {
+            for _ in 0..50 {
+                let exercise = drill.generate(&mut rng);
+                assert_eq!(exercise.piece.is_some(), drill == Drill::PieceVision);
+                assert!(exercise.blockers.len() <= MAX_BLOCKERS);
+                assert!(!exercise.blockers.contains(&exercise.square));
+
+                let solution = drill.solution(&exercise).unwrap();
+                assert!(drill.check(&exercise, &solution));
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! JavaScript bindings of the rules, so the web frontend accepts exactly the
+//! moves the server does. Positions are passed around as FEN.
+
+use wasm_bindgen::prelude::*;
+
+use crate::pgn::{parse_pgn, validate_game};
+use crate::referee::{Referee, Termination};
+
+fn referee(fen: &str) -> Result<Referee, JsError> {
+    Referee::from_fen(fen).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Legal moves of the position in UCI notation.
+#[wasm_bindgen(js_name = legalMoves)]
+pub fn legal_moves(fen: &str) -> Result<Vec<String>, JsError> {
+    Ok(referee(fen)?.legal_moves())
+}
+
+/// The position after a move given in UCI (`e2e4`) or SAN (`e4`).
+#[wasm_bindgen(js_name = playMove)]
+pub fn play_move(fen: &str, notation: &str) -> Result<String, JsError> {
+    let mut referee = referee(fen)?;
+    if referee.play_uci(notation).is_err() {
+        referee.play_san(notation).map_err(|e| JsError::new(&e.to_string()))?;
+    }
+    Ok(referee.fen())
+}
+
+/// Move in SAN, e.g. for a move list, without playing it.
+#[wasm_bindgen(js_name = toSan)]
+pub fn to_san(fen: &str, uci: &str) -> Result<String, JsError> {
+    referee(fen)?.play_uci(uci).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// How the game ended by the rules in this position (`checkmate`,
+/// `stalemate`, `insufficient_material` or `fifty_moves`), if it did.
+/// Repetitions need the game's history and are left to the server.
+#[wasm_bindgen(js_name = outcome)]
+pub fn outcome(fen: &str) -> Result<Option<String>, JsError> {
+    let termination = referee(fen)?.outcome().map(|(_, termination)| match termination {
+        Termination::Checkmate => "checkmate",
+        Termination::Stalemate => "stalemate",
+        Termination::InsufficientMaterial => "insufficient_material",
+        Termination::FiftyMoves => "fifty_moves",
+        Termination::Repetition => "repetition",
+    });
+    Ok(termination.map(str::to_string))
+}
+
+/// Final position of a PGN game, failing on the first illegal move.
+#[wasm_bindgen(js_name = validatePgn)]
+pub fn validate_pgn(pgn: &str) -> Result<String, JsError> {
+    let parsed = parse_pgn(pgn).map_err(|e| JsError::new(&e.to_string()))?;
+    let validated = validate_game(&parsed).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(validated.final_fen)
+}
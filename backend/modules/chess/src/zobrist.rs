@@ -0,0 +1,127 @@
+//! Zobrist hashing of positions, updated move by move.
+//!
+//! The keys are generated at compile time from a fixed seed, so a hash is
+//! the same across runs and builds and may be stored.
+
+use shakmaty::{Bitboard, Chess, Color, EnPassantMode, File, Move, Piece, Position, Role, Square};
+
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    /// By the square of the rook that may castle
+    castling: [u64; 64],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+}
+
+const KEYS: Keys = generate();
+
+const fn generate() -> Keys {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 64],
+        en_passant: [0; 8],
+        black_to_move: 0,
+    };
+
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            state = xorshift(state);
+            keys.pieces[piece][square] = state;
+            square += 1;
+        }
+        piece += 1;
+    }
+    let mut square = 0;
+    while square < 64 {
+        state = xorshift(state);
+        keys.castling[square] = state;
+        square += 1;
+    }
+    let mut file = 0;
+    while file < 8 {
+        state = xorshift(state);
+        keys.en_passant[file] = state;
+        file += 1;
+    }
+    keys.black_to_move = xorshift(state);
+    keys
+}
+
+const fn xorshift(mut state: u64) -> u64 {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+}
+
+fn piece(color: Color, role: Role, square: Square) -> u64 {
+    let piece = if color.is_white() { 0 } else { 6 } + role as usize - 1;
+    KEYS.pieces[piece][usize::from(square)]
+}
+
+fn castling(rights: Bitboard) -> u64 {
+    rights.into_iter().fold(0, |hash, rook| hash ^ KEYS.castling[usize::from(rook)])
+}
+
+/// Castling rights, en passant file and side to move; everything but the
+/// pieces.
+fn state(position: &Chess) -> u64 {
+    let mut hash = castling(position.castles().castling_rights());
+    if let Some(square) = position.ep_square(EnPassantMode::Legal) {
+        hash ^= KEYS.en_passant[usize::from(square.file())];
+    }
+    if position.turn().is_black() {
+        hash ^= KEYS.black_to_move;
+    }
+    hash
+}
+
+/// Hash of a position from scratch. Positions that are the same for the
+/// repetition rule hash the same: move counters are left out and an en
+/// passant square counts only if a capture there is legal.
+pub fn hash(position: &Chess) -> u64 {
+    let board = position.board();
+    let mut hash = state(position);
+    for color in [Color::White, Color::Black] {
+        for role in Role::ALL {
+            for square in board.by_piece(Piece { color, role }) {
+                hash ^= piece(color, role, square);
+            }
+        }
+    }
+    hash
+}
+
+/// Hash of `after`, reached by playing `mv` from `before` whose hash is
+/// `hash`, touching only the pieces that moved.
+pub fn update(hash: u64, before: &Chess, mv: &Move, after: &Chess) -> u64 {
+    let us = before.turn();
+    let them = !us;
+    let mut hash = hash ^ state(before) ^ state(after);
+    match *mv {
+        Move::Normal { role, from, capture, to, promotion } => {
+            hash ^= piece(us, role, from) ^ piece(us, promotion.unwrap_or(role), to);
+            if let Some(captured) = capture {
+                hash ^= piece(them, captured, to);
+            }
+        }
+        Move::EnPassant { from, to } => {
+            let captured = Square::from_coords(to.file(), from.rank());
+            hash ^= piece(us, Role::Pawn, from) ^ piece(us, Role::Pawn, to) ^ piece(them, Role::Pawn, captured);
+        }
+        Move::Castle { king, rook } => {
+            let (king_file, rook_file) = if rook.file() > king.file() { (File::G, File::F) } else { (File::C, File::D) };
+            hash ^= piece(us, Role::King, king)
+                ^ piece(us, Role::King, Square::from_coords(king_file, king.rank()))
+                ^ piece(us, Role::Rook, rook)
+                ^ piece(us, Role::Rook, Square::from_coords(rook_file, rook.rank()));
+        }
+        Move::Put { role, to } => {
+            hash ^= piece(us, role, to);
+        }
+    }
+    hash
+}
@@ -38,4 +38,40 @@ mod tests {
         let empty_map = empty_board.piece_map();
         assert_eq!(empty_map.len(), 0);
     }
+
+    #[test]
+    fn test_attackers_and_slider_blockers() {
+        let e1 = Square { value: 4 };
+        let e2 = Square { value: 12 };
+        let d2 = Square { value: 11 };
+        let b4 = Square { value: 25 };
+        let e5 = Square { value: 36 };
+        let f3 = Square { value: 21 };
+        let e8 = Square { value: 60 };
+
+        let board = Board::empty()
+            .put_or_replace(Piece { color: Color::White, role: Role::King }, e1)
+            .put_or_replace(Piece { color: Color::White, role: Role::Knight }, e2)
+            .put_or_replace(Piece { color: Color::White, role: Role::Pawn }, d2)
+            .put_or_replace(Piece { color: Color::Black, role: Role::Rook }, e8)
+            .put_or_replace(Piece { color: Color::Black, role: Role::Bishop }, b4)
+            .put_or_replace(Piece { color: Color::Black, role: Role::Knight }, f3);
+
+        // The knight on e2 is pinned by the rook; the pawn on d2 shields the
+        // king from the bishop but a second piece would make it no pin
+        assert_eq!(board.slider_blockers(e1, Color::White), e2.bitboard() | d2.bitboard());
+        let crowded = board.put_or_replace(Piece { color: Color::White, role: Role::Pawn }, e5);
+        assert_eq!(crowded.slider_blockers(e1, Color::White), d2.bitboard());
+
+        assert_eq!(board.attackers(e1, Color::Black, board.occupied), f3.bitboard());
+        assert!(board.attacks(e1, Color::Black));
+        let without_knight = board.discard_by_square(f3);
+        assert!(!without_knight.attacks(e1, Color::Black));
+        assert_eq!(
+            without_knight.attackers(e1, Color::Black, without_knight.occupied ^ e2.bitboard()),
+            e8.bitboard()
+        );
+        assert_eq!(board.attackers(e2, Color::White, board.occupied), e1.bitboard());
+        assert_eq!(board.attackers(Square { value: 20 }, Color::White, board.occupied), d2.bitboard());
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,89 @@
+//! The fuzz targets' seeds, run on every test: the checks of
+//! `fuzz/fuzz_targets` against each checked-in input, and the outcome the
+//! real-world ones should have.
+
+use chess::{parse_pgn, validate_game, PgnError, Referee};
+use std::fs;
+use std::path::PathBuf;
+
+fn seeds(target: &str) -> Vec<(String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fuzz/seeds").join(target);
+    let mut seeds: Vec<(String, String)> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e))
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    seeds.sort();
+    seeds
+}
+
+fn import(pgn: &str) -> Result<usize, PgnError> {
+    let game = validate_game(&parse_pgn(pgn)?)?;
+    assert_eq!(game.moves.len(), game.ply_count);
+    Referee::from_fen(&game.final_fen).expect("final position reads back");
+    Ok(game.ply_count)
+}
+
+#[test]
+fn pgn_seeds_import_or_fail_cleanly() {
+    let imported = [
+        ("bom_crlf.pgn", 41),
+        ("compact_move_numbers.pgn", 7),
+        ("deep_nesting.pgn", 2),
+        ("escape_lines.pgn", 4),
+        ("escaped_tags.pgn", 2),
+        ("lichess_clocks.pgn", 10),
+        ("nested_variations.pgn", 87),
+        ("nonstandard_results.pgn", 28),
+        ("only_tags.pgn", 0),
+        ("unterminated_comment.pgn", 2),
+        ("zero_castling.pgn", 12),
+    ];
+    for (name, pgn) in seeds("parse_pgn") {
+        let result = import(&pgn);
+        match imported.iter().find(|(seed, _)| *seed == name) {
+            Some(&(_, plies)) => assert_eq!(result.ok(), Some(plies), "{}", name),
+            None => assert!(result.is_err(), "{} should not import", name),
+        }
+    }
+}
+
+#[test]
+fn fen_seeds_read_back_as_themselves() {
+    let mut read = Vec::new();
+    for (name, fen) in seeds("parse_fen") {
+        let Ok(referee) = Referee::from_fen(&fen) else {
+            continue;
+        };
+        let reread = Referee::from_fen(&referee.fen()).expect("written FEN reads back");
+        assert_eq!(reread.fen(), referee.fen(), "{}", name);
+        assert_eq!(reread.zobrist(), referee.zobrist(), "{}", name);
+
+        for uci in referee.legal_moves() {
+            let mut next = referee.clone();
+            next.play_uci(&uci).expect("legal move plays");
+            let reached = Referee::from_fen(&next.fen()).expect("written FEN reads back");
+            assert_eq!(next.zobrist(), reached.zobrist(), "{} after {}", name, uci);
+            next.take_back().expect("move takes back");
+            assert_eq!(next.zobrist(), referee.zobrist(), "{}", name);
+        }
+        read.push(name);
+    }
+    assert_eq!(
+        read,
+        [
+            "bom_crlf.fen",
+            "en_passant.fen",
+            "huge_counters.fen",
+            "kiwipete.fen",
+            "no_counters.fen",
+            "pointless_en_passant.fen",
+            "promotion.fen",
+            "shredder_castling.fen",
+            "start.fen",
+        ]
+    );
+}
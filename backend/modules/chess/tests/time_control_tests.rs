@@ -1,4 +1,7 @@
-use chess::{TimeControl, PlayerClock};
+#![cfg(feature = "clock")]
+
+use chess::{ManualTime, PlayerClock, TimeControl};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(test)]
@@ -35,4 +38,29 @@ mod tests {
         clock.set_remaining_time(Duration::from_secs(0));
         assert!(clock.time_out());
     }
+
+    #[test]
+    fn test_clock_reads_its_time_source() {
+        let time = Arc::new(ManualTime::new(1_000_000));
+        let mut clock = PlayerClock::with_time_source(Duration::from_secs(60), time.clone());
+
+        clock.start();
+        time.advance(1_500);
+        assert_eq!(clock.get_real_time_remaining(), Duration::from_millis(58_500));
+        clock.stop();
+        time.advance(10_000);
+        assert_eq!(clock.get_real_time_remaining(), Duration::from_millis(58_500));
+    }
+
+    #[test]
+    fn test_running_clock_survives_serialization() {
+        let mut clock = PlayerClock::new(Duration::from_secs(60));
+        clock.start();
+
+        let restored: PlayerClock = serde_json::from_str(&serde_json::to_string(&clock).unwrap()).unwrap();
+        assert!(restored.is_running);
+        assert_eq!(restored.last_move_ms, clock.last_move_ms);
+        assert!(restored.get_real_time_remaining() <= Duration::from_secs(60));
+        assert!(restored.get_real_time_remaining() > Duration::from_secs(59));
+    }
 }
@@ -58,6 +58,9 @@ pub struct Model {
     /// Original PGN string if game was imported
     #[sea_orm(column_type = "Text", nullable)]
     pub original_pgn: Option<String>,
+    /// Handicap of an odds game, `None` for the standard starting position
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub odds: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
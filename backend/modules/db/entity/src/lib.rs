@@ -2,6 +2,46 @@ pub mod prelude;
 pub mod game;
 pub mod player;
 pub mod refresh_token;
+pub mod player_role;
+pub mod moderation_report;
+pub mod moderation_action;
+pub mod player_rating;
+pub mod leaderboard_entry;
+pub mod player_stats;
+pub mod rating_history;
+pub mod tournament;
+pub mod tournament_template;
+pub mod player_trophy;
+pub mod game_annotation;
+pub mod player_friend;
+pub mod engine_match;
+pub mod engine_match_game;
+pub mod training_session;
+pub mod game_attestation;
+pub mod game_import;
+pub mod player_wallet;
+pub mod game_dispute;
+pub mod rating_recalculation;
+pub mod game_archive;
+pub mod indexed_game;
+pub mod game_position;
+pub mod game_event;
+pub mod game_snapshot;
+pub mod webhook_subscription;
+pub mod webhook_delivery;
+pub mod player_preferences;
+pub mod account_closure;
+pub mod import_job;
+pub mod import_quarantine;
+pub mod title_verification;
+pub mod title_verification_event;
+pub mod club;
+pub mod club_member;
+pub mod ladder_challenge;
+pub mod coaching_link;
+pub mod coaching_assignment;
+pub mod player_fingerprint;
+pub mod account_link;
 
 #[path = "../user.rs"]
 pub mod user;
\ No newline at end of file
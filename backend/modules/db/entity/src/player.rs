@@ -2,6 +2,8 @@
 
 use sea_orm::entity::prelude::*;
 
+use super::title_verification::ChessTitle;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "player")]
 pub struct Model {
@@ -20,8 +22,11 @@ pub struct Model {
     pub real_name: String,
     pub location: Option<String>,
     pub fide_rating: Option<i32>,
+    pub fide_id: Option<i64>,
     pub social_links: Option<Vec<String>>,
-    pub is_enabled: bool
+    pub is_enabled: bool,
+    /// Verified title, set when a moderator approves a title verification
+    pub title: Option<ChessTitle>,
 }
 
 
@@ -3,3 +3,43 @@
 pub use super::game::Entity as Game;
 pub use super::player::Entity as Player;
 pub use super::refresh_token::Entity as RefreshToken;
+pub use super::player_role::Entity as PlayerRole;
+pub use super::moderation_report::Entity as ModerationReport;
+pub use super::moderation_action::Entity as ModerationAction;
+pub use super::player_rating::Entity as PlayerRating;
+pub use super::leaderboard_entry::Entity as LeaderboardEntry;
+pub use super::player_stats::Entity as PlayerStats;
+pub use super::rating_history::Entity as RatingHistory;
+pub use super::tournament::Entity as Tournament;
+pub use super::tournament_template::Entity as TournamentTemplate;
+pub use super::player_trophy::Entity as PlayerTrophy;
+pub use super::game_annotation::Entity as GameAnnotation;
+pub use super::player_friend::Entity as PlayerFriend;
+pub use super::engine_match::Entity as EngineMatch;
+pub use super::engine_match_game::Entity as EngineMatchGame;
+pub use super::training_session::Entity as TrainingSession;
+pub use super::game_attestation::Entity as GameAttestation;
+pub use super::game_import::Entity as GameImport;
+pub use super::player_wallet::Entity as PlayerWallet;
+pub use super::game_dispute::Entity as GameDispute;
+pub use super::rating_recalculation::Entity as RatingRecalculation;
+pub use super::game_archive::Entity as GameArchive;
+pub use super::indexed_game::Entity as IndexedGame;
+pub use super::game_position::Entity as GamePosition;
+pub use super::game_event::Entity as GameEvent;
+pub use super::game_snapshot::Entity as GameSnapshot;
+pub use super::webhook_subscription::Entity as WebhookSubscription;
+pub use super::webhook_delivery::Entity as WebhookDelivery;
+pub use super::player_preferences::Entity as PlayerPreferences;
+pub use super::account_closure::Entity as AccountClosure;
+pub use super::import_job::Entity as ImportJob;
+pub use super::import_quarantine::Entity as ImportQuarantine;
+pub use super::title_verification::Entity as TitleVerification;
+pub use super::title_verification_event::Entity as TitleVerificationEvent;
+pub use super::club::Entity as Club;
+pub use super::club_member::Entity as ClubMember;
+pub use super::ladder_challenge::Entity as LadderChallenge;
+pub use super::coaching_link::Entity as CoachingLink;
+pub use super::coaching_assignment::Entity as CoachingAssignment;
+pub use super::player_fingerprint::Entity as PlayerFingerprint;
+pub use super::account_link::Entity as AccountLink;
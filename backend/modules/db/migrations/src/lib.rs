@@ -8,6 +8,38 @@ mod m20250604_160341_create_games_and_moves;
 mod m20250605_090000_add_game_search_indexes;
 mod m20260127_create_refresh_tokens_table;
 mod m20260127_180000_add_game_imported_flag;
+mod m20261016_090000_create_moderation_tables;
+mod m20261016_100000_create_leaderboard_tables;
+mod m20261016_110000_create_player_stats_table;
+mod m20261016_120000_add_rating_history;
+mod m20261016_130000_create_tournaments_table;
+mod m20261016_140000_create_tournament_templates;
+mod m20261016_150000_create_player_trophies;
+mod m20261016_160000_create_game_annotations;
+mod m20261016_170000_create_engine_matches;
+mod m20261016_180000_add_game_odds;
+mod m20261016_190000_create_training_sessions;
+mod m20261016_200000_create_game_imports;
+mod m20261016_210000_create_game_attestations;
+mod m20261016_220000_create_player_wallets;
+mod m20261016_230000_add_player_fide_id;
+mod m20261016_240000_create_game_disputes;
+mod m20261016_250000_create_rating_recalculations;
+mod m20261016_260000_create_game_archive;
+mod m20261016_270000_add_search_indexes;
+mod m20261016_280000_create_position_index;
+mod m20261016_290000_create_game_events;
+mod m20261016_300000_create_webhooks;
+mod m20261016_310000_create_player_preferences;
+mod m20261016_320000_create_account_closures;
+mod m20261016_330000_add_template_arena_pairing;
+mod m20261016_340000_create_import_quarantine;
+mod m20261016_350000_create_title_verifications;
+mod m20261016_360000_create_clubs;
+mod m20261016_370000_create_coaching;
+mod m20261016_380000_add_integrity_signals;
+mod m20261016_390000_create_account_links;
+mod m20261016_400000_add_engine_match_heartbeat;
 
 
 pub struct Migrator;
@@ -24,6 +56,38 @@ impl MigratorTrait for Migrator {
             Box::new(m20250605_090000_add_game_search_indexes::Migration),
             Box::new(m20260127_create_refresh_tokens_table::Migration),
             Box::new(m20260127_180000_add_game_imported_flag::Migration),
+            Box::new(m20261016_090000_create_moderation_tables::Migration),
+            Box::new(m20261016_100000_create_leaderboard_tables::Migration),
+            Box::new(m20261016_110000_create_player_stats_table::Migration),
+            Box::new(m20261016_120000_add_rating_history::Migration),
+            Box::new(m20261016_130000_create_tournaments_table::Migration),
+            Box::new(m20261016_140000_create_tournament_templates::Migration),
+            Box::new(m20261016_150000_create_player_trophies::Migration),
+            Box::new(m20261016_160000_create_game_annotations::Migration),
+            Box::new(m20261016_170000_create_engine_matches::Migration),
+            Box::new(m20261016_180000_add_game_odds::Migration),
+            Box::new(m20261016_190000_create_training_sessions::Migration),
+            Box::new(m20261016_200000_create_game_imports::Migration),
+            Box::new(m20261016_210000_create_game_attestations::Migration),
+            Box::new(m20261016_220000_create_player_wallets::Migration),
+            Box::new(m20261016_230000_add_player_fide_id::Migration),
+            Box::new(m20261016_240000_create_game_disputes::Migration),
+            Box::new(m20261016_250000_create_rating_recalculations::Migration),
+            Box::new(m20261016_260000_create_game_archive::Migration),
+            Box::new(m20261016_270000_add_search_indexes::Migration),
+            Box::new(m20261016_280000_create_position_index::Migration),
+            Box::new(m20261016_290000_create_game_events::Migration),
+            Box::new(m20261016_300000_create_webhooks::Migration),
+            Box::new(m20261016_310000_create_player_preferences::Migration),
+            Box::new(m20261016_320000_create_account_closures::Migration),
+            Box::new(m20261016_330000_add_template_arena_pairing::Migration),
+            Box::new(m20261016_340000_create_import_quarantine::Migration),
+            Box::new(m20261016_350000_create_title_verifications::Migration),
+            Box::new(m20261016_360000_create_clubs::Migration),
+            Box::new(m20261016_370000_create_coaching::Migration),
+            Box::new(m20261016_380000_add_integrity_signals::Migration),
+            Box::new(m20261016_390000_create_account_links::Migration),
+            Box::new(m20261016_400000_add_engine_match_heartbeat::Migration),
         ]
     }
 }
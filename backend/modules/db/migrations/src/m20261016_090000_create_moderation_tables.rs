@@ -0,0 +1,335 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(PlayerRoleKind::Type)
+                    .values([PlayerRoleKind::Moderator, PlayerRoleKind::Arbiter, PlayerRoleKind::Admin])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ReportReason::Type)
+                    .values([
+                        ReportReason::Cheating,
+                        ReportReason::Abuse,
+                        ReportReason::Spam,
+                        ReportReason::Sandbagging,
+                        ReportReason::OffensiveUsername,
+                        ReportReason::Other,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ReportStatus::Type)
+                    .values([ReportStatus::Open, ReportStatus::Resolved, ReportStatus::Dismissed])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ModerationActionKind::Type)
+                    .values([ModerationActionKind::Warn, ModerationActionKind::Mute, ModerationActionKind::Ban])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Roles granted to players on top of regular access
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerRole::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerRole::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PlayerRole::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerRole::Role).custom(PlayerRoleKind::Type).not_null())
+                    .col(ColumnDef::new(PlayerRole::GrantedBy).uuid().null())
+                    .col(
+                        ColumnDef::new(PlayerRole::GrantedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_role_player")
+                            .from((Smdb, PlayerRole::Table), PlayerRole::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_player_role_player_role")
+                    .table((Smdb, PlayerRole::Table))
+                    .col(PlayerRole::PlayerId)
+                    .col(PlayerRole::Role)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Reports filed by players and flags raised by moderators
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, ModerationReport::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(ModerationReport::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ModerationReport::ReporterId).uuid().not_null())
+                    .col(ColumnDef::new(ModerationReport::ReportedPlayerId).uuid().not_null())
+                    .col(ColumnDef::new(ModerationReport::GameId).uuid().null())
+                    .col(ColumnDef::new(ModerationReport::Reason).custom(ReportReason::Type).not_null())
+                    .col(ColumnDef::new(ModerationReport::Details).text().null())
+                    .col(ColumnDef::new(ModerationReport::ChatContext).json_binary().null())
+                    .col(
+                        ColumnDef::new(ModerationReport::IsGameFlag)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(ModerationReport::Status).custom(ReportStatus::Type).not_null())
+                    .col(ColumnDef::new(ModerationReport::ResolvedBy).uuid().null())
+                    .col(ColumnDef::new(ModerationReport::ResolutionNote).text().null())
+                    .col(
+                        ColumnDef::new(ModerationReport::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModerationReport::ResolvedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_moderation_report_reported_player")
+                            .from((Smdb, ModerationReport::Table), ModerationReport::ReportedPlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_moderation_report_game")
+                            .from((Smdb, ModerationReport::Table), ModerationReport::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The moderator queue reads open reports oldest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_moderation_report_status_created_at")
+                    .table((Smdb, ModerationReport::Table))
+                    .col(ModerationReport::Status)
+                    .col(ModerationReport::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Warnings, mutes and bans applied to accounts
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, ModerationAction::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(ModerationAction::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ModerationAction::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(ModerationAction::ModeratorId).uuid().not_null())
+                    .col(ColumnDef::new(ModerationAction::Kind).custom(ModerationActionKind::Type).not_null())
+                    .col(ColumnDef::new(ModerationAction::Reason).text().not_null())
+                    .col(ColumnDef::new(ModerationAction::ReportId).uuid().null())
+                    .col(
+                        ColumnDef::new(ModerationAction::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModerationAction::ExpiresAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(ModerationAction::RevokedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(ModerationAction::RevokedBy).uuid().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_moderation_action_player")
+                            .from((Smdb, ModerationAction::Table), ModerationAction::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_moderation_action_report")
+                            .from((Smdb, ModerationAction::Table), ModerationAction::ReportId)
+                            .to((Smdb, ModerationReport::Table), ModerationReport::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Login and middleware checks look up actions per player and kind
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_moderation_action_player_kind")
+                    .table((Smdb, ModerationAction::Table))
+                    .col(ModerationAction::PlayerId)
+                    .col(ModerationAction::Kind)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Moderation tables created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, ModerationAction::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, ModerationReport::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerRole::Table)).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(ModerationActionKind::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ReportStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ReportReason::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(PlayerRoleKind::Type).to_owned())
+            .await?;
+
+        println!("Moderation tables dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerRole {
+    Table,
+    Id,
+    PlayerId,
+    Role,
+    GrantedBy,
+    GrantedAt,
+}
+
+#[derive(DeriveIden)]
+enum ModerationReport {
+    Table,
+    Id,
+    ReporterId,
+    ReportedPlayerId,
+    GameId,
+    Reason,
+    Details,
+    ChatContext,
+    IsGameFlag,
+    Status,
+    ResolvedBy,
+    ResolutionNote,
+    CreatedAt,
+    ResolvedAt,
+}
+
+#[derive(DeriveIden)]
+enum ModerationAction {
+    Table,
+    Id,
+    PlayerId,
+    ModeratorId,
+    Kind,
+    Reason,
+    ReportId,
+    CreatedAt,
+    ExpiresAt,
+    RevokedAt,
+    RevokedBy,
+}
+
+#[derive(DeriveIden)]
+enum PlayerRoleKind {
+    #[sea_orm(iden = "player_role_kind")]
+    Type,
+    Moderator,
+    Arbiter,
+    Admin,
+}
+
+#[derive(DeriveIden)]
+enum ReportReason {
+    #[sea_orm(iden = "report_reason")]
+    Type,
+    Cheating,
+    Abuse,
+    Spam,
+    Sandbagging,
+    OffensiveUsername,
+    Other,
+}
+
+#[derive(DeriveIden)]
+enum ReportStatus {
+    #[sea_orm(iden = "report_status")]
+    Type,
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(DeriveIden)]
+enum ModerationActionKind {
+    #[sea_orm(iden = "moderation_action_kind")]
+    Type,
+    Warn,
+    Mute,
+    Ban,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,163 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(RatingCategory::Type)
+                    .values([
+                        RatingCategory::Bullet,
+                        RatingCategory::Blitz,
+                        RatingCategory::Rapid,
+                        RatingCategory::Classical,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Current rating per player and time control
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerRating::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerRating::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerRating::Category).custom(RatingCategory::Type).not_null())
+                    .col(ColumnDef::new(PlayerRating::Rating).integer().not_null().default(1500))
+                    .col(ColumnDef::new(PlayerRating::GamesPlayed).integer().not_null().default(0))
+                    .col(
+                        ColumnDef::new(PlayerRating::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(PlayerRating::PlayerId)
+                            .col(PlayerRating::Category),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_rating_player")
+                            .from((Smdb, PlayerRating::Table), PlayerRating::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Materialized ranking, rebuilt by the leaderboard refresh job
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, LeaderboardEntry::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(LeaderboardEntry::Category).custom(RatingCategory::Type).not_null())
+                    .col(ColumnDef::new(LeaderboardEntry::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(LeaderboardEntry::Rank).integer().not_null())
+                    .col(ColumnDef::new(LeaderboardEntry::PreviousRank).integer().null())
+                    .col(ColumnDef::new(LeaderboardEntry::Rating).integer().not_null())
+                    .col(ColumnDef::new(LeaderboardEntry::GamesPlayed).integer().not_null())
+                    .col(
+                        ColumnDef::new(LeaderboardEntry::RefreshedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(LeaderboardEntry::Category)
+                            .col(LeaderboardEntry::PlayerId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_leaderboard_entry_player")
+                            .from((Smdb, LeaderboardEntry::Table), LeaderboardEntry::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Top-N reads walk this index in rank order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_leaderboard_entry_category_rank")
+                    .table((Smdb, LeaderboardEntry::Table))
+                    .col(LeaderboardEntry::Category)
+                    .col(LeaderboardEntry::Rank)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Leaderboard tables created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, LeaderboardEntry::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerRating::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(RatingCategory::Type).to_owned())
+            .await?;
+
+        println!("Leaderboard tables dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerRating {
+    Table,
+    PlayerId,
+    Category,
+    Rating,
+    GamesPlayed,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LeaderboardEntry {
+    Table,
+    Category,
+    PlayerId,
+    Rank,
+    PreviousRank,
+    Rating,
+    GamesPlayed,
+    RefreshedAt,
+}
+
+#[derive(DeriveIden)]
+enum RatingCategory {
+    #[sea_orm(iden = "rating_category")]
+    Type,
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
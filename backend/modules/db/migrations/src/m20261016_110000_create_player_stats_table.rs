@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerStats::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerStats::PlayerId).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PlayerStats::Stats).json_binary().not_null())
+                    .col(ColumnDef::new(PlayerStats::GamesCounted).integer().not_null().default(0))
+                    .col(ColumnDef::new(PlayerStats::LastGameId).uuid().null())
+                    .col(
+                        ColumnDef::new(PlayerStats::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_stats_player")
+                            .from((Smdb, PlayerStats::Table), PlayerStats::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Player stats table created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerStats::Table)).to_owned())
+            .await?;
+
+        println!("Player stats table dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerStats {
+    Table,
+    PlayerId,
+    Stats,
+    GamesCounted,
+    LastGameId,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,144 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Glicko-2 state alongside the displayed rating
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, PlayerRating::Table))
+                    .add_column(
+                        ColumnDef::new(PlayerRating::RatingDeviation)
+                            .double()
+                            .not_null()
+                            .default(350.0),
+                    )
+                    .add_column(
+                        ColumnDef::new(PlayerRating::Volatility)
+                            .double()
+                            .not_null()
+                            .default(0.06),
+                    )
+                    .add_column(
+                        ColumnDef::new(PlayerRating::LastRatedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(PlayerRating::RdUpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, RatingHistory::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(RatingHistory::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(RatingHistory::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(RatingHistory::Category).custom(RatingCategory::Type).not_null())
+                    .col(ColumnDef::new(RatingHistory::GameId).uuid().null())
+                    .col(ColumnDef::new(RatingHistory::Rating).integer().not_null())
+                    .col(ColumnDef::new(RatingHistory::RatingDeviation).double().not_null())
+                    .col(
+                        ColumnDef::new(RatingHistory::RecordedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_rating_history_player")
+                            .from((Smdb, RatingHistory::Table), RatingHistory::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Charts read one player's history for one time control in time order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rating_history_player_category_recorded_at")
+                    .table((Smdb, RatingHistory::Table))
+                    .col(RatingHistory::PlayerId)
+                    .col(RatingHistory::Category)
+                    .col(RatingHistory::RecordedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Rating history table created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, RatingHistory::Table)).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, PlayerRating::Table))
+                    .drop_column(PlayerRating::RatingDeviation)
+                    .drop_column(PlayerRating::Volatility)
+                    .drop_column(PlayerRating::LastRatedAt)
+                    .drop_column(PlayerRating::RdUpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Rating history table dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerRating {
+    Table,
+    RatingDeviation,
+    Volatility,
+    LastRatedAt,
+    RdUpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RatingHistory {
+    Table,
+    Id,
+    PlayerId,
+    Category,
+    GameId,
+    Rating,
+    RatingDeviation,
+    RecordedAt,
+}
+
+#[derive(DeriveIden)]
+enum RatingCategory {
+    #[sea_orm(iden = "rating_category")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
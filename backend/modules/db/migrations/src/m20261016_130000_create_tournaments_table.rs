@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, Tournament::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(Tournament::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Tournament::Name).string().not_null())
+                    .col(ColumnDef::new(Tournament::ArbiterId).uuid().not_null())
+                    .col(ColumnDef::new(Tournament::TimeControl).custom(RatingCategory::Type).not_null())
+                    .col(ColumnDef::new(Tournament::Config).json_binary().not_null())
+                    .col(ColumnDef::new(Tournament::State).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Tournament::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Tournament::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tournament_arbiter")
+                            .from((Smdb, Tournament::Table), Tournament::ArbiterId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tournament_arbiter")
+                    .table((Smdb, Tournament::Table))
+                    .col(Tournament::ArbiterId)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Tournament table created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, Tournament::Table)).to_owned())
+            .await?;
+
+        println!("Tournament table dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tournament {
+    Table,
+    Id,
+    Name,
+    ArbiterId,
+    TimeControl,
+    Config,
+    State,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RatingCategory {
+    #[sea_orm(iden = "rating_category")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,250 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TournamentFormat::Type)
+                    .values([TournamentFormat::Swiss, TournamentFormat::Arena])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TournamentStatus::Type)
+                    .values([
+                        TournamentStatus::Registration,
+                        TournamentStatus::Ongoing,
+                        TournamentStatus::Finished,
+                        TournamentStatus::Cancelled,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Recurring events the scheduler turns into tournaments
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, TournamentTemplate::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(TournamentTemplate::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(TournamentTemplate::Name).string().not_null())
+                    .col(ColumnDef::new(TournamentTemplate::Format).custom(TournamentFormat::Type).not_null())
+                    .col(ColumnDef::new(TournamentTemplate::TimeControl).custom(RatingCategory::Type).not_null())
+                    .col(ColumnDef::new(TournamentTemplate::TotalRounds).integer().null())
+                    .col(ColumnDef::new(TournamentTemplate::DurationMinutes).integer().null())
+                    .col(ColumnDef::new(TournamentTemplate::Accelerated).boolean().not_null().default(false))
+                    .col(ColumnDef::new(TournamentTemplate::Schedule).string().not_null())
+                    .col(ColumnDef::new(TournamentTemplate::RegistrationOpensMinutes).integer().not_null())
+                    .col(ColumnDef::new(TournamentTemplate::RegistrationClosesMinutes).integer().not_null())
+                    .col(ColumnDef::new(TournamentTemplate::Enabled).boolean().not_null().default(true))
+                    .col(
+                        ColumnDef::new(TournamentTemplate::NextRunAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(TournamentTemplate::CreatedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(TournamentTemplate::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TournamentTemplate::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tournament_template_created_by")
+                            .from((Smdb, TournamentTemplate::Table), TournamentTemplate::CreatedBy)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tournament_template_next_run")
+                    .table((Smdb, TournamentTemplate::Table))
+                    .col(TournamentTemplate::Enabled)
+                    .col(TournamentTemplate::NextRunAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Lifecycle of scheduled tournaments; existing rows were started by hand
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .add_column(
+                        ColumnDef::new(Tournament::Format)
+                            .custom(TournamentFormat::Type)
+                            .not_null()
+                            .default("swiss"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Tournament::Status)
+                            .custom(TournamentStatus::Type)
+                            .not_null()
+                            .default("ongoing"),
+                    )
+                    .add_column(ColumnDef::new(Tournament::TemplateId).uuid().null())
+                    .add_column(ColumnDef::new(Tournament::DurationMinutes).integer().null())
+                    .add_column(
+                        ColumnDef::new(Tournament::RegistrationOpensAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(Tournament::RegistrationClosesAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(Tournament::StartsAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_tournament_template")
+                            .from_tbl((Smdb, Tournament::Table))
+                            .from_col(Tournament::TemplateId)
+                            .to_tbl((Smdb, TournamentTemplate::Table))
+                            .to_col(TournamentTemplate::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tournament_status_starts_at")
+                    .table((Smdb, Tournament::Table))
+                    .col(Tournament::Status)
+                    .col(Tournament::StartsAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Tournament template tables created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .drop_foreign_key(Alias::new("fk_tournament_template"))
+                    .drop_column(Tournament::Format)
+                    .drop_column(Tournament::Status)
+                    .drop_column(Tournament::TemplateId)
+                    .drop_column(Tournament::DurationMinutes)
+                    .drop_column(Tournament::RegistrationOpensAt)
+                    .drop_column(Tournament::RegistrationClosesAt)
+                    .drop_column(Tournament::StartsAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table((Smdb, TournamentTemplate::Table)).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(TournamentStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(TournamentFormat::Type).to_owned())
+            .await?;
+
+        println!("Tournament template tables dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TournamentTemplate {
+    Table,
+    Id,
+    Name,
+    Format,
+    TimeControl,
+    TotalRounds,
+    DurationMinutes,
+    Accelerated,
+    Schedule,
+    RegistrationOpensMinutes,
+    RegistrationClosesMinutes,
+    Enabled,
+    NextRunAt,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Tournament {
+    Table,
+    Format,
+    Status,
+    TemplateId,
+    DurationMinutes,
+    RegistrationOpensAt,
+    RegistrationClosesAt,
+    StartsAt,
+}
+
+#[derive(DeriveIden)]
+enum TournamentFormat {
+    #[sea_orm(iden = "tournament_format")]
+    Type,
+    Swiss,
+    Arena,
+}
+
+#[derive(DeriveIden)]
+enum TournamentStatus {
+    #[sea_orm(iden = "tournament_status")]
+    Type,
+    Registration,
+    Ongoing,
+    Finished,
+    Cancelled,
+}
+
+#[derive(DeriveIden)]
+enum RatingCategory {
+    #[sea_orm(iden = "rating_category")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,136 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Serialized `tournament::PrizeStructure`
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .add_column(
+                        ColumnDef::new(Tournament::Prizes)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'{\"prizes\": []}'::jsonb")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerTrophy::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerTrophy::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PlayerTrophy::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerTrophy::TournamentId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerTrophy::Prize).string().not_null())
+                    .col(ColumnDef::new(PlayerTrophy::Rank).integer().not_null())
+                    .col(ColumnDef::new(PlayerTrophy::Score).double().not_null())
+                    .col(
+                        ColumnDef::new(PlayerTrophy::AwardedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_trophy_player")
+                            .from((Smdb, PlayerTrophy::Table), PlayerTrophy::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_trophy_tournament")
+                            .from((Smdb, PlayerTrophy::Table), PlayerTrophy::TournamentId)
+                            .to((Smdb, Tournament::Table), Tournament::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A player wins at most one prize per tournament
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_player_trophy_tournament_player")
+                    .table((Smdb, PlayerTrophy::Table))
+                    .col(PlayerTrophy::TournamentId)
+                    .col(PlayerTrophy::PlayerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Profiles list a player's trophies newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_player_trophy_player_awarded_at")
+                    .table((Smdb, PlayerTrophy::Table))
+                    .col(PlayerTrophy::PlayerId)
+                    .col(PlayerTrophy::AwardedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Player trophy table created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerTrophy::Table)).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .drop_column(Tournament::Prizes)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Player trophy table dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerTrophy {
+    Table,
+    Id,
+    PlayerId,
+    TournamentId,
+    Prize,
+    Rank,
+    Score,
+    AwardedAt,
+}
+
+#[derive(DeriveIden)]
+enum Tournament {
+    Table,
+    Id,
+    Prizes,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,184 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AnnotationVisibility::Type)
+                    .values([
+                        AnnotationVisibility::Private,
+                        AnnotationVisibility::Friends,
+                        AnnotationVisibility::Public,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // One annotation tree per author and game, stored as serialized `chess::AnnotationTree`
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameAnnotation::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameAnnotation::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(GameAnnotation::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GameAnnotation::PlayerId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(GameAnnotation::Visibility)
+                            .custom(AnnotationVisibility::Type)
+                            .not_null()
+                            .default("private"),
+                    )
+                    .col(ColumnDef::new(GameAnnotation::Tree).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(GameAnnotation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GameAnnotation::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_annotation_game")
+                            .from((Smdb, GameAnnotation::Table), GameAnnotation::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_annotation_player")
+                            .from((Smdb, GameAnnotation::Table), GameAnnotation::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_annotation_game_player")
+                    .table((Smdb, GameAnnotation::Table))
+                    .col(GameAnnotation::GameId)
+                    .col(GameAnnotation::PlayerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Friends a player shares friends-only content with
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerFriend::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerFriend::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerFriend::FriendId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(PlayerFriend::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(PlayerFriend::PlayerId)
+                            .col(PlayerFriend::FriendId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_friend_player")
+                            .from((Smdb, PlayerFriend::Table), PlayerFriend::PlayerId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_friend_friend")
+                            .from((Smdb, PlayerFriend::Table), PlayerFriend::FriendId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Game annotation tables created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerFriend::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, GameAnnotation::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(AnnotationVisibility::Type).to_owned())
+            .await?;
+
+        println!("Game annotation tables dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameAnnotation {
+    Table,
+    Id,
+    GameId,
+    PlayerId,
+    Visibility,
+    Tree,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum PlayerFriend {
+    Table,
+    PlayerId,
+    FriendId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AnnotationVisibility {
+    #[sea_orm(iden = "annotation_visibility")]
+    Type,
+    Private,
+    Friends,
+    Public,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,192 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(EngineMatchStatus::Type)
+                    .values([
+                        EngineMatchStatus::Queued,
+                        EngineMatchStatus::Running,
+                        EngineMatchStatus::Finished,
+                        EngineMatchStatus::Cancelled,
+                        EngineMatchStatus::Failed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Engine-vs-engine matches; results are counted from engine A's side
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, EngineMatch::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(EngineMatch::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(EngineMatch::Name).string().not_null())
+                    .col(ColumnDef::new(EngineMatch::Config).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(EngineMatch::Status)
+                            .custom(EngineMatchStatus::Type)
+                            .not_null()
+                            .default("queued"),
+                    )
+                    .col(ColumnDef::new(EngineMatch::Wins).integer().not_null().default(0))
+                    .col(ColumnDef::new(EngineMatch::Draws).integer().not_null().default(0))
+                    .col(ColumnDef::new(EngineMatch::Losses).integer().not_null().default(0))
+                    .col(ColumnDef::new(EngineMatch::Error).text().null())
+                    .col(ColumnDef::new(EngineMatch::CreatedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(EngineMatch::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EngineMatch::StartedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(EngineMatch::FinishedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_engine_match_created_by")
+                            .from((Smdb, EngineMatch::Table), EngineMatch::CreatedBy)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_engine_match_status_created")
+                    .table((Smdb, EngineMatch::Table))
+                    .col(EngineMatch::Status)
+                    .col(EngineMatch::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, EngineMatchGame::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(EngineMatchGame::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(EngineMatchGame::MatchId).uuid().not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Round).integer().not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Opening).text().null())
+                    .col(ColumnDef::new(EngineMatchGame::AIsWhite).boolean().not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Result).string_len(7).not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Termination).string().not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Plies).integer().not_null())
+                    .col(ColumnDef::new(EngineMatchGame::Pgn).text().not_null())
+                    .col(
+                        ColumnDef::new(EngineMatchGame::PlayedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_engine_match_game_match")
+                            .from((Smdb, EngineMatchGame::Table), EngineMatchGame::MatchId)
+                            .to((Smdb, EngineMatch::Table), EngineMatch::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_engine_match_game_round")
+                    .table((Smdb, EngineMatchGame::Table))
+                    .col(EngineMatchGame::MatchId)
+                    .col(EngineMatchGame::Round)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Engine match tables created successfully.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, EngineMatchGame::Table)).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table((Smdb, EngineMatch::Table)).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(EngineMatchStatus::Type).to_owned())
+            .await?;
+
+        println!("Engine match tables dropped.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum EngineMatch {
+    Table,
+    Id,
+    Name,
+    Config,
+    Status,
+    Wins,
+    Draws,
+    Losses,
+    Error,
+    CreatedBy,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum EngineMatchGame {
+    Table,
+    Id,
+    MatchId,
+    Round,
+    Opening,
+    AIsWhite,
+    Result,
+    Termination,
+    Plies,
+    Pgn,
+    PlayedAt,
+}
+
+#[derive(DeriveIden)]
+enum EngineMatchStatus {
+    #[sea_orm(iden = "engine_match_status")]
+    Type,
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
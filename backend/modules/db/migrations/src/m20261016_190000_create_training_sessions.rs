@@ -0,0 +1,125 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TrainingDrill::Type)
+                    .values([
+                        TrainingDrill::FindSquare,
+                        TrainingDrill::NameSquare,
+                        TrainingDrill::SquareColor,
+                        TrainingDrill::PieceVision,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // One run of a coordinate or board-vision drill; scored on submission
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, TrainingSession::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(TrainingSession::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(TrainingSession::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(TrainingSession::Drill).custom(TrainingDrill::Type).not_null())
+                    .col(ColumnDef::new(TrainingSession::Exercises).json_binary().not_null())
+                    .col(ColumnDef::new(TrainingSession::Answers).json_binary().null())
+                    .col(ColumnDef::new(TrainingSession::Correct).integer().null())
+                    .col(ColumnDef::new(TrainingSession::DurationMs).integer().null())
+                    .col(
+                        ColumnDef::new(TrainingSession::StartedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TrainingSession::FinishedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_training_session_player")
+                            .from((Smdb, TrainingSession::Table), TrainingSession::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_training_session_player_drill")
+                    .table((Smdb, TrainingSession::Table))
+                    .col(TrainingSession::PlayerId)
+                    .col(TrainingSession::Drill)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Best scores per drill
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_training_session_drill_score")
+                    .table((Smdb, TrainingSession::Table))
+                    .col(TrainingSession::Drill)
+                    .col((TrainingSession::Correct, IndexOrder::Desc))
+                    .col(TrainingSession::DurationMs)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, TrainingSession::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(TrainingDrill::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TrainingSession {
+    Table,
+    Id,
+    PlayerId,
+    Drill,
+    Exercises,
+    Answers,
+    Correct,
+    DurationMs,
+    StartedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum TrainingDrill {
+    #[sea_orm(iden = "training_drill")]
+    Type,
+    FindSquare,
+    NameSquare,
+    SquareColor,
+    PieceVision,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
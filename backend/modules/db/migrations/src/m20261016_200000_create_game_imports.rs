@@ -0,0 +1,134 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ImportSource::Type)
+                    .values([ImportSource::Lichess, ImportSource::ChessCom])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Where an imported game came from; one row per imported game
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameImport::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameImport::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(GameImport::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GameImport::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(GameImport::Source).custom(ImportSource::Type).not_null())
+                    .col(ColumnDef::new(GameImport::ExternalId).string_len(64).not_null())
+                    .col(ColumnDef::new(GameImport::ExternalUrl).text().null())
+                    .col(ColumnDef::new(GameImport::ExternalUsername).string_len(64).not_null())
+                    .col(ColumnDef::new(GameImport::WhiteName).string_len(64).not_null())
+                    .col(ColumnDef::new(GameImport::BlackName).string_len(64).not_null())
+                    .col(
+                        ColumnDef::new(GameImport::ImportedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_import_game")
+                            .from((Smdb, GameImport::Table), GameImport::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_import_player")
+                            .from((Smdb, GameImport::Table), GameImport::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A game from another site is imported once per player
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_import_unique_external")
+                    .table((Smdb, GameImport::Table))
+                    .col(GameImport::PlayerId)
+                    .col(GameImport::Source)
+                    .col(GameImport::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_import_game")
+                    .table((Smdb, GameImport::Table))
+                    .col(GameImport::GameId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, GameImport::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ImportSource::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameImport {
+    Table,
+    Id,
+    GameId,
+    PlayerId,
+    Source,
+    ExternalId,
+    ExternalUrl,
+    ExternalUsername,
+    WhiteName,
+    BlackName,
+    ImportedAt,
+}
+
+#[derive(DeriveIden)]
+enum ImportSource {
+    #[sea_orm(iden = "import_source")]
+    Type,
+    Lichess,
+    ChessCom,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
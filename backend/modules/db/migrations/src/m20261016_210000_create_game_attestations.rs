@@ -0,0 +1,128 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AttestationStatus::Type)
+                    .values([
+                        AttestationStatus::Pending,
+                        AttestationStatus::Submitted,
+                        AttestationStatus::Confirmed,
+                        AttestationStatus::Failed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Result of a finished rated game as submitted to the StarkNet
+        // attestation contract; one row per game
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameAttestation::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameAttestation::GameId).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(GameAttestation::Result).small_integer().not_null())
+                    .col(ColumnDef::new(GameAttestation::PgnHash).string_len(66).not_null())
+                    .col(ColumnDef::new(GameAttestation::Status).custom(AttestationStatus::Type).not_null())
+                    .col(ColumnDef::new(GameAttestation::TxHash).string_len(66).null())
+                    .col(ColumnDef::new(GameAttestation::Nonce).big_integer().null())
+                    .col(ColumnDef::new(GameAttestation::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(GameAttestation::LastError).text().null())
+                    .col(
+                        ColumnDef::new(GameAttestation::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GameAttestation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GameAttestation::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_attestation_game")
+                            .from((Smdb, GameAttestation::Table), GameAttestation::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The submitter picks due attestations by status and retry time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_attestation_due")
+                    .table((Smdb, GameAttestation::Table))
+                    .col(GameAttestation::Status)
+                    .col(GameAttestation::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, GameAttestation::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(AttestationStatus::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameAttestation {
+    Table,
+    GameId,
+    Result,
+    PgnHash,
+    Status,
+    TxHash,
+    Nonce,
+    Attempts,
+    LastError,
+    NextAttemptAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AttestationStatus {
+    #[sea_orm(iden = "attestation_status")]
+    Type,
+    Pending,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // StarkNet accounts a player signs in with; a player may link several
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerWallet::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerWallet::Address).string_len(66).not_null().primary_key())
+                    .col(ColumnDef::new(PlayerWallet::PlayerId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(PlayerWallet::LinkedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_wallet_player")
+                            .from((Smdb, PlayerWallet::Table), PlayerWallet::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_player_wallet_player")
+                    .table((Smdb, PlayerWallet::Table))
+                    .col(PlayerWallet::PlayerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerWallet::Table)).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerWallet {
+    Table,
+    Address,
+    PlayerId,
+    LinkedAt,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
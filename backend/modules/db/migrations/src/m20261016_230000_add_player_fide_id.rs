@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // FIDE id used when tournaments are reported for rating
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Player::Table))
+                    .add_column(ColumnDef::new(Player::FideId).big_integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Added fide_id column to player table.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Player::Table))
+                    .drop_column(Player::FideId)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Removed fide_id column from player table.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    FideId,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,194 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(DisputeReason::Type)
+                    .values([
+                        DisputeReason::WrongFlag,
+                        DisputeReason::Disconnection,
+                        DisputeReason::IllegalMove,
+                        DisputeReason::Other,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(DisputeStatus::Type)
+                    .values([DisputeStatus::Open, DisputeStatus::Upheld, DisputeStatus::Overturned])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Results contested by a player of the game, with the game as it
+        // stood when the case was opened and the arbiter's decision
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameDispute::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameDispute::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(GameDispute::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GameDispute::ClaimantId).uuid().not_null())
+                    .col(ColumnDef::new(GameDispute::Reason).custom(DisputeReason::Type).not_null())
+                    .col(ColumnDef::new(GameDispute::Details).text().null())
+                    .col(ColumnDef::new(GameDispute::Evidence).json_binary().not_null())
+                    .col(ColumnDef::new(GameDispute::TournamentId).uuid().null())
+                    .col(ColumnDef::new(GameDispute::Round).integer().null())
+                    .col(ColumnDef::new(GameDispute::OriginalResult).custom(ResultSide::Type).not_null())
+                    .col(ColumnDef::new(GameDispute::RequestedResult).custom(ResultSide::Type).null())
+                    .col(ColumnDef::new(GameDispute::Status).custom(DisputeStatus::Type).not_null())
+                    .col(ColumnDef::new(GameDispute::DecidedBy).uuid().null())
+                    .col(ColumnDef::new(GameDispute::DecisionNote).text().null())
+                    .col(ColumnDef::new(GameDispute::CorrectedResult).custom(ResultSide::Type).null())
+                    .col(
+                        ColumnDef::new(GameDispute::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GameDispute::DecidedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_dispute_game")
+                            .from((Smdb, GameDispute::Table), GameDispute::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_dispute_claimant")
+                            .from((Smdb, GameDispute::Table), GameDispute::ClaimantId)
+                            .to(Player::Table, Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_dispute_tournament")
+                            .from((Smdb, GameDispute::Table), GameDispute::TournamentId)
+                            .to((Smdb, Tournament::Table), Tournament::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The arbiter queue lists cases by status, oldest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_dispute_status_created")
+                    .table((Smdb, GameDispute::Table))
+                    .col(GameDispute::Status)
+                    .col(GameDispute::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Opening a case checks the game has no other open one
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_dispute_game")
+                    .table((Smdb, GameDispute::Table))
+                    .col(GameDispute::GameId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, GameDispute::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(DisputeStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(DisputeReason::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameDispute {
+    Table,
+    Id,
+    GameId,
+    ClaimantId,
+    Reason,
+    Details,
+    Evidence,
+    TournamentId,
+    Round,
+    OriginalResult,
+    RequestedResult,
+    Status,
+    DecidedBy,
+    DecisionNote,
+    CorrectedResult,
+    CreatedAt,
+    DecidedAt,
+}
+
+#[derive(DeriveIden)]
+enum DisputeReason {
+    #[sea_orm(iden = "dispute_reason")]
+    Type,
+    WrongFlag,
+    Disconnection,
+    IllegalMove,
+    Other,
+}
+
+#[derive(DeriveIden)]
+enum DisputeStatus {
+    #[sea_orm(iden = "dispute_status")]
+    Type,
+    Open,
+    Upheld,
+    Overturned,
+}
+
+#[derive(DeriveIden)]
+enum ResultSide {
+    #[sea_orm(iden = "result_side")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Tournament {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
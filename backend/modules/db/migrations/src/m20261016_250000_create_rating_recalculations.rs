@@ -0,0 +1,146 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Volatility after each game, so ratings can be replayed from any point
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, RatingHistory::Table))
+                    .add_column(ColumnDef::new(RatingHistory::Volatility).double().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // A replay looks up the points every player got from a game
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rating_history_game")
+                    .table((Smdb, RatingHistory::Table))
+                    .col(RatingHistory::GameId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(RecalculationStatus::Type)
+                    .values([
+                        RecalculationStatus::Queued,
+                        RecalculationStatus::Done,
+                        RecalculationStatus::Failed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Queued rating replays and the before/after audit of each
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, RatingRecalculation::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(RatingRecalculation::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(RatingRecalculation::Reason).text().not_null())
+                    .col(ColumnDef::new(RatingRecalculation::PlayerIds).json_binary().not_null())
+                    .col(ColumnDef::new(RatingRecalculation::Since).timestamp_with_time_zone().not_null())
+                    .col(
+                        ColumnDef::new(RatingRecalculation::Status)
+                            .custom(RecalculationStatus::Type)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RatingRecalculation::Audit).json_binary().null())
+                    .col(ColumnDef::new(RatingRecalculation::Error).text().null())
+                    .col(ColumnDef::new(RatingRecalculation::RequestedBy).uuid().null())
+                    .col(
+                        ColumnDef::new(RatingRecalculation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RatingRecalculation::FinishedAt).timestamp_with_time_zone().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The worker takes the oldest queued recalculation
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rating_recalculation_status_created")
+                    .table((Smdb, RatingRecalculation::Table))
+                    .col(RatingRecalculation::Status)
+                    .col(RatingRecalculation::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, RatingRecalculation::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(RecalculationStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_rating_history_game")
+                    .table((Smdb, RatingHistory::Table))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, RatingHistory::Table))
+                    .drop_column(RatingHistory::Volatility)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RatingHistory {
+    Table,
+    GameId,
+    Volatility,
+}
+
+#[derive(DeriveIden)]
+enum RatingRecalculation {
+    Table,
+    Id,
+    Reason,
+    PlayerIds,
+    Since,
+    Status,
+    Audit,
+    Error,
+    RequestedBy,
+    CreatedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum RecalculationStatus {
+    #[sea_orm(iden = "recalculation_status")]
+    Type,
+    Queued,
+    Done,
+    Failed,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
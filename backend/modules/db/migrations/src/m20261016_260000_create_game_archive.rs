@@ -0,0 +1,174 @@
+use sea_orm_migration::prelude::*;
+
+/// Columns shared by `game` and `game_archive`, in the order rows are copied
+const GAME_COLUMNS: &str = r#""id", "white_player", "black_player", "fen", "pgn", "result", "variant", "started_at", "duration_sec", "created_at", "updated_at", "is_imported", "original_pgn", "odds""#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Finished games moved out of the hot game table, partitioned by the
+        // year they were created. The archival job creates partitions as it
+        // needs them; the key has to include the partition column.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE TABLE IF NOT EXISTS "smdb"."game_archive" (
+                    "id" uuid NOT NULL,
+                    "white_player" uuid NOT NULL,
+                    "black_player" uuid NOT NULL,
+                    "fen" text NOT NULL,
+                    "pgn" jsonb NOT NULL,
+                    "result" result_side,
+                    "variant" game_variant NOT NULL,
+                    "started_at" timestamp with time zone NOT NULL,
+                    "duration_sec" integer NOT NULL,
+                    "created_at" timestamp with time zone NOT NULL,
+                    "updated_at" timestamp with time zone NOT NULL,
+                    "is_imported" boolean NOT NULL DEFAULT false,
+                    "original_pgn" text,
+                    "odds" jsonb,
+                    "archived_at" timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY ("id", "created_at")
+                ) PARTITION BY RANGE ("created_at")"#,
+            )
+            .await?;
+
+        // Game history pages through a player's games newest first, like on the game table
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_archive_white_player_created_at_id")
+                    .table((Smdb, GameArchive::Table))
+                    .col(GameArchive::WhitePlayer)
+                    .col(GameArchive::CreatedAt)
+                    .col(GameArchive::Id)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_archive_black_player_created_at_id")
+                    .table((Smdb, GameArchive::Table))
+                    .col(GameArchive::BlackPlayer)
+                    .col(GameArchive::CreatedAt)
+                    .col(GameArchive::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Single games are looked up by id alone
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_archive_id")
+                    .table((Smdb, GameArchive::Table))
+                    .col(GameArchive::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Annotations, imports, attestations, disputes and reports keep
+        // pointing at a game after it is archived, so they can no longer
+        // reference the game table alone
+        for (table, name) in [
+            (GameAnnotation::Table.into_iden(), "fk_game_annotation_game"),
+            (GameImport::Table.into_iden(), "fk_game_import_game"),
+            (GameAttestation::Table.into_iden(), "fk_game_attestation_game"),
+            (GameDispute::Table.into_iden(), "fk_game_dispute_game"),
+            (ModerationReport::Table.into_iden(), "fk_moderation_report_game"),
+        ] {
+            manager
+                .drop_foreign_key(ForeignKey::drop().name(name).table((Smdb.into_iden(), table)).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Move archived games back so the foreign keys hold again
+        manager
+            .get_connection()
+            .execute_unprepared(&format!(
+                r#"INSERT INTO "smdb"."game" ({columns}) SELECT {columns} FROM "smdb"."game_archive""#,
+                columns = GAME_COLUMNS,
+            ))
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, GameArchive::Table)).to_owned())
+            .await?;
+
+        for (table, column, name, on_delete) in [
+            (GameAnnotation::Table.into_iden(), GameAnnotation::GameId.into_iden(), "fk_game_annotation_game", ForeignKeyAction::Cascade),
+            (GameImport::Table.into_iden(), GameImport::GameId.into_iden(), "fk_game_import_game", ForeignKeyAction::Cascade),
+            (GameAttestation::Table.into_iden(), GameAttestation::GameId.into_iden(), "fk_game_attestation_game", ForeignKeyAction::Cascade),
+            (GameDispute::Table.into_iden(), GameDispute::GameId.into_iden(), "fk_game_dispute_game", ForeignKeyAction::Cascade),
+            (ModerationReport::Table.into_iden(), ModerationReport::GameId.into_iden(), "fk_moderation_report_game", ForeignKeyAction::SetNull),
+        ] {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name(name)
+                        .from((Smdb.into_iden(), table), column)
+                        .to((Smdb, Game::Table), Game::Id)
+                        .on_delete(on_delete)
+                        .on_update(ForeignKeyAction::Cascade)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum GameArchive {
+    Table,
+    Id,
+    WhitePlayer,
+    BlackPlayer,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum GameAnnotation {
+    Table,
+    GameId,
+}
+
+#[derive(DeriveIden)]
+enum GameImport {
+    Table,
+    GameId,
+}
+
+#[derive(DeriveIden)]
+enum GameAttestation {
+    Table,
+    GameId,
+}
+
+#[derive(DeriveIden)]
+enum GameDispute {
+    Table,
+    GameId,
+}
+
+#[derive(DeriveIden)]
+enum ModerationReport {
+    Table,
+    GameId,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
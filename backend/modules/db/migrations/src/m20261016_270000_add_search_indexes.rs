@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Trigram similarity for typo-tolerant and substring matches
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm").await?;
+
+        // Player search matches usernames by trigram
+        db.execute_unprepared(
+            r#"CREATE INDEX IF NOT EXISTS "idx_player_username_trgm" ON "player" USING GIN ("username" gin_trgm_ops)"#,
+        )
+        .await?;
+
+        // Tournament search matches names by trigram and by whole words
+        db.execute_unprepared(
+            r#"CREATE INDEX IF NOT EXISTS "idx_tournament_name_trgm" ON "smdb"."tournament" USING GIN ("name" gin_trgm_ops)"#,
+        )
+        .await?;
+        db.execute_unprepared(
+            r#"CREATE INDEX IF NOT EXISTS "idx_tournament_name_tsv" ON "smdb"."tournament" USING GIN (to_tsvector('simple', "name"))"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "smdb"."idx_tournament_name_tsv""#).await?;
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "smdb"."idx_tournament_name_trgm""#).await?;
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "idx_player_username_trgm""#).await?;
+        // The extension may be used elsewhere, so it stays installed
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Finished games whose positions were indexed, with what position
+        // searches filter on. Games may be archived later, so there is no
+        // foreign key to the game table.
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, IndexedGame::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(IndexedGame::GameId).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(IndexedGame::WhiteName).string_len(100).not_null())
+                    .col(ColumnDef::new(IndexedGame::BlackName).string_len(100).not_null())
+                    .col(ColumnDef::new(IndexedGame::WhiteRating).integer().null())
+                    .col(ColumnDef::new(IndexedGame::BlackRating).integer().null())
+                    .col(ColumnDef::new(IndexedGame::Result).custom(ResultSide::Type).not_null())
+                    .col(ColumnDef::new(IndexedGame::PlayedAt).timestamp_with_time_zone().not_null())
+                    .col(
+                        ColumnDef::new(IndexedGame::IndexedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every position of an indexed game, by Zobrist hash and material
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GamePosition::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GamePosition::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GamePosition::Ply).integer().not_null())
+                    .col(ColumnDef::new(GamePosition::Zobrist).big_integer().not_null())
+                    .col(ColumnDef::new(GamePosition::Material).big_integer().not_null())
+                    .primary_key(Index::create().col(GamePosition::GameId).col(GamePosition::Ply))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_game_position_indexed_game")
+                            .from((Smdb, GamePosition::Table), GamePosition::GameId)
+                            .to((Smdb, IndexedGame::Table), IndexedGame::GameId)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Position searches look up a hash or a material balance
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_position_zobrist")
+                    .table((Smdb, GamePosition::Table))
+                    .col(GamePosition::Zobrist)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_position_material")
+                    .table((Smdb, GamePosition::Table))
+                    .col(GamePosition::Material)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Results are listed newest game first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_indexed_game_played_at")
+                    .table((Smdb, IndexedGame::Table))
+                    .col(IndexedGame::PlayedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, GamePosition::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, IndexedGame::Table)).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum IndexedGame {
+    Table,
+    GameId,
+    WhiteName,
+    BlackName,
+    WhiteRating,
+    BlackRating,
+    Result,
+    PlayedAt,
+    IndexedAt,
+}
+
+#[derive(DeriveIden)]
+enum GamePosition {
+    Table,
+    GameId,
+    Ply,
+    Zobrist,
+    Material,
+}
+
+#[derive(DeriveIden)]
+enum ResultSide {
+    #[sea_orm(iden = "result_side")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
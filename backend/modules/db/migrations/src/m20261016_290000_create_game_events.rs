@@ -0,0 +1,145 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(GameEventKind::Type)
+                    .values([
+                        GameEventKind::Move,
+                        GameEventKind::DrawOffered,
+                        GameEventKind::DrawDeclined,
+                        GameEventKind::DrawAccepted,
+                        GameEventKind::TakebackOffered,
+                        GameEventKind::TakebackAccepted,
+                        GameEventKind::TakebackDeclined,
+                        GameEventKind::ClockSet,
+                        GameEventKind::Ended,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Everything that happened in a game, numbered from 1 per game. Rows
+        // are never changed; the game row is rebuilt from them. No foreign
+        // key, so the log stays when the game is archived.
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameEvent::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameEvent::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GameEvent::Seq).integer().not_null())
+                    .col(ColumnDef::new(GameEvent::Kind).custom(GameEventKind::Type).not_null())
+                    .col(ColumnDef::new(GameEvent::Payload).json_binary().not_null())
+                    .col(ColumnDef::new(GameEvent::ActorId).uuid().null())
+                    .col(
+                        ColumnDef::new(GameEvent::RecordedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(Index::create().col(GameEvent::GameId).col(GameEvent::Seq))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Corrections go in as new events, never as edits
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE OR REPLACE FUNCTION "smdb"."reject_game_event_change"() RETURNS trigger AS $$
+                BEGIN
+                    RAISE EXCEPTION 'game_event is append-only';
+                END;
+                $$ LANGUAGE plpgsql;
+                CREATE TRIGGER "trg_game_event_append_only"
+                    BEFORE UPDATE OR DELETE ON "smdb"."game_event"
+                    FOR EACH ROW EXECUTE FUNCTION "smdb"."reject_game_event_change"()"#,
+            )
+            .await?;
+
+        // Game state folded up to an event, so rebuilding reads only the
+        // events after the latest snapshot
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, GameSnapshot::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(GameSnapshot::GameId).uuid().not_null())
+                    .col(ColumnDef::new(GameSnapshot::Seq).integer().not_null())
+                    .col(ColumnDef::new(GameSnapshot::State).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(GameSnapshot::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(Index::create().col(GameSnapshot::GameId).col(GameSnapshot::Seq))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, GameSnapshot::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, GameEvent::Table)).to_owned())
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(r#"DROP FUNCTION IF EXISTS "smdb"."reject_game_event_change"()"#)
+            .await?;
+        manager
+            .drop_type(Type::drop().name(GameEventKind::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameEvent {
+    Table,
+    GameId,
+    Seq,
+    Kind,
+    Payload,
+    ActorId,
+    RecordedAt,
+}
+
+#[derive(DeriveIden)]
+enum GameSnapshot {
+    Table,
+    GameId,
+    Seq,
+    State,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum GameEventKind {
+    #[sea_orm(iden = "game_event_kind")]
+    Type,
+    Move,
+    DrawOffered,
+    DrawDeclined,
+    DrawAccepted,
+    TakebackOffered,
+    TakebackAccepted,
+    TakebackDeclined,
+    ClockSet,
+    Ended,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
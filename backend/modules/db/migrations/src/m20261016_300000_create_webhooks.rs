@@ -0,0 +1,192 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(WebhookDeliveryStatus::Type)
+                    .values([
+                        WebhookDeliveryStatus::Pending,
+                        WebhookDeliveryStatus::Delivered,
+                        WebhookDeliveryStatus::Failed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // URLs integrators want events posted to, signed with their secret
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, WebhookSubscription::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(WebhookSubscription::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(WebhookSubscription::OwnerId).uuid().not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Url).string_len(2048).not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Secret).string_len(128).not_null())
+                    .col(ColumnDef::new(WebhookSubscription::Events).array(ColumnType::Text).not_null())
+                    .col(
+                        ColumnDef::new(WebhookSubscription::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscription::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_subscription_owner")
+                            .from((Smdb, WebhookSubscription::Table), WebhookSubscription::OwnerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_subscription_owner")
+                    .table((Smdb, WebhookSubscription::Table))
+                    .col(WebhookSubscription::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // One event for one subscription, kept as the delivery log
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, WebhookDelivery::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(WebhookDelivery::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(WebhookDelivery::SubscriptionId).uuid().not_null())
+                    .col(ColumnDef::new(WebhookDelivery::Event).string_len(64).not_null())
+                    .col(ColumnDef::new(WebhookDelivery::Payload).json_binary().not_null())
+                    .col(ColumnDef::new(WebhookDelivery::Status).custom(WebhookDeliveryStatus::Type).not_null())
+                    .col(ColumnDef::new(WebhookDelivery::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(WebhookDelivery::ResponseStatus).small_integer().null())
+                    .col(ColumnDef::new(WebhookDelivery::LastError).text().null())
+                    .col(
+                        ColumnDef::new(WebhookDelivery::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDelivery::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDelivery::DeliveredAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_delivery_subscription")
+                            .from((Smdb, WebhookDelivery::Table), WebhookDelivery::SubscriptionId)
+                            .to((Smdb, WebhookSubscription::Table), WebhookSubscription::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The sender picks due deliveries by status and retry time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_delivery_due")
+                    .table((Smdb, WebhookDelivery::Table))
+                    .col(WebhookDelivery::Status)
+                    .col(WebhookDelivery::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Delivery logs are read per subscription, newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_delivery_subscription")
+                    .table((Smdb, WebhookDelivery::Table))
+                    .col(WebhookDelivery::SubscriptionId)
+                    .col(WebhookDelivery::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, WebhookDelivery::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, WebhookSubscription::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(WebhookDeliveryStatus::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookSubscription {
+    Table,
+    Id,
+    OwnerId,
+    Url,
+    Secret,
+    Events,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDelivery {
+    Table,
+    Id,
+    SubscriptionId,
+    Event,
+    Payload,
+    Status,
+    Attempts,
+    ResponseStatus,
+    LastError,
+    NextAttemptAt,
+    CreatedAt,
+    DeliveredAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveryStatus {
+    #[sea_orm(iden = "webhook_delivery_status")]
+    Type,
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,101 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ChatAudience::Type)
+                    .values([ChatAudience::Everyone, ChatAudience::Friends, ChatAudience::Nobody])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Settings a player changed from the defaults; players without a
+        // row use the defaults
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerPreferences::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerPreferences::PlayerId).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PlayerPreferences::AutoQueen).boolean().not_null().default(false))
+                    .col(ColumnDef::new(PlayerPreferences::Premove).boolean().not_null().default(true))
+                    .col(ColumnDef::new(PlayerPreferences::BoardTheme).string_len(32).not_null().default("brown"))
+                    .col(ColumnDef::new(PlayerPreferences::Sound).boolean().not_null().default(true))
+                    .col(ColumnDef::new(PlayerPreferences::ZenMode).boolean().not_null().default(false))
+                    .col(
+                        ColumnDef::new(PlayerPreferences::ChatAudience)
+                            .custom(ChatAudience::Type)
+                            .not_null()
+                            .default("everyone"),
+                    )
+                    .col(ColumnDef::new(PlayerPreferences::ChatProfanityFilter).boolean().not_null().default(true))
+                    .col(
+                        ColumnDef::new(PlayerPreferences::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_preferences_player")
+                            .from((Smdb, PlayerPreferences::Table), PlayerPreferences::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerPreferences::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ChatAudience::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerPreferences {
+    Table,
+    PlayerId,
+    AutoQueen,
+    Premove,
+    BoardTheme,
+    Sound,
+    ZenMode,
+    ChatAudience,
+    ChatProfanityFilter,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ChatAudience {
+    #[sea_orm(iden = "chat_audience")]
+    Type,
+    Everyone,
+    Friends,
+    Nobody,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Closed accounts; the row stays after anonymization so the player
+        // cannot be reactivated
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, AccountClosure::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(AccountClosure::PlayerId).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(AccountClosure::RequestedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AccountClosure::PurgeAfter).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(AccountClosure::AnonymizedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_account_closure_player")
+                            .from((Smdb, AccountClosure::Table), AccountClosure::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The purge worker looks for closures past their grace period
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_account_closure_pending")
+                    .table((Smdb, AccountClosure::Table))
+                    .col(AccountClosure::AnonymizedAt)
+                    .col(AccountClosure::PurgeAfter)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, AccountClosure::Table)).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccountClosure {
+    Table,
+    PlayerId,
+    RequestedAt,
+    PurgeAfter,
+    AnonymizedAt,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Fast or fair pairing of the arenas a template creates
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, TournamentTemplate::Table))
+                    .add_column(ColumnDef::new(TournamentTemplate::ArenaPairing).json_binary().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Added arena_pairing column to tournament_template table.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, TournamentTemplate::Table))
+                    .drop_column(TournamentTemplate::ArenaPairing)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Removed arena_pairing column from tournament_template table.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TournamentTemplate {
+    Table,
+    ArenaPairing,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
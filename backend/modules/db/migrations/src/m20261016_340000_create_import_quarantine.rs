@@ -0,0 +1,215 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(QuarantineStatus::Type)
+                    .values([QuarantineStatus::Pending, QuarantineStatus::Resolved, QuarantineStatus::Dismissed])
+                    .to_owned(),
+            )
+            .await?;
+
+        // One row per account import, with what became of the games fetched
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, ImportJob::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(ImportJob::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ImportJob::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(ImportJob::Source).custom(ImportSource::Type).not_null())
+                    .col(ColumnDef::new(ImportJob::ExternalUsername).string_len(64).not_null())
+                    .col(ColumnDef::new(ImportJob::Fetched).integer().not_null())
+                    .col(ColumnDef::new(ImportJob::Imported).integer().not_null())
+                    .col(ColumnDef::new(ImportJob::Duplicates).integer().not_null())
+                    .col(ColumnDef::new(ImportJob::Failed).integer().not_null())
+                    .col(
+                        ColumnDef::new(ImportJob::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_import_job_player")
+                            .from((Smdb, ImportJob::Table), ImportJob::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_import_job_player")
+                    .table((Smdb, ImportJob::Table))
+                    .col(ImportJob::PlayerId)
+                    .col(ImportJob::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Fetched games that failed to import, kept with their text until the
+        // player corrects or dismisses them
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, ImportQuarantine::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(ImportQuarantine::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(ImportQuarantine::JobId).uuid().not_null())
+                    .col(ColumnDef::new(ImportQuarantine::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(ImportQuarantine::ExternalId).string_len(64).null())
+                    .col(ColumnDef::new(ImportQuarantine::Pgn).text().not_null())
+                    .col(ColumnDef::new(ImportQuarantine::Error).text().not_null())
+                    .col(ColumnDef::new(ImportQuarantine::Status).custom(QuarantineStatus::Type).not_null())
+                    .col(ColumnDef::new(ImportQuarantine::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(ImportQuarantine::GameId).uuid().null())
+                    .col(
+                        ColumnDef::new(ImportQuarantine::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportQuarantine::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_import_quarantine_job")
+                            .from((Smdb, ImportQuarantine::Table), ImportQuarantine::JobId)
+                            .to((Smdb, ImportJob::Table), ImportJob::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_import_quarantine_player")
+                            .from((Smdb, ImportQuarantine::Table), ImportQuarantine::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_import_quarantine_game")
+                            .from((Smdb, ImportQuarantine::Table), ImportQuarantine::GameId)
+                            .to((Smdb, Game::Table), Game::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A player's review queue, and the counts per job
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_import_quarantine_player_status")
+                    .table((Smdb, ImportQuarantine::Table))
+                    .col(ImportQuarantine::PlayerId)
+                    .col(ImportQuarantine::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_import_quarantine_job")
+                    .table((Smdb, ImportQuarantine::Table))
+                    .col(ImportQuarantine::JobId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, ImportQuarantine::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, ImportJob::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(QuarantineStatus::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImportJob {
+    Table,
+    Id,
+    PlayerId,
+    Source,
+    ExternalUsername,
+    Fetched,
+    Imported,
+    Duplicates,
+    Failed,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ImportQuarantine {
+    Table,
+    Id,
+    JobId,
+    PlayerId,
+    ExternalId,
+    Pgn,
+    Error,
+    Status,
+    Attempts,
+    GameId,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum QuarantineStatus {
+    #[sea_orm(iden = "quarantine_status")]
+    Type,
+    Pending,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(DeriveIden)]
+enum ImportSource {
+    #[sea_orm(iden = "import_source")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
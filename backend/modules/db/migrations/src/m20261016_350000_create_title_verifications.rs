@@ -0,0 +1,244 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ChessTitle::Type)
+                    .values([
+                        ChessTitle::Gm,
+                        ChessTitle::Im,
+                        ChessTitle::Fm,
+                        ChessTitle::Cm,
+                        ChessTitle::Wgm,
+                        ChessTitle::Wim,
+                        ChessTitle::Wfm,
+                        ChessTitle::Wcm,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TitleVerificationStatus::Type)
+                    .values([
+                        TitleVerificationStatus::Pending,
+                        TitleVerificationStatus::Approved,
+                        TitleVerificationStatus::Rejected,
+                        TitleVerificationStatus::Revoked,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Verified title shown as a badge on the profile
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Player::Table))
+                    .add_column(ColumnDef::new(Player::Title).custom(ChessTitle::Type).null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Titles claimed by players with their FIDE id as evidence, and the
+        // moderator's review
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, TitleVerification::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(TitleVerification::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(TitleVerification::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(TitleVerification::Title).custom(ChessTitle::Type).not_null())
+                    .col(ColumnDef::new(TitleVerification::FideId).big_integer().not_null())
+                    .col(ColumnDef::new(TitleVerification::Evidence).text().not_null())
+                    .col(ColumnDef::new(TitleVerification::Status).custom(TitleVerificationStatus::Type).not_null())
+                    .col(ColumnDef::new(TitleVerification::ReviewedBy).uuid().null())
+                    .col(ColumnDef::new(TitleVerification::ReviewNote).text().null())
+                    .col(
+                        ColumnDef::new(TitleVerification::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TitleVerification::ReviewedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_title_verification_player")
+                            .from((Smdb, TitleVerification::Table), TitleVerification::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The moderator queue lists requests by status, oldest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_title_verification_status_created")
+                    .table((Smdb, TitleVerification::Table))
+                    .col(TitleVerification::Status)
+                    .col(TitleVerification::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_title_verification_player")
+                    .table((Smdb, TitleVerification::Table))
+                    .col(TitleVerification::PlayerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every change of status, by whom and why
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, TitleVerificationEvent::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(TitleVerificationEvent::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(TitleVerificationEvent::VerificationId).uuid().not_null())
+                    .col(ColumnDef::new(TitleVerificationEvent::ActorId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(TitleVerificationEvent::FromStatus)
+                            .custom(TitleVerificationStatus::Type)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(TitleVerificationEvent::ToStatus)
+                            .custom(TitleVerificationStatus::Type)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TitleVerificationEvent::Note).text().null())
+                    .col(
+                        ColumnDef::new(TitleVerificationEvent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_title_verification_event_verification")
+                            .from((Smdb, TitleVerificationEvent::Table), TitleVerificationEvent::VerificationId)
+                            .to((Smdb, TitleVerification::Table), TitleVerification::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_title_verification_event_verification")
+                    .table((Smdb, TitleVerificationEvent::Table))
+                    .col(TitleVerificationEvent::VerificationId)
+                    .col(TitleVerificationEvent::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, TitleVerificationEvent::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, TitleVerification::Table)).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Player::Table))
+                    .drop_column(Player::Title)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_type(Type::drop().name(TitleVerificationStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ChessTitle::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TitleVerification {
+    Table,
+    Id,
+    PlayerId,
+    Title,
+    FideId,
+    Evidence,
+    Status,
+    ReviewedBy,
+    ReviewNote,
+    CreatedAt,
+    ReviewedAt,
+}
+
+#[derive(DeriveIden)]
+enum TitleVerificationEvent {
+    Table,
+    Id,
+    VerificationId,
+    ActorId,
+    FromStatus,
+    ToStatus,
+    Note,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ChessTitle {
+    #[sea_orm(iden = "chess_title")]
+    Type,
+    Gm,
+    Im,
+    Fm,
+    Cm,
+    Wgm,
+    Wim,
+    Wfm,
+    Wcm,
+}
+
+#[derive(DeriveIden)]
+enum TitleVerificationStatus {
+    #[sea_orm(iden = "title_verification_status")]
+    Type,
+    Pending,
+    Approved,
+    Rejected,
+    Revoked,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+    Title,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,276 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ClubRole::Type)
+                    .values([ClubRole::Owner, ClubRole::Admin, ClubRole::Member])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(LadderChallengeStatus::Type)
+                    .values([
+                        LadderChallengeStatus::Pending,
+                        LadderChallengeStatus::Accepted,
+                        LadderChallengeStatus::Declined,
+                        LadderChallengeStatus::Completed,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, Club::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(Club::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Club::Name).string_len(100).not_null().unique_key())
+                    .col(ColumnDef::new(Club::Description).text().not_null().default(""))
+                    .col(ColumnDef::new(Club::OwnerId).uuid().not_null())
+                    // Anyone may join an open club; admins add members to the others
+                    .col(ColumnDef::new(Club::IsOpen).boolean().not_null().default(true))
+                    // How many places up the ladder a member may challenge
+                    .col(ColumnDef::new(Club::LadderReach).integer().not_null().default(3))
+                    .col(
+                        ColumnDef::new(Club::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_club_owner")
+                            .from((Smdb, Club::Table), Club::OwnerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, ClubMember::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(ClubMember::ClubId).uuid().not_null())
+                    .col(ColumnDef::new(ClubMember::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(ClubMember::Role).custom(ClubRole::Type).not_null())
+                    // Place on the club ladder, 1 at the top
+                    .col(ColumnDef::new(ClubMember::LadderRank).integer().not_null())
+                    .col(
+                        ColumnDef::new(ClubMember::JoinedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(Index::create().col(ClubMember::ClubId).col(ClubMember::PlayerId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_club_member_club")
+                            .from((Smdb, ClubMember::Table), ClubMember::ClubId)
+                            .to((Smdb, Club::Table), Club::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_club_member_player")
+                            .from((Smdb, ClubMember::Table), ClubMember::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Clubs of a player, and the club-scoped game lobby
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_club_member_player")
+                    .table((Smdb, ClubMember::Table))
+                    .col(ClubMember::PlayerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, LadderChallenge::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(LadderChallenge::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(LadderChallenge::ClubId).uuid().not_null())
+                    .col(ColumnDef::new(LadderChallenge::ChallengerId).uuid().not_null())
+                    .col(ColumnDef::new(LadderChallenge::DefenderId).uuid().not_null())
+                    .col(ColumnDef::new(LadderChallenge::Status).custom(LadderChallengeStatus::Type).not_null())
+                    // No foreign key: the game moves to the archive once it is old
+                    .col(ColumnDef::new(LadderChallenge::GameId).uuid().null())
+                    .col(ColumnDef::new(LadderChallenge::WinnerId).uuid().null())
+                    .col(
+                        ColumnDef::new(LadderChallenge::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(LadderChallenge::ResolvedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ladder_challenge_club")
+                            .from((Smdb, LadderChallenge::Table), LadderChallenge::ClubId)
+                            .to((Smdb, Club::Table), Club::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ladder_challenge_club_status")
+                    .table((Smdb, LadderChallenge::Table))
+                    .col(LadderChallenge::ClubId)
+                    .col(LadderChallenge::Status)
+                    .col(LadderChallenge::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tournaments only club members can enter
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .add_column(ColumnDef::new(Tournament::ClubId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_tournament_club")
+                            .from_tbl((Smdb, Tournament::Table))
+                            .from_col(Tournament::ClubId)
+                            .to_tbl((Smdb, Club::Table))
+                            .to_col(Club::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, Tournament::Table))
+                    .drop_foreign_key(Alias::new("fk_tournament_club"))
+                    .drop_column(Tournament::ClubId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, LadderChallenge::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, ClubMember::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, Club::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(LadderChallengeStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(ClubRole::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Club {
+    Table,
+    Id,
+    Name,
+    Description,
+    OwnerId,
+    IsOpen,
+    LadderReach,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ClubMember {
+    Table,
+    ClubId,
+    PlayerId,
+    Role,
+    LadderRank,
+    JoinedAt,
+}
+
+#[derive(DeriveIden)]
+enum LadderChallenge {
+    Table,
+    Id,
+    ClubId,
+    ChallengerId,
+    DefenderId,
+    Status,
+    GameId,
+    WinnerId,
+    CreatedAt,
+    ResolvedAt,
+}
+
+#[derive(DeriveIden)]
+enum ClubRole {
+    #[sea_orm(iden = "club_role")]
+    Type,
+    Owner,
+    Admin,
+    Member,
+}
+
+#[derive(DeriveIden)]
+enum LadderChallengeStatus {
+    #[sea_orm(iden = "ladder_challenge_status")]
+    Type,
+    Pending,
+    Accepted,
+    Declined,
+    Completed,
+}
+
+#[derive(DeriveIden)]
+enum Tournament {
+    Table,
+    ClubId,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
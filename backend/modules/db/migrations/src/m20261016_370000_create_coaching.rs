@@ -0,0 +1,284 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(CoachingLinkStatus::Type)
+                    .values([CoachingLinkStatus::Pending, CoachingLinkStatus::Active, CoachingLinkStatus::Ended])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AssignmentKind::Type)
+                    .values([AssignmentKind::Puzzle, AssignmentKind::Study, AssignmentKind::Drill])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AssignmentStatus::Type)
+                    .values([AssignmentStatus::Assigned, AssignmentStatus::Completed])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, CoachingLink::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(CoachingLink::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(CoachingLink::CoachId).uuid().not_null())
+                    .col(ColumnDef::new(CoachingLink::StudentId).uuid().not_null())
+                    .col(ColumnDef::new(CoachingLink::Status).custom(CoachingLinkStatus::Type).not_null())
+                    .col(
+                        ColumnDef::new(CoachingLink::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    // Set when the student consents to share their games and stats
+                    .col(ColumnDef::new(CoachingLink::AcceptedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(CoachingLink::EndedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_coaching_link_coach")
+                            .from((Smdb, CoachingLink::Table), CoachingLink::CoachId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_coaching_link_student")
+                            .from((Smdb, CoachingLink::Table), CoachingLink::StudentId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_coaching_link_coach")
+                    .table((Smdb, CoachingLink::Table))
+                    .col(CoachingLink::CoachId)
+                    .col(CoachingLink::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_coaching_link_student")
+                    .table((Smdb, CoachingLink::Table))
+                    .col(CoachingLink::StudentId)
+                    .col(CoachingLink::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, CoachingAssignment::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(CoachingAssignment::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(CoachingAssignment::LinkId).uuid().not_null())
+                    .col(ColumnDef::new(CoachingAssignment::CoachId).uuid().not_null())
+                    .col(ColumnDef::new(CoachingAssignment::StudentId).uuid().not_null())
+                    .col(ColumnDef::new(CoachingAssignment::Kind).custom(AssignmentKind::Type).not_null())
+                    .col(ColumnDef::new(CoachingAssignment::Title).string_len(200).not_null())
+                    .col(ColumnDef::new(CoachingAssignment::Instructions).text().not_null().default(""))
+                    // Puzzle: starting position and the line that solves it, in UCI
+                    .col(ColumnDef::new(CoachingAssignment::Fen).text().null())
+                    .col(ColumnDef::new(CoachingAssignment::Solution).text().null())
+                    // Study: the game to go through; no foreign key, as the game
+                    // moves to the archive once it is old
+                    .col(ColumnDef::new(CoachingAssignment::GameId).uuid().null())
+                    // Drill: the drill and the correct answers a session needs
+                    .col(ColumnDef::new(CoachingAssignment::Drill).custom(TrainingDrill::Type).null())
+                    .col(ColumnDef::new(CoachingAssignment::Target).integer().null())
+                    .col(ColumnDef::new(CoachingAssignment::DueAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(CoachingAssignment::Status).custom(AssignmentStatus::Type).not_null())
+                    .col(ColumnDef::new(CoachingAssignment::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(CoachingAssignment::TrainingSessionId).uuid().null())
+                    .col(ColumnDef::new(CoachingAssignment::StudentNote).text().null())
+                    .col(
+                        ColumnDef::new(CoachingAssignment::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CoachingAssignment::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CoachingAssignment::CompletedAt).timestamp_with_time_zone().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_coaching_assignment_link")
+                            .from((Smdb, CoachingAssignment::Table), CoachingAssignment::LinkId)
+                            .to((Smdb, CoachingLink::Table), CoachingLink::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_coaching_assignment_session")
+                            .from((Smdb, CoachingAssignment::Table), CoachingAssignment::TrainingSessionId)
+                            .to((Smdb, TrainingSession::Table), TrainingSession::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A student's open work, soonest due first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_coaching_assignment_student")
+                    .table((Smdb, CoachingAssignment::Table))
+                    .col(CoachingAssignment::StudentId)
+                    .col(CoachingAssignment::Status)
+                    .col(CoachingAssignment::DueAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_coaching_assignment_link")
+                    .table((Smdb, CoachingAssignment::Table))
+                    .col(CoachingAssignment::LinkId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, CoachingAssignment::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, CoachingLink::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(AssignmentStatus::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(AssignmentKind::Type).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(CoachingLinkStatus::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CoachingLink {
+    Table,
+    Id,
+    CoachId,
+    StudentId,
+    Status,
+    CreatedAt,
+    AcceptedAt,
+    EndedAt,
+}
+
+#[derive(DeriveIden)]
+enum CoachingAssignment {
+    Table,
+    Id,
+    LinkId,
+    CoachId,
+    StudentId,
+    Kind,
+    Title,
+    Instructions,
+    Fen,
+    Solution,
+    GameId,
+    Drill,
+    Target,
+    DueAt,
+    Status,
+    Attempts,
+    TrainingSessionId,
+    StudentNote,
+    CreatedAt,
+    UpdatedAt,
+    CompletedAt,
+}
+
+#[derive(DeriveIden)]
+enum CoachingLinkStatus {
+    #[sea_orm(iden = "coaching_link_status")]
+    Type,
+    Pending,
+    Active,
+    Ended,
+}
+
+#[derive(DeriveIden)]
+enum AssignmentKind {
+    #[sea_orm(iden = "assignment_kind")]
+    Type,
+    Puzzle,
+    Study,
+    Drill,
+}
+
+#[derive(DeriveIden)]
+enum AssignmentStatus {
+    #[sea_orm(iden = "assignment_status")]
+    Type,
+    Assigned,
+    Completed,
+}
+
+#[derive(DeriveIden)]
+enum TrainingDrill {
+    #[sea_orm(iden = "training_drill")]
+    Type,
+}
+
+#[derive(DeriveIden)]
+enum TrainingSession {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
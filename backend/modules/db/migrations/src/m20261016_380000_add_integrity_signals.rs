@@ -0,0 +1,93 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(IntegritySignal::Type)
+                    .values([
+                        IntegritySignal::PrizeSandbagging,
+                        IntegritySignal::RatingVolatility,
+                        IntegritySignal::WinTrading,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Queue entries raised by the rating integrity scan name the pattern
+        // they matched and carry what matched it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, ModerationReport::Table))
+                    .add_column(ColumnDef::new(ModerationReport::Signal).custom(IntegritySignal::Type).null())
+                    .add_column(ColumnDef::new(ModerationReport::Evidence).json_binary().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_moderation_report_signal")
+                    .table((Smdb, ModerationReport::Table))
+                    .col(ModerationReport::ReportedPlayerId)
+                    .col(ModerationReport::Signal)
+                    .col(ModerationReport::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_moderation_report_signal")
+                    .table((Smdb, ModerationReport::Table))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, ModerationReport::Table))
+                    .drop_column(ModerationReport::Evidence)
+                    .drop_column(ModerationReport::Signal)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_type(Type::drop().name(IntegritySignal::Type).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ModerationReport {
+    Table,
+    ReportedPlayerId,
+    Status,
+    Signal,
+    Evidence,
+}
+
+#[derive(DeriveIden)]
+enum IntegritySignal {
+    #[sea_orm(iden = "integrity_signal")]
+    Type,
+    PrizeSandbagging,
+    RatingVolatility,
+    WinTrading,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,173 @@
+use sea_orm_migration::{prelude::*, prelude::extension::postgres::Type};
+use sea_orm_migration::prelude::ForeignKeyAction;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(FingerprintKind::Type)
+                    .values([FingerprintKind::Network, FingerprintKind::Device])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(IntegritySignal::Type)
+                    .add_value(IntegritySignal::MultiAccount)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keyed hashes of the networks and devices a player logged in or
+        // connected from; the addresses themselves are never stored
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, PlayerFingerprint::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayerFingerprint::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(PlayerFingerprint::Kind).custom(FingerprintKind::Type).not_null())
+                    .col(ColumnDef::new(PlayerFingerprint::Hash).string_len(64).not_null())
+                    .col(ColumnDef::new(PlayerFingerprint::FirstSeenAt).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(PlayerFingerprint::LastSeenAt).timestamp_with_time_zone().not_null())
+                    .col(ColumnDef::new(PlayerFingerprint::SeenCount).integer().not_null().default(1))
+                    .primary_key(Index::create().col(PlayerFingerprint::PlayerId).col(PlayerFingerprint::Hash))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_player_fingerprint_player")
+                            .from((Smdb, PlayerFingerprint::Table), PlayerFingerprint::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_player_fingerprint_hash")
+                    .table((Smdb, PlayerFingerprint::Table))
+                    .col(PlayerFingerprint::Hash)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Accounts a moderator found to belong to the same person, stored
+        // once per pair with the lower id first
+        manager
+            .create_table(
+                Table::create()
+                    .table((Smdb, AccountLink::Table))
+                    .if_not_exists()
+                    .col(ColumnDef::new(AccountLink::PlayerId).uuid().not_null())
+                    .col(ColumnDef::new(AccountLink::LinkedPlayerId).uuid().not_null())
+                    .col(ColumnDef::new(AccountLink::MarkedBy).uuid().not_null())
+                    .col(ColumnDef::new(AccountLink::Note).text().null())
+                    .col(
+                        ColumnDef::new(AccountLink::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .primary_key(Index::create().col(AccountLink::PlayerId).col(AccountLink::LinkedPlayerId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_account_link_player")
+                            .from((Smdb, AccountLink::Table), AccountLink::PlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_account_link_linked_player")
+                            .from((Smdb, AccountLink::Table), AccountLink::LinkedPlayerId)
+                            .to((Smdb, Player::Table), Player::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_account_link_linked_player")
+                    .table((Smdb, AccountLink::Table))
+                    .col(AccountLink::LinkedPlayerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table((Smdb, AccountLink::Table)).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table((Smdb, PlayerFingerprint::Table)).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(FingerprintKind::Type).to_owned())
+            .await?;
+        // Postgres cannot drop a value from an enum; `multi_account` stays
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayerFingerprint {
+    Table,
+    PlayerId,
+    Kind,
+    Hash,
+    FirstSeenAt,
+    LastSeenAt,
+    SeenCount,
+}
+
+#[derive(DeriveIden)]
+enum AccountLink {
+    Table,
+    PlayerId,
+    LinkedPlayerId,
+    MarkedBy,
+    Note,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum FingerprintKind {
+    #[sea_orm(iden = "fingerprint_kind")]
+    Type,
+    Network,
+    Device,
+}
+
+#[derive(DeriveIden)]
+enum IntegritySignal {
+    #[sea_orm(iden = "integrity_signal")]
+    Type,
+    MultiAccount,
+}
+
+#[derive(DeriveIden)]
+enum Player {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Refreshed by the instance running a match, so that other instances
+        // only take over matches whose runner went away
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, EngineMatch::Table))
+                    .add_column(ColumnDef::new(EngineMatch::HeartbeatAt).timestamp_with_time_zone().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Added heartbeat_at column to engine_match table.");
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table((Smdb, EngineMatch::Table))
+                    .drop_column(EngineMatch::HeartbeatAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        println!("Removed heartbeat_at column from engine_match table.");
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum EngineMatch {
+    Table,
+    HeartbeatAt,
+}
+
+#[derive(DeriveIden)]
+struct Smdb;
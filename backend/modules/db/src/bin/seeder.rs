@@ -104,6 +104,7 @@ async fn main() -> Result<(), DbErr> {
             updated_at: Set(Utc::now().into()),
             is_imported: Set(false),
             original_pgn: Set(None),
+            odds: Set(None),
         };
 
         Game::insert(game).exec(&db).await?;
@@ -92,6 +92,7 @@ mod tests {
             ("real_name","character varying"),
             ("location","character varying"),
             ("fide_rating","integer"),
+            ("fide_id","bigint"),
             ("social_links","ARRAY")
         ]);
 
@@ -0,0 +1,40 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::account_closure;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CloseAccountRequest {
+    /// Current password, to confirm the closure
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ReactivateAccountRequest {
+    #[validate(length(min = 1, message = "Username is required"))]
+    #[schema(example = "magnus")]
+    pub username: String,
+
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountClosureDisplay {
+    #[schema(value_type = String, format = "date-time")]
+    pub requested_at: DateTime<FixedOffset>,
+    /// Personal data is removed after this; until then the account can be reactivated
+    #[schema(value_type = String, format = "date-time")]
+    pub purge_after: DateTime<FixedOffset>,
+}
+
+impl From<account_closure::Model> for AccountClosureDisplay {
+    fn from(value: account_closure::Model) -> Self {
+        Self {
+            requested_at: value.requested_at,
+            purge_after: value.purge_after,
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
-use validator::Validate;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use uuid::Uuid;
+
+use crate::games::PlayerColor;
 
 // Define a regex for validating FEN chess position notation
 static FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -11,14 +15,126 @@ static FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
     ).unwrap()
 });
 
+// Chess960 castling rights may name the rook file (Shredder-FEN and X-FEN)
+static CHESS960_FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+      r"^([rnbqkpRNBQKP1-8]+/){7}[rnbqkpRNBQKP1-8]+\s[bw]\s(-|[KQkqA-Ha-h]+)\s(-|[a-h][36])\s\d+\s\d+$"
+    ).unwrap()
+});
+
+// Crazyhouse adds the pockets, in brackets or as a ninth rank, and marks
+// promoted pieces with `~`
+static CRAZYHOUSE_FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+      r"^([rnbqkpRNBQKP1-8~]+/){7}[rnbqkpRNBQKP1-8~]+(\[[qrbnpQRBNP]*\]|/[qrbnpQRBNP]*)\s[bw]\s(-|[KQkq]+)\s(-|[a-h][36])\s\d+\s\d+$"
+    ).unwrap()
+});
+
+const FEN_MESSAGE: &str = "Must be a valid FEN string in format: [piece placement] [active color] [castling] [en passant] [halfmove clock] [fullmove number]";
+
+/// Rules an analysis runs under; each may be served by its own engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisVariant {
+    #[default]
+    Standard,
+    Chess960,
+    Crazyhouse,
+}
+
+impl AnalysisVariant {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::Standard),
+            "chess960" => Some(Self::Chess960),
+            "crazyhouse" => Some(Self::Crazyhouse),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Chess960 => "chess960",
+            Self::Crazyhouse => "crazyhouse",
+        }
+    }
+
+    fn fen_regex(self) -> &'static Regex {
+        match self {
+            Self::Standard => &FEN_REGEX,
+            Self::Chess960 => &CHESS960_FEN_REGEX,
+            Self::Crazyhouse => &CRAZYHOUSE_FEN_REGEX,
+        }
+    }
+}
+
+/// Who an analysis is run for, which decides how far it is cut back while
+/// the engine pool is busy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisTier {
+    /// Anonymous callers and guest sessions
+    #[default]
+    Guest,
+    /// Signed-in players
+    Player,
+    /// Players whose analysis keeps its depth whatever the load
+    Premium,
+}
+
+impl AnalysisTier {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "guest" => Some(Self::Guest),
+            "player" => Some(Self::Player),
+            "premium" => Some(Self::Premium),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Guest => "guest",
+            Self::Player => "player",
+            Self::Premium => "premium",
+        }
+    }
+}
+
+fn validate_fen(fen: &str, variant: AnalysisVariant) -> Result<(), ValidationError> {
+    if variant.fen_regex().is_match(fen) {
+        return Ok(());
+    }
+    let mut err = ValidationError::new("fen");
+    err.message = Some(Cow::Borrowed(FEN_MESSAGE));
+    Err(err)
+}
+
+fn validate_suggestion_fen(request: &AiSuggestionRequest) -> Result<(), ValidationError> {
+    validate_fen(&request.fen, request.variant)
+}
+
+fn validate_analysis_fen(request: &PositionAnalysisRequest) -> Result<(), ValidationError> {
+    validate_fen(&request.fen, request.variant)
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_suggestion_fen"))]
 pub struct AiSuggestionRequest {
-    #[validate(regex(
-        path = "FEN_REGEX", 
-        message = "Must be a valid FEN string in format: [piece placement] [active color] [castling] [en passant] [halfmove clock] [fullmove number]"
-    ))]
+    /// FEN of the position, in the notation of `variant`
     #[schema(example = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
     pub fen: String,
+
+    /// Picks the engine that searches the position
+    #[serde(default)]
+    pub variant: AnalysisVariant,
+
+    /// Game the position comes from, when following one move by move: its
+    /// positions are searched on the same engine, which keeps what it
+    /// learned from the last one
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
     
     #[validate(range(min = 1, max = 20, message = "Depth must be between 1 and 20"))]
     #[schema(example = 10)]
@@ -47,13 +163,19 @@ pub struct AiSuggestionResponse {
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_analysis_fen"))]
 pub struct PositionAnalysisRequest {
-    #[validate(regex(
-        path = "FEN_REGEX",
-        message = "Must be a valid FEN string in format: [piece placement] [active color] [castling] [en passant] [halfmove clock] [fullmove number]"
-    ))]
+    /// FEN of the position, in the notation of `variant`
     #[schema(example = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
     pub fen: String,
+
+    /// Picks the engine that analyses the position
+    #[serde(default)]
+    pub variant: AnalysisVariant,
+
+    /// Game the position comes from, as for suggestions
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
     
     #[validate(range(min = 1, max = 30, message = "Depth must be between 1 and 30"))]
     #[schema(example = 15)]
@@ -81,3 +203,238 @@ pub struct AlternativeMove {
     #[schema(example = 0.25)]
     pub evaluation: f32,
 }
+
+/// Jobs of one priority that have started, and how long they were queued.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineWaitStats {
+    #[schema(example = 1200)]
+    pub started: u64,
+
+    #[schema(example = 85)]
+    pub mean_wait_ms: u64,
+
+    #[schema(example = 4100)]
+    pub max_wait_ms: u64,
+}
+
+/// Load of the engine pool: interactive requests go ahead of batch jobs.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineQueueStats {
+    #[schema(example = 4)]
+    pub slots: usize,
+
+    #[schema(example = 1)]
+    pub reserved_interactive: usize,
+
+    #[schema(example = 2)]
+    pub per_user_limit: usize,
+
+    #[schema(example = 3)]
+    pub running: usize,
+
+    #[schema(example = 2)]
+    pub running_batch: usize,
+
+    #[schema(example = 0)]
+    pub interactive_waiting: usize,
+
+    #[schema(example = 17)]
+    pub batch_waiting: usize,
+
+    pub interactive_wait: EngineWaitStats,
+
+    pub batch_wait: EngineWaitStats,
+
+    /// Share of their depth and time interactive searches get at the current
+    /// load, before the floor of the caller's tier
+    #[schema(example = 0.75)]
+    pub depth_scale: f32,
+
+    /// Engines kept between searches of followed games
+    #[schema(example = 5)]
+    pub sessions: usize,
+}
+
+/// A setting an engine accepts.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineOptionDisplay {
+    #[schema(example = "Skill Level")]
+    pub name: String,
+
+    /// `check`, `spin`, `combo`, `button` or `string`
+    #[schema(example = "spin")]
+    pub kind: String,
+
+    #[schema(example = "20")]
+    pub default: Option<String>,
+
+    #[schema(example = 0)]
+    pub min: Option<i64>,
+
+    #[schema(example = 20)]
+    pub max: Option<i64>,
+
+    /// Values a `combo` option can take
+    pub vars: Vec<String>,
+}
+
+/// An engine the server runs, as it introduced itself.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineDisplay {
+    /// `analysis` for the engine behind analysis and bots, `analysis-<variant>`
+    /// for one that analyses a variant, otherwise the name of a match engine
+    #[schema(example = "analysis")]
+    pub id: String,
+
+    #[schema(example = "Stockfish 16")]
+    pub name: Option<String>,
+
+    #[schema(example = "the Stockfish developers (see AUTHORS file)")]
+    pub author: Option<String>,
+
+    pub options: Vec<EngineOptionDisplay>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct EngineBenchmarkRequest {
+    /// Plies searched in every position; 12 when missing
+    #[validate(range(min = 1, max = 30, message = "Depth must be between 1 and 30"))]
+    #[schema(example = 12)]
+    pub depth: Option<u8>,
+}
+
+/// One position of the benchmark suite, as an engine searched it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BenchmarkPositionDisplay {
+    #[schema(example = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
+    pub fen: String,
+
+    #[schema(example = "e2e4")]
+    pub best_move: String,
+
+    /// Depth the engine reached, which may be less than asked
+    #[schema(example = 12)]
+    pub depth: Option<u8>,
+
+    #[schema(example = 184_210)]
+    pub nodes: Option<u64>,
+
+    #[schema(example = 95)]
+    pub time_ms: u64,
+}
+
+/// The benchmark suite run on one engine. Node counts are missing for
+/// engines that do not report them.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EngineBenchmarkDisplay {
+    /// Same ids as the engine list
+    #[schema(example = "analysis")]
+    pub id: String,
+
+    #[schema(example = 12)]
+    pub depth: u8,
+
+    pub positions: Vec<BenchmarkPositionDisplay>,
+
+    #[schema(example = 1_473_680)]
+    pub total_nodes: Option<u64>,
+
+    #[schema(example = 760)]
+    pub total_time_ms: u64,
+
+    #[schema(example = 1_939_052)]
+    pub nodes_per_second: Option<u64>,
+}
+
+/// An engine opponent of a set strength and style.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BotDisplay {
+    #[schema(example = "casual")]
+    pub id: String,
+
+    #[schema(example = "Casey")]
+    pub name: String,
+
+    #[schema(example = "Plays the classical openings and misses the odd tactic")]
+    pub description: String,
+
+    /// Rough playing strength
+    #[schema(example = 1200)]
+    pub rating: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct StartBotGameRequest {
+    #[schema(example = "casual")]
+    pub bot: String,
+
+    #[validate(range(min = 60, max = 7200, message = "Time control must be between 1 minute and 2 hours"))]
+    #[schema(example = 600)]
+    pub time_control: i32,
+
+    #[validate(range(min = 0, max = 60, message = "Increment must be between 0 and 60 seconds"))]
+    #[schema(example = 0)]
+    pub increment: i32,
+
+    /// The player's color; random when missing
+    pub player_color: Option<PlayerColor>,
+}
+
+/// A new game against a bot. Bot games are never rated.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BotGameDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+
+    #[schema(example = "casual")]
+    pub bot: String,
+
+    #[schema(example = "black")]
+    pub bot_color: PlayerColor,
+
+    #[schema(example = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
+    pub current_fen: String,
+
+    pub time_control: i32,
+    pub increment: i32,
+    pub rated: bool,
+
+    /// The bot's first move when it has White
+    pub bot_move: Option<BotMoveResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BotMoveRequest {
+    #[schema(example = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")]
+    pub fen: String,
+
+    /// The game's moves in UCI notation from the standard starting position,
+    /// so the bot can follow its openings; leave empty for other positions
+    #[serde(default)]
+    #[schema(example = json!(["e2e4"]))]
+    pub moves: Vec<String>,
+
+    /// Time left on the bot's clock, which it paces its replies to
+    #[schema(example = 540000)]
+    pub remaining_ms: Option<u64>,
+
+    #[schema(example = 0)]
+    pub increment_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BotMoveResponse {
+    #[schema(example = "e7e5")]
+    pub chess_move: String,
+
+    /// From the bot's point of view; missing for book moves
+    #[schema(example = 0.2)]
+    pub evaluation: Option<f32>,
+
+    pub from_book: bool,
+
+    /// Time the bot took over the move, search included; it takes longer
+    /// over hard moves, as a person would
+    #[schema(example = 2400)]
+    pub computation_time_ms: u32,
+}
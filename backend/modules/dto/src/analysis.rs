@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::games::Side;
+
+/// Stage of the game a move was played in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    /// The first ten moves, unless the pieces came off before
+    Opening,
+    Middlegame,
+    /// Pieces other than kings and pawns worth 26 pawns or less on the board
+    Endgame,
+}
+
+/// How bad an annotated mistake was
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MistakeSeverity {
+    /// Marked `?`
+    Mistake,
+    /// Marked `??`
+    Blunder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct MoveTimeDisplay {
+    /// 1 is White's first move
+    pub ply: u32,
+    pub side: Side,
+    #[schema(example = "Nf3")]
+    pub notation: String,
+    pub phase: GamePhase,
+    /// Time the mover thought; absent for moves logged without a clock
+    pub spent_ms: Option<i64>,
+    /// The mover's clock after the move
+    pub remaining_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct PhaseTimeDisplay {
+    pub phase: GamePhase,
+    /// Moves of the side in this phase, timed or not
+    pub moves: u32,
+    pub spent_ms: i64,
+}
+
+/// A move the annotations mark as a mistake, with the clock around it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct CriticalMoveDisplay {
+    pub ply: u32,
+    #[schema(example = "Qh5")]
+    pub notation: String,
+    pub severity: MistakeSeverity,
+    pub spent_ms: Option<i64>,
+    pub remaining_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct SideTimeUsage {
+    /// Time spent on all timed moves
+    pub spent_ms: i64,
+    /// Opening, middlegame and endgame, in that order
+    pub phases: Vec<PhaseTimeDisplay>,
+    /// The move thought on longest; the earliest of equally long ones
+    pub longest_think: Option<MoveTimeDisplay>,
+    /// Moves marked `?` or `??` in the annotations the caller may read
+    pub critical_moves: Vec<CriticalMoveDisplay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct TimeUsageReport {
+    pub white: SideTimeUsage,
+    pub black: SideTimeUsage,
+    /// Every move that stands, in the order played
+    pub moves: Vec<MoveTimeDisplay>,
+}
+
+/// Post-game report of a finished game
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GameAnalysisDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+    pub time: TimeUsageReport,
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::game_annotation;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Who may read a set of annotations
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationVisibility {
+    Private,
+    /// You and the players on your friends list
+    Friends,
+    Public,
+}
+
+/// Evaluation marker, exported as a PGN NAG
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Nag {
+    Good,
+    Mistake,
+    Brilliant,
+    Blunder,
+    Interesting,
+    Dubious,
+    Equal,
+    Unclear,
+    WhiteSlightlyBetter,
+    BlackSlightlyBetter,
+    WhiteBetter,
+    BlackBetter,
+    WhiteWinning,
+    BlackWinning,
+}
+
+// Mirrors `chess::MoveNote`; the service converts between the two through serde
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct MoveNote {
+    #[schema(example = "The only move that keeps the balance")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nags: Vec<Nag>,
+    /// Lines that could have been played instead of this move
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<Vec<VariationMove>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct VariationMove {
+    #[schema(example = "Nf3")]
+    pub san: String,
+    #[serde(default)]
+    pub note: MoveNote,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SaveAnnotationsRequest {
+    pub visibility: AnnotationVisibility,
+    /// Notes keyed by ply; ply 1 is White's first move
+    pub moves: BTreeMap<u32, MoveNote>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AnnotationDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub author_id: Uuid,
+    pub author_username: String,
+    pub visibility: AnnotationVisibility,
+    pub moves: BTreeMap<u32, MoveNote>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FriendDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub username: String,
+    #[schema(value_type = String, format = "date-time")]
+    pub added_at: DateTime<FixedOffset>,
+}
+
+impl From<AnnotationVisibility> for game_annotation::AnnotationVisibility {
+    fn from(value: AnnotationVisibility) -> Self {
+        match value {
+            AnnotationVisibility::Private => Self::Private,
+            AnnotationVisibility::Friends => Self::Friends,
+            AnnotationVisibility::Public => Self::Public,
+        }
+    }
+}
+
+impl From<game_annotation::AnnotationVisibility> for AnnotationVisibility {
+    fn from(value: game_annotation::AnnotationVisibility) -> Self {
+        match value {
+            game_annotation::AnnotationVisibility::Private => Self::Private,
+            game_annotation::AnnotationVisibility::Friends => Self::Friends,
+            game_annotation::AnnotationVisibility::Public => Self::Public,
+        }
+    }
+}
@@ -51,6 +51,31 @@ pub struct AuthResponse {
 
     #[schema(example = "chess_master")]
     pub username: String,
+
+    /// Games of the guest session the registration was made from, now
+    /// played by the new account
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub migrated_games: Vec<Uuid>,
+}
+
+/// Short-lived identity for playing casual games without an account
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GuestSessionResponse {
+    #[schema(example = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...")]
+    pub access_token: String,
+
+    #[schema(example = "Bearer")]
+    pub token_type: String,
+
+    #[schema(example = 7200)]
+    pub expires_in: usize,
+
+    #[schema(value_type = String, format = "uuid")]
+    pub guest_id: Uuid,
+
+    #[schema(example = "Guest-3f9a1c")]
+    pub username: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -117,3 +142,68 @@ pub struct LogoutResponse {
 }
 
 pub type LoginResponse = AuthResponse;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct WalletChallengeRequest {
+    /// StarkNet account address, hex
+    #[validate(length(min = 3, max = 66, message = "Address must be a hex felt"))]
+    #[schema(example = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7")]
+    pub address: String,
+}
+
+/// Nonce to sign, as SNIP-12 typed data ready for `account.signMessage`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WalletChallengeResponse {
+    #[schema(example = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7")]
+    pub address: String,
+    #[schema(example = "0x5f1c9a0e3b7d4c2a")]
+    pub nonce: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = Object)]
+    pub typed_data: serde_json::Value,
+}
+
+/// What to solve before registering or creating a game anonymously; the
+/// solution goes in the `X-Captcha` header
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CaptchaChallengeResponse {
+    /// `none` when no captcha is required, `widget` or `proof_of_work`
+    #[schema(example = "proof_of_work")]
+    pub kind: String,
+    /// `hcaptcha` or `turnstile`, for widgets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_key: Option<String>,
+    /// Send `"{challenge}:{counter}"` where the SHA-256 of it starts with
+    /// `difficulty` zero bits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "9f2c4e6a8b0d1f3e5a7c9e1b3d5f7a9c")]
+    pub challenge: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 18)]
+    pub difficulty: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct WalletSignatureRequest {
+    #[validate(length(min = 3, max = 66, message = "Address must be a hex felt"))]
+    #[schema(example = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7")]
+    pub address: String,
+    /// Nonce of the challenge that was signed
+    #[schema(example = "0x5f1c9a0e3b7d4c2a")]
+    pub nonce: String,
+    /// Signature felts as returned by the wallet, hex or decimal
+    #[validate(length(min = 1, max = 16, message = "Signature must have between 1 and 16 parts"))]
+    pub signature: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WalletLinkResponse {
+    #[schema(example = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7")]
+    pub address: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+}
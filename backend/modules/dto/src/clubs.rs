@@ -0,0 +1,248 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{club, club_member, ladder_challenge};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::tournaments::TournamentDisplay;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClubRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Completed,
+}
+
+fn default_open() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateClubRequest {
+    #[validate(length(min = 3, max = 100, message = "Name must be between 3 and 100 characters"))]
+    #[schema(example = "Lagos Knights")]
+    pub name: String,
+
+    #[serde(default)]
+    #[validate(length(max = 2000, message = "Description must be at most 2000 characters"))]
+    #[schema(example = "Weekly blitz and a friendly ladder")]
+    pub description: String,
+
+    /// Anyone may join an open club; admins add members to the others
+    #[serde(default = "default_open")]
+    pub is_open: bool,
+
+    /// How many places up the ladder a member may challenge (default 3)
+    #[validate(range(min = 1, max = 20, message = "Ladder reach must be between 1 and 20"))]
+    #[schema(example = 3)]
+    pub ladder_reach: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AddMemberRequest {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateMemberRequest {
+    /// `admin` or `member`; the owner stays the owner
+    pub role: ClubRole,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ClubListQuery {
+    /// Part of the club name
+    #[schema(example = "knights")]
+    pub name: Option<String>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChallengeRequest {
+    /// Member ranked above the challenger, within the club's ladder reach
+    #[schema(value_type = String, format = "uuid")]
+    pub defender_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RespondChallengeRequest {
+    pub accept: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChallengeResultRequest {
+    /// Finished game between the two players, played since the challenge
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ChallengeQuery {
+    #[schema(example = "pending")]
+    pub status: Option<ChallengeStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClubDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub owner_id: Uuid,
+    pub is_open: bool,
+    pub ladder_reach: i32,
+    #[schema(example = 24)]
+    pub member_count: u64,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClubMemberDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub username: String,
+    pub role: ClubRole,
+    /// Place on the club ladder, 1 at the top
+    #[schema(example = 4)]
+    pub ladder_rank: i32,
+    #[schema(value_type = String, format = "date-time")]
+    pub joined_at: DateTime<FixedOffset>,
+}
+
+/// What a club page shows.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClubPage {
+    pub club: ClubDisplay,
+    /// Top of the ladder
+    pub ladder: Vec<ClubMemberDisplay>,
+    /// Latest club tournaments, newest first
+    pub tournaments: Vec<TournamentDisplay>,
+    /// Challenges waiting for an answer or a game
+    pub open_challenges: Vec<LadderChallengeDisplay>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LadderChallengeDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub club_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub challenger_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub defender_id: Uuid,
+    pub status: ChallengeStatus,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+    /// Absent while undecided and for a draw
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub winner_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub resolved_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<ClubRole> for club_member::ClubRole {
+    fn from(value: ClubRole) -> Self {
+        match value {
+            ClubRole::Owner => Self::Owner,
+            ClubRole::Admin => Self::Admin,
+            ClubRole::Member => Self::Member,
+        }
+    }
+}
+
+impl From<club_member::ClubRole> for ClubRole {
+    fn from(value: club_member::ClubRole) -> Self {
+        match value {
+            club_member::ClubRole::Owner => Self::Owner,
+            club_member::ClubRole::Admin => Self::Admin,
+            club_member::ClubRole::Member => Self::Member,
+        }
+    }
+}
+
+impl From<ChallengeStatus> for ladder_challenge::ChallengeStatus {
+    fn from(value: ChallengeStatus) -> Self {
+        match value {
+            ChallengeStatus::Pending => Self::Pending,
+            ChallengeStatus::Accepted => Self::Accepted,
+            ChallengeStatus::Declined => Self::Declined,
+            ChallengeStatus::Completed => Self::Completed,
+        }
+    }
+}
+
+impl From<ladder_challenge::ChallengeStatus> for ChallengeStatus {
+    fn from(value: ladder_challenge::ChallengeStatus) -> Self {
+        match value {
+            ladder_challenge::ChallengeStatus::Pending => Self::Pending,
+            ladder_challenge::ChallengeStatus::Accepted => Self::Accepted,
+            ladder_challenge::ChallengeStatus::Declined => Self::Declined,
+            ladder_challenge::ChallengeStatus::Completed => Self::Completed,
+        }
+    }
+}
+
+impl ClubDisplay {
+    pub fn new(club: club::Model, member_count: u64) -> Self {
+        Self {
+            id: club.id,
+            name: club.name,
+            description: club.description,
+            owner_id: club.owner_id,
+            is_open: club.is_open,
+            ladder_reach: club.ladder_reach,
+            member_count,
+            created_at: club.created_at,
+        }
+    }
+}
+
+impl ClubMemberDisplay {
+    pub fn new(member: club_member::Model, username: String) -> Self {
+        Self {
+            player_id: member.player_id,
+            username,
+            role: member.role.into(),
+            ladder_rank: member.ladder_rank,
+            joined_at: member.joined_at,
+        }
+    }
+}
+
+impl From<ladder_challenge::Model> for LadderChallengeDisplay {
+    fn from(value: ladder_challenge::Model) -> Self {
+        Self {
+            id: value.id,
+            club_id: value.club_id,
+            challenger_id: value.challenger_id,
+            defender_id: value.defender_id,
+            status: value.status.into(),
+            game_id: value.game_id,
+            winner_id: value.winner_id,
+            created_at: value.created_at,
+            resolved_at: value.resolved_at,
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{coaching_assignment, coaching_link};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::stats::PlayerStats;
+use crate::training::{DrillStats, TrainingDrill};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoachingLinkStatus {
+    Pending,
+    Active,
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentKind {
+    /// Find the line that solves a position
+    Puzzle,
+    /// Go through a game
+    Study,
+    /// Reach a score on a training drill
+    Drill,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentStatus {
+    Assigned,
+    Completed,
+}
+
+/// A finished game from the student's side of the board
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StudentOutcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InviteStudentRequest {
+    #[schema(value_type = String, format = "uuid")]
+    pub student_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateAssignmentRequest {
+    #[schema(value_type = String, format = "uuid")]
+    pub student_id: Uuid,
+
+    pub kind: AssignmentKind,
+
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    #[schema(example = "Back-rank mates")]
+    pub title: String,
+
+    #[serde(default)]
+    #[validate(length(max = 5000, message = "Instructions must be at most 5000 characters"))]
+    #[schema(example = "Find the mate before moving; write down the candidate moves")]
+    pub instructions: String,
+
+    /// Puzzle: the starting position
+    #[schema(example = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1")]
+    pub fen: Option<String>,
+
+    /// Puzzle: the solving line in UCI, both sides' moves
+    #[validate(length(min = 1, max = 40, message = "A solution has between 1 and 40 moves"))]
+    #[schema(example = json!(["d1d8"]))]
+    pub solution: Option<Vec<String>>,
+
+    /// Study: the game to go through
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+
+    /// Drill: the drill to train
+    pub drill: Option<TrainingDrill>,
+
+    /// Drill: correct answers a session needs, out of 20
+    #[validate(range(min = 1, max = 20, message = "Target must be between 1 and 20"))]
+    #[schema(example = 16)]
+    pub target: Option<i32>,
+
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub due_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Fields left out stay as they are.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateAssignmentRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: Option<String>,
+
+    #[validate(length(max = 5000, message = "Instructions must be at most 5000 characters"))]
+    pub instructions: Option<String>,
+
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub due_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CompleteAssignmentRequest {
+    /// Puzzle: the line found, in UCI, with the replies
+    #[validate(length(max = 40, message = "At most 40 moves"))]
+    #[schema(example = json!(["d1d8"]))]
+    pub moves: Option<Vec<String>>,
+
+    /// Drill: a finished session of the drill, started after the assignment
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub training_session_id: Option<Uuid>,
+
+    #[validate(length(max = 2000, message = "Note must be at most 2000 characters"))]
+    #[schema(example = "Missed the quiet move at first")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct AssignmentQuery {
+    #[schema(example = "assigned")]
+    pub status: Option<AssignmentStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CoachingLinkDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub coach_id: Uuid,
+    pub coach_username: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub student_id: Uuid,
+    pub student_username: String,
+    pub status: CoachingLinkStatus,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub accepted_at: Option<DateTime<FixedOffset>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub ended_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AssignmentDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub coach_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub student_id: Uuid,
+    pub kind: AssignmentKind,
+    pub title: String,
+    pub instructions: String,
+    pub fen: Option<String>,
+    /// Shown to the student once the puzzle is solved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution: Option<Vec<String>>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+    pub drill: Option<TrainingDrill>,
+    pub target: Option<i32>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub due_at: Option<DateTime<FixedOffset>>,
+    pub status: AssignmentStatus,
+    /// Puzzle solutions submitted, right or wrong
+    pub attempts: i32,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub training_session_id: Option<Uuid>,
+    pub student_note: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub completed_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Outcome of a completion attempt; a wrong puzzle line leaves the
+/// assignment open.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompletionDisplay {
+    pub completed: bool,
+    pub assignment: AssignmentDisplay,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StudentGameDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// `white` or `black`
+    #[schema(example = "white")]
+    pub color: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub opponent_id: Uuid,
+    pub opponent_username: String,
+    /// Absent while the game is played and for abandoned games
+    pub outcome: Option<StudentOutcome>,
+    pub rated: bool,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+/// What a coach sees of a student who consented to the link.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StudentOverview {
+    #[schema(value_type = String, format = "uuid")]
+    pub student_id: Uuid,
+    pub username: String,
+    pub stats: PlayerStats,
+    pub training: Vec<DrillStats>,
+    /// Newest first
+    pub recent_games: Vec<StudentGameDisplay>,
+    /// Assignments from this coach still open, and completed
+    pub open_assignments: u64,
+    pub completed_assignments: u64,
+}
+
+impl From<CoachingLinkStatus> for coaching_link::CoachingLinkStatus {
+    fn from(value: CoachingLinkStatus) -> Self {
+        match value {
+            CoachingLinkStatus::Pending => Self::Pending,
+            CoachingLinkStatus::Active => Self::Active,
+            CoachingLinkStatus::Ended => Self::Ended,
+        }
+    }
+}
+
+impl From<coaching_link::CoachingLinkStatus> for CoachingLinkStatus {
+    fn from(value: coaching_link::CoachingLinkStatus) -> Self {
+        match value {
+            coaching_link::CoachingLinkStatus::Pending => Self::Pending,
+            coaching_link::CoachingLinkStatus::Active => Self::Active,
+            coaching_link::CoachingLinkStatus::Ended => Self::Ended,
+        }
+    }
+}
+
+impl From<AssignmentKind> for coaching_assignment::AssignmentKind {
+    fn from(value: AssignmentKind) -> Self {
+        match value {
+            AssignmentKind::Puzzle => Self::Puzzle,
+            AssignmentKind::Study => Self::Study,
+            AssignmentKind::Drill => Self::Drill,
+        }
+    }
+}
+
+impl From<coaching_assignment::AssignmentKind> for AssignmentKind {
+    fn from(value: coaching_assignment::AssignmentKind) -> Self {
+        match value {
+            coaching_assignment::AssignmentKind::Puzzle => Self::Puzzle,
+            coaching_assignment::AssignmentKind::Study => Self::Study,
+            coaching_assignment::AssignmentKind::Drill => Self::Drill,
+        }
+    }
+}
+
+impl From<AssignmentStatus> for coaching_assignment::AssignmentStatus {
+    fn from(value: AssignmentStatus) -> Self {
+        match value {
+            AssignmentStatus::Assigned => Self::Assigned,
+            AssignmentStatus::Completed => Self::Completed,
+        }
+    }
+}
+
+impl From<coaching_assignment::AssignmentStatus> for AssignmentStatus {
+    fn from(value: coaching_assignment::AssignmentStatus) -> Self {
+        match value {
+            coaching_assignment::AssignmentStatus::Assigned => Self::Assigned,
+            coaching_assignment::AssignmentStatus::Completed => Self::Completed,
+        }
+    }
+}
+
+impl CoachingLinkDisplay {
+    pub fn new(link: coaching_link::Model, coach_username: String, student_username: String) -> Self {
+        Self {
+            id: link.id,
+            coach_id: link.coach_id,
+            coach_username,
+            student_id: link.student_id,
+            student_username,
+            status: link.status.into(),
+            created_at: link.created_at,
+            accepted_at: link.accepted_at,
+            ended_at: link.ended_at,
+        }
+    }
+}
+
+impl AssignmentDisplay {
+    /// `viewer` sees the puzzle solution if they set it, or once they
+    /// solved it.
+    pub fn new(assignment: coaching_assignment::Model, viewer: Uuid) -> Self {
+        let reveal = viewer == assignment.coach_id
+            || assignment.status == coaching_assignment::AssignmentStatus::Completed;
+        Self {
+            id: assignment.id,
+            coach_id: assignment.coach_id,
+            student_id: assignment.student_id,
+            kind: assignment.kind.into(),
+            title: assignment.title,
+            instructions: assignment.instructions,
+            fen: assignment.fen,
+            solution: assignment
+                .solution
+                .filter(|_| reveal)
+                .map(|line| line.split_whitespace().map(str::to_string).collect()),
+            game_id: assignment.game_id,
+            drill: assignment.drill.map(TrainingDrill::from),
+            target: assignment.target,
+            due_at: assignment.due_at,
+            status: assignment.status.into(),
+            attempts: assignment.attempts,
+            training_session_id: assignment.training_session_id,
+            student_note: assignment.student_note,
+            created_at: assignment.created_at,
+            completed_at: assignment.completed_at,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{game::ResultSide, game_dispute};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::games::GameResult;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeReason {
+    /// Lost on time because of server lag rather than the player's clock
+    WrongFlag,
+    Disconnection,
+    IllegalMove,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Open,
+    Upheld,
+    Overturned,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct OpenDisputeRequest {
+    pub reason: DisputeReason,
+
+    #[validate(length(max = 2000, message = "Details must be at most 2000 characters"))]
+    #[schema(example = "My clock still showed 4 seconds when the game ended on time")]
+    pub details: Option<String>,
+
+    /// Result the claimant believes is right
+    pub requested_result: Option<GameResult>,
+
+    /// Tournament the game was played in; its standings follow the decision
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub tournament_id: Option<Uuid>,
+
+    /// Tournament round of the game, required with `tournament_id`
+    #[validate(range(min = 1, message = "Round must be at least 1"))]
+    #[schema(example = 3)]
+    pub round: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct DecideDisputeRequest {
+    /// Either `upheld` or `overturned`
+    pub status: DisputeStatus,
+
+    /// The right result, required when overturning
+    pub corrected_result: Option<GameResult>,
+
+    #[validate(length(max = 2000, message = "Note must be at most 2000 characters"))]
+    #[schema(example = "Server logs show a 6 second stall before the flag")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct DisputeQueueQuery {
+    #[schema(example = "open")]
+    pub status: Option<DisputeStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+/// The game as it stood when the dispute was opened.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct DisputeEvidence {
+    /// Stored moves and headers, including any clock annotations
+    #[schema(value_type = Object)]
+    pub pgn: serde_json::Value,
+    pub final_fen: String,
+    pub result: GameResult,
+    #[schema(value_type = String, format = "date-time")]
+    pub started_at: DateTime<FixedOffset>,
+    #[schema(value_type = String, format = "date-time")]
+    pub finished_at: DateTime<FixedOffset>,
+    /// Wall-clock length of the game
+    #[schema(example = 412)]
+    pub duration_sec: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DisputeDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub claimant_id: Uuid,
+    pub reason: DisputeReason,
+    pub details: Option<String>,
+    pub evidence: Option<DisputeEvidence>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub tournament_id: Option<Uuid>,
+    pub round: Option<i32>,
+    pub original_result: GameResult,
+    pub requested_result: Option<GameResult>,
+    pub status: DisputeStatus,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub decided_by: Option<Uuid>,
+    pub decision_note: Option<String>,
+    pub corrected_result: Option<GameResult>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub decided_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<DisputeReason> for game_dispute::DisputeReason {
+    fn from(value: DisputeReason) -> Self {
+        match value {
+            DisputeReason::WrongFlag => Self::WrongFlag,
+            DisputeReason::Disconnection => Self::Disconnection,
+            DisputeReason::IllegalMove => Self::IllegalMove,
+            DisputeReason::Other => Self::Other,
+        }
+    }
+}
+
+impl From<game_dispute::DisputeReason> for DisputeReason {
+    fn from(value: game_dispute::DisputeReason) -> Self {
+        match value {
+            game_dispute::DisputeReason::WrongFlag => Self::WrongFlag,
+            game_dispute::DisputeReason::Disconnection => Self::Disconnection,
+            game_dispute::DisputeReason::IllegalMove => Self::IllegalMove,
+            game_dispute::DisputeReason::Other => Self::Other,
+        }
+    }
+}
+
+impl From<DisputeStatus> for game_dispute::DisputeStatus {
+    fn from(value: DisputeStatus) -> Self {
+        match value {
+            DisputeStatus::Open => Self::Open,
+            DisputeStatus::Upheld => Self::Upheld,
+            DisputeStatus::Overturned => Self::Overturned,
+        }
+    }
+}
+
+impl From<game_dispute::DisputeStatus> for DisputeStatus {
+    fn from(value: game_dispute::DisputeStatus) -> Self {
+        match value {
+            game_dispute::DisputeStatus::Open => Self::Open,
+            game_dispute::DisputeStatus::Upheld => Self::Upheld,
+            game_dispute::DisputeStatus::Overturned => Self::Overturned,
+        }
+    }
+}
+
+impl From<GameResult> for ResultSide {
+    fn from(value: GameResult) -> Self {
+        match value {
+            GameResult::WhiteWin => Self::WhiteWins,
+            GameResult::BlackWin => Self::BlackWins,
+            GameResult::Draw => Self::Draw,
+            GameResult::InProgress => Self::Ongoing,
+        }
+    }
+}
+
+impl From<ResultSide> for GameResult {
+    fn from(value: ResultSide) -> Self {
+        match value {
+            ResultSide::WhiteWins => Self::WhiteWin,
+            ResultSide::BlackWins => Self::BlackWin,
+            ResultSide::Draw => Self::Draw,
+            ResultSide::Ongoing | ResultSide::Abandoned => Self::InProgress,
+        }
+    }
+}
+
+impl From<game_dispute::Model> for DisputeDisplay {
+    fn from(value: game_dispute::Model) -> Self {
+        let evidence = serde_json::from_value::<DisputeEvidence>(value.evidence).ok();
+
+        Self {
+            id: value.id,
+            game_id: value.game_id,
+            claimant_id: value.claimant_id,
+            reason: value.reason.into(),
+            details: value.details,
+            evidence,
+            tournament_id: value.tournament_id,
+            round: value.round,
+            original_result: value.original_result.into(),
+            requested_result: value.requested_result.map(Into::into),
+            status: value.status.into(),
+            decided_by: value.decided_by,
+            decision_note: value.decision_note,
+            corrected_result: value.corrected_result.map(Into::into),
+            created_at: value.created_at,
+            decided_at: value.decided_at,
+        }
+    }
+}
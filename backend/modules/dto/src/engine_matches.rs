@@ -0,0 +1,174 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::engine_match;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineMatchStatus {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed,
+}
+
+/// One side of a match: a configured engine binary and its UCI options.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct MatchEngine {
+    /// Label used in results and PGN headers
+    #[schema(example = "stockfish-skill-8")]
+    pub name: String,
+    /// Engine binary, one of the keys of `MATCH_ENGINES`
+    #[schema(example = "stockfish")]
+    pub engine: String,
+    #[serde(default)]
+    #[schema(example = json!({"Skill Level": "8", "Hash": "64"}))]
+    pub options: BTreeMap<String, String>,
+}
+
+/// When games are ended early. Moves and plies count from the end of the opening.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct AdjudicationRules {
+    /// Win once both engines agree a side is ahead by this many centipawns
+    #[schema(example = 1000)]
+    pub resign_cp: Option<i32>,
+    #[schema(example = 3)]
+    pub resign_moves: u32,
+    /// Draw once both engines score the position within this many centipawns
+    #[schema(example = 10)]
+    pub draw_cp: Option<i32>,
+    #[schema(example = 8)]
+    pub draw_moves: u32,
+    #[schema(example = 40)]
+    pub draw_from_move: u32,
+    #[schema(example = 400)]
+    pub max_plies: Option<u32>,
+}
+
+/// Stop the match once engine A is shown to be no stronger than `elo0`, or
+/// at least `elo1` stronger, than engine B.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct SprtRequest {
+    #[schema(example = 0.0)]
+    pub elo0: f64,
+    #[schema(example = 5.0)]
+    pub elo1: f64,
+    #[schema(example = 0.05)]
+    pub alpha: f64,
+    #[schema(example = 0.05)]
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateEngineMatchRequest {
+    #[validate(length(min = 3, max = 100, message = "Name must be between 3 and 100 characters"))]
+    #[schema(example = "Bot level 8 vs level 7")]
+    pub name: String,
+
+    /// The configuration under test; results are counted from its side
+    pub engine_a: MatchEngine,
+    pub engine_b: MatchEngine,
+
+    #[validate(range(min = 1, max = 20000, message = "Matches have between 1 and 20000 games"))]
+    #[schema(example = 200)]
+    pub games: u32,
+
+    #[validate(range(min = 100, max = 3600000, message = "Base time must be between 0.1 seconds and an hour"))]
+    #[schema(example = 10000)]
+    pub base_ms: u64,
+
+    #[validate(range(max = 60000, message = "Increment must be at most a minute"))]
+    #[schema(example = 100)]
+    pub increment_ms: u64,
+
+    /// Opening lines in SAN, each played twice with colors reversed
+    #[serde(default)]
+    #[validate(length(max = 1000, message = "At most 1000 openings"))]
+    #[schema(example = json!(["1. e4 c5 2. Nf3 d6", "1. d4 Nf6 2. c4 e6"]))]
+    pub openings: Vec<String>,
+
+    /// Defaults apply when omitted
+    pub adjudication: Option<AdjudicationRules>,
+
+    pub sprt: Option<SprtRequest>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SprtVerdict {
+    Continue,
+    AcceptH0,
+    AcceptH1,
+}
+
+/// Results from engine A's side.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MatchSummaryDisplay {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Points per game, 0 to 1
+    pub score: f64,
+    pub elo: Option<f64>,
+    /// Half width of the 95% confidence interval
+    pub elo_margin: Option<f64>,
+    pub llr: Option<f64>,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+    pub verdict: Option<SprtVerdict>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineMatchDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    pub status: EngineMatchStatus,
+    pub engine_a: MatchEngine,
+    pub engine_b: MatchEngine,
+    pub games: u32,
+    pub games_played: u32,
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    pub summary: MatchSummaryDisplay,
+    pub error: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub started_at: Option<DateTime<FixedOffset>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub finished_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EngineMatchGameDisplay {
+    pub round: u32,
+    pub opening: Option<String>,
+    /// Label of the engine that had White
+    pub white: String,
+    pub black: String,
+    #[schema(example = "1/2-1/2")]
+    pub result: String,
+    #[schema(example = "adjudicated_draw")]
+    pub termination: String,
+    pub plies: u32,
+    pub pgn: String,
+    #[schema(value_type = String, format = "date-time")]
+    pub played_at: DateTime<FixedOffset>,
+}
+
+impl From<engine_match::EngineMatchStatus> for EngineMatchStatus {
+    fn from(value: engine_match::EngineMatchStatus) -> Self {
+        match value {
+            engine_match::EngineMatchStatus::Queued => Self::Queued,
+            engine_match::EngineMatchStatus::Running => Self::Running,
+            engine_match::EngineMatchStatus::Finished => Self::Finished,
+            engine_match::EngineMatchStatus::Cancelled => Self::Cancelled,
+            engine_match::EngineMatchStatus::Failed => Self::Failed,
+        }
+    }
+}
@@ -1,8 +1,9 @@
+use db_entity::{game_attestation, game_import, import_job, import_quarantine};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
@@ -11,7 +12,12 @@ static CHESS_MOVE_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[a-h][1-8][a-h][1-8][qrbnQRBN]?$").unwrap()
 });
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+// Usernames on Lichess and Chess.com; they also end up in request paths
+static EXTERNAL_USERNAME_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9_-]{2,30}$").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum PlayerColor {
     #[serde(rename = "white")]
     White,
@@ -34,7 +40,7 @@ pub enum GameStatus {
     Aborted,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum GameResult {
     #[serde(rename = "white_win")]
     WhiteWin,
@@ -46,6 +52,57 @@ pub enum GameResult {
     InProgress,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsGiver {
+    White,
+    Black,
+}
+
+/// Knights, bishops and rooks are removed from the queenside first; the
+/// pawn is the f-pawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsPiece {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+}
+
+// Mirrors `chess::Odds`; the service converts between the two through serde
+/// Handicap given by the stronger side. Odds games are never rated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct GameOdds {
+    /// The side giving the odds
+    pub giver: OddsGiver,
+    /// Pieces the giver starts without
+    #[serde(default)]
+    #[schema(example = json!(["queen"]))]
+    pub removed: Vec<OddsPiece>,
+    /// Moves the other side plays before the giver's first move, 0 to 3
+    #[serde(default)]
+    #[schema(example = 0)]
+    pub extra_moves: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    /// Concatenated PGN
+    #[default]
+    Pgn,
+    /// A ZIP holding a single `games.pgn`
+    Zip,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ArchiveQuery {
+    #[serde(default)]
+    pub format: ArchiveFormat,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateGameRequest {
     #[validate(range(min = 60, max = 7200, message = "Time control must be between 1 minute and 2 hours"))]
@@ -56,6 +113,14 @@ pub struct CreateGameRequest {
     
     pub player_color: Option<PlayerColor>,
     pub opponent_id: Option<Uuid>,
+
+    /// Start from a handicap position instead of the standard one
+    pub odds: Option<GameOdds>,
+
+    /// Whether the game counts for ratings; rated unless odds are given.
+    /// Guests can only create casual games
+    #[serde(default)]
+    pub rated: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -75,7 +140,11 @@ pub struct GameDisplayDTO {
     #[schema(example = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
     pub current_fen: String,
     
+    /// Skipped turns of an odds game are recorded as `--`
     pub move_history: Vec<String>,
+    pub odds: Option<GameOdds>,
+    /// Whether the game counts for ratings; odds and imported games do not
+    pub rated: bool,
     pub time_control: i32,
     pub increment: i32,
     pub white_time_remaining: i32,
@@ -123,6 +192,10 @@ pub struct ListGamesQuery {
     
     #[schema(value_type = Option<String>, format = "uuid", example = "123e4567-e89b-12d3-a456-426614174000")]
     pub player_id: Option<Uuid>,
+
+    /// Only games with a member of this club in either seat
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub club_id: Option<Uuid>,
     
     #[schema(default = 1, example = 1)]
     /// Deprecated: Use cursor-based pagination
@@ -168,3 +241,416 @@ pub struct ImportGameResponse {
     
     pub error: Option<String>,
 }
+
+/// Site an account import fetches games from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Lichess,
+    ChessCom,
+}
+
+/// Request body for importing the games of a Lichess or Chess.com account
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ImportAccountRequest {
+    pub source: ImportSource,
+
+    #[validate(regex(
+        path = "EXTERNAL_USERNAME_REGEX",
+        message = "Username must be 2 to 30 letters, digits, '-' or '_'"
+    ))]
+    #[schema(example = "DrNykterstein")]
+    pub username: String,
+
+    /// Lichess OAuth token; only needed for games the account does not make public
+    #[validate(length(max = 256, message = "Token is too long"))]
+    pub token: Option<String>,
+
+    /// Most recent games to fetch
+    #[validate(range(min = 1, max = 500, message = "Between 1 and 500 games can be imported at once"))]
+    #[schema(default = 100, example = 100)]
+    pub max_games: Option<u32>,
+}
+
+/// A fetched game that could not be imported
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportFailure {
+    /// Game id on the source site, when the PGN names one
+    pub external_id: Option<String>,
+    pub error: String,
+    /// The game in the import quarantine, to correct and retry
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub quarantine_id: Option<Uuid>,
+}
+
+/// Outcome of an account import
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportAccountResponse {
+    pub source: ImportSource,
+    pub username: String,
+    /// Ids of the games stored by this import
+    #[schema(value_type = Vec<String>)]
+    pub imported: Vec<Uuid>,
+    /// Games skipped because an earlier import already stored them
+    pub duplicates: u32,
+    pub failed: Vec<ImportFailure>,
+    /// Import job the counts and quarantined games are kept under
+    #[schema(value_type = String, format = "uuid")]
+    pub job_id: Uuid,
+}
+
+impl From<game_import::ImportSource> for ImportSource {
+    fn from(value: game_import::ImportSource) -> Self {
+        match value {
+            game_import::ImportSource::Lichess => Self::Lichess,
+            game_import::ImportSource::ChessCom => Self::ChessCom,
+        }
+    }
+}
+
+impl From<ImportSource> for game_import::ImportSource {
+    fn from(value: ImportSource) -> Self {
+        match value {
+            ImportSource::Lichess => Self::Lichess,
+            ImportSource::ChessCom => Self::ChessCom,
+        }
+    }
+}
+
+/// An account import and what became of the games it fetched
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportJobDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub source: ImportSource,
+    pub username: String,
+    #[schema(example = 100)]
+    pub fetched: i32,
+    #[schema(example = 95)]
+    pub imported: i32,
+    #[schema(example = 2)]
+    pub duplicates: i32,
+    #[schema(example = 3)]
+    pub failed: i32,
+    /// Failed games still waiting to be corrected
+    #[schema(example = 1)]
+    pub pending: u64,
+    /// Failed games imported on a retry
+    #[schema(example = 1)]
+    pub resolved: u64,
+    #[schema(example = 1)]
+    pub dismissed: u64,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl ImportJobDisplay {
+    /// `counts` are the job's quarantined games: pending, resolved, dismissed
+    pub fn new(job: import_job::Model, (pending, resolved, dismissed): (u64, u64, u64)) -> Self {
+        Self {
+            id: job.id,
+            source: job.source.into(),
+            username: job.external_username,
+            fetched: job.fetched,
+            imported: job.imported,
+            duplicates: job.duplicates,
+            failed: job.failed,
+            pending,
+            resolved,
+            dismissed,
+            created_at: job.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ImportJobsQuery {
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineStatus {
+    Pending,
+    Resolved,
+    Dismissed,
+}
+
+impl From<QuarantineStatus> for import_quarantine::QuarantineStatus {
+    fn from(value: QuarantineStatus) -> Self {
+        match value {
+            QuarantineStatus::Pending => Self::Pending,
+            QuarantineStatus::Resolved => Self::Resolved,
+            QuarantineStatus::Dismissed => Self::Dismissed,
+        }
+    }
+}
+
+impl From<import_quarantine::QuarantineStatus> for QuarantineStatus {
+    fn from(value: import_quarantine::QuarantineStatus) -> Self {
+        match value {
+            import_quarantine::QuarantineStatus::Pending => Self::Pending,
+            import_quarantine::QuarantineStatus::Resolved => Self::Resolved,
+            import_quarantine::QuarantineStatus::Dismissed => Self::Dismissed,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct QuarantineQuery {
+    /// Only games of this import job
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub job_id: Option<Uuid>,
+
+    #[schema(example = "pending")]
+    pub status: Option<QuarantineStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+/// A fetched game that failed to import
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QuarantinedImportDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub job_id: Uuid,
+    pub external_id: Option<String>,
+    /// The text as fetched, or as last corrected
+    pub pgn: String,
+    /// Why the last attempt failed
+    #[schema(example = "Illegal move at move 12: 'Nxe5' - Move is not legal in this position")]
+    pub error: String,
+    pub status: QuarantineStatus,
+    pub attempts: i32,
+    /// Game stored once resolved
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+impl From<import_quarantine::Model> for QuarantinedImportDisplay {
+    fn from(model: import_quarantine::Model) -> Self {
+        Self {
+            id: model.id,
+            job_id: model.job_id,
+            external_id: model.external_id,
+            pgn: model.pgn,
+            error: model.error,
+            status: model.status.into(),
+            attempts: model.attempts,
+            game_id: model.game_id,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+/// Request body for retrying a quarantined game
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct RetryImportRequest {
+    /// Corrected PGN; without it the quarantined text is tried again
+    #[validate(length(min = 10, max = 50000, message = "PGN must be between 10 and 50000 characters"))]
+    pub pgn: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationStatus {
+    Pending,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+/// Where the StarkNet attestation of a rated game stands
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameAttestationResponse {
+    #[schema(value_type = String)]
+    pub game_id: Uuid,
+    pub status: AttestationStatus,
+    /// Starknet keccak of the game's canonical PGN
+    pub pgn_hash: String,
+    pub tx_hash: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// Whether the contract holds exactly this game's players, result and
+    /// PGN hash; absent until confirmed or when no node is configured
+    pub verified: Option<bool>,
+}
+
+impl From<game_attestation::AttestationStatus> for AttestationStatus {
+    fn from(value: game_attestation::AttestationStatus) -> Self {
+        match value {
+            game_attestation::AttestationStatus::Pending => Self::Pending,
+            game_attestation::AttestationStatus::Submitted => Self::Submitted,
+            game_attestation::AttestationStatus::Confirmed => Self::Confirmed,
+            game_attestation::AttestationStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+/// What a replay found to disagree with the stored game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// A stored move is illegal or comes after the game ended
+    IllegalMove,
+    /// The stored position is not the one the moves lead to
+    Position,
+    /// The stored result contradicts how the moves end the game
+    Result,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReplayDivergence {
+    pub kind: DivergenceKind,
+    /// 1-based index into the stored move list, for illegal moves
+    pub ply: Option<u32>,
+    pub detail: String,
+}
+
+/// Outcome of replaying a game's stored moves from its starting position
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameVerificationResponse {
+    #[schema(value_type = String)]
+    pub game_id: Uuid,
+    /// True when nothing diverged
+    pub consistent: bool,
+    pub plies_replayed: u32,
+    pub stored_fen: String,
+    pub replayed_fen: String,
+    /// SHA-256 of the board, side to move, castling and en passant fields;
+    /// absent when the stored FEN is unreadable
+    pub stored_position_hash: Option<String>,
+    pub replayed_position_hash: String,
+    pub stored_result: GameResult,
+    /// Result by the rules, when the moves end the game on the board
+    pub replayed_result: Option<GameResult>,
+    /// e.g. `checkmate` or `threefold_repetition`
+    pub termination: Option<String>,
+    pub divergences: Vec<ReplayDivergence>,
+}
+
+/// A side of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    White,
+    Black,
+}
+
+impl Side {
+    pub fn opponent(self) -> Self {
+        match self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+}
+
+/// Something that happened in a game, as appended to its log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// A move in UCI or SAN, with the mover's clock after it in a timed game
+    Move { notation: String, clock_ms: Option<i64> },
+    DrawOffered { by: Side },
+    DrawDeclined { by: Side },
+    /// Ends the game in a draw by agreement
+    DrawAccepted { by: Side },
+    TakebackOffered { by: Side },
+    /// Takes back the last move
+    TakebackAccepted { by: Side },
+    TakebackDeclined { by: Side },
+    /// Clocks set outside of a move, e.g. when a game is paused or time is added
+    ClockSet { white_ms: i64, black_ms: i64 },
+    /// A result the board cannot see, e.g. `resignation` or `timeout`
+    Ended { result: GameResult, termination: String },
+}
+
+/// A game as its log has it after a given event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct GameLogState {
+    /// Events folded in so far; 0 before the first move
+    pub seq: i32,
+    pub fen: String,
+    /// Mainline in SAN; skipped turns of an odds game are recorded as `--`
+    pub moves: Vec<String>,
+    pub white_ms: Option<i64>,
+    pub black_ms: Option<i64>,
+    pub draw_offer: Option<Side>,
+    pub takeback_offer: Option<Side>,
+    pub result: GameResult,
+    /// e.g. `checkmate`, `agreement` or `resignation`
+    pub termination: Option<String>,
+}
+
+/// One entry of a game's log
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameEventRecord {
+    pub seq: i32,
+    pub event: GameEvent,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub actor_id: Option<Uuid>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct GameEventsQuery {
+    /// Return the events after this sequence number; a client that is
+    /// resyncing passes the last one it saw
+    #[schema(default = 0, example = 0)]
+    pub after: Option<i32>,
+
+    #[schema(default = 100, example = 100)]
+    pub limit: Option<u64>,
+}
+
+/// Events of a game's log and the state they lead to
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameLogResponse {
+    #[schema(value_type = String)]
+    pub game_id: Uuid,
+    pub events: Vec<GameEventRecord>,
+    /// Sequence number to pass as `after` for the next page, if any
+    pub next_after: Option<i32>,
+    /// State after the latest event, not only the returned ones
+    pub state: GameLogState,
+}
+
+/// Game events held in memory by the write-behind buffer, and how writing
+/// them out has gone since the server started
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct AutosaveStats {
+    /// Longest an accepted event waits before it is written; 0 writes every
+    /// event at once
+    #[schema(example = 1000)]
+    pub interval_ms: u64,
+    /// Events of one game written as soon as this many are waiting
+    #[schema(example = 20)]
+    pub max_buffered: usize,
+    /// Running games whose state is held in memory
+    #[schema(example = 120)]
+    pub games: usize,
+    /// Events accepted but not written yet
+    #[schema(example = 85)]
+    pub buffered_events: usize,
+    /// Batches written, and the events in them
+    #[schema(example = 5400)]
+    pub flushes: u64,
+    #[schema(example = 61200)]
+    pub flushed_events: u64,
+    /// Batches that could not be written and wait for the next attempt
+    #[schema(example = 0)]
+    pub failed_flushes: u64,
+    /// Events dropped because the game's log was written by someone else
+    /// meanwhile; their players resync from the log
+    #[schema(example = 0)]
+    pub discarded_events: u64,
+}
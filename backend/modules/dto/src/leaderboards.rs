@@ -0,0 +1,75 @@
+use db_entity::{leaderboard_entry, player_rating::RatingCategory};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeControlCategory {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct LeaderboardQuery {
+    /// Number of entries to return (max 100)
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardEntryDisplay {
+    #[schema(example = 1)]
+    pub rank: i32,
+    #[schema(example = 3)]
+    pub previous_rank: Option<i32>,
+    /// Places gained since the previous refresh; negative when dropping, absent for newcomers
+    #[schema(example = 2)]
+    pub rank_delta: Option<i32>,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    #[schema(example = "chess_master")]
+    pub username: String,
+    #[schema(example = 2250)]
+    pub rating: i32,
+    #[schema(example = 412)]
+    pub games_played: i32,
+}
+
+impl LeaderboardEntryDisplay {
+    pub fn new(entry: leaderboard_entry::Model, username: String) -> Self {
+        Self {
+            rank: entry.rank,
+            previous_rank: entry.previous_rank,
+            rank_delta: entry.previous_rank.map(|previous| previous - entry.rank),
+            player_id: entry.player_id,
+            username,
+            rating: entry.rating,
+            games_played: entry.games_played,
+        }
+    }
+}
+
+impl From<TimeControlCategory> for RatingCategory {
+    fn from(value: TimeControlCategory) -> Self {
+        match value {
+            TimeControlCategory::Bullet => Self::Bullet,
+            TimeControlCategory::Blitz => Self::Blitz,
+            TimeControlCategory::Rapid => Self::Rapid,
+            TimeControlCategory::Classical => Self::Classical,
+        }
+    }
+}
+
+impl From<RatingCategory> for TimeControlCategory {
+    fn from(value: RatingCategory) -> Self {
+        match value {
+            RatingCategory::Bullet => Self::Bullet,
+            RatingCategory::Blitz => Self::Blitz,
+            RatingCategory::Rapid => Self::Rapid,
+            RatingCategory::Classical => Self::Classical,
+        }
+    }
+}
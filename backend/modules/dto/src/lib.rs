@@ -2,4 +2,21 @@ pub mod players;
 pub mod responses;
 pub mod games;
 pub mod auth;
-pub mod ai;
\ No newline at end of file
+pub mod ai;
+pub mod moderation;
+pub mod disputes;
+pub mod search;
+pub mod leaderboards;
+pub mod stats;
+pub mod ratings;
+pub mod tournaments;
+pub mod annotations;
+pub mod engine_matches;
+pub mod training;
+pub mod webhooks;
+pub mod preferences;
+pub mod account;
+pub mod titles;
+pub mod clubs;
+pub mod coaching;
+pub mod analysis;
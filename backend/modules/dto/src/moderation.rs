@@ -0,0 +1,401 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{moderation_action, moderation_report, player_role};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::leaderboards::TimeControlCategory;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Cheating,
+    Abuse,
+    Spam,
+    Sandbagging,
+    OffensiveUsername,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationActionKind {
+    Warn,
+    Mute,
+    Ban,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerRole {
+    Moderator,
+    Arbiter,
+    Admin,
+}
+
+/// What the rating integrity scan found, by the pattern it matched
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(tag = "signal", rename_all = "snake_case")]
+pub enum IntegrityEvidence {
+    /// Entered a tournament under a rating prize's limit after being rated
+    /// at or above it shortly before
+    PrizeSandbagging {
+        #[schema(value_type = String, format = "uuid")]
+        tournament_id: Uuid,
+        tournament_name: String,
+        prize: String,
+        category: TimeControlCategory,
+        /// The prize is for players rated below this
+        max_rating: i32,
+        peak_rating: i32,
+        #[schema(value_type = String, format = "date-time")]
+        peak_at: DateTime<FixedOffset>,
+        /// Rating the player entered with
+        entry_rating: i32,
+        /// Rated games lost since the peak
+        losses: u32,
+    },
+    /// Rating fell far from a recent peak
+    RatingVolatility {
+        category: TimeControlCategory,
+        peak_rating: i32,
+        #[schema(value_type = String, format = "date-time")]
+        peak_at: DateTime<FixedOffset>,
+        low_rating: i32,
+        #[schema(value_type = String, format = "date-time")]
+        low_at: DateTime<FixedOffset>,
+        /// Rated games between the peak and the low, and how many were lost
+        games: u32,
+        losses: u32,
+    },
+    /// Kept beating the same opponent, mostly in short games
+    WinTrading {
+        #[schema(value_type = String, format = "uuid")]
+        opponent_id: Uuid,
+        /// Rated games between the two and how many the reported player won
+        games: u32,
+        wins: u32,
+        /// Games over within a few moves
+        short_games: u32,
+        /// Newest first
+        #[schema(value_type = Vec<String>)]
+        game_ids: Vec<Uuid>,
+    },
+    /// Logged in or connected from the same devices or networks as another
+    /// account
+    MultiAccount {
+        #[schema(value_type = String, format = "uuid")]
+        other_player_id: Uuid,
+        /// Device ids and networks both accounts were seen on
+        shared_devices: u32,
+        shared_networks: u32,
+        /// Last time both were seen on one of them
+        #[schema(value_type = String, format = "date-time")]
+        last_seen_at: DateTime<FixedOffset>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateReportRequest {
+    #[schema(value_type = String, format = "uuid", example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub reported_player_id: Uuid,
+
+    #[schema(value_type = Option<String>, format = "uuid", example = "123e4567-e89b-12d3-a456-426614174001")]
+    pub game_id: Option<Uuid>,
+
+    pub reason: ReportReason,
+
+    #[validate(length(max = 2000, message = "Details must be at most 2000 characters"))]
+    #[schema(example = "Opponent played engine moves after move 20")]
+    pub details: Option<String>,
+
+    /// Chat lines relevant to the report, oldest first
+    #[validate(length(max = 50, message = "At most 50 chat lines can be attached"))]
+    pub chat_context: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct FlagGameRequest {
+    /// Participant of the game the flag is about
+    #[schema(value_type = String, format = "uuid", example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub player_id: Uuid,
+
+    pub reason: ReportReason,
+
+    #[validate(length(max = 2000, message = "Details must be at most 2000 characters"))]
+    #[schema(example = "Accuracy far above the player's usual level")]
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ResolveReportRequest {
+    /// Final status, either `resolved` or `dismissed`
+    pub status: ReportStatus,
+
+    #[validate(length(max = 2000, message = "Note must be at most 2000 characters"))]
+    #[schema(example = "Warned the player")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ModerationActionRequest {
+    pub kind: ModerationActionKind,
+
+    #[validate(length(min = 3, max = 2000, message = "Reason must be between 3 and 2000 characters"))]
+    #[schema(example = "Repeated abusive chat messages")]
+    pub reason: String,
+
+    /// Duration in hours; omit for a permanent action
+    #[validate(range(min = 1, max = 87600, message = "Duration must be between 1 hour and 10 years"))]
+    #[schema(example = 24)]
+    pub duration_hours: Option<i64>,
+
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub report_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct GrantRoleRequest {
+    pub role: PlayerRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct LinkAccountRequest {
+    /// The other account of the same person
+    #[schema(value_type = String, format = "uuid", example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub player_id: Uuid,
+
+    #[validate(length(max = 2000, message = "Note must be at most 2000 characters"))]
+    #[schema(example = "Same device as the banned account, admitted in chat")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ReportQueueQuery {
+    #[schema(example = "open")]
+    pub status: Option<ReportStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub reporter_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub reported_player_id: Uuid,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub chat_context: Vec<String>,
+    pub is_game_flag: bool,
+    pub status: ReportStatus,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub resolved_by: Option<Uuid>,
+    pub resolution_note: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub resolved_at: Option<DateTime<FixedOffset>>,
+    /// Set on entries raised by the rating integrity scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<IntegrityEvidence>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ModerationActionDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub moderator_id: Uuid,
+    pub kind: ModerationActionKind,
+    pub reason: String,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub report_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub revoked_at: Option<DateTime<FixedOffset>>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LinkedAccountDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub username: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub marked_by: Uuid,
+    pub note: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+/// Another account seen on the same devices or networks
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct SharedFingerprintDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub username: String,
+    pub shared_devices: u32,
+    pub shared_networks: u32,
+    /// Last time both were seen on one of them
+    #[schema(value_type = String, format = "date-time")]
+    pub last_seen_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountLinksDisplay {
+    /// Accounts moderators marked as the same person; they share sanctions
+    pub linked: Vec<LinkedAccountDisplay>,
+    /// Unlinked accounts sharing devices or networks, most shared first
+    pub suspected: Vec<SharedFingerprintDisplay>,
+}
+
+impl From<ReportReason> for moderation_report::ReportReason {
+    fn from(value: ReportReason) -> Self {
+        match value {
+            ReportReason::Cheating => Self::Cheating,
+            ReportReason::Abuse => Self::Abuse,
+            ReportReason::Spam => Self::Spam,
+            ReportReason::Sandbagging => Self::Sandbagging,
+            ReportReason::OffensiveUsername => Self::OffensiveUsername,
+            ReportReason::Other => Self::Other,
+        }
+    }
+}
+
+impl From<moderation_report::ReportReason> for ReportReason {
+    fn from(value: moderation_report::ReportReason) -> Self {
+        match value {
+            moderation_report::ReportReason::Cheating => Self::Cheating,
+            moderation_report::ReportReason::Abuse => Self::Abuse,
+            moderation_report::ReportReason::Spam => Self::Spam,
+            moderation_report::ReportReason::Sandbagging => Self::Sandbagging,
+            moderation_report::ReportReason::OffensiveUsername => Self::OffensiveUsername,
+            moderation_report::ReportReason::Other => Self::Other,
+        }
+    }
+}
+
+impl From<ReportStatus> for moderation_report::ReportStatus {
+    fn from(value: ReportStatus) -> Self {
+        match value {
+            ReportStatus::Open => Self::Open,
+            ReportStatus::Resolved => Self::Resolved,
+            ReportStatus::Dismissed => Self::Dismissed,
+        }
+    }
+}
+
+impl From<moderation_report::ReportStatus> for ReportStatus {
+    fn from(value: moderation_report::ReportStatus) -> Self {
+        match value {
+            moderation_report::ReportStatus::Open => Self::Open,
+            moderation_report::ReportStatus::Resolved => Self::Resolved,
+            moderation_report::ReportStatus::Dismissed => Self::Dismissed,
+        }
+    }
+}
+
+impl From<ModerationActionKind> for moderation_action::ActionKind {
+    fn from(value: ModerationActionKind) -> Self {
+        match value {
+            ModerationActionKind::Warn => Self::Warn,
+            ModerationActionKind::Mute => Self::Mute,
+            ModerationActionKind::Ban => Self::Ban,
+        }
+    }
+}
+
+impl From<moderation_action::ActionKind> for ModerationActionKind {
+    fn from(value: moderation_action::ActionKind) -> Self {
+        match value {
+            moderation_action::ActionKind::Warn => Self::Warn,
+            moderation_action::ActionKind::Mute => Self::Mute,
+            moderation_action::ActionKind::Ban => Self::Ban,
+        }
+    }
+}
+
+impl From<PlayerRole> for player_role::Role {
+    fn from(value: PlayerRole) -> Self {
+        match value {
+            PlayerRole::Moderator => Self::Moderator,
+            PlayerRole::Arbiter => Self::Arbiter,
+            PlayerRole::Admin => Self::Admin,
+        }
+    }
+}
+
+impl From<player_role::Role> for PlayerRole {
+    fn from(value: player_role::Role) -> Self {
+        match value {
+            player_role::Role::Moderator => Self::Moderator,
+            player_role::Role::Arbiter => Self::Arbiter,
+            player_role::Role::Admin => Self::Admin,
+        }
+    }
+}
+
+impl From<moderation_report::Model> for ReportDisplay {
+    fn from(value: moderation_report::Model) -> Self {
+        let chat_context = value
+            .chat_context
+            .and_then(|json| serde_json::from_value::<Vec<String>>(json).ok())
+            .unwrap_or_default();
+
+        Self {
+            id: value.id,
+            reporter_id: value.reporter_id,
+            reported_player_id: value.reported_player_id,
+            game_id: value.game_id,
+            reason: value.reason.into(),
+            details: value.details,
+            chat_context,
+            is_game_flag: value.is_game_flag,
+            status: value.status.into(),
+            resolved_by: value.resolved_by,
+            resolution_note: value.resolution_note,
+            created_at: value.created_at,
+            resolved_at: value.resolved_at,
+            evidence: value.evidence.and_then(|json| serde_json::from_value(json).ok()),
+        }
+    }
+}
+
+impl From<moderation_action::Model> for ModerationActionDisplay {
+    fn from(value: moderation_action::Model) -> Self {
+        let is_active = value.is_active_at(chrono::Utc::now().fixed_offset());
+
+        Self {
+            id: value.id,
+            player_id: value.player_id,
+            moderator_id: value.moderator_id,
+            kind: value.kind.into(),
+            reason: value.reason,
+            report_id: value.report_id,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            revoked_at: value.revoked_at,
+            is_active,
+        }
+    }
+}
@@ -78,6 +78,7 @@ pub struct UpdatePlayer {
     pub flair: Option<String>,
     pub location: Option<String>,
     pub fide_rating: Option<i32>,
+    pub fide_id: Option<i64>,
     pub social_links: Option<Vec<String>>,
 }
 
@@ -90,6 +91,8 @@ pub struct DisplayPlayer {
     pub country: Option<String>,
     pub flair: Option<String>,
     pub real_name: String,
+    /// Verified title, e.g. `GM`
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -103,7 +106,10 @@ pub struct UpdatedPlayer {
     pub real_name: String,
     pub location: Option<String>,
     pub fide_rating: Option<i32>,
+    pub fide_id: Option<i64>,
     pub social_links: Option<Vec<String>>,
+    /// Verified title, e.g. `GM`
+    pub title: Option<String>,
 }
 
 impl From<Model> for UpdatedPlayer {
@@ -118,7 +124,9 @@ impl From<Model> for UpdatedPlayer {
             real_name: value.real_name,
             location: value.location,
             fide_rating: value.fide_rating,
+            fide_id: value.fide_id,
             social_links: value.social_links,
+            title: value.title.map(|title| title.code().to_string()),
         }
     }
 }
@@ -133,6 +141,7 @@ impl From<Model> for DisplayPlayer {
             country: Some(value.country),
             flair: Some(value.flair),
             real_name: value.real_name,
+            title: value.title.map(|title| title.code().to_string()),
         }
     }
 }
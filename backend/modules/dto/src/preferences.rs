@@ -0,0 +1,144 @@
+use db_entity::player_preferences;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardTheme {
+    Brown,
+    Blue,
+    Green,
+    Purple,
+    Grey,
+    Wood,
+    Marble,
+}
+
+impl BoardTheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Brown => "brown",
+            Self::Blue => "blue",
+            Self::Green => "green",
+            Self::Purple => "purple",
+            Self::Grey => "grey",
+            Self::Wood => "wood",
+            Self::Marble => "marble",
+        }
+    }
+
+    /// The theme stored under `name`; themes no longer offered fall back to brown
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "blue" => Self::Blue,
+            "green" => Self::Green,
+            "purple" => Self::Purple,
+            "grey" => Self::Grey,
+            "wood" => Self::Wood,
+            "marble" => Self::Marble,
+            _ => Self::Brown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatAudience {
+    Everyone,
+    /// Players on the friends list only
+    Friends,
+    Nobody,
+}
+
+/// A player's settings, defaults included
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct PreferencesDisplay {
+    /// Promotions sent without a piece become queens
+    pub auto_queen: bool,
+    pub premove: bool,
+    pub board_theme: BoardTheme,
+    pub sound: bool,
+    /// Hide ratings, chat and everything else but the board during games
+    pub zen_mode: bool,
+    /// Who may chat with the player during games
+    pub chat_audience: ChatAudience,
+    pub chat_profanity_filter: bool,
+}
+
+impl Default for PreferencesDisplay {
+    fn default() -> Self {
+        Self {
+            auto_queen: false,
+            premove: true,
+            board_theme: BoardTheme::Brown,
+            sound: true,
+            zen_mode: false,
+            chat_audience: ChatAudience::Everyone,
+            chat_profanity_filter: true,
+        }
+    }
+}
+
+/// Settings to change; the ones left out keep their value
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePreferencesRequest {
+    #[schema(example = true)]
+    pub auto_queen: Option<bool>,
+    pub premove: Option<bool>,
+    #[schema(example = "blue")]
+    pub board_theme: Option<BoardTheme>,
+    pub sound: Option<bool>,
+    pub zen_mode: Option<bool>,
+    pub chat_audience: Option<ChatAudience>,
+    pub chat_profanity_filter: Option<bool>,
+}
+
+impl PreferencesDisplay {
+    /// These settings with the ones in `changes` applied
+    pub fn merge(self, changes: &UpdatePreferencesRequest) -> Self {
+        Self {
+            auto_queen: changes.auto_queen.unwrap_or(self.auto_queen),
+            premove: changes.premove.unwrap_or(self.premove),
+            board_theme: changes.board_theme.unwrap_or(self.board_theme),
+            sound: changes.sound.unwrap_or(self.sound),
+            zen_mode: changes.zen_mode.unwrap_or(self.zen_mode),
+            chat_audience: changes.chat_audience.unwrap_or(self.chat_audience),
+            chat_profanity_filter: changes.chat_profanity_filter.unwrap_or(self.chat_profanity_filter),
+        }
+    }
+}
+
+impl From<ChatAudience> for player_preferences::ChatAudience {
+    fn from(value: ChatAudience) -> Self {
+        match value {
+            ChatAudience::Everyone => Self::Everyone,
+            ChatAudience::Friends => Self::Friends,
+            ChatAudience::Nobody => Self::Nobody,
+        }
+    }
+}
+
+impl From<player_preferences::ChatAudience> for ChatAudience {
+    fn from(value: player_preferences::ChatAudience) -> Self {
+        match value {
+            player_preferences::ChatAudience::Everyone => Self::Everyone,
+            player_preferences::ChatAudience::Friends => Self::Friends,
+            player_preferences::ChatAudience::Nobody => Self::Nobody,
+        }
+    }
+}
+
+impl From<player_preferences::Model> for PreferencesDisplay {
+    fn from(value: player_preferences::Model) -> Self {
+        Self {
+            auto_queen: value.auto_queen,
+            premove: value.premove,
+            board_theme: BoardTheme::parse(&value.board_theme),
+            sound: value.sound,
+            zen_mode: value.zen_mode,
+            chat_audience: value.chat_audience.into(),
+            chat_profanity_filter: value.chat_profanity_filter,
+        }
+    }
+}
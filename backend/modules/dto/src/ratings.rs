@@ -0,0 +1,168 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{rating_history, rating_recalculation};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::leaderboards::TimeControlCategory;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct RatingHistoryQuery {
+    /// Time control to chart
+    pub tc: TimeControlCategory,
+
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub from: Option<DateTime<Utc>>,
+
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub to: Option<DateTime<Utc>>,
+
+    #[schema(example = 500)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RatingPoint {
+    #[schema(example = 1642)]
+    pub rating: i32,
+    #[schema(example = 74.5)]
+    pub rating_deviation: f64,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub game_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub recorded_at: DateTime<FixedOffset>,
+}
+
+impl From<rating_history::Model> for RatingPoint {
+    fn from(value: rating_history::Model) -> Self {
+        Self {
+            rating: value.rating,
+            rating_deviation: value.rating_deviation,
+            game_id: value.game_id,
+            recorded_at: value.recorded_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SeasonResetRequest {
+    /// Limit the reset to one time control; all time controls when omitted
+    pub time_control: Option<TimeControlCategory>,
+
+    /// Deviation every rating is raised to at least
+    #[validate(range(min = 30.0, max = 350.0, message = "Reset deviation must be between 30 and 350"))]
+    #[schema(example = 150.0)]
+    pub reset_rd: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct RecalculateRatingsRequest {
+    /// Players whose rated games are replayed
+    #[validate(length(min = 1, max = 500, message = "Between 1 and 500 players can be recalculated at once"))]
+    #[schema(value_type = Vec<String>)]
+    pub player_ids: Vec<Uuid>,
+
+    /// Games rated from this point on are replayed
+    #[schema(value_type = String, format = "date-time")]
+    pub since: DateTime<Utc>,
+
+    #[validate(length(min = 3, max = 2000, message = "Reason must be between 3 and 2000 characters"))]
+    #[schema(example = "Results of round 4 were entered the wrong way round")]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct VoidGamesRequest {
+    /// Games started from this point on are voided
+    #[schema(value_type = String, format = "date-time")]
+    pub since: DateTime<Utc>,
+
+    #[validate(length(min = 3, max = 2000, message = "Reason must be between 3 and 2000 characters"))]
+    #[schema(example = "Engine assistance confirmed by fair-play review")]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecalculationStatus {
+    Queued,
+    Done,
+    Failed,
+}
+
+/// One rating as it was before a recalculation and as the replay left it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct RatingAuditEntry {
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub time_control: TimeControlCategory,
+    #[schema(example = 1712)]
+    pub rating_before: i32,
+    #[schema(example = 68.2)]
+    pub rating_deviation_before: f64,
+    #[schema(example = 1698)]
+    pub rating_after: i32,
+    #[schema(example = 68.9)]
+    pub rating_deviation_after: f64,
+    /// Rated games replayed
+    #[schema(example = 14)]
+    pub games_replayed: i32,
+    /// Games that no longer count, removed from the history
+    #[schema(example = 2)]
+    pub games_voided: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecalculationDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub reason: String,
+    #[schema(value_type = Vec<String>)]
+    pub player_ids: Vec<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub since: DateTime<FixedOffset>,
+    pub status: RecalculationStatus,
+    /// Filled once the recalculation is done
+    pub audit: Vec<RatingAuditEntry>,
+    pub error: Option<String>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub requested_by: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub finished_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<rating_recalculation::RecalculationStatus> for RecalculationStatus {
+    fn from(value: rating_recalculation::RecalculationStatus) -> Self {
+        match value {
+            rating_recalculation::RecalculationStatus::Queued => Self::Queued,
+            rating_recalculation::RecalculationStatus::Done => Self::Done,
+            rating_recalculation::RecalculationStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<rating_recalculation::Model> for RecalculationDisplay {
+    fn from(value: rating_recalculation::Model) -> Self {
+        let player_ids = serde_json::from_value::<Vec<Uuid>>(value.player_ids).unwrap_or_default();
+        let audit = value
+            .audit
+            .and_then(|json| serde_json::from_value::<Vec<RatingAuditEntry>>(json).ok())
+            .unwrap_or_default();
+
+        Self {
+            id: value.id,
+            reason: value.reason,
+            player_ids,
+            since: value.since,
+            status: value.status.into(),
+            audit,
+            error: value.error,
+            requested_by: value.requested_by,
+            created_at: value.created_at,
+            finished_at: value.finished_at,
+        }
+    }
+}
@@ -4,12 +4,20 @@ use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::players::{DisplayPlayer, UpdatedPlayer};
+use crate::tournaments::TrophyDisplay;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct PlayerAddedBody {
     pub player: DisplayPlayer,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct PlayerFoundBody {
+    pub player: DisplayPlayer,
+    /// Tournament prizes won, newest first
+    pub trophies: Vec<TrophyDisplay>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct DeletedBody {}
 
@@ -29,7 +37,7 @@ pub struct PlayerAdded {
 pub struct PlayerFound {
     #[schema(example = "Player found")]
     pub message: String,
-    pub body: PlayerAddedBody,
+    pub body: PlayerFoundBody,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
@@ -0,0 +1,113 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::indexed_game;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::games::GameResult;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Player,
+    Tournament,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+pub struct SearchQuery {
+    #[validate(length(min = 2, max = 100, message = "Search text must be between 2 and 100 characters"))]
+    #[schema(example = "magnus")]
+    pub q: String,
+
+    /// Only search players or only tournaments; both by default
+    #[schema(example = "player")]
+    pub kind: Option<SearchKind>,
+
+    /// Number of results to return (max 50)
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+
+    /// Results to skip, from the `next_offset` of the previous page
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+/// A player or tournament matching a search, best matches first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// Username of a player, name of a tournament
+    #[schema(example = "magnus_fan")]
+    pub name: String,
+    /// Relevance; names starting with the search text score above 1
+    #[schema(example = 1.45)]
+    pub score: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+pub struct PositionSearchQuery {
+    /// Position to find; games reaching it by any move order match
+    #[validate(length(max = 100, message = "FEN must be at most 100 characters"))]
+    #[schema(example = "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2")]
+    pub fen: Option<String>,
+
+    /// Material to find, one letter per piece: upper case for white, lower
+    /// case for black, kings optional
+    #[validate(length(max = 40, message = "Material must be at most 40 pieces"))]
+    #[schema(example = "KRPkr")]
+    pub material: Option<String>,
+
+    /// Only games that ended this way
+    pub result: Option<GameResult>,
+
+    /// Only games where both players were rated at least this much
+    #[schema(example = 2200)]
+    pub min_rating: Option<i32>,
+
+    /// Number of games to return (max 50)
+    #[schema(example = 20)]
+    pub limit: Option<u64>,
+
+    /// Games to skip, from the `next_offset` of the previous page
+    #[schema(example = 0)]
+    pub offset: Option<u64>,
+}
+
+/// A game that reached the searched position.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PositionMatch {
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+    /// First half-move after which the game reached the position
+    #[schema(example = 3)]
+    pub ply: i32,
+    #[schema(example = "Carlsen, Magnus")]
+    pub white: String,
+    #[schema(example = "Nakamura, Hikaru")]
+    pub black: String,
+    #[schema(example = 2830)]
+    pub white_rating: Option<i32>,
+    #[schema(example = 2780)]
+    pub black_rating: Option<i32>,
+    pub result: GameResult,
+    #[schema(value_type = String, format = "date-time")]
+    pub played_at: DateTime<FixedOffset>,
+}
+
+impl PositionMatch {
+    pub fn new(game: indexed_game::Model, ply: i32) -> Self {
+        Self {
+            game_id: game.game_id,
+            ply,
+            white: game.white_name,
+            black: game.black_name,
+            white_rating: game.white_rating,
+            black_rating: game.black_rating,
+            result: game.result.into(),
+            played_at: game.played_at,
+        }
+    }
+}
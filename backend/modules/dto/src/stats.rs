@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ResultRecord {
+    #[schema(example = 12)]
+    pub wins: u32,
+    #[schema(example = 4)]
+    pub draws: u32,
+    #[schema(example = 9)]
+    pub losses: u32,
+}
+
+impl ResultRecord {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points scored as a percentage of points available.
+    pub fn score_percentage(&self) -> Option<f64> {
+        match self.games() {
+            0 => None,
+            games => Some((self.wins as f64 + self.draws as f64 * 0.5) * 100.0 / games as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct StreakStats {
+    /// Positive for consecutive wins, negative for consecutive losses, zero after a draw
+    #[schema(example = 3)]
+    pub current: i32,
+    #[schema(example = 7)]
+    pub longest_win: u32,
+    #[schema(example = 4)]
+    pub longest_loss: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PlayerStats {
+    pub total: ResultRecord,
+    pub as_white: ResultRecord,
+    pub as_black: ResultRecord,
+    /// Keyed by ECO code, e.g. `B90`
+    pub by_opening: BTreeMap<String, ResultRecord>,
+    /// Keyed by opponent rating band, e.g. `1800-1999`
+    pub by_opponent_band: BTreeMap<String, ResultRecord>,
+    /// Keyed by UTC period: `night`, `morning`, `afternoon`, `evening`
+    pub by_time_of_day: BTreeMap<String, ResultRecord>,
+    #[schema(example = 87.5)]
+    pub average_accuracy: Option<f64>,
+    /// Number of games that contributed an accuracy figure
+    pub accuracy_games: u32,
+    pub streaks: StreakStats,
+}
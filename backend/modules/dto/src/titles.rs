@@ -0,0 +1,180 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{title_verification, title_verification_event};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// FIDE title, written as its abbreviation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChessTitle {
+    Gm,
+    Im,
+    Fm,
+    Cm,
+    Wgm,
+    Wim,
+    Wfm,
+    Wcm,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Revoked,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SubmitTitleRequest {
+    #[schema(example = "IM")]
+    pub title: ChessTitle,
+
+    /// FIDE id the title is registered under
+    #[validate(range(min = 1, message = "FIDE id must be positive"))]
+    #[schema(example = 1503014)]
+    pub fide_id: i64,
+
+    /// Links or notes a moderator can check the title against
+    #[validate(length(min = 1, max = 2000, message = "Evidence must be between 1 and 2000 characters"))]
+    #[schema(example = "https://ratings.fide.com/profile/1503014")]
+    pub evidence: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct DecideTitleRequest {
+    /// `approved` or `rejected` for a pending request, `revoked` for an
+    /// approved one
+    pub status: VerificationStatus,
+
+    #[validate(length(max = 2000, message = "Note must be at most 2000 characters"))]
+    #[schema(example = "Title confirmed on the FIDE profile")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct TitleQueueQuery {
+    #[schema(example = "pending")]
+    pub status: Option<VerificationStatus>,
+
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TitleVerificationDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub title: ChessTitle,
+    pub fide_id: i64,
+    pub evidence: String,
+    pub status: VerificationStatus,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub reviewed_by: Option<Uuid>,
+    pub review_note: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub reviewed_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TitleVerificationEventDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub actor_id: Uuid,
+    /// Absent for the submission
+    pub from_status: Option<VerificationStatus>,
+    pub to_status: VerificationStatus,
+    pub note: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl From<ChessTitle> for title_verification::ChessTitle {
+    fn from(value: ChessTitle) -> Self {
+        match value {
+            ChessTitle::Gm => Self::Gm,
+            ChessTitle::Im => Self::Im,
+            ChessTitle::Fm => Self::Fm,
+            ChessTitle::Cm => Self::Cm,
+            ChessTitle::Wgm => Self::Wgm,
+            ChessTitle::Wim => Self::Wim,
+            ChessTitle::Wfm => Self::Wfm,
+            ChessTitle::Wcm => Self::Wcm,
+        }
+    }
+}
+
+impl From<title_verification::ChessTitle> for ChessTitle {
+    fn from(value: title_verification::ChessTitle) -> Self {
+        match value {
+            title_verification::ChessTitle::Gm => Self::Gm,
+            title_verification::ChessTitle::Im => Self::Im,
+            title_verification::ChessTitle::Fm => Self::Fm,
+            title_verification::ChessTitle::Cm => Self::Cm,
+            title_verification::ChessTitle::Wgm => Self::Wgm,
+            title_verification::ChessTitle::Wim => Self::Wim,
+            title_verification::ChessTitle::Wfm => Self::Wfm,
+            title_verification::ChessTitle::Wcm => Self::Wcm,
+        }
+    }
+}
+
+impl From<VerificationStatus> for title_verification::VerificationStatus {
+    fn from(value: VerificationStatus) -> Self {
+        match value {
+            VerificationStatus::Pending => Self::Pending,
+            VerificationStatus::Approved => Self::Approved,
+            VerificationStatus::Rejected => Self::Rejected,
+            VerificationStatus::Revoked => Self::Revoked,
+        }
+    }
+}
+
+impl From<title_verification::VerificationStatus> for VerificationStatus {
+    fn from(value: title_verification::VerificationStatus) -> Self {
+        match value {
+            title_verification::VerificationStatus::Pending => Self::Pending,
+            title_verification::VerificationStatus::Approved => Self::Approved,
+            title_verification::VerificationStatus::Rejected => Self::Rejected,
+            title_verification::VerificationStatus::Revoked => Self::Revoked,
+        }
+    }
+}
+
+impl From<title_verification::Model> for TitleVerificationDisplay {
+    fn from(value: title_verification::Model) -> Self {
+        Self {
+            id: value.id,
+            player_id: value.player_id,
+            title: value.title.into(),
+            fide_id: value.fide_id,
+            evidence: value.evidence,
+            status: value.status.into(),
+            reviewed_by: value.reviewed_by,
+            review_note: value.review_note,
+            created_at: value.created_at,
+            reviewed_at: value.reviewed_at,
+        }
+    }
+}
+
+impl From<title_verification_event::Model> for TitleVerificationEventDisplay {
+    fn from(value: title_verification_event::Model) -> Self {
+        Self {
+            id: value.id,
+            actor_id: value.actor_id,
+            from_status: value.from_status.map(Into::into),
+            to_status: value.to_status.into(),
+            note: value.note,
+            created_at: value.created_at,
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::{tournament, tournament_template};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::games::GameResult;
+use crate::leaderboards::TimeControlCategory;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentFormat {
+    Swiss,
+    Arena,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentStatus {
+    Registration,
+    Ongoing,
+    Finished,
+    Cancelled,
+}
+
+/// What an arena favours when pairing the players waiting for a game
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArenaPairingMode {
+    /// Pair players in the order they joined the queue
+    Fast,
+    /// Pair players with the closest-rated opponent available
+    #[default]
+    Fair,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, Validate, PartialEq, Eq)]
+pub struct ArenaPairing {
+    #[serde(default)]
+    pub pairing: ArenaPairingMode,
+    /// In fair mode, players who have waited this many seconds are paired
+    /// next with the closest-rated opponent, whatever the rating gap
+    #[validate(range(max = 600, message = "Players wait at most 600 seconds"))]
+    #[schema(example = 60)]
+    pub max_wait_secs: u32,
+}
+
+/// Whether players who withdrew from a Swiss tournament may come back, and
+/// what the rounds they missed are worth
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReentryPolicy {
+    /// A withdrawal is final
+    Forbidden,
+    /// Missed rounds score nothing
+    #[default]
+    ZeroForMissed,
+    /// Missed rounds score as requested byes
+    ByeForMissed,
+}
+
+/// How players are seeded for round 1, which pairs the top half of the
+/// field against the bottom half
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Seeding {
+    #[default]
+    Rating,
+    /// Drawn by lot, from `deterministic_seed` when one is set
+    Random,
+    /// In the order of `seed_order`
+    Manual,
+}
+
+/// Color of the top seed on board 1 of round 1; the boards below alternate
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InitialColor {
+    #[default]
+    White,
+    Black,
+    Random,
+}
+
+/// Who a prize goes to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrizeKind {
+    /// Every player finishing between `from` and `to` (1-based, inclusive)
+    Place { from: u32, to: u32 },
+    /// The best `places` finishers rated at least `min_rating` and below `max_rating`
+    RatingCategory {
+        min_rating: Option<i32>,
+        max_rating: Option<i32>,
+        places: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct Prize {
+    #[schema(example = "Best U1600")]
+    pub name: String,
+    pub kind: PrizeKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SetPrizesRequest {
+    /// Place prizes are awarded first; rating category prizes then go, in
+    /// listed order, to players who have not won a prize yet
+    #[validate(length(max = 50, message = "At most 50 prizes per tournament"))]
+    pub prizes: Vec<Prize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StandingDisplay {
+    #[schema(example = 1)]
+    pub rank: u32,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub name: String,
+    pub rating: i32,
+    #[schema(example = 5.5)]
+    pub score: f32,
+    pub buchholz: f32,
+    pub sonneborn_berger: f32,
+    pub wins: u32,
+    /// Prize won with this place; provisional until the tournament finishes
+    pub prize: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrophyDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub tournament_id: Uuid,
+    pub tournament_name: String,
+    #[schema(example = "Champion")]
+    pub prize: String,
+    pub rank: i32,
+    pub score: f64,
+    #[schema(value_type = String, format = "date-time")]
+    pub awarded_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ValidateTrfRequest {
+    /// Contents of a FIDE TRF16 report file
+    #[validate(length(min = 1, max = 2000000, message = "Report must be between 1 and 2000000 characters"))]
+    pub report: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct ImportRegistrationsRequest {
+    /// Participants as CSV, one per row, under a header naming the columns
+    /// `name`, `rating`, `federation` and `fide_id` in any order; only `name`
+    /// is required
+    #[validate(length(min = 1, max = 1000000, message = "CSV must be between 1 and 1000000 characters"))]
+    #[schema(example = "name,rating,federation,fide_id\nCarlsen Magnus,2830,NOR,1503014")]
+    pub csv: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportedRegistrationDisplay {
+    /// Line of the CSV the participant was read from
+    #[schema(example = 2)]
+    pub line: u64,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    #[schema(example = "carlsen-magnus")]
+    pub username: String,
+    /// Rating the player is seeded with
+    #[schema(example = 2830)]
+    pub rating: i32,
+    /// Whether a player record was created for the row rather than found by
+    /// FIDE ID or username
+    pub created: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RejectedRowDisplay {
+    #[schema(example = 5)]
+    pub line: u64,
+    #[schema(example = "Rating 'abc' is not a number")]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegistrationImportDisplay {
+    pub registered: Vec<ImportedRegistrationDisplay>,
+    /// Rows left out, and why; the others are registered regardless
+    pub rejected: Vec<RejectedRowDisplay>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrfValidationDisplay {
+    pub name: Option<String>,
+    #[schema(example = 48)]
+    pub players: usize,
+    #[schema(example = 7)]
+    pub rounds: usize,
+    /// Round data that doesn't agree between players; empty when the report is consistent
+    #[schema(example = json!(["Players 3 and 12 disagree about their round 2 game"]))]
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateTournamentRequest {
+    #[validate(length(min = 3, max = 100, message = "Name must be between 3 and 100 characters"))]
+    #[schema(example = "Friday Night Blitz")]
+    pub name: String,
+
+    #[validate(range(min = 1, max = 15, message = "Tournaments have between 1 and 15 rounds"))]
+    #[schema(example = 7)]
+    pub total_rounds: u32,
+
+    /// Ratings of this time control seed the players
+    pub time_control: TimeControlCategory,
+
+    #[validate(length(min = 2, max = 1000, message = "Between 2 and 1000 players can be entered"))]
+    #[schema(value_type = Vec<String>)]
+    pub player_ids: Vec<Uuid>,
+
+    /// Pair deterministically with this seed
+    #[schema(example = 20261016)]
+    pub deterministic_seed: Option<u64>,
+
+    /// Use FIDE Baku acceleration in the early rounds
+    #[serde(default)]
+    pub accelerated: bool,
+
+    /// Points for the bye given to the odd player out (default 1)
+    #[validate(range(min = 0.0, max = 1.0, message = "Bye points must be between 0 and 1"))]
+    #[schema(example = 1.0)]
+    pub bye_points: Option<f32>,
+
+    /// Points for a bye requested by the player (default 0.5)
+    #[validate(range(min = 0.0, max = 1.0, message = "Requested bye points must be between 0 and 1"))]
+    #[schema(example = 0.5)]
+    pub requested_bye_points: Option<f32>,
+
+    #[serde(default)]
+    pub reentry: ReentryPolicy,
+
+    #[serde(default)]
+    pub seeding: Seeding,
+
+    /// Players top seed first, for manual seeding; entered players left out
+    /// follow by rating
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub seed_order: Vec<Uuid>,
+
+    #[serde(default)]
+    pub initial_color: InitialColor,
+
+    #[serde(default)]
+    #[validate(length(max = 50, message = "At most 50 prizes per tournament"))]
+    pub prizes: Vec<Prize>,
+
+    /// Club whose members alone may enter; the creator must be one of its
+    /// owner or admins
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub club_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ByeRequest {
+    /// Round to sit out; the current round only while it is not yet paired
+    #[schema(example = 4)]
+    pub round: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SwapColorsRequest {
+    /// Either player of the pairing
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ForcePairingRequest {
+    #[schema(value_type = String, format = "uuid")]
+    pub white_player_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub black_player_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ForfeitRequest {
+    /// Player awarded the game; their opponent forfeits
+    #[schema(value_type = String, format = "uuid")]
+    pub winner_id: Uuid,
+}
+
+/// A result to assume for a game of the current round
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HypotheticalResult {
+    /// White player of the game
+    #[schema(value_type = String, format = "uuid")]
+    pub white_player_id: Uuid,
+    /// `in_progress` is taken as a draw
+    pub result: GameResult,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct PreviewRoundRequest {
+    /// Games of the current round left out are taken as draws; games already
+    /// scored keep their result
+    #[serde(default)]
+    #[validate(length(max = 500, message = "At most 500 results"))]
+    pub results: Vec<HypotheticalResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PairingDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub white_player_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub black_player_id: Uuid,
+    /// Set when the arbiter decided the game by forfeit
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub forfeit_winner_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoundDisplay {
+    #[schema(example = 3)]
+    pub round: u32,
+    pub pairings: Vec<PairingDisplay>,
+    #[schema(value_type = Vec<String>)]
+    pub byes: Vec<Uuid>,
+    /// Byes asked for in this round that the pairer has not credited yet
+    #[schema(value_type = Vec<String>)]
+    pub requested_byes: Vec<Uuid>,
+    /// Active players still waiting for a pairing
+    #[schema(value_type = Vec<String>)]
+    pub unpaired: Vec<Uuid>,
+    /// Players withdrawn from the tournament, who are not paired
+    #[schema(value_type = Vec<String>)]
+    pub withdrawn: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TournamentDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub arbiter_id: Uuid,
+    pub time_control: TimeControlCategory,
+    pub format: TournamentFormat,
+    pub status: TournamentStatus,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub template_id: Option<Uuid>,
+    /// Club whose members alone may enter
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub club_id: Option<Uuid>,
+    pub total_rounds: u32,
+    pub completed_rounds: u32,
+    /// Registered players
+    #[schema(example = 24)]
+    pub player_count: usize,
+    pub current_round: RoundDisplay,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub registration_opens_at: Option<DateTime<FixedOffset>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub registration_closes_at: Option<DateTime<FixedOffset>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub starts_at: Option<DateTime<FixedOffset>>,
+    pub prizes: Vec<Prize>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateTemplateRequest {
+    #[validate(length(min = 3, max = 100, message = "Name must be between 3 and 100 characters"))]
+    #[schema(example = "Hourly Blitz Arena")]
+    pub name: String,
+
+    pub format: TournamentFormat,
+
+    pub time_control: TimeControlCategory,
+
+    /// Required for Swiss events
+    #[validate(range(min = 1, max = 15, message = "Tournaments have between 1 and 15 rounds"))]
+    #[schema(example = 7)]
+    pub total_rounds: Option<u32>,
+
+    /// Required for arena events
+    #[validate(range(min = 10, max = 720, message = "Arenas last between 10 and 720 minutes"))]
+    #[schema(example = 57)]
+    pub duration_minutes: Option<u32>,
+
+    #[serde(default)]
+    pub accelerated: bool,
+
+    /// Cron expression in UTC: minute hour day-of-month month day-of-week
+    #[validate(length(min = 9, max = 100, message = "Schedule must be a cron expression"))]
+    #[schema(example = "0 * * * *")]
+    pub schedule: String,
+
+    /// Minutes before the start that registration opens
+    #[validate(range(min = 1, max = 10080, message = "Registration opens between 1 minute and 7 days before the start"))]
+    #[schema(example = 60)]
+    pub registration_opens_minutes: u32,
+
+    /// Minutes before the start that registration closes
+    #[validate(range(max = 1440, message = "Registration closes at most a day before the start"))]
+    #[schema(example = 0)]
+    pub registration_closes_minutes: u32,
+
+    /// Pairing of arena events; fair pairing with a 60 second wait bound when left out
+    #[validate]
+    pub arena_pairing: Option<ArenaPairing>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateTemplateRequest {
+    #[validate(length(min = 3, max = 100, message = "Name must be between 3 and 100 characters"))]
+    pub name: Option<String>,
+
+    #[validate(range(min = 1, max = 15, message = "Tournaments have between 1 and 15 rounds"))]
+    pub total_rounds: Option<u32>,
+
+    #[validate(range(min = 10, max = 720, message = "Arenas last between 10 and 720 minutes"))]
+    pub duration_minutes: Option<u32>,
+
+    pub accelerated: Option<bool>,
+
+    #[validate(length(min = 9, max = 100, message = "Schedule must be a cron expression"))]
+    pub schedule: Option<String>,
+
+    #[validate(range(min = 1, max = 10080, message = "Registration opens between 1 minute and 7 days before the start"))]
+    pub registration_opens_minutes: Option<u32>,
+
+    #[validate(range(max = 1440, message = "Registration closes at most a day before the start"))]
+    pub registration_closes_minutes: Option<u32>,
+
+    #[validate]
+    pub arena_pairing: Option<ArenaPairing>,
+
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TemplateDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    pub format: TournamentFormat,
+    pub time_control: TimeControlCategory,
+    pub total_rounds: Option<i32>,
+    pub duration_minutes: Option<i32>,
+    pub accelerated: bool,
+    #[schema(example = "0 18 * * 6")]
+    pub schedule: String,
+    pub registration_opens_minutes: i32,
+    pub registration_closes_minutes: i32,
+    /// Set for arena templates
+    pub arena_pairing: Option<ArenaPairing>,
+    pub enabled: bool,
+    /// Start of the next tournament the scheduler will create
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub next_run_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<tournament_template::Model> for TemplateDisplay {
+    fn from(value: tournament_template::Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            format: value.format.into(),
+            time_control: value.time_control.into(),
+            total_rounds: value.total_rounds,
+            duration_minutes: value.duration_minutes,
+            accelerated: value.accelerated,
+            schedule: value.schedule,
+            registration_opens_minutes: value.registration_opens_minutes,
+            registration_closes_minutes: value.registration_closes_minutes,
+            arena_pairing: value.arena_pairing.and_then(|json| serde_json::from_value(json).ok()),
+            enabled: value.enabled,
+            next_run_at: value.next_run_at,
+        }
+    }
+}
+
+impl From<TournamentFormat> for tournament::TournamentFormat {
+    fn from(value: TournamentFormat) -> Self {
+        match value {
+            TournamentFormat::Swiss => Self::Swiss,
+            TournamentFormat::Arena => Self::Arena,
+        }
+    }
+}
+
+impl From<tournament::TournamentFormat> for TournamentFormat {
+    fn from(value: tournament::TournamentFormat) -> Self {
+        match value {
+            tournament::TournamentFormat::Swiss => Self::Swiss,
+            tournament::TournamentFormat::Arena => Self::Arena,
+        }
+    }
+}
+
+impl From<tournament::TournamentStatus> for TournamentStatus {
+    fn from(value: tournament::TournamentStatus) -> Self {
+        match value {
+            tournament::TournamentStatus::Registration => Self::Registration,
+            tournament::TournamentStatus::Ongoing => Self::Ongoing,
+            tournament::TournamentStatus::Finished => Self::Finished,
+            tournament::TournamentStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
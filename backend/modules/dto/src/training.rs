@@ -0,0 +1,149 @@
+use chrono::{DateTime, FixedOffset};
+use db_entity::training_session;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrainingDrill {
+    /// Click the named square
+    FindSquare,
+    /// Name the highlighted square
+    NameSquare,
+    /// Answer `light` or `dark`
+    SquareColor,
+    /// List every square the piece attacks
+    PieceVision,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionPiece {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+// Mirrors `chess::Exercise`; the service converts between the two through serde
+/// Blockers are opponent pawns: the piece attacks their squares but not the
+/// squares behind them.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct Exercise {
+    #[schema(example = "e4")]
+    pub square: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub piece: Option<VisionPiece>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(example = json!(["e6"]))]
+    pub blockers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StartTrainingRequest {
+    pub drill: TrainingDrill,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SubmitTrainingRequest {
+    /// One answer per exercise, in order: a square, `light` or `dark`, or
+    /// the attacked squares separated by spaces; empty to skip
+    #[validate(length(max = 100, message = "Too many answers"))]
+    #[schema(example = json!(["e4", "c6", "a1 a2 b1"]))]
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrainingSessionDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub drill: TrainingDrill,
+    pub exercises: Vec<Exercise>,
+    #[schema(value_type = String, format = "date-time")]
+    pub started_at: DateTime<FixedOffset>,
+    /// Answers submitted later are refused
+    #[schema(value_type = String, format = "date-time")]
+    pub expires_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExerciseResult {
+    pub exercise: Exercise,
+    pub answer: String,
+    pub solution: String,
+    pub correct: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrainingResultDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub drill: TrainingDrill,
+    pub correct: u32,
+    pub total: u32,
+    /// Share of correct answers, 0 to 1
+    pub accuracy: f64,
+    /// From the start of the session to the submission
+    pub duration_ms: u32,
+    /// Whether this beats the player's previous best on this drill
+    pub personal_best: bool,
+    pub results: Vec<ExerciseResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BestScore {
+    #[schema(value_type = String, format = "uuid")]
+    pub session_id: Uuid,
+    pub correct: u32,
+    pub total: u32,
+    pub duration_ms: u32,
+    #[schema(value_type = String, format = "date-time")]
+    pub achieved_at: DateTime<FixedOffset>,
+}
+
+/// A player's results on one drill, over finished sessions.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DrillStats {
+    pub drill: TrainingDrill,
+    pub sessions: u32,
+    pub exercises: u32,
+    pub correct: u32,
+    pub accuracy: f64,
+    pub average_ms_per_exercise: Option<u32>,
+    /// Most correct answers, fastest first among equals
+    pub best: Option<BestScore>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrainingLeaderboardEntry {
+    pub rank: u32,
+    #[schema(value_type = String, format = "uuid")]
+    pub player_id: Uuid,
+    pub username: String,
+    pub best: BestScore,
+}
+
+impl From<training_session::TrainingDrill> for TrainingDrill {
+    fn from(value: training_session::TrainingDrill) -> Self {
+        match value {
+            training_session::TrainingDrill::FindSquare => Self::FindSquare,
+            training_session::TrainingDrill::NameSquare => Self::NameSquare,
+            training_session::TrainingDrill::SquareColor => Self::SquareColor,
+            training_session::TrainingDrill::PieceVision => Self::PieceVision,
+        }
+    }
+}
+
+impl From<TrainingDrill> for training_session::TrainingDrill {
+    fn from(value: TrainingDrill) -> Self {
+        match value {
+            TrainingDrill::FindSquare => Self::FindSquare,
+            TrainingDrill::NameSquare => Self::NameSquare,
+            TrainingDrill::SquareColor => Self::SquareColor,
+            TrainingDrill::PieceVision => Self::PieceVision,
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{webhook_delivery, webhook_subscription};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::games::GameResult;
+use crate::tournaments::RoundDisplay;
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Hash)]
+pub enum WebhookEventType {
+    #[serde(rename = "game.finished")]
+    GameFinished,
+    #[serde(rename = "tournament.round_paired")]
+    TournamentRoundPaired,
+}
+
+impl WebhookEventType {
+    /// Name stored with subscriptions and sent in the `X-StarkMate-Event` header
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GameFinished => "game.finished",
+            Self::TournamentRoundPaired => "tournament.round_paired",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "game.finished" => Some(Self::GameFinished),
+            "tournament.round_paired" => Some(Self::TournamentRoundPaired),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateWebhookRequest {
+    /// `http` or `https` URL the events are posted to
+    #[validate(length(min = 1, max = 2048, message = "URL must be at most 2048 characters"))]
+    #[schema(example = "https://example.com/hooks/starkmate")]
+    pub url: String,
+
+    /// Key the payloads are signed with; kept by the caller, never shown again
+    #[validate(length(min = 16, max = 128, message = "Secret must be 16 to 128 characters"))]
+    #[schema(example = "whsec_7f3c1a9b2e4d6f80")]
+    pub secret: String,
+
+    #[validate(length(min = 1, message = "Subscribe to at least one event"))]
+    pub events: Vec<WebhookEventType>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WebhookDeliveriesQuery {
+    #[schema(example = 50)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// One entry of a webhook's delivery log
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryDisplay {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(example = "game.finished")]
+    pub event: String,
+    /// Body as posted to the URL
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    /// HTTP status of the latest attempt, if the endpoint answered
+    #[schema(example = 200)]
+    pub response_status: Option<i16>,
+    pub last_error: Option<String>,
+    #[schema(value_type = String, format = "date-time")]
+    pub next_attempt_at: DateTime<FixedOffset>,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<FixedOffset>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub delivered_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Data of a `game.finished` event
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GameFinishedData {
+    #[schema(value_type = String, format = "uuid")]
+    pub game_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub white_player_id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub black_player_id: Uuid,
+    pub result: GameResult,
+    /// e.g. `checkmate`, `agreement` or `resignation`
+    pub termination: Option<String>,
+    pub fen: String,
+    /// Mainline in SAN
+    pub moves: Vec<String>,
+}
+
+/// Data of a `tournament.round_paired` event
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoundPairedData {
+    #[schema(value_type = String, format = "uuid")]
+    pub tournament_id: Uuid,
+    pub round: RoundDisplay,
+}
+
+/// Something that happened, to be posted to the webhooks subscribed to it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "event", content = "data")]
+pub enum WebhookEvent {
+    #[serde(rename = "game.finished")]
+    GameFinished(GameFinishedData),
+    #[serde(rename = "tournament.round_paired")]
+    TournamentRoundPaired(RoundPairedData),
+}
+
+impl WebhookEvent {
+    pub fn event_type(&self) -> WebhookEventType {
+        match self {
+            Self::GameFinished(_) => WebhookEventType::GameFinished,
+            Self::TournamentRoundPaired(_) => WebhookEventType::TournamentRoundPaired,
+        }
+    }
+}
+
+/// Body posted to a webhook URL. `id` matches the `X-StarkMate-Delivery`
+/// header and stays the same across retries, so receivers can drop repeats.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookPayload {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+}
+
+impl From<webhook_delivery::WebhookDeliveryStatus> for WebhookDeliveryStatus {
+    fn from(value: webhook_delivery::WebhookDeliveryStatus) -> Self {
+        match value {
+            webhook_delivery::WebhookDeliveryStatus::Pending => Self::Pending,
+            webhook_delivery::WebhookDeliveryStatus::Delivered => Self::Delivered,
+            webhook_delivery::WebhookDeliveryStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<webhook_subscription::Model> for WebhookDisplay {
+    fn from(value: webhook_subscription::Model) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            events: value.events.iter().filter_map(|e| WebhookEventType::parse(e)).collect(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<webhook_delivery::Model> for WebhookDeliveryDisplay {
+    fn from(value: webhook_delivery::Model) -> Self {
+        Self {
+            id: value.id,
+            event: value.event,
+            payload: value.payload,
+            status: value.status.into(),
+            attempts: value.attempts,
+            response_status: value.response_status,
+            last_error: value.last_error,
+            next_attempt_at: value.next_attempt_at,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
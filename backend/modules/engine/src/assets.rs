@@ -0,0 +1,330 @@
+//! Engine binaries and NNUE networks fetched on demand.
+//!
+//! A manifest lists, for each engine, a binary per platform and optionally
+//! the network it evaluates with. Files are downloaded once into a cache
+//! directory, checked against their SHA-256 and reused while they still
+//! match, so a host needs nothing installed beforehand.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+use crate::builtin::{BUILTIN_ENGINE, BuiltinEngine};
+use crate::process::ProcessEngine;
+use crate::{Engine, EngineError, EngineInfo};
+
+/// UCI option naming the network file an NNUE engine loads.
+pub const EVAL_FILE_OPTION: &str = "EvalFile";
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid asset manifest: {0}")]
+    Manifest(String),
+    #[error("No engine '{0}' in the asset manifest")]
+    UnknownEngine(String),
+    #[error("Engine '{engine}' has no binary for {platform}")]
+    UnsupportedPlatform { engine: String, platform: String },
+    #[error("Failed to download {url}: {reason}")]
+    Download { url: String, reason: String },
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch { file: String, expected: String, actual: String },
+}
+
+/// A file to fetch: where from and the SHA-256 (hex) it must have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetSpec {
+    /// Name of the file in the cache
+    pub file_name: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+/// Binary of an engine for one platform, e.g. `linux-x86_64`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryAsset {
+    pub platform: String,
+    #[serde(flatten)]
+    pub asset: AssetSpec,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineAssets {
+    pub name: String,
+    /// Must be a bare executable, not an archive
+    pub binaries: Vec<BinaryAsset>,
+    /// Network passed as `EvalFile`; engines with an embedded net need none
+    #[serde(default)]
+    pub network: Option<AssetSpec>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetManifest {
+    pub engines: Vec<EngineAssets>,
+}
+
+impl AssetManifest {
+    pub fn from_json(json: &str) -> Result<Self, AssetError> {
+        serde_json::from_str(json).map_err(|e| AssetError::Manifest(e.to_string()))
+    }
+
+    pub async fn load(path: &Path) -> Result<Self, AssetError> {
+        Self::from_json(&tokio::fs::read_to_string(path).await?)
+    }
+
+    pub fn engine(&self, name: &str) -> Result<&EngineAssets, AssetError> {
+        self.engines
+            .iter()
+            .find(|engine| engine.name == name)
+            .ok_or_else(|| AssetError::UnknownEngine(name.to_string()))
+    }
+}
+
+/// Platform of this host in manifest terms, e.g. `linux-x86_64` or `macos-aarch64`.
+pub fn current_platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// An engine ready to start: its binary and the network it should load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedEngine {
+    pub binary: PathBuf,
+    pub eval_file: Option<PathBuf>,
+}
+
+impl PreparedEngine {
+    /// An engine installed on the host, with whatever network it embeds.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: path.into(),
+            eval_file: None,
+        }
+    }
+
+    /// The engine written in Rust, for hosts without a UCI binary.
+    pub fn builtin() -> Self {
+        Self::from_path(BUILTIN_ENGINE)
+    }
+
+    /// Whether no binary is configured, so the built-in engine runs instead.
+    pub fn is_builtin(&self) -> bool {
+        self.binary.as_os_str().is_empty() || self.binary == Path::new(BUILTIN_ENGINE)
+    }
+
+    /// Start the engine with its network selected.
+    pub async fn start(&self) -> Result<Box<dyn Engine>, EngineError> {
+        if self.is_builtin() {
+            return Ok(Box::new(BuiltinEngine::new()));
+        }
+        let mut engine = ProcessEngine::new(&self.binary.to_string_lossy()).await?;
+        if let Some(eval_file) = &self.eval_file {
+            engine.set_option(EVAL_FILE_OPTION, &eval_file.to_string_lossy()).await?;
+        }
+        Ok(Box::new(engine))
+    }
+
+    /// Start the engine just long enough to learn what it is.
+    pub async fn identify(&self) -> Result<EngineInfo, EngineError> {
+        let mut engine = self.start().await?;
+        let info = engine.info();
+        engine.quit().await?;
+        Ok(info)
+    }
+}
+
+/// Download cache for engine assets.
+#[derive(Clone)]
+pub struct AssetCache {
+    dir: PathBuf,
+    client: reqwest::Client,
+}
+
+impl AssetCache {
+    pub fn new(dir: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Where `asset` is kept. The checksum is part of the name, so a new
+    /// release never overwrites a file an engine may still have open.
+    pub fn path_of(&self, asset: &AssetSpec) -> PathBuf {
+        let prefix: String = asset.sha256.chars().take(12).collect();
+        self.dir.join(format!("{}-{}", prefix.to_lowercase(), asset.file_name))
+    }
+
+    /// Binary for this host and network of `name`, fetched if not cached.
+    pub async fn prepare(&self, manifest: &AssetManifest, name: &str) -> Result<PreparedEngine, AssetError> {
+        let engine = manifest.engine(name)?;
+        let platform = current_platform();
+        let binary = engine
+            .binaries
+            .iter()
+            .find(|binary| binary.platform == platform)
+            .ok_or_else(|| AssetError::UnsupportedPlatform {
+                engine: name.to_string(),
+                platform,
+            })?;
+
+        let binary = self.ensure(&binary.asset, true).await?;
+        let eval_file = match &engine.network {
+            Some(network) => Some(self.ensure(network, false).await?),
+            None => None,
+        };
+        Ok(PreparedEngine { binary, eval_file })
+    }
+
+    /// Path of a verified copy of `asset`, downloading it when missing or corrupt.
+    pub async fn ensure(&self, asset: &AssetSpec, executable: bool) -> Result<PathBuf, AssetError> {
+        let path = self.path_of(asset);
+        if tokio::fs::try_exists(&path).await? {
+            if sha256_of_file(&path).await?.eq_ignore_ascii_case(&asset.sha256) {
+                return Ok(path);
+            }
+            log::warn!("Cached {} is corrupt, downloading it again", path.display());
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let partial = path.with_extension("part");
+        let actual = match self.download(&asset.url, &partial).await {
+            Ok(actual) => actual,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e);
+            }
+        };
+        if !actual.eq_ignore_ascii_case(&asset.sha256) {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(AssetError::ChecksumMismatch {
+                file: asset.file_name.clone(),
+                expected: asset.sha256.clone(),
+                actual,
+            });
+        }
+        if executable {
+            make_executable(&partial).await?;
+        }
+        tokio::fs::rename(&partial, &path).await?;
+        log::info!("Downloaded {} to {}", asset.url, path.display());
+        Ok(path)
+    }
+
+    // Stream the file to `target`, returning its SHA-256
+    async fn download(&self, url: &str, target: &Path) -> Result<String, AssetError> {
+        let failed = |reason: String| AssetError::Download {
+            url: url.to_string(),
+            reason,
+        };
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| failed(e.to_string()))?;
+
+        let mut file = tokio::fs::File::create(target).await?;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| failed(e.to_string()))? {
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(hex(&hasher.finalize()))
+    }
+}
+
+pub async fn sha256_of_file(path: &Path) -> Result<String, AssetError> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(hex(&Sha256::digest(&bytes)))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(unix)]
+async fn make_executable(path: &Path) -> Result<(), AssetError> {
+    use std::os::unix::fs::PermissionsExt;
+    tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn make_executable(_path: &Path) -> Result<(), AssetError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-256 of "nnue"
+    const NNUE_SHA256: &str = "f3ae829e4184f63e4dece4f880104df86daa568f18527dab7cbcc299cd689091";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("engine-assets-{}-{}", name, std::process::id()))
+    }
+
+    fn manifest() -> AssetManifest {
+        AssetManifest::from_json(&format!(
+            r#"{{"engines": [{{
+                "name": "stockfish",
+                "binaries": [{{"platform": "{}", "file_name": "stockfish", "url": "http://127.0.0.1:9/stockfish", "sha256": "{}"}}],
+                "network": {{"file_name": "nn.nnue", "url": "http://127.0.0.1:9/nn.nnue", "sha256": "{}"}}
+            }}]}}"#,
+            current_platform(),
+            NNUE_SHA256,
+            NNUE_SHA256
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn manifest_names_files_by_checksum() {
+        let manifest = manifest();
+        let engine = manifest.engine("stockfish").unwrap();
+        assert_eq!(engine.binaries[0].platform, current_platform());
+        assert!(matches!(manifest.engine("komodo"), Err(AssetError::UnknownEngine(_))));
+
+        let cache = AssetCache::new("/var/cache/engines", Duration::from_secs(1));
+        let network = engine.network.as_ref().unwrap();
+        assert_eq!(cache.path_of(network), PathBuf::from("/var/cache/engines/f3ae829e4184-nn.nnue"));
+    }
+
+    #[tokio::test]
+    async fn verified_files_are_reused_and_corrupt_ones_fetched_again() {
+        let dir = scratch_dir("reuse");
+        let cache = AssetCache::new(&dir, Duration::from_secs(1));
+        let network = manifest().engines[0].network.clone().unwrap();
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(cache.path_of(&network), b"nnue").await.unwrap();
+
+        // Cached with the right checksum: no download needed
+        assert_eq!(cache.ensure(&network, false).await.unwrap(), cache.path_of(&network));
+
+        // Corrupt copy: downloaded again, which fails without a server
+        tokio::fs::write(cache.path_of(&network), b"truncated").await.unwrap();
+        assert!(matches!(cache.ensure(&network, false).await, Err(AssetError::Download { .. })));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn hosts_without_a_binary_are_refused() {
+        let mut manifest = manifest();
+        manifest.engines[0].binaries[0].platform = "plan9-mips".to_string();
+        let cache = AssetCache::new(scratch_dir("platform"), Duration::from_secs(1));
+        assert!(matches!(
+            cache.prepare(&manifest, "stockfish").await,
+            Err(AssetError::UnsupportedPlatform { .. })
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+//! A fixed benchmark: the same positions searched to the same depth, so that
+//! runs on different hosts, or before and after a deploy, can be compared.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, EngineError, GoParams};
+
+/// Depth of a benchmark run when none is asked for
+pub const BENCH_DEPTH: u8 = 12;
+
+/// Openings, middlegames and endgames, quiet and tactical.
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+];
+
+/// How the engine did on one position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchPosition {
+    pub fen: String,
+    pub best_move: String,
+    /// Depth the engine reached, which may be less than asked
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub depth: u8,
+    pub positions: Vec<BenchPosition>,
+}
+
+impl BenchReport {
+    /// Nodes of the whole run; `None` when the engine does not count them
+    pub fn total_nodes(&self) -> Option<u64> {
+        self.positions.iter().map(|position| position.nodes).sum()
+    }
+
+    pub fn total_time_ms(&self) -> u64 {
+        self.positions.iter().map(|position| position.time_ms).sum()
+    }
+
+    pub fn nodes_per_second(&self) -> Option<u64> {
+        let nodes = self.total_nodes()?;
+        Some(nodes * 1000 / self.total_time_ms().max(1))
+    }
+}
+
+/// Search every benchmark position to `depth` with `engine`, timing each.
+pub async fn run(engine: &mut dyn Engine, depth: u8) -> Result<BenchReport, EngineError> {
+    let mut positions = Vec::with_capacity(BENCH_POSITIONS.len());
+    for fen in BENCH_POSITIONS {
+        engine.new_game().await?;
+        engine.set_position(fen).await?;
+        engine.is_ready().await?;
+
+        let started = Instant::now();
+        let result = engine
+            .go(GoParams {
+                depth: Some(depth),
+                time_limit_ms: None,
+                search_moves: None,
+                clock: None,
+            })
+            .await?;
+        positions.push(BenchPosition {
+            fen: fen.to_string(),
+            best_move: result.best_move,
+            depth: result.depth,
+            nodes: result.nodes,
+            time_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+    Ok(BenchReport { depth, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::BuiltinEngine;
+
+    #[tokio::test]
+    async fn test_benchmark_searches_every_position() {
+        let mut engine = BuiltinEngine::new();
+        let report = run(&mut engine, 1).await.unwrap();
+
+        assert_eq!(report.positions.len(), BENCH_POSITIONS.len());
+        assert!(report.positions.iter().all(|position| position.depth == Some(1)));
+        assert!(report.total_nodes().unwrap() > 0);
+        assert!(report.nodes_per_second().is_some());
+    }
+
+    #[test]
+    fn test_nodes_per_second_needs_every_count() {
+        let position = |nodes| BenchPosition {
+            fen: BENCH_POSITIONS[0].to_string(),
+            best_move: "e2e4".to_string(),
+            depth: Some(12),
+            nodes,
+            time_ms: 500,
+        };
+        let report = BenchReport { depth: 12, positions: vec![position(Some(400_000)), position(Some(600_000))] };
+        assert_eq!(report.nodes_per_second(), Some(1_000_000));
+
+        let report = BenchReport { depth: 12, positions: vec![position(Some(400_000)), position(None)] };
+        assert_eq!(report.nodes_per_second(), None);
+    }
+}
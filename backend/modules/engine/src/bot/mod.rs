@@ -0,0 +1,372 @@
+//! Engine opponents of a set strength and style.
+//!
+//! A bot weakens the engine three ways: a shallow search, the engine's own
+//! `Skill Level`, and noise added to the evaluation of each candidate line
+//! of a MultiPV search, so that it sometimes prefers a worse move. It
+//! follows its favourite openings for as long as the game does, and takes
+//! its time over moves as set by its [`MoveTiming`].
+
+use chess::Referee;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, EngineError, GoParams, PvLine};
+
+pub mod timing;
+
+pub use timing::{BotClock, MoveTiming, complexity};
+
+/// Centipawns a forced mate counts for when lines are compared
+const MATE_CP: i64 = 100_000;
+
+/// How a bot plays.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotProfile {
+    /// Used in requests, e.g. `casual`
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Rough playing strength, for display
+    pub rating: u32,
+    pub depth: Option<u8>,
+    pub movetime_ms: Option<u32>,
+    /// UCI `Skill Level`, 0 to 20
+    pub skill_level: Option<u8>,
+    /// Largest random change to a candidate line's evaluation
+    pub eval_noise_cp: u32,
+    /// Lines searched so that the noise has moves to choose from
+    pub candidates: u8,
+    /// Opening lines in UCI notation, followed while the game does
+    #[serde(default)]
+    pub openings: Vec<String>,
+    #[serde(default)]
+    pub timing: MoveTiming,
+}
+
+/// A move chosen by a bot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotMove {
+    /// UCI notation
+    pub uci: String,
+    /// From the bot's point of view; `None` for book moves
+    pub evaluation: Option<f32>,
+    pub from_book: bool,
+    /// How hard the move was to find, from 0 to 1; 0 for book moves
+    pub complexity: f64,
+}
+
+impl BotProfile {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        id: &str,
+        name: &str,
+        description: &str,
+        rating: u32,
+        depth: u8,
+        skill_level: u8,
+        eval_noise_cp: u32,
+        candidates: u8,
+        openings: &[&str],
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            rating,
+            depth: Some(depth),
+            movetime_ms: None,
+            skill_level: Some(skill_level),
+            eval_noise_cp,
+            candidates,
+            openings: openings.iter().map(|line| line.to_string()).collect(),
+            timing: MoveTiming::default(),
+        }
+    }
+
+    fn with_timing(mut self, min_ms: u64, max_ms: u64, jitter_percent: u8) -> Self {
+        self.timing = MoveTiming { min_ms, max_ms, jitter_percent, ..MoveTiming::default() };
+        self
+    }
+
+    /// The bots offered to players, weakest first.
+    pub fn builtin() -> Vec<BotProfile> {
+        vec![
+            BotProfile::new(
+                "beginner",
+                "Pip",
+                "Just learned the moves and loves an early queen sortie",
+                800,
+                1,
+                0,
+                300,
+                5,
+                &["e2e4 e7e5 d1h5", "e2e4 e7e5 f1c4 b8c6 d1h5", "d2d4 e7e5"],
+            )
+            // Quick and erratic
+            .with_timing(300, 2_500, 50),
+            BotProfile::new(
+                "casual",
+                "Casey",
+                "Plays the classical openings and misses the odd tactic",
+                1200,
+                4,
+                3,
+                120,
+                4,
+                &["e2e4 e7e5 g1f3 b8c6 f1c4", "d2d4 d7d5 c2c4 e7e6", "e2e4 e7e5 g1f3 b8c6 f1c4 f8c5"],
+            ),
+            BotProfile::new(
+                "gambiteer",
+                "Gambiteer",
+                "Gives up pawns for the initiative at every chance",
+                1600,
+                8,
+                8,
+                50,
+                3,
+                &["e2e4 e7e5 f2f4", "e2e4 c7c5 d2d4 c5d4 c2c3", "d2d4 g8f6 c2c4 e7e5"],
+            )
+            .with_timing(400, 3_000, 30),
+            BotProfile::new(
+                "fortress",
+                "Fortress",
+                "Solid structures, few risks and long endgames",
+                1900,
+                10,
+                12,
+                20,
+                3,
+                &["d2d4 d7d5 c1f4", "e2e4 c7c6 d2d4 d7d5", "d2d4 d7d5 c2c4 c7c6"],
+            )
+            .with_timing(800, 6_000, 20),
+            BotProfile::new("master", "Master", "The engine at full strength", 2400, 16, 20, 0, 1, &[])
+                .with_timing(1_000, 8_000, 15),
+        ]
+    }
+
+    pub fn find(id: &str) -> Option<BotProfile> {
+        Self::builtin().into_iter().find(|profile| profile.id == id)
+    }
+
+    /// The next move of one of the bot's openings the game has followed
+    /// so far, picked at random among them. `played` holds the game's moves
+    /// in UCI notation from the standard starting position.
+    pub fn book_move(&self, played: &[String], rng: &mut impl Rng) -> Option<String> {
+        let next: Vec<&str> = self
+            .openings
+            .iter()
+            .filter_map(|line| {
+                let line: Vec<&str> = line.split_whitespace().collect();
+                let follows = line.len() > played.len() && line.iter().zip(played).all(|(a, b)| *a == b.as_str());
+                follows.then(|| line[played.len()])
+            })
+            .collect();
+        next.choose(rng).map(|uci| uci.to_string())
+    }
+
+    /// The book move for the game so far, chosen from `seed`.
+    pub fn opening_move(&self, played: &[String], seed: u64) -> Option<BotMove> {
+        let uci = self.book_move(played, &mut StdRng::seed_from_u64(seed))?;
+        Some(BotMove { uci, evaluation: None, from_book: true, complexity: 0.0 })
+    }
+
+    /// UCI options the engine searches with for this bot.
+    pub fn uci_options(&self) -> Vec<(&'static str, String)> {
+        let mut options = Vec::new();
+        if let Some(skill_level) = self.skill_level {
+            options.push(("Skill Level", skill_level.min(20).to_string()));
+        }
+        if self.uses_noise() {
+            options.push(("MultiPV", self.candidates.to_string()));
+        }
+        options
+    }
+
+    fn uses_noise(&self) -> bool {
+        self.eval_noise_cp > 0 && self.candidates > 1
+    }
+}
+
+// A line's evaluation in centipawns, mates counting for more the sooner they come
+fn line_cp(line: &PvLine) -> i64 {
+    match (line.mate, line.evaluation) {
+        (Some(mate), _) if mate > 0 => MATE_CP - mate as i64,
+        (Some(mate), _) => -MATE_CP - mate as i64,
+        (None, Some(evaluation)) => (evaluation * 100.0).round() as i64,
+        (None, None) => 0,
+    }
+}
+
+/// The line that scores best once each evaluation is shifted by up to
+/// `noise_cp` either way.
+pub fn pick_line<'a>(lines: &'a [PvLine], noise_cp: u32, rng: &mut impl Rng) -> Option<&'a PvLine> {
+    let noise = noise_cp as i64;
+    lines
+        .iter()
+        .filter(|line| !line.moves.is_empty())
+        .map(|line| (line_cp(line) + rng.gen_range(-noise..=noise), line))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, line)| line)
+}
+
+/// Choose the bot's move in `fen`. `played` holds the game's moves from the
+/// standard starting position, for the opening book; leave it empty for
+/// games started from another position. The same `seed` gives the same
+/// choices.
+pub async fn choose_move<E: Engine + ?Sized>(
+    engine: &mut E,
+    profile: &BotProfile,
+    fen: &str,
+    played: &[String],
+    seed: u64,
+) -> Result<BotMove, EngineError> {
+    match profile.opening_move(played, seed) {
+        Some(book) => Ok(book),
+        None => search_move(engine, profile, fen, seed).await,
+    }
+}
+
+/// Search `fen` within the bot's limits, letting the noise pick among the
+/// candidate lines.
+pub async fn search_move<E: Engine + ?Sized>(
+    engine: &mut E,
+    profile: &BotProfile,
+    fen: &str,
+    seed: u64,
+) -> Result<BotMove, EngineError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for (name, value) in profile.uci_options() {
+        engine.set_option(name, &value).await?;
+    }
+    engine.is_ready().await?;
+    engine.set_position(fen).await?;
+    let result = engine
+        .go(GoParams {
+            depth: profile.depth,
+            time_limit_ms: profile.movetime_ms,
+            search_moves: None,
+            clock: None,
+        })
+        .await?;
+    let legal_moves = Referee::from_fen(fen).map(|referee| referee.legal_moves().len()).unwrap_or(0);
+    let complexity = complexity(legal_moves, &result.lines);
+
+    if profile.uses_noise()
+        && let Some(line) = pick_line(&result.lines, profile.eval_noise_cp, &mut rng)
+    {
+        return Ok(BotMove {
+            uci: line.moves[0].clone(),
+            evaluation: line.evaluation,
+            from_book: false,
+            complexity,
+        });
+    }
+    Ok(BotMove { uci: result.best_move, evaluation: result.evaluation, from_book: false, complexity })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EngineResult;
+    use async_trait::async_trait;
+
+    fn line(uci: &str, evaluation: f32) -> PvLine {
+        PvLine { moves: vec![uci.to_string()], evaluation: Some(evaluation), mate: None }
+    }
+
+    /// Reports the same lines whatever it is asked, and remembers its options.
+    struct FixedEngine {
+        lines: Vec<PvLine>,
+        options: Vec<(String, String)>,
+    }
+
+    #[async_trait]
+    impl Engine for FixedEngine {
+        async fn go(&mut self, _params: GoParams) -> Result<EngineResult, EngineError> {
+            Ok(EngineResult {
+                best_move: self.lines[0].moves[0].clone(),
+                evaluation: self.lines[0].evaluation,
+                mate: None,
+                depth: Some(1),
+                principal_variation: self.lines[0].moves.clone(),
+                lines: self.lines.clone(),
+                nodes: None,
+            })
+        }
+        async fn stop(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn set_position(&mut self, _fen: &str) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn is_ready(&mut self) -> Result<bool, EngineError> {
+            Ok(true)
+        }
+        async fn quit(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+            self.options.push((name.to_string(), value.to_string()));
+            Ok(())
+        }
+        async fn new_game(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn noise_only_overturns_close_lines() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let lines = [line("e2e4", 0.3), line("d2d4", 0.25), line("g2g4", -2.0)];
+        assert_eq!(pick_line(&lines, 0, &mut rng).unwrap().moves[0], "e2e4");
+
+        let picks: Vec<String> = (0..200)
+            .map(|_| pick_line(&lines, 50, &mut rng).unwrap().moves[0].clone())
+            .collect();
+        assert!(picks.iter().any(|uci| uci == "d2d4"));
+        assert!(picks.iter().all(|uci| uci != "g2g4"));
+
+        let mate = PvLine { moves: vec!["h5f7".to_string()], evaluation: None, mate: Some(1) };
+        assert_eq!(pick_line(&[line("e2e4", 9.0), mate], 300, &mut rng).unwrap().moves[0], "h5f7");
+    }
+
+    #[test]
+    fn openings_are_followed_while_the_game_does() {
+        let profile = BotProfile::find("gambiteer").unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let played = |moves: &str| moves.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+
+        assert_eq!(profile.book_move(&played("e2e4 e7e5"), &mut rng).as_deref(), Some("f2f4"));
+        assert_eq!(profile.book_move(&played("d2d4 g8f6 c2c4"), &mut rng).as_deref(), Some("e7e5"));
+        assert_eq!(profile.book_move(&played("e2e4 e7e6"), &mut rng), None);
+        assert!(BotProfile::find("master").unwrap().book_move(&[], &mut rng).is_none());
+    }
+
+    #[tokio::test]
+    async fn bots_search_with_their_limits() {
+        let profile = BotProfile::find("casual").unwrap();
+        let mut engine = FixedEngine {
+            lines: vec![line("e7e5", 0.2), line("c7c5", 0.1)],
+            options: Vec::new(),
+        };
+        let fen = "rnbqkbnr/pppppppp/8/8/6P1/8/PPPPPP1P/RNBQKBNR b KQkq - 0 1";
+        let played = ["g2g4".to_string()];
+
+        // Out of book, the move comes from one of the searched lines
+        let chosen = choose_move(&mut engine, &profile, fen, &played, 3).await.unwrap();
+        assert!(!chosen.from_book);
+        // Twenty legal moves and two lines a tenth of a pawn apart
+        assert!(chosen.complexity > 0.7);
+        assert!(["e7e5", "c7c5"].contains(&chosen.uci.as_str()));
+        assert_eq!(
+            engine.options,
+            vec![("Skill Level".to_string(), "3".to_string()), ("MultiPV".to_string(), "4".to_string())]
+        );
+        assert_eq!(choose_move(&mut engine, &profile, fen, &played, 3).await.unwrap(), chosen);
+
+        let book = choose_move(&mut engine, &profile, fen, &["d2d4".to_string()], 3).await.unwrap();
+        assert_eq!((book.uci.as_str(), book.from_book), ("d7d5", true));
+    }
+}
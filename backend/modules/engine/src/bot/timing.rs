@@ -0,0 +1,128 @@
+//! How long a bot takes over a move. A bot that answers instantly never
+//! feels like an opponent, and in a time scramble it keeps its whole clock
+//! while the human's runs out, so bots spend time the way people do: more
+//! on hard choices, less on forced ones, and never more than their clock
+//! can afford.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::line_cp;
+use crate::PvLine;
+
+/// Legal moves beyond which a position counts as fully open
+const BROAD_POSITION_MOVES: usize = 40;
+
+/// Gap between the two best lines below which the choice counts as hard
+const CLOSE_CALL_CP: i64 = 200;
+
+/// A bot's pace, part of its profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveTiming {
+    /// Time over the simplest move
+    pub min_ms: u64,
+    /// Time over the hardest move when the clock allows it
+    pub max_ms: u64,
+    /// Moves the remaining clock is shared over
+    pub moves_to_go: u32,
+    /// Random change to each delay, in percent of it
+    pub jitter_percent: u8,
+}
+
+impl Default for MoveTiming {
+    fn default() -> Self {
+        Self {
+            min_ms: 500,
+            max_ms: 4_000,
+            moves_to_go: 30,
+            jitter_percent: 25,
+        }
+    }
+}
+
+/// The bot's side of the game clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotClock {
+    pub remaining_ms: u64,
+    #[serde(default)]
+    pub increment_ms: u64,
+}
+
+/// How hard a move is to find, from 0 for a forced move to 1: many legal
+/// moves and candidate lines close in value make it harder.
+pub fn complexity(legal_moves: usize, lines: &[PvLine]) -> f64 {
+    if legal_moves <= 1 {
+        return 0.0;
+    }
+    let breadth = legal_moves.min(BROAD_POSITION_MOVES) as f64 / BROAD_POSITION_MOVES as f64;
+    let closeness = match lines {
+        [best, second, ..] => {
+            let gap = (line_cp(best) - line_cp(second)).abs().min(CLOSE_CALL_CP);
+            1.0 - gap as f64 / CLOSE_CALL_CP as f64
+        }
+        // Without a second line the choice is of middling difficulty
+        _ => 0.5,
+    };
+    0.4 * breadth + 0.6 * closeness
+}
+
+impl MoveTiming {
+    /// Time to take over a move of `complexity`, within what `clock` allows.
+    pub fn think_time_ms(&self, complexity: f64, clock: Option<BotClock>, rng: &mut impl Rng) -> u64 {
+        let max_ms = self.max_ms.max(self.min_ms);
+        let mut ms = self.min_ms as f64 + (max_ms - self.min_ms) as f64 * complexity.clamp(0.0, 1.0);
+
+        let jitter = self.jitter_percent.min(100) as f64 / 100.0;
+        if jitter > 0.0 {
+            ms *= 1.0 + rng.gen_range(-jitter..=jitter);
+        }
+
+        if let Some(clock) = clock {
+            // Most of the increment comes back after the move, so it may be spent
+            let budget = clock.remaining_ms / self.moves_to_go.max(1) as u64 + clock.increment_ms * 4 / 5;
+            ms = ms.min(budget as f64);
+        }
+        ms.max(0.0).round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn line(evaluation: f32) -> PvLine {
+        PvLine { moves: vec!["e2e4".to_string()], evaluation: Some(evaluation), mate: None }
+    }
+
+    #[test]
+    fn hard_choices_take_longer_than_forced_ones() {
+        assert_eq!(complexity(1, &[line(0.5), line(0.4)]), 0.0);
+        let close = complexity(30, &[line(0.3), line(0.25)]);
+        let clear = complexity(30, &[line(3.0), line(0.2)]);
+        assert!(close > clear);
+
+        let timing = MoveTiming { jitter_percent: 0, ..MoveTiming::default() };
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(timing.think_time_ms(0.0, None, &mut rng), 500);
+        assert_eq!(timing.think_time_ms(1.0, None, &mut rng), 4_000);
+        assert!(timing.think_time_ms(close, None, &mut rng) > timing.think_time_ms(clear, None, &mut rng));
+    }
+
+    #[test]
+    fn the_clock_caps_the_delay() {
+        let timing = MoveTiming::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let low = BotClock { remaining_ms: 15_000, increment_ms: 0 };
+        for _ in 0..50 {
+            assert!(timing.think_time_ms(1.0, Some(low), &mut rng) <= 500);
+            let free = timing.think_time_ms(0.5, None, &mut rng);
+            assert!((1_687..=2_813).contains(&free), "{}", free);
+        }
+        // The increment can be spent
+        let with_increment = BotClock { remaining_ms: 15_000, increment_ms: 2_000 };
+        assert!(timing.think_time_ms(1.0, Some(with_increment), &mut rng) <= 2_100);
+        assert!(timing.think_time_ms(1.0, Some(with_increment), &mut rng) > 500);
+    }
+}
@@ -0,0 +1,397 @@
+//! A small engine written in Rust: material and piece-square evaluation,
+//! searched with alpha-beta to a shallow depth.
+//!
+//! It is far weaker than any UCI engine but needs no binary, so it stands in
+//! where none is configured. It also answers the same way every time, which
+//! makes it the engine for tests of the analysis pipeline.
+
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chess::{Referee, Termination};
+
+use crate::{Engine, EngineError, EngineInfo, EngineOption, EngineResult, GoParams, PvLine};
+
+/// Configured in place of a binary path to select this engine.
+pub const BUILTIN_ENGINE: &str = "builtin";
+
+/// Plies searched when the request names no depth
+pub const DEFAULT_DEPTH: u8 = 3;
+/// Deeper requests are cut to this many plies
+pub const MAX_DEPTH: u8 = 4;
+
+const MAX_LINES: usize = 10;
+const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// Searches in-process; every instance is independent.
+#[derive(Debug, Clone)]
+pub struct BuiltinEngine {
+    referee: Referee,
+    /// Lines reported, as set with the `MultiPV` option
+    lines: usize,
+}
+
+impl BuiltinEngine {
+    pub fn new() -> Self {
+        Self { referee: Referee::default(), lines: 1 }
+    }
+}
+
+impl Default for BuiltinEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Engine for BuiltinEngine {
+    async fn go(&mut self, params: GoParams) -> Result<EngineResult, EngineError> {
+        let depth = params.depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+        let referee = self.referee.clone();
+        let lines = self.lines.max(1);
+        let (found, nodes) = tokio::task::spawn_blocking(move || search(referee, depth, lines, params.search_moves.as_deref()))
+            .await
+            .map_err(|e| EngineError::Unknown(e.to_string()))?;
+
+        let (score, best_line) = found
+            .first()
+            .cloned()
+            .ok_or_else(|| EngineError::Unknown("No legal moves in the position".to_string()))?;
+        let (evaluation, mate) = score_of(score);
+        Ok(EngineResult {
+            best_move: best_line[0].clone(),
+            evaluation,
+            mate,
+            depth: Some(depth),
+            principal_variation: best_line,
+            lines: if found.len() > 1 {
+                found
+                    .into_iter()
+                    .map(|(score, moves)| {
+                        let (evaluation, mate) = score_of(score);
+                        PvLine { moves, evaluation, mate }
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            nodes: Some(nodes),
+        })
+    }
+
+    async fn stop(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    async fn set_position(&mut self, fen: &str) -> Result<(), EngineError> {
+        self.referee = Referee::from_fen(fen).map_err(|e| EngineError::ParseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn is_ready(&mut self) -> Result<bool, EngineError> {
+        Ok(true)
+    }
+
+    async fn quit(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    /// Only `MultiPV` has an effect; the search has nothing else to tune.
+    async fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        if name.eq_ignore_ascii_case("MultiPV") {
+            let lines: usize = value
+                .parse()
+                .map_err(|_| EngineError::ParseError(format!("MultiPV '{}'", value)))?;
+            self.lines = lines.clamp(1, MAX_LINES);
+        }
+        Ok(())
+    }
+
+    async fn new_game(&mut self) -> Result<(), EngineError> {
+        self.referee = Referee::default();
+        Ok(())
+    }
+
+    fn info(&self) -> EngineInfo {
+        EngineInfo {
+            name: Some(format!("StarkMate built-in {}", env!("CARGO_PKG_VERSION"))),
+            author: Some("the StarkMate developers".to_string()),
+            options: vec![EngineOption {
+                name: "MultiPV".to_string(),
+                kind: "spin".to_string(),
+                default: Some("1".to_string()),
+                min: Some(1),
+                max: Some(MAX_LINES as i64),
+                vars: Vec::new(),
+            }],
+        }
+    }
+}
+
+/// The best `lines` root moves with their scores and principal variations,
+/// best first, and the number of nodes searched.
+fn search(
+    mut referee: Referee,
+    depth: u8,
+    lines: usize,
+    search_moves: Option<&[String]>,
+) -> (Vec<(i32, Vec<String>)>, u64) {
+    let mut found: Vec<(i32, Vec<String>)> = Vec::new();
+    let mut nodes = 0;
+    let mut best = -INFINITY;
+    for mv in ordered_moves(&referee) {
+        if search_moves.is_some_and(|allowed| !allowed.contains(&mv)) {
+            continue;
+        }
+        // A single line only has to beat the best so far, so the rest can be cut
+        let alpha = if lines == 1 { best } else { -INFINITY };
+        let mut line = Vec::new();
+        let token = referee.make_move(&mv).expect("legal moves can be played");
+        let score = -negamax(&mut referee, depth - 1, 1, -INFINITY, -alpha, &mut line, &mut nodes);
+        referee.unmake_move(token).expect("the last move can be taken back");
+        best = best.max(score);
+        line.insert(0, mv);
+        found.push((score, line));
+    }
+    // Stable, so equal scores keep the move order and the search stays deterministic
+    found.sort_by_key(|(score, _)| Reverse(*score));
+    found.truncate(lines);
+    (found, nodes)
+}
+
+/// Score of the position for the side to move, fail-hard within
+/// `alpha..beta`, with the line that reaches it in `pv`. Every position
+/// visited is counted in `nodes`.
+fn negamax(
+    referee: &mut Referee,
+    depth: u8,
+    ply: i32,
+    mut alpha: i32,
+    beta: i32,
+    pv: &mut Vec<String>,
+    nodes: &mut u64,
+) -> i32 {
+    *nodes += 1;
+    pv.clear();
+    if let Some((_, termination)) = referee.outcome() {
+        // Mate always goes against the side to move; sooner mates score higher
+        return match termination {
+            Termination::Checkmate => -(MATE - ply),
+            _ => 0,
+        };
+    }
+    if depth == 0 {
+        return evaluate(referee);
+    }
+
+    let mut line = Vec::new();
+    for mv in ordered_moves(referee) {
+        let token = referee.make_move(&mv).expect("legal moves can be played");
+        let score = -negamax(referee, depth - 1, ply + 1, -beta, -alpha, &mut line, nodes);
+        referee.unmake_move(token).expect("the last move can be taken back");
+        if score > alpha {
+            alpha = score;
+            pv.clear();
+            pv.push(mv);
+            pv.append(&mut line);
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+    alpha
+}
+
+/// Legal moves with captures of the most valuable pieces first, so that
+/// alpha-beta cuts early.
+fn ordered_moves(referee: &Referee) -> Vec<String> {
+    let mut board = [None; 64];
+    for (square, piece) in referee.pieces() {
+        board[square as usize] = Some(piece);
+    }
+    let victim = |mv: &String| {
+        let bytes = mv.as_bytes();
+        let square = usize::from(bytes[3] - b'1') * 8 + usize::from(bytes[2] - b'a');
+        board[square].map_or(0, piece_value)
+    };
+
+    let mut moves = referee.legal_moves();
+    moves.sort_by_key(|mv| Reverse(victim(mv)));
+    moves
+}
+
+/// Material and piece placement, in centipawns for the side to move.
+fn evaluate(referee: &Referee) -> i32 {
+    let white: i32 = referee
+        .pieces()
+        .into_iter()
+        .map(|(square, piece)| {
+            let (rank, file) = (usize::from(square / 8), usize::from(square % 8));
+            // Tables are laid out from White's side, eighth rank first
+            let (row, sign) = if piece.is_ascii_uppercase() { (7 - rank, 1) } else { (rank, -1) };
+            sign * (piece_value(piece) + table_of(piece)[row * 8 + file])
+        })
+        .sum();
+    if referee.white_to_move() { white } else { -white }
+}
+
+fn piece_value(piece: char) -> i32 {
+    match piece.to_ascii_lowercase() {
+        'p' => 100,
+        'n' => 320,
+        'b' => 330,
+        'r' => 500,
+        'q' => 900,
+        _ => 0,
+    }
+}
+
+fn table_of(piece: char) -> &'static [i32; 64] {
+    match piece.to_ascii_lowercase() {
+        'p' => &PAWN,
+        'n' => &KNIGHT,
+        'b' => &BISHOP,
+        'r' => &ROOK,
+        'q' => &QUEEN,
+        _ => &KING,
+    }
+}
+
+/// A score as reported over UCI: pawns, or moves to mate when one is found.
+fn score_of(score: i32) -> (Option<f32>, Option<i32>) {
+    if score.abs() < MATE - 1000 {
+        return (Some(score as f32 / 100.0), None);
+    }
+    let moves = (MATE - score.abs() + 1) / 2;
+    (None, Some(if score > 0 { moves } else { -moves }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn analyse(fen: &str, depth: u8) -> EngineResult {
+        let mut engine = BuiltinEngine::new();
+        engine.set_position(fen).await.unwrap();
+        engine
+            .go(GoParams { depth: Some(depth), time_limit_ms: None, search_moves: None, clock: None })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_finds_mate_in_one() {
+        let result = analyse("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", 3).await;
+        assert_eq!(result.best_move, "d1d8");
+        assert_eq!(result.mate, Some(1));
+        assert_eq!(result.evaluation, None);
+    }
+
+    #[tokio::test]
+    async fn test_takes_a_hanging_queen() {
+        let result = analyse("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2).await;
+        assert_eq!(result.best_move, "d2d5");
+        assert!(result.evaluation.unwrap() > 3.0);
+        assert_eq!(result.principal_variation[0], "d2d5");
+    }
+
+    #[tokio::test]
+    async fn test_multipv_lines_are_deterministic() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let mut engine = BuiltinEngine::new();
+        engine.set_option("MultiPV", "3").await.unwrap();
+        engine.set_position(start).await.unwrap();
+        let params = GoParams { depth: Some(2), time_limit_ms: None, search_moves: None, clock: None };
+
+        let first = engine.go(params.clone()).await.unwrap();
+        let second = engine.go(params).await.unwrap();
+        assert_eq!(first.lines.len(), 3);
+        assert_eq!(first.lines, second.lines);
+        assert_eq!(first.best_move, first.lines[0].moves[0]);
+    }
+
+    #[tokio::test]
+    async fn test_search_moves_and_finished_games() {
+        let mut engine = BuiltinEngine::new();
+        engine.set_position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").await.unwrap();
+        let params = GoParams { depth: Some(1), time_limit_ms: None, search_moves: Some(vec!["a2a3".to_string()]), clock: None };
+        assert_eq!(engine.go(params).await.unwrap().best_move, "a2a3");
+
+        // Black is mated
+        engine.set_position("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").await.unwrap();
+        assert!(engine.go(GoParams { depth: None, time_limit_ms: None, search_moves: None, clock: None }).await.is_err());
+    }
+}
@@ -2,9 +2,18 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod assets;
+pub mod bench;
+pub mod bot;
+pub mod builtin;
+pub mod load;
+pub mod matches;
 pub mod parser;
 pub mod process;
+pub mod queue;
+pub mod sessions;
 pub mod uci;
+pub mod variant;
 
 #[derive(Error, Debug)]
 pub enum EngineError {
@@ -16,6 +25,8 @@ pub enum EngineError {
     Timeout,
     #[error("Parse error: {0}")]
     ParseError(String),
+    #[error("No engine analyses {0}")]
+    UnsupportedVariant(String),
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -25,14 +36,69 @@ pub struct GoParams {
     pub depth: Option<u8>,
     pub time_limit_ms: Option<u32>,
     pub search_moves: Option<Vec<String>>,
+    /// Remaining clock times, for searches under a game time control
+    #[serde(default)]
+    pub clock: Option<ClockParams>,
+}
+
+/// Clock state sent with `go` as `wtime`/`btime`/`winc`/`binc`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ClockParams {
+    pub white_ms: u64,
+    pub black_ms: u64,
+    pub white_increment_ms: u64,
+    pub black_increment_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EngineResult {
     pub best_move: String,
     pub evaluation: Option<f32>,
+    /// Moves to mate, negative when the engine is getting mated
+    #[serde(default)]
+    pub mate: Option<i32>,
     pub depth: Option<u8>,
     pub principal_variation: Vec<String>,
+    /// Every line of a MultiPV search, best first; empty when the engine
+    /// reported a single line
+    #[serde(default)]
+    pub lines: Vec<PvLine>,
+    /// Nodes searched, when the engine counts them
+    #[serde(default)]
+    pub nodes: Option<u64>,
+}
+
+/// What an engine said about itself in answer to `uci`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineInfo {
+    /// `id name`, e.g. `Stockfish 16`
+    pub name: Option<String>,
+    /// `id author`
+    pub author: Option<String>,
+    pub options: Vec<EngineOption>,
+}
+
+/// An option the engine accepts with `setoption`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineOption {
+    pub name: String,
+    /// `check`, `spin`, `combo`, `button` or `string`
+    pub kind: String,
+    pub default: Option<String>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    /// Values a `combo` option can take
+    #[serde(default)]
+    pub vars: Vec<String>,
+}
+
+/// One of the lines of a MultiPV search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PvLine {
+    pub moves: Vec<String>,
+    pub evaluation: Option<f32>,
+    #[serde(default)]
+    pub mate: Option<i32>,
 }
 
 #[async_trait]
@@ -42,4 +108,17 @@ pub trait Engine: Send + Sync {
     async fn set_position(&mut self, fen: &str) -> Result<(), EngineError>;
     async fn is_ready(&mut self) -> Result<bool, EngineError>;
     async fn quit(&mut self) -> Result<(), EngineError>;
+    async fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError>;
+    async fn new_game(&mut self) -> Result<(), EngineError>;
+
+    /// Name, author and options the engine reports about itself.
+    fn info(&self) -> EngineInfo {
+        EngineInfo::default()
+    }
+
+    /// Whether the engine once went silent mid-search; such an engine is
+    /// not trusted with another search.
+    fn is_stalled(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,243 @@
+//! Search budgets that follow the load of the engine pool.
+//!
+//! While the pool is busy, live analysis is searched less deeply and for
+//! less time so that every request still comes back quickly; once it quiets
+//! down, budgets grow back to what was asked. How far a request may be cut
+//! back depends on the tier of whoever it is run for.
+
+use dto::ai::AnalysisTier;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::queue::QueueStats;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadConfig {
+    /// Plies searched when a request names neither a depth nor a time limit
+    pub default_depth: u8,
+    /// Budgets are never cut below this depth
+    pub min_depth: u8,
+    /// Nor below this time limit
+    pub min_time_limit_ms: u32,
+    /// Load, as engines in use or wanted per slot, at which budgets shrink
+    pub busy: f64,
+    /// Load at which they grow back
+    pub idle: f64,
+    /// Change of the scale per adjustment
+    pub step: f64,
+    /// Shortest time between two adjustments, so that one burst of requests
+    /// does not take the scale all the way down
+    pub adjust_every: Duration,
+    /// Lowest scale for each tier; 1 keeps the full budget
+    pub guest_floor: f64,
+    pub player_floor: f64,
+    pub premium_floor: f64,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            default_depth: 18,
+            min_depth: 8,
+            min_time_limit_ms: 500,
+            busy: 0.9,
+            idle: 0.5,
+            step: 0.25,
+            adjust_every: Duration::from_secs(2),
+            guest_floor: 0.25,
+            player_floor: 0.5,
+            premium_floor: 1.0,
+        }
+    }
+}
+
+impl LoadConfig {
+    /// Lowest scale searches for `tier` are run at.
+    pub fn floor(&self, tier: AnalysisTier) -> f64 {
+        match tier {
+            AnalysisTier::Guest => self.guest_floor,
+            AnalysisTier::Player => self.player_floor,
+            AnalysisTier::Premium => self.premium_floor,
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+/// What one search may spend.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SearchBudget {
+    pub depth: Option<u8>,
+    pub time_limit_ms: Option<u32>,
+    /// Share of the asked budget kept, 1 when nothing was cut
+    pub scale: f64,
+}
+
+struct State {
+    scale: f64,
+    adjusted_at: Option<Instant>,
+}
+
+/// Scales search budgets to the load of a pool. Clones share the same scale.
+#[derive(Clone)]
+pub struct LoadController {
+    config: LoadConfig,
+    state: Arc<Mutex<State>>,
+}
+
+impl LoadController {
+    pub fn new(config: LoadConfig) -> Self {
+        let idle = config.idle.max(0.0);
+        Self {
+            config: LoadConfig {
+                default_depth: config.default_depth.max(1),
+                min_depth: config.min_depth.max(1),
+                idle,
+                busy: config.busy.max(idle),
+                step: config.step.clamp(0.01, 1.0),
+                ..config
+            },
+            state: Arc::new(Mutex::new(State { scale: 1.0, adjusted_at: None })),
+        }
+    }
+
+    pub fn config(&self) -> LoadConfig {
+        self.config
+    }
+
+    /// Share of their budget searches are run at now, before tier floors.
+    pub fn scale(&self) -> f64 {
+        self.state.lock().unwrap().scale
+    }
+
+    /// Engines running or waited for, per slot: 1 when every slot is busy,
+    /// more when interactive jobs are queued as well. Batch jobs waiting do
+    /// not count, as they never hold up live analysis.
+    pub fn load(stats: &QueueStats, slots: usize) -> f64 {
+        (stats.running + stats.interactive_waiting) as f64 / slots.max(1) as f64
+    }
+
+    /// The budget of a search for `tier` asking for `depth` and
+    /// `time_limit_ms`, with the pool as `stats` and `slots` show it.
+    pub fn plan(
+        &self,
+        tier: AnalysisTier,
+        depth: Option<u8>,
+        time_limit_ms: Option<u32>,
+        stats: &QueueStats,
+        slots: usize,
+    ) -> SearchBudget {
+        let scale = self.observe_at(Self::load(stats, slots), Instant::now());
+        self.budget(tier, depth, time_limit_ms, scale)
+    }
+
+    fn budget(&self, tier: AnalysisTier, depth: Option<u8>, time_limit_ms: Option<u32>, scale: f64) -> SearchBudget {
+        let scale = scale.max(self.config.floor(tier));
+        let depth = match (depth, time_limit_ms) {
+            (None, None) => Some(self.config.default_depth),
+            _ => depth,
+        };
+        SearchBudget {
+            depth: depth.map(|depth| {
+                let scaled = (f64::from(depth) * scale).round() as u8;
+                scaled.clamp(self.config.min_depth.min(depth), depth)
+            }),
+            time_limit_ms: time_limit_ms.map(|time| {
+                let scaled = (f64::from(time) * scale).round() as u32;
+                scaled.clamp(self.config.min_time_limit_ms.min(time), time)
+            }),
+            scale,
+        }
+    }
+
+    // Step the scale towards the load seen at `now`, at most once per
+    // `adjust_every`, and return it
+    fn observe_at(&self, load: f64, now: Instant) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        let due = state
+            .adjusted_at
+            .is_none_or(|at| now.saturating_duration_since(at) >= self.config.adjust_every);
+        if due {
+            let scale = if load >= self.config.busy {
+                (state.scale - self.config.step).max(0.0)
+            } else if load <= self.config.idle {
+                (state.scale + self.config.step).min(1.0)
+            } else {
+                state.scale
+            };
+            if scale != state.scale {
+                log::info!("Analysis budgets at {:.0}% with the engine pool at {:.0}% load", scale * 100.0, load * 100.0);
+                state.scale = scale;
+                state.adjusted_at = Some(now);
+            }
+        }
+        state.scale
+    }
+}
+
+impl Default for LoadController {
+    fn default() -> Self {
+        Self::new(LoadConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> LoadController {
+        LoadController::new(LoadConfig::default())
+    }
+
+    fn stats(running: usize, interactive_waiting: usize) -> QueueStats {
+        QueueStats { running, interactive_waiting, ..QueueStats::default() }
+    }
+
+    #[test]
+    fn budgets_shrink_under_load_and_grow_back_when_idle() {
+        let controller = controller();
+        let start = Instant::now();
+        let step = controller.config().adjust_every;
+
+        assert_eq!(controller.observe_at(LoadController::load(&stats(4, 2), 4), start), 0.75);
+        // A burst within one interval moves the scale once
+        assert_eq!(controller.observe_at(1.5, start + step / 2), 0.75);
+        assert_eq!(controller.observe_at(1.5, start + step), 0.5);
+        assert_eq!(controller.observe_at(1.5, start + step * 2), 0.25);
+        assert_eq!(controller.observe_at(1.5, start + step * 3), 0.0);
+        assert_eq!(controller.observe_at(1.5, start + step * 4), 0.0);
+
+        // Between idle and busy the scale holds
+        assert_eq!(controller.observe_at(0.75, start + step * 5), 0.0);
+        assert_eq!(controller.observe_at(0.25, start + step * 6), 0.25);
+        for i in 7..12 {
+            controller.observe_at(0.0, start + step * i);
+        }
+        assert_eq!(controller.scale(), 1.0);
+    }
+
+    #[test]
+    fn tiers_keep_their_floor() {
+        let controller = controller();
+        let guest = controller.budget(AnalysisTier::Guest, Some(20), Some(8000), 0.0);
+        assert_eq!(guest, SearchBudget { depth: Some(8), time_limit_ms: Some(2000), scale: 0.25 });
+
+        let player = controller.budget(AnalysisTier::Player, Some(20), None, 0.0);
+        assert_eq!(player, SearchBudget { depth: Some(10), time_limit_ms: None, scale: 0.5 });
+
+        let premium = controller.budget(AnalysisTier::Premium, Some(20), Some(8000), 0.0);
+        assert_eq!(premium, SearchBudget { depth: Some(20), time_limit_ms: Some(8000), scale: 1.0 });
+    }
+
+    #[test]
+    fn budgets_stay_within_the_request() {
+        let controller = controller();
+        assert_eq!(controller.budget(AnalysisTier::Guest, None, None, 1.0).depth, Some(18));
+        assert_eq!(controller.budget(AnalysisTier::Guest, None, None, 0.0).depth, Some(8));
+        // Shallow requests are never deepened to the minimum
+        assert_eq!(controller.budget(AnalysisTier::Guest, Some(5), None, 0.0).depth, Some(5));
+
+        let timed = controller.budget(AnalysisTier::Guest, None, Some(1000), 0.25);
+        assert_eq!(timed, SearchBudget { depth: None, time_limit_ms: Some(500), scale: 0.25 });
+    }
+}
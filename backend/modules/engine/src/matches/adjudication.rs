@@ -0,0 +1,155 @@
+use chess::PgnGameResult;
+use serde::{Deserialize, Serialize};
+
+use super::GameEnd;
+
+/// Centipawn value given to mate scores.
+pub const MATE_SCORE: i32 = 100_000;
+
+/// When to end a game before it is over by the rules. Scores are the
+/// engines' own evaluations, converted to White's point of view; moves and
+/// plies are counted from the end of the book opening.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Adjudication {
+    /// Award the game once both engines agree one side is ahead by this many
+    /// centipawns...
+    pub resign_cp: Option<i32>,
+    /// ...for this many consecutive moves each
+    pub resign_moves: u32,
+    /// Draw the game once both engines score it within this many centipawns...
+    pub draw_cp: Option<i32>,
+    /// ...for this many consecutive moves each
+    pub draw_moves: u32,
+    /// No draw adjudication before this move number
+    pub draw_from_move: u32,
+    /// Draw the game after this many plies
+    pub max_plies: Option<u32>,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Self {
+            resign_cp: Some(1000),
+            resign_moves: 3,
+            draw_cp: Some(10),
+            draw_moves: 8,
+            draw_from_move: 40,
+            max_plies: Some(400),
+        }
+    }
+}
+
+/// White's-view score of a search result, if the engine reported one.
+/// `white_to_move` is the side the engine searched for.
+pub fn white_score(evaluation: Option<f32>, mate: Option<i32>, white_to_move: bool) -> Option<i32> {
+    let score = match (mate, evaluation) {
+        (Some(moves), _) if moves > 0 => MATE_SCORE,
+        (Some(_), _) => -MATE_SCORE,
+        (None, Some(pawns)) => (pawns * 100.0).round() as i32,
+        (None, None) => return None,
+    };
+    Some(if white_to_move { score } else { -score })
+}
+
+/// Verdict on a game given the score reported with each ply played so far.
+pub fn adjudicate(rules: &Adjudication, scores: &[Option<i32>]) -> Option<(PgnGameResult, GameEnd)> {
+    // The last `moves` moves of both engines, all with a score
+    let window = |moves: u32| -> Option<Vec<i32>> {
+        let plies = moves as usize * 2;
+        if moves == 0 || scores.len() < plies {
+            return None;
+        }
+        scores[scores.len() - plies..].iter().copied().collect()
+    };
+
+    if let (Some(threshold), Some(recent)) = (rules.resign_cp, window(rules.resign_moves)) {
+        if recent.iter().all(|s| *s >= threshold) {
+            return Some((PgnGameResult::WhiteWins, GameEnd::AdjudicatedWin));
+        }
+        if recent.iter().all(|s| *s <= -threshold) {
+            return Some((PgnGameResult::BlackWins, GameEnd::AdjudicatedWin));
+        }
+    }
+
+    let past_draw_move = scores.len() >= rules.draw_from_move as usize * 2;
+    if let (Some(threshold), Some(recent)) = (rules.draw_cp, window(rules.draw_moves))
+        && past_draw_move
+        && recent.iter().all(|s| s.abs() <= threshold)
+    {
+        return Some((PgnGameResult::Draw, GameEnd::AdjudicatedDraw));
+    }
+
+    if rules.max_plies.is_some_and(|max| scores.len() >= max as usize) {
+        return Some((PgnGameResult::Draw, GameEnd::MaxPlies));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Adjudication {
+        Adjudication {
+            resign_cp: Some(500),
+            resign_moves: 2,
+            draw_cp: Some(10),
+            draw_moves: 2,
+            draw_from_move: 3,
+            max_plies: Some(20),
+        }
+    }
+
+    #[test]
+    fn scores_are_seen_from_white() {
+        assert_eq!(white_score(Some(0.35), None, true), Some(35));
+        assert_eq!(white_score(Some(0.35), None, false), Some(-35));
+        assert_eq!(white_score(None, Some(-2), true), Some(-MATE_SCORE));
+        assert_eq!(white_score(None, Some(3), false), Some(-MATE_SCORE));
+        assert_eq!(white_score(None, None, true), None);
+    }
+
+    #[test]
+    fn awards_the_game_when_both_engines_agree() {
+        let agreed = [Some(20), Some(600), Some(700), Some(650), Some(900)];
+        assert_eq!(
+            adjudicate(&rules(), &agreed),
+            Some((PgnGameResult::WhiteWins, GameEnd::AdjudicatedWin))
+        );
+
+        // The losing engine does not see it yet
+        let disputed = [Some(20), Some(600), Some(100), Some(650), Some(900)];
+        assert_eq!(adjudicate(&rules(), &disputed), None);
+    }
+
+    #[test]
+    fn draws_dead_positions_only_after_the_draw_move() {
+        let early = [Some(0), Some(5), Some(-3), Some(0)];
+        assert_eq!(adjudicate(&rules(), &early), None);
+
+        let late = [Some(40), Some(30), Some(0), Some(5), Some(-3), Some(0)];
+        assert_eq!(
+            adjudicate(&rules(), &late),
+            Some((PgnGameResult::Draw, GameEnd::AdjudicatedDraw))
+        );
+    }
+
+    #[test]
+    fn missing_scores_and_disabled_rules_never_adjudicate() {
+        let unscored = [None, Some(900), Some(900), Some(900)];
+        assert_eq!(adjudicate(&rules(), &unscored), None);
+
+        let disabled = Adjudication {
+            resign_cp: None,
+            draw_cp: None,
+            max_plies: None,
+            ..rules()
+        };
+        assert_eq!(adjudicate(&disabled, &[Some(900); 30]), None);
+        assert_eq!(
+            adjudicate(&rules(), &[Some(200); 20]),
+            Some((PgnGameResult::Draw, GameEnd::MaxPlies))
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Engine-vs-engine matches: two UCI engines play a series of games from
+//! book openings under a time control, with adjudication of decided or dead
+//! drawn games, and an Elo/SPRT summary of the results.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+pub mod adjudication;
+pub mod runner;
+pub mod sprt;
+
+pub use adjudication::{Adjudication, MATE_SCORE, adjudicate, white_score};
+pub use runner::{EngineLauncher, GamePlan, GameRecord, MatchRunner, ProcessLauncher, Side, schedule};
+pub use sprt::{MatchSummary, Sprt, SprtVerdict};
+
+#[derive(Error, Debug)]
+pub enum MatchError {
+    #[error("Invalid opening '{0}'")]
+    InvalidOpening(String),
+    #[error("Engine '{engine}' failed to start: {reason}")]
+    Launch { engine: String, reason: String },
+}
+
+/// How a match game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEnd {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoves,
+    Repetition,
+    AdjudicatedWin,
+    AdjudicatedDraw,
+    MaxPlies,
+    TimeForfeit,
+    IllegalMove,
+    /// The engine crashed, hung or sent something unreadable
+    EngineFailure,
+}
+
+impl GameEnd {
+    /// PGN `Termination` tag value.
+    pub fn pgn_termination(&self) -> &'static str {
+        match self {
+            GameEnd::AdjudicatedWin | GameEnd::AdjudicatedDraw | GameEnd::MaxPlies => "adjudication",
+            GameEnd::TimeForfeit => "time forfeit",
+            GameEnd::IllegalMove => "rules infraction",
+            GameEnd::EngineFailure => "abandoned",
+            _ => "normal",
+        }
+    }
+}
+
+impl From<chess::Termination> for GameEnd {
+    fn from(value: chess::Termination) -> Self {
+        match value {
+            chess::Termination::Checkmate => GameEnd::Checkmate,
+            chess::Termination::Stalemate => GameEnd::Stalemate,
+            chess::Termination::InsufficientMaterial => GameEnd::InsufficientMaterial,
+            chess::Termination::FiftyMoves => GameEnd::FiftyMoves,
+            chess::Termination::Repetition => GameEnd::Repetition,
+        }
+    }
+}
+
+/// An engine binary and the UCI options it plays with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineSpec {
+    /// Name shown in PGN headers and results
+    pub name: String,
+    pub path: String,
+    /// Sent with `setoption` before the first game, e.g. `Hash` or `Skill Level`
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+/// Sudden death with increment, identical for both engines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchClock {
+    pub base_ms: u64,
+    pub increment_ms: u64,
+}
+
+impl MatchClock {
+    /// PGN `TimeControl` tag, e.g. `10+0.1`.
+    pub fn to_pgn_tag(&self) -> String {
+        let seconds = |ms: u64| (ms as f64 / 1000.0).to_string();
+        format!("{}+{}", seconds(self.base_ms), seconds(self.increment_ms))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchConfig {
+    /// Event name in PGN headers
+    pub name: String,
+    pub engine_a: EngineSpec,
+    pub engine_b: EngineSpec,
+    pub games: u32,
+    pub clock: MatchClock,
+    /// Opening lines in SAN; each is played twice with colors reversed.
+    /// Games start from the initial position when empty.
+    #[serde(default)]
+    pub openings: Vec<String>,
+    #[serde(default)]
+    pub adjudication: Adjudication,
+    /// Stop early once the test accepts either hypothesis
+    #[serde(default)]
+    pub sprt: Option<Sprt>,
+}
@@ -0,0 +1,371 @@
+use async_trait::async_trait;
+use chess::{AnnotationTree, PgnGameResult, PgnHeaders, Referee, write_pgn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::time::Instant;
+
+use super::adjudication::{adjudicate, white_score};
+use super::{EngineSpec, GameEnd, MatchConfig, MatchError};
+use crate::process::ProcessEngine;
+use crate::{ClockParams, Engine, EngineError, GoParams};
+
+/// One of the two engines of a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    A,
+    B,
+}
+
+/// A game to play: its opening and which engine has White.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamePlan {
+    /// 1-based
+    pub round: u32,
+    pub opening: Option<String>,
+    pub a_is_white: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub plan: GamePlan,
+    /// The whole game in SAN, opening included
+    pub moves: Vec<String>,
+    pub result: PgnGameResult,
+    pub end: GameEnd,
+    pub pgn: String,
+}
+
+impl GameRecord {
+    /// The engine that won, `None` for a draw.
+    pub fn winner(&self) -> Option<Side> {
+        let (white, black) = if self.plan.a_is_white { (Side::A, Side::B) } else { (Side::B, Side::A) };
+        match self.result {
+            PgnGameResult::WhiteWins => Some(white),
+            PgnGameResult::BlackWins => Some(black),
+            _ => None,
+        }
+    }
+}
+
+/// Starts engines for a match; lets tests play without engine binaries.
+#[async_trait]
+pub trait EngineLauncher: Send + Sync {
+    async fn launch(&self, spec: &EngineSpec) -> Result<Box<dyn Engine>, EngineError>;
+}
+
+/// Runs each engine as a UCI child process.
+pub struct ProcessLauncher;
+
+#[async_trait]
+impl EngineLauncher for ProcessLauncher {
+    async fn launch(&self, spec: &EngineSpec) -> Result<Box<dyn Engine>, EngineError> {
+        let mut engine = ProcessEngine::new(&spec.path).await?;
+        for (name, value) in &spec.options {
+            engine.set_option(name, value).await?;
+        }
+        engine.is_ready().await?;
+        Ok(Box::new(engine))
+    }
+}
+
+/// Games of a match: every opening twice with colors reversed, cycling
+/// through the book until `games` are scheduled.
+pub fn schedule(config: &MatchConfig) -> Vec<GamePlan> {
+    (0..config.games)
+        .map(|index| GamePlan {
+            round: index + 1,
+            opening: match config.openings.len() {
+                0 => None,
+                len => Some(config.openings[(index as usize / 2) % len].clone()),
+            },
+            a_is_white: index % 2 == 0,
+        })
+        .collect()
+}
+
+pub struct MatchRunner<L> {
+    config: MatchConfig,
+    launcher: L,
+}
+
+impl<L: EngineLauncher> MatchRunner<L> {
+    pub fn new(config: MatchConfig, launcher: L) -> Self {
+        Self { config, launcher }
+    }
+
+    pub fn config(&self) -> &MatchConfig {
+        &self.config
+    }
+
+    /// Play one game with freshly started engines. Engine failures during the
+    /// game lose it; only engines failing to start abort the match.
+    pub async fn play(&self, plan: &GamePlan) -> Result<GameRecord, MatchError> {
+        let opening = plan.opening.as_deref().unwrap_or_default();
+        let mut referee =
+            Referee::from_opening(opening).map_err(|_| MatchError::InvalidOpening(opening.to_string()))?;
+
+        let (white_spec, black_spec) = if plan.a_is_white {
+            (&self.config.engine_a, &self.config.engine_b)
+        } else {
+            (&self.config.engine_b, &self.config.engine_a)
+        };
+        let mut white = self.start(white_spec).await?;
+        let mut black = match self.start(black_spec).await {
+            Ok(engine) => engine,
+            Err(err) => {
+                let _ = white.quit().await;
+                return Err(err);
+            }
+        };
+
+        let clock = self.config.clock;
+        let mut remaining = [clock.base_ms, clock.base_ms];
+        let mut scores = Vec::new();
+
+        let (result, end) = loop {
+            if let Some((result, termination)) = referee.outcome() {
+                break (result, termination.into());
+            }
+            if let Some(verdict) = adjudicate(&self.config.adjudication, &scores) {
+                break verdict;
+            }
+
+            let white_to_move = referee.white_to_move();
+            let forfeit = if white_to_move { PgnGameResult::BlackWins } else { PgnGameResult::WhiteWins };
+            let (engine, side) = if white_to_move { (&mut white, 0) } else { (&mut black, 1) };
+
+            let params = GoParams {
+                depth: None,
+                time_limit_ms: None,
+                search_moves: None,
+                clock: Some(ClockParams {
+                    white_ms: remaining[0],
+                    black_ms: remaining[1],
+                    white_increment_ms: clock.increment_ms,
+                    black_increment_ms: clock.increment_ms,
+                }),
+            };
+            let started = Instant::now();
+            let searched = match engine.set_position(&referee.fen()).await {
+                Ok(()) => engine.go(params).await,
+                Err(err) => Err(err),
+            };
+            let elapsed = started.elapsed().as_millis() as u64;
+
+            let search = match searched {
+                Ok(search) => search,
+                Err(EngineError::Timeout) => break (forfeit, GameEnd::TimeForfeit),
+                Err(_) => break (forfeit, GameEnd::EngineFailure),
+            };
+            if elapsed > remaining[side] {
+                break (forfeit, GameEnd::TimeForfeit);
+            }
+            remaining[side] = remaining[side] - elapsed + clock.increment_ms;
+
+            if referee.play_uci(&search.best_move).is_err() {
+                break (forfeit, GameEnd::IllegalMove);
+            }
+            scores.push(white_score(search.evaluation, search.mate, white_to_move));
+        };
+
+        let _ = white.quit().await;
+        let _ = black.quit().await;
+
+        let pgn = self.pgn(plan, white_spec, black_spec, &referee, &result, end);
+        Ok(GameRecord {
+            plan: plan.clone(),
+            moves: referee.moves().to_vec(),
+            result,
+            end,
+            pgn,
+        })
+    }
+
+    async fn start(&self, spec: &EngineSpec) -> Result<Box<dyn Engine>, MatchError> {
+        let launch_error = |err: EngineError| MatchError::Launch {
+            engine: spec.name.clone(),
+            reason: err.to_string(),
+        };
+        let mut engine = self.launcher.launch(spec).await.map_err(launch_error)?;
+        engine.new_game().await.map_err(launch_error)?;
+        Ok(engine)
+    }
+
+    fn pgn(
+        &self,
+        plan: &GamePlan,
+        white: &EngineSpec,
+        black: &EngineSpec,
+        referee: &Referee,
+        result: &PgnGameResult,
+        end: GameEnd,
+    ) -> String {
+        let mut other = HashMap::new();
+        other.insert("TimeControl".to_string(), self.config.clock.to_pgn_tag());
+        other.insert("Termination".to_string(), end.pgn_termination().to_string());
+
+        let headers = PgnHeaders {
+            event: Some(self.config.name.clone()),
+            site: Some("StarkMate".to_string()),
+            date: None,
+            round: Some(plan.round.to_string()),
+            white: white.name.clone(),
+            black: black.name.clone(),
+            result: result.clone(),
+            other,
+        };
+        write_pgn(&headers, referee.moves(), &AnnotationTree::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EngineResult;
+    use crate::matches::{Adjudication, MatchClock};
+    use std::collections::{BTreeMap, VecDeque};
+
+    /// Plays the scripted moves in order, whatever the position.
+    struct ScriptedEngine {
+        moves: VecDeque<(&'static str, f32)>,
+    }
+
+    #[async_trait]
+    impl Engine for ScriptedEngine {
+        async fn go(&mut self, _params: GoParams) -> Result<EngineResult, EngineError> {
+            let (best_move, evaluation) = self.moves.pop_front().ok_or(EngineError::NotRunning)?;
+            Ok(EngineResult {
+                best_move: best_move.to_string(),
+                evaluation: Some(evaluation),
+                mate: None,
+                depth: Some(1),
+                principal_variation: Vec::new(),
+                lines: Vec::new(),
+                nodes: None,
+            })
+        }
+        async fn stop(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn set_position(&mut self, _fen: &str) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn is_ready(&mut self) -> Result<bool, EngineError> {
+            Ok(true)
+        }
+        async fn quit(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn set_option(&mut self, _name: &str, _value: &str) -> Result<(), EngineError> {
+            Ok(())
+        }
+        async fn new_game(&mut self) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    struct ScriptedLauncher {
+        scripts: HashMap<String, Vec<(&'static str, f32)>>,
+    }
+
+    #[async_trait]
+    impl EngineLauncher for ScriptedLauncher {
+        async fn launch(&self, spec: &EngineSpec) -> Result<Box<dyn Engine>, EngineError> {
+            let moves = self.scripts.get(&spec.name).ok_or(EngineError::NotRunning)?;
+            Ok(Box::new(ScriptedEngine {
+                moves: moves.iter().copied().collect(),
+            }))
+        }
+    }
+
+    fn spec(name: &str) -> EngineSpec {
+        EngineSpec {
+            name: name.to_string(),
+            path: format!("/usr/bin/{}", name),
+            options: BTreeMap::new(),
+        }
+    }
+
+    fn config(openings: Vec<String>, games: u32) -> MatchConfig {
+        MatchConfig {
+            name: "Config check".to_string(),
+            engine_a: spec("alpha"),
+            engine_b: spec("beta"),
+            games,
+            clock: MatchClock {
+                base_ms: 10_000,
+                increment_ms: 100,
+            },
+            openings,
+            adjudication: Adjudication::default(),
+            sprt: None,
+        }
+    }
+
+    fn runner(alpha: Vec<(&'static str, f32)>, beta: Vec<(&'static str, f32)>) -> MatchRunner<ScriptedLauncher> {
+        let scripts = HashMap::from([("alpha".to_string(), alpha), ("beta".to_string(), beta)]);
+        MatchRunner::new(config(Vec::new(), 2), ScriptedLauncher { scripts })
+    }
+
+    #[test]
+    fn schedules_each_opening_with_both_colors() {
+        let plans = schedule(&config(vec!["1. e4".to_string(), "1. d4".to_string()], 5));
+        let summary: Vec<(u32, &str, bool)> = plans
+            .iter()
+            .map(|p| (p.round, p.opening.as_deref().unwrap(), p.a_is_white))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "1. e4", true),
+                (2, "1. e4", false),
+                (3, "1. d4", true),
+                (4, "1. d4", false),
+                (5, "1. e4", true),
+            ]
+        );
+        assert!(schedule(&config(Vec::new(), 1))[0].opening.is_none());
+    }
+
+    #[tokio::test]
+    async fn plays_until_mate() {
+        let runner = runner(
+            vec![("f2f3", -0.5), ("g2g4", -3.0)],
+            vec![("e7e5", 0.2), ("d8h4", 0.0)],
+        );
+        let plan = GamePlan {
+            round: 1,
+            opening: None,
+            a_is_white: true,
+        };
+
+        let game = runner.play(&plan).await.unwrap();
+        assert_eq!(game.moves, vec!["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(game.result, PgnGameResult::BlackWins);
+        assert_eq!(game.end, GameEnd::Checkmate);
+        assert_eq!(game.winner(), Some(Side::B));
+        assert!(game.pgn.contains("[White \"alpha\"]"));
+        assert!(game.pgn.contains("[TimeControl \"10+0.1\"]"));
+    }
+
+    #[tokio::test]
+    async fn illegal_moves_and_failures_lose_the_game() {
+        let runner = runner(vec![("e2e5", 0.0)], vec![]);
+
+        // Beta has White and nothing to play
+        let plan = GamePlan {
+            round: 2,
+            opening: None,
+            a_is_white: false,
+        };
+        let game = runner.play(&plan).await.unwrap();
+        assert_eq!(game.end, GameEnd::EngineFailure);
+        assert_eq!(game.winner(), Some(Side::A));
+
+        let plan = GamePlan { a_is_white: true, ..plan };
+        let game = runner.play(&plan).await.unwrap();
+        assert_eq!(game.end, GameEnd::IllegalMove);
+        assert_eq!(game.result, PgnGameResult::BlackWins);
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+/// Sequential probability ratio test of H0: elo = `elo0` against
+/// H1: elo = `elo1`, using the trinomial (win/draw/loss) GSPRT approximation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probability of accepting H1 when H0 holds
+    pub alpha: f64,
+    /// Probability of accepting H0 when H1 holds
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SprtVerdict {
+    Continue,
+    /// The tested engine is no stronger than `elo0`
+    AcceptH0,
+    /// The tested engine is at least `elo1` stronger
+    AcceptH1,
+}
+
+/// Results from the first engine's point of view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchSummary {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Points per game, 0.0 to 1.0
+    pub score: f64,
+    /// Elo difference; unknown while every game is won or every game lost
+    pub elo: Option<f64>,
+    /// Half width of the 95% confidence interval of `elo`
+    pub elo_margin: Option<f64>,
+    pub llr: Option<f64>,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+    pub verdict: Option<SprtVerdict>,
+}
+
+impl Sprt {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.elo0 >= self.elo1 {
+            return Err("elo0 must be lower than elo1".to_string());
+        }
+        for bound in [self.alpha, self.beta] {
+            if !(bound > 0.0 && bound < 0.5) {
+                return Err("alpha and beta must be between 0 and 0.5".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Log-likelihood ratio below which H0 is accepted.
+    pub fn lower_bound(&self) -> f64 {
+        (self.beta / (1.0 - self.alpha)).ln()
+    }
+
+    /// Log-likelihood ratio above which H1 is accepted.
+    pub fn upper_bound(&self) -> f64 {
+        ((1.0 - self.beta) / self.alpha).ln()
+    }
+
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        let Some((score, variance)) = score_and_variance(wins, draws, losses) else {
+            return 0.0;
+        };
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let games = (wins + draws + losses) as f64;
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        games * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, llr: f64) -> SprtVerdict {
+        if llr >= self.upper_bound() {
+            SprtVerdict::AcceptH1
+        } else if llr <= self.lower_bound() {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+impl MatchSummary {
+    pub fn new(wins: u32, draws: u32, losses: u32, sprt: Option<&Sprt>) -> Self {
+        let games = wins + draws + losses;
+        let (score, elo, elo_margin) = match score_and_variance(wins, draws, losses) {
+            Some((score, variance)) => {
+                // 95% interval of the score, mapped to Elo
+                let deviation = 1.96 * (variance / games as f64).sqrt();
+                let margin = match (elo_of(score - deviation), elo_of(score + deviation)) {
+                    (Some(low), Some(high)) => Some((high - low) / 2.0),
+                    _ => None,
+                };
+                (score, elo_of(score), margin)
+            }
+            None => (0.0, None, None),
+        };
+        let llr = sprt.map(|test| test.llr(wins, draws, losses));
+
+        Self {
+            wins,
+            draws,
+            losses,
+            score,
+            elo,
+            elo_margin,
+            llr,
+            lower_bound: sprt.map(Sprt::lower_bound),
+            upper_bound: sprt.map(Sprt::upper_bound),
+            verdict: sprt.zip(llr).map(|(test, llr)| test.verdict(llr)),
+        }
+    }
+
+    /// True once the SPRT has accepted either hypothesis.
+    pub fn is_conclusive(&self) -> bool {
+        matches!(self.verdict, Some(SprtVerdict::AcceptH0 | SprtVerdict::AcceptH1))
+    }
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn elo_of(score: f64) -> Option<f64> {
+    (score > 0.0 && score < 1.0).then(|| -400.0 * (1.0 / score - 1.0).log10())
+}
+
+/// Mean points per game and their variance per game.
+fn score_and_variance(wins: u32, draws: u32, losses: u32) -> Option<(f64, f64)> {
+    let games = (wins + draws + losses) as f64;
+    if games == 0.0 {
+        return None;
+    }
+    let (w, d, l) = (wins as f64 / games, draws as f64 / games, losses as f64 / games);
+    let score = w + d / 2.0;
+    let variance = w * (1.0 - score).powi(2) + d * (0.5 - score).powi(2) + l * score.powi(2);
+    Some((score, variance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprt() -> Sprt {
+        Sprt {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    #[test]
+    fn elo_follows_the_score() {
+        let even = MatchSummary::new(10, 20, 10, None);
+        assert_eq!(even.score, 0.5);
+        assert!(even.elo.unwrap().abs() < 1e-9);
+
+        // 75% is about +191 Elo
+        let ahead = MatchSummary::new(60, 30, 10, None);
+        assert!((ahead.elo.unwrap() - 190.8).abs() < 0.1);
+        assert!(ahead.elo_margin.unwrap() > 0.0);
+
+        assert_eq!(MatchSummary::new(5, 0, 0, None).elo, None);
+        assert_eq!(MatchSummary::new(0, 0, 0, None).score, 0.0);
+    }
+
+    #[test]
+    fn bounds_follow_the_error_rates() {
+        let test = sprt();
+        assert!((test.lower_bound() + 2.944).abs() < 1e-3);
+        assert!((test.upper_bound() - 2.944).abs() < 1e-3);
+        assert!(test.validate().is_ok());
+        assert!(Sprt { elo1: -5.0, ..sprt() }.validate().is_err());
+        assert!(Sprt { alpha: 0.0, ..sprt() }.validate().is_err());
+    }
+
+    #[test]
+    fn clear_results_end_the_test() {
+        let stronger = MatchSummary::new(600, 300, 300, Some(&sprt()));
+        assert_eq!(stronger.verdict, Some(SprtVerdict::AcceptH1));
+        assert!(stronger.is_conclusive());
+
+        let weaker = MatchSummary::new(300, 300, 600, Some(&sprt()));
+        assert_eq!(weaker.verdict, Some(SprtVerdict::AcceptH0));
+
+        let early = MatchSummary::new(3, 4, 2, Some(&sprt()));
+        assert_eq!(early.verdict, Some(SprtVerdict::Continue));
+        assert!(!early.is_conclusive());
+    }
+}
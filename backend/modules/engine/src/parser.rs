@@ -1,4 +1,4 @@
-use crate::{EngineResult};
+use crate::{EngineOption, EngineResult};
 
 pub fn parse_uci_line(line: &str) -> Option<UciMessage> {
     let parts: Vec<&str> = line.split_whitespace().collect();
@@ -18,6 +18,7 @@ pub fn parse_uci_line(line: &str) -> Option<UciMessage> {
                 None
             }
         }
+        "option" => parse_option(&parts[1..]).map(UciMessage::Option),
         "uciok" => Some(UciMessage::UciOk),
         "readyok" => Some(UciMessage::ReadyOk),
         "bestmove" => {
@@ -37,6 +38,8 @@ pub fn parse_uci_line(line: &str) -> Option<UciMessage> {
             let mut depth = None;
             let mut score_cp = None;
             let mut score_mate = None;
+            let mut multipv = None;
+            let mut nodes = None;
             let mut pv = Vec::new();
             
             let mut i = 1;
@@ -48,6 +51,14 @@ pub fn parse_uci_line(line: &str) -> Option<UciMessage> {
                             i += 2;
                         } else { i += 1; }
                     }
+                    "multipv" if i + 1 < parts.len() => {
+                        multipv = parts[i + 1].parse::<u8>().ok();
+                        i += 2;
+                    }
+                    "nodes" if i + 1 < parts.len() => {
+                        nodes = parts[i + 1].parse::<u64>().ok();
+                        i += 2;
+                    }
                     "score" => {
                         if i + 2 < parts.len() {
                             match parts[i + 1] {
@@ -73,20 +84,63 @@ pub fn parse_uci_line(line: &str) -> Option<UciMessage> {
                     _ => { i += 1; }
                 }
             }
-            Some(UciMessage::Info { depth, score_cp, score_mate, pv })
+            Some(UciMessage::Info { depth, score_cp, score_mate, multipv, nodes, pv })
         }
         _ => Some(UciMessage::Unknown(line.to_string())),
     }
 }
 
+/// `option name <name> type <kind> [default <value>] [min <n>] [max <n>] [var <value>]*`,
+/// whose name and values may hold spaces.
+fn parse_option(parts: &[&str]) -> Option<EngineOption> {
+    const KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+
+    let mut option = EngineOption {
+        name: String::new(),
+        kind: String::new(),
+        default: None,
+        min: None,
+        max: None,
+        vars: Vec::new(),
+    };
+    let mut i = 0;
+    while i < parts.len() {
+        let keyword = parts[i];
+        let end = (i + 1..parts.len()).find(|&j| KEYWORDS.contains(&parts[j])).unwrap_or(parts.len());
+        let value = parts[i + 1..end].join(" ");
+        match keyword {
+            "name" => option.name = value,
+            "type" => option.kind = value,
+            "default" => option.default = Some(value),
+            "min" => option.min = value.parse().ok(),
+            "max" => option.max = value.parse().ok(),
+            "var" => option.vars.push(value),
+            _ => {}
+        }
+        i = end;
+    }
+
+    (!option.name.is_empty() && !option.kind.is_empty()).then_some(option)
+}
+
 #[derive(Debug, Clone)]
 pub enum UciMessage {
     IdName(String),
     IdAuthor(String),
+    Option(EngineOption),
     UciOk,
     ReadyOk,
     BestMove { best_move: String, ponder: Option<String> },
-    Info { depth: Option<u8>, score_cp: Option<i32>, score_mate: Option<i32>, pv: Vec<String> },
+    /// `multipv` numbers the line from 1 when the engine reports several;
+    /// `nodes` counts every node searched so far
+    Info {
+        depth: Option<u8>,
+        score_cp: Option<i32>,
+        score_mate: Option<i32>,
+        multipv: Option<u8>,
+        nodes: Option<u64>,
+        pv: Vec<String>,
+    },
     Unknown(String),
 }
 
@@ -96,8 +150,11 @@ impl From<UciMessage> for Option<EngineResult> {
             UciMessage::BestMove { best_move, .. } => Some(EngineResult {
                 best_move,
                 evaluation: None,
+                mate: None,
                 depth: None,
                 principal_variation: Vec::new(),
+                lines: Vec::new(),
+                nodes: None,
             }),
             _ => None,
         }
@@ -134,7 +191,7 @@ mod tests {
     #[test]
     fn test_parse_info() {
         let msg = parse_uci_line("info depth 12 score cp 35 pv e2e4 e7e5 Ng1f3").unwrap();
-        if let UciMessage::Info { depth, score_cp, score_mate, pv } = msg {
+        if let UciMessage::Info { depth, score_cp, score_mate, pv, .. } = msg {
             assert_eq!(depth, Some(12));
             assert_eq!(score_cp, Some(35));
             assert_eq!(score_mate, None);
@@ -147,7 +204,7 @@ mod tests {
     #[test]
     fn test_parse_info_mate() {
         let msg = parse_uci_line("info depth 12 score mate 3 pv e2e4 e7e5 Ng1f3").unwrap();
-        if let UciMessage::Info { depth, score_cp, score_mate, pv } = msg {
+        if let UciMessage::Info { depth, score_cp, score_mate, pv, .. } = msg {
             assert_eq!(depth, Some(12));
             assert_eq!(score_cp, None);
             assert_eq!(score_mate, Some(3));
@@ -157,6 +214,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_info_multipv() {
+        let msg = parse_uci_line("info depth 8 multipv 2 score cp -12 pv d2d4 d7d5").unwrap();
+        if let UciMessage::Info { multipv, score_cp, pv, .. } = msg {
+            assert_eq!(multipv, Some(2));
+            assert_eq!(score_cp, Some(-12));
+            assert_eq!(pv, vec!["d2d4", "d7d5"]);
+        } else {
+            panic!("Expected Info");
+        }
+    }
+
+    #[test]
+    fn test_parse_info_nodes() {
+        let msg = parse_uci_line("info depth 20 seldepth 27 nodes 1843210 nps 1502000 time 1227 pv e2e4").unwrap();
+        if let UciMessage::Info { depth, nodes, pv, .. } = msg {
+            assert_eq!(depth, Some(20));
+            assert_eq!(nodes, Some(1_843_210));
+            assert_eq!(pv, vec!["e2e4"]);
+        } else {
+            panic!("Expected Info");
+        }
+    }
+
+    #[test]
+    fn test_parse_option() {
+        let msg = parse_uci_line("option name Skill Level type spin default 20 min 0 max 20").unwrap();
+        if let UciMessage::Option(option) = msg {
+            assert_eq!(option.name, "Skill Level");
+            assert_eq!(option.kind, "spin");
+            assert_eq!(option.default.as_deref(), Some("20"));
+            assert_eq!((option.min, option.max), (Some(0), Some(20)));
+        } else {
+            panic!("Expected Option");
+        }
+
+        let msg = parse_uci_line("option name Analysis Contempt type combo default Both var Off var White var Both").unwrap();
+        if let UciMessage::Option(option) = msg {
+            assert_eq!(option.vars, vec!["Off", "White", "Both"]);
+        } else {
+            panic!("Expected Option");
+        }
+
+        // An empty default is kept as one
+        let msg = parse_uci_line("option name EvalFile type string default").unwrap();
+        assert!(matches!(msg, UciMessage::Option(EngineOption { default: Some(ref d), .. }) if d.is_empty()));
+        assert!(parse_uci_line("option type check").is_none());
+    }
+
     #[test]
     fn test_parse_id() {
         let msg = parse_uci_line("id name Stockfish 16").unwrap();
@@ -1,57 +1,111 @@
-use tokio::process::{Command, Child};
+use tokio::process::{Command, Child, ChildStderr};
 use tokio::io::{BufReader, AsyncBufReadExt, AsyncWriteExt};
 use std::process::Stdio;
 use async_trait::async_trait;
-use crate::{Engine, EngineError, EngineResult, GoParams};
+use crate::{Engine, EngineError, EngineInfo, EngineResult, GoParams, PvLine};
 use crate::parser::{parse_uci_line, UciMessage};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+
+/// Lines of stderr kept per engine process
+pub const STDERR_LINES: usize = 64;
+/// Longest stderr line kept, in bytes
+const STDERR_LINE_BYTES: usize = 512;
+/// Lines of stderr quoted in an error
+const STDERR_CONTEXT_LINES: usize = 10;
+/// How long a search may go without a line from the engine before the
+/// engine is flagged as stalled
+pub const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct ProcessEngine {
+    path: String,
     child: Child,
     stdin: tokio::process::ChildStdin,
     stdout_reader: Arc<Mutex<BufReader<tokio::process::ChildStdout>>>,
+    info: EngineInfo,
+    stderr: Arc<StderrLog>,
+    /// Turns true once the engine closed stderr
+    stderr_closed: watch::Receiver<bool>,
+    watchdog: Arc<Watchdog>,
 }
 
 impl ProcessEngine {
     pub async fn new(path: &str) -> Result<Self, EngineError> {
+        Self::with_stall_timeout(path, DEFAULT_STALL_TIMEOUT).await
+    }
+
+    /// An engine flagged as stalled once a search goes `stall_timeout`
+    /// without a line from it.
+    pub async fn with_stall_timeout(path: &str, stall_timeout: Duration) -> Result<Self, EngineError> {
         let mut child = Command::new(path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let stdin = child.stdin.take().ok_or(EngineError::NotRunning)?;
         let stdout = child.stdout.take().ok_or(EngineError::NotRunning)?;
+        let stderr_pipe = child.stderr.take().ok_or(EngineError::NotRunning)?;
         let stdout_reader = Arc::new(Mutex::new(BufReader::new(stdout)));
 
+        let stderr = Arc::new(StderrLog::default());
+        let stderr_closed = capture_stderr(stderr_pipe, stderr.clone());
+        let watchdog = Arc::new(Watchdog::new(stall_timeout));
+        watch_searches(Arc::downgrade(&watchdog), stderr.clone(), path.to_string());
+
         let mut engine = Self {
+            path: path.to_string(),
             child,
             stdin,
             stdout_reader,
+            info: EngineInfo::default(),
+            stderr,
+            stderr_closed,
+            watchdog,
         };
 
         // Initialize UCI
         engine.send_command("uci").await?;
         
-        // Wait for uciok with 5-second timeout
+        // Wait for uciok with 5-second timeout, noting what the engine
+        // says about itself meanwhile
+        let mut info = EngineInfo::default();
         tokio::time::timeout(std::time::Duration::from_secs(5), async {
             loop {
                 let line = engine.read_line().await?;
-                if let Some(UciMessage::UciOk) = parse_uci_line(&line) {
-                    break;
+                match parse_uci_line(&line) {
+                    Some(UciMessage::IdName(name)) => info.name = Some(name),
+                    Some(UciMessage::IdAuthor(author)) => info.author = Some(author),
+                    Some(UciMessage::Option(option)) => info.options.push(option),
+                    Some(UciMessage::UciOk) => break,
+                    _ => {}
                 }
             }
             Ok::<(), EngineError>(())
         }).await.map_err(|_| EngineError::Timeout)??;
+        engine.info = info;
 
         Ok(engine)
     }
 
+    /// What the engine last wrote to stderr, oldest line first.
+    pub fn recent_stderr(&self) -> Vec<String> {
+        self.stderr.recent()
+    }
+
     async fn send_command(&mut self, cmd: &str) -> Result<(), EngineError> {
-        self.stdin.write_all(format!("{}\n", cmd).as_bytes()).await?;
-        self.stdin.flush().await?;
-        Ok(())
+        let written = match self.stdin.write_all(format!("{}\n", cmd).as_bytes()).await {
+            Ok(()) => self.stdin.flush().await,
+            Err(e) => Err(e),
+        };
+        match written {
+            // The engine is gone, which says more than the pipe does
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Err(self.exited().await),
+            written => Ok(written?),
+        }
     }
 
     async fn read_line(&self) -> Result<String, EngineError> {
@@ -59,15 +113,31 @@ impl ProcessEngine {
         let mut line = String::new();
         let bytes_read = reader.read_line(&mut line).await?;
         if bytes_read == 0 {
-            return Err(EngineError::NotRunning);
+            return Err(self.exited().await);
         }
+        self.watchdog.output();
         Ok(line.trim().to_string())
     }
-}
 
-#[async_trait]
-impl Engine for ProcessEngine {
-    async fn go(&mut self, params: GoParams) -> Result<EngineResult, EngineError> {
+    /// The error for an engine that closed stdout, with what it wrote to
+    /// stderr on the way out.
+    async fn exited(&self) -> EngineError {
+        let mut closed = self.stderr_closed.clone();
+        let _ = tokio::time::timeout(Duration::from_millis(250), closed.wait_for(|closed| *closed)).await;
+        EngineError::Unknown(format!("Engine {} exited{}", self.path, stderr_context(&self.stderr.recent())))
+    }
+
+    fn flag_stalled(&self) {
+        if !self.watchdog.stalled.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "Engine {} stopped answering{}",
+                self.path,
+                stderr_context(&self.stderr.recent())
+            );
+        }
+    }
+
+    async fn search(&mut self, params: GoParams) -> Result<EngineResult, EngineError> {
         let mut cmd = "go".to_string();
         if let Some(depth) = params.depth {
             cmd.push_str(&format!(" depth {}", depth));
@@ -75,11 +145,25 @@ impl Engine for ProcessEngine {
         if let Some(time) = params.time_limit_ms {
             cmd.push_str(&format!(" movetime {}", time));
         }
+        if let Some(clock) = params.clock {
+            cmd.push_str(&format!(
+                " wtime {} btime {} winc {} binc {}",
+                clock.white_ms, clock.black_ms, clock.white_increment_ms, clock.black_increment_ms
+            ));
+        }
         
         self.send_command(&cmd).await?;
 
         let mut last_info = None;
-        let timeout_duration = params.time_limit_ms.map(|t| std::time::Duration::from_millis(t as u64 + 1000)).unwrap_or(std::time::Duration::from_secs(30));
+        let mut nodes = None;
+        // Latest report of each line of a MultiPV search
+        let mut lines: BTreeMap<u8, PvLine> = BTreeMap::new();
+        let timeout_duration = match (params.time_limit_ms, params.clock) {
+            (Some(t), _) => std::time::Duration::from_millis(t as u64 + 1000),
+            // The engine may think for at most the time left on its own clock
+            (None, Some(clock)) => std::time::Duration::from_millis(clock.white_ms.max(clock.black_ms) + 1000),
+            (None, None) => std::time::Duration::from_secs(30),
+        };
 
         let result = tokio::time::timeout(timeout_duration, async {
             loop {
@@ -89,18 +173,37 @@ impl Engine for ProcessEngine {
                         let mut result = EngineResult {
                             best_move,
                             evaluation: None,
+                            mate: None,
                             depth: None,
                             principal_variation: Vec::new(),
+                            lines: Vec::new(),
+                            nodes,
                         };
-                        if let Some(UciMessage::Info { depth, score_cp, score_mate: _, pv }) = last_info.clone() {
+                        if let Some(UciMessage::Info { depth, score_cp, score_mate, pv, .. }) = last_info.clone() {
                             result.depth = depth;
                             result.evaluation = score_cp.map(|cp| cp as f32 / 100.0);
+                            result.mate = score_mate;
                             result.principal_variation = pv;
                         }
+                        if lines.len() > 1 {
+                            result.lines = std::mem::take(&mut lines).into_values().collect();
+                        }
                         return Ok(result);
                     }
-                    Some(UciMessage::Info { depth, score_cp, score_mate, pv }) => {
-                        last_info = Some(UciMessage::Info { depth, score_cp, score_mate, pv });
+                    Some(UciMessage::Info { depth, score_cp, score_mate, multipv, nodes: searched, pv }) => {
+                        // Engines often report the final count on a line of its own
+                        nodes = searched.or(nodes);
+                        if !pv.is_empty() {
+                            lines.insert(multipv.unwrap_or(1), PvLine {
+                                moves: pv.clone(),
+                                evaluation: score_cp.map(|cp| cp as f32 / 100.0),
+                                mate: score_mate,
+                            });
+                        }
+                        // The best line is the one reported as the result
+                        if multipv.unwrap_or(1) == 1 {
+                            last_info = Some(UciMessage::Info { depth, score_cp, score_mate, multipv, nodes: searched, pv });
+                        }
                     }
                     _ => {}
                 }
@@ -111,33 +214,56 @@ impl Engine for ProcessEngine {
             Ok(res) => res,
             Err(_) => {
                 let _ = self.send_command("stop").await;
-                // Drain lines until BestMove
-                loop {
-                    let line = self.read_line().await?;
-                    match parse_uci_line(&line) {
-                        Some(UciMessage::BestMove { best_move, .. }) => {
-                            let mut result = EngineResult {
-                                best_move,
-                                evaluation: None,
-                                depth: None,
-                                principal_variation: Vec::new(),
-                            };
-                            if let Some(UciMessage::Info { depth, score_cp, score_mate: _, pv }) = last_info {
-                                result.depth = depth;
-                                result.evaluation = score_cp.map(|cp| cp as f32 / 100.0);
-                                result.principal_variation = pv;
+                // Drain lines until BestMove, unless the engine stopped
+                // answering altogether
+                let drain = async {
+                    loop {
+                        let line = self.read_line().await?;
+                        match parse_uci_line(&line) {
+                            Some(UciMessage::BestMove { best_move, .. }) => {
+                                let mut result = EngineResult {
+                                    best_move,
+                                    evaluation: None,
+                                    mate: None,
+                                    depth: None,
+                                    principal_variation: Vec::new(),
+                                    lines: Vec::new(),
+                                    nodes,
+                                };
+                                if let Some(UciMessage::Info { depth, score_cp, pv, .. }) = last_info {
+                                    result.depth = depth;
+                                    result.evaluation = score_cp.map(|cp| cp as f32 / 100.0);
+                                    result.principal_variation = pv;
+                                }
+                                return Err(EngineError::Timeout);
                             }
-                            return Err(EngineError::Timeout);
-                        }
-                        Some(UciMessage::Info { depth, score_cp, score_mate, pv }) => {
-                            last_info = Some(UciMessage::Info { depth, score_cp, score_mate, pv });
+                            Some(UciMessage::Info { depth, score_cp, score_mate, multipv, nodes, pv })
+                                if multipv.unwrap_or(1) == 1 =>
+                            {
+                                last_info = Some(UciMessage::Info { depth, score_cp, score_mate, multipv, nodes, pv });
+                            }
+                            _ => {}
                         }
-                        _ => {}
                     }
-                }
+                };
+                let drained: Result<Result<EngineResult, EngineError>, _> =
+                    tokio::time::timeout(self.watchdog.stall_timeout, drain).await;
+                drained.unwrap_or_else(|_| {
+                    self.flag_stalled();
+                    let _ = self.child.start_kill();
+                    Err(EngineError::Timeout)
+                })
             }
         }
     }
+}
+
+#[async_trait]
+impl Engine for ProcessEngine {
+    async fn go(&mut self, params: GoParams) -> Result<EngineResult, EngineError> {
+        let _searching = self.watchdog.search();
+        self.search(params).await
+    }
 
     async fn stop(&mut self) -> Result<(), EngineError> {
         self.send_command("stop").await
@@ -162,12 +288,19 @@ impl Engine for ProcessEngine {
             Ok(res) => res,
             Err(_) => {
                 let _ = self.send_command("stop").await;
-                // Drain lines until ReadyOk
-                loop {
-                    let line = self.read_line().await?;
-                    if let Some(UciMessage::ReadyOk) = parse_uci_line(&line) {
-                        break;
+                // Drain lines until ReadyOk, unless the engine stopped
+                // answering altogether
+                let drain = async {
+                    loop {
+                        let line = self.read_line().await?;
+                        if let Some(UciMessage::ReadyOk) = parse_uci_line(&line) {
+                            return Ok::<(), EngineError>(());
+                        }
                     }
+                };
+                if tokio::time::timeout(self.watchdog.stall_timeout, drain).await.is_err() {
+                    self.flag_stalled();
+                    let _ = self.child.start_kill();
                 }
                 Err(EngineError::Timeout)
             }
@@ -179,6 +312,23 @@ impl Engine for ProcessEngine {
         let _ = self.child.wait().await;
         Ok(())
     }
+
+    async fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        self.send_command(&format!("setoption name {} value {}", name, value)).await
+    }
+
+    async fn new_game(&mut self) -> Result<(), EngineError> {
+        self.send_command("ucinewgame").await
+    }
+
+    /// As reported when the engine started.
+    fn info(&self) -> EngineInfo {
+        self.info.clone()
+    }
+
+    fn is_stalled(&self) -> bool {
+        self.watchdog.stalled.load(Ordering::Relaxed)
+    }
 }
 
 impl Drop for ProcessEngine {
@@ -187,3 +337,181 @@ impl Drop for ProcessEngine {
         let _ = self.child.start_kill();
     }
 }
+
+/// The last [`STDERR_LINES`] lines an engine wrote to stderr.
+#[derive(Default)]
+struct StderrLog {
+    lines: std::sync::Mutex<VecDeque<String>>,
+}
+
+impl StderrLog {
+    fn push(&self, mut line: String) {
+        if line.len() > STDERR_LINE_BYTES {
+            let mut end = STDERR_LINE_BYTES;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            line.truncate(end);
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if lines.len() == STDERR_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn recent(&self) -> Vec<String> {
+        self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().cloned().collect()
+    }
+}
+
+/// Keep what the engine writes to stderr until it closes it. The receiver
+/// turns true once it has.
+fn capture_stderr(stderr: ChildStderr, log: Arc<StderrLog>) -> watch::Receiver<bool> {
+    let (closed, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = line.trim_end();
+            if !line.is_empty() {
+                log.push(line.to_string());
+            }
+        }
+        let _ = closed.send(true);
+    });
+    receiver
+}
+
+/// `; stderr: ...` with the last lines of `lines`, or nothing when there
+/// are none.
+fn stderr_context(lines: &[String]) -> String {
+    if lines.is_empty() {
+        return String::new();
+    }
+    let recent = &lines[lines.len().saturating_sub(STDERR_CONTEXT_LINES)..];
+    format!("; stderr: {}", recent.join(" | "))
+}
+
+/// Whether an engine is searching and when it last wrote a line.
+struct Watchdog {
+    stall_timeout: Duration,
+    searching: AtomicBool,
+    last_output: std::sync::Mutex<Instant>,
+    /// Set once the engine went silent mid-search, and kept
+    stalled: AtomicBool,
+}
+
+impl Watchdog {
+    fn new(stall_timeout: Duration) -> Self {
+        Self {
+            stall_timeout,
+            searching: AtomicBool::new(false),
+            last_output: std::sync::Mutex::new(Instant::now()),
+            stalled: AtomicBool::new(false),
+        }
+    }
+
+    fn output(&self) {
+        *self.last_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Instant::now();
+    }
+
+    /// Mark the engine as searching until the guard is dropped.
+    fn search(self: &Arc<Self>) -> SearchGuard {
+        self.output();
+        self.searching.store(true, Ordering::Relaxed);
+        SearchGuard(self.clone())
+    }
+
+    /// Flag the engine when it has been searching without a word for the
+    /// stall timeout. True when it was flagged just now.
+    fn check(&self, now: Instant) -> bool {
+        let last_output = *self.last_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.searching.load(Ordering::Relaxed)
+            && now.saturating_duration_since(last_output) >= self.stall_timeout
+            && !self.stalled.swap(true, Ordering::Relaxed)
+    }
+}
+
+struct SearchGuard(Arc<Watchdog>);
+
+impl Drop for SearchGuard {
+    fn drop(&mut self) {
+        self.0.searching.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Check the engine's searches until it is dropped, warning with its recent
+/// stderr when one stalls.
+fn watch_searches(watchdog: Weak<Watchdog>, stderr: Arc<StderrLog>, path: String) {
+    tokio::spawn(async move {
+        let period = watchdog.upgrade().map_or(Duration::ZERO, |watchdog| watchdog.stall_timeout / 4);
+        let mut interval = tokio::time::interval(period.max(Duration::from_millis(100)));
+        loop {
+            interval.tick().await;
+            let Some(watchdog) = watchdog.upgrade() else {
+                break;
+            };
+            if watchdog.check(Instant::now()) {
+                log::warn!(
+                    "Engine {} wrote nothing for {:?} mid-search{}",
+                    path,
+                    watchdog.stall_timeout,
+                    stderr_context(&stderr.recent())
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An executable shell script standing in for an engine.
+    fn script(name: &str, body: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir().join(format!("engine-process-{}-{}", name, std::process::id()));
+        std::fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn stderr_keeps_the_last_lines() {
+        let log = StderrLog::default();
+        for n in 0..STDERR_LINES + 6 {
+            log.push(format!("line {}", n));
+        }
+        let recent = log.recent();
+        assert_eq!(recent.len(), STDERR_LINES);
+        assert_eq!(recent[0], "line 6");
+
+        log.push("é".repeat(STDERR_LINE_BYTES));
+        assert_eq!(log.recent().last().unwrap().len(), STDERR_LINE_BYTES);
+        assert_eq!(stderr_context(&[]), "");
+    }
+
+    #[tokio::test]
+    async fn engines_that_exit_report_their_stderr() {
+        let path = script("exits", "echo 'cannot open nn.nnue' >&2\nexit 1\n");
+        let err = ProcessEngine::new(&path).await.err().unwrap();
+        assert!(matches!(&err, EngineError::Unknown(message) if message.contains("cannot open nn.nnue")), "{}", err);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn engines_silent_mid_search_are_flagged() {
+        let path = script(
+            "stalls",
+            "while read line; do\n  case \"$line\" in\n    uci) echo uciok ;;\n    go*) echo 'searching' >&2; exec sleep 30 ;;\n  esac\ndone\n",
+        );
+        let mut engine = ProcessEngine::with_stall_timeout(&path, Duration::from_millis(200)).await.unwrap();
+        assert!(!engine.is_stalled());
+
+        let params = GoParams { depth: None, time_limit_ms: Some(100), search_moves: None, clock: None };
+        assert!(matches!(engine.go(params).await, Err(EngineError::Timeout)));
+        assert!(engine.is_stalled());
+        assert_eq!(engine.recent_stderr(), vec!["searching".to_string()]);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,376 @@
+//! Scheduling of analysis jobs onto a fixed number of engine slots.
+//!
+//! Interactive jobs (live analysis) are always dispatched before batch jobs
+//! (post-game analysis, puzzle mining), and some slots are kept free of batch
+//! work so that an interactive request never waits behind a full pool of it.
+//! Each user may only run a few jobs at once, and users with queued jobs of
+//! the same priority take turns.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// How urgently a job needs an engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// A user is waiting on the result
+    Interactive,
+    /// Background work
+    Batch,
+}
+
+impl JobPriority {
+    const ALL: [JobPriority; 2] = [JobPriority::Interactive, JobPriority::Batch];
+
+    fn index(self) -> usize {
+        match self {
+            JobPriority::Interactive => 0,
+            JobPriority::Batch => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Engines that may search at the same time
+    pub slots: usize,
+    /// Slots batch jobs may not take
+    pub reserved_interactive: usize,
+    /// Jobs one user may run at the same time
+    pub per_user_limit: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            slots: 4,
+            reserved_interactive: 1,
+            per_user_limit: 2,
+        }
+    }
+}
+
+/// Time jobs of one priority spent queued before they started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaitStats {
+    pub started: u64,
+    pub total_wait_ms: u64,
+    pub max_wait_ms: u64,
+}
+
+impl WaitStats {
+    fn record(&mut self, wait: Duration) {
+        let wait_ms = wait.as_millis() as u64;
+        self.started += 1;
+        self.total_wait_ms += wait_ms;
+        self.max_wait_ms = self.max_wait_ms.max(wait_ms);
+    }
+
+    pub fn mean_wait_ms(&self) -> u64 {
+        self.total_wait_ms.checked_div(self.started).unwrap_or(0)
+    }
+}
+
+/// Queue depth and wait times of both priorities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub running: usize,
+    pub running_batch: usize,
+    pub interactive_waiting: usize,
+    pub batch_waiting: usize,
+    pub interactive_wait: WaitStats,
+    pub batch_wait: WaitStats,
+}
+
+struct Waiter {
+    enqueued_at: Instant,
+    tx: oneshot::Sender<JobSlot>,
+}
+
+/// Waiting jobs of one priority, per user, served round-robin.
+#[derive(Default)]
+struct Lane {
+    jobs: HashMap<String, VecDeque<Waiter>>,
+    turns: VecDeque<String>,
+}
+
+impl Lane {
+    fn push(&mut self, user: String, waiter: Waiter) {
+        let jobs = self.jobs.entry(user.clone()).or_default();
+        if jobs.is_empty() {
+            self.turns.push_back(user);
+        }
+        jobs.push_back(waiter);
+    }
+
+    // The oldest job of the next user whose turn it is and who is under the
+    // limit; jobs whose caller gave up are dropped on the way
+    fn next(&mut self, running_per_user: &HashMap<String, usize>, per_user_limit: usize) -> Option<(String, Waiter)> {
+        for _ in 0..self.turns.len() {
+            let user = self.turns.pop_front()?;
+            let jobs = self.jobs.get_mut(&user)?;
+            jobs.retain(|waiter| !waiter.tx.is_closed());
+
+            if jobs.is_empty() {
+                self.jobs.remove(&user);
+                continue;
+            }
+            if running_per_user.get(&user).copied().unwrap_or(0) >= per_user_limit {
+                self.turns.push_back(user);
+                continue;
+            }
+
+            let waiter = jobs.pop_front()?;
+            if jobs.is_empty() {
+                self.jobs.remove(&user);
+            } else {
+                self.turns.push_back(user.clone());
+            }
+            return Some((user, waiter));
+        }
+        None
+    }
+
+    fn waiting(&self) -> usize {
+        self.jobs
+            .values()
+            .flat_map(|jobs| jobs.iter())
+            .filter(|waiter| !waiter.tx.is_closed())
+            .count()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    running_batch: usize,
+    running_per_user: HashMap<String, usize>,
+    lanes: [Lane; 2],
+    waits: [WaitStats; 2],
+}
+
+/// Gate in front of the engine pool. Clones share the same queue.
+#[derive(Clone)]
+pub struct AnalysisQueue {
+    config: QueueConfig,
+    state: Arc<Mutex<State>>,
+}
+
+/// Permission to run one job on an engine; the slot is freed when dropped.
+pub struct JobSlot {
+    queue: AnalysisQueue,
+    user: String,
+    priority: JobPriority,
+    waited: Duration,
+}
+
+impl JobSlot {
+    pub fn priority(&self) -> JobPriority {
+        self.priority
+    }
+
+    /// Time spent queued before the job could start
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        self.queue.release(&self.user, self.priority);
+    }
+}
+
+impl AnalysisQueue {
+    pub fn new(config: QueueConfig) -> Self {
+        let slots = config.slots.max(1);
+        Self {
+            config: QueueConfig {
+                slots,
+                reserved_interactive: config.reserved_interactive.min(slots - 1),
+                per_user_limit: config.per_user_limit.max(1),
+            },
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    pub fn config(&self) -> QueueConfig {
+        self.config
+    }
+
+    /// Wait for a slot for a job of `user`. Dropping the future gives up the
+    /// place in the queue.
+    pub async fn acquire(&self, user: &str, priority: JobPriority) -> JobSlot {
+        let (tx, rx) = oneshot::channel();
+        let waiter = Waiter {
+            enqueued_at: Instant::now(),
+            tx,
+        };
+        self.state.lock().unwrap().lanes[priority.index()].push(user.to_string(), waiter);
+        self.dispatch();
+
+        // The sender is only dropped once the slot was handed over
+        rx.await.expect("analysis queue dropped a waiting job")
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let state = self.state.lock().unwrap();
+        QueueStats {
+            running: state.running,
+            running_batch: state.running_batch,
+            interactive_waiting: state.lanes[JobPriority::Interactive.index()].waiting(),
+            batch_waiting: state.lanes[JobPriority::Batch.index()].waiting(),
+            interactive_wait: state.waits[JobPriority::Interactive.index()],
+            batch_wait: state.waits[JobPriority::Batch.index()],
+        }
+    }
+
+    // Start as many queued jobs as the slots allow
+    fn dispatch(&self) {
+        let mut granted = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            let state = &mut *state;
+            for priority in JobPriority::ALL {
+                loop {
+                    if state.running >= self.config.slots {
+                        break;
+                    }
+                    if priority == JobPriority::Batch
+                        && state.running_batch >= self.config.slots - self.config.reserved_interactive
+                    {
+                        break;
+                    }
+                    let Some((user, waiter)) =
+                        state.lanes[priority.index()].next(&state.running_per_user, self.config.per_user_limit)
+                    else {
+                        break;
+                    };
+
+                    let waited = waiter.enqueued_at.elapsed();
+                    state.waits[priority.index()].record(waited);
+                    state.running += 1;
+                    if priority == JobPriority::Batch {
+                        state.running_batch += 1;
+                    }
+                    *state.running_per_user.entry(user.clone()).or_insert(0) += 1;
+                    granted.push((waiter.tx, user, priority, waited));
+                }
+            }
+        }
+
+        // Outside the lock: a slot whose caller left is dropped, which frees it
+        for (tx, user, priority, waited) in granted {
+            let _ = tx.send(JobSlot {
+                queue: self.clone(),
+                user,
+                priority,
+                waited,
+            });
+        }
+    }
+
+    fn release(&self, user: &str, priority: JobPriority) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.running -= 1;
+            if priority == JobPriority::Batch {
+                state.running_batch -= 1;
+            }
+            if let Some(count) = state.running_per_user.get_mut(user) {
+                *count -= 1;
+                if *count == 0 {
+                    state.running_per_user.remove(user);
+                }
+            }
+        }
+        self.dispatch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    fn queue(slots: usize, reserved_interactive: usize, per_user_limit: usize) -> AnalysisQueue {
+        AnalysisQueue::new(QueueConfig {
+            slots,
+            reserved_interactive,
+            per_user_limit,
+        })
+    }
+
+    async fn started(queue: &AnalysisQueue, user: &str, priority: JobPriority) -> Option<JobSlot> {
+        timeout(Duration::from_millis(50), queue.acquire(user, priority)).await.ok()
+    }
+
+    #[tokio::test]
+    async fn batch_jobs_leave_reserved_slots_to_interactive_ones() {
+        let queue = queue(2, 1, 4);
+        let _batch = started(&queue, "miner", JobPriority::Batch).await.unwrap();
+        assert!(started(&queue, "miner", JobPriority::Batch).await.is_none());
+
+        let live = started(&queue, "alice", JobPriority::Interactive).await;
+        assert!(live.is_some());
+        assert_eq!(queue.stats().running, 2);
+        assert_eq!(queue.stats().running_batch, 1);
+    }
+
+    #[tokio::test]
+    async fn interactive_jobs_are_dispatched_first() {
+        let queue = queue(1, 0, 4);
+        let running = started(&queue, "alice", JobPriority::Batch).await.unwrap();
+
+        let batch_queue = queue.clone();
+        let batch = tokio::spawn(async move { batch_queue.acquire("bob", JobPriority::Batch).await });
+        tokio::task::yield_now().await;
+        let live_queue = queue.clone();
+        let live = tokio::spawn(async move { live_queue.acquire("carol", JobPriority::Interactive).await });
+        while queue.stats().interactive_waiting + queue.stats().batch_waiting < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        drop(running);
+        let live = live.await.unwrap();
+        assert_eq!(live.priority(), JobPriority::Interactive);
+        assert!(!batch.is_finished());
+        drop(live);
+        assert_eq!(batch.await.unwrap().priority(), JobPriority::Batch);
+        assert_eq!(queue.stats().interactive_wait.started, 1);
+        assert_eq!(queue.stats().batch_wait.started, 2);
+    }
+
+    #[tokio::test]
+    async fn users_are_capped_and_take_turns() {
+        let queue = queue(3, 0, 1);
+        let first = started(&queue, "alice", JobPriority::Batch).await.unwrap();
+        // Alice is at her limit, so her second job waits while Bob's starts
+        let alice_queue = queue.clone();
+        let alice = tokio::spawn(async move { alice_queue.acquire("alice", JobPriority::Batch).await });
+        while queue.stats().batch_waiting == 0 {
+            tokio::task::yield_now().await;
+        }
+        let bob = started(&queue, "bob", JobPriority::Batch).await;
+        assert!(bob.is_some());
+        assert_eq!(queue.stats().batch_waiting, 1);
+
+        drop(first);
+        assert!(timeout(Duration::from_millis(50), alice).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn abandoned_jobs_leave_the_queue() {
+        let queue = queue(1, 0, 1);
+        let running = started(&queue, "alice", JobPriority::Interactive).await.unwrap();
+        assert!(started(&queue, "bob", JobPriority::Interactive).await.is_none());
+        assert_eq!(queue.stats().interactive_waiting, 0);
+
+        drop(running);
+        assert_eq!(queue.stats().running, 0);
+        assert!(started(&queue, "bob", JobPriority::Interactive).await.is_some());
+    }
+}
@@ -0,0 +1,216 @@
+//! Engines kept running between searches of the same game.
+//!
+//! An engine that searched one position of a game has most of the next
+//! one's tree in its hash already. A game that is followed move by move
+//! keeps its engine between searches, without `ucinewgame`, so each search
+//! starts from what the last one learned. Engines nobody asked for in a
+//! while, and the least recently used ones beyond the limit, are quit.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::Engine;
+use crate::variant::AnalysisVariant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Engines kept at most; 0 starts an engine for every search
+    pub max_sessions: usize,
+    /// Engines unused this long are quit
+    pub idle_timeout: Duration,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 8,
+            idle_timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+struct Session {
+    engine: Box<dyn Engine>,
+    variant: AnalysisVariant,
+    last_used: Instant,
+}
+
+/// Engines of followed games, by game. Clones share the same engines.
+#[derive(Clone)]
+pub struct EngineSessions {
+    config: SessionConfig,
+    sessions: Arc<Mutex<HashMap<Uuid, Session>>>,
+}
+
+impl EngineSessions {
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn config(&self) -> SessionConfig {
+        self.config
+    }
+
+    /// Engines kept now, idle ones included until they are swept.
+    pub async fn len(&self) -> usize {
+        self.sessions.lock().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// The engine kept for `game`, when it analyses `variant`. The caller
+    /// has it to itself until it is given back with [`check_in`]; a search
+    /// of the same game meanwhile starts an engine of its own.
+    ///
+    /// [`check_in`]: EngineSessions::check_in
+    pub async fn check_out(&self, game: Uuid, variant: AnalysisVariant) -> Option<Box<dyn Engine>> {
+        let (session, expired) = {
+            let mut sessions = self.sessions.lock().await;
+            let expired = self.take_expired(&mut sessions, Instant::now());
+            (sessions.remove(&game), expired)
+        };
+        quit_all(expired).await;
+
+        match session {
+            Some(session) if session.variant == variant => Some(session.engine),
+            Some(session) => {
+                quit_all(vec![session.engine]).await;
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Keep `engine`, which just searched a position of `game`, for the
+    /// game's next search. An engine already kept for the game is quit, as
+    /// is `engine` itself when it stalled.
+    pub async fn check_in(&self, game: Uuid, variant: AnalysisVariant, engine: Box<dyn Engine>) {
+        if self.config.max_sessions == 0 || engine.is_stalled() {
+            return quit_all(vec![engine]).await;
+        }
+
+        let now = Instant::now();
+        let retired = {
+            let mut sessions = self.sessions.lock().await;
+            let mut retired = self.take_expired(&mut sessions, now);
+            let session = Session { engine, variant, last_used: now };
+            if let Some(previous) = sessions.insert(game, session) {
+                retired.push(previous.engine);
+            }
+            while sessions.len() > self.config.max_sessions {
+                let Some(oldest) = sessions.iter().min_by_key(|(_, session)| session.last_used).map(|(game, _)| *game)
+                else {
+                    break;
+                };
+                retired.extend(sessions.remove(&oldest).map(|session| session.engine));
+            }
+            retired
+        };
+        quit_all(retired).await;
+    }
+
+    /// Quit the engine kept for `game`, e.g. once the game is over.
+    pub async fn end(&self, game: Uuid) {
+        let session = self.sessions.lock().await.remove(&game);
+        quit_all(session.into_iter().map(|session| session.engine).collect()).await;
+    }
+
+    /// Quit every engine left unused for longer than the idle timeout.
+    pub async fn sweep(&self) {
+        let expired = self.take_expired(&mut *self.sessions.lock().await, Instant::now());
+        quit_all(expired).await;
+    }
+
+    fn take_expired(&self, sessions: &mut HashMap<Uuid, Session>, now: Instant) -> Vec<Box<dyn Engine>> {
+        let expired: Vec<Uuid> = sessions
+            .iter()
+            .filter(|(_, session)| now.saturating_duration_since(session.last_used) >= self.config.idle_timeout)
+            .map(|(game, _)| *game)
+            .collect();
+        expired
+            .iter()
+            .filter_map(|game| sessions.remove(game))
+            .map(|session| session.engine)
+            .collect()
+    }
+}
+
+impl Default for EngineSessions {
+    fn default() -> Self {
+        Self::new(SessionConfig::default())
+    }
+}
+
+// Outside the lock: quitting waits for the engine to exit
+async fn quit_all(engines: Vec<Box<dyn Engine>>) {
+    for mut engine in engines {
+        if let Err(e) = engine.quit().await {
+            log::warn!("Engine of a followed game failed to quit: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::BuiltinEngine;
+
+    fn engine() -> Box<dyn Engine> {
+        Box::new(BuiltinEngine::new())
+    }
+
+    fn game(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    #[tokio::test]
+    async fn engines_are_kept_per_game_and_variant() {
+        let sessions = EngineSessions::default();
+        assert!(sessions.check_out(game(1), AnalysisVariant::Standard).await.is_none());
+
+        sessions.check_in(game(1), AnalysisVariant::Standard, engine()).await;
+        assert_eq!(sessions.len().await, 1);
+        assert!(sessions.check_out(game(2), AnalysisVariant::Standard).await.is_none());
+        assert!(sessions.check_out(game(1), AnalysisVariant::Standard).await.is_some());
+        // Checked out engines are no one else's
+        assert!(sessions.check_out(game(1), AnalysisVariant::Standard).await.is_none());
+
+        sessions.check_in(game(1), AnalysisVariant::Standard, engine()).await;
+        assert!(sessions.check_out(game(1), AnalysisVariant::Chess960).await.is_none());
+        assert!(sessions.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn the_least_recently_used_engines_go_first() {
+        let sessions = EngineSessions::new(SessionConfig { max_sessions: 2, ..SessionConfig::default() });
+        for n in 1..=3 {
+            sessions.check_in(game(n), AnalysisVariant::Standard, engine()).await;
+        }
+        assert_eq!(sessions.len().await, 2);
+        assert!(sessions.check_out(game(1), AnalysisVariant::Standard).await.is_none());
+        assert!(sessions.check_out(game(3), AnalysisVariant::Standard).await.is_some());
+
+        sessions.end(game(2)).await;
+        assert!(sessions.is_empty().await);
+
+        let disabled = EngineSessions::new(SessionConfig { max_sessions: 0, ..SessionConfig::default() });
+        disabled.check_in(game(1), AnalysisVariant::Standard, engine()).await;
+        assert!(disabled.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn idle_engines_are_swept() {
+        let sessions = EngineSessions::new(SessionConfig { idle_timeout: Duration::ZERO, ..SessionConfig::default() });
+        sessions.check_in(game(1), AnalysisVariant::Standard, engine()).await;
+        sessions.sweep().await;
+        assert!(sessions.is_empty().await);
+    }
+}
@@ -0,0 +1,33 @@
+//! UCI setup for the variants analysis supports.
+//!
+//! Stockfish plays standard chess and Chess960; other variants need an
+//! engine such as Fairy-Stockfish, told which rules to play by.
+
+pub use dto::ai::AnalysisVariant;
+
+/// Whether an engine built for standard chess can analyse `variant`.
+pub fn standard_engine_plays(variant: AnalysisVariant) -> bool {
+    matches!(variant, AnalysisVariant::Standard | AnalysisVariant::Chess960)
+}
+
+/// Options to set before the engine is sent a position of `variant`.
+pub fn uci_options(variant: AnalysisVariant) -> &'static [(&'static str, &'static str)] {
+    match variant {
+        AnalysisVariant::Standard => &[],
+        AnalysisVariant::Chess960 => &[("UCI_Chess960", "true")],
+        AnalysisVariant::Crazyhouse => &[("UCI_Variant", "crazyhouse")],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants_need_the_right_engine_and_options() {
+        assert!(standard_engine_plays(AnalysisVariant::Chess960));
+        assert!(!standard_engine_plays(AnalysisVariant::Crazyhouse));
+        assert!(uci_options(AnalysisVariant::Standard).is_empty());
+        assert_eq!(uci_options(AnalysisVariant::Crazyhouse), &[("UCI_Variant", "crazyhouse")]);
+    }
+}
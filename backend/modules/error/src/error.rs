@@ -20,6 +20,19 @@ pub enum ApiError {
         move_text: String,
         reason: String,
     },
+    /// Request is well-formed but cannot be applied
+    BadRequest(String),
+    /// Request lacks valid authentication
+    Unauthorized(String),
+    /// Authenticated caller is not allowed to perform the action
+    Forbidden(String),
+    /// Caller must wait before repeating the request
+    TooManyRequests(String),
+    /// A site the server relies on failed or answered unexpectedly
+    BadGateway(String),
+    /// Data the server stored or built cannot be read or written back;
+    /// never the caller's fault
+    Internal(String),
 }
 
 impl From<DbErr> for ApiError {
@@ -89,6 +102,12 @@ impl fmt::Display for ApiError {
             ApiError::IllegalMoveError { move_number, move_text, reason } => {
                 write!(f, "Illegal move at move {}: '{}' - {}", move_number, move_text, reason)
             }
+            ApiError::BadRequest(msg) => write!(f, "{}", msg),
+            ApiError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            ApiError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            ApiError::TooManyRequests(msg) => write!(f, "Too many requests: {}", msg),
+            ApiError::BadGateway(msg) => write!(f, "Upstream error: {}", msg),
+            ApiError::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
     }
 }
@@ -124,6 +143,30 @@ impl ApiError {
                 "error": self.to_string(),
                 "code": 422
             })),
+            ApiError::BadRequest(_) => HttpResponse::BadRequest().json(json!({
+                "error": self.to_string(),
+                "code": 400
+            })),
+            ApiError::Unauthorized(_) => HttpResponse::Unauthorized().json(json!({
+                "error": self.to_string(),
+                "code": 401
+            })),
+            ApiError::Forbidden(_) => HttpResponse::Forbidden().json(json!({
+                "error": self.to_string(),
+                "code": 403
+            })),
+            ApiError::TooManyRequests(_) => HttpResponse::TooManyRequests().json(json!({
+                "error": self.to_string(),
+                "code": 429
+            })),
+            ApiError::BadGateway(_) => HttpResponse::BadGateway().json(json!({
+                "error": self.to_string(),
+                "code": 502
+            })),
+            ApiError::Internal(_) => HttpResponse::InternalServerError().json(json!({
+                "error": self.to_string(),
+                "code": 500
+            })),
         }
     }
 }
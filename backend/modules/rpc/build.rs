@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // A vendored protoc, so the build needs no system install
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::configure().compile_protos(&["proto/engine.proto", "proto/matchmaking.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::env;
+use std::time::Duration;
+
+use dotenv::dotenv;
+use rpc::engine::EnginePoolService;
+use rpc::pb::engine_pool_server::EnginePoolServer;
+use service::engine_service::{
+    AnalysisQueue, BENCH_DEPTH, EngineService, LoadConfig, PreparedEngine, QueueConfig, SessionConfig,
+};
+use tonic::transport::Server;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+/// Run the benchmark suite on every engine and print how fast each searched.
+async fn bench(engines: &EngineService, depth: u8) -> Result<(), Box<dyn std::error::Error>> {
+    for report in engines.benchmark(depth).await? {
+        println!("{} at depth {}", report.id, report.depth);
+        for position in &report.positions {
+            println!(
+                "  {:<72} {:>6} {:>12} nodes {:>7} ms",
+                position.fen,
+                position.best_move,
+                position.nodes.map_or("-".to_string(), |nodes| nodes.to_string()),
+                position.time_ms
+            );
+        }
+        println!(
+            "  total {} nodes in {} ms, {} nodes/s",
+            report.total_nodes.map_or("-".to_string(), |nodes| nodes.to_string()),
+            report.total_time_ms,
+            report.nodes_per_second.map_or("-".to_string(), |nps| nps.to_string())
+        );
+    }
+    Ok(())
+}
+
+/// Runs the engine pool on its own, for the API processes that set
+/// `ENGINE_GRPC_URL`. `engine-server bench [depth]` runs the benchmark suite
+/// on the configured engines instead and exits.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let _telemetry = telemetry::init("starkmate-engine-server", "error");
+
+    let addr = env::var("ENGINE_GRPC_ADDR").unwrap_or_else(|_| "0.0.0.0:50051".to_string()).parse()?;
+    let session_idle = Duration::from_secs(env_or("ENGINE_SESSION_IDLE_SECS", 120u64).max(1));
+    let engines = EngineService::with_queue(
+        PreparedEngine::from_path(env::var("ENGINE_PATH").unwrap_or_else(|_| "stockfish".to_string())),
+        AnalysisQueue::new(QueueConfig {
+            slots: env_or("ENGINE_SLOTS", 4),
+            reserved_interactive: env_or("ENGINE_RESERVED_INTERACTIVE_SLOTS", 1),
+            per_user_limit: env_or("ENGINE_JOBS_PER_USER", 2),
+        }),
+    )
+    .with_load({
+        let defaults = LoadConfig::default();
+        LoadConfig {
+            default_depth: env_or("ANALYSIS_DEFAULT_DEPTH", defaults.default_depth),
+            min_depth: env_or("ANALYSIS_MIN_DEPTH", defaults.min_depth),
+            busy: env_or("ANALYSIS_BUSY_LOAD", defaults.busy),
+            idle: env_or("ANALYSIS_IDLE_LOAD", defaults.idle),
+            guest_floor: env_or("ANALYSIS_GUEST_FLOOR", defaults.guest_floor),
+            player_floor: env_or("ANALYSIS_PLAYER_FLOOR", defaults.player_floor),
+            premium_floor: env_or("ANALYSIS_PREMIUM_FLOOR", defaults.premium_floor),
+            ..defaults
+        }
+    })
+    .with_sessions(SessionConfig {
+        max_sessions: env_or("ENGINE_SESSIONS", 8),
+        idle_timeout: session_idle,
+    })
+    .with_variant_engines(
+        env::var("VARIANT_ENGINES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(variant, path)| (variant.trim().to_string(), path.trim().to_string())),
+    );
+
+    let mut args = env::args().skip(1);
+    if args.next().as_deref() == Some("bench") {
+        let depth = args.next().and_then(|depth| depth.parse().ok()).unwrap_or(BENCH_DEPTH);
+        return bench(&engines, depth).await;
+    }
+
+    // Quit the engines of followed games that went quiet
+    let session_engines = engines.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(session_idle);
+        loop {
+            ticker.tick().await;
+            session_engines.sweep_sessions().await;
+        }
+    });
+
+    let (health, health_service) = tonic_health::server::health_reporter();
+    health.set_serving::<EnginePoolServer<EnginePoolService>>().await;
+
+    log::info!("Engine pool listening on {}", addr);
+    Server::builder()
+        .add_service(health_service)
+        .add_service(EnginePoolServer::new(EnginePoolService::new(engines)))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::env;
+
+use dotenv::dotenv;
+use matchmaking::redis::{create_redis_pool, test_redis_connection};
+use matchmaking::MatchmakingService;
+use rpc::matchmaking::MatchmakingRpc;
+use rpc::pb::matchmaking_server::MatchmakingServer;
+use tonic::transport::Server;
+
+/// Runs the matchmaking queues on their own, next to Redis.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let _telemetry = telemetry::init("starkmate-matchmaking", "error");
+
+    let addr = env::var("MATCHMAKING_GRPC_ADDR").unwrap_or_else(|_| "0.0.0.0:50052".to_string()).parse()?;
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+    let pool = create_redis_pool(&redis_url)?;
+
+    // Report not serving until Redis answers, so the health check says why
+    let (health, health_service) = tonic_health::server::health_reporter();
+    match test_redis_connection(&pool).await {
+        Ok(()) => health.set_serving::<MatchmakingServer<MatchmakingRpc>>().await,
+        Err(e) => {
+            log::error!("Redis is not reachable at {}: {}", redis_url, e);
+            health.set_not_serving::<MatchmakingServer<MatchmakingRpc>>().await;
+        }
+    }
+
+    log::info!("Matchmaking listening on {}", addr);
+    Server::builder()
+        .add_service(health_service)
+        .add_service(MatchmakingServer::new(MatchmakingRpc::new(MatchmakingService::new(pool))))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
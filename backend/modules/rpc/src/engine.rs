@@ -0,0 +1,526 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dto::ai::{AnalysisTier, AnalysisVariant, BenchmarkPositionDisplay, EngineBenchmarkDisplay, EngineQueueStats, EngineWaitStats};
+use engine::bot::{BotMove, BotProfile};
+use engine::{EngineError, EngineInfo, EngineOption, EngineResult, PvLine};
+use service::engine_service::{EngineService, JobPriority, RemoteEngine};
+use telemetry::{current_request_id, request_id_from, with_request_id, REQUEST_ID_HEADER};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Response, Status};
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+
+use crate::pb::engine_pool_client::EnginePoolClient;
+use crate::pb::engine_pool_server::EnginePool;
+use crate::pb;
+
+/// Serves the engine pool of this process over gRPC.
+pub struct EnginePoolService {
+    engines: EngineService,
+}
+
+impl EnginePoolService {
+    pub fn new(engines: EngineService) -> Self {
+        Self { engines }
+    }
+
+    async fn search(&self, request: pb::AnalyzeRequest) -> Result<Response<pb::AnalyzeResponse>, Status> {
+        let depth = request
+            .depth
+            .map(u8::try_from)
+            .transpose()
+            .map_err(|_| Status::invalid_argument("depth is out of range"))?;
+        let variant = variant_of(&request.variant)?;
+        let tier = tier_of(&request.tier)?;
+        let game = request
+            .game_id
+            .as_deref()
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|_| Status::invalid_argument("game_id is not a UUID"))?;
+        let result = self
+            .engines
+            .get_suggestion(
+                &request.user,
+                priority_of(request.priority()),
+                tier,
+                variant,
+                game,
+                &request.fen,
+                depth,
+                request.time_limit_ms,
+            )
+            .await
+            .map_err(status_of)?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn search_bot_move(&self, request: pb::BotMoveRequest) -> Result<Response<pb::BotMoveResponse>, Status> {
+        let profile = BotProfile::find(&request.profile_id)
+            .ok_or_else(|| Status::not_found(format!("Bot '{}'", request.profile_id)))?;
+        let chosen = self
+            .engines
+            .search_bot_move(&request.user, &profile, &request.fen, request.seed)
+            .await
+            .map_err(status_of)?;
+        Ok(Response::new(pb::BotMoveResponse {
+            uci: chosen.uci,
+            evaluation: chosen.evaluation,
+            complexity: chosen.complexity,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl EnginePool for EnginePoolService {
+    async fn analyze(&self, request: Request<pb::AnalyzeRequest>) -> Result<Response<pb::AnalyzeResponse>, Status> {
+        let (request_id, span) = call_span(&request, "analyze");
+        with_request_id(request_id, self.search(request.into_inner()).instrument(span)).await
+    }
+
+    async fn bot_move(&self, request: Request<pb::BotMoveRequest>) -> Result<Response<pb::BotMoveResponse>, Status> {
+        let (request_id, span) = call_span(&request, "bot_move");
+        with_request_id(request_id, self.search_bot_move(request.into_inner()).instrument(span)).await
+    }
+
+    async fn queue_stats(&self, _: Request<pb::QueueStatsRequest>) -> Result<Response<pb::QueueStatsResponse>, Status> {
+        let stats = self.engines.queue_stats().await.map_err(status_of)?;
+        Ok(Response::new(stats.into()))
+    }
+
+    async fn engine_info(&self, _: Request<pb::EngineInfoRequest>) -> Result<Response<pb::EngineInfoResponse>, Status> {
+        let info = self.engines.engine_info().await.map_err(status_of)?;
+        Ok(Response::new(info.into()))
+    }
+
+    async fn benchmark(&self, request: Request<pb::BenchmarkRequest>) -> Result<Response<pb::BenchmarkResponse>, Status> {
+        let (request_id, span) = call_span(&request, "benchmark");
+        let depth = u8::try_from(request.into_inner().depth).map_err(|_| Status::invalid_argument("depth is out of range"))?;
+        let benchmark = async {
+            let reports = self.engines.benchmark(depth).await.map_err(status_of)?;
+            Ok(Response::new(pb::BenchmarkResponse {
+                engines: reports.into_iter().map(Into::into).collect(),
+            }))
+        };
+        with_request_id(request_id, benchmark.instrument(span)).await
+    }
+}
+
+/// The engine pool of an `engine-server` process, for an [`EngineService`]
+/// that starts no engines itself.
+#[derive(Clone)]
+pub struct RemoteEnginePool {
+    client: EnginePoolClient<Channel>,
+}
+
+impl RemoteEnginePool {
+    /// A client for the pool at `url`. It connects on first use, so the API
+    /// starts while the pool is still down; `timeout` bounds each request,
+    /// including the time it waits in the pool's queue.
+    pub fn connect_lazy(url: &str, timeout: Duration) -> Result<Self, tonic::transport::Error> {
+        let channel = Endpoint::from_shared(url.to_string())?.timeout(timeout).connect_lazy();
+        Ok(Self { client: EnginePoolClient::new(channel) })
+    }
+}
+
+#[async_trait]
+impl RemoteEngine for RemoteEnginePool {
+    async fn search(
+        &self,
+        user: &str,
+        priority: JobPriority,
+        tier: AnalysisTier,
+        variant: AnalysisVariant,
+        game: Option<Uuid>,
+        fen: &str,
+        depth: Option<u8>,
+        time_limit_ms: Option<u32>,
+    ) -> Result<EngineResult, EngineError> {
+        let request = pb::AnalyzeRequest {
+            user: user.to_string(),
+            priority: pb::JobPriority::from(priority).into(),
+            fen: fen.to_string(),
+            depth: depth.map(u32::from),
+            time_limit_ms,
+            variant: variant.as_str().to_string(),
+            tier: tier.as_str().to_string(),
+            game_id: game.map(|game| game.to_string()),
+        };
+        let response = self.client.clone().analyze(traced(request)).await.map_err(error_of)?;
+        response.into_inner().try_into()
+    }
+
+    async fn bot_move(&self, user: &str, profile_id: &str, fen: &str, seed: u64) -> Result<BotMove, EngineError> {
+        let request = pb::BotMoveRequest {
+            user: user.to_string(),
+            profile_id: profile_id.to_string(),
+            fen: fen.to_string(),
+            seed,
+        };
+        let chosen = self.client.clone().bot_move(traced(request)).await.map_err(error_of)?.into_inner();
+        Ok(BotMove {
+            uci: chosen.uci,
+            evaluation: chosen.evaluation,
+            from_book: false,
+            complexity: chosen.complexity,
+        })
+    }
+
+    async fn queue_stats(&self) -> Result<EngineQueueStats, EngineError> {
+        let response = self.client.clone().queue_stats(traced(pb::QueueStatsRequest {})).await.map_err(error_of)?;
+        Ok(response.into_inner().into())
+    }
+
+    async fn engine_info(&self) -> Result<EngineInfo, EngineError> {
+        let response = self.client.clone().engine_info(traced(pb::EngineInfoRequest {})).await.map_err(error_of)?;
+        Ok(response.into_inner().into())
+    }
+
+    async fn benchmark(&self, depth: u8) -> Result<Vec<EngineBenchmarkDisplay>, EngineError> {
+        let request = pb::BenchmarkRequest { depth: u32::from(depth) };
+        let response = self.client.clone().benchmark(traced(request)).await.map_err(error_of)?;
+        response.into_inner().engines.into_iter().map(TryInto::try_into).collect()
+    }
+}
+
+/// `message` with the id of the request it is sent for, so the pool's
+/// spans can be matched with the caller's.
+fn traced<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Some(value) = current_request_id().and_then(|request_id| request_id.parse().ok()) {
+        request.metadata_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    request
+}
+
+/// The span a call is served in, with the request id its caller sent.
+fn call_span<T>(request: &Request<T>, method: &'static str) -> (String, Span) {
+    let request_id = request_id_from(request.metadata().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()));
+    let span = tracing::info_span!("grpc", method, %request_id);
+    (request_id, span)
+}
+
+fn priority_of(priority: pb::JobPriority) -> JobPriority {
+    match priority {
+        pb::JobPriority::Batch => JobPriority::Batch,
+        pb::JobPriority::Interactive | pb::JobPriority::Unspecified => JobPriority::Interactive,
+    }
+}
+
+fn variant_of(name: &str) -> Result<AnalysisVariant, Status> {
+    if name.is_empty() {
+        return Ok(AnalysisVariant::Standard);
+    }
+    AnalysisVariant::parse(name).ok_or_else(|| Status::invalid_argument(format!("Unknown variant '{}'", name)))
+}
+
+fn tier_of(name: &str) -> Result<AnalysisTier, Status> {
+    if name.is_empty() {
+        return Ok(AnalysisTier::Guest);
+    }
+    AnalysisTier::parse(name).ok_or_else(|| Status::invalid_argument(format!("Unknown tier '{}'", name)))
+}
+
+impl From<JobPriority> for pb::JobPriority {
+    fn from(value: JobPriority) -> Self {
+        match value {
+            JobPriority::Interactive => Self::Interactive,
+            JobPriority::Batch => Self::Batch,
+        }
+    }
+}
+
+fn status_of(err: EngineError) -> Status {
+    match err {
+        EngineError::Timeout => Status::deadline_exceeded(err.to_string()),
+        EngineError::NotRunning => Status::unavailable(err.to_string()),
+        EngineError::UnsupportedVariant(variant) => Status::failed_precondition(variant),
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+fn error_of(status: Status) -> EngineError {
+    match status.code() {
+        Code::DeadlineExceeded => EngineError::Timeout,
+        Code::Unavailable => EngineError::NotRunning,
+        Code::FailedPrecondition => EngineError::UnsupportedVariant(status.message().to_string()),
+        _ => EngineError::Unknown(format!("engine pool: {}", status.message())),
+    }
+}
+
+impl From<EngineResult> for pb::AnalyzeResponse {
+    fn from(value: EngineResult) -> Self {
+        Self {
+            best_move: value.best_move,
+            evaluation: value.evaluation,
+            mate: value.mate,
+            depth: value.depth.map(u32::from),
+            principal_variation: value.principal_variation,
+            lines: value
+                .lines
+                .into_iter()
+                .map(|line| pb::PvLine { moves: line.moves, evaluation: line.evaluation, mate: line.mate })
+                .collect(),
+            nodes: value.nodes,
+        }
+    }
+}
+
+impl TryFrom<pb::AnalyzeResponse> for EngineResult {
+    type Error = EngineError;
+
+    fn try_from(value: pb::AnalyzeResponse) -> Result<Self, Self::Error> {
+        let depth = value
+            .depth
+            .map(u8::try_from)
+            .transpose()
+            .map_err(|_| EngineError::ParseError(format!("depth {:?} is out of range", value.depth)))?;
+        Ok(Self {
+            best_move: value.best_move,
+            evaluation: value.evaluation,
+            mate: value.mate,
+            depth,
+            principal_variation: value.principal_variation,
+            lines: value
+                .lines
+                .into_iter()
+                .map(|line| PvLine { moves: line.moves, evaluation: line.evaluation, mate: line.mate })
+                .collect(),
+            nodes: value.nodes,
+        })
+    }
+}
+
+impl From<EngineQueueStats> for pb::QueueStatsResponse {
+    fn from(value: EngineQueueStats) -> Self {
+        let wait = |stats: EngineWaitStats| pb::WaitStats {
+            started: stats.started,
+            mean_wait_ms: stats.mean_wait_ms,
+            max_wait_ms: stats.max_wait_ms,
+        };
+        Self {
+            slots: value.slots as u32,
+            reserved_interactive: value.reserved_interactive as u32,
+            per_user_limit: value.per_user_limit as u32,
+            running: value.running as u32,
+            running_batch: value.running_batch as u32,
+            interactive_waiting: value.interactive_waiting as u32,
+            batch_waiting: value.batch_waiting as u32,
+            interactive_wait: Some(wait(value.interactive_wait)),
+            batch_wait: Some(wait(value.batch_wait)),
+            depth_scale: value.depth_scale,
+            sessions: value.sessions as u32,
+        }
+    }
+}
+
+impl From<pb::QueueStatsResponse> for EngineQueueStats {
+    fn from(value: pb::QueueStatsResponse) -> Self {
+        let wait = |stats: Option<pb::WaitStats>| {
+            let stats = stats.unwrap_or_default();
+            EngineWaitStats {
+                started: stats.started,
+                mean_wait_ms: stats.mean_wait_ms,
+                max_wait_ms: stats.max_wait_ms,
+            }
+        };
+        Self {
+            slots: value.slots as usize,
+            reserved_interactive: value.reserved_interactive as usize,
+            per_user_limit: value.per_user_limit as usize,
+            running: value.running as usize,
+            running_batch: value.running_batch as usize,
+            interactive_waiting: value.interactive_waiting as usize,
+            batch_waiting: value.batch_waiting as usize,
+            interactive_wait: wait(value.interactive_wait),
+            batch_wait: wait(value.batch_wait),
+            depth_scale: value.depth_scale,
+            sessions: value.sessions as usize,
+        }
+    }
+}
+
+impl From<EngineInfo> for pb::EngineInfoResponse {
+    fn from(value: EngineInfo) -> Self {
+        Self {
+            name: value.name,
+            author: value.author,
+            options: value
+                .options
+                .into_iter()
+                .map(|option| pb::EngineOption {
+                    name: option.name,
+                    kind: option.kind,
+                    default: option.default,
+                    min: option.min,
+                    max: option.max,
+                    vars: option.vars,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<pb::EngineInfoResponse> for EngineInfo {
+    fn from(value: pb::EngineInfoResponse) -> Self {
+        Self {
+            name: value.name,
+            author: value.author,
+            options: value
+                .options
+                .into_iter()
+                .map(|option| EngineOption {
+                    name: option.name,
+                    kind: option.kind,
+                    default: option.default,
+                    min: option.min,
+                    max: option.max,
+                    vars: option.vars,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<EngineBenchmarkDisplay> for pb::EngineBenchmark {
+    fn from(value: EngineBenchmarkDisplay) -> Self {
+        Self {
+            id: value.id,
+            depth: u32::from(value.depth),
+            positions: value
+                .positions
+                .into_iter()
+                .map(|position| pb::BenchmarkPosition {
+                    fen: position.fen,
+                    best_move: position.best_move,
+                    depth: position.depth.map(u32::from),
+                    nodes: position.nodes,
+                    time_ms: position.time_ms,
+                })
+                .collect(),
+            total_nodes: value.total_nodes,
+            total_time_ms: value.total_time_ms,
+            nodes_per_second: value.nodes_per_second,
+        }
+    }
+}
+
+impl TryFrom<pb::EngineBenchmark> for EngineBenchmarkDisplay {
+    type Error = EngineError;
+
+    fn try_from(value: pb::EngineBenchmark) -> Result<Self, Self::Error> {
+        let depth_of = |depth: u32| {
+            u8::try_from(depth).map_err(|_| EngineError::ParseError(format!("depth {} is out of range", depth)))
+        };
+        let positions = value
+            .positions
+            .into_iter()
+            .map(|position| {
+                Ok(BenchmarkPositionDisplay {
+                    depth: position.depth.map(depth_of).transpose()?,
+                    fen: position.fen,
+                    best_move: position.best_move,
+                    nodes: position.nodes,
+                    time_ms: position.time_ms,
+                })
+            })
+            .collect::<Result<_, EngineError>>()?;
+        Ok(Self {
+            id: value.id,
+            depth: depth_of(value.depth)?,
+            positions,
+            total_nodes: value.total_nodes,
+            total_time_ms: value.total_time_ms,
+            nodes_per_second: value.nodes_per_second,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_survive_the_wire() {
+        let result = EngineResult {
+            best_move: "e2e4".to_string(),
+            evaluation: Some(0.3),
+            mate: None,
+            depth: Some(18),
+            principal_variation: vec!["e2e4".to_string(), "e7e5".to_string()],
+            lines: vec![PvLine { moves: vec!["e2e4".to_string()], evaluation: Some(0.3), mate: None }],
+            nodes: Some(2_000_000),
+        };
+        let back = EngineResult::try_from(pb::AnalyzeResponse::from(result.clone())).unwrap();
+        assert_eq!(back.best_move, result.best_move);
+        assert_eq!(back.depth, result.depth);
+        assert_eq!(back.principal_variation, result.principal_variation);
+        assert_eq!(back.lines, result.lines);
+        assert_eq!(back.nodes, result.nodes);
+
+        let too_deep = pb::AnalyzeResponse { depth: Some(300), ..Default::default() };
+        assert!(EngineResult::try_from(too_deep).is_err());
+    }
+
+    #[test]
+    fn test_engine_info_survives_the_wire() {
+        let info = EngineInfo {
+            name: Some("Stockfish 16".to_string()),
+            author: None,
+            options: vec![EngineOption {
+                name: "UCI_Variant".to_string(),
+                kind: "combo".to_string(),
+                default: Some("chess".to_string()),
+                min: None,
+                max: None,
+                vars: vec!["chess".to_string(), "atomic".to_string()],
+            }],
+        };
+        assert_eq!(EngineInfo::from(pb::EngineInfoResponse::from(info.clone())), info);
+    }
+
+    #[test]
+    fn test_benchmarks_survive_the_wire() {
+        let report = EngineBenchmarkDisplay {
+            id: "analysis".to_string(),
+            depth: 12,
+            positions: vec![BenchmarkPositionDisplay {
+                fen: "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".to_string(),
+                best_move: "e1d2".to_string(),
+                depth: Some(12),
+                nodes: Some(52_000),
+                time_ms: 40,
+            }],
+            total_nodes: Some(52_000),
+            total_time_ms: 40,
+            nodes_per_second: Some(1_300_000),
+        };
+        let back = EngineBenchmarkDisplay::try_from(pb::EngineBenchmark::from(report.clone())).unwrap();
+        assert_eq!(back.id, report.id);
+        assert_eq!(back.positions[0].nodes, Some(52_000));
+        assert_eq!(back.nodes_per_second, report.nodes_per_second);
+
+        let too_deep = pb::EngineBenchmark { depth: 300, ..Default::default() };
+        assert!(EngineBenchmarkDisplay::try_from(too_deep).is_err());
+    }
+
+    #[test]
+    fn test_errors_map_to_status_codes_and_back() {
+        assert!(matches!(error_of(status_of(EngineError::Timeout)), EngineError::Timeout));
+        assert!(matches!(error_of(status_of(EngineError::NotRunning)), EngineError::NotRunning));
+        assert!(matches!(error_of(status_of(EngineError::ParseError("x".to_string()))), EngineError::Unknown(_)));
+        assert!(matches!(
+            error_of(status_of(EngineError::UnsupportedVariant("crazyhouse".to_string()))),
+            EngineError::UnsupportedVariant(variant) if variant == "crazyhouse"
+        ));
+        assert_eq!(variant_of("").unwrap(), AnalysisVariant::Standard);
+        assert_eq!(variant_of("crazyhouse").unwrap(), AnalysisVariant::Crazyhouse);
+        assert!(variant_of("atomic").is_err());
+        assert_eq!(tier_of("").unwrap(), AnalysisTier::Guest);
+        assert_eq!(tier_of(AnalysisTier::Premium.as_str()).unwrap(), AnalysisTier::Premium);
+        assert!(tier_of("gold").is_err());
+        assert_eq!(priority_of(pb::JobPriority::Unspecified), JobPriority::Interactive);
+        assert_eq!(priority_of(pb::JobPriority::from(JobPriority::Batch)), JobPriority::Batch);
+    }
+}
@@ -0,0 +1,12 @@
+//! Internal gRPC API, so that the engine pool and matchmaking can run as
+//! processes of their own and scale apart from the actix API. Only meant
+//! for the private network between StarkMate services.
+
+pub mod engine;
+#[cfg(feature = "matchmaking")]
+pub mod matchmaking;
+
+/// Messages and service stubs generated from `proto/`.
+pub mod pb {
+    tonic::include_proto!("starkmate.internal.v1");
+}
@@ -0,0 +1,118 @@
+use chrono::Utc;
+use matchmaking::{MatchRequest, MatchType, MatchmakingResponse, MatchmakingService, Player};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::pb::matchmaking_server::Matchmaking;
+use crate::pb;
+
+/// Serves the matchmaking queues of this process over gRPC.
+pub struct MatchmakingRpc {
+    service: MatchmakingService,
+}
+
+impl MatchmakingRpc {
+    pub fn new(service: MatchmakingService) -> Self {
+        Self { service }
+    }
+}
+
+#[tonic::async_trait]
+impl Matchmaking for MatchmakingRpc {
+    async fn join_queue(&self, request: Request<pb::JoinQueueRequest>) -> Result<Response<pb::JoinQueueResponse>, Status> {
+        let request = request.into_inner();
+        let match_type = match_type_of(request.match_type())?;
+        let queued = MatchRequest {
+            id: Uuid::new_v4(),
+            player: Player {
+                wallet_address: request.wallet_address,
+                elo: request.elo,
+                join_time: Utc::now(),
+            },
+            match_type,
+            invite_address: request.invite_address,
+            max_elo_diff: request.max_elo_diff,
+        };
+        let response = self.service.join_queue(queued).await.map_err(Status::unavailable)?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn get_queue_status(
+        &self,
+        request: Request<pb::QueueStatusRequest>,
+    ) -> Result<Response<pb::QueueStatusResponse>, Status> {
+        let request_id = uuid_of(&request.into_inner().request_id)?;
+        let status = self
+            .service
+            .get_queue_status(request_id)
+            .await
+            .map_err(Status::unavailable)?
+            .ok_or_else(|| Status::not_found("Request not found"))?;
+        Ok(Response::new(pb::QueueStatusResponse {
+            position: status.position as u32,
+            estimated_wait_ms: status.estimated_wait_time.as_millis() as u64,
+            match_type: pb::MatchType::from(status.match_type).into(),
+        }))
+    }
+
+    async fn cancel_request(
+        &self,
+        request: Request<pb::CancelRequestRequest>,
+    ) -> Result<Response<pb::CancelRequestResponse>, Status> {
+        let request_id = uuid_of(&request.into_inner().request_id)?;
+        if !self.service.cancel_request(request_id).await.map_err(Status::unavailable)? {
+            return Err(Status::not_found("Request not found"));
+        }
+        Ok(Response::new(pb::CancelRequestResponse {}))
+    }
+
+    async fn accept_invite(&self, request: Request<pb::AcceptInviteRequest>) -> Result<Response<pb::JoinQueueResponse>, Status> {
+        let request = request.into_inner();
+        let inviter_request_id = uuid_of(&request.inviter_request_id)?;
+        let player = Player {
+            wallet_address: request.wallet_address,
+            elo: request.elo,
+            join_time: Utc::now(),
+        };
+        let response = self
+            .service
+            .accept_private_invite(inviter_request_id, player)
+            .await
+            .map_err(Status::unavailable)?
+            .ok_or_else(|| Status::not_found("Invite not found"))?;
+        Ok(Response::new(response.into()))
+    }
+}
+
+fn match_type_of(match_type: pb::MatchType) -> Result<MatchType, Status> {
+    match match_type {
+        pb::MatchType::Rated => Ok(MatchType::Rated),
+        pb::MatchType::Casual => Ok(MatchType::Casual),
+        pb::MatchType::Private => Ok(MatchType::Private),
+        pb::MatchType::Unspecified => Err(Status::invalid_argument("match_type is required")),
+    }
+}
+
+fn uuid_of(id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(id).map_err(|_| Status::invalid_argument(format!("'{}' is not a request ID", id)))
+}
+
+impl From<MatchType> for pb::MatchType {
+    fn from(value: MatchType) -> Self {
+        match value {
+            MatchType::Rated => Self::Rated,
+            MatchType::Casual => Self::Casual,
+            MatchType::Private => Self::Private,
+        }
+    }
+}
+
+impl From<MatchmakingResponse> for pb::JoinQueueResponse {
+    fn from(value: MatchmakingResponse) -> Self {
+        Self {
+            status: value.status,
+            match_id: value.match_id.map(|id| id.to_string()),
+            request_id: value.request_id.to_string(),
+        }
+    }
+}
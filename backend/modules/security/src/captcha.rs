@@ -0,0 +1,289 @@
+//! Abuse-prevention challenges for endpoints anyone can call.
+//!
+//! Clients fetch what to solve, then send the solution in the `X-Captcha`
+//! header. A solution is either a token from a CAPTCHA widget (hCaptcha or
+//! Cloudflare Turnstile), checked with the provider's `siteverify` endpoint,
+//! or the answer to a proof of work issued by the server, which costs a
+//! browser a moment but makes farming accounts by the thousand expensive.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CaptchaError {
+    Missing,
+    Rejected,
+    Expired,
+    Provider(String),
+}
+
+impl fmt::Display for CaptchaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "Captcha solution required in the X-Captcha header"),
+            Self::Rejected => write!(f, "Captcha solution is not valid"),
+            Self::Expired => write!(f, "Captcha challenge has expired"),
+            Self::Provider(e) => write!(f, "Captcha provider error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptchaError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaProvider {
+    Hcaptcha,
+    Turnstile,
+}
+
+impl CaptchaProvider {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "hcaptcha" => Some(Self::Hcaptcha),
+            "turnstile" => Some(Self::Turnstile),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hcaptcha => "hcaptcha",
+            Self::Turnstile => "turnstile",
+        }
+    }
+
+    fn verify_url(self) -> &'static str {
+        match self {
+            Self::Hcaptcha => "https://api.hcaptcha.com/siteverify",
+            Self::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+        }
+    }
+}
+
+/// What a client must solve before calling a protected endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptchaChallenge {
+    /// Render the provider's widget with `site_key` and send its token
+    Widget { provider: CaptchaProvider, site_key: String },
+    /// Find a `counter` whose SHA-256 of `"{challenge}:{counter}"` starts
+    /// with `difficulty` zero bits, and send `"{challenge}:{counter}"`
+    ProofOfWork {
+        challenge: String,
+        difficulty: u8,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+/// Issues challenges and checks their solutions.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    fn challenge(&self) -> CaptchaChallenge;
+
+    /// Check `solution`, sent by a client at `remote_ip` when known.
+    async fn verify(&self, solution: &str, remote_ip: Option<&str>) -> Result<(), CaptchaError>;
+}
+
+/// Checks widget tokens with the provider.
+pub struct SiteVerifyCaptcha {
+    provider: CaptchaProvider,
+    site_key: String,
+    secret: String,
+    client: reqwest::Client,
+}
+
+impl SiteVerifyCaptcha {
+    pub fn new(provider: CaptchaProvider, site_key: String, secret: String, timeout: std::time::Duration) -> Self {
+        Self {
+            provider,
+            site_key,
+            secret,
+            client: reqwest::Client::builder().timeout(timeout).build().unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for SiteVerifyCaptcha {
+    fn challenge(&self) -> CaptchaChallenge {
+        CaptchaChallenge::Widget {
+            provider: self.provider,
+            site_key: self.site_key.clone(),
+        }
+    }
+
+    async fn verify(&self, solution: &str, remote_ip: Option<&str>) -> Result<(), CaptchaError> {
+        let mut form = vec![("secret", self.secret.as_str()), ("response", solution)];
+        if let Some(remote_ip) = remote_ip {
+            form.push(("remoteip", remote_ip));
+        }
+        let response: Value = self
+            .client
+            .post(self.provider.verify_url())
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| CaptchaError::Provider(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| CaptchaError::Provider(e.to_string()))?;
+
+        if response["success"].as_bool() == Some(true) {
+            Ok(())
+        } else {
+            Err(CaptchaError::Rejected)
+        }
+    }
+}
+
+/// Hashcash-style proof of work. Challenges are single-use; clones share
+/// the outstanding ones, so it can serve every worker.
+#[derive(Clone)]
+pub struct ProofOfWork {
+    difficulty: u8,
+    ttl: Duration,
+    issued: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+}
+
+impl ProofOfWork {
+    pub fn new(difficulty: u8, ttl: Duration) -> Self {
+        Self {
+            difficulty: difficulty.min(32),
+            ttl,
+            issued: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for ProofOfWork {
+    fn challenge(&self) -> CaptchaChallenge {
+        let challenge = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        let expires_at = Utc::now() + self.ttl;
+
+        let mut issued = self.issued.lock().unwrap();
+        let now = Utc::now();
+        issued.retain(|_, expires_at| *expires_at > now);
+        issued.insert(challenge.clone(), expires_at);
+
+        CaptchaChallenge::ProofOfWork {
+            challenge,
+            difficulty: self.difficulty,
+            expires_at,
+        }
+    }
+
+    async fn verify(&self, solution: &str, _remote_ip: Option<&str>) -> Result<(), CaptchaError> {
+        let (challenge, _) = solution.split_once(':').ok_or(CaptchaError::Rejected)?;
+        if leading_zero_bits(&Sha256::digest(solution.as_bytes())) < u32::from(self.difficulty) {
+            return Err(CaptchaError::Rejected);
+        }
+        // Only a correct answer uses the challenge up, so a typo can be retried
+        let expires_at = self
+            .issued
+            .lock()
+            .unwrap()
+            .remove(challenge)
+            .ok_or(CaptchaError::Rejected)?;
+        if expires_at <= Utc::now() {
+            return Err(CaptchaError::Expired);
+        }
+        Ok(())
+    }
+}
+
+/// The configured verifier, shared by every worker. Tests use their own
+/// [`CaptchaVerifier`] to get past it.
+#[derive(Clone)]
+pub struct Captcha {
+    verifier: Arc<dyn CaptchaVerifier>,
+}
+
+impl Captcha {
+    pub fn new(verifier: Arc<dyn CaptchaVerifier>) -> Self {
+        Self { verifier }
+    }
+
+    pub fn challenge(&self) -> CaptchaChallenge {
+        self.verifier.challenge()
+    }
+
+    pub async fn verify(&self, solution: Option<&str>, remote_ip: Option<&str>) -> Result<(), CaptchaError> {
+        match solution.map(str::trim) {
+            Some(solution) if !solution.is_empty() => self.verifier.verify(solution, remote_ip).await,
+            _ => Err(CaptchaError::Missing),
+        }
+    }
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(challenge: &str, difficulty: u8) -> String {
+        (0u64..)
+            .map(|counter| format!("{}:{}", challenge, counter))
+            .find(|solution| leading_zero_bits(&Sha256::digest(solution.as_bytes())) >= u32::from(difficulty))
+            .unwrap()
+    }
+
+    fn issue(pow: &ProofOfWork) -> String {
+        match pow.challenge() {
+            CaptchaChallenge::ProofOfWork { challenge, .. } => challenge,
+            other => panic!("unexpected challenge {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xff]), 0);
+        assert_eq!(leading_zero_bits(&[0x00, 0x10]), 11);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00]), 16);
+    }
+
+    #[tokio::test]
+    async fn test_proof_of_work_is_single_use() {
+        let pow = ProofOfWork::new(8, Duration::minutes(5));
+        let solution = solve(&issue(&pow), 8);
+
+        assert_eq!(pow.verify(&solution, None).await, Ok(()));
+        assert_eq!(pow.verify(&solution, None).await, Err(CaptchaError::Rejected));
+    }
+
+    #[tokio::test]
+    async fn test_proof_of_work_rejects_unissued_and_expired_challenges() {
+        let pow = ProofOfWork::new(4, Duration::minutes(5));
+        assert_eq!(pow.verify(&solve("made-up", 4), None).await, Err(CaptchaError::Rejected));
+
+        let expired = ProofOfWork::new(4, Duration::seconds(-1));
+        let solution = solve(&issue(&expired), 4);
+        assert_eq!(expired.verify(&solution, None).await, Err(CaptchaError::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_missing_solution_is_refused_before_the_verifier() {
+        let captcha = Captcha::new(Arc::new(ProofOfWork::new(4, Duration::minutes(5))));
+        assert_eq!(captcha.verify(None, None).await, Err(CaptchaError::Missing));
+        assert_eq!(captcha.verify(Some("  "), None).await, Err(CaptchaError::Missing));
+    }
+}
@@ -0,0 +1,317 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::Error,
+    http::{
+        header::{HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    web::Bytes,
+    HttpResponse,
+};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Request header carrying the client-chosen key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Response header set on replayed responses
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+/// Longest accepted key
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// A response kept for replay.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    fn into_response(self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status).body(self.body);
+        *response.headers_mut() = self.headers;
+        response
+    }
+}
+
+/// Outcome of looking up a key before running the request.
+#[derive(Debug)]
+pub enum IdempotencyLookup {
+    /// First use of the key; the request runs and its response is stored
+    Proceed,
+    /// The key was used for the same request before
+    Replay(CachedResponse),
+    /// The first request with this key has not finished yet
+    InProgress,
+    /// The key was used for a different method or path
+    Mismatch,
+}
+
+#[derive(Debug)]
+enum Entry {
+    InFlight { request: String, since: Instant },
+    Done { request: String, response: CachedResponse, since: Instant },
+}
+
+impl Entry {
+    fn since(&self) -> Instant {
+        match self {
+            Entry::InFlight { since, .. } | Entry::Done { since, .. } => *since,
+        }
+    }
+}
+
+/// In-memory store of responses by idempotency key, shared by every worker.
+/// Entries expire after `ttl`.
+#[derive(Debug, Clone)]
+pub struct IdempotencyStore {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    ttl: Duration,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        IdempotencyStore {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    /// Reserve `key` for `request` (method and path), or report why the
+    /// request must not run.
+    pub fn begin(&self, key: &str, request: &str) -> IdempotencyLookup {
+        let mut entries = self.entries.lock().unwrap();
+        let ttl = self.ttl;
+        entries.retain(|_, entry| entry.since().elapsed() < ttl);
+
+        match entries.get(key) {
+            Some(Entry::InFlight { request: first, .. }) | Some(Entry::Done { request: first, .. })
+                if first != request =>
+            {
+                IdempotencyLookup::Mismatch
+            }
+            Some(Entry::InFlight { .. }) => IdempotencyLookup::InProgress,
+            Some(Entry::Done { response, .. }) => IdempotencyLookup::Replay(response.clone()),
+            None => {
+                entries.insert(
+                    key.to_string(),
+                    Entry::InFlight {
+                        request: request.to_string(),
+                        since: Instant::now(),
+                    },
+                );
+                IdempotencyLookup::Proceed
+            }
+        }
+    }
+
+    /// Store the response of a request started with `begin`.
+    pub fn complete(&self, key: &str, request: &str, response: CachedResponse) {
+        self.entries.lock().unwrap().insert(
+            key.to_string(),
+            Entry::Done {
+                request: request.to_string(),
+                response,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    /// Release a key whose request failed, so that it can be retried.
+    pub fn release(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// A key reserved with `begin`. Released when dropped before its response is
+/// stored, so a request cut short by the client going away can be retried.
+struct Reservation {
+    store: IdempotencyStore,
+    key: String,
+    settled: bool,
+}
+
+impl Reservation {
+    fn complete(mut self, request: &str, response: CachedResponse) {
+        self.store.complete(&self.key, request, response);
+        self.settled = true;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.store.release(&self.key);
+        }
+    }
+}
+
+/// Middleware replaying the stored response when a mutating request is
+/// retried with the same `Idempotency-Key`. Keys are scoped to the
+/// `Authorization` header, so clients cannot see each other's responses;
+/// requests without one, such as logins, are never deduplicated. Server
+/// errors are not stored.
+pub struct IdempotencyMiddleware {
+    store: IdempotencyStore,
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(store: IdempotencyStore) -> Self {
+        IdempotencyMiddleware { store }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for IdempotencyMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = IdempotencyMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(IdempotencyMiddlewareService {
+            service,
+            store: self.store.clone(),
+        })
+    }
+}
+
+pub struct IdempotencyMiddlewareService<S> {
+    service: S,
+    store: IdempotencyStore,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let mutating = matches!(
+            *req.method(),
+            Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+        );
+        let key_header = req.headers().get(IDEMPOTENCY_KEY_HEADER).cloned();
+        // Anonymous callers share no scope a key could safely live in
+        let auth = req.headers().get("Authorization").cloned();
+
+        let (key_header, auth) = match (key_header, auth) {
+            (Some(value), Some(auth)) if mutating => (value, auth),
+            _ => {
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_boxed_body()) });
+            }
+        };
+
+        let key = match key_header.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key.to_string(),
+            _ => {
+                return Box::pin(async move {
+                    Ok(req.into_response(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Idempotency-Key must be 1 to 255 visible ASCII characters",
+                    )))
+                });
+            }
+        };
+
+        // Scope the key to the caller without keeping their token around
+        let mut hasher = Sha256::new();
+        hasher.update(auth.as_bytes());
+        let scoped_key = format!("{:x}:{}", hasher.finalize(), key);
+        let request = format!("{} {}", req.method(), req.uri());
+
+        match self.store.begin(&scoped_key, &request) {
+            IdempotencyLookup::Proceed => {}
+            IdempotencyLookup::Replay(cached) => {
+                return Box::pin(async move { Ok(req.into_response(replay(cached))) });
+            }
+            IdempotencyLookup::InProgress => {
+                return Box::pin(async move {
+                    Ok(req.into_response(error_response(
+                        StatusCode::CONFLICT,
+                        "A request with this Idempotency-Key is still in progress",
+                    )))
+                });
+            }
+            IdempotencyLookup::Mismatch => {
+                return Box::pin(async move {
+                    Ok(req.into_response(error_response(
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Idempotency-Key was already used for a different request",
+                    )))
+                });
+            }
+        }
+
+        let reservation = Reservation {
+            store: self.store.clone(),
+            key: scoped_key,
+            settled: false,
+        };
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let status = res.status();
+            let headers = res.headers().clone();
+            let (req, res) = res.into_parts();
+            let bytes = match body::to_bytes(res.into_body()).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok(ServiceResponse::new(
+                        req,
+                        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response body"),
+                    ));
+                }
+            };
+
+            let cached = CachedResponse {
+                status,
+                headers,
+                body: bytes,
+            };
+            if !status.is_server_error() {
+                reservation.complete(&request, cached.clone());
+            }
+
+            Ok(ServiceResponse::new(req, cached.into_response()))
+        })
+    }
+}
+
+fn replay(cached: CachedResponse) -> HttpResponse {
+    let mut response = cached.into_response();
+    response.headers_mut().insert(
+        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
+
+fn error_response(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(json!({
+        "error": message,
+        "code": status.as_u16()
+    }))
+}
@@ -1,19 +1,73 @@
 use actix_web::{
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    error::{Error, ErrorUnauthorized},
+    error::{Error, ErrorForbidden, ErrorUnauthorized},
     body::{BoxBody, MessageBody},
-    HttpMessage, HttpResponse,
+    web, HttpMessage, HttpResponse,
 };
 use futures_util::future::{ok, LocalBoxFuture, Ready};
 use std::task::{Context, Poll};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::errors::ErrorKind;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::revocation::TokenDenylist;
+
+/// Version of the claims written into new tokens. Version 1 tokens, which
+/// carry no `ver`, are still accepted; they have no player profile.
+pub const CLAIMS_VERSION: u8 = 2;
+
+fn legacy_claims_version() -> u8 {
+    1
+}
+
+/// Account flags carried in a token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountFlags {
+    #[serde(default)]
+    pub bot: bool,
+    #[serde(default)]
+    pub banned: bool,
+    #[serde(default)]
+    pub titled: bool,
+}
+
+/// The player behind a token as of when it was issued, so services can
+/// authorize without a database round trip. It is only as fresh as the
+/// token; anything that must see a role or ban the moment it changes should
+/// still ask the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub player_id: Uuid,
+    /// Roles as stored, e.g. `moderator`
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub flags: AccountFlags,
+    /// Rating by time control, e.g. `blitz`, for those the player has played
+    #[serde(default)]
+    pub ratings: BTreeMap<String, i32>,
+    /// Verified title, e.g. `GM`, set along with `flags.titled`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl PlayerProfile {
+    /// Whether the player holds `role`. Admins implicitly hold every role.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|held| held == role || held == "admin")
+    }
+}
 
 /// JWT Claims structure containing user identification and expiration
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
+    /// Schema version of these claims; see [`CLAIMS_VERSION`]
+    #[serde(default = "legacy_claims_version")]
+    pub ver: u8,
     /// Subject (user ID)
     pub sub: String,
     /// User ID as integer
@@ -24,6 +78,16 @@ pub struct Claims {
     pub exp: usize,
     /// Issued at time (Unix timestamp)
     pub iat: usize,
+    /// Set on guest tokens, which belong to no account; `sub` is the guest ID
+    #[serde(default)]
+    pub guest: bool,
+    /// Token ID, by which a single token is revoked
+    #[serde(default)]
+    pub jti: Option<String>,
+    /// Missing on guest tokens, version 1 tokens and tokens issued for a
+    /// username with no player behind it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<PlayerProfile>,
 }
 
 /// JWT Service for token generation and validation
@@ -44,17 +108,37 @@ impl JwtService {
 
     /// Generate a new JWT token for a user
     pub fn generate_token(&self, user_id: i32, username: &str) -> Result<String, jsonwebtoken::errors::Error> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as usize;
+        self.issue(user_id, username, None)
+    }
+
+    /// Generate a token for a user that also carries their player profile
+    pub fn generate_player_token(
+        &self,
+        user_id: i32,
+        username: &str,
+        profile: PlayerProfile,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        self.issue(user_id, username, Some(profile))
+    }
+
+    fn issue(
+        &self,
+        user_id: i32,
+        username: &str,
+        player: Option<PlayerProfile>,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = unix_now();
 
         let claims = Claims {
+            ver: CLAIMS_VERSION,
             sub: user_id.to_string(),
             user_id,
             username: username.to_string(),
             exp: now + self.expiration_time,
             iat: now,
+            guest: false,
+            jti: Some(Uuid::new_v4().to_string()),
+            player,
         };
 
         let token = encode(
@@ -66,7 +150,31 @@ impl JwtService {
         Ok(token)
     }
 
-    /// Validate and decode a JWT token
+    /// Generate a token for a guest session, valid for `ttl` seconds
+    pub fn generate_guest_token(&self, guest_id: Uuid, name: &str, ttl: usize) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = unix_now();
+
+        let claims = Claims {
+            ver: CLAIMS_VERSION,
+            sub: guest_id.to_string(),
+            user_id: 0,
+            username: name.to_string(),
+            exp: now + ttl,
+            iat: now,
+            guest: true,
+            jti: Some(Uuid::new_v4().to_string()),
+            player: None,
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret_key.as_ref()),
+        )
+    }
+
+    /// Validate and decode a JWT token. Tokens of a claims version this
+    /// build does not know are refused.
     pub fn validate_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
         let token_data = decode::<Claims>(
             token,
@@ -74,20 +182,33 @@ impl JwtService {
             &Validation::new(Algorithm::HS256),
         )?;
 
-        Ok(token_data.claims)
+        let mut claims = token_data.claims;
+        match claims.ver {
+            // Version 1 had no profile; never trust one found there
+            1 => claims.player = None,
+            CLAIMS_VERSION => {}
+            _ => return Err(ErrorKind::InvalidToken.into()),
+        }
+        Ok(claims)
     }
 
     /// Extract token from Authorization header
     pub fn extract_token_from_header(auth_header: &str) -> Option<String> {
-        if auth_header.starts_with("Bearer ") {
-            Some(auth_header[7..].to_string())
-        } else {
-            None
-        }
+        auth_header.strip_prefix("Bearer ").map(str::to_string)
     }
 }
 
-/// Middleware for JWT authentication
+/// Seconds since the Unix epoch; a clock set before 1970 reads as zero
+/// rather than panicking.
+fn unix_now() -> usize {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as usize)
+        .unwrap_or_default()
+}
+
+/// Middleware for JWT authentication. Tokens revoked in the app's
+/// [`TokenDenylist`], when one is registered as app data, are refused.
 pub struct JwtAuthMiddleware {
     secret_key: Rc<String>,
     expiration_time: usize,
@@ -117,7 +238,7 @@ where
 
     fn new_transform(&self, service: S) -> Self::Future {
         ok(JwtAuthMiddlewareService {
-            service,
+            service: Rc::new(service),
             secret_key: self.secret_key.clone(),
             expiration_time: self.expiration_time,
         })
@@ -125,7 +246,7 @@ where
 }
 
 pub struct JwtAuthMiddlewareService<S> {
-    service: S,
+    service: Rc<S>,
     secret_key: Rc<String>,
     expiration_time: usize,
 }
@@ -167,12 +288,24 @@ where
                     // Validate token
                     let jwt_service = JwtService::new((*secret_key).clone(), expiration_time);
                     match jwt_service.validate_token(&token) {
+                        // Guests have no account behind them
+                        Ok(claims) if claims.guest => {
+                            Box::pin(async move {
+                                Err(ErrorForbidden("Guest sessions cannot use this endpoint; register to continue"))
+                            })
+                        }
                         Ok(claims) => {
-                            // Store claims in request extensions
-                            req.extensions_mut().insert(claims);
-                            let fut = self.service.call(req);
-                            Box::pin(async move { 
-                                let res = fut.await?;
+                            let service = self.service.clone();
+                            let denylist = req.app_data::<web::Data<TokenDenylist>>().cloned();
+                            Box::pin(async move {
+                                if let Some(denylist) = denylist {
+                                    if denylist.is_revoked(&claims).await {
+                                        return Err(ErrorUnauthorized("Token has been revoked"));
+                                    }
+                                }
+                                // Store claims in request extensions
+                                req.extensions_mut().insert(claims);
+                                let res = service.call(req).await?;
                                 Ok(res.map_into_boxed_body())
                             })
                         }
@@ -196,3 +329,50 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sign(claims: serde_json::Value) -> String {
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(b"test-secret")).unwrap()
+    }
+
+    #[test]
+    fn test_player_tokens_carry_the_profile() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let profile = PlayerProfile {
+            player_id: Uuid::new_v4(),
+            roles: vec!["moderator".to_string()],
+            flags: AccountFlags { titled: true, ..Default::default() },
+            ratings: BTreeMap::from([("blitz".to_string(), 2100)]),
+            title: Some("GM".to_string()),
+        };
+        let token = jwt_service.generate_player_token(1, "magnus", profile.clone()).unwrap();
+
+        let claims = jwt_service.validate_token(&token).unwrap();
+        assert_eq!(claims.ver, CLAIMS_VERSION);
+        assert_eq!(claims.player, Some(profile));
+        assert!(claims.player.unwrap().has_role("moderator"));
+    }
+
+    #[test]
+    fn test_claims_versions() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let now = unix_now();
+        let claims = |extra: serde_json::Value| {
+            let mut claims = json!({"sub": "1", "user_id": 1, "username": "magnus", "exp": now + 60, "iat": now});
+            claims.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            sign(claims)
+        };
+
+        // Version 1 tokens predate profiles, so one found there is dropped
+        let profile = json!({"player_id": Uuid::new_v4(), "roles": ["admin"]});
+        let legacy = jwt_service.validate_token(&claims(json!({"player": profile}))).unwrap();
+        assert_eq!(legacy.ver, 1);
+        assert!(legacy.player.is_none());
+
+        assert!(jwt_service.validate_token(&claims(json!({"ver": CLAIMS_VERSION + 1}))).is_err());
+    }
+}
@@ -1,5 +1,13 @@
+pub mod captcha;
+pub mod idempotency;
 pub mod jwt;
+pub mod revocation;
 pub mod token_service;
+pub mod wallet;
 
-pub use jwt::{JwtAuthMiddleware, JwtService, Claims};
+pub use captcha::{Captcha, CaptchaChallenge, CaptchaError, CaptchaProvider, CaptchaVerifier, ProofOfWork, SiteVerifyCaptcha};
+pub use idempotency::{IdempotencyMiddleware, IdempotencyStore};
+pub use jwt::{AccountFlags, Claims, JwtAuthMiddleware, JwtService, PlayerProfile, CLAIMS_VERSION};
+pub use revocation::{MemoryRevocationStore, RedisRevocationStore, RevocationError, RevocationStore, TokenDenylist};
 pub use token_service::{TokenService, TokenServiceError};
+pub use wallet::{RpcSignatureVerifier, SignInChallenge, SignInDomain, SignatureVerifier, WalletAuth, WalletError};
@@ -0,0 +1,307 @@
+//! Access tokens cut off before they expire.
+//!
+//! Logging out revokes the one token by its `jti`; banning or closing an
+//! account revokes every token the account was issued up to that moment.
+//! Access tokens are otherwise stateless, so `JwtAuthMiddleware` checks the
+//! denylist on each request when one is configured. Lookups are cached for
+//! a few seconds, so Redis is not hit on every call, and a failing store
+//! lets requests through rather than locking everyone out.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, RedisResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::jwt::Claims;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RevocationError(pub String);
+
+impl fmt::Display for RevocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token denylist error: {}", self.0)
+    }
+}
+
+impl std::error::Error for RevocationError {}
+
+/// Where revocations are kept: `key` was revoked at `at` (Unix seconds),
+/// and the entry may be dropped after `ttl` seconds.
+#[async_trait]
+pub trait RevocationStore: Send + Sync {
+    async fn revoke(&self, key: &str, at: i64, ttl: u64) -> Result<(), RevocationError>;
+
+    async fn revoked_at(&self, key: &str) -> Result<Option<i64>, RevocationError>;
+}
+
+/// Revocations held by this process only, for single-instance deployments.
+#[derive(Clone, Default)]
+pub struct MemoryRevocationStore {
+    /// Revoked at, and when the entry expires
+    entries: Arc<Mutex<HashMap<String, (i64, i64)>>>,
+}
+
+impl MemoryRevocationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RevocationStore for MemoryRevocationStore {
+    async fn revoke(&self, key: &str, at: i64, ttl: u64) -> Result<(), RevocationError> {
+        let now = Utc::now().timestamp();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(key.to_string(), (at, now + ttl as i64));
+        Ok(())
+    }
+
+    async fn revoked_at(&self, key: &str) -> Result<Option<i64>, RevocationError> {
+        let now = Utc::now().timestamp();
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(at, _)| *at))
+    }
+}
+
+/// Revocations shared by every API instance through Redis. The connection
+/// is opened on first use and again after an error, so Redis may start after
+/// the API or restart under it.
+#[derive(Clone)]
+pub struct RedisRevocationStore {
+    client: redis::Client,
+    conn: Arc<tokio::sync::Mutex<Option<MultiplexedConnection>>>,
+}
+
+impl RedisRevocationStore {
+    pub fn new(redis_url: &str) -> Result<Self, RevocationError> {
+        let client = redis::Client::open(redis_url).map_err(|e| RevocationError(e.to_string()))?;
+        Ok(Self {
+            client,
+            conn: Arc::new(tokio::sync::Mutex::new(None)),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<MultiplexedConnection> {
+        let mut conn = self.conn.lock().await;
+        if let Some(conn) = conn.as_ref() {
+            return Ok(conn.clone());
+        }
+        let connected = self.client.get_multiplexed_async_connection().await?;
+        *conn = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// Drop the connection after `result` failed, so the next call reconnects.
+    async fn checked<T>(&self, result: RedisResult<T>) -> Result<T, RevocationError> {
+        if result.is_err() {
+            *self.conn.lock().await = None;
+        }
+        result.map_err(|e| RevocationError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl RevocationStore for RedisRevocationStore {
+    async fn revoke(&self, key: &str, at: i64, ttl: u64) -> Result<(), RevocationError> {
+        let result = match self.connection().await {
+            Ok(mut conn) => conn.set_ex(format!("revoked:{}", key), at, ttl.max(1)).await,
+            Err(e) => Err(e),
+        };
+        self.checked(result).await
+    }
+
+    async fn revoked_at(&self, key: &str) -> Result<Option<i64>, RevocationError> {
+        let result = match self.connection().await {
+            Ok(mut conn) => conn.get(format!("revoked:{}", key)).await,
+            Err(e) => Err(e),
+        };
+        self.checked(result).await
+    }
+}
+
+/// Lookups by key: when the key was revoked, if it was, and when it was asked
+type LookupCache = HashMap<String, (Option<i64>, Instant)>;
+
+/// The configured store behind a short-lived cache, shared by every worker.
+#[derive(Clone)]
+pub struct TokenDenylist {
+    store: Arc<dyn RevocationStore>,
+    /// Lifetime of access tokens, and so of account-wide revocations
+    token_lifetime: u64,
+    cache_ttl: Duration,
+    cache: Arc<Mutex<LookupCache>>,
+}
+
+impl TokenDenylist {
+    pub fn new(store: Arc<dyn RevocationStore>, token_lifetime: u64, cache_ttl: Duration) -> Self {
+        Self {
+            store,
+            token_lifetime,
+            cache_ttl,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Revoke the token carrying `claims`, until it would have expired.
+    /// Tokens issued without a `jti` can only be revoked with their account.
+    pub async fn revoke_token(&self, claims: &Claims) -> Result<(), RevocationError> {
+        let Some(jti) = &claims.jti else {
+            return Ok(());
+        };
+        let now = Utc::now().timestamp();
+        let ttl = (claims.exp as i64 - now).max(1) as u64;
+        self.revoke(&token_key(jti), now, ttl).await
+    }
+
+    /// Revoke every token issued to `username` up to now.
+    pub async fn revoke_account(&self, username: &str) -> Result<(), RevocationError> {
+        let now = Utc::now().timestamp();
+        self.revoke(&account_key(username), now, self.token_lifetime).await
+    }
+
+    /// Whether the token carrying `claims` was revoked. Store errors are
+    /// logged and count as not revoked.
+    pub async fn is_revoked(&self, claims: &Claims) -> bool {
+        if let Some(jti) = &claims.jti {
+            if self.lookup(&token_key(jti)).await.is_some() {
+                return true;
+            }
+        }
+        matches!(self.lookup(&account_key(&claims.username)).await, Some(at) if claims.iat as i64 <= at)
+    }
+
+    async fn revoke(&self, key: &str, at: i64, ttl: u64) -> Result<(), RevocationError> {
+        self.store.revoke(key, at, ttl).await?;
+        // Seen at once by this instance; others within the cache TTL
+        self.remember(key, Some(at));
+        Ok(())
+    }
+
+    async fn lookup(&self, key: &str) -> Option<i64> {
+        let cached = self.cache.lock().unwrap().get(key).copied();
+        if let Some((revoked_at, cached_at)) = cached {
+            if cached_at.elapsed() < self.cache_ttl {
+                return revoked_at;
+            }
+        }
+        match self.store.revoked_at(key).await {
+            Ok(revoked_at) => {
+                self.remember(key, revoked_at);
+                revoked_at
+            }
+            Err(e) => {
+                log::error!("Failed to check token denylist: {}", e);
+                None
+            }
+        }
+    }
+
+    fn remember(&self, key: &str, revoked_at: Option<i64>) {
+        let mut cache = self.cache.lock().unwrap();
+        let cache_ttl = self.cache_ttl;
+        cache.retain(|_, (_, cached_at)| cached_at.elapsed() < cache_ttl);
+        cache.insert(key.to_string(), (revoked_at, Instant::now()));
+    }
+}
+
+fn token_key(jti: &str) -> String {
+    format!("jti:{}", jti)
+}
+
+fn account_key(username: &str) -> String {
+    format!("account:{}", username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt::JwtService;
+
+    fn memory_denylist(cache_ttl: Duration) -> (TokenDenylist, MemoryRevocationStore) {
+        let store = MemoryRevocationStore::new();
+        (TokenDenylist::new(Arc::new(store.clone()), 3600, cache_ttl), store)
+    }
+
+    fn claims(jwt_service: &JwtService, username: &str) -> Claims {
+        let token = jwt_service.generate_token(1, username).unwrap();
+        jwt_service.validate_token(&token).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_revoking_a_token_leaves_the_others() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let (denylist, _) = memory_denylist(Duration::from_secs(5));
+        let first = claims(&jwt_service, "magnus");
+        let second = claims(&jwt_service, "magnus");
+
+        denylist.revoke_token(&first).await.unwrap();
+        assert!(denylist.is_revoked(&first).await);
+        assert!(!denylist.is_revoked(&second).await);
+    }
+
+    #[tokio::test]
+    async fn test_revoking_an_account_cuts_off_earlier_tokens_only() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let (denylist, _) = memory_denylist(Duration::from_secs(5));
+        let mut earlier = claims(&jwt_service, "magnus");
+        earlier.iat -= 60;
+        let mut later = claims(&jwt_service, "magnus");
+        later.iat += 60;
+
+        denylist.revoke_account("magnus").await.unwrap();
+        assert!(denylist.is_revoked(&earlier).await);
+        assert!(!denylist.is_revoked(&later).await);
+        assert!(!denylist.is_revoked(&claims(&jwt_service, "hikaru")).await);
+    }
+
+    #[tokio::test]
+    async fn test_lookups_are_cached_until_the_ttl() {
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let token = claims(&jwt_service, "magnus");
+
+        // Revoked by another instance after this one looked
+        let (denylist, store) = memory_denylist(Duration::from_secs(60));
+        assert!(!denylist.is_revoked(&token).await);
+        store.revoke(&token_key(token.jti.as_ref().unwrap()), 0, 60).await.unwrap();
+        assert!(!denylist.is_revoked(&token).await);
+
+        let (denylist, store) = memory_denylist(Duration::ZERO);
+        assert!(!denylist.is_revoked(&token).await);
+        store.revoke(&token_key(token.jti.as_ref().unwrap()), 0, 60).await.unwrap();
+        assert!(denylist.is_revoked(&token).await);
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_refuses_revoked_tokens() {
+        use crate::jwt::JwtAuthMiddleware;
+        use actix_web::{test, web, App, HttpResponse};
+
+        let jwt_service = JwtService::new("test-secret".to_string(), 3600);
+        let (denylist, _) = memory_denylist(Duration::from_secs(5));
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(denylist.clone())).service(
+                web::resource("/")
+                    .wrap(JwtAuthMiddleware::new("test-secret".to_string(), 3600))
+                    .to(HttpResponse::Ok),
+            ),
+        )
+        .await;
+        let token = jwt_service.generate_token(1, "magnus").unwrap();
+        let request = || test::TestRequest::get().uri("/").insert_header(("Authorization", format!("Bearer {}", token)));
+
+        let response = test::call_service(&app, request().to_request()).await;
+        assert!(response.status().is_success());
+
+        denylist.revoke_token(&jwt_service.validate_token(&token).unwrap()).await.unwrap();
+        let error = test::try_call_service(&app, request().to_request()).await.unwrap_err();
+        assert_eq!(error.as_response_error().status_code(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+}
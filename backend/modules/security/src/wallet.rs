@@ -0,0 +1,398 @@
+//! Sign-In with StarkNet.
+//!
+//! The server issues a single-use nonce for an account address as SNIP-12
+//! (revision 0) typed data, the wallet signs it, and the account contract
+//! itself is asked whether the signature is valid. Asking the contract keeps
+//! every account type working, whatever its signature scheme.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::Serialize;
+use serde_json::{json, Value};
+use starknet_core::crypto::compute_hash_on_elements;
+use starknet_core::types::FieldElement;
+use starknet_core::utils::{cairo_short_string_to_felt, get_selector_from_name, starknet_keccak};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+const DOMAIN_TYPE: &str = "StarkNetDomain(name:felt,version:felt,chainId:felt)";
+const SIGN_IN_TYPE: &str = "SignIn(address:felt,nonce:felt,expiresAt:felt)";
+const MESSAGE_PREFIX: &str = "StarkNet Message";
+
+/// `VALID` as a short string, returned by SNIP-6 accounts for good signatures.
+const VALID: u64 = 0x56414c4944;
+
+/// `CONTRACT_ERROR` in the RPC spec; accounts often revert on bad signatures.
+const CONTRACT_ERROR_CODE: i64 = 40;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WalletError {
+    InvalidAddress,
+    InvalidSignature,
+    ChallengeNotFound,
+    ChallengeExpired,
+    Node(String),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress => write!(f, "Address is not a StarkNet account address"),
+            Self::InvalidSignature => write!(f, "Signature does not match the challenge"),
+            Self::ChallengeNotFound => write!(f, "No challenge was issued for this address and nonce"),
+            Self::ChallengeExpired => write!(f, "Challenge has expired"),
+            Self::Node(e) => write!(f, "StarkNet node error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+/// Checks signatures on behalf of account contracts.
+#[async_trait]
+pub trait SignatureVerifier: Send + Sync {
+    async fn is_valid_signature(
+        &self,
+        account: FieldElement,
+        hash: FieldElement,
+        signature: &[FieldElement],
+    ) -> Result<bool, WalletError>;
+}
+
+/// Asks the account contract through a StarkNet JSON-RPC node.
+pub struct RpcSignatureVerifier {
+    rpc_url: String,
+    client: reqwest::Client,
+}
+
+impl RpcSignatureVerifier {
+    pub fn new(rpc_url: String, timeout: std::time::Duration) -> Self {
+        Self {
+            rpc_url,
+            client: reqwest::Client::builder().timeout(timeout).build().unwrap_or_default(),
+        }
+    }
+
+    // Output of a view call, or `None` when the contract reverted
+    async fn call(
+        &self,
+        contract: FieldElement,
+        entry_point: &str,
+        calldata: &[FieldElement],
+    ) -> Result<Option<Vec<FieldElement>>, WalletError> {
+        let selector = get_selector_from_name(entry_point).map_err(|e| WalletError::Node(e.to_string()))?;
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_call",
+            "params": {
+                "request": {
+                    "contract_address": hex(contract),
+                    "entry_point_selector": hex(selector),
+                    "calldata": calldata.iter().copied().map(hex).collect::<Vec<_>>(),
+                },
+                "block_id": "latest",
+            },
+        });
+        let response: Value = self
+            .client
+            .post(&self.rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| WalletError::Node(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| WalletError::Node(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            if error["code"].as_i64() == Some(CONTRACT_ERROR_CODE) {
+                return Ok(None);
+            }
+            return Err(WalletError::Node(error["message"].as_str().unwrap_or_default().to_string()));
+        }
+        response["result"]
+            .as_array()
+            .ok_or_else(|| WalletError::Node("call result is not a list".to_string()))?
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .and_then(|value| FieldElement::from_hex_be(value).ok())
+                    .ok_or_else(|| WalletError::Node(format!("expected a felt, got {}", value)))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+}
+
+#[async_trait]
+impl SignatureVerifier for RpcSignatureVerifier {
+    async fn is_valid_signature(
+        &self,
+        account: FieldElement,
+        hash: FieldElement,
+        signature: &[FieldElement],
+    ) -> Result<bool, WalletError> {
+        let mut calldata = vec![hash, FieldElement::from(signature.len())];
+        calldata.extend_from_slice(signature);
+
+        // SNIP-6 accounts, then older ones with the camelCase entry point
+        for entry_point in ["is_valid_signature", "isValidSignature"] {
+            if let Some(output) = self.call(account, entry_point, &calldata).await? {
+                return Ok(matches!(output.first(), Some(felt) if *felt == FieldElement::from(VALID) || *felt == FieldElement::ONE));
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// SNIP-12 domain the sign-in message is bound to.
+#[derive(Debug, Clone)]
+pub struct SignInDomain {
+    pub name: String,
+    pub version: String,
+    /// e.g. `SN_MAIN` or `SN_SEPOLIA`
+    pub chain_id: String,
+}
+
+/// A nonce issued to an address, with the typed data the wallet must sign.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignInChallenge {
+    pub address: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+    pub typed_data: Value,
+}
+
+/// Issues challenges and checks the signed answers. Clones share the
+/// outstanding challenges, so it can serve every worker.
+#[derive(Clone)]
+pub struct WalletAuth {
+    domain: SignInDomain,
+    ttl: Duration,
+    verifier: Arc<dyn SignatureVerifier>,
+    challenges: Arc<Mutex<HashMap<String, SignInChallenge>>>,
+}
+
+impl WalletAuth {
+    pub fn new(domain: SignInDomain, ttl: Duration, verifier: Arc<dyn SignatureVerifier>) -> Self {
+        Self {
+            domain,
+            ttl,
+            verifier,
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Issue a single-use challenge for `address`.
+    pub fn challenge(&self, address: &str) -> Result<SignInChallenge, WalletError> {
+        let address = parse_felt(address).ok_or(WalletError::InvalidAddress)?;
+        let nonce = FieldElement::from(rand::thread_rng().gen::<u128>());
+        let expires_at = Utc::now() + self.ttl;
+
+        let challenge = SignInChallenge {
+            address: normalize(address),
+            nonce: hex(nonce),
+            expires_at,
+            typed_data: self.typed_data(address, nonce, expires_at),
+        };
+        let mut challenges = self.challenges.lock().unwrap();
+        let now = Utc::now();
+        challenges.retain(|_, pending| pending.expires_at > now);
+        challenges.insert(challenge.nonce.clone(), challenge.clone());
+        Ok(challenge)
+    }
+
+    /// Check the signature over the challenge issued with `nonce` and return
+    /// the normalized account address. The challenge is used up either way.
+    pub async fn verify(&self, address: &str, nonce: &str, signature: &[String]) -> Result<String, WalletError> {
+        let account = parse_felt(address).ok_or(WalletError::InvalidAddress)?;
+        let nonce = parse_felt(nonce).ok_or(WalletError::ChallengeNotFound)?;
+        let challenge = self
+            .challenges
+            .lock()
+            .unwrap()
+            .remove(&hex(nonce))
+            .ok_or(WalletError::ChallengeNotFound)?;
+        if challenge.address != normalize(account) {
+            return Err(WalletError::ChallengeNotFound);
+        }
+        if challenge.expires_at <= Utc::now() {
+            return Err(WalletError::ChallengeExpired);
+        }
+
+        let signature = signature
+            .iter()
+            .map(|part| parse_felt(part))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(WalletError::InvalidSignature)?;
+        let hash = self.message_hash(account, nonce, challenge.expires_at);
+        if self.verifier.is_valid_signature(account, hash, &signature).await? {
+            Ok(challenge.address)
+        } else {
+            Err(WalletError::InvalidSignature)
+        }
+    }
+
+    /// Hash of the sign-in message as the wallet computes it.
+    pub fn message_hash(&self, account: FieldElement, nonce: FieldElement, expires_at: DateTime<Utc>) -> FieldElement {
+        let domain = compute_hash_on_elements(&[
+            starknet_keccak(DOMAIN_TYPE.as_bytes()),
+            short_string(&self.domain.name),
+            short_string(&self.domain.version),
+            short_string(&self.domain.chain_id),
+        ]);
+        let message = compute_hash_on_elements(&[
+            starknet_keccak(SIGN_IN_TYPE.as_bytes()),
+            account,
+            nonce,
+            FieldElement::from(expires_at.timestamp().max(0) as u64),
+        ]);
+        compute_hash_on_elements(&[short_string(MESSAGE_PREFIX), domain, account, message])
+    }
+
+    fn typed_data(&self, account: FieldElement, nonce: FieldElement, expires_at: DateTime<Utc>) -> Value {
+        json!({
+            "types": {
+                "StarkNetDomain": [
+                    {"name": "name", "type": "felt"},
+                    {"name": "version", "type": "felt"},
+                    {"name": "chainId", "type": "felt"},
+                ],
+                "SignIn": [
+                    {"name": "address", "type": "felt"},
+                    {"name": "nonce", "type": "felt"},
+                    {"name": "expiresAt", "type": "felt"},
+                ],
+            },
+            "primaryType": "SignIn",
+            "domain": {
+                "name": self.domain.name,
+                "version": self.domain.version,
+                "chainId": self.domain.chain_id,
+            },
+            "message": {
+                "address": hex(account),
+                "nonce": hex(nonce),
+                "expiresAt": expires_at.timestamp().to_string(),
+            },
+        })
+    }
+}
+
+/// Address as stored: 0x-prefixed, zero-padded to 64 hex digits.
+pub fn normalize(address: FieldElement) -> String {
+    format!("{:#066x}", address)
+}
+
+fn parse_felt(value: &str) -> Option<FieldElement> {
+    let value = value.trim();
+    if value.starts_with("0x") || value.starts_with("0X") {
+        FieldElement::from_hex_be(value).ok()
+    } else {
+        FieldElement::from_dec_str(value).ok()
+    }
+}
+
+// Names in the domain are short strings; longer ones cannot be encoded
+fn short_string(value: &str) -> FieldElement {
+    cairo_short_string_to_felt(value).unwrap_or(FieldElement::ZERO)
+}
+
+fn hex(felt: FieldElement) -> String {
+    format!("{:#x}", felt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet_core::crypto::{ecdsa_sign, ecdsa_verify, Signature};
+    use starknet_crypto::get_public_key;
+
+    const PRIVATE_KEY: u64 = 0x5eed;
+
+    /// An account checking plain Stark-curve signatures of one key.
+    struct KeyAccount {
+        public_key: FieldElement,
+    }
+
+    #[async_trait]
+    impl SignatureVerifier for KeyAccount {
+        async fn is_valid_signature(
+            &self,
+            _account: FieldElement,
+            hash: FieldElement,
+            signature: &[FieldElement],
+        ) -> Result<bool, WalletError> {
+            let [r, s] = signature else { return Ok(false) };
+            Ok(ecdsa_verify(&self.public_key, &hash, &Signature { r: *r, s: *s }).unwrap_or(false))
+        }
+    }
+
+    fn wallet_auth(ttl: Duration) -> WalletAuth {
+        let domain = SignInDomain {
+            name: "StarkMate".to_string(),
+            version: "1".to_string(),
+            chain_id: "SN_SEPOLIA".to_string(),
+        };
+        let public_key = get_public_key(&FieldElement::from(PRIVATE_KEY));
+        WalletAuth::new(domain, ttl, Arc::new(KeyAccount { public_key }))
+    }
+
+    fn sign(auth: &WalletAuth, challenge: &SignInChallenge) -> Vec<String> {
+        let hash = auth.message_hash(
+            parse_felt(&challenge.address).unwrap(),
+            parse_felt(&challenge.nonce).unwrap(),
+            challenge.expires_at,
+        );
+        let signature = ecdsa_sign(&FieldElement::from(PRIVATE_KEY), &hash).unwrap();
+        vec![hex(signature.r), hex(signature.s)]
+    }
+
+    #[tokio::test]
+    async fn signed_challenges_are_accepted_once() {
+        let auth = wallet_auth(Duration::minutes(5));
+        let challenge = auth.challenge("0x0ABC").unwrap();
+        assert_eq!(challenge.address, format!("0x{:0>64}", "abc"));
+        assert_eq!(challenge.typed_data["message"]["nonce"], challenge.nonce);
+
+        let signature = sign(&auth, &challenge);
+        assert_eq!(auth.verify("0xabc", &challenge.nonce, &signature).await, Ok(challenge.address.clone()));
+        assert_eq!(
+            auth.verify("0xabc", &challenge.nonce, &signature).await,
+            Err(WalletError::ChallengeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn wrong_signers_addresses_and_stale_challenges_are_refused() {
+        let auth = wallet_auth(Duration::minutes(5));
+        let challenge = auth.challenge("0xabc").unwrap();
+        let mut forged = sign(&auth, &challenge);
+        forged[1] = hex(parse_felt(&forged[1]).unwrap() + FieldElement::ONE);
+        assert_eq!(
+            auth.verify("0xabc", &challenge.nonce, &forged).await,
+            Err(WalletError::InvalidSignature)
+        );
+
+        let challenge = auth.challenge("0xabc").unwrap();
+        let signature = sign(&auth, &challenge);
+        assert_eq!(
+            auth.verify("0xdef", &challenge.nonce, &signature).await,
+            Err(WalletError::ChallengeNotFound)
+        );
+        assert_eq!(auth.challenge("not an address").unwrap_err(), WalletError::InvalidAddress);
+
+        let expired = wallet_auth(Duration::seconds(-1));
+        let challenge = expired.challenge("0xabc").unwrap();
+        let signature = sign(&expired, &challenge);
+        assert_eq!(
+            expired.verify("0xabc", &challenge.nonce, &signature).await,
+            Err(WalletError::ChallengeExpired)
+        );
+    }
+}
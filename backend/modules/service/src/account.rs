@@ -0,0 +1,564 @@
+use chrono::{DateTime, Duration, Utc};
+use db_entity::{
+    account_closure, coaching_assignment, coaching_link, game_annotation, game_dispute, moderation_report, player, player_fingerprint, player_friend,
+    player_preferences, player_rating, player_role, player_trophy, player_wallet, rating_history, refresh_token, title_verification,
+    training_session, webhook_subscription,
+};
+use error::error::ApiError;
+use futures_util::stream::{self, Stream};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::archive::{central_directory, render_page, ZipEntryRecord, ZipEntryWriter, EXPORT_PAGE_SIZE};
+use crate::games::GameService;
+use crate::helper::password;
+use crate::preferences::PreferenceService;
+
+/// Closed accounts anonymized per pass of the purge worker
+const PURGE_BATCH: u64 = 50;
+
+pub struct AccountService;
+
+/// What a pass of [`AccountService::purge_due`] did.
+#[derive(Debug, Default)]
+pub struct PurgePass {
+    pub anonymized: usize,
+    pub failed: usize,
+}
+
+/// Files of the personal data archive, in the order they are written.
+enum Section {
+    Profile,
+    /// Next page of games to read, by keyset cursor
+    Games(Option<String>),
+    Messages,
+    Sessions,
+    Directory,
+    Done,
+}
+
+struct DataExport {
+    db: Arc<DatabaseConnection>,
+    player: player::Model,
+    session_owner: i32,
+    modified: DateTime<Utc>,
+    /// Where the next file starts in the archive
+    offset: u64,
+    records: Vec<ZipEntryRecord>,
+    games: Option<ZipEntryWriter>,
+    section: Section,
+}
+
+impl AccountService {
+    /// Close the account of `player` once `password` matches. The account is
+    /// disabled at once and anonymized when `grace` has passed, unless it is
+    /// reactivated before.
+    pub async fn close(
+        db: &DatabaseConnection,
+        player: &player::Model,
+        password: &str,
+        grace: Duration,
+    ) -> Result<account_closure::Model, ApiError> {
+        check_password(player, password)?;
+        if Self::closure(db, player.id).await?.is_some() {
+            return Err(ApiError::BadRequest("Account is already closed".to_string()));
+        }
+
+        let now = Utc::now();
+        let txn = db.begin().await?;
+        let closure = account_closure::ActiveModel {
+            player_id: Set(player.id),
+            requested_at: Set(now.fixed_offset()),
+            purge_after: Set((now + grace).fixed_offset()),
+            anonymized_at: Set(None),
+        }
+        .insert(&txn)
+        .await?;
+
+        let mut row = player.clone().into_active_model();
+        row.is_enabled = Set(false);
+        row.update(&txn).await?;
+        txn.commit().await?;
+
+        Ok(closure)
+    }
+
+    /// Reopen a closed account within its grace period.
+    pub async fn reactivate(db: &DatabaseConnection, username: &str, password: &str) -> Result<player::Model, ApiError> {
+        let player = player::Entity::find()
+            .filter(player::Column::Username.eq(username))
+            .one(db)
+            .await?
+            .ok_or(ApiError::InvalidCredentials)?;
+        check_password(&player, password)?;
+
+        let closure = Self::closure(db, player.id)
+            .await?
+            .ok_or_else(|| ApiError::BadRequest("Account is not closed".to_string()))?;
+        if !can_reactivate(&closure, Utc::now()) {
+            return Err(ApiError::Forbidden(
+                "The grace period is over; the account can no longer be reactivated".to_string(),
+            ));
+        }
+
+        let txn = db.begin().await?;
+        account_closure::Entity::delete_by_id(player.id).exec(&txn).await?;
+        let mut row = player.into_active_model();
+        row.is_enabled = Set(true);
+        let player = row.update(&txn).await?;
+        txn.commit().await?;
+
+        Ok(player)
+    }
+
+    /// Closure request of `player_id`, if the account was closed.
+    pub async fn closure<C: ConnectionTrait>(
+        db: &C,
+        player_id: Uuid,
+    ) -> Result<Option<account_closure::Model>, ApiError> {
+        Ok(account_closure::Entity::find_by_id(player_id).one(db).await?)
+    }
+
+    /// Anonymize closed accounts whose grace period ended before `now`.
+    pub async fn purge_due(db: &DatabaseConnection, now: DateTime<Utc>) -> Result<PurgePass, ApiError> {
+        let due = account_closure::Entity::find()
+            .filter(account_closure::Column::AnonymizedAt.is_null())
+            .filter(account_closure::Column::PurgeAfter.lte(now.fixed_offset()))
+            .order_by_asc(account_closure::Column::PurgeAfter)
+            .limit(PURGE_BATCH)
+            .all(db)
+            .await?;
+
+        let mut pass = PurgePass::default();
+        for closure in due {
+            match Self::anonymize(db, closure, now).await {
+                Ok(()) => pass.anonymized += 1,
+                Err(err) => {
+                    log::error!("Failed to anonymize a closed account: {}", err);
+                    pass.failed += 1;
+                }
+            }
+        }
+        Ok(pass)
+    }
+
+    /// Strip the personal data of a closed account. Games stay, so opponents
+    /// keep their records, and show the placeholder username instead.
+    async fn anonymize(
+        db: &DatabaseConnection,
+        closure: account_closure::Model,
+        now: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        let player_id = closure.player_id;
+        let txn = db.begin().await?;
+
+        if let Some(player) = player::Entity::find_by_id(player_id).one(&txn).await? {
+            let (username, email) = anonymized_identity(player_id);
+            let mut row = player.into_active_model();
+            row.username = Set(username);
+            row.email = Set(email);
+            row.password_hash = Set(Vec::new());
+            row.biography = Set(String::new());
+            row.country = Set(String::new());
+            row.flair = Set(String::new());
+            row.real_name = Set(String::new());
+            row.location = Set(None);
+            row.fide_rating = Set(None);
+            row.fide_id = Set(None);
+            row.social_links = Set(None);
+            row.is_enabled = Set(false);
+            row.title = Set(None);
+            row.update(&txn).await?;
+        }
+
+        player_preferences::Entity::delete_by_id(player_id).exec(&txn).await?;
+        player_friend::Entity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(player_friend::Column::PlayerId.eq(player_id))
+                    .add(player_friend::Column::FriendId.eq(player_id)),
+            )
+            .exec(&txn)
+            .await?;
+        player_wallet::Entity::delete_many()
+            .filter(player_wallet::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        player_role::Entity::delete_many()
+            .filter(player_role::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        webhook_subscription::Entity::delete_many()
+            .filter(webhook_subscription::Column::OwnerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        game_annotation::Entity::delete_many()
+            .filter(game_annotation::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        training_session::Entity::delete_many()
+            .filter(training_session::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        title_verification::Entity::delete_many()
+            .filter(title_verification::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        player_fingerprint::Entity::delete_many()
+            .filter(player_fingerprint::Column::PlayerId.eq(player_id))
+            .exec(&txn)
+            .await?;
+        // Assignments go with their link
+        coaching_link::Entity::delete_many()
+            .filter(
+                Condition::any()
+                    .add(coaching_link::Column::CoachId.eq(player_id))
+                    .add(coaching_link::Column::StudentId.eq(player_id)),
+            )
+            .exec(&txn)
+            .await?;
+
+        let mut row = closure.into_active_model();
+        row.anonymized_at = Set(Some(now.fixed_offset()));
+        row.update(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Everything stored about `player` as a ZIP archive: `profile.json`,
+    /// `games.pgn`, `messages.json` and `sessions.json`. Games are read a
+    /// page at a time, as in [`crate::archive::ArchiveService::export`].
+    /// Sessions are the refresh tokens issued to `session_owner`.
+    pub fn export(
+        db: Arc<DatabaseConnection>,
+        player: player::Model,
+        session_owner: i32,
+    ) -> impl Stream<Item = Result<Vec<u8>, ApiError>> {
+        let state = DataExport {
+            db,
+            player,
+            session_owner,
+            modified: Utc::now(),
+            offset: 0,
+            records: Vec::new(),
+            games: None,
+            section: Section::Profile,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let chunk = match std::mem::replace(&mut state.section, Section::Done) {
+                Section::Profile => {
+                    state.section = Section::Games(None);
+                    match profile(&state.db, &state.player).await {
+                        Ok(profile) => state.add_json("profile.json", &profile),
+                        Err(err) => Err(err),
+                    }
+                }
+                Section::Games(cursor) => state.games_page(cursor).await,
+                Section::Messages => {
+                    state.section = Section::Sessions;
+                    match messages(&state.db, state.player.id).await {
+                        Ok(messages) => state.add_json("messages.json", &messages),
+                        Err(err) => Err(err),
+                    }
+                }
+                Section::Sessions => {
+                    state.section = Section::Directory;
+                    match sessions(&state.db, state.session_owner).await {
+                        Ok(sessions) => state.add_json("sessions.json", &sessions),
+                        Err(err) => Err(err),
+                    }
+                }
+                Section::Directory => central_directory(&state.records, state.offset),
+                Section::Done => return None,
+            };
+            Some((chunk, state))
+        })
+    }
+}
+
+impl DataExport {
+    /// A whole file of the archive.
+    fn add_json(&mut self, name: &str, value: &Value) -> Result<Vec<u8>, ApiError> {
+        let data = serde_json::to_vec_pretty(value)
+            .map_err(|err| ApiError::Internal(format!("Failed to encode {}: {}", name, err)))?;
+        let mut entry = ZipEntryWriter::at(name, self.modified, self.offset);
+        let mut out = entry.write(&data);
+        out.extend(self.finish_entry(entry)?);
+        Ok(out)
+    }
+
+    /// The next page of `games.pgn`, finishing the file after the last one.
+    async fn games_page(&mut self, cursor: Option<String>) -> Result<Vec<u8>, ApiError> {
+        let (games, next) =
+            GameService::list_games(&self.db, cursor, EXPORT_PAGE_SIZE, Some(self.player.id), None, None).await?;
+        let pgn = render_page(&self.db, &games).await?;
+
+        let mut entry = self
+            .games
+            .take()
+            .unwrap_or_else(|| ZipEntryWriter::at("games.pgn", self.modified, self.offset));
+        let mut out = entry.write(pgn.as_bytes());
+        match next {
+            Some(next) => {
+                self.games = Some(entry);
+                self.section = Section::Games(Some(next));
+            }
+            None => {
+                out.extend(self.finish_entry(entry)?);
+                self.section = Section::Messages;
+            }
+        }
+        Ok(out)
+    }
+
+    fn finish_entry(&mut self, entry: ZipEntryWriter) -> Result<Vec<u8>, ApiError> {
+        let (out, record, end) = entry.finish_entry()?;
+        self.records.push(record);
+        self.offset = end;
+        Ok(out)
+    }
+}
+
+fn check_password(player: &player::Model, password: &str) -> Result<(), ApiError> {
+    let hash = std::str::from_utf8(&player.password_hash).map_err(|_| ApiError::InvalidCredentials)?;
+    password::verify_password(password, hash).map_err(|_| ApiError::InvalidCredentials)
+}
+
+/// Whether a closed account can still be reopened at `now`.
+fn can_reactivate(closure: &account_closure::Model, now: DateTime<Utc>) -> bool {
+    closure.anonymized_at.is_none() && now < closure.purge_after
+}
+
+/// Username and email that replace those of an anonymized player; unique
+/// because they are derived from the player ID.
+fn anonymized_identity(player_id: Uuid) -> (String, String) {
+    let id = player_id.simple().to_string();
+    (format!("deleted-{}", id), format!("deleted-{}@invalid", id))
+}
+
+async fn profile(db: &DatabaseConnection, player: &player::Model) -> Result<Value, ApiError> {
+    let id = player.id;
+    let ratings = player_rating::Entity::find()
+        .filter(player_rating::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let rating_history = rating_history::Entity::find()
+        .filter(rating_history::Column::PlayerId.eq(id))
+        .order_by_asc(rating_history::Column::RecordedAt)
+        .all(db)
+        .await?;
+    let roles = player_role::Entity::find()
+        .filter(player_role::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let wallets = player_wallet::Entity::find()
+        .filter(player_wallet::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let friends = player_friend::Entity::find()
+        .filter(player_friend::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let trophies = player_trophy::Entity::find()
+        .filter(player_trophy::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let training = training_session::Entity::find()
+        .filter(training_session::Column::PlayerId.eq(id))
+        .all(db)
+        .await?;
+    let webhooks = webhook_subscription::Entity::find()
+        .filter(webhook_subscription::Column::OwnerId.eq(id))
+        .all(db)
+        .await?;
+    let title_verifications = title_verification::Entity::find()
+        .filter(title_verification::Column::PlayerId.eq(id))
+        .order_by_asc(title_verification::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let coaching_links = coaching_link::Entity::find()
+        .filter(
+            Condition::any()
+                .add(coaching_link::Column::CoachId.eq(id))
+                .add(coaching_link::Column::StudentId.eq(id)),
+        )
+        .order_by_asc(coaching_link::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let coaching_assignments = coaching_assignment::Entity::find()
+        .filter(
+            Condition::any()
+                .add(coaching_assignment::Column::CoachId.eq(id))
+                .add(coaching_assignment::Column::StudentId.eq(id)),
+        )
+        .order_by_asc(coaching_assignment::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(json!({
+        "player": {
+            "id": player.id,
+            "username": player.username,
+            "email": player.email,
+            "real_name": player.real_name,
+            "biography": player.biography,
+            "country": player.country,
+            "flair": player.flair,
+            "location": player.location,
+            "fide_rating": player.fide_rating,
+            "fide_id": player.fide_id,
+            "social_links": player.social_links,
+            "title": player.title.map(|title| title.code()),
+        },
+        "preferences": PreferenceService::get(db, id).await?,
+        "ratings": ratings,
+        "rating_history": rating_history,
+        "roles": roles,
+        "wallets": wallets,
+        "friends": friends,
+        "trophies": trophies,
+        "training_sessions": training,
+        "webhooks": webhooks,
+        "title_verifications": title_verifications,
+        "coaching_links": coaching_links,
+        "coaching_assignments": coaching_assignments,
+    }))
+}
+
+/// What the player wrote: game annotations, disputes and reports they filed,
+/// with the chat captured in those reports.
+async fn messages(db: &DatabaseConnection, player_id: Uuid) -> Result<Value, ApiError> {
+    let annotations = game_annotation::Entity::find()
+        .filter(game_annotation::Column::PlayerId.eq(player_id))
+        .all(db)
+        .await?;
+    let disputes = game_dispute::Entity::find()
+        .filter(game_dispute::Column::ClaimantId.eq(player_id))
+        .all(db)
+        .await?;
+    let reports = moderation_report::Entity::find()
+        .filter(moderation_report::Column::ReporterId.eq(player_id))
+        .all(db)
+        .await?;
+
+    Ok(json!({
+        "annotations": annotations,
+        "disputes": disputes,
+        "reports": reports,
+    }))
+}
+
+/// Refresh tokens, without their hashes.
+async fn sessions(db: &DatabaseConnection, session_owner: i32) -> Result<Value, ApiError> {
+    let tokens = refresh_token::Entity::find()
+        .filter(refresh_token::Column::PlayerId.eq(session_owner))
+        .order_by_desc(refresh_token::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(Value::Array(
+        tokens
+            .into_iter()
+            .map(|token| {
+                json!({
+                    "id": token.id,
+                    "family_id": token.family_id,
+                    "created_at": token.created_at,
+                    "used_at": token.used_at,
+                    "expires_at": token.expires_at,
+                    "is_revoked": token.is_revoked,
+                })
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use sea_orm::{DbBackend, MockDatabase};
+
+    fn closure(purge_after: DateTime<Utc>, anonymized: bool) -> account_closure::Model {
+        account_closure::Model {
+            player_id: Uuid::new_v4(),
+            requested_at: Utc::now().fixed_offset(),
+            purge_after: purge_after.fixed_offset(),
+            anonymized_at: anonymized.then(|| Utc::now().fixed_offset()),
+        }
+    }
+
+    #[test]
+    fn test_reactivation_only_within_grace_period() {
+        let now = Utc::now();
+        assert!(can_reactivate(&closure(now + Duration::days(1), false), now));
+        assert!(!can_reactivate(&closure(now - Duration::days(1), false), now));
+        assert!(!can_reactivate(&closure(now + Duration::days(1), true), now));
+    }
+
+    #[test]
+    fn test_anonymized_identity_is_unique_per_player() {
+        let (first, first_email) = anonymized_identity(Uuid::new_v4());
+        let (second, _) = anonymized_identity(Uuid::new_v4());
+        assert!(first.starts_with("deleted-"));
+        assert!(first_email.ends_with("@invalid"));
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_export_lists_every_file_in_the_directory() {
+        let player = player::Model {
+            id: Uuid::new_v4(),
+            username: "magnus".to_string(),
+            email: "magnus@example.com".to_string(),
+            password_hash: b"secret-hash".to_vec(),
+            biography: String::new(),
+            country: "NO".to_string(),
+            flair: String::new(),
+            real_name: "Magnus".to_string(),
+            location: None,
+            fide_rating: None,
+            fide_id: None,
+            social_links: None,
+            is_enabled: true,
+            title: None,
+        };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([Vec::<player_rating::Model>::new()])
+            .append_query_results([Vec::<rating_history::Model>::new()])
+            .append_query_results([Vec::<player_role::Model>::new()])
+            .append_query_results([Vec::<player_wallet::Model>::new()])
+            .append_query_results([Vec::<player_friend::Model>::new()])
+            .append_query_results([Vec::<player_trophy::Model>::new()])
+            .append_query_results([Vec::<training_session::Model>::new()])
+            .append_query_results([Vec::<webhook_subscription::Model>::new()])
+            .append_query_results([Vec::<title_verification::Model>::new()])
+            .append_query_results([Vec::<coaching_link::Model>::new()])
+            .append_query_results([Vec::<coaching_assignment::Model>::new()])
+            .append_query_results([Vec::<player_preferences::Model>::new()])
+            .append_query_results([Vec::<db_entity::game::Model>::new()])
+            .append_query_results([Vec::<db_entity::game_archive::Model>::new()])
+            .append_query_results([Vec::<game_annotation::Model>::new()])
+            .append_query_results([Vec::<game_dispute::Model>::new()])
+            .append_query_results([Vec::<moderation_report::Model>::new()])
+            .append_query_results([Vec::<refresh_token::Model>::new()])
+            .into_connection();
+
+        let chunks: Vec<_> = AccountService::export(Arc::new(db), player, 1).collect().await;
+        let archive: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.unwrap()).collect();
+
+        let text = String::from_utf8_lossy(&archive);
+        let end = archive.len() - 22;
+        assert_eq!(u16::from_le_bytes([archive[end + 10], archive[end + 11]]), 4);
+        for name in ["profile.json", "games.pgn", "messages.json", "sessions.json"] {
+            // Once in the local header, once in the central directory
+            assert_eq!(text.matches(name).count(), 2, "{}", name);
+        }
+    }
+}
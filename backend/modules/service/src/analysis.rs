@@ -0,0 +1,233 @@
+use chess::{AnnotationTree, Material, MoveNote, Nag, PieceCounts};
+use dto::analysis::{
+    CriticalMoveDisplay, GameAnalysisDisplay, GamePhase, MistakeSeverity, MoveTimeDisplay, PhaseTimeDisplay,
+    SideTimeUsage, TimeUsageReport,
+};
+use dto::games::Side;
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+use crate::annotations::{is_finished, AnnotationService};
+use crate::game_archive::GameArchiveService;
+use crate::game_events::{GameEventService, LoggedMove};
+
+/// Plies counted as the opening, unless the game reaches an endgame first
+const OPENING_PLIES: u32 = 20;
+
+/// Pieces other than kings and pawns, both sides together, in pawns, at or
+/// below which a position is an endgame
+const ENDGAME_MATERIAL: u32 = 26;
+
+const PHASES: [GamePhase; 3] = [GamePhase::Opening, GamePhase::Middlegame, GamePhase::Endgame];
+
+pub struct GameAnalysisService;
+
+impl GameAnalysisService {
+    /// Post-game report of a finished game. Mistakes are the moves marked
+    /// in the annotations `viewer` may read.
+    pub async fn report(db: &DatabaseConnection, game_id: Uuid, viewer: Uuid) -> Result<GameAnalysisDisplay, ApiError> {
+        let game = GameArchiveService::find(db, game_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+        if !is_finished(&game) {
+            return Err(ApiError::BadRequest("Games are analysed once they are finished".to_string()));
+        }
+
+        let moves = GameEventService::logged_moves(db, &game).await?;
+        let annotations = AnnotationService::visible_tree(db, game_id, viewer).await?;
+        Ok(GameAnalysisDisplay { game_id, time: time_usage(&moves, &annotations) })
+    }
+}
+
+/// `[%clk]` and `[%emt]` comments, the clock left and the time spent, on
+/// each move the log has a clock for.
+pub(crate) fn clock_comments(moves: &[LoggedMove]) -> AnnotationTree {
+    let mut tree = AnnotationTree::default();
+    for logged in moves {
+        let Some(remaining) = logged.clock_after_ms else { continue };
+        let mut comment = format!("[%clk {}]", clock_text(remaining));
+        if let Some(spent) = spent_ms(logged) {
+            comment.push_str(&format!(" [%emt {}]", clock_text(spent)));
+        }
+        tree.moves.insert(logged.ply, MoveNote { comment: Some(comment), ..MoveNote::default() });
+    }
+    tree
+}
+
+fn time_usage(moves: &[LoggedMove], annotations: &AnnotationTree) -> TimeUsageReport {
+    let moves: Vec<MoveTimeDisplay> = moves.iter().map(move_time).collect();
+    TimeUsageReport {
+        white: side_usage(Side::White, &moves, annotations),
+        black: side_usage(Side::Black, &moves, annotations),
+        moves,
+    }
+}
+
+fn side_usage(side: Side, moves: &[MoveTimeDisplay], annotations: &AnnotationTree) -> SideTimeUsage {
+    let own: Vec<&MoveTimeDisplay> = moves.iter().filter(|m| m.side == side).collect();
+    let phases = PHASES
+        .iter()
+        .map(|phase| {
+            let in_phase: Vec<_> = own.iter().filter(|m| m.phase == *phase).collect();
+            PhaseTimeDisplay {
+                phase: *phase,
+                moves: in_phase.len() as u32,
+                spent_ms: in_phase.iter().filter_map(|m| m.spent_ms).sum(),
+            }
+        })
+        .collect();
+    let longest_think = own
+        .iter()
+        .filter(|m| m.spent_ms.is_some())
+        .min_by_key(|m| Reverse(m.spent_ms))
+        .map(|m| (*m).clone());
+    let critical_moves = own
+        .iter()
+        .filter_map(|m| {
+            Some(CriticalMoveDisplay {
+                ply: m.ply,
+                notation: m.notation.clone(),
+                severity: severity(annotations.moves.get(&m.ply)?)?,
+                spent_ms: m.spent_ms,
+                remaining_ms: m.remaining_ms,
+            })
+        })
+        .collect();
+
+    SideTimeUsage {
+        spent_ms: own.iter().filter_map(|m| m.spent_ms).sum(),
+        phases,
+        longest_think,
+        critical_moves,
+    }
+}
+
+fn move_time(logged: &LoggedMove) -> MoveTimeDisplay {
+    MoveTimeDisplay {
+        ply: logged.ply,
+        side: logged.side,
+        notation: logged.notation.clone(),
+        phase: phase_of(logged.ply, &logged.material),
+        spent_ms: spent_ms(logged),
+        remaining_ms: logged.clock_after_ms,
+    }
+}
+
+/// What the mover's clock lost over the move; never below zero, should
+/// the clock have been set higher meanwhile.
+fn spent_ms(logged: &LoggedMove) -> Option<i64> {
+    Some((logged.clock_before_ms? - logged.clock_after_ms?).max(0))
+}
+
+fn phase_of(ply: u32, material: &Material) -> GamePhase {
+    let pieces = |side: &PieceCounts| side.points() - side.pawns as u32;
+    if pieces(&material.white) + pieces(&material.black) <= ENDGAME_MATERIAL {
+        GamePhase::Endgame
+    } else if ply <= OPENING_PLIES {
+        GamePhase::Opening
+    } else {
+        GamePhase::Middlegame
+    }
+}
+
+fn severity(note: &MoveNote) -> Option<MistakeSeverity> {
+    if note.nags.contains(&Nag::Blunder) {
+        Some(MistakeSeverity::Blunder)
+    } else if note.nags.contains(&Nag::Mistake) {
+        Some(MistakeSeverity::Mistake)
+    } else {
+        None
+    }
+}
+
+/// h:mm:ss, as PGN clock commands write time
+fn clock_text(ms: i64) -> String {
+    let seconds = ms.max(0) / 1000;
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: PieceCounts = PieceCounts { pawns: 8, knights: 2, bishops: 2, rooks: 2, queens: 1, kings: 1 };
+    const ROOK_ENDING: PieceCounts = PieceCounts { pawns: 5, knights: 0, bishops: 0, rooks: 1, queens: 0, kings: 1 };
+
+    fn logged(ply: u32, pieces: PieceCounts, clocks: Option<(i64, i64)>) -> LoggedMove {
+        LoggedMove {
+            ply,
+            side: if ply % 2 == 1 { Side::White } else { Side::Black },
+            notation: format!("m{}", ply),
+            clock_before_ms: clocks.map(|(before, _)| before),
+            clock_after_ms: clocks.map(|(_, after)| after),
+            material: Material { white: pieces, black: pieces },
+        }
+    }
+
+    #[test]
+    fn test_time_is_split_by_phase_with_the_longest_think() {
+        let moves = [
+            logged(1, FULL, Some((300_000, 298_000))),
+            logged(2, FULL, Some((300_000, 290_000))),
+            logged(21, FULL, Some((298_000, 238_000))),
+            logged(22, FULL, None),
+            logged(23, ROOK_ENDING, Some((238_000, 178_000))),
+            logged(24, ROOK_ENDING, Some((290_000, 295_000))),
+        ];
+        let report = time_usage(&moves, &AnnotationTree::default());
+
+        let phases: Vec<_> = report.white.phases.iter().map(|p| (p.phase, p.moves, p.spent_ms)).collect();
+        assert_eq!(phases, [
+            (GamePhase::Opening, 1, 2_000),
+            (GamePhase::Middlegame, 1, 60_000),
+            (GamePhase::Endgame, 1, 60_000),
+        ]);
+        assert_eq!(report.white.spent_ms, 122_000);
+        // Equally long thinks: the earlier one
+        assert_eq!(report.white.longest_think.as_ref().map(|m| m.ply), Some(21));
+
+        // An untimed move still counts as a move; a clock that went up as no time
+        let phases: Vec<_> = report.black.phases.iter().map(|p| (p.moves, p.spent_ms)).collect();
+        assert_eq!(phases, [(1, 10_000), (1, 0), (1, 0)]);
+        assert_eq!(report.moves[3].spent_ms, None);
+        assert_eq!(report.black.longest_think.as_ref().map(|m| m.ply), Some(2));
+    }
+
+    #[test]
+    fn test_critical_moves_take_the_clock_from_the_move() {
+        let moves = [
+            logged(1, FULL, Some((60_000, 58_000))),
+            logged(2, FULL, Some((60_000, 59_000))),
+            logged(3, FULL, Some((58_000, 12_000))),
+        ];
+        let mut annotations = AnnotationTree::default();
+        annotations.moves.insert(2, MoveNote { nags: vec![Nag::Good], ..MoveNote::default() });
+        annotations.moves.insert(3, MoveNote { nags: vec![Nag::Mistake, Nag::Blunder], ..MoveNote::default() });
+
+        let report = time_usage(&moves, &annotations);
+        assert_eq!(report.white.critical_moves, [CriticalMoveDisplay {
+            ply: 3,
+            notation: "m3".to_string(),
+            severity: MistakeSeverity::Blunder,
+            spent_ms: Some(46_000),
+            remaining_ms: Some(12_000),
+        }]);
+        assert!(report.black.critical_moves.is_empty());
+    }
+
+    #[test]
+    fn test_clock_comments_skip_untimed_moves() {
+        let tree = clock_comments(&[
+            logged(1, FULL, Some((5_400_000, 5_391_500))),
+            logged(2, FULL, None),
+            logged(3, FULL, Some((5_391_500, 3_700_000))),
+        ]);
+        let comments: Vec<_> = tree.moves.iter().map(|(ply, note)| (*ply, note.comment.as_deref().unwrap())).collect();
+        assert_eq!(comments, [
+            (1, "[%clk 1:29:51] [%emt 0:00:08]"),
+            (3, "[%clk 1:01:40] [%emt 0:28:11]"),
+        ]);
+    }
+}
@@ -0,0 +1,390 @@
+use chess::{write_pgn, AnnotationTree, PgnGameResult, PgnHeaders};
+use chrono::Utc;
+use db_entity::{
+    game::{self, ResultSide},
+    game_annotation::{self, AnnotationVisibility},
+    player,
+    title_verification::ChessTitle,
+};
+use dto::annotations::{AnnotationDisplay, MoveNote, SaveAnnotationsRequest};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::analysis::clock_comments;
+use crate::friends::FriendService;
+use crate::game_events::GameEventService;
+use crate::game_archive::GameArchiveService;
+
+/// The seven-tag roster, written from the game itself rather than stored headers
+const ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+pub struct AnnotationService;
+
+impl AnnotationService {
+    /// Save `author`'s annotations of a finished game, replacing any earlier ones.
+    pub async fn save(
+        db: &DatabaseConnection,
+        game_id: Uuid,
+        author: &player::Model,
+        request: SaveAnnotationsRequest,
+    ) -> Result<AnnotationDisplay, ApiError> {
+        let game = find_game(db, game_id).await?;
+        if author.id != game.white_player && author.id != game.black_player {
+            return Err(ApiError::Forbidden(
+                "Only the players of a game can annotate it".to_string(),
+            ));
+        }
+        if !is_finished(&game) {
+            return Err(ApiError::BadRequest(
+                "Games can be annotated once they are finished".to_string(),
+            ));
+        }
+
+        let tree = to_tree(request.moves)?;
+        tree.validate(&moves_of(&game)?)
+            .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        let tree = serde_json::to_value(&tree)
+            .map_err(|err| ApiError::Internal(format!("Cannot serialize annotations: {}", err)))?;
+        let now = Utc::now().fixed_offset();
+
+        let existing = game_annotation::Entity::find()
+            .filter(game_annotation::Column::GameId.eq(game_id))
+            .filter(game_annotation::Column::PlayerId.eq(author.id))
+            .one(db)
+            .await?;
+
+        let saved = match existing {
+            Some(existing) => {
+                let mut active: game_annotation::ActiveModel = existing.into();
+                active.visibility = Set(request.visibility.into());
+                active.tree = Set(tree);
+                active.updated_at = Set(now);
+                active.update(db).await?
+            }
+            None => {
+                let model = game_annotation::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    game_id: Set(game_id),
+                    player_id: Set(author.id),
+                    visibility: Set(request.visibility.into()),
+                    tree: Set(tree),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                };
+                model.insert(db).await?
+            }
+        };
+
+        display(saved, author.username.clone())
+    }
+
+    /// Annotations of a game `viewer` may read, their own first, then the
+    /// most recently updated.
+    pub async fn list_visible(
+        db: &DatabaseConnection,
+        game_id: Uuid,
+        viewer: Uuid,
+    ) -> Result<Vec<AnnotationDisplay>, ApiError> {
+        find_game(db, game_id).await?;
+
+        let annotations = game_annotation::Entity::find()
+            .filter(game_annotation::Column::GameId.eq(game_id))
+            .order_by_desc(game_annotation::Column::UpdatedAt)
+            .find_also_related(player::Entity)
+            .all(db)
+            .await?;
+
+        let mut visible = Vec::new();
+        for (annotation, author) in annotations {
+            let is_friend = annotation.visibility == AnnotationVisibility::Friends
+                && FriendService::is_friend(db, annotation.player_id, viewer).await?;
+            if can_read(&annotation, viewer, is_friend) {
+                let username = author.map(|a| a.username).unwrap_or_default();
+                visible.push(display(annotation, username)?);
+            }
+        }
+        visible.sort_by_key(|a| a.author_id != viewer);
+        Ok(visible)
+    }
+
+    pub async fn delete(db: &DatabaseConnection, game_id: Uuid, author_id: Uuid) -> Result<(), ApiError> {
+        let result = game_annotation::Entity::delete_many()
+            .filter(game_annotation::Column::GameId.eq(game_id))
+            .filter(game_annotation::Column::PlayerId.eq(author_id))
+            .exec(db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(ApiError::NotFound("Annotations".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Every annotation set of a game `viewer` may read merged into one.
+    /// Comments by other authors are prefixed with their username.
+    pub async fn visible_tree(db: &DatabaseConnection, game_id: Uuid, viewer: Uuid) -> Result<AnnotationTree, ApiError> {
+        let mut merged = AnnotationTree::default();
+        for annotation in Self::list_visible(db, game_id, viewer).await? {
+            let mut tree = to_tree(annotation.moves)?;
+            if annotation.author_id != viewer {
+                tree.prefix_comments(&format!("{}: ", annotation.author_username));
+            }
+            merged.merge(&tree);
+        }
+        Ok(merged)
+    }
+
+    /// The game as PGN with every annotation set `viewer` may read merged
+    /// in, after the clock of each move the game's log has timed.
+    pub async fn export_pgn(db: &DatabaseConnection, game_id: Uuid, viewer: Uuid) -> Result<String, ApiError> {
+        let game = find_game(db, game_id).await?;
+        let players: HashMap<Uuid, (String, Option<ChessTitle>)> = player::Entity::find()
+            .filter(player::Column::Id.is_in([game.white_player, game.black_player]))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| (p.id, (p.username, p.title)))
+            .collect();
+
+        let mut merged = clock_comments(&GameEventService::logged_moves(db, &game).await?);
+        merged.merge(&Self::visible_tree(db, game_id, viewer).await?);
+
+        let name = |id: &Uuid| players.get(id).map_or_else(|| "?".to_string(), |(name, _)| name.clone());
+        let title = |id: &Uuid| players.get(id).and_then(|(_, title)| *title);
+        let mut headers = headers_of(&game, name(&game.white_player), name(&game.black_player));
+        headers.other.extend(title_tags(&game, title(&game.white_player), title(&game.black_player)));
+        Ok(write_pgn(&headers, &moves_of(&game)?, &merged))
+    }
+}
+
+async fn find_game(db: &DatabaseConnection, game_id: Uuid) -> Result<game::Model, ApiError> {
+    GameArchiveService::find(db, game_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Game".to_string()))
+}
+
+pub(crate) fn is_finished(game: &game::Model) -> bool {
+    game.result.as_ref().is_some_and(|r| *r != ResultSide::Ongoing)
+}
+
+/// Whether `viewer` may read `annotation`; `is_friend` tells whether the
+/// viewer is on the author's friends list.
+fn can_read(annotation: &game_annotation::Model, viewer: Uuid, is_friend: bool) -> bool {
+    match annotation.visibility {
+        _ if annotation.player_id == viewer => true,
+        AnnotationVisibility::Public => true,
+        AnnotationVisibility::Friends => is_friend,
+        AnnotationVisibility::Private => false,
+    }
+}
+
+/// Mainline moves of a game in SAN. Played games store them under
+/// `pgn.moves`, as a list or as movetext; imported games keep the original PGN.
+pub(crate) fn moves_of(game: &game::Model) -> Result<Vec<String>, ApiError> {
+    match game.pgn.get("moves") {
+        Some(Value::Array(moves)) => Ok(moves.iter().filter_map(|m| m.as_str()).map(str::to_string).collect()),
+        Some(Value::String(movetext)) => Ok(movetext
+            .split_whitespace()
+            .filter(|token| !token.ends_with('.') && !matches!(*token, "1-0" | "0-1" | "1/2-1/2" | "*"))
+            .map(str::to_string)
+            .collect()),
+        _ => match &game.original_pgn {
+            Some(original) => chess::parse_pgn(original)
+                .map(|parsed| parsed.moves)
+                .map_err(|err| ApiError::PgnParseError(err.to_string())),
+            None => Ok(Vec::new()),
+        },
+    }
+}
+
+/// Imported games name their players from the original tags, since both
+/// seats hold the importing player.
+pub(crate) fn headers_of(game: &game::Model, white: String, black: String) -> PgnHeaders {
+    let stored = game.pgn.get("headers").and_then(|h| h.as_object());
+    let tag = |key: &str| stored.and_then(|h| h.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+    let name = |key: &str, fallback: String| tag(key).filter(|_| game.is_imported).unwrap_or(fallback);
+
+    let other = stored
+        .map(|h| {
+            h.iter()
+                .filter(|(key, _)| !ROSTER.contains(&key.as_str()))
+                .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    PgnHeaders {
+        event: tag("Event"),
+        site: tag("Site").or_else(|| Some("StarkMate".to_string())),
+        date: Some(game.started_at.format("%Y.%m.%d").to_string()),
+        round: tag("Round"),
+        white: name("White", white),
+        black: name("Black", black),
+        result: match game.result {
+            Some(ResultSide::WhiteWins) => PgnGameResult::WhiteWins,
+            Some(ResultSide::BlackWins) => PgnGameResult::BlackWins,
+            Some(ResultSide::Draw) => PgnGameResult::Draw,
+            _ => PgnGameResult::Ongoing,
+        },
+        other,
+    }
+}
+
+/// `WhiteTitle` and `BlackTitle` tags for the players' verified titles.
+/// Imported games keep the title tags of the original PGN, if any.
+pub(crate) fn title_tags(
+    game: &game::Model,
+    white: Option<ChessTitle>,
+    black: Option<ChessTitle>,
+) -> HashMap<String, String> {
+    if game.is_imported {
+        return HashMap::new();
+    }
+    [("WhiteTitle", white), ("BlackTitle", black)]
+        .into_iter()
+        .filter_map(|(tag, title)| title.map(|title| (tag.to_string(), title.code().to_string())))
+        .collect()
+}
+
+// The DTO notes mirror `chess::MoveNote` field for field, so they convert through serde
+fn to_tree(moves: BTreeMap<u32, MoveNote>) -> Result<AnnotationTree, ApiError> {
+    serde_json::from_value(serde_json::json!({ "moves": moves }))
+        .map_err(|err| ApiError::BadRequest(format!("Invalid annotations: {}", err)))
+}
+
+fn display(model: game_annotation::Model, author_username: String) -> Result<AnnotationDisplay, ApiError> {
+    let moves = serde_json::from_value(model.tree.get("moves").cloned().unwrap_or_default())
+        .map_err(|err| ApiError::Internal(format!("Stored annotations are unreadable: {}", err)))?;
+
+    Ok(AnnotationDisplay {
+        id: model.id,
+        game_id: model.game_id,
+        author_id: model.player_id,
+        author_username,
+        visibility: model.visibility.into(),
+        moves,
+        updated_at: model.updated_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use dto::annotations::{Nag, VariationMove};
+    use serde_json::json;
+
+    fn finished_game(pgn: Value) -> game::Model {
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn,
+            result: Some(ResultSide::WhiteWins),
+            variant: game::GameVariant::Standard,
+            started_at: at,
+            duration_sec: 600,
+            created_at: at,
+            updated_at: at,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    fn annotation(author: Uuid, visibility: AnnotationVisibility) -> game_annotation::Model {
+        let at = Utc::now().fixed_offset();
+        game_annotation::Model {
+            id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            player_id: author,
+            visibility,
+            tree: json!({ "moves": {} }),
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn reads_moves_from_lists_and_movetext() {
+        let listed = finished_game(json!({ "moves": ["e4", "e5", "Nf3"] }));
+        let movetext = finished_game(json!({ "moves": "1. e4 e5 2. Nf3 1-0" }));
+
+        assert_eq!(moves_of(&listed).unwrap(), vec!["e4", "e5", "Nf3"]);
+        assert_eq!(moves_of(&movetext).unwrap(), vec!["e4", "e5", "Nf3"]);
+        assert!(moves_of(&finished_game(json!({}))).unwrap().is_empty());
+    }
+
+    #[test]
+    fn visibility_decides_who_reads() {
+        let author = Uuid::new_v4();
+        let other = Uuid::new_v4();
+
+        assert!(can_read(&annotation(author, AnnotationVisibility::Private), author, false));
+        assert!(!can_read(&annotation(author, AnnotationVisibility::Private), other, true));
+        assert!(can_read(&annotation(author, AnnotationVisibility::Friends), other, true));
+        assert!(!can_read(&annotation(author, AnnotationVisibility::Friends), other, false));
+        assert!(can_read(&annotation(author, AnnotationVisibility::Public), other, false));
+    }
+
+    #[test]
+    fn request_notes_convert_to_a_tree() {
+        let mut moves = BTreeMap::new();
+        moves.insert(
+            2,
+            MoveNote {
+                comment: Some("Sharper than e5".to_string()),
+                nags: vec![Nag::Interesting],
+                variations: vec![vec![VariationMove {
+                    san: "e5".to_string(),
+                    note: MoveNote::default(),
+                }]],
+            },
+        );
+
+        let tree = to_tree(moves).unwrap();
+        let game_moves = vec!["e4".to_string(), "c5".to_string()];
+        assert!(tree.validate(&game_moves).is_ok());
+        assert_eq!(tree.moves[&2].nags, vec![chess::Nag::Interesting]);
+    }
+
+    #[test]
+    fn headers_come_from_the_game() {
+        let game = finished_game(json!({ "headers": { "Event": "Club night", "ECO": "B20" } }));
+        let headers = headers_of(&game, "alice".to_string(), "bob".to_string());
+
+        assert_eq!(headers.event.as_deref(), Some("Club night"));
+        assert_eq!(headers.date.as_deref(), Some("2026.10.16"));
+        assert_eq!(headers.result, PgnGameResult::WhiteWins);
+        assert_eq!(headers.other.get("ECO").map(String::as_str), Some("B20"));
+    }
+
+    #[test]
+    fn imported_games_keep_their_player_names() {
+        let mut game = finished_game(json!({ "headers": { "White": "DrNykterstein", "Black": "Hikaru" } }));
+        let headers = headers_of(&game, "alice".to_string(), "alice".to_string());
+        assert_eq!(headers.white, "alice");
+
+        game.is_imported = true;
+        let headers = headers_of(&game, "alice".to_string(), "alice".to_string());
+        assert_eq!(headers.white, "DrNykterstein");
+        assert_eq!(headers.black, "Hikaru");
+    }
+
+    #[test]
+    fn verified_titles_are_tagged() {
+        let mut game = finished_game(json!({}));
+        let tags = title_tags(&game, Some(ChessTitle::Gm), None);
+        assert_eq!(tags.get("WhiteTitle").map(String::as_str), Some("GM"));
+        assert!(!tags.contains_key("BlackTitle"));
+
+        game.is_imported = true;
+        assert!(title_tags(&game, Some(ChessTitle::Gm), Some(ChessTitle::Wim)).is_empty());
+    }
+}
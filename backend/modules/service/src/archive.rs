@@ -0,0 +1,412 @@
+use chess::{write_pgn, AnnotationTree};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use db_entity::{game, player, title_verification::ChessTitle};
+use dto::games::ArchiveFormat;
+use error::error::ApiError;
+use flate2::{write::DeflateEncoder, Compression, Crc};
+use futures_util::stream::{self, Stream};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::annotations::{headers_of, moves_of, title_tags};
+use crate::games::GameService;
+
+/// Games read from the database per chunk of the archive
+pub const EXPORT_PAGE_SIZE: u64 = 100;
+
+/// Name of the PGN file inside zipped archives
+pub const ARCHIVE_ENTRY_NAME: &str = "games.pgn";
+
+pub struct ArchiveService;
+
+enum Progress {
+    /// Next page of games to read, by keyset cursor
+    Page(Option<String>),
+    Done,
+}
+
+struct ExportState {
+    db: Arc<DatabaseConnection>,
+    player_id: Uuid,
+    zip: Option<ZipEntryWriter>,
+    progress: Progress,
+}
+
+impl ArchiveService {
+    /// Every game of `player_id`, newest first, as concatenated PGN or as a
+    /// ZIP holding one PGN file. Games are read and encoded a page at a time
+    /// so the archive is never held in memory as a whole.
+    pub fn export(
+        db: Arc<DatabaseConnection>,
+        player_id: Uuid,
+        format: ArchiveFormat,
+    ) -> impl Stream<Item = Result<Vec<u8>, ApiError>> {
+        let state = ExportState {
+            db,
+            player_id,
+            zip: match format {
+                ArchiveFormat::Pgn => None,
+                ArchiveFormat::Zip => Some(ZipEntryWriter::new(ARCHIVE_ENTRY_NAME, Utc::now())),
+            },
+            progress: Progress::Page(None),
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let cursor = match std::mem::replace(&mut state.progress, Progress::Done) {
+                Progress::Page(cursor) => cursor,
+                Progress::Done => return None,
+            };
+
+            let (games, next) =
+                match GameService::list_games(&state.db, cursor, EXPORT_PAGE_SIZE, Some(state.player_id), None, None).await {
+                    Ok(page) => page,
+                    Err(err) => return Some((Err(err.into()), state)),
+                };
+            let pgn = match render_page(&state.db, &games).await {
+                Ok(pgn) => pgn,
+                Err(err) => return Some((Err(err), state)),
+            };
+
+            let chunk = match state.zip.take() {
+                None => Ok(pgn.into_bytes()),
+                Some(mut zip) => {
+                    let mut chunk = zip.write(pgn.as_bytes());
+                    if next.is_some() {
+                        state.zip = Some(zip);
+                        Ok(chunk)
+                    } else {
+                        zip.finish().map(|rest| {
+                            chunk.extend(rest);
+                            chunk
+                        })
+                    }
+                }
+            };
+            if next.is_some() {
+                state.progress = Progress::Page(next);
+            }
+            Some((chunk, state))
+        })
+    }
+}
+
+/// PGN of a page of games, each followed by a blank line.
+pub(crate) async fn render_page(db: &DatabaseConnection, games: &[game::Model]) -> Result<String, ApiError> {
+    let ids: HashSet<Uuid> = games
+        .iter()
+        .flat_map(|game| [game.white_player, game.black_player])
+        .collect();
+    let players: HashMap<Uuid, (String, Option<ChessTitle>)> = if ids.is_empty() {
+        HashMap::new()
+    } else {
+        player::Entity::find()
+            .filter(player::Column::Id.is_in(ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| (p.id, (p.username, p.title)))
+            .collect()
+    };
+    let seat = |id: &Uuid| players.get(id).cloned().unwrap_or_else(|| ("?".to_string(), None));
+
+    let mut out = String::new();
+    for game in games {
+        out.push_str(&game_pgn(game, seat(&game.white_player), seat(&game.black_player))?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// `white` and `black` are the players' usernames and verified titles
+fn game_pgn(
+    game: &game::Model,
+    white: (String, Option<ChessTitle>),
+    black: (String, Option<ChessTitle>),
+) -> Result<String, ApiError> {
+    let mut headers = headers_of(game, white.0, black.0);
+    headers.other.extend(title_tags(game, white.1, black.1));
+    // Odds games start from their handicap position
+    if let Some(odds) = game.odds.clone() {
+        let odds: chess::Odds = serde_json::from_value(odds)
+            .map_err(|err| ApiError::Internal(format!("Stored odds are unreadable: {}", err)))?;
+        let tags = odds.pgn_tags().map_err(|err| ApiError::Internal(err.to_string()))?;
+        headers.other.extend(tags);
+    }
+    Ok(write_pgn(&headers, &moves_of(game)?, &AnnotationTree::default()))
+}
+
+/// Writes one deflated file of a ZIP archive, chunk by chunk. Sizes and
+/// checksum follow the file data in a data descriptor, so nothing has to be
+/// known up front.
+pub(crate) struct ZipEntryWriter {
+    name: String,
+    modified: (u16, u16),
+    /// Where the entry's local header starts in the archive
+    offset: u64,
+    encoder: DeflateEncoder<Vec<u8>>,
+    crc: Crc,
+    compressed: u64,
+    started: bool,
+}
+
+/// What the central directory records about a finished entry.
+pub(crate) struct ZipEntryRecord {
+    name: String,
+    modified: (u16, u16),
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    offset: u32,
+}
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+/// Version 2.0: deflate and data descriptors
+const ZIP_VERSION: u16 = 20;
+/// Sizes in a data descriptor, names in UTF-8
+const ZIP_FLAGS: u16 = 0x0008 | 0x0800;
+const DEFLATE: u16 = 8;
+
+fn too_large() -> ApiError {
+    ApiError::BadRequest("Archive exceeds the 4 GiB ZIP limit".to_string())
+}
+
+impl ZipEntryWriter {
+    /// The only file of an archive.
+    pub(crate) fn new(name: &str, modified: DateTime<Utc>) -> Self {
+        Self::at(name, modified, 0)
+    }
+
+    /// A file whose local header starts `offset` bytes into the archive.
+    pub(crate) fn at(name: &str, modified: DateTime<Utc>, offset: u64) -> Self {
+        ZipEntryWriter {
+            name: name.to_string(),
+            modified: dos_time(modified),
+            offset,
+            encoder: DeflateEncoder::new(Vec::new(), Compression::default()),
+            crc: Crc::new(),
+            compressed: 0,
+            started: false,
+        }
+    }
+
+    /// Compress `data`, returning the bytes of the archive ready so far.
+    pub(crate) fn write(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        if !self.started {
+            self.started = true;
+            out.extend(self.local_header());
+        }
+        self.crc.update(data);
+        // Writing into a Vec cannot fail
+        let _ = self.encoder.write_all(data);
+        let compressed = std::mem::take(self.encoder.get_mut());
+        self.compressed += compressed.len() as u64;
+        out.extend(compressed);
+        out
+    }
+
+    /// The rest of the archive: remaining compressed data, the data
+    /// descriptor and the central directory.
+    pub(crate) fn finish(self) -> Result<Vec<u8>, ApiError> {
+        let (mut out, record, end) = self.finish_entry()?;
+        out.extend(central_directory(&[record], end)?);
+        Ok(out)
+    }
+
+    /// The rest of this file: remaining compressed data and the data
+    /// descriptor, with its central directory record and the offset the
+    /// next file starts at.
+    pub(crate) fn finish_entry(mut self) -> Result<(Vec<u8>, ZipEntryRecord, u64), ApiError> {
+        let mut out = self.write(&[]);
+        let rest = self
+            .encoder
+            .finish()
+            .map_err(|err| ApiError::Internal(format!("Failed to compress archive: {}", err)))?;
+        self.compressed += rest.len() as u64;
+        out.extend(rest);
+
+        let compressed = u32::try_from(self.compressed).map_err(|_| too_large())?;
+        let size = self.crc.amount();
+        let crc = self.crc.sum();
+        out.extend(DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+        out.extend(crc.to_le_bytes());
+        out.extend(compressed.to_le_bytes());
+        out.extend(size.to_le_bytes());
+
+        let end = self.offset + 30 + self.name.len() as u64 + self.compressed + 16;
+        let record = ZipEntryRecord {
+            name: self.name,
+            modified: self.modified,
+            crc,
+            compressed,
+            size,
+            offset: u32::try_from(self.offset).map_err(|_| too_large())?,
+        };
+        Ok((out, record, end))
+    }
+
+    fn local_header(&self) -> Vec<u8> {
+        let name = self.name.as_bytes();
+        let mut out = Vec::new();
+        out.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        out.extend(ZIP_VERSION.to_le_bytes());
+        out.extend(ZIP_FLAGS.to_le_bytes());
+        out.extend(DEFLATE.to_le_bytes());
+        out.extend(self.modified.0.to_le_bytes());
+        out.extend(self.modified.1.to_le_bytes());
+        out.extend([0; 12]); // checksum and sizes, in the data descriptor
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend(0u16.to_le_bytes()); // extra field length
+        out.extend(name);
+        out
+    }
+}
+
+/// Central directory of the files in `records`, starting `offset` bytes
+/// into the archive, and the end of central directory record.
+pub(crate) fn central_directory(records: &[ZipEntryRecord], offset: u64) -> Result<Vec<u8>, ApiError> {
+    let mut central = Vec::new();
+    for record in records {
+        let name = record.name.as_bytes();
+        central.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        central.extend(ZIP_VERSION.to_le_bytes()); // made by
+        central.extend(ZIP_VERSION.to_le_bytes()); // needed to extract
+        central.extend(ZIP_FLAGS.to_le_bytes());
+        central.extend(DEFLATE.to_le_bytes());
+        central.extend(record.modified.0.to_le_bytes());
+        central.extend(record.modified.1.to_le_bytes());
+        central.extend(record.crc.to_le_bytes());
+        central.extend(record.compressed.to_le_bytes());
+        central.extend(record.size.to_le_bytes());
+        central.extend((name.len() as u16).to_le_bytes());
+        central.extend([0; 12]); // extra field, comment, disk, attributes
+        central.extend(record.offset.to_le_bytes());
+        central.extend(name);
+    }
+
+    let offset = u32::try_from(offset).map_err(|_| too_large())?;
+    let mut out = central.clone();
+    out.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    out.extend([0; 4]); // disk numbers
+    out.extend((records.len() as u16).to_le_bytes());
+    out.extend((records.len() as u16).to_le_bytes());
+    out.extend((central.len() as u32).to_le_bytes());
+    out.extend(offset.to_le_bytes());
+    out.extend(0u16.to_le_bytes()); // comment length
+    Ok(out)
+}
+
+/// MS-DOS time and date, as stored in ZIP headers.
+fn dos_time(at: DateTime<Utc>) -> (u16, u16) {
+    let time = (at.hour() << 11) | (at.minute() << 5) | (at.second() / 2);
+    let date = ((at.year().clamp(1980, 2107) - 1980) as u32) << 9 | (at.month() << 5) | at.day();
+    (time as u16, date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db_entity::game::ResultSide;
+    use db_entity::game_archive;
+    use flate2::read::DeflateDecoder;
+    use futures_util::StreamExt;
+    use sea_orm::{DbBackend, MockDatabase};
+    use serde_json::json;
+    use std::io::Read;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn exports_games_with_odds_tags() {
+        let at = Utc::now().fixed_offset();
+        let player_id = Uuid::new_v4();
+        let game = game::Model {
+            id: Uuid::new_v4(),
+            white_player: player_id,
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn: json!({ "moves": ["e4", "e5"] }),
+            result: Some(ResultSide::WhiteWins),
+            variant: game::GameVariant::Standard,
+            started_at: at,
+            duration_sec: 600,
+            created_at: at,
+            updated_at: at,
+            is_imported: false,
+            original_pgn: None,
+            odds: Some(json!({ "giver": "white", "removed": ["queen"] })),
+        };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![game]])
+            .append_query_results([Vec::<game_archive::Model>::new()])
+            .append_query_results([Vec::<player::Model>::new()])
+            .into_connection();
+
+        let chunks: Vec<_> = ArchiveService::export(Arc::new(db), player_id, ArchiveFormat::Pgn)
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        let pgn = String::from_utf8(chunks.into_iter().next().unwrap().unwrap()).unwrap();
+        assert!(pgn.contains("[White \"?\"]"));
+        assert!(pgn.contains("[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1\"]"));
+        assert!(pgn.contains("1. e4 e5 1-0"));
+    }
+
+    #[test]
+    fn zip_entry_round_trips() {
+        let chunks = ["[Event \"A\"]\n\n1. e4 e5 *\n\n", "[Event \"B\"]\n\n1. d4 *\n\n"];
+        let mut zip = ZipEntryWriter::new(ARCHIVE_ENTRY_NAME, Utc::now());
+        let mut archive = Vec::new();
+        for chunk in chunks {
+            archive.extend(zip.write(chunk.as_bytes()));
+        }
+        archive.extend(zip.finish().unwrap());
+
+        assert_eq!(u32_at(&archive, 0), LOCAL_HEADER_SIGNATURE);
+        let data_start = 30 + ARCHIVE_ENTRY_NAME.len();
+        assert_eq!(&archive[30..data_start], ARCHIVE_ENTRY_NAME.as_bytes());
+
+        // The end of central directory record points back at the central directory
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        let central = u32_at(&archive, end + 16) as usize;
+        assert_eq!(u32_at(&archive, central), CENTRAL_HEADER_SIGNATURE);
+        let compressed = u32_at(&archive, central + 20) as usize;
+        let size = u32_at(&archive, central + 24) as usize;
+
+        let descriptor = data_start + compressed;
+        assert_eq!(u32_at(&archive, descriptor), DATA_DESCRIPTOR_SIGNATURE);
+        assert_eq!(descriptor + 16, central);
+
+        let mut text = String::new();
+        DeflateDecoder::new(&archive[data_start..descriptor])
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, chunks.concat());
+        assert_eq!(size, text.len());
+
+        let mut crc = Crc::new();
+        crc.update(text.as_bytes());
+        assert_eq!(u32_at(&archive, descriptor + 4), crc.sum());
+    }
+
+    #[test]
+    fn dos_time_packs_fields() {
+        let at = DateTime::parse_from_rfc3339("2026-10-16T13:45:58Z").unwrap().with_timezone(&Utc);
+        let (time, date) = dos_time(at);
+        assert_eq!(time >> 11, 13);
+        assert_eq!((time >> 5) & 0x3f, 45);
+        assert_eq!(time & 0x1f, 29);
+        assert_eq!(date >> 9, 46);
+        assert_eq!((date >> 5) & 0x0f, 10);
+        assert_eq!(date & 0x1f, 16);
+    }
+}
@@ -0,0 +1,453 @@
+pub use attestation::rpc::{StarknetConfig, StarknetRpcClient};
+pub use attestation::{AttestationChain, FieldElement, NonceManager};
+
+use attestation::{pgn_hash, Attestation, AttestedResult, ChainError, TxStatus};
+use chess::{write_pgn, AnnotationTree, PgnGameResult, PgnHeaders};
+use chrono::{Duration, Utc};
+use db_entity::game::{self, ResultSide};
+use db_entity::game_attestation::{self, AttestationStatus};
+use dto::games::GameAttestationResponse;
+use error::error::ApiError;
+use sea_orm::sea_query::Query;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::annotations::moves_of;
+use crate::game_archive::GameArchiveService;
+use crate::game_events::GameEventService;
+
+/// Attestations submitted or checked per pass of the submitter
+pub const ATTESTATION_BATCH: u64 = 20;
+
+/// Submissions of one game before it is marked failed
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Delay before the first retry; doubled after each further failure
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// What one pass of the submitter did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SubmitterPass {
+    pub queued: usize,
+    pub submitted: usize,
+    pub confirmed: usize,
+    pub failed: usize,
+}
+
+pub struct AttestationService;
+
+impl AttestationService {
+    /// Queue finished rated games that have no attestation yet, then send
+    /// due ones and check on those already sent.
+    pub async fn run_pass(
+        db: &DatabaseConnection,
+        chain: &dyn AttestationChain,
+        nonces: &mut NonceManager,
+    ) -> Result<SubmitterPass, ApiError> {
+        let mut pass = SubmitterPass {
+            queued: Self::enqueue_finished(db, ATTESTATION_BATCH).await?,
+            ..Default::default()
+        };
+        Self::confirm_submitted(db, chain, nonces, &mut pass).await?;
+        Self::submit_due(db, chain, nonces, &mut pass).await?;
+        Ok(pass)
+    }
+
+    /// Add pending attestations for up to `limit` finished rated games.
+    pub async fn enqueue_finished(db: &DatabaseConnection, limit: u64) -> Result<usize, ApiError> {
+        let games = game::Entity::find()
+            .filter(game::Column::Result.is_in([ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw]))
+            .filter(game::Column::IsImported.eq(false))
+            .filter(game::Column::Odds.is_null())
+            .filter(
+                game::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(game_attestation::Column::GameId)
+                        .from(game_attestation::Entity)
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(game::Column::UpdatedAt)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        let mut queued = 0;
+        for game in games.into_iter().filter(crate::games::is_rated) {
+            // The attested PGN is written from the game's log when it has one
+            let game = GameEventService::with_logged_moves(db, game).await?;
+            let Some(attestation) = attestation_of(&game)? else {
+                continue;
+            };
+            let now = Utc::now().fixed_offset();
+            game_attestation::ActiveModel {
+                game_id: Set(game.id),
+                result: Set(attestation.result.code()),
+                pgn_hash: Set(hex(attestation.pgn_hash)),
+                status: Set(AttestationStatus::Pending),
+                tx_hash: Set(None),
+                nonce: Set(None),
+                attempts: Set(0),
+                last_error: Set(None),
+                next_attempt_at: Set(now),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(db)
+            .await?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Send pending attestations whose retry time has come.
+    async fn submit_due(
+        db: &DatabaseConnection,
+        chain: &dyn AttestationChain,
+        nonces: &mut NonceManager,
+        pass: &mut SubmitterPass,
+    ) -> Result<(), ApiError> {
+        let due = game_attestation::Entity::find()
+            .filter(game_attestation::Column::Status.eq(AttestationStatus::Pending))
+            .filter(game_attestation::Column::NextAttemptAt.lte(Utc::now().fixed_offset()))
+            .order_by_asc(game_attestation::Column::NextAttemptAt)
+            .limit(ATTESTATION_BATCH)
+            .find_also_related(game::Entity)
+            .all(db)
+            .await?;
+
+        for (row, game) in due {
+            let Some(game) = game else { continue };
+            let attestation = stored_attestation(&row, &game)?;
+
+            let nonce = match nonces.next(chain).await {
+                Ok(nonce) => nonce,
+                Err(err) => {
+                    // The node is unreachable; leave the rest for the next pass
+                    log::warn!("Cannot read the attestation account nonce: {}", err);
+                    return Ok(());
+                }
+            };
+            match chain.submit(&attestation, nonce).await {
+                Ok(tx_hash) => {
+                    nonces.advance();
+                    let attempts = row.attempts + 1;
+                    let mut row = row.into_active_model();
+                    row.status = Set(AttestationStatus::Submitted);
+                    row.tx_hash = Set(Some(hex(tx_hash)));
+                    row.nonce = Set(u64::try_from(nonce).ok().and_then(|nonce| i64::try_from(nonce).ok()));
+                    row.attempts = Set(attempts);
+                    row.last_error = Set(None);
+                    row.updated_at = Set(Utc::now().fixed_offset());
+                    row.update(db).await?;
+                    pass.submitted += 1;
+                }
+                Err(err) => {
+                    nonces.reset();
+                    if record_failure(db, row, &err).await? {
+                        pass.failed += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Confirm sent attestations the chain accepted, and fail rejected ones.
+    async fn confirm_submitted(
+        db: &DatabaseConnection,
+        chain: &dyn AttestationChain,
+        nonces: &mut NonceManager,
+        pass: &mut SubmitterPass,
+    ) -> Result<(), ApiError> {
+        let sent = game_attestation::Entity::find()
+            .filter(game_attestation::Column::Status.eq(AttestationStatus::Submitted))
+            .order_by_asc(game_attestation::Column::UpdatedAt)
+            .limit(ATTESTATION_BATCH)
+            .all(db)
+            .await?;
+
+        for row in sent {
+            let Some(tx_hash) = row.tx_hash.as_deref().and_then(|hash| FieldElement::from_hex_be(hash).ok()) else {
+                record_failure(db, row, &ChainError::Malformed("stored transaction hash is unreadable".to_string()))
+                    .await?;
+                continue;
+            };
+            match chain.transaction_status(tx_hash).await {
+                Ok(TxStatus::Pending) => {}
+                Ok(TxStatus::Accepted) => {
+                    let mut row = row.into_active_model();
+                    row.status = Set(AttestationStatus::Confirmed);
+                    row.updated_at = Set(Utc::now().fixed_offset());
+                    row.update(db).await?;
+                    pass.confirmed += 1;
+                }
+                Ok(TxStatus::Rejected(reason)) => {
+                    // A rejected transaction may or may not have used its nonce
+                    nonces.reset();
+                    if record_failure(db, row, &ChainError::Rejected(reason)).await? {
+                        pass.failed += 1;
+                    }
+                }
+                Err(err) => log::warn!("Cannot check attestation transaction {}: {}", hex(tx_hash), err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Attestation state of a game. With a chain, confirmed attestations are
+    /// read back and compared with what the game record says now.
+    pub async fn verify(
+        db: &DatabaseConnection,
+        chain: Option<&dyn AttestationChain>,
+        game_id: Uuid,
+    ) -> Result<GameAttestationResponse, ApiError> {
+        let row = game_attestation::Entity::find_by_id(game_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Attestation".to_string()))?;
+        // Confirmed attestations outlive the game in the hot table
+        let game = GameArchiveService::find(db, game_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+        let game = GameEventService::with_logged_moves(db, game).await?;
+
+        let verified = match chain {
+            Some(chain) if row.status == AttestationStatus::Confirmed => {
+                let on_chain = chain
+                    .attestation(game_id)
+                    .await
+                    .map_err(|err| ApiError::BadGateway(err.to_string()))?;
+                Some(on_chain.is_some() && on_chain == attestation_of(&game)?)
+            }
+            _ => None,
+        };
+
+        Ok(GameAttestationResponse {
+            game_id,
+            status: row.status.into(),
+            pgn_hash: row.pgn_hash,
+            tx_hash: row.tx_hash,
+            attempts: row.attempts,
+            last_error: row.last_error,
+            verified,
+        })
+    }
+}
+
+/// What the contract should hold for a finished game; `None` for games
+/// without a decisive or drawn result.
+pub fn attestation_of(game: &game::Model) -> Result<Option<Attestation>, ApiError> {
+    let result = match game.result {
+        Some(ResultSide::WhiteWins) => AttestedResult::WhiteWins,
+        Some(ResultSide::BlackWins) => AttestedResult::BlackWins,
+        Some(ResultSide::Draw) => AttestedResult::Draw,
+        _ => return Ok(None),
+    };
+    Ok(Some(Attestation {
+        game_id: game.id,
+        white: game.white_player,
+        black: game.black_player,
+        result,
+        pgn_hash: pgn_hash(&canonical_pgn(game, result)?),
+    }))
+}
+
+/// PGN whose hash is attested. Players are named by id and only the seven
+/// tag roster is kept, so renaming an account does not change the hash.
+pub fn canonical_pgn(game: &game::Model, result: AttestedResult) -> Result<String, ApiError> {
+    let headers = PgnHeaders {
+        event: None,
+        site: None,
+        date: Some(game.started_at.format("%Y.%m.%d").to_string()),
+        round: None,
+        white: game.white_player.to_string(),
+        black: game.black_player.to_string(),
+        result: match result {
+            AttestedResult::WhiteWins => PgnGameResult::WhiteWins,
+            AttestedResult::BlackWins => PgnGameResult::BlackWins,
+            AttestedResult::Draw => PgnGameResult::Draw,
+        },
+        other: HashMap::new(),
+    };
+    Ok(write_pgn(&headers, &moves_of(game)?, &AnnotationTree::default()))
+}
+
+// The attestation as queued, so retries send exactly what was hashed first
+fn stored_attestation(row: &game_attestation::Model, game: &game::Model) -> Result<Attestation, ApiError> {
+    let unreadable = |what: &str| ApiError::Internal(format!("Stored attestation has an unreadable {}", what));
+    Ok(Attestation {
+        game_id: row.game_id,
+        white: game.white_player,
+        black: game.black_player,
+        result: AttestedResult::from_code(row.result).ok_or_else(|| unreadable("result"))?,
+        pgn_hash: FieldElement::from_hex_be(&row.pgn_hash).map_err(|_| unreadable("PGN hash"))?,
+    })
+}
+
+// Schedule a retry with exponential backoff, or give up on errors resending
+// cannot fix and after too many attempts; returns whether it failed for good
+async fn record_failure(
+    db: &DatabaseConnection,
+    row: game_attestation::Model,
+    err: &ChainError,
+) -> Result<bool, ApiError> {
+    let attempts = row.attempts + 1;
+    let failed = !err.is_retryable() || attempts >= MAX_ATTEMPTS;
+    let now = Utc::now().fixed_offset();
+    log::warn!("Attestation of game {} failed (attempt {}): {}", row.game_id, attempts, err);
+
+    let mut row = row.into_active_model();
+    row.status = Set(if failed { AttestationStatus::Failed } else { AttestationStatus::Pending });
+    row.tx_hash = Set(None);
+    row.attempts = Set(attempts);
+    row.last_error = Set(Some(err.to_string()));
+    row.next_attempt_at = Set(now + retry_delay(attempts));
+    row.updated_at = Set(now);
+    row.update(db).await?;
+    Ok(failed)
+}
+
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::seconds((RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS))
+}
+
+fn hex(felt: FieldElement) -> String {
+    format!("{:#x}", felt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attestation::mock::MockChain;
+    use chrono::FixedOffset;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn finished_game() -> game::Model {
+        let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string(),
+            pgn: serde_json::json!({"moves": ["f2f3", "e7e5", "g2g4", "d8h4"]}),
+            result: Some(ResultSide::BlackWins),
+            variant: game::GameVariant::Standard,
+            started_at: now,
+            duration_sec: 30,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    fn pending_row(game: &game::Model, attempts: i32) -> game_attestation::Model {
+        let attestation = attestation_of(game).unwrap().unwrap();
+        let now = Utc::now().fixed_offset();
+        game_attestation::Model {
+            game_id: game.id,
+            result: attestation.result.code(),
+            pgn_hash: hex(attestation.pgn_hash),
+            status: AttestationStatus::Pending,
+            tx_hash: None,
+            nonce: None,
+            attempts,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn pgn_hash_ignores_names_but_not_moves() {
+        let game = finished_game();
+        let attestation = attestation_of(&game).unwrap().unwrap();
+        assert_eq!(attestation.result, AttestedResult::BlackWins);
+
+        let mut renamed = game.clone();
+        renamed.pgn = serde_json::json!({"headers": {"White": "someone"}, "moves": ["f2f3", "e7e5", "g2g4", "d8h4"]});
+        assert_eq!(attestation_of(&renamed).unwrap().unwrap().pgn_hash, attestation.pgn_hash);
+
+        let mut edited = game.clone();
+        edited.pgn = serde_json::json!({"moves": ["f2f4", "e7e5", "g2g4", "d8h4"]});
+        assert_ne!(attestation_of(&edited).unwrap().unwrap().pgn_hash, attestation.pgn_hash);
+
+        let mut ongoing = game;
+        ongoing.result = Some(ResultSide::Ongoing);
+        assert!(attestation_of(&ongoing).unwrap().is_none());
+    }
+
+    #[test]
+    fn retries_back_off_exponentially_up_to_a_cap() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(3), Duration::seconds(120));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), Duration::seconds(RETRY_MAX_SECS));
+    }
+
+    #[tokio::test]
+    async fn due_attestations_are_submitted_with_consecutive_nonces() {
+        let (first, second) = (finished_game(), finished_game());
+        let (first_row, second_row) = (pending_row(&first, 0), pending_row(&second, 0));
+        let sent = |row: &game_attestation::Model, nonce| game_attestation::Model {
+            status: AttestationStatus::Submitted,
+            nonce: Some(nonce),
+            attempts: 1,
+            ..row.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![(first_row.clone(), first.clone()), (second_row.clone(), second.clone())]])
+            .append_query_results([vec![sent(&first_row, 0)], vec![sent(&second_row, 1)]])
+            .into_connection();
+
+        let chain = MockChain::new();
+        let mut nonces = NonceManager::new();
+        let mut pass = SubmitterPass::default();
+        AttestationService::submit_due(&db, &chain, &mut nonces, &mut pass).await.unwrap();
+
+        assert_eq!(pass.submitted, 2);
+        assert_eq!(chain.submitted(), 2);
+        let on_chain = chain.attestation(second.id).await.unwrap().unwrap();
+        assert_eq!(Some(on_chain), attestation_of(&second).unwrap());
+    }
+
+    #[tokio::test]
+    async fn failed_submissions_are_retried_then_given_up() {
+        let game = finished_game();
+        let retried = game_attestation::Model {
+            attempts: 1,
+            ..pending_row(&game, 0)
+        };
+        let failed = game_attestation::Model {
+            status: AttestationStatus::Failed,
+            attempts: MAX_ATTEMPTS,
+            ..pending_row(&game, 0)
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![(pending_row(&game, 0), game.clone())]])
+            .append_query_results([vec![retried]])
+            .append_query_results([vec![(pending_row(&game, MAX_ATTEMPTS - 1), game.clone())]])
+            .append_query_results([vec![failed]])
+            .into_connection();
+
+        let chain = MockChain::new();
+        chain.fail_next([ChainError::Transport("timeout".to_string()), ChainError::InvalidNonce]);
+        let mut nonces = NonceManager::new();
+
+        let mut pass = SubmitterPass::default();
+        AttestationService::submit_due(&db, &chain, &mut nonces, &mut pass).await.unwrap();
+        assert_eq!(pass, SubmitterPass::default());
+
+        AttestationService::submit_due(&db, &chain, &mut nonces, &mut pass).await.unwrap();
+        assert_eq!(pass.failed, 1);
+        assert_eq!(chain.submitted(), 0);
+    }
+}
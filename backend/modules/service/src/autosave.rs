@@ -0,0 +1,391 @@
+use db_entity::game;
+use dto::games::{AutosaveStats, GameEvent, GameLogState};
+use error::error::ApiError;
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait, QuerySelect, TransactionTrait};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::game_events::{accept, write, written_seq, Accepted, Fold, GameEventService};
+
+/// Games with nothing buffered are dropped from memory after this long
+/// without an event, and loaded from their log again on the next one
+const IDLE_AFTER: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+pub struct AutosaveConfig {
+    /// Longest an accepted event waits in memory; zero writes each event
+    /// at once
+    pub interval: Duration,
+    /// Events of one game written as soon as this many are waiting
+    pub max_buffered: usize,
+}
+
+/// A running game held in memory: its row, its state after every event
+/// accepted so far, and the events not written yet.
+struct Buffered {
+    game: game::Model,
+    fold: Fold,
+    /// Sequence number of the latest event in the stored log
+    written_seq: i32,
+    pending: Vec<Accepted>,
+    last_event_at: Instant,
+}
+
+type Slot = Arc<tokio::sync::Mutex<Option<Buffered>>>;
+
+/// Write-behind buffer in front of [`GameEventService::append`], so a
+/// bullet game costs a write every interval rather than every move.
+///
+/// Events are checked against the game's state in memory and answered at
+/// once; they reach the log in order, one transaction per game, on each
+/// [`MoveBuffer::flush_due`], when [`AutosaveConfig::max_buffered`] are
+/// waiting, and as soon as a game ends.
+///
+/// Crash consistency: events still in memory when the process dies are
+/// lost, at most one interval of them, even though the players were told
+/// they were accepted. The log stays whole: a batch is written in one
+/// transaction, so it lands entirely or not at all, and the log ends at
+/// the last batch written. Clients that resync from the log then see the
+/// game as it was then and replay their moves. A game's events must go
+/// through one buffer; a batch finding the log grown since it was read is
+/// dropped rather than written over it.
+pub struct MoveBuffer {
+    config: AutosaveConfig,
+    games: Mutex<HashMap<Uuid, Slot>>,
+    buffered_events: AtomicUsize,
+    flushes: AtomicU64,
+    flushed_events: AtomicU64,
+    failed_flushes: AtomicU64,
+    discarded_events: AtomicU64,
+}
+
+impl MoveBuffer {
+    pub fn new(config: AutosaveConfig) -> Self {
+        Self {
+            config: AutosaveConfig { max_buffered: config.max_buffered.max(1), ..config },
+            games: Mutex::new(HashMap::new()),
+            buffered_events: AtomicUsize::new(0),
+            flushes: AtomicU64::new(0),
+            flushed_events: AtomicU64::new(0),
+            failed_flushes: AtomicU64::new(0),
+            discarded_events: AtomicU64::new(0),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    /// Like [`GameEventService::append`], but the event is written later,
+    /// unless it ends the game or fills the game's batch.
+    pub async fn append(
+        &self,
+        db: &DatabaseConnection,
+        game_id: Uuid,
+        actor: Option<Uuid>,
+        event: GameEvent,
+    ) -> Result<GameLogState, ApiError> {
+        if self.config.interval.is_zero() {
+            return GameEventService::append(db, game_id, actor, event).await;
+        }
+
+        let slot = self.slot(game_id);
+        let mut held = slot.lock().await;
+        if held.is_none() {
+            *held = Some(load(db, game_id).await?);
+        }
+        let buffered = held.as_mut().expect("loaded above");
+        let accepted = accept(db, &buffered.game, &mut buffered.fold, actor, event).await?;
+        let ends_game = accepted.ends_game();
+        buffered.pending.push(accepted);
+        buffered.last_event_at = Instant::now();
+        self.buffered_events.fetch_add(1, Ordering::Relaxed);
+        let state = buffered.fold.state.clone();
+
+        if ends_game || buffered.pending.len() >= self.config.max_buffered {
+            match self.flush_slot(db, &mut held).await {
+                Ok(()) if ends_game => {
+                    *held = None;
+                    self.forget(game_id, &slot);
+                }
+                Ok(()) => {}
+                // The event is gone with the rest of the batch
+                Err(err) if held.is_none() => return Err(err),
+                // Still buffered; the next pass tries again
+                Err(err) => log::error!("Failed to write the events of game {}: {}", game_id, err),
+            }
+        }
+        Ok(state)
+    }
+
+    /// Write out every game with events waiting, and drop idle games from
+    /// memory. Runs every [`AutosaveConfig::interval`].
+    pub async fn flush_due(&self, db: &DatabaseConnection) {
+        for (game_id, slot) in self.slots() {
+            let mut held = slot.lock().await;
+            let idle = match held.as_ref() {
+                Some(buffered) => buffered.pending.is_empty() && buffered.last_event_at.elapsed() >= IDLE_AFTER,
+                None => true,
+            };
+            if idle {
+                *held = None;
+                self.forget(game_id, &slot);
+                continue;
+            }
+            if let Err(err) = self.flush_slot(db, &mut held).await {
+                log::error!("Failed to write the events of game {}: {}", game_id, err);
+            }
+        }
+    }
+
+    /// Write out the events of one game now, so the stored log has every
+    /// event accepted so far.
+    pub async fn flush_game(&self, db: &DatabaseConnection, game_id: Uuid) -> Result<(), ApiError> {
+        let slot = self.games.lock().unwrap().get(&game_id).cloned();
+        match slot {
+            Some(slot) => self.flush_slot(db, &mut *slot.lock().await).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Write out everything waiting, e.g. before the server stops.
+    pub async fn flush_all(&self, db: &DatabaseConnection) {
+        for (game_id, slot) in self.slots() {
+            if let Err(err) = self.flush_slot(db, &mut *slot.lock().await).await {
+                log::error!("Failed to write the events of game {}: {}", game_id, err);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> AutosaveStats {
+        AutosaveStats {
+            interval_ms: self.config.interval.as_millis() as u64,
+            max_buffered: self.config.max_buffered,
+            games: self.games.lock().unwrap().len(),
+            buffered_events: self.buffered_events.load(Ordering::Relaxed),
+            flushes: self.flushes.load(Ordering::Relaxed),
+            flushed_events: self.flushed_events.load(Ordering::Relaxed),
+            failed_flushes: self.failed_flushes.load(Ordering::Relaxed),
+            discarded_events: self.discarded_events.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write the pending events of a held game in one transaction. When the
+    /// stored log has grown since the game was loaded, the game is dropped
+    /// from memory with its pending events; on other failures they stay.
+    async fn flush_slot(&self, db: &DatabaseConnection, held: &mut Option<Buffered>) -> Result<(), ApiError> {
+        let Some(buffered) = held.as_mut() else {
+            return Ok(());
+        };
+        let count = buffered.pending.len();
+        if count == 0 {
+            return Ok(());
+        }
+
+        match write_batch(db, buffered).await {
+            Ok(()) => {
+                buffered.written_seq = buffered.fold.state.seq;
+                buffered.pending.clear();
+                self.buffered_events.fetch_sub(count, Ordering::Relaxed);
+                self.flushes.fetch_add(1, Ordering::Relaxed);
+                self.flushed_events.fetch_add(count as u64, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(Flush::Diverged) => {
+                let game_id = buffered.game.id;
+                *held = None;
+                self.buffered_events.fetch_sub(count, Ordering::Relaxed);
+                self.discarded_events.fetch_add(count as u64, Ordering::Relaxed);
+                Err(ApiError::BadRequest(format!(
+                    "The log of game {} changed elsewhere; {} unwritten events were dropped",
+                    game_id, count
+                )))
+            }
+            Err(Flush::Failed(err)) => {
+                self.failed_flushes.fetch_add(1, Ordering::Relaxed);
+                Err(err)
+            }
+        }
+    }
+
+    fn slot(&self, game_id: Uuid) -> Slot {
+        self.games.lock().unwrap().entry(game_id).or_default().clone()
+    }
+
+    fn slots(&self) -> Vec<(Uuid, Slot)> {
+        self.games.lock().unwrap().iter().map(|(id, slot)| (*id, slot.clone())).collect()
+    }
+
+    /// Drop a game's slot, unless it was replaced meanwhile.
+    fn forget(&self, game_id: Uuid, slot: &Slot) {
+        let mut games = self.games.lock().unwrap();
+        if games.get(&game_id).is_some_and(|held| Arc::ptr_eq(held, slot)) {
+            games.remove(&game_id);
+        }
+    }
+}
+
+enum Flush {
+    /// Someone else wrote to the game's log since it was loaded
+    Diverged,
+    Failed(ApiError),
+}
+
+impl From<ApiError> for Flush {
+    fn from(err: ApiError) -> Self {
+        Flush::Failed(err)
+    }
+}
+
+impl From<DbErr> for Flush {
+    fn from(err: DbErr) -> Self {
+        Flush::Failed(err.into())
+    }
+}
+
+async fn load(db: &DatabaseConnection, game_id: Uuid) -> Result<Buffered, ApiError> {
+    let game = game::Entity::find_by_id(game_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+    let fold = Fold::load(db, &game).await?;
+    Ok(Buffered { written_seq: fold.state.seq, game, fold, pending: Vec::new(), last_event_at: Instant::now() })
+}
+
+async fn write_batch(db: &DatabaseConnection, buffered: &Buffered) -> Result<(), Flush> {
+    let txn = db.begin().await?;
+    // The same lock as unbuffered appends take
+    let game = game::Entity::find_by_id(buffered.game.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+    if written_seq(&txn, game.id).await? != buffered.written_seq {
+        return Err(Flush::Diverged);
+    }
+    write(&txn, game, buffered.pending.clone(), &buffered.fold.state).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use db_entity::game::GameVariant;
+    use db_entity::game_event::{self, GameEventKind};
+    use db_entity::{game_snapshot, webhook_subscription};
+    use dto::games::GameResult;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use serde_json::json;
+
+    fn game() -> game::Model {
+        let now = Utc::now().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn: json!({}),
+            result: None,
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 60,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    fn play(notation: &str) -> GameEvent {
+        GameEvent::Move { notation: notation.to_string(), clock_ms: None }
+    }
+
+    fn buffer(max_buffered: usize) -> MoveBuffer {
+        MoveBuffer::new(AutosaveConfig { interval: Duration::from_secs(1), max_buffered })
+    }
+
+    /// A database that loads `game` with an empty log
+    fn loaded(game: &game::Model) -> MockDatabase {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![game.clone()]])
+            .append_query_results([Vec::<game_snapshot::Model>::new()])
+            .append_query_results([Vec::<game_event::Model>::new()])
+    }
+
+    #[tokio::test]
+    async fn test_moves_wait_in_memory_until_the_batch_is_full() {
+        let game = game();
+        let db = loaded(&game)
+            // The batch: lock, log check, events, game row
+            .append_query_results([vec![game.clone()]])
+            .append_query_results([Vec::<game_event::Model>::new()])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 3 }])
+            .append_query_results([vec![game.clone()]])
+            .into_connection();
+        let buffer = buffer(3);
+
+        buffer.append(&db, game.id, Some(game.white_player), play("e4")).await.unwrap();
+        let state = buffer.append(&db, game.id, Some(game.black_player), play("e5")).await.unwrap();
+        assert_eq!((state.seq, state.moves.len()), (2, 2));
+        assert_eq!((buffer.stats().buffered_events, buffer.stats().flushes), (2, 0));
+
+        // Refused events are not buffered
+        assert!(buffer.append(&db, game.id, Some(game.black_player), play("d5")).await.is_err());
+        buffer.append(&db, game.id, Some(game.white_player), play("Nf3")).await.unwrap();
+
+        let stats = buffer.stats();
+        assert_eq!((stats.games, stats.buffered_events, stats.flushes, stats.flushed_events), (1, 0, 1, 3));
+    }
+
+    #[tokio::test]
+    async fn test_ending_a_game_writes_it_out_and_forgets_it() {
+        let game = game();
+        let db = loaded(&game)
+            .append_query_results([vec![game.clone()]])
+            .append_query_results([Vec::<game_event::Model>::new()])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 2 }])
+            // No webhook subscriptions to the end of the game
+            .append_query_results([Vec::<webhook_subscription::Model>::new()])
+            .append_query_results([vec![game.clone()]])
+            .into_connection();
+        let buffer = buffer(20);
+
+        buffer.append(&db, game.id, Some(game.white_player), play("e4")).await.unwrap();
+        let resigned = GameEvent::Ended { result: GameResult::WhiteWin, termination: "resignation".to_string() };
+        let state = buffer.append(&db, game.id, Some(game.black_player), resigned).await.unwrap();
+
+        assert_eq!(state.result, GameResult::WhiteWin);
+        let stats = buffer.stats();
+        assert_eq!((stats.games, stats.buffered_events, stats.flushed_events), (0, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_a_log_grown_elsewhere_drops_the_batch() {
+        let game = game();
+        let written = game_event::Model {
+            game_id: game.id,
+            seq: 1,
+            kind: GameEventKind::Move,
+            payload: json!({ "type": "move", "notation": "d4" }),
+            actor_id: Some(game.white_player),
+            recorded_at: Utc::now().fixed_offset(),
+        };
+        let db = loaded(&game)
+            .append_query_results([vec![game.clone()]])
+            .append_query_results([vec![written]])
+            .into_connection();
+        let buffer = buffer(20);
+
+        buffer.append(&db, game.id, Some(game.white_player), play("e4")).await.unwrap();
+        assert!(buffer.flush_game(&db, game.id).await.is_err());
+
+        let stats = buffer.stats();
+        assert_eq!((stats.buffered_events, stats.flushes, stats.discarded_events), (0, 0, 1));
+    }
+}
@@ -0,0 +1,173 @@
+use chess::Referee;
+use dto::ai::{BotDisplay, BotGameDisplay, BotMoveRequest, BotMoveResponse, StartBotGameRequest};
+use dto::games::PlayerColor;
+use engine::EngineError;
+use engine::bot::BotClock;
+use error::error::ApiError;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::engine_service::{BotProfile, EngineService};
+use crate::games::STARTING_FEN;
+
+/// Games against engine bots. The bot answers each position it is sent,
+/// so a bot game is played like any other with the bot's moves relayed
+/// by the client. Replies take as long as the bot's `MoveTiming` says,
+/// not as long as the search.
+pub struct BotService;
+
+impl BotService {
+    pub fn list() -> Vec<BotDisplay> {
+        BotProfile::builtin()
+            .into_iter()
+            .map(|profile| BotDisplay {
+                id: profile.id,
+                name: profile.name,
+                description: profile.description,
+                rating: profile.rating,
+            })
+            .collect()
+    }
+
+    pub fn find(id: &str) -> Result<BotProfile, ApiError> {
+        BotProfile::find(id).ok_or_else(|| ApiError::NotFound(format!("Bot '{}'", id)))
+    }
+
+    /// Start a game against a bot, with the bot's first move when it has
+    /// White. `user` is who the engine searches for.
+    pub async fn start_game(
+        engine_service: &EngineService,
+        user: &str,
+        request: StartBotGameRequest,
+    ) -> Result<BotGameDisplay, ApiError> {
+        let profile = Self::find(&request.bot)?;
+        let id = Uuid::new_v4();
+        let bot_color = match request.player_color {
+            Some(PlayerColor::White) => PlayerColor::Black,
+            Some(PlayerColor::Black) => PlayerColor::White,
+            // Decided by the game id, which is random anyway
+            Some(PlayerColor::Random) | None if id.as_bytes()[0].is_multiple_of(2) => PlayerColor::White,
+            Some(PlayerColor::Random) | None => PlayerColor::Black,
+        };
+
+        let clock = BotClock {
+            remaining_ms: request.time_control as u64 * 1_000,
+            increment_ms: request.increment as u64 * 1_000,
+        };
+        let bot_move = match bot_color {
+            PlayerColor::White => Some(Self::reply(engine_service, user, &profile, STARTING_FEN, &[], Some(clock)).await?),
+            _ => None,
+        };
+        Ok(BotGameDisplay {
+            id,
+            bot: profile.id,
+            bot_color,
+            current_fen: STARTING_FEN.to_string(),
+            time_control: request.time_control,
+            increment: request.increment,
+            rated: false,
+            bot_move,
+        })
+    }
+
+    /// The bot's move in the position of `request`.
+    pub async fn next_move(
+        engine_service: &EngineService,
+        user: &str,
+        bot: &str,
+        request: &BotMoveRequest,
+    ) -> Result<BotMoveResponse, ApiError> {
+        let profile = Self::find(bot)?;
+        let clock = request.remaining_ms.map(|remaining_ms| BotClock {
+            remaining_ms,
+            increment_ms: request.increment_ms.unwrap_or(0),
+        });
+        Self::reply(engine_service, user, &profile, &request.fen, &request.moves, clock).await
+    }
+
+    async fn reply(
+        engine_service: &EngineService,
+        user: &str,
+        profile: &BotProfile,
+        fen: &str,
+        played: &[String],
+        clock: Option<BotClock>,
+    ) -> Result<BotMoveResponse, ApiError> {
+        let referee = Referee::from_fen(fen).map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        if referee.outcome().is_some() {
+            return Err(ApiError::BadRequest("The game is over in this position".to_string()));
+        }
+
+        let start_time = std::time::Instant::now();
+        let chosen = engine_service
+            .bot_move(user, profile, fen, played, rand::random())
+            .await
+            .map_err(engine_failed)?;
+
+        // The search counts toward the time the bot takes
+        let think_time = Duration::from_millis(profile.timing.think_time_ms(chosen.complexity, clock, &mut rand::thread_rng()));
+        if let Some(wait) = think_time.checked_sub(start_time.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(BotMoveResponse {
+            chess_move: chosen.uci,
+            evaluation: chosen.evaluation,
+            from_book: chosen.from_book,
+            computation_time_ms: u32::try_from(start_time.elapsed().as_millis()).unwrap_or(u32::MAX),
+        })
+    }
+}
+
+fn engine_failed(err: EngineError) -> ApiError {
+    log::error!("Engine error while a bot was moving: {}", err);
+    ApiError::BadGateway("The bot's engine failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // No engine binary; only book moves and refusals can be answered
+    fn engine_service() -> EngineService {
+        EngineService::new("/nonexistent/engine".to_string())
+    }
+
+    #[tokio::test]
+    async fn bot_with_white_opens_from_its_book() {
+        let request = StartBotGameRequest {
+            bot: "casual".to_string(),
+            time_control: 600,
+            increment: 0,
+            player_color: Some(PlayerColor::Black),
+        };
+        let game = BotService::start_game(&engine_service(), "tester", request).await.unwrap();
+        assert_eq!((game.bot_color, game.rated), (PlayerColor::White, false));
+        let first = game.bot_move.unwrap();
+        assert!(first.from_book);
+        assert!(["e2e4", "d2d4"].contains(&first.chess_move.as_str()));
+        // Even a book move is not played instantly
+        assert!(first.computation_time_ms >= 375);
+    }
+
+    #[tokio::test]
+    async fn unknown_bots_and_finished_games_are_refused() {
+        let request = BotMoveRequest {
+            fen: STARTING_FEN.to_string(),
+            moves: Vec::new(),
+            remaining_ms: None,
+            increment_ms: None,
+        };
+        let unknown = BotService::next_move(&engine_service(), "tester", "grandmaster", &request).await;
+        assert!(matches!(unknown, Err(ApiError::NotFound(_))));
+
+        // Fool's mate
+        let mated = BotMoveRequest {
+            fen: "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string(),
+            moves: Vec::new(),
+            remaining_ms: None,
+            increment_ms: None,
+        };
+        let over = BotService::next_move(&engine_service(), "tester", "casual", &mated).await;
+        assert!(matches!(over, Err(ApiError::BadRequest(_))));
+    }
+}
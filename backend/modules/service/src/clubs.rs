@@ -0,0 +1,688 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{
+    club, club_member, game, ladder_challenge, player, tournament as tournament_entity,
+    club_member::ClubRole,
+    game::ResultSide,
+    ladder_challenge::ChallengeStatus,
+};
+use dto::clubs::{ClubDisplay, ClubMemberDisplay, ClubPage, CreateClubRequest, LadderChallengeDisplay};
+use error::error::ApiError;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    IntoActiveModel, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::game_archive::GameArchiveService;
+use crate::moderation::MAX_QUEUE_PAGE;
+use crate::tournaments::display as tournament_display;
+
+/// Places up the ladder a member may challenge, unless the club sets its own
+pub const DEFAULT_LADDER_REACH: i32 = 3;
+
+/// Ladder places and club tournaments shown on a club page
+const PAGE_LADDER: u64 = 10;
+const PAGE_TOURNAMENTS: u64 = 5;
+
+pub struct ClubService;
+
+impl ClubService {
+    /// Found a club; its founder is its owner and tops the ladder.
+    pub async fn create(
+        db: &DatabaseConnection,
+        owner_id: Uuid,
+        request: CreateClubRequest,
+    ) -> Result<club::Model, ApiError> {
+        let name = request.name.trim().to_string();
+        let taken = club::Entity::find().filter(club::Column::Name.eq(name.clone())).one(db).await?;
+        if taken.is_some() {
+            return Err(ApiError::BadRequest("A club with this name already exists".to_string()));
+        }
+
+        let now = now();
+        let txn = db.begin().await?;
+        let club = club::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(request.description),
+            owner_id: Set(owner_id),
+            is_open: Set(request.is_open),
+            ladder_reach: Set(request.ladder_reach.unwrap_or(DEFAULT_LADDER_REACH)),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        club_member::ActiveModel {
+            club_id: Set(club.id),
+            player_id: Set(owner_id),
+            role: Set(ClubRole::Owner),
+            ladder_rank: Set(1),
+            joined_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(club)
+    }
+
+    pub async fn get(db: &DatabaseConnection, club_id: Uuid) -> Result<club::Model, ApiError> {
+        club::Entity::find_by_id(club_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Club {}", club_id)))
+    }
+
+    /// Clubs by name, optionally those whose name contains `name`.
+    pub async fn list(
+        db: &DatabaseConnection,
+        name: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<ClubDisplay>, ApiError> {
+        let mut query = club::Entity::find();
+        if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+            query = query.filter(club::Column::Name.contains(name.trim()));
+        }
+        let clubs = query
+            .order_by(club::Column::Name, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?;
+
+        let mut displays = Vec::with_capacity(clubs.len());
+        for club in clubs {
+            let count = member_count(db, club.id).await?;
+            displays.push(ClubDisplay::new(club, count));
+        }
+        Ok(displays)
+    }
+
+    /// The club with the top of its ladder, its latest tournaments and the
+    /// challenges still open.
+    pub async fn page(db: &DatabaseConnection, club_id: Uuid) -> Result<ClubPage, ApiError> {
+        let club = Self::get(db, club_id).await?;
+        let count = member_count(db, club_id).await?;
+        let ladder = Self::members(db, club_id, Some(PAGE_LADDER)).await?;
+        let tournaments = tournament_entity::Entity::find()
+            .filter(tournament_entity::Column::ClubId.eq(club_id))
+            .order_by(tournament_entity::Column::CreatedAt, Order::Desc)
+            .limit(PAGE_TOURNAMENTS)
+            .all(db)
+            .await?
+            .iter()
+            .map(tournament_display)
+            .collect::<Result<Vec<_>, _>>()?;
+        let open_challenges = ladder_challenge::Entity::find()
+            .filter(ladder_challenge::Column::ClubId.eq(club_id))
+            .filter(ladder_challenge::Column::Status.is_in([ChallengeStatus::Pending, ChallengeStatus::Accepted]))
+            .order_by(ladder_challenge::Column::CreatedAt, Order::Asc)
+            .limit(MAX_QUEUE_PAGE)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(LadderChallengeDisplay::from)
+            .collect();
+
+        Ok(ClubPage { club: ClubDisplay::new(club, count), ladder, tournaments, open_challenges })
+    }
+
+    /// Members in ladder order, top first; the whole ladder without `limit`.
+    pub async fn members(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        limit: Option<u64>,
+    ) -> Result<Vec<ClubMemberDisplay>, ApiError> {
+        let members = club_member::Entity::find()
+            .filter(club_member::Column::ClubId.eq(club_id))
+            .order_by(club_member::Column::LadderRank, Order::Asc)
+            .limit(limit)
+            .all(db)
+            .await?;
+        let names: HashMap<Uuid, String> = player::Entity::find()
+            .filter(player::Column::Id.is_in(members.iter().map(|member| member.player_id)))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| (p.id, p.username))
+            .collect();
+
+        Ok(members
+            .into_iter()
+            .map(|member| {
+                let username = names.get(&member.player_id).cloned().unwrap_or_default();
+                ClubMemberDisplay::new(member, username)
+            })
+            .collect())
+    }
+
+    /// A single membership with the member's username.
+    pub async fn display_member(
+        db: &DatabaseConnection,
+        member: club_member::Model,
+    ) -> Result<ClubMemberDisplay, ApiError> {
+        let username = player::Entity::find_by_id(member.player_id)
+            .one(db)
+            .await?
+            .map(|p| p.username)
+            .unwrap_or_default();
+        Ok(ClubMemberDisplay::new(member, username))
+    }
+
+    pub async fn membership<C: ConnectionTrait>(
+        db: &C,
+        club_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<Option<club_member::Model>, ApiError> {
+        Ok(club_member::Entity::find_by_id((club_id, player_id)).one(db).await?)
+    }
+
+    /// Join an open club, at the bottom of its ladder.
+    pub async fn join(db: &DatabaseConnection, club_id: Uuid, player_id: Uuid) -> Result<club_member::Model, ApiError> {
+        let club = Self::get(db, club_id).await?;
+        if !club.is_open {
+            return Err(ApiError::Forbidden("This club is invite-only; ask an admin to add you".to_string()));
+        }
+        Self::enroll(db, club_id, player_id).await
+    }
+
+    /// Add a player to the club, at the bottom of its ladder. Owner and
+    /// admins only.
+    pub async fn add_member(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        actor_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<club_member::Model, ApiError> {
+        Self::get(db, club_id).await?;
+        Self::require_staff(db, club_id, actor_id).await?;
+        player::Entity::find_by_id(player_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Player {}", player_id)))?;
+        Self::enroll(db, club_id, player_id).await
+    }
+
+    async fn enroll(db: &DatabaseConnection, club_id: Uuid, player_id: Uuid) -> Result<club_member::Model, ApiError> {
+        let txn = db.begin().await?;
+        let members = lock_members(&txn, club_id).await?;
+        if members.iter().any(|member| member.player_id == player_id) {
+            return Err(ApiError::BadRequest("Already a member of this club".to_string()));
+        }
+        let member = club_member::ActiveModel {
+            club_id: Set(club_id),
+            player_id: Set(player_id),
+            role: Set(ClubRole::Member),
+            ladder_rank: Set(members.len() as i32 + 1),
+            joined_at: Set(now()),
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(member)
+    }
+
+    /// Leave the club. The owner stays; everyone below moves up a place and
+    /// the player's open challenges are declined.
+    pub async fn leave(db: &DatabaseConnection, club_id: Uuid, player_id: Uuid) -> Result<(), ApiError> {
+        let member = Self::membership(db, club_id, player_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Club membership".to_string()))?;
+        if member.role == ClubRole::Owner {
+            return Err(ApiError::BadRequest("The owner cannot leave the club".to_string()));
+        }
+        Self::remove(db, club_id, player_id).await
+    }
+
+    /// Remove a member. The owner can remove anyone else, admins only plain
+    /// members.
+    pub async fn remove_member(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        actor_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<(), ApiError> {
+        let actor = Self::require_staff(db, club_id, actor_id).await?;
+        let member = Self::membership(db, club_id, player_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Club membership".to_string()))?;
+        if !outranks(actor.role, member.role) {
+            return Err(ApiError::Forbidden("Only the owner can remove admins".to_string()));
+        }
+        Self::remove(db, club_id, player_id).await
+    }
+
+    async fn remove(db: &DatabaseConnection, club_id: Uuid, player_id: Uuid) -> Result<(), ApiError> {
+        let txn = db.begin().await?;
+        let members = lock_members(&txn, club_id).await?;
+        let ranks: Vec<(Uuid, i32)> = members.iter().map(|m| (m.player_id, m.ladder_rank)).collect();
+        club_member::Entity::delete_by_id((club_id, player_id)).exec(&txn).await?;
+        save_ranks(&txn, club_id, &ranks, &ranks_after_leaving(&ranks, player_id)).await?;
+
+        ladder_challenge::Entity::update_many()
+            .col_expr(ladder_challenge::Column::Status, ChallengeStatus::Declined.into())
+            .col_expr(ladder_challenge::Column::ResolvedAt, Expr::value(now()))
+            .filter(ladder_challenge::Column::ClubId.eq(club_id))
+            .filter(ladder_challenge::Column::Status.is_in([ChallengeStatus::Pending, ChallengeStatus::Accepted]))
+            .filter(
+                Condition::any()
+                    .add(ladder_challenge::Column::ChallengerId.eq(player_id))
+                    .add(ladder_challenge::Column::DefenderId.eq(player_id)),
+            )
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Make a member an admin or a plain member again. Owner only.
+    pub async fn set_role(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        actor_id: Uuid,
+        player_id: Uuid,
+        role: ClubRole,
+    ) -> Result<club_member::Model, ApiError> {
+        if role == ClubRole::Owner {
+            return Err(ApiError::BadRequest("A club has exactly one owner".to_string()));
+        }
+        let actor = Self::require_staff(db, club_id, actor_id).await?;
+        if actor.role != ClubRole::Owner {
+            return Err(ApiError::Forbidden("Only the owner can change roles".to_string()));
+        }
+        let member = Self::membership(db, club_id, player_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Club membership".to_string()))?;
+        if member.role == ClubRole::Owner {
+            return Err(ApiError::BadRequest("The owner keeps their role".to_string()));
+        }
+        let mut active = member.into_active_model();
+        active.role = Set(role);
+        Ok(active.update(db).await?)
+    }
+
+    /// The actor's membership, if they are the club's owner or an admin.
+    pub async fn require_staff<C: ConnectionTrait>(
+        db: &C,
+        club_id: Uuid,
+        actor_id: Uuid,
+    ) -> Result<club_member::Model, ApiError> {
+        match Self::membership(db, club_id, actor_id).await? {
+            Some(member) if member.role != ClubRole::Member => Ok(member),
+            _ => Err(ApiError::Forbidden("Club owner or admin required".to_string())),
+        }
+    }
+
+    /// Check a club tournament before it is created: its creator runs the
+    /// club and everyone entered is a member.
+    pub async fn check_tournament<C: ConnectionTrait>(
+        db: &C,
+        club_id: Uuid,
+        creator_id: Uuid,
+        player_ids: &[Uuid],
+    ) -> Result<(), ApiError> {
+        if club::Entity::find_by_id(club_id).one(db).await?.is_none() {
+            return Err(ApiError::NotFound(format!("Club {}", club_id)));
+        }
+        Self::require_staff(db, club_id, creator_id).await?;
+        let members: Vec<Uuid> = club_member::Entity::find()
+            .filter(club_member::Column::ClubId.eq(club_id))
+            .filter(club_member::Column::PlayerId.is_in(player_ids.iter().copied()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|member| member.player_id)
+            .collect();
+        match player_ids.iter().find(|id| !members.contains(id)) {
+            Some(outsider) => Err(ApiError::BadRequest(format!("Player {} is not a member of the club", outsider))),
+            None => Ok(()),
+        }
+    }
+
+    /// Challenge a member ranked above, within the club's ladder reach.
+    pub async fn challenge(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        challenger_id: Uuid,
+        defender_id: Uuid,
+    ) -> Result<ladder_challenge::Model, ApiError> {
+        let club = Self::get(db, club_id).await?;
+        let challenger = Self::membership(db, club_id, challenger_id)
+            .await?
+            .ok_or_else(|| ApiError::Forbidden("Only club members can challenge".to_string()))?;
+        let defender = Self::membership(db, club_id, defender_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Club membership".to_string()))?;
+        if !within_reach(challenger.ladder_rank, defender.ladder_rank, club.ladder_reach) {
+            return Err(ApiError::BadRequest(format!(
+                "Members can challenge up to {} places above them",
+                club.ladder_reach
+            )));
+        }
+
+        let open = ladder_challenge::Entity::find()
+            .filter(ladder_challenge::Column::ClubId.eq(club_id))
+            .filter(ladder_challenge::Column::Status.is_in([ChallengeStatus::Pending, ChallengeStatus::Accepted]))
+            .filter(ladder_challenge::Column::ChallengerId.eq(challenger_id))
+            .one(db)
+            .await?;
+        if open.is_some() {
+            return Err(ApiError::BadRequest("Finish your open challenge first".to_string()));
+        }
+
+        Ok(ladder_challenge::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            club_id: Set(club_id),
+            challenger_id: Set(challenger_id),
+            defender_id: Set(defender_id),
+            status: Set(ChallengeStatus::Pending),
+            game_id: Set(None),
+            winner_id: Set(None),
+            created_at: Set(now()),
+            resolved_at: Set(None),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// The club's challenges of a status, oldest first.
+    pub async fn challenges(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        status: ChallengeStatus,
+        limit: u64,
+    ) -> Result<Vec<ladder_challenge::Model>, ApiError> {
+        Ok(ladder_challenge::Entity::find()
+            .filter(ladder_challenge::Column::ClubId.eq(club_id))
+            .filter(ladder_challenge::Column::Status.eq(status))
+            .order_by(ladder_challenge::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// Accept or decline a pending challenge. Defender only.
+    pub async fn respond(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        challenge_id: Uuid,
+        defender_id: Uuid,
+        accept: bool,
+    ) -> Result<ladder_challenge::Model, ApiError> {
+        let challenge = find_challenge(db, club_id, challenge_id).await?;
+        if challenge.defender_id != defender_id {
+            return Err(ApiError::Forbidden("Only the challenged member can answer".to_string()));
+        }
+        if challenge.status != ChallengeStatus::Pending {
+            return Err(ApiError::BadRequest("Challenge is already answered".to_string()));
+        }
+
+        let mut active = challenge.into_active_model();
+        if accept {
+            active.status = Set(ChallengeStatus::Accepted);
+        } else {
+            active.status = Set(ChallengeStatus::Declined);
+            active.resolved_at = Set(Some(now()));
+        }
+        Ok(active.update(db).await?)
+    }
+
+    /// Settle an accepted challenge with the game the two played for it. A
+    /// win by the challenger takes the defender's place, everyone from there
+    /// down to the challenger's old place moving down one; otherwise the
+    /// ladder stays as it is.
+    pub async fn report_result(
+        db: &DatabaseConnection,
+        club_id: Uuid,
+        challenge_id: Uuid,
+        reporter_id: Uuid,
+        game_id: Uuid,
+    ) -> Result<ladder_challenge::Model, ApiError> {
+        let challenge = find_challenge(db, club_id, challenge_id).await?;
+        if reporter_id != challenge.challenger_id && reporter_id != challenge.defender_id {
+            return Err(ApiError::Forbidden("Only the players of the challenge can report it".to_string()));
+        }
+        if challenge.status != ChallengeStatus::Accepted {
+            return Err(ApiError::BadRequest("Only accepted challenges can be settled".to_string()));
+        }
+        let game = GameArchiveService::find(db, game_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+        let winner = decided_by(&challenge, &game)?;
+
+        let txn = db.begin().await?;
+        let members = lock_members(&txn, club_id).await?;
+        let ranks: Vec<(Uuid, i32)> = members.iter().map(|m| (m.player_id, m.ladder_rank)).collect();
+        if winner == Some(challenge.challenger_id) {
+            let updated = ranks_after_upset(&ranks, challenge.challenger_id, challenge.defender_id);
+            save_ranks(&txn, club_id, &ranks, &updated).await?;
+        }
+
+        let mut active = challenge.into_active_model();
+        active.status = Set(ChallengeStatus::Completed);
+        active.game_id = Set(Some(game_id));
+        active.winner_id = Set(winner);
+        active.resolved_at = Set(Some(now()));
+        let challenge = active.update(&txn).await?;
+        txn.commit().await?;
+        Ok(challenge)
+    }
+}
+
+async fn member_count<C: ConnectionTrait>(db: &C, club_id: Uuid) -> Result<u64, ApiError> {
+    Ok(club_member::Entity::find()
+        .filter(club_member::Column::ClubId.eq(club_id))
+        .count(db)
+        .await?)
+}
+
+/// The club's members, locked so concurrent ladder changes queue up.
+async fn lock_members(txn: &DatabaseTransaction, club_id: Uuid) -> Result<Vec<club_member::Model>, ApiError> {
+    Ok(club_member::Entity::find()
+        .filter(club_member::Column::ClubId.eq(club_id))
+        .order_by(club_member::Column::LadderRank, Order::Asc)
+        .lock_exclusive()
+        .all(txn)
+        .await?)
+}
+
+/// Write the ranks that changed from `before` to `after`.
+async fn save_ranks(
+    txn: &DatabaseTransaction,
+    club_id: Uuid,
+    before: &[(Uuid, i32)],
+    after: &[(Uuid, i32)],
+) -> Result<(), ApiError> {
+    for (player_id, rank) in after {
+        if before.contains(&(*player_id, *rank)) {
+            continue;
+        }
+        club_member::Entity::update_many()
+            .col_expr(club_member::Column::LadderRank, Expr::value(*rank))
+            .filter(club_member::Column::ClubId.eq(club_id))
+            .filter(club_member::Column::PlayerId.eq(*player_id))
+            .exec(txn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn find_challenge(
+    db: &DatabaseConnection,
+    club_id: Uuid,
+    challenge_id: Uuid,
+) -> Result<ladder_challenge::Model, ApiError> {
+    ladder_challenge::Entity::find_by_id(challenge_id)
+        .one(db)
+        .await?
+        .filter(|challenge| challenge.club_id == club_id)
+        .ok_or_else(|| ApiError::NotFound(format!("Challenge {}", challenge_id)))
+}
+
+/// Whether `role` may remove or demote a member holding `other`.
+fn outranks(role: ClubRole, other: ClubRole) -> bool {
+    match role {
+        ClubRole::Owner => other != ClubRole::Owner,
+        ClubRole::Admin => other == ClubRole::Member,
+        ClubRole::Member => false,
+    }
+}
+
+/// Whether a member at `challenger` may challenge the one at `defender`,
+/// `reach` places up at most.
+fn within_reach(challenger: i32, defender: i32, reach: i32) -> bool {
+    defender < challenger && challenger - defender <= reach
+}
+
+/// Ladder after the challenger beat the defender: the challenger takes the
+/// defender's place and everyone from there down to the challenger's old
+/// place moves down one. Unchanged if the challenger was already above.
+fn ranks_after_upset(ranks: &[(Uuid, i32)], challenger: Uuid, defender: Uuid) -> Vec<(Uuid, i32)> {
+    let rank_of = |id: Uuid| ranks.iter().find(|(player, _)| *player == id).map(|(_, rank)| *rank);
+    let (Some(from), Some(to)) = (rank_of(challenger), rank_of(defender)) else {
+        return ranks.to_vec();
+    };
+    if to >= from {
+        return ranks.to_vec();
+    }
+    ranks
+        .iter()
+        .map(|(player, rank)| match *rank {
+            _ if *player == challenger => (*player, to),
+            rank if rank >= to && rank < from => (*player, rank + 1),
+            rank => (*player, rank),
+        })
+        .collect()
+}
+
+/// Ladder after `leaver` left: everyone below moves up a place.
+fn ranks_after_leaving(ranks: &[(Uuid, i32)], leaver: Uuid) -> Vec<(Uuid, i32)> {
+    let Some(left) = ranks.iter().find(|(player, _)| *player == leaver).map(|(_, rank)| *rank) else {
+        return ranks.to_vec();
+    };
+    ranks
+        .iter()
+        .filter(|(player, _)| *player != leaver)
+        .map(|(player, rank)| (*player, if *rank > left { rank - 1 } else { *rank }))
+        .collect()
+}
+
+/// Winner of a challenge by `game`, None for a draw. The game must be a
+/// finished one between the two players, started after the challenge.
+fn decided_by(challenge: &ladder_challenge::Model, game: &game::Model) -> Result<Option<Uuid>, ApiError> {
+    let players = [game.white_player, game.black_player];
+    if !players.contains(&challenge.challenger_id) || !players.contains(&challenge.defender_id) {
+        return Err(ApiError::BadRequest("The game was not played between the two players".to_string()));
+    }
+    if game.created_at < challenge.created_at {
+        return Err(ApiError::BadRequest("The game was played before the challenge".to_string()));
+    }
+    match game.result {
+        Some(ResultSide::WhiteWins) => Ok(Some(game.white_player)),
+        Some(ResultSide::BlackWins) => Ok(Some(game.black_player)),
+        Some(ResultSide::Draw) => Ok(None),
+        _ => Err(ApiError::BadRequest("The game has not finished".to_string())),
+    }
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use db_entity::game::GameVariant;
+
+    fn ladder(size: usize) -> Vec<(Uuid, i32)> {
+        (1..=size as i32).map(|rank| (Uuid::new_v4(), rank)).collect()
+    }
+
+    fn rank(ranks: &[(Uuid, i32)], id: Uuid) -> i32 {
+        ranks.iter().find(|(player, _)| *player == id).unwrap().1
+    }
+
+    #[test]
+    fn test_challenges_reach_a_few_places_up() {
+        assert!(within_reach(5, 2, 3));
+        assert!(!within_reach(5, 1, 3));
+        assert!(!within_reach(2, 5, 3));
+        assert!(!within_reach(3, 3, 3));
+    }
+
+    #[test]
+    fn test_an_upset_takes_the_defenders_place() {
+        let ranks = ladder(6);
+        let (challenger, defender) = (ranks[4].0, ranks[1].0);
+        let after = ranks_after_upset(&ranks, challenger, defender);
+
+        assert_eq!(rank(&after, challenger), 2);
+        assert_eq!(rank(&after, defender), 3);
+        assert_eq!(rank(&after, ranks[2].0), 4);
+        assert_eq!(rank(&after, ranks[3].0), 5);
+        assert_eq!(rank(&after, ranks[0].0), 1);
+        assert_eq!(rank(&after, ranks[5].0), 6);
+
+        // Already above: nothing moves
+        assert_eq!(ranks_after_upset(&ranks, defender, challenger), ranks);
+    }
+
+    #[test]
+    fn test_leaving_closes_the_gap() {
+        let ranks = ladder(4);
+        let after = ranks_after_leaving(&ranks, ranks[1].0);
+        assert_eq!(after.iter().map(|(_, rank)| *rank).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(rank(&after, ranks[3].0), 3);
+    }
+
+    #[test]
+    fn test_only_the_owner_removes_admins() {
+        assert!(outranks(ClubRole::Owner, ClubRole::Admin));
+        assert!(outranks(ClubRole::Admin, ClubRole::Member));
+        assert!(!outranks(ClubRole::Admin, ClubRole::Admin));
+        assert!(!outranks(ClubRole::Owner, ClubRole::Owner));
+    }
+
+    #[test]
+    fn test_the_game_decides_the_challenge() {
+        let created_at = Utc::now().fixed_offset();
+        let challenge = ladder_challenge::Model {
+            id: Uuid::new_v4(),
+            club_id: Uuid::new_v4(),
+            challenger_id: Uuid::new_v4(),
+            defender_id: Uuid::new_v4(),
+            status: ChallengeStatus::Accepted,
+            game_id: None,
+            winner_id: None,
+            created_at,
+            resolved_at: None,
+        };
+        let mut game = game::Model {
+            id: Uuid::new_v4(),
+            white_player: challenge.defender_id,
+            black_player: challenge.challenger_id,
+            fen: String::new(),
+            pgn: serde_json::json!({}),
+            result: Some(ResultSide::BlackWins),
+            variant: GameVariant::Standard,
+            started_at: created_at + Duration::minutes(5),
+            duration_sec: 300,
+            created_at: created_at + Duration::minutes(5),
+            updated_at: created_at + Duration::minutes(15),
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        };
+        assert_eq!(decided_by(&challenge, &game).unwrap(), Some(challenge.challenger_id));
+
+        game.result = Some(ResultSide::Draw);
+        assert_eq!(decided_by(&challenge, &game).unwrap(), None);
+
+        game.result = Some(ResultSide::Ongoing);
+        assert!(decided_by(&challenge, &game).is_err());
+
+        game.result = Some(ResultSide::WhiteWins);
+        game.created_at = created_at - Duration::hours(1);
+        assert!(decided_by(&challenge, &game).is_err());
+    }
+}
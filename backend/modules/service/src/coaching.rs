@@ -0,0 +1,562 @@
+use chess::{Referee, Termination};
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{
+    coaching_assignment, coaching_link, game, player, training_session,
+    coaching_assignment::{AssignmentKind, AssignmentStatus},
+    coaching_link::CoachingLinkStatus,
+    game::ResultSide,
+};
+use dto::coaching::{
+    CoachingLinkDisplay, CompleteAssignmentRequest, CreateAssignmentRequest, StudentGameDisplay, StudentOutcome,
+    StudentOverview, UpdateAssignmentRequest,
+};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Order, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::game_archive::GameArchiveService;
+use crate::games::{is_rated, GameService};
+use crate::moderation::MAX_QUEUE_PAGE;
+use crate::stats::StatsService;
+use crate::training::TrainingService;
+
+/// Games shown on a student's overview
+const RECENT_GAMES: u64 = 20;
+
+pub struct CoachingService;
+
+impl CoachingService {
+    /// Offer to coach `student_id`. Nothing is shared until the student
+    /// accepts.
+    pub async fn invite(
+        db: &DatabaseConnection,
+        coach_id: Uuid,
+        student_id: Uuid,
+    ) -> Result<CoachingLinkDisplay, ApiError> {
+        if coach_id == student_id {
+            return Err(ApiError::BadRequest("Cannot coach yourself".to_string()));
+        }
+        player::Entity::find_by_id(student_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Player {}", student_id)))?;
+        let open = coaching_link::Entity::find()
+            .filter(coaching_link::Column::CoachId.eq(coach_id))
+            .filter(coaching_link::Column::StudentId.eq(student_id))
+            .filter(coaching_link::Column::Status.ne(CoachingLinkStatus::Ended))
+            .one(db)
+            .await?;
+        if open.is_some() {
+            return Err(ApiError::BadRequest("Already coaching or invited this student".to_string()));
+        }
+
+        let link = coaching_link::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            coach_id: Set(coach_id),
+            student_id: Set(student_id),
+            status: Set(CoachingLinkStatus::Pending),
+            created_at: Set(now()),
+            accepted_at: Set(None),
+            ended_at: Set(None),
+        }
+        .insert(db)
+        .await?;
+        Ok(display_links(db, vec![link]).await?.remove(0))
+    }
+
+    /// Pending and active links where the player is the coach, or else the
+    /// student, oldest first.
+    pub async fn links(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        as_coach: bool,
+    ) -> Result<Vec<CoachingLinkDisplay>, ApiError> {
+        let column = if as_coach {
+            coaching_link::Column::CoachId
+        } else {
+            coaching_link::Column::StudentId
+        };
+        let links = coaching_link::Entity::find()
+            .filter(column.eq(player_id))
+            .filter(coaching_link::Column::Status.ne(CoachingLinkStatus::Ended))
+            .order_by(coaching_link::Column::CreatedAt, Order::Asc)
+            .all(db)
+            .await?;
+        display_links(db, links).await
+    }
+
+    /// The student consents: the coach may now set assignments and read
+    /// their recent games and stats.
+    pub async fn accept(
+        db: &DatabaseConnection,
+        link_id: Uuid,
+        student_id: Uuid,
+    ) -> Result<CoachingLinkDisplay, ApiError> {
+        let link = coaching_link::Entity::find_by_id(link_id)
+            .one(db)
+            .await?
+            .filter(|link| link.student_id == student_id)
+            .ok_or_else(|| ApiError::NotFound(format!("Coaching link {}", link_id)))?;
+        if link.status != CoachingLinkStatus::Pending {
+            return Err(ApiError::BadRequest("Only a pending invitation can be accepted".to_string()));
+        }
+
+        let mut active = link.into_active_model();
+        active.status = Set(CoachingLinkStatus::Active);
+        active.accepted_at = Set(Some(now()));
+        let link = active.update(db).await?;
+        Ok(display_links(db, vec![link]).await?.remove(0))
+    }
+
+    /// End a link, or decline an invitation. Either side can; the coach
+    /// loses access at once and open assignments can no longer be completed.
+    pub async fn end(db: &DatabaseConnection, link_id: Uuid, player_id: Uuid) -> Result<(), ApiError> {
+        let link = coaching_link::Entity::find_by_id(link_id)
+            .one(db)
+            .await?
+            .filter(|link| link.coach_id == player_id || link.student_id == player_id)
+            .ok_or_else(|| ApiError::NotFound(format!("Coaching link {}", link_id)))?;
+        if link.status == CoachingLinkStatus::Ended {
+            return Err(ApiError::BadRequest("This coaching link has already ended".to_string()));
+        }
+
+        let mut active = link.into_active_model();
+        active.status = Set(CoachingLinkStatus::Ended);
+        active.ended_at = Set(Some(now()));
+        active.update(db).await?;
+        Ok(())
+    }
+
+    /// The student's stats, drill results and recent games, for a coach
+    /// they accepted.
+    pub async fn overview(
+        db: &DatabaseConnection,
+        coach_id: Uuid,
+        student_id: Uuid,
+    ) -> Result<StudentOverview, ApiError> {
+        active_link(db, coach_id, student_id).await?;
+        let student = player::Entity::find_by_id(student_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Player {}", student_id)))?;
+
+        let (games, _) = GameService::list_games(db, None, RECENT_GAMES, Some(student_id), None, None).await?;
+        let opponents = games.iter().map(|game| opponent_of(game, student_id));
+        let names = usernames(db, opponents).await?;
+        let recent_games = games
+            .iter()
+            .map(|game| {
+                let opponent_id = opponent_of(game, student_id);
+                StudentGameDisplay {
+                    id: game.id,
+                    color: if game.white_player == student_id { "white" } else { "black" }.to_string(),
+                    opponent_id,
+                    opponent_username: names.get(&opponent_id).cloned().unwrap_or_default(),
+                    outcome: outcome_for(game, student_id),
+                    rated: is_rated(game),
+                    created_at: game.created_at,
+                }
+            })
+            .collect();
+
+        let from_coach = coaching_assignment::Entity::find()
+            .filter(coaching_assignment::Column::CoachId.eq(coach_id))
+            .filter(coaching_assignment::Column::StudentId.eq(student_id));
+        let open_assignments = from_coach
+            .clone()
+            .filter(coaching_assignment::Column::Status.eq(AssignmentStatus::Assigned))
+            .count(db)
+            .await?;
+        let completed_assignments = from_coach
+            .filter(coaching_assignment::Column::Status.eq(AssignmentStatus::Completed))
+            .count(db)
+            .await?;
+
+        Ok(StudentOverview {
+            student_id,
+            username: student.username,
+            stats: StatsService::get(db, student_id).await?,
+            training: TrainingService::stats(db, student_id).await?,
+            recent_games,
+            open_assignments,
+            completed_assignments,
+        })
+    }
+
+    /// Set a student work. Puzzle lines are checked move by move from the
+    /// position; a study needs a game that exists.
+    pub async fn create_assignment(
+        db: &DatabaseConnection,
+        coach_id: Uuid,
+        request: CreateAssignmentRequest,
+    ) -> Result<coaching_assignment::Model, ApiError> {
+        let link = active_link(db, coach_id, request.student_id).await?;
+        let kind = AssignmentKind::from(request.kind);
+
+        let mut model = coaching_assignment::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            link_id: Set(link.id),
+            coach_id: Set(coach_id),
+            student_id: Set(request.student_id),
+            kind: Set(kind),
+            title: Set(request.title.trim().to_string()),
+            instructions: Set(request.instructions),
+            fen: Set(None),
+            solution: Set(None),
+            game_id: Set(None),
+            drill: Set(None),
+            target: Set(None),
+            due_at: Set(request.due_at),
+            status: Set(AssignmentStatus::Assigned),
+            attempts: Set(0),
+            training_session_id: Set(None),
+            student_note: Set(None),
+            created_at: Set(now()),
+            updated_at: Set(now()),
+            completed_at: Set(None),
+        };
+        match kind {
+            AssignmentKind::Puzzle => {
+                let (Some(fen), Some(solution)) = (request.fen, request.solution) else {
+                    return Err(ApiError::BadRequest("A puzzle needs a fen and a solution".to_string()));
+                };
+                model.solution = Set(Some(checked_line(&fen, &solution)?));
+                model.fen = Set(Some(fen));
+            }
+            AssignmentKind::Study => {
+                let game_id = request
+                    .game_id
+                    .ok_or_else(|| ApiError::BadRequest("A study needs a game_id".to_string()))?;
+                GameArchiveService::find(db, game_id)
+                    .await?
+                    .ok_or_else(|| ApiError::NotFound(format!("Game {}", game_id)))?;
+                model.game_id = Set(Some(game_id));
+            }
+            AssignmentKind::Drill => {
+                let (Some(drill), Some(target)) = (request.drill, request.target) else {
+                    return Err(ApiError::BadRequest("A drill assignment needs a drill and a target".to_string()));
+                };
+                model.drill = Set(Some(drill.into()));
+                model.target = Set(Some(target));
+            }
+        }
+        Ok(model.insert(db).await?)
+    }
+
+    /// An assignment its coach or student is reading.
+    pub async fn assignment(
+        db: &DatabaseConnection,
+        assignment_id: Uuid,
+        viewer: Uuid,
+    ) -> Result<coaching_assignment::Model, ApiError> {
+        coaching_assignment::Entity::find_by_id(assignment_id)
+            .one(db)
+            .await?
+            .filter(|assignment| assignment.coach_id == viewer || assignment.student_id == viewer)
+            .ok_or_else(|| ApiError::NotFound(format!("Assignment {}", assignment_id)))
+    }
+
+    /// Assignments of a student, soonest due first; only those from
+    /// `coach_id` if given.
+    pub async fn assignments(
+        db: &DatabaseConnection,
+        student_id: Uuid,
+        coach_id: Option<Uuid>,
+        status: Option<AssignmentStatus>,
+        limit: u64,
+    ) -> Result<Vec<coaching_assignment::Model>, ApiError> {
+        let mut query = coaching_assignment::Entity::find()
+            .filter(coaching_assignment::Column::StudentId.eq(student_id));
+        if let Some(coach_id) = coach_id {
+            query = query.filter(coaching_assignment::Column::CoachId.eq(coach_id));
+        }
+        if let Some(status) = status {
+            query = query.filter(coaching_assignment::Column::Status.eq(status));
+        }
+        Ok(query
+            .order_by(coaching_assignment::Column::DueAt, Order::Asc)
+            .order_by(coaching_assignment::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// Change the title, instructions or due date of an open assignment.
+    /// Coach only.
+    pub async fn update_assignment(
+        db: &DatabaseConnection,
+        assignment_id: Uuid,
+        coach_id: Uuid,
+        request: UpdateAssignmentRequest,
+    ) -> Result<coaching_assignment::Model, ApiError> {
+        let assignment = Self::assignment(db, assignment_id, coach_id).await?;
+        if assignment.coach_id != coach_id {
+            return Err(ApiError::Forbidden("Only the coach can change an assignment".to_string()));
+        }
+        if assignment.status == AssignmentStatus::Completed {
+            return Err(ApiError::BadRequest("Completed assignments cannot be changed".to_string()));
+        }
+
+        let mut active = assignment.into_active_model();
+        if let Some(title) = request.title {
+            active.title = Set(title.trim().to_string());
+        }
+        if let Some(instructions) = request.instructions {
+            active.instructions = Set(instructions);
+        }
+        if let Some(due_at) = request.due_at {
+            active.due_at = Set(Some(due_at));
+        }
+        active.updated_at = Set(now());
+        Ok(active.update(db).await?)
+    }
+
+    /// Coach only.
+    pub async fn delete_assignment(db: &DatabaseConnection, assignment_id: Uuid, coach_id: Uuid) -> Result<(), ApiError> {
+        let assignment = Self::assignment(db, assignment_id, coach_id).await?;
+        if assignment.coach_id != coach_id {
+            return Err(ApiError::Forbidden("Only the coach can delete an assignment".to_string()));
+        }
+        coaching_assignment::Entity::delete_by_id(assignment.id).exec(db).await?;
+        Ok(())
+    }
+
+    /// The student hands in an assignment. A puzzle is complete once the
+    /// line submitted is the solution or mates from the position, every
+    /// submission counting as an attempt; a drill once a session of it,
+    /// started since the assignment was set, reached the target. Returns
+    /// whether the assignment is now complete.
+    pub async fn complete(
+        db: &DatabaseConnection,
+        assignment_id: Uuid,
+        student_id: Uuid,
+        request: CompleteAssignmentRequest,
+    ) -> Result<(bool, coaching_assignment::Model), ApiError> {
+        let assignment = Self::assignment(db, assignment_id, student_id).await?;
+        if assignment.student_id != student_id {
+            return Err(ApiError::Forbidden("Only the student can complete an assignment".to_string()));
+        }
+        if assignment.status == AssignmentStatus::Completed {
+            return Err(ApiError::BadRequest("Assignment is already completed".to_string()));
+        }
+        active_link(db, assignment.coach_id, student_id).await?;
+
+        let mut session_id = None;
+        let mut active = assignment.clone().into_active_model();
+        match assignment.kind {
+            AssignmentKind::Puzzle => {
+                let moves = request
+                    .moves
+                    .ok_or_else(|| ApiError::BadRequest("Submit the moves that solve the puzzle".to_string()))?;
+                active.attempts = Set(assignment.attempts + 1);
+                let fen = assignment.fen.as_deref().unwrap_or_default();
+                let solution = assignment.solution.as_deref().unwrap_or_default();
+                if !solves(fen, solution, &moves) {
+                    active.updated_at = Set(now());
+                    return Ok((false, active.update(db).await?));
+                }
+            }
+            AssignmentKind::Drill => {
+                let id = request
+                    .training_session_id
+                    .ok_or_else(|| ApiError::BadRequest("Give the training session that reached the target".to_string()))?;
+                let session = training_session::Entity::find_by_id(id)
+                    .one(db)
+                    .await?
+                    .filter(|session| session.player_id == student_id)
+                    .ok_or_else(|| ApiError::NotFound("Training session".to_string()))?;
+                check_session(&assignment, &session)?;
+                session_id = Some(session.id);
+            }
+            AssignmentKind::Study => {}
+        }
+
+        active.status = Set(AssignmentStatus::Completed);
+        active.training_session_id = Set(session_id);
+        active.student_note = Set(request.note);
+        active.completed_at = Set(Some(now()));
+        active.updated_at = Set(now());
+        Ok((true, active.update(db).await?))
+    }
+}
+
+async fn active_link(
+    db: &DatabaseConnection,
+    coach_id: Uuid,
+    student_id: Uuid,
+) -> Result<coaching_link::Model, ApiError> {
+    coaching_link::Entity::find()
+        .filter(coaching_link::Column::CoachId.eq(coach_id))
+        .filter(coaching_link::Column::StudentId.eq(student_id))
+        .filter(coaching_link::Column::Status.eq(CoachingLinkStatus::Active))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Forbidden("No active coaching link with this student".to_string()))
+}
+
+async fn display_links(
+    db: &DatabaseConnection,
+    links: Vec<coaching_link::Model>,
+) -> Result<Vec<CoachingLinkDisplay>, ApiError> {
+    let ids = links.iter().flat_map(|link| [link.coach_id, link.student_id]);
+    let names = usernames(db, ids).await?;
+    Ok(links
+        .into_iter()
+        .map(|link| {
+            let coach = names.get(&link.coach_id).cloned().unwrap_or_default();
+            let student = names.get(&link.student_id).cloned().unwrap_or_default();
+            CoachingLinkDisplay::new(link, coach, student)
+        })
+        .collect())
+}
+
+async fn usernames(
+    db: &DatabaseConnection,
+    ids: impl IntoIterator<Item = Uuid>,
+) -> Result<HashMap<Uuid, String>, ApiError> {
+    Ok(player::Entity::find()
+        .filter(player::Column::Id.is_in(ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p.username))
+        .collect())
+}
+
+fn opponent_of(game: &game::Model, player_id: Uuid) -> Uuid {
+    if game.white_player == player_id {
+        game.black_player
+    } else {
+        game.white_player
+    }
+}
+
+/// How a finished game went for `player_id`.
+fn outcome_for(game: &game::Model, player_id: Uuid) -> Option<StudentOutcome> {
+    let white = game.white_player == player_id;
+    match game.result.as_ref()? {
+        ResultSide::WhiteWins if white => Some(StudentOutcome::Win),
+        ResultSide::BlackWins if !white => Some(StudentOutcome::Win),
+        ResultSide::WhiteWins | ResultSide::BlackWins => Some(StudentOutcome::Loss),
+        ResultSide::Draw => Some(StudentOutcome::Draw),
+        ResultSide::Ongoing | ResultSide::Abandoned => None,
+    }
+}
+
+/// The solving line as stored: every move legal from `fen`, in UCI.
+fn checked_line(fen: &str, moves: &[String]) -> Result<String, ApiError> {
+    let mut board = Referee::from_fen(fen).map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    for (index, uci) in moves.iter().enumerate() {
+        board
+            .play_uci(uci.trim())
+            .map_err(|_| ApiError::BadRequest(format!("Move {} of the solution, {}, is illegal", index + 1, uci)))?;
+    }
+    Ok(moves.iter().map(|uci| uci.trim()).collect::<Vec<_>>().join(" "))
+}
+
+/// Whether `moves` solve the puzzle: the stored line, or any legal line no
+/// longer than it that mates.
+fn solves(fen: &str, solution: &str, moves: &[String]) -> bool {
+    let expected: Vec<&str> = solution.split_whitespace().collect();
+    let played: Vec<&str> = moves.iter().map(|uci| uci.trim()).collect();
+    if played == expected {
+        return true;
+    }
+    if played.is_empty() || played.len() > expected.len() {
+        return false;
+    }
+    let Ok(mut board) = Referee::from_fen(fen) else {
+        return false;
+    };
+    if played.iter().any(|uci| board.play_uci(uci).is_err()) {
+        return false;
+    }
+    matches!(board.outcome(), Some((_, Termination::Checkmate)))
+}
+
+fn check_session(
+    assignment: &coaching_assignment::Model,
+    session: &training_session::Model,
+) -> Result<(), ApiError> {
+    if Some(session.drill) != assignment.drill {
+        return Err(ApiError::BadRequest("The session is of another drill".to_string()));
+    }
+    if session.started_at < assignment.created_at {
+        return Err(ApiError::BadRequest("The session was started before the assignment was set".to_string()));
+    }
+    let correct = session
+        .correct
+        .ok_or_else(|| ApiError::BadRequest("Submit the session's answers first".to_string()))?;
+    let target = assignment.target.unwrap_or_default();
+    if correct < target {
+        return Err(ApiError::BadRequest(format!("The session scored {} of the {} needed", correct, target)));
+    }
+    Ok(())
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+
+    fn line(moves: &[&str]) -> Vec<String> {
+        moves.iter().map(|uci| uci.to_string()).collect()
+    }
+
+    #[test]
+    fn test_solution_lines_are_checked_from_the_position() {
+        assert_eq!(checked_line(BACK_RANK, &line(&["d1d8"])).unwrap(), "d1d8");
+        assert!(checked_line(BACK_RANK, &line(&["d1d9"])).is_err());
+        assert!(checked_line(BACK_RANK, &line(&["d1d8", "g8h7"])).is_err());
+        assert!(checked_line("not a position", &line(&["d1d8"])).is_err());
+    }
+
+    #[test]
+    fn test_the_solution_or_another_mate_solves_a_puzzle() {
+        // The rook mates on d8 or, after Rd1-d7, not at all
+        assert!(solves(BACK_RANK, "d1d8", &line(&["d1d8"])));
+        assert!(!solves(BACK_RANK, "d1d8", &line(&["d1d7"])));
+        assert!(!solves(BACK_RANK, "d1d8", &line(&[])));
+
+        // Two rooks: Ra8 is the line set, Rb8 mates as well
+        let two_rooks = "6k1/5ppp/8/8/8/8/R4PPP/1R4K1 w - - 0 1";
+        assert!(solves(two_rooks, "a2a8", &line(&["b1b8"])));
+        assert!(!solves(two_rooks, "a2a8", &line(&["a2a7"])));
+    }
+
+    #[test]
+    fn test_outcomes_are_from_the_students_side() {
+        let student = Uuid::new_v4();
+        let mut game = game::Model {
+            id: Uuid::new_v4(),
+            white_player: student,
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn: serde_json::json!({}),
+            result: Some(ResultSide::WhiteWins),
+            variant: game::GameVariant::Standard,
+            started_at: now(),
+            duration_sec: 0,
+            created_at: now(),
+            updated_at: now(),
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        };
+        assert_eq!(outcome_for(&game, student), Some(StudentOutcome::Win));
+        assert_eq!(outcome_for(&game, game.black_player), Some(StudentOutcome::Loss));
+        game.result = Some(ResultSide::Draw);
+        assert_eq!(outcome_for(&game, student), Some(StudentOutcome::Draw));
+        game.result = Some(ResultSide::Abandoned);
+        assert_eq!(outcome_for(&game, student), None);
+    }
+}
@@ -0,0 +1,400 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entity::{
+    game, game_dispute,
+    game::ResultSide,
+    game_dispute::DisputeStatus,
+    tournament::TournamentFormat,
+};
+use dto::disputes::{DecideDisputeRequest, DisputeEvidence, OpenDisputeRequest};
+use dto::games::GameResult as DisplayResult;
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, Order,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use tournament::{GameResult, RoundOutcome, TournamentState};
+use uuid::Uuid;
+
+use crate::moderation::MAX_QUEUE_PAGE;
+use crate::recalculation::RecalculationService;
+use crate::tournaments::{state_of, TournamentService};
+
+pub struct DisputeService;
+
+impl DisputeService {
+    /// Contest the result of a finished game the claimant played in. The
+    /// game's moves and clock are copied into the case as evidence.
+    pub async fn open(
+        db: &DatabaseConnection,
+        claimant_id: Uuid,
+        game_id: Uuid,
+        request: OpenDisputeRequest,
+    ) -> Result<game_dispute::Model, ApiError> {
+        let game = Self::find_game(db, game_id).await?;
+        if !is_participant(&game, claimant_id) {
+            return Err(ApiError::Forbidden("Only players of the game can dispute its result".to_string()));
+        }
+        let original = scored_result(&game)
+            .ok_or_else(|| ApiError::BadRequest("Only finished games can be disputed".to_string()))?;
+
+        let requested: Option<ResultSide> = request.requested_result.map(Into::into);
+        if let Some(requested) = &requested {
+            if white_score(requested).is_none() {
+                return Err(ApiError::BadRequest("The requested result must be a win or a draw".to_string()));
+            }
+            if *requested == original {
+                return Err(ApiError::BadRequest("The requested result is the current one".to_string()));
+            }
+        }
+
+        let round = match (request.tournament_id, request.round) {
+            (Some(tournament_id), Some(round)) => {
+                let tournament = TournamentService::get(db, tournament_id).await?;
+                if tournament.format != TournamentFormat::Swiss {
+                    return Err(ApiError::BadRequest("Arena tournaments have no rounds".to_string()));
+                }
+                if !played_in_round(&state_of(&tournament)?, round, game.white_player, game.black_player) {
+                    return Err(ApiError::BadRequest(format!(
+                        "The players have no scored game in round {} of the tournament",
+                        round
+                    )));
+                }
+                Some(round as i32)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(ApiError::BadRequest(
+                    "A tournament and its round must be given together".to_string(),
+                ))
+            }
+        };
+
+        let already_open = game_dispute::Entity::find()
+            .filter(game_dispute::Column::GameId.eq(game_id))
+            .filter(game_dispute::Column::Status.eq(DisputeStatus::Open))
+            .one(db)
+            .await?;
+        if already_open.is_some() {
+            return Err(ApiError::BadRequest("This game already has an open dispute".to_string()));
+        }
+
+        let model = game_dispute::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            game_id: Set(game_id),
+            claimant_id: Set(claimant_id),
+            reason: Set(request.reason.into()),
+            details: Set(request.details),
+            evidence: Set(evidence_of(&game)),
+            tournament_id: Set(request.tournament_id),
+            round: Set(round),
+            original_result: Set(original),
+            requested_result: Set(requested),
+            status: Set(DisputeStatus::Open),
+            decided_by: Set(None),
+            decision_note: Set(None),
+            corrected_result: Set(None),
+            created_at: Set(now()),
+            decided_at: Set(None),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    /// Arbiter queue, oldest first.
+    pub async fn list(
+        db: &DatabaseConnection,
+        status: DisputeStatus,
+        limit: u64,
+    ) -> Result<Vec<game_dispute::Model>, ApiError> {
+        Ok(game_dispute::Entity::find()
+            .filter(game_dispute::Column::Status.eq(status))
+            .order_by(game_dispute::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// Uphold or overturn a disputed result. Overturning rewrites the game
+    /// result, then the tournament standings and both players' ratings, all
+    /// in the transaction that decides the dispute.
+    pub async fn decide(
+        db: &DatabaseConnection,
+        dispute_id: Uuid,
+        arbiter_id: Uuid,
+        request: DecideDisputeRequest,
+    ) -> Result<game_dispute::Model, ApiError> {
+        let status: DisputeStatus = request.status.into();
+        let corrected: Option<ResultSide> = request.corrected_result.map(Into::into);
+        match (status, &corrected) {
+            (DisputeStatus::Open, _) => {
+                return Err(ApiError::BadRequest(
+                    "A dispute can only be upheld or overturned".to_string(),
+                ))
+            }
+            (DisputeStatus::Upheld, Some(_)) => {
+                return Err(ApiError::BadRequest(
+                    "Only an overturned result takes a corrected result".to_string(),
+                ))
+            }
+            (DisputeStatus::Overturned, None) => {
+                return Err(ApiError::BadRequest(
+                    "Overturning a result needs the corrected result".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        // Two arbiters deciding at once are serialized on the dispute row
+        let txn = db.begin().await?;
+        let dispute = game_dispute::Entity::find_by_id(dispute_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Dispute {}", dispute_id)))?;
+        if dispute.status != DisputeStatus::Open {
+            return Err(ApiError::BadRequest("Dispute is already decided".to_string()));
+        }
+
+        let game = game::Entity::find_by_id(dispute.game_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Game {}", dispute.game_id)))?;
+        if is_participant(&game, arbiter_id) {
+            return Err(ApiError::Forbidden(
+                "Arbiters cannot decide disputes about their own games".to_string(),
+            ));
+        }
+
+        if let Some(corrected) = &corrected {
+            Self::overturn(&txn, &dispute, game, corrected.clone(), arbiter_id).await?;
+        }
+
+        let mut active: game_dispute::ActiveModel = dispute.into();
+        active.status = Set(status);
+        active.decided_by = Set(Some(arbiter_id));
+        active.decision_note = Set(request.note);
+        active.corrected_result = Set(corrected);
+        active.decided_at = Set(Some(now()));
+
+        let dispute = active.update(&txn).await?;
+        txn.commit().await?;
+        Ok(dispute)
+    }
+
+    /// Apply `corrected` to the game and everything scored from it. The
+    /// tournament goes first, as it is the step that may still be refused;
+    /// both players' ratings are then recalculated from the game on.
+    async fn overturn(
+        txn: &DatabaseTransaction,
+        dispute: &game_dispute::Model,
+        game: game::Model,
+        corrected: ResultSide,
+        arbiter_id: Uuid,
+    ) -> Result<(), ApiError> {
+        if white_score(&corrected).is_none() {
+            return Err(ApiError::BadRequest("The corrected result must be a win or a draw".to_string()));
+        }
+        if game.result.as_ref() != Some(&dispute.original_result) {
+            return Err(ApiError::BadRequest(
+                "The game result changed after the dispute was opened".to_string(),
+            ));
+        }
+        if corrected == dispute.original_result {
+            return Err(ApiError::BadRequest("The corrected result is the current one".to_string()));
+        }
+
+        if let (Some(tournament_id), Some(round), Some(result)) =
+            (dispute.tournament_id, dispute.round, white_result(&corrected))
+        {
+            TournamentService::correct_result(txn, tournament_id, round as u32, game.white_player, result).await?;
+        }
+
+        let (game_id, players, started_at) = (game.id, vec![game.white_player, game.black_player], game.started_at);
+        let mut active: game::ActiveModel = game.into();
+        active.result = Set(Some(corrected));
+        active.updated_at = Set(now());
+        active.update(txn).await?;
+
+        let reason = format!("Result of game {} overturned on dispute {}", game_id, dispute.id);
+        RecalculationService::enqueue(txn, players, started_at, reason, Some(arbiter_id)).await?;
+        Ok(())
+    }
+
+    async fn find_game(db: &DatabaseConnection, game_id: Uuid) -> Result<game::Model, ApiError> {
+        game::Entity::find_by_id(game_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Game {}", game_id)))
+    }
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+fn is_participant(game: &game::Model, player_id: Uuid) -> bool {
+    game.white_player == player_id || game.black_player == player_id
+}
+
+/// The result of a game that has one to dispute.
+fn scored_result(game: &game::Model) -> Option<ResultSide> {
+    game.result.clone().filter(|result| white_score(result).is_some())
+}
+
+/// Points white scored, for a finished game.
+pub fn white_score(result: &ResultSide) -> Option<f64> {
+    match result {
+        ResultSide::WhiteWins => Some(1.0),
+        ResultSide::Draw => Some(0.5),
+        ResultSide::BlackWins => Some(0.0),
+        ResultSide::Ongoing | ResultSide::Abandoned => None,
+    }
+}
+
+/// The result from white's side, as tournaments record it.
+pub fn white_result(result: &ResultSide) -> Option<GameResult> {
+    match result {
+        ResultSide::WhiteWins => Some(GameResult::Win),
+        ResultSide::Draw => Some(GameResult::Draw),
+        ResultSide::BlackWins => Some(GameResult::Loss),
+        ResultSide::Ongoing | ResultSide::Abandoned => None,
+    }
+}
+
+/// Whether `player` met `opponent` over the board in `round` and the game was scored.
+pub fn played_in_round(state: &TournamentState, round: u32, player: Uuid, opponent: Uuid) -> bool {
+    state
+        .players
+        .get(&player)
+        .and_then(|p| p.round_record(round))
+        .is_some_and(|record| {
+            matches!(record.outcome, RoundOutcome::Game { opponent: met, result: Some(_), .. } if met == opponent)
+        })
+}
+
+/// Snapshot of the game attached to a new dispute.
+pub fn evidence_of(game: &game::Model) -> serde_json::Value {
+    let evidence = DisputeEvidence {
+        pgn: game.pgn.clone(),
+        final_fen: game.fen.clone(),
+        result: game.result.clone().map_or(DisplayResult::InProgress, Into::into),
+        started_at: game.started_at,
+        finished_at: game.started_at + Duration::seconds(game.duration_sec as i64),
+        duration_sec: game.duration_sec,
+    };
+    serde_json::to_value(evidence).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db_entity::game::GameVariant;
+    use db_entity::game_dispute::DisputeReason;
+    use dto::disputes::DisputeStatus as DecidedStatus;
+    use sea_orm::{DbBackend, MockDatabase};
+    use serde_json::json;
+    use tournament::{Player, TournamentState};
+
+    fn finished_game(result: Option<ResultSide>) -> game::Model {
+        let started_at = Utc::now().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: "8/8/8/8/8/5k2/8/5K2 w - - 0 60".to_string(),
+            pgn: json!({ "moves": ["e4", "e5"], "clocks": [179800, 179500] }),
+            result,
+            variant: GameVariant::Standard,
+            started_at,
+            duration_sec: 300,
+            created_at: started_at,
+            updated_at: started_at,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    #[test]
+    fn test_only_finished_games_can_be_disputed() {
+        assert_eq!(scored_result(&finished_game(Some(ResultSide::Draw))), Some(ResultSide::Draw));
+        assert_eq!(scored_result(&finished_game(Some(ResultSide::Abandoned))), None);
+        assert_eq!(scored_result(&finished_game(Some(ResultSide::Ongoing))), None);
+        assert_eq!(scored_result(&finished_game(None)), None);
+
+        assert_eq!(white_result(&ResultSide::BlackWins), Some(GameResult::Loss));
+        assert_eq!(white_score(&ResultSide::Draw), Some(0.5));
+    }
+
+    fn dispute_of(game: &game::Model, status: DisputeStatus) -> game_dispute::Model {
+        game_dispute::Model {
+            id: Uuid::new_v4(),
+            game_id: game.id,
+            claimant_id: game.black_player,
+            reason: DisputeReason::WrongFlag,
+            details: None,
+            evidence: evidence_of(game),
+            tournament_id: None,
+            round: None,
+            original_result: ResultSide::WhiteWins,
+            requested_result: Some(ResultSide::Draw),
+            status,
+            decided_by: None,
+            decision_note: None,
+            corrected_result: None,
+            created_at: game.started_at,
+            decided_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decision_locks_the_dispute_before_checking_it() {
+        let game = finished_game(Some(ResultSide::WhiteWins));
+        // Another arbiter decided the dispute while this one waited on the lock
+        let dispute = dispute_of(&game, DisputeStatus::Upheld);
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![dispute.clone()]])
+            .into_connection();
+
+        let request = DecideDisputeRequest {
+            status: DecidedStatus::Overturned,
+            corrected_result: Some(DisplayResult::Draw),
+            note: None,
+        };
+        let err = DisputeService::decide(&db, dispute.id, Uuid::new_v4(), request).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains("FOR UPDATE"), "{}", log);
+        assert!(!log.contains("UPDATE \\\"smdb\\\""), "{}", log);
+        assert!(!log.contains("INSERT"), "{}", log);
+    }
+
+    #[test]
+    fn test_evidence_keeps_moves_and_clock() {
+        let game = finished_game(Some(ResultSide::BlackWins));
+        let evidence: DisputeEvidence = serde_json::from_value(evidence_of(&game)).unwrap();
+        assert_eq!(evidence.pgn, game.pgn);
+        assert_eq!(evidence.final_fen, game.fen);
+        assert_eq!(evidence.result, DisplayResult::BlackWin);
+        assert_eq!((evidence.finished_at - evidence.started_at).num_seconds(), 300);
+    }
+
+    #[test]
+    fn test_played_in_round_needs_a_scored_game() {
+        let white = Player::new(Uuid::new_v4(), "White".to_string(), 1600);
+        let black = Player::new(Uuid::new_v4(), "Black".to_string(), 1500);
+        let (white_id, black_id) = (white.id, black.id);
+        let mut state = TournamentState::new(vec![white, black], 3);
+
+        state.force_pairing(white_id, black_id).unwrap();
+        assert!(!played_in_round(&state, 1, white_id, black_id));
+
+        state.apply_round_results(vec![(white_id, GameResult::Loss), (black_id, GameResult::Win)]);
+        assert!(played_in_round(&state, 1, white_id, black_id));
+        assert!(played_in_round(&state, 1, black_id, white_id));
+        assert!(!played_in_round(&state, 2, white_id, black_id));
+        assert!(!played_in_round(&state, 1, white_id, Uuid::new_v4()));
+    }
+}
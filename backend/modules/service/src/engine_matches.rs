@@ -0,0 +1,520 @@
+use chess::Referee;
+use chrono::{Duration, Utc};
+use db_entity::{
+    engine_match::{self, EngineMatchStatus},
+    engine_match_game,
+};
+use dto::engine_matches::{
+    AdjudicationRules, CreateEngineMatchRequest, EngineMatchDisplay, EngineMatchGameDisplay,
+    MatchEngine, MatchSummaryDisplay, SprtVerdict as SprtVerdictDisplay,
+};
+use engine::matches::{
+    Adjudication, EngineSpec, GameEnd, GameRecord, MatchClock, MatchConfig, MatchRunner, MatchSummary,
+    ProcessLauncher, Side, Sprt, SprtVerdict, schedule,
+};
+use error::error::ApiError;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// How often the instance running a match says it is still alive
+pub const MATCH_HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(30);
+/// Missed heartbeats after which a running match is taken over
+const MISSED_HEARTBEATS: i64 = 4;
+
+pub struct EngineMatchService;
+
+impl EngineMatchService {
+    /// Queue a match. `engines` maps the engine names requests may use to
+    /// the binaries configured on this server.
+    pub async fn create(
+        db: &DatabaseConnection,
+        created_by: Uuid,
+        request: CreateEngineMatchRequest,
+        engines: &HashMap<String, String>,
+    ) -> Result<engine_match::Model, ApiError> {
+        validate_request(&request, engines)?;
+        let config = serde_json::to_value(&request)
+            .map_err(|err| ApiError::Internal(format!("Cannot serialize match: {}", err)))?;
+
+        let model = engine_match::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(request.name),
+            config: Set(config),
+            status: Set(EngineMatchStatus::Queued),
+            wins: Set(0),
+            draws: Set(0),
+            losses: Set(0),
+            error: Set(None),
+            created_by: Set(created_by),
+            created_at: Set(Utc::now().fixed_offset()),
+            started_at: Set(None),
+            finished_at: Set(None),
+            heartbeat_at: Set(None),
+        };
+        Ok(model.insert(db).await?)
+    }
+
+    pub async fn list(db: &DatabaseConnection) -> Result<Vec<engine_match::Model>, ApiError> {
+        Ok(engine_match::Entity::find()
+            .order_by_desc(engine_match::Column::CreatedAt)
+            .all(db)
+            .await?)
+    }
+
+    pub async fn find(db: &DatabaseConnection, id: Uuid) -> Result<engine_match::Model, ApiError> {
+        engine_match::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Engine match".to_string()))
+    }
+
+    pub async fn games(db: &DatabaseConnection, id: Uuid) -> Result<Vec<engine_match_game::Model>, ApiError> {
+        Ok(engine_match_game::Entity::find()
+            .filter(engine_match_game::Column::MatchId.eq(id))
+            .order_by_asc(engine_match_game::Column::Round)
+            .all(db)
+            .await?)
+    }
+
+    /// Stop a queued or running match. A running match stops once its
+    /// current game is over; games already played are kept.
+    pub async fn cancel(db: &DatabaseConnection, id: Uuid) -> Result<engine_match::Model, ApiError> {
+        let existing = Self::find(db, id).await?;
+        if !matches!(existing.status, EngineMatchStatus::Queued | EngineMatchStatus::Running) {
+            return Err(ApiError::BadRequest("Only queued or running matches can be cancelled".to_string()));
+        }
+
+        let mut active: engine_match::ActiveModel = existing.into();
+        active.status = Set(EngineMatchStatus::Cancelled);
+        active.finished_at = Set(Some(Utc::now().fixed_offset()));
+        Ok(active.update(db).await?)
+    }
+
+    /// Queue again the running matches whose instance stopped sending
+    /// heartbeats, as after a crash or restart; they resume after their last
+    /// stored game. Matches other instances are still playing are left alone.
+    pub async fn requeue_interrupted(db: &DatabaseConnection) -> Result<u64, ApiError> {
+        let stale_before =
+            Utc::now().fixed_offset() - Duration::seconds(MATCH_HEARTBEAT.as_secs() as i64 * MISSED_HEARTBEATS);
+        let result = engine_match::Entity::update_many()
+            .col_expr(engine_match::Column::Status, EngineMatchStatus::Queued.into())
+            .filter(engine_match::Column::Status.eq(EngineMatchStatus::Running))
+            .filter(
+                Condition::any()
+                    .add(engine_match::Column::HeartbeatAt.is_null())
+                    .add(engine_match::Column::HeartbeatAt.lt(stale_before)),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Play the oldest queued match to the end. Returns its id, or `None`
+    /// when nothing is queued.
+    pub async fn run_next(
+        db: &DatabaseConnection,
+        engines: &HashMap<String, String>,
+    ) -> Result<Option<Uuid>, ApiError> {
+        let txn = db.begin().await?;
+        let next = engine_match::Entity::find()
+            .filter(engine_match::Column::Status.eq(EngineMatchStatus::Queued))
+            .order_by_asc(engine_match::Column::CreatedAt)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        let Some(next) = next else {
+            return Ok(None);
+        };
+
+        let started_at = next.started_at.unwrap_or_else(|| Utc::now().fixed_offset());
+        let mut active: engine_match::ActiveModel = next.into();
+        active.status = Set(EngineMatchStatus::Running);
+        active.started_at = Set(Some(started_at));
+        active.heartbeat_at = Set(Some(Utc::now().fixed_offset()));
+        let model = active.update(&txn).await?;
+        txn.commit().await?;
+
+        let id = model.id;
+        let outcome = tokio::select! {
+            outcome = Self::run(db, model, engines) => outcome,
+            _ = Self::keep_alive(db, id) => unreachable!("heartbeats never stop on their own"),
+        };
+        if let Err(err) = outcome {
+            Self::close(db, id, EngineMatchStatus::Failed, Some(err.to_string())).await?;
+        }
+        Ok(Some(id))
+    }
+
+    async fn run(
+        db: &DatabaseConnection,
+        model: engine_match::Model,
+        engines: &HashMap<String, String>,
+    ) -> Result<(), ApiError> {
+        let request = request_of(&model)?;
+        let runner = MatchRunner::new(match_config(&request, engines)?, ProcessLauncher);
+        let sprt = sprt_of(&request);
+        let played: HashSet<u32> = Self::games(db, model.id)
+            .await?
+            .into_iter()
+            .map(|g| g.round as u32)
+            .collect();
+
+        for plan in schedule(runner.config()) {
+            if played.contains(&plan.round) {
+                continue;
+            }
+
+            let current = Self::find(db, model.id).await?;
+            if current.status != EngineMatchStatus::Running {
+                return Ok(());
+            }
+            if summary_of(&current, sprt.as_ref()).is_conclusive() {
+                break;
+            }
+
+            let record = runner
+                .play(&plan)
+                .await
+                .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+            Self::store(db, model.id, &record).await?;
+        }
+
+        Self::close(db, model.id, EngineMatchStatus::Finished, None).await
+    }
+
+    /// Refresh the heartbeat of a running match until dropped.
+    async fn keep_alive(db: &DatabaseConnection, id: Uuid) {
+        let mut ticker = tokio::time::interval(MATCH_HEARTBEAT);
+        // The first tick is immediate, and the match was just claimed
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let beat = engine_match::Entity::update_many()
+                .col_expr(engine_match::Column::HeartbeatAt, Expr::value(Utc::now().fixed_offset()))
+                .filter(engine_match::Column::Id.eq(id))
+                .filter(engine_match::Column::Status.eq(EngineMatchStatus::Running))
+                .exec(db)
+                .await;
+            if let Err(e) = beat {
+                log::warn!("Failed to refresh the heartbeat of engine match {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Save a game and add its result to the match.
+    async fn store(db: &DatabaseConnection, match_id: Uuid, record: &GameRecord) -> Result<(), ApiError> {
+        let txn = db.begin().await?;
+        let game = engine_match_game::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            match_id: Set(match_id),
+            round: Set(record.plan.round as i32),
+            opening: Set(record.plan.opening.clone()),
+            a_is_white: Set(record.plan.a_is_white),
+            result: Set(record.result.to_pgn_string().to_string()),
+            termination: Set(termination_name(record.end)),
+            plies: Set(record.moves.len() as i32),
+            pgn: Set(record.pgn.clone()),
+            played_at: Set(Utc::now().fixed_offset()),
+        };
+        game.insert(&txn).await?;
+
+        let current = engine_match::Entity::find_by_id(match_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Engine match".to_string()))?;
+        let (wins, draws, losses) = (current.wins, current.draws, current.losses);
+        let mut active: engine_match::ActiveModel = current.into();
+        match record.winner() {
+            Some(Side::A) => active.wins = Set(wins + 1),
+            Some(Side::B) => active.losses = Set(losses + 1),
+            None => active.draws = Set(draws + 1),
+        }
+        active.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn close(
+        db: &DatabaseConnection,
+        id: Uuid,
+        status: EngineMatchStatus,
+        error: Option<String>,
+    ) -> Result<(), ApiError> {
+        let existing = Self::find(db, id).await?;
+        // A match cancelled meanwhile stays cancelled
+        if existing.status != EngineMatchStatus::Running {
+            return Ok(());
+        }
+
+        let mut active: engine_match::ActiveModel = existing.into();
+        active.status = Set(status);
+        active.error = Set(error);
+        active.finished_at = Set(Some(Utc::now().fixed_offset()));
+        active.update(db).await?;
+        Ok(())
+    }
+}
+
+fn validate_request(request: &CreateEngineMatchRequest, engines: &HashMap<String, String>) -> Result<(), ApiError> {
+    for side in [&request.engine_a, &request.engine_b] {
+        if !engines.contains_key(&side.engine) {
+            return Err(ApiError::BadRequest(format!("Unknown engine '{}'", side.engine)));
+        }
+        // Options are sent to the engine on a single line each
+        let single_line = |text: &str| !text.contains(['\n', '\r']);
+        if let Some((name, _)) = side
+            .options
+            .iter()
+            .find(|(name, value)| name.trim().is_empty() || !single_line(name) || !single_line(value))
+        {
+            return Err(ApiError::BadRequest(format!("Invalid option '{}'", name.trim())));
+        }
+    }
+    if request.engine_a.name == request.engine_b.name {
+        return Err(ApiError::BadRequest("The two engines need different names".to_string()));
+    }
+
+    for opening in &request.openings {
+        Referee::from_opening(opening)
+            .map_err(|err| ApiError::BadRequest(format!("Invalid opening '{}': {}", opening, err)))?;
+    }
+    if let Some(sprt) = sprt_of(request) {
+        sprt.validate().map_err(ApiError::BadRequest)?;
+    }
+    Ok(())
+}
+
+fn request_of(model: &engine_match::Model) -> Result<CreateEngineMatchRequest, ApiError> {
+    serde_json::from_value(model.config.clone())
+        .map_err(|err| ApiError::Internal(format!("Stored match configuration is unreadable: {}", err)))
+}
+
+/// Runner configuration with engine names resolved to configured binaries.
+fn match_config(request: &CreateEngineMatchRequest, engines: &HashMap<String, String>) -> Result<MatchConfig, ApiError> {
+    let spec = |side: &MatchEngine| -> Result<EngineSpec, ApiError> {
+        let path = engines
+            .get(&side.engine)
+            .ok_or_else(|| ApiError::BadRequest(format!("Engine '{}' is no longer configured", side.engine)))?;
+        Ok(EngineSpec {
+            name: side.name.clone(),
+            path: path.clone(),
+            options: side.options.clone(),
+        })
+    };
+
+    Ok(MatchConfig {
+        name: request.name.clone(),
+        engine_a: spec(&request.engine_a)?,
+        engine_b: spec(&request.engine_b)?,
+        games: request.games,
+        clock: MatchClock {
+            base_ms: request.base_ms,
+            increment_ms: request.increment_ms,
+        },
+        openings: request.openings.clone(),
+        adjudication: request.adjudication.map(adjudication_of).unwrap_or_default(),
+        sprt: sprt_of(request),
+    })
+}
+
+fn adjudication_of(rules: AdjudicationRules) -> Adjudication {
+    Adjudication {
+        resign_cp: rules.resign_cp,
+        resign_moves: rules.resign_moves,
+        draw_cp: rules.draw_cp,
+        draw_moves: rules.draw_moves,
+        draw_from_move: rules.draw_from_move,
+        max_plies: rules.max_plies,
+    }
+}
+
+fn sprt_of(request: &CreateEngineMatchRequest) -> Option<Sprt> {
+    request.sprt.map(|sprt| Sprt {
+        elo0: sprt.elo0,
+        elo1: sprt.elo1,
+        alpha: sprt.alpha,
+        beta: sprt.beta,
+    })
+}
+
+fn summary_of(model: &engine_match::Model, sprt: Option<&Sprt>) -> MatchSummary {
+    MatchSummary::new(model.wins as u32, model.draws as u32, model.losses as u32, sprt)
+}
+
+fn termination_name(end: GameEnd) -> String {
+    serde_json::to_value(end)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn summary_display(summary: MatchSummary) -> MatchSummaryDisplay {
+    MatchSummaryDisplay {
+        wins: summary.wins,
+        draws: summary.draws,
+        losses: summary.losses,
+        score: summary.score,
+        elo: summary.elo,
+        elo_margin: summary.elo_margin,
+        llr: summary.llr,
+        lower_bound: summary.lower_bound,
+        upper_bound: summary.upper_bound,
+        verdict: summary.verdict.map(|verdict| match verdict {
+            SprtVerdict::Continue => SprtVerdictDisplay::Continue,
+            SprtVerdict::AcceptH0 => SprtVerdictDisplay::AcceptH0,
+            SprtVerdict::AcceptH1 => SprtVerdictDisplay::AcceptH1,
+        }),
+    }
+}
+
+/// A match with its Elo and SPRT summary.
+pub fn display(model: engine_match::Model) -> Result<EngineMatchDisplay, ApiError> {
+    let request = request_of(&model)?;
+    let summary = summary_of(&model, sprt_of(&request).as_ref());
+
+    Ok(EngineMatchDisplay {
+        id: model.id,
+        name: model.name,
+        status: model.status.into(),
+        engine_a: request.engine_a,
+        engine_b: request.engine_b,
+        games: request.games,
+        games_played: (model.wins + model.draws + model.losses) as u32,
+        base_ms: request.base_ms,
+        increment_ms: request.increment_ms,
+        summary: summary_display(summary),
+        error: model.error,
+        created_at: model.created_at,
+        started_at: model.started_at,
+        finished_at: model.finished_at,
+    })
+}
+
+pub fn game_display(model: &engine_match::Model, game: engine_match_game::Model) -> Result<EngineMatchGameDisplay, ApiError> {
+    let request = request_of(model)?;
+    let (white, black) = if game.a_is_white {
+        (request.engine_a.name, request.engine_b.name)
+    } else {
+        (request.engine_b.name, request.engine_a.name)
+    };
+
+    Ok(EngineMatchGameDisplay {
+        round: game.round as u32,
+        opening: game.opening,
+        white,
+        black,
+        result: game.result,
+        termination: game.termination,
+        plies: game.plies as u32,
+        pgn: game.pgn,
+        played_at: game.played_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dto::engine_matches::SprtRequest;
+    use sea_orm::{DbBackend, MockDatabase, MockExecResult};
+    use std::collections::BTreeMap;
+
+    fn engines() -> HashMap<String, String> {
+        HashMap::from([("stockfish".to_string(), "/usr/games/stockfish".to_string())])
+    }
+
+    fn side(name: &str, skill: &str) -> MatchEngine {
+        MatchEngine {
+            name: name.to_string(),
+            engine: "stockfish".to_string(),
+            options: BTreeMap::from([("Skill Level".to_string(), skill.to_string())]),
+        }
+    }
+
+    fn request() -> CreateEngineMatchRequest {
+        CreateEngineMatchRequest {
+            name: "Level 8 vs level 7".to_string(),
+            engine_a: side("level-8", "8"),
+            engine_b: side("level-7", "7"),
+            games: 100,
+            base_ms: 10_000,
+            increment_ms: 100,
+            openings: vec!["1. e4 c5".to_string()],
+            adjudication: None,
+            sprt: Some(SprtRequest {
+                elo0: 0.0,
+                elo1: 10.0,
+                alpha: 0.05,
+                beta: 0.05,
+            }),
+        }
+    }
+
+    #[test]
+    fn resolves_engines_to_configured_binaries() {
+        let config = match_config(&request(), &engines()).unwrap();
+        assert_eq!(config.engine_a.path, "/usr/games/stockfish");
+        assert_eq!(config.engine_b.options["Skill Level"], "7");
+        assert_eq!(config.adjudication, Adjudication::default());
+        assert!(config.sprt.is_some());
+    }
+
+    #[test]
+    fn rejects_unknown_engines_and_unsafe_options() {
+        assert!(validate_request(&request(), &engines()).is_ok());
+
+        let mut unknown = request();
+        unknown.engine_b.engine = "/bin/sh".to_string();
+        assert!(validate_request(&unknown, &engines()).is_err());
+
+        let mut injected = request();
+        injected
+            .engine_a
+            .options
+            .insert("Hash".to_string(), "16\nquit".to_string());
+        assert!(validate_request(&injected, &engines()).is_err());
+
+        let mut same_name = request();
+        same_name.engine_b.name = "level-8".to_string();
+        assert!(validate_request(&same_name, &engines()).is_err());
+    }
+
+    #[test]
+    fn rejects_illegal_openings_and_bad_sprt_bounds() {
+        let mut opening = request();
+        opening.openings.push("1. e4 e4".to_string());
+        assert!(validate_request(&opening, &engines()).is_err());
+
+        let mut sprt = request();
+        sprt.sprt = Some(SprtRequest {
+            elo0: 5.0,
+            elo1: 0.0,
+            alpha: 0.05,
+            beta: 0.05,
+        });
+        assert!(validate_request(&sprt, &engines()).is_err());
+    }
+
+    #[test]
+    fn terminations_are_stored_by_name() {
+        assert_eq!(termination_name(GameEnd::AdjudicatedDraw), "adjudicated_draw");
+        assert_eq!(termination_name(GameEnd::Checkmate), "checkmate");
+    }
+
+    #[tokio::test]
+    async fn only_matches_without_a_recent_heartbeat_are_requeued() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+        assert_eq!(EngineMatchService::requeue_interrupted(&db).await.unwrap(), 1);
+
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains("\\\"heartbeat_at\\\" IS NULL OR"), "{}", log);
+        assert!(log.contains("\\\"heartbeat_at\\\" <"), "{}", log);
+    }
+}
@@ -1,42 +1,456 @@
-use engine::{Engine, process::ProcessEngine, GoParams, EngineResult, EngineError};
+use async_trait::async_trait;
+use dto::ai::{
+    AnalysisTier, AnalysisVariant, BenchmarkPositionDisplay, EngineBenchmarkDisplay, EngineDisplay, EngineOptionDisplay,
+    EngineQueueStats, EngineWaitStats,
+};
+pub use engine::assets::{AssetCache, AssetManifest, PreparedEngine};
+pub use engine::bench::BENCH_DEPTH;
+use engine::bench::BenchReport;
+pub use engine::bot::{BotMove, BotProfile};
+pub use engine::load::{LoadConfig, LoadController};
+use engine::queue::WaitStats;
+use engine::variant::{standard_engine_plays, uci_options};
+pub use engine::queue::{AnalysisQueue, JobPriority, QueueConfig};
+pub use engine::sessions::{EngineSessions, SessionConfig};
+pub use engine::EngineError;
+use engine::{Engine, GoParams, EngineInfo, EngineResult};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::collections::HashMap;
 use uuid::Uuid;
+use tracing::Instrument;
 
+/// An engine pool running in another process, e.g. reached over gRPC.
+/// It queues jobs itself, so requests are forwarded as they come.
+#[async_trait]
+pub trait RemoteEngine: Send + Sync {
+    #[allow(clippy::too_many_arguments)]
+    async fn search(
+        &self,
+        user: &str,
+        priority: JobPriority,
+        tier: AnalysisTier,
+        variant: AnalysisVariant,
+        game: Option<Uuid>,
+        fen: &str,
+        depth: Option<u8>,
+        time_limit_ms: Option<u32>,
+    ) -> Result<EngineResult, EngineError>;
+
+    /// The engine move of the built-in bot `profile_id`.
+    async fn bot_move(&self, user: &str, profile_id: &str, fen: &str, seed: u64) -> Result<BotMove, EngineError>;
+
+    async fn queue_stats(&self) -> Result<EngineQueueStats, EngineError>;
+
+    /// What the engine of the pool says it is.
+    async fn engine_info(&self) -> Result<EngineInfo, EngineError>;
+
+    /// The benchmark suite run on the engines of the pool.
+    async fn benchmark(&self, depth: u8) -> Result<Vec<EngineBenchmarkDisplay>, EngineError>;
+}
+
+#[derive(Clone)]
 pub struct EngineService {
-    engines: Arc<Mutex<HashMap<Uuid, Box<dyn Engine>>>>,
-    engine_path: String,
+    /// Engines kept between searches of followed games
+    sessions: EngineSessions,
+    engine: PreparedEngine,
+    /// Engines for variants, e.g. Fairy-Stockfish for crazyhouse; others
+    /// fall back to `engine` where it plays them
+    variant_engines: HashMap<AnalysisVariant, PreparedEngine>,
+    queue: AnalysisQueue,
+    /// Cuts interactive searches back while the queue is busy
+    load: LoadController,
+    remote: Option<Arc<dyn RemoteEngine>>,
+    /// Engines identified so far, by binary path; a binary does not change
+    /// while the server runs
+    identities: Arc<Mutex<HashMap<String, EngineInfo>>>,
 }
 
 impl EngineService {
     pub fn new(engine_path: String) -> Self {
+        Self::with_queue(PreparedEngine::from_path(engine_path), AnalysisQueue::new(QueueConfig::default()))
+    }
+
+    /// Share `queue` with every other service that runs engines, so that the
+    /// pool as a whole stays within its slots.
+    pub fn with_queue(engine: PreparedEngine, queue: AnalysisQueue) -> Self {
+        Self {
+            sessions: EngineSessions::default(),
+            engine,
+            variant_engines: HashMap::new(),
+            queue,
+            load: LoadController::default(),
+            remote: None,
+            identities: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Analyse `variant` with `engine`, within the same slots as every
+    /// other engine of the service.
+    pub fn with_variant_engine(mut self, variant: AnalysisVariant, engine: PreparedEngine) -> Self {
+        self.variant_engines.insert(variant, engine);
+        self
+    }
+
+    /// Scale interactive searches to the load of the queue as `load` says.
+    pub fn with_load(mut self, load: LoadConfig) -> Self {
+        self.load = LoadController::new(load);
+        self
+    }
+
+    /// Keep engines for followed games as `sessions` says.
+    pub fn with_sessions(mut self, sessions: SessionConfig) -> Self {
+        self.sessions = EngineSessions::new(sessions);
+        self
+    }
+
+    /// Route variants to engines from `variant=path` pairs, skipping
+    /// variants that analysis does not support.
+    pub fn with_variant_engines(self, engines: impl IntoIterator<Item = (String, String)>) -> Self {
+        engines.into_iter().fold(self, |service, (name, path)| match AnalysisVariant::parse(&name) {
+            Some(variant) => service.with_variant_engine(variant, PreparedEngine::from_path(path)),
+            None => {
+                log::error!("Unknown variant '{}' in the variant engines", name);
+                service
+            }
+        })
+    }
+
+    /// Engines configured for variants, by variant.
+    pub fn variant_engines(&self) -> Vec<(AnalysisVariant, &PreparedEngine)> {
+        let mut engines: Vec<_> = self.variant_engines.iter().map(|(variant, engine)| (*variant, engine)).collect();
+        engines.sort_by_key(|(variant, _)| *variant);
+        engines
+    }
+
+    /// The engine that analyses `variant`.
+    fn engine_for(&self, variant: AnalysisVariant) -> Result<&PreparedEngine, EngineError> {
+        match self.variant_engines.get(&variant) {
+            Some(engine) => Ok(engine),
+            None if standard_engine_plays(variant) => Ok(&self.engine),
+            None => Err(EngineError::UnsupportedVariant(variant.as_str().to_string())),
+        }
+    }
+
+    /// Send every search to `remote` instead of starting engines here.
+    pub fn remote(remote: Arc<dyn RemoteEngine>) -> Self {
         Self {
-            engines: Arc::new(Mutex::new(HashMap::new())),
-            engine_path,
+            remote: Some(remote),
+            ..Self::new(String::new())
         }
     }
 
-    pub async fn get_suggestion(&self, fen: &str, depth: Option<u8>, time_limit_ms: Option<u32>) -> Result<EngineResult, EngineError> {
-        // For now, we'll create a new engine instance for each request
-        // In a real scenario, we might want to pool them
-        let mut engine: ProcessEngine = ProcessEngine::new(&self.engine_path).await?;
-        engine.is_ready().await?;
+    /// Search `fen` once an engine slot is free. `user` is whoever the job is
+    /// run for, so that no one can take over the pool. Interactive searches
+    /// are cut back while the pool is busy, as far as `tier` allows; batch
+    /// jobs already give way to them and keep what they asked for. Positions
+    /// of the same `game` are searched on the engine kept for it, which
+    /// still has the previous search in its hash.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "engine_search", skip_all, fields(?priority, ?variant, ?depth, remote = self.remote.is_some()))]
+    pub async fn get_suggestion(
+        &self,
+        user: &str,
+        priority: JobPriority,
+        tier: AnalysisTier,
+        variant: AnalysisVariant,
+        game: Option<Uuid>,
+        fen: &str,
+        depth: Option<u8>,
+        time_limit_ms: Option<u32>,
+    ) -> Result<EngineResult, EngineError> {
+        if let Some(remote) = &self.remote {
+            return remote.search(user, priority, tier, variant, game, fen, depth, time_limit_ms).await;
+        }
+
+        let prepared = self.engine_for(variant)?;
+        let (depth, time_limit_ms) = match priority {
+            JobPriority::Interactive => {
+                let budget = self.load.plan(tier, depth, time_limit_ms, &self.queue.stats(), self.queue.config().slots);
+                (budget.depth, budget.time_limit_ms)
+            }
+            JobPriority::Batch => (depth, time_limit_ms),
+        };
+        // Held until the engine has quit or been put back
+        let _slot = self.queue.acquire(user, priority).instrument(tracing::info_span!("engine_queue")).await;
+
+        let kept = match game {
+            Some(game) => self.sessions.check_out(game, variant).await,
+            None => None,
+        };
+        let mut engine = match kept {
+            Some(mut engine) => match engine.is_ready().await {
+                Ok(_) => engine,
+                // Gone since its last search
+                Err(_) => start_engine(prepared, variant).await?,
+            },
+            None => start_engine(prepared, variant).await?,
+        };
         engine.set_position(fen).await?;
         
         let params = GoParams {
             depth,
             time_limit_ms,
             search_moves: None,
+            clock: None,
         };
         
         let result = engine.go(params).await?;
-        engine.quit().await?;
+        match game {
+            Some(game) => self.sessions.check_in(game, variant, engine).await,
+            None => engine.quit().await?,
+        }
         
         Ok(result)
     }
 
-    pub async fn analyze_position(&self, fen: &str, depth: u8) -> Result<EngineResult, EngineError> {
-        self.get_suggestion(fen, Some(depth), None).await
+    #[allow(clippy::too_many_arguments)]
+    pub async fn analyze_position(
+        &self,
+        user: &str,
+        priority: JobPriority,
+        tier: AnalysisTier,
+        variant: AnalysisVariant,
+        game: Option<Uuid>,
+        fen: &str,
+        depth: u8,
+    ) -> Result<EngineResult, EngineError> {
+        self.get_suggestion(user, priority, tier, variant, game, fen, Some(depth), None).await
+    }
+
+    /// Quit the engines of followed games that went quiet.
+    pub async fn sweep_sessions(&self) {
+        if self.remote.is_none() {
+            self.sessions.sweep().await;
+        }
+    }
+
+    /// The move `profile` plays in `fen`, searched like any interactive
+    /// request. `played` holds the game's moves in UCI notation from the
+    /// standard starting position, for the bot's opening book.
+    pub async fn bot_move(
+        &self,
+        user: &str,
+        profile: &BotProfile,
+        fen: &str,
+        played: &[String],
+        seed: u64,
+    ) -> Result<BotMove, EngineError> {
+        // Book moves need no engine
+        if let Some(book) = profile.opening_move(played, seed) {
+            return Ok(book);
+        }
+        self.search_bot_move(user, profile, fen, seed).await
+    }
+
+    /// The move `profile` finds in `fen` with the engine, its opening book
+    /// left aside.
+    #[tracing::instrument(name = "engine_bot_move", skip_all, fields(profile = %profile.id, remote = self.remote.is_some()))]
+    pub async fn search_bot_move(
+        &self,
+        user: &str,
+        profile: &BotProfile,
+        fen: &str,
+        seed: u64,
+    ) -> Result<BotMove, EngineError> {
+        if let Some(remote) = &self.remote {
+            return remote.bot_move(user, &profile.id, fen, seed).await;
+        }
+
+        let _slot = self.queue.acquire(user, JobPriority::Interactive).instrument(tracing::info_span!("engine_queue")).await;
+        let mut engine = self.engine.start().await?;
+        let chosen = engine::bot::search_move(engine.as_mut(), profile, fen, seed).await?;
+        engine.quit().await?;
+        Ok(chosen)
+    }
+
+    pub async fn queue_stats(&self) -> Result<EngineQueueStats, EngineError> {
+        if let Some(remote) = &self.remote {
+            return remote.queue_stats().await;
+        }
+
+        let config = self.queue.config();
+        let stats = self.queue.stats();
+        Ok(EngineQueueStats {
+            slots: config.slots,
+            reserved_interactive: config.reserved_interactive,
+            per_user_limit: config.per_user_limit,
+            running: stats.running,
+            running_batch: stats.running_batch,
+            interactive_waiting: stats.interactive_waiting,
+            batch_waiting: stats.batch_waiting,
+            interactive_wait: wait_stats(&stats.interactive_wait),
+            batch_wait: wait_stats(&stats.batch_wait),
+            depth_scale: self.load.scale() as f32,
+            sessions: self.sessions.len().await,
+        })
+    }
+}
+
+impl EngineService {
+    /// Run the benchmark suite to `depth` on the analysis engine, then on
+    /// every variant engine, each in a batch slot so that users still get
+    /// theirs.
+    pub async fn benchmark(&self, depth: u8) -> Result<Vec<EngineBenchmarkDisplay>, EngineError> {
+        if let Some(remote) = &self.remote {
+            return remote.benchmark(depth).await;
+        }
+
+        let mut engines = vec![("analysis".to_string(), &self.engine)];
+        for (variant, engine) in self.variant_engines() {
+            engines.push((format!("analysis-{}", variant.as_str()), engine));
+        }
+
+        let mut reports = Vec::with_capacity(engines.len());
+        for (id, prepared) in engines {
+            let _slot = self.queue.acquire("benchmark", JobPriority::Batch).await;
+            let mut engine = prepared.start().await?;
+            let report = engine::bench::run(engine.as_mut(), depth).await;
+            engine.quit().await?;
+            reports.push(benchmark_display(&id, report?));
+        }
+        Ok(reports)
+    }
+
+    /// Name, author and options of the engine behind analysis and bots.
+    pub async fn engine_info(&self) -> Result<EngineInfo, EngineError> {
+        if let Some(remote) = &self.remote {
+            return remote.engine_info().await;
+        }
+        self.identify(&self.engine).await
+    }
+
+    /// What `engine` says it is, starting it the first time only.
+    pub async fn identify(&self, engine: &PreparedEngine) -> Result<EngineInfo, EngineError> {
+        let key = engine.binary.to_string_lossy().into_owned();
+        let mut identities = self.identities.lock().await;
+        if let Some(info) = identities.get(&key) {
+            return Ok(info.clone());
+        }
+        let info = engine.identify().await?;
+        identities.insert(key, info.clone());
+        Ok(info)
+    }
+}
+
+/// `report` as shown to clients, for the engine known as `id`.
+pub fn benchmark_display(id: &str, report: BenchReport) -> EngineBenchmarkDisplay {
+    EngineBenchmarkDisplay {
+        id: id.to_string(),
+        depth: report.depth,
+        total_nodes: report.total_nodes(),
+        total_time_ms: report.total_time_ms(),
+        nodes_per_second: report.nodes_per_second(),
+        positions: report
+            .positions
+            .into_iter()
+            .map(|position| BenchmarkPositionDisplay {
+                fen: position.fen,
+                best_move: position.best_move,
+                depth: position.depth,
+                nodes: position.nodes,
+                time_ms: position.time_ms,
+            })
+            .collect(),
+    }
+}
+
+/// `info` as shown to clients, for the engine known as `id`.
+pub fn engine_display(id: &str, info: EngineInfo) -> EngineDisplay {
+    EngineDisplay {
+        id: id.to_string(),
+        name: info.name,
+        author: info.author,
+        options: info
+            .options
+            .into_iter()
+            .map(|option| EngineOptionDisplay {
+                name: option.name,
+                kind: option.kind,
+                default: option.default,
+                min: option.min,
+                max: option.max,
+                vars: option.vars,
+            })
+            .collect(),
+    }
+}
+
+/// `prepared`, started and set up for `variant`.
+async fn start_engine(prepared: &PreparedEngine, variant: AnalysisVariant) -> Result<Box<dyn Engine>, EngineError> {
+    let mut engine = prepared.start().await?;
+    for (name, value) in uci_options(variant) {
+        engine.set_option(name, value).await?;
+    }
+    engine.is_ready().await?;
+    Ok(engine)
+}
+
+fn wait_stats(stats: &WaitStats) -> EngineWaitStats {
+    EngineWaitStats {
+        started: stats.started,
+        mean_wait_ms: stats.mean_wait_ms(),
+        max_wait_ms: stats.max_wait_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::builtin::BUILTIN_ENGINE;
+
+    fn engine_service() -> EngineService {
+        EngineService::new(BUILTIN_ENGINE.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_analysis_runs_on_the_builtin_engine() {
+        let result = engine_service()
+            .analyze_position("tester", JobPriority::Interactive, AnalysisTier::Guest, AnalysisVariant::Standard, None, "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", 3)
+            .await
+            .unwrap();
+        assert_eq!(result.best_move, "d1d8");
+        assert_eq!(result.mate, Some(1));
+
+        let info = engine_service().engine_info().await.unwrap();
+        assert!(info.name.unwrap().starts_with("StarkMate built-in"));
+    }
+
+    #[tokio::test]
+    async fn test_followed_games_keep_their_engine() {
+        let service = engine_service();
+        let game = Uuid::from_u128(1);
+        for fen in ["6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "6k1/5ppp/8/8/8/8/5PPP/3R2K1 b - - 0 1"] {
+            service
+                .analyze_position("tester", JobPriority::Interactive, AnalysisTier::Guest, AnalysisVariant::Standard, Some(game), fen, 2)
+                .await
+                .unwrap();
+            assert_eq!(service.queue_stats().await.unwrap().sessions, 1);
+        }
+
+        service
+            .analyze_position("tester", JobPriority::Interactive, AnalysisTier::Guest, AnalysisVariant::Standard, None, "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", 2)
+            .await
+            .unwrap();
+        assert_eq!(service.queue_stats().await.unwrap().sessions, 1);
+        assert_eq!(service.queue_stats().await.unwrap().running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_variants_without_an_engine_are_refused() {
+        let result = engine_service()
+            .get_suggestion("tester", JobPriority::Interactive, AnalysisTier::Guest, AnalysisVariant::Crazyhouse, None, "8/8/8/8/8/8/8/8[] w - - 0 1", None, None)
+            .await;
+        assert!(matches!(result, Err(EngineError::UnsupportedVariant(_))));
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_covers_every_engine() {
+        let service = engine_service().with_variant_engine(AnalysisVariant::Chess960, PreparedEngine::builtin());
+        let reports = service.benchmark(1).await.unwrap();
+
+        let ids: Vec<&str> = reports.iter().map(|report| report.id.as_str()).collect();
+        assert_eq!(ids, ["analysis", "analysis-chess960"]);
+        assert!(reports.iter().all(|report| report.positions.len() == 8 && report.nodes_per_second.is_some()));
     }
 }
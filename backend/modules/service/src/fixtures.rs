@@ -0,0 +1,284 @@
+use chess::Referee;
+use chrono::{Duration, Utc};
+use db_entity::{
+    game, player, player_rating, rating_history, tournament as tournament_entity,
+    game::{GameVariant, ResultSide},
+    player_rating::RatingCategory,
+    tournament::{TournamentFormat, TournamentStatus},
+};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use tournament::{GameResult, Player, PrizeStructure, SwissConfig, SwissPairer, TournamentState};
+use uuid::Uuid;
+
+use crate::helper::password;
+use crate::rating::DEFAULT_VOLATILITY;
+use crate::tournaments::{arbiter_error, to_json};
+
+/// Password of every fixture player
+pub const FIXTURE_PASSWORD: &str = "fixture-password";
+
+/// Rating points recorded for each fixture player, oldest first
+const HISTORY_POINTS: i64 = 10;
+
+/// Username, real name, country and blitz rating of the fixture players
+const PLAYERS: [(&str, &str, &str, i32); 8] = [
+    ("fixture_alice", "Alice Fixture", "NG", 2105),
+    ("fixture_bola", "Bola Fixture", "NG", 1980),
+    ("fixture_chen", "Chen Fixture", "CN", 1875),
+    ("fixture_dana", "Dana Fixture", "US", 1760),
+    ("fixture_emre", "Emre Fixture", "TR", 1640),
+    ("fixture_femi", "Femi Fixture", "GH", 1525),
+    ("fixture_gita", "Gita Fixture", "IN", 1410),
+    ("fixture_hugo", "Hugo Fixture", "FR", 1290),
+];
+
+/// Finished games: moves in SAN and result
+const FINISHED_GAMES: [(&str, ResultSide); 4] = [
+    ("e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 d6 c3 O-O h3 Nb8 d4 Nbd7", ResultSide::Draw),
+    ("d4 d5 c4 e6 Nc3 Nf6 Bg5 Be7 e3 O-O Nf3 h6 Bh4 b6 cxd5 Nxd5 Bxe7 Qxe7 Nxd5 exd5", ResultSide::WhiteWins),
+    ("e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3 e5 Nb3 Be6 f3 Be7 Qd2 O-O O-O-O Nbd7 g4 b5", ResultSide::BlackWins),
+    ("e4 e5 Qh5 Nc6 Bc4 Nf6 Qxf7#", ResultSide::WhiteWins),
+];
+
+/// Games still being played: moves in SAN so far
+const ONGOING_GAMES: [&str; 2] = ["e4 e6 d4 d5 Nc3 Bb4", "c4 e5 Nc3 Nf6 g3"];
+
+/// What was loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureSummary {
+    pub players: usize,
+    pub rating_points: usize,
+    pub finished_games: usize,
+    pub ongoing_games: usize,
+    /// Swiss tournament with its first round scored and its second paired
+    pub tournament_id: Uuid,
+}
+
+pub struct FixtureService;
+
+impl FixtureService {
+    /// Load sample players with their rating histories, finished and
+    /// ongoing games between them, and a tournament in its second round,
+    /// in one transaction. Refused once the fixtures are loaded.
+    pub async fn load(db: &DatabaseConnection) -> Result<FixtureSummary, ApiError> {
+        let txn = db.begin().await?;
+        if player::Entity::find()
+            .filter(player::Column::Username.eq(PLAYERS[0].0))
+            .one(&txn)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::BadRequest("Fixtures are already loaded".to_string()));
+        }
+
+        let players = Self::insert_players(&txn).await?;
+        let rating_points = Self::insert_ratings(&txn, &players).await?;
+
+        let now = Utc::now().fixed_offset();
+        for (index, (moves, result)) in FINISHED_GAMES.iter().enumerate() {
+            let (white, black) = (&players[index * 2], &players[index * 2 + 1]);
+            let started_at = now - Duration::days(index as i64 + 1);
+            insert_game(&txn, white.0, black.0, moves, result.clone(), started_at).await?;
+        }
+        for (index, moves) in ONGOING_GAMES.iter().enumerate() {
+            let (white, black) = (&players[index + 1], &players[players.len() - 1 - index]);
+            let started_at = now - Duration::minutes(5 * (index as i64 + 1));
+            insert_game(&txn, white.0, black.0, moves, ResultSide::Ongoing, started_at).await?;
+        }
+
+        let tournament = Self::insert_tournament(&txn, &players).await?;
+        txn.commit().await?;
+
+        Ok(FixtureSummary {
+            players: players.len(),
+            rating_points,
+            finished_games: FINISHED_GAMES.len(),
+            ongoing_games: ONGOING_GAMES.len(),
+            tournament_id: tournament.id,
+        })
+    }
+
+    /// Ids, usernames and ratings of the inserted players
+    async fn insert_players(txn: &DatabaseTransaction) -> Result<Vec<(Uuid, String, i32)>, ApiError> {
+        let password_hash = password::hash_password(FIXTURE_PASSWORD)?.into_bytes();
+        let mut players = Vec::new();
+        for (username, real_name, country, rating) in PLAYERS {
+            let model = player::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                username: Set(username.to_string()),
+                email: Set(format!("{}@fixtures.starkmate.dev", username)),
+                password_hash: Set(password_hash.clone()),
+                real_name: Set(real_name.to_string()),
+                country: Set(country.to_string()),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+            players.push((model.id, model.username, rating));
+        }
+        Ok(players)
+    }
+
+    /// Blitz ratings climbing from the default to each player's rating over
+    /// the last month. Returns how many history points were recorded.
+    async fn insert_ratings(txn: &DatabaseTransaction, players: &[(Uuid, String, i32)]) -> Result<usize, ApiError> {
+        let now = Utc::now().fixed_offset();
+        let mut points = 0;
+        for (player_id, _, rating) in players {
+            for step in 1..=HISTORY_POINTS {
+                let progress = step as f64 / HISTORY_POINTS as f64;
+                rating_history::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    player_id: Set(*player_id),
+                    category: Set(RatingCategory::Blitz),
+                    game_id: Set(None),
+                    rating: Set(1500 + ((rating - 1500) as f64 * progress).round() as i32),
+                    rating_deviation: Set(350.0 - 290.0 * progress),
+                    volatility: Set(Some(DEFAULT_VOLATILITY)),
+                    recorded_at: Set(now - Duration::days(3 * (HISTORY_POINTS - step))),
+                }
+                .insert(txn)
+                .await?;
+                points += 1;
+            }
+            player_rating::ActiveModel {
+                player_id: Set(*player_id),
+                category: Set(RatingCategory::Blitz),
+                rating: Set(*rating),
+                rating_deviation: Set(60.0),
+                volatility: Set(DEFAULT_VOLATILITY),
+                games_played: Set(HISTORY_POINTS as i32),
+                updated_at: Set(now),
+                last_rated_at: Set(Some(now)),
+                rd_updated_at: Set(now),
+            }
+            .insert(txn)
+            .await?;
+        }
+        Ok(points)
+    }
+
+    /// A Swiss tournament of every fixture player. The first round is
+    /// scored with the higher rated player winning, the second is paired
+    /// and waiting for results.
+    async fn insert_tournament(
+        txn: &DatabaseTransaction,
+        players: &[(Uuid, String, i32)],
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let config = SwissConfig { total_rounds: 5, deterministic_seed: Some(20261016), ..SwissConfig::default() };
+        let mut state = TournamentState::new(
+            players.iter().map(|(id, username, rating)| Player::new(*id, username.clone(), *rating)).collect(),
+            config.total_rounds,
+        );
+        let pairer = SwissPairer::new(config.clone());
+
+        state.pair_remaining(&pairer).map_err(arbiter_error)?;
+        let rating_of = |id| players.iter().find(|(player, _, _)| *player == id).map_or(0, |(_, _, rating)| *rating);
+        let results = state
+            .pairings
+            .iter()
+            .filter(|pairing| pairing.round == 1)
+            .flat_map(|pairing| {
+                let white = if rating_of(pairing.white_player) >= rating_of(pairing.black_player) {
+                    GameResult::Win
+                } else {
+                    GameResult::Loss
+                };
+                [(pairing.white_player, white), (pairing.black_player, white.reversed())]
+            })
+            .collect();
+        state.apply_round_results(results);
+        state.pair_remaining(&pairer).map_err(arbiter_error)?;
+
+        let now = Utc::now().fixed_offset();
+        let model = tournament_entity::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set("Fixture Open".to_string()),
+            arbiter_id: Set(players[0].0),
+            time_control: Set(RatingCategory::Blitz),
+            config: Set(to_json(&config)?),
+            state: Set(to_json(&state)?),
+            created_at: Set(now - Duration::hours(2)),
+            updated_at: Set(now),
+            format: Set(TournamentFormat::Swiss),
+            status: Set(TournamentStatus::Ongoing),
+            template_id: Set(None),
+            duration_minutes: Set(None),
+            registration_opens_at: Set(None),
+            registration_closes_at: Set(None),
+            starts_at: Set(Some(now - Duration::hours(2))),
+            prizes: Set(to_json(&PrizeStructure::default())?),
+            club_id: Set(None),
+        }
+        .insert(txn)
+        .await?;
+        Ok(model)
+    }
+}
+
+async fn insert_game(
+    txn: &DatabaseTransaction,
+    white: Uuid,
+    black: Uuid,
+    moves: &str,
+    result: ResultSide,
+    started_at: chrono::DateTime<chrono::FixedOffset>,
+) -> Result<(), ApiError> {
+    let moves: Vec<&str> = moves.split_whitespace().collect();
+    let fen = final_fen(&moves)?;
+    game::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        white_player: Set(white),
+        black_player: Set(black),
+        fen: Set(fen),
+        pgn: Set(serde_json::json!({ "moves": moves })),
+        result: Set(Some(result)),
+        variant: Set(GameVariant::Standard),
+        started_at: Set(started_at),
+        duration_sec: Set(300),
+        created_at: Set(started_at),
+        updated_at: Set(started_at + Duration::minutes(10)),
+        is_imported: Set(false),
+        original_pgn: Set(None),
+        odds: Set(None),
+    }
+    .insert(txn)
+    .await?;
+    Ok(())
+}
+
+/// Position after `moves` from the standard starting position
+fn final_fen(moves: &[&str]) -> Result<String, ApiError> {
+    let mut board = Referee::default();
+    for notation in moves {
+        board
+            .play_san(notation)
+            .map_err(|err| ApiError::BadRequest(format!("Fixture move {} is illegal: {}", notation, err)))?;
+    }
+    Ok(board.fen())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_games_are_legal() {
+        for (moves, _) in FINISHED_GAMES {
+            final_fen(&moves.split_whitespace().collect::<Vec<_>>()).unwrap();
+        }
+        for moves in ONGOING_GAMES {
+            final_fen(&moves.split_whitespace().collect::<Vec<_>>()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_fixture_games_fit_the_players() {
+        assert!(FINISHED_GAMES.len() * 2 <= PLAYERS.len());
+        assert!(ONGOING_GAMES.len() < PLAYERS.len() / 2);
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::Utc;
+use db_entity::{player, player_friend};
+use dto::annotations::FriendDisplay;
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use uuid::Uuid;
+
+pub struct FriendService;
+
+impl FriendService {
+    /// Put `friend_id` on the friends list of `player_id`. Adding someone
+    /// already on the list is a no-op.
+    pub async fn add(db: &DatabaseConnection, player_id: Uuid, friend_id: Uuid) -> Result<(), ApiError> {
+        if player_id == friend_id {
+            return Err(ApiError::BadRequest("You cannot add yourself as a friend".to_string()));
+        }
+        if player::Entity::find_by_id(friend_id).one(db).await?.is_none() {
+            return Err(ApiError::NotFound("Player".to_string()));
+        }
+        if Self::is_friend(db, player_id, friend_id).await? {
+            return Ok(());
+        }
+
+        let entry = player_friend::ActiveModel {
+            player_id: Set(player_id),
+            friend_id: Set(friend_id),
+            created_at: Set(Utc::now().fixed_offset()),
+        };
+        entry.insert(db).await?;
+        Ok(())
+    }
+
+    pub async fn remove(db: &DatabaseConnection, player_id: Uuid, friend_id: Uuid) -> Result<(), ApiError> {
+        let result = player_friend::Entity::delete_by_id((player_id, friend_id)).exec(db).await?;
+        if result.rows_affected == 0 {
+            return Err(ApiError::NotFound("Friend".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The friends list of `player_id`, most recently added first.
+    pub async fn list(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<FriendDisplay>, ApiError> {
+        let friends = player_friend::Entity::find()
+            .filter(player_friend::Column::PlayerId.eq(player_id))
+            .order_by_desc(player_friend::Column::CreatedAt)
+            .find_also_related(player::Entity)
+            .all(db)
+            .await?;
+
+        Ok(friends
+            .into_iter()
+            .map(|(entry, friend)| FriendDisplay {
+                player_id: entry.friend_id,
+                username: friend.map(|f| f.username).unwrap_or_default(),
+                added_at: entry.created_at,
+            })
+            .collect())
+    }
+
+    /// Whether `friend_id` is on the friends list of `player_id`.
+    pub async fn is_friend(db: &DatabaseConnection, player_id: Uuid, friend_id: Uuid) -> Result<bool, ApiError> {
+        let count = player_friend::Entity::find()
+            .filter(player_friend::Column::PlayerId.eq(player_id))
+            .filter(player_friend::Column::FriendId.eq(friend_id))
+            .count(db)
+            .await?;
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use db_entity::game::{self, ResultSide};
+use db_entity::game_archive;
+use db_entity::game_attestation::{self, AttestationStatus};
+use db_entity::game_dispute::{self, DisputeStatus};
+use error::error::ApiError;
+use sea_orm::sea_query::Query;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Results of games that are over and will not change again
+pub const FINISHED: [ResultSide; 4] = [ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw, ResultSide::Abandoned];
+
+pub struct GameArchiveService;
+
+impl GameArchiveService {
+    /// Move up to `batch` finished games last updated before `cutoff` from
+    /// the game table into the archive, in one transaction. Games with an
+    /// open dispute or an attestation still on its way are left for a later
+    /// pass. Returns how many games were moved.
+    pub async fn run_pass(
+        db: &DatabaseConnection,
+        cutoff: DateTime<FixedOffset>,
+        batch: u64,
+    ) -> Result<u64, ApiError> {
+        let txn = db.begin().await?;
+        let games = game::Entity::find()
+            .filter(game::Column::Result.is_in(FINISHED))
+            .filter(game::Column::UpdatedAt.lt(cutoff))
+            .filter(
+                game::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(game_dispute::Column::GameId)
+                        .from(game_dispute::Entity)
+                        .and_where(game_dispute::Column::Status.eq(DisputeStatus::Open))
+                        .to_owned(),
+                ),
+            )
+            .filter(
+                game::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(game_attestation::Column::GameId)
+                        .from(game_attestation::Entity)
+                        .and_where(
+                            game_attestation::Column::Status
+                                .is_in([AttestationStatus::Pending, AttestationStatus::Submitted]),
+                        )
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(game::Column::UpdatedAt)
+            .limit(batch)
+            .lock_exclusive()
+            .all(&txn)
+            .await?;
+        if games.is_empty() {
+            return Ok(0);
+        }
+
+        let years: BTreeSet<i32> = games.iter().map(|game| game.created_at.with_timezone(&Utc).year()).collect();
+        for year in years {
+            txn.execute_unprepared(&create_partition_sql(year)).await?;
+        }
+
+        let moved = games.len() as u64;
+        let ids: Vec<Uuid> = games.iter().map(|game| game.id).collect();
+        let archived_at = Utc::now().fixed_offset();
+        game_archive::Entity::insert_many(games.into_iter().map(|game| archived(game, archived_at)))
+            .exec(&txn)
+            .await?;
+        game::Entity::delete_many()
+            .filter(game::Column::Id.is_in(ids))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(moved)
+    }
+
+    /// A game by id, from the game table or else from the archive.
+    pub async fn find<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<Option<game::Model>, DbErr> {
+        if let Some(game) = game::Entity::find_by_id(id).one(db).await? {
+            return Ok(Some(game));
+        }
+        let archived = game_archive::Entity::find()
+            .filter(game_archive::Column::Id.eq(id))
+            .one(db)
+            .await?;
+        Ok(archived.map(restored))
+    }
+
+    /// The games with the given ids, wherever they are stored.
+    pub async fn find_many<C: ConnectionTrait>(
+        db: &C,
+        ids: impl IntoIterator<Item = Uuid>,
+    ) -> Result<Vec<game::Model>, DbErr> {
+        let ids: BTreeSet<Uuid> = ids.into_iter().collect();
+        let mut games = game::Entity::find()
+            .filter(game::Column::Id.is_in(ids.iter().copied()))
+            .all(db)
+            .await?;
+        if games.len() < ids.len() {
+            let archived = game_archive::Entity::find()
+                .filter(game_archive::Column::Id.is_in(ids.iter().copied()))
+                .all(db)
+                .await?;
+            games.extend(archived.into_iter().map(restored));
+        }
+        Ok(games)
+    }
+}
+
+/// DDL for the archive partition holding games created in `year` (UTC).
+pub fn create_partition_sql(year: i32) -> String {
+    format!(
+        r#"CREATE TABLE IF NOT EXISTS "smdb"."game_archive_{year}" PARTITION OF "smdb"."game_archive" FOR VALUES FROM ('{year}-01-01 00:00:00+00') TO ('{next}-01-01 00:00:00+00')"#,
+        year = year,
+        next = year + 1,
+    )
+}
+
+/// The archive row of a finished game.
+fn archived(game: game::Model, archived_at: DateTime<FixedOffset>) -> game_archive::ActiveModel {
+    game_archive::ActiveModel {
+        id: Set(game.id),
+        white_player: Set(game.white_player),
+        black_player: Set(game.black_player),
+        fen: Set(game.fen),
+        pgn: Set(game.pgn),
+        result: Set(game.result),
+        variant: Set(game.variant),
+        started_at: Set(game.started_at),
+        duration_sec: Set(game.duration_sec),
+        created_at: Set(game.created_at),
+        updated_at: Set(game.updated_at),
+        is_imported: Set(game.is_imported),
+        original_pgn: Set(game.original_pgn),
+        odds: Set(game.odds),
+        archived_at: Set(archived_at),
+    }
+}
+
+/// An archived game as read from the game table, so callers need not care
+/// where it is stored.
+pub fn restored(archived: game_archive::Model) -> game::Model {
+    game::Model {
+        id: archived.id,
+        white_player: archived.white_player,
+        black_player: archived.black_player,
+        fen: archived.fen,
+        pgn: archived.pgn,
+        result: archived.result,
+        variant: archived.variant,
+        started_at: archived.started_at,
+        duration_sec: archived.duration_sec,
+        created_at: archived.created_at,
+        updated_at: archived.updated_at,
+        is_imported: archived.is_imported,
+        original_pgn: archived.original_pgn,
+        odds: archived.odds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db_entity::game::GameVariant;
+    use sea_orm::ActiveValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_partition_covers_one_utc_year() {
+        assert_eq!(
+            create_partition_sql(2025),
+            r#"CREATE TABLE IF NOT EXISTS "smdb"."game_archive_2025" PARTITION OF "smdb"."game_archive" FOR VALUES FROM ('2025-01-01 00:00:00+00') TO ('2026-01-01 00:00:00+00')"#
+        );
+    }
+
+    #[test]
+    fn test_archived_game_restores_unchanged() {
+        let now = Utc::now().fixed_offset();
+        let game = game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: "8/8/8/8/8/8/8/K6k w - - 0 1".to_string(),
+            pgn: json!({"moves": ["e4", "e5"]}),
+            result: Some(ResultSide::Draw),
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 600,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        };
+
+        let row = archived(game.clone(), now);
+        let ActiveValue::Set(created_at) = row.created_at.clone() else {
+            panic!("partition key must be set");
+        };
+        assert_eq!(created_at, game.created_at);
+
+        let stored = game_archive::Model {
+            id: game.id,
+            white_player: game.white_player,
+            black_player: game.black_player,
+            fen: game.fen.clone(),
+            pgn: game.pgn.clone(),
+            result: game.result.clone(),
+            variant: game.variant.clone(),
+            started_at: game.started_at,
+            duration_sec: game.duration_sec,
+            created_at: game.created_at,
+            updated_at: game.updated_at,
+            is_imported: game.is_imported,
+            original_pgn: game.original_pgn.clone(),
+            odds: game.odds.clone(),
+            archived_at: now,
+        };
+        assert_eq!(restored(stored), game);
+    }
+}
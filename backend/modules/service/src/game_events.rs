@@ -0,0 +1,805 @@
+use chess::odds::PASS;
+use chess::{Material, PgnGameResult, Referee};
+use chrono::Utc;
+use db_entity::game::{self, ResultSide};
+use db_entity::game_event::{self, GameEventKind};
+use db_entity::game_snapshot;
+use dto::games::{GameEvent, GameEventRecord, GameLogResponse, GameLogState, GameResult, Side};
+use dto::webhooks::{GameFinishedData, WebhookEvent};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::game_archive::GameArchiveService;
+use crate::games::is_rated;
+use crate::preferences::PreferenceService;
+use crate::rating::RatingService;
+use crate::replay::{start_board, termination_name};
+use crate::stats::{FinishedGameDetails, StatsService};
+use crate::webhooks::WebhookService;
+
+/// Events between two snapshots of a game's state
+pub const SNAPSHOT_EVERY: i32 = 50;
+
+/// Most events one page of a game's log may hold
+pub const MAX_EVENT_PAGE: u64 = 500;
+
+pub struct GameEventService;
+
+/// A move of a game's log that stands, with the mover's clock around it
+/// and the material on the board when it was played.
+#[derive(Debug, Clone)]
+pub struct LoggedMove {
+    pub ply: u32,
+    pub side: Side,
+    pub notation: String,
+    /// The mover's clock before and after the move, in timed games whose
+    /// log has them
+    pub clock_before_ms: Option<i64>,
+    pub clock_after_ms: Option<i64>,
+    pub material: Material,
+}
+
+impl GameEventService {
+    /// Check `event` against the state the log of a running game leads to,
+    /// append it and bring the game row in line with the new state, in one
+    /// transaction. `actor` is the player who caused the event, or `None`
+    /// for the server, which alone sets clocks and ends games other than by
+    /// resignation.
+    pub async fn append(
+        db: &DatabaseConnection,
+        game_id: Uuid,
+        actor: Option<Uuid>,
+        event: GameEvent,
+    ) -> Result<GameLogState, ApiError> {
+        let txn = db.begin().await?;
+        // Locking the game row serializes appends, so the log has no gaps
+        let game = game::Entity::find_by_id(game_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+
+        let mut fold = Fold::load(&txn, &game).await?;
+        let accepted = accept(&txn, &game, &mut fold, actor, event).await?;
+        write(&txn, game, vec![accepted], &fold.state).await?;
+
+        txn.commit().await?;
+        Ok(fold.state)
+    }
+
+    /// Up to `limit` events of a game after sequence number `after`, with
+    /// the state after its latest event, for clients that resync. The log
+    /// outlives the game row when the game is archived.
+    pub async fn log(db: &DatabaseConnection, game_id: Uuid, after: i32, limit: u64) -> Result<GameLogResponse, ApiError> {
+        let game = GameArchiveService::find(db, game_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+        let limit = limit.clamp(1, MAX_EVENT_PAGE);
+
+        // One event more than asked tells whether there is a next page
+        let rows = game_event::Entity::find()
+            .filter(game_event::Column::GameId.eq(game_id))
+            .filter(game_event::Column::Seq.gt(after))
+            .order_by_asc(game_event::Column::Seq)
+            .limit(limit + 1)
+            .all(db)
+            .await?;
+        let next_after = (rows.len() as u64 > limit).then(|| rows[limit as usize - 1].seq);
+
+        let events = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(|row| {
+                Ok(GameEventRecord {
+                    seq: row.seq,
+                    event: stored_event(&row)?,
+                    actor_id: row.actor_id,
+                    recorded_at: row.recorded_at.to_utc(),
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        let state = Fold::load(db, &game).await?.state;
+
+        Ok(GameLogResponse { game_id, events, next_after, state })
+    }
+
+    /// `game` with the moves of its log in place of the stored ones, so
+    /// replays and attestations read what was played rather than what the
+    /// game row says. Games without a log are returned unchanged.
+    pub async fn with_logged_moves<C: ConnectionTrait>(db: &C, mut game: game::Model) -> Result<game::Model, ApiError> {
+        let state = Fold::load(db, &game).await?.state;
+        if state.seq > 0 {
+            set_moves(&mut game.pgn, &state.moves);
+        }
+        Ok(game)
+    }
+
+    /// The moves of a game's log in the order they were played, leaving
+    /// out the ones taken back. Games without a log have none.
+    pub async fn logged_moves<C: ConnectionTrait>(db: &C, game: &game::Model) -> Result<Vec<LoggedMove>, ApiError> {
+        let rows = game_event::Entity::find()
+            .filter(game_event::Column::GameId.eq(game.id))
+            .order_by_asc(game_event::Column::Seq)
+            .all(db)
+            .await?;
+        let events = rows.iter().map(stored_event).collect::<Result<Vec<_>, ApiError>>()?;
+        replay_moves(game, &events)
+    }
+}
+
+fn replay_moves(game: &game::Model, events: &[GameEvent]) -> Result<Vec<LoggedMove>, ApiError> {
+    let mut fold = Fold::new(game)?;
+    let mut moves: Vec<LoggedMove> = Vec::new();
+    for event in events {
+        let side = fold.to_move();
+        let clock_before_ms = match side {
+            Side::White => fold.state.white_ms,
+            Side::Black => fold.state.black_ms,
+        };
+        let material = fold.board.material();
+        fold.apply(event)?;
+
+        match event {
+            GameEvent::Move { clock_ms, .. } => moves.push(LoggedMove {
+                ply: fold.state.moves.len() as u32,
+                side,
+                notation: fold.state.moves.last().cloned().unwrap_or_default(),
+                clock_before_ms,
+                clock_after_ms: *clock_ms,
+                material,
+            }),
+            GameEvent::TakebackAccepted { .. } => {
+                moves.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(moves)
+}
+
+/// An event checked against a game's state, with what writing it takes:
+/// its row, a snapshot when one is due, and the notice of the game's end
+/// when it ended the game.
+#[derive(Clone)]
+pub(crate) struct Accepted {
+    row: game_event::ActiveModel,
+    snapshot: Option<game_snapshot::ActiveModel>,
+    finished: Option<GameFinishedData>,
+}
+
+impl Accepted {
+    pub(crate) fn ends_game(&self) -> bool {
+        self.finished.is_some()
+    }
+}
+
+/// Check `event` as caused by `actor` and fold it into `fold`; `fold` is
+/// left as it was when the event is refused.
+pub(crate) async fn accept<C: ConnectionTrait>(
+    db: &C,
+    game: &game::Model,
+    fold: &mut Fold,
+    actor: Option<Uuid>,
+    mut event: GameEvent,
+) -> Result<Accepted, ApiError> {
+    if let Some(actor) = actor {
+        check_actor(game, fold, actor, &event)?;
+    }
+    let was_running = fold.state.result == GameResult::InProgress;
+    if let Err(err) = fold.apply(&event) {
+        // A promotion sent without a piece is a queen for players who ask for that
+        let queened = match (actor, queen_promotion(&event)) {
+            (Some(actor), Some(queened)) if PreferenceService::get(db, actor).await?.auto_queen => queened,
+            _ => return Err(err),
+        };
+        fold.apply(&queened)?;
+        event = queened;
+    }
+    let state = &fold.state;
+    let now = Utc::now().fixed_offset();
+
+    let snapshot = if state.seq % SNAPSHOT_EVERY == 0 {
+        Some(game_snapshot::ActiveModel {
+            game_id: Set(game.id),
+            seq: Set(state.seq),
+            state: Set(to_json(state)?),
+            created_at: Set(now),
+        })
+    } else {
+        None
+    };
+    let finished = (was_running && state.result != GameResult::InProgress).then(|| GameFinishedData {
+        game_id: game.id,
+        white_player_id: game.white_player,
+        black_player_id: game.black_player,
+        result: state.result,
+        termination: state.termination.clone(),
+        fen: state.fen.clone(),
+        moves: state.moves.clone(),
+    });
+
+    Ok(Accepted {
+        row: game_event::ActiveModel {
+            game_id: Set(game.id),
+            seq: Set(state.seq),
+            kind: Set(kind_of(&event)),
+            payload: Set(to_json(&event)?),
+            actor_id: Set(actor),
+            recorded_at: Set(now),
+        },
+        snapshot,
+        finished,
+    })
+}
+
+/// Write `accepted`, in order, and bring the row of `game` in line with
+/// `state`, the state after the last of them; a game they end is rated,
+/// unless it stays out of the rating pools, and counts towards its players'
+/// stats. Run it in the transaction holding the lock on the game row.
+pub(crate) async fn write<C: ConnectionTrait + TransactionTrait>(
+    txn: &C,
+    game: game::Model,
+    accepted: Vec<Accepted>,
+    state: &GameLogState,
+) -> Result<(), ApiError> {
+    let mut rows = Vec::with_capacity(accepted.len());
+    let mut snapshots = Vec::new();
+    let mut finished = Vec::new();
+    for event in accepted {
+        rows.push(event.row);
+        snapshots.extend(event.snapshot);
+        finished.extend(event.finished);
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    game_event::Entity::insert_many(rows).exec(txn).await?;
+    if !snapshots.is_empty() {
+        game_snapshot::Entity::insert_many(snapshots).exec(txn).await?;
+    }
+    let ended = !finished.is_empty();
+    for finished in finished {
+        WebhookService::publish(txn, WebhookEvent::GameFinished(finished)).await?;
+    }
+    let game = projected(game, state).update(txn).await?;
+    if ended {
+        let ratings = match is_rated(&game) && game.white_player != game.black_player {
+            true => RatingService::record_game(txn, &game).await?,
+            false => None,
+        };
+        let details = FinishedGameDetails {
+            white_rating: ratings.map(|(white, _)| white.rating),
+            black_rating: ratings.map(|(_, black)| black.rating),
+            ..FinishedGameDetails::default()
+        };
+        StatsService::record_finished_game(txn, &game, details).await?;
+    }
+    Ok(())
+}
+
+/// Sequence number of the latest event written for a game, 0 without one.
+pub(crate) async fn written_seq<C: ConnectionTrait>(db: &C, game_id: Uuid) -> Result<i32, ApiError> {
+    let latest = game_event::Entity::find()
+        .filter(game_event::Column::GameId.eq(game_id))
+        .order_by_desc(game_event::Column::Seq)
+        .one(db)
+        .await?;
+    Ok(latest.map_or(0, |row| row.seq))
+}
+
+/// A game's state together with the board it is played on. Everything
+/// here is derived from the game's starting position and its log alone.
+pub(crate) struct Fold {
+    pub(crate) state: GameLogState,
+    board: Referee,
+}
+
+impl Fold {
+    /// The state before the first event.
+    fn new(game: &game::Model) -> Result<Self, ApiError> {
+        let board = start_board(game)?;
+        let clock = (game.duration_sec > 0).then_some(game.duration_sec as i64 * 1000);
+        Ok(Self {
+            state: GameLogState {
+                seq: 0,
+                fen: board.fen(),
+                moves: board.moves().to_vec(),
+                white_ms: clock,
+                black_ms: clock,
+                draw_offer: None,
+                takeback_offer: None,
+                result: GameResult::InProgress,
+                termination: None,
+            },
+            board,
+        })
+    }
+
+    /// The state after the latest event: the latest snapshot, with the
+    /// events after it folded in.
+    pub(crate) async fn load<C: ConnectionTrait>(db: &C, game: &game::Model) -> Result<Self, ApiError> {
+        let snapshot = game_snapshot::Entity::find()
+            .filter(game_snapshot::Column::GameId.eq(game.id))
+            .order_by_desc(game_snapshot::Column::Seq)
+            .one(db)
+            .await?;
+        let mut fold = match snapshot {
+            Some(snapshot) => Self::restore(game, &snapshot)?,
+            None => Self::new(game)?,
+        };
+
+        let events = game_event::Entity::find()
+            .filter(game_event::Column::GameId.eq(game.id))
+            .filter(game_event::Column::Seq.gt(fold.state.seq))
+            .order_by_asc(game_event::Column::Seq)
+            .all(db)
+            .await?;
+        for row in &events {
+            fold.apply(&stored_event(row)?)?;
+        }
+        Ok(fold)
+    }
+
+    /// The state of a snapshot, with its board rebuilt from the moves.
+    fn restore(game: &game::Model, snapshot: &game_snapshot::Model) -> Result<Self, ApiError> {
+        let state: GameLogState = serde_json::from_value(snapshot.state.clone())
+            .map_err(|err| ApiError::Internal(format!("Snapshot {} is unreadable: {}", snapshot.seq, err)))?;
+        let mut board = start_board(game)?;
+        // Skipped turns of odds games are replayed by the referee itself
+        for notation in state.moves.iter().filter(|notation| *notation != PASS) {
+            board
+                .play_uci(notation)
+                .or_else(|_| board.play_san(notation))
+                .map_err(|err| ApiError::Internal(err.to_string()))?;
+        }
+        Ok(Self { state, board })
+    }
+
+    fn to_move(&self) -> Side {
+        if self.board.white_to_move() {
+            Side::White
+        } else {
+            Side::Black
+        }
+    }
+
+    /// Fold `event` into the state, or fail if the game does not allow it
+    /// now; the state is left as it was then.
+    fn apply(&mut self, event: &GameEvent) -> Result<(), ApiError> {
+        let mover = self.to_move();
+        let state = &mut self.state;
+        if state.result != GameResult::InProgress {
+            return Err(ApiError::BadRequest("The game is over".to_string()));
+        }
+
+        match event {
+            GameEvent::Move { notation, clock_ms } => {
+                self.board
+                    .play_uci(notation)
+                    .or_else(|_| self.board.play_san(notation))
+                    .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+                if let Some(clock_ms) = clock_ms {
+                    match mover {
+                        Side::White => state.white_ms = Some(*clock_ms),
+                        Side::Black => state.black_ms = Some(*clock_ms),
+                    }
+                }
+                // Moving instead of answering declines the opponent's offers
+                if state.draw_offer == Some(mover.opponent()) {
+                    state.draw_offer = None;
+                }
+                state.takeback_offer = None;
+                if let Some((result, termination)) = self.board.outcome() {
+                    state.result = match result {
+                        PgnGameResult::WhiteWins => GameResult::WhiteWin,
+                        PgnGameResult::BlackWins => GameResult::BlackWin,
+                        PgnGameResult::Draw | PgnGameResult::Ongoing => GameResult::Draw,
+                    };
+                    state.termination = Some(termination_name(termination).to_string());
+                }
+            }
+            GameEvent::DrawOffered { by } => {
+                if state.draw_offer.is_some() {
+                    return Err(ApiError::BadRequest("A draw offer is already pending".to_string()));
+                }
+                state.draw_offer = Some(*by);
+            }
+            GameEvent::DrawDeclined { by } | GameEvent::DrawAccepted { by } => {
+                if state.draw_offer != Some(by.opponent()) {
+                    return Err(ApiError::BadRequest("No pending draw offer from the opponent".to_string()));
+                }
+                state.draw_offer = None;
+                if matches!(event, GameEvent::DrawAccepted { .. }) {
+                    state.result = GameResult::Draw;
+                    state.termination = Some("agreement".to_string());
+                }
+            }
+            GameEvent::TakebackOffered { by } => {
+                if state.takeback_offer.is_some() {
+                    return Err(ApiError::BadRequest("A takeback offer is already pending".to_string()));
+                }
+                if state.moves.is_empty() {
+                    return Err(ApiError::BadRequest("There is no move to take back".to_string()));
+                }
+                state.takeback_offer = Some(*by);
+            }
+            GameEvent::TakebackDeclined { by } | GameEvent::TakebackAccepted { by } => {
+                if state.takeback_offer != Some(by.opponent()) {
+                    return Err(ApiError::BadRequest("No pending takeback offer from the opponent".to_string()));
+                }
+                if matches!(event, GameEvent::TakebackAccepted { .. }) {
+                    self.board.take_back().map_err(|err| ApiError::BadRequest(err.to_string()))?;
+                }
+                state.takeback_offer = None;
+            }
+            GameEvent::ClockSet { white_ms, black_ms } => {
+                state.white_ms = Some(*white_ms);
+                state.black_ms = Some(*black_ms);
+            }
+            GameEvent::Ended { result, termination } => {
+                if *result == GameResult::InProgress {
+                    return Err(ApiError::BadRequest("A game cannot end in progress".to_string()));
+                }
+                state.result = *result;
+                state.termination = Some(termination.clone());
+                state.draw_offer = None;
+                state.takeback_offer = None;
+            }
+        }
+
+        state.seq += 1;
+        state.fen = self.board.fen();
+        state.moves = self.board.moves().to_vec();
+        Ok(())
+    }
+}
+
+/// Reject events a player may not cause: moves out of turn, answers on
+/// behalf of the opponent, clock changes, and endings other than resigning.
+fn check_actor(game: &game::Model, fold: &Fold, actor: Uuid, event: &GameEvent) -> Result<(), ApiError> {
+    let side = if actor == game.white_player {
+        Side::White
+    } else if actor == game.black_player {
+        Side::Black
+    } else {
+        return Err(ApiError::Forbidden("Only the players of the game can play it".to_string()));
+    };
+
+    match event {
+        GameEvent::Move { .. } if fold.to_move() != side => Err(ApiError::BadRequest("It is not your turn".to_string())),
+        GameEvent::Move { .. } => Ok(()),
+        GameEvent::DrawOffered { by }
+        | GameEvent::DrawDeclined { by }
+        | GameEvent::DrawAccepted { by }
+        | GameEvent::TakebackOffered { by }
+        | GameEvent::TakebackAccepted { by }
+        | GameEvent::TakebackDeclined { by } => match *by == side {
+            true => Ok(()),
+            false => Err(ApiError::Forbidden("Players can only act for their own side".to_string())),
+        },
+        GameEvent::ClockSet { .. } => Err(ApiError::Forbidden("Only the server sets clocks".to_string())),
+        GameEvent::Ended { result, .. } => {
+            let resigned = match side {
+                Side::White => GameResult::BlackWin,
+                Side::Black => GameResult::WhiteWin,
+            };
+            match *result == resigned {
+                true => Ok(()),
+                false => Err(ApiError::Forbidden("Players can only end a game by resigning".to_string())),
+            }
+        }
+    }
+}
+
+fn kind_of(event: &GameEvent) -> GameEventKind {
+    match event {
+        GameEvent::Move { .. } => GameEventKind::Move,
+        GameEvent::DrawOffered { .. } => GameEventKind::DrawOffered,
+        GameEvent::DrawDeclined { .. } => GameEventKind::DrawDeclined,
+        GameEvent::DrawAccepted { .. } => GameEventKind::DrawAccepted,
+        GameEvent::TakebackOffered { .. } => GameEventKind::TakebackOffered,
+        GameEvent::TakebackAccepted { .. } => GameEventKind::TakebackAccepted,
+        GameEvent::TakebackDeclined { .. } => GameEventKind::TakebackDeclined,
+        GameEvent::ClockSet { .. } => GameEventKind::ClockSet,
+        GameEvent::Ended { .. } => GameEventKind::Ended,
+    }
+}
+
+// `event` with a queen added, when it is a move to the last rank that names
+// no promotion piece: `e7e8` becomes `e7e8q` and `exd8+` becomes `exd8=Q+`
+fn queen_promotion(event: &GameEvent) -> Option<GameEvent> {
+    let GameEvent::Move { notation, clock_ms } = event else {
+        return None;
+    };
+    let bytes = notation.as_bytes();
+    let is_file = |b: u8| (b'a'..=b'h').contains(&b);
+    let is_last_rank = |b: u8| b == b'1' || b == b'8';
+
+    let queened = if bytes.len() == 4
+        && is_file(bytes[0])
+        && is_file(bytes[2])
+        && matches!(bytes[1], b'2' | b'7')
+        && is_last_rank(bytes[3])
+    {
+        format!("{}q", notation)
+    } else {
+        let body = notation.trim_end_matches(['+', '#']);
+        let last = *body.as_bytes().last()?;
+        if body.contains('=') || !is_file(body.as_bytes()[0]) || !is_last_rank(last) {
+            return None;
+        }
+        format!("{}=Q{}", body, &notation[body.len()..])
+    };
+    Some(GameEvent::Move { notation: queened, clock_ms: *clock_ms })
+}
+
+fn stored_event(row: &game_event::Model) -> Result<GameEvent, ApiError> {
+    serde_json::from_value(row.payload.clone())
+        .map_err(|err| ApiError::Internal(format!("Event {} of game {} is unreadable: {}", row.seq, row.game_id, err)))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Value, ApiError> {
+    serde_json::to_value(value).map_err(|err| ApiError::Internal(err.to_string()))
+}
+
+/// The game row as the log state has it; tags stored with the moves stay.
+fn projected(game: game::Model, state: &GameLogState) -> game::ActiveModel {
+    let mut pgn = game.pgn.clone();
+    set_moves(&mut pgn, &state.moves);
+    let mut row = game.into_active_model();
+    row.fen = Set(state.fen.clone());
+    row.pgn = Set(pgn);
+    if state.result != GameResult::InProgress {
+        row.result = Set(Some(ResultSide::from(state.result)));
+    }
+    row.updated_at = Set(Utc::now().fixed_offset());
+    row
+}
+
+fn set_moves(pgn: &mut Value, moves: &[String]) {
+    match pgn.as_object_mut() {
+        Some(fields) => {
+            fields.insert("moves".to_string(), moves.into());
+        }
+        None => *pgn = serde_json::json!({ "moves": moves }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db_entity::game::GameVariant;
+    use db_entity::player_rating::{self, RatingCategory};
+    use db_entity::{player_stats, rating_history, webhook_subscription};
+    use sea_orm::{DbBackend, MockDatabase, MockExecResult};
+    use serde_json::json;
+
+    fn game() -> game::Model {
+        let now = Utc::now().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn: json!({}),
+            result: None,
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 300,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    fn play(notation: &str) -> GameEvent {
+        GameEvent::Move { notation: notation.to_string(), clock_ms: None }
+    }
+
+    fn fold(game: &game::Model, events: &[GameEvent]) -> Result<Fold, ApiError> {
+        let mut fold = Fold::new(game)?;
+        for event in events {
+            fold.apply(event)?;
+        }
+        Ok(fold)
+    }
+
+    #[test]
+    fn test_log_rebuilds_moves_offers_and_result() {
+        let game = game();
+        let events = [
+            play("f2f3"),
+            GameEvent::Move { notation: "e5".to_string(), clock_ms: Some(295_000) },
+            GameEvent::TakebackOffered { by: Side::Black },
+            GameEvent::TakebackAccepted { by: Side::White },
+            play("e7e5"),
+            GameEvent::DrawOffered { by: Side::White },
+            play("g4"),
+            play("Qh4"),
+        ];
+        let state = fold(&game, &events).unwrap().state;
+        assert_eq!(state.seq, 8);
+        assert_eq!(state.moves, ["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(state.black_ms, Some(295_000));
+        assert_eq!(state.white_ms, Some(300_000));
+        // White's own offer stood until Black moved
+        assert_eq!(state.draw_offer, None);
+        assert_eq!(state.result, GameResult::BlackWin);
+        assert_eq!(state.termination.as_deref(), Some("checkmate"));
+
+        // Folding the same log again gives the same state
+        assert_eq!(fold(&game, &events).unwrap().state, state);
+    }
+
+    #[test]
+    fn test_logged_moves_leave_out_moves_taken_back() {
+        let timed = |notation: &str, clock_ms| GameEvent::Move { notation: notation.to_string(), clock_ms: Some(clock_ms) };
+        let events = [
+            timed("e2e4", 296_000),
+            timed("e7e5", 290_000),
+            GameEvent::TakebackOffered { by: Side::Black },
+            GameEvent::TakebackAccepted { by: Side::White },
+            timed("c7c5", 281_000),
+            play("g1f3"),
+        ];
+        let moves = replay_moves(&game(), &events).unwrap();
+        let summary: Vec<_> = moves
+            .iter()
+            .map(|m| (m.ply, m.side, m.notation.as_str(), m.clock_before_ms, m.clock_after_ms))
+            .collect();
+        assert_eq!(summary, [
+            (1, Side::White, "e4", Some(300_000), Some(296_000)),
+            (2, Side::Black, "c5", Some(290_000), Some(281_000)),
+            (3, Side::White, "Nf3", Some(296_000), None),
+        ]);
+        assert_eq!(moves[0].material.white.points(), 39);
+    }
+
+    #[test]
+    fn test_events_the_state_does_not_allow_are_rejected() {
+        let game = game();
+        assert!(fold(&game, &[play("e2e5")]).is_err());
+        assert!(fold(&game, &[GameEvent::TakebackOffered { by: Side::White }]).is_err());
+        assert!(fold(&game, &[GameEvent::DrawOffered { by: Side::White }, GameEvent::DrawAccepted { by: Side::White }]).is_err());
+
+        let agreed = fold(&game, &[GameEvent::DrawOffered { by: Side::White }, GameEvent::DrawAccepted { by: Side::Black }]).unwrap();
+        assert_eq!(agreed.state.result, GameResult::Draw);
+        let mut over = agreed;
+        assert!(over.apply(&play("e2e4")).is_err());
+        assert_eq!(over.state.seq, 2);
+    }
+
+    #[test]
+    fn test_players_act_only_for_their_side() {
+        let game = game();
+        let fold = Fold::new(&game).unwrap();
+        let resign = |result| GameEvent::Ended { result, termination: "resignation".to_string() };
+
+        assert!(check_actor(&game, &fold, game.white_player, &play("e2e4")).is_ok());
+        assert!(check_actor(&game, &fold, game.black_player, &play("e7e5")).is_err());
+        assert!(check_actor(&game, &fold, Uuid::new_v4(), &play("e2e4")).is_err());
+        assert!(check_actor(&game, &fold, game.black_player, &GameEvent::DrawOffered { by: Side::White }).is_err());
+        assert!(check_actor(&game, &fold, game.black_player, &resign(GameResult::WhiteWin)).is_ok());
+        assert!(check_actor(&game, &fold, game.black_player, &resign(GameResult::BlackWin)).is_err());
+        assert!(check_actor(&game, &fold, game.white_player, &GameEvent::ClockSet { white_ms: 1, black_ms: 1 }).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_restores_the_board() {
+        let game = game();
+        let state = fold(&game, &[play("e2e4"), play("e7e5"), play("g1f3")]).unwrap().state;
+        let snapshot = game_snapshot::Model {
+            game_id: game.id,
+            seq: state.seq,
+            state: serde_json::to_value(&state).unwrap(),
+            created_at: Utc::now().fixed_offset(),
+        };
+
+        let mut restored = Fold::restore(&game, &snapshot).unwrap();
+        assert_eq!(restored.state, state);
+        restored.apply(&play("b8c6")).unwrap();
+        assert_eq!(restored.state.moves.last().map(String::as_str), Some("Nc6"));
+    }
+
+    #[test]
+    fn test_queen_promotion_completes_bare_promotions_only() {
+        let queened = |notation| match queen_promotion(&play(notation)) {
+            Some(GameEvent::Move { notation, .. }) => Some(notation),
+            _ => None,
+        };
+
+        assert_eq!(queened("e7e8").as_deref(), Some("e7e8q"));
+        assert_eq!(queened("b2a1").as_deref(), Some("b2a1q"));
+        assert_eq!(queened("e8").as_deref(), Some("e8=Q"));
+        assert_eq!(queened("exd8+").as_deref(), Some("exd8=Q+"));
+        assert_eq!(queened("e7e8n"), None);
+        assert_eq!(queened("e8=N"), None);
+        assert_eq!(queened("Re8"), None);
+        assert_eq!(queened("e2e4"), None);
+        assert_eq!(queened("O-O"), None);
+        assert!(queen_promotion(&GameEvent::DrawOffered { by: Side::White }).is_none());
+    }
+
+    /// The statements Black resigning `game`, with an empty log, takes,
+    /// both players having neither ratings nor stats yet
+    async fn resign(game: &game::Model) -> String {
+        let mut finished = game.clone();
+        finished.result = Some(ResultSide::WhiteWins);
+        let now = Utc::now().fixed_offset();
+        let rating_of = |player_id| player_rating::Model {
+            player_id,
+            category: RatingCategory::Blitz,
+            rating: 1500,
+            rating_deviation: 350.0,
+            volatility: 0.06,
+            games_played: 1,
+            updated_at: now,
+            last_rated_at: Some(now),
+            rd_updated_at: now,
+        };
+        let point_of = |player_id| rating_history::Model {
+            id: Uuid::new_v4(),
+            player_id,
+            category: RatingCategory::Blitz,
+            game_id: Some(game.id),
+            rating: 1500,
+            rating_deviation: 350.0,
+            volatility: Some(0.06),
+            recorded_at: now,
+        };
+        let stats_of = |player_id| player_stats::Model {
+            player_id,
+            stats: json!({}),
+            games_counted: 1,
+            last_game_id: Some(game.id),
+            updated_at: now,
+        };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            // The locked game and its empty log
+            .append_query_results([vec![game.clone()]])
+            .append_query_results([Vec::<game_snapshot::Model>::new()])
+            .append_query_results([Vec::<game_event::Model>::new()])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .append_query_results([Vec::<webhook_subscription::Model>::new()])
+            .append_query_results([vec![finished]])
+            // Ratings going into the game, then each player's new rating
+            .append_query_results([Vec::<player_rating::Model>::new(), Vec::new()])
+            .append_query_results([Vec::<player_rating::Model>::new(), vec![rating_of(game.white_player)]])
+            .append_query_results([vec![point_of(game.white_player)]])
+            .append_query_results([Vec::<player_rating::Model>::new(), vec![rating_of(game.black_player)]])
+            .append_query_results([vec![point_of(game.black_player)]])
+            .append_query_results([Vec::<player_stats::Model>::new(), vec![stats_of(game.white_player)]])
+            .append_query_results([Vec::<player_stats::Model>::new(), vec![stats_of(game.black_player)]])
+            .into_connection();
+
+        let resigned = GameEvent::Ended { result: GameResult::WhiteWin, termination: "resignation".to_string() };
+        GameEventService::append(&db, game.id, Some(game.black_player), resigned).await.unwrap();
+        format!("{:?}", db.into_transaction_log())
+    }
+
+    #[tokio::test]
+    async fn test_finishing_a_game_counts_it_in_the_players_stats() {
+        let log = resign(&game()).await;
+        assert_eq!(log.matches("INSERT INTO \\\"smdb\\\".\\\"player_stats\\\"").count(), 2, "{}", log);
+        let total = |wins, losses| format!(r#""total": Object {{"draws": Number(0), "losses": Number({}), "wins": Number({})}}"#, losses, wins);
+        assert!(log.contains(&total(1, 0)) && log.contains(&total(0, 1)), "{}", log);
+    }
+
+    #[tokio::test]
+    async fn test_finishing_a_rated_game_records_both_players_ratings() {
+        let game = game();
+        let log = resign(&game).await;
+        assert_eq!(log.matches("INSERT INTO \\\"smdb\\\".\\\"rating_history\\\"").count(), 2, "{}", log);
+        // Five minutes a side is blitz; White gained what Black lost
+        assert!(log.contains("String(Some(\"blitz\"))"), "{}", log);
+        assert!(log.contains("Int(Some(1662))") && log.contains("Int(Some(1338))"), "{}", log);
+    }
+}
@@ -1,18 +1,24 @@
-use db_entity::{game, prelude::Game};
+use db_entity::{club_member, game, game_archive, prelude::{Game, GameArchive}};
 use sea_orm::{
-    ColumnTrait, DbErr, EntityTrait, Order, QueryFilter,
+    sea_query::Query, ColumnTrait, DbErr, EntityTrait, Order, QueryFilter,
     QueryOrder, QuerySelect,
 };
 use sea_orm::{Condition, DatabaseConnection};
 use uuid::Uuid;
 use chrono::{DateTime, Utc, TimeZone};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
-use dto::games::GameStatus;
+use dto::games::{GameOdds, GameStatus};
+use error::error::ApiError;
+
+use crate::game_archive::restored;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub struct GameService;
 
 impl GameService {
-    /// List games with keyset pagination.
+    /// List games with keyset pagination, including finished games that were
+    /// moved to the archive.
     /// 
     /// # Arguments
     /// * `db` - Database connection
@@ -20,6 +26,7 @@ impl GameService {
     /// * `limit` - Number of items to return
     /// * `player_id` - Optional player ID filter (checks both white and black players)
     /// * `status` - Optional status filter (currently maps to result being not null for finished games, or specific status if column exists)
+    /// * `club_id` - Optional club filter: games with a member of the club in either seat, e.g. the club's lobby
     /// 
     /// Note: The current schema uses `result` to determine if a game is finished. 
     /// Active games might have `result` as NULL (after our migration).
@@ -29,83 +36,61 @@ impl GameService {
         limit: u64,
         player_id: Option<Uuid>,
         status: Option<GameStatus>,
+        club_id: Option<Uuid>,
     ) -> Result<(Vec<game::Model>, Option<String>), DbErr> {
-        let mut query = Game::find();
+        let after = cursor.and_then(|cursor_str| Self::decode_cursor(&cursor_str).ok());
 
-        // 1. Apply Filtering
-        if let Some(pid) = player_id {
-            // Filter by player (white OR black)
-            // effective union of indexes logic would be nice, but OR is simpler to write here.
-            // "idx_games_white_player_created_at_id" and "idx_games_black_player_created_at_id"
-            // Postgres creates a BitmapOr for these two indexes usually.
-            let condition = Condition::any()
-                .add(game::Column::WhitePlayer.eq(pid))
-                .add(game::Column::BlackPlayer.eq(pid));
-            query = query.filter(condition);
-        }
-
-        if let Some(s) = status {
-            match s {
-                GameStatus::Waiting | GameStatus::InProgress => {
-                     // Active games: result is NULL
-                     query = query.filter(game::Column::Result.is_null());
-                },
-                GameStatus::Completed | GameStatus::Aborted => {
-                    // Finished games: result is NOT NULL
-                    // Note: "Aborted" vs "Completed" might need distinguishing via ResultSide if we had it, 
-                    // but for now we just check if it has a result.
-                    query = query.filter(game::Column::Result.is_not_null());
-                }
-            }
-        }
-
-        // 2. Apply Cursor (Keyset Pagination)
         // Sort by created_at DESC, id DESC
-        query = query
+        // Fetch limit + 1 to check if there is a next page
+        let mut games = Game::find()
+            .filter(history_condition(
+                [
+                    game::Column::WhitePlayer,
+                    game::Column::BlackPlayer,
+                    game::Column::Result,
+                    game::Column::CreatedAt,
+                    game::Column::Id,
+                ],
+                player_id,
+                status.as_ref(),
+                club_id,
+                after,
+            ))
             .order_by(game::Column::CreatedAt, Order::Desc)
-            .order_by(game::Column::Id, Order::Desc);
+            .order_by(game::Column::Id, Order::Desc)
+            .limit(limit + 1)
+            .all(db)
+            .await?;
 
-        if let Some(cursor_str) = cursor {
-            if let Ok((last_created_at, last_id)) = Self::decode_cursor(&cursor_str) {
-                // created_at < last_created_at OR (created_at = last_created_at AND id < last_id)
-                // SeaORM tuple comparison: (col1, col2) < (val1, val2)
-                // query = query.filter(
-                //    Condition::any()
-                //        .add(game::Column::CreatedAt.lt(last_created_at))
-                //        .add(
-                //            Condition::all()
-                //                .add(game::Column::CreatedAt.eq(last_created_at))
-                //                .add(game::Column::Id.lt(last_id))
-                //        )
-                // );
-                // Actually, SeaORM supports tuple comparison conveniently? 
-                // Not directly in the builder API widely in all versions, but the composite condition above is correct for (A, B) < (a, b) logic.
-                // However, tuple comparison `(A, B) < (a, b)` logic is standard SQL but SeaORM DSL is explicit.
-                
-                // Constructing: (created_at, id) < (last_created_at, last_id)
-                // Equivalent to: created_at < last_created_at OR (created_at = last_created_at AND id < last_id) (for DESC, DESC)
-                // WAIT! For DESC sort, "next page" means values SMALLER than cursor?
-                // Yes. Sorting DESC means newest first. Cursor is at some point. We want older stuff.
-                // So we want `created_at < cursor.created_at`.
-                // If created_at == cursor.created_at, then `id < cursor.id` (assuming ID also DESC).
-                
-                let condition = Condition::any()
-                    .add(game::Column::CreatedAt.lt(last_created_at))
-                    .add(
-                        Condition::all()
-                            .add(game::Column::CreatedAt.eq(last_created_at))
-                            .add(game::Column::Id.lt(last_id))
-                    );
-                
-                query = query.filter(condition);
+        // Archived games are all finished, so active games never need the archive.
+        // Both pages are in the same order, so merging them gives the next page
+        // over both tables.
+        if !matches!(status, Some(GameStatus::Waiting | GameStatus::InProgress)) {
+            let archived = GameArchive::find()
+                .filter(history_condition(
+                    [
+                        game_archive::Column::WhitePlayer,
+                        game_archive::Column::BlackPlayer,
+                        game_archive::Column::Result,
+                        game_archive::Column::CreatedAt,
+                        game_archive::Column::Id,
+                    ],
+                    player_id,
+                    status.as_ref(),
+                    club_id,
+                    after,
+                ))
+                .order_by(game_archive::Column::CreatedAt, Order::Desc)
+                .order_by(game_archive::Column::Id, Order::Desc)
+                .limit(limit + 1)
+                .all(db)
+                .await?;
+            if !archived.is_empty() {
+                games.extend(archived.into_iter().map(restored));
+                games.sort_by_key(|game| std::cmp::Reverse((game.created_at, game.id)));
             }
         }
 
-        // 3. Limit and Execution
-        // Fetch limit + 1 to check if there is a next page
-        let results = query.limit(limit + 1).all(db).await?;
-
-        let mut games = results;
         let mut next_cursor: Option<String> = None;
 
         if games.len() as u64 > limit {
@@ -152,6 +137,86 @@ impl GameService {
     }
 }
 
+/// Filter of a game history page, for the game table or the archive. The
+/// columns are white player, black player, result, creation time and id.
+///
+/// Note: The current schema uses `result` to determine if a game is finished.
+fn history_condition<C: ColumnTrait>(
+    [white_player, black_player, result, created_at, id]: [C; 5],
+    player_id: Option<Uuid>,
+    status: Option<&GameStatus>,
+    club_id: Option<Uuid>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+) -> Condition {
+    let mut condition = Condition::all();
+
+    if let Some(pid) = player_id {
+        // Filter by player (white OR black)
+        // "idx_games_white_player_created_at_id" and "idx_games_black_player_created_at_id"
+        // Postgres creates a BitmapOr for these two indexes usually.
+        condition = condition.add(Condition::any().add(white_player.eq(pid)).add(black_player.eq(pid)));
+    }
+
+    if let Some(club_id) = club_id {
+        let members = Query::select()
+            .column(club_member::Column::PlayerId)
+            .from(club_member::Entity)
+            .and_where(club_member::Column::ClubId.eq(club_id))
+            .to_owned();
+        condition = condition.add(
+            Condition::any()
+                .add(white_player.in_subquery(members.clone()))
+                .add(black_player.in_subquery(members)),
+        );
+    }
+
+    match status {
+        // Active games: result is NULL
+        Some(GameStatus::Waiting | GameStatus::InProgress) => condition = condition.add(result.is_null()),
+        // Finished games: result is NOT NULL
+        // Note: "Aborted" vs "Completed" might need distinguishing via ResultSide if we had it,
+        // but for now we just check if it has a result.
+        Some(GameStatus::Completed | GameStatus::Aborted) => condition = condition.add(result.is_not_null()),
+        None => {}
+    }
+
+    if let Some((last_created_at, last_id)) = after {
+        // Keyset pagination: (created_at, id) < (last_created_at, last_id)
+        // Sorting DESC means newest first, so the next page holds older games:
+        // created_at < last_created_at OR (created_at = last_created_at AND id < last_id)
+        condition = condition.add(
+            Condition::any()
+                .add(created_at.lt(last_created_at))
+                .add(Condition::all().add(created_at.eq(last_created_at)).add(id.lt(last_id))),
+        );
+    }
+
+    condition
+}
+
+/// Starting position of a new game: the standard one, or the handicap
+/// position of an odds game.
+pub fn start_fen(odds: Option<&GameOdds>) -> Result<String, ApiError> {
+    let Some(odds) = odds else {
+        return Ok(STARTING_FEN.to_string());
+    };
+    // `GameOdds` mirrors `chess::Odds` field for field, so they convert through serde
+    let odds: chess::Odds = serde_json::to_value(odds)
+        .and_then(serde_json::from_value)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid odds: {}", err)))?;
+    odds.start_fen().map_err(|err| ApiError::BadRequest(err.to_string()))
+}
+
+/// Handicap of an odds game, as stored on the game record.
+pub fn odds_of(game: &game::Model) -> Option<GameOdds> {
+    game.odds.clone().and_then(|odds| serde_json::from_value(odds).ok())
+}
+
+/// Odds games and imported games stay out of the rating pools.
+pub fn is_rated(game: &game::Model) -> bool {
+    game.odds.is_none() && !game.is_imported
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,8 +257,11 @@ mod tests {
                     updated_at: Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
                     is_imported: false,
                     original_pgn: None,
+                    odds: None,
                 }],
             ])
+            // Second query reads the archive
+            .append_query_results([Vec::<game_archive::Model>::new()])
             .into_connection();
             
         let player_id = Uuid::new_v4();
@@ -203,14 +271,16 @@ mod tests {
             None,
             10,
             Some(player_id),
+            None,
             None
         ).await;
         
         // Get transaction log to verify SQL
         let transaction_log = db.into_transaction_log();
         
-        // We expect one query
-        assert_eq!(transaction_log.len(), 1);
+        // We expect one query for the game table and one for the archive
+        assert_eq!(transaction_log.len(), 2);
+        assert!(format!("{:?}", transaction_log[1]).contains(r#"\"game_archive\".\"white_player\" = $1"#));
         
         let log = &transaction_log[0];
         let log_str = format!("{:?}", log);
@@ -247,7 +317,9 @@ mod tests {
                     updated_at: Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
                     is_imported: false,
                     original_pgn: None,
+                    odds: None,
             }]])
+            .append_query_results([Vec::<game_archive::Model>::new()])
             .into_connection();
             
         let _result = GameService::list_games(
@@ -255,6 +327,7 @@ mod tests {
             Some(cursor),
             10,
             None,
+            None,
             None
         ).await;
         
@@ -268,4 +341,43 @@ mod tests {
         assert!(log_str.contains(r#"\"game\".\"created_at\" = $2"#));
         assert!(log_str.contains(r#"\"game\".\"id\" < $3"#));
     }
+
+    #[test]
+    fn test_odds_games_start_from_the_handicap_position_unrated() {
+        assert_eq!(start_fen(None).unwrap(), STARTING_FEN);
+
+        let odds = GameOdds {
+            giver: dto::games::OddsGiver::White,
+            removed: vec![dto::games::OddsPiece::Knight],
+            extra_moves: 0,
+        };
+        assert_eq!(
+            start_fen(Some(&odds)).unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1"
+        );
+        assert!(start_fen(Some(&GameOdds { removed: Vec::new(), ..odds.clone() })).is_err());
+
+        let now = Utc::now().fixed_offset();
+        let mut game = game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: STARTING_FEN.to_string(),
+            pgn: serde_json::json!({}),
+            result: None,
+            variant: db_entity::game::GameVariant::Standard,
+            started_at: now,
+            duration_sec: 600,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        };
+        assert!(is_rated(&game));
+
+        game.odds = Some(serde_json::to_value(&odds).unwrap());
+        assert!(!is_rated(&game));
+        assert_eq!(odds_of(&game), Some(odds));
+    }
 }
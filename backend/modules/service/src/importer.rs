@@ -0,0 +1,786 @@
+use chess::{PgnGameResult, PgnHeaders, ValidatedGame};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use db_entity::{game, game::GameVariant, game::ResultSide, game_import, import_job, import_quarantine};
+use db_entity::import_quarantine::QuarantineStatus;
+use dto::games::{ImportAccountRequest, ImportAccountResponse, ImportFailure, ImportJobDisplay, ImportSource};
+use error::error::ApiError;
+use reqwest::{header, Client, RequestBuilder, StatusCode};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::moderation::MAX_QUEUE_PAGE;
+use crate::positions::PositionIndexService;
+
+pub const LICHESS_API_URL: &str = "https://lichess.org";
+pub const CHESS_COM_API_URL: &str = "https://api.chess.com";
+
+/// Games fetched when the request does not say how many
+pub const DEFAULT_IMPORT_GAMES: u32 = 100;
+
+/// Longest name kept for the players of an imported game
+const MAX_NAME_LEN: usize = 64;
+
+// Chess.com refuses requests without a user agent
+const USER_AGENT: &str = "StarkMate game importer";
+
+/// HTTP client for the public game APIs of Lichess and Chess.com.
+#[derive(Debug, Clone)]
+pub struct GameFetcher {
+    client: Client,
+}
+
+impl GameFetcher {
+    pub fn new(timeout: Duration) -> Self {
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build the HTTP client");
+        GameFetcher { client }
+    }
+
+    /// The most recent `max` games of `username`, newest first, one PGN each.
+    pub async fn fetch(
+        &self,
+        source: ImportSource,
+        username: &str,
+        token: Option<&str>,
+        max: u32,
+    ) -> Result<Vec<String>, ApiError> {
+        match source {
+            ImportSource::Lichess => self.fetch_lichess(username, token, max).await,
+            ImportSource::ChessCom => self.fetch_chess_com(username, max).await,
+        }
+    }
+
+    async fn fetch_lichess(&self, username: &str, token: Option<&str>, max: u32) -> Result<Vec<String>, ApiError> {
+        let url = format!(
+            "{}/api/games/user/{}?max={}&clocks=false&evals=false",
+            LICHESS_API_URL, username, max
+        );
+        let mut request = self.client.get(url).header(header::ACCEPT, "application/x-chess-pgn");
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let body = get_text(request, ImportSource::Lichess).await?;
+        Ok(split_pgn(&body))
+    }
+
+    async fn fetch_chess_com(&self, username: &str, max: u32) -> Result<Vec<String>, ApiError> {
+        let url = format!(
+            "{}/pub/player/{}/games/archives",
+            CHESS_COM_API_URL,
+            username.to_lowercase()
+        );
+        let body = get_text(self.client.get(url), ImportSource::ChessCom).await?;
+        // Monthly archive URLs, oldest first
+        let archives: Vec<String> = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|list| list.get("archives").cloned())
+            .and_then(|archives| serde_json::from_value(archives).ok())
+            .ok_or_else(|| ApiError::BadGateway("Unexpected archive list from Chess.com".to_string()))?;
+
+        let max = max as usize;
+        let mut games = Vec::new();
+        for archive in archives.iter().rev() {
+            // Only follow links back to the API itself
+            if !archive.starts_with(CHESS_COM_API_URL) {
+                continue;
+            }
+            let body = get_text(self.client.get(format!("{}/pgn", archive)), ImportSource::ChessCom).await?;
+            // Games inside an archive are oldest first as well
+            games.extend(split_pgn(&body).into_iter().rev());
+            if games.len() >= max {
+                break;
+            }
+        }
+        games.truncate(max);
+        Ok(games)
+    }
+}
+
+async fn get_text(request: RequestBuilder, source: ImportSource) -> Result<String, ApiError> {
+    let site = site_name(source);
+    let response = request
+        .send()
+        .await
+        .map_err(|err| ApiError::BadGateway(format!("{} could not be reached: {}", site, err)))?;
+
+    match response.status() {
+        StatusCode::NOT_FOUND => Err(ApiError::NotFound(format!("{} account", site))),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            Err(ApiError::BadRequest(format!("{} rejected the access token", site)))
+        }
+        StatusCode::TOO_MANY_REQUESTS => Err(ApiError::TooManyRequests(format!(
+            "{} is limiting requests, try again in a minute",
+            site
+        ))),
+        status if !status.is_success() => Err(ApiError::BadGateway(format!("{} answered {}", site, status))),
+        _ => response
+            .text()
+            .await
+            .map_err(|err| ApiError::BadGateway(format!("Failed to read the answer of {}: {}", site, err))),
+    }
+}
+
+fn site_name(source: ImportSource) -> &'static str {
+    match source {
+        ImportSource::Lichess => "Lichess",
+        ImportSource::ChessCom => "Chess.com",
+    }
+}
+
+/// Split a file of several PGN games into one string per game. A tag line
+/// after movetext starts the next game.
+pub fn split_pgn(text: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut current = String::new();
+    let mut in_movetext = false;
+
+    // A byte order mark would hide the first tag line
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            if in_movetext {
+                games.push(std::mem::take(&mut current));
+                in_movetext = false;
+            }
+        } else if !trimmed.is_empty() {
+            in_movetext = true;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    games.push(current);
+
+    games
+        .into_iter()
+        .map(|game| game.trim().to_string())
+        .filter(|game| !game.is_empty())
+        .collect()
+}
+
+/// Id and address of the game on the source site, read from its tags:
+/// `GameId` or `Site` on Lichess, `Link` on Chess.com.
+pub fn external_ref(source: ImportSource, headers: &PgnHeaders) -> Option<(String, String)> {
+    let url = match source {
+        ImportSource::Lichess => headers.site.clone(),
+        ImportSource::ChessCom => headers.other.get("Link").cloned(),
+    }?;
+    let from_tag = match source {
+        ImportSource::Lichess => headers.other.get("GameId").cloned(),
+        ImportSource::ChessCom => None,
+    };
+    let id = from_tag
+        .or_else(|| url.trim_end_matches('/').rsplit('/').next().map(str::to_string))
+        .filter(|id| !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric()))?;
+    Some((id, url))
+}
+
+struct Candidate {
+    external_id: String,
+    url: String,
+    game: ValidatedGame,
+    pgn: String,
+}
+
+pub struct ImportService;
+
+impl ImportService {
+    /// Fetch the recent games of an account on another site and store the
+    /// ones `player_id` has not imported yet.
+    pub async fn import_account(
+        db: &DatabaseConnection,
+        fetcher: &GameFetcher,
+        player_id: Uuid,
+        request: &ImportAccountRequest,
+    ) -> Result<ImportAccountResponse, ApiError> {
+        let max = request.max_games.unwrap_or(DEFAULT_IMPORT_GAMES);
+        let pgns = fetcher
+            .fetch(request.source, &request.username, request.token.as_deref(), max)
+            .await?;
+        Self::store(db, player_id, request.source, &request.username, pgns).await
+    }
+
+    /// Parse, dedupe and store fetched games, recorded as one import job. A
+    /// game that does not parse or replay is reported and quarantined, and
+    /// does not stop the others.
+    pub async fn store(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        source: ImportSource,
+        username: &str,
+        pgns: Vec<String>,
+    ) -> Result<ImportAccountResponse, ApiError> {
+        let job_id = Uuid::new_v4();
+        let now = Utc::now().fixed_offset();
+        let fetched = pgns.len();
+        let mut failed = Vec::new();
+        let mut quarantined = Vec::new();
+        let mut candidates = Vec::new();
+        for pgn in pgns {
+            match prepare(source, &pgn) {
+                Ok(candidate) => candidates.push(candidate),
+                Err(mut failure) => {
+                    let id = Uuid::new_v4();
+                    failure.quarantine_id = Some(id);
+                    quarantined.push(import_quarantine::ActiveModel {
+                        id: Set(id),
+                        job_id: Set(job_id),
+                        player_id: Set(player_id),
+                        external_id: Set(failure.external_id.clone()),
+                        pgn: Set(pgn),
+                        error: Set(failure.error.clone()),
+                        status: Set(QuarantineStatus::Pending),
+                        attempts: Set(0),
+                        game_id: Set(None),
+                        created_at: Set(now),
+                        updated_at: Set(now),
+                    });
+                    failed.push(failure);
+                }
+            }
+        }
+
+        let mut seen: HashSet<String> = if candidates.is_empty() {
+            HashSet::new()
+        } else {
+            game_import::Entity::find()
+                .filter(game_import::Column::PlayerId.eq(player_id))
+                .filter(game_import::Column::Source.eq(game_import::ImportSource::from(source)))
+                .filter(game_import::Column::ExternalId.is_in(candidates.iter().map(|c| c.external_id.clone())))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|row| row.external_id)
+                .collect()
+        };
+
+        let mut imported = Vec::new();
+        let mut duplicates = 0;
+        for candidate in candidates {
+            if !seen.insert(candidate.external_id.clone()) {
+                duplicates += 1;
+                continue;
+            }
+            imported.push(insert(db, player_id, source, username, candidate).await?);
+        }
+
+        import_job::ActiveModel {
+            id: Set(job_id),
+            player_id: Set(player_id),
+            source: Set(source.into()),
+            external_username: Set(username.to_string()),
+            fetched: Set(fetched as i32),
+            imported: Set(imported.len() as i32),
+            duplicates: Set(duplicates as i32),
+            failed: Set(failed.len() as i32),
+            created_at: Set(now),
+        }
+        .insert(db)
+        .await?;
+        if !quarantined.is_empty() {
+            import_quarantine::Entity::insert_many(quarantined)
+                .exec_without_returning(db)
+                .await?;
+        }
+
+        Ok(ImportAccountResponse {
+            source,
+            username: username.to_string(),
+            imported,
+            duplicates,
+            failed,
+            job_id,
+        })
+    }
+
+    /// The player's import jobs, newest first, with what became of their
+    /// quarantined games.
+    pub async fn jobs(db: &DatabaseConnection, player_id: Uuid, limit: u64) -> Result<Vec<ImportJobDisplay>, ApiError> {
+        let jobs = import_job::Entity::find()
+            .filter(import_job::Column::PlayerId.eq(player_id))
+            .order_by(import_job::Column::CreatedAt, Order::Desc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?;
+        let mut counts = quarantine_counts(db, jobs.iter().map(|job| job.id).collect()).await?;
+        Ok(jobs
+            .into_iter()
+            .map(|job| {
+                let job_counts = counts.remove(&job.id).unwrap_or_default();
+                ImportJobDisplay::new(job, job_counts)
+            })
+            .collect())
+    }
+
+    pub async fn job(db: &DatabaseConnection, player_id: Uuid, job_id: Uuid) -> Result<ImportJobDisplay, ApiError> {
+        let job = import_job::Entity::find_by_id(job_id)
+            .filter(import_job::Column::PlayerId.eq(player_id))
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Import job".to_string()))?;
+        let counts = quarantine_counts(db, vec![job.id]).await?.remove(&job.id).unwrap_or_default();
+        Ok(ImportJobDisplay::new(job, counts))
+    }
+
+    /// The player's quarantined games, oldest first.
+    pub async fn quarantine(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        job_id: Option<Uuid>,
+        status: QuarantineStatus,
+        limit: u64,
+    ) -> Result<Vec<import_quarantine::Model>, ApiError> {
+        let mut select = import_quarantine::Entity::find()
+            .filter(import_quarantine::Column::PlayerId.eq(player_id))
+            .filter(import_quarantine::Column::Status.eq(status));
+        if let Some(job_id) = job_id {
+            select = select.filter(import_quarantine::Column::JobId.eq(job_id));
+        }
+        Ok(select
+            .order_by(import_quarantine::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// Try a quarantined game again, from `pgn` when the player corrected it
+    /// and otherwise from the text kept. A failed attempt keeps the text and
+    /// its error; a game that turns out imported already resolves the entry
+    /// without being stored twice.
+    pub async fn retry(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        quarantine_id: Uuid,
+        pgn: Option<String>,
+    ) -> Result<import_quarantine::Model, ApiError> {
+        let entry = pending_entry(db, player_id, quarantine_id).await?;
+        let job = import_job::Entity::find_by_id(entry.job_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Import job".to_string()))?;
+        let source = ImportSource::from(job.source);
+        let pgn = pgn.unwrap_or_else(|| entry.pgn.clone());
+
+        let attempts = entry.attempts + 1;
+        let mut update: import_quarantine::ActiveModel = entry.into();
+        update.pgn = Set(pgn.clone());
+        update.attempts = Set(attempts);
+        update.updated_at = Set(Utc::now().fixed_offset());
+
+        let candidate = match prepare(source, &pgn) {
+            Ok(candidate) => candidate,
+            Err(failure) => {
+                update.external_id = Set(failure.external_id);
+                update.error = Set(failure.error.clone());
+                update.update(db).await?;
+                return Err(ApiError::PgnParseError(failure.error));
+            }
+        };
+
+        let external_id = candidate.external_id.clone();
+        let imported_before = game_import::Entity::find()
+            .filter(game_import::Column::PlayerId.eq(player_id))
+            .filter(game_import::Column::Source.eq(game_import::ImportSource::from(source)))
+            .filter(game_import::Column::ExternalId.eq(&external_id))
+            .one(db)
+            .await?;
+        let game_id = match imported_before {
+            Some(import) => import.game_id,
+            None => insert(db, player_id, source, &job.external_username, candidate).await?,
+        };
+        update.external_id = Set(Some(external_id));
+        update.status = Set(QuarantineStatus::Resolved);
+        update.game_id = Set(Some(game_id));
+        Ok(update.update(db).await?)
+    }
+
+    /// Give up on a quarantined game.
+    pub async fn dismiss(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        quarantine_id: Uuid,
+    ) -> Result<import_quarantine::Model, ApiError> {
+        let mut update: import_quarantine::ActiveModel = pending_entry(db, player_id, quarantine_id).await?.into();
+        update.status = Set(QuarantineStatus::Dismissed);
+        update.updated_at = Set(Utc::now().fixed_offset());
+        Ok(update.update(db).await?)
+    }
+}
+
+async fn pending_entry(
+    db: &DatabaseConnection,
+    player_id: Uuid,
+    quarantine_id: Uuid,
+) -> Result<import_quarantine::Model, ApiError> {
+    let entry = import_quarantine::Entity::find_by_id(quarantine_id)
+        .filter(import_quarantine::Column::PlayerId.eq(player_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Quarantined game".to_string()))?;
+    if entry.status != QuarantineStatus::Pending {
+        return Err(ApiError::BadRequest("The game is no longer in quarantine".to_string()));
+    }
+    Ok(entry)
+}
+
+/// Quarantined games of each job: pending, resolved and dismissed.
+async fn quarantine_counts(
+    db: &DatabaseConnection,
+    job_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, (u64, u64, u64)>, ApiError> {
+    if job_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows: Vec<(Uuid, QuarantineStatus, i64)> = import_quarantine::Entity::find()
+        .select_only()
+        .column(import_quarantine::Column::JobId)
+        .column(import_quarantine::Column::Status)
+        .column_as(Expr::col(import_quarantine::Column::Id).count(), "count")
+        .filter(import_quarantine::Column::JobId.is_in(job_ids))
+        .group_by(import_quarantine::Column::JobId)
+        .group_by(import_quarantine::Column::Status)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut counts: HashMap<Uuid, (u64, u64, u64)> = HashMap::new();
+    for (job_id, status, count) in rows {
+        let job_counts = counts.entry(job_id).or_default();
+        let slot = match status {
+            QuarantineStatus::Pending => &mut job_counts.0,
+            QuarantineStatus::Resolved => &mut job_counts.1,
+            QuarantineStatus::Dismissed => &mut job_counts.2,
+        };
+        *slot = count.max(0) as u64;
+    }
+    Ok(counts)
+}
+
+fn prepare(source: ImportSource, pgn: &str) -> Result<Candidate, ImportFailure> {
+    let parsed = chess::parse_pgn(pgn).map_err(|err| ImportFailure {
+        external_id: None,
+        error: err.to_string(),
+        quarantine_id: None,
+    })?;
+    let (external_id, url) = external_ref(source, &parsed.headers).ok_or_else(|| ImportFailure {
+        external_id: None,
+        error: format!("PGN does not link to a game on {}", site_name(source)),
+        quarantine_id: None,
+    })?;
+    let game = chess::validate_game(&parsed).map_err(|err| ImportFailure {
+        external_id: Some(external_id.clone()),
+        error: err.to_string(),
+        quarantine_id: None,
+    })?;
+    Ok(Candidate {
+        external_id,
+        url,
+        game,
+        pgn: pgn.to_string(),
+    })
+}
+
+async fn insert(
+    db: &DatabaseConnection,
+    player_id: Uuid,
+    source: ImportSource,
+    username: &str,
+    candidate: Candidate,
+) -> Result<Uuid, ApiError> {
+    let now = Utc::now().fixed_offset();
+    let headers = &candidate.game.headers;
+    let game_id = Uuid::new_v4();
+
+    let txn = db.begin().await?;
+    let game = game::ActiveModel {
+        id: Set(game_id),
+        // Opponents on the other site have no account here, so both seats
+        // hold the importer; the names live in the tags and in game_import
+        white_player: Set(player_id),
+        black_player: Set(player_id),
+        fen: Set(candidate.game.final_fen.clone()),
+        pgn: Set(json!({ "headers": tags_of(headers), "moves": candidate.game.moves })),
+        result: Set(Some(result_side(&headers.result))),
+        variant: Set(GameVariant::Standard),
+        started_at: Set(started_at(headers).unwrap_or(now)),
+        duration_sec: Set(0),
+        created_at: Set(now),
+        updated_at: Set(now),
+        is_imported: Set(true),
+        original_pgn: Set(Some(candidate.pgn)),
+        odds: Set(None),
+    }
+    .insert(&txn)
+    .await?;
+    PositionIndexService::index_game(&txn, &game).await?;
+
+    game_import::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        game_id: Set(game_id),
+        player_id: Set(player_id),
+        source: Set(source.into()),
+        external_id: Set(candidate.external_id),
+        external_url: Set(Some(candidate.url)),
+        external_username: Set(username.to_string()),
+        white_name: Set(headers.white.chars().take(MAX_NAME_LEN).collect()),
+        black_name: Set(headers.black.chars().take(MAX_NAME_LEN).collect()),
+        imported_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    Ok(game_id)
+}
+
+/// Every tag of the game, as stored under `pgn.headers`.
+fn tags_of(headers: &PgnHeaders) -> Map<String, Value> {
+    let mut tags: Map<String, Value> = headers
+        .other
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+        .collect();
+    let roster = [
+        ("Event", headers.event.clone()),
+        ("Site", headers.site.clone()),
+        ("Date", headers.date.clone()),
+        ("Round", headers.round.clone()),
+        ("White", Some(headers.white.clone())),
+        ("Black", Some(headers.black.clone())),
+        ("Result", Some(headers.result.to_pgn_string().to_string())),
+    ];
+    for (key, value) in roster {
+        if let Some(value) = value {
+            tags.insert(key.to_string(), Value::String(value));
+        }
+    }
+    tags
+}
+
+fn result_side(result: &PgnGameResult) -> ResultSide {
+    match result {
+        PgnGameResult::WhiteWins => ResultSide::WhiteWins,
+        PgnGameResult::BlackWins => ResultSide::BlackWins,
+        PgnGameResult::Draw => ResultSide::Draw,
+        PgnGameResult::Ongoing => ResultSide::Ongoing,
+    }
+}
+
+/// Start of the game from `UTCDate`/`UTCTime`, falling back to `Date` and
+/// `StartTime`.
+fn started_at(headers: &PgnHeaders) -> Option<DateTime<FixedOffset>> {
+    let date = headers.other.get("UTCDate").or(headers.date.as_ref())?;
+    let date = NaiveDate::parse_from_str(date, "%Y.%m.%d").ok()?;
+    let time = headers
+        .other
+        .get("UTCTime")
+        .or_else(|| headers.other.get("StartTime"))
+        .and_then(|time| NaiveTime::parse_from_str(time, "%H:%M:%S").ok())
+        .unwrap_or_default();
+    Some(date.and_time(time).and_utc().fixed_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, MockDatabase, MockExecResult};
+
+    const LICHESS_EXPORT: &str = r#"[Event "Rated Blitz game"]
+[Site "https://lichess.org/AbCd1234"]
+[Date "2026.10.01"]
+[White "alice"]
+[Black "bob"]
+[Result "1-0"]
+[UTCDate "2026.10.01"]
+[UTCTime "18:04:05"]
+
+1. e4 { [%eval 0.3] } 1... e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+
+[Event "Rated Bullet game"]
+[Site "https://lichess.org/Zyxw9876"]
+[White "bob"]
+[Black "alice"]
+[Result "*"]
+
+1. d4 d5 *
+"#;
+
+    #[test]
+    fn splits_exports_into_games() {
+        let games = split_pgn(LICHESS_EXPORT);
+        assert_eq!(games.len(), 2);
+        assert!(games[0].starts_with("[Event \"Rated Blitz game\"]"));
+        assert!(games[0].ends_with("4. Qxf7# 1-0"));
+        assert!(games[1].ends_with("1. d4 d5 *"));
+        assert!(split_pgn("\n\n").is_empty());
+
+        let saved_on_windows = format!("\u{feff}{}", LICHESS_EXPORT.replace('\n', "\r\n"));
+        let games = split_pgn(&saved_on_windows);
+        assert_eq!(games.len(), 2);
+        assert!(games[0].starts_with("[Event \"Rated Blitz game\"]"));
+    }
+
+    #[test]
+    fn reads_the_game_on_the_source_site() {
+        let lichess = chess::parse_pgn(&split_pgn(LICHESS_EXPORT)[0]).unwrap();
+        assert_eq!(
+            external_ref(ImportSource::Lichess, &lichess.headers),
+            Some(("AbCd1234".to_string(), "https://lichess.org/AbCd1234".to_string()))
+        );
+        assert_eq!(
+            started_at(&lichess.headers).unwrap().to_rfc3339(),
+            "2026-10-01T18:04:05+00:00"
+        );
+
+        let chess_com = chess::parse_pgn(
+            "[Site \"Chess.com\"]\n[White \"a\"]\n[Black \"b\"]\n[Result \"0-1\"]\n[Link \"https://www.chess.com/game/live/123456789\"]\n\n1. f3 e5 2. g4 Qh4# 0-1",
+        )
+        .unwrap();
+        assert_eq!(
+            external_ref(ImportSource::ChessCom, &chess_com.headers).map(|(id, _)| id),
+            Some("123456789".to_string())
+        );
+        // Chess.com games name the site, not the game, in `Site`
+        assert_eq!(external_ref(ImportSource::Lichess, &chess_com.headers), None);
+    }
+
+    #[tokio::test]
+    async fn skips_imported_games_and_reports_bad_ones() {
+        let player_id = Uuid::new_v4();
+        let existing = game_import::Model {
+            id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            player_id,
+            source: game_import::ImportSource::Lichess,
+            external_id: "AbCd1234".to_string(),
+            external_url: Some("https://lichess.org/AbCd1234".to_string()),
+            external_username: "alice".to_string(),
+            white_name: "alice".to_string(),
+            black_name: "bob".to_string(),
+            imported_at: Utc::now().fixed_offset(),
+        };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![existing]])
+            .append_query_results([vec![job(player_id)]])
+            .append_exec_results([MockExecResult { last_insert_id: 0, rows_affected: 1 }])
+            .into_connection();
+
+        let mut pgns = split_pgn(LICHESS_EXPORT);
+        pgns.truncate(1);
+        pgns.push(pgns[0].clone());
+        pgns.push(BAD_PGN.to_string());
+
+        let summary = ImportService::store(&db, player_id, ImportSource::Lichess, "alice", pgns)
+            .await
+            .unwrap();
+        assert!(summary.imported.is_empty());
+        assert_eq!(summary.duplicates, 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].external_id.as_deref(), Some("Bad00001"));
+        assert!(summary.failed[0].quarantine_id.is_some());
+
+        // The job, then the failed game with its text, are recorded
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 3);
+        let quarantined = format!("{:?}", log[2]);
+        assert!(quarantined.contains("import_quarantine") && quarantined.contains("1. e5 *"));
+    }
+
+    const BAD_PGN: &str = "[White \"a\"]\n[Black \"b\"]\n[Site \"https://lichess.org/Bad00001\"]\n\n1. e5 *";
+
+    fn job(player_id: Uuid) -> import_job::Model {
+        import_job::Model {
+            id: Uuid::new_v4(),
+            player_id,
+            source: game_import::ImportSource::Lichess,
+            external_username: "alice".to_string(),
+            fetched: 3,
+            imported: 0,
+            duplicates: 2,
+            failed: 1,
+            created_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    fn quarantined(job: &import_job::Model) -> import_quarantine::Model {
+        import_quarantine::Model {
+            id: Uuid::new_v4(),
+            job_id: job.id,
+            player_id: job.player_id,
+            external_id: Some("Bad00001".to_string()),
+            pgn: BAD_PGN.to_string(),
+            error: "Illegal move at move 1: 'e5' - Move is not legal in this position".to_string(),
+            status: QuarantineStatus::Pending,
+            attempts: 0,
+            game_id: None,
+            created_at: job.created_at,
+            updated_at: job.created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_keep_failures_and_resolve_games_imported_before() {
+        let player_id = Uuid::new_v4();
+        let job = job(player_id);
+        let entry = quarantined(&job);
+
+        // The text as fetched still fails; the attempt is counted
+        let failed_again = import_quarantine::Model { attempts: 1, ..entry.clone() };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![entry.clone()]])
+            .append_query_results([vec![job.clone()]])
+            .append_query_results([vec![failed_again]])
+            .into_connection();
+        let err = ImportService::retry(&db, player_id, entry.id, None).await.unwrap_err();
+        assert!(matches!(err, ApiError::PgnParseError(ref message) if message.contains("'e5'")));
+
+        // Corrected, it turns out to be a game imported since
+        let imported = game_import::Model {
+            id: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            player_id,
+            source: game_import::ImportSource::Lichess,
+            external_id: "Bad00001".to_string(),
+            external_url: Some("https://lichess.org/Bad00001".to_string()),
+            external_username: "alice".to_string(),
+            white_name: "a".to_string(),
+            black_name: "b".to_string(),
+            imported_at: Utc::now().fixed_offset(),
+        };
+        let resolved = import_quarantine::Model {
+            status: QuarantineStatus::Resolved,
+            attempts: 1,
+            game_id: Some(imported.game_id),
+            ..entry.clone()
+        };
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![entry.clone()]])
+            .append_query_results([vec![job.clone()]])
+            .append_query_results([vec![imported.clone()]])
+            .append_query_results([vec![resolved]])
+            .into_connection();
+        let corrected = BAD_PGN.replace("1. e5", "1. e4");
+        let entry_after = ImportService::retry(&db, player_id, entry.id, Some(corrected)).await.unwrap();
+        assert_eq!(entry_after.status, QuarantineStatus::Resolved);
+        assert_eq!(entry_after.game_id, Some(imported.game_id));
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(!log.contains("INSERT"));
+
+        // Resolved games leave the queue
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![entry_after]])
+            .into_connection();
+        let err = ImportService::dismiss(&db, player_id, entry.id).await.unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(_)));
+    }
+}
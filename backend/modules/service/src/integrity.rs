@@ -0,0 +1,469 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use db_entity::{
+    game, moderation_report, rating_history,
+    game::ResultSide,
+    moderation_report::{IntegritySignal, ReportReason, ReportStatus},
+    player_rating::RatingCategory,
+    tournament::{self as tournament_entity, TournamentStatus},
+};
+use dto::moderation::IntegrityEvidence;
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use tournament::{PrizeKind, PrizeStructure};
+use uuid::Uuid;
+
+use crate::annotations::moves_of;
+use crate::games::is_rated;
+use crate::linking::{correlate, LinkService};
+use crate::tournaments::{prizes_of, state_of};
+
+/// How far back the scan reads ratings and games. A player is queued for
+/// a pattern at most once in this long.
+pub const SCAN_WINDOW_DAYS: i64 = 30;
+
+/// Reporter of the entries the scan raises
+pub const SCAN_REPORTER: Uuid = Uuid::nil();
+
+/// Fall from a peak rating, in points, that counts as abnormal
+const VOLATILITY_DROP: i32 = 200;
+
+/// Rated games a pair must have played before their results are looked at
+const WIN_TRADING_GAMES: u32 = 6;
+
+/// Share of a pair's games one of them must have won
+const WIN_TRADING_SHARE: f64 = 0.8;
+
+/// Games over within this many plies are short
+const SHORT_GAME_PLIES: usize = 20;
+
+/// What a pass of [`IntegrityService::run_pass`] queued.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IntegrityPass {
+    pub prize_sandbagging: usize,
+    pub rating_volatility: usize,
+    pub win_trading: usize,
+    pub multi_account: usize,
+}
+
+pub struct IntegrityService;
+
+impl IntegrityService {
+    /// Look through the ratings, games and fingerprints of the last
+    /// [`SCAN_WINDOW_DAYS`] for sandbagging, win trading and accounts
+    /// sharing devices, and put what is found in the moderation queue with
+    /// the evidence.
+    pub async fn run_pass(db: &DatabaseConnection, now: DateTime<FixedOffset>) -> Result<IntegrityPass, ApiError> {
+        let since = now - Duration::days(SCAN_WINDOW_DAYS);
+
+        // Changes other than rated games, such as season resets, are left out
+        let mut histories: HashMap<(Uuid, RatingCategory), Vec<rating_history::Model>> = HashMap::new();
+        for entry in rating_history::Entity::find()
+            .filter(rating_history::Column::RecordedAt.gte(since))
+            .filter(rating_history::Column::GameId.is_not_null())
+            .order_by_asc(rating_history::Column::RecordedAt)
+            .all(db)
+            .await?
+        {
+            histories.entry((entry.player_id, entry.category)).or_default().push(entry);
+        }
+
+        let mut findings = Vec::new();
+        let tournaments = tournament_entity::Entity::find()
+            .filter(tournament_entity::Column::Status.is_in([TournamentStatus::Registration, TournamentStatus::Ongoing]))
+            .all(db)
+            .await?;
+        for tournament in &tournaments {
+            let prizes = prizes_of(tournament)?;
+            for player in state_of(tournament)?.players.values() {
+                let Some(history) = histories.get(&(player.id, tournament.time_control)) else { continue };
+                if let Some(evidence) = prize_sandbagging(tournament, &prizes, player.rating, history) {
+                    findings.push((player.id, evidence));
+                }
+            }
+        }
+
+        for ((player_id, category), history) in &histories {
+            if let Some(evidence) = rating_volatility(*category, history) {
+                findings.push((*player_id, evidence));
+            }
+        }
+
+        let games = game::Entity::find()
+            .filter(game::Column::UpdatedAt.gte(since))
+            .filter(game::Column::Result.is_in([ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw]))
+            .all(db)
+            .await?;
+        findings.extend(win_trading(&games));
+
+        let fingerprints = LinkService::recent_fingerprints(db, since).await?;
+        let linked = LinkService::linked_pairs(db).await?;
+        for ((first, second), shared) in correlate(&fingerprints) {
+            if !shared.is_suspicious() || linked.contains(&(first, second)) {
+                continue;
+            }
+            for (player, other) in [(first, second), (second, first)] {
+                findings.push((player, IntegrityEvidence::MultiAccount {
+                    other_player_id: other,
+                    shared_devices: shared.devices,
+                    shared_networks: shared.networks,
+                    last_seen_at: shared.last_seen_at,
+                }));
+            }
+        }
+
+        let mut pass = IntegrityPass::default();
+        for (player_id, evidence) in findings {
+            let signal = signal_of(&evidence);
+            if !Self::queue(db, player_id, evidence, now, since).await? {
+                continue;
+            }
+            match signal {
+                IntegritySignal::PrizeSandbagging => pass.prize_sandbagging += 1,
+                IntegritySignal::RatingVolatility => pass.rating_volatility += 1,
+                IntegritySignal::WinTrading => pass.win_trading += 1,
+                IntegritySignal::MultiAccount => pass.multi_account += 1,
+            }
+        }
+        Ok(pass)
+    }
+
+    /// Add an entry to the queue unless the player got one for the same
+    /// pattern since `since`, whatever became of it.
+    async fn queue(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        evidence: IntegrityEvidence,
+        now: DateTime<FixedOffset>,
+        since: DateTime<FixedOffset>,
+    ) -> Result<bool, ApiError> {
+        let signal = signal_of(&evidence);
+        let queued = moderation_report::Entity::find()
+            .filter(moderation_report::Column::ReportedPlayerId.eq(player_id))
+            .filter(moderation_report::Column::Signal.eq(signal))
+            .filter(moderation_report::Column::CreatedAt.gte(since))
+            .one(db)
+            .await?;
+        if queued.is_some() {
+            return Ok(false);
+        }
+
+        let stored = serde_json::to_value(&evidence)
+            .map_err(|err| ApiError::Internal(format!("Cannot serialize evidence: {}", err)))?;
+        moderation_report::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            reporter_id: Set(SCAN_REPORTER),
+            reported_player_id: Set(player_id),
+            game_id: Set(None),
+            reason: Set(match signal {
+                IntegritySignal::PrizeSandbagging | IntegritySignal::RatingVolatility => ReportReason::Sandbagging,
+                IntegritySignal::WinTrading => ReportReason::Cheating,
+                IntegritySignal::MultiAccount => ReportReason::Other,
+            }),
+            details: Set(Some(summary(&evidence))),
+            chat_context: Set(None),
+            is_game_flag: Set(false),
+            status: Set(ReportStatus::Open),
+            resolved_by: Set(None),
+            resolution_note: Set(None),
+            created_at: Set(now),
+            resolved_at: Set(None),
+            signal: Set(Some(signal)),
+            evidence: Set(Some(stored)),
+        }
+        .insert(db)
+        .await?;
+        Ok(true)
+    }
+}
+
+fn signal_of(evidence: &IntegrityEvidence) -> IntegritySignal {
+    match evidence {
+        IntegrityEvidence::PrizeSandbagging { .. } => IntegritySignal::PrizeSandbagging,
+        IntegrityEvidence::RatingVolatility { .. } => IntegritySignal::RatingVolatility,
+        IntegrityEvidence::WinTrading { .. } => IntegritySignal::WinTrading,
+        IntegrityEvidence::MultiAccount { .. } => IntegritySignal::MultiAccount,
+    }
+}
+
+/// One line a moderator can read in the queue without opening the evidence.
+fn summary(evidence: &IntegrityEvidence) -> String {
+    match evidence {
+        IntegrityEvidence::PrizeSandbagging { tournament_name, prize, max_rating, peak_rating, entry_rating, losses, .. } => {
+            format!(
+                "Entered {} at {} for '{}' (below {}) after peaking at {} and losing {} rated games since",
+                tournament_name, entry_rating, prize, max_rating, peak_rating, losses
+            )
+        }
+        IntegrityEvidence::RatingVolatility { peak_rating, low_rating, games, losses, .. } => format!(
+            "Rating fell from {} to {} over {} rated games, {} of them lost",
+            peak_rating, low_rating, games, losses
+        ),
+        IntegrityEvidence::WinTrading { opponent_id, games, wins, short_games, .. } => format!(
+            "Won {} of {} rated games against {}, {} of them short",
+            wins, games, opponent_id, short_games
+        ),
+        IntegrityEvidence::MultiAccount { other_player_id, shared_devices, shared_networks, .. } => format!(
+            "Seen on {} devices and {} networks also used by {}",
+            shared_devices, shared_networks, other_player_id
+        ),
+    }
+}
+
+/// A player who entered `tournament` under the limit of one of its rating
+/// prizes after being rated at or above that limit in `history`.
+fn prize_sandbagging(
+    tournament: &tournament_entity::Model,
+    prizes: &PrizeStructure,
+    entry_rating: i32,
+    history: &[rating_history::Model],
+) -> Option<IntegrityEvidence> {
+    let (prize, max_rating) = prizes.prizes.iter().find_map(|prize| match prize.kind {
+        PrizeKind::RatingCategory { min_rating, max_rating: Some(max_rating), .. }
+            if entry_rating < max_rating && min_rating.is_none_or(|min| entry_rating >= min) =>
+        {
+            Some((prize, max_rating))
+        }
+        _ => None,
+    })?;
+    // The earliest of equal peaks, so the losses since are all counted
+    let peak = history
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| Reverse(entry.rating))?
+        .0;
+    if history[peak].rating < max_rating {
+        return None;
+    }
+
+    Some(IntegrityEvidence::PrizeSandbagging {
+        tournament_id: tournament.id,
+        tournament_name: tournament.name.clone(),
+        prize: prize.name.clone(),
+        category: tournament.time_control.into(),
+        max_rating,
+        peak_rating: history[peak].rating,
+        peak_at: history[peak].recorded_at,
+        entry_rating,
+        losses: losses(&history[peak..]),
+    })
+}
+
+/// The largest fall from a peak to a later low in `history`, if it is
+/// [`VOLATILITY_DROP`] points or more.
+fn rating_volatility(category: RatingCategory, history: &[rating_history::Model]) -> Option<IntegrityEvidence> {
+    let mut peak = 0;
+    let mut largest: Option<(usize, usize)> = None;
+    for (index, entry) in history.iter().enumerate() {
+        if entry.rating > history[peak].rating {
+            peak = index;
+        }
+        let drop = history[peak].rating - entry.rating;
+        if largest.is_none_or(|(from, to)| drop > history[from].rating - history[to].rating) {
+            largest = Some((peak, index));
+        }
+    }
+    let (from, to) = largest?;
+    if history[from].rating - history[to].rating < VOLATILITY_DROP {
+        return None;
+    }
+
+    Some(IntegrityEvidence::RatingVolatility {
+        category: category.into(),
+        peak_rating: history[from].rating,
+        peak_at: history[from].recorded_at,
+        low_rating: history[to].rating,
+        low_at: history[to].recorded_at,
+        games: (to - from) as u32,
+        losses: losses(&history[from..=to]),
+    })
+}
+
+/// Games in a run of rating entries that cost rating
+fn losses(history: &[rating_history::Model]) -> u32 {
+    history.windows(2).filter(|pair| pair[1].rating < pair[0].rating).count() as u32
+}
+
+/// Players who won most of their many rated games against the same
+/// opponent, mostly in short games.
+fn win_trading(games: &[game::Model]) -> Vec<(Uuid, IntegrityEvidence)> {
+    let mut pairs: HashMap<(Uuid, Uuid), Vec<&game::Model>> = HashMap::new();
+    for game in games.iter().filter(|g| is_rated(g) && g.white_player != g.black_player) {
+        let pair = (game.white_player.min(game.black_player), game.white_player.max(game.black_player));
+        pairs.entry(pair).or_default().push(game);
+    }
+
+    let mut findings = Vec::new();
+    for ((first, second), mut played) in pairs {
+        let count = played.len() as u32;
+        if count < WIN_TRADING_GAMES {
+            continue;
+        }
+        let short_games = played
+            .iter()
+            .filter(|g| moves_of(g).is_ok_and(|moves| moves.len() <= SHORT_GAME_PLIES))
+            .count() as u32;
+        if short_games * 2 < count {
+            continue;
+        }
+        played.sort_by_key(|g| Reverse(g.updated_at));
+
+        for (player, opponent) in [(first, second), (second, first)] {
+            let wins = played.iter().filter(|g| winner(g) == Some(player)).count() as u32;
+            if (wins as f64) < WIN_TRADING_SHARE * count as f64 {
+                continue;
+            }
+            findings.push((player, IntegrityEvidence::WinTrading {
+                opponent_id: opponent,
+                games: count,
+                wins,
+                short_games,
+                game_ids: played.iter().map(|g| g.id).collect(),
+            }));
+        }
+    }
+    findings
+}
+
+fn winner(game: &game::Model) -> Option<Uuid> {
+    match game.result {
+        Some(ResultSide::WhiteWins) => Some(game.white_player),
+        Some(ResultSide::BlackWins) => Some(game.black_player),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use db_entity::game::GameVariant;
+    use db_entity::tournament::TournamentFormat;
+    use serde_json::json;
+    use tournament::Prize;
+
+    fn history(ratings: &[i32]) -> Vec<rating_history::Model> {
+        let player_id = Uuid::new_v4();
+        let start = Utc::now().fixed_offset() - Duration::days(10);
+        ratings
+            .iter()
+            .enumerate()
+            .map(|(index, rating)| rating_history::Model {
+                id: Uuid::new_v4(),
+                player_id,
+                category: RatingCategory::Blitz,
+                game_id: Some(Uuid::new_v4()),
+                rating: *rating,
+                rating_deviation: 60.0,
+                volatility: None,
+                recorded_at: start + Duration::hours(index as i64),
+            })
+            .collect()
+    }
+
+    fn game(white: Uuid, black: Uuid, result: ResultSide, plies: usize) -> game::Model {
+        let now = Utc::now().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: white,
+            black_player: black,
+            fen: String::new(),
+            pgn: json!({ "moves": vec!["e4"; plies] }),
+            result: Some(result),
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 180,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    fn tournament() -> tournament_entity::Model {
+        let now = Utc::now().fixed_offset();
+        tournament_entity::Model {
+            id: Uuid::new_v4(),
+            name: "Autumn Open".to_string(),
+            arbiter_id: Uuid::new_v4(),
+            time_control: RatingCategory::Blitz,
+            config: json!({}),
+            state: json!({}),
+            created_at: now,
+            updated_at: now,
+            format: TournamentFormat::Swiss,
+            status: TournamentStatus::Registration,
+            template_id: None,
+            duration_minutes: None,
+            registration_opens_at: None,
+            registration_closes_at: None,
+            starts_at: None,
+            prizes: json!({}),
+            club_id: None,
+        }
+    }
+
+    fn rating_prize(name: &str, min_rating: Option<i32>, max_rating: Option<i32>) -> Prize {
+        Prize { name: name.to_string(), kind: PrizeKind::RatingCategory { min_rating, max_rating, places: 1 } }
+    }
+
+    #[test]
+    fn test_entering_under_a_prize_limit_after_a_peak_above_it_is_sandbagging() {
+        let prizes = PrizeStructure {
+            prizes: vec![
+                Prize { name: "First".to_string(), kind: PrizeKind::Place { from: 1, to: 1 } },
+                rating_prize("Best U1400", None, Some(1400)),
+                rating_prize("Best U1600", Some(1400), Some(1600)),
+            ],
+        };
+        let history = history(&[1580, 1620, 1620, 1590, 1600, 1560]);
+
+        let Some(IntegrityEvidence::PrizeSandbagging { prize, max_rating, peak_rating, losses, .. }) =
+            prize_sandbagging(&tournament(), &prizes, 1560, &history)
+        else {
+            panic!("expected a finding");
+        };
+        assert_eq!((prize.as_str(), max_rating, peak_rating, losses), ("Best U1600", 1600, 1620, 2));
+
+        // Never rated at the limit, or not under it when entering
+        assert!(prize_sandbagging(&tournament(), &prizes, 1560, &history[..1]).is_none());
+        assert!(prize_sandbagging(&tournament(), &prizes, 1610, &history).is_none());
+    }
+
+    #[test]
+    fn test_volatility_takes_the_largest_fall_from_a_peak() {
+        let steady = history(&[1500, 1650, 1520, 1600, 1480, 1530]);
+        assert!(rating_volatility(RatingCategory::Blitz, &steady).is_none());
+
+        let dumped = history(&[1500, 1700, 1650, 1660, 1540, 1490, 1560]);
+        let Some(IntegrityEvidence::RatingVolatility { peak_rating, low_rating, games, losses, .. }) =
+            rating_volatility(RatingCategory::Blitz, &dumped)
+        else {
+            panic!("expected a finding");
+        };
+        assert_eq!((peak_rating, low_rating, games, losses), (1700, 1490, 4, 3));
+    }
+
+    #[test]
+    fn test_win_trading_needs_many_one_sided_mostly_short_games() {
+        let (booster, boosted, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut games: Vec<_> = (0..5).map(|_| game(booster, boosted, ResultSide::BlackWins, 8)).collect();
+        games.push(game(boosted, booster, ResultSide::WhiteWins, 60));
+        games.push(game(booster, boosted, ResultSide::Draw, 70));
+        // A long rivalry with lopsided results but full games
+        games.extend((0..8).map(|_| game(other, booster, ResultSide::WhiteWins, 80)));
+
+        let findings = win_trading(&games);
+        assert_eq!(findings.len(), 1);
+        let (player, IntegrityEvidence::WinTrading { opponent_id, games, wins, short_games, game_ids }) = &findings[0]
+        else {
+            panic!("expected win trading");
+        };
+        assert_eq!((*player, *opponent_id), (boosted, booster));
+        assert_eq!((*games, *wins, *short_games, game_ids.len()), (7, 6, 5, 7));
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use db_entity::{
+    leaderboard_entry, player, player_rating,
+    player_rating::RatingCategory,
+};
+use error::error::ApiError;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, Iterable, Order, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+/// Players with fewer rated games are provisional and stay off the leaderboard.
+pub const LEADERBOARD_MIN_GAMES: i32 = 10;
+
+/// Upper bound on a single leaderboard page.
+pub const MAX_LEADERBOARD_SIZE: u64 = 100;
+
+/// Rows are inserted in chunks to stay under the Postgres bind parameter limit.
+const INSERT_CHUNK: usize = 1000;
+
+/// A player's position in a freshly computed ranking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankedPlayer {
+    pub player_id: Uuid,
+    pub rating: i32,
+    pub games_played: i32,
+    pub rank: i32,
+    pub previous_rank: Option<i32>,
+}
+
+pub struct LeaderboardService;
+
+impl LeaderboardService {
+    /// Rebuild the materialized ranking for every time control.
+    /// Returns the number of ranked entries written.
+    pub async fn refresh(db: &DatabaseConnection) -> Result<usize, ApiError> {
+        let mut written = 0;
+        for category in RatingCategory::iter() {
+            written += Self::refresh_category(db, category).await?;
+        }
+        Ok(written)
+    }
+
+    async fn refresh_category(db: &DatabaseConnection, category: RatingCategory) -> Result<usize, ApiError> {
+        let ratings = player_rating::Entity::find()
+            .filter(player_rating::Column::Category.eq(category))
+            .filter(player_rating::Column::GamesPlayed.gte(LEADERBOARD_MIN_GAMES))
+            .all(db)
+            .await?;
+
+        let previous: HashMap<Uuid, i32> = leaderboard_entry::Entity::find()
+            .filter(leaderboard_entry::Column::Category.eq(category))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|entry| (entry.player_id, entry.rank))
+            .collect();
+
+        let ranked = rank_players(ratings, &previous);
+        let refreshed_at = Utc::now().fixed_offset();
+        let count = ranked.len();
+
+        let txn = db.begin().await?;
+
+        leaderboard_entry::Entity::delete_many()
+            .filter(leaderboard_entry::Column::Category.eq(category))
+            .exec(&txn)
+            .await?;
+
+        for chunk in ranked.chunks(INSERT_CHUNK) {
+            let rows = chunk.iter().map(|r| leaderboard_entry::ActiveModel {
+                category: Set(category),
+                player_id: Set(r.player_id),
+                rank: Set(r.rank),
+                previous_rank: Set(r.previous_rank),
+                rating: Set(r.rating),
+                games_played: Set(r.games_played),
+                refreshed_at: Set(refreshed_at),
+            });
+            leaderboard_entry::Entity::insert_many(rows).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(count)
+    }
+
+    /// Top of the ranking for a time control, with usernames.
+    pub async fn top(
+        db: &DatabaseConnection,
+        category: RatingCategory,
+        limit: u64,
+    ) -> Result<Vec<(leaderboard_entry::Model, String)>, ApiError> {
+        let rows = leaderboard_entry::Entity::find()
+            .filter(leaderboard_entry::Column::Category.eq(category))
+            .order_by(leaderboard_entry::Column::Rank, Order::Asc)
+            .order_by(leaderboard_entry::Column::PlayerId, Order::Asc)
+            .limit(limit.clamp(1, MAX_LEADERBOARD_SIZE))
+            .find_also_related(player::Entity)
+            .all(db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(entry, player)| {
+                let username = player.map(|p| p.username).unwrap_or_default();
+                (entry, username)
+            })
+            .collect())
+    }
+
+    /// A single player's ranking entry for a time control.
+    pub async fn rank_of(
+        db: &DatabaseConnection,
+        category: RatingCategory,
+        player_id: Uuid,
+    ) -> Result<(leaderboard_entry::Model, String), ApiError> {
+        let row = leaderboard_entry::Entity::find()
+            .filter(leaderboard_entry::Column::Category.eq(category))
+            .filter(leaderboard_entry::Column::PlayerId.eq(player_id))
+            .find_also_related(player::Entity)
+            .one(db)
+            .await?;
+
+        match row {
+            Some((entry, player)) => Ok((entry, player.map(|p| p.username).unwrap_or_default())),
+            None => Err(ApiError::NotFound(format!("Ranking for player {}", player_id))),
+        }
+    }
+}
+
+/// Order players by rating and assign competition ranks ("1224"): equal
+/// ratings share a rank and the next distinct rating skips ahead.
+pub fn rank_players(
+    mut ratings: Vec<player_rating::Model>,
+    previous: &HashMap<Uuid, i32>,
+) -> Vec<RankedPlayer> {
+    ratings.sort_by(|a, b| {
+        b.rating
+            .cmp(&a.rating)
+            .then_with(|| b.games_played.cmp(&a.games_played))
+            .then_with(|| a.player_id.cmp(&b.player_id))
+    });
+
+    let mut ranked = Vec::with_capacity(ratings.len());
+    let mut current_rank = 0;
+    let mut last_rating = None;
+
+    for (index, rating) in ratings.into_iter().enumerate() {
+        if last_rating != Some(rating.rating) {
+            current_rank = index as i32 + 1;
+            last_rating = Some(rating.rating);
+        }
+        ranked.push(RankedPlayer {
+            player_id: rating.player_id,
+            rating: rating.rating,
+            games_played: rating.games_played,
+            rank: current_rank,
+            previous_rank: previous.get(&rating.player_id).copied(),
+        });
+    }
+
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(player_id: Uuid, rating: i32) -> player_rating::Model {
+        player_rating::Model {
+            player_id,
+            category: RatingCategory::Blitz,
+            rating,
+            rating_deviation: 60.0,
+            volatility: 0.06,
+            games_played: 20,
+            updated_at: Utc::now().fixed_offset(),
+            last_rated_at: None,
+            rd_updated_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_rank_players_shares_rank_on_ties() {
+        let (a, b, c, d) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let ranked = rank_players(
+            vec![rating(a, 1800), rating(b, 2100), rating(c, 1800), rating(d, 1500)],
+            &HashMap::new(),
+        );
+
+        let ranks: Vec<i32> = ranked.iter().map(|r| r.rank).collect();
+        assert_eq!(ranks, vec![1, 2, 2, 4]);
+        assert_eq!(ranked[0].player_id, b);
+        assert_eq!(ranked[3].player_id, d);
+    }
+
+    #[test]
+    fn test_rank_players_carries_previous_rank() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let previous = HashMap::from([(a, 1)]);
+        let ranked = rank_players(vec![rating(a, 1900), rating(b, 2000)], &previous);
+
+        assert_eq!(ranked[0].player_id, b);
+        assert_eq!(ranked[0].previous_rank, None);
+        assert_eq!(ranked[1].player_id, a);
+        assert_eq!(ranked[1].previous_rank, Some(1));
+    }
+}
@@ -1,4 +1,40 @@
 pub mod helper;
 pub mod players;
 pub mod engine_service;
+pub mod bots;
 pub mod games;
+pub mod moderation;
+pub mod disputes;
+pub mod search;
+pub mod positions;
+pub mod leaderboard;
+pub mod stats;
+pub mod rating;
+pub mod recalculation;
+pub mod tournaments;
+pub mod tournament_templates;
+pub mod schedule;
+pub mod trophies;
+pub mod annotations;
+pub mod friends;
+pub mod engine_matches;
+pub mod training;
+pub mod archive;
+pub mod game_archive;
+pub mod game_events;
+pub mod importer;
+pub mod attestations;
+pub mod wallets;
+pub mod replay;
+pub mod webhooks;
+pub mod preferences;
+pub mod account;
+pub mod registration_import;
+pub mod fixtures;
+pub mod titles;
+pub mod clubs;
+pub mod coaching;
+pub mod analysis;
+pub mod integrity;
+pub mod linking;
+pub mod autosave;
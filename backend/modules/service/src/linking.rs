@@ -0,0 +1,401 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{
+    account_link, player, player_fingerprint,
+    player_fingerprint::FingerprintKind,
+};
+use dto::moderation::{AccountLinksDisplay, LinkAccountRequest, LinkedAccountDisplay, SharedFingerprintDisplay};
+use error::error::ApiError;
+use hmac::{Hmac, Mac};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use sha2::Sha256;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use uuid::Uuid;
+
+use crate::moderation::ModerationService;
+
+/// Longest `X-Device-Id` taken; longer values are ignored
+pub const MAX_DEVICE_ID_LEN: usize = 128;
+
+/// Fingerprints shared by more accounts than this, such as a campus
+/// network or a club's tablet, say nothing about any two of them
+const MAX_ACCOUNTS_PER_FINGERPRINT: usize = 5;
+
+/// Networks two accounts must share when they share no device
+const SUSPICIOUS_NETWORKS: u32 = 2;
+
+/// Keyed hashing of the networks and devices players come from. Equal
+/// inputs give equal hashes, but without `FINGERPRINT_SECRET` a hash
+/// cannot be traced back to an address or device.
+#[derive(Clone)]
+pub struct Fingerprinter {
+    key: Vec<u8>,
+}
+
+impl Fingerprinter {
+    pub fn new(secret: &str) -> Self {
+        Self { key: secret.as_bytes().to_vec() }
+    }
+
+    /// Hashes of the network `remote_addr` belongs to and of `device_id`,
+    /// for whichever of them is usable.
+    pub fn fingerprints(&self, remote_addr: Option<&str>, device_id: Option<&str>) -> Vec<(FingerprintKind, String)> {
+        let network = remote_addr
+            .and_then(network_of)
+            .map(|network| (FingerprintKind::Network, self.hash("network", &network)));
+        let device = device_id
+            .map(str::trim)
+            .filter(|id| !id.is_empty() && id.len() <= MAX_DEVICE_ID_LEN)
+            .map(|id| (FingerprintKind::Device, self.hash("device", id)));
+        network.into_iter().chain(device).collect()
+    }
+
+    fn hash(&self, kind: &str, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(kind.as_bytes());
+        mac.update(b":");
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// The /24 of an IPv4 address or the /48 of an IPv6 one, so addresses a
+/// provider rotates within stay one network. `addr` may carry a port.
+fn network_of(addr: &str) -> Option<String> {
+    let ip = addr
+        .parse::<IpAddr>()
+        .or_else(|_| addr.parse::<SocketAddr>().map(|socket| socket.ip()))
+        .ok()?;
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    };
+    Some(match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            format!("{:x}:{:x}:{:x}::/48", segments[0], segments[1], segments[2])
+        }
+    })
+}
+
+/// What two accounts were both seen on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedFingerprints {
+    pub devices: u32,
+    pub networks: u32,
+    /// Last time both were seen on one of them
+    pub last_seen_at: DateTime<FixedOffset>,
+}
+
+impl SharedFingerprints {
+    /// A device in common, or more than one network
+    pub fn is_suspicious(&self) -> bool {
+        self.devices > 0 || self.networks >= SUSPICIOUS_NETWORKS
+    }
+}
+
+/// Pairs of accounts, lower id first, that share any fingerprint used by
+/// at most [`MAX_ACCOUNTS_PER_FINGERPRINT`] accounts.
+pub fn correlate(fingerprints: &[player_fingerprint::Model]) -> HashMap<(Uuid, Uuid), SharedFingerprints> {
+    let mut by_hash: HashMap<&str, Vec<&player_fingerprint::Model>> = HashMap::new();
+    for fingerprint in fingerprints {
+        by_hash.entry(&fingerprint.hash).or_default().push(fingerprint);
+    }
+
+    let mut pairs: HashMap<(Uuid, Uuid), SharedFingerprints> = HashMap::new();
+    for seen in by_hash.values().filter(|seen| seen.len() <= MAX_ACCOUNTS_PER_FINGERPRINT) {
+        for (index, first) in seen.iter().enumerate() {
+            for second in &seen[index + 1..] {
+                let together = first.last_seen_at.min(second.last_seen_at);
+                let shared = pairs
+                    .entry((first.player_id.min(second.player_id), first.player_id.max(second.player_id)))
+                    .or_insert(SharedFingerprints { devices: 0, networks: 0, last_seen_at: together });
+                match first.kind {
+                    FingerprintKind::Device => shared.devices += 1,
+                    FingerprintKind::Network => shared.networks += 1,
+                }
+                shared.last_seen_at = shared.last_seen_at.max(together);
+            }
+        }
+    }
+    pairs
+}
+
+pub struct LinkService;
+
+impl LinkService {
+    /// Note that `player_id` was seen on `fingerprints` just now.
+    pub async fn record(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        fingerprints: Vec<(FingerprintKind, String)>,
+    ) -> Result<(), ApiError> {
+        let now = Utc::now().fixed_offset();
+        for (kind, hash) in fingerprints {
+            match player_fingerprint::Entity::find_by_id((player_id, hash.clone())).one(db).await? {
+                Some(existing) => {
+                    let seen_count = existing.seen_count.saturating_add(1);
+                    let mut active: player_fingerprint::ActiveModel = existing.into();
+                    active.last_seen_at = Set(now);
+                    active.seen_count = Set(seen_count);
+                    active.update(db).await?;
+                }
+                None => {
+                    player_fingerprint::ActiveModel {
+                        player_id: Set(player_id),
+                        hash: Set(hash),
+                        kind: Set(kind),
+                        first_seen_at: Set(now),
+                        last_seen_at: Set(now),
+                        seen_count: Set(1),
+                    }
+                    .insert(db)
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fingerprints last seen since `since`, for the correlation scan.
+    pub async fn recent_fingerprints(
+        db: &DatabaseConnection,
+        since: DateTime<FixedOffset>,
+    ) -> Result<Vec<player_fingerprint::Model>, ApiError> {
+        Ok(player_fingerprint::Entity::find()
+            .filter(player_fingerprint::Column::LastSeenAt.gte(since))
+            .all(db)
+            .await?)
+    }
+
+    /// Forget fingerprints not seen since `before`.
+    pub async fn purge_fingerprints(db: &DatabaseConnection, before: DateTime<FixedOffset>) -> Result<u64, ApiError> {
+        let deleted = player_fingerprint::Entity::delete_many()
+            .filter(player_fingerprint::Column::LastSeenAt.lt(before))
+            .exec(db)
+            .await?;
+        Ok(deleted.rows_affected)
+    }
+
+    /// Mark two accounts as the same person, so actions on one, such as a
+    /// ban, hold for the other.
+    pub async fn mark(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        moderator_id: Uuid,
+        request: LinkAccountRequest,
+    ) -> Result<account_link::Model, ApiError> {
+        if player_id == request.player_id {
+            return Err(ApiError::BadRequest("An account cannot be linked to itself".to_string()));
+        }
+        ModerationService::find_player(db, player_id).await?;
+        ModerationService::find_player(db, request.player_id).await?;
+
+        let pair = (player_id.min(request.player_id), player_id.max(request.player_id));
+        if account_link::Entity::find_by_id(pair).one(db).await?.is_some() {
+            return Err(ApiError::BadRequest("Accounts are already linked".to_string()));
+        }
+
+        Ok(account_link::ActiveModel {
+            player_id: Set(pair.0),
+            linked_player_id: Set(pair.1),
+            marked_by: Set(moderator_id),
+            note: Set(request.note),
+            created_at: Set(Utc::now().fixed_offset()),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Undo [`LinkService::mark`]; sanctions applied meanwhile stay on
+    /// whichever account they were applied to.
+    pub async fn unmark(db: &DatabaseConnection, player_id: Uuid, other_id: Uuid) -> Result<(), ApiError> {
+        let deleted = account_link::Entity::delete_by_id((player_id.min(other_id), player_id.max(other_id)))
+            .exec(db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(ApiError::NotFound("Account link".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Links of `player_id`, either way round.
+    pub async fn links_of(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<account_link::Model>, ApiError> {
+        Ok(account_link::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(account_link::Column::PlayerId.eq(player_id))
+                    .add(account_link::Column::LinkedPlayerId.eq(player_id)),
+            )
+            .all(db)
+            .await?)
+    }
+
+    /// Accounts marked as the same person as `player_id`.
+    pub async fn linked_ids(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<Uuid>, ApiError> {
+        let links = Self::links_of(db, player_id).await?;
+        Ok(links.iter().map(|link| other_of(link, player_id)).collect())
+    }
+
+    /// Every linked pair, lower id first, for the scan to skip.
+    pub async fn linked_pairs(db: &DatabaseConnection) -> Result<HashSet<(Uuid, Uuid)>, ApiError> {
+        let links = account_link::Entity::find().all(db).await?;
+        Ok(links.into_iter().map(|link| (link.player_id, link.linked_player_id)).collect())
+    }
+
+    /// The accounts linked to `player_id` and those sharing its devices or
+    /// networks without being linked.
+    pub async fn links(db: &DatabaseConnection, player_id: Uuid) -> Result<AccountLinksDisplay, ApiError> {
+        ModerationService::find_player(db, player_id).await?;
+        let links = Self::links_of(db, player_id).await?;
+
+        let own = player_fingerprint::Entity::find()
+            .filter(player_fingerprint::Column::PlayerId.eq(player_id))
+            .all(db)
+            .await?;
+        let fingerprints = if own.is_empty() {
+            Vec::new()
+        } else {
+            player_fingerprint::Entity::find()
+                .filter(player_fingerprint::Column::Hash.is_in(own.iter().map(|f| f.hash.clone())))
+                .all(db)
+                .await?
+        };
+        let linked: HashSet<Uuid> = links.iter().map(|link| other_of(link, player_id)).collect();
+        let mut shared: Vec<(Uuid, SharedFingerprints)> = correlate(&fingerprints)
+            .into_iter()
+            .filter_map(|((first, second), shared)| match (first == player_id, second == player_id) {
+                (true, _) => Some((second, shared)),
+                (_, true) => Some((first, shared)),
+                _ => None,
+            })
+            .filter(|(other, _)| !linked.contains(other))
+            .collect();
+        shared.sort_by_key(|(_, shared)| Reverse((shared.devices, shared.networks, shared.last_seen_at)));
+
+        let ids: Vec<Uuid> = linked.iter().copied().chain(shared.iter().map(|(other, _)| *other)).collect();
+        let usernames: HashMap<Uuid, String> = if ids.is_empty() {
+            HashMap::new()
+        } else {
+            player::Entity::find()
+                .filter(player::Column::Id.is_in(ids))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|p| (p.id, p.username))
+                .collect()
+        };
+        let username = |id: &Uuid| usernames.get(id).cloned().unwrap_or_default();
+
+        Ok(AccountLinksDisplay {
+            linked: links
+                .into_iter()
+                .map(|link| {
+                    let other = other_of(&link, player_id);
+                    LinkedAccountDisplay {
+                        player_id: other,
+                        username: username(&other),
+                        marked_by: link.marked_by,
+                        note: link.note,
+                        created_at: link.created_at,
+                    }
+                })
+                .collect(),
+            suspected: shared
+                .into_iter()
+                .map(|(other, shared)| SharedFingerprintDisplay {
+                    player_id: other,
+                    username: username(&other),
+                    shared_devices: shared.devices,
+                    shared_networks: shared.networks,
+                    last_seen_at: shared.last_seen_at,
+                })
+                .collect(),
+        })
+    }
+}
+
+fn other_of(link: &account_link::Model, player_id: Uuid) -> Uuid {
+    if link.player_id == player_id {
+        link.linked_player_id
+    } else {
+        link.player_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn seen(player_id: Uuid, kind: FingerprintKind, hash: &str, days_ago: i64) -> player_fingerprint::Model {
+        let at = Utc::now().fixed_offset() - Duration::days(days_ago);
+        player_fingerprint::Model {
+            player_id,
+            hash: hash.to_string(),
+            kind,
+            first_seen_at: at,
+            last_seen_at: at,
+            seen_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_networks_are_hashed_by_prefix_and_kinds_never_collide() {
+        let fingerprinter = Fingerprinter::new("secret");
+        let home = fingerprinter.fingerprints(Some("203.0.113.7"), None);
+        assert_eq!(home, fingerprinter.fingerprints(Some("203.0.113.200:51234"), None));
+        assert_eq!(home, fingerprinter.fingerprints(Some("::ffff:203.0.113.9"), None));
+        assert_ne!(home, fingerprinter.fingerprints(Some("203.0.114.7"), None));
+        assert_eq!(
+            fingerprinter.fingerprints(Some("2001:db8:1:2::1"), None),
+            fingerprinter.fingerprints(Some("[2001:db8:1:ffff::9]:443"), None),
+        );
+        assert_eq!(home[0].1.len(), 64);
+
+        // The same text as a device id, another key, or unusable input
+        let device = fingerprinter.fingerprints(None, Some("203.0.113.0/24"));
+        assert_eq!(device[0].0, FingerprintKind::Device);
+        assert_ne!(device[0].1, home[0].1);
+        assert_ne!(Fingerprinter::new("other").fingerprints(Some("203.0.113.7"), None), home);
+        assert!(fingerprinter.fingerprints(Some("unknown"), Some("  ")).is_empty());
+        assert!(fingerprinter.fingerprints(None, Some(&"x".repeat(MAX_DEVICE_ID_LEN + 1))).is_empty());
+    }
+
+    #[test]
+    fn test_accounts_on_a_device_or_several_networks_are_suspicious() {
+        let (main, alt, roommate) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut fingerprints = vec![
+            seen(main, FingerprintKind::Device, "phone", 1),
+            seen(alt, FingerprintKind::Device, "phone", 3),
+            seen(main, FingerprintKind::Network, "home", 1),
+            seen(alt, FingerprintKind::Network, "home", 2),
+            seen(roommate, FingerprintKind::Network, "home", 1),
+        ];
+        // A network half the club uses
+        fingerprints.extend(
+            std::iter::once(roommate)
+                .chain(std::iter::once(main))
+                .chain((0..MAX_ACCOUNTS_PER_FINGERPRINT).map(|_| Uuid::new_v4()))
+                .map(|player| seen(player, FingerprintKind::Network, "club", 0)),
+        );
+
+        let pairs = correlate(&fingerprints);
+        let alts = pairs[&(main.min(alt), main.max(alt))];
+        assert_eq!((alts.devices, alts.networks), (1, 1));
+        assert_eq!(alts.last_seen_at, fingerprints[3].last_seen_at);
+        assert!(alts.is_suspicious());
+
+        let housemates = pairs[&(main.min(roommate), main.max(roommate))];
+        assert_eq!((housemates.devices, housemates.networks), (0, 1));
+        assert!(!housemates.is_suspicious());
+        assert_eq!(pairs.len(), 3);
+    }
+}
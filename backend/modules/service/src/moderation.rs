@@ -0,0 +1,386 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entity::{
+    game, moderation_action, moderation_report, player, player_role,
+    moderation_action::ActionKind,
+    moderation_report::ReportStatus,
+    player_role::Role,
+};
+use dto::moderation::{
+    CreateReportRequest, FlagGameRequest, ModerationActionRequest, ResolveReportRequest,
+};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Order, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use uuid::Uuid;
+
+use crate::linking::LinkService;
+
+/// Upper bound on a single page of the moderator queue.
+pub const MAX_QUEUE_PAGE: u64 = 200;
+
+pub struct ModerationService;
+
+impl ModerationService {
+    /// Resolve the player behind an authenticated username.
+    pub async fn find_player_by_username(
+        db: &DatabaseConnection,
+        username: &str,
+    ) -> Result<Option<player::Model>, ApiError> {
+        Ok(player::Entity::find()
+            .filter(player::Column::Username.eq(username))
+            .one(db)
+            .await?)
+    }
+
+    /// Roles held by a player.
+    pub async fn roles_for(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<Role>, ApiError> {
+        let roles = player_role::Entity::find()
+            .filter(player_role::Column::PlayerId.eq(player_id))
+            .all(db)
+            .await?;
+
+        Ok(roles.into_iter().map(|r| r.role).collect())
+    }
+
+    /// Whether the player holds `role`. Admins implicitly hold every role.
+    pub async fn has_role(db: &DatabaseConnection, player_id: Uuid, role: Role) -> Result<bool, ApiError> {
+        let roles = Self::roles_for(db, player_id).await?;
+        Ok(roles_satisfy(&roles, role))
+    }
+
+    /// Grant a role to a player. Granting a role the player already holds is a no-op.
+    /// `granted_by` is `None` for roles granted with `starkmate-admin`.
+    pub async fn grant_role(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        role: Role,
+        granted_by: Option<Uuid>,
+    ) -> Result<player_role::Model, ApiError> {
+        Self::ensure_player_exists(db, player_id).await?;
+
+        if let Some(existing) = player_role::Entity::find()
+            .filter(player_role::Column::PlayerId.eq(player_id))
+            .filter(player_role::Column::Role.eq(role))
+            .one(db)
+            .await?
+        {
+            return Ok(existing);
+        }
+
+        let model = player_role::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            role: Set(role),
+            granted_by: Set(granted_by),
+            granted_at: Set(now()),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    /// File a report against another player.
+    pub async fn create_report(
+        db: &DatabaseConnection,
+        reporter_id: Uuid,
+        request: CreateReportRequest,
+    ) -> Result<moderation_report::Model, ApiError> {
+        if reporter_id == request.reported_player_id {
+            return Err(ApiError::BadRequest("Players cannot report themselves".to_string()));
+        }
+        Self::ensure_player_exists(db, request.reported_player_id).await?;
+
+        if let Some(game_id) = request.game_id {
+            let game = Self::find_game(db, game_id).await?;
+            if !is_participant(&game, request.reported_player_id) {
+                return Err(ApiError::BadRequest(
+                    "Reported player did not take part in the referenced game".to_string(),
+                ));
+            }
+        }
+
+        let chat_context = request
+            .chat_context
+            .filter(|lines| !lines.is_empty())
+            .map(|lines| serde_json::json!(lines));
+
+        let model = moderation_report::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            reporter_id: Set(reporter_id),
+            reported_player_id: Set(request.reported_player_id),
+            game_id: Set(request.game_id),
+            reason: Set(request.reason.into()),
+            details: Set(request.details),
+            chat_context: Set(chat_context),
+            is_game_flag: Set(false),
+            status: Set(ReportStatus::Open),
+            resolved_by: Set(None),
+            resolution_note: Set(None),
+            created_at: Set(now()),
+            resolved_at: Set(None),
+            signal: Set(None),
+            evidence: Set(None),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    /// Flag a game for review. Flags land in the same queue as player reports.
+    pub async fn flag_game(
+        db: &DatabaseConnection,
+        moderator_id: Uuid,
+        game_id: Uuid,
+        request: FlagGameRequest,
+    ) -> Result<moderation_report::Model, ApiError> {
+        let game = Self::find_game(db, game_id).await?;
+        if !is_participant(&game, request.player_id) {
+            return Err(ApiError::BadRequest(
+                "Flagged player did not take part in the game".to_string(),
+            ));
+        }
+
+        let model = moderation_report::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            reporter_id: Set(moderator_id),
+            reported_player_id: Set(request.player_id),
+            game_id: Set(Some(game_id)),
+            reason: Set(request.reason.into()),
+            details: Set(request.details),
+            chat_context: Set(None),
+            is_game_flag: Set(true),
+            status: Set(ReportStatus::Open),
+            resolved_by: Set(None),
+            resolution_note: Set(None),
+            created_at: Set(now()),
+            resolved_at: Set(None),
+            signal: Set(None),
+            evidence: Set(None),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    /// Moderator queue, oldest first so nothing starves.
+    pub async fn list_reports(
+        db: &DatabaseConnection,
+        status: ReportStatus,
+        limit: u64,
+    ) -> Result<Vec<moderation_report::Model>, ApiError> {
+        Ok(moderation_report::Entity::find()
+            .filter(moderation_report::Column::Status.eq(status))
+            .order_by(moderation_report::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// Close a report as resolved or dismissed.
+    pub async fn resolve_report(
+        db: &DatabaseConnection,
+        report_id: Uuid,
+        moderator_id: Uuid,
+        request: ResolveReportRequest,
+    ) -> Result<moderation_report::Model, ApiError> {
+        let status: ReportStatus = request.status.into();
+        if status == ReportStatus::Open {
+            return Err(ApiError::BadRequest(
+                "A report can only be resolved or dismissed".to_string(),
+            ));
+        }
+
+        let report = moderation_report::Entity::find_by_id(report_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Report {}", report_id)))?;
+
+        if report.status != ReportStatus::Open {
+            return Err(ApiError::BadRequest("Report is already closed".to_string()));
+        }
+
+        let mut active: moderation_report::ActiveModel = report.into();
+        active.status = Set(status);
+        active.resolved_by = Set(Some(moderator_id));
+        active.resolution_note = Set(request.note);
+        active.resolved_at = Set(Some(now()));
+
+        Ok(active.update(db).await?)
+    }
+
+    /// Warn, mute or ban a player.
+    pub async fn apply_action(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        moderator_id: Uuid,
+        request: ModerationActionRequest,
+    ) -> Result<moderation_action::Model, ApiError> {
+        Self::ensure_player_exists(db, player_id).await?;
+
+        let created_at = now();
+        let model = moderation_action::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            moderator_id: Set(moderator_id),
+            kind: Set(request.kind.into()),
+            reason: Set(request.reason),
+            report_id: Set(request.report_id),
+            created_at: Set(created_at),
+            expires_at: Set(expiry_for(created_at, request.duration_hours)),
+            revoked_at: Set(None),
+            revoked_by: Set(None),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    /// Lift an action before it expires.
+    pub async fn revoke_action(
+        db: &DatabaseConnection,
+        action_id: Uuid,
+        moderator_id: Uuid,
+    ) -> Result<moderation_action::Model, ApiError> {
+        let action = moderation_action::Entity::find_by_id(action_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Moderation action {}", action_id)))?;
+
+        if action.revoked_at.is_some() {
+            return Ok(action);
+        }
+
+        let mut active: moderation_action::ActiveModel = action.into();
+        active.revoked_at = Set(Some(now()));
+        active.revoked_by = Set(Some(moderator_id));
+
+        Ok(active.update(db).await?)
+    }
+
+    /// Full action history for a player, newest first.
+    pub async fn list_actions(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+    ) -> Result<Vec<moderation_action::Model>, ApiError> {
+        Ok(moderation_action::Entity::find()
+            .filter(moderation_action::Column::PlayerId.eq(player_id))
+            .order_by(moderation_action::Column::CreatedAt, Order::Desc)
+            .all(db)
+            .await?)
+    }
+
+    /// The action of `kind` currently in force for a player, if any. Actions
+    /// on accounts marked as the same person count too.
+    pub async fn active_action(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        kind: ActionKind,
+    ) -> Result<Option<moderation_action::Model>, ApiError> {
+        let current = now();
+        let mut players = LinkService::linked_ids(db, player_id).await?;
+        players.push(player_id);
+        let actions = moderation_action::Entity::find()
+            .filter(moderation_action::Column::PlayerId.is_in(players))
+            .filter(moderation_action::Column::Kind.eq(kind))
+            .filter(moderation_action::Column::RevokedAt.is_null())
+            .order_by(moderation_action::Column::CreatedAt, Order::Desc)
+            .all(db)
+            .await?;
+
+        Ok(actions.into_iter().find(|a| a.is_active_at(current)))
+    }
+
+    /// Reject banned accounts with a message that says until when.
+    pub async fn ensure_not_banned(db: &DatabaseConnection, player_id: Uuid) -> Result<(), ApiError> {
+        match Self::active_action(db, player_id, ActionKind::Ban).await? {
+            Some(ban) => Err(ApiError::Forbidden(ban_message(&ban))),
+            None => Ok(()),
+        }
+    }
+
+    /// A player by ID, closed accounts included.
+    pub async fn find_player(db: &DatabaseConnection, player_id: Uuid) -> Result<player::Model, ApiError> {
+        player::Entity::find_by_id(player_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Player {}", player_id)))
+    }
+
+    async fn ensure_player_exists(db: &DatabaseConnection, player_id: Uuid) -> Result<(), ApiError> {
+        Self::find_player(db, player_id).await.map(|_| ())
+    }
+
+    async fn find_game(db: &DatabaseConnection, game_id: Uuid) -> Result<game::Model, ApiError> {
+        game::Entity::find_by_id(game_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Game {}", game_id)))
+    }
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+fn is_participant(game: &game::Model, player_id: Uuid) -> bool {
+    game.white_player == player_id || game.black_player == player_id
+}
+
+/// Admins hold every role; everyone else needs the role itself.
+pub fn roles_satisfy(roles: &[Role], required: Role) -> bool {
+    roles.iter().any(|r| *r == required || *r == Role::Admin)
+}
+
+/// Expiry for an action created at `created_at`; `None` duration means permanent.
+pub fn expiry_for(created_at: DateTime<FixedOffset>, duration_hours: Option<i64>) -> Option<DateTime<FixedOffset>> {
+    duration_hours.map(|hours| created_at + Duration::hours(hours))
+}
+
+fn ban_message(ban: &moderation_action::Model) -> String {
+    match ban.expires_at {
+        Some(expires_at) => format!("Account is banned until {}", expires_at.to_rfc3339()),
+        None => "Account is permanently banned".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(expires_at: Option<DateTime<FixedOffset>>) -> moderation_action::Model {
+        moderation_action::Model {
+            id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            moderator_id: Uuid::new_v4(),
+            kind: ActionKind::Ban,
+            reason: "abuse".to_string(),
+            report_id: None,
+            created_at: now(),
+            expires_at,
+            revoked_at: None,
+            revoked_by: None,
+        }
+    }
+
+    #[test]
+    fn test_admin_satisfies_every_role() {
+        assert!(roles_satisfy(&[Role::Admin], Role::Moderator));
+        assert!(roles_satisfy(&[Role::Moderator], Role::Moderator));
+        assert!(!roles_satisfy(&[Role::Arbiter], Role::Moderator));
+        assert!(!roles_satisfy(&[], Role::Arbiter));
+    }
+
+    #[test]
+    fn test_action_expiry() {
+        let created = now();
+        assert_eq!(expiry_for(created, None), None);
+        assert_eq!(expiry_for(created, Some(24)), Some(created + Duration::hours(24)));
+
+        let temporary = action(expiry_for(created, Some(1)));
+        assert!(temporary.is_active_at(created));
+        assert!(!temporary.is_active_at(created + Duration::hours(2)));
+
+        let mut permanent = action(None);
+        assert!(permanent.is_active_at(created + Duration::days(3650)));
+        permanent.revoked_at = Some(created);
+        assert!(!permanent.is_active_at(created));
+    }
+}
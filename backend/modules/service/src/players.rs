@@ -108,6 +108,9 @@ pub async fn update_player(id: Uuid, payload: UpdatePlayer) -> Result<player::Mo
     if let Some(fide_rating) = payload.fide_rating {
         active_model.fide_rating = Set(Some(fide_rating));
     }
+    if let Some(fide_id) = payload.fide_id {
+        active_model.fide_id = Set(Some(fide_id));
+    }
     if let Some(social_links) = payload.social_links {
         active_model.social_links = Set(Some(social_links));
     }
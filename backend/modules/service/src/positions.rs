@@ -0,0 +1,284 @@
+use chess::odds::PASS;
+use chess::{Material, Referee};
+use chrono::Utc;
+use db_entity::game::{self, GameVariant, ResultSide};
+use db_entity::{game_position, indexed_game, player, rating_history};
+use dto::games::GameResult;
+use dto::search::{PositionMatch, PositionSearchQuery};
+use error::error::ApiError;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::annotations::{headers_of, moves_of};
+use crate::replay::start_board;
+
+/// Finished games the background indexer reads per pass
+pub const INDEX_BATCH: u64 = 200;
+
+/// Most games one position search page may hold
+pub const MAX_POSITION_PAGE: u64 = 50;
+
+/// Results of the games that are indexed
+const INDEXED_RESULTS: [ResultSide; 3] = [ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw];
+
+const MAX_NAME_LEN: usize = 100;
+
+pub struct PositionIndexService;
+
+impl PositionIndexService {
+    /// Index up to `limit` finished games that are not indexed yet, oldest
+    /// first, each in its own transaction. Returns how many were indexed.
+    pub async fn index_pending(db: &DatabaseConnection, limit: u64) -> Result<usize, ApiError> {
+        let games = game::Entity::find()
+            .filter(game::Column::Result.is_in(INDEXED_RESULTS))
+            .filter(
+                game::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(indexed_game::Column::GameId)
+                        .from(indexed_game::Entity)
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(game::Column::UpdatedAt)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        for game in &games {
+            let txn = db.begin().await?;
+            Self::index_game(&txn, game).await?;
+            txn.commit().await?;
+        }
+        Ok(games.len())
+    }
+
+    /// Store every position a finished game reached. Positions after a move
+    /// that cannot be replayed are left out, as are all positions of
+    /// variants that cannot be replayed; the game is still marked indexed.
+    pub async fn index_game<C: ConnectionTrait>(db: &C, game: &game::Model) -> Result<(), ApiError> {
+        let Some(result) = game.result.clone().filter(|result| INDEXED_RESULTS.contains(result)) else {
+            return Ok(());
+        };
+
+        let usernames: HashMap<Uuid, String> = player::Entity::find()
+            .filter(player::Column::Id.is_in([game.white_player, game.black_player]))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|player| (player.id, player.username))
+            .collect();
+        let name = |id: &Uuid| usernames.get(id).cloned().unwrap_or_else(|| "?".to_string());
+        let headers = headers_of(game, name(&game.white_player), name(&game.black_player));
+
+        // Imported games carry the ratings in their tags, games played here
+        // in the rating history
+        let (white_rating, black_rating) = if game.is_imported {
+            let elo = |tag: &str| headers.other.get(tag).and_then(|value| value.trim().parse().ok());
+            (elo("WhiteElo"), elo("BlackElo"))
+        } else {
+            let ratings: HashMap<Uuid, i32> = rating_history::Entity::find()
+                .filter(rating_history::Column::GameId.eq(game.id))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|point| (point.player_id, point.rating))
+                .collect();
+            (ratings.get(&game.white_player).copied(), ratings.get(&game.black_player).copied())
+        };
+
+        indexed_game::ActiveModel {
+            game_id: Set(game.id),
+            white_name: Set(headers.white.chars().take(MAX_NAME_LEN).collect()),
+            black_name: Set(headers.black.chars().take(MAX_NAME_LEN).collect()),
+            white_rating: Set(white_rating),
+            black_rating: Set(black_rating),
+            result: Set(result),
+            played_at: Set(game.started_at),
+            indexed_at: Set(Utc::now().fixed_offset()),
+        }
+        .insert(db)
+        .await?;
+
+        let positions = positions_of(game);
+        if !positions.is_empty() {
+            game_position::Entity::insert_many(positions.into_iter().map(|(ply, zobrist, material)| {
+                game_position::ActiveModel {
+                    game_id: Set(game.id),
+                    ply: Set(ply as i32),
+                    zobrist: Set(zobrist as i64),
+                    material: Set(material.key() as i64),
+                }
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Drop what is indexed of `game` and index it again, for games whose
+    /// moves or players were corrected after they were indexed.
+    pub async fn reindex_game(db: &DatabaseConnection, game: &game::Model) -> Result<(), ApiError> {
+        let txn = db.begin().await?;
+        game_position::Entity::delete_many()
+            .filter(game_position::Column::GameId.eq(game.id))
+            .exec(&txn)
+            .await?;
+        indexed_game::Entity::delete_many()
+            .filter(indexed_game::Column::GameId.eq(game.id))
+            .exec(&txn)
+            .await?;
+        Self::index_game(&txn, game).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Indexed games that reached a position or a material balance, newest
+    /// first. Returns the page and the offset of the next one, if any.
+    pub async fn search(
+        db: &DatabaseConnection,
+        query: &PositionSearchQuery,
+    ) -> Result<(Vec<PositionMatch>, Option<u64>), ApiError> {
+        let zobrist = match query.fen.as_deref() {
+            Some(fen) => Some(Referee::from_fen(fen).map_err(|err| ApiError::BadRequest(err.to_string()))?.zobrist()),
+            None => None,
+        };
+        let material = match query.material.as_deref() {
+            Some(signature) => Some(
+                Material::from_signature(signature.trim())
+                    .ok_or_else(|| ApiError::BadRequest(format!("Invalid material '{}'", signature)))?,
+            ),
+            None => None,
+        };
+        if zobrist.is_none() && material.is_none() {
+            return Err(ApiError::BadRequest("Give a position in `fen` or a `material` balance".to_string()));
+        }
+        if query.result == Some(GameResult::InProgress) {
+            return Err(ApiError::BadRequest("Only finished games are indexed".to_string()));
+        }
+
+        let limit = query.limit.unwrap_or(20).clamp(1, MAX_POSITION_PAGE);
+        let offset = query.offset.unwrap_or(0);
+
+        let mut select = game_position::Entity::find()
+            .select_only()
+            .column(game_position::Column::GameId)
+            .column_as(Expr::col((game_position::Entity, game_position::Column::Ply)).min(), "ply")
+            .inner_join(indexed_game::Entity);
+        if let Some(zobrist) = zobrist {
+            select = select.filter(game_position::Column::Zobrist.eq(zobrist as i64));
+        }
+        if let Some(material) = material {
+            select = select.filter(game_position::Column::Material.eq(material.key() as i64));
+        }
+        if let Some(result) = query.result {
+            select = select.filter(indexed_game::Column::Result.eq(ResultSide::from(result)));
+        }
+        if let Some(min_rating) = query.min_rating {
+            select = select
+                .filter(indexed_game::Column::WhiteRating.gte(min_rating))
+                .filter(indexed_game::Column::BlackRating.gte(min_rating));
+        }
+
+        // One game more than asked tells whether there is a next page
+        let hits: Vec<(Uuid, i32)> = select
+            .group_by(game_position::Column::GameId)
+            .group_by(indexed_game::Column::PlayedAt)
+            .order_by_desc(indexed_game::Column::PlayedAt)
+            .order_by_desc(game_position::Column::GameId)
+            .limit(limit + 1)
+            .offset(offset)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let next_offset = (hits.len() as u64 > limit).then_some(offset + limit);
+
+        let hits: Vec<(Uuid, i32)> = hits.into_iter().take(limit as usize).collect();
+        let mut games: HashMap<Uuid, indexed_game::Model> = indexed_game::Entity::find()
+            .filter(indexed_game::Column::GameId.is_in(hits.iter().map(|(id, _)| *id)))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|game| (game.game_id, game))
+            .collect();
+        let matches = hits
+            .into_iter()
+            .filter_map(|(id, ply)| games.remove(&id).map(|game| PositionMatch::new(game, ply)))
+            .collect();
+        Ok((matches, next_offset))
+    }
+}
+
+/// Ply, Zobrist hash and material of every position `game` reached, from
+/// the starting position on, stopping at the first move that cannot be
+/// played.
+pub fn positions_of(game: &game::Model) -> Vec<(usize, u64, Material)> {
+    if matches!(game.variant, GameVariant::Chess960 | GameVariant::ThreeCheck) {
+        return Vec::new();
+    }
+    let (Ok(mut board), Ok(moves)) = (start_board(game), moves_of(game)) else {
+        return Vec::new();
+    };
+
+    let mut positions = vec![(0, board.zobrist(), board.material())];
+    for (index, notation) in moves.iter().enumerate() {
+        // Skipped turns of odds games are replayed by the referee itself
+        if notation == PASS {
+            continue;
+        }
+        if board.play_uci(notation).or_else(|_| board.play_san(notation)).is_err() {
+            break;
+        }
+        positions.push((index + 1, board.zobrist(), board.material()));
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn game(moves: &[&str]) -> game::Model {
+        let now = Utc::now().fixed_offset();
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: String::new(),
+            pgn: json!({ "moves": moves }),
+            result: Some(ResultSide::Draw),
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 600,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    #[test]
+    fn test_transpositions_share_a_hash() {
+        let french = positions_of(&game(&["e4", "e6", "d4", "d5"]));
+        let transposed = positions_of(&game(&["d4", "e6", "e4", "d5"]));
+        assert_eq!(french.len(), 5);
+        assert_eq!(french[4].1, transposed[4].1);
+        assert_ne!(french[2].1, transposed[2].1);
+
+        let searched = Referee::from_fen("rnbqkbnr/ppp2ppp/4p3/3p4/3PP3/8/PPP2PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+        assert_eq!(searched.zobrist(), french[4].1);
+    }
+
+    #[test]
+    fn test_positions_stop_at_unplayable_move() {
+        let positions = positions_of(&game(&["e4", "e5", "Ke3", "Nf6"]));
+        assert_eq!(positions.iter().map(|(ply, _, _)| *ply).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(positions[2].2, Material::from_signature("QRRBBNNPPPPPPPPqrrbbnnpppppppp").unwrap());
+    }
+}
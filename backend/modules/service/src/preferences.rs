@@ -0,0 +1,83 @@
+use chrono::Utc;
+use db_entity::player_preferences;
+use dto::preferences::{PreferencesDisplay, UpdatePreferencesRequest};
+use error::error::ApiError;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set};
+use uuid::Uuid;
+
+pub struct PreferenceService;
+
+impl PreferenceService {
+    /// Settings of `player_id`, the defaults for those never changed.
+    pub async fn get<C: ConnectionTrait>(db: &C, player_id: Uuid) -> Result<PreferencesDisplay, ApiError> {
+        Ok(player_preferences::Entity::find_by_id(player_id)
+            .one(db)
+            .await?
+            .map(PreferencesDisplay::from)
+            .unwrap_or_default())
+    }
+
+    /// Apply `changes` to the settings of `player_id` and return them all.
+    pub async fn update(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        changes: UpdatePreferencesRequest,
+    ) -> Result<PreferencesDisplay, ApiError> {
+        let stored = player_preferences::Entity::find_by_id(player_id).one(db).await?;
+        let current = stored.clone().map(PreferencesDisplay::from).unwrap_or_default();
+        let merged = current.merge(&changes);
+
+        let exists = stored.is_some();
+        let mut row = match stored {
+            Some(stored) => stored.into_active_model(),
+            None => player_preferences::ActiveModel { player_id: Set(player_id), ..Default::default() },
+        };
+        row.auto_queen = Set(merged.auto_queen);
+        row.premove = Set(merged.premove);
+        row.board_theme = Set(merged.board_theme.as_str().to_string());
+        row.sound = Set(merged.sound);
+        row.zen_mode = Set(merged.zen_mode);
+        row.chat_audience = Set(merged.chat_audience.into());
+        row.chat_profanity_filter = Set(merged.chat_profanity_filter);
+        row.updated_at = Set(Utc::now().fixed_offset());
+        if exists {
+            row.update(db).await?;
+        } else {
+            row.insert(db).await?;
+        }
+
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dto::preferences::{BoardTheme, ChatAudience};
+
+    #[test]
+    fn test_merge_keeps_settings_left_out() {
+        let changes = UpdatePreferencesRequest {
+            auto_queen: Some(true),
+            board_theme: Some(BoardTheme::Marble),
+            chat_audience: Some(ChatAudience::Friends),
+            ..Default::default()
+        };
+        let merged = PreferencesDisplay::default().merge(&changes);
+
+        assert!(merged.auto_queen);
+        assert_eq!(merged.board_theme, BoardTheme::Marble);
+        assert_eq!(merged.chat_audience, ChatAudience::Friends);
+        assert!(merged.premove);
+        assert!(merged.sound);
+        assert!(!merged.zen_mode);
+        assert!(merged.chat_profanity_filter);
+    }
+
+    #[test]
+    fn test_unknown_settings_are_rejected() {
+        assert!(serde_json::from_str::<UpdatePreferencesRequest>(r#"{"auto_queen": true}"#).is_ok());
+        assert!(serde_json::from_str::<UpdatePreferencesRequest>(r#"{"autoqueen": true}"#).is_err());
+        assert!(serde_json::from_str::<UpdatePreferencesRequest>(r#"{"board_theme": "neon"}"#).is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entity::{
+    game, player_rating, rating_history,
+    player_rating::RatingCategory,
+};
+use error::error::ApiError;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, Order,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::disputes::white_score;
+
+/// Conversion factor between the Glicko and Glicko-2 scales.
+pub const GLICKO2_SCALE: f64 = 173.7178;
+
+pub const DEFAULT_RATING: i32 = 1500;
+pub const DEFAULT_RATING_DEVIATION: f64 = 350.0;
+pub const DEFAULT_VOLATILITY: f64 = 0.06;
+
+/// Glicko-2 system constant τ, constraining how fast volatility changes.
+pub const SYSTEM_TAU: f64 = 0.5;
+
+/// Deviation never grows past that of an unrated player.
+pub const MAX_RATING_DEVIATION: f64 = DEFAULT_RATING_DEVIATION;
+
+/// Upper bound on a single history request.
+pub const MAX_HISTORY_POINTS: u64 = 2000;
+
+/// Rating state after a game, as produced by the rating calculation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatingUpdate {
+    pub rating: i32,
+    pub rating_deviation: f64,
+    pub volatility: f64,
+}
+
+impl Default for RatingUpdate {
+    /// The rating of a player without rated games.
+    fn default() -> Self {
+        Self {
+            rating: DEFAULT_RATING,
+            rating_deviation: DEFAULT_RATING_DEVIATION,
+            volatility: DEFAULT_VOLATILITY,
+        }
+    }
+}
+
+/// A game of a rating period: the opponent's rating going into it and the
+/// points scored against them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatedResult {
+    pub opponent_rating: i32,
+    pub opponent_deviation: f64,
+    pub score: f64,
+}
+
+pub struct RatingService;
+
+impl RatingService {
+    /// Store a player's new rating and append a history point.
+    pub async fn record_rated_game<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        player_id: Uuid,
+        category: RatingCategory,
+        game_id: Option<Uuid>,
+        update: RatingUpdate,
+    ) -> Result<rating_history::Model, ApiError> {
+        let now = Utc::now().fixed_offset();
+        let txn = db.begin().await?;
+
+        let existing = player_rating::Entity::find_by_id((player_id, category))
+            .one(&txn)
+            .await?;
+
+        match existing {
+            Some(current) => {
+                let games_played = current.games_played + 1;
+                let mut active: player_rating::ActiveModel = current.into();
+                active.rating = Set(update.rating);
+                active.rating_deviation = Set(update.rating_deviation);
+                active.volatility = Set(update.volatility);
+                active.games_played = Set(games_played);
+                active.updated_at = Set(now);
+                active.last_rated_at = Set(Some(now));
+                active.rd_updated_at = Set(now);
+                active.update(&txn).await?;
+            }
+            None => {
+                player_rating::ActiveModel {
+                    player_id: Set(player_id),
+                    category: Set(category),
+                    rating: Set(update.rating),
+                    rating_deviation: Set(update.rating_deviation),
+                    volatility: Set(update.volatility),
+                    games_played: Set(1),
+                    updated_at: Set(now),
+                    last_rated_at: Set(Some(now)),
+                    rd_updated_at: Set(now),
+                }
+                .insert(&txn)
+                .await?;
+            }
+        }
+
+        let point = rating_history::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            category: Set(category),
+            game_id: Set(game_id),
+            rating: Set(update.rating),
+            rating_deviation: Set(update.rating_deviation),
+            volatility: Set(Some(update.volatility)),
+            recorded_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok(point)
+    }
+
+    /// Rate a finished game for both players, each against the rating the
+    /// other had going into it, and record a history point for each.
+    /// Returns the ratings White and Black had before the game; games
+    /// without a decisive or drawn result are not rated.
+    pub async fn record_game<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        game: &game::Model,
+    ) -> Result<Option<(RatingUpdate, RatingUpdate)>, ApiError> {
+        let Some(score) = game.result.as_ref().and_then(white_score) else {
+            return Ok(None);
+        };
+        let category = category_of(game);
+        let white = Self::rating_of(db, game.white_player, category).await?;
+        let black = Self::rating_of(db, game.black_player, category).await?;
+
+        let against = |opponent: RatingUpdate, score| RatedResult {
+            opponent_rating: opponent.rating,
+            opponent_deviation: opponent.rating_deviation,
+            score,
+        };
+        let white_after = rate_period(white, &[against(black, score)]);
+        let black_after = rate_period(black, &[against(white, 1.0 - score)]);
+        Self::record_rated_game(db, game.white_player, category, Some(game.id), white_after).await?;
+        Self::record_rated_game(db, game.black_player, category, Some(game.id), black_after).await?;
+        Ok(Some((white, black)))
+    }
+
+    /// A player's rating in one time control; the default rating when they
+    /// have not played it.
+    async fn rating_of<C: ConnectionTrait>(db: &C, player_id: Uuid, category: RatingCategory) -> Result<RatingUpdate, ApiError> {
+        Ok(player_rating::Entity::find_by_id((player_id, category))
+            .one(db)
+            .await?
+            .map_or_else(RatingUpdate::default, |current| RatingUpdate {
+                rating: current.rating,
+                rating_deviation: current.rating_deviation,
+                volatility: current.volatility,
+            }))
+    }
+
+    /// A player's current ratings, one per time control they have played.
+    pub async fn current(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<player_rating::Model>, ApiError> {
+        Ok(player_rating::Entity::find()
+            .filter(player_rating::Column::PlayerId.eq(player_id))
+            .all(db)
+            .await?)
+    }
+
+    /// Rating points for one player and time control, oldest first.
+    pub async fn history(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        category: RatingCategory,
+        from: Option<DateTime<FixedOffset>>,
+        to: Option<DateTime<FixedOffset>>,
+        limit: u64,
+    ) -> Result<Vec<rating_history::Model>, ApiError> {
+        let mut query = rating_history::Entity::find()
+            .filter(rating_history::Column::PlayerId.eq(player_id))
+            .filter(rating_history::Column::Category.eq(category));
+
+        if let Some(from) = from {
+            query = query.filter(rating_history::Column::RecordedAt.gte(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(rating_history::Column::RecordedAt.lte(to));
+        }
+
+        Ok(query
+            .order_by(rating_history::Column::RecordedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_HISTORY_POINTS))
+            .all(db)
+            .await?)
+    }
+
+    /// Grow the deviation of players who sat out whole rating periods.
+    /// Returns the number of ratings touched.
+    pub async fn apply_inactivity_decay(
+        db: &DatabaseConnection,
+        now: DateTime<FixedOffset>,
+        period: Duration,
+    ) -> Result<usize, ApiError> {
+        if period <= Duration::zero() {
+            return Ok(0);
+        }
+
+        let stale = player_rating::Entity::find()
+            .filter(player_rating::Column::LastRatedAt.is_not_null())
+            .filter(player_rating::Column::RdUpdatedAt.lte(now - period))
+            .filter(player_rating::Column::RatingDeviation.lt(MAX_RATING_DEVIATION))
+            .all(db)
+            .await?;
+
+        let mut touched = 0;
+        for rating in stale {
+            let periods = elapsed_periods(rating.rd_updated_at, now, period);
+            if periods == 0 {
+                continue;
+            }
+
+            let rating_deviation = inflate_deviation(rating.rating_deviation, rating.volatility, periods);
+            let rd_updated_at = rating.rd_updated_at + period * periods as i32;
+
+            let mut active: player_rating::ActiveModel = rating.into();
+            active.rating_deviation = Set(rating_deviation);
+            active.rd_updated_at = Set(rd_updated_at);
+            active.update(db).await?;
+            touched += 1;
+        }
+
+        Ok(touched)
+    }
+
+    /// Start a new season: raise every deviation to at least `reset_rd` so
+    /// ratings move faster again, without touching the ratings themselves.
+    pub async fn season_reset(
+        db: &DatabaseConnection,
+        category: Option<RatingCategory>,
+        reset_rd: f64,
+    ) -> Result<u64, ApiError> {
+        let reset_rd = reset_rd.min(MAX_RATING_DEVIATION);
+        let mut update = player_rating::Entity::update_many()
+            .col_expr(
+                player_rating::Column::RatingDeviation,
+                Expr::cust_with_values("GREATEST(\"rating_deviation\", $1)", [reset_rd]),
+            )
+            .col_expr(player_rating::Column::RdUpdatedAt, Expr::value(Utc::now().fixed_offset()));
+
+        if let Some(category) = category {
+            update = update.filter(player_rating::Column::Category.eq(category));
+        }
+
+        Ok(update.exec(db).await?.rows_affected)
+    }
+}
+
+/// Rating pool a game counts towards, by the time each side has.
+pub fn category_of(game: &game::Model) -> RatingCategory {
+    match game.duration_sec {
+        ..=179 => RatingCategory::Bullet,
+        180..=479 => RatingCategory::Blitz,
+        480..=1_499 => RatingCategory::Rapid,
+        _ => RatingCategory::Classical,
+    }
+}
+
+/// Number of whole rating periods between `since` and `now`.
+pub fn elapsed_periods(since: DateTime<FixedOffset>, now: DateTime<FixedOffset>, period: Duration) -> i64 {
+    let elapsed = now - since;
+    if elapsed < period || period <= Duration::zero() {
+        return 0;
+    }
+    elapsed.num_seconds() / period.num_seconds().max(1)
+}
+
+/// Glicko-2 step 6 applied for rating periods without games:
+/// φ* = sqrt(φ² + t·σ²), capped at the deviation of an unrated player.
+pub fn inflate_deviation(rating_deviation: f64, volatility: f64, periods: i64) -> f64 {
+    if periods <= 0 {
+        return rating_deviation;
+    }
+    let phi = rating_deviation / GLICKO2_SCALE;
+    let phi_star = (phi * phi + periods as f64 * volatility * volatility).sqrt();
+    (phi_star * GLICKO2_SCALE).min(MAX_RATING_DEVIATION)
+}
+
+/// Glicko-2 steps 2 to 8: the rating after a period with `results`. A
+/// period without games only grows the deviation.
+pub fn rate_period(player: RatingUpdate, results: &[RatedResult]) -> RatingUpdate {
+    let mu = (player.rating - DEFAULT_RATING) as f64 / GLICKO2_SCALE;
+    let phi = player.rating_deviation / GLICKO2_SCALE;
+    if results.is_empty() {
+        return RatingUpdate {
+            rating_deviation: inflate_deviation(player.rating_deviation, player.volatility, 1),
+            ..player
+        };
+    }
+
+    let games: Vec<(f64, f64, f64)> = results
+        .iter()
+        .map(|r| {
+            let mu_j = (r.opponent_rating - DEFAULT_RATING) as f64 / GLICKO2_SCALE;
+            let g = g_factor(r.opponent_deviation / GLICKO2_SCALE);
+            let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+            (g, expected, r.score)
+        })
+        .collect();
+
+    let v = 1.0 / games.iter().map(|(g, e, _)| g * g * e * (1.0 - e)).sum::<f64>();
+    let improvement: f64 = games.iter().map(|(g, e, s)| g * (s - e)).sum();
+    let delta = v * improvement;
+
+    let sigma = new_volatility(phi, player.volatility, v, delta);
+    let phi_star = (phi * phi + sigma * sigma).sqrt();
+    let phi_new = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let mu_new = mu + phi_new * phi_new * improvement;
+
+    RatingUpdate {
+        rating: (mu_new * GLICKO2_SCALE).round() as i32 + DEFAULT_RATING,
+        rating_deviation: (phi_new * GLICKO2_SCALE).min(MAX_RATING_DEVIATION),
+        volatility: sigma,
+    }
+}
+
+fn g_factor(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (std::f64::consts::PI * std::f64::consts::PI)).sqrt()
+}
+
+/// Glicko-2 step 5, solved with the Illinois algorithm.
+fn new_volatility(phi: f64, sigma: f64, v: f64, delta: f64) -> f64 {
+    const EPSILON: f64 = 1e-6;
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+            - (x - a) / (SYSTEM_TAU * SYSTEM_TAU)
+    };
+
+    let mut low = a;
+    let mut high = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * SYSTEM_TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * SYSTEM_TAU
+    };
+
+    let (mut f_low, mut f_high) = (f(low), f(high));
+    while (high - low).abs() > EPSILON {
+        let c = low + (low - high) * f_low / (f_high - f_low);
+        let f_c = f(c);
+        if f_c * f_high <= 0.0 {
+            low = high;
+            f_low = f_high;
+        } else {
+            f_low /= 2.0;
+        }
+        high = c;
+        f_high = f_c;
+    }
+    (low / 2.0).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflate_deviation_matches_glicko2() {
+        // One idle period with σ = 0.06 takes RD 50 to about 51.07
+        let rd = inflate_deviation(50.0, 0.06, 1);
+        assert!((rd - 51.07).abs() < 0.01, "got {}", rd);
+
+        // Several periods compound the same as applying them one by one
+        let stepwise = (0..3).fold(50.0, |rd, _| inflate_deviation(rd, 0.06, 1));
+        assert!((inflate_deviation(50.0, 0.06, 3) - stepwise).abs() < 1e-9);
+
+        assert_eq!(inflate_deviation(340.0, 0.06, 1000), MAX_RATING_DEVIATION);
+        assert_eq!(inflate_deviation(80.0, 0.06, 0), 80.0);
+    }
+
+    #[test]
+    fn test_rate_period_matches_glickman_example() {
+        // The worked example of the Glicko-2 paper
+        let player = RatingUpdate { rating: 1500, rating_deviation: 200.0, volatility: 0.06 };
+        let results = [
+            RatedResult { opponent_rating: 1400, opponent_deviation: 30.0, score: 1.0 },
+            RatedResult { opponent_rating: 1550, opponent_deviation: 100.0, score: 0.0 },
+            RatedResult { opponent_rating: 1700, opponent_deviation: 300.0, score: 0.0 },
+        ];
+        let rated = rate_period(player, &results);
+        assert_eq!(rated.rating, 1464);
+        assert!((rated.rating_deviation - 151.52).abs() < 0.01, "got {}", rated.rating_deviation);
+        assert!((rated.volatility - 0.05999).abs() < 0.00001, "got {}", rated.volatility);
+
+        // Without games only the deviation moves
+        let idle = rate_period(player, &[]);
+        assert_eq!(idle.rating, 1500);
+        assert!(idle.rating_deviation > 200.0);
+    }
+
+    #[test]
+    fn test_elapsed_periods() {
+        let start = Utc::now().fixed_offset();
+        let week = Duration::days(7);
+        assert_eq!(elapsed_periods(start, start + Duration::days(6), week), 0);
+        assert_eq!(elapsed_periods(start, start + Duration::days(7), week), 1);
+        assert_eq!(elapsed_periods(start, start + Duration::days(22), week), 3);
+    }
+}
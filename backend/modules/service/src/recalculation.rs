@@ -0,0 +1,543 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{
+    game, game_archive, player_rating, rating_history, rating_recalculation,
+    game::ResultSide,
+    player_rating::RatingCategory,
+    rating_recalculation::RecalculationStatus,
+};
+use dto::ratings::RatingAuditEntry;
+use error::error::ApiError;
+use sea_orm::{
+    sea_query::Expr, ActiveEnum, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::disputes::white_score;
+use crate::game_archive::GameArchiveService;
+use crate::rating::{rate_period, RatedResult, RatingUpdate, DEFAULT_VOLATILITY};
+
+/// A rated game as the replay sees it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedGame {
+    pub game_id: Uuid,
+    pub category: RatingCategory,
+    /// When the game was rated
+    pub played_at: DateTime<FixedOffset>,
+    pub white: Uuid,
+    pub black: Uuid,
+    /// Points white scored; `None` for games voided since
+    pub white_score: Option<f64>,
+    /// Ratings going into the game as recorded, used for players outside the replay
+    pub white_before: RatingUpdate,
+    pub black_before: RatingUpdate,
+}
+
+/// The rating one replayed player got from one game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedGame {
+    pub game_id: Uuid,
+    pub player_id: Uuid,
+    pub category: RatingCategory,
+    pub played_at: DateTime<FixedOffset>,
+    /// `None` when the game no longer counts
+    pub rating: Option<RatingUpdate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub games: Vec<ReplayedGame>,
+    /// Rating of each replayed player once every game is replayed
+    pub ratings: HashMap<(Uuid, RatingCategory), RatingUpdate>,
+}
+
+pub struct RecalculationService;
+
+impl RecalculationService {
+    /// Queue a replay of the rated games `players` played from `since` on. A
+    /// recalculation still waiting in the queue absorbs the new request.
+    pub async fn enqueue<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        players: Vec<Uuid>,
+        since: DateTime<FixedOffset>,
+        reason: String,
+        requested_by: Option<Uuid>,
+    ) -> Result<rating_recalculation::Model, ApiError> {
+        let now = Utc::now().fixed_offset();
+        let txn = db.begin().await?;
+
+        let queued = rating_recalculation::Entity::find()
+            .filter(rating_recalculation::Column::Status.eq(RecalculationStatus::Queued))
+            .order_by_asc(rating_recalculation::Column::CreatedAt)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+
+        let model = match queued {
+            Some(queued) => {
+                let mut merged = player_ids_of(&queued);
+                for player in players {
+                    if !merged.contains(&player) {
+                        merged.push(player);
+                    }
+                }
+                let since = since.min(queued.since);
+                let reason = format!("{}; {}", queued.reason, reason);
+
+                let mut active: rating_recalculation::ActiveModel = queued.into();
+                active.player_ids = Set(serde_json::json!(merged));
+                active.since = Set(since);
+                active.reason = Set(reason);
+                active.update(&txn).await?
+            }
+            None => {
+                let mut unique = Vec::new();
+                for player in players {
+                    if !unique.contains(&player) {
+                        unique.push(player);
+                    }
+                }
+                rating_recalculation::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    reason: Set(reason),
+                    player_ids: Set(serde_json::json!(unique)),
+                    since: Set(since),
+                    status: Set(RecalculationStatus::Queued),
+                    audit: Set(None),
+                    error: Set(None),
+                    requested_by: Set(requested_by),
+                    created_at: Set(now),
+                    finished_at: Set(None),
+                }
+                .insert(&txn)
+                .await?
+            }
+        };
+
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    pub async fn get(db: &DatabaseConnection, id: Uuid) -> Result<rating_recalculation::Model, ApiError> {
+        rating_recalculation::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Recalculation {}", id)))
+    }
+
+    /// Void the finished games `player_id` started from `since` on, archived
+    /// ones included, and queue a recalculation for them and everyone they
+    /// played.
+    pub async fn void_games(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        since: DateTime<FixedOffset>,
+        reason: String,
+        requested_by: Option<Uuid>,
+    ) -> Result<rating_recalculation::Model, ApiError> {
+        let games = game::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(game::Column::WhitePlayer.eq(player_id))
+                    .add(game::Column::BlackPlayer.eq(player_id)),
+            )
+            .filter(game::Column::StartedAt.gte(since))
+            .filter(game::Column::Result.is_in([ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw]))
+            .all(db)
+            .await?;
+        let archived = game_archive::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(game_archive::Column::WhitePlayer.eq(player_id))
+                    .add(game_archive::Column::BlackPlayer.eq(player_id)),
+            )
+            .filter(game_archive::Column::StartedAt.gte(since))
+            .filter(game_archive::Column::Result.is_in([ResultSide::WhiteWins, ResultSide::BlackWins, ResultSide::Draw]))
+            .all(db)
+            .await?;
+        if games.is_empty() && archived.is_empty() {
+            return Err(ApiError::BadRequest("The player has no finished games to void".to_string()));
+        }
+
+        let mut players = vec![player_id];
+        players.extend(games.iter().flat_map(|g| [g.white_player, g.black_player]));
+        players.extend(archived.iter().flat_map(|g| [g.white_player, g.black_player]));
+
+        game::Entity::update_many()
+            .col_expr(game::Column::Result, ResultSide::Abandoned.into())
+            .col_expr(game::Column::UpdatedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(game::Column::Id.is_in(games.iter().map(|g| g.id)))
+            .exec(db)
+            .await?;
+        game_archive::Entity::update_many()
+            .col_expr(game_archive::Column::Result, ResultSide::Abandoned.into())
+            .col_expr(game_archive::Column::UpdatedAt, Expr::value(Utc::now().fixed_offset()))
+            .filter(game_archive::Column::Id.is_in(archived.iter().map(|g| g.id)))
+            .exec(db)
+            .await?;
+
+        let reason = format!("{} games of {} voided: {}", games.len() + archived.len(), player_id, reason);
+        Self::enqueue(db, players, since, reason, requested_by).await
+    }
+
+    /// Run the oldest queued recalculation. Returns its id, or `None` when
+    /// nothing is queued. The replay is applied in one transaction, so a
+    /// failed or interrupted run leaves the ratings untouched.
+    pub async fn run_next(db: &DatabaseConnection) -> Result<Option<Uuid>, ApiError> {
+        let txn = db.begin().await?;
+        let next = rating_recalculation::Entity::find()
+            .filter(rating_recalculation::Column::Status.eq(RecalculationStatus::Queued))
+            .order_by_asc(rating_recalculation::Column::CreatedAt)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        let Some(next) = next else {
+            return Ok(None);
+        };
+
+        let id = next.id;
+        match Self::apply(&txn, &next).await {
+            Ok(audit) => {
+                let mut active: rating_recalculation::ActiveModel = next.into();
+                active.status = Set(RecalculationStatus::Done);
+                active.audit = Set(Some(serde_json::to_value(audit).unwrap_or_default()));
+                active.finished_at = Set(Some(Utc::now().fixed_offset()));
+                active.update(&txn).await?;
+                txn.commit().await?;
+            }
+            Err(err) => {
+                txn.rollback().await?;
+                let mut active: rating_recalculation::ActiveModel = next.into();
+                active.status = Set(RecalculationStatus::Failed);
+                active.error = Set(Some(err.to_string()));
+                active.finished_at = Set(Some(Utc::now().fixed_offset()));
+                active.update(db).await?;
+            }
+        }
+        Ok(Some(id))
+    }
+
+    /// Replay the games of a recalculation and store the ratings it gives.
+    /// Rating points of replayed games are overwritten rather than appended,
+    /// so running the same recalculation again changes nothing.
+    async fn apply(
+        txn: &DatabaseTransaction,
+        job: &rating_recalculation::Model,
+    ) -> Result<Vec<RatingAuditEntry>, ApiError> {
+        let affected: HashSet<Uuid> = player_ids_of(job).into_iter().collect();
+
+        // Games the players were rated in since the checkpoint
+        let own_points = rating_history::Entity::find()
+            .filter(rating_history::Column::PlayerId.is_in(affected.iter().copied()))
+            .filter(rating_history::Column::RecordedAt.gte(job.since))
+            .filter(rating_history::Column::GameId.is_not_null())
+            .all(txn)
+            .await?;
+        let game_ids: HashSet<Uuid> = own_points.iter().filter_map(|p| p.game_id).collect();
+        if game_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let games = GameArchiveService::find_many(txn, game_ids.iter().copied()).await?;
+        let game_points = rating_history::Entity::find()
+            .filter(rating_history::Column::GameId.is_in(game_ids.iter().copied()))
+            .order_by_asc(rating_history::Column::RecordedAt)
+            .all(txn)
+            .await?;
+
+        // Everything before the last game, for checkpoints and opponents' ratings
+        let players: HashSet<Uuid> = game_points.iter().map(|p| p.player_id).chain(affected.iter().copied()).collect();
+        let last_played = game_points.iter().map(|p| p.recorded_at).max().unwrap_or(job.since);
+        let history = rating_history::Entity::find()
+            .filter(rating_history::Column::PlayerId.is_in(players))
+            .filter(rating_history::Column::RecordedAt.lte(last_played))
+            .order_by_asc(rating_history::Column::RecordedAt)
+            .all(txn)
+            .await?;
+
+        let recorded = recorded_games(&games, &game_points, &history);
+        let mut checkpoints = HashMap::new();
+        for game in &recorded {
+            for player in [game.white, game.black] {
+                if affected.contains(&player) {
+                    checkpoints
+                        .entry((player, game.category))
+                        .or_insert_with(|| rating_before(&history, player, game.category, job.since, None));
+                }
+            }
+        }
+
+        let replay = replay(&affected, &checkpoints, &recorded);
+        for replayed in &replay.games {
+            match replayed.rating {
+                Some(rating) => {
+                    rating_history::Entity::update_many()
+                        .col_expr(rating_history::Column::Rating, Expr::value(rating.rating))
+                        .col_expr(rating_history::Column::RatingDeviation, Expr::value(rating.rating_deviation))
+                        .col_expr(rating_history::Column::Volatility, Expr::value(rating.volatility))
+                        .filter(rating_history::Column::GameId.eq(replayed.game_id))
+                        .filter(rating_history::Column::PlayerId.eq(replayed.player_id))
+                        .exec(txn)
+                        .await?;
+                }
+                None => {
+                    rating_history::Entity::delete_many()
+                        .filter(rating_history::Column::GameId.eq(replayed.game_id))
+                        .filter(rating_history::Column::PlayerId.eq(replayed.player_id))
+                        .exec(txn)
+                        .await?;
+                }
+            }
+        }
+
+        let mut ratings: Vec<_> = replay.ratings.iter().collect();
+        ratings.sort_by_key(|((player, category), _)| (*player, category.to_value()));
+
+        let now = Utc::now().fixed_offset();
+        let mut audit = Vec::new();
+        for (&(player_id, category), rating) in ratings {
+            let Some(current) = player_rating::Entity::find_by_id((player_id, category))
+                .lock_exclusive()
+                .one(txn)
+                .await?
+            else {
+                continue;
+            };
+
+            let of_rating = replay.games.iter().filter(|g| g.player_id == player_id && g.category == category);
+            let games_replayed = of_rating.clone().filter(|g| g.rating.is_some()).count() as i32;
+            let games_voided = of_rating.clone().filter(|g| g.rating.is_none()).count() as i32;
+            let last_rated = of_rating.filter(|g| g.rating.is_some()).map(|g| g.played_at).max();
+
+            audit.push(RatingAuditEntry {
+                player_id,
+                time_control: category.into(),
+                rating_before: current.rating,
+                rating_deviation_before: current.rating_deviation,
+                rating_after: rating.rating,
+                rating_deviation_after: rating.rating_deviation,
+                games_replayed,
+                games_voided,
+            });
+
+            let games_played = (current.games_played - games_voided).max(0);
+            let rd_updated_at = last_rated.unwrap_or(current.rd_updated_at);
+            let mut active: player_rating::ActiveModel = current.into();
+            active.rating = Set(rating.rating);
+            active.rating_deviation = Set(rating.rating_deviation);
+            active.volatility = Set(rating.volatility);
+            active.games_played = Set(games_played);
+            active.rd_updated_at = Set(rd_updated_at);
+            active.updated_at = Set(now);
+            active.update(txn).await?;
+        }
+
+        Ok(audit)
+    }
+}
+
+fn player_ids_of(model: &rating_recalculation::Model) -> Vec<Uuid> {
+    serde_json::from_value(model.player_ids.clone()).unwrap_or_default()
+}
+
+/// The latest rating `player` had in `category` before `before`, leaving
+/// out the points of `game`; the default rating when there is none.
+pub fn rating_before(
+    history: &[rating_history::Model],
+    player: Uuid,
+    category: RatingCategory,
+    before: DateTime<FixedOffset>,
+    game: Option<Uuid>,
+) -> RatingUpdate {
+    history
+        .iter()
+        .filter(|p| p.player_id == player && p.category == category && p.recorded_at < before)
+        .filter(|p| game.is_none() || p.game_id != game)
+        .max_by_key(|p| p.recorded_at)
+        .map_or_else(RatingUpdate::default, |p| RatingUpdate {
+            rating: p.rating,
+            rating_deviation: p.rating_deviation,
+            volatility: p.volatility.unwrap_or(DEFAULT_VOLATILITY),
+        })
+}
+
+/// Rated games in the order they were rated. Games no longer stored are left out.
+pub fn recorded_games(
+    games: &[game::Model],
+    game_points: &[rating_history::Model],
+    history: &[rating_history::Model],
+) -> Vec<RecordedGame> {
+    let mut recorded: Vec<RecordedGame> = games
+        .iter()
+        .filter_map(|game| {
+            let first = game_points
+                .iter()
+                .filter(|p| p.game_id == Some(game.id))
+                .min_by_key(|p| p.recorded_at)?;
+            let before = |player| rating_before(history, player, first.category, first.recorded_at, Some(game.id));
+            Some(RecordedGame {
+                game_id: game.id,
+                category: first.category,
+                played_at: first.recorded_at,
+                white: game.white_player,
+                black: game.black_player,
+                white_score: game.result.as_ref().and_then(white_score),
+                white_before: before(game.white_player),
+                black_before: before(game.black_player),
+            })
+        })
+        .collect();
+    recorded.sort_by_key(|g| (g.played_at, g.game_id));
+    recorded
+}
+
+/// Rate `games` again, in order, for the `affected` players only, starting
+/// each from its checkpoint. Their opponents keep the ratings they were
+/// recorded with.
+pub fn replay(
+    affected: &HashSet<Uuid>,
+    checkpoints: &HashMap<(Uuid, RatingCategory), RatingUpdate>,
+    games: &[RecordedGame],
+) -> Replay {
+    let mut ratings = checkpoints.clone();
+    let mut replayed = Vec::new();
+
+    for game in games {
+        let going_in = |ratings: &HashMap<_, RatingUpdate>, player: Uuid, recorded: RatingUpdate| {
+            if affected.contains(&player) {
+                ratings.get(&(player, game.category)).copied().unwrap_or_default()
+            } else {
+                recorded
+            }
+        };
+        let white = going_in(&ratings, game.white, game.white_before);
+        let black = going_in(&ratings, game.black, game.black_before);
+
+        let sides = [
+            (game.white, white, black, game.white_score),
+            (game.black, black, white, game.white_score.map(|score| 1.0 - score)),
+        ];
+        for (player_id, own, opponent, score) in sides {
+            if !affected.contains(&player_id) {
+                continue;
+            }
+            let rating = score.map(|score| {
+                rate_period(
+                    own,
+                    &[RatedResult {
+                        opponent_rating: opponent.rating,
+                        opponent_deviation: opponent.rating_deviation,
+                        score,
+                    }],
+                )
+            });
+            ratings.insert((player_id, game.category), rating.unwrap_or(own));
+            replayed.push(ReplayedGame {
+                game_id: game.game_id,
+                player_id,
+                category: game.category,
+                played_at: game.played_at,
+                rating,
+            });
+        }
+    }
+
+    Replay { games: replayed, ratings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn game(white: Uuid, black: Uuid, minutes: i64, white_score: Option<f64>) -> RecordedGame {
+        RecordedGame {
+            game_id: Uuid::new_v4(),
+            category: RatingCategory::Blitz,
+            played_at: Utc::now().fixed_offset() + Duration::minutes(minutes),
+            white,
+            black,
+            white_score,
+            white_before: RatingUpdate { rating: 1620, rating_deviation: 80.0, volatility: 0.06 },
+            black_before: RatingUpdate { rating: 1580, rating_deviation: 90.0, volatility: 0.06 },
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_unchanged_results() {
+        let (player, first, second) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let games = [game(player, first, 0, Some(1.0)), game(second, player, 10, Some(0.5))];
+        let start = RatingUpdate { rating: 1500, rating_deviation: 120.0, volatility: 0.06 };
+
+        // The ratings as they were recorded when the games were played
+        let after_first = rate_period(
+            start,
+            &[RatedResult { opponent_rating: 1580, opponent_deviation: 90.0, score: 1.0 }],
+        );
+        let after_second = rate_period(
+            after_first,
+            &[RatedResult { opponent_rating: 1620, opponent_deviation: 80.0, score: 0.5 }],
+        );
+
+        let affected = HashSet::from([player]);
+        let checkpoints = HashMap::from([((player, RatingCategory::Blitz), start)]);
+        let replayed = replay(&affected, &checkpoints, &games);
+
+        assert_eq!(replayed.games.len(), 2);
+        assert_eq!(replayed.games[0].rating, Some(after_first));
+        assert_eq!(replayed.ratings[&(player, RatingCategory::Blitz)], after_second);
+
+        // Replaying again gives the very same ratings
+        assert_eq!(replay(&affected, &checkpoints, &games), replayed);
+    }
+
+    #[test]
+    fn test_replay_drops_voided_games() {
+        let (cheater, victim, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let start = RatingUpdate { rating: 1500, rating_deviation: 120.0, volatility: 0.06 };
+        let games = [game(cheater, victim, 0, None), game(victim, other, 10, Some(1.0))];
+
+        let affected = HashSet::from([cheater, victim]);
+        let checkpoints = HashMap::from([
+            ((cheater, RatingCategory::Blitz), start),
+            ((victim, RatingCategory::Blitz), start),
+        ]);
+        let replayed = replay(&affected, &checkpoints, &games);
+
+        // The voided game counts for neither player; the other one is replayed
+        let voided: Vec<_> = replayed.games.iter().filter(|g| g.rating.is_none()).collect();
+        assert_eq!(voided.len(), 2);
+        assert_eq!(replayed.ratings[&(cheater, RatingCategory::Blitz)], start);
+        assert!(replayed.ratings[&(victim, RatingCategory::Blitz)].rating > start.rating);
+
+        // Players outside the replay are never rated again
+        assert!(replayed.games.iter().all(|g| g.player_id != other));
+        assert!(!replayed.ratings.contains_key(&(other, RatingCategory::Blitz)));
+    }
+
+    #[test]
+    fn test_rating_before_skips_the_game_itself() {
+        let player = Uuid::new_v4();
+        let (earlier, game_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let at = Utc::now().fixed_offset();
+        let point = |game_id: Uuid, rating: i32, minutes: i64| rating_history::Model {
+            id: Uuid::new_v4(),
+            player_id: player,
+            category: RatingCategory::Blitz,
+            game_id: Some(game_id),
+            rating,
+            rating_deviation: 70.0,
+            volatility: None,
+            recorded_at: at + Duration::minutes(minutes),
+        };
+        let history = [point(earlier, 1540, 0), point(game_id, 1555, 5)];
+
+        let before = rating_before(&history, player, RatingCategory::Blitz, at + Duration::minutes(10), Some(game_id));
+        assert_eq!(before.rating, 1540);
+        assert_eq!(before.volatility, DEFAULT_VOLATILITY);
+
+        let unrated = rating_before(&history, player, RatingCategory::Bullet, at + Duration::minutes(10), None);
+        assert_eq!(unrated, RatingUpdate::default());
+    }
+}
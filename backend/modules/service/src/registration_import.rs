@@ -0,0 +1,247 @@
+//! Tournament entries from a spreadsheet. Club organizers keep their entry
+//! lists as CSV; each row is matched to a player by FIDE ID or username, or
+//! gets a player record of its own, before being registered.
+
+use csv::{ReaderBuilder, Trim};
+use db_entity::player;
+use dto::tournaments::RejectedRowDisplay;
+use error::error::ApiError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Most rows one import may hold, as many players as a tournament is
+/// created with
+pub const MAX_IMPORT_ROWS: usize = 1000;
+
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// Ratings outside this range are typos rather than players
+pub const RATING_RANGE: std::ops::RangeInclusive<i32> = 100..=3500;
+
+/// A participant read from the CSV.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParticipantRow {
+    pub line: u64,
+    pub name: String,
+    pub rating: Option<i32>,
+    /// Three-letter FIDE federation code
+    pub federation: Option<String>,
+    pub fide_id: Option<i64>,
+}
+
+/// The participants in `csv`, and the rows that could not be read. Fails
+/// when the header has no `name` column or there are too many rows.
+pub fn parse_participants(csv: &str) -> Result<(Vec<ParticipantRow>, Vec<RejectedRowDisplay>), ApiError> {
+    let mut reader = ReaderBuilder::new().trim(Trim::All).flexible(true).from_reader(csv.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ApiError::BadRequest(format!("CSV header is unreadable: {}", e)))?
+        .clone();
+    let column = |name: &str| headers.iter().position(|header| header.eq_ignore_ascii_case(name));
+    let name_column = column("name").ok_or_else(|| ApiError::BadRequest("CSV header has no 'name' column".to_string()))?;
+    let (rating_column, federation_column, fide_id_column) = (column("rating"), column("federation"), column("fide_id"));
+
+    let mut participants = Vec::new();
+    let mut rejected = Vec::new();
+    // Rows with a FIDE ID are told apart by it, the others by name
+    let mut seen = HashSet::new();
+    for (index, record) in reader.records().enumerate() {
+        if index >= MAX_IMPORT_ROWS {
+            return Err(ApiError::BadRequest(format!("At most {} participants can be imported at once", MAX_IMPORT_ROWS)));
+        }
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line());
+                rejected.push(RejectedRowDisplay { line, reason: format!("Row is unreadable: {}", e) });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let field = |column: Option<usize>| column.and_then(|column| record.get(column)).filter(|value| !value.is_empty());
+
+        match participant(line, field(Some(name_column)), field(rating_column), field(federation_column), field(fide_id_column)) {
+            Ok(row) => {
+                let key = row.fide_id.map_or_else(|| row.name.to_lowercase(), |fide_id| fide_id.to_string());
+                if seen.insert(key) {
+                    participants.push(row);
+                } else {
+                    rejected.push(RejectedRowDisplay { line, reason: "Participant is listed twice".to_string() });
+                }
+            }
+            Err(reason) => rejected.push(RejectedRowDisplay { line, reason }),
+        }
+    }
+    Ok((participants, rejected))
+}
+
+fn participant(
+    line: u64,
+    name: Option<&str>,
+    rating: Option<&str>,
+    federation: Option<&str>,
+    fide_id: Option<&str>,
+) -> Result<ParticipantRow, String> {
+    let name = name.ok_or_else(|| "Name is missing".to_string())?;
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Name is longer than {} characters", MAX_NAME_LENGTH));
+    }
+    let rating = rating
+        .map(|rating| match rating.parse::<i32>() {
+            Ok(value) if RATING_RANGE.contains(&value) => Ok(value),
+            Ok(value) => Err(format!("Rating {} is out of range", value)),
+            Err(_) => Err(format!("Rating '{}' is not a number", rating)),
+        })
+        .transpose()?;
+    let federation = federation
+        .map(|federation| match federation.len() == 3 && federation.chars().all(|c| c.is_ascii_alphabetic()) {
+            true => Ok(federation.to_ascii_uppercase()),
+            false => Err(format!("Federation '{}' is not a three-letter code", federation)),
+        })
+        .transpose()?;
+    let fide_id = fide_id
+        .map(|fide_id| match fide_id.parse::<i64>() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(format!("FIDE ID '{}' is not a number", fide_id)),
+        })
+        .transpose()?;
+
+    Ok(ParticipantRow { line, name: name.to_string(), rating, federation, fide_id })
+}
+
+/// The player `row` stands for: the one with its FIDE ID, else the one whose
+/// username is its name, else a new player. Returns whether it was created.
+/// Created players have no email or password, so nobody signs in to them.
+pub async fn find_or_create_player<C: ConnectionTrait>(
+    db: &C,
+    row: &ParticipantRow,
+) -> Result<(player::Model, bool), ApiError> {
+    if let Some(fide_id) = row.fide_id {
+        if let Some(found) = player::Entity::find().filter(player::Column::FideId.eq(fide_id)).one(db).await? {
+            return Ok((found, false));
+        }
+    }
+    if let Some(found) = player::Entity::find().filter(player::Column::Username.eq(&row.name)).one(db).await? {
+        return Ok((found, false));
+    }
+
+    let id = Uuid::new_v4();
+    let created = player::ActiveModel {
+        id: Set(id),
+        username: Set(available_username(db, &row.name).await?),
+        email: Set(format!("imported-{}@invalid", id)),
+        password_hash: Set(Vec::new()),
+        real_name: Set(row.name.clone()),
+        country: Set(row.federation.clone().unwrap_or_default()),
+        fide_rating: Set(row.rating),
+        fide_id: Set(row.fide_id),
+        ..Default::default()
+    };
+    Ok((created.insert(db).await?, true))
+}
+
+/// A username made from `name` that nobody has yet.
+async fn available_username<C: ConnectionTrait>(db: &C, name: &str) -> Result<String, ApiError> {
+    let base = username_from(name);
+    for suffix in 1..=MAX_IMPORT_ROWS {
+        let candidate = match suffix {
+            1 => base.clone(),
+            _ => format!("{}-{}", base, suffix),
+        };
+        let taken = player::Entity::find()
+            .filter(player::Column::Username.eq(&candidate))
+            .one(db)
+            .await?
+            .is_some();
+        if !taken {
+            return Ok(candidate);
+        }
+    }
+    Ok(format!("{}-{}", base, &Uuid::new_v4().simple().to_string()[..6]))
+}
+
+/// Lowercase letters and digits of `name` joined by dashes, short enough to
+/// take a suffix within the 32 characters usernames are allowed.
+fn username_from(name: &str) -> String {
+    let mut username = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            username.push(c);
+        } else if !username.is_empty() && !username.ends_with('-') {
+            username.push('-');
+        }
+    }
+    username.truncate(24);
+    let username = username.trim_end_matches('-').to_string();
+    if username.len() < 3 {
+        return format!("player-{}", username).trim_end_matches('-').to_string();
+    }
+    username
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_are_found_by_header() {
+        let csv = "FIDE_ID, Name ,Federation\n1503014,\"Carlsen, Magnus\",nor\n,Jane Doe,\n";
+        let (rows, rejected) = parse_participants(csv).unwrap();
+
+        assert!(rejected.is_empty());
+        assert_eq!(
+            rows,
+            vec![
+                ParticipantRow {
+                    line: 2,
+                    name: "Carlsen, Magnus".to_string(),
+                    rating: None,
+                    federation: Some("NOR".to_string()),
+                    fide_id: Some(1503014),
+                },
+                ParticipantRow { line: 3, name: "Jane Doe".to_string(), rating: None, federation: None, fide_id: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_rows_are_reported_by_line() {
+        let csv = "name,rating,federation,fide_id\n\
+                   ,1500,,\n\
+                   Jane Doe,abc,,\n\
+                   John Roe,9000,,\n\
+                   Ann Poe,1800,Norway,\n\
+                   Bob Loe,1800,,x1\n\
+                   Sam Hoe,1900,ENG,42\n\
+                   Sam Hoe again,1900,ENG,42\n";
+        let (rows, rejected) = parse_participants(csv).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        let reasons: Vec<(u64, &str)> = rejected.iter().map(|r| (r.line, r.reason.as_str())).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (2, "Name is missing"),
+                (3, "Rating 'abc' is not a number"),
+                (4, "Rating 9000 is out of range"),
+                (5, "Federation 'Norway' is not a three-letter code"),
+                (6, "FIDE ID 'x1' is not a number"),
+                (8, "Participant is listed twice"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_needs_a_name_column() {
+        assert!(parse_participants("rating,fide_id\n1500,1\n").is_err());
+    }
+
+    #[test]
+    fn test_username_from_name() {
+        assert_eq!(username_from("Carlsen, Magnus"), "carlsen-magnus");
+        assert_eq!(username_from("  Ødegaard  "), "degaard");
+        assert_eq!(username_from("Li"), "player-li");
+        assert_eq!(username_from("Aleksandra Goryachkina-Kosteniuk"), "aleksandra-goryachkina-k");
+    }
+}
@@ -0,0 +1,201 @@
+use chess::odds::PASS;
+use chess::{PgnGameResult, Referee, Termination};
+use db_entity::game::{self, GameVariant, ResultSide};
+use dto::games::{DivergenceKind, GameResult, GameVerificationResponse, ReplayDivergence};
+use error::error::ApiError;
+use sea_orm::DatabaseConnection;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::annotations::moves_of;
+use crate::game_archive::GameArchiveService;
+use crate::game_events::GameEventService;
+
+pub struct ReplayService;
+
+impl ReplayService {
+    /// Replay the moves of a game and compare where they lead with the
+    /// stored position and result. Games with an event log are replayed
+    /// from the log, so a game row that drifted from it diverges.
+    pub async fn verify(db: &DatabaseConnection, game_id: Uuid) -> Result<GameVerificationResponse, ApiError> {
+        let game = GameArchiveService::find(db, game_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Game".to_string()))?;
+        replay(&GameEventService::with_logged_moves(db, game).await?)
+    }
+}
+
+/// Replay `game` from its starting position, stopping at the first move
+/// that cannot be played.
+pub fn replay(game: &game::Model) -> Result<GameVerificationResponse, ApiError> {
+    if matches!(game.variant, GameVariant::Chess960 | GameVariant::ThreeCheck) {
+        return Err(ApiError::BadRequest(format!("Replay of {:?} games is not supported", game.variant)));
+    }
+
+    let mut board = start_board(game)?;
+
+    let mut divergences = Vec::new();
+    let mut plies_replayed = 0;
+    for (index, notation) in moves_of(game)?.iter().enumerate() {
+        // Skipped turns of odds games are replayed by the referee itself
+        if notation == PASS {
+            continue;
+        }
+        let played = board.play_uci(notation).or_else(|_| board.play_san(notation));
+        if let Err(err) = played {
+            divergences.push(ReplayDivergence {
+                kind: DivergenceKind::IllegalMove,
+                ply: Some(index as u32 + 1),
+                detail: format!("{} ({})", err, notation),
+            });
+            break;
+        }
+        plies_replayed += 1;
+    }
+
+    let replayed_fen = board.fen();
+    let replayed_position_hash = position_hash(&replayed_fen);
+    let stored_position_hash = Referee::from_fen(&game.fen).ok().map(|stored| position_hash(&stored.fen()));
+    let complete = divergences.is_empty();
+    if complete && stored_position_hash.as_ref() != Some(&replayed_position_hash) {
+        divergences.push(ReplayDivergence {
+            kind: DivergenceKind::Position,
+            ply: None,
+            detail: match stored_position_hash {
+                Some(_) => format!("The moves lead to {}", replayed_fen),
+                None => "The stored FEN is unreadable".to_string(),
+            },
+        });
+    }
+
+    let stored_result = stored_result(game);
+    let outcome = board.outcome();
+    let replayed_result = outcome.as_ref().map(|(result, _)| match result {
+        PgnGameResult::WhiteWins => GameResult::WhiteWin,
+        PgnGameResult::BlackWins => GameResult::BlackWin,
+        PgnGameResult::Draw | PgnGameResult::Ongoing => GameResult::Draw,
+    });
+    // Resignations, timeouts and agreed draws end games the board cannot
+    // see, so only a result by the rules can contradict the stored one
+    if let Some(replayed) = replayed_result.filter(|replayed| complete && *replayed != stored_result) {
+        divergences.push(ReplayDivergence {
+            kind: DivergenceKind::Result,
+            ply: None,
+            detail: format!("Stored as {:?} but the moves end in {:?}", stored_result, replayed),
+        });
+    }
+
+    Ok(GameVerificationResponse {
+        game_id: game.id,
+        consistent: divergences.is_empty(),
+        plies_replayed,
+        stored_fen: game.fen.clone(),
+        replayed_fen,
+        stored_position_hash,
+        replayed_position_hash,
+        stored_result,
+        replayed_result,
+        termination: outcome.map(|(_, termination)| termination_name(termination).to_string()),
+        divergences,
+    })
+}
+
+/// SHA-256 (hex) of the board, side to move, castling and en passant
+/// fields; the move counters are left out.
+pub fn position_hash(fen: &str) -> String {
+    let position = fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ");
+    Sha256::digest(position.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The board a game started from: the standard position, or the handicap
+/// position of an odds game.
+pub(crate) fn start_board(game: &game::Model) -> Result<Referee, ApiError> {
+    match game.odds.clone() {
+        Some(odds) => {
+            let odds: chess::Odds = serde_json::from_value(odds)
+                .map_err(|err| ApiError::Internal(format!("Stored odds are unreadable: {}", err)))?;
+            Referee::from_odds(&odds).map_err(|err| ApiError::Internal(err.to_string()))
+        }
+        None => Ok(Referee::default()),
+    }
+}
+
+fn stored_result(game: &game::Model) -> GameResult {
+    match game.result {
+        Some(ResultSide::WhiteWins) => GameResult::WhiteWin,
+        Some(ResultSide::BlackWins) => GameResult::BlackWin,
+        Some(ResultSide::Draw) => GameResult::Draw,
+        _ => GameResult::InProgress,
+    }
+}
+
+pub(crate) fn termination_name(termination: Termination) -> &'static str {
+    match termination {
+        Termination::Checkmate => "checkmate",
+        Termination::Stalemate => "stalemate",
+        Termination::InsufficientMaterial => "insufficient_material",
+        Termination::FiftyMoves => "fifty_moves",
+        Termination::Repetition => "threefold_repetition",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    const FOOLS_MATE: &str = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+
+    fn game(moves: &[&str], fen: &str, result: Option<ResultSide>) -> game::Model {
+        let now = Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
+        game::Model {
+            id: Uuid::new_v4(),
+            white_player: Uuid::new_v4(),
+            black_player: Uuid::new_v4(),
+            fen: fen.to_string(),
+            pgn: serde_json::json!({ "moves": moves }),
+            result,
+            variant: GameVariant::Standard,
+            started_at: now,
+            duration_sec: 30,
+            created_at: now,
+            updated_at: now,
+            is_imported: false,
+            original_pgn: None,
+            odds: None,
+        }
+    }
+
+    #[test]
+    fn consistent_games_replay_to_their_stored_position_and_result() {
+        let report = replay(&game(&["f2f3", "e5", "g4", "d8h4"], FOOLS_MATE, Some(ResultSide::BlackWins))).unwrap();
+        assert!(report.consistent, "{:?}", report.divergences);
+        assert_eq!(report.plies_replayed, 4);
+        assert_eq!(report.replayed_result, Some(GameResult::BlackWin));
+        assert_eq!(report.termination.as_deref(), Some("checkmate"));
+        assert_eq!(report.stored_position_hash, Some(report.replayed_position_hash.clone()));
+
+        // A resignation is not visible on the board and contradicts nothing
+        let resigned = game(&["e2e4"], "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", Some(ResultSide::WhiteWins));
+        assert!(replay(&resigned).unwrap().consistent);
+    }
+
+    #[test]
+    fn corrupted_games_report_where_they_diverge() {
+        let wrong_result = replay(&game(&["f2f3", "e7e5", "g2g4", "d8h4"], FOOLS_MATE, Some(ResultSide::WhiteWins))).unwrap();
+        assert_eq!(wrong_result.divergences.len(), 1);
+        assert_eq!(wrong_result.divergences[0].kind, DivergenceKind::Result);
+
+        let wrong_position = replay(&game(&["f2f3", "e7e5"], FOOLS_MATE, None)).unwrap();
+        assert_eq!(wrong_position.divergences[0].kind, DivergenceKind::Position);
+
+        let after_mate = replay(&game(&["f2f3", "e7e5", "g2g4", "d8h4", "a2a3"], FOOLS_MATE, Some(ResultSide::BlackWins))).unwrap();
+        assert_eq!(after_mate.plies_replayed, 4);
+        assert_eq!(after_mate.divergences[0].kind, DivergenceKind::IllegalMove);
+        assert_eq!(after_mate.divergences[0].ply, Some(5));
+        assert!(!after_mate.consistent);
+    }
+}
@@ -0,0 +1,210 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+
+/// How far ahead `next_after` searches before giving up on an expression
+/// that can never fire (e.g. `0 0 31 2 *`).
+const MAX_SEARCH_YEARS: i32 = 5;
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`)
+/// evaluated in UTC. Fields accept `*`, numbers, lists (`1,15`), ranges
+/// (`1-5`) and steps (`*/15`, `0-30/10`). Day of week runs 0-6 from Sunday,
+/// with 7 also meaning Sunday. As in cron, when both day fields are
+/// restricted a day matching either one fires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Schedule must have 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, "day of week")?;
+        // 7 is an alias for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, "minute")?,
+            hours: parse_field(fields[1], 0, 23, "hour")?,
+            days_of_month: parse_field(fields[2], 1, 31, "day of month")?,
+            months: parse_field(fields[3], 1, 12, "month")?,
+            days_of_week,
+            day_of_month_any: fields[2] == "*",
+            day_of_week_any: fields[4] == "*",
+        })
+    }
+
+    /// First firing strictly after `after`, at minute precision.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(366 * MAX_SEARCH_YEARS as i64);
+        let mut t = start;
+
+        while t < limit {
+            if !has(self.months, t.month()) {
+                t = start_of_next_month(t)?;
+                continue;
+            }
+            if !self.day_matches(t) {
+                t = start_of_day(t)? + Duration::days(1);
+                continue;
+            }
+            if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let dom = has(self.days_of_month, t.day());
+        let dow = has(self.days_of_week, t.weekday().num_days_from_sunday());
+
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn start_of_day(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    Utc.with_ymd_and_hms(t.year(), t.month(), t.day(), 0, 0, 0).single()
+}
+
+fn start_of_next_month(t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = if t.month() == 12 {
+        (t.year() + 1, 1)
+    } else {
+        (t.year(), t.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut set = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step '{}' in {} field", step, name))?;
+                if step == 0 {
+                    return Err(format!("Step in {} field must be positive", name));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max, name)?, parse_value(end, min, max, name)?)
+        } else {
+            let value = parse_value(range, min, max, name)?;
+            // `5/15` means from 5 to the end of the range
+            if part.contains('/') { (value, max) } else { (value, value) }
+        };
+
+        if start > end {
+            return Err(format!("Range {}-{} in {} field is reversed", start, end, name));
+        }
+
+        let mut value = start;
+        while value <= end {
+            set |= 1 << value;
+            value += step;
+        }
+    }
+
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, name: &str) -> Result<u32, String> {
+    let parsed: u32 = value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in {} field", value, name))?;
+    if parsed < min || parsed > max {
+        return Err(format!("Value {} in {} field must be between {} and {}", parsed, name, min, max));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn hourly_fires_on_the_hour() {
+        let schedule = CronSchedule::parse("0 * * * *").unwrap();
+        assert_eq!(schedule.next_after(at(2026, 10, 16, 9, 30)), Some(at(2026, 10, 16, 10, 0)));
+        // Strictly after: a firing time maps to the following one
+        assert_eq!(schedule.next_after(at(2026, 10, 16, 10, 0)), Some(at(2026, 10, 16, 11, 0)));
+    }
+
+    #[test]
+    fn weekly_fires_on_the_weekday() {
+        // Saturdays at 18:00; 2026-10-16 is a Friday
+        let schedule = CronSchedule::parse("0 18 * * 6").unwrap();
+        assert_eq!(schedule.next_after(at(2026, 10, 16, 12, 0)), Some(at(2026, 10, 17, 18, 0)));
+        assert_eq!(schedule.next_after(at(2026, 10, 17, 18, 0)), Some(at(2026, 10, 24, 18, 0)));
+    }
+
+    #[test]
+    fn steps_ranges_and_lists() {
+        let schedule = CronSchedule::parse("*/20 9-10 * * 1,3").unwrap();
+        // Friday evening -> Monday 09:00
+        assert_eq!(schedule.next_after(at(2026, 10, 16, 20, 0)), Some(at(2026, 10, 19, 9, 0)));
+        assert_eq!(schedule.next_after(at(2026, 10, 19, 10, 40)), Some(at(2026, 10, 21, 9, 0)));
+    }
+
+    #[test]
+    fn sunday_alias_and_month_rollover() {
+        let schedule = CronSchedule::parse("30 12 * * 7").unwrap();
+        assert_eq!(schedule.next_after(at(2026, 10, 16, 0, 0)), Some(at(2026, 10, 18, 12, 30)));
+
+        let new_year = CronSchedule::parse("0 0 1 1 *").unwrap();
+        assert_eq!(new_year.next_after(at(2026, 10, 16, 0, 0)), Some(at(2027, 1, 1, 0, 0)));
+    }
+
+    #[test]
+    fn rejects_malformed_and_impossible_expressions() {
+        assert!(CronSchedule::parse("0 * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+
+        let never = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(at(2026, 10, 16, 0, 0)), None);
+    }
+}
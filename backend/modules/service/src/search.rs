@@ -0,0 +1,127 @@
+use dto::search::{SearchKind, SearchResult};
+use error::error::ApiError;
+use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, Statement};
+use uuid::Uuid;
+
+/// Most results one search page may hold
+pub const MAX_SEARCH_PAGE: u64 = 50;
+
+/// Enabled players by username. `$1` is the search text, `$2` its prefix
+/// pattern and `$3` its substring pattern.
+const PLAYER_SEARCH: &str = r#"SELECT 'player' AS "kind", "id", "username" AS "name",
+    (similarity("username", $1) + CASE WHEN "username" ILIKE $2 THEN 1 ELSE 0 END)::real AS "score"
+    FROM "player"
+    WHERE "is_enabled" AND ("username" % $1 OR "username" ILIKE $3)"#;
+
+/// Tournaments by name, also matching whole words anywhere in the name.
+const TOURNAMENT_SEARCH: &str = r#"SELECT 'tournament' AS "kind", "id", "name",
+    (GREATEST(similarity("name", $1), ts_rank(to_tsvector('simple', "name"), plainto_tsquery('simple', $1)))
+        + CASE WHEN "name" ILIKE $2 THEN 1 ELSE 0 END)::real AS "score"
+    FROM "smdb"."tournament"
+    WHERE "name" % $1 OR to_tsvector('simple', "name") @@ plainto_tsquery('simple', $1) OR "name" ILIKE $3"#;
+
+#[derive(Debug, FromQueryResult)]
+struct SearchRow {
+    kind: String,
+    id: Uuid,
+    name: String,
+    score: f32,
+}
+
+pub struct SearchService;
+
+impl SearchService {
+    /// Players and tournaments whose names match `text`, best matches first.
+    /// Returns the page and the offset of the next one, if there is more.
+    pub async fn search(
+        db: &DatabaseConnection,
+        text: &str,
+        kind: Option<SearchKind>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<(Vec<SearchResult>, Option<u64>), ApiError> {
+        let text = text.trim();
+        let limit = limit.clamp(1, MAX_SEARCH_PAGE);
+        let escaped = like_escape(text);
+
+        // One row more than asked tells whether there is a next page
+        let rows = SearchRow::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            search_sql(kind),
+            [
+                text.into(),
+                format!("{}%", escaped).into(),
+                format!("%{}%", escaped).into(),
+                (limit + 1).into(),
+                offset.into(),
+            ],
+        ))
+        .all(db)
+        .await?;
+
+        let next_offset = (rows.len() as u64 > limit).then_some(offset + limit);
+        let results = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(|row| SearchResult {
+                kind: if row.kind == "player" { SearchKind::Player } else { SearchKind::Tournament },
+                id: row.id,
+                name: row.name,
+                score: row.score,
+            })
+            .collect();
+        Ok((results, next_offset))
+    }
+}
+
+/// The ranked query over the kinds searched; `$4` and `$5` are the limit
+/// and offset.
+fn search_sql(kind: Option<SearchKind>) -> String {
+    let parts: Vec<&str> = match kind {
+        Some(SearchKind::Player) => vec![PLAYER_SEARCH],
+        Some(SearchKind::Tournament) => vec![TOURNAMENT_SEARCH],
+        None => vec![PLAYER_SEARCH, TOURNAMENT_SEARCH],
+    };
+    format!(
+        r#"SELECT * FROM ({}) AS "hits" ORDER BY "score" DESC, "name", "id" LIMIT $4 OFFSET $5"#,
+        parts.join(" UNION ALL ")
+    )
+}
+
+/// Escape the `LIKE` wildcards in user input so it only matches literally.
+fn like_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_escape_matches_wildcards_literally() {
+        assert_eq!(like_escape("magnus"), "magnus");
+        assert_eq!(like_escape("100%_win\\"), "100\\%\\_win\\\\");
+    }
+
+    #[test]
+    fn test_search_sql_only_searches_requested_kind() {
+        let players = search_sql(Some(SearchKind::Player));
+        assert!(players.contains(r#"FROM "player""#));
+        assert!(!players.contains("UNION ALL"));
+
+        let tournaments = search_sql(Some(SearchKind::Tournament));
+        assert!(tournaments.contains(r#"FROM "smdb"."tournament""#));
+        assert!(!tournaments.contains(r#"FROM "player""#));
+
+        let both = search_sql(None);
+        assert!(both.contains("UNION ALL"));
+        assert!(both.ends_with(r#"ORDER BY "score" DESC, "name", "id" LIMIT $4 OFFSET $5"#));
+    }
+}
@@ -0,0 +1,295 @@
+use chrono::{DateTime, Duration, FixedOffset, Timelike, Utc};
+use db_entity::{game, game::ResultSide, player_stats};
+use dto::stats::{PlayerStats, ResultRecord};
+use error::error::ApiError;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DatabaseConnection, EntityTrait, Set};
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+/// Width of the opponent rating bands, in rating points.
+pub const RATING_BAND_WIDTH: i32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsColor {
+    White,
+    Black,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsOutcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// One player's view of a finished game.
+#[derive(Debug, Clone)]
+pub struct GameSummary {
+    pub game_id: Uuid,
+    pub player_id: Uuid,
+    pub color: StatsColor,
+    pub outcome: StatsOutcome,
+    pub eco: Option<String>,
+    pub opponent_rating: Option<i32>,
+    pub accuracy: Option<f64>,
+    pub finished_at: DateTime<FixedOffset>,
+}
+
+/// Figures known when a game finishes that the game row does not carry.
+#[derive(Debug, Clone, Default)]
+pub struct FinishedGameDetails {
+    pub eco: Option<String>,
+    pub white_rating: Option<i32>,
+    pub black_rating: Option<i32>,
+    pub white_accuracy: Option<f64>,
+    pub black_accuracy: Option<f64>,
+}
+
+pub struct StatsService;
+
+impl StatsService {
+    /// Aggregates for a player; players without finished games get empty stats.
+    pub async fn get(db: &DatabaseConnection, player_id: Uuid) -> Result<PlayerStats, ApiError> {
+        match player_stats::Entity::find_by_id(player_id).one(db).await? {
+            Some(row) => Ok(serde_json::from_value(row.stats).unwrap_or_default()),
+            None => Ok(PlayerStats::default()),
+        }
+    }
+
+    /// Fold a finished game into both players' aggregates.
+    /// Games without a decisive or drawn result are ignored.
+    pub async fn record_finished_game<C: ConnectionTrait>(
+        db: &C,
+        game: &game::Model,
+        details: FinishedGameDetails,
+    ) -> Result<(), ApiError> {
+        let Some(summaries) = summaries_for_game(game, details) else {
+            return Ok(());
+        };
+
+        for summary in summaries.iter() {
+            Self::record_summary(db, summary).await?;
+        }
+        Ok(())
+    }
+
+    async fn record_summary<C: ConnectionTrait>(db: &C, summary: &GameSummary) -> Result<(), ApiError> {
+        let existing = player_stats::Entity::find_by_id(summary.player_id).one(db).await?;
+        let now = Utc::now().fixed_offset();
+
+        match existing {
+            Some(row) => {
+                if row.last_game_id == Some(summary.game_id) {
+                    return Ok(());
+                }
+
+                let mut stats: PlayerStats = serde_json::from_value(row.stats.clone()).unwrap_or_default();
+                apply_game(&mut stats, summary);
+
+                let games_counted = row.games_counted + 1;
+                let mut active: player_stats::ActiveModel = row.into();
+                active.stats = Set(to_json(&stats));
+                active.games_counted = Set(games_counted);
+                active.last_game_id = Set(Some(summary.game_id));
+                active.updated_at = Set(now);
+                active.update(db).await?;
+            }
+            None => {
+                let mut stats = PlayerStats::default();
+                apply_game(&mut stats, summary);
+
+                player_stats::ActiveModel {
+                    player_id: Set(summary.player_id),
+                    stats: Set(to_json(&stats)),
+                    games_counted: Set(1),
+                    last_game_id: Set(Some(summary.game_id)),
+                    updated_at: Set(now),
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_json(stats: &PlayerStats) -> Json {
+    serde_json::to_value(stats).unwrap_or_default()
+}
+
+/// Split a finished game into one summary per player.
+pub fn summaries_for_game(game: &game::Model, details: FinishedGameDetails) -> Option<[GameSummary; 2]> {
+    let (white_outcome, black_outcome) = match game.result.as_ref()? {
+        ResultSide::WhiteWins => (StatsOutcome::Win, StatsOutcome::Loss),
+        ResultSide::BlackWins => (StatsOutcome::Loss, StatsOutcome::Win),
+        ResultSide::Draw => (StatsOutcome::Draw, StatsOutcome::Draw),
+        ResultSide::Ongoing | ResultSide::Abandoned => return None,
+    };
+
+    let eco = details.eco.or_else(|| eco_from_pgn(&game.pgn));
+    let finished_at = game.started_at + Duration::seconds(game.duration_sec as i64);
+
+    Some([
+        GameSummary {
+            game_id: game.id,
+            player_id: game.white_player,
+            color: StatsColor::White,
+            outcome: white_outcome,
+            eco: eco.clone(),
+            opponent_rating: details.black_rating,
+            accuracy: details.white_accuracy,
+            finished_at,
+        },
+        GameSummary {
+            game_id: game.id,
+            player_id: game.black_player,
+            color: StatsColor::Black,
+            outcome: black_outcome,
+            eco,
+            opponent_rating: details.white_rating,
+            accuracy: details.black_accuracy,
+            finished_at,
+        },
+    ])
+}
+
+/// Look for an ECO tag in the stored PGN headers.
+fn eco_from_pgn(pgn: &Json) -> Option<String> {
+    let headers = pgn.get("headers").unwrap_or(pgn);
+    headers
+        .get("ECO")
+        .or_else(|| headers.get("eco"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty() && s != "?")
+}
+
+/// Update running aggregates with a single game.
+pub fn apply_game(stats: &mut PlayerStats, summary: &GameSummary) {
+    record(&mut stats.total, summary.outcome);
+
+    let by_color = match summary.color {
+        StatsColor::White => &mut stats.as_white,
+        StatsColor::Black => &mut stats.as_black,
+    };
+    record(by_color, summary.outcome);
+
+    if let Some(eco) = &summary.eco {
+        record(stats.by_opening.entry(eco.clone()).or_default(), summary.outcome);
+    }
+
+    if let Some(rating) = summary.opponent_rating {
+        record(stats.by_opponent_band.entry(rating_band(rating)).or_default(), summary.outcome);
+    }
+
+    let period = time_of_day(summary.finished_at.with_timezone(&Utc).hour());
+    record(stats.by_time_of_day.entry(period.to_string()).or_default(), summary.outcome);
+
+    if let Some(accuracy) = summary.accuracy {
+        let previous = stats.average_accuracy.unwrap_or(0.0) * stats.accuracy_games as f64;
+        stats.accuracy_games += 1;
+        stats.average_accuracy = Some((previous + accuracy) / stats.accuracy_games as f64);
+    }
+
+    let streaks = &mut stats.streaks;
+    streaks.current = match summary.outcome {
+        StatsOutcome::Win => streaks.current.max(0) + 1,
+        StatsOutcome::Loss => streaks.current.min(0) - 1,
+        StatsOutcome::Draw => 0,
+    };
+    if streaks.current > 0 {
+        streaks.longest_win = streaks.longest_win.max(streaks.current as u32);
+    } else if streaks.current < 0 {
+        streaks.longest_loss = streaks.longest_loss.max(streaks.current.unsigned_abs());
+    }
+}
+
+fn record(record: &mut ResultRecord, outcome: StatsOutcome) {
+    match outcome {
+        StatsOutcome::Win => record.wins += 1,
+        StatsOutcome::Draw => record.draws += 1,
+        StatsOutcome::Loss => record.losses += 1,
+    }
+}
+
+/// Label for the rating band containing `rating`, e.g. `1800-1999`.
+pub fn rating_band(rating: i32) -> String {
+    if rating < 1000 {
+        return "<1000".to_string();
+    }
+    if rating >= 2600 {
+        return "2600+".to_string();
+    }
+    let low = rating - rating % RATING_BAND_WIDTH;
+    format!("{}-{}", low, low + RATING_BAND_WIDTH - 1)
+}
+
+/// UTC period for an hour of the day.
+pub fn time_of_day(hour: u32) -> &'static str {
+    match hour {
+        0..=5 => "night",
+        6..=11 => "morning",
+        12..=17 => "afternoon",
+        _ => "evening",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn summary(color: StatsColor, outcome: StatsOutcome, hour: u32) -> GameSummary {
+        GameSummary {
+            game_id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            color,
+            outcome,
+            eco: Some("B90".to_string()),
+            opponent_rating: Some(1850),
+            accuracy: Some(80.0),
+            finished_at: Utc.with_ymd_and_hms(2026, 10, 16, hour, 0, 0).unwrap().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_rating_band() {
+        assert_eq!(rating_band(850), "<1000");
+        assert_eq!(rating_band(1000), "1000-1199");
+        assert_eq!(rating_band(1999), "1800-1999");
+        assert_eq!(rating_band(2712), "2600+");
+    }
+
+    #[test]
+    fn test_apply_game_aggregates() {
+        let mut stats = PlayerStats::default();
+        apply_game(&mut stats, &summary(StatsColor::White, StatsOutcome::Win, 9));
+        apply_game(&mut stats, &summary(StatsColor::Black, StatsOutcome::Win, 21));
+        let mut last = summary(StatsColor::Black, StatsOutcome::Draw, 22);
+        last.accuracy = Some(90.0);
+        apply_game(&mut stats, &last);
+
+        assert_eq!(stats.total, ResultRecord { wins: 2, draws: 1, losses: 0 });
+        assert_eq!(stats.as_white.wins, 1);
+        assert_eq!(stats.as_black.games(), 2);
+        assert_eq!(stats.by_opening["B90"].games(), 3);
+        assert_eq!(stats.by_opponent_band["1800-1999"].games(), 3);
+        assert_eq!(stats.by_time_of_day["evening"].games(), 2);
+        assert_eq!(stats.by_time_of_day["morning"].wins, 1);
+        assert_eq!(stats.accuracy_games, 3);
+        assert!((stats.average_accuracy.unwrap() - 250.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.streaks.current, 0);
+        assert_eq!(stats.streaks.longest_win, 2);
+    }
+
+    #[test]
+    fn test_streaks_switch_sign() {
+        let mut stats = PlayerStats::default();
+        for outcome in [StatsOutcome::Win, StatsOutcome::Loss, StatsOutcome::Loss, StatsOutcome::Loss] {
+            apply_game(&mut stats, &summary(StatsColor::White, outcome, 12));
+        }
+        assert_eq!(stats.streaks.current, -3);
+        assert_eq!(stats.streaks.longest_loss, 3);
+        assert_eq!(stats.streaks.longest_win, 1);
+    }
+}
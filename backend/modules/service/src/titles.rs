@@ -0,0 +1,215 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use db_entity::{
+    player, title_verification, title_verification_event,
+    title_verification::VerificationStatus,
+};
+use dto::titles::{DecideTitleRequest, SubmitTitleRequest};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Order,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::moderation::MAX_QUEUE_PAGE;
+
+pub struct TitleVerificationService;
+
+impl TitleVerificationService {
+    /// Ask for `request.title` to be shown on the player's profile. A player
+    /// has at most one request waiting for review.
+    pub async fn submit(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        request: SubmitTitleRequest,
+    ) -> Result<title_verification::Model, ApiError> {
+        let pending = title_verification::Entity::find()
+            .filter(title_verification::Column::PlayerId.eq(player_id))
+            .filter(title_verification::Column::Status.eq(VerificationStatus::Pending))
+            .one(db)
+            .await?;
+        if pending.is_some() {
+            return Err(ApiError::BadRequest("A title verification is already pending review".to_string()));
+        }
+
+        let txn = db.begin().await?;
+        let model = title_verification::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            title: Set(request.title.into()),
+            fide_id: Set(request.fide_id),
+            evidence: Set(request.evidence),
+            status: Set(VerificationStatus::Pending),
+            reviewed_by: Set(None),
+            review_note: Set(None),
+            created_at: Set(now()),
+            reviewed_at: Set(None),
+        }
+        .insert(&txn)
+        .await?;
+        record(&txn, model.id, player_id, None, VerificationStatus::Pending, None).await?;
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    /// Moderator queue, oldest first.
+    pub async fn list(
+        db: &DatabaseConnection,
+        status: VerificationStatus,
+        limit: u64,
+    ) -> Result<Vec<title_verification::Model>, ApiError> {
+        Ok(title_verification::Entity::find()
+            .filter(title_verification::Column::Status.eq(status))
+            .order_by(title_verification::Column::CreatedAt, Order::Asc)
+            .limit(limit.clamp(1, MAX_QUEUE_PAGE))
+            .all(db)
+            .await?)
+    }
+
+    /// The player's requests, newest first.
+    pub async fn mine(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<title_verification::Model>, ApiError> {
+        Ok(title_verification::Entity::find()
+            .filter(title_verification::Column::PlayerId.eq(player_id))
+            .order_by(title_verification::Column::CreatedAt, Order::Desc)
+            .all(db)
+            .await?)
+    }
+
+    pub async fn get(db: &DatabaseConnection, verification_id: Uuid) -> Result<title_verification::Model, ApiError> {
+        title_verification::Entity::find_by_id(verification_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Title verification {}", verification_id)))
+    }
+
+    /// Approve, reject or revoke a request. Approval puts the title on the
+    /// player's profile and its abbreviation in their flair; revocation
+    /// takes both off again. Every decision is written to the audit log.
+    pub async fn decide(
+        db: &DatabaseConnection,
+        verification_id: Uuid,
+        moderator_id: Uuid,
+        request: DecideTitleRequest,
+    ) -> Result<title_verification::Model, ApiError> {
+        let to: VerificationStatus = request.status.into();
+        let verification = Self::get(db, verification_id).await?;
+        let from = verification.status;
+        transition(from, to)?;
+        if verification.player_id == moderator_id {
+            return Err(ApiError::Forbidden("Moderators cannot review their own title".to_string()));
+        }
+
+        let txn = db.begin().await?;
+        let player = player::Entity::find_by_id(verification.player_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Player {}", verification.player_id)))?;
+        match to {
+            VerificationStatus::Approved => {
+                let fide_id = verification.fide_id;
+                let mut row = player.into_active_model();
+                row.title = Set(Some(verification.title));
+                row.flair = Set(verification.title.code().to_string());
+                row.fide_id = Set(Some(fide_id));
+                row.update(&txn).await?;
+            }
+            VerificationStatus::Revoked if player.title == Some(verification.title) => {
+                let clear_flair = player.flair == verification.title.code();
+                let mut row = player.into_active_model();
+                row.title = Set(None);
+                if clear_flair {
+                    row.flair = Set(String::new());
+                }
+                row.update(&txn).await?;
+            }
+            _ => {}
+        }
+
+        let mut active = verification.into_active_model();
+        active.status = Set(to);
+        active.reviewed_by = Set(Some(moderator_id));
+        active.review_note = Set(request.note.clone());
+        active.reviewed_at = Set(Some(now()));
+        let updated = active.update(&txn).await?;
+        record(&txn, verification_id, moderator_id, Some(from), to, request.note).await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+
+    /// Audit log of a request, oldest first.
+    pub async fn events(
+        db: &DatabaseConnection,
+        verification_id: Uuid,
+    ) -> Result<Vec<title_verification_event::Model>, ApiError> {
+        Ok(title_verification_event::Entity::find()
+            .filter(title_verification_event::Column::VerificationId.eq(verification_id))
+            .order_by(title_verification_event::Column::CreatedAt, Order::Asc)
+            .all(db)
+            .await?)
+    }
+}
+
+/// Refuse any change of status other than reviewing a pending request or
+/// revoking an approved one.
+fn transition(from: VerificationStatus, to: VerificationStatus) -> Result<(), ApiError> {
+    use VerificationStatus::*;
+    match (from, to) {
+        (Pending, Approved) | (Pending, Rejected) | (Approved, Revoked) => Ok(()),
+        (Pending, _) => Err(ApiError::BadRequest(
+            "A pending title can only be approved or rejected".to_string(),
+        )),
+        (Approved, _) => Err(ApiError::BadRequest("An approved title can only be revoked".to_string())),
+        (Rejected, _) | (Revoked, _) => Err(ApiError::BadRequest(
+            "This title verification is closed; the player can submit a new one".to_string(),
+        )),
+    }
+}
+
+async fn record<C: ConnectionTrait>(
+    db: &C,
+    verification_id: Uuid,
+    actor_id: Uuid,
+    from: Option<VerificationStatus>,
+    to: VerificationStatus,
+    note: Option<String>,
+) -> Result<(), ApiError> {
+    title_verification_event::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        verification_id: Set(verification_id),
+        actor_id: Set(actor_id),
+        from_status: Set(from),
+        to_status: Set(to),
+        note: Set(note),
+        created_at: Set(now()),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().fixed_offset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use VerificationStatus::*;
+
+    #[test]
+    fn test_pending_titles_are_approved_or_rejected() {
+        assert!(transition(Pending, Approved).is_ok());
+        assert!(transition(Pending, Rejected).is_ok());
+        assert!(transition(Pending, Revoked).is_err());
+        assert!(transition(Pending, Pending).is_err());
+    }
+
+    #[test]
+    fn test_only_approved_titles_are_revoked() {
+        assert!(transition(Approved, Revoked).is_ok());
+        assert!(transition(Approved, Rejected).is_err());
+        assert!(transition(Rejected, Approved).is_err());
+        assert!(transition(Revoked, Approved).is_err());
+        assert!(transition(Revoked, Revoked).is_err());
+    }
+}
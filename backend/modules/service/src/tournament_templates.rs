@@ -0,0 +1,330 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entity::{tournament::TournamentFormat, tournament_template};
+use dto::tournaments::{ArenaPairing, ArenaPairingMode as DisplayMode, CreateTemplateRequest, UpdateTemplateRequest};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use serde_json::Value as Json;
+use tournament::{ArenaConfig, ArenaPairingMode};
+use uuid::Uuid;
+
+use crate::schedule::CronSchedule;
+use crate::tournaments::TournamentService;
+
+pub struct TemplateService;
+
+impl TemplateService {
+    pub async fn create(
+        db: &DatabaseConnection,
+        created_by: Uuid,
+        request: CreateTemplateRequest,
+    ) -> Result<tournament_template::Model, ApiError> {
+        let format: TournamentFormat = request.format.into();
+        let schedule = parse_schedule(&request.schedule)?;
+        validate_shape(
+            format,
+            request.total_rounds,
+            request.duration_minutes,
+            request.registration_opens_minutes,
+            request.registration_closes_minutes,
+        )?;
+        let arena_pairing = match format {
+            TournamentFormat::Arena => Some(arena_config(request.arena_pairing)),
+            TournamentFormat::Swiss if request.arena_pairing.is_some() => {
+                return Err(ApiError::BadRequest("Only arena templates have arena_pairing".to_string()));
+            }
+            TournamentFormat::Swiss => None,
+        };
+        let now = Utc::now().fixed_offset();
+
+        let model = tournament_template::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(request.name),
+            format: Set(format),
+            time_control: Set(request.time_control.into()),
+            total_rounds: Set(request.total_rounds.map(|r| r as i32)),
+            duration_minutes: Set(request.duration_minutes.map(|d| d as i32)),
+            accelerated: Set(request.accelerated),
+            schedule: Set(request.schedule),
+            registration_opens_minutes: Set(request.registration_opens_minutes as i32),
+            registration_closes_minutes: Set(request.registration_closes_minutes as i32),
+            arena_pairing: Set(arena_pairing),
+            enabled: Set(true),
+            next_run_at: Set(next_run(&schedule, now)),
+            created_by: Set(created_by),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+
+        Ok(model.insert(db).await?)
+    }
+
+    pub async fn list(db: &DatabaseConnection) -> Result<Vec<tournament_template::Model>, ApiError> {
+        Ok(tournament_template::Entity::find()
+            .order_by_asc(tournament_template::Column::Name)
+            .all(db)
+            .await?)
+    }
+
+    /// Apply the given changes. The next run is recomputed when the schedule
+    /// changes or the template is enabled again.
+    pub async fn update(
+        db: &DatabaseConnection,
+        id: Uuid,
+        request: UpdateTemplateRequest,
+    ) -> Result<tournament_template::Model, ApiError> {
+        let existing = Self::find(db, id).await?;
+
+        let total_rounds = request.total_rounds.or(existing.total_rounds.map(|r| r as u32));
+        let duration_minutes = request.duration_minutes.or(existing.duration_minutes.map(|d| d as u32));
+        let opens = request
+            .registration_opens_minutes
+            .unwrap_or(existing.registration_opens_minutes as u32);
+        let closes = request
+            .registration_closes_minutes
+            .unwrap_or(existing.registration_closes_minutes as u32);
+        validate_shape(existing.format, total_rounds, duration_minutes, opens, closes)?;
+        if request.arena_pairing.is_some() && existing.format != TournamentFormat::Arena {
+            return Err(ApiError::BadRequest("Only arena templates have arena_pairing".to_string()));
+        }
+
+        let schedule = parse_schedule(request.schedule.as_deref().unwrap_or(&existing.schedule))?;
+        let enabled = request.enabled.unwrap_or(existing.enabled);
+        let reschedule = request.schedule.as_ref().is_some_and(|s| *s != existing.schedule)
+            || (enabled && !existing.enabled);
+        let now = Utc::now().fixed_offset();
+
+        let mut active: tournament_template::ActiveModel = existing.into();
+        if let Some(name) = request.name {
+            active.name = Set(name);
+        }
+        if let Some(schedule) = request.schedule {
+            active.schedule = Set(schedule);
+        }
+        if let Some(accelerated) = request.accelerated {
+            active.accelerated = Set(accelerated);
+        }
+        if let Some(pairing) = request.arena_pairing {
+            active.arena_pairing = Set(Some(arena_config(Some(pairing))));
+        }
+        active.total_rounds = Set(total_rounds.map(|r| r as i32));
+        active.duration_minutes = Set(duration_minutes.map(|d| d as i32));
+        active.registration_opens_minutes = Set(opens as i32);
+        active.registration_closes_minutes = Set(closes as i32);
+        active.enabled = Set(enabled);
+        if reschedule {
+            active.next_run_at = Set(next_run(&schedule, now));
+        }
+        active.updated_at = Set(now);
+
+        Ok(active.update(db).await?)
+    }
+
+    /// Delete a template. Tournaments it already created are kept.
+    pub async fn delete(db: &DatabaseConnection, id: Uuid) -> Result<(), ApiError> {
+        let result = tournament_template::Entity::delete_by_id(id).exec(db).await?;
+        if result.rows_affected == 0 {
+            return Err(ApiError::NotFound("Tournament template".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Create the tournaments of every enabled template whose registration
+    /// has opened by `now`. Returns how many tournaments were created.
+    pub async fn run_due(db: &DatabaseConnection, now: DateTime<FixedOffset>) -> Result<usize, ApiError> {
+        let candidates = tournament_template::Entity::find()
+            .filter(tournament_template::Column::Enabled.eq(true))
+            .filter(tournament_template::Column::NextRunAt.is_not_null())
+            .all(db)
+            .await?;
+
+        let mut created = 0;
+        for candidate in candidates {
+            if !is_due(&candidate, now) {
+                continue;
+            }
+
+            // Lock the row so two scheduler ticks cannot create the same run
+            let txn = db.begin().await?;
+            let template = match tournament_template::Entity::find_by_id(candidate.id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?
+            {
+                Some(template) if template.enabled && is_due(&template, now) => template,
+                _ => continue,
+            };
+
+            // Schedules are validated on write; a bad one stops the template until it is fixed
+            let (starts_at, next_run_at) = match CronSchedule::parse(&template.schedule) {
+                Ok(schedule) => plan_run(&template, &schedule, now),
+                Err(_) => (None, None),
+            };
+
+            if let Some(starts_at) = starts_at {
+                TournamentService::create_from_template(&txn, &template, starts_at).await?;
+                created += 1;
+            }
+
+            let mut active: tournament_template::ActiveModel = template.into();
+            active.next_run_at = Set(next_run_at);
+            active.updated_at = Set(now);
+            active.update(&txn).await?;
+
+            txn.commit().await?;
+        }
+        Ok(created)
+    }
+
+    async fn find(db: &DatabaseConnection, id: Uuid) -> Result<tournament_template::Model, ApiError> {
+        tournament_template::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Tournament template".to_string()))
+    }
+}
+
+fn parse_schedule(expression: &str) -> Result<CronSchedule, ApiError> {
+    CronSchedule::parse(expression).map_err(ApiError::BadRequest)
+}
+
+fn validate_shape(
+    format: TournamentFormat,
+    total_rounds: Option<u32>,
+    duration_minutes: Option<u32>,
+    registration_opens_minutes: u32,
+    registration_closes_minutes: u32,
+) -> Result<(), ApiError> {
+    match format {
+        TournamentFormat::Swiss if total_rounds.is_none() => {
+            return Err(ApiError::BadRequest("Swiss templates need total_rounds".to_string()));
+        }
+        TournamentFormat::Arena if duration_minutes.is_none() => {
+            return Err(ApiError::BadRequest("Arena templates need duration_minutes".to_string()));
+        }
+        _ => {}
+    }
+    if registration_closes_minutes >= registration_opens_minutes {
+        return Err(ApiError::BadRequest(
+            "Registration must close after it opens".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Stored form of the arena pairing chosen for a template; the defaults
+/// when none was chosen.
+fn arena_config(pairing: Option<ArenaPairing>) -> Json {
+    let config = match pairing {
+        Some(pairing) => ArenaConfig {
+            pairing: match pairing.pairing {
+                DisplayMode::Fast => ArenaPairingMode::Fast,
+                DisplayMode::Fair => ArenaPairingMode::Fair,
+            },
+            max_wait_secs: pairing.max_wait_secs,
+        },
+        None => ArenaConfig::default(),
+    };
+    serde_json::to_value(config).unwrap_or_default()
+}
+
+fn next_run(schedule: &CronSchedule, after: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    schedule
+        .next_after(after.with_timezone(&Utc))
+        .map(|t| t.fixed_offset())
+}
+
+/// True once registration for the template's next run should open.
+fn is_due(template: &tournament_template::Model, now: DateTime<FixedOffset>) -> bool {
+    template.next_run_at.is_some_and(|next| {
+        next - Duration::minutes(template.registration_opens_minutes as i64) <= now
+    })
+}
+
+/// Start time of the tournament to create now, if any, and the run after it.
+/// A run whose start has already passed (e.g. while the server was down) is
+/// skipped rather than created late.
+fn plan_run(
+    template: &tournament_template::Model,
+    schedule: &CronSchedule,
+    now: DateTime<FixedOffset>,
+) -> (Option<DateTime<FixedOffset>>, Option<DateTime<FixedOffset>>) {
+    match template.next_run_at {
+        Some(next) if next > now => (Some(next), next_run(schedule, next)),
+        _ => (None, next_run(schedule, now)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use db_entity::player_rating::RatingCategory;
+
+    fn at(h: u32, mi: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2026, 10, 16, h, mi, 0).unwrap().fixed_offset()
+    }
+
+    fn hourly(next_run_at: Option<DateTime<FixedOffset>>) -> tournament_template::Model {
+        tournament_template::Model {
+            id: Uuid::new_v4(),
+            name: "Hourly Blitz Arena".to_string(),
+            format: TournamentFormat::Arena,
+            time_control: RatingCategory::Blitz,
+            total_rounds: None,
+            duration_minutes: Some(57),
+            accelerated: false,
+            schedule: "0 * * * *".to_string(),
+            registration_opens_minutes: 30,
+            registration_closes_minutes: 0,
+            arena_pairing: None,
+            enabled: true,
+            next_run_at,
+            created_by: Uuid::new_v4(),
+            created_at: at(0, 0),
+            updated_at: at(0, 0),
+        }
+    }
+
+    #[test]
+    fn due_once_registration_opens() {
+        let template = hourly(Some(at(10, 0)));
+        assert!(!is_due(&template, at(9, 29)));
+        assert!(is_due(&template, at(9, 30)));
+        assert!(!is_due(&hourly(None), at(9, 30)));
+    }
+
+    #[test]
+    fn plans_the_upcoming_run_and_the_one_after() {
+        let template = hourly(Some(at(10, 0)));
+        let schedule = CronSchedule::parse(&template.schedule).unwrap();
+
+        assert_eq!(plan_run(&template, &schedule, at(9, 30)), (Some(at(10, 0)), Some(at(11, 0))));
+    }
+
+    #[test]
+    fn skips_runs_that_already_started() {
+        let template = hourly(Some(at(10, 0)));
+        let schedule = CronSchedule::parse(&template.schedule).unwrap();
+
+        assert_eq!(plan_run(&template, &schedule, at(12, 15)), (None, Some(at(13, 0))));
+    }
+
+    #[test]
+    fn templates_need_the_fields_of_their_format() {
+        assert!(validate_shape(TournamentFormat::Swiss, None, Some(60), 60, 0).is_err());
+        assert!(validate_shape(TournamentFormat::Arena, Some(7), None, 60, 0).is_err());
+        assert!(validate_shape(TournamentFormat::Swiss, Some(7), None, 60, 0).is_ok());
+        assert!(validate_shape(TournamentFormat::Arena, None, Some(57), 10, 10).is_err());
+    }
+
+    #[test]
+    fn arena_pairing_is_stored_as_the_arena_config() {
+        let stored = arena_config(Some(ArenaPairing { pairing: DisplayMode::Fast, max_wait_secs: 30 }));
+        let config: ArenaConfig = serde_json::from_value(stored).unwrap();
+        assert_eq!(config.pairing, ArenaPairingMode::Fast);
+        assert_eq!(config.max_wait_secs, 30);
+    }
+}
@@ -0,0 +1,967 @@
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use db_entity::{
+    club_member, player, player_rating, tournament as tournament_entity, tournament_template,
+    tournament::{TournamentFormat, TournamentStatus},
+};
+use dto::tournaments::{
+    CreateTournamentRequest, InitialColor as DisplayColor, PairingDisplay, Prize as PrizeDisplay, PrizeKind as PrizeKindDisplay,
+    ImportedRegistrationDisplay, RegistrationImportDisplay, ReentryPolicy as DisplayReentry, RejectedRowDisplay,
+    RoundDisplay, Seeding, StandingDisplay, TournamentDisplay, TrfValidationDisplay,
+};
+use dto::games::GameResult as DisplayResult;
+use dto::webhooks::{RoundPairedData, WebhookEvent};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, QueryFilter, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use tournament::{
+    ArbiterError, ArenaConfig, BakuAcceleration, FideDetails, GameResult, Pairing, Player, PairingResult, Prize,
+    InitialColor, PrizeKind, PrizeStructure, ReentryPolicy, SeedingOrder, SwissConfig, SwissPairer, TournamentState, TrfHeader,
+};
+use uuid::Uuid;
+
+use crate::clubs::ClubService;
+use crate::rating::DEFAULT_RATING;
+use crate::registration_import;
+use crate::trophies::TrophyService;
+use crate::webhooks::WebhookService;
+
+/// Fewest registrations a scheduled tournament needs to start.
+pub const MIN_PLAYERS_TO_START: usize = 2;
+
+pub struct TournamentService;
+
+impl TournamentService {
+    /// Create a tournament run by `arbiter_id`, seeding players from their ratings.
+    /// Club tournaments are created by the club's owner or admins and only
+    /// enter its members.
+    pub async fn create(
+        db: &DatabaseConnection,
+        arbiter_id: Uuid,
+        request: CreateTournamentRequest,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let category: player_rating::RatingCategory = request.time_control.into();
+        let prizes = prize_structure(request.prizes.clone())?;
+
+        let mut player_ids = request.player_ids.clone();
+        player_ids.sort();
+        player_ids.dedup();
+        if let Some(stranger) = request.seed_order.iter().find(|id| !player_ids.contains(id)) {
+            return Err(ApiError::BadRequest(format!("Seeded player {} is not entered", stranger)));
+        }
+        let seeding = match request.seeding {
+            Seeding::Rating => SeedingOrder::Rating,
+            Seeding::Random => SeedingOrder::Random,
+            Seeding::Manual if request.seed_order.is_empty() => {
+                return Err(ApiError::BadRequest("Manual seeding needs a seed order".to_string()))
+            }
+            Seeding::Manual => SeedingOrder::Manual(request.seed_order.clone()),
+        };
+
+        let players = player::Entity::find()
+            .filter(player::Column::Id.is_in(player_ids.clone()))
+            .all(db)
+            .await?;
+        if let Some(missing) = player_ids.iter().find(|id| !players.iter().any(|p| p.id == **id)) {
+            return Err(ApiError::NotFound(format!("Player {}", missing)));
+        }
+        if let Some(club_id) = request.club_id {
+            ClubService::check_tournament(db, club_id, arbiter_id, &player_ids).await?;
+        }
+
+        let ratings = player_rating::Entity::find()
+            .filter(player_rating::Column::PlayerId.is_in(player_ids))
+            .filter(player_rating::Column::Category.eq(category))
+            .all(db)
+            .await?;
+
+        let entrants = players
+            .into_iter()
+            .map(|p| {
+                let rating = ratings
+                    .iter()
+                    .find(|r| r.player_id == p.id)
+                    .map(|r| r.rating)
+                    .unwrap_or(DEFAULT_RATING);
+                Player::new(p.id, p.username, rating)
+            })
+            .collect();
+
+        let defaults = SwissConfig::default();
+        let config = SwissConfig {
+            total_rounds: request.total_rounds,
+            deterministic_seed: request.deterministic_seed,
+            acceleration: request
+                .accelerated
+                .then(|| BakuAcceleration::standard(request.total_rounds)),
+            bye_points: request.bye_points.unwrap_or(defaults.bye_points),
+            requested_bye_points: request.requested_bye_points.unwrap_or(defaults.requested_bye_points),
+            reentry: match request.reentry {
+                DisplayReentry::Forbidden => ReentryPolicy::Forbidden,
+                DisplayReentry::ZeroForMissed => ReentryPolicy::ZeroForMissed,
+                DisplayReentry::ByeForMissed => ReentryPolicy::ByeForMissed,
+            },
+            seeding,
+            initial_color: match request.initial_color {
+                DisplayColor::White => InitialColor::White,
+                DisplayColor::Black => InitialColor::Black,
+                DisplayColor::Random => InitialColor::Random,
+            },
+            ..defaults
+        };
+        let state = TournamentState::new(entrants, request.total_rounds);
+        let now = Utc::now().fixed_offset();
+
+        let model = tournament_entity::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(request.name),
+            arbiter_id: Set(arbiter_id),
+            time_control: Set(category),
+            config: Set(to_json(&config)?),
+            state: Set(to_json(&state)?),
+            created_at: Set(now),
+            updated_at: Set(now),
+            format: Set(TournamentFormat::Swiss),
+            status: Set(TournamentStatus::Ongoing),
+            template_id: Set(None),
+            duration_minutes: Set(None),
+            registration_opens_at: Set(None),
+            registration_closes_at: Set(None),
+            starts_at: Set(Some(now)),
+            prizes: Set(to_json(&prizes)?),
+            club_id: Set(request.club_id),
+        }
+        .insert(db)
+        .await?;
+
+        Ok(model)
+    }
+
+    /// Create the tournament for one run of a template, starting at `starts_at`.
+    pub async fn create_from_template<C: ConnectionTrait>(
+        conn: &C,
+        template: &tournament_template::Model,
+        starts_at: DateTime<FixedOffset>,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let total_rounds = template.total_rounds.unwrap_or(0).max(0) as u32;
+        let config = match template.format {
+            TournamentFormat::Swiss => to_json(&SwissConfig {
+                total_rounds,
+                acceleration: template
+                    .accelerated
+                    .then(|| BakuAcceleration::standard(total_rounds)),
+                ..SwissConfig::default()
+            })?,
+            TournamentFormat::Arena => match &template.arena_pairing {
+                Some(config) => config.clone(),
+                None => to_json(&ArenaConfig::default())?,
+            },
+        };
+        let state = TournamentState::new(Vec::new(), total_rounds);
+        let now = Utc::now().fixed_offset();
+
+        let model = tournament_entity::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(format!("{} {}", template.name, starts_at.format("%Y-%m-%d %H:%M"))),
+            arbiter_id: Set(template.created_by),
+            time_control: Set(template.time_control),
+            config: Set(config),
+            state: Set(to_json(&state)?),
+            created_at: Set(now),
+            updated_at: Set(now),
+            format: Set(template.format),
+            status: Set(TournamentStatus::Registration),
+            template_id: Set(Some(template.id)),
+            duration_minutes: Set(template.duration_minutes),
+            registration_opens_at: Set(Some(starts_at - Duration::minutes(template.registration_opens_minutes as i64))),
+            registration_closes_at: Set(Some(starts_at - Duration::minutes(template.registration_closes_minutes as i64))),
+            starts_at: Set(Some(starts_at)),
+            prizes: Set(to_json(&PrizeStructure::default())?),
+            club_id: Set(None),
+        }
+        .insert(conn)
+        .await?;
+
+        Ok(model)
+    }
+
+    /// Register `player_id` while the tournament's registration window is open.
+    pub async fn register(
+        db: &DatabaseConnection,
+        id: Uuid,
+        player_id: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let now = Utc::now().fixed_offset();
+        let txn = db.begin().await?;
+        let model = Self::lock(&txn, id).await?;
+
+        if !registration_open(&model, now) {
+            return Err(ApiError::BadRequest("Registration is not open".to_string()));
+        }
+
+        let mut state = state_of(&model)?;
+        if state.players.contains_key(&player_id) {
+            return Err(ApiError::BadRequest("Already registered".to_string()));
+        }
+        if let Some(club_id) = model.club_id {
+            if club_member::Entity::find_by_id((club_id, player_id)).one(&txn).await?.is_none() {
+                return Err(ApiError::Forbidden("Only club members can enter this tournament".to_string()));
+            }
+        }
+
+        let player = player::Entity::find_by_id(player_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Player".to_string()))?;
+        let rating = player_rating::Entity::find_by_id((player_id, model.time_control))
+            .one(&txn)
+            .await?
+            .map(|r| r.rating)
+            .unwrap_or(DEFAULT_RATING);
+        state.players.insert(player_id, Player::new(player_id, player.username, rating));
+
+        let model = Self::save(&txn, model, &state, None).await?;
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    /// Register every participant of an organizer's CSV in one go, linking
+    /// rows to existing players or creating them. Rows that cannot be
+    /// registered are reported rather than failing the import.
+    pub async fn import_registrations(
+        db: &DatabaseConnection,
+        id: Uuid,
+        csv: &str,
+    ) -> Result<(tournament_entity::Model, RegistrationImportDisplay), ApiError> {
+        let (rows, mut rejected) = registration_import::parse_participants(csv)?;
+        let now = Utc::now().fixed_offset();
+        let txn = db.begin().await?;
+        let model = Self::lock(&txn, id).await?;
+
+        if !registration_open(&model, now) {
+            return Err(ApiError::BadRequest("Registration is not open".to_string()));
+        }
+
+        let mut state = state_of(&model)?;
+        let mut registered = Vec::new();
+        for row in rows {
+            let (player, created) = registration_import::find_or_create_player(&txn, &row).await?;
+            let reason = if !player.is_enabled {
+                Some("Account is closed")
+            } else if state.players.contains_key(&player.id) {
+                Some("Already registered")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                rejected.push(RejectedRowDisplay { line: row.line, reason: reason.to_string() });
+                continue;
+            }
+
+            let rating = match row.rating {
+                Some(rating) => rating,
+                None => player_rating::Entity::find_by_id((player.id, model.time_control))
+                    .one(&txn)
+                    .await?
+                    .map(|r| r.rating)
+                    .unwrap_or(DEFAULT_RATING),
+            };
+            state.players.insert(player.id, Player::new(player.id, player.username.clone(), rating));
+            registered.push(ImportedRegistrationDisplay {
+                line: row.line,
+                player_id: player.id,
+                username: player.username,
+                rating,
+                created,
+            });
+        }
+        rejected.sort_by_key(|row| row.line);
+
+        let model = Self::save(&txn, model, &state, None).await?;
+        txn.commit().await?;
+        Ok((model, RegistrationImportDisplay { registered, rejected }))
+    }
+
+    /// Start scheduled tournaments whose start time has passed and finish
+    /// arenas that have run their length. Returns how many changed status.
+    pub async fn advance_due(db: &DatabaseConnection, now: DateTime<FixedOffset>) -> Result<usize, ApiError> {
+        let due = tournament_entity::Entity::find()
+            .filter(
+                tournament_entity::Column::Status
+                    .eq(TournamentStatus::Registration)
+                    .and(tournament_entity::Column::StartsAt.lte(now))
+                    .or(tournament_entity::Column::Status
+                        .eq(TournamentStatus::Ongoing)
+                        .and(tournament_entity::Column::Format.eq(TournamentFormat::Arena))),
+            )
+            .all(db)
+            .await?;
+
+        let mut changed = 0;
+        for candidate in due {
+            let txn = db.begin().await?;
+            let model = Self::lock(&txn, candidate.id).await?;
+            let mut state = state_of(&model)?;
+
+            let next = match lifecycle_step(&model, state.players.len(), now) {
+                Some(status) => status,
+                None => continue,
+            };
+
+            let mut paired = false;
+            if next == TournamentStatus::Ongoing && model.format == TournamentFormat::Swiss {
+                let pairer = SwissPairer::new(config_of(&model)?);
+                paired = !state.pair_remaining(&pairer).map_err(arbiter_error)?.is_empty();
+            }
+
+            let model = Self::save(&txn, model, &state, Some(next)).await?;
+            if paired {
+                WebhookService::publish(&txn, round_paired(&model, &state)).await?;
+            }
+            if next == TournamentStatus::Finished {
+                TrophyService::award(&txn, &model, &state).await?;
+            }
+            txn.commit().await?;
+            changed += 1;
+        }
+        Ok(changed)
+    }
+
+    /// Replace the prize structure. Prizes are fixed once the tournament ends.
+    pub async fn set_prizes(
+        db: &DatabaseConnection,
+        id: Uuid,
+        prizes: Vec<PrizeDisplay>,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let structure = prize_structure(prizes)?;
+        let txn = db.begin().await?;
+        let model = Self::lock(&txn, id).await?;
+
+        if !NOT_CLOSED.contains(&model.status) {
+            return Err(status_error(model.status));
+        }
+
+        let mut active: tournament_entity::ActiveModel = model.into();
+        active.prizes = Set(to_json(&structure)?);
+        active.updated_at = Set(Utc::now().fixed_offset());
+        let model = active.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    /// End the tournament on its current standings and award its prizes.
+    pub async fn finish(db: &DatabaseConnection, id: Uuid) -> Result<tournament_entity::Model, ApiError> {
+        let txn = db.begin().await?;
+        let model = Self::lock(&txn, id).await?;
+
+        if model.status != TournamentStatus::Ongoing {
+            return Err(status_error(model.status));
+        }
+
+        let state = state_of(&model)?;
+        let model = Self::save(&txn, model, &state, Some(TournamentStatus::Finished)).await?;
+        TrophyService::award(&txn, &model, &state).await?;
+
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    pub async fn get(db: &DatabaseConnection, id: Uuid) -> Result<tournament_entity::Model, ApiError> {
+        tournament_entity::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Tournament".to_string()))
+    }
+
+    /// The tournament as a FIDE TRF16 report, taking federations, FIDE ids and
+    /// FIDE ratings from the player profiles.
+    pub async fn trf_report(
+        db: &DatabaseConnection,
+        id: Uuid,
+    ) -> Result<(tournament_entity::Model, String), ApiError> {
+        let model = Self::get(db, id).await?;
+        if model.format != TournamentFormat::Swiss {
+            return Err(ApiError::BadRequest("Arena tournaments cannot be rated".to_string()));
+        }
+        let state = state_of(&model)?;
+
+        let mut ids: Vec<Uuid> = state.players.keys().copied().collect();
+        ids.push(model.arbiter_id);
+        let profiles = player::Entity::find()
+            .filter(player::Column::Id.is_in(ids))
+            .all(db)
+            .await?;
+
+        let details: HashMap<Uuid, FideDetails> = profiles
+            .iter()
+            .map(|p| {
+                let federation = Some(p.country.to_uppercase())
+                    .filter(|c| c.len() == 3 && c.chars().all(|ch| ch.is_ascii_alphabetic()));
+                let details = FideDetails {
+                    federation,
+                    fide_id: p.fide_id.and_then(|id| u64::try_from(id).ok()),
+                    fide_rating: p.fide_rating,
+                    ..FideDetails::default()
+                };
+                (p.id, details)
+            })
+            .collect();
+        let header = TrfHeader {
+            name: model.name.clone(),
+            start: model.starts_at.map(|at| at.date_naive()),
+            end: (model.status == TournamentStatus::Finished).then(|| model.updated_at.date_naive()),
+            chief_arbiter: profiles
+                .iter()
+                .find(|p| p.id == model.arbiter_id)
+                .map(|p| if p.real_name.is_empty() { p.username.clone() } else { p.real_name.clone() }),
+            time_control: Some(format!("{:?}", model.time_control)),
+            ..TrfHeader::default()
+        };
+
+        let report = state.to_trf(&header, &details);
+        Ok((model, report))
+    }
+
+    /// Swap colors in the current-round pairing of `player_id`.
+    pub async fn swap_colors(
+        db: &DatabaseConnection,
+        id: Uuid,
+        player_id: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, IN_PROGRESS, |state, _| state.swap_colors(player_id).map(|_| ())).await
+    }
+
+    /// Pair two players in the current round, bypassing the pairer.
+    pub async fn force_pairing(
+        db: &DatabaseConnection,
+        id: Uuid,
+        white: Uuid,
+        black: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, IN_PROGRESS, |state, _| state.force_pairing(white, black).map(|_| ())).await
+    }
+
+    /// Award the current-round game of `winner` by forfeit.
+    pub async fn record_forfeit(
+        db: &DatabaseConnection,
+        id: Uuid,
+        winner: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, IN_PROGRESS, |state, _| state.record_forfeit(winner).map(|_| ())).await
+    }
+
+    /// Replace the result `player_id` scored in `round`, as decided on a
+    /// dispute. Standings of finished tournaments follow the correction too.
+    pub async fn correct_result<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        id: Uuid,
+        round: u32,
+        player_id: Uuid,
+        result: GameResult,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, SCORED, |state, _| state.correct_result(round, player_id, result).map(|_| ())).await
+    }
+
+    /// Ask for a bye in `round` on behalf of `player_id`.
+    pub async fn request_bye(
+        db: &DatabaseConnection,
+        id: Uuid,
+        player_id: Uuid,
+        round: u32,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, NOT_CLOSED, |state, _| state.request_bye(player_id, round).map(|_| ())).await
+    }
+
+    /// Withdraw `player_id` from the rounds not yet paired for them.
+    pub async fn withdraw(
+        db: &DatabaseConnection,
+        id: Uuid,
+        player_id: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, IN_PROGRESS, |state, _| state.withdraw(player_id).map(|_| ())).await
+    }
+
+    /// Bring `player_id` back in time for the next pairing, as the
+    /// tournament's re-entry policy allows.
+    pub async fn reenter(
+        db: &DatabaseConnection,
+        id: Uuid,
+        player_id: Uuid,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        Self::update_state(db, id, IN_PROGRESS, |state, config| state.reenter(player_id, config).map(|_| ())).await
+    }
+
+    /// Run the pairer over everyone not yet paired in the current round.
+    pub async fn pair_remaining(
+        db: &DatabaseConnection,
+        id: Uuid,
+    ) -> Result<(tournament_entity::Model, Vec<PairingResult>), ApiError> {
+        let mut results = Vec::new();
+        let model = Self::update_state(db, id, IN_PROGRESS, |state, config| {
+            results = state.pair_remaining(&SwissPairer::new(config.clone()))?;
+            Ok(())
+        })
+        .await?;
+        if !results.is_empty() {
+            // The pairings are stored by now, so failing to queue the event is only logged
+            if let Err(err) = WebhookService::publish(db, round_paired(&model, &state_of(&model)?)).await {
+                log::error!("Failed to queue round pairing webhooks for tournament {}: {}", model.id, err);
+            }
+        }
+        Ok((model, results))
+    }
+
+    /// The pairings the next round would get if the current round ended with
+    /// `results` (white player and result of their game), without storing
+    /// anything. A round not yet paired is previewed as it stands.
+    pub async fn preview_round(
+        db: &DatabaseConnection,
+        id: Uuid,
+        results: &[(Uuid, DisplayResult)],
+    ) -> Result<RoundDisplay, ApiError> {
+        let model = Self::get(db, id).await?;
+        if model.format != TournamentFormat::Swiss {
+            return Err(ApiError::BadRequest("Arena tournaments have no rounds".to_string()));
+        }
+        if !IN_PROGRESS.contains(&model.status) {
+            return Err(status_error(model.status));
+        }
+
+        let results: Vec<(Uuid, GameResult)> = results
+            .iter()
+            .filter_map(|&(white, result)| match result {
+                DisplayResult::WhiteWin => Some((white, GameResult::Win)),
+                DisplayResult::BlackWin => Some((white, GameResult::Loss)),
+                DisplayResult::Draw => Some((white, GameResult::Draw)),
+                DisplayResult::InProgress => None,
+            })
+            .collect();
+        let projected = state_of(&model)?.with_projected_results(&results).map_err(arbiter_error)?;
+        let preview = projected
+            .preview_remaining(&SwissPairer::new(config_of(&model)?))
+            .map_err(arbiter_error)?;
+        Ok(preview_display(&projected, &preview))
+    }
+
+    /// Load the state of a Swiss tournament under a row lock, apply `change`
+    /// and store the result, so concurrent arbiter actions cannot pair a player twice.
+    async fn update_state<C, F>(
+        db: &C,
+        id: Uuid,
+        allowed: &[TournamentStatus],
+        change: F,
+    ) -> Result<tournament_entity::Model, ApiError>
+    where
+        C: ConnectionTrait + TransactionTrait,
+        F: FnOnce(&mut TournamentState, &SwissConfig) -> Result<(), ArbiterError>,
+    {
+        let txn = db.begin().await?;
+        let model = Self::lock(&txn, id).await?;
+
+        if model.format != TournamentFormat::Swiss {
+            return Err(ApiError::BadRequest("Arena tournaments have no rounds".to_string()));
+        }
+        if !allowed.contains(&model.status) {
+            return Err(status_error(model.status));
+        }
+
+        let config = config_of(&model)?;
+        let mut state = state_of(&model)?;
+        change(&mut state, &config).map_err(arbiter_error)?;
+
+        let model = Self::save(&txn, model, &state, None).await?;
+        txn.commit().await?;
+        Ok(model)
+    }
+
+    async fn lock(txn: &DatabaseTransaction, id: Uuid) -> Result<tournament_entity::Model, ApiError> {
+        tournament_entity::Entity::find_by_id(id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Tournament".to_string()))
+    }
+
+    async fn save(
+        txn: &DatabaseTransaction,
+        model: tournament_entity::Model,
+        state: &TournamentState,
+        status: Option<TournamentStatus>,
+    ) -> Result<tournament_entity::Model, ApiError> {
+        let mut active: tournament_entity::ActiveModel = model.into();
+        active.state = Set(to_json(state)?);
+        if let Some(status) = status {
+            active.status = Set(status);
+        }
+        active.updated_at = Set(Utc::now().fixed_offset());
+        Ok(active.update(txn).await?)
+    }
+}
+
+fn status_error(status: TournamentStatus) -> ApiError {
+    ApiError::BadRequest(format!("Tournament is {:?}", status).to_lowercase())
+}
+
+const IN_PROGRESS: &[TournamentStatus] = &[TournamentStatus::Ongoing];
+const NOT_CLOSED: &[TournamentStatus] = &[TournamentStatus::Registration, TournamentStatus::Ongoing];
+const SCORED: &[TournamentStatus] = &[TournamentStatus::Ongoing, TournamentStatus::Finished];
+
+pub fn registration_open(model: &tournament_entity::Model, now: DateTime<FixedOffset>) -> bool {
+    model.status == TournamentStatus::Registration
+        && model.registration_opens_at.is_none_or(|opens| opens <= now)
+        && model.registration_closes_at.is_none_or(|closes| now < closes)
+}
+
+/// Status a tournament moves to at `now`, if any: scheduled tournaments start
+/// (or are cancelled for lack of players) and arenas finish after their length.
+pub fn lifecycle_step(
+    model: &tournament_entity::Model,
+    player_count: usize,
+    now: DateTime<FixedOffset>,
+) -> Option<TournamentStatus> {
+    let starts_at = model.starts_at?;
+
+    match model.status {
+        TournamentStatus::Registration if starts_at <= now => {
+            if player_count >= MIN_PLAYERS_TO_START {
+                Some(TournamentStatus::Ongoing)
+            } else {
+                Some(TournamentStatus::Cancelled)
+            }
+        }
+        TournamentStatus::Ongoing if model.format == TournamentFormat::Arena => {
+            let length = Duration::minutes(model.duration_minutes? as i64);
+            (starts_at + length <= now).then_some(TournamentStatus::Finished)
+        }
+        _ => None,
+    }
+}
+
+pub fn state_of(model: &tournament_entity::Model) -> Result<TournamentState, ApiError> {
+    serde_json::from_value(model.state.clone())
+        .map_err(|err| ApiError::Internal(format!("Stored tournament state is unreadable: {}", err)))
+}
+
+pub fn config_of(model: &tournament_entity::Model) -> Result<SwissConfig, ApiError> {
+    serde_json::from_value(model.config.clone())
+        .map_err(|err| ApiError::Internal(format!("Stored tournament config is unreadable: {}", err)))
+}
+
+pub fn prizes_of(model: &tournament_entity::Model) -> Result<PrizeStructure, ApiError> {
+    serde_json::from_value(model.prizes.clone())
+        .map_err(|err| ApiError::Internal(format!("Stored prize structure is unreadable: {}", err)))
+}
+
+/// Read a TRF16 report and list where its round data disagrees with itself.
+pub fn validate_trf(report: &str) -> Result<TrfValidationDisplay, ApiError> {
+    let report = tournament::trf::parse(report).map_err(|err| ApiError::BadRequest(err.to_string()))?;
+
+    Ok(TrfValidationDisplay {
+        issues: report.validate().iter().map(ToString::to_string).collect(),
+        rounds: report.players.iter().map(|p| p.rounds.len()).max().unwrap_or(0),
+        players: report.players.len(),
+        name: report.name,
+    })
+}
+
+/// Current standings with the prize each place would win.
+pub fn standings_display(model: &tournament_entity::Model) -> Result<Vec<StandingDisplay>, ApiError> {
+    let state = state_of(model)?;
+    let standings = state.standings();
+    let awards = prizes_of(model)?.award(&standings);
+
+    Ok(standings
+        .into_iter()
+        .map(|s| StandingDisplay {
+            rank: s.rank,
+            player_id: s.player,
+            prize: awards.iter().find(|a| a.player == s.player).map(|a| a.prize.clone()),
+            name: s.name,
+            rating: s.rating,
+            score: s.score,
+            buchholz: s.tiebreaks.buchholz,
+            sonneborn_berger: s.tiebreaks.sonneborn_berger,
+            wins: s.tiebreaks.wins,
+        })
+        .collect())
+}
+
+fn prize_structure(prizes: Vec<PrizeDisplay>) -> Result<PrizeStructure, ApiError> {
+    let structure = PrizeStructure {
+        prizes: prizes
+            .into_iter()
+            .map(|p| Prize {
+                name: p.name,
+                kind: match p.kind {
+                    PrizeKindDisplay::Place { from, to } => PrizeKind::Place { from, to },
+                    PrizeKindDisplay::RatingCategory { min_rating, max_rating, places } => {
+                        PrizeKind::RatingCategory { min_rating, max_rating, places }
+                    }
+                },
+            })
+            .collect(),
+    };
+    structure
+        .validate()
+        .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    Ok(structure)
+}
+
+fn prize_display(prize: Prize) -> PrizeDisplay {
+    PrizeDisplay {
+        name: prize.name,
+        kind: match prize.kind {
+            PrizeKind::Place { from, to } => PrizeKindDisplay::Place { from, to },
+            PrizeKind::RatingCategory { min_rating, max_rating, places } => {
+                PrizeKindDisplay::RatingCategory { min_rating, max_rating, places }
+            }
+        },
+    }
+}
+
+/// Public view of a tournament and its current round.
+pub fn display(model: &tournament_entity::Model) -> Result<TournamentDisplay, ApiError> {
+    let state = state_of(model)?;
+
+    Ok(TournamentDisplay {
+        id: model.id,
+        name: model.name.clone(),
+        arbiter_id: model.arbiter_id,
+        time_control: model.time_control.into(),
+        format: model.format.into(),
+        status: model.status.into(),
+        template_id: model.template_id,
+        club_id: model.club_id,
+        total_rounds: state.total_rounds,
+        completed_rounds: state.completed_rounds,
+        player_count: state.players.len(),
+        current_round: round_display(&state, state.current_round),
+        registration_opens_at: model.registration_opens_at,
+        registration_closes_at: model.registration_closes_at,
+        starts_at: model.starts_at,
+        prizes: prizes_of(model)?.prizes.into_iter().map(prize_display).collect(),
+        created_at: model.created_at,
+    })
+}
+
+// Webhook event for the pairings of the current round
+fn round_paired(model: &tournament_entity::Model, state: &TournamentState) -> WebhookEvent {
+    WebhookEvent::TournamentRoundPaired(RoundPairedData {
+        tournament_id: model.id,
+        round: round_display(state, state.current_round),
+    })
+}
+
+pub fn round_display(state: &TournamentState, round: u32) -> RoundDisplay {
+    let pairing_display = |p: &Pairing| PairingDisplay {
+        white_player_id: p.white_player,
+        black_player_id: p.black_player,
+        forfeit_winner_id: state.forfeit_of(round, p.white_player).map(|f| f.winner),
+    };
+
+    RoundDisplay {
+        round,
+        pairings: state.round_pairings(round).into_iter().map(pairing_display).collect(),
+        byes: state.round_byes(round).into_iter().map(|p| p.id).collect(),
+        requested_byes: state
+            .requested_byes
+            .iter()
+            .filter(|r| r.round == round && !state.round_byes(round).iter().any(|p| p.id == r.player))
+            .map(|r| r.player)
+            .collect(),
+        unpaired: if round == state.current_round {
+            state.unpaired_players().into_iter().map(|p| p.id).collect()
+        } else {
+            Vec::new()
+        },
+        withdrawn: state.withdrawn_players().into_iter().map(|p| p.id).collect(),
+    }
+}
+
+/// The current round of `state` with the pairer's `preview` added, as
+/// `round_display` would show it once paired.
+pub fn preview_display(state: &TournamentState, preview: &[PairingResult]) -> RoundDisplay {
+    let mut display = round_display(state, state.current_round);
+    for result in preview {
+        match result {
+            PairingResult::Paired(p) => display.pairings.push(PairingDisplay {
+                white_player_id: p.white_player,
+                black_player_id: p.black_player,
+                forfeit_winner_id: None,
+            }),
+            PairingResult::Bye(id) => display.byes.push(*id),
+        }
+    }
+    display.byes.sort();
+
+    let scheduled = |id: &Uuid| {
+        display.byes.contains(id)
+            || display.pairings.iter().any(|p| p.white_player_id == *id || p.black_player_id == *id)
+    };
+    let requested_byes = display.requested_byes.iter().copied().filter(|id| !scheduled(id)).collect();
+    let unpaired = display.unpaired.iter().copied().filter(|id| !scheduled(id)).collect();
+    display.requested_byes = requested_byes;
+    display.unpaired = unpaired;
+    display
+}
+
+pub(crate) fn arbiter_error(err: ArbiterError) -> ApiError {
+    match err {
+        ArbiterError::UnknownPlayer(id) => ApiError::NotFound(format!("Player {} in this tournament", id)),
+        other => ApiError::BadRequest(other.to_string()),
+    }
+}
+
+pub(crate) fn to_json<T: serde::Serialize>(value: &T) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(value)
+        .map_err(|err| ApiError::Internal(format!("Cannot serialize tournament: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn round_display_marks_forfeits_and_unpaired() {
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let players = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| Player::new(*id, format!("p{}", i), 1500))
+            .collect();
+        let mut state = TournamentState::new(players, 3);
+
+        state.force_pairing(ids[0], ids[1]).unwrap();
+        state.record_forfeit(ids[1]).unwrap();
+
+        let round = round_display(&state, 1);
+        assert_eq!(round.pairings.len(), 1);
+        assert_eq!(round.pairings[0].forfeit_winner_id, Some(ids[1]));
+        assert_eq!(round.unpaired, vec![ids[2]]);
+        assert!(round.withdrawn.is_empty());
+
+        state.withdraw(ids[2]).unwrap();
+        let round = round_display(&state, 1);
+        assert!(round.unpaired.is_empty());
+        assert_eq!(round.withdrawn, vec![ids[2]]);
+    }
+
+    #[test]
+    fn preview_display_adds_the_previewed_round() {
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let players = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| Player::new(*id, format!("p{}", i), 1500))
+            .collect();
+        let mut state = TournamentState::new(players, 3);
+        state.force_pairing(ids[0], ids[1]).unwrap();
+
+        let preview = state.preview_remaining(&SwissPairer::new(SwissConfig::default())).unwrap();
+        let round = preview_display(&state, &preview);
+        assert_eq!(round.pairings.len(), 1);
+        assert_eq!(round.byes, vec![ids[2]]);
+        assert!(round.unpaired.is_empty());
+        // Nothing was stored
+        assert_eq!(state.unpaired_players().len(), 1);
+    }
+
+    #[test]
+    fn invalid_prize_structures_are_rejected() {
+        let overlapping = vec![PrizeDisplay {
+            name: "Podium".to_string(),
+            kind: PrizeKindDisplay::Place { from: 3, to: 1 },
+        }];
+        assert!(matches!(prize_structure(overlapping), Err(ApiError::BadRequest(_))));
+
+        let band = PrizeDisplay {
+            name: "Best U1600".to_string(),
+            kind: PrizeKindDisplay::RatingCategory {
+                min_rating: None,
+                max_rating: Some(1600),
+                places: 2,
+            },
+        };
+        let structure = prize_structure(vec![band.clone()]).unwrap();
+        assert_eq!(structure.prizes.into_iter().map(prize_display).collect::<Vec<_>>(), vec![band]);
+    }
+
+    #[test]
+    fn arbiter_errors_map_to_client_errors() {
+        let id = Uuid::new_v4();
+        assert!(matches!(arbiter_error(ArbiterError::UnknownPlayer(id)), ApiError::NotFound(_)));
+        assert!(matches!(arbiter_error(ArbiterError::AlreadyScheduled(id)), ApiError::BadRequest(_)));
+    }
+
+    fn scheduled(format: TournamentFormat, status: TournamentStatus) -> tournament_entity::Model {
+        let starts_at = Utc.with_ymd_and_hms(2026, 10, 16, 18, 0, 0).unwrap().fixed_offset();
+        tournament_entity::Model {
+            id: Uuid::new_v4(),
+            name: "Hourly Blitz Arena".to_string(),
+            arbiter_id: Uuid::new_v4(),
+            time_control: player_rating::RatingCategory::Blitz,
+            config: serde_json::Value::Null,
+            state: serde_json::Value::Null,
+            created_at: starts_at,
+            updated_at: starts_at,
+            format,
+            status,
+            template_id: None,
+            duration_minutes: Some(57),
+            registration_opens_at: Some(starts_at - Duration::minutes(60)),
+            registration_closes_at: Some(starts_at - Duration::minutes(5)),
+            starts_at: Some(starts_at),
+            prizes: serde_json::json!({ "prizes": [] }),
+            club_id: None,
+        }
+    }
+
+    #[test]
+    fn registration_window_is_half_open() {
+        let model = scheduled(TournamentFormat::Swiss, TournamentStatus::Registration);
+        let starts_at = model.starts_at.unwrap();
+
+        assert!(!registration_open(&model, starts_at - Duration::minutes(61)));
+        assert!(registration_open(&model, starts_at - Duration::minutes(60)));
+        assert!(!registration_open(&model, starts_at - Duration::minutes(5)));
+
+        let started = scheduled(TournamentFormat::Swiss, TournamentStatus::Ongoing);
+        assert!(!registration_open(&started, starts_at - Duration::minutes(30)));
+    }
+
+    #[test]
+    fn scheduled_tournaments_start_or_cancel_at_start_time() {
+        let model = scheduled(TournamentFormat::Swiss, TournamentStatus::Registration);
+        let starts_at = model.starts_at.unwrap();
+
+        assert_eq!(lifecycle_step(&model, 8, starts_at - Duration::minutes(1)), None);
+        assert_eq!(lifecycle_step(&model, 8, starts_at), Some(TournamentStatus::Ongoing));
+        assert_eq!(lifecycle_step(&model, 1, starts_at), Some(TournamentStatus::Cancelled));
+    }
+
+    #[test]
+    fn arenas_finish_after_their_duration() {
+        let model = scheduled(TournamentFormat::Arena, TournamentStatus::Ongoing);
+        let starts_at = model.starts_at.unwrap();
+
+        assert_eq!(lifecycle_step(&model, 8, starts_at + Duration::minutes(56)), None);
+        assert_eq!(lifecycle_step(&model, 8, starts_at + Duration::minutes(57)), Some(TournamentStatus::Finished));
+
+        // Swiss events finish when their last round is played, not on a clock
+        let swiss = scheduled(TournamentFormat::Swiss, TournamentStatus::Ongoing);
+        assert_eq!(lifecycle_step(&swiss, 8, starts_at + Duration::days(1)), None);
+    }
+
+    #[test]
+    fn unreadable_stored_tournaments_are_server_errors() {
+        // `scheduled` leaves the state and config empty
+        let model = scheduled(TournamentFormat::Swiss, TournamentStatus::Ongoing);
+        assert!(matches!(state_of(&model), Err(ApiError::Internal(_))));
+        assert!(matches!(config_of(&model), Err(ApiError::Internal(_))));
+        assert_eq!(ApiError::Internal("x".to_string()).error_response().status(), 500);
+    }
+}
@@ -0,0 +1,340 @@
+use chrono::{Duration, Utc};
+use db_entity::{player, training_session};
+use dto::training::{
+    BestScore, DrillStats, Exercise, ExerciseResult, TrainingDrill, TrainingLeaderboardEntry,
+    TrainingResultDisplay, TrainingSessionDisplay,
+};
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Exercises in every session, so that scores of a drill compare
+pub const SESSION_EXERCISES: usize = 20;
+
+/// Time allowed between starting a session and submitting its answers
+pub const SESSION_TIMEOUT_SECS: i64 = 600;
+
+/// Longest answer accepted, enough for a queen's full vision
+const MAX_ANSWER_LEN: usize = 200;
+
+pub const MAX_LEADERBOARD_ENTRIES: u64 = 100;
+
+/// Finished sessions read to build a leaderboard, best first
+const LEADERBOARD_SCAN: u64 = 2000;
+
+const DRILLS: [TrainingDrill; 4] = [
+    TrainingDrill::FindSquare,
+    TrainingDrill::NameSquare,
+    TrainingDrill::SquareColor,
+    TrainingDrill::PieceVision,
+];
+
+pub struct TrainingService;
+
+impl TrainingService {
+    /// Start a session of `SESSION_EXERCISES` random exercises.
+    pub async fn start(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        drill: TrainingDrill,
+    ) -> Result<TrainingSessionDisplay, ApiError> {
+        let generator = chess_drill(drill);
+        let mut rng = rand::thread_rng();
+        let exercises: Vec<chess::Exercise> =
+            (0..SESSION_EXERCISES).map(|_| generator.generate(&mut rng)).collect();
+
+        let model = training_session::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            drill: Set(drill.into()),
+            exercises: Set(serde_json::to_value(&exercises).unwrap_or_default()),
+            answers: Set(None),
+            correct: Set(None),
+            duration_ms: Set(None),
+            started_at: Set(Utc::now().fixed_offset()),
+            finished_at: Set(None),
+        }
+        .insert(db)
+        .await?;
+
+        Ok(TrainingSessionDisplay {
+            id: model.id,
+            drill,
+            exercises: exercises_of(&model)?,
+            started_at: model.started_at,
+            expires_at: model.started_at + Duration::seconds(SESSION_TIMEOUT_SECS),
+        })
+    }
+
+    /// Score the answers of one of the player's sessions. The time taken is
+    /// measured by the server, from the start of the session.
+    pub async fn submit(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        session_id: Uuid,
+        answers: Vec<String>,
+    ) -> Result<TrainingResultDisplay, ApiError> {
+        let session = training_session::Entity::find_by_id(session_id)
+            .one(db)
+            .await?
+            .filter(|session| session.player_id == player_id)
+            .ok_or_else(|| ApiError::NotFound("Training session".to_string()))?;
+        if session.finished_at.is_some() {
+            return Err(ApiError::BadRequest("Answers were already submitted for this session".to_string()));
+        }
+
+        let now = Utc::now().fixed_offset();
+        let elapsed = now - session.started_at;
+        if elapsed > Duration::seconds(SESSION_TIMEOUT_SECS) {
+            return Err(ApiError::BadRequest("The session has expired".to_string()));
+        }
+
+        let exercises = exercises_of(&session)?;
+        if answers.len() != exercises.len() {
+            return Err(ApiError::BadRequest(format!("Expected {} answers", exercises.len())));
+        }
+        if answers.iter().any(|answer| answer.len() > MAX_ANSWER_LEN) {
+            return Err(ApiError::BadRequest(format!("Answers are at most {} characters", MAX_ANSWER_LEN)));
+        }
+
+        let drill = TrainingDrill::from(session.drill);
+        let results = score(drill, exercises, answers)?;
+        let correct = results.iter().filter(|result| result.correct).count() as u32;
+        let duration_ms = elapsed.num_milliseconds().max(0) as u32;
+
+        let previous = Self::finished(db, player_id, drill).await?;
+        let personal_best = best_of(&previous).is_none_or(|best| beats((correct, duration_ms), &best));
+
+        let submitted: Vec<&String> = results.iter().map(|result| &result.answer).collect();
+        let mut active: training_session::ActiveModel = session.into();
+        active.answers = Set(Some(serde_json::to_value(submitted).unwrap_or_default()));
+        active.correct = Set(Some(correct as i32));
+        active.duration_ms = Set(Some(duration_ms as i32));
+        active.finished_at = Set(Some(now));
+        let model = active.update(db).await?;
+
+        let total = results.len() as u32;
+        Ok(TrainingResultDisplay {
+            id: model.id,
+            drill,
+            correct,
+            total,
+            accuracy: ratio(correct, total),
+            duration_ms,
+            personal_best,
+            results,
+        })
+    }
+
+    /// The player's accuracy, timing and best score on every drill.
+    pub async fn stats(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<DrillStats>, ApiError> {
+        let mut stats = Vec::with_capacity(DRILLS.len());
+        for drill in DRILLS {
+            let sessions = Self::finished(db, player_id, drill).await?;
+            stats.push(drill_stats(drill, &sessions));
+        }
+        Ok(stats)
+    }
+
+    /// Best score of each player on a drill, best first.
+    pub async fn leaderboard(
+        db: &DatabaseConnection,
+        drill: TrainingDrill,
+        limit: u64,
+    ) -> Result<Vec<TrainingLeaderboardEntry>, ApiError> {
+        let sessions = training_session::Entity::find()
+            .filter(training_session::Column::Drill.eq(training_session::TrainingDrill::from(drill)))
+            .filter(training_session::Column::FinishedAt.is_not_null())
+            .order_by_desc(training_session::Column::Correct)
+            .order_by_asc(training_session::Column::DurationMs)
+            .order_by_asc(training_session::Column::FinishedAt)
+            .limit(LEADERBOARD_SCAN)
+            .find_also_related(player::Entity)
+            .all(db)
+            .await?;
+
+        let mut seen = HashSet::new();
+        Ok(sessions
+            .into_iter()
+            .filter(|(session, _)| seen.insert(session.player_id))
+            .take(limit.min(MAX_LEADERBOARD_ENTRIES) as usize)
+            .enumerate()
+            .filter_map(|(index, (session, player))| {
+                Some(TrainingLeaderboardEntry {
+                    rank: index as u32 + 1,
+                    player_id: session.player_id,
+                    username: player.map(|p| p.username).unwrap_or_default(),
+                    best: best_score(&session)?,
+                })
+            })
+            .collect())
+    }
+
+    async fn finished(
+        db: &DatabaseConnection,
+        player_id: Uuid,
+        drill: TrainingDrill,
+    ) -> Result<Vec<training_session::Model>, ApiError> {
+        Ok(training_session::Entity::find()
+            .filter(training_session::Column::PlayerId.eq(player_id))
+            .filter(training_session::Column::Drill.eq(training_session::TrainingDrill::from(drill)))
+            .filter(training_session::Column::FinishedAt.is_not_null())
+            .all(db)
+            .await?)
+    }
+}
+
+fn chess_drill(drill: TrainingDrill) -> chess::Drill {
+    match drill {
+        TrainingDrill::FindSquare => chess::Drill::FindSquare,
+        TrainingDrill::NameSquare => chess::Drill::NameSquare,
+        TrainingDrill::SquareColor => chess::Drill::SquareColor,
+        TrainingDrill::PieceVision => chess::Drill::PieceVision,
+    }
+}
+
+// The DTO exercises mirror `chess::Exercise` field for field
+fn exercises_of(session: &training_session::Model) -> Result<Vec<Exercise>, ApiError> {
+    serde_json::from_value(session.exercises.clone())
+        .map_err(|err| ApiError::Internal(format!("Stored exercises are unreadable: {}", err)))
+}
+
+/// Check each answer against its exercise.
+pub fn score(drill: TrainingDrill, exercises: Vec<Exercise>, answers: Vec<String>) -> Result<Vec<ExerciseResult>, ApiError> {
+    let drill = chess_drill(drill);
+    exercises
+        .into_iter()
+        .zip(answers)
+        .map(|(exercise, answer)| {
+            let checked: chess::Exercise = serde_json::to_value(&exercise)
+                .and_then(serde_json::from_value)
+                .map_err(|err| ApiError::Internal(format!("Invalid exercise: {}", err)))?;
+            let solution = drill
+                .solution(&checked)
+                .ok_or_else(|| ApiError::BadRequest("Invalid exercise".to_string()))?;
+            Ok(ExerciseResult {
+                correct: drill.check(&checked, &answer),
+                exercise,
+                answer,
+                solution,
+            })
+        })
+        .collect()
+}
+
+/// Whether `(correct, duration_ms)` is better than `best`: more correct
+/// answers, or as many in less time.
+fn beats((correct, duration_ms): (u32, u32), best: &BestScore) -> bool {
+    correct > best.correct || (correct == best.correct && duration_ms < best.duration_ms)
+}
+
+fn best_score(session: &training_session::Model) -> Option<BestScore> {
+    let exercises = session.exercises.as_array().map_or(0, Vec::len) as u32;
+    Some(BestScore {
+        session_id: session.id,
+        correct: session.correct? as u32,
+        total: exercises,
+        duration_ms: session.duration_ms? as u32,
+        achieved_at: session.finished_at?,
+    })
+}
+
+fn best_of(sessions: &[training_session::Model]) -> Option<BestScore> {
+    sessions.iter().filter_map(best_score).fold(None, |best, score| match best {
+        Some(best) if !beats((score.correct, score.duration_ms), &best) => Some(best),
+        _ => Some(score),
+    })
+}
+
+fn ratio(correct: u32, total: u32) -> f64 {
+    if total == 0 { 0.0 } else { correct as f64 / total as f64 }
+}
+
+/// Totals over a player's finished sessions of one drill.
+pub fn drill_stats(drill: TrainingDrill, sessions: &[training_session::Model]) -> DrillStats {
+    let scored: Vec<BestScore> = sessions.iter().filter_map(best_score).collect();
+    let exercises: u32 = scored.iter().map(|s| s.total).sum();
+    let correct: u32 = scored.iter().map(|s| s.correct).sum();
+    let duration: u64 = scored.iter().map(|s| s.duration_ms as u64).sum();
+
+    DrillStats {
+        drill,
+        sessions: scored.len() as u32,
+        exercises,
+        correct,
+        accuracy: ratio(correct, exercises),
+        average_ms_per_exercise: (exercises > 0).then(|| (duration / exercises as u64) as u32),
+        best: best_of(sessions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(correct: i32, duration_ms: i32) -> training_session::Model {
+        let now = Utc::now().fixed_offset();
+        let exercises: Vec<Exercise> = (0..SESSION_EXERCISES)
+            .map(|_| Exercise {
+                square: "a1".to_string(),
+                piece: None,
+                blockers: Vec::new(),
+            })
+            .collect();
+        training_session::Model {
+            id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            drill: training_session::TrainingDrill::FindSquare,
+            exercises: serde_json::to_value(exercises).unwrap(),
+            answers: None,
+            correct: Some(correct),
+            duration_ms: Some(duration_ms),
+            started_at: now,
+            finished_at: Some(now),
+        }
+    }
+
+    #[test]
+    fn scores_each_answer() {
+        let exercises = vec![
+            Exercise {
+                square: "h8".to_string(),
+                piece: None,
+                blockers: Vec::new(),
+            },
+            Exercise {
+                square: "a1".to_string(),
+                piece: None,
+                blockers: Vec::new(),
+            },
+        ];
+        let results = score(
+            TrainingDrill::SquareColor,
+            exercises,
+            vec!["dark".to_string(), "light".to_string()],
+        )
+        .unwrap();
+        assert!(results[0].correct);
+        assert!(!results[1].correct);
+        assert_eq!(results[1].solution, "dark");
+    }
+
+    #[test]
+    fn best_score_prefers_more_correct_then_faster() {
+        let sessions = vec![session(15, 30_000), session(18, 60_000), session(18, 45_000)];
+        let best = best_of(&sessions).unwrap();
+        assert_eq!((best.correct, best.duration_ms), (18, 45_000));
+        assert!(beats((18, 40_000), &best));
+        assert!(!beats((17, 10_000), &best));
+
+        let stats = drill_stats(TrainingDrill::FindSquare, &sessions);
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(stats.exercises, 60);
+        assert_eq!(stats.correct, 51);
+        assert_eq!(stats.average_ms_per_exercise, Some(2250));
+        assert!(drill_stats(TrainingDrill::FindSquare, &[]).best.is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::Utc;
+use db_entity::{player_trophy, tournament as tournament_entity};
+use dto::tournaments::TrophyDisplay;
+use error::error::ApiError;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use tournament::TournamentState;
+use uuid::Uuid;
+
+use crate::tournaments::prizes_of;
+
+pub struct TrophyService;
+
+impl TrophyService {
+    /// Award the prizes of a finished tournament from its final standings.
+    /// Any earlier awards for the tournament are replaced.
+    pub async fn award<C: ConnectionTrait>(
+        conn: &C,
+        model: &tournament_entity::Model,
+        state: &TournamentState,
+    ) -> Result<Vec<player_trophy::Model>, ApiError> {
+        let awards = prizes_of(model)?.award(&state.standings());
+        let now = Utc::now().fixed_offset();
+
+        player_trophy::Entity::delete_many()
+            .filter(player_trophy::Column::TournamentId.eq(model.id))
+            .exec(conn)
+            .await?;
+
+        let mut trophies = Vec::with_capacity(awards.len());
+        for award in awards {
+            let trophy = player_trophy::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                player_id: Set(award.player),
+                tournament_id: Set(model.id),
+                prize: Set(award.prize),
+                rank: Set(award.rank as i32),
+                score: Set(award.score as f64),
+                awarded_at: Set(now),
+            };
+            trophies.push(trophy.insert(conn).await?);
+        }
+        Ok(trophies)
+    }
+
+    /// Trophies shown on a player's profile, newest first.
+    pub async fn for_player(db: &DatabaseConnection, player_id: Uuid) -> Result<Vec<TrophyDisplay>, ApiError> {
+        let trophies = player_trophy::Entity::find()
+            .filter(player_trophy::Column::PlayerId.eq(player_id))
+            .order_by_desc(player_trophy::Column::AwardedAt)
+            .find_also_related(tournament_entity::Entity)
+            .all(db)
+            .await?;
+
+        Ok(trophies
+            .into_iter()
+            .map(|(trophy, tournament)| TrophyDisplay {
+                tournament_id: trophy.tournament_id,
+                tournament_name: tournament.map(|t| t.name).unwrap_or_default(),
+                prize: trophy.prize,
+                rank: trophy.rank,
+                score: trophy.score,
+                awarded_at: trophy.awarded_at,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::Utc;
+use db_entity::{player, player_wallet};
+use error::error::ApiError;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use uuid::Uuid;
+
+pub struct WalletService;
+
+impl WalletService {
+    /// Link a verified account address to `player_id`. Linking an address
+    /// the player already holds is a no-op; one held by someone else is refused.
+    pub async fn link(db: &DatabaseConnection, player_id: Uuid, address: &str) -> Result<player_wallet::Model, ApiError> {
+        if let Some(existing) = player_wallet::Entity::find_by_id(address.to_string()).one(db).await? {
+            if existing.player_id == player_id {
+                return Ok(existing);
+            }
+            return Err(ApiError::BadRequest("Wallet is linked to another account".to_string()));
+        }
+
+        Ok(player_wallet::ActiveModel {
+            address: Set(address.to_string()),
+            player_id: Set(player_id),
+            linked_at: Set(Utc::now().fixed_offset()),
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Player the address is linked to, if any.
+    pub async fn find_player(db: &DatabaseConnection, address: &str) -> Result<Option<player::Model>, ApiError> {
+        Ok(player_wallet::Entity::find_by_id(address.to_string())
+            .find_also_related(player::Entity)
+            .one(db)
+            .await?
+            .and_then(|(_, player)| player))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    const ADDRESS: &str = "0x0000000000000000000000000000000000000000000000000000000000000abc";
+
+    fn wallet(player_id: Uuid) -> player_wallet::Model {
+        player_wallet::Model {
+            address: ADDRESS.to_string(),
+            player_id,
+            linked_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    #[tokio::test]
+    async fn addresses_stay_with_the_first_account_that_links_them() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![wallet(owner)], vec![wallet(owner)]])
+            .into_connection();
+
+        assert_eq!(WalletService::link(&db, owner, ADDRESS).await.unwrap().player_id, owner);
+        assert!(matches!(
+            WalletService::link(&db, other, ADDRESS).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+}
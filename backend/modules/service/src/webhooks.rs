@@ -0,0 +1,366 @@
+use chrono::{Duration, Utc};
+use db_entity::webhook_delivery::{self, WebhookDeliveryStatus};
+use db_entity::webhook_subscription;
+use dto::webhooks::{CreateWebhookRequest, WebhookEvent, WebhookPayload};
+use error::error::ApiError;
+use hmac::{Hmac, Mac};
+use reqwest::{redirect, Client, Url};
+use sea_orm::sea_query::{Expr, PgFunc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use sha2::Sha256;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Subscriptions one player may have
+pub const MAX_WEBHOOKS_PER_PLAYER: u64 = 10;
+
+/// Deliveries sent per pass of the sender
+pub const WEBHOOK_BATCH: u64 = 50;
+
+/// Attempts at one delivery before it is marked failed
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Delay before the first retry; doubled after each further failure
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// Longest response or transport error kept in the delivery log
+const MAX_ERROR_LEN: usize = 500;
+
+const USER_AGENT: &str = "StarkMate webhooks";
+
+/// What one pass of the sender did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryPass {
+    pub delivered: usize,
+    pub retried: usize,
+    pub failed: usize,
+}
+
+/// HTTP client that posts signed payloads to webhook URLs. Redirects are
+/// not followed, so an endpoint cannot bounce requests onto private hosts.
+#[derive(Debug, Clone)]
+pub struct WebhookSender {
+    client: Client,
+}
+
+impl WebhookSender {
+    pub fn new(timeout: std::time::Duration) -> Self {
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(timeout)
+            .redirect(redirect::Policy::none())
+            .build()
+            .expect("Failed to build the HTTP client");
+        WebhookSender { client }
+    }
+
+    /// Post one delivery; the HTTP status the endpoint answered with, or why
+    /// it could not be reached.
+    async fn send(
+        &self,
+        subscription: &webhook_subscription::Model,
+        delivery: &webhook_delivery::Model,
+    ) -> Result<u16, String> {
+        let body = serde_json::to_vec(&delivery.payload).map_err(|err| err.to_string())?;
+        let response = self
+            .client
+            .post(&subscription.url)
+            .header("Content-Type", "application/json")
+            .header("X-StarkMate-Event", &delivery.event)
+            .header("X-StarkMate-Delivery", delivery.id.to_string())
+            .header("X-StarkMate-Signature", signature(&subscription.secret, Utc::now().timestamp(), &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+pub struct WebhookService;
+
+impl WebhookService {
+    /// Register a URL for `owner_id` to be sent the given events.
+    pub async fn create(
+        db: &DatabaseConnection,
+        owner_id: Uuid,
+        request: CreateWebhookRequest,
+    ) -> Result<webhook_subscription::Model, ApiError> {
+        let url = check_url(&request.url)?;
+
+        let existing = webhook_subscription::Entity::find()
+            .filter(webhook_subscription::Column::OwnerId.eq(owner_id))
+            .count(db)
+            .await?;
+        if existing >= MAX_WEBHOOKS_PER_PLAYER {
+            return Err(ApiError::BadRequest(format!(
+                "At most {} webhooks per player",
+                MAX_WEBHOOKS_PER_PLAYER
+            )));
+        }
+
+        let mut events: Vec<String> = request.events.iter().map(|e| e.as_str().to_string()).collect();
+        events.sort();
+        events.dedup();
+
+        let now = Utc::now().fixed_offset();
+        let subscription = webhook_subscription::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            owner_id: Set(owner_id),
+            url: Set(url.to_string()),
+            secret: Set(request.secret),
+            events: Set(events),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await?;
+        Ok(subscription)
+    }
+
+    pub async fn list(db: &DatabaseConnection, owner_id: Uuid) -> Result<Vec<webhook_subscription::Model>, ApiError> {
+        Ok(webhook_subscription::Entity::find()
+            .filter(webhook_subscription::Column::OwnerId.eq(owner_id))
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(db)
+            .await?)
+    }
+
+    /// Remove a subscription along with its delivery log.
+    pub async fn delete(db: &DatabaseConnection, owner_id: Uuid, id: Uuid) -> Result<(), ApiError> {
+        let subscription = Self::owned(db, owner_id, id).await?;
+        subscription.delete(db).await?;
+        Ok(())
+    }
+
+    /// Latest `limit` deliveries of a subscription, newest first.
+    pub async fn deliveries(
+        db: &DatabaseConnection,
+        owner_id: Uuid,
+        id: Uuid,
+        limit: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, ApiError> {
+        let subscription = Self::owned(db, owner_id, id).await?;
+        Ok(webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::SubscriptionId.eq(subscription.id))
+            .order_by_desc(webhook_delivery::Column::CreatedAt)
+            .limit(limit.clamp(1, 200))
+            .all(db)
+            .await?)
+    }
+
+    /// Queue `event` for every subscription to its type. Called inside the
+    /// transaction that makes the change, so events are queued exactly when
+    /// the change is stored. Returns how many deliveries were queued.
+    pub async fn publish<C: ConnectionTrait>(db: &C, event: WebhookEvent) -> Result<usize, ApiError> {
+        let name = event.event_type().as_str();
+        let subscriptions = webhook_subscription::Entity::find()
+            .filter(Expr::val(name).eq(PgFunc::any(Expr::col(webhook_subscription::Column::Events))))
+            .all(db)
+            .await?;
+
+        let now = Utc::now();
+        for subscription in &subscriptions {
+            let id = Uuid::new_v4();
+            let payload = WebhookPayload { id, created_at: now, event: event.clone() };
+            webhook_delivery::ActiveModel {
+                id: Set(id),
+                subscription_id: Set(subscription.id),
+                event: Set(name.to_string()),
+                payload: Set(serde_json::to_value(&payload)
+                    .map_err(|err| ApiError::Internal(format!("Cannot serialize webhook payload: {}", err)))?),
+                status: Set(WebhookDeliveryStatus::Pending),
+                attempts: Set(0),
+                response_status: Set(None),
+                last_error: Set(None),
+                next_attempt_at: Set(now.fixed_offset()),
+                created_at: Set(now.fixed_offset()),
+                delivered_at: Set(None),
+            }
+            .insert(db)
+            .await?;
+        }
+        Ok(subscriptions.len())
+    }
+
+    /// Send pending deliveries whose retry time has come.
+    pub async fn deliver_due(db: &DatabaseConnection, sender: &WebhookSender) -> Result<DeliveryPass, ApiError> {
+        let due = webhook_delivery::Entity::find()
+            .filter(webhook_delivery::Column::Status.eq(WebhookDeliveryStatus::Pending))
+            .filter(webhook_delivery::Column::NextAttemptAt.lte(Utc::now().fixed_offset()))
+            .order_by_asc(webhook_delivery::Column::NextAttemptAt)
+            .limit(WEBHOOK_BATCH)
+            .find_also_related(webhook_subscription::Entity)
+            .all(db)
+            .await?;
+
+        let mut pass = DeliveryPass::default();
+        for (row, subscription) in due {
+            let Some(subscription) = subscription else { continue };
+            let attempts = row.attempts + 1;
+            let outcome = sender.send(&subscription, &row).await;
+            let now = Utc::now().fixed_offset();
+
+            let mut active = row.clone().into_active_model();
+            active.attempts = Set(attempts);
+            match outcome {
+                Ok(status) if (200..300).contains(&status) => {
+                    active.status = Set(WebhookDeliveryStatus::Delivered);
+                    active.response_status = Set(Some(status as i16));
+                    active.last_error = Set(None);
+                    active.delivered_at = Set(Some(now));
+                    pass.delivered += 1;
+                }
+                outcome => {
+                    let (status, error) = match outcome {
+                        Ok(status) => (Some(status as i16), format!("Endpoint answered {}", status)),
+                        Err(err) => (None, err),
+                    };
+                    log::warn!("Webhook delivery {} failed (attempt {}): {}", row.id, attempts, error);
+                    let failed = attempts >= MAX_ATTEMPTS;
+                    active.status =
+                        Set(if failed { WebhookDeliveryStatus::Failed } else { WebhookDeliveryStatus::Pending });
+                    active.response_status = Set(status);
+                    active.last_error = Set(Some(error.chars().take(MAX_ERROR_LEN).collect()));
+                    active.next_attempt_at = Set(now + retry_delay(attempts));
+                    if failed {
+                        pass.failed += 1;
+                    } else {
+                        pass.retried += 1;
+                    }
+                }
+            }
+            active.update(db).await?;
+        }
+        Ok(pass)
+    }
+
+    async fn owned(
+        db: &DatabaseConnection,
+        owner_id: Uuid,
+        id: Uuid,
+    ) -> Result<webhook_subscription::Model, ApiError> {
+        webhook_subscription::Entity::find_by_id(id)
+            .filter(webhook_subscription::Column::OwnerId.eq(owner_id))
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Webhook".to_string()))
+    }
+}
+
+/// Value of the `X-StarkMate-Signature` header: the send time and the
+/// HMAC-SHA256 of `"{timestamp}.{body}"` keyed with the subscription secret.
+/// Receivers recompute it and reject stale timestamps to stop replays.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("t={},v1={}", timestamp, digest)
+}
+
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::seconds((RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS))
+}
+
+// Webhooks go to public http(s) endpoints only, so the server cannot be
+// pointed at itself or at hosts on its private network
+fn check_url(raw: &str) -> Result<Url, ApiError> {
+    let invalid = |reason: &str| ApiError::BadRequest(format!("Webhook URL {}", reason));
+    let url = Url::parse(raw).map_err(|_| invalid("is not a valid URL"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid("must use http or https"));
+    }
+    let host = url.host_str().ok_or_else(|| invalid("has no host"))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let private = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let first = ip.segments()[0];
+            // Unique local fc00::/7 and link-local fe80::/10
+            ip.is_loopback() || ip.is_unspecified() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+        }
+        Err(_) => host.eq_ignore_ascii_case("localhost") || host.to_ascii_lowercase().ends_with(".localhost"),
+    };
+    if private {
+        return Err(invalid("must point to a public host"));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dto::games::GameResult;
+    use dto::webhooks::GameFinishedData;
+
+    #[test]
+    fn signature_is_hmac_of_timestamp_and_body() {
+        assert_eq!(
+            signature("whsec_test_secret_1234", 1_700_000_000, br#"{"event":"game.finished"}"#),
+            "t=1700000000,v1=ea6d137d0c4ec44200413b37230bba24f3e1f7075bf00b04cab189180ece66bf"
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), Duration::seconds(RETRY_MAX_SECS));
+    }
+
+    #[test]
+    fn check_url_accepts_public_http_hosts_only() {
+        assert!(check_url("https://example.com/hooks").is_ok());
+        assert!(check_url("http://203.0.113.7:8080/hook").is_ok());
+
+        for url in [
+            "ftp://example.com/hook",
+            "not a url",
+            "http://localhost:3000/hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd12::1]/hook",
+        ] {
+            assert!(check_url(url).is_err(), "{} should be rejected", url);
+        }
+    }
+
+    #[test]
+    fn payload_carries_event_name_and_data() {
+        let game_id = Uuid::new_v4();
+        let payload = WebhookPayload {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            event: WebhookEvent::GameFinished(GameFinishedData {
+                game_id,
+                white_player_id: Uuid::new_v4(),
+                black_player_id: Uuid::new_v4(),
+                result: GameResult::Draw,
+                termination: Some("agreement".to_string()),
+                fen: "8/8/8/8/8/8/8/K6k w - - 0 1".to_string(),
+                moves: Vec::new(),
+            }),
+        };
+
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["event"], "game.finished");
+        assert_eq!(value["data"]["game_id"], game_id.to_string());
+        assert_eq!(value["data"]["result"], "draw");
+        assert_eq!(value["id"], payload.id.to_string());
+    }
+}
@@ -0,0 +1,156 @@
+//! Tracing shared by the API, the socket server and the gRPC services.
+//!
+//! Each request gets a request id where it enters the system: the
+//! `X-Request-Id` header of an HTTP request when the caller sent a usable
+//! one, a fresh id otherwise, and a fresh id for every socket message. The
+//! id is recorded on the request's span, so everything logged while serving
+//! it, database queries included, carries it, and [`with_request_id`] keeps
+//! it at hand for calls to other services, which pass it on in their own
+//! `x-request-id` header.
+//!
+//! [`init`] installs the subscriber. `log` records are forwarded to it, so
+//! modules that log through the `log` crate show up inside their spans.
+//! With the `otlp` feature, spans are also exported to the OpenTelemetry
+//! collector at `OTEL_EXPORTER_OTLP_ENDPOINT` when it is set.
+
+use std::future::Future;
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+use uuid::Uuid;
+
+/// Header carrying the request id, on HTTP requests and responses and on
+/// gRPC calls between services
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest request id taken from a caller
+pub const MAX_REQUEST_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// A fresh request id.
+pub fn new_request_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// The id a caller sent, if it is 1 to 64 letters, digits, `-`, `_` or
+/// `.`, otherwise a fresh one, so ids cannot smuggle anything into logs.
+pub fn request_id_from(sent: Option<&str>) -> String {
+    match sent {
+        Some(id)
+            if !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) =>
+        {
+            id.to_string()
+        }
+        _ => new_request_id(),
+    }
+}
+
+/// Run `future` as part of the request `id`.
+pub async fn with_request_id<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// Id of the request the current task serves, if it runs under
+/// [`with_request_id`].
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Flushes exported spans when dropped; keep it until the process exits.
+#[must_use]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush exported spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the subscriber for `service_name`, filtered by `RUST_LOG` or
+/// else `default_filter`. Must run inside a Tokio runtime when spans are
+/// exported.
+pub fn init(service_name: &str, default_filter: &str) -> TelemetryGuard {
+    let (otlp, guard) = otlp_layer(service_name, default_filter);
+    let result = Registry::default()
+        .with(otlp)
+        .with(tracing_subscriber::fmt::layer().with_filter(filter(default_filter)))
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Tracing was already set up: {}", e);
+    }
+    guard
+}
+
+fn filter(default_filter: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter))
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+#[cfg(feature = "otlp")]
+fn otlp_layer(service_name: &str, default_filter: &str) -> (Option<BoxedLayer>, TelemetryGuard) {
+    use opentelemetry::trace::TracerProvider;
+
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").map_or(true, |endpoint| endpoint.is_empty()) {
+        return (None, TelemetryGuard { provider: None });
+    }
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_tonic().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to set up the OTLP exporter: {}; spans are not exported", e);
+            return (None, TelemetryGuard { provider: None });
+        }
+    };
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service_name.to_string());
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(opentelemetry_sdk::Resource::builder().with_service_name(service_name.clone()).build())
+        .build();
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(service_name))
+        .with_filter(filter(default_filter));
+    (Some(Box::new(layer)), TelemetryGuard { provider: Some(provider) })
+}
+
+#[cfg(not(feature = "otlp"))]
+fn otlp_layer(_service_name: &str, _default_filter: &str) -> (Option<BoxedLayer>, TelemetryGuard) {
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok_and(|endpoint| !endpoint.is_empty()) {
+        eprintln!("OTEL_EXPORTER_OTLP_ENDPOINT is set, but this build has no `otlp` feature; spans are not exported");
+    }
+    (None, TelemetryGuard {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_plain_request_ids_are_taken_from_callers() {
+        assert_eq!(request_id_from(Some("req-42_a.b")), "req-42_a.b");
+        for sent in [None, Some(""), Some("two words"), Some("line\nbreak"), Some(&"x".repeat(65)[..])] {
+            let id = request_id_from(sent);
+            assert!(Uuid::parse_str(&id).is_ok(), "{:?} was kept", sent);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_the_request_id_is_visible_to_the_task_serving_it() {
+        assert_eq!(current_request_id(), None);
+        let seen = with_request_id("abc".to_string(), async { current_request_id() }).await;
+        assert_eq!(seen.as_deref(), Some("abc"));
+        assert_eq!(current_request_id(), None);
+    }
+}
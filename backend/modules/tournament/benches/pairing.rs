@@ -0,0 +1,101 @@
+//! Swiss pairing of a large open: `cargo bench -p tournament`. A round of
+//! 5,000 players should pair in well under 100ms, both in the pairer alone
+//! and through the arbiter pairing the rest of a round.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use tournament::{BakuAcceleration, GameResult, Player, SwissConfig, SwissPairer, TournamentState};
+use uuid::Uuid;
+
+const PLAYERS: u128 = 5_000;
+
+fn config(acceleration: Option<BakuAcceleration>) -> SwissConfig {
+    SwissConfig {
+        total_rounds: 9,
+        deterministic_seed: Some(2_000),
+        acceleration,
+        ..SwissConfig::default()
+    }
+}
+
+/// A field of `PLAYERS` with `rounds` already played. Results follow rating
+/// with some upsets and draws so the score groups spread out like a real open.
+fn open(config: &SwissConfig, rounds: u32) -> TournamentState {
+    let players = (0..PLAYERS)
+        .map(|i| {
+            let rating = 1_000 + ((i * 7_919) % 1_800) as i32;
+            Player::new(Uuid::from_u128(i + 1), format!("Player {}", i + 1), rating)
+        })
+        .collect();
+    let mut tournament = TournamentState::new(players, config.total_rounds);
+    let pairer = SwissPairer::new(config.clone());
+
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..rounds {
+        let paired = pairer.pair_round(&tournament).expect("round pairs");
+        pairer.commit_round(&mut tournament, &paired).expect("round commits");
+        let mut results = Vec::new();
+        for pairing in &paired.pairings {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let favourite_is_white = tournament.players[&pairing.white_player].rating
+                >= tournament.players[&pairing.black_player].rating;
+            let (white, black) = match (seed % 10, favourite_is_white) {
+                (0..=1, _) => (GameResult::Draw, GameResult::Draw),
+                (2, true) | (3.., false) => (GameResult::Loss, GameResult::Win),
+                _ => (GameResult::Win, GameResult::Loss),
+            };
+            results.push((pairing.white_player, white));
+            results.push((pairing.black_player, black));
+        }
+        tournament.apply_round_results(results);
+    }
+    tournament
+}
+
+fn pair_round(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pair_round_5000");
+    group.sample_size(20);
+    for (name, acceleration) in [("plain", None), ("accelerated", Some(BakuAcceleration::standard(9)))] {
+        let config = config(acceleration);
+        let pairer = SwissPairer::new(config.clone());
+        for rounds in [0, 1] {
+            let tournament = open(&config, rounds);
+            group.bench_with_input(BenchmarkId::new(name, rounds + 1), &tournament, |b, tournament| {
+                b.iter(|| pairer.pair_round(tournament).expect("round pairs"))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// The arbiter path the server takes: one board forced by hand, then the
+/// rest of the field paired around it.
+fn pair_remaining(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pair_remaining_5000");
+    group.sample_size(20);
+    for (name, acceleration) in [("plain", None), ("accelerated", Some(BakuAcceleration::standard(9)))] {
+        let config = config(acceleration);
+        let pairer = SwissPairer::new(config.clone());
+        for rounds in [0, 1] {
+            let mut tournament = open(&config, rounds);
+            tournament
+                .force_pairing(Uuid::from_u128(1), Uuid::from_u128(2))
+                .expect("board forced");
+            group.bench_with_input(BenchmarkId::new(name, rounds + 1), &tournament, |b, tournament| {
+                b.iter_batched(
+                    || tournament.clone(),
+                    |mut tournament| tournament.pair_remaining(&pairer).expect("round pairs"),
+                    BatchSize::LargeInput,
+                )
+            });
+            group.bench_with_input(BenchmarkId::new(format!("{}_preview", name), rounds + 1), &tournament, |b, tournament| {
+                b.iter(|| tournament.preview_remaining(&pairer).expect("round pairs"))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pair_round, pair_remaining);
+criterion_main!(benches);
@@ -1,85 +1,177 @@
 use crate::pairing::{Pairing, PairingStrategy, TournamentPlayer};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::HashSet;
 
-pub struct ArenaPairingStrategy;
+/// How long a player may wait in fair mode before being paired regardless of rating
+pub const DEFAULT_MAX_WAIT_SECS: u32 = 60;
+
+/// What an arena favours when it matches the players waiting for a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArenaPairingMode {
+    /// Pair players in the order they joined the queue
+    Fast,
+    /// Pair players with the closest-rated opponent available
+    #[default]
+    Fair,
+}
+
+/// Pairing settings of one arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArenaConfig {
+    #[serde(default)]
+    pub pairing: ArenaPairingMode,
+    /// In fair mode, players who have waited this long are paired first with
+    /// the closest-rated opponent left, so nobody sits out round after round
+    #[serde(default = "default_max_wait_secs")]
+    pub max_wait_secs: u32,
+}
+
+fn default_max_wait_secs() -> u32 {
+    DEFAULT_MAX_WAIT_SECS
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            pairing: ArenaPairingMode::default(),
+            max_wait_secs: DEFAULT_MAX_WAIT_SECS,
+        }
+    }
+}
+
+pub struct ArenaPairingStrategy {
+    config: ArenaConfig,
+}
 
 impl ArenaPairingStrategy {
     pub fn new() -> Self {
-        Self
+        Self::with_config(ArenaConfig::default())
     }
-}
 
-impl PairingStrategy for ArenaPairingStrategy {
-    fn pair(&self, mut players: Vec<TournamentPlayer>) -> (Vec<Pairing>, Vec<TournamentPlayer>) {
+    pub fn with_config(config: ArenaConfig) -> Self {
+        Self { config }
+    }
+
+    /// Pair the waiting `players` as of `now`, which decides who waited too long.
+    pub fn pair_at(
+        &self,
+        mut players: Vec<TournamentPlayer>,
+        now: DateTime<Utc>,
+    ) -> (Vec<Pairing>, Vec<TournamentPlayer>) {
         if players.is_empty() {
             return (vec![], vec![]);
         }
 
-        // 1. Sort by ELO (descending or ascending doesn't matter for closeness, let's use descending)
-        players.sort_by(|a, b| b.elo.cmp(&a.elo));
-
         let mut pairings = Vec::new();
         let mut paired_indices = HashSet::new();
-        let mut remaining_players = Vec::new();
+
+        match self.config.pairing {
+            ArenaPairingMode::Fast => players.sort_by_key(|p| p.joined_at),
+            ArenaPairingMode::Fair => {
+                // Sort by ELO so the players after each one are the closest-rated
+                players.sort_by_key(|p| Reverse(p.elo));
+
+                // Players who waited too long choose first, longest wait first
+                let max_wait = Duration::seconds(i64::from(self.config.max_wait_secs));
+                let mut overdue: Vec<usize> = (0..players.len())
+                    .filter(|&i| now - players[i].joined_at >= max_wait)
+                    .collect();
+                overdue.sort_by_key(|&i| players[i].joined_at);
+
+                for i in overdue {
+                    if paired_indices.contains(&i) {
+                        continue;
+                    }
+                    let mut candidates: Vec<usize> = (0..players.len()).filter(|&j| j != i).collect();
+                    candidates.sort_by_key(|&j| players[i].elo.abs_diff(players[j].elo));
+                    if let Some(j) = find_opponent(&players, &paired_indices, i, candidates) {
+                        pair(&players, &mut paired_indices, &mut pairings, i, j);
+                    }
+                }
+            }
+        }
 
         for i in 0..players.len() {
             if paired_indices.contains(&i) {
                 continue;
             }
+            // No opponent found (e.g., last player), will be added to remaining
+            if let Some(j) = find_opponent(&players, &paired_indices, i, (i + 1)..players.len()) {
+                pair(&players, &mut paired_indices, &mut pairings, i, j);
+            }
+        }
 
-            let player_a = &players[i];
-            let mut best_match_idx = None;
-            let mut fallback_match_idx = None; // Closest player even if repeated
+        // Collect remaining players
+        let remaining_players = players
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !paired_indices.contains(i))
+            .map(|(_, player)| player)
+            .collect();
 
-            // Search for a suitable opponent
-            for j in (i + 1)..players.len() {
-                if paired_indices.contains(&j) {
-                    continue;
-                }
+        (pairings, remaining_players)
+    }
+}
 
-                let player_b = &players[j];
-                
-                // Track the closest available player as fallback (soft constraint)
-                if fallback_match_idx.is_none() {
-                    fallback_match_idx = Some(j);
-                }
+impl Default for ArenaPairingStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-                // Check soft constraint: avoid pairing if played recently
-                // Assuming recent_opponents contains IDs of players played against.
-                // We check if the LAST opponent is player_b.
-                let played_recently = player_a.recent_opponents.last().map_or(false, |id| *id == player_b.id)
-                    || player_b.recent_opponents.last().map_or(false, |id| *id == player_a.id);
+impl PairingStrategy for ArenaPairingStrategy {
+    fn pair(&self, players: Vec<TournamentPlayer>) -> (Vec<Pairing>, Vec<TournamentPlayer>) {
+        self.pair_at(players, Utc::now())
+    }
+}
 
-                if !played_recently {
-                    best_match_idx = Some(j);
-                    break; // Found the best (closest ELO) non-repeat match
-                }
-            }
+/// The first unpaired player among `candidates` that `players[i]` did not
+/// just play, or failing that the first unpaired one (soft constraint).
+fn find_opponent(
+    players: &[TournamentPlayer],
+    paired_indices: &HashSet<usize>,
+    i: usize,
+    candidates: impl IntoIterator<Item = usize>,
+) -> Option<usize> {
+    let player_a = &players[i];
+    let mut fallback_match_idx = None; // Closest player even if repeated
 
-            // Use best match if found, otherwise fallback
-            let target_idx = best_match_idx.or(fallback_match_idx);
-
-            if let Some(j) = target_idx {
-                paired_indices.insert(i);
-                paired_indices.insert(j);
-                pairings.push(Pairing {
-                    player1: players[i].clone(),
-                    player2: players[j].clone(),
-                });
-            } else {
-                // No opponent found (e.g., last player), will be added to remaining
-            }
+    for j in candidates {
+        if paired_indices.contains(&j) {
+            continue;
         }
+        let player_b = &players[j];
 
-        // Collect remaining players
-        for (i, player) in players.into_iter().enumerate() {
-            if !paired_indices.contains(&i) {
-                remaining_players.push(player);
-            }
+        if fallback_match_idx.is_none() {
+            fallback_match_idx = Some(j);
         }
 
-        (pairings, remaining_players)
+        // Avoid pairing if the LAST opponent of either player is the other
+        let played_recently = player_a.recent_opponents.last() == Some(&player_b.id)
+            || player_b.recent_opponents.last() == Some(&player_a.id);
+        if !played_recently {
+            return Some(j);
+        }
     }
+    fallback_match_idx
+}
+
+fn pair(
+    players: &[TournamentPlayer],
+    paired_indices: &mut HashSet<usize>,
+    pairings: &mut Vec<Pairing>,
+    i: usize,
+    j: usize,
+) {
+    paired_indices.insert(i);
+    paired_indices.insert(j);
+    pairings.push(Pairing {
+        player1: players[i].clone(),
+        player2: players[j].clone(),
+    });
 }
 
 #[cfg(test)]
@@ -160,6 +252,54 @@ mod tests {
         assert_eq!(pairs.len(), 1);
     }
 
+    #[test]
+    fn test_fast_pairing_follows_the_queue() {
+        let mut first = create_player(2400, vec![]);
+        first.joined_at = Utc::now() - chrono::Duration::seconds(20);
+        let mut second = create_player(1000, vec![]);
+        second.joined_at = Utc::now() - chrono::Duration::seconds(10);
+        let third = create_player(2390, vec![]);
+
+        let strat = ArenaPairingStrategy::with_config(ArenaConfig {
+            pairing: ArenaPairingMode::Fast,
+            ..ArenaConfig::default()
+        });
+        let (pairs, left) = strat.pair(vec![third.clone(), second.clone(), first.clone()]);
+
+        assert_eq!((pairs[0].player1.id, pairs[0].player2.id), (first.id, second.id));
+        assert_eq!(left[0].id, third.id);
+    }
+
+    #[test]
+    fn test_fair_pairing_serves_players_who_waited_too_long() {
+        let now = Utc::now();
+        let p1 = create_player(2000, vec![]);
+        let p2 = create_player(1990, vec![]);
+        let mut waiting = create_player(1500, vec![]);
+        waiting.joined_at = now - chrono::Duration::seconds(90);
+
+        let strat = ArenaPairingStrategy::with_config(ArenaConfig {
+            pairing: ArenaPairingMode::Fair,
+            max_wait_secs: 60,
+        });
+
+        // Before the bound the lowest-rated player is the one left over
+        let (_, left) = strat.pair_at(vec![p1.clone(), p2.clone(), waiting.clone()], now - chrono::Duration::seconds(40));
+        assert_eq!(left[0].id, waiting.id);
+
+        // After it they are paired with the closest-rated opponent
+        let (pairs, left) = strat.pair_at(vec![p1.clone(), p2.clone(), waiting.clone()], now);
+        assert_eq!((pairs[0].player1.id, pairs[0].player2.id), (waiting.id, p2.id));
+        assert_eq!(left[0].id, p1.id);
+    }
+
+    #[test]
+    fn test_arena_config_defaults_to_fair_pairing() {
+        let config: ArenaConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, ArenaConfig::default());
+        assert_eq!(config.pairing, ArenaPairingMode::Fair);
+    }
+
     #[test]
     #[ignore]
     fn test_pair_performance_1000_players() {
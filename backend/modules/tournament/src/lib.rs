@@ -1,8 +1,19 @@
 pub mod swiss;
 pub mod pairing;
 pub mod arena;
+pub mod prizes;
+pub mod playoff;
+pub mod trf;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
 
 pub use swiss::{
     Player, Color, Pairing, TournamentState, PairingResult, SwissConfig, GameResult,
-    SwissPairer, PairingError
+    SwissPairer, PairedRound, PairingError, RoundAudit, PairingDecision, FloatDirection,
+    ArbiterError, Forfeit, ByeRequest, EntryChange, EntryChangeKind, ReentryPolicy, SeedingOrder, InitialColor, ByeRecord, ByeKind, BakuAcceleration, Standing, Tiebreaks,
+    ColorHistory, GameRecord, RoundRecord, RoundOutcome
 };
+pub use arena::{ArenaConfig, ArenaPairingMode};
+pub use prizes::{Award, Prize, PrizeError, PrizeKind, PrizeStructure};
+pub use playoff::{ArmageddonClocks, GameMode, PlayedGame, Playoff, PlayoffError, PlayoffGame};
+pub use trf::{FideDetails, TrfError, TrfHeader, TrfIssue, TrfReport};
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::swiss::GameResult;
+
+/// How the result on the board is scored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Standard,
+    /// Black has less time on the clock but a draw counts as a Black win
+    Armageddon,
+}
+
+impl GameMode {
+    /// White's result once the mode's rules are applied to White's result on
+    /// the board.
+    pub fn white_result(self, board: GameResult) -> GameResult {
+        match (self, board) {
+            (GameMode::Armageddon, GameResult::Draw) => GameResult::Loss,
+            (_, result) => result,
+        }
+    }
+}
+
+/// Clocks of an armageddon game. The defaults are FIDE's five minutes
+/// against four.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArmageddonClocks {
+    pub white_ms: u64,
+    pub black_ms: u64,
+    #[serde(default)]
+    pub increment_ms: u64,
+}
+
+impl Default for ArmageddonClocks {
+    fn default() -> Self {
+        Self { white_ms: 300_000, black_ms: 240_000, increment_ms: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayoffError {
+    /// The match needs at least one game before any tiebreak
+    NoGames,
+    /// Black must start with less time than White
+    InvalidClocks,
+    SamePlayer,
+    /// The match has a winner, or ended level without a tiebreak game
+    MatchOver,
+}
+
+impl std::fmt::Display for PlayoffError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayoffError::NoGames => write!(f, "A playoff needs at least one game"),
+            PlayoffError::InvalidClocks => write!(f, "Armageddon gives Black less time than White"),
+            PlayoffError::SamePlayer => write!(f, "A player cannot play a playoff against themselves"),
+            PlayoffError::MatchOver => write!(f, "The playoff is already over"),
+        }
+    }
+}
+
+impl std::error::Error for PlayoffError {}
+
+/// The next game of a playoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayoffGame {
+    pub white: Uuid,
+    pub black: Uuid,
+    pub mode: GameMode,
+    /// Set for the armageddon game
+    pub clocks: Option<ArmageddonClocks>,
+}
+
+/// A game of a playoff with its scored result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayedGame {
+    pub white: Uuid,
+    pub black: Uuid,
+    pub mode: GameMode,
+    /// White's result after the mode's rules, so never a draw in armageddon
+    pub white_result: GameResult,
+}
+
+/// A match between two players that settles a knockout tie or a shared
+/// place: `games` games with alternating colors, the first player taking
+/// White in the first, then, if still level and `final_tiebreak` is set, one
+/// armageddon game. Colors keep alternating into the armageddon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Playoff {
+    pub players: [Uuid; 2],
+    pub games: u32,
+    pub final_tiebreak: Option<ArmageddonClocks>,
+    #[serde(default)]
+    pub played: Vec<PlayedGame>,
+}
+
+impl Playoff {
+    pub fn new(
+        first: Uuid,
+        second: Uuid,
+        games: u32,
+        final_tiebreak: Option<ArmageddonClocks>,
+    ) -> Result<Self, PlayoffError> {
+        if games == 0 {
+            return Err(PlayoffError::NoGames);
+        }
+        if first == second {
+            return Err(PlayoffError::SamePlayer);
+        }
+        if final_tiebreak.is_some_and(|c| c.black_ms >= c.white_ms) {
+            return Err(PlayoffError::InvalidClocks);
+        }
+        Ok(Self { players: [first, second], games, final_tiebreak, played: Vec::new() })
+    }
+
+    /// Points of a player across the games played so far.
+    pub fn score(&self, player: Uuid) -> f32 {
+        self.played
+            .iter()
+            .map(|game| {
+                let white_points = match game.white_result {
+                    GameResult::Win => 1.0,
+                    GameResult::Draw => 0.5,
+                    GameResult::Loss => 0.0,
+                };
+                if game.white == player {
+                    white_points
+                } else if game.black == player {
+                    1.0 - white_points
+                } else {
+                    0.0
+                }
+            })
+            .sum()
+    }
+
+    /// The player who has won the match. A lead the other player can no
+    /// longer make up in the remaining regular games is enough.
+    pub fn winner(&self) -> Option<Uuid> {
+        let [first, second] = self.players;
+        let (a, b) = (self.score(first), self.score(second));
+        let remaining = self.games.saturating_sub(self.played.len() as u32) as f32;
+        if (a - b).abs() > remaining {
+            Some(if a > b { first } else { second })
+        } else {
+            None
+        }
+    }
+
+    /// The game to play next, or `None` once the match is over.
+    pub fn next_game(&self) -> Option<PlayoffGame> {
+        if self.winner().is_some() {
+            return None;
+        }
+        let index = self.played.len() as u32;
+        let (mode, clocks) = if index < self.games {
+            (GameMode::Standard, None)
+        } else if index == self.games && self.final_tiebreak.is_some() {
+            (GameMode::Armageddon, self.final_tiebreak)
+        } else {
+            return None;
+        };
+        let white = self.players[index as usize % 2];
+        let black = self.players[(index as usize + 1) % 2];
+        Some(PlayoffGame { white, black, mode, clocks })
+    }
+
+    /// Score the next game from White's result on the board.
+    pub fn record(&mut self, board: GameResult) -> Result<PlayedGame, PlayoffError> {
+        let game = self.next_game().ok_or(PlayoffError::MatchOver)?;
+        let played = PlayedGame {
+            white: game.white,
+            black: game.black,
+            mode: game.mode,
+            white_result: game.mode.white_result(board),
+        };
+        self.played.push(played);
+        Ok(played)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armageddon_decides_a_level_match() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut playoff = Playoff::new(a, b, 2, Some(ArmageddonClocks::default())).unwrap();
+
+        assert_eq!(playoff.record(GameResult::Win).unwrap().white, a);
+        assert_eq!(playoff.record(GameResult::Win).unwrap().white, b);
+        assert_eq!(playoff.winner(), None);
+
+        let armageddon = playoff.next_game().unwrap();
+        assert_eq!((armageddon.white, armageddon.mode), (a, GameMode::Armageddon));
+        assert_eq!(armageddon.clocks, Some(ArmageddonClocks::default()));
+
+        // A draw on the board goes to Black
+        let played = playoff.record(GameResult::Draw).unwrap();
+        assert_eq!(played.white_result, GameResult::Loss);
+        assert_eq!(playoff.winner(), Some(b));
+        assert_eq!(playoff.record(GameResult::Win), Err(PlayoffError::MatchOver));
+    }
+
+    #[test]
+    fn match_ends_once_the_lead_is_safe() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut playoff = Playoff::new(a, b, 4, Some(ArmageddonClocks::default())).unwrap();
+
+        playoff.record(GameResult::Win).unwrap();
+        playoff.record(GameResult::Loss).unwrap();
+        assert_eq!(playoff.winner(), None);
+        playoff.record(GameResult::Win).unwrap();
+        assert_eq!((playoff.score(a), playoff.winner()), (3.0, Some(a)));
+        assert_eq!(playoff.next_game(), None);
+    }
+
+    #[test]
+    fn level_match_without_tiebreak_stays_level() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut playoff = Playoff::new(a, b, 2, None).unwrap();
+
+        playoff.record(GameResult::Draw).unwrap();
+        playoff.record(GameResult::Draw).unwrap();
+        assert_eq!((playoff.winner(), playoff.next_game()), (None, None));
+        assert_eq!(
+            Playoff::new(a, b, 2, Some(ArmageddonClocks { white_ms: 240_000, black_ms: 240_000, increment_ms: 0 })),
+            Err(PlayoffError::InvalidClocks)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::swiss::Standing;
+
+/// Who a prize goes to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrizeKind {
+    /// Every player finishing between `from` and `to` (1-based, inclusive)
+    Place { from: u32, to: u32 },
+    /// The best `places` finishers rated within the bounds, e.g. best U1600
+    RatingCategory {
+        min_rating: Option<i32>,
+        max_rating: Option<i32>,
+        places: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prize {
+    pub name: String,
+    pub kind: PrizeKind,
+}
+
+/// Prizes of a tournament. Place prizes are awarded first; rating category
+/// prizes then go, in listed order, to players who have not won a prize yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrizeStructure {
+    pub prizes: Vec<Prize>,
+}
+
+/// A prize won by a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Award {
+    pub prize: String,
+    pub player: Uuid,
+    /// Overall finishing place
+    pub rank: u32,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrizeError {
+    EmptyName,
+    DuplicateName(String),
+    InvalidPlaces(String),
+    InvalidRatingRange(String),
+}
+
+impl std::fmt::Display for PrizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrizeError::EmptyName => write!(f, "Prizes need a name"),
+            PrizeError::DuplicateName(name) => write!(f, "Prize '{}' is listed twice", name),
+            PrizeError::InvalidPlaces(name) => write!(f, "Prize '{}' covers no places", name),
+            PrizeError::InvalidRatingRange(name) => {
+                write!(f, "Prize '{}' has a minimum rating above its maximum", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrizeError {}
+
+impl PrizeKind {
+    fn accepts_rating(&self, rating: i32) -> bool {
+        match self {
+            PrizeKind::Place { .. } => true,
+            PrizeKind::RatingCategory { min_rating, max_rating, .. } => {
+                min_rating.is_none_or(|min| rating >= min) && max_rating.is_none_or(|max| rating < max)
+            }
+        }
+    }
+}
+
+impl PrizeStructure {
+    pub fn validate(&self) -> Result<(), PrizeError> {
+        let mut names = HashSet::new();
+
+        for prize in &self.prizes {
+            if prize.name.trim().is_empty() {
+                return Err(PrizeError::EmptyName);
+            }
+            if !names.insert(prize.name.as_str()) {
+                return Err(PrizeError::DuplicateName(prize.name.clone()));
+            }
+            match prize.kind {
+                PrizeKind::Place { from, to } if from == 0 || to < from => {
+                    return Err(PrizeError::InvalidPlaces(prize.name.clone()));
+                }
+                PrizeKind::RatingCategory { places: 0, .. } => {
+                    return Err(PrizeError::InvalidPlaces(prize.name.clone()));
+                }
+                PrizeKind::RatingCategory {
+                    min_rating: Some(min),
+                    max_rating: Some(max),
+                    ..
+                } if min >= max => {
+                    return Err(PrizeError::InvalidRatingRange(prize.name.clone()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Winners of every prize given final `standings` in rank order. A player
+    /// wins at most one prize.
+    pub fn award(&self, standings: &[Standing]) -> Vec<Award> {
+        let mut awards = Vec::new();
+        let mut winners = HashSet::new();
+
+        let place_prizes = self.prizes.iter().filter(|p| matches!(p.kind, PrizeKind::Place { .. }));
+        let category_prizes = self
+            .prizes
+            .iter()
+            .filter(|p| matches!(p.kind, PrizeKind::RatingCategory { .. }));
+
+        for prize in place_prizes.chain(category_prizes) {
+            let eligible = standings
+                .iter()
+                .filter(|s| !winners.contains(&s.player) && prize.kind.accepts_rating(s.rating));
+
+            let won: Vec<&Standing> = match prize.kind {
+                PrizeKind::Place { from, to } => eligible.filter(|s| s.rank >= from && s.rank <= to).collect(),
+                PrizeKind::RatingCategory { places, .. } => eligible.take(places as usize).collect(),
+            };
+
+            for standing in won {
+                winners.insert(standing.player);
+                awards.push(Award {
+                    prize: prize.name.clone(),
+                    player: standing.player,
+                    rank: standing.rank,
+                    score: standing.score,
+                });
+            }
+        }
+        awards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiss::Tiebreaks;
+
+    fn standings(ratings: &[i32]) -> Vec<Standing> {
+        ratings
+            .iter()
+            .enumerate()
+            .map(|(i, rating)| Standing {
+                rank: i as u32 + 1,
+                player: Uuid::new_v4(),
+                name: format!("p{}", i + 1),
+                rating: *rating,
+                score: (ratings.len() - i) as f32,
+                tiebreaks: Tiebreaks {
+                    buchholz: 0.0,
+                    sonneborn_berger: 0.0,
+                    wins: 0,
+                },
+            })
+            .collect()
+    }
+
+    fn prize(name: &str, kind: PrizeKind) -> Prize {
+        Prize {
+            name: name.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn place_prizes_cover_their_range() {
+        let field = standings(&[2000, 1900, 1800, 1700]);
+        let structure = PrizeStructure {
+            prizes: vec![
+                prize("Champion", PrizeKind::Place { from: 1, to: 1 }),
+                prize("Podium", PrizeKind::Place { from: 2, to: 3 }),
+            ],
+        };
+
+        let awards = structure.award(&field);
+        let won: Vec<(&str, u32)> = awards.iter().map(|a| (a.prize.as_str(), a.rank)).collect();
+        assert_eq!(won, vec![("Champion", 1), ("Podium", 2), ("Podium", 3)]);
+    }
+
+    #[test]
+    fn category_prizes_skip_players_who_already_won() {
+        // The U1800 player finishing 2nd takes the place prize, so the category goes to 4th
+        let field = standings(&[2000, 1500, 1900, 1600]);
+        let structure = PrizeStructure {
+            prizes: vec![
+                prize(
+                    "Best U1800",
+                    PrizeKind::RatingCategory {
+                        min_rating: None,
+                        max_rating: Some(1800),
+                        places: 1,
+                    },
+                ),
+                prize("Top two", PrizeKind::Place { from: 1, to: 2 }),
+            ],
+        };
+
+        let awards = structure.award(&field);
+        assert_eq!(awards.len(), 3);
+        assert_eq!(awards[2].prize, "Best U1800");
+        assert_eq!(awards[2].player, field[3].player);
+    }
+
+    #[test]
+    fn prizes_beyond_the_field_are_not_awarded() {
+        let field = standings(&[2000]);
+        let structure = PrizeStructure {
+            prizes: vec![prize("Podium", PrizeKind::Place { from: 1, to: 3 })],
+        };
+
+        assert_eq!(structure.award(&field).len(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_structures() {
+        let invalid = [
+            prize("", PrizeKind::Place { from: 1, to: 1 }),
+            prize("First", PrizeKind::Place { from: 0, to: 1 }),
+            prize("First", PrizeKind::Place { from: 3, to: 2 }),
+            prize(
+                "Band",
+                PrizeKind::RatingCategory {
+                    min_rating: Some(1800),
+                    max_rating: Some(1600),
+                    places: 1,
+                },
+            ),
+        ];
+        for prize in invalid {
+            let structure = PrizeStructure { prizes: vec![prize] };
+            assert!(structure.validate().is_err());
+        }
+
+        let duplicated = PrizeStructure {
+            prizes: vec![
+                prize("First", PrizeKind::Place { from: 1, to: 1 }),
+                prize("First", PrizeKind::Place { from: 2, to: 2 }),
+            ],
+        };
+        assert_eq!(duplicated.validate(), Err(PrizeError::DuplicateName("First".to_string())));
+    }
+}
@@ -0,0 +1,461 @@
+use super::*;
+use std::collections::HashSet;
+
+/// A bye a player asked for ahead of a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByeRequest {
+    pub round: u32,
+    pub player: Uuid,
+}
+
+/// A paired game decided by the arbiter without being played.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Forfeit {
+    pub round: u32,
+    pub winner: Uuid,
+    pub loser: Uuid,
+}
+
+/// A player leaving the tournament or coming back, from `round` on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryChange {
+    pub round: u32,
+    pub player: Uuid,
+    pub kind: EntryChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryChangeKind {
+    Withdrawn,
+    Reentered,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArbiterError {
+    UnknownPlayer(Uuid),
+    InactivePlayer(Uuid),
+    ActivePlayer(Uuid),
+    ReentryForbidden,
+    SamePlayer,
+    AlreadyScheduled(Uuid),
+    AlreadyPlayed(Uuid, Uuid),
+    NotPaired(Uuid),
+    AlreadyForfeited(Uuid),
+    NoResult(Uuid, u32),
+    InvalidRound(u32),
+    TournamentComplete,
+    Pairing(PairingError),
+}
+
+impl std::fmt::Display for ArbiterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArbiterError::UnknownPlayer(id) => write!(f, "Player {} is not in this tournament", id),
+            ArbiterError::InactivePlayer(id) => write!(f, "Player {} has withdrawn", id),
+            ArbiterError::ActivePlayer(id) => write!(f, "Player {} has not withdrawn", id),
+            ArbiterError::ReentryForbidden => write!(f, "Withdrawn players cannot re-enter this tournament"),
+            ArbiterError::SamePlayer => write!(f, "A player cannot be paired with themselves"),
+            ArbiterError::AlreadyScheduled(id) => write!(f, "Player {} is already paired or has a bye this round", id),
+            ArbiterError::AlreadyPlayed(a, b) => write!(f, "Players {} and {} have already played each other", a, b),
+            ArbiterError::NotPaired(id) => write!(f, "Player {} has no pairing this round", id),
+            ArbiterError::AlreadyForfeited(id) => write!(f, "The game of player {} has already been forfeited", id),
+            ArbiterError::NoResult(id, round) => write!(f, "Player {} has no game result in round {}", id, round),
+            ArbiterError::InvalidRound(round) => write!(f, "Round {} has already been paired or does not exist", round),
+            ArbiterError::TournamentComplete => write!(f, "All rounds have been played"),
+            ArbiterError::Pairing(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ArbiterError {}
+
+impl From<PairingError> for ArbiterError {
+    fn from(err: PairingError) -> Self {
+        ArbiterError::Pairing(err)
+    }
+}
+
+impl TournamentState {
+    pub fn round_pairings(&self, round: u32) -> Vec<&Pairing> {
+        self.pairings.iter().filter(|p| p.round == round).collect()
+    }
+
+    pub fn pairing_of(&self, round: u32, player: Uuid) -> Option<&Pairing> {
+        self.pairings
+            .iter()
+            .find(|p| p.round == round && (p.white_player == player || p.black_player == player))
+    }
+
+    /// True when the player already has a pairing, a bye or a pending bye request in the round.
+    pub fn is_scheduled(&self, round: u32, player: Uuid) -> bool {
+        self.pairing_of(round, player).is_some()
+            || self.players.get(&player).is_some_and(|p| p.bye_in_round(round).is_some())
+            || self.has_requested_bye(round, player)
+    }
+
+    pub fn has_requested_bye(&self, round: u32, player: Uuid) -> bool {
+        self.requested_byes.iter().any(|r| r.round == round && r.player == player)
+    }
+
+    /// Players who had a bye in `round`, whether allocated or requested.
+    pub fn round_byes(&self, round: u32) -> Vec<&Player> {
+        let mut players: Vec<&Player> = self
+            .players
+            .values()
+            .filter(|p| p.bye_in_round(round).is_some())
+            .collect();
+        players.sort_by_key(|p| p.id);
+        players
+    }
+
+    /// Ask for a bye in the current or a later round. The bye is credited
+    /// with `SwissConfig::requested_bye_points` when that round is paired.
+    pub fn request_bye(&mut self, player: Uuid, round: u32) -> Result<ByeRequest, ArbiterError> {
+        self.ensure_in_progress()?;
+
+        let entrant = self.players.get(&player).ok_or(ArbiterError::UnknownPlayer(player))?;
+        if !entrant.is_active {
+            return Err(ArbiterError::InactivePlayer(player));
+        }
+        if round < self.current_round || round > self.total_rounds {
+            return Err(ArbiterError::InvalidRound(round));
+        }
+        if self.is_scheduled(round, player) {
+            return Err(ArbiterError::AlreadyScheduled(player));
+        }
+
+        let request = ByeRequest { round, player };
+        self.requested_byes.push(request.clone());
+        Ok(request)
+    }
+
+    /// Withdraw a player from the rounds not yet paired for them. A game or
+    /// bye they already have this round stands; byes they asked for later
+    /// are dropped.
+    pub fn withdraw(&mut self, player: Uuid) -> Result<EntryChange, ArbiterError> {
+        self.ensure_in_progress()?;
+
+        let entrant = self.players.get(&player).ok_or(ArbiterError::UnknownPlayer(player))?;
+        if !entrant.is_active {
+            return Err(ArbiterError::InactivePlayer(player));
+        }
+        let round = self.current_round;
+        let from = if self.pairing_of(round, player).is_some() || entrant.bye_in_round(round).is_some() {
+            round + 1
+        } else {
+            round
+        };
+
+        if let Some(entrant) = self.players.get_mut(&player) {
+            entrant.is_active = false;
+        }
+        self.requested_byes.retain(|r| r.player != player || r.round < from);
+        let change = EntryChange { round: from, player, kind: EntryChangeKind::Withdrawn };
+        self.entry_changes.push(change.clone());
+        Ok(change)
+    }
+
+    /// Bring a withdrawn player back for the next pairing. The rounds they
+    /// missed go into their history, scored as `config.reentry` says.
+    pub fn reenter(&mut self, player: Uuid, config: &SwissConfig) -> Result<EntryChange, ArbiterError> {
+        self.ensure_in_progress()?;
+
+        let entrant = self.players.get(&player).ok_or(ArbiterError::UnknownPlayer(player))?;
+        if entrant.is_active {
+            return Err(ArbiterError::ActivePlayer(player));
+        }
+        let points = match config.reentry {
+            ReentryPolicy::Forbidden => return Err(ArbiterError::ReentryForbidden),
+            ReentryPolicy::ZeroForMissed => 0.0,
+            ReentryPolicy::ByeForMissed => config.requested_bye_points,
+        };
+        let withdrawn_from = self
+            .entry_changes
+            .iter()
+            .rev()
+            .find(|c| c.player == player && c.kind == EntryChangeKind::Withdrawn)
+            .map_or(1, |c| c.round);
+        let round = self.current_round;
+
+        if let Some(entrant) = self.players.get_mut(&player) {
+            for missed in withdrawn_from..round {
+                if entrant.round_record(missed).is_none() {
+                    entrant.add_absence(missed, points);
+                }
+            }
+            entrant.is_active = true;
+        }
+        let change = EntryChange { round, player, kind: EntryChangeKind::Reentered };
+        self.entry_changes.push(change.clone());
+        Ok(change)
+    }
+
+    /// Players withdrawn and not back, in id order.
+    pub fn withdrawn_players(&self) -> Vec<&Player> {
+        let mut players: Vec<&Player> = self.players.values().filter(|p| !p.is_active).collect();
+        players.sort_by_key(|p| p.id);
+        players
+    }
+
+    pub fn forfeit_of(&self, round: u32, player: Uuid) -> Option<&Forfeit> {
+        self.forfeits
+            .iter()
+            .find(|f| f.round == round && (f.winner == player || f.loser == player))
+    }
+
+    /// Active players with neither a pairing nor a bye in the current round.
+    pub fn unpaired_players(&self) -> Vec<&Player> {
+        let mut players: Vec<&Player> = self
+            .get_active_players()
+            .into_iter()
+            .filter(|p| !self.is_scheduled(self.current_round, p.id))
+            .collect();
+        players.sort_by_key(|p| p.id);
+        players
+    }
+
+    /// Swap white and black in the current-round pairing of `player`.
+    pub fn swap_colors(&mut self, player: Uuid) -> Result<Pairing, ArbiterError> {
+        self.ensure_in_progress()?;
+        let round = self.current_round;
+
+        if self.forfeit_of(round, player).is_some() {
+            return Err(ArbiterError::AlreadyForfeited(player));
+        }
+
+        let pairing = self
+            .pairings
+            .iter_mut()
+            .find(|p| p.round == round && (p.white_player == player || p.black_player == player))
+            .ok_or(ArbiterError::NotPaired(player))?;
+        std::mem::swap(&mut pairing.white_player, &mut pairing.black_player);
+        let pairing = pairing.clone();
+        self.note_pairing(&pairing);
+
+        self.record_override(
+            vec![pairing.white_player, pairing.black_player],
+            format!("swapped colors, {} now has white", pairing.white_player),
+        );
+        Ok(pairing)
+    }
+
+    /// Pair two unscheduled players in the current round, bypassing the pairer.
+    pub fn force_pairing(&mut self, white: Uuid, black: Uuid) -> Result<Pairing, ArbiterError> {
+        self.ensure_in_progress()?;
+        let round = self.current_round;
+
+        if white == black {
+            return Err(ArbiterError::SamePlayer);
+        }
+        for id in [white, black] {
+            let player = self.players.get(&id).ok_or(ArbiterError::UnknownPlayer(id))?;
+            if !player.is_active {
+                return Err(ArbiterError::InactivePlayer(id));
+            }
+            if self.is_scheduled(round, id) {
+                return Err(ArbiterError::AlreadyScheduled(id));
+            }
+        }
+        if self.players[&white].has_played_against(&black) {
+            return Err(ArbiterError::AlreadyPlayed(white, black));
+        }
+
+        let pairing = Pairing {
+            white_player: white,
+            black_player: black,
+            round,
+        };
+        self.pairings.push(pairing.clone());
+        self.note_pairing(&pairing);
+
+        self.record_override(vec![white, black], format!("forced {} (white) vs {} (black)", white, black));
+        Ok(pairing)
+    }
+
+    /// Award `winner` the current-round game by forfeit. The game does not
+    /// count as played, so the two players may still meet later.
+    pub fn record_forfeit(&mut self, winner: Uuid) -> Result<Forfeit, ArbiterError> {
+        self.ensure_in_progress()?;
+        let round = self.current_round;
+
+        if !self.players.contains_key(&winner) {
+            return Err(ArbiterError::UnknownPlayer(winner));
+        }
+        let pairing = self.pairing_of(round, winner).ok_or(ArbiterError::NotPaired(winner))?;
+        if self.forfeit_of(round, winner).is_some() {
+            return Err(ArbiterError::AlreadyForfeited(winner));
+        }
+
+        let loser = if pairing.white_player == winner {
+            pairing.black_player
+        } else {
+            pairing.white_player
+        };
+        let forfeit = Forfeit { round, winner, loser };
+        self.forfeits.push(forfeit.clone());
+
+        self.record_override(vec![winner, loser], format!("{} wins by forfeit against {}", winner, loser));
+        Ok(forfeit)
+    }
+
+    /// Change the result of a game already scored in `round`, as when a
+    /// disputed result is overturned. `result` is from `player`'s side; the
+    /// opponent gets the reverse. Returns the result it replaced.
+    pub fn correct_result(&mut self, round: u32, player: Uuid, result: GameResult) -> Result<GameResult, ArbiterError> {
+        let entrant = self.players.get(&player).ok_or(ArbiterError::UnknownPlayer(player))?;
+        let opponent = match entrant.round_record(round).map(|r| &r.outcome) {
+            Some(RoundOutcome::Game { opponent, result: Some(_), .. }) => *opponent,
+            _ => return Err(ArbiterError::NoResult(player, round)),
+        };
+
+        let previous = self
+            .players
+            .get_mut(&player)
+            .and_then(|p| p.correct_result(round, result))
+            .ok_or(ArbiterError::NoResult(player, round))?;
+        if let Some(opponent) = self.players.get_mut(&opponent) {
+            opponent.correct_result(round, result.reversed());
+        }
+
+        self.record_override_in(
+            round,
+            vec![player, opponent],
+            format!("corrected round {} result of {} against {} to {:?}", round, player, opponent, result),
+        );
+        Ok(previous)
+    }
+
+    /// Run the pairer over the players not yet scheduled this round and keep
+    /// the resulting pairings and bye alongside any manual ones.
+    pub fn pair_remaining(&mut self, pairer: &SwissPairer) -> Result<Vec<PairingResult>, ArbiterError> {
+        self.ensure_in_progress()?;
+        let round = self.current_round;
+
+        let paired = {
+            let pool = self.pairing_pool();
+            if !self.players.values().any(&pool) {
+                return Ok(Vec::new());
+            }
+            pairer.pair_among(self, &pool)?
+        };
+
+        // Guard against the pairer handing anyone a second game this round
+        let mut seen = HashSet::new();
+        for id in paired.players() {
+            if !seen.insert(id) || self.pairing_of(round, id).is_some() {
+                return Err(ArbiterError::AlreadyScheduled(id));
+            }
+        }
+
+        // Fixes any acceleration group the round was paired with
+        pairer.commit_round(self, &paired)?;
+        Ok(paired.results())
+    }
+
+    /// The pairings and byes `pair_remaining` would add, without adding them.
+    pub fn preview_remaining(&self, pairer: &SwissPairer) -> Result<Vec<PairingResult>, ArbiterError> {
+        self.ensure_in_progress()?;
+        let pool = self.pairing_pool();
+        if !self.players.values().any(&pool) {
+            return Ok(Vec::new());
+        }
+        Ok(pairer.pair_among(self, &pool)?.results())
+    }
+
+    /// A copy of the tournament with the games of the current round scored
+    /// as `results` says and the next round begun, for previewing its
+    /// pairings. `results` are from the named player's side; unfinished
+    /// games it leaves out are taken as draws. When the current round has
+    /// no pairings yet there is nothing to score and the copy stays on it.
+    pub fn with_projected_results(&self, results: &[(Uuid, GameResult)]) -> Result<TournamentState, ArbiterError> {
+        self.ensure_in_progress()?;
+        let round = self.current_round;
+        let pairings = self.round_pairings(round);
+
+        if let Some(&(player, _)) = results.iter().find(|(id, _)| self.pairing_of(round, *id).is_none()) {
+            return Err(if self.players.contains_key(&player) {
+                ArbiterError::NotPaired(player)
+            } else {
+                ArbiterError::UnknownPlayer(player)
+            });
+        }
+
+        let mut projected = self.clone();
+        if pairings.is_empty() {
+            return Ok(projected);
+        }
+
+        let mut outcomes = Vec::with_capacity(pairings.len() * 2);
+        for pairing in pairings {
+            // Games already scored keep their result
+            let outcome = self
+                .players
+                .get(&pairing.white_player)
+                .and_then(|p| p.round_record(round))
+                .map(|r| &r.outcome);
+            if matches!(outcome, Some(RoundOutcome::Game { result: Some(_), .. } | RoundOutcome::Forfeit { .. })) {
+                continue;
+            }
+            let white_result = results
+                .iter()
+                .find_map(|&(id, result)| {
+                    if id == pairing.white_player {
+                        Some(result)
+                    } else if id == pairing.black_player {
+                        Some(result.reversed())
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(GameResult::Draw);
+            outcomes.push((pairing.white_player, white_result));
+            outcomes.push((pairing.black_player, white_result.reversed()));
+        }
+        projected.apply_round_results(outcomes);
+        projected.ensure_in_progress()?;
+        Ok(projected)
+    }
+
+    /// Which players are still to be paired in the current round. Players
+    /// with a pending bye request count, so the pairer credits their bye.
+    fn pairing_pool(&self) -> impl Fn(&Player) -> bool + '_ {
+        let round = self.current_round;
+        let paired: HashSet<Uuid> = self
+            .round_pairings(round)
+            .into_iter()
+            .flat_map(|p| [p.white_player, p.black_player])
+            .collect();
+        move |player| player.is_active && !paired.contains(&player.id) && player.bye_in_round(round).is_none()
+    }
+
+    fn ensure_in_progress(&self) -> Result<(), ArbiterError> {
+        if self.is_complete() {
+            Err(ArbiterError::TournamentComplete)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Put a current-round pairing in both players' round history.
+    fn note_pairing(&mut self, pairing: &Pairing) {
+        if let Some(white) = self.players.get_mut(&pairing.white_player) {
+            white.add_pairing(pairing.round, pairing.black_player, Color::White);
+        }
+        if let Some(black) = self.players.get_mut(&pairing.black_player) {
+            black.add_pairing(pairing.round, pairing.white_player, Color::Black);
+        }
+    }
+
+    fn record_override(&mut self, players: Vec<Uuid>, action: String) {
+        self.record_override_in(self.current_round, players, action);
+    }
+
+    fn record_override_in(&mut self, round: u32, players: Vec<Uuid>, action: String) {
+        if self.audit_for_round(round).is_none() {
+            self.pairing_audits.push(RoundAudit::new(round, None));
+        }
+        if let Some(audit) = self.pairing_audits.iter_mut().find(|a| a.round == round) {
+            audit.record(PairingDecision::ArbiterOverride { players, action });
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Direction a player moved relative to their own score group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FloatDirection {
+    Up,
+    Down,
+}
+
+/// A single step the pairer took while building a round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PairingDecision {
+    Bye {
+        player: Uuid,
+        score: f32,
+        rating: i32,
+        reason: String,
+    },
+    Paired {
+        white: Uuid,
+        black: Uuid,
+        score_group: f32,
+        color_reason: String,
+    },
+    CandidateRejected {
+        player: Uuid,
+        candidate: Uuid,
+        reason: String,
+    },
+    Floated {
+        player: Uuid,
+        opponent: Uuid,
+        from_score: f32,
+        to_score: f32,
+        direction: FloatDirection,
+    },
+    /// The player of an odd score group picked to float down, by their
+    /// float history
+    FloaterChosen {
+        player: Uuid,
+        score_group: f32,
+        float_score: i32,
+        reason: String,
+    },
+    /// A manual change made by the arbiter after (or instead of) the pairer
+    ArbiterOverride {
+        players: Vec<Uuid>,
+        action: String,
+    },
+}
+
+/// Every decision taken while pairing one round, in the order taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundAudit {
+    pub round: u32,
+    /// Seed used for tie-breaking, when pairing deterministically
+    pub seed: Option<u64>,
+    pub decisions: Vec<PairingDecision>,
+}
+
+impl RoundAudit {
+    pub fn new(round: u32, seed: Option<u64>) -> Self {
+        Self {
+            round,
+            seed,
+            decisions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, decision: PairingDecision) {
+        self.decisions.push(decision);
+    }
+
+    /// Decisions that mention a player, for answering "why was I paired like this?"
+    pub fn decisions_for(&self, player: Uuid) -> Vec<&PairingDecision> {
+        self.decisions
+            .iter()
+            .filter(|d| d.involves(player))
+            .collect()
+    }
+}
+
+impl PairingDecision {
+    pub fn involves(&self, id: Uuid) -> bool {
+        match self {
+            PairingDecision::Bye { player, .. } => *player == id,
+            PairingDecision::Paired { white, black, .. } => *white == id || *black == id,
+            PairingDecision::CandidateRejected { player, candidate, .. } => *player == id || *candidate == id,
+            PairingDecision::Floated { player, opponent, .. } => *player == id || *opponent == id,
+            PairingDecision::FloaterChosen { player, .. } => *player == id,
+            PairingDecision::ArbiterOverride { players, .. } => players.contains(&id),
+        }
+    }
+}
+
+impl fmt::Display for PairingDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairingDecision::Bye { player, score, rating, reason } => {
+                write!(f, "Bye to {} (score {}, rating {}): {}", player, score, rating, reason)
+            }
+            PairingDecision::Paired { white, black, score_group, color_reason } => {
+                write!(f, "{} (white) vs {} (black) in score group {}: {}", white, black, score_group, color_reason)
+            }
+            PairingDecision::CandidateRejected { player, candidate, reason } => {
+                write!(f, "{} not paired with {}: {}", player, candidate, reason)
+            }
+            PairingDecision::Floated { player, opponent, from_score, to_score, direction } => {
+                let direction = match direction {
+                    FloatDirection::Up => "up",
+                    FloatDirection::Down => "down",
+                };
+                write!(
+                    f,
+                    "{} floated {} from score group {} to {} to meet {}",
+                    player, direction, from_score, to_score, opponent
+                )
+            }
+            PairingDecision::FloaterChosen { player, score_group, float_score, reason } => {
+                write!(f, "{} floats down from score group {} (float score {}): {}", player, score_group, float_score, reason)
+            }
+            PairingDecision::ArbiterOverride { action, .. } => write!(f, "Arbiter: {}", action),
+        }
+    }
+}
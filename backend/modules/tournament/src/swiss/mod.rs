@@ -1,23 +1,268 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+pub mod arbiter;
+pub mod audit;
 pub mod pairer;
+pub mod standings;
 #[cfg(test)]
 mod tests;
 
-pub use pairer::{SwissPairer, PairingError};
+pub use arbiter::{ArbiterError, ByeRequest, EntryChange, EntryChangeKind, Forfeit};
+pub use audit::{FloatDirection, PairingDecision, RoundAudit};
+pub use pairer::{PairedRound, SwissPairer, PairingError};
+pub use standings::{Standing, Tiebreaks};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredPlayer")]
 pub struct Player {
     pub id: Uuid,
     pub name: String,
     pub rating: i32,
     pub score: f32,
-    pub color_history: Vec<Color>,
-    pub opponents: Vec<Uuid>,
+    pub color_history: ColorHistory,
+    pub opponents: HashSet<Uuid>,
+    /// Opponent and result of each game, in the order played
+    pub results: Vec<GameRecord>,
     pub is_active: bool,
-    pub float_score: i32, // Tracks up/down floating: positive = up, negative = down
+    pub float_score: i32, // Tracks up/down floating: positive = down, negative = up
+    /// Rounds this player sat out, and what each was worth
+    #[serde(default)]
+    pub byes: Vec<ByeRecord>,
+    /// What happened to this player in each round, in round order
+    pub history: Vec<RoundRecord>,
+}
+
+/// `Player` as stored, also accepting the earlier layout: opponents in the
+/// order played, the full color of every game and a bare result per game.
+#[derive(Deserialize)]
+struct StoredPlayer {
+    id: Uuid,
+    name: String,
+    rating: i32,
+    score: f32,
+    color_history: ColorHistory,
+    opponents: Vec<Uuid>,
+    #[serde(default)]
+    results: StoredResults,
+    is_active: bool,
+    float_score: i32,
+    #[serde(default)]
+    byes: Vec<ByeRecord>,
+    /// Missing from players stored before it was kept
+    history: Option<Vec<RoundRecord>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredResults {
+    Records(Vec<GameRecord>),
+    /// One per entry of `opponents`, in the same order
+    Bare(Vec<GameResult>),
+}
+
+impl Default for StoredResults {
+    fn default() -> Self {
+        StoredResults::Records(Vec::new())
+    }
+}
+
+impl From<StoredPlayer> for Player {
+    fn from(stored: StoredPlayer) -> Self {
+        let results = match stored.results {
+            StoredResults::Records(records) => records,
+            StoredResults::Bare(results) => stored
+                .opponents
+                .iter()
+                .zip(results)
+                .map(|(&opponent, result)| GameRecord { opponent, result })
+                .collect(),
+        };
+        let history = stored
+            .history
+            .unwrap_or_else(|| inferred_history(&results, &stored.byes));
+        Self {
+            id: stored.id,
+            name: stored.name,
+            rating: stored.rating,
+            score: stored.score,
+            color_history: stored.color_history,
+            opponents: stored.opponents.into_iter().collect(),
+            results,
+            is_active: stored.is_active,
+            float_score: stored.float_score,
+            byes: stored.byes,
+            history,
+        }
+    }
+}
+
+/// Round history for a player stored without one. Games are placed in the
+/// rounds without a bye, in order; their colors and any floats are unknown.
+fn inferred_history(results: &[GameRecord], byes: &[ByeRecord]) -> Vec<RoundRecord> {
+    let mut games = results.iter();
+    let mut history = Vec::with_capacity(results.len() + byes.len());
+    let mut round = 1;
+    loop {
+        let outcome = match byes.iter().find(|b| b.round == round) {
+            Some(bye) => RoundOutcome::Bye { kind: bye.kind, points: bye.points },
+            None => match games.next() {
+                Some(game) => RoundOutcome::Game {
+                    opponent: game.opponent,
+                    color: None,
+                    result: Some(game.result),
+                },
+                None if byes.iter().any(|b| b.round > round) => {
+                    round += 1;
+                    continue;
+                }
+                None => break,
+            },
+        };
+        history.push(RoundRecord { round, outcome, float: None });
+        round += 1;
+    }
+    history
+}
+
+/// What happened to a player in one round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundRecord {
+    pub round: u32,
+    pub outcome: RoundOutcome,
+    /// Set when the player was paired outside their own score group
+    #[serde(default)]
+    pub float: Option<FloatDirection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RoundOutcome {
+    /// A game over the board; the result is `None` until it is reported and
+    /// the color only for games recorded before colors were kept per round
+    Game {
+        opponent: Uuid,
+        color: Option<Color>,
+        result: Option<GameResult>,
+    },
+    /// A paired game the arbiter decided without it being played
+    Forfeit {
+        opponent: Uuid,
+        color: Option<Color>,
+        result: GameResult,
+    },
+    Bye { kind: ByeKind, points: f32 },
+    /// A round missed while withdrawn, scored when the player re-entered
+    Absent { points: f32 },
+}
+
+impl RoundRecord {
+    /// The opponent paired against, forfeits included; `None` for a bye.
+    pub fn opponent(&self) -> Option<Uuid> {
+        match self.outcome {
+            RoundOutcome::Game { opponent, .. } | RoundOutcome::Forfeit { opponent, .. } => Some(opponent),
+            RoundOutcome::Bye { .. } | RoundOutcome::Absent { .. } => None,
+        }
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        match self.outcome {
+            RoundOutcome::Game { color, .. } | RoundOutcome::Forfeit { color, .. } => color,
+            RoundOutcome::Bye { .. } | RoundOutcome::Absent { .. } => None,
+        }
+    }
+}
+
+/// A game a player has finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub opponent: Uuid,
+    pub result: GameResult,
+}
+
+/// The colors a player has had, kept only as far as pairing needs them: the
+/// balance, and the last two for the rule against three in a row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "StoredColors")]
+pub struct ColorHistory {
+    /// Whites minus blacks
+    balance: i32,
+    /// The latest game first
+    last_two: [Option<Color>; 2],
+}
+
+/// `ColorHistory` as stored; earlier versions kept every color in order.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredColors {
+    Compact {
+        balance: i32,
+        last_two: [Option<Color>; 2],
+    },
+    Full(Vec<Color>),
+}
+
+impl From<StoredColors> for ColorHistory {
+    fn from(stored: StoredColors) -> Self {
+        match stored {
+            StoredColors::Compact { balance, last_two } => Self { balance, last_two },
+            StoredColors::Full(colors) => {
+                let mut history = Self::default();
+                for color in colors {
+                    history.push(color);
+                }
+                history
+            }
+        }
+    }
+}
+
+impl ColorHistory {
+    pub fn push(&mut self, color: Color) {
+        self.balance += match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        self.last_two = [Some(color), self.last_two[0]];
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_two[0].is_none()
+    }
+
+    /// Whites minus blacks.
+    pub fn balance(&self) -> i32 {
+        self.balance
+    }
+
+    /// Color of the latest game, if any.
+    pub fn last(&self) -> Option<Color> {
+        self.last_two[0]
+    }
+
+    /// The color of both of the last two games, which FIDE rules out for
+    /// the next one.
+    pub fn repeated(&self) -> Option<Color> {
+        match self.last_two {
+            [Some(latest), Some(before)] if latest == before => Some(latest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ByeKind {
+    /// Given by the pairer to the odd player out
+    Allocated,
+    /// Asked for by the player before the round was paired
+    Requested,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ByeRecord {
+    pub round: u32,
+    pub points: f32,
+    pub kind: ByeKind,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -26,7 +271,7 @@ pub enum Color {
     Black,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Pairing {
     pub white_player: Uuid,
     pub black_player: Uuid,
@@ -40,9 +285,24 @@ pub struct TournamentState {
     pub pairings: Vec<Pairing>,
     pub completed_rounds: u32,
     pub total_rounds: u32,
+    /// Pairing decisions recorded for each paired round
+    #[serde(default)]
+    pub pairing_audits: Vec<RoundAudit>,
+    /// Byes players asked for ahead of the round they apply to
+    #[serde(default)]
+    pub requested_byes: Vec<ByeRequest>,
+    /// Games decided by the arbiter rather than over the board
+    #[serde(default)]
+    pub forfeits: Vec<Forfeit>,
+    /// Group A of an accelerated tournament, fixed the first time it is paired
+    #[serde(default)]
+    pub accelerated_players: Vec<Uuid>,
+    /// Withdrawals and re-entries, in the order made
+    #[serde(default)]
+    pub entry_changes: Vec<EntryChange>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PairingResult {
     Paired(Pairing),
     Bye(Uuid),
@@ -53,6 +313,108 @@ pub struct SwissConfig {
     pub total_rounds: u32,
     pub rating_importance: f32, // Weight for rating in tie-breaking
     pub color_balance_weight: f32,
+    /// When set, players with equal score and rating are ordered by a hash of
+    /// this seed and their id, so the same input always yields the same pairings
+    #[serde(default)]
+    pub deterministic_seed: Option<u64>,
+    /// Baku acceleration for large opens; `None` pairs on actual scores only
+    #[serde(default)]
+    pub acceleration: Option<BakuAcceleration>,
+    /// Points for the bye the pairer gives the odd player out
+    #[serde(default = "default_bye_points")]
+    pub bye_points: f32,
+    /// Points for a bye the player requested (usually half or zero)
+    #[serde(default = "default_requested_bye_points")]
+    pub requested_bye_points: f32,
+    /// Whether withdrawn players may come back, and what the rounds they
+    /// missed are worth
+    #[serde(default)]
+    pub reentry: ReentryPolicy,
+    /// Order of the starting ranks round 1 is paired on
+    #[serde(default)]
+    pub seeding: SeedingOrder,
+    /// Color of the top seed on the first board of round 1
+    #[serde(default)]
+    pub initial_color: InitialColor,
+}
+
+/// How players are seeded for round 1, which pairs the top half of the
+/// field against the bottom half.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeedingOrder {
+    #[default]
+    Rating,
+    /// Drawn by lot, from `deterministic_seed` when one is set
+    Random,
+    /// Player ids, top seed first; players left out follow by rating
+    Manual(Vec<Uuid>),
+}
+
+/// Color of the top seed on board 1 of round 1; the boards below alternate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InitialColor {
+    #[default]
+    White,
+    Black,
+    /// Drawn by lot, from `deterministic_seed` when one is set
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReentryPolicy {
+    /// A withdrawal is final
+    Forbidden,
+    /// Missed rounds score nothing
+    #[default]
+    ZeroForMissed,
+    /// Missed rounds score as requested byes
+    ByeForMissed,
+}
+
+fn default_bye_points() -> f32 {
+    1.0
+}
+
+fn default_requested_bye_points() -> f32 {
+    0.5
+}
+
+/// FIDE Baku acceleration: players in the top half of the starting ranks
+/// (group A) are paired as if they had extra virtual points in early rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BakuAcceleration {
+    /// Number of leading rounds in which group A receives virtual points
+    pub accelerated_rounds: u32,
+    /// Leading rounds worth a full virtual point; the remaining accelerated rounds are worth half
+    pub full_point_rounds: u32,
+}
+
+impl BakuAcceleration {
+    /// FIDE defaults: the first half of the rounds (rounded up) are accelerated,
+    /// the first half of those (rounded up) with a full virtual point.
+    pub fn standard(total_rounds: u32) -> Self {
+        let accelerated_rounds = total_rounds.div_ceil(2);
+        Self {
+            accelerated_rounds,
+            full_point_rounds: accelerated_rounds.div_ceil(2),
+        }
+    }
+
+    /// Virtual points a group A player carries when round `round` is paired.
+    pub fn virtual_points(&self, round: u32) -> f32 {
+        if round <= self.full_point_rounds {
+            1.0
+        } else if round <= self.accelerated_rounds {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    /// Size of group A: twice the player count divided by four, rounded up.
+    pub fn group_a_size(player_count: usize) -> usize {
+        2 * player_count.div_ceil(4)
+    }
 }
 
 impl Default for SwissConfig {
@@ -61,6 +423,13 @@ impl Default for SwissConfig {
             total_rounds: 5,
             rating_importance: 0.1,
             color_balance_weight: 0.2,
+            deterministic_seed: None,
+            acceleration: None,
+            bye_points: default_bye_points(),
+            requested_bye_points: default_requested_bye_points(),
+            reentry: ReentryPolicy::default(),
+            seeding: SeedingOrder::default(),
+            initial_color: InitialColor::default(),
         }
     }
 }
@@ -72,22 +441,109 @@ impl Player {
             name,
             rating,
             score: 0.0,
-            color_history: Vec::new(),
-            opponents: Vec::new(),
+            color_history: ColorHistory::default(),
+            opponents: HashSet::new(),
+            results: Vec::new(),
             is_active: true,
             float_score: 0,
+            byes: Vec::new(),
+            history: Vec::new(),
         }
     }
 
-    pub fn add_game_result(&mut self, opponent: Uuid, color: Color, result: GameResult) {
-        self.opponents.push(opponent);
+    pub fn add_game_result(&mut self, round: u32, opponent: Uuid, color: Color, result: GameResult) {
+        self.opponents.insert(opponent);
         self.color_history.push(color);
-        
-        match result {
-            GameResult::Win => self.score += 1.0,
-            GameResult::Draw => self.score += 0.5,
-            GameResult::Loss => self.score += 0.0,
+        self.results.push(GameRecord { opponent, result });
+        self.set_outcome(round, RoundOutcome::Game { opponent, color: Some(color), result: Some(result) });
+        self.score += result.points();
+    }
+
+    /// Replace the result of the game played in `round`, adjusting the score.
+    /// Returns the result it replaced, or `None` when no game was scored that round.
+    pub fn correct_result(&mut self, round: u32, result: GameResult) -> Option<GameResult> {
+        let record = self.history.iter_mut().find(|r| r.round == round)?;
+        let (opponent, previous) = match &mut record.outcome {
+            RoundOutcome::Game { opponent, result: Some(previous), .. } => {
+                let replaced = *previous;
+                *previous = result;
+                (*opponent, replaced)
+            }
+            _ => return None,
+        };
+        if let Some(game) = self.results.iter_mut().find(|g| g.opponent == opponent) {
+            game.result = result;
+        }
+        self.score += result.points() - previous.points();
+        Some(previous)
+    }
+
+    /// Note the game this player was paired into for `round`, still to be played.
+    pub fn add_pairing(&mut self, round: u32, opponent: Uuid, color: Color) {
+        self.set_outcome(round, RoundOutcome::Game { opponent, color: Some(color), result: None });
+    }
+
+    /// Score the game of `round` the arbiter decided by forfeit. It does not
+    /// count as played.
+    pub fn add_forfeit(&mut self, round: u32, opponent: Uuid, color: Option<Color>, result: GameResult) {
+        if result == GameResult::Win {
+            self.score += 1.0;
         }
+        self.set_outcome(round, RoundOutcome::Forfeit { opponent, color, result });
+    }
+
+    /// Credit a bye for `round`.
+    pub fn add_bye(&mut self, round: u32, points: f32, kind: ByeKind) {
+        self.score += points;
+        self.byes.push(ByeRecord { round, points, kind });
+        self.set_outcome(round, RoundOutcome::Bye { kind, points });
+    }
+
+    /// Score `round`, missed while withdrawn.
+    pub fn add_absence(&mut self, round: u32, points: f32) {
+        self.score += points;
+        self.set_outcome(round, RoundOutcome::Absent { points });
+    }
+
+    /// Note that the player floated up or down when `round` was paired.
+    pub fn add_float(&mut self, round: u32, direction: FloatDirection) {
+        self.float_score += match direction {
+            FloatDirection::Down => 1,
+            FloatDirection::Up => -1,
+        };
+        if let Some(record) = self.history.iter_mut().find(|r| r.round == round) {
+            record.float = Some(direction);
+        }
+    }
+
+    pub fn round_record(&self, round: u32) -> Option<&RoundRecord> {
+        self.history.iter().find(|r| r.round == round)
+    }
+
+    /// Replace what happened in `round`, keeping any float already noted.
+    fn set_outcome(&mut self, round: u32, outcome: RoundOutcome) {
+        let record = self.record_mut(round, outcome.clone());
+        record.outcome = outcome;
+    }
+
+    /// The record of `round`, added with `outcome` when there is none yet.
+    fn record_mut(&mut self, round: u32, outcome: RoundOutcome) -> &mut RoundRecord {
+        let index = match self.history.iter().position(|r| r.round >= round) {
+            Some(index) if self.history[index].round == round => index,
+            Some(index) => {
+                self.history.insert(index, RoundRecord { round, outcome, float: None });
+                index
+            }
+            None => {
+                self.history.push(RoundRecord { round, outcome, float: None });
+                self.history.len() - 1
+            }
+        };
+        &mut self.history[index]
+    }
+
+    pub fn bye_in_round(&self, round: u32) -> Option<&ByeRecord> {
+        self.byes.iter().find(|b| b.round == round)
     }
 
     pub fn has_played_against(&self, opponent_id: &Uuid) -> bool {
@@ -95,13 +551,30 @@ impl Player {
     }
 
     pub fn get_color_balance(&self) -> i32 {
-        let white_count = self.color_history.iter().filter(|&&c| c == Color::White).count() as i32;
-        let black_count = self.color_history.iter().filter(|&&c| c == Color::Black).count() as i32;
-        white_count - black_count
+        self.color_history.balance()
     }
 
+    /// Due white after two blacks in a row, or when behind on whites unless
+    /// the last two games were both white.
     pub fn should_prefer_white(&self) -> bool {
-        self.get_color_balance() < 0
+        match self.color_history.repeated() {
+            Some(color) => color == Color::Black,
+            None => self.get_color_balance() < 0,
+        }
+    }
+
+    /// The color FIDE requires this player to have next: the other one after
+    /// two of the same in a row, or whichever brings a balance of two back
+    /// toward zero.
+    pub fn absolute_color(&self) -> Option<Color> {
+        let balance = self.get_color_balance();
+        match self.color_history.repeated() {
+            Some(Color::White) => Some(Color::Black),
+            Some(Color::Black) => Some(Color::White),
+            None if balance >= 2 => Some(Color::Black),
+            None if balance <= -2 => Some(Color::White),
+            None => None,
+        }
     }
 
     pub fn can_be_paired_with(&self, other: &Player) -> bool {
@@ -116,6 +589,25 @@ pub enum GameResult {
     Loss,
 }
 
+impl GameResult {
+    pub fn points(self) -> f32 {
+        match self {
+            GameResult::Win => 1.0,
+            GameResult::Draw => 0.5,
+            GameResult::Loss => 0.0,
+        }
+    }
+
+    /// The same game seen from the opponent's side.
+    pub fn reversed(self) -> Self {
+        match self {
+            GameResult::Win => GameResult::Loss,
+            GameResult::Draw => GameResult::Draw,
+            GameResult::Loss => GameResult::Win,
+        }
+    }
+}
+
 impl TournamentState {
     pub fn new(players: Vec<Player>, total_rounds: u32) -> Self {
         let player_map: HashMap<Uuid, Player> = players
@@ -129,6 +621,11 @@ impl TournamentState {
             pairings: Vec::new(),
             completed_rounds: 0,
             total_rounds,
+            pairing_audits: Vec::new(),
+            requested_byes: Vec::new(),
+            forfeits: Vec::new(),
+            accelerated_players: Vec::new(),
+            entry_changes: Vec::new(),
         }
     }
 
@@ -150,7 +647,18 @@ impl TournamentState {
     }
 
     pub fn apply_round_results(&mut self, results: Vec<(Uuid, GameResult)>) {
+        let forfeits: Vec<Forfeit> = self
+            .forfeits
+            .iter()
+            .filter(|f| f.round == self.current_round)
+            .cloned()
+            .collect();
+
         for (player_id, result) in results {
+            // Forfeited games are scored below and never count as played
+            if forfeits.iter().any(|f| f.winner == player_id || f.loser == player_id) {
+                continue;
+            }
             if let Some(player) = self.players.get_mut(&player_id) {
                 // Find the opponent and color from current round pairings
                 if let Some(pairing) = self.pairings.iter().find(|p| {
@@ -166,15 +674,49 @@ impl TournamentState {
                     } else {
                         Color::Black
                     };
-                    player.add_game_result(opponent_id, color, result);
+                    player.add_game_result(self.current_round, opponent_id, color, result);
                 }
             }
         }
+
+        for forfeit in forfeits {
+            let pairing = self.pairing_of(forfeit.round, forfeit.winner).cloned();
+            let color_of = |id: Uuid| {
+                pairing
+                    .as_ref()
+                    .map(|p| if p.white_player == id { Color::White } else { Color::Black })
+            };
+            if let Some(winner) = self.players.get_mut(&forfeit.winner) {
+                winner.add_forfeit(forfeit.round, forfeit.loser, color_of(forfeit.winner), GameResult::Win);
+            }
+            if let Some(loser) = self.players.get_mut(&forfeit.loser) {
+                loser.add_forfeit(forfeit.round, forfeit.winner, color_of(forfeit.loser), GameResult::Loss);
+            }
+        }
         
         self.completed_rounds += 1;
         self.current_round += 1;
     }
 
+    /// What happened to a player in each round so far, in round order:
+    /// opponent or bye, color, result and any float. Empty for unknown players.
+    pub fn round_history(&self, player: Uuid) -> &[RoundRecord] {
+        self.players.get(&player).map_or(&[], |p| p.history.as_slice())
+    }
+
+    pub fn audit_for_round(&self, round: u32) -> Option<&RoundAudit> {
+        self.pairing_audits.iter().find(|a| a.round == round)
+    }
+
+    /// Store the audit for a round, after any decisions already recorded
+    /// for it such as manual pairings.
+    pub fn record_audit(&mut self, audit: RoundAudit) {
+        match self.pairing_audits.iter_mut().find(|a| a.round == audit.round) {
+            Some(existing) => existing.decisions.extend(audit.decisions),
+            None => self.pairing_audits.push(audit),
+        }
+    }
+
     pub fn is_complete(&self) -> bool {
         self.completed_rounds >= self.total_rounds
     }
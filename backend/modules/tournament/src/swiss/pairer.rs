@@ -1,164 +1,515 @@
 use super::*;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 
 pub struct SwissPairer {
     config: SwissConfig,
 }
 
+/// A player as the pairer sees them for one round, borrowed from the
+/// tournament rather than copied out of it.
+#[derive(Clone, Copy)]
+struct Candidate<'a> {
+    player: &'a Player,
+    /// Real score plus any acceleration virtual points
+    score: f32,
+}
+
+/// What pairing one round needs besides the players: the audit being
+/// written and the floats to note once the round is paired.
+struct RoundContext {
+    round: u32,
+    /// Let players who both must have the same color meet anyway
+    relax_colors: bool,
+    audit: RoundAudit,
+    floats: Vec<(Uuid, FloatDirection)>,
+}
+
+/// A round worked out by `SwissPairer::pair_round`, not yet written to the
+/// tournament; `SwissPairer::commit_round` does that.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairedRound {
+    pub round: u32,
+    pub pairings: Vec<Pairing>,
+    /// The odd player out
+    pub bye: Option<Uuid>,
+    /// Players granted the byes they requested, in id order
+    pub requested_byes: Vec<Uuid>,
+    /// Players paired outside their score group
+    pub floats: Vec<(Uuid, FloatDirection)>,
+    pub audit: RoundAudit,
+}
+
+impl PairedRound {
+    /// Pairings first, then the allocated bye, then requested byes.
+    pub fn results(&self) -> Vec<PairingResult> {
+        self.pairings
+            .iter()
+            .cloned()
+            .map(PairingResult::Paired)
+            .chain(self.bye.map(PairingResult::Bye))
+            .chain(self.requested_byes.iter().copied().map(PairingResult::Bye))
+            .collect()
+    }
+
+    /// Everyone the round schedules, paired or on a bye.
+    pub fn players(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.pairings
+            .iter()
+            .flat_map(|p| [p.white_player, p.black_player])
+            .chain(self.bye)
+            .chain(self.requested_byes.iter().copied())
+    }
+}
+
 impl SwissPairer {
     pub fn new(config: SwissConfig) -> Self {
         Self { config }
     }
 
-    pub fn pair_round(&self, tournament: &mut TournamentState) -> Result<Vec<PairingResult>, PairingError> {
-        // Clone players to avoid borrow issues
-        let players: Vec<Player> = tournament.players.values().cloned().collect();
-        let mut player_refs: Vec<&Player> = players.iter().collect();
-        player_refs.sort_by(|a, b| {
-            b.score.partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-                .then(b.rating.cmp(&a.rating))
-        });
-        
+    /// Work out the pairings and byes of the current round. The tournament
+    /// is left as it is, so a round can be paired again or only previewed;
+    /// `commit_round` writes the result to it.
+    pub fn pair_round(&self, tournament: &TournamentState) -> Result<PairedRound, PairingError> {
+        self.pair_among(tournament, |_| true)
+    }
+
+    /// Like `pair_round`, over only the players `eligible` accepts. They are
+    /// borrowed from the tournament, so pairing the rest of a large field
+    /// copies none of it; acceleration still goes by the whole field.
+    pub fn pair_among(
+        &self,
+        tournament: &TournamentState,
+        eligible: impl Fn(&Player) -> bool,
+    ) -> Result<PairedRound, PairingError> {
+        let round = tournament.current_round;
+        let mut audit = RoundAudit::new(round, self.config.deterministic_seed);
+        let requested_byes = self.requested_byes(tournament, &eligible, &mut audit);
+
+        // Pair on borrowed players carrying their pairing scores, i.e. real
+        // score plus any acceleration virtual points
+        let skipped: HashSet<Uuid> = requested_byes.iter().copied().collect();
+        let accelerated: HashSet<Uuid> = self.group_a(tournament).into_iter().collect();
+        let virtual_points = self.config.acceleration.map_or(0.0, |a| a.virtual_points(round));
+
+        let mut players: Vec<Candidate> = tournament
+            .players
+            .values()
+            .filter(|p| eligible(p) && !skipped.contains(&p.id))
+            .map(|player| Candidate {
+                player,
+                score: player.score + if accelerated.contains(&player.id) { virtual_points } else { 0.0 },
+            })
+            .collect();
+
+        // Round 1 is paired on the starting ranks rather than by score
+        // group; a field with any history has no round 1 left to seed
+        let first_round = round == 1 && players.iter().all(|c| c.player.history.is_empty());
+        let draw = first_round.then(|| self.config.deterministic_seed.unwrap_or_else(|| Uuid::new_v4().as_u64_pair().0));
+        match draw {
+            Some(draw) => players.sort_by(|a, b| self.seed_order(a, b, draw)),
+            None => players.sort_by(|a, b| self.rank_order(a, b)),
+        }
+
+        let mut context = RoundContext {
+            round,
+            relax_colors: false,
+            audit,
+            floats: Vec::new(),
+        };
+
         // Handle odd number of players - assign bye to lowest ranked
-        if player_refs.len() % 2 == 1 {
-            let bye_player_id = self.assign_bye(&mut player_refs, tournament)?;
-            let pairings = self.pair_even_players(player_refs, tournament)?;
-            Ok(pairings.into_iter().chain(vec![PairingResult::Bye(bye_player_id)]).collect())
+        let bye = if players.len() % 2 == 1 {
+            Some(self.assign_bye(&mut players, &mut context.audit)?)
         } else {
-            let pairings = self.pair_even_players(player_refs, tournament)?;
-            Ok(pairings)
+            None
+        };
+
+        let last_round = round >= tournament.total_rounds;
+        let before_pairing = last_round.then(|| context.audit.clone());
+        let paired = match draw.and_then(|draw| self.pair_seeded(&players, &mut context, draw)) {
+            Some(pairings) => Ok(pairings),
+            None => self.pair_even_players(&players, &mut context),
+        };
+        let pairings = match (paired, before_pairing) {
+            // Rather than leave the final round unpaired, give someone a
+            // color the absolute constraints rule out
+            (Err(PairingError::CannotPairRemainingPlayers), Some(audit)) => {
+                context = RoundContext {
+                    round,
+                    relax_colors: true,
+                    audit,
+                    floats: Vec::new(),
+                };
+                self.pair_even_players(&players, &mut context)?
+            }
+            (result, _) => result?,
+        };
+
+        Ok(PairedRound {
+            round,
+            pairings: pairings
+                .into_iter()
+                .filter_map(|r| match r {
+                    PairingResult::Paired(pairing) => Some(pairing),
+                    PairingResult::Bye(_) => None,
+                })
+                .collect(),
+            bye,
+            requested_byes,
+            floats: context.floats,
+            audit: context.audit,
+        })
+    }
+
+    /// Write a round from `pair_round` to the tournament: credit its byes,
+    /// record its pairings with each player's color, note the floats and
+    /// keep the audit. A round that is not the current one, or whose players
+    /// already have a game or bye in it, is refused, so committing twice
+    /// cannot credit a bye twice.
+    pub fn commit_round(&self, tournament: &mut TournamentState, paired: &PairedRound) -> Result<(), PairingError> {
+        let round = paired.round;
+        if round != tournament.current_round {
+            return Err(PairingError::InvalidTournamentState);
+        }
+        let scheduled = paired.players().any(|id| match tournament.players.get(&id) {
+            Some(player) => tournament.pairing_of(round, id).is_some() || player.bye_in_round(round).is_some(),
+            None => true,
+        });
+        if scheduled {
+            return Err(PairingError::InvalidTournamentState);
+        }
+
+        self.prepare(tournament);
+        for id in &paired.requested_byes {
+            if let Some(player) = tournament.players.get_mut(id) {
+                player.add_bye(round, self.config.requested_bye_points, ByeKind::Requested);
+            }
+        }
+        if let Some(player) = paired.bye.and_then(|id| tournament.players.get_mut(&id)) {
+            player.add_bye(round, self.config.bye_points, ByeKind::Allocated);
+        }
+        for pairing in &paired.pairings {
+            if let Some(white) = tournament.players.get_mut(&pairing.white_player) {
+                white.add_pairing(round, pairing.black_player, Color::White);
+            }
+            if let Some(black) = tournament.players.get_mut(&pairing.black_player) {
+                black.add_pairing(round, pairing.white_player, Color::Black);
+            }
+            tournament.pairings.push(pairing.clone());
+        }
+        for &(id, direction) in &paired.floats {
+            if let Some(player) = tournament.players.get_mut(&id) {
+                player.add_float(round, direction);
+            }
+        }
+
+        tournament.record_audit(paired.audit.clone());
+        Ok(())
+    }
+
+    /// Fix group A of an accelerated tournament the first time it is needed, so
+    /// later withdrawals or partial pairings cannot shift who is in it.
+    pub fn prepare(&self, tournament: &mut TournamentState) {
+        tournament.accelerated_players = self.group_a(tournament);
+    }
+
+    /// Group A of an accelerated tournament: the one already fixed, or the
+    /// top half of the field by rating when none is yet. Empty without
+    /// acceleration.
+    pub fn group_a(&self, tournament: &TournamentState) -> Vec<Uuid> {
+        if self.config.acceleration.is_none() || !tournament.accelerated_players.is_empty() {
+            return tournament.accelerated_players.clone();
+        }
+
+        let mut ranked: Vec<&Player> = tournament.players.values().collect();
+        ranked.sort_by(|a, b| b.rating.cmp(&a.rating).then(a.id.cmp(&b.id)));
+
+        let group_a_size = BakuAcceleration::group_a_size(ranked.len());
+        ranked.iter().take(group_a_size).map(|p| p.id).collect()
+    }
+
+    /// Ranking order used everywhere in the pairer: score, then rating, then
+    /// (when deterministic) the seeded tie key and finally the id.
+    fn rank_order(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        let order = b.score.partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then(b.player.rating.cmp(&a.player.rating));
+
+        match self.config.deterministic_seed {
+            Some(seed) => order
+                .then_with(|| tie_key(seed, &a.player.id).cmp(&tie_key(seed, &b.player.id)))
+                .then_with(|| a.player.id.cmp(&b.player.id)),
+            None => order,
+        }
+    }
+
+    /// Order of the starting ranks: pairing score (acceleration puts group A
+    /// ahead), then the configured seeding, then the ranking order.
+    fn seed_order(&self, a: &Candidate, b: &Candidate, draw: u64) -> Ordering {
+        let by_seed = match &self.config.seeding {
+            SeedingOrder::Rating => Ordering::Equal,
+            SeedingOrder::Random => tie_key(draw, &a.player.id).cmp(&tie_key(draw, &b.player.id)),
+            SeedingOrder::Manual(order) => {
+                let rank = |id: &Uuid| order.iter().position(|seeded| seeded == id).unwrap_or(order.len());
+                rank(&a.player.id).cmp(&rank(&b.player.id))
+            }
+        };
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then(by_seed)
+            .then_with(|| self.rank_order(a, b))
+    }
+
+    /// Round 1 by seeding: in each score group the top half meets the
+    /// bottom half in order, the odd player out dropping to the next group.
+    /// The top seed of board 1 has the initial color and the boards below
+    /// alternate. `None` when some seeded pair may not meet, e.g. after
+    /// manual pairings, leaving the round to the score-group pairing.
+    fn pair_seeded(&self, players: &[Candidate], context: &mut RoundContext, draw: u64) -> Option<Vec<PairingResult>> {
+        let mut boards: Vec<(&Candidate, &Candidate)> = Vec::with_capacity(players.len() / 2);
+        let mut carried: Option<&Candidate> = None;
+        for group in players.chunk_by(|a, b| a.score == b.score) {
+            let mut members: Vec<&Candidate> = carried.take().into_iter().chain(group).collect();
+            if members.len() % 2 == 1 {
+                carried = members.pop();
+            }
+            let (top, bottom) = members.split_at(members.len() / 2);
+            boards.extend(top.iter().copied().zip(bottom.iter().copied()));
+        }
+        if carried.is_some() || boards.iter().any(|(a, b)| self.can_pair(a, b, context.relax_colors).is_err()) {
+            return None;
+        }
+
+        let first = match self.config.initial_color {
+            InitialColor::White => Color::White,
+            InitialColor::Black => Color::Black,
+            InitialColor::Random if splitmix64(draw).is_multiple_of(2) => Color::White,
+            InitialColor::Random => Color::Black,
+        };
+        let mut pairings = Vec::with_capacity(boards.len());
+        for (board, (higher, lower)) in boards.into_iter().enumerate() {
+            let color = match (first, board % 2) {
+                (color, 0) => color,
+                (Color::White, _) => Color::Black,
+                (Color::Black, _) => Color::White,
+            };
+            let (white, black) = match color {
+                Color::White => (higher, lower),
+                Color::Black => (lower, higher),
+            };
+            let pairing = Pairing {
+                white_player: white.player.id,
+                black_player: black.player.id,
+                round: context.round,
+            };
+            context.audit.record(PairingDecision::Paired {
+                white: pairing.white_player,
+                black: pairing.black_player,
+                score_group: higher.score,
+                color_reason: format!("board {} of the seeding, {} takes {:?}", board + 1, higher.player.name, color),
+            });
+            self.record_floats(higher, lower, &mut context.audit);
+            self.update_float_scores(higher, lower, &mut context.floats);
+            pairings.push(PairingResult::Paired(pairing));
         }
+        Some(pairings)
     }
 
-    fn assign_bye(&self, players: &mut Vec<&Player>, tournament: &mut TournamentState) -> Result<Uuid, PairingError> {
-        // Find the lowest ranked player who hasn't had a bye yet
+    /// Byes requested for the current round by `eligible` players, which
+    /// take them out of the pairing pool. Requests are honored in player id
+    /// order.
+    fn requested_byes(
+        &self,
+        tournament: &TournamentState,
+        eligible: impl Fn(&Player) -> bool,
+        audit: &mut RoundAudit,
+    ) -> Vec<Uuid> {
+        let round = tournament.current_round;
+        let mut granted: Vec<Uuid> = tournament
+            .requested_byes
+            .iter()
+            .filter(|r| r.round == round)
+            .map(|r| r.player)
+            .filter(|id| {
+                tournament
+                    .players
+                    .get(id)
+                    .is_some_and(|p| eligible(p) && p.bye_in_round(round).is_none())
+            })
+            .collect();
+        granted.sort();
+        granted.dedup();
+
+        for id in &granted {
+            if let Some(player) = tournament.players.get(id) {
+                audit.record(PairingDecision::Bye {
+                    player: *id,
+                    score: player.score + self.config.requested_bye_points,
+                    rating: player.rating,
+                    reason: format!("requested before the round, worth {} points", self.config.requested_bye_points),
+                });
+            }
+        }
+        granted
+    }
+
+    fn assign_bye(&self, players: &mut Vec<Candidate>, audit: &mut RoundAudit) -> Result<Uuid, PairingError> {
+        // Players are in rank order; the bye goes to the lowest ranked one who hasn't had a bye yet
         let bye_candidate = players
             .iter()
             .enumerate()
-            .filter(|(_, p): &(_, &&Player)| !p.has_had_bye())
-            .min_by(|(_, a), (_, b)| {
-                a.score.partial_cmp(&b.score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-                    .then(a.rating.cmp(&b.rating))
-            });
+            .rev()
+            .find(|(_, c)| !c.player.has_had_bye());
 
         match bye_candidate {
-            Some((index, player)) => {
-                let player_id = player.id;
+            Some((index, candidate)) => {
+                let player_id = candidate.player.id;
+                let skipped = players.len() - 1 - index;
+                let reason = if skipped == 0 {
+                    "lowest ranked player".to_string()
+                } else {
+                    format!("lowest ranked player without a previous bye ({} lower ranked already had one)", skipped)
+                };
+                audit.record(PairingDecision::Bye {
+                    player: player_id,
+                    score: candidate.score,
+                    rating: candidate.player.rating,
+                    reason,
+                });
                 players.remove(index);
-                
-                // Award 1 point for bye
-                if let Some(p) = tournament.players.get_mut(&player_id) {
-                    p.score += 1.0;
-                }
-                
                 Ok(player_id)
             }
             None => Err(PairingError::NoValidByeCandidate),
         }
     }
 
-    fn pair_even_players(&self, players: Vec<&Player>, tournament: &mut TournamentState) -> Result<Vec<PairingResult>, PairingError> {
-        let mut pairings = Vec::new();
-        let _unpaired_players: Vec<Uuid> = players.iter().map(|p| p.id).collect();
-        let mut used_players = std::collections::HashSet::new();
-
-        // Dutch System: Process score groups
-        let mut score_groups = self.create_score_groups(&players);
-        
-        for group in score_groups.iter_mut() {
-            if group.len() < 2 {
-                continue;
+    fn pair_even_players(
+        &self,
+        players: &[Candidate],
+        context: &mut RoundContext,
+    ) -> Result<Vec<PairingResult>, PairingError> {
+        let mut pairings = Vec::with_capacity(players.len() / 2);
+        let mut used = vec![false; players.len()];
+
+        // Dutch System: process score groups. Players are in rank order, so
+        // each score group is a run of equal scores
+        let mut start = 0;
+        for group in players.chunk_by(|a, b| a.score == b.score) {
+            let end = start + group.len();
+            let group_used = &mut used[start..end];
+            // An odd group floats someone down; set them aside before pairing the rest
+            let floater = (group.len() % 2 == 1).then(|| self.choose_floater(group, context));
+            if let Some(index) = floater {
+                group_used[index] = true;
+            }
+            let group_pairings = self.pair_within_group(group, group_used, context)?;
+            if let Some(index) = floater {
+                group_used[index] = false;
             }
-
-            // Sort within group by rating (higher first)
-            group.sort_by(|a, b| b.rating.cmp(&a.rating));
-
-            // Pair within score group first
-            let group_pairings = self.pair_within_group(group, tournament, &mut used_players)?;
             pairings.extend(group_pairings);
+            start = end;
         }
 
         // Handle remaining players with score differences (floaters)
-        let remaining_players: Vec<&Player> = players
+        let remaining_players: Vec<&Candidate> = players
             .iter()
-            .filter(|p| !used_players.contains(&p.id))
-            .copied()
+            .zip(&used)
+            .filter(|(_, &used)| !used)
+            .map(|(p, _)| p)
             .collect();
 
         if !remaining_players.is_empty() {
-            let float_pairings = self.handle_floaters(remaining_players, tournament)?;
+            let float_pairings = self.handle_floaters(&remaining_players, context)?;
             pairings.extend(float_pairings);
         }
 
         Ok(pairings)
     }
 
-    fn create_score_groups<'a>(&self, players: &[&'a Player]) -> Vec<Vec<&'a Player>> {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut groups: HashMap<u64, Vec<&'a Player>> = HashMap::new();
-        
-        for player in players {
-            let mut hasher = DefaultHasher::new();
-            let score_bits = player.score.to_bits();
-            score_bits.hash(&mut hasher);
-            let key = hasher.finish();
-            
-            groups
-                .entry(key)
-                .or_insert_with(Vec::new)
-                .push(player);
+    /// Which player of an odd score group floats down: the lowest ranked of
+    /// those who did not float down last round and have floated down least.
+    fn choose_floater(&self, group: &[Candidate], context: &mut RoundContext) -> usize {
+        let previous = context.round.saturating_sub(1);
+        let history = |candidate: &Candidate| {
+            let floated_last = candidate
+                .player
+                .round_record(previous)
+                .is_some_and(|r| r.float == Some(FloatDirection::Down));
+            (floated_last, candidate.player.float_score)
+        };
+        // The first minimum going up from the bottom is the lowest ranked one
+        let index = (0..group.len()).rev().min_by_key(|&i| history(&group[i])).unwrap_or(group.len() - 1);
+
+        if group.len() > 1 {
+            let floater = &group[index];
+            let passed_over = group.len() - 1 - index;
+            let reason = if passed_over == 0 {
+                "lowest ranked player of an odd score group".to_string()
+            } else {
+                format!("floated down least of an odd score group ({} lower ranked floated down more or last round)", passed_over)
+            };
+            context.audit.record(PairingDecision::FloaterChosen {
+                player: floater.player.id,
+                score_group: floater.score,
+                float_score: floater.player.float_score,
+                reason,
+            });
         }
-
-        let mut sorted_groups: Vec<Vec<&'a Player>> = groups
-            .into_values()
-            .collect();
-        
-        // Sort groups by score (highest first)
-        sorted_groups.sort_by(|a, b| b[0].score.partial_cmp(&a[0].score).unwrap());
-        sorted_groups
+        index
     }
 
     fn pair_within_group(
         &self,
-        group: &[&Player],
-        tournament: &mut TournamentState,
-        used_players: &mut std::collections::HashSet<Uuid>,
+        group: &[Candidate],
+        used: &mut [bool],
+        context: &mut RoundContext,
     ) -> Result<Vec<PairingResult>, PairingError> {
         let mut pairings = Vec::new();
-        let mut group_players: Vec<&Player> = group.to_vec();
+        let mut first = 0;
 
         // Try to pair players avoiding color repeats and previous opponents
-        while group_players.len() >= 2 {
-            let player1 = group_players[0];
-            let mut found_pair = false;
+        loop {
+            while first < group.len() && used[first] {
+                first += 1;
+            }
+            if first == group.len() {
+                break;
+            }
+            let player1 = &group[first];
 
             // Find best opponent for player1
-            for (i, &player2) in group_players.iter().enumerate().skip(1) {
-                if self.can_pair(player1, player2, tournament) {
-                    let pairing = self.create_pairing(player1, player2, tournament.current_round)?;
-                    pairings.push(PairingResult::Paired(pairing));
-                    
-                    // Update float scores
-                    self.update_float_scores(player1, player2, tournament, false);
-                    
-                    used_players.insert(player1.id);
-                    used_players.insert(player2.id);
-                    
-                    group_players.remove(i);
-                    group_players.remove(0);
-                    found_pair = true;
-                    break;
+            let opponent = (first + 1..group.len()).filter(|&i| !used[i]).find(|&i| {
+                let player2 = &group[i];
+                match self.can_pair(player1, player2, context.relax_colors) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        context.audit.record(PairingDecision::CandidateRejected {
+                            player: player1.player.id,
+                            candidate: player2.player.id,
+                            reason: reason.to_string(),
+                        });
+                        false
+                    }
                 }
-            }
+            });
 
-            if !found_pair {
-                // No valid pair found in this group, will be handled as floater
-                break;
-            }
+            // No valid pair found in this group, will be handled as floater
+            let Some(i) = opponent else { break };
+            let player2 = &group[i];
+
+            let (pairing, color_reason) = self.create_pairing(player1, player2, context.round)?;
+            context.audit.record(PairingDecision::Paired {
+                white: pairing.white_player,
+                black: pairing.black_player,
+                score_group: player1.score,
+                color_reason,
+            });
+            pairings.push(PairingResult::Paired(pairing));
+
+            used[first] = true;
+            used[i] = true;
         }
 
         Ok(pairings)
@@ -166,134 +517,182 @@ impl SwissPairer {
 
     fn handle_floaters(
         &self,
-        remaining_players: Vec<&Player>,
-        tournament: &mut TournamentState,
+        players: &[&Candidate],
+        context: &mut RoundContext,
     ) -> Result<Vec<PairingResult>, PairingError> {
         let mut pairings = Vec::new();
-        let mut players = remaining_players;
+        let mut used = vec![false; players.len()];
 
-        // Sort remaining players by score then rating
-        players.sort_by(|a, b| {
-            b.score.partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-                .then(b.rating.cmp(&a.rating))
-        });
-
-        // Pair remaining players, allowing score differences
-        for i in (0..players.len()).step_by(2) {
-            if i + 1 >= players.len() {
-                break;
+        // Remaining players are still in rank order; pair each with the
+        // next one they haven't met, allowing score differences
+        for first in 0..players.len() {
+            if used[first] {
+                continue;
             }
+            let player1 = players[first];
+
+            let opponent = (first + 1..players.len()).filter(|&i| !used[i]).find(|&i| {
+                let player2 = players[i];
+                match self.can_pair(player1, player2, context.relax_colors) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        context.audit.record(PairingDecision::CandidateRejected {
+                            player: player1.player.id,
+                            candidate: player2.player.id,
+                            reason: reason.to_string(),
+                        });
+                        false
+                    }
+                }
+            });
 
-            let player1 = players[i];
-            let player2 = players[i + 1];
-
-            if self.can_pair(player1, player2, tournament) {
-                let pairing = self.create_pairing(player1, player2, tournament.current_round)?;
-                pairings.push(PairingResult::Paired(pairing));
-                
-                // Update float scores (these are floaters)
-                self.update_float_scores(player1, player2, tournament, true);
-            } else {
+            let Some(i) = opponent else {
+                context.audit.record(PairingDecision::CandidateRejected {
+                    player: player1.player.id,
+                    candidate: player1.player.id,
+                    reason: "no legal floater pairing left".to_string(),
+                });
                 return Err(PairingError::CannotPairRemainingPlayers);
-            }
+            };
+            let player2 = players[i];
+
+            let (pairing, color_reason) = self.create_pairing(player1, player2, context.round)?;
+            context.audit.record(PairingDecision::Paired {
+                white: pairing.white_player,
+                black: pairing.black_player,
+                score_group: player1.score.max(player2.score),
+                color_reason,
+            });
+            self.record_floats(player1, player2, &mut context.audit);
+            pairings.push(PairingResult::Paired(pairing));
+
+            // Update float scores (these are floaters)
+            self.update_float_scores(player1, player2, &mut context.floats);
+
+            used[first] = true;
+            used[i] = true;
         }
 
         Ok(pairings)
     }
 
-    fn can_pair(&self, player1: &Player, player2: &Player, _tournament: &TournamentState) -> bool {
+    /// Whether two players may meet, or why not.
+    fn can_pair(&self, player1: &Candidate, player2: &Candidate, relax_colors: bool) -> Result<(), &'static str> {
         // Basic checks
-        if !player1.can_be_paired_with(player2) {
-            return false;
+        if !player1.player.can_be_paired_with(player2.player) {
+            return Err("already played each other");
         }
 
-        // Color balance preference
-        let color_preference_ok = self.check_color_preference(player1, player2);
-
-        color_preference_ok
-    }
-
-    fn check_color_preference(&self, player1: &Player, player2: &Player) -> bool {
-        let p1_prefers_white = player1.should_prefer_white();
-        let p2_prefers_white = player2.should_prefer_white();
-
-        // Prefer giving white to player who needs it more
-        if p1_prefers_white && !p2_prefers_white {
-            return true;
-        }
-        if !p1_prefers_white && p2_prefers_white {
-            return true;
+        // Absolute color constraints: whichever color one of them must have,
+        // the other must be able to take the opposite
+        match (player1.player.absolute_color(), player2.player.absolute_color()) {
+            (Some(Color::White), Some(Color::White)) if !relax_colors => Err("both must have white"),
+            (Some(Color::Black), Some(Color::Black)) if !relax_colors => Err("both must have black"),
+            _ => Ok(()),
         }
-
-        // If both prefer same color, it's still acceptable but less ideal
-        true
     }
 
-    fn create_pairing(&self, player1: &Player, player2: &Player, round: u32) -> Result<Pairing, PairingError> {
-        let (white_player, black_player) = if player1.should_prefer_white() {
-            (player1.id, player2.id)
+    fn create_pairing(&self, player1: &Candidate, player2: &Candidate, round: u32) -> Result<(Pairing, String), PairingError> {
+        let (player1, player2) = (player1.player, player2.player);
+        let (white_player, black_player, reason) = if let Some(color) = player1.absolute_color() {
+            let reason = format!("{} must have {:?} (color balance {})", player1.name, color, player1.get_color_balance());
+            match color {
+                Color::White => (player1.id, player2.id, reason),
+                Color::Black => (player2.id, player1.id, reason),
+            }
+        } else if let Some(color) = player2.absolute_color() {
+            let reason = format!("{} must have {:?} (color balance {})", player2.name, color, player2.get_color_balance());
+            match color {
+                Color::White => (player2.id, player1.id, reason),
+                Color::Black => (player1.id, player2.id, reason),
+            }
+        } else if player1.should_prefer_white() {
+            (player1.id, player2.id, format!("{} is due white (color balance {})", player1.name, player1.get_color_balance()))
         } else if player2.should_prefer_white() {
-            (player2.id, player1.id)
+            (player2.id, player1.id, format!("{} is due white (color balance {})", player2.name, player2.get_color_balance()))
         } else {
             // If neither has strong preference, higher rating gets white
+            let reason = "no color preference; higher rated player takes white".to_string();
             if player1.rating >= player2.rating {
-                (player1.id, player2.id)
+                (player1.id, player2.id, reason)
             } else {
-                (player2.id, player1.id)
+                (player2.id, player1.id, reason)
             }
         };
 
-        Ok(Pairing {
-            white_player,
-            black_player,
-            round,
-        })
+        Ok((
+            Pairing {
+                white_player,
+                black_player,
+                round,
+            },
+            reason,
+        ))
     }
 
-    fn update_float_scores(
-        &self,
-        player1: &Player,
-        player2: &Player,
-        tournament: &mut TournamentState,
-        is_floater: bool,
-    ) {
-        if is_floater {
-            // Update float scores for players paired across score groups
-            let _score_diff = (player1.score - player2.score).abs();
-            
-            if player1.score > player2.score {
-                if let Some(p) = tournament.players.get_mut(&player1.id) {
-                    p.float_score += 1; // Floating down
-                }
-                if let Some(p) = tournament.players.get_mut(&player2.id) {
-                    p.float_score -= 1; // Floating up
-                }
-            } else if player2.score > player1.score {
-                if let Some(p) = tournament.players.get_mut(&player1.id) {
-                    p.float_score -= 1; // Floating up
-                }
-                if let Some(p) = tournament.players.get_mut(&player2.id) {
-                    p.float_score += 1; // Floating down
-                }
-            }
+    fn record_floats(&self, player1: &Candidate, player2: &Candidate, audit: &mut RoundAudit) {
+        let (higher, lower) = match player1.score.partial_cmp(&player2.score) {
+            Some(Ordering::Greater) => (player1, player2),
+            Some(Ordering::Less) => (player2, player1),
+            _ => return,
+        };
+
+        audit.record(PairingDecision::Floated {
+            player: higher.player.id,
+            opponent: lower.player.id,
+            from_score: higher.score,
+            to_score: lower.score,
+            direction: FloatDirection::Down,
+        });
+        audit.record(PairingDecision::Floated {
+            player: lower.player.id,
+            opponent: higher.player.id,
+            from_score: lower.score,
+            to_score: higher.score,
+            direction: FloatDirection::Up,
+        });
+    }
+
+    /// Note which way two players paired across score groups floated.
+    fn update_float_scores(&self, player1: &Candidate, player2: &Candidate, floats: &mut Vec<(Uuid, FloatDirection)>) {
+        let (id1, id2) = (player1.player.id, player2.player.id);
+        if player1.score > player2.score {
+            floats.push((id1, FloatDirection::Down));
+            floats.push((id2, FloatDirection::Up));
+        } else if player2.score > player1.score {
+            floats.push((id1, FloatDirection::Up));
+            floats.push((id2, FloatDirection::Down));
         }
     }
 }
 
+/// Seeded tie key for a player: SplitMix64 over the seed and the id bytes.
+/// Stable across runs and platforms, unlike `DefaultHasher`.
+fn tie_key(seed: u64, id: &Uuid) -> u64 {
+    let (high, low) = id.as_u64_pair();
+    splitmix64(splitmix64(seed ^ high) ^ low)
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
 // Extension methods for Player
 impl Player {
+    /// Whether the pairer already gave this player a bye; requested byes don't count.
     pub fn has_had_bye(&self) -> bool {
-        // Check if player has a full point from a round without an opponent
-        self.score == 1.0 && self.opponents.is_empty() && self.completed_rounds() > 0
+        self.byes.iter().any(|b| b.kind == ByeKind::Allocated)
     }
 
     pub fn completed_rounds(&self) -> u32 {
-        self.opponents.len() as u32
+        self.results.len() as u32
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PairingError {
     NoValidByeCandidate,
     CannotPairRemainingPlayers,
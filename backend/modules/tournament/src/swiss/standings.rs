@@ -0,0 +1,87 @@
+use super::*;
+use std::cmp::Ordering;
+
+/// Tiebreak scores, compared in field order after the score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tiebreaks {
+    /// Sum of the opponents' scores
+    pub buchholz: f32,
+    /// Sum of the scores of beaten opponents plus half those of drawn ones
+    pub sonneborn_berger: f32,
+    /// Games won over the board
+    pub wins: u32,
+}
+
+/// A player's place in the standings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    /// 1-based finishing place; tied players are separated by tiebreaks,
+    /// then rating, then id
+    pub rank: u32,
+    pub player: Uuid,
+    pub name: String,
+    pub rating: i32,
+    pub score: f32,
+    pub tiebreaks: Tiebreaks,
+}
+
+impl TournamentState {
+    pub fn tiebreaks_of(&self, player: &Player) -> Tiebreaks {
+        let opponent_score = |id: &Uuid| self.players.get(id).map_or(0.0, |p| p.score);
+        // Games played over the board and finished; forfeits don't count
+        let games = player.history.iter().filter_map(|r| match r.outcome {
+            RoundOutcome::Game { opponent, result: Some(result), .. } => Some((opponent, result)),
+            _ => None,
+        });
+
+        let (mut buchholz, mut sonneborn_berger, mut wins) = (0.0, 0.0, 0);
+        for (opponent, result) in games {
+            buchholz += opponent_score(&opponent);
+            sonneborn_berger += match result {
+                GameResult::Win => opponent_score(&opponent),
+                GameResult::Draw => opponent_score(&opponent) / 2.0,
+                GameResult::Loss => 0.0,
+            };
+            if result == GameResult::Win {
+                wins += 1;
+            }
+        }
+
+        Tiebreaks {
+            buchholz,
+            sonneborn_berger,
+            wins,
+        }
+    }
+
+    /// Every entrant, withdrawn players included, ordered by score and tiebreaks.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .values()
+            .map(|p| Standing {
+                rank: 0,
+                player: p.id,
+                name: p.name.clone(),
+                rating: p.rating,
+                score: p.score,
+                tiebreaks: self.tiebreaks_of(p),
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            let desc = |x: f32, y: f32| y.partial_cmp(&x).unwrap_or(Ordering::Equal);
+            desc(a.score, b.score)
+                .then(desc(a.tiebreaks.buchholz, b.tiebreaks.buchholz))
+                .then(desc(a.tiebreaks.sonneborn_berger, b.tiebreaks.sonneborn_berger))
+                .then(b.tiebreaks.wins.cmp(&a.tiebreaks.wins))
+                .then(b.rating.cmp(&a.rating))
+                .then(a.player.cmp(&b.player))
+        });
+
+        for (index, standing) in standings.iter_mut().enumerate() {
+            standing.rank = index as u32 + 1;
+        }
+        standings
+    }
+}
@@ -3,6 +3,13 @@ mod tests {
     use super::super::*;
     use uuid::Uuid;
 
+    /// Pair the current round and write it to the tournament.
+    fn commit(pairer: &SwissPairer, tournament: &mut TournamentState) -> Result<Vec<PairingResult>, PairingError> {
+        let paired = pairer.pair_round(tournament)?;
+        pairer.commit_round(tournament, &paired)?;
+        Ok(paired.results())
+    }
+
     fn create_test_players() -> Vec<Player> {
         vec![
             Player::new(Uuid::new_v4(), "Alice".to_string(), 2000),
@@ -81,6 +88,64 @@ mod tests {
         assert!(player.should_prefer_white()); // Prefers white now
     }
 
+    #[test]
+    fn test_color_history_keeps_balance_and_last_two() {
+        let mut player = Player::new(Uuid::new_v4(), "Test".to_string(), 1500);
+        for color in [Color::White, Color::White, Color::Black, Color::Black] {
+            player.color_history.push(color);
+        }
+        assert_eq!(player.get_color_balance(), 0);
+        assert_eq!(player.color_history.last(), Some(Color::Black));
+        assert_eq!(player.color_history.repeated(), Some(Color::Black));
+        assert!(player.should_prefer_white());
+
+        // Behind on whites, but a third white in a row is ruled out
+        let mut player = Player::new(Uuid::new_v4(), "Test".to_string(), 1500);
+        for color in [Color::Black, Color::Black, Color::Black, Color::White, Color::White] {
+            player.color_history.push(color);
+        }
+        assert_eq!(player.get_color_balance(), -1);
+        assert_eq!(player.color_history.repeated(), Some(Color::White));
+        assert!(!player.should_prefer_white());
+    }
+
+    #[test]
+    fn test_player_reads_earlier_layout() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let stored = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "name": "Alice",
+            "rating": 2000,
+            "score": 1.5,
+            "color_history": ["White", "Black", "Black"],
+            "opponents": [a, b],
+            "results": ["Win", "Draw"],
+            "is_active": true,
+            "float_score": 0,
+        });
+
+        let player: Player = serde_json::from_value(stored).unwrap();
+        assert!(player.has_played_against(&a) && player.has_played_against(&b));
+        assert_eq!(
+            player.results,
+            vec![
+                GameRecord { opponent: a, result: GameResult::Win },
+                GameRecord { opponent: b, result: GameResult::Draw },
+            ]
+        );
+        assert_eq!(player.get_color_balance(), -1);
+        assert_eq!(player.color_history.repeated(), Some(Color::Black));
+        assert_eq!(player.history.len(), 2);
+        assert_eq!(player.history[1].round, 2);
+        assert_eq!(player.history[1].opponent(), Some(b));
+
+        // And the current layout reads back as written
+        let again: Player = serde_json::from_value(serde_json::to_value(&player).unwrap()).unwrap();
+        assert_eq!(again.opponents, player.opponents);
+        assert_eq!(again.results, player.results);
+        assert_eq!(again.color_history, player.color_history);
+    }
+
     #[test]
     fn test_game_result_application() {
         let mut tournament = TournamentState::new(create_test_players(), 5);
@@ -117,7 +182,7 @@ mod tests {
         let mut tournament = TournamentState::new(players, 5);
         let pairer = SwissPairer::new(SwissConfig::default());
         
-        let pairings = pairer.pair_round(&mut tournament).unwrap();
+        let pairings = commit(&pairer, &mut tournament).unwrap();
         
         // Should have 2 pairings (4 players) and 1 bye (5th player)
         assert_eq!(pairings.len(), 3);
@@ -137,7 +202,7 @@ mod tests {
         let mut tournament = TournamentState::new(players, 5);
         let pairer = SwissPairer::new(SwissConfig::default());
         
-        let pairings = pairer.pair_round(&mut tournament).unwrap();
+        let pairings = commit(&pairer, &mut tournament).unwrap();
         
         // Should have exactly 2 pairings, no byes
         assert_eq!(pairings.len(), 2);
@@ -161,7 +226,7 @@ mod tests {
         let expected_bye_candidate = initial_players.last().unwrap(); // Lowest rated
         let expected_id = expected_bye_candidate.id;
         
-        let pairings = pairer.pair_round(&mut tournament).unwrap();
+        let pairings = commit(&pairer, &mut tournament).unwrap();
         
         // Find the bye
         let bye_player_id = pairings.iter()
@@ -187,15 +252,7 @@ mod tests {
         let pairer = SwissPairer::new(SwissConfig::default());
         
         // First round
-        let first_round_pairings = pairer.pair_round(&mut tournament).unwrap();
-        tournament.pairings.clear();
-        
-        // Convert pairing results to actual pairings
-        for pairing_result in &first_round_pairings {
-            if let PairingResult::Paired(pairing) = pairing_result {
-                tournament.pairings.push(pairing.clone());
-            }
-        }
+        commit(&pairer, &mut tournament).unwrap();
         
         // Apply dummy results to advance
         let player_ids: Vec<Uuid> = tournament.players.keys().cloned().collect();
@@ -212,7 +269,7 @@ mod tests {
         tournament.apply_round_results(results);
         
         // Second round
-        let second_round_pairings = pairer.pair_round(&mut tournament).unwrap();
+        let second_round_pairings = commit(&pairer, &mut tournament).unwrap();
         
         // Verify no repeat pairings
         for pairing_result in &second_round_pairings {
@@ -224,6 +281,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_floater_skips_opponent_already_met() {
+        let mut players = create_test_players();
+        players.truncate(4);
+        let (a, b, c, d) = (players[0].id, players[1].id, players[2].id, players[3].id);
+        players[0].score = 1.0;
+        players[0].opponents.insert(b);
+        players[1].score = 1.0;
+        players[1].opponents.insert(a);
+        players[2].score = 0.5;
+        let mut tournament = TournamentState::new(players, 5);
+        tournament.current_round = 2;
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        // Alice and Bob share the top group but already met, so both float
+        // down past each other onto Charlie and Diana
+        let pairings = commit(&pairer, &mut tournament).unwrap();
+        let opponent_of = |id: Uuid| {
+            pairings.iter().find_map(|r| match r {
+                PairingResult::Paired(p) if p.white_player == id => Some(p.black_player),
+                PairingResult::Paired(p) if p.black_player == id => Some(p.white_player),
+                _ => None,
+            })
+        };
+        assert_eq!(opponent_of(a), Some(c));
+        assert_eq!(opponent_of(b), Some(d));
+        assert_eq!(tournament.players[&a].float_score, 1);
+        assert_eq!(tournament.players[&c].float_score, -1);
+        assert_eq!(tournament.round_history(a)[0].float, Some(FloatDirection::Down));
+        assert_eq!(tournament.round_history(c)[0].float, Some(FloatDirection::Up));
+    }
+
+    #[test]
+    fn test_round_history_follows_pairings_results_and_byes() {
+        let players = create_test_players();
+        let eve = players[4].id;
+        let mut tournament = TournamentState::new(players, 5);
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        let pairings = commit(&pairer, &mut tournament).unwrap();
+        assert_eq!(
+            tournament.round_history(eve),
+            &[RoundRecord {
+                round: 1,
+                outcome: RoundOutcome::Bye { kind: ByeKind::Allocated, points: 1.0 },
+                float: None,
+            }]
+        );
+
+        let mut results = Vec::new();
+        for result in pairings {
+            if let PairingResult::Paired(pairing) = result {
+                let record = &tournament.round_history(pairing.white_player)[0];
+                assert_eq!(record.opponent(), Some(pairing.black_player));
+                assert_eq!(record.color(), Some(Color::White));
+                results.push((pairing.white_player, GameResult::Win));
+                results.push((pairing.black_player, GameResult::Loss));
+            }
+        }
+        tournament.apply_round_results(results);
+
+        for pairing in &tournament.pairings {
+            let history = tournament.round_history(pairing.black_player);
+            assert_eq!(history.len(), 1);
+            assert_eq!(
+                history[0].outcome,
+                RoundOutcome::Game {
+                    opponent: pairing.white_player,
+                    color: Some(Color::Black),
+                    result: Some(GameResult::Loss),
+                }
+            );
+        }
+        assert!(tournament.round_history(Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    fn test_absolute_colors_keep_players_apart() {
+        let mut players = create_test_players();
+        players.truncate(4);
+        let (a, b, c, d) = (players[0].id, players[1].id, players[2].id, players[3].id);
+        for player in players.iter_mut().take(2) {
+            player.color_history.push(Color::White);
+            player.color_history.push(Color::White);
+        }
+        assert_eq!(players[0].absolute_color(), Some(Color::Black));
+        let mut tournament = TournamentState::new(players, 5);
+        tournament.current_round = 3;
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        // Alice and Bob would both get a third white, so each takes black
+        // against the next player down instead
+        let pairings = commit(&pairer, &mut tournament).unwrap();
+        assert!(pairings.contains(&PairingResult::Paired(Pairing { white_player: c, black_player: a, round: 3 })));
+        assert!(pairings.contains(&PairingResult::Paired(Pairing { white_player: d, black_player: b, round: 3 })));
+        let audit = tournament.audit_for_round(3).unwrap();
+        assert!(audit.decisions.contains(&PairingDecision::CandidateRejected {
+            player: a,
+            candidate: b,
+            reason: "both must have black".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_absolute_colors_relaxed_only_in_last_round() {
+        let mut players = create_test_players();
+        players.truncate(2);
+        players[0].color_history.push(Color::Black);
+        players[0].color_history.push(Color::Black);
+        players[1].color_history.push(Color::White);
+        players[1].color_history.push(Color::Black);
+        players[1].color_history.push(Color::Black);
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        let mut tournament = TournamentState::new(players.clone(), 4);
+        tournament.current_round = 3;
+        assert_eq!(commit(&pairer, &mut tournament), Err(PairingError::CannotPairRemainingPlayers));
+
+        let mut tournament = TournamentState::new(players, 3);
+        tournament.current_round = 3;
+        let pairings = commit(&pairer, &mut tournament).unwrap();
+        assert_eq!(pairings.len(), 1);
+    }
+
     #[test]
     fn test_tournament_completion() {
         let players = create_test_players();
@@ -253,7 +434,7 @@ mod tests {
         let pairer = SwissPairer::new(SwissConfig::default());
         
         // Simulate first round
-        let round1_pairings = pairer.pair_round(&mut tournament).unwrap();
+        let round1_pairings = commit(&pairer, &mut tournament).unwrap();
         assert_eq!(round1_pairings.len(), 4); // 4 pairings, no byes (8 players)
         
         // Apply realistic first round results (higher rated players tend to win)
@@ -290,7 +471,601 @@ mod tests {
         assert!(unique_scores.len() > 1, "Players should have different scores after round 1");
         
         // Second round should pair players with same scores when possible
-        let round2_pairings = pairer.pair_round(&mut tournament).unwrap();
+        let round2_pairings = commit(&pairer, &mut tournament).unwrap();
         assert_eq!(round2_pairings.len(), 4);
     }
+    #[test]
+    fn test_seeded_pairing_is_independent_of_insertion_order() {
+        // Equal ratings force every ordering decision down to the tie key
+        let players: Vec<Player> = (0..7)
+            .map(|i| Player::new(Uuid::new_v4(), format!("Player{}", i), 1500))
+            .collect();
+        let mut reversed = players.clone();
+        reversed.reverse();
+
+        let pairer = SwissPairer::new(SwissConfig {
+            deterministic_seed: Some(42),
+            ..SwissConfig::default()
+        });
+
+        let mut first = TournamentState::new(players, 5);
+        let mut second = TournamentState::new(reversed, 5);
+
+        let first_pairings = commit(&pairer, &mut first).unwrap();
+        let second_pairings = commit(&pairer, &mut second).unwrap();
+
+        assert_eq!(first_pairings, second_pairings);
+    }
+
+    #[test]
+    fn test_pair_round_records_audit() {
+        let mut tournament = TournamentState::new(create_test_players(), 5);
+        let pairer = SwissPairer::new(SwissConfig {
+            deterministic_seed: Some(7),
+            ..SwissConfig::default()
+        });
+
+        let pairings = commit(&pairer, &mut tournament).unwrap();
+        let audit = tournament.audit_for_round(1).expect("round 1 audit");
+
+        assert_eq!(audit.seed, Some(7));
+
+        let byes = audit
+            .decisions
+            .iter()
+            .filter(|d| matches!(d, PairingDecision::Bye { .. }))
+            .count();
+        let paired = audit
+            .decisions
+            .iter()
+            .filter(|d| matches!(d, PairingDecision::Paired { .. }))
+            .count();
+        assert_eq!(byes, 1);
+        assert_eq!(paired, pairings.len() - 1);
+
+        // A committed round cannot be committed again
+        assert_eq!(commit(&pairer, &mut tournament), Err(PairingError::InvalidTournamentState));
+        assert_eq!(tournament.pairing_audits.len(), 1);
+    }
+    #[test]
+    fn test_arbiter_force_pairing_rejects_double_pairing() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players, 5);
+
+        tournament.force_pairing(ids[0], ids[1]).unwrap();
+
+        assert_eq!(
+            tournament.force_pairing(ids[1], ids[2]),
+            Err(ArbiterError::AlreadyScheduled(ids[1]))
+        );
+        assert_eq!(tournament.force_pairing(ids[2], ids[2]), Err(ArbiterError::SamePlayer));
+
+        let stranger = Uuid::new_v4();
+        assert_eq!(
+            tournament.force_pairing(ids[2], stranger),
+            Err(ArbiterError::UnknownPlayer(stranger))
+        );
+    }
+
+    #[test]
+    fn test_arbiter_pair_remaining_keeps_forced_pairing() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players, 5);
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        let forced = tournament.force_pairing(ids[4], ids[0]).unwrap();
+        let results = tournament.pair_remaining(&pairer).unwrap();
+
+        // 3 players left: one pairing and one bye
+        assert_eq!(results.len(), 2);
+        assert_eq!(tournament.round_pairings(1).len(), 2);
+        assert!(tournament.round_pairings(1).contains(&&forced));
+        assert_eq!(tournament.round_byes(1).len(), 1);
+        assert!(tournament.unpaired_players().is_empty());
+
+        // Nobody is left to pair
+        assert!(tournament.pair_remaining(&pairer).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_arbiter_swap_colors_and_forfeit() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players, 5);
+
+        tournament.force_pairing(ids[0], ids[1]).unwrap();
+        let swapped = tournament.swap_colors(ids[0]).unwrap();
+        assert_eq!(swapped.white_player, ids[1]);
+        assert_eq!(swapped.black_player, ids[0]);
+
+        let forfeit = tournament.record_forfeit(ids[0]).unwrap();
+        assert_eq!(forfeit.loser, ids[1]);
+        assert_eq!(tournament.record_forfeit(ids[1]), Err(ArbiterError::AlreadyForfeited(ids[1])));
+        assert_eq!(tournament.swap_colors(ids[0]), Err(ArbiterError::AlreadyForfeited(ids[0])));
+
+        // Results reported for a forfeited game are ignored
+        tournament.apply_round_results(vec![(ids[1], GameResult::Win), (ids[0], GameResult::Loss)]);
+        assert_eq!(tournament.players[&ids[0]].score, 1.0);
+        assert_eq!(tournament.players[&ids[1]].score, 0.0);
+        assert!(!tournament.players[&ids[0]].has_played_against(&ids[1]));
+        assert_eq!(
+            tournament.round_history(ids[0])[0].outcome,
+            RoundOutcome::Forfeit { opponent: ids[1], color: Some(Color::Black), result: GameResult::Win }
+        );
+
+        let audit = tournament.audit_for_round(1).unwrap();
+        assert_eq!(audit.decisions_for(ids[0]).len(), 3);
+    }
+
+    #[test]
+    fn test_arbiter_corrects_disputed_result() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players, 5);
+
+        tournament.force_pairing(ids[0], ids[1]).unwrap();
+        tournament.apply_round_results(vec![(ids[0], GameResult::Loss), (ids[1], GameResult::Win)]);
+
+        // A lost-on-time call turned out to be lag: the game is a draw
+        let previous = tournament.correct_result(1, ids[1], GameResult::Draw).unwrap();
+        assert_eq!(previous, GameResult::Win);
+        assert_eq!(tournament.players[&ids[0]].score, 0.5);
+        assert_eq!(tournament.players[&ids[1]].score, 0.5);
+        assert_eq!(tournament.players[&ids[0]].results[0].result, GameResult::Draw);
+        assert_eq!(
+            tournament.round_history(ids[0])[0].outcome,
+            RoundOutcome::Game { opponent: ids[1], color: Some(Color::White), result: Some(GameResult::Draw) }
+        );
+
+        // Only scored games can be corrected
+        assert_eq!(
+            tournament.correct_result(2, ids[0], GameResult::Win),
+            Err(ArbiterError::NoResult(ids[0], 2))
+        );
+        assert_eq!(
+            tournament.correct_result(1, ids[2], GameResult::Win),
+            Err(ArbiterError::NoResult(ids[2], 1))
+        );
+
+        let audit = tournament.audit_for_round(1).unwrap();
+        assert_eq!(audit.decisions_for(ids[1]).len(), 2);
+    }
+
+    #[test]
+    fn test_baku_acceleration_standard_schedule() {
+        let acceleration = BakuAcceleration::standard(9);
+        assert_eq!(acceleration.accelerated_rounds, 5);
+        assert_eq!(acceleration.full_point_rounds, 3);
+
+        assert_eq!(acceleration.virtual_points(1), 1.0);
+        assert_eq!(acceleration.virtual_points(3), 1.0);
+        assert_eq!(acceleration.virtual_points(4), 0.5);
+        assert_eq!(acceleration.virtual_points(5), 0.5);
+        assert_eq!(acceleration.virtual_points(6), 0.0);
+
+        assert_eq!(BakuAcceleration::group_a_size(4), 2);
+        assert_eq!(BakuAcceleration::group_a_size(10), 6);
+    }
+
+    #[test]
+    fn test_baku_acceleration_changes_score_groups() {
+        let a1 = Player::new(Uuid::new_v4(), "A1".to_string(), 2400);
+        let a2 = Player::new(Uuid::new_v4(), "A2".to_string(), 2300);
+        let b1 = Player::new(Uuid::new_v4(), "B1".to_string(), 1800);
+        let b2 = Player::new(Uuid::new_v4(), "B2".to_string(), 1700);
+        let (a1_id, a2_id, b1_id, b2_id) = (a1.id, a2.id, b1.id, b2.id);
+
+        let mut tournament = TournamentState::new(vec![a1, a2, b1, b2], 4);
+        let pairer = SwissPairer::new(SwissConfig {
+            total_rounds: 4,
+            acceleration: Some(BakuAcceleration {
+                accelerated_rounds: 2,
+                full_point_rounds: 2,
+            }),
+            ..SwissConfig::default()
+        });
+
+        // Round 1: group A and group B are paired apart
+        tournament.pair_remaining(&pairer).unwrap();
+        let mut group_a = tournament.accelerated_players.clone();
+        group_a.sort();
+        let mut expected = vec![a1_id, a2_id];
+        expected.sort();
+        assert_eq!(group_a, expected);
+        let pairing = tournament.pairing_of(1, a1_id).unwrap();
+        assert!(pairing.white_player == a2_id || pairing.black_player == a2_id);
+
+        tournament.apply_round_results(vec![
+            (a1_id, GameResult::Win),
+            (a2_id, GameResult::Loss),
+            (b1_id, GameResult::Win),
+            (b2_id, GameResult::Loss),
+        ]);
+
+        // Round 2: A2 (0 + 1 virtual) meets B1 (1 + 0) instead of B2
+        tournament.pair_remaining(&pairer).unwrap();
+        let pairing = tournament.pairing_of(2, a2_id).unwrap();
+        assert!(pairing.white_player == b1_id || pairing.black_player == b1_id);
+
+        // Virtual points are never added to real scores
+        assert_eq!(tournament.players[&a2_id].score, 0.0);
+    }
+    #[test]
+    fn test_requested_and_configured_byes() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players, 5);
+        let pairer = SwissPairer::new(SwissConfig {
+            bye_points: 0.5,
+            requested_bye_points: 0.0,
+            ..SwissConfig::default()
+        });
+
+        // Alice sits out round 1; the other four pair up, so no allocated bye is needed
+        tournament.request_bye(ids[0], 1).unwrap();
+        assert_eq!(tournament.request_bye(ids[0], 1), Err(ArbiterError::AlreadyScheduled(ids[0])));
+        assert_eq!(tournament.request_bye(ids[1], 9), Err(ArbiterError::InvalidRound(9)));
+
+        let results = tournament.pair_remaining(&pairer).unwrap();
+        assert!(results.contains(&PairingResult::Bye(ids[0])));
+        assert_eq!(tournament.round_pairings(1).len(), 2);
+
+        let alice = &tournament.players[&ids[0]];
+        assert_eq!(alice.score, 0.0);
+        assert_eq!(alice.byes, vec![ByeRecord { round: 1, points: 0.0, kind: ByeKind::Requested }]);
+        assert!(!alice.has_had_bye());
+    }
+
+    #[test]
+    fn test_allocated_bye_uses_configured_points() {
+        let mut tournament = TournamentState::new(create_test_players(), 5);
+        let pairer = SwissPairer::new(SwissConfig {
+            bye_points: 0.5,
+            ..SwissConfig::default()
+        });
+
+        let results = commit(&pairer, &mut tournament).unwrap();
+        let bye_id = results
+            .iter()
+            .find_map(|r| match r {
+                PairingResult::Bye(id) => Some(*id),
+                _ => None,
+            })
+            .unwrap();
+
+        let player = &tournament.players[&bye_id];
+        assert_eq!(player.score, 0.5);
+        assert!(player.has_had_bye());
+        assert_eq!(player.bye_in_round(1).map(|b| b.kind), Some(ByeKind::Allocated));
+    }
+
+    #[test]
+    fn test_standings_break_ties_by_buchholz_then_sonneborn_berger() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+        let mut tournament = TournamentState::new(players.into_iter().take(4).collect(), 2);
+
+        tournament.force_pairing(a, b).unwrap();
+        tournament.force_pairing(c, d).unwrap();
+        tournament.apply_round_results(vec![
+            (a, GameResult::Win),
+            (b, GameResult::Loss),
+            (c, GameResult::Draw),
+            (d, GameResult::Draw),
+        ]);
+
+        tournament.force_pairing(a, c).unwrap();
+        tournament.force_pairing(b, d).unwrap();
+        tournament.apply_round_results(vec![
+            (a, GameResult::Draw),
+            (c, GameResult::Draw),
+            (b, GameResult::Win),
+            (d, GameResult::Loss),
+        ]);
+
+        // Bob and Charlie both have 1 point and 2 Buchholz; Charlie's draws
+        // against stronger opponents give the better Sonneborn-Berger
+        let standings = tournament.standings();
+        let order: Vec<Uuid> = standings.iter().map(|s| s.player).collect();
+        assert_eq!(order, vec![a, c, b, d]);
+        assert_eq!(standings[1].tiebreaks.buchholz, standings[2].tiebreaks.buchholz);
+        assert_eq!(standings[1].tiebreaks.sonneborn_berger, 1.0);
+        assert_eq!(standings[2].tiebreaks.sonneborn_berger, 0.5);
+        assert_eq!(standings[0].tiebreaks.wins, 1);
+        assert_eq!(standings.iter().map(|s| s.rank).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_pair_round_leaves_the_tournament_until_committed() {
+        let mut tournament = TournamentState::new(create_test_players(), 5);
+        let pairer = SwissPairer::new(SwissConfig {
+            deterministic_seed: Some(7),
+            ..SwissConfig::default()
+        });
+        let requester = *tournament.players.keys().next().unwrap();
+        tournament.request_bye(requester, 1).unwrap();
+        let before = serde_json::to_value(&tournament).unwrap();
+
+        // Pairing twice gives the same round and credits nothing
+        let paired = pairer.pair_round(&tournament).unwrap();
+        assert_eq!(pairer.pair_round(&tournament).unwrap(), paired);
+        assert_eq!(serde_json::to_value(&tournament).unwrap(), before);
+        assert_eq!(paired.requested_byes, vec![requester]);
+        assert!(paired.bye.is_none());
+
+        pairer.commit_round(&mut tournament, &paired).unwrap();
+        assert_eq!(tournament.players[&requester].score, 0.5);
+        assert_eq!(tournament.round_pairings(1).len(), 2);
+        assert!(tournament.audit_for_round(1).is_some());
+
+        // Committing it again would credit the bye twice
+        assert_eq!(pairer.commit_round(&mut tournament, &paired), Err(PairingError::InvalidTournamentState));
+        assert_eq!(tournament.players[&requester].score, 0.5);
+    }
+
+    #[test]
+    fn test_projected_results_preview_the_next_round() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let mut tournament = TournamentState::new(players.into_iter().take(4).collect(), 3);
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        tournament.force_pairing(ids[0], ids[1]).unwrap();
+        tournament.force_pairing(ids[2], ids[3]).unwrap();
+        let before = serde_json::to_value(&tournament).unwrap();
+
+        // Alice and Charlie win: they meet next round, as do Bob and Diana
+        let projected = tournament
+            .with_projected_results(&[(ids[0], GameResult::Win), (ids[3], GameResult::Loss)])
+            .unwrap();
+        assert_eq!(serde_json::to_value(&tournament).unwrap(), before);
+        assert_eq!(projected.current_round, 2);
+        assert_eq!(projected.players[&ids[2]].score, 1.0);
+
+        let preview = projected.preview_remaining(&pairer).unwrap();
+        let mut pairs: Vec<Vec<Uuid>> = preview
+            .iter()
+            .map(|r| match r {
+                PairingResult::Paired(p) => {
+                    let mut pair = vec![p.white_player, p.black_player];
+                    pair.sort();
+                    pair
+                }
+                PairingResult::Bye(id) => vec![*id],
+            })
+            .collect();
+        pairs.sort();
+        let mut expected = vec![vec![ids[0], ids[2]], vec![ids[1], ids[3]]];
+        for pair in &mut expected {
+            pair.sort();
+        }
+        expected.sort();
+        assert_eq!(pairs, expected);
+        assert!(projected.round_pairings(2).is_empty());
+
+        // Only players paired this round can be given a result
+        assert_eq!(
+            tournament.with_projected_results(&[(ids[4], GameResult::Win)]).err(),
+            Some(ArbiterError::UnknownPlayer(ids[4]))
+        );
+    }
+
+    #[test]
+    fn test_withdrawn_players_miss_rounds_until_they_reenter() {
+        let players = create_test_players().into_iter().take(4).collect();
+        let mut tournament = TournamentState::new(players, 5);
+        let config = SwissConfig::default();
+        let pairer = SwissPairer::new(config.clone());
+        let draws = |tournament: &TournamentState| -> Vec<(Uuid, GameResult)> {
+            let round = tournament.current_round;
+            tournament
+                .round_pairings(round)
+                .iter()
+                .flat_map(|p| [(p.white_player, GameResult::Draw), (p.black_player, GameResult::Draw)])
+                .collect()
+        };
+
+        tournament.pair_remaining(&pairer).unwrap();
+        let alice = tournament.round_pairings(1)[0].white_player;
+        tournament.request_bye(alice, 3).unwrap();
+
+        // Her round 1 game stands; she is out from round 2 and her bye is dropped
+        let change = tournament.withdraw(alice).unwrap();
+        assert_eq!(change, EntryChange { round: 2, player: alice, kind: EntryChangeKind::Withdrawn });
+        assert_eq!(tournament.withdraw(alice), Err(ArbiterError::InactivePlayer(alice)));
+        assert!(!tournament.has_requested_bye(3, alice));
+        assert_eq!(tournament.request_bye(alice, 4), Err(ArbiterError::InactivePlayer(alice)));
+
+        tournament.apply_round_results(draws(&tournament));
+        let results = tournament.pair_remaining(&pairer).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(tournament.pairing_of(2, alice).is_none() && tournament.players[&alice].bye_in_round(2).is_none());
+        tournament.apply_round_results(draws(&tournament));
+
+        let change = tournament.reenter(alice, &config).unwrap();
+        assert_eq!(change, EntryChange { round: 3, player: alice, kind: EntryChangeKind::Reentered });
+        assert_eq!(tournament.reenter(alice, &config), Err(ArbiterError::ActivePlayer(alice)));
+        assert_eq!(tournament.round_history(alice)[1].outcome, RoundOutcome::Absent { points: 0.0 });
+        assert_eq!(tournament.players[&alice].score, 0.5);
+
+        tournament.pair_remaining(&pairer).unwrap();
+        assert!(tournament.pairing_of(3, alice).is_some());
+        assert_eq!(tournament.entry_changes.len(), 2);
+    }
+
+    #[test]
+    fn test_reentry_policy_scores_missed_rounds() {
+        let players = create_test_players();
+        let alice = players[0].id;
+        let mut tournament = TournamentState::new(players, 5);
+        tournament.withdraw(alice).unwrap();
+        tournament.apply_round_results(Vec::new());
+        tournament.apply_round_results(Vec::new());
+
+        let forbidden = SwissConfig { reentry: ReentryPolicy::Forbidden, ..SwissConfig::default() };
+        assert_eq!(tournament.clone().reenter(alice, &forbidden), Err(ArbiterError::ReentryForbidden));
+
+        let byes = SwissConfig { reentry: ReentryPolicy::ByeForMissed, ..SwissConfig::default() };
+        tournament.reenter(alice, &byes).unwrap();
+        let history: Vec<&RoundOutcome> = tournament.round_history(alice).iter().map(|r| &r.outcome).collect();
+        assert_eq!(history, vec![&RoundOutcome::Absent { points: 0.5 }, &RoundOutcome::Absent { points: 0.5 }]);
+        assert_eq!(tournament.players[&alice].score, 1.0);
+        assert!(tournament.withdrawn_players().is_empty());
+    }
+
+    #[test]
+    fn test_first_round_pairs_top_half_against_bottom_half() {
+        let mut players = create_test_players();
+        players.push(Player::new(Uuid::new_v4(), "Frank".to_string(), 1500));
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let tournament = TournamentState::new(players, 5);
+        let pair = |config: SwissConfig| {
+            let paired = SwissPairer::new(config).pair_round(&tournament).unwrap();
+            paired.pairings.iter().map(|p| (p.white_player, p.black_player)).collect::<Vec<_>>()
+        };
+
+        // By rating, alternating colors from White on board 1
+        assert_eq!(pair(SwissConfig::default()), vec![(ids[0], ids[3]), (ids[4], ids[1]), (ids[2], ids[5])]);
+
+        let manual = SwissConfig {
+            seeding: SeedingOrder::Manual(vec![ids[5], ids[4]]),
+            initial_color: InitialColor::Black,
+            ..SwissConfig::default()
+        };
+        assert_eq!(pair(manual), vec![(ids[1], ids[5]), (ids[4], ids[2]), (ids[3], ids[0])]);
+
+        // A drawn seeding is the same for the same seed
+        let random = SwissConfig {
+            seeding: SeedingOrder::Random,
+            initial_color: InitialColor::Random,
+            deterministic_seed: Some(7),
+            ..SwissConfig::default()
+        };
+        assert_eq!(pair(random.clone()), pair(random));
+    }
+
+    #[test]
+    fn test_first_round_seeding_gives_the_bye_to_the_last_seed() {
+        let players = create_test_players();
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        let tournament = TournamentState::new(players, 5);
+        let config = SwissConfig {
+            seeding: SeedingOrder::Manual(vec![ids[4], ids[3], ids[2], ids[1], ids[0]]),
+            ..SwissConfig::default()
+        };
+
+        let paired = SwissPairer::new(config).pair_round(&tournament).unwrap();
+        assert_eq!(paired.bye, Some(ids[0]));
+        let pairs: Vec<(Uuid, Uuid)> = paired.pairings.iter().map(|p| (p.white_player, p.black_player)).collect();
+        assert_eq!(pairs, vec![(ids[4], ids[2]), (ids[1], ids[3])]);
+    }
+
+    #[test]
+    fn test_odd_score_group_floats_the_player_floated_least() {
+        let mut players = create_test_players();
+        players.truncate(4);
+        let ids: Vec<Uuid> = players.iter().map(|p| p.id).collect();
+        for player in players.iter_mut().take(3) {
+            player.score = 1.0;
+        }
+        // Charlie, the lowest ranked of the three, floated down before
+        players[2].float_score = 1;
+        let mut tournament = TournamentState::new(players, 5);
+        tournament.current_round = 3;
+        tournament.completed_rounds = 2;
+
+        let paired = SwissPairer::new(SwissConfig::default()).pair_round(&tournament).unwrap();
+        let opponent_of = |id: Uuid| {
+            paired
+                .pairings
+                .iter()
+                .find_map(|p| match (p.white_player == id, p.black_player == id) {
+                    (true, _) => Some(p.black_player),
+                    (_, true) => Some(p.white_player),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(opponent_of(ids[1]), ids[3]);
+        assert_eq!(opponent_of(ids[0]), ids[2]);
+        assert!(paired.audit.decisions.iter().any(|d| matches!(
+            d,
+            PairingDecision::FloaterChosen { player, float_score: 0, .. } if *player == ids[1]
+        )));
+        assert!(paired.floats.contains(&(ids[1], FloatDirection::Down)));
+    }
+
+    mod properties {
+        use super::super::super::*;
+        use crate::testing::{build_tournament, pairing_cases, round_violations, swiss_configs};
+        use proptest::prelude::*;
+
+        fn is_allowed_failure(error: &PairingError) -> bool {
+            matches!(error, PairingError::CannotPairRemainingPlayers | PairingError::NoValidByeCandidate)
+        }
+
+        /// Higher rated players win, equal ratings draw.
+        fn rating_results(tournament: &TournamentState, paired: &PairedRound) -> Vec<(Uuid, GameResult)> {
+            paired
+                .pairings
+                .iter()
+                .flat_map(|p| {
+                    let (white, black) = (&tournament.players[&p.white_player], &tournament.players[&p.black_player]);
+                    let result = match white.rating.cmp(&black.rating) {
+                        std::cmp::Ordering::Greater => GameResult::Win,
+                        std::cmp::Ordering::Equal => GameResult::Draw,
+                        std::cmp::Ordering::Less => GameResult::Loss,
+                    };
+                    [(white.id, result), (black.id, result.reversed())]
+                })
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn test_paired_rounds_keep_the_invariants((tournament, config) in pairing_cases(1..=40, 1..=9)) {
+                let pairer = SwissPairer::new(config);
+                match pairer.pair_round(&tournament) {
+                    Ok(paired) => {
+                        prop_assert_eq!(round_violations(&tournament, &paired), Vec::<String>::new());
+                        let mut committed = tournament.clone();
+                        prop_assert!(pairer.commit_round(&mut committed, &paired).is_ok());
+                    }
+                    Err(error) => prop_assert!(is_allowed_failure(&error), "unexpected {:?}", error),
+                }
+            }
+
+            #[test]
+            fn test_first_round_always_pairs(
+                ratings in prop::collection::vec(1_000..2_800i32, 1..=60),
+                config in (1..=11u32).prop_flat_map(swiss_configs),
+            ) {
+                let tournament = build_tournament(config.total_rounds, &ratings, &[], &[]);
+                let paired = SwissPairer::new(config).pair_round(&tournament);
+                prop_assert!(paired.is_ok(), "{:?}", paired.err());
+                prop_assert_eq!(round_violations(&tournament, &paired.unwrap()), Vec::<String>::new());
+            }
+
+            #[test]
+            fn test_remaining_rounds_keep_the_invariants((mut tournament, config) in pairing_cases(2..=24, 3..=9)) {
+                let pairer = SwissPairer::new(config);
+                while tournament.current_round <= tournament.total_rounds {
+                    let paired = match pairer.pair_round(&tournament) {
+                        Ok(paired) => paired,
+                        Err(error) => {
+                            prop_assert!(is_allowed_failure(&error), "unexpected {:?}", error);
+                            break;
+                        }
+                    };
+                    prop_assert_eq!(round_violations(&tournament, &paired), Vec::<String>::new());
+                    pairer.commit_round(&mut tournament, &paired).unwrap();
+                    let results = rating_results(&tournament, &paired);
+                    tournament.apply_round_results(results);
+                }
+            }
+        }
+    }
 }
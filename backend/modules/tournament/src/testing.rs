@@ -0,0 +1,213 @@
+//! Property-based test support for the Swiss pairer: proptest strategies
+//! for tournaments part way through, and the rules any paired round must
+//! keep. Built for this crate's tests, and for other crates' with the
+//! `test-support` feature.
+//!
+//! Histories are played with random orders, colors and results rather than
+//! by the pairer, so they reach score groups, color runs and opponent graphs
+//! the greedy pairer would not have produced itself.
+
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use uuid::Uuid;
+
+use crate::swiss::{
+    BakuAcceleration, ByeKind, ByeRequest, Color, GameResult, PairedRound, Pairing, Player, SwissConfig,
+    TournamentState,
+};
+
+/// The random choices behind one round of a generated history.
+#[derive(Debug, Clone)]
+pub struct RoundScript {
+    /// Sort key of each player; players are paired in this order
+    pub order: Vec<u32>,
+    /// Result for each player when they have white
+    pub results: Vec<GameResult>,
+    /// Whether each player takes white when paired first
+    pub white_first: Vec<bool>,
+}
+
+fn game_results() -> impl Strategy<Value = GameResult> {
+    prop_oneof![Just(GameResult::Win), Just(GameResult::Draw), Just(GameResult::Loss)]
+}
+
+fn round_scripts(players: usize) -> impl Strategy<Value = RoundScript> {
+    (
+        prop::collection::vec(any::<u32>(), players),
+        prop::collection::vec(game_results(), players),
+        prop::collection::vec(any::<bool>(), players),
+    )
+        .prop_map(|(order, results, white_first)| RoundScript { order, results, white_first })
+}
+
+/// Tournaments of `players` entrants and `total_rounds` rounds with some
+/// rounds played and at least one left to pair. A few players have asked
+/// for a bye in the round to pair.
+pub fn tournament_states(
+    players: RangeInclusive<usize>,
+    total_rounds: RangeInclusive<u32>,
+) -> impl Strategy<Value = TournamentState> {
+    (players, total_rounds).prop_flat_map(|(count, total_rounds)| {
+        (
+            prop::collection::vec(1_000..2_800i32, count),
+            prop::collection::vec(round_scripts(count), 0..total_rounds as usize),
+            prop::collection::vec(prop::bool::weighted(0.05), count),
+        )
+            .prop_map(move |(ratings, rounds, bye_requests)| {
+                build_tournament(total_rounds, &ratings, &rounds, &bye_requests)
+            })
+    })
+}
+
+/// Pairer settings worth varying: the tie-break seed and acceleration.
+pub fn swiss_configs(total_rounds: u32) -> impl Strategy<Value = SwissConfig> {
+    (any::<Option<u64>>(), any::<bool>()).prop_map(move |(deterministic_seed, accelerated)| SwissConfig {
+        total_rounds,
+        deterministic_seed,
+        acceleration: accelerated.then(|| BakuAcceleration::standard(total_rounds)),
+        ..SwissConfig::default()
+    })
+}
+
+/// A tournament from `tournament_states` with settings for its pairer.
+pub fn pairing_cases(
+    players: RangeInclusive<usize>,
+    total_rounds: RangeInclusive<u32>,
+) -> impl Strategy<Value = (TournamentState, SwissConfig)> {
+    tournament_states(players, total_rounds).prop_flat_map(|tournament| {
+        let total_rounds = tournament.total_rounds;
+        (Just(tournament), swiss_configs(total_rounds))
+    })
+}
+
+/// Players with ids 1, 2, ... and the given ratings, with `rounds` played.
+pub fn build_tournament(
+    total_rounds: u32,
+    ratings: &[i32],
+    rounds: &[RoundScript],
+    bye_requests: &[bool],
+) -> TournamentState {
+    let ids: Vec<Uuid> = (1..=ratings.len() as u128).map(Uuid::from_u128).collect();
+    let players = ids
+        .iter()
+        .zip(ratings)
+        .enumerate()
+        .map(|(i, (&id, &rating))| Player::new(id, format!("Player {}", i + 1), rating))
+        .collect();
+    let mut tournament = TournamentState::new(players, total_rounds);
+    for script in rounds {
+        play_round(&mut tournament, &ids, script);
+    }
+
+    let round = tournament.current_round;
+    tournament.requested_byes = ids
+        .iter()
+        .zip(bye_requests)
+        .filter(|(_, &asked)| asked)
+        .map(|(&player, _)| ByeRequest { round, player })
+        .collect();
+    tournament
+}
+
+/// Pair players in script order, each with the next one they have not met,
+/// and score the games. Whoever is left over gets the round's one allocated
+/// bye if they have not had one, and otherwise sits the round out.
+fn play_round(tournament: &mut TournamentState, ids: &[Uuid], script: &RoundScript) {
+    let round = tournament.current_round;
+    let mut waiting: Vec<usize> = (0..ids.len()).collect();
+    waiting.sort_by_key(|&i| script.order[i]);
+
+    let mut results = Vec::new();
+    let mut bye_given = false;
+    while !waiting.is_empty() {
+        let first = waiting.remove(0);
+        let opponent = waiting
+            .iter()
+            .position(|&j| !tournament.players[&ids[first]].has_played_against(&ids[j]));
+        match opponent {
+            Some(position) => {
+                let second = waiting.remove(position);
+                let (white, black) = if script.white_first[first] { (first, second) } else { (second, first) };
+                tournament.pairings.push(Pairing { white_player: ids[white], black_player: ids[black], round });
+                results.push((ids[white], script.results[white]));
+                results.push((ids[black], script.results[white].reversed()));
+            }
+            None => {
+                let player = tournament.players.get_mut(&ids[first]).expect("generated player");
+                if !bye_given && !player.has_had_bye() {
+                    player.add_bye(round, 1.0, ByeKind::Allocated);
+                    bye_given = true;
+                } else {
+                    player.add_bye(round, 0.0, ByeKind::Requested);
+                }
+            }
+        }
+    }
+    tournament.apply_round_results(results);
+}
+
+/// The rules `paired`, a round worked out for `tournament`, breaks; empty
+/// when it keeps them all:
+///
+/// - every player is scheduled exactly once, in a game or on a bye
+/// - nobody meets an opponent they have already played
+/// - at most one bye is allocated, only to an odd player out who has not
+///   had one, and requested byes go only to players who asked
+/// - before the final round, players who must have a color get it
+pub fn round_violations(tournament: &TournamentState, paired: &PairedRound) -> Vec<String> {
+    let mut violations = Vec::new();
+    let round = paired.round;
+
+    let mut scheduled: HashMap<Uuid, usize> = HashMap::new();
+    for id in paired.players() {
+        *scheduled.entry(id).or_insert(0) += 1;
+    }
+    for (id, times) in &scheduled {
+        if !tournament.players.contains_key(id) {
+            violations.push(format!("unknown player {} is scheduled", id));
+        } else if *times > 1 {
+            violations.push(format!("player {} is scheduled {} times", id, times));
+        }
+    }
+    for id in tournament.players.keys() {
+        if !scheduled.contains_key(id) {
+            violations.push(format!("player {} is left out", id));
+        }
+    }
+
+    for pairing in &paired.pairings {
+        let (Some(white), Some(black)) =
+            (tournament.players.get(&pairing.white_player), tournament.players.get(&pairing.black_player))
+        else {
+            continue;
+        };
+        if pairing.round != round {
+            violations.push(format!("{} v {} is paired for round {}, not {}", white.id, black.id, pairing.round, round));
+        }
+        if white.id == black.id || white.has_played_against(&black.id) {
+            violations.push(format!("{} and {} meet again", white.id, black.id));
+        }
+        if round < tournament.total_rounds {
+            for (player, color) in [(white, Color::White), (black, Color::Black)] {
+                if player.absolute_color().is_some_and(|required| required != color) {
+                    violations.push(format!("{} must not have {:?} (color balance {})", player.id, color, player.get_color_balance()));
+                }
+            }
+        }
+    }
+
+    let pool = tournament.players.len() - paired.requested_byes.len();
+    match paired.bye.and_then(|id| tournament.players.get(&id)) {
+        Some(_) if pool.is_multiple_of(2) => violations.push("a bye is allocated with an even number to pair".to_string()),
+        Some(player) if player.has_had_bye() => violations.push(format!("{} is allocated a second bye", player.id)),
+        None if !pool.is_multiple_of(2) => violations.push("nobody is allocated the odd bye".to_string()),
+        _ => {}
+    }
+    for id in &paired.requested_byes {
+        if !tournament.requested_byes.iter().any(|r| r.round == round && r.player == *id) {
+            violations.push(format!("{} is given a bye they did not ask for", id));
+        }
+    }
+    violations
+}
@@ -0,0 +1,528 @@
+//! FIDE Tournament Report File (TRF16) export, and reading a report back to
+//! check that its round data agrees with itself before it is submitted.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::swiss::{ByeKind, Color, GameResult, RoundOutcome, TournamentState};
+
+/// First column of the round data on a player line; each round takes ten.
+const ROUNDS_FROM: usize = 91;
+const ROUND_WIDTH: usize = 10;
+
+/// Tournament details for the report header the state doesn't hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrfHeader {
+    pub name: String,
+    pub city: Option<String>,
+    /// Three letter FIDE code of the organizing federation
+    pub federation: Option<String>,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+    pub chief_arbiter: Option<String>,
+    pub time_control: Option<String>,
+    /// Date each round was played, in order
+    pub round_dates: Vec<NaiveDate>,
+}
+
+/// Rating-body details of a player, kept on the profile rather than in the
+/// tournament.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FideDetails {
+    /// Three letter FIDE federation code
+    pub federation: Option<String>,
+    pub fide_id: Option<u64>,
+    pub fide_rating: Option<i32>,
+    /// GM, IM, FM, WGM and so on
+    pub title: Option<String>,
+    pub birth_date: Option<NaiveDate>,
+}
+
+/// Result codes of the round data, as in the TRF16 specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrfResult {
+    Win,
+    Draw,
+    Loss,
+    ForfeitWin,
+    ForfeitLoss,
+    /// Requested bye worth a full point
+    FullBye,
+    HalfBye,
+    ZeroBye,
+    /// Bye given by the pairer
+    AllocatedBye,
+    /// Paired but not played yet
+    Pending,
+}
+
+impl TrfResult {
+    fn code(self) -> char {
+        match self {
+            TrfResult::Win => '1',
+            TrfResult::Draw => '=',
+            TrfResult::Loss => '0',
+            TrfResult::ForfeitWin => '+',
+            TrfResult::ForfeitLoss => '-',
+            TrfResult::FullBye => 'F',
+            TrfResult::HalfBye => 'H',
+            TrfResult::ZeroBye => 'Z',
+            TrfResult::AllocatedBye => 'U',
+            TrfResult::Pending => ' ',
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        Some(match code {
+            '1' | 'W' => TrfResult::Win,
+            '=' | 'D' => TrfResult::Draw,
+            '0' | 'L' => TrfResult::Loss,
+            '+' => TrfResult::ForfeitWin,
+            '-' => TrfResult::ForfeitLoss,
+            'F' => TrfResult::FullBye,
+            'H' => TrfResult::HalfBye,
+            'Z' => TrfResult::ZeroBye,
+            'U' => TrfResult::AllocatedBye,
+            ' ' => TrfResult::Pending,
+            _ => return None,
+        })
+    }
+
+    pub fn points(self) -> f32 {
+        match self {
+            TrfResult::Win | TrfResult::ForfeitWin | TrfResult::FullBye | TrfResult::AllocatedBye => 1.0,
+            TrfResult::Draw | TrfResult::HalfBye => 0.5,
+            TrfResult::Loss | TrfResult::ForfeitLoss | TrfResult::ZeroBye | TrfResult::Pending => 0.0,
+        }
+    }
+
+    /// What the opponent's entry for the same game must say.
+    fn opposite(self) -> Option<Self> {
+        match self {
+            TrfResult::Win => Some(TrfResult::Loss),
+            TrfResult::Loss => Some(TrfResult::Win),
+            TrfResult::Draw => Some(TrfResult::Draw),
+            TrfResult::ForfeitWin => Some(TrfResult::ForfeitLoss),
+            TrfResult::ForfeitLoss => Some(TrfResult::ForfeitWin),
+            TrfResult::Pending => Some(TrfResult::Pending),
+            _ => None,
+        }
+    }
+}
+
+/// One round of a player line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrfRound {
+    /// Starting rank of the opponent; `None` for a bye or an unpaired round
+    pub opponent: Option<u32>,
+    pub color: Option<Color>,
+    pub result: TrfResult,
+}
+
+/// A player line of a report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrfPlayer {
+    pub starting_rank: u32,
+    pub name: String,
+    pub rating: Option<i32>,
+    pub federation: Option<String>,
+    pub fide_id: Option<u64>,
+    pub points: f32,
+    pub rank: Option<u32>,
+    pub rounds: Vec<TrfRound>,
+}
+
+/// A report read back from TRF16 text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrfReport {
+    pub name: Option<String>,
+    pub players: Vec<TrfPlayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrfError {
+    /// A player line that doesn't follow the fixed column layout
+    MalformedLine { line: usize, reason: String },
+}
+
+impl std::fmt::Display for TrfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrfError::MalformedLine { line, reason } => write!(f, "Line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for TrfError {}
+
+/// A disagreement within a report's round data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TrfIssue {
+    DuplicateStartingRank { starting_rank: u32 },
+    UnknownOpponent { player: u32, round: u32, opponent: u32 },
+    /// The opponent's entry for the round names someone else, the same
+    /// color or a result that doesn't match
+    Mismatch { player: u32, round: u32, opponent: u32 },
+    PointsMismatch { player: u32, declared: f32, counted: f32 },
+}
+
+impl std::fmt::Display for TrfIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrfIssue::DuplicateStartingRank { starting_rank } => {
+                write!(f, "Starting rank {} is used by more than one player", starting_rank)
+            }
+            TrfIssue::UnknownOpponent { player, round, opponent } => {
+                write!(f, "Player {} meets unknown player {} in round {}", player, opponent, round)
+            }
+            TrfIssue::Mismatch { player, round, opponent } => write!(
+                f,
+                "Players {} and {} disagree about their round {} game",
+                player, opponent, round
+            ),
+            TrfIssue::PointsMismatch { player, declared, counted } => write!(
+                f,
+                "Player {} declares {} points but the rounds add up to {}",
+                player, declared, counted
+            ),
+        }
+    }
+}
+
+impl TournamentState {
+    /// The tournament as a TRF16 report. Starting ranks follow rating, then
+    /// name; rounds run up to the last one anyone has a record for.
+    pub fn to_trf(&self, header: &TrfHeader, details: &HashMap<Uuid, FideDetails>) -> String {
+        let mut players: Vec<_> = self.players.values().collect();
+        players.sort_by(|a, b| b.rating.cmp(&a.rating).then_with(|| a.name.cmp(&b.name)).then(a.id.cmp(&b.id)));
+        let starting_rank: HashMap<Uuid, u32> =
+            players.iter().enumerate().map(|(i, p)| (p.id, i as u32 + 1)).collect();
+        let rank: HashMap<Uuid, u32> = self.standings().into_iter().map(|s| (s.player, s.rank)).collect();
+        let rounds = players
+            .iter()
+            .filter_map(|p| p.history.last().map(|r| r.round))
+            .max()
+            .unwrap_or(0)
+            .max(self.completed_rounds);
+        let no_details = FideDetails::default();
+        let detail = |id: &Uuid| details.get(id).unwrap_or(&no_details);
+
+        let mut lines = vec![format!("012 {}", header.name)];
+        let optional = [
+            ("022", header.city.clone()),
+            ("032", header.federation.clone()),
+            ("042", header.start.map(trf_date)),
+            ("052", header.end.map(trf_date)),
+        ];
+        lines.extend(optional.into_iter().filter_map(|(code, value)| value.map(|v| format!("{} {}", code, v))));
+        lines.push(format!("062 {}", players.len()));
+        lines.push(format!(
+            "072 {}",
+            players.iter().filter(|p| detail(&p.id).fide_rating.is_some()).count()
+        ));
+        lines.push("092 Individual: Swiss-System".to_string());
+        if let Some(arbiter) = &header.chief_arbiter {
+            lines.push(format!("102 {}", arbiter));
+        }
+        if let Some(time_control) = &header.time_control {
+            lines.push(format!("122 {}", time_control));
+        }
+        if !header.round_dates.is_empty() {
+            let dates: String = header
+                .round_dates
+                .iter()
+                .map(|d| format!("{:<width$}", d.format("%y/%m/%d"), width = ROUND_WIDTH))
+                .collect();
+            lines.push(format!("{:<width$}{}", "132", dates.trim_end(), width = ROUNDS_FROM).trim_end().to_string());
+        }
+
+        for player in &players {
+            let fide = detail(&player.id);
+            let mut line = format!(
+                "001 {:>4}  {:>3} {:<33} {:>4} {:<3} {:>11} {:<10} {:>4.1} {:>4}",
+                starting_rank[&player.id],
+                fide.title.as_deref().unwrap_or(""),
+                player.name.chars().take(33).collect::<String>(),
+                fide.fide_rating.map(|r| r.to_string()).unwrap_or_default(),
+                fide.federation.as_deref().unwrap_or(""),
+                fide.fide_id.map(|id| id.to_string()).unwrap_or_default(),
+                fide.birth_date.map(trf_date).unwrap_or_default(),
+                player.score,
+                rank.get(&player.id).copied().unwrap_or(0),
+            );
+            for round in 1..=rounds {
+                let entry = match player.round_record(round).map(|r| &r.outcome) {
+                    Some(RoundOutcome::Game { opponent, color, result }) => TrfRound {
+                        opponent: starting_rank.get(opponent).copied(),
+                        color: *color,
+                        result: match result {
+                            Some(GameResult::Win) => TrfResult::Win,
+                            Some(GameResult::Draw) => TrfResult::Draw,
+                            Some(GameResult::Loss) => TrfResult::Loss,
+                            None => TrfResult::Pending,
+                        },
+                    },
+                    Some(RoundOutcome::Forfeit { opponent, color, result }) => TrfRound {
+                        opponent: starting_rank.get(opponent).copied(),
+                        color: *color,
+                        result: if *result == GameResult::Win { TrfResult::ForfeitWin } else { TrfResult::ForfeitLoss },
+                    },
+                    Some(RoundOutcome::Bye { kind, points }) => TrfRound {
+                        opponent: None,
+                        color: None,
+                        result: match (kind, points) {
+                            (_, p) if *p <= 0.0 => TrfResult::ZeroBye,
+                            (_, p) if *p < 1.0 => TrfResult::HalfBye,
+                            (ByeKind::Allocated, _) => TrfResult::AllocatedBye,
+                            (ByeKind::Requested, _) => TrfResult::FullBye,
+                        },
+                    },
+                    Some(RoundOutcome::Absent { points }) => TrfRound {
+                        opponent: None,
+                        color: None,
+                        result: match points {
+                            p if *p <= 0.0 => TrfResult::ZeroBye,
+                            p if *p < 1.0 => TrfResult::HalfBye,
+                            _ => TrfResult::FullBye,
+                        },
+                    },
+                    // Not paired, e.g. after withdrawing
+                    None => TrfRound { opponent: None, color: None, result: TrfResult::ZeroBye },
+                };
+                line.push_str(&format!(
+                    "  {:>4} {} {}",
+                    entry.opponent.map_or("0000".to_string(), |o| o.to_string()),
+                    match entry.color {
+                        Some(Color::White) => 'w',
+                        Some(Color::Black) => 'b',
+                        None => '-',
+                    },
+                    entry.result.code()
+                ));
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+fn trf_date(date: NaiveDate) -> String {
+    date.format("%Y/%m/%d").to_string()
+}
+
+/// Read a TRF16 report. Only the tournament name and the player lines are
+/// kept; other records are skipped.
+pub fn parse(text: &str) -> Result<TrfReport, TrfError> {
+    let mut report = TrfReport { name: None, players: Vec::new() };
+
+    for (index, line) in text.lines().enumerate() {
+        let columns: Vec<char> = line.chars().collect();
+        let field = |from: usize, to: usize| -> String {
+            columns.get(from - 1..to.min(columns.len())).map_or(String::new(), |c| c.iter().collect::<String>().trim().to_string())
+        };
+        let malformed = |reason: String| TrfError::MalformedLine { line: index + 1, reason };
+
+        match line.get(..3) {
+            Some("012") => report.name = Some(line[3..].trim().to_string()),
+            Some("001") => {
+                if columns.len() < 89 {
+                    return Err(malformed("player line is shorter than 89 columns".to_string()));
+                }
+                let number = |from: usize, to: usize, what: &str| -> Result<Option<u64>, TrfError> {
+                    let value = field(from, to);
+                    if value.is_empty() {
+                        return Ok(None);
+                    }
+                    value.parse().map(Some).map_err(|_| malformed(format!("{} '{}' is not a number", what, value)))
+                };
+
+                let starting_rank = number(5, 8, "starting rank")?
+                    .ok_or_else(|| malformed("starting rank is missing".to_string()))? as u32;
+                let points_field = field(81, 84);
+                let points = points_field
+                    .parse()
+                    .map_err(|_| malformed(format!("points '{}' are not a number", points_field)))?;
+
+                let mut rounds = Vec::new();
+                let mut start = ROUNDS_FROM;
+                while start < columns.len() {
+                    let round = rounds.len() + 1;
+                    let opponent = number(start + 1, start + 4, "opponent")?.filter(|&o| o != 0).map(|o| o as u32);
+                    let color = match columns.get(start + 5) {
+                        Some('w') => Some(Color::White),
+                        Some('b') => Some(Color::Black),
+                        Some('-') | Some(' ') | None => None,
+                        Some(other) => return Err(malformed(format!("round {} color '{}' is unknown", round, other))),
+                    };
+                    let code = columns.get(start + 7).copied().unwrap_or(' ');
+                    let result = TrfResult::from_code(code)
+                        .ok_or_else(|| malformed(format!("round {} result '{}' is unknown", round, code)))?;
+                    rounds.push(TrfRound { opponent, color, result });
+                    start += ROUND_WIDTH;
+                }
+
+                report.players.push(TrfPlayer {
+                    starting_rank,
+                    name: field(15, 47),
+                    rating: number(49, 52, "rating")?.map(|r| r as i32),
+                    federation: Some(field(54, 56)).filter(|f| !f.is_empty()),
+                    fide_id: number(58, 68, "FIDE id")?,
+                    points,
+                    rank: number(86, 89, "rank")?.map(|r| r as u32).filter(|&r| r != 0),
+                    rounds,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(report)
+}
+
+impl TrfReport {
+    /// Check that every game appears the same way on both players' lines and
+    /// that each player's points add up. Empty when the report is consistent.
+    pub fn validate(&self) -> Vec<TrfIssue> {
+        let mut issues = Vec::new();
+        let mut by_rank: HashMap<u32, &TrfPlayer> = HashMap::new();
+        for player in &self.players {
+            if by_rank.insert(player.starting_rank, player).is_some() {
+                issues.push(TrfIssue::DuplicateStartingRank { starting_rank: player.starting_rank });
+            }
+        }
+
+        for player in &self.players {
+            for (index, entry) in player.rounds.iter().enumerate() {
+                let round = index as u32 + 1;
+                let Some(opponent) = entry.opponent else { continue };
+                let Some(other) = by_rank.get(&opponent) else {
+                    issues.push(TrfIssue::UnknownOpponent { player: player.starting_rank, round, opponent });
+                    continue;
+                };
+                let theirs = other.rounds.get(index);
+                let points_back = theirs.is_some_and(|t| t.opponent == Some(player.starting_rank));
+                let agrees = points_back
+                    && theirs.is_some_and(|t| {
+                        entry.result.opposite() == Some(t.result)
+                            && match (entry.color, t.color) {
+                                (Some(ours), Some(theirs)) => ours != theirs,
+                                _ => true,
+                            }
+                    });
+                // A game both lines name is reported once, from the lower starting rank
+                if !agrees && (!points_back || player.starting_rank < opponent) {
+                    issues.push(TrfIssue::Mismatch { player: player.starting_rank, round, opponent });
+                }
+            }
+
+            let counted: f32 = player.rounds.iter().map(|r| r.result.points()).sum();
+            if (counted - player.points).abs() > 0.01 {
+                issues.push(TrfIssue::PointsMismatch { player: player.starting_rank, declared: player.points, counted });
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swiss::{ByeKind, Player, SwissConfig, SwissPairer};
+
+    fn played_tournament() -> TournamentState {
+        let players = ["Alice", "Bob", "Charlie", "Diana", "Eve"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Player::new(Uuid::new_v4(), name.to_string(), 2000 - 100 * i as i32))
+            .collect();
+        let mut tournament = TournamentState::new(players, 3);
+        let pairer = SwissPairer::new(SwissConfig::default());
+
+        for _ in 0..2 {
+            let paired = pairer.pair_round(&tournament).unwrap();
+            pairer.commit_round(&mut tournament, &paired).unwrap();
+            let mut results = Vec::new();
+            for pairing in &paired.pairings {
+                results.push((pairing.white_player, GameResult::Draw));
+                results.push((pairing.black_player, GameResult::Draw));
+            }
+            tournament.apply_round_results(results);
+        }
+        tournament
+    }
+
+    #[test]
+    fn export_reads_back_consistent() {
+        let tournament = played_tournament();
+        let alice = tournament.players.values().find(|p| p.name == "Alice").unwrap().id;
+        let details = HashMap::from([(
+            alice,
+            FideDetails {
+                federation: Some("NOR".to_string()),
+                fide_id: Some(1_503_014),
+                fide_rating: Some(2830),
+                title: Some("GM".to_string()),
+                birth_date: NaiveDate::from_ymd_opt(1990, 11, 30),
+            },
+        )]);
+        let header = TrfHeader {
+            name: "StarkMate Open".to_string(),
+            round_dates: vec![NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(); 3],
+            ..TrfHeader::default()
+        };
+
+        let text = tournament.to_trf(&header, &details);
+        let alice_line = text.lines().find(|l| l.contains("Alice")).unwrap();
+        assert!(alice_line.starts_with("001    1   GM Alice"));
+        assert_eq!(&alice_line[48..52], "2830");
+        assert_eq!(&alice_line[53..56], "NOR");
+        assert_eq!(alice_line[57..68].trim(), "1503014");
+        assert_eq!(&alice_line[69..79], "1990/11/30");
+        assert!(text.contains("072 1\n"));
+
+        let report = parse(&text).unwrap();
+        assert_eq!(report.name.as_deref(), Some("StarkMate Open"));
+        assert_eq!(report.players.len(), 5);
+        assert!(report.players.iter().all(|p| p.rounds.len() == 2));
+        assert_eq!(report.validate(), vec![]);
+
+        // The bye of round one is the pairer's, worth a full point
+        let eve = report.players.iter().find(|p| p.name == "Eve").unwrap();
+        assert_eq!(eve.rounds[0], TrfRound { opponent: None, color: None, result: TrfResult::AllocatedBye });
+    }
+
+    #[test]
+    fn validate_finds_disagreeing_rounds() {
+        let mut tournament = played_tournament();
+        let bob = tournament.players.values().find(|p| p.name == "Bob").unwrap().id;
+        tournament.players.get_mut(&bob).unwrap().add_bye(3, 0.5, ByeKind::Requested);
+        let mut report = parse(&tournament.to_trf(&TrfHeader::default(), &HashMap::new())).unwrap();
+
+        // Bob's half-point bye is already counted in his declared score
+        assert_eq!(report.validate(), vec![]);
+
+        let player = report.players.iter_mut().find(|p| p.name == "Bob").unwrap();
+        player.points += 1.0;
+        let starting_rank = player.starting_rank;
+        let opponent = player.rounds[1].opponent.unwrap();
+        player.rounds[1].result = TrfResult::Win;
+
+        let issues = report.validate();
+        assert!(issues.contains(&TrfIssue::Mismatch {
+            player: starting_rank.min(opponent),
+            round: 2,
+            opponent: starting_rank.max(opponent),
+        }));
+        assert!(issues.contains(&TrfIssue::PointsMismatch { player: starting_rank, declared: 2.5, counted: 2.0 }));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_player_lines() {
+        assert_eq!(
+            parse("012 Test\n001    1      Short"),
+            Err(TrfError::MalformedLine { line: 2, reason: "player line is shorter than 89 columns".to_string() })
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Replays recorded games against a running socket server with many
+// simulated players at once and reports move latency and broadcast lag.
+//
+//   loadtest [--url ws://127.0.0.1:8080] [--games 100] [--script games.txt]
+//            [--think-ms 250] [--ramp-secs 10] [--timeout-ms 10000]
+//
+// A script holds one game per line in SAN or UCI; without one, a few
+// well-known games are replayed. The server's flood limits apply to the
+// simulated players too, so think times far below 200 ms get moves refused.
+
+use std::env;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use socket::load::{self, LoadConfig, DEFAULT_SCRIPT};
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(LoadConfig, Option<String>), String> {
+    let mut config = LoadConfig::default();
+    let mut script = None;
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        let number = |value: &str| value.parse::<u64>().map_err(|_| format!("{} must be a number", flag));
+        match flag.as_str() {
+            "--url" => config.url = value,
+            "--games" => config.games = number(&value)?.max(1) as usize,
+            "--script" => script = Some(value),
+            "--think-ms" => config.think = Duration::from_millis(number(&value)?),
+            "--ramp-secs" => config.ramp = Duration::from_secs(number(&value)?),
+            "--timeout-ms" => config.timeout = Duration::from_millis(number(&value)?.max(1)),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    Ok((config, script))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let (config, script) = match parse_args(env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let script = match script {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Cannot read {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => DEFAULT_SCRIPT.to_string(),
+    };
+    let games = match load::parse_script(&script) {
+        Ok(games) => games,
+        Err(e) => {
+            eprintln!("Invalid script: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Replaying {} games against {}", config.games, config.url);
+    let report = load::run(&config, &games).await;
+    print!("{}", report);
+    if report.completed == report.games {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
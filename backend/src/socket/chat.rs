@@ -0,0 +1,392 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use uuid::Uuid;
+
+use crate::game::{ServerState, GAME_STATE};
+use crate::latency::now_ms;
+use crate::models::{BoardTag, GameStatus, ServerMessage, SessionPlayerId};
+
+pub const MAX_LINE_CHARS: usize = 500;
+// Lines kept for connections joining the channel late
+const RECENT_LINES: usize = 50;
+const MAX_PINNED: usize = 3;
+pub const DEFAULT_CLOSE_AFTER_MS: u64 = 30 * 60_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct ChatConfig {
+    // Key arbiters sign announcements with; without one nobody may announce
+    pub arbiter_key: Option<String>,
+    // How long a channel stays open once every game of its tournament is over
+    pub close_after_ms: u64,
+}
+
+static CHAT_CONFIG: OnceLock<ChatConfig> = OnceLock::new();
+
+pub fn init_chat_config(config: ChatConfig) {
+    if CHAT_CONFIG.set(config).is_err() {
+        log::warn!("Tournament chat was already configured");
+    }
+}
+
+fn close_after_ms() -> u64 {
+    CHAT_CONFIG.get().map_or(DEFAULT_CLOSE_AFTER_MS, |config| config.close_after_ms)
+}
+
+pub fn is_arbiter_key(key: &str) -> bool {
+    CHAT_CONFIG
+        .get()
+        .and_then(|config| config.arbiter_key.as_deref())
+        .is_some_and(|arbiter_key| arbiter_key == key)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnouncementKind {
+    #[default]
+    Notice,
+    RoundStart { round: u32 },
+}
+
+// Lines and announcements of a channel are numbered together, so a
+// connection can tell when it missed one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChatLine {
+    pub id: u64,
+    pub player_id: SessionPlayerId,
+    pub text: String,
+    pub sent_at_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Announcement {
+    pub id: u64,
+    pub kind: AnnouncementKind,
+    pub text: String,
+    pub pinned: bool,
+    pub sent_at_ms: u64,
+}
+
+// The chat of one tournament, opened with its first board
+pub struct ChatChannel {
+    sender: broadcast::Sender<ServerMessage>,
+    pinned: Vec<Announcement>,
+    recent: VecDeque<ChatLine>,
+    // Rounds whose start was announced
+    rounds: BTreeSet<u32>,
+    last_id: u64,
+    // Last change to the channel or to a game of the tournament
+    last_activity_ms: u64,
+}
+
+impl ChatChannel {
+    fn new(capacity: usize, now_ms: u64) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            pinned: Vec::new(),
+            recent: VecDeque::new(),
+            rounds: BTreeSet::new(),
+            last_id: 0,
+            last_activity_ms: now_ms,
+        }
+    }
+
+    fn snapshot(&self, tournament_id: Uuid) -> ServerMessage {
+        ServerMessage::TournamentChatJoined {
+            tournament_id,
+            last_id: self.last_id,
+            pinned: self.pinned.clone(),
+            recent: self.recent.iter().cloned().collect(),
+        }
+    }
+
+    fn announce(&mut self, tournament_id: Uuid, kind: AnnouncementKind, text: String, pinned: bool, now_ms: u64) -> ServerMessage {
+        self.last_id += 1;
+        let announcement = Announcement { id: self.last_id, kind, text, pinned, sent_at_ms: now_ms };
+        if pinned {
+            // A new round's notice replaces the last one
+            if let AnnouncementKind::RoundStart { .. } = kind {
+                self.pinned.retain(|a| !matches!(a.kind, AnnouncementKind::RoundStart { .. }));
+            }
+            self.pinned.push(announcement.clone());
+            if self.pinned.len() > MAX_PINNED {
+                self.pinned.remove(0);
+            }
+        }
+        self.last_activity_ms = now_ms;
+
+        let message = ServerMessage::TournamentAnnouncement { tournament_id, announcement };
+        // Channels nobody follows have no receivers; that is fine
+        let _ = self.sender.send(message.clone());
+        message
+    }
+}
+
+// Id of the line or announcement a channel message carries
+fn message_id(message: &ServerMessage) -> Option<u64> {
+    match message {
+        ServerMessage::TournamentChat { line, .. } => Some(line.id),
+        ServerMessage::TournamentAnnouncement { announcement, .. } => Some(announcement.id),
+        _ => None,
+    }
+}
+
+// Trimmed text of a line or announcement, refusing what nobody should see
+fn clean(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Chat messages cannot be empty".to_string());
+    }
+    if text.chars().count() > MAX_LINE_CHARS {
+        return Err(format!("Chat messages are limited to {} characters", MAX_LINE_CHARS));
+    }
+    if text.chars().any(|c| c.is_control() && c != '\n') {
+        return Err("Chat messages cannot contain control characters".to_string());
+    }
+    Ok(text.to_string())
+}
+
+// Open the tournament's channel for a board placed in it, announcing the
+// round when it is the round's first board. Called under the state lock.
+pub fn open_round(state: &mut ServerState, tag: BoardTag) {
+    let now = now_ms();
+    let capacity = state.broadcast_capacity;
+    let channel = state.chats.entry(tag.tournament_id).or_insert_with(|| {
+        log::info!("Opened the chat of tournament {}", tag.tournament_id);
+        ChatChannel::new(capacity, now)
+    });
+    channel.last_activity_ms = now;
+    if channel.rounds.insert(tag.round) {
+        let kind = AnnouncementKind::RoundStart { round: tag.round };
+        channel.announce(tag.tournament_id, kind, format!("Round {} has started", tag.round), true, now);
+    }
+}
+
+// Keep the channel of a tournament whose games changed open
+pub fn touch(state: &mut ServerState, tournament_id: Uuid, now_ms: u64) {
+    if let Some(channel) = state.chats.get_mut(&tournament_id) {
+        channel.last_activity_ms = now_ms;
+    }
+}
+
+// The pinned announcements and latest lines of a tournament's channel
+pub fn join_chat(tournament_id: Uuid) -> Result<ServerMessage, String> {
+    let state = GAME_STATE.lock().unwrap();
+    chat_snapshot(&state, tournament_id).ok_or_else(|| "This tournament has no chat open".to_string())
+}
+
+// Same as `join_chat`, for callers holding the state lock
+pub fn chat_snapshot(state: &ServerState, tournament_id: Uuid) -> Option<ServerMessage> {
+    state.chats.get(&tournament_id).map(|channel| channel.snapshot(tournament_id))
+}
+
+pub fn send_line(tournament_id: Uuid, player_id: &SessionPlayerId, text: &str) -> Result<ServerMessage, String> {
+    let text = clean(text)?;
+    let mut state = GAME_STATE.lock().unwrap();
+    let channel = state
+        .chats
+        .get_mut(&tournament_id)
+        .ok_or_else(|| "This tournament has no chat open".to_string())?;
+
+    let now = now_ms();
+    channel.last_id += 1;
+    let line = ChatLine { id: channel.last_id, player_id: player_id.clone(), text, sent_at_ms: now };
+    channel.recent.push_back(line.clone());
+    if channel.recent.len() > RECENT_LINES {
+        channel.recent.pop_front();
+    }
+    channel.last_activity_ms = now;
+
+    let message = ServerMessage::TournamentChat { tournament_id, line };
+    let _ = channel.sender.send(message.clone());
+    Ok(message)
+}
+
+// An arbiter's announcement; callers check the arbiter key
+pub fn announce(tournament_id: Uuid, kind: AnnouncementKind, text: &str, pinned: bool) -> Result<ServerMessage, String> {
+    let text = clean(text)?;
+    let mut state = GAME_STATE.lock().unwrap();
+    let channel = state
+        .chats
+        .get_mut(&tournament_id)
+        .ok_or_else(|| "This tournament has no chat open".to_string())?;
+    log::info!("Arbiter announcement in tournament {}: {}", tournament_id, text);
+    Ok(channel.announce(tournament_id, kind, text, pinned, now_ms()))
+}
+
+// Close the channels of tournaments whose games are all over and have
+// been quiet for `close_after_ms`, telling whoever still follows them
+fn close_finished(state: &mut ServerState, now_ms: u64, close_after_ms: u64) -> usize {
+    let playing: BTreeSet<Uuid> = state
+        .rooms
+        .values()
+        .filter(|room| {
+            room.game_state
+                .as_ref()
+                .is_none_or(|game_state| matches!(game_state.status, GameStatus::Waiting | GameStatus::InProgress | GameStatus::Paused))
+        })
+        .filter_map(|room| room.board_tag.map(|tag| tag.tournament_id))
+        .collect();
+    let finished: Vec<Uuid> = state
+        .chats
+        .iter()
+        .filter(|(id, channel)| {
+            !playing.contains(id) && now_ms.saturating_sub(channel.last_activity_ms) >= close_after_ms
+        })
+        .map(|(id, _)| *id)
+        .collect();
+
+    for tournament_id in &finished {
+        if let Some(channel) = state.chats.remove(tournament_id) {
+            let _ = channel.sender.send(ServerMessage::TournamentChatClosed { tournament_id: *tournament_id });
+            log::info!("Closed the chat of tournament {}", tournament_id);
+        }
+    }
+    finished.len()
+}
+
+// Close the channels of finished tournaments. Returns the number closed.
+pub fn close_finished_channels() -> usize {
+    let mut state = GAME_STATE.lock().unwrap();
+    close_finished(&mut state, now_ms(), close_after_ms())
+}
+
+// A tournament's chat as seen by a connection. Lines already in the
+// snapshot the connection got are skipped; after a gap, the connection gets
+// a fresh snapshot instead.
+pub struct ChatReceiver {
+    pub tournament_id: Uuid,
+    next_id: u64,
+    receiver: Option<broadcast::Receiver<ServerMessage>>,
+}
+
+impl ChatReceiver {
+    // Follow a channel from the snapshot ending with line `last_id`
+    pub fn new(tournament_id: Uuid, last_id: u64) -> Self {
+        let receiver = GAME_STATE
+            .lock()
+            .unwrap()
+            .chats
+            .get(&tournament_id)
+            .map(|channel| channel.sender.subscribe());
+        Self { tournament_id, next_id: last_id + 1, receiver }
+    }
+
+    // Messages to forward right now, if any
+    pub fn try_next(&mut self) -> Vec<ServerMessage> {
+        let Some(receiver) = self.receiver.as_mut() else { return Vec::new() };
+        match receiver.try_recv() {
+            Ok(message) => match message_id(&message) {
+                Some(id) if id < self.next_id => Vec::new(),
+                Some(id) if id == self.next_id => {
+                    self.next_id += 1;
+                    vec![message]
+                }
+                Some(_) => self.resync(),
+                None => vec![message],
+            },
+            Err(TryRecvError::Lagged(_)) => self.resync(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Start again from the channel as it stands, taken under the state lock
+    // so no line falls between the snapshot and the new subscription
+    fn resync(&mut self) -> Vec<ServerMessage> {
+        let state = GAME_STATE.lock().unwrap();
+        let Some(channel) = state.chats.get(&self.tournament_id) else {
+            self.receiver = None;
+            return vec![ServerMessage::TournamentChatClosed { tournament_id: self.tournament_id }];
+        };
+        self.receiver = Some(channel.sender.subscribe());
+        self.next_id = channel.last_id + 1;
+        log::warn!("A chat follower of tournament {} missed lines", self.tournament_id);
+        vec![channel.snapshot(self.tournament_id)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{create_room_with_time, join_room, place_on_board};
+    use crate::models::RoomId;
+
+    fn cleanup(tournament_id: Uuid, rooms: &[RoomId]) {
+        let mut state = GAME_STATE.lock().unwrap();
+        for room_id in rooms {
+            state.rooms.remove(room_id);
+            state.message_senders.remove(room_id);
+        }
+        state.halls.retain(|(id, _), _| *id != tournament_id);
+        state.chats.remove(&tournament_id);
+    }
+
+    #[test]
+    fn test_rounds_are_announced_and_pinned() {
+        let tournament_id = Uuid::new_v4();
+        assert!(join_chat(tournament_id).is_err());
+        let first = create_room_with_time(60_000, 0);
+        place_on_board(&first, BoardTag { tournament_id, round: 1, board: 1 }).unwrap();
+
+        let last_id = match join_chat(tournament_id).unwrap() {
+            ServerMessage::TournamentChatJoined { last_id, pinned, .. } => {
+                assert_eq!(pinned[0].kind, AnnouncementKind::RoundStart { round: 1 });
+                last_id
+            }
+            other => panic!("expected TournamentChatJoined, got {:?}", other),
+        };
+        let mut follower = ChatReceiver::new(tournament_id, last_id);
+
+        let player = "white_player".parse().unwrap();
+        send_line(tournament_id, &player, "  good luck all ").unwrap();
+        assert!(send_line(tournament_id, &player, " ").is_err());
+        assert!(send_line(tournament_id, &player, &"x".repeat(MAX_LINE_CHARS + 1)).is_err());
+        announce(tournament_id, AnnouncementKind::Notice, "No phones in the hall", true).unwrap();
+        let second = create_room_with_time(60_000, 0);
+        place_on_board(&second, BoardTag { tournament_id, round: 2, board: 1 }).unwrap();
+
+        let ids: Vec<u64> = (0..3).flat_map(|_| follower.try_next()).filter_map(|m| message_id(&m)).collect();
+        assert_eq!(ids, [2, 3, 4]);
+        match join_chat(tournament_id).unwrap() {
+            ServerMessage::TournamentChatJoined { pinned, recent, .. } => {
+                // Round 2's notice replaced round 1's
+                let kinds: Vec<_> = pinned.iter().map(|a| a.kind).collect();
+                assert_eq!(kinds, [AnnouncementKind::Notice, AnnouncementKind::RoundStart { round: 2 }]);
+                assert_eq!(recent[0].text, "good luck all");
+            }
+            other => panic!("expected TournamentChatJoined, got {:?}", other),
+        }
+        cleanup(tournament_id, &[first, second]);
+    }
+
+    #[test]
+    fn test_channel_closes_once_the_tournament_is_over() {
+        let tournament_id = Uuid::new_v4();
+        let room_id = create_room_with_time(60_000, 0);
+        place_on_board(&room_id, BoardTag { tournament_id, round: 1, board: 1 }).unwrap();
+        join_room(&room_id, &"white_player".parse().unwrap(), None).unwrap();
+        join_room(&room_id, &"black_player".parse().unwrap(), None).unwrap();
+        let mut follower = ChatReceiver::new(tournament_id, 1);
+        let later = now_ms() + 60_000;
+
+        // A game is still being played
+        assert_eq!(close_finished(&mut GAME_STATE.lock().unwrap(), later, 1_000), 0);
+        {
+            let mut state = GAME_STATE.lock().unwrap();
+            let room = state.rooms.get_mut(&room_id).unwrap();
+            room.game_state.as_mut().unwrap().status = GameStatus::Aborted;
+            assert_eq!(close_finished(&mut state, later, 120_000), 0);
+            assert_eq!(close_finished(&mut state, later, 1_000), 1);
+        }
+        assert!(matches!(
+            &follower.try_next()[..],
+            [ServerMessage::TournamentChatClosed { tournament_id: closed }] if *closed == tournament_id
+        ));
+        assert!(join_chat(tournament_id).is_err());
+        cleanup(tournament_id, &[room_id]);
+    }
+}
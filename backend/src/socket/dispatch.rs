@@ -0,0 +1,613 @@
+use crate::game::{
+    accept_draw, accept_takeback, clock_sync, create_room_with_clocks, decline_draw, ensure_room,
+    get_game_log, implicit_room_creation, join_room_rated, leave_room, offer_draw, offer_takeback, pause_game,
+    place_on_board, reject_takeback, remove_room, resume_game, send_move, GAME_STATE,
+};
+use crate::chat::{announce, is_arbiter_key, join_chat, send_line};
+use crate::flood::{Action, FloodGuard, Throttled};
+use crate::hall::watch_hall;
+use crate::latency::now_ms;
+use crate::lease::{claim_room, fence_room, FencingToken};
+use crate::models::{
+    ClientMessage, PieceColor, RoomId, ServerMessage, SessionPlayerId, DEFAULT_INCREMENT_MS,
+    DEFAULT_INITIAL_TIME_MS,
+};
+
+// The player a connection acts for: the first one any of its messages
+// names. Messages for any other player are refused, so one socket cannot
+// move or answer offers for the opponent.
+#[derive(Debug, Default)]
+pub struct Session {
+    player_id: Option<SessionPlayerId>,
+    flood: FloodGuard,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn authenticate(&mut self, player_id: &SessionPlayerId) -> Result<(), Rejection> {
+        match &self.player_id {
+            Some(current) if current != player_id => Err(Rejection::new(
+                "NOT_AUTHENTICATED",
+                format!("This connection plays as {}, not {}", current, player_id),
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.player_id = Some(player_id.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+// A message refused by validation or by the game, answered with an Error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Rejection {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    pub fn into_message(self) -> ServerMessage {
+        ServerMessage::Error {
+            code: self.code.to_string(),
+            message: self.message,
+        }
+    }
+}
+
+// What a message needs from its sender beyond being well formed
+enum Access {
+    // Read-only requests anyone may make
+    Public,
+    // Acts for a player, who need not be seated yet
+    Player,
+    // Acts for a player seated in the room
+    Member,
+    // A move, which only the side to move may make
+    Mover,
+    // Signed with the arbiter key rather than made for a player
+    Arbiter,
+}
+
+fn access(message: &ClientMessage) -> Access {
+    match message {
+        ClientMessage::RequestGameLog(_)
+        | ClientMessage::ClockSyncRequest(_)
+        | ClientMessage::WatchHall(_)
+        | ClientMessage::UnwatchHall(_)
+        | ClientMessage::JoinTournamentChat(_)
+        | ClientMessage::LeaveTournamentChat(_) => Access::Public,
+        ClientMessage::CreateRoom(_) | ClientMessage::JoinRoom(_) | ClientMessage::SendTournamentChat(_) => {
+            Access::Player
+        }
+        ClientMessage::Announce(_) | ClientMessage::PauseGame(_) | ClientMessage::ResumeGame(_) => Access::Arbiter,
+        ClientMessage::SendMove(_) => Access::Mover,
+        ClientMessage::LeaveRoom(_)
+        | ClientMessage::OfferTakeback(_)
+        | ClientMessage::AcceptTakeback(_)
+        | ClientMessage::RejectTakeback(_)
+        | ClientMessage::OfferDraw(_)
+        | ClientMessage::AcceptDraw(_)
+        | ClientMessage::DeclineDraw(_) => Access::Member,
+    }
+}
+
+// The connection must stay within its limits for the kind of action
+fn throttle(session: &mut Session, message: &ClientMessage) -> Result<(), Rejection> {
+    session.flood.check(Action::of(message), now_ms()).map_err(|throttled| {
+        let code = match throttled {
+            Throttled::Limited { .. } => "RATE_LIMITED",
+            Throttled::Muted { .. } => "MUTED",
+        };
+        Rejection::new(code, throttled.to_string())
+    })
+}
+
+// The message must act for the connection's player, if for anyone
+fn authenticate(session: &mut Session, message: &ClientMessage) -> Result<(), Rejection> {
+    match message.player_id() {
+        Some(player_id) => session.authenticate(player_id),
+        None => Ok(()),
+    }
+}
+
+// The player must sit in the room and, for moves, be the side to move
+fn check_seat(message: &ClientMessage) -> Result<(), Rejection> {
+    let (room_id, player_id) = match (access(message), message.room_id(), message.player_id()) {
+        (Access::Member | Access::Mover, Some(room_id), Some(player_id)) => (room_id, player_id),
+        _ => return Ok(()),
+    };
+
+    let state = GAME_STATE.lock().unwrap();
+    let room = state
+        .rooms
+        .get(room_id)
+        .ok_or_else(|| Rejection::new("ROOM_NOT_FOUND", "Room not found"))?;
+    let player = room
+        .players
+        .iter()
+        .find(|p| &p.id == player_id)
+        .ok_or_else(|| Rejection::new("NOT_IN_ROOM", "Player not in room"))?;
+
+    if let ClientMessage::SendMove(payload) = message {
+        // A retried move is answered with its earlier result whoever is to
+        // move now
+        if payload.seq.is_some_and(|seq| seq < room.moves.len()) {
+            return Ok(());
+        }
+        let game_state = room
+            .game_state
+            .as_ref()
+            .ok_or_else(|| Rejection::new("GAME_NOT_STARTED", "Game not started"))?;
+        if player.color.as_ref() != Some(&game_state.current_turn) {
+            let side = match game_state.current_turn {
+                PieceColor::White => "White",
+                PieceColor::Black => "Black",
+            };
+            return Err(Rejection::new("NOT_YOUR_TURN", format!("It is {}'s turn", side)));
+        }
+    }
+    Ok(())
+}
+
+// Announcements and pauses must carry the arbiter key
+fn check_arbiter(message: &ClientMessage) -> Result<(), Rejection> {
+    let arbiter_key = match message {
+        ClientMessage::Announce(payload) => &payload.arbiter_key,
+        ClientMessage::PauseGame(payload) => &payload.arbiter_key,
+        ClientMessage::ResumeGame(payload) => &payload.arbiter_key,
+        _ => return Ok(()),
+    };
+    if is_arbiter_key(arbiter_key) {
+        Ok(())
+    } else {
+        Err(Rejection::new("NOT_ARBITER", "Only arbiters may do that"))
+    }
+}
+
+// Games closed to spectators are only followed from their own seats
+fn check_audience(session: &Session, message: &ClientMessage) -> Result<(), Rejection> {
+    let room_id = match (access(message), message.room_id()) {
+        (Access::Public, Some(room_id)) => room_id,
+        _ => return Ok(()),
+    };
+
+    let state = GAME_STATE.lock().unwrap();
+    match state.rooms.get(room_id) {
+        Some(room) if !room.visible_to(session.player_id.as_ref()) => Err(Rejection::new(
+            "SPECTATORS_NOT_ALLOWED",
+            "Spectators are not allowed in this game",
+        )),
+        // Unknown rooms are reported by the game
+        _ => Ok(()),
+    }
+}
+
+// Only the instance holding a room's lease may change it
+async fn claim(room_id: &RoomId) -> Result<Option<FencingToken>, Rejection> {
+    claim_room(room_id).await.map_err(|e| {
+        log::warn!("Refusing operation on room {}: {}", room_id, e);
+        Rejection::new("ROOM_NOT_OWNED", e)
+    })
+}
+
+// The lease may have been lost since it was claimed, so its token is checked
+// against Redis right before the room changes
+async fn fence(room_id: &RoomId, token: Option<FencingToken>) -> Result<(), Rejection> {
+    let Some(token) = token else {
+        return Ok(());
+    };
+    fence_room(room_id, token).await.map_err(|e| {
+        log::warn!("Refusing operation on room {}: {}", room_id, e);
+        Rejection::new("ROOM_NOT_OWNED", e)
+    })
+}
+
+// Validate a client message and carry it out, returning the reply for the
+// sender. Every message is checked the same way before it reaches the game:
+// whether the connection is within its rate limits, who sends it, whether
+// it speaks for an arbiter, whether this instance hosts the room, whether the sender sits in it or may watch
+// it and, for moves, whether it is their turn. Replies that concern
+// the whole room are broadcast by the game functions as well.
+pub async fn dispatch(
+    session: &mut Session,
+    message: ClientMessage,
+    lag_compensation_ms: u64,
+) -> Result<ServerMessage, Rejection> {
+    throttle(session, &message)?;
+    authenticate(session, &message)?;
+    check_arbiter(&message)?;
+    let token = match message.room_id() {
+        Some(room_id) => claim(room_id).await?,
+        None => None,
+    };
+    check_seat(&message)?;
+    check_audience(session, &message)?;
+    if let Some(room_id) = message.room_id() {
+        fence(room_id, token).await?;
+    }
+
+    let rejected = |code: &'static str| move |e: String| Rejection::new(code, e);
+    match message {
+        ClientMessage::CreateRoom(payload) => {
+            let white_time_ms = payload.initial_time_ms.unwrap_or(DEFAULT_INITIAL_TIME_MS);
+            let black_time_ms = payload.black_initial_time_ms.unwrap_or(white_time_ms);
+            // Black's draw odds are paid for with less time
+            if payload.armageddon && black_time_ms >= white_time_ms {
+                return Err(Rejection::new("CREATE_ERROR", "Armageddon gives Black less time than White"));
+            }
+            if payload.board.is_some_and(|tag| tag.round == 0 || tag.board == 0) {
+                return Err(Rejection::new("CREATE_ERROR", "Rounds and boards are numbered from 1"));
+            }
+            if payload.board.is_some() && !payload.settings.allow_spectators {
+                return Err(Rejection::new("CREATE_ERROR", "Tournament boards are always shown in the hall"));
+            }
+            let room_id = create_room_with_clocks(
+                white_time_ms,
+                black_time_ms,
+                payload.increment_ms.unwrap_or(DEFAULT_INCREMENT_MS),
+                payload.armageddon,
+                payload.settings,
+            );
+            if let Some(tag) = payload.board {
+                if let Err(e) = place_on_board(&room_id, tag) {
+                    remove_room(&room_id);
+                    return Err(Rejection::new("CREATE_ERROR", e));
+                }
+            }
+            log::info!("Player {} created room {}", payload.player_id, room_id);
+            let token = claim(&room_id).await?;
+            fence(&room_id, token).await?;
+            join_room_rated(&room_id, &payload.player_id, payload.player_name, payload.player_rating)
+                .map_err(rejected("CREATE_ERROR"))
+        }
+        ClientMessage::JoinRoom(payload) => {
+            log::info!("Player {} joining room {}", payload.player_id, payload.room_id);
+            // Rooms must be created with CreateRoom unless the legacy
+            // behaviour is switched on
+            if implicit_room_creation() {
+                ensure_room(&payload.room_id);
+            }
+            join_room_rated(&payload.room_id, &payload.player_id, payload.player_name, payload.player_rating)
+                .map_err(rejected("JOIN_ERROR"))
+        }
+        ClientMessage::SendMove(payload) => {
+            log::info!(
+                "Player {} making move {} in room {}",
+                payload.player_id,
+                payload.move_notation,
+                payload.room_id
+            );
+            send_move(
+                &payload.room_id,
+                &payload.player_id,
+                &payload.move_notation,
+                payload.seq,
+                lag_compensation_ms,
+            )
+            .map_err(rejected("MOVE_ERROR"))
+        }
+        ClientMessage::LeaveRoom(payload) => {
+            log::info!("Player {} leaving room {}", payload.player_id, payload.room_id);
+            leave_room(&payload.room_id, &payload.player_id).map_err(rejected("LEAVE_ERROR"))
+        }
+        ClientMessage::RequestGameLog(payload) => {
+            log::info!("Game log requested for room {}", payload.room_id);
+            get_game_log(&payload.room_id).map_err(rejected("LOG_ERROR"))
+        }
+        ClientMessage::OfferTakeback(payload) => {
+            log::info!("Player {} offering takeback in room {}", payload.player_id, payload.room_id);
+            offer_takeback(&payload.room_id, &payload.player_id).map_err(rejected("TAKEBACK_OFFER_ERROR"))
+        }
+        ClientMessage::AcceptTakeback(payload) => {
+            log::info!("Player {} accepting takeback in room {}", payload.player_id, payload.room_id);
+            accept_takeback(&payload.room_id, &payload.player_id).map_err(rejected("TAKEBACK_ACCEPT_ERROR"))
+        }
+        ClientMessage::RejectTakeback(payload) => {
+            log::info!("Player {} rejecting takeback in room {}", payload.player_id, payload.room_id);
+            reject_takeback(&payload.room_id, &payload.player_id).map_err(rejected("TAKEBACK_REJECT_ERROR"))
+        }
+        ClientMessage::OfferDraw(payload) => {
+            log::info!("Player {} offering a draw in room {}", payload.player_id, payload.room_id);
+            offer_draw(&payload.room_id, &payload.player_id).map_err(rejected("DRAW_OFFER_ERROR"))
+        }
+        ClientMessage::AcceptDraw(payload) => {
+            log::info!("Player {} accepting a draw in room {}", payload.player_id, payload.room_id);
+            accept_draw(&payload.room_id, &payload.player_id).map_err(rejected("DRAW_ACCEPT_ERROR"))
+        }
+        ClientMessage::DeclineDraw(payload) => {
+            log::info!("Player {} declining a draw in room {}", payload.player_id, payload.room_id);
+            decline_draw(&payload.room_id, &payload.player_id).map_err(rejected("DRAW_DECLINE_ERROR"))
+        }
+        ClientMessage::ClockSyncRequest(payload) => {
+            // Answer the requester only; everyone gets periodic updates anyway
+            clock_sync(&payload.room_id, payload.client_time_ms).map_err(rejected("CLOCK_SYNC_ERROR"))
+        }
+        ClientMessage::WatchHall(payload) => Ok(watch_hall(payload.tournament_id, payload.round)),
+        ClientMessage::UnwatchHall(payload) => Ok(ServerMessage::HallUnwatched {
+            tournament_id: payload.tournament_id,
+            round: payload.round,
+        }),
+        ClientMessage::JoinTournamentChat(payload) => join_chat(payload.tournament_id).map_err(rejected("CHAT_ERROR")),
+        ClientMessage::LeaveTournamentChat(payload) => Ok(ServerMessage::TournamentChatLeft {
+            tournament_id: payload.tournament_id,
+        }),
+        ClientMessage::SendTournamentChat(payload) => {
+            send_line(payload.tournament_id, &payload.player_id, &payload.text).map_err(rejected("CHAT_ERROR"))
+        }
+        ClientMessage::Announce(payload) => {
+            announce(payload.tournament_id, payload.kind, &payload.text, payload.pinned)
+                .map_err(rejected("ANNOUNCE_ERROR"))
+        }
+        ClientMessage::PauseGame(payload) => {
+            let reason = payload.reason.as_deref().unwrap_or("Paused by the arbiter");
+            pause_game(&payload.room_id, reason).map_err(rejected("PAUSE_ERROR"))
+        }
+        ClientMessage::ResumeGame(payload) => resume_game(&payload.room_id).map_err(rejected("RESUME_ERROR")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{init_chat_config, AnnouncementKind, ChatConfig};
+    use crate::game::create_room_with_time;
+    use crate::models::{
+        AcceptDrawPayload, AcceptTakebackPayload, AnnouncePayload, BoardTag, CreateRoomPayload, DeclineDrawPayload,
+        GameSettings, GameStatus, JoinRoomPayload, OfferDrawPayload, OfferTakebackPayload, RequestGameLogPayload,
+        SendMovePayload, TakebackPolicy, TournamentChatLinePayload, TournamentChatPayload,
+    };
+    use uuid::Uuid;
+
+    fn player(id: &str) -> SessionPlayerId {
+        id.parse().unwrap()
+    }
+
+    fn join(room_id: RoomId, id: &str) -> ClientMessage {
+        ClientMessage::JoinRoom(JoinRoomPayload { room_id, player_id: player(id), player_name: None, player_rating: None })
+    }
+
+    fn mv(room_id: RoomId, id: &str, notation: &str) -> ClientMessage {
+        ClientMessage::SendMove(SendMovePayload {
+            room_id,
+            player_id: player(id),
+            move_notation: notation.to_string(),
+            seq: None,
+        })
+    }
+
+    fn cleanup_room(room_id: &RoomId) {
+        let mut state = GAME_STATE.lock().unwrap();
+        state.rooms.remove(room_id);
+        state.message_senders.remove(room_id);
+    }
+
+    #[tokio::test]
+    async fn test_connections_act_for_one_player() {
+        let room_id = create_room_with_time(60_000, 0);
+        let (mut white, mut black) = (Session::new(), Session::new());
+        dispatch(&mut white, join(room_id, "white_player"), 0).await.unwrap();
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+        assert_eq!(white.player_id, Some(player("white_player")));
+
+        // White's connection may not move or offer a draw for Black
+        let spoofed = dispatch(&mut white, mv(room_id, "black_player", "e7e5"), 0).await;
+        assert_eq!(spoofed.unwrap_err().code, "NOT_AUTHENTICATED");
+        let offer = ClientMessage::OfferDraw(OfferDrawPayload { room_id, player_id: player("black_player") });
+        assert_eq!(dispatch(&mut white, offer, 0).await.unwrap_err().code, "NOT_AUTHENTICATED");
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_moves_need_a_seat_and_the_turn() {
+        let room_id = create_room_with_time(60_000, 0);
+        let (mut white, mut black) = (Session::new(), Session::new());
+        dispatch(&mut white, join(room_id, "white_player"), 0).await.unwrap();
+
+        let early = dispatch(&mut white, mv(room_id, "white_player", "e2e4"), 0).await;
+        assert_eq!(early.unwrap_err().code, "GAME_NOT_STARTED");
+        let outsider = dispatch(&mut black, mv(room_id, "black_player", "e7e5"), 0).await;
+        assert_eq!(outsider.unwrap_err().code, "NOT_IN_ROOM");
+        let unknown = dispatch(&mut Session::new(), mv(RoomId::new(), "white_player", "e2e4"), 0).await;
+        assert_eq!(unknown.unwrap_err().code, "ROOM_NOT_FOUND");
+
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+        let out_of_turn = dispatch(&mut black, mv(room_id, "black_player", "e7e5"), 0).await;
+        assert_eq!(
+            out_of_turn.unwrap_err(),
+            Rejection::new("NOT_YOUR_TURN", "It is White's turn")
+        );
+        dispatch(&mut white, mv(room_id, "white_player", "e2e4"), 0).await.unwrap();
+        let twice = dispatch(&mut white, mv(room_id, "white_player", "d2d4"), 0).await;
+        assert_eq!(twice.unwrap_err().code, "NOT_YOUR_TURN");
+        dispatch(&mut black, mv(room_id, "black_player", "e7e5"), 0).await.unwrap();
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_draw_offers() {
+        let room_id = create_room_with_time(60_000, 0);
+        let (mut white, mut black) = (Session::new(), Session::new());
+        dispatch(&mut white, join(room_id, "white_player"), 0).await.unwrap();
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+        let offer = || ClientMessage::OfferDraw(OfferDrawPayload { room_id, player_id: player("white_player") });
+        let accept = || ClientMessage::AcceptDraw(AcceptDrawPayload { room_id, player_id: player("black_player") });
+        let decline = || ClientMessage::DeclineDraw(DeclineDrawPayload { room_id, player_id: player("black_player") });
+
+        dispatch(&mut white, offer(), 0).await.unwrap();
+        assert!(matches!(dispatch(&mut black, decline(), 0).await, Ok(ServerMessage::DrawDeclined { .. })));
+        assert_eq!(dispatch(&mut black, accept(), 0).await.unwrap_err().code, "DRAW_ACCEPT_ERROR");
+
+        // Moving instead of answering declines the offer
+        dispatch(&mut white, mv(room_id, "white_player", "e2e4"), 0).await.unwrap();
+        dispatch(&mut white, offer(), 0).await.unwrap();
+        dispatch(&mut black, mv(room_id, "black_player", "e7e5"), 0).await.unwrap();
+        assert_eq!(dispatch(&mut black, accept(), 0).await.unwrap_err().code, "DRAW_ACCEPT_ERROR");
+
+        dispatch(&mut white, offer(), 0).await.unwrap();
+        match dispatch(&mut black, accept(), 0).await {
+            Ok(ServerMessage::DrawAccepted { game_state, .. }) => assert!(matches!(game_state.status, GameStatus::Draw)),
+            other => panic!("expected DrawAccepted, got {:?}", other),
+        }
+        let after = dispatch(&mut white, mv(room_id, "white_player", "d2d4"), 0).await;
+        assert_eq!(after.unwrap_err().code, "MOVE_ERROR");
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_armageddon_draw_goes_to_black() {
+        let create = |black_initial_time_ms| {
+            ClientMessage::CreateRoom(CreateRoomPayload {
+                player_id: player("white_player"),
+                player_name: None,
+                player_rating: None,
+                initial_time_ms: Some(300_000),
+                black_initial_time_ms,
+                increment_ms: None,
+                armageddon: true,
+                board: None,
+                settings: GameSettings::default(),
+            })
+        };
+        let (mut white, mut black) = (Session::new(), Session::new());
+
+        let even = dispatch(&mut white, create(None), 0).await;
+        assert_eq!(even.unwrap_err().code, "CREATE_ERROR");
+        let room_id = match dispatch(&mut white, create(Some(240_000)), 0).await {
+            Ok(ServerMessage::RoomJoined { room_id, .. }) => room_id,
+            other => panic!("expected RoomJoined, got {:?}", other),
+        };
+        {
+            let state = GAME_STATE.lock().unwrap();
+            let room = &state.rooms[&room_id];
+            assert_eq!((room.white_remaining_ms, room.black_remaining_ms), (300_000, 240_000));
+        }
+
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+        let offer = ClientMessage::OfferDraw(OfferDrawPayload { room_id, player_id: player("white_player") });
+        let accept = ClientMessage::AcceptDraw(AcceptDrawPayload { room_id, player_id: player("black_player") });
+        dispatch(&mut white, offer, 0).await.unwrap();
+        match dispatch(&mut black, accept, 0).await {
+            Ok(ServerMessage::DrawAccepted { game_state, .. }) => {
+                assert!(matches!(game_state.status, GameStatus::Draw));
+                assert_eq!(game_state.awarded_to, Some(PieceColor::Black));
+            }
+            other => panic!("expected DrawAccepted, got {:?}", other),
+        }
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_spammed_offers_are_rate_limited() {
+        let room_id = create_room_with_time(60_000, 0);
+        let (mut white, mut black) = (Session::new(), Session::new());
+        dispatch(&mut white, join(room_id, "white_player"), 0).await.unwrap();
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+        let offer = || ClientMessage::OfferDraw(OfferDrawPayload { room_id, player_id: player("white_player") });
+
+        let mut codes = Vec::new();
+        for _ in 0..7 {
+            codes.push(dispatch(&mut white, offer(), 0).await.err().map(|r| r.code));
+        }
+        assert_eq!(codes[0], None);
+        assert_eq!(codes[4], Some("DRAW_OFFER_ERROR"));
+        assert_eq!(codes[5..], [Some("RATE_LIMITED"), Some("RATE_LIMITED")]);
+        // Moves have their own budget
+        dispatch(&mut white, mv(room_id, "white_player", "e2e4"), 0).await.unwrap();
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_room_settings_limit_takebacks_and_spectators() {
+        let settings = GameSettings {
+            takebacks: TakebackPolicy::Limited(1),
+            allow_chat: false,
+            allow_spectators: false,
+        };
+        let create = ClientMessage::CreateRoom(CreateRoomPayload {
+            player_id: player("white_player"),
+            player_name: None,
+            player_rating: None,
+            initial_time_ms: Some(60_000),
+            black_initial_time_ms: None,
+            increment_ms: None,
+            armageddon: false,
+            board: None,
+            settings,
+        });
+        let (mut white, mut black, mut spectator) = (Session::new(), Session::new(), Session::new());
+        let room_id = match dispatch(&mut white, create, 0).await {
+            Ok(ServerMessage::RoomJoined { room_id, settings: created, .. }) => {
+                assert_eq!(created, settings);
+                room_id
+            }
+            other => panic!("expected RoomJoined, got {:?}", other),
+        };
+        dispatch(&mut black, join(room_id, "black_player"), 0).await.unwrap();
+
+        let offer = || ClientMessage::OfferTakeback(OfferTakebackPayload { room_id, player_id: player("white_player") });
+        let accept = || ClientMessage::AcceptTakeback(AcceptTakebackPayload { room_id, player_id: player("black_player") });
+        dispatch(&mut white, mv(room_id, "white_player", "e2e4"), 0).await.unwrap();
+        dispatch(&mut black, mv(room_id, "black_player", "e7e5"), 0).await.unwrap();
+        dispatch(&mut white, offer(), 0).await.unwrap();
+        dispatch(&mut black, accept(), 0).await.unwrap();
+        dispatch(&mut white, mv(room_id, "white_player", "d2d4"), 0).await.unwrap();
+        dispatch(&mut black, mv(room_id, "black_player", "d7d5"), 0).await.unwrap();
+        let again = dispatch(&mut white, offer(), 0).await.unwrap_err();
+        assert_eq!(again.code, "TAKEBACK_OFFER_ERROR");
+        assert!(again.message.contains("No takebacks left"));
+
+        let log = || ClientMessage::RequestGameLog(RequestGameLogPayload { room_id });
+        let refused = dispatch(&mut spectator, log(), 0).await.unwrap_err();
+        assert_eq!(refused.code, "SPECTATORS_NOT_ALLOWED");
+        assert!(dispatch(&mut white, log(), 0).await.is_ok());
+        cleanup_room(&room_id);
+    }
+
+    #[tokio::test]
+    async fn test_tournament_chat_is_moderated() {
+        init_chat_config(ChatConfig { arbiter_key: Some("arbiter-key".to_string()), close_after_ms: 60_000 });
+        let tournament_id = Uuid::new_v4();
+        let room_id = create_room_with_time(60_000, 0);
+        place_on_board(&room_id, BoardTag { tournament_id, round: 1, board: 1 }).unwrap();
+        let mut player_session = Session::new();
+
+        let joined = dispatch(&mut player_session, ClientMessage::JoinTournamentChat(TournamentChatPayload { tournament_id }), 0).await;
+        assert!(matches!(joined, Ok(ServerMessage::TournamentChatJoined { .. })));
+        let announce = |arbiter_key: &str| {
+            ClientMessage::Announce(AnnouncePayload {
+                tournament_id,
+                arbiter_key: arbiter_key.to_string(),
+                text: "Round 1 starts in five minutes".to_string(),
+                kind: AnnouncementKind::Notice,
+                pinned: true,
+            })
+        };
+        let forged = dispatch(&mut player_session, announce("guess"), 0).await;
+        assert_eq!(forged.unwrap_err().code, "NOT_ARBITER");
+        assert!(dispatch(&mut Session::new(), announce("arbiter-key"), 0).await.is_ok());
+
+        let line = || {
+            ClientMessage::SendTournamentChat(TournamentChatLinePayload {
+                tournament_id,
+                player_id: player("white_player"),
+                text: "gl hf".to_string(),
+            })
+        };
+        let mut codes = Vec::new();
+        for _ in 0..5 {
+            codes.push(dispatch(&mut player_session, line(), 0).await.err().map(|r| r.code));
+        }
+        // The forged announcement spent one of the five chat actions
+        assert_eq!(codes, [None, None, None, None, Some("RATE_LIMITED")]);
+        cleanup_room(&room_id);
+        GAME_STATE.lock().unwrap().chats.remove(&tournament_id);
+    }
+}
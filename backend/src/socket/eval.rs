@@ -0,0 +1,118 @@
+use redis::aio::MultiplexedConnection;
+use redis::RedisResult;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::game::broadcast_eval;
+use crate::models::{CompactEval, RoomId};
+
+// Depths of the API's cached analyses looked up, deepest first
+pub const DEFAULT_CLOUD_EVAL_DEPTHS: [u8; 3] = [20, 18, 15];
+
+// The fields of a cached `/v1/ai/analyze` response spectators get
+#[derive(Debug, Deserialize)]
+struct CachedAnalysis {
+    evaluation: f32,
+    #[serde(default)]
+    best_line: Vec<String>,
+}
+
+/// Analyses the API cached in Redis (its `CACHE_REDIS_URL`), looked up by
+/// position so spectators get an eval without asking the engines.
+#[derive(Clone)]
+pub struct CloudEval {
+    conn: MultiplexedConnection,
+    depths: Vec<u8>,
+}
+
+static CLOUD_EVAL: OnceLock<CloudEval> = OnceLock::new();
+
+// Key the API caches a full-depth analysis of a standard position under
+pub fn analysis_key(depth: u8, fen: &str) -> String {
+    format!("cache:analysis:standard:{}:{}", depth, fen)
+}
+
+impl CloudEval {
+    pub async fn connect(redis_url: &str, mut depths: Vec<u8>) -> RedisResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+        depths.sort_unstable_by(|a, b| b.cmp(a));
+        depths.dedup();
+        Ok(CloudEval { conn, depths })
+    }
+
+    /// The deepest cached analysis of `fen`, if any depth has one.
+    pub async fn lookup(&self, fen: &str) -> RedisResult<Option<CompactEval>> {
+        if self.depths.is_empty() {
+            return Ok(None);
+        }
+        let keys: Vec<String> = self.depths.iter().map(|depth| analysis_key(*depth, fen)).collect();
+        let cached: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut self.conn.clone()).await?;
+        Ok(deepest(&self.depths, cached))
+    }
+}
+
+// The first analysis that parses, `cached` being in the order of `depths`
+fn deepest(depths: &[u8], cached: Vec<Option<String>>) -> Option<CompactEval> {
+    depths.iter().zip(cached).find_map(|(depth, value)| {
+        let analysis: CachedAnalysis = serde_json::from_str(&value?).ok()?;
+        Some(CompactEval {
+            evaluation: analysis.evaluation,
+            depth: *depth,
+            best_move: analysis.best_line.into_iter().next(),
+        })
+    })
+}
+
+// Enable evals in the spectator feed; without them moves go out with
+// their timing only
+pub fn init_cloud_eval(cloud_eval: CloudEval) {
+    if CLOUD_EVAL.set(cloud_eval).is_err() {
+        log::warn!("Cloud eval was already initialized");
+    }
+}
+
+// Look up the position after move `ply` and send its eval to the room,
+// unless the game moved on or took the move back meanwhile. Moves never
+// wait for the lookup.
+pub fn announce(room_id: RoomId, ply: usize, fen: String) {
+    let Some(cloud_eval) = CLOUD_EVAL.get() else {
+        return;
+    };
+    tokio::spawn(async move {
+        match cloud_eval.lookup(&fen).await {
+            Ok(Some(evaluation)) => {
+                broadcast_eval(&room_id, ply, evaluation);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Cloud eval lookup failed for room {}: {}", room_id, e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_match_the_api_cache() {
+        assert_eq!(
+            analysis_key(18, "8/8/8/8/8/8/8/K6k w - - 0 1"),
+            "cache:analysis:standard:18:8/8/8/8/8/8/8/K6k w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_the_deepest_cached_analysis_wins() {
+        let analysis = |evaluation: f32| {
+            Some(format!(
+                r#"{{"evaluation": {}, "best_line": ["e2e4", "e7e5"], "alternatives": [], "position_type": "Analyzed by Engine"}}"#,
+                evaluation
+            ))
+        };
+        let eval = deepest(&[20, 18, 15], vec![None, analysis(0.3), analysis(0.1)]).unwrap();
+        assert_eq!(eval, CompactEval { evaluation: 0.3, depth: 18, best_move: Some("e2e4".to_string()) });
+
+        assert_eq!(deepest(&[20, 18], vec![Some("not json".to_string()), None]), None);
+    }
+}
@@ -0,0 +1,274 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::models::ClientMessage;
+
+// Kinds of action a connection is limited on separately, so that spamming
+// offers never costs a player their moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Move,
+    // Takeback and draw offers and the answers to them
+    Offer,
+    // Creating, joining and leaving rooms, watching halls, pausing games
+    Room,
+    // Game logs and clock syncs
+    Read,
+    // Tournament chat lines and announcements
+    Chat,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [Action::Move, Action::Offer, Action::Room, Action::Read, Action::Chat];
+
+    pub fn of(message: &ClientMessage) -> Self {
+        match message {
+            ClientMessage::SendMove(_) => Action::Move,
+            ClientMessage::OfferTakeback(_)
+            | ClientMessage::AcceptTakeback(_)
+            | ClientMessage::RejectTakeback(_)
+            | ClientMessage::OfferDraw(_)
+            | ClientMessage::AcceptDraw(_)
+            | ClientMessage::DeclineDraw(_) => Action::Offer,
+            ClientMessage::CreateRoom(_)
+            | ClientMessage::JoinRoom(_)
+            | ClientMessage::LeaveRoom(_)
+            | ClientMessage::WatchHall(_)
+            | ClientMessage::UnwatchHall(_)
+            | ClientMessage::JoinTournamentChat(_)
+            | ClientMessage::LeaveTournamentChat(_)
+            | ClientMessage::PauseGame(_)
+            | ClientMessage::ResumeGame(_) => Action::Room,
+            ClientMessage::RequestGameLog(_) | ClientMessage::ClockSyncRequest(_) => Action::Read,
+            ClientMessage::SendTournamentChat(_) | ClientMessage::Announce(_) => Action::Chat,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    // Suffix of the environment variable configuring the action
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Move => "MOVE",
+            Action::Offer => "OFFER",
+            Action::Room => "ROOM",
+            Action::Read => "READ",
+            Action::Chat => "CHAT",
+        }
+    }
+}
+
+// A token bucket: `burst` actions at once, refilled at `per_second`.
+// Written as "burst/per_second", e.g. "5/0.2".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketConfig {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl FromStr for BucketConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (burst, per_second) = s
+            .split_once('/')
+            .ok_or_else(|| format!("Expected burst/per_second, got '{}'", s))?;
+        let burst: u32 = burst.trim().parse().map_err(|_| format!("Invalid burst '{}'", burst))?;
+        let per_second: f64 = per_second
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid rate '{}'", per_second))?;
+        if burst == 0 || !per_second.is_finite() || per_second <= 0.0 {
+            return Err(format!("Limit '{}' would refuse every action", s));
+        }
+        Ok(Self { burst, per_second })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloodConfig {
+    // Limits in the order of `Action::ALL`
+    pub limits: [BucketConfig; 5],
+    // Refused actions in a row of one kind that mute that kind
+    pub mute_after: u32,
+    pub mute_ms: u64,
+}
+
+impl Default for FloodConfig {
+    fn default() -> Self {
+        Self {
+            limits: [
+                // Premoves arrive in bursts
+                BucketConfig { burst: 20, per_second: 5.0 },
+                BucketConfig { burst: 5, per_second: 0.2 },
+                BucketConfig { burst: 10, per_second: 1.0 },
+                BucketConfig { burst: 20, per_second: 5.0 },
+                BucketConfig { burst: 5, per_second: 0.5 },
+            ],
+            mute_after: 10,
+            mute_ms: 30_000,
+        }
+    }
+}
+
+impl FloodConfig {
+    pub fn set_limit(&mut self, action: Action, limit: BucketConfig) {
+        self.limits[action.index()] = limit;
+    }
+}
+
+static FLOOD_CONFIG: OnceLock<FloodConfig> = OnceLock::new();
+
+pub fn init_flood_config(config: FloodConfig) {
+    if FLOOD_CONFIG.set(config).is_err() {
+        log::warn!("Flood limits were already configured");
+    }
+}
+
+// Why an action was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttled {
+    // Over the limit; the next action of the kind is allowed after `retry_after_ms`
+    Limited { action: Action, retry_after_ms: u64 },
+    // Flooded past the limit; the kind is refused until `remaining_ms` pass
+    Muted { action: Action, remaining_ms: u64 },
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Throttled::Limited { action, retry_after_ms } => write!(
+                f,
+                "Too many {} actions; try again in {}ms",
+                action.name().to_lowercase(),
+                retry_after_ms
+            ),
+            Throttled::Muted { action, remaining_ms } => write!(
+                f,
+                "{} actions are muted for {}s after flooding",
+                action.name().to_lowercase(),
+                remaining_ms.div_ceil(1_000)
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_ms: Option<u64>,
+    refused_in_row: u32,
+    muted_until_ms: Option<u64>,
+}
+
+// Limits of one connection
+#[derive(Debug, Clone)]
+pub struct FloodGuard {
+    config: FloodConfig,
+    buckets: [Bucket; 5],
+}
+
+impl Default for FloodGuard {
+    fn default() -> Self {
+        Self::new(FLOOD_CONFIG.get().copied().unwrap_or_default())
+    }
+}
+
+impl FloodGuard {
+    pub fn new(config: FloodConfig) -> Self {
+        let buckets = config.limits.map(|limit| Bucket {
+            tokens: limit.burst as f64,
+            updated_ms: None,
+            refused_in_row: 0,
+            muted_until_ms: None,
+        });
+        Self { config, buckets }
+    }
+
+    // Spend a token for `action` at `now_ms`, or say why it is refused
+    pub fn check(&mut self, action: Action, now_ms: u64) -> Result<(), Throttled> {
+        let limit = self.config.limits[action.index()];
+        let bucket = &mut self.buckets[action.index()];
+
+        if let Some(until) = bucket.muted_until_ms {
+            if now_ms < until {
+                return Err(Throttled::Muted { action, remaining_ms: until - now_ms });
+            }
+            bucket.muted_until_ms = None;
+            bucket.refused_in_row = 0;
+        }
+
+        let elapsed_s = bucket.updated_ms.map_or(0.0, |then| now_ms.saturating_sub(then) as f64 / 1_000.0);
+        bucket.tokens = (bucket.tokens + elapsed_s * limit.per_second).min(limit.burst as f64);
+        bucket.updated_ms = Some(now_ms);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.refused_in_row = 0;
+            return Ok(());
+        }
+
+        bucket.refused_in_row += 1;
+        if bucket.refused_in_row >= self.config.mute_after {
+            log::warn!(
+                "Muting {:?} actions of a connection for {}ms after {} refused in a row",
+                action, self.config.mute_ms, bucket.refused_in_row
+            );
+            bucket.muted_until_ms = Some(now_ms + self.config.mute_ms);
+            return Err(Throttled::Muted { action, remaining_ms: self.config.mute_ms });
+        }
+        let retry_after_ms = ((1.0 - bucket.tokens) / limit.per_second * 1_000.0).ceil() as u64;
+        Err(Throttled::Limited { action, retry_after_ms })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> FloodGuard {
+        let mut config = FloodConfig { mute_after: 3, mute_ms: 10_000, ..FloodConfig::default() };
+        config.set_limit(Action::Offer, BucketConfig { burst: 2, per_second: 0.5 });
+        FloodGuard::new(config)
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let mut guard = guard();
+        assert!(guard.check(Action::Offer, 0).is_ok());
+        assert!(guard.check(Action::Offer, 0).is_ok());
+        assert_eq!(
+            guard.check(Action::Offer, 0),
+            Err(Throttled::Limited { action: Action::Offer, retry_after_ms: 2_000 })
+        );
+        // Other kinds keep their own budget
+        assert!(guard.check(Action::Move, 0).is_ok());
+        assert!(guard.check(Action::Offer, 2_000).is_ok());
+    }
+
+    #[test]
+    fn test_flooding_mutes_the_action() {
+        let mut guard = guard();
+        guard.check(Action::Offer, 0).unwrap();
+        guard.check(Action::Offer, 0).unwrap();
+        assert!(matches!(guard.check(Action::Offer, 0), Err(Throttled::Limited { .. })));
+        assert!(matches!(guard.check(Action::Offer, 10), Err(Throttled::Limited { .. })));
+        assert_eq!(
+            guard.check(Action::Offer, 20),
+            Err(Throttled::Muted { action: Action::Offer, remaining_ms: 10_000 })
+        );
+        // Refilled, but still muted
+        assert!(matches!(guard.check(Action::Offer, 5_000), Err(Throttled::Muted { .. })));
+        assert!(guard.check(Action::Offer, 10_020).is_ok());
+    }
+
+    #[test]
+    fn test_limits_parse() {
+        assert_eq!("5/0.2".parse(), Ok(BucketConfig { burst: 5, per_second: 0.2 }));
+        assert!("5".parse::<BucketConfig>().is_err());
+        assert!("0/1".parse::<BucketConfig>().is_err());
+    }
+}
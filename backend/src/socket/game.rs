@@ -1,100 +1,220 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
-use crate::models::{GameStatus, PieceColor, Player, Room, ServerMessage};
-
-const LATENCY_BUFFER_MS: u64 = 750;
+use crate::chat::{self, ChatChannel};
+use crate::eval;
+use crate::hall::{self, Hall};
+use crate::latency::now_ms;
+use crate::ongoing;
+use crate::models::{
+    play_notation, status_after, Adjournment, BoardTag, CompactEval, GameSettings, GameState, GameStatus, OfferKind, PieceColor,
+    Player, Room, RoomId, ServerMessage, SessionPlayerId,
+};
 
 type MessageSender = broadcast::Sender<ServerMessage>;
+type MessageReceiver = broadcast::Receiver<ServerMessage>;
+
+// Messages each room's channel holds for receivers that have not caught up
+pub const DEFAULT_BROADCAST_CAPACITY: usize = 100;
+
+// How often running clocks are broadcast to the players
+pub const CLOCK_UPDATE_INTERVAL_MS: u64 = 1_000;
+
+// Time each side has for its first move before the game is aborted
+pub const DEFAULT_FIRST_MOVE_TIMEOUT_MS: u64 = 30_000;
+
+// Remaining time below which players and spectators get a LowTime alert
+pub const DEFAULT_LOW_TIME_MS: u64 = 10_000;
+
+// How long a draw or takeback offer waits for an answer before it expires
+pub const DEFAULT_OFFER_TTL_MS: u64 = 60_000;
 
 pub struct ServerState {
-    pub rooms: HashMap<String, Room>,
-    pub message_senders: HashMap<String, MessageSender>,
+    pub rooms: HashMap<RoomId, Room>,
+    pub message_senders: HashMap<RoomId, MessageSender>,
+    // Legacy behaviour: joining an unknown room creates it
+    pub implicit_room_creation: bool,
+    pub broadcast_capacity: usize,
+    // Messages receivers missed by falling behind, per room
+    pub dropped_messages: HashMap<RoomId, u64>,
+    // Tournament boards by tournament and round, for the hall view
+    pub halls: HashMap<(Uuid, u32), Hall>,
+    pub first_move_timeout_ms: u64,
+    pub low_time_ms: u64,
+    pub offer_ttl_ms: u64,
+    // Chat channels of the tournaments being played
+    pub chats: HashMap<Uuid, ChatChannel>,
 }
 
 lazy_static::lazy_static! {
     pub static ref GAME_STATE: Arc<Mutex<ServerState>> = Arc::new(Mutex::new(ServerState {
         rooms: HashMap::new(),
         message_senders: HashMap::new(),
+        implicit_room_creation: false,
+        broadcast_capacity: DEFAULT_BROADCAST_CAPACITY,
+        dropped_messages: HashMap::new(),
+        halls: HashMap::new(),
+        first_move_timeout_ms: DEFAULT_FIRST_MOVE_TIMEOUT_MS,
+        low_time_ms: DEFAULT_LOW_TIME_MS,
+        offer_ttl_ms: DEFAULT_OFFER_TTL_MS,
+        chats: HashMap::new(),
     }));
 }
 
 // Initialize the game state
-pub fn init_game_state() {
+pub fn init_game_state(
+    implicit_room_creation: bool,
+    broadcast_capacity: usize,
+    first_move_timeout_ms: u64,
+    low_time_ms: u64,
+    offer_ttl_ms: u64,
+) {
     // This function is called at startup to ensure the lazy_static is initialized
-    let _guard = GAME_STATE.lock().unwrap();
-    log::info!("Game state initialized");
+    let mut state = GAME_STATE.lock().unwrap();
+    state.implicit_room_creation = implicit_room_creation;
+    // Tokio refuses empty channels
+    state.broadcast_capacity = broadcast_capacity.max(1);
+    state.first_move_timeout_ms = first_move_timeout_ms;
+    state.low_time_ms = low_time_ms;
+    state.offer_ttl_ms = offer_ttl_ms;
+    log::info!(
+        "Game state initialized (implicit room creation {}, broadcast capacity {}, first move timeout {}ms, low time {}ms, offer TTL {}ms)",
+        if implicit_room_creation { "enabled" } else { "disabled" },
+        state.broadcast_capacity,
+        state.first_move_timeout_ms,
+        state.low_time_ms,
+        state.offer_ttl_ms
+    );
+}
+
+// Whether joining an unknown room should create it
+pub fn implicit_room_creation() -> bool {
+    GAME_STATE.lock().unwrap().implicit_room_creation
 }
 
 // Get a clone of the message sender for a room
-pub fn get_room_sender(room_id: &str) -> Option<MessageSender> {
+pub fn get_room_sender(room_id: &RoomId) -> Option<MessageSender> {
     let state = GAME_STATE.lock().unwrap();
     state.message_senders.get(room_id).cloned()
 }
 
 // Create a new room
-pub fn create_room() -> String {
-    let room_id = Uuid::new_v4().to_string();
-    let (tx, _) = broadcast::channel(100);
+pub fn create_room() -> RoomId {
+    let room_id = RoomId::new();
 
     let mut state = GAME_STATE.lock().unwrap();
-    state.rooms.insert(room_id.clone(), Room::new(room_id.clone()));
-    state.message_senders.insert(room_id.clone(), tx);
+    let (tx, _) = broadcast::channel(state.broadcast_capacity);
+    state.rooms.insert(room_id, Room::new(room_id));
+    state.message_senders.insert(room_id, tx);
 
     room_id
 }
 
 // Create a new room with custom time control
-pub fn create_room_with_time(initial_time_ms: u64, increment_ms: u64) -> String {
-    let room_id = Uuid::new_v4().to_string();
-    let (tx, _) = broadcast::channel(100);
+pub fn create_room_with_time(initial_time_ms: u64, increment_ms: u64) -> RoomId {
+    create_room_with_clocks(initial_time_ms, initial_time_ms, increment_ms, false, GameSettings::default())
+}
+
+// Create a room where each side starts with its own time; armageddon rooms
+// score drawn games as a win for Black
+pub fn create_room_with_clocks(
+    white_time_ms: u64,
+    black_time_ms: u64,
+    increment_ms: u64,
+    armageddon: bool,
+    settings: GameSettings,
+) -> RoomId {
+    let room_id = RoomId::new();
 
     let mut state = GAME_STATE.lock().unwrap();
-    state.rooms.insert(
-        room_id.clone(),
-        Room::new_with_time(room_id.clone(), initial_time_ms, increment_ms),
-    );
-    state.message_senders.insert(room_id.clone(), tx);
+    let (tx, _) = broadcast::channel(state.broadcast_capacity);
+    let mut room = Room::new_with_clocks(room_id, white_time_ms, black_time_ms, increment_ms);
+    room.armageddon = armageddon;
+    room.settings = settings;
+    state.rooms.insert(room_id, room);
+    state.message_senders.insert(room_id, tx);
 
     log::info!(
-        "Created room {} with time control: {}ms + {}ms increment",
-        room_id, initial_time_ms, increment_ms
+        "Created room {} with time control: {}ms/{}ms + {}ms increment{}",
+        room_id,
+        white_time_ms,
+        black_time_ms,
+        increment_ms,
+        if armageddon { ", armageddon" } else { "" }
     );
 
     room_id
 }
 
-// Join a room
-pub fn join_room(room_id: &str, player_id: &str, player_name: Option<String>) -> Result<ServerMessage, String> {
+// Drop a room nobody has joined yet
+pub fn remove_room(room_id: &RoomId) {
     let mut state = GAME_STATE.lock().unwrap();
+    state.rooms.remove(room_id);
+    state.message_senders.remove(room_id);
+    ongoing::publish(&state, &[*room_id]);
+}
 
-    // Check if room exists, create if not
+// Put a room's game on a tournament board, where the hall view shows it
+pub fn place_on_board(room_id: &RoomId, tag: BoardTag) -> Result<(), String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    if state.rooms.values().any(|room| room.id != *room_id && room.board_tag == Some(tag)) {
+        return Err(format!("Board {} of round {} already has a game", tag.board, tag.round));
+    }
+    let room = state.rooms.get_mut(room_id).ok_or_else(|| "Room not found".to_string())?;
+    room.board_tag = Some(tag);
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
+    chat::open_round(&mut state, tag);
+    Ok(())
+}
+
+// Create a room with a client-chosen ID unless it already exists.
+// Only used when implicit room creation is enabled.
+pub fn ensure_room(room_id: &RoomId) {
+    let mut state = GAME_STATE.lock().unwrap();
     if !state.rooms.contains_key(room_id) {
-        drop(state); // Release the lock before creating the room
+        let (tx, _) = broadcast::channel(state.broadcast_capacity);
+        state.rooms.insert(*room_id, Room::new(*room_id));
+        state.message_senders.insert(*room_id, tx);
+        log::info!("Implicitly created room {}", room_id);
+    }
+}
 
-        // Create the room with the requested ID
-        let (tx, _) = broadcast::channel(100);
+// Join an existing room
+pub fn join_room(
+    room_id: &RoomId,
+    player_id: &SessionPlayerId,
+    player_name: Option<String>,
+) -> Result<ServerMessage, String> {
+    join_room_rated(room_id, player_id, player_name, None)
+}
 
-        state = GAME_STATE.lock().unwrap();
-        // Re-check after re-locking to avoid race condition
-        if !state.rooms.contains_key(room_id) {
-            state.rooms.insert(room_id.to_string(), Room::new(room_id.to_string()));
-            state.message_senders.insert(room_id.to_string(), tx);
-        }
-    }
+// Join an existing room with the rating the player's client gave, shown to
+// the room and on the list of games to watch
+pub fn join_room_rated(
+    room_id: &RoomId,
+    player_id: &SessionPlayerId,
+    player_name: Option<String>,
+    rating: Option<u32>,
+) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    let low_time_ms = state.low_time_ms;
 
-    let room = state.rooms.get_mut(room_id).unwrap();
+    let room = state
+        .rooms
+        .get_mut(room_id)
+        .ok_or_else(|| "Room not found".to_string())?;
 
     // Check if this is the second player (game will start)
     let is_game_starting = room.players.len() == 1;
 
     // Create player
     let player = Player {
-        id: player_id.to_string(),
+        id: player_id.clone(),
         name: player_name.unwrap_or_else(|| format!("Player {}", player_id)),
+        rating,
         color: None,
     };
 
@@ -103,20 +223,21 @@ pub fn join_room(room_id: &str, player_id: &str, player_name: Option<String>) ->
 
     // If second player joined, start White's clock
     if is_game_starting {
-        let now_ms = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_err(|e| format!("System clock error: {}", e))?
-            .as_millis() as u64;
+        let now_ms = now_ms();
         room.last_move_at = Some(now_ms);
         log::info!("Game started in room {}, clock started at {}ms", room_id, now_ms);
     }
 
     // Create response message
     let response = ServerMessage::RoomJoined {
-        room_id: room_id.to_string(),
-        player_id: player_id.to_string(),
+        room_id: *room_id,
+        player_id: player_id.clone(),
         players: room.players.clone(),
         game_state: room.game_state.clone(),
+        position: room.position(),
+        settings: room.settings,
+        clock: room.clock_settings(low_time_ms),
+        available_actions: room.available_actions(),
     };
 
     // Broadcast to other players in the room
@@ -125,29 +246,86 @@ pub fn join_room(room_id: &str, player_id: &str, player_name: Option<String>) ->
             log::warn!("Failed to broadcast RoomJoined message: {:?}", e);
         }
     }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
 
     Ok(response)
 }
 
-// Send a move
-pub fn send_move(room_id: &str, player_id: &str, move_notation: &str) -> Result<ServerMessage, String> {
+// Send a move. `seq` is the number of moves the client had seen played;
+// a retry of a move already applied gets its result again instead of
+// being played twice. `lag_compensation_ms` is the grace allowed past the
+// flag for the mover's connection latency.
+pub fn send_move(
+    room_id: &RoomId,
+    player_id: &SessionPlayerId,
+    move_notation: &str,
+    seq: Option<usize>,
+    lag_compensation_ms: u64,
+) -> Result<ServerMessage, String> {
     let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
 
     // Check if room exists
     let room = state.rooms.get_mut(room_id).ok_or_else(|| "Room not found".to_string())?;
 
     // Check if player is in the room
-    if !room.players.iter().any(|p| p.id == player_id) {
+    if !room.players.iter().any(|p| &p.id == player_id) {
         return Err("Player not in room".to_string());
     }
 
+    if let Some(seq) = seq {
+        let played = room.moves.len();
+        if seq < played {
+            let record = &room.moves[seq];
+            if &record.player_id == player_id && record.move_notation == move_notation {
+                // Duplicate delivery: answer the sender only, nothing to broadcast
+                let game_state = room.game_state.clone().ok_or_else(|| "Game not started".to_string())?;
+                return Ok(ServerMessage::MoveMade {
+                    room_id: *room_id,
+                    player_id: player_id.clone(),
+                    move_notation: move_notation.to_string(),
+                    game_state,
+                    position: room.position(),
+                    available_actions: room.available_actions(),
+                    timing: room.move_timing(seq).unwrap_or_default(),
+                });
+            }
+            return Err(format!("Out of sequence: move {} was already played", seq));
+        }
+        if seq > played {
+            return Err(format!("Out of sequence: expected move {}, got {}", played, seq));
+        }
+    }
+
+    let now_ms = now_ms();
+
+    // A first move after the deadline calls the game off rather than
+    // losing it on time
+    let grace_ms = state.first_move_timeout_ms + lag_compensation_ms;
+    if let Some(ServerMessage::GameAborted { reason, .. }) = abort_if_overdue(&mut state, room_id, now_ms, grace_ms) {
+        return Err(reason);
+    }
+    let room = state.rooms.get_mut(room_id).ok_or_else(|| "Room not found".to_string())?;
+
     // Check if game has started
     let game_state = room.game_state.as_mut().ok_or_else(|| "Game not started".to_string())?;
 
-    let now_ms = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map_err(|e| format!("System clock error: {}", e))?
-        .as_millis() as u64;
+    // The clocks of a paused game are stopped, so its time is not checked
+    if let Some(adjournment) = &room.adjournment {
+        return Err(format!("Game is paused: {}", adjournment.reason));
+    }
+
+    // A finished game keeps its result however long ago it ended
+    if !matches!(game_state.status, GameStatus::InProgress) {
+        return Err("Game is not active".to_string());
+    }
+
+    // The board would play the move for whichever side is to move
+    let mover = room.players.iter().find(|p| &p.id == player_id).and_then(|p| p.color.as_ref());
+    if mover != Some(&game_state.current_turn) {
+        return Err("Not your turn".to_string());
+    }
 
     // Determine which player is moving based on current turn
     let is_white = matches!(game_state.current_turn, PieceColor::White);
@@ -158,15 +336,15 @@ pub fn send_move(room_id: &str, player_id: &str, move_notation: &str) -> Result<
         .map(|last| now_ms.saturating_sub(last))
         .unwrap_or(0);
 
-    // Check if move is within time (with latency buffer)
-    if elapsed_ms > player_remaining + LATENCY_BUFFER_MS {
+    // Check if move is within time (with lag compensation)
+    if elapsed_ms > player_remaining + lag_compensation_ms {
         // Time exceeded - reject move and end game
         let winner_color = if is_white { "Black" } else { "White" };
         let loser_color = if is_white { "White" } else { "Black" };
 
         log::warn!(
-            "Move rejected: player {} in room {} exceeded time. Elapsed: {}ms, Remaining: {}ms, Buffer: {}ms",
-            player_id, room_id, elapsed_ms, player_remaining, LATENCY_BUFFER_MS
+            "Move rejected: player {} in room {} exceeded time. Elapsed: {}ms, Remaining: {}ms, Lag compensation: {}ms",
+            player_id, room_id, elapsed_ms, player_remaining, lag_compensation_ms
         );
 
         game_state.status = GameStatus::Timeout;
@@ -176,10 +354,10 @@ pub fn send_move(room_id: &str, player_id: &str, move_notation: &str) -> Result<
             (String::new(), String::new()),
             |(winner, loser), p| {
                 match &p.color {
-                    Some(PieceColor::White) if is_white => (winner, p.id.clone()),
-                    Some(PieceColor::White) => (p.id.clone(), loser),
-                    Some(PieceColor::Black) if !is_white => (winner, p.id.clone()),
-                    Some(PieceColor::Black) => (p.id.clone(), loser),
+                    Some(PieceColor::White) if is_white => (winner, p.id.to_string()),
+                    Some(PieceColor::White) => (p.id.to_string(), loser),
+                    Some(PieceColor::Black) if !is_white => (winner, p.id.to_string()),
+                    Some(PieceColor::Black) => (p.id.to_string(), loser),
                     None => (winner, loser),
                 }
             }
@@ -188,46 +366,88 @@ pub fn send_move(room_id: &str, player_id: &str, move_notation: &str) -> Result<
         // Broadcast timeout
         if let Some(sender) = state.message_senders.get(room_id) {
             let timeout_msg = ServerMessage::GameTimeout {
-                room_id: room_id.to_string(),
+                room_id: *room_id,
                 winner_id: winner_id.clone(),
                 loser_id: loser_id.clone(),
                 reason: format!("{} ran out of time", loser_color),
             };
             let _ = sender.send(timeout_msg);
         }
+        hall::publish(&mut state, &[*room_id]);
+        ongoing::publish(&state, &[*room_id]);
 
         return Err(format!("Time expired. {} wins on time.", winner_color));
     }
 
+    // Illegal moves leave the clocks alone
+    play_notation(&mut room.board, move_notation)?;
+
     // Deduct elapsed time from player's clock and add increment
-    if is_white {
+    let remaining_ms = if is_white {
         room.white_remaining_ms = room.white_remaining_ms.saturating_sub(elapsed_ms);
         room.white_remaining_ms += room.increment_ms;
+        room.white_remaining_ms
     } else {
         room.black_remaining_ms = room.black_remaining_ms.saturating_sub(elapsed_ms);
         room.black_remaining_ms += room.increment_ms;
-    }
+        room.black_remaining_ms
+    };
 
     room.last_move_at = Some(now_ms);
     game_state.apply_move(move_notation)?;
+    if let Some((_, termination)) = room.board.outcome() {
+        game_state.status = status_after(termination);
+        game_state.settle_draw(room.armageddon);
+    }
     let game_state_clone = game_state.clone();
-    room.add_move(player_id.to_string(), move_notation.to_string());
+    room.add_move(player_id.clone(), move_notation.to_string(), elapsed_ms, remaining_ms);
+    let ply = room.moves.len();
+    // A takeback asked for before this move would now undo different moves
+    let mut expired: Vec<ServerMessage> = room.expire_offer(OfferKind::Takeback, "A move was played").into_iter().collect();
+    // Moving instead of answering declines the opponent's draw offer
+    if room.pending_draw.as_ref().is_some_and(|offerer| offerer != player_id) {
+        expired.extend(room.expire_offer(OfferKind::Draw, "A move was played"));
+    }
 
+    let position = room.position();
+    let fen = position.fen.clone();
     let response = ServerMessage::MoveMade {
-        room_id: room_id.to_string(),
-        player_id: player_id.to_string(),
+        room_id: *room_id,
+        player_id: player_id.clone(),
         move_notation: move_notation.to_string(),
         game_state: game_state_clone,
+        position,
+        available_actions: room.available_actions(),
+        timing: room.move_timing(ply - 1).unwrap_or_default(),
     };
 
     if let Some(sender) = state.message_senders.get(room_id) {
         let _ = sender.send(response.clone());
+        for message in expired {
+            let _ = sender.send(message);
+        }
     }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
+    eval::announce(*room_id, ply, fen);
 
     Ok(response)
 }
 
-pub fn leave_room(room_id: &str, player_id: &str) -> Result<ServerMessage, String> {
+// Send the room the eval of the position after `ply` moves, if that is
+// still the position on the board
+pub fn broadcast_eval(room_id: &RoomId, ply: usize, evaluation: CompactEval) -> bool {
+    let state = GAME_STATE.lock().unwrap();
+    if state.rooms.get(room_id).is_none_or(|room| room.moves.len() != ply) {
+        return false;
+    }
+    match state.message_senders.get(room_id) {
+        Some(sender) => sender.send(ServerMessage::MoveEval { room_id: *room_id, ply, evaluation }).is_ok(),
+        None => false,
+    }
+}
+
+pub fn leave_room(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
     let mut state = GAME_STATE.lock().unwrap();
 
     // Check if room exists and remove player
@@ -241,8 +461,8 @@ pub fn leave_room(room_id: &str, player_id: &str) -> Result<ServerMessage, Strin
 
     // Create response message
     let response = ServerMessage::PlayerLeft {
-        room_id: room_id.to_string(),
-        player_id: player_id.to_string(),
+        room_id: *room_id,
+        player_id: player_id.clone(),
     };
 
     // Broadcast to all players in the room
@@ -254,13 +474,15 @@ pub fn leave_room(room_id: &str, player_id: &str) -> Result<ServerMessage, Strin
     if should_cleanup {
         state.rooms.remove(room_id);
         state.message_senders.remove(room_id);
+        state.dropped_messages.remove(room_id);
     }
+    ongoing::publish(&state, &[*room_id]);
 
     Ok(response)
 }
 
 // Get game log
-pub fn get_game_log(room_id: &str) -> Result<ServerMessage, String> {
+pub fn get_game_log(room_id: &RoomId) -> Result<ServerMessage, String> {
     let state = GAME_STATE.lock().unwrap();
     
     // Check if room exists
@@ -268,17 +490,232 @@ pub fn get_game_log(room_id: &str) -> Result<ServerMessage, String> {
     
     // Create response message
     let response = ServerMessage::GameLog {
-        room_id: room_id.to_string(),
+        room_id: *room_id,
         moves: room.moves.clone(),
     };
     
     Ok(response)
 }
 
+// Current clocks of a room, for a client that asked to resync
+pub fn clock_sync(room_id: &RoomId, client_time_ms: Option<u64>) -> Result<ServerMessage, String> {
+    let state = GAME_STATE.lock().unwrap();
+    let room = state.rooms.get(room_id).ok_or_else(|| "Room not found".to_string())?;
+    Ok(room.clock_update(now_ms(), client_time_ms))
+}
+
+// Abort the game in a room when a side let `timeout_ms` pass without making
+// its first move, telling the room and the hall showing it
+fn abort_if_overdue(state: &mut ServerState, room_id: &RoomId, now_ms: u64, timeout_ms: u64) -> Option<ServerMessage> {
+    let room = state.rooms.get_mut(room_id)?;
+    let side = room.first_move_overdue(now_ms, timeout_ms)?;
+    let game_state = room.game_state.as_mut()?;
+    game_state.status = GameStatus::Aborted;
+    let game_state = game_state.clone();
+    room.pending_draw = None;
+    room.pending_takeback = None;
+
+    let side_name = match side {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+    let message = ServerMessage::GameAborted {
+        room_id: *room_id,
+        player_id: room.players.iter().find(|p| p.color.as_ref() == Some(&side)).map(|p| p.id.clone()),
+        game_state,
+        reason: format!("Game aborted: {} did not make a first move", side_name),
+        available_actions: room.available_actions(),
+    };
+    log::info!("Aborted the game in room {}: {} did not make a first move", room_id, side_name);
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(message.clone());
+    }
+    hall::publish(state, &[*room_id]);
+    ongoing::publish(state, &[*room_id]);
+    Some(message)
+}
+
+// Abort every game whose first move is overdue. Returns the number aborted.
+pub fn abort_overdue_games() -> usize {
+    let mut state = GAME_STATE.lock().unwrap();
+    let now = now_ms();
+    let timeout_ms = state.first_move_timeout_ms;
+    let room_ids: Vec<RoomId> = state.rooms.keys().copied().collect();
+    room_ids
+        .iter()
+        .filter(|room_id| abort_if_overdue(&mut state, room_id, now, timeout_ms).is_some())
+        .count()
+}
+
+// Withdraw the offers of a room left unanswered past the offer TTL, telling
+// the room. Returns the number withdrawn.
+fn expire_offers_in(state: &mut ServerState, room_id: &RoomId, now_ms: u64) -> usize {
+    let ttl_ms = state.offer_ttl_ms;
+    let Some(room) = state.rooms.get_mut(room_id) else {
+        return 0;
+    };
+    let expired = room.expire_stale_offers(now_ms, ttl_ms);
+    if let Some(sender) = state.message_senders.get(room_id) {
+        for message in &expired {
+            let _ = sender.send(message.clone());
+        }
+    }
+    expired.len()
+}
+
+// Withdraw every offer left unanswered past the offer TTL. Returns the number
+// withdrawn.
+pub fn expire_stale_offers() -> usize {
+    let mut state = GAME_STATE.lock().unwrap();
+    let now = now_ms();
+    let room_ids: Vec<RoomId> = state.rooms.keys().copied().collect();
+    room_ids.iter().map(|room_id| expire_offers_in(&mut state, room_id, now)).sum()
+}
+
+// Stop a game in progress, charging the side to move for its time so far,
+// until it is resumed. Used by arbiters for disputes and hybrid events.
+pub fn pause_game(room_id: &RoomId, reason: &str) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    let room = state.rooms.get_mut(room_id).ok_or_else(|| "Room not found".to_string())?;
+    let now = now_ms();
+    let running = room.running_clock();
+    let game_state = room.game_state.as_mut().ok_or_else(|| "Game not started".to_string())?;
+    if !matches!(game_state.status, GameStatus::InProgress) {
+        return Err("Only games in progress can be paused".to_string());
+    }
+
+    let elapsed_ms = room.last_move_at.map_or(0, |last| now.saturating_sub(last));
+    match running {
+        Some(PieceColor::White) => room.white_remaining_ms = room.white_remaining_ms.saturating_sub(elapsed_ms),
+        Some(PieceColor::Black) => room.black_remaining_ms = room.black_remaining_ms.saturating_sub(elapsed_ms),
+        None => {}
+    }
+    game_state.status = GameStatus::Paused;
+    room.adjournment = Some(Adjournment {
+        reason: reason.to_string(),
+        paused_at_ms: now,
+        clock_running: room.last_move_at.is_some(),
+    });
+    room.last_move_at = None;
+
+    let response = ServerMessage::GamePaused {
+        room_id: *room_id,
+        reason: reason.to_string(),
+        white_remaining_ms: room.white_remaining_ms,
+        black_remaining_ms: room.black_remaining_ms,
+    };
+    log::info!("Paused the game in room {}: {}", room_id, reason);
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(response.clone());
+    }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
+    Ok(response)
+}
+
+// Restart a paused game where it stopped
+pub fn resume_game(room_id: &RoomId) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    let room = state.rooms.get_mut(room_id).ok_or_else(|| "Room not found".to_string())?;
+    let adjournment = room.adjournment.take().ok_or_else(|| "Game is not paused".to_string())?;
+    let now = now_ms();
+    let game_state = room.game_state.as_mut().ok_or_else(|| "Game not started".to_string())?;
+    game_state.status = GameStatus::InProgress;
+    let game_state = game_state.clone();
+    room.last_move_at = adjournment.clock_running.then_some(now);
+
+    let response = ServerMessage::GameResumed {
+        room_id: *room_id,
+        game_state,
+        white_remaining_ms: room.white_remaining_ms,
+        black_remaining_ms: room.black_remaining_ms,
+        available_actions: room.available_actions(),
+    };
+    log::info!(
+        "Resumed the game in room {} after {}ms",
+        room_id,
+        now.saturating_sub(adjournment.paused_at_ms)
+    );
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(response.clone());
+    }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
+    Ok(response)
+}
+
+// Send the clocks of every room with a running clock to its players, and
+// to the halls showing the tournament boards among them, along with an alert
+// for each side that just ran low on time. Returns the number of rooms
+// updated.
+pub fn broadcast_clock_updates() -> usize {
+    let mut state = GAME_STATE.lock().unwrap();
+    let now = now_ms();
+    let low_time_ms = state.low_time_ms;
+    let ServerState { rooms, message_senders, .. } = &mut *state;
+    let mut running = Vec::new();
+    for (room_id, room) in rooms.iter_mut() {
+        if room.running_clock().is_none() {
+            continue;
+        }
+        if let Some(sender) = message_senders.get(room_id) {
+            // Rooms nobody listens to have no receivers; that is fine
+            let _ = sender.send(room.clock_update(now, None));
+            for alert in room.low_time_alerts(now, low_time_ms) {
+                let _ = sender.send(alert);
+            }
+            running.push(*room_id);
+        }
+    }
+    hall::publish(&mut state, &running);
+    running.len()
+}
+
+// Bring a receiver that fell behind by `missed` messages back in step: the
+// messages still queued for it are discarded and replaced by the room as it
+// stands now. Broadcasts happen under the state lock, so none are lost or
+// repeated between the two.
+pub fn resync(room_id: &RoomId, receiver: &mut MessageReceiver, missed: u64) -> Result<Vec<ServerMessage>, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    *receiver = receiver.resubscribe();
+
+    let dropped = state.dropped_messages.entry(*room_id).or_insert(0);
+    *dropped += missed;
+    log::warn!(
+        "A receiver in room {} missed {} messages ({} dropped in the room so far)",
+        room_id, missed, dropped
+    );
+
+    room_snapshot(&state, room_id, missed)
+}
+
+// The room as it stands, for a client that has not seen `missed` of its
+// messages
+pub fn room_snapshot(state: &ServerState, room_id: &RoomId, missed: u64) -> Result<Vec<ServerMessage>, String> {
+    let room = state.rooms.get(room_id).ok_or_else(|| "Room not found".to_string())?;
+    Ok(vec![
+        ServerMessage::Resync {
+            room_id: *room_id,
+            missed,
+            players: room.players.clone(),
+            game_state: room.game_state.clone(),
+            moves: room.moves.clone(),
+            position: room.position(),
+            clock: room.clock_settings(state.low_time_ms),
+            available_actions: room.available_actions(),
+        },
+        room.clock_update(now_ms(), None),
+    ])
+}
+
 // Handle a takeback offer from a player.
 // Current behavior: only board state and move history are affected; clocks/time controls are not modified.
-pub fn offer_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, String> {
+pub fn offer_takeback(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
     let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
 
     let room = state
         .rooms
@@ -286,10 +723,16 @@ pub fn offer_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, S
         .ok_or_else(|| "Room not found".to_string())?;
 
     // Ensure player is in the room
-    if !room.players.iter().any(|p| p.id == player_id) {
+    if !room.players.iter().any(|p| &p.id == player_id) {
         return Err("Player not in room".to_string());
     }
 
+    if !matches!(room.game_state.as_ref().map(|g| &g.status), Some(GameStatus::InProgress)) {
+        return Err("Game is not active".to_string());
+    }
+
+    room.may_take_back(player_id)?;
+
     // Require at least one full move (two half-moves) to be able to take back
     if room.moves.len() < 2 {
         return Err("Not enough moves to take back".to_string());
@@ -300,11 +743,11 @@ pub fn offer_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, S
         return Err("A takeback request is already pending".to_string());
     }
 
-    room.pending_takeback = Some(player_id.to_string());
+    room.make_offer(OfferKind::Takeback, player_id.clone(), now_ms());
 
     let response = ServerMessage::TakebackOffered {
-        room_id: room_id.to_string(),
-        requester_id: player_id.to_string(),
+        room_id: *room_id,
+        requester_id: player_id.clone(),
     };
 
     if let Some(sender) = state.message_senders.get(room_id) {
@@ -315,8 +758,9 @@ pub fn offer_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, S
 }
 
 // Accept a pending takeback request and roll back one full move (two half-moves).
-pub fn accept_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, String> {
+pub fn accept_takeback(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
     let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
 
     let room = state
         .rooms
@@ -324,7 +768,7 @@ pub fn accept_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage,
         .ok_or_else(|| "Room not found".to_string())?;
 
     // Ensure player is in the room
-    if !room.players.iter().any(|p| p.id == player_id) {
+    if !room.players.iter().any(|p| &p.id == player_id) {
         return Err("Player not in room".to_string());
     }
 
@@ -335,44 +779,50 @@ pub fn accept_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage,
     };
 
     // Only the other player (not requester) can accept
-    if requester_id == player_id {
+    if &requester_id == player_id {
         return Err("Requester cannot accept their own takeback".to_string());
     }
 
+    // The game may have ended since the request
+    if !matches!(room.game_state.as_ref().map(|g| &g.status), Some(GameStatus::InProgress)) {
+        return Err("Game is not active".to_string());
+    }
+
     // Need at least one full move (two half-moves) to roll back
     if room.moves.len() < 2 {
         return Err("Not enough moves to take back".to_string());
     }
 
-    // Truncate last two half-moves
-    let new_len = room.moves.len() - 2;
-    room.moves.truncate(new_len);
+    // Unmake the last two half-moves
+    room.take_back(2)?;
 
-    // Rebuild game state from initial position and remaining moves
     let mut game_state = GameState::new_game();
-    for mv in &room.moves {
-        game_state.apply_move(&mv.move_notation)?;
-    }
-
+    game_state.current_turn = room.side_to_move();
     room.game_state = Some(game_state.clone());
     room.pending_takeback = None;
+    *room.takebacks_used.entry(requester_id).or_insert(0) += 1;
 
     let response = ServerMessage::TakebackAccepted {
-        room_id: room_id.to_string(),
+        room_id: *room_id,
         game_state,
         moves: room.moves.clone(),
+        position: room.position(),
+        available_actions: room.available_actions(),
     };
 
     if let Some(sender) = state.message_senders.get(room_id) {
         let _ = sender.send(response.clone());
     }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
 
     Ok(response)
 }
 
 // Reject a pending takeback request.
-pub fn reject_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage, String> {
+pub fn reject_takeback(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
     let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
 
     let room = state
         .rooms
@@ -380,7 +830,7 @@ pub fn reject_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage,
         .ok_or_else(|| "Room not found".to_string())?;
 
     // Ensure player is in the room
-    if !room.players.iter().any(|p| p.id == player_id) {
+    if !room.players.iter().any(|p| &p.id == player_id) {
         return Err("Player not in room".to_string());
     }
 
@@ -392,8 +842,128 @@ pub fn reject_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage,
     room.pending_takeback = None;
 
     let response = ServerMessage::TakebackRejected {
-        room_id: room_id.to_string(),
-        by_player_id: player_id.to_string(),
+        room_id: *room_id,
+        by_player_id: player_id.clone(),
+    };
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(response.clone());
+    }
+
+    Ok(response)
+}
+
+// Offer a draw; the offer stands until the opponent answers or moves, or
+// it expires unanswered.
+pub fn offer_draw(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
+
+    let room = state
+        .rooms
+        .get_mut(room_id)
+        .ok_or_else(|| "Room not found".to_string())?;
+
+    if !room.players.iter().any(|p| &p.id == player_id) {
+        return Err("Player not in room".to_string());
+    }
+
+    if !matches!(room.game_state.as_ref().map(|g| &g.status), Some(GameStatus::InProgress)) {
+        return Err("Game is not active".to_string());
+    }
+
+    if room.pending_draw.is_some() {
+        return Err("A draw offer is already pending".to_string());
+    }
+
+    room.make_offer(OfferKind::Draw, player_id.clone(), now_ms());
+
+    let response = ServerMessage::DrawOffered {
+        room_id: *room_id,
+        player_id: player_id.clone(),
+    };
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(response.clone());
+    }
+
+    Ok(response)
+}
+
+// Accept the opponent's draw offer, ending the game.
+pub fn accept_draw(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
+
+    let room = state
+        .rooms
+        .get_mut(room_id)
+        .ok_or_else(|| "Room not found".to_string())?;
+
+    if !room.players.iter().any(|p| &p.id == player_id) {
+        return Err("Player not in room".to_string());
+    }
+
+    match &room.pending_draw {
+        Some(offerer) if offerer == player_id => {
+            return Err("Players cannot accept their own draw offer".to_string())
+        }
+        Some(_) => {}
+        None => return Err("No pending draw offer".to_string()),
+    }
+
+    let game_state = room.game_state.as_mut().ok_or_else(|| "Game not started".to_string())?;
+    // The game may have ended since the offer
+    if !matches!(game_state.status, GameStatus::InProgress) {
+        return Err("Game is not active".to_string());
+    }
+    game_state.status = GameStatus::Draw;
+    game_state.settle_draw(room.armageddon);
+    let game_state = game_state.clone();
+    room.pending_draw = None;
+
+    let response = ServerMessage::DrawAccepted {
+        room_id: *room_id,
+        game_state,
+        available_actions: room.available_actions(),
+    };
+
+    if let Some(sender) = state.message_senders.get(room_id) {
+        let _ = sender.send(response.clone());
+    }
+    hall::publish(&mut state, &[*room_id]);
+    ongoing::publish(&state, &[*room_id]);
+
+    Ok(response)
+}
+
+// Decline the opponent's draw offer.
+pub fn decline_draw(room_id: &RoomId, player_id: &SessionPlayerId) -> Result<ServerMessage, String> {
+    let mut state = GAME_STATE.lock().unwrap();
+    expire_offers_in(&mut state, room_id, now_ms());
+
+    let room = state
+        .rooms
+        .get_mut(room_id)
+        .ok_or_else(|| "Room not found".to_string())?;
+
+    if !room.players.iter().any(|p| &p.id == player_id) {
+        return Err("Player not in room".to_string());
+    }
+
+    match &room.pending_draw {
+        Some(offerer) if offerer == player_id => {
+            return Err("Players cannot decline their own draw offer".to_string())
+        }
+        Some(_) => {}
+        None => return Err("No pending draw offer".to_string()),
+    }
+
+    room.pending_draw = None;
+
+    let response = ServerMessage::DrawDeclined {
+        room_id: *room_id,
+        by_player_id: player_id.clone(),
     };
 
     if let Some(sender) = state.message_senders.get(room_id) {
@@ -406,12 +976,12 @@ pub fn reject_takeback(room_id: &str, player_id: &str) -> Result<ServerMessage,
 // Database integration functions
 // These are placeholders for future implementation
 
-pub fn save_game_to_db(_room_id: &str) -> Result<(), String> {
+pub fn save_game_to_db(_room_id: &RoomId) -> Result<(), String> {
     // In a real implementation, this would save the game state to a database
     Ok(())
 }
 
-pub fn load_game_from_db(_room_id: &str) -> Result<Room, String> {
+pub fn load_game_from_db(_room_id: &RoomId) -> Result<Room, String> {
     // In a real implementation, this would load the game state from a database
     Err("Not implemented".to_string())
 }
@@ -419,79 +989,264 @@ pub fn load_game_from_db(_room_id: &str) -> Result<Room, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
-    use std::time::Duration;
+    use crate::latency::{with_time_source, DEFAULT_LAG_COMPENSATION_MS, MIN_LAG_COMPENSATION_MS};
+    use crate::models::MoveTiming;
+    use chess::ManualTime;
 
-    fn cleanup_room(room_id: &str) {
+    fn player(id: &str) -> SessionPlayerId {
+        id.parse().unwrap()
+    }
+
+    // Run `f` on a manual clock, which it advances instead of sleeping
+    fn on_manual_clock(f: impl FnOnce(&ManualTime)) {
+        let time = Arc::new(ManualTime::new(chess::time_source::now_ms()));
+        with_time_source(time.clone(), || f(&time));
+    }
+
+    fn cleanup_room(room_id: &RoomId) {
         let mut state = GAME_STATE.lock().unwrap();
         state.rooms.remove(room_id);
         state.message_senders.remove(room_id);
+        state.dropped_messages.remove(room_id);
     }
 
     #[test]
     fn test_move_within_time() {
         let room_id = create_room_with_time(10_000, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        let result = send_move(&room_id, "white_player", "e2e4");
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        let result = send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS);
         assert!(result.is_ok());
         cleanup_room(&room_id);
     }
 
+    #[test]
+    fn test_moves_carry_their_timing_and_evals_follow_them() {
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(60_000, 2_000);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+
+            time.advance(3_000);
+            send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+            time.advance(1_000);
+            send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+            time.advance(5_000);
+            match send_move(&room_id, &player("white_player"), "g1f3", None, DEFAULT_LAG_COMPENSATION_MS) {
+                Ok(ServerMessage::MoveMade { timing, .. }) => {
+                    assert_eq!(timing, MoveTiming { spent_ms: 5_000, remaining_ms: 56_000, average_ms: 4_000 });
+                }
+                other => panic!("expected MoveMade, got {:?}", other),
+            }
+            // A retry is answered with the timing of the move it repeats
+            match send_move(&room_id, &player("white_player"), "e2e4", Some(0), DEFAULT_LAG_COMPENSATION_MS) {
+                Ok(ServerMessage::MoveMade { timing, .. }) => assert_eq!(timing.spent_ms, 3_000),
+                other => panic!("expected MoveMade, got {:?}", other),
+            }
+
+            let evaluation = CompactEval { evaluation: 0.2, depth: 18, best_move: Some("b8c6".to_string()) };
+            // The board has moved on from the second move
+            assert!(!broadcast_eval(&room_id, 2, evaluation.clone()));
+            assert!(broadcast_eval(&room_id, 3, evaluation));
+            let evals = std::iter::from_fn(|| receiver.try_recv().ok())
+                .filter(|message| matches!(message, ServerMessage::MoveEval { ply: 3, .. }))
+                .count();
+            assert_eq!(evals, 1);
+            cleanup_room(&room_id);
+        });
+    }
+
     #[test]
     fn test_move_after_flag_fall() {
-        let room_id = create_room_with_time(1000, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        thread::sleep(Duration::from_millis(2000));
-        let result = send_move(&room_id, "white_player", "e2e4");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Time expired"));
-        cleanup_room(&room_id);
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(1000, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(2_000);
+            let result = send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS);
+            assert!(result.is_err());
+            assert!(result.unwrap_err().contains("Time expired"));
+            cleanup_room(&room_id);
+        });
     }
 
     #[test]
     fn test_move_within_latency_buffer() {
-        let room_id = create_room_with_time(500, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        thread::sleep(Duration::from_millis(800));
-        let result = send_move(&room_id, "white_player", "e2e4");
-        assert!(result.is_ok());
-        cleanup_room(&room_id);
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(500, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(800);
+            let result = send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS);
+            assert!(result.is_ok());
+            cleanup_room(&room_id);
+        });
     }
 
     #[test]
     fn test_move_after_latency_buffer() {
-        let room_id = create_room_with_time(500, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        thread::sleep(Duration::from_millis(1500));
-        let result = send_move(&room_id, "white_player", "e2e4");
-        assert!(result.is_err());
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(500, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(1_500);
+            let result = send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS);
+            assert!(result.is_err());
+            cleanup_room(&room_id);
+        });
+    }
+
+    #[test]
+    fn test_fast_connection_gets_less_grace() {
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(500, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(800);
+            let result = send_move(&room_id, &player("white_player"), "e2e4", None, MIN_LAG_COMPENSATION_MS);
+            assert!(result.unwrap_err().contains("Time expired"));
+            cleanup_room(&room_id);
+        });
+    }
+
+    #[test]
+    fn test_clock_sync_counts_running_clock_down() {
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(10_000, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            let waiting = clock_sync(&room_id, Some(42)).unwrap();
+            assert!(matches!(
+                waiting,
+                ServerMessage::ClockUpdate { running: None, white_remaining_ms: 10_000, client_time_ms: Some(42), .. }
+            ));
+
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(100);
+            match clock_sync(&room_id, None).unwrap() {
+                ServerMessage::ClockUpdate { running, white_remaining_ms, black_remaining_ms, .. } => {
+                    assert!(matches!(running, Some(PieceColor::White)));
+                    assert!(white_remaining_ms <= 9_900);
+                    assert_eq!(black_remaining_ms, 10_000);
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+
+            let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+            assert!(broadcast_clock_updates() >= 1);
+            assert!(matches!(receiver.try_recv(), Ok(ServerMessage::ClockUpdate { .. })));
+            cleanup_room(&room_id);
+        });
+    }
+
+    #[test]
+    fn test_room_joined_carries_the_clock_settings() {
+        let room_id = create_room_with_time(180_000, 2_000);
+        match join_room(&room_id, &player("white_player"), None).unwrap() {
+            ServerMessage::RoomJoined { clock, .. } => {
+                assert_eq!(clock.white_initial_ms, 180_000);
+                assert_eq!(clock.black_initial_ms, 180_000);
+                assert_eq!(clock.increment_ms, 2_000);
+                assert!(clock.low_time_ms > 0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
         cleanup_room(&room_id);
     }
 
     #[test]
-    fn test_clock_deduction() {
-        let room_id = create_room_with_time(10_000, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        thread::sleep(Duration::from_millis(100));
-        send_move(&room_id, "white_player", "e2e4").unwrap();
-        let state = GAME_STATE.lock().unwrap();
-        let room = state.rooms.get(&room_id).unwrap();
-        assert!(room.white_remaining_ms < 10_000);
-        assert_eq!(room.black_remaining_ms, 10_000);
+    fn test_low_time_is_announced_once_per_drop() {
+        let room_id = create_room_with_time(5_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+
+        let mut state = GAME_STATE.lock().unwrap();
+        let room = state.rooms.get_mut(&room_id).unwrap();
+        let now = now_ms();
+        assert!(room.low_time_alerts(now, 4_000).is_empty());
+
+        let alerts = room.low_time_alerts(now + 2_000, 4_000);
+        assert!(matches!(
+            alerts.as_slice(),
+            [ServerMessage::LowTime { color: PieceColor::White, threshold_ms: 4_000, .. }]
+        ));
+        assert!(room.low_time_alerts(now + 3_000, 4_000).is_empty());
+        // Black is not warned while its clock is stopped
+        assert!(room.low_time_alerts(now + 3_000, 6_000).is_empty());
         drop(state);
         cleanup_room(&room_id);
     }
 
+    #[test]
+    fn test_unanswered_offers_expire() {
+        let room_id = create_room_with_time(60_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        offer_draw(&room_id, &player("white_player")).unwrap();
+        let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+
+        assert_eq!(expire_stale_offers_for(&room_id, 0), 0);
+        let ttl_ms = GAME_STATE.lock().unwrap().offer_ttl_ms;
+        assert_eq!(expire_stale_offers_for(&room_id, ttl_ms), 1);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ServerMessage::OfferExpired { offer: OfferKind::Draw, player_id, .. }) if player_id == player("white_player")
+        ));
+        // A stale accept finds nothing to accept
+        assert!(accept_draw(&room_id, &player("black_player")).is_err());
+        cleanup_room(&room_id);
+    }
+
+    // Age the offers of a room by `age_ms` and sweep them
+    fn expire_stale_offers_for(room_id: &RoomId, age_ms: u64) -> usize {
+        let mut state = GAME_STATE.lock().unwrap();
+        let now = now_ms();
+        let room = state.rooms.get_mut(room_id).unwrap();
+        room.draw_offered_at = room.draw_offered_at.map(|at| at.saturating_sub(age_ms));
+        room.takeback_offered_at = room.takeback_offered_at.map(|at| at.saturating_sub(age_ms));
+        expire_offers_in(&mut state, room_id, now)
+    }
+
+    #[test]
+    fn test_move_expires_takeback_request() {
+        let room_id = create_room_with_time(60_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        offer_takeback(&room_id, &player("white_player")).unwrap();
+        let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+
+        send_move(&room_id, &player("white_player"), "g1f3", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        assert!(matches!(receiver.try_recv(), Ok(ServerMessage::MoveMade { .. })));
+        assert!(matches!(receiver.try_recv(), Ok(ServerMessage::OfferExpired { offer: OfferKind::Takeback, .. })));
+        assert!(accept_takeback(&room_id, &player("black_player")).is_err());
+        assert_eq!(GAME_STATE.lock().unwrap().rooms[&room_id].moves.len(), 3);
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_clock_deduction() {
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(10_000, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(100);
+            send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+            let state = GAME_STATE.lock().unwrap();
+            let room = state.rooms.get(&room_id).unwrap();
+            assert!(room.white_remaining_ms < 10_000);
+            assert_eq!(room.black_remaining_ms, 10_000);
+            drop(state);
+            cleanup_room(&room_id);
+        });
+    }
+
     #[test]
     fn test_increment_applied() {
         let room_id = create_room_with_time(10_000, 2_000);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
         
         // Capture initial time before the move
         let initial_time = {
@@ -500,7 +1255,7 @@ mod tests {
             room.white_remaining_ms
         };
         
-        send_move(&room_id, "white_player", "e2e4").unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
         
         let state = GAME_STATE.lock().unwrap();
         let room = state.rooms.get(&room_id).unwrap();
@@ -519,16 +1274,239 @@ mod tests {
 
     #[test]
     fn test_game_timeout_status() {
-        let room_id = create_room_with_time(100, 0);
-        join_room(&room_id, "white_player", None).unwrap();
-        join_room(&room_id, "black_player", None).unwrap();
-        thread::sleep(Duration::from_millis(1000));
-        let _ = send_move(&room_id, "white_player", "e2e4");
+        on_manual_clock(|time| {
+            let room_id = create_room_with_time(100, 0);
+            join_room(&room_id, &player("white_player"), None).unwrap();
+            join_room(&room_id, &player("black_player"), None).unwrap();
+            time.advance(1_000);
+            let _ = send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS);
+            let state = GAME_STATE.lock().unwrap();
+            let room = state.rooms.get(&room_id).unwrap();
+            let game_state = room.game_state.as_ref().unwrap();
+            assert!(matches!(game_state.status, GameStatus::Timeout));
+            drop(state);
+            cleanup_room(&room_id);
+        });
+    }
+
+    #[test]
+    fn test_paused_game_stops_clocks_and_moves() {
+        let room_id = create_room_with_time(60_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+
+        assert!(resume_game(&room_id).is_err());
+        pause_game(&room_id, "Disputed position").unwrap();
+        assert!(pause_game(&room_id, "Again").is_err());
+        let refused = send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS);
+        assert_eq!(refused.unwrap_err(), "Game is paused: Disputed position");
+        {
+            let state = GAME_STATE.lock().unwrap();
+            let room = &state.rooms[&room_id];
+            assert_eq!(room.running_clock(), None);
+            // The pause is saved with the room
+            let saved: Room = serde_json::from_str(&serde_json::to_string(room).unwrap()).unwrap();
+            assert_eq!(saved.position(), room.position());
+            assert_eq!(saved.adjournment.map(|a| a.reason), Some("Disputed position".to_string()));
+        }
+
+        match resume_game(&room_id) {
+            Ok(ServerMessage::GameResumed { game_state, .. }) => assert!(matches!(game_state.status, GameStatus::InProgress)),
+            other => panic!("expected GameResumed, got {:?}", other),
+        }
+        assert_eq!(GAME_STATE.lock().unwrap().rooms[&room_id].running_clock(), Some(PieceColor::Black));
+        send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_unstarted_game_is_aborted() {
+        let room_id = create_room_with_time(10_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+
+        let mut state = GAME_STATE.lock().unwrap();
+        let later = now_ms() + DEFAULT_FIRST_MOVE_TIMEOUT_MS + 1_000;
+        assert!(abort_if_overdue(&mut state, &room_id, now_ms(), DEFAULT_FIRST_MOVE_TIMEOUT_MS).is_none());
+        let aborted = abort_if_overdue(&mut state, &room_id, later, DEFAULT_FIRST_MOVE_TIMEOUT_MS);
+        match aborted {
+            Some(ServerMessage::GameAborted { player_id, game_state, .. }) => {
+                assert_eq!(player_id, Some(player("black_player")));
+                assert!(matches!(game_state.status, GameStatus::Aborted));
+            }
+            other => panic!("expected GameAborted, got {:?}", other),
+        }
+        drop(state);
+
+        assert!(matches!(receiver.try_recv(), Ok(ServerMessage::GameAborted { .. })));
+        let late = send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS);
+        assert!(late.is_err());
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_started_game_is_not_aborted() {
+        let room_id = create_room_with_time(10_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+
+        let mut state = GAME_STATE.lock().unwrap();
+        let later = now_ms() + DEFAULT_FIRST_MOVE_TIMEOUT_MS + 1_000;
+        assert!(abort_if_overdue(&mut state, &room_id, later, DEFAULT_FIRST_MOVE_TIMEOUT_MS).is_none());
+        drop(state);
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_join_unknown_room_fails() {
+        let room_id = RoomId::new();
+        let result = join_room(&room_id, &player("white_player"), None);
+        assert_eq!(result.unwrap_err(), "Room not found");
+        assert!(get_room_sender(&room_id).is_none());
+    }
+
+    #[test]
+    fn test_ensure_room_keeps_existing_room() {
+        let room_id = RoomId::new();
+        ensure_room(&room_id);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        ensure_room(&room_id);
+        let result = join_room(&room_id, &player("white_player"), None);
+        assert_eq!(result.unwrap_err(), "Player is already in the room");
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_retried_move_is_not_applied_twice() {
+        let room_id = create_room_with_time(10_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+        send_move(&room_id, &player("white_player"), "e2e4", Some(0), DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        let retry = send_move(&room_id, &player("white_player"), "e2e4", Some(0), DEFAULT_LAG_COMPENSATION_MS);
+        assert!(matches!(retry, Ok(ServerMessage::MoveMade { .. })));
+
         let state = GAME_STATE.lock().unwrap();
         let room = state.rooms.get(&room_id).unwrap();
-        let game_state = room.game_state.as_ref().unwrap();
-        assert!(matches!(game_state.status, GameStatus::Timeout));
+        assert_eq!(room.moves.len(), 1);
+        assert!(matches!(room.game_state.as_ref().unwrap().current_turn, PieceColor::Black));
         drop(state);
+
+        // A different move with a stale or future number is refused
+        let stale = send_move(&room_id, &player("black_player"), "e7e5", Some(0), DEFAULT_LAG_COMPENSATION_MS);
+        assert!(stale.unwrap_err().contains("Out of sequence"));
+        let ahead = send_move(&room_id, &player("black_player"), "e7e5", Some(2), DEFAULT_LAG_COMPENSATION_MS);
+        assert!(ahead.unwrap_err().contains("Out of sequence"));
+        send_move(&room_id, &player("black_player"), "e7e5", Some(1), DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_move_made_carries_position() {
+        let room_id = create_room_with_time(10_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+
+        let illegal = send_move(&room_id, &player("white_player"), "e2e5", None, DEFAULT_LAG_COMPENSATION_MS);
+        assert!(illegal.is_err());
+
+        match send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS) {
+            Ok(ServerMessage::MoveMade { position, .. }) => {
+                assert_eq!(position.fen, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+                assert_eq!(position.fullmove_number, 1);
+                assert_eq!(position.halfmove_clock, 0);
+            }
+            other => panic!("expected MoveMade, got {:?}", other),
+        }
+        match send_move(&room_id, &player("black_player"), "Nf6", None, DEFAULT_LAG_COMPENSATION_MS) {
+            Ok(ServerMessage::MoveMade { position, .. }) => {
+                assert_eq!(position.fullmove_number, 2);
+                assert_eq!(position.halfmove_clock, 1);
+            }
+            other => panic!("expected MoveMade, got {:?}", other),
+        }
+        cleanup_room(&room_id);
+    }
+
+    #[test]
+    fn test_lagged_receiver_is_resynced() {
+        let room_id = create_room_with_time(10_000, 0);
+        join_room(&room_id, &player("white_player"), None).unwrap();
+        join_room(&room_id, &player("black_player"), None).unwrap();
+
+        let mut receiver = get_room_sender(&room_id).unwrap().subscribe();
+        let capacity = GAME_STATE.lock().unwrap().broadcast_capacity;
+        // Tokio rounds the capacity up to a power of two
+        for _ in 0..capacity * 2 {
+            broadcast_clock_updates();
+        }
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+
+        let missed = match receiver.try_recv() {
+            Err(broadcast::error::TryRecvError::Lagged(missed)) => missed,
+            other => panic!("expected the receiver to lag, got {:?}", other),
+        };
+        let messages = resync(&room_id, &mut receiver, missed).unwrap();
+        match &messages[0] {
+            ServerMessage::Resync { moves, position, .. } => {
+                assert_eq!(moves.len(), 1);
+                assert_eq!(position.fen, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+            }
+            other => panic!("expected Resync, got {:?}", other),
+        }
+        assert!(matches!(messages[1], ServerMessage::ClockUpdate { .. }));
+
+        // The backlog is gone; only what is sent from now on arrives
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(GAME_STATE.lock().unwrap().dropped_messages.get(&room_id), Some(&missed));
         cleanup_room(&room_id);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_messages_carry_the_available_actions() {
+        let settings = GameSettings { takebacks: crate::models::TakebackPolicy::Limited(1), ..GameSettings::default() };
+        let room_id = create_room_with_clocks(10_000, 10_000, 0, false, settings);
+        match join_room(&room_id, &player("white_player"), None).unwrap() {
+            ServerMessage::RoomJoined { available_actions, .. } => assert_eq!(available_actions, Default::default()),
+            other => panic!("unexpected {:?}", other),
+        }
+        match join_room(&room_id, &player("black_player"), None).unwrap() {
+            ServerMessage::RoomJoined { available_actions, .. } => {
+                assert!(available_actions.white.can_abort && available_actions.white.can_resign);
+                assert!(!available_actions.white.can_takeback);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        send_move(&room_id, &player("white_player"), "e2e4", None, DEFAULT_LAG_COMPENSATION_MS).unwrap();
+        match send_move(&room_id, &player("black_player"), "e7e5", None, DEFAULT_LAG_COMPENSATION_MS).unwrap() {
+            ServerMessage::MoveMade { available_actions, .. } => {
+                assert!(!available_actions.black.can_abort);
+                assert!(available_actions.black.can_takeback && available_actions.white.can_offer_draw);
+                assert!(!available_actions.white.can_claim_draw);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Black used its only takeback
+        offer_takeback(&room_id, &player("black_player")).unwrap();
+        match accept_takeback(&room_id, &player("white_player")).unwrap() {
+            ServerMessage::TakebackAccepted { available_actions, .. } => {
+                assert!(available_actions.white.can_abort);
+                assert!(!available_actions.black.can_takeback);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        offer_draw(&room_id, &player("white_player")).unwrap();
+        match accept_draw(&room_id, &player("black_player")).unwrap() {
+            ServerMessage::DrawAccepted { available_actions, .. } => assert_eq!(available_actions, Default::default()),
+            other => panic!("unexpected {:?}", other),
+        }
+        cleanup_room(&room_id);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use uuid::Uuid;
+
+use crate::chat;
+use crate::game::{ServerState, GAME_STATE};
+use crate::latency::now_ms;
+use crate::models::{GameStatus, PieceColor, Room, RoomId, ServerMessage};
+
+// What the hall view shows of one board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardSummary {
+    pub board: u32,
+    pub room_id: RoomId,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub last_move: Option<String>,
+    pub white_ms: u64,
+    pub black_ms: u64,
+    // "1-0", "0-1" or "1/2-1/2" once the game is over, "*" if aborted
+    pub result: Option<String>,
+}
+
+// The fields of a board that changed since the previous update. Fields
+// left out are unchanged; a field sent as null was cleared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BoardDelta {
+    pub board: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<RoomId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub white: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub black: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub white_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub black_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Option<String>>,
+}
+
+// The boards of one tournament round as last sent to its watchers. Every
+// update is numbered so a watcher can tell when it missed one.
+pub struct Hall {
+    sender: broadcast::Sender<ServerMessage>,
+    boards: BTreeMap<u32, BoardSummary>,
+    seq: u64,
+}
+
+impl Hall {
+    fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender, boards: BTreeMap::new(), seq: 0 }
+    }
+
+    fn snapshot(&self, tournament_id: Uuid, round: u32) -> ServerMessage {
+        ServerMessage::HallSnapshot {
+            tournament_id,
+            round,
+            seq: self.seq,
+            boards: self.boards.values().cloned().collect(),
+        }
+    }
+}
+
+// A room's game as the hall shows it at `now_ms`
+pub fn summary(room: &Room, board: u32, now_ms: u64) -> BoardSummary {
+    let name = |color: PieceColor| {
+        room.players
+            .iter()
+            .find(|p| p.color.as_ref() == Some(&color))
+            .map(|p| p.name.clone())
+    };
+    let (white_ms, black_ms) = match room.clock_update(now_ms, None) {
+        ServerMessage::ClockUpdate { white_remaining_ms, black_remaining_ms, .. } => {
+            (white_remaining_ms, black_remaining_ms)
+        }
+        _ => (room.white_remaining_ms, room.black_remaining_ms),
+    };
+    let result = room.game_state.as_ref().and_then(|game_state| {
+        let loser = |color: &PieceColor| match color {
+            PieceColor::White => "0-1",
+            PieceColor::Black => "1-0",
+        };
+        match (&game_state.status, &game_state.awarded_to) {
+            (GameStatus::Waiting | GameStatus::InProgress | GameStatus::Paused, _) => None,
+            // The side to move is the one mated or out of time
+            (GameStatus::Checkmate | GameStatus::Timeout, _) => Some(loser(&game_state.current_turn)),
+            (GameStatus::Stalemate | GameStatus::Draw, Some(winner)) => Some(match winner {
+                PieceColor::White => "1-0",
+                PieceColor::Black => "0-1",
+            }),
+            (GameStatus::Stalemate | GameStatus::Draw, None) => Some("1/2-1/2"),
+            (GameStatus::Aborted, _) => Some("*"),
+        }
+    });
+
+    BoardSummary {
+        board,
+        room_id: room.id,
+        white: name(PieceColor::White),
+        black: name(PieceColor::Black),
+        last_move: room.moves.last().map(|m| m.move_notation.clone()),
+        white_ms,
+        black_ms,
+        result: result.map(str::to_string),
+    }
+}
+
+// What changed from `old` to `new`; None when nothing did
+pub fn delta(old: Option<&BoardSummary>, new: &BoardSummary) -> Option<BoardDelta> {
+    fn changed<T: PartialEq + Clone>(old: Option<&T>, new: &T) -> Option<T> {
+        (old != Some(new)).then(|| new.clone())
+    }
+
+    let delta = BoardDelta {
+        board: new.board,
+        room_id: changed(old.map(|o| &o.room_id), &new.room_id),
+        white: changed(old.map(|o| &o.white), &new.white),
+        black: changed(old.map(|o| &o.black), &new.black),
+        last_move: changed(old.map(|o| &o.last_move), &new.last_move),
+        white_ms: changed(old.map(|o| &o.white_ms), &new.white_ms),
+        black_ms: changed(old.map(|o| &o.black_ms), &new.black_ms),
+        result: changed(old.map(|o| &o.result), &new.result),
+    };
+    (delta != BoardDelta { board: new.board, ..BoardDelta::default() }).then_some(delta)
+}
+
+// Send the halls showing any of `rooms` what changed on their boards, one
+// update per hall. Called under the state lock after every change to a room.
+pub fn publish(state: &mut ServerState, rooms: &[RoomId]) {
+    let now = now_ms();
+    let mut changes: HashMap<(Uuid, u32), Vec<BoardSummary>> = HashMap::new();
+    for room_id in rooms {
+        let Some(room) = state.rooms.get(room_id) else { continue };
+        if let Some(tag) = room.board_tag {
+            changes
+                .entry((tag.tournament_id, tag.round))
+                .or_default()
+                .push(summary(room, tag.board, now));
+        }
+    }
+
+    let capacity = state.broadcast_capacity;
+    for ((tournament_id, round), boards) in changes {
+        let hall = state
+            .halls
+            .entry((tournament_id, round))
+            .or_insert_with(|| Hall::new(capacity));
+        let deltas: Vec<BoardDelta> = boards
+            .into_iter()
+            .filter_map(|board| {
+                let delta = delta(hall.boards.get(&board.board), &board);
+                hall.boards.insert(board.board, board);
+                delta
+            })
+            .collect();
+        if deltas.is_empty() {
+            continue;
+        }
+        chat::touch(state, tournament_id, now);
+        let Some(hall) = state.halls.get_mut(&(tournament_id, round)) else { continue };
+        hall.seq += 1;
+        // Halls nobody watches have no receivers; that is fine
+        let _ = hall.sender.send(ServerMessage::HallUpdate {
+            tournament_id,
+            round,
+            seq: hall.seq,
+            boards: deltas,
+        });
+    }
+}
+
+// Every board of a round as last sent, opening the hall if no game of the
+// round has been played yet
+pub fn watch_hall(tournament_id: Uuid, round: u32) -> ServerMessage {
+    let mut state = GAME_STATE.lock().unwrap();
+    let capacity = state.broadcast_capacity;
+    state
+        .halls
+        .entry((tournament_id, round))
+        .or_insert_with(|| Hall::new(capacity))
+        .snapshot(tournament_id, round)
+}
+
+// Every round of a tournament that has a hall, first round first, each as
+// last sent. Called under the state lock.
+pub fn tournament_snapshots(state: &ServerState, tournament_id: Uuid) -> Vec<ServerMessage> {
+    let mut rounds: Vec<u32> = state
+        .halls
+        .keys()
+        .filter(|(id, _)| *id == tournament_id)
+        .map(|(_, round)| *round)
+        .collect();
+    rounds.sort_unstable();
+    rounds
+        .into_iter()
+        .filter_map(|round| state.halls.get(&(tournament_id, round)).map(|hall| hall.snapshot(tournament_id, round)))
+        .collect()
+}
+
+// A hall's updates as seen by a connection. Updates already covered by the
+// snapshot the connection got are skipped; after a gap, the connection gets a
+// fresh snapshot instead.
+pub struct HallReceiver {
+    pub tournament_id: Uuid,
+    pub round: u32,
+    next_seq: u64,
+    receiver: Option<broadcast::Receiver<ServerMessage>>,
+}
+
+impl HallReceiver {
+    // Follow a hall from the snapshot numbered `seq`
+    pub fn new(tournament_id: Uuid, round: u32, seq: u64) -> Self {
+        let receiver = GAME_STATE
+            .lock()
+            .unwrap()
+            .halls
+            .get(&(tournament_id, round))
+            .map(|hall| hall.sender.subscribe());
+        Self { tournament_id, round, next_seq: seq + 1, receiver }
+    }
+
+    // Messages to forward right now, if any
+    pub fn try_next(&mut self) -> Vec<ServerMessage> {
+        let Some(receiver) = self.receiver.as_mut() else { return Vec::new() };
+        match receiver.try_recv() {
+            Ok(ServerMessage::HallUpdate { seq, .. }) if seq < self.next_seq => Vec::new(),
+            Ok(update @ ServerMessage::HallUpdate { seq, .. }) if seq == self.next_seq => {
+                self.next_seq += 1;
+                vec![update]
+            }
+            Ok(_) | Err(TryRecvError::Lagged(_)) => self.resync(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Start again from the hall as it stands, taken under the state lock so
+    // no update falls between the snapshot and the new subscription
+    fn resync(&mut self) -> Vec<ServerMessage> {
+        let state = GAME_STATE.lock().unwrap();
+        let Some(hall) = state.halls.get(&(self.tournament_id, self.round)) else { return Vec::new() };
+        self.receiver = Some(hall.sender.subscribe());
+        self.next_seq = hall.seq + 1;
+        log::warn!("A hall watcher of round {} of tournament {} missed updates", self.round, self.tournament_id);
+        vec![hall.snapshot(self.tournament_id, self.round)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{create_room_with_time, join_room, place_on_board, send_move};
+    use crate::models::BoardTag;
+
+    fn cleanup(tournament_id: Uuid, rooms: &[RoomId]) {
+        let mut state = GAME_STATE.lock().unwrap();
+        for room_id in rooms {
+            state.rooms.remove(room_id);
+            state.message_senders.remove(room_id);
+        }
+        state.halls.retain(|(id, _), _| *id != tournament_id);
+    }
+
+    #[test]
+    fn test_hall_sends_only_what_changed() {
+        let tournament_id = Uuid::new_v4();
+        let room_id = create_room_with_time(60_000, 0);
+        place_on_board(&room_id, BoardTag { tournament_id, round: 1, board: 1 }).unwrap();
+        let white = "white_player".parse().unwrap();
+        let black = "black_player".parse().unwrap();
+
+        assert!(matches!(
+            watch_hall(tournament_id, 1),
+            ServerMessage::HallSnapshot { seq: 1, ref boards, .. } if boards.len() == 1 && boards[0].white.is_none()
+        ));
+        let mut watcher = HallReceiver::new(tournament_id, 1, 1);
+
+        join_room(&room_id, &white, Some("Alice".to_string())).unwrap();
+        join_room(&room_id, &black, Some("Bob".to_string())).unwrap();
+        send_move(&room_id, &white, "e2e4", None, 0).unwrap();
+
+        let updates: Vec<BoardDelta> = (0..3)
+            .flat_map(|_| watcher.try_next())
+            .map(|message| match message {
+                ServerMessage::HallUpdate { mut boards, .. } => boards.remove(0),
+                other => panic!("expected HallUpdate, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(updates[0].white, Some(Some("Alice".to_string())));
+        assert_eq!((updates[0].black.clone(), updates[0].last_move.clone()), (None, None));
+        assert_eq!(updates[1].black, Some(Some("Bob".to_string())));
+        assert_eq!(updates[2].last_move, Some(Some("e2e4".to_string())));
+        assert_eq!((updates[2].white.clone(), updates[2].room_id), (None, None));
+        assert!(watcher.try_next().is_empty());
+
+        // A second game of the same round goes to the same watchers
+        let other = create_room_with_time(60_000, 0);
+        place_on_board(&other, BoardTag { tournament_id, round: 1, board: 2 }).unwrap();
+        assert!(matches!(&watcher.try_next()[..], [ServerMessage::HallUpdate { seq: 5, boards, .. }] if boards[0].board == 2));
+        assert_eq!(
+            place_on_board(&other, BoardTag { tournament_id, round: 1, board: 1 }),
+            Err("Board 1 of round 1 already has a game".to_string())
+        );
+        cleanup(tournament_id, &[room_id, other]);
+    }
+
+    #[test]
+    fn test_hall_watcher_resyncs_after_a_gap() {
+        let tournament_id = Uuid::new_v4();
+        let room_id = create_room_with_time(60_000, 0);
+        place_on_board(&room_id, BoardTag { tournament_id, round: 2, board: 4 }).unwrap();
+
+        // Follows from a snapshot before the board was placed, so update 1
+        // is missing when update 2 arrives
+        let mut watcher = HallReceiver::new(tournament_id, 2, 0);
+        join_room(&room_id, &"white_player".parse().unwrap(), None).unwrap();
+        match &watcher.try_next()[..] {
+            [ServerMessage::HallSnapshot { seq: 2, boards, .. }] => assert!(boards[0].white.is_some()),
+            other => panic!("expected HallSnapshot, got {:?}", other),
+        }
+        assert!(watcher.try_next().is_empty());
+        cleanup(tournament_id, &[room_id]);
+    }
+}
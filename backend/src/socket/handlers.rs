@@ -1,28 +1,30 @@
-use futures_util::{SinkExt, StreamExt};
+use futures_util::SinkExt;
 use serde_json::{from_str, to_string};
 use tokio::sync::broadcast;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::game::{
-    accept_takeback,
-    get_game_log,
-    get_room_sender,
-    join_room,
-    leave_room,
-    offer_takeback,
-    reject_takeback,
-    send_move,
-};
-use crate::models::{ClientMessage, ServerMessage};
+use crate::chat::ChatReceiver;
+use crate::dispatch::{dispatch, Session};
+use crate::game::get_room_sender;
+use crate::hall::HallReceiver;
+use crate::latency::LatencyEstimator;
+use crate::lease::release_room;
+use crate::models::{ClientMessage, RoomId, ServerMessage, SessionPlayerId};
+
+type ClientSink = futures_util::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    Message,
+>;
 
 // Handle a client message
 pub async fn handle_client_message(
     message: &str,
-    sender: &mut futures_util::stream::SplitSink<
-        tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-        Message,
-    >,
-    room_senders: &mut Vec<(String, broadcast::Sender<ServerMessage>)>,
+    sender: &mut ClientSink,
+    session: &mut Session,
+    room_senders: &mut Vec<(RoomId, SessionPlayerId, broadcast::Sender<ServerMessage>)>,
+    halls: &mut Vec<HallReceiver>,
+    chats: &mut Vec<ChatReceiver>,
+    latency: &LatencyEstimator,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Parse the message
     let client_message: ClientMessage = match from_str(message) {
@@ -31,7 +33,7 @@ pub async fn handle_client_message(
             log::error!("Failed to parse client message: {}", e);
             let error_msg = ServerMessage::Error {
                 code: "PARSE_ERROR".to_string(),
-                message: "Failed to parse message".to_string(),
+                message: format!("Failed to parse message: {}", e),
             };
             sender
                 .send(Message::Text(to_string(&error_msg)?))
@@ -40,153 +42,53 @@ pub async fn handle_client_message(
         }
     };
 
-    // Handle the message based on its type
-    match client_message {
-        ClientMessage::JoinRoom(payload) => {
-            log::info!(
-                "Player {} joining room {}",
-                payload.player_id,
-                payload.room_id
-            );
-
-            match join_room(&payload.room_id, &payload.player_id, payload.player_name) {
-                Ok(response) => {
-                    // Send response to client
-                    sender.send(Message::Text(to_string(&response)?)).await?;
+    let target = client_message.room_id().copied();
+    if let Some(room_id) = &target {
+        tracing::Span::current().record("room_id", tracing::field::display(room_id));
+    }
+    match dispatch(session, client_message, latency.lag_compensation_ms()).await {
+        Ok(response) => {
+            sender.send(Message::Text(to_string(&response)?)).await?;
 
-                    // Subscribe to room messages
-                    if let Some(room_sender) = get_room_sender(&payload.room_id) {
-                        room_senders.push((payload.room_id, room_sender));
+            // Follow the rooms this connection sits in
+            match &response {
+                ServerMessage::RoomJoined { room_id, player_id, .. } => {
+                    if let Some(room_sender) = get_room_sender(room_id) {
+                        room_senders.push((*room_id, player_id.clone(), room_sender));
                     }
                 }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "JOIN_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
-                }
-            }
-        }
-        ClientMessage::SendMove(payload) => {
-            log::info!(
-                "Player {} making move {} in room {}",
-                payload.player_id,
-                payload.move_notation,
-                payload.room_id
-            );
-
-            match send_move(&payload.room_id, &payload.player_id, &payload.move_notation) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
-                }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "MOVE_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
-                }
-            }
-        }
-        ClientMessage::LeaveRoom(payload) => {
-            log::info!(
-                "Player {} leaving room {}",
-                payload.player_id,
-                payload.room_id
-            );
-
-            match leave_room(&payload.room_id, &payload.player_id) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
-
-                    // Unsubscribe from room messages
-                    room_senders.retain(|(id, _)| id != &payload.room_id);
-                }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "LEAVE_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
+                ServerMessage::PlayerLeft { room_id, .. } => {
+                    room_senders.retain(|(id, _, _)| id != room_id);
                 }
-            }
-        }
-        ClientMessage::RequestGameLog(payload) => {
-            log::info!("Game log requested for room {}", payload.room_id);
-
-            match get_game_log(&payload.room_id) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
+                // And the halls it watches
+                ServerMessage::HallSnapshot { tournament_id, round, seq, .. } => {
+                    halls.retain(|h| (h.tournament_id, h.round) != (*tournament_id, *round));
+                    halls.push(HallReceiver::new(*tournament_id, *round, *seq));
                 }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "LOG_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
+                ServerMessage::HallUnwatched { tournament_id, round } => {
+                    halls.retain(|h| (h.tournament_id, h.round) != (*tournament_id, *round));
                 }
-            }
-        }
-        ClientMessage::OfferTakeback(payload) => {
-            log::info!(
-                "Player {} offering takeback in room {}",
-                payload.player_id,
-                payload.room_id
-            );
-
-            match offer_takeback(&payload.room_id, &payload.player_id) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
+                // And the tournament chats it follows
+                ServerMessage::TournamentChatJoined { tournament_id, last_id, .. } => {
+                    chats.retain(|c| c.tournament_id != *tournament_id);
+                    chats.push(ChatReceiver::new(*tournament_id, *last_id));
                 }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "TAKEBACK_OFFER_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
+                ServerMessage::TournamentChatLeft { tournament_id } => {
+                    chats.retain(|c| c.tournament_id != *tournament_id);
                 }
+                _ => {}
             }
         }
-        ClientMessage::AcceptTakeback(payload) => {
-            log::info!(
-                "Player {} accepting takeback in room {}",
-                payload.player_id,
-                payload.room_id
-            );
-
-            match accept_takeback(&payload.room_id, &payload.player_id) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
-                }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "TAKEBACK_ACCEPT_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
-                }
-            }
+        Err(rejection) => {
+            sender.send(Message::Text(to_string(&rejection.into_message())?)).await?;
         }
-        ClientMessage::RejectTakeback(payload) => {
-            log::info!(
-                "Player {} rejecting takeback in room {}",
-                payload.player_id,
-                payload.room_id
-            );
+    }
 
-            match reject_takeback(&payload.room_id, &payload.player_id) {
-                Ok(response) => {
-                    sender.send(Message::Text(to_string(&response)?)).await?;
-                }
-                Err(e) => {
-                    let error_msg = ServerMessage::Error {
-                        code: "TAKEBACK_REJECT_ERROR".to_string(),
-                        message: e,
-                    };
-                    sender.send(Message::Text(to_string(&error_msg)?)).await?;
-                }
-            }
+    // Do not keep leases on rooms this instance does not host, such as
+    // unknown rooms or rooms the last player left
+    if let Some(room_id) = &target {
+        if get_room_sender(room_id).is_none() {
+            release_room(room_id).await;
         }
     }
 
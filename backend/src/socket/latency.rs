@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess::TimeSource;
+
+// Extra time on the flag-fall check until a connection has answered a ping
+pub const DEFAULT_LAG_COMPENSATION_MS: u64 = 750;
+pub const MIN_LAG_COMPENSATION_MS: u64 = 100;
+pub const MAX_LAG_COMPENSATION_MS: u64 = 2_000;
+
+// How often each connection is pinged
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+// Longer round trips are a stalled connection rather than latency
+const MAX_RTT_SAMPLE_MS: u64 = 10_000;
+
+// Rolling round-trip estimate of one connection, smoothed like TCP's
+// retransmission timer (RFC 6298)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyEstimator {
+    srtt_ms: Option<f64>,
+    rttvar_ms: f64,
+}
+
+impl LatencyEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, rtt_ms: u64) {
+        let sample = rtt_ms.min(MAX_RTT_SAMPLE_MS) as f64;
+        match self.srtt_ms {
+            None => {
+                self.srtt_ms = Some(sample);
+                self.rttvar_ms = sample / 2.0;
+            }
+            Some(srtt) => {
+                self.rttvar_ms = 0.75 * self.rttvar_ms + 0.25 * (srtt - sample).abs();
+                self.srtt_ms = Some(0.875 * srtt + 0.125 * sample);
+            }
+        }
+    }
+
+    pub fn rtt_ms(&self) -> Option<u64> {
+        self.srtt_ms.map(|srtt| srtt.round() as u64)
+    }
+
+    // One smoothed round trip plus four deviations, within the caps. The
+    // round trip covers both the previous move reaching the player and
+    // their reply reaching the server.
+    pub fn lag_compensation_ms(&self) -> u64 {
+        match self.srtt_ms {
+            None => DEFAULT_LAG_COMPENSATION_MS,
+            Some(srtt) => ((srtt + 4.0 * self.rttvar_ms).round() as u64)
+                .clamp(MIN_LAG_COMPENSATION_MS, MAX_LAG_COMPENSATION_MS),
+        }
+    }
+}
+
+thread_local! {
+    // Stands in for the monotonic clock on this thread while set
+    static TIME_SOURCE: RefCell<Option<Arc<dyn TimeSource>>> = const { RefCell::new(None) };
+}
+
+// Milliseconds on the monotonic clock shared with the chess crate, so that
+// clocks and round trips don't jump when the host clock is stepped
+pub fn now_ms() -> u64 {
+    TIME_SOURCE
+        .with(|source| source.borrow().as_ref().map(|time| time.now_ms()))
+        .unwrap_or_else(chess::time_source::now_ms)
+}
+
+// Read the time from `time` on this thread while `f` runs, so that tests
+// and simulations set the clocks of the game functions they call
+pub fn with_time_source<R>(time: Arc<dyn TimeSource>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<dyn TimeSource>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            TIME_SOURCE.with(|source| *source.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(TIME_SOURCE.with(|source| source.replace(Some(time))));
+    f()
+}
+
+// Ping frames carry their send time, which the pong echoes back
+pub fn ping_payload(sent_ms: u64) -> Vec<u8> {
+    sent_ms.to_be_bytes().to_vec()
+}
+
+// Round trip of a pong to one of our pings; None for unsolicited pongs
+pub fn rtt_from_pong(payload: &[u8], now_ms: u64) -> Option<u64> {
+    let sent_ms = u64::from_be_bytes(payload.try_into().ok()?);
+    now_ms.checked_sub(sent_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_until_first_sample() {
+        let latency = LatencyEstimator::new();
+        assert_eq!(latency.rtt_ms(), None);
+        assert_eq!(latency.lag_compensation_ms(), DEFAULT_LAG_COMPENSATION_MS);
+    }
+
+    #[test]
+    fn test_estimate_follows_samples_within_caps() {
+        let mut lan = LatencyEstimator::new();
+        for _ in 0..20 {
+            lan.record(4);
+        }
+        assert_eq!(lan.rtt_ms(), Some(4));
+        assert_eq!(lan.lag_compensation_ms(), MIN_LAG_COMPENSATION_MS);
+
+        let mut mobile = LatencyEstimator::new();
+        for rtt in [300, 500, 250, 700, 400] {
+            mobile.record(rtt);
+        }
+        let compensation = mobile.lag_compensation_ms();
+        assert!(compensation > DEFAULT_LAG_COMPENSATION_MS, "got {}", compensation);
+
+        mobile.record(60_000);
+        assert_eq!(mobile.lag_compensation_ms(), MAX_LAG_COMPENSATION_MS);
+    }
+
+    #[test]
+    fn test_pong_round_trip() {
+        let payload = ping_payload(1_000);
+        assert_eq!(rtt_from_pong(&payload, 1_250), Some(250));
+        assert_eq!(rtt_from_pong(b"hello", 1_250), None);
+        assert_eq!(rtt_from_pong(&payload, 900), None);
+    }
+}
@@ -0,0 +1,459 @@
+use redis::aio::MultiplexedConnection;
+use redis::{RedisResult, Script};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::models::RoomId;
+
+pub const DEFAULT_LEASE_TTL_MS: u64 = 15_000;
+
+// Takes the lease when it is free, or extends it when this instance holds it.
+// Replies {1, token} on success and {0, owner} when another instance holds it.
+const ACQUIRE_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current then
+  local owner, token = string.match(current, '^(.*):(%d+)$')
+  if owner == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return {1, token}
+  end
+  return {0, owner or current}
+end
+local token = redis.call('INCR', KEYS[2])
+redis.call('SET', KEYS[1], ARGV[1] .. ':' .. token, 'PX', ARGV[2])
+return {1, tostring(token)}
+"#;
+
+// Compare-and-set on the lease: extends it only while it still carries our
+// fencing token, so a holder whose lease expired or changed hands is refused
+const FENCE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Number issued each time a room lease changes hands. It only grows, so a
+/// holder whose lease expired can tell that someone else owned the room since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FencingToken(pub u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseOutcome {
+    Acquired(FencingToken),
+    /// Another instance owns the room
+    OwnedBy(String),
+}
+
+/// Where leases are kept. Each call is one atomic step on one lease key, as
+/// done by the scripts above.
+pub trait LeaseStore: Clone + Send + Sync + 'static {
+    /// Take or extend the lease for `owner`: `(true, token)`, or `(false,
+    /// owner)` naming the instance that holds it
+    fn acquire(&self, key: &str, owner: &str, ttl_ms: u64) -> impl Future<Output = RedisResult<(bool, String)>> + Send;
+
+    /// Extend the lease only while it still holds `value`
+    fn fence(&self, key: &str, value: &str, ttl_ms: u64) -> impl Future<Output = RedisResult<bool>> + Send;
+
+    /// Delete the lease only while it still holds `value`
+    fn release(&self, key: &str, value: &str) -> impl Future<Output = RedisResult<()>> + Send;
+}
+
+#[derive(Clone)]
+pub struct RedisLeaseStore {
+    conn: MultiplexedConnection,
+}
+
+impl LeaseStore for RedisLeaseStore {
+    async fn acquire(&self, key: &str, owner: &str, ttl_ms: u64) -> RedisResult<(bool, String)> {
+        let (acquired, value): (i64, String) = Script::new(ACQUIRE_SCRIPT)
+            .key(key)
+            .key(FENCE_KEY)
+            .arg(owner)
+            .arg(ttl_ms)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok((acquired == 1, value))
+    }
+
+    async fn fence(&self, key: &str, value: &str, ttl_ms: u64) -> RedisResult<bool> {
+        let renewed: i64 = Script::new(FENCE_SCRIPT)
+            .key(key)
+            .arg(value)
+            .arg(ttl_ms)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(renewed == 1)
+    }
+
+    async fn release(&self, key: &str, value: &str) -> RedisResult<()> {
+        let _: i64 = Script::new(RELEASE_SCRIPT)
+            .key(key)
+            .arg(value)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Per-room leases, so that exactly one socket server instance holds the
+/// authoritative state of a room. Leases expire after `ttl` unless renewed.
+#[derive(Clone)]
+pub struct RoomLeases<S = RedisLeaseStore> {
+    store: S,
+    instance_id: String,
+    ttl: Duration,
+    held: Arc<Mutex<HashMap<RoomId, FencingToken>>>,
+}
+
+static ROOM_LEASES: OnceLock<RoomLeases> = OnceLock::new();
+
+pub fn lease_key(room_id: &RoomId) -> String {
+    format!("socket:room:{}:lease", room_id)
+}
+
+// One counter for every room, so that closed rooms leave nothing behind.
+// Never expires, so tokens keep growing across leases
+pub const FENCE_KEY: &str = "socket:room-lease:fence";
+
+fn lease_value(instance_id: &str, token: FencingToken) -> String {
+    format!("{}:{}", instance_id, token.0)
+}
+
+impl RoomLeases {
+    pub async fn connect(redis_url: &str, instance_name: &str, ttl: Duration) -> RedisResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+        Ok(RoomLeases::new(RedisLeaseStore { conn }, instance_name, ttl))
+    }
+}
+
+impl<S: LeaseStore> RoomLeases<S> {
+    /// Leases of this process. Its id is `instance_name` made unique, so that
+    /// a restart overlapping the process it replaces does not take over the
+    /// leases that one still holds.
+    pub fn new(store: S, instance_name: &str, ttl: Duration) -> Self {
+        RoomLeases {
+            store,
+            instance_id: format!("{}/{}", instance_name, uuid::Uuid::new_v4().simple()),
+            ttl,
+            held: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    fn ttl_ms(&self) -> u64 {
+        self.ttl.as_millis() as u64
+    }
+
+    /// Take the lease of a room this instance does not hold yet.
+    pub async fn acquire(&self, room_id: &RoomId) -> RedisResult<LeaseOutcome> {
+        let (acquired, value) = self.store.acquire(&lease_key(room_id), &self.instance_id, self.ttl_ms()).await?;
+        if !acquired {
+            return Ok(LeaseOutcome::OwnedBy(value));
+        }
+        let token = FencingToken(value.parse().unwrap_or_default());
+        self.held.lock().unwrap().insert(*room_id, token);
+        Ok(LeaseOutcome::Acquired(token))
+    }
+
+    /// Extend a held lease if it still carries `token`. `false` means it
+    /// expired and may have changed hands, so the local state of the room can
+    /// no longer be trusted.
+    pub async fn renew(&self, room_id: &RoomId, token: FencingToken) -> RedisResult<bool> {
+        let renewed = self
+            .store
+            .fence(&lease_key(room_id), &lease_value(&self.instance_id, token), self.ttl_ms())
+            .await?;
+        if !renewed {
+            self.forget(room_id, token);
+        }
+        Ok(renewed)
+    }
+
+    pub async fn release(&self, room_id: &RoomId) -> RedisResult<()> {
+        let token = self.held.lock().unwrap().remove(room_id);
+        if let Some(token) = token {
+            self.store.release(&lease_key(room_id), &lease_value(&self.instance_id, token)).await?;
+        }
+        Ok(())
+    }
+
+    pub fn held_token(&self, room_id: &RoomId) -> Option<FencingToken> {
+        self.held.lock().unwrap().get(room_id).copied()
+    }
+
+    // Drop a lost lease unless a newer one was taken meanwhile
+    fn forget(&self, room_id: &RoomId, token: FencingToken) {
+        let mut held = self.held.lock().unwrap();
+        if held.get(room_id) == Some(&token) {
+            held.remove(room_id);
+        }
+    }
+
+    /// The token this instance holds `room_id` under, acquiring the lease
+    /// when it holds none yet. Whether a held lease is still current is only
+    /// known to `fence`.
+    pub async fn claim(&self, room_id: &RoomId) -> Result<FencingToken, String> {
+        if let Some(token) = self.held_token(room_id) {
+            return Ok(token);
+        }
+
+        match self.acquire(room_id).await {
+            Ok(LeaseOutcome::Acquired(token)) => Ok(token),
+            Ok(LeaseOutcome::OwnedBy(owner)) => Err(format!("Room {} is hosted by instance {}", room_id, owner)),
+            Err(e) => Err(format!("Room lease store unavailable: {}", e)),
+        }
+    }
+
+    /// Check in the store that `token` is still the room's current lease,
+    /// right before changing the room under it.
+    pub async fn fence(&self, room_id: &RoomId, token: FencingToken) -> Result<(), String> {
+        match self.renew(room_id, token).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(format!("Lease on room {} was lost, rejoin the room", room_id)),
+            Err(e) => Err(format!("Room lease store unavailable: {}", e)),
+        }
+    }
+
+    /// Renew every held lease every third of the TTL, dropping the ones lost.
+    pub fn spawn_renewal(&self) {
+        let leases = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(leases.ttl / 3);
+            loop {
+                ticker.tick().await;
+                let held: Vec<(RoomId, FencingToken)> =
+                    leases.held.lock().unwrap().iter().map(|(id, token)| (*id, *token)).collect();
+                for (room_id, token) in held {
+                    match leases.renew(&room_id, token).await {
+                        Ok(true) => {}
+                        Ok(false) => log::warn!("Lost the lease on room {}", room_id),
+                        Err(e) => log::error!("Failed to renew the lease on room {}: {}", room_id, e),
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Enable room leases; without them this instance owns every room
+pub fn init_room_leases(leases: RoomLeases) {
+    leases.spawn_renewal();
+    if ROOM_LEASES.set(leases).is_err() {
+        log::warn!("Room leases were already initialized");
+    }
+}
+
+// Check that this instance may change the room, returning the token to
+// change it under; rooms carry none without leases
+pub async fn claim_room(room_id: &RoomId) -> Result<Option<FencingToken>, String> {
+    match ROOM_LEASES.get() {
+        Some(leases) => leases.claim(room_id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+// Check that the room is still held under `token` before changing it
+pub async fn fence_room(room_id: &RoomId, token: FencingToken) -> Result<(), String> {
+    match ROOM_LEASES.get() {
+        Some(leases) => leases.fence(room_id, token).await,
+        None => Ok(()),
+    }
+}
+
+// The value this instance's lease on the room carries in Redis, for writes
+// about the room to be refused once the lease is no longer ours
+pub fn held_lease(room_id: &RoomId) -> Option<String> {
+    let leases = ROOM_LEASES.get()?;
+    leases.held_token(room_id).map(|token| lease_value(&leases.instance_id, token))
+}
+
+// Give up a room this instance no longer hosts
+pub async fn release_room(room_id: &RoomId) {
+    if let Some(leases) = ROOM_LEASES.get() {
+        if let Err(e) = leases.release(room_id).await {
+            log::error!("Failed to release the lease on room {}: {}", room_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Leases in memory on a clock the test moves, following the scripts
+    #[derive(Clone, Default)]
+    struct FakeStore {
+        inner: Arc<Mutex<FakeLeases>>,
+    }
+
+    #[derive(Default)]
+    struct FakeLeases {
+        now_ms: u64,
+        fence: u64,
+        // Value and expiry of each lease
+        leases: HashMap<String, (String, u64)>,
+    }
+
+    impl FakeStore {
+        fn advance(&self, ms: u64) {
+            self.inner.lock().unwrap().now_ms += ms;
+        }
+
+        fn current(&self, key: &str) -> Option<String> {
+            let mut inner = self.inner.lock().unwrap();
+            let now_ms = inner.now_ms;
+            inner.leases.retain(|_, (_, expires_at)| *expires_at > now_ms);
+            inner.leases.get(key).map(|(value, _)| value.clone())
+        }
+    }
+
+    impl LeaseStore for FakeStore {
+        async fn acquire(&self, key: &str, owner: &str, ttl_ms: u64) -> RedisResult<(bool, String)> {
+            let current = self.current(key);
+            let mut inner = self.inner.lock().unwrap();
+            let expires_at = inner.now_ms + ttl_ms;
+            if let Some(value) = current {
+                let (holder, token) = value.rsplit_once(':').unwrap();
+                if holder != owner {
+                    return Ok((false, holder.to_string()));
+                }
+                let token = token.to_string();
+                inner.leases.insert(key.to_string(), (value, expires_at));
+                return Ok((true, token));
+            }
+            inner.fence += 1;
+            let token = inner.fence;
+            inner.leases.insert(key.to_string(), (format!("{}:{}", owner, token), expires_at));
+            Ok((true, token.to_string()))
+        }
+
+        async fn fence(&self, key: &str, value: &str, ttl_ms: u64) -> RedisResult<bool> {
+            if self.current(key).as_deref() != Some(value) {
+                return Ok(false);
+            }
+            let mut inner = self.inner.lock().unwrap();
+            let expires_at = inner.now_ms + ttl_ms;
+            inner.leases.insert(key.to_string(), (value.to_string(), expires_at));
+            Ok(true)
+        }
+
+        async fn release(&self, key: &str, value: &str) -> RedisResult<()> {
+            if self.current(key).as_deref() == Some(value) {
+                self.inner.lock().unwrap().leases.remove(key);
+            }
+            Ok(())
+        }
+    }
+
+    const TTL: Duration = Duration::from_millis(1_000);
+
+    #[test]
+    fn test_leases_are_per_room_and_the_fence_is_shared() {
+        let room = RoomId::new();
+        assert_eq!(lease_key(&room), format!("socket:room:{}:lease", room));
+        assert!(!FENCE_KEY.contains(&room.to_string()));
+        assert_ne!(lease_key(&room), lease_key(&RoomId::new()));
+        assert_eq!(lease_value("socket-a", FencingToken(7)), "socket-a:7");
+    }
+
+    #[test]
+    fn test_processes_with_the_same_name_get_their_own_ids() {
+        let store = FakeStore::default();
+        let first = RoomLeases::new(store.clone(), "socket-a", TTL);
+        let second = RoomLeases::new(store, "socket-a", TTL);
+        assert!(first.instance_id().starts_with("socket-a/"));
+        assert_ne!(first.instance_id(), second.instance_id());
+    }
+
+    #[tokio::test]
+    async fn test_one_instance_at_a_time_acquires_a_room() {
+        let store = FakeStore::default();
+        let first = RoomLeases::new(store.clone(), "socket", TTL);
+        let second = RoomLeases::new(store, "socket", TTL);
+        let room = RoomId::new();
+
+        let token = first.claim(&room).await.unwrap();
+        assert_eq!(first.claim(&room).await, Ok(token));
+        assert_eq!(second.acquire(&room).await.unwrap(), LeaseOutcome::OwnedBy(first.instance_id().to_string()));
+        assert!(second.claim(&room).await.is_err());
+
+        // Another room gets a later token from the shared fence
+        let other = first.claim(&RoomId::new()).await.unwrap();
+        assert!(other > token);
+    }
+
+    #[tokio::test]
+    async fn test_renewing_keeps_the_lease_past_its_ttl() {
+        let store = FakeStore::default();
+        let first = RoomLeases::new(store.clone(), "socket", TTL);
+        let second = RoomLeases::new(store.clone(), "socket", TTL);
+        let room = RoomId::new();
+
+        let token = first.claim(&room).await.unwrap();
+        for _ in 0..3 {
+            store.advance(600);
+            assert!(first.renew(&room, token).await.unwrap());
+        }
+        assert!(matches!(second.acquire(&room).await.unwrap(), LeaseOutcome::OwnedBy(_)));
+        assert!(first.fence(&room, token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_leases_are_taken_over_under_a_newer_token() {
+        let store = FakeStore::default();
+        let first = RoomLeases::new(store.clone(), "socket", TTL);
+        let second = RoomLeases::new(store.clone(), "socket", TTL);
+        let room = RoomId::new();
+
+        let stale = first.claim(&room).await.unwrap();
+        store.advance(1_000);
+        let token = second.claim(&room).await.unwrap();
+        assert!(token > stale);
+        assert!(second.fence(&room, token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stale_tokens_are_fenced_off() {
+        let store = FakeStore::default();
+        let first = RoomLeases::new(store.clone(), "socket", TTL);
+        let second = RoomLeases::new(store.clone(), "socket", TTL);
+        let room = RoomId::new();
+
+        let stale = first.claim(&room).await.unwrap();
+        store.advance(1_000);
+        let token = second.claim(&room).await.unwrap();
+
+        // The first instance still believes it holds the room until the
+        // store turns its token away
+        assert_eq!(first.claim(&room).await, Ok(stale));
+        assert!(first.fence(&room, stale).await.is_err());
+        assert_eq!(first.held_token(&room), None);
+        assert!(first.claim(&room).await.is_err());
+
+        // Nor does releasing with it drop the new holder's lease
+        first.release(&room).await.unwrap();
+        assert!(second.fence(&room, token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rooms_are_local_without_leases() {
+        let room = RoomId::new();
+        assert_eq!(claim_room(&room).await, Ok(None));
+        assert!(fence_room(&room, FencingToken(1)).await.is_ok());
+        assert_eq!(held_lease(&room), None);
+    }
+}
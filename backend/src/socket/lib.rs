@@ -1,5 +1,16 @@
 // Re-export modules for testing
+pub mod chat;
+pub mod dispatch;
+pub mod eval;
+pub mod flood;
 pub mod game;
+pub mod hall;
 pub mod handlers;
+pub mod latency;
+pub mod lease;
+pub mod load;
 pub mod models;
+pub mod ongoing;
+pub mod sim;
+pub mod sse;
 pub mod websocket;
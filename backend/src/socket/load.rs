@@ -0,0 +1,375 @@
+// Load generator for the socket server. Simulated players connect in pairs,
+// sit down in a room of their own and replay recorded games move by move,
+// timing how long each move takes to be confirmed to its player and to
+// reach the opponent. Run with many games at once, the percentiles show how
+// the server holds up as connections and rooms pile up.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use chess::Referee;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::models::play_notation;
+
+// Clock of every simulated game, long enough that no replay loses on time
+const GAME_TIME_MS: u64 = 3_600_000;
+
+// Games replayed when no script is given
+pub const DEFAULT_SCRIPT: &str = "\
+# Ruy Lopez, Breyer
+e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 d6 c3 O-O h3 Nb8 d4 Nbd7
+# Queen's Gambit Declined, Tartakower
+d4 d5 c4 e6 Nc3 Nf6 Bg5 Be7 e3 O-O Nf3 h6 Bh4 b6 cxd5 Nxd5 Bxe7 Qxe7 Nxd5 exd5
+# Sicilian Najdorf, English Attack
+e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3 e5 Nb3 Be6 f3 Be7 Qd2 O-O O-O-O Nbd7 g4 b5
+# Scholar's mate, a game that ends on the board
+e4 e5 Qh5 Nc6 Bc4 Nf6 Qxf7#
+";
+
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    // WebSocket address of the server, e.g. ws://127.0.0.1:8080
+    pub url: String,
+    // Games played at once, two connections each
+    pub games: usize,
+    // Pause before each move
+    pub think: Duration,
+    // Time over which the games are started, so connections arrive steadily
+    pub ramp: Duration,
+    // Longest wait for any answer before the game is given up
+    pub timeout: Duration,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            url: "ws://127.0.0.1:8080".to_string(),
+            games: 100,
+            think: Duration::from_millis(250),
+            ramp: Duration::from_secs(10),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+// The games of a script, one per line in SAN or UCI. Move numbers and
+// results are skipped, as is everything after a '#' that starts a word.
+// Every game is replayed locally first, so a broken script fails here
+// rather than as rejected moves under load.
+pub fn parse_script(script: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut games = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let moves: Vec<String> = line
+            .split_whitespace()
+            .take_while(|word| !word.starts_with('#'))
+            .filter(|word| !is_move_number(word) && !matches!(*word, "1-0" | "0-1" | "1/2-1/2" | "*"))
+            .map(str::to_string)
+            .collect();
+        if moves.is_empty() {
+            continue;
+        }
+        let mut board = Referee::default();
+        for notation in &moves {
+            play_notation(&mut board, notation).map_err(|e| format!("line {}: {}: {}", index + 1, notation, e))?;
+        }
+        games.push(moves);
+    }
+    if games.is_empty() {
+        return Err("The script holds no games".to_string());
+    }
+    Ok(games)
+}
+
+fn is_move_number(word: &str) -> bool {
+    let digits = word.trim_end_matches('.');
+    digits.len() < word.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+// Samples of one measurement, in microseconds
+#[derive(Debug, Clone, Default)]
+pub struct Latencies(Vec<u64>);
+
+impl Latencies {
+    pub fn record(&mut self, sample: Duration) {
+        self.0.push(sample.as_micros() as u64);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Nearest-rank percentile, `percent` between 0 and 100
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let mut sorted = self.0.clone();
+        sorted.sort_unstable();
+        let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(Duration::from_micros(sorted[rank.clamp(1, sorted.len()) - 1]))
+    }
+
+    fn extend(&mut self, other: Latencies) {
+        self.0.extend(other.0);
+    }
+}
+
+impl fmt::Display for Latencies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |percent| self.percentile(percent).map_or(0.0, |d: Duration| d.as_secs_f64() * 1000.0);
+        write!(
+            f,
+            "p50 {:.1} ms  p90 {:.1} ms  p99 {:.1} ms  max {:.1} ms  ({} samples)",
+            ms(50.0),
+            ms(90.0),
+            ms(99.0),
+            ms(100.0),
+            self.len()
+        )
+    }
+}
+
+// What a run measured
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub games: usize,
+    pub completed: usize,
+    // Time from sending a move until its player has it confirmed
+    pub move_ack: Latencies,
+    // Time from sending a move until the opponent has it
+    pub broadcast_lag: Latencies,
+    // Games given up, by reason
+    pub failures: BTreeMap<String, usize>,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        writeln!(f, "{} games ({} connections) in {:.1} s", self.games, self.games * 2, seconds)?;
+        writeln!(f, "  completed      {}", self.completed)?;
+        writeln!(f, "  moves          {} ({:.1}/s)", self.move_ack.len(), self.move_ack.len() as f64 / seconds)?;
+        writeln!(f, "  move ack       {}", self.move_ack)?;
+        writeln!(f, "  broadcast lag  {}", self.broadcast_lag)?;
+        for (reason, count) in &self.failures {
+            writeln!(f, "  failed         {} x {}", count, reason)?;
+        }
+        Ok(())
+    }
+}
+
+// Timings of one game, and why it stopped early if it did
+#[derive(Debug, Default)]
+struct GameRun {
+    move_ack: Latencies,
+    broadcast_lag: Latencies,
+    failure: Option<String>,
+}
+
+// Play `config.games` games at once, cycling through `scripts`
+pub async fn run(config: &LoadConfig, scripts: &[Vec<String>]) -> LoadReport {
+    // Player ids of this run, so repeated runs against one server do not meet
+    let run_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let started = Instant::now();
+    let spacing = config.ramp / config.games.max(1) as u32;
+
+    let tasks: Vec<_> = (0..config.games)
+        .map(|index| {
+            let config = config.clone();
+            let moves = scripts[index % scripts.len()].clone();
+            let prefix = format!("load-{}-{}", run_id, index);
+            tokio::spawn(async move {
+                tokio::time::sleep(spacing * index as u32).await;
+                play(&config, &prefix, &moves).await
+            })
+        })
+        .collect();
+
+    let mut report = LoadReport { games: config.games, ..LoadReport::default() };
+    for task in tasks {
+        let game = task.await.unwrap_or_else(|e| GameRun { failure: Some(format!("task: {}", e)), ..GameRun::default() });
+        report.move_ack.extend(game.move_ack);
+        report.broadcast_lag.extend(game.broadcast_lag);
+        match game.failure {
+            Some(reason) => *report.failures.entry(reason).or_default() += 1,
+            None => report.completed += 1,
+        }
+    }
+    report.elapsed = started.elapsed();
+    report
+}
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn play(config: &LoadConfig, prefix: &str, moves: &[String]) -> GameRun {
+    let mut game = GameRun::default();
+    if let Err(reason) = replay(config, prefix, moves, &mut game).await {
+        game.failure = Some(reason);
+    }
+    game
+}
+
+async fn replay(config: &LoadConfig, prefix: &str, moves: &[String], game: &mut GameRun) -> Result<(), String> {
+    let white_id = format!("{}-w", prefix);
+    let black_id = format!("{}-b", prefix);
+    let mut white = connect(&config.url).await?;
+    let mut black = connect(&config.url).await?;
+
+    send(&mut white, json!({
+        "type": "CreateRoom",
+        "payload": { "player_id": white_id, "initial_time_ms": GAME_TIME_MS, "increment_ms": 0 }
+    }))
+    .await?;
+    let room_id = next_matching(&mut white, config.timeout, |msg| {
+        (msg["type"] == "RoomJoined").then(|| msg["room_id"].clone())
+    })
+    .await
+    .map_err(|e| format!("create room: {}", e))?;
+
+    send(&mut black, json!({
+        "type": "JoinRoom",
+        "payload": { "room_id": room_id, "player_id": black_id }
+    }))
+    .await?;
+    next_matching(&mut black, config.timeout, |msg| {
+        (msg["type"] == "RoomJoined" && msg["player_id"] == black_id.as_str()).then_some(())
+    })
+    .await
+    .map_err(|e| format!("join room: {}", e))?;
+
+    for (ply, notation) in moves.iter().enumerate() {
+        tokio::time::sleep(config.think).await;
+        let (mover, mover_id, opponent) = if ply % 2 == 0 {
+            (&mut white, &white_id, &mut black)
+        } else {
+            (&mut black, &black_id, &mut white)
+        };
+
+        let sent = Instant::now();
+        send(mover, json!({
+            "type": "SendMove",
+            "payload": { "room_id": room_id, "player_id": mover_id, "move_notation": notation, "seq": ply }
+        }))
+        .await?;
+        // The mover hears of its move twice, as the answer and as the
+        // room's broadcast; earlier copies are told apart by the position
+        let this_move = |msg: &Value| {
+            (msg["type"] == "MoveMade" && plies_played(&msg["position"]) == Some(ply + 1)).then(Instant::now)
+        };
+        let (acked, seen) = tokio::join!(
+            next_matching(mover, config.timeout, this_move),
+            next_matching(opponent, config.timeout, this_move)
+        );
+        game.move_ack.record(acked.map_err(|e| format!("move: {}", e))? - sent);
+        game.broadcast_lag.record(seen.map_err(|e| format!("broadcast: {}", e))? - sent);
+    }
+
+    // Leave, so the server can drop the room
+    for (client, player_id) in [(&mut white, &white_id), (&mut black, &black_id)] {
+        let _ = send(client, json!({
+            "type": "LeaveRoom",
+            "payload": { "room_id": room_id, "player_id": player_id }
+        }))
+        .await;
+        let _ = client.close(None).await;
+    }
+    Ok(())
+}
+
+async fn connect(url: &str) -> Result<Client, String> {
+    connect_async(url).await.map(|(client, _)| client).map_err(|e| format!("connect: {}", e))
+}
+
+async fn send(client: &mut Client, message: Value) -> Result<(), String> {
+    client.send(Message::Text(message.to_string())).await.map_err(|e| format!("send: {}", e))
+}
+
+// Read messages until `wanted` picks one. An Error from the server ends
+// the wait, named by its code so that failures can be counted by kind.
+async fn next_matching<T>(
+    client: &mut Client,
+    timeout: Duration,
+    wanted: impl Fn(&Value) -> Option<T>,
+) -> Result<T, String> {
+    let wait = async {
+        while let Some(message) = client.next().await {
+            match message.map_err(|e| e.to_string())? {
+                Message::Text(text) => {
+                    let Ok(msg) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if msg["type"] == "Error" {
+                        return Err(format!("error {}", msg["code"].as_str().unwrap_or("?")));
+                    }
+                    if let Some(found) = wanted(&msg) {
+                        return Ok(found);
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Err("connection closed".to_string())
+    };
+    tokio::time::timeout(timeout, wait).await.map_err(|_| "timed out".to_string())?
+}
+
+// Half-moves played to reach a position of a game from the standard start
+fn plies_played(position: &Value) -> Option<usize> {
+    let fullmove = position["fullmove_number"].as_u64()? as usize;
+    let black_to_move = position["fen"].as_str()?.split(' ').nth(1)? == "b";
+    Some(fullmove.checked_sub(1)? * 2 + black_to_move as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_script_replays() {
+        let games = parse_script(DEFAULT_SCRIPT).unwrap();
+        assert_eq!(games.len(), 4);
+        assert_eq!(games[3].last().map(String::as_str), Some("Qxf7#"));
+    }
+
+    #[test]
+    fn test_script_skips_move_numbers_results_and_comments() {
+        let games = parse_script("1. e4 e5 2. Nf3 Nc6 1-0 # a comment\n\n1. d2d4 1... g8f6 *").unwrap();
+        assert_eq!(games, vec![vec!["e4", "e5", "Nf3", "Nc6"], vec!["d2d4", "g8f6"]]);
+
+        let broken = parse_script("e4 e5\ne4 e4").unwrap_err();
+        assert!(broken.starts_with("line 2: e4"), "{}", broken);
+        assert!(parse_script("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_percentiles_use_the_nearest_rank() {
+        let mut latencies = Latencies::default();
+        assert_eq!(latencies.percentile(50.0), None);
+        for ms in (1..=100).rev() {
+            latencies.record(Duration::from_millis(ms));
+        }
+        assert_eq!(latencies.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(latencies.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(latencies.percentile(100.0), Some(Duration::from_millis(100)));
+        assert_eq!(latencies.percentile(0.0), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_plies_are_counted_from_the_position() {
+        let after = |fen: &str, fullmove: u32| json!({ "fen": fen, "fullmove_number": fullmove });
+        assert_eq!(plies_played(&after("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", 1)), Some(1));
+        assert_eq!(plies_played(&after("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", 2)), Some(2));
+        assert_eq!(plies_played(&json!(null)), None);
+    }
+}
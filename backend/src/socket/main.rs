@@ -1,24 +1,147 @@
+mod chat;
+mod dispatch;
+mod eval;
+mod flood;
 mod game;
+mod hall;
 mod handlers;
+mod latency;
+mod lease;
 mod models;
+mod ongoing;
+mod sse;
 mod websocket;
 
 use std::env;
+use std::time::Duration;
 use tokio::net::TcpListener;
+use tracing::Instrument;
 use websocket::handle_connection;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize the logger
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
+    // Logs and spans, with a request id for every client message
+    let _telemetry = telemetry::init("starkmate-socket", "info");
     
     // Get the address from environment or use default
     let addr = env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
     
     log::info!("Starting WebSocket server on {}", addr);
     
+    // Legacy clients join rooms that do not exist yet to create them
+    let implicit_room_creation = env::var("IMPLICIT_ROOM_CREATION")
+        .map(|value| matches!(value.as_str(), "1" | "true"))
+        .unwrap_or(false);
+
+    // Messages a room buffers for slow connections before they need a resync
+    let broadcast_capacity = env::var("BROADCAST_CAPACITY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(game::DEFAULT_BROADCAST_CAPACITY);
+
+    // Time each side has for its first move before the game is aborted
+    let first_move_timeout_ms = env::var("FIRST_MOVE_TIMEOUT_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(game::DEFAULT_FIRST_MOVE_TIMEOUT_MS);
+
+    // Remaining time below which a side is warned that it is running out
+    let low_time_ms = env::var("LOW_TIME_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(game::DEFAULT_LOW_TIME_MS);
+
+    // Time a draw or takeback offer waits for an answer; 0 keeps offers
+    // until they are answered or a move is played
+    let offer_ttl_ms = env::var("OFFER_TTL_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(game::DEFAULT_OFFER_TTL_MS);
+
     // Initialize the game state
-    game::init_game_state();
+    game::init_game_state(implicit_room_creation, broadcast_capacity, first_move_timeout_ms, low_time_ms, offer_ttl_ms);
+
+    // Per-connection limits on each kind of action, e.g. FLOOD_OFFER=5/0.2
+    // for bursts of five offers refilled at one every five seconds
+    let mut flood_config = flood::FloodConfig::default();
+    for action in flood::Action::ALL {
+        let var = format!("FLOOD_{}", action.name());
+        if let Ok(value) = env::var(&var) {
+            match value.parse() {
+                Ok(limit) => flood_config.set_limit(action, limit),
+                Err(e) => log::warn!("Ignoring {}: {}", var, e),
+            }
+        }
+    }
+    if let Some(mute_after) = env::var("FLOOD_MUTE_AFTER").ok().and_then(|value| value.parse().ok()) {
+        flood_config.mute_after = mute_after;
+    }
+    if let Some(mute_ms) = env::var("FLOOD_MUTE_MS").ok().and_then(|value| value.parse().ok()) {
+        flood_config.mute_ms = mute_ms;
+    }
+    flood::init_flood_config(flood_config);
+
+    // Arbiters sign tournament announcements with ARBITER_KEY; tournament
+    // chats close once their games are over and the channel went quiet
+    chat::init_chat_config(chat::ChatConfig {
+        arbiter_key: env::var("ARBITER_KEY").ok().filter(|key| !key.is_empty()),
+        close_after_ms: env::var("TOURNAMENT_CHAT_CLOSE_AFTER_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(chat::DEFAULT_CLOSE_AFTER_MS),
+    });
+
+    // With several instances, rooms are leased in Redis so that one instance
+    // at a time holds each room's state
+    if let Ok(redis_url) = env::var("ROOM_LEASE_REDIS_URL") {
+        // Each process adds a suffix of its own to the name
+        let instance_name = env::var("INSTANCE_ID").unwrap_or_else(|_| "socket".to_string());
+        let ttl_ms = env::var("ROOM_LEASE_TTL_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(lease::DEFAULT_LEASE_TTL_MS);
+        let leases = lease::RoomLeases::connect(&redis_url, &instance_name, Duration::from_millis(ttl_ms.max(300))).await?;
+        log::info!("Room leases enabled for instance {} ({}ms TTL)", leases.instance_id(), ttl_ms);
+        lease::init_room_leases(leases);
+    }
+
+    // Spectators get the eval of each new position when the API analysed
+    // it already; CLOUD_EVAL_REDIS_URL is the API's CACHE_REDIS_URL
+    if let Ok(redis_url) = env::var("CLOUD_EVAL_REDIS_URL") {
+        let depths = match env::var("CLOUD_EVAL_DEPTHS") {
+            Ok(value) => value.split(',').filter_map(|depth| depth.trim().parse().ok()).collect(),
+            Err(_) => eval::DEFAULT_CLOUD_EVAL_DEPTHS.to_vec(),
+        };
+        let cloud_eval = eval::CloudEval::connect(&redis_url, depths).await?;
+        log::info!("Cloud eval enabled for the spectator feed");
+        eval::init_cloud_eval(cloud_eval);
+    }
+
+    // With several instances, each shares its list of games being played in
+    // Redis so that any of them lists the games of all
+    if let Ok(redis_url) = env::var("ONGOING_GAMES_REDIS_URL") {
+        let ttl_ms = env::var("ONGOING_GAMES_TTL_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(ongoing::DEFAULT_MIRROR_TTL_MS);
+        let mirror = ongoing::OngoingMirror::connect(&redis_url, Duration::from_millis(ttl_ms.max(3_000))).await?;
+        log::info!("Ongoing games shared in Redis ({}ms TTL)", ttl_ms);
+        ongoing::init_ongoing_mirror(mirror);
+    }
+    
+    // Keep clients' clock displays in step with the server, abort games
+    // nobody started in time, expire unanswered offers and close the chats
+    // of finished tournaments
+    tokio::spawn(async {
+        let mut ticker = tokio::time::interval(Duration::from_millis(game::CLOCK_UPDATE_INTERVAL_MS));
+        loop {
+            ticker.tick().await;
+            game::broadcast_clock_updates();
+            game::abort_overdue_games();
+            game::expire_stale_offers();
+            chat::close_finished_channels();
+        }
+    });
     
     // Create the TCP listener
     let listener = TcpListener::bind(&addr).await?;
@@ -30,12 +153,23 @@ loop {
                 Ok((stream, addr)) => {
                     log::info!("New connection from: {}", addr);
                     
-                    // Spawn a new task for each connection
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_connection(stream, addr).await {
-                            log::error!("Error handling connection: {}", e);
+                    // Spawn a new task for each connection; clients that
+                    // cannot hold a WebSocket may follow games and
+                    // tournaments as Server-Sent Events instead
+                    tokio::spawn(
+                        async move {
+                            let result = match sse::route_connection(&stream).await {
+                                sse::Route::WebSocket => handle_connection(stream, addr).await,
+                                sse::Route::Stream(request) => sse::handle_stream(stream, addr, request).await,
+                                sse::Route::Ongoing(request) => ongoing::serve(stream, request).await,
+                                sse::Route::NotFound => sse::not_found(stream).await,
+                            };
+                            if let Err(e) = result {
+                                log::error!("Error handling connection: {}", e);
+                            }
                         }
-                    });
+                        .instrument(tracing::info_span!("connection", %addr)),
+                    );
                 }
                 Err(e) => {
                     log::error!("Failed to accept connection: {}", e);
@@ -1,61 +1,405 @@
+use chess::{Referee, Termination};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::time::SystemTime;
+use uuid::Uuid;
+
+use crate::chat::{Announcement, AnnouncementKind, ChatLine};
+use crate::hall::{BoardDelta, BoardSummary};
+
+const MAX_PLAYER_ID_LEN: usize = 64;
+
+// Room identifier; only UUIDs are accepted from clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoomId(Uuid);
+
+impl RoomId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for RoomId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for RoomId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s)
+            .map(Self)
+            .map_err(|_| format!("Invalid room id: {}", s))
+    }
+}
+
+impl fmt::Display for RoomId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// Player identifier for the lifetime of a socket session: 1 to 64 ASCII
+// letters, digits, '-' or '_'
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SessionPlayerId(String);
+
+impl SessionPlayerId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for SessionPlayerId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_PLAYER_ID_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid player id: {}", value));
+        }
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for SessionPlayerId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_string())
+    }
+}
+
+impl From<SessionPlayerId> for String {
+    fn from(value: SessionPlayerId) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for SessionPlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 // Client message types
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", content = "payload")]
 pub enum ClientMessage {
+    CreateRoom(CreateRoomPayload),
     JoinRoom(JoinRoomPayload),
+    #[serde(alias = "MakeMove")]
     SendMove(SendMovePayload),
     LeaveRoom(LeaveRoomPayload),
     RequestGameLog(RequestGameLogPayload),
     OfferTakeback(OfferTakebackPayload),
     AcceptTakeback(AcceptTakebackPayload),
     RejectTakeback(RejectTakebackPayload),
+    OfferDraw(OfferDrawPayload),
+    AcceptDraw(AcceptDrawPayload),
+    DeclineDraw(DeclineDrawPayload),
+    ClockSyncRequest(ClockSyncRequestPayload),
+    WatchHall(HallPayload),
+    UnwatchHall(HallPayload),
+    JoinTournamentChat(TournamentChatPayload),
+    LeaveTournamentChat(TournamentChatPayload),
+    SendTournamentChat(TournamentChatLinePayload),
+    Announce(AnnouncePayload),
+    PauseGame(PauseGamePayload),
+    ResumeGame(ResumeGamePayload),
+}
+
+impl ClientMessage {
+    // Room the message acts on; None for CreateRoom and tournament messages
+    pub fn room_id(&self) -> Option<&RoomId> {
+        match self {
+            ClientMessage::CreateRoom(_)
+            | ClientMessage::WatchHall(_)
+            | ClientMessage::UnwatchHall(_)
+            | ClientMessage::JoinTournamentChat(_)
+            | ClientMessage::LeaveTournamentChat(_)
+            | ClientMessage::SendTournamentChat(_)
+            | ClientMessage::Announce(_) => None,
+            ClientMessage::JoinRoom(payload) => Some(&payload.room_id),
+            ClientMessage::SendMove(payload) => Some(&payload.room_id),
+            ClientMessage::LeaveRoom(payload) => Some(&payload.room_id),
+            ClientMessage::RequestGameLog(payload) => Some(&payload.room_id),
+            ClientMessage::OfferTakeback(payload) => Some(&payload.room_id),
+            ClientMessage::AcceptTakeback(payload) => Some(&payload.room_id),
+            ClientMessage::RejectTakeback(payload) => Some(&payload.room_id),
+            ClientMessage::OfferDraw(payload) => Some(&payload.room_id),
+            ClientMessage::AcceptDraw(payload) => Some(&payload.room_id),
+            ClientMessage::DeclineDraw(payload) => Some(&payload.room_id),
+            ClientMessage::ClockSyncRequest(payload) => Some(&payload.room_id),
+            ClientMessage::PauseGame(payload) => Some(&payload.room_id),
+            ClientMessage::ResumeGame(payload) => Some(&payload.room_id),
+        }
+    }
+
+    // Player the message acts for; None for read-only requests
+    pub fn player_id(&self) -> Option<&SessionPlayerId> {
+        match self {
+            ClientMessage::CreateRoom(payload) => Some(&payload.player_id),
+            ClientMessage::JoinRoom(payload) => Some(&payload.player_id),
+            ClientMessage::SendMove(payload) => Some(&payload.player_id),
+            ClientMessage::LeaveRoom(payload) => Some(&payload.player_id),
+            ClientMessage::OfferTakeback(payload) => Some(&payload.player_id),
+            ClientMessage::AcceptTakeback(payload) => Some(&payload.player_id),
+            ClientMessage::RejectTakeback(payload) => Some(&payload.player_id),
+            ClientMessage::OfferDraw(payload) => Some(&payload.player_id),
+            ClientMessage::AcceptDraw(payload) => Some(&payload.player_id),
+            ClientMessage::DeclineDraw(payload) => Some(&payload.player_id),
+            ClientMessage::SendTournamentChat(payload) => Some(&payload.player_id),
+            ClientMessage::RequestGameLog(_)
+            | ClientMessage::ClockSyncRequest(_)
+            | ClientMessage::WatchHall(_)
+            | ClientMessage::UnwatchHall(_)
+            | ClientMessage::JoinTournamentChat(_)
+            | ClientMessage::LeaveTournamentChat(_)
+            | ClientMessage::Announce(_)
+            | ClientMessage::PauseGame(_)
+            | ClientMessage::ResumeGame(_) => None,
+        }
+    }
+}
+
+// Creates a room and joins the creator as White; time control defaults to
+// the room defaults when omitted
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomPayload {
+    pub player_id: SessionPlayerId,
+    pub player_name: Option<String>,
+    // Rating to show with the player's name, as the client knows it
+    pub player_rating: Option<u32>,
+    pub initial_time_ms: Option<u64>,
+    // Black's starting time when it differs from White's
+    pub black_initial_time_ms: Option<u64>,
+    pub increment_ms: Option<u64>,
+    // Drawn games count as a win for Black, who must start with less time
+    #[serde(default)]
+    pub armageddon: bool,
+    // Tournament board the game is played on, shown in the hall view
+    pub board: Option<BoardTag>,
+    #[serde(default)]
+    pub settings: GameSettings,
+}
+
+// Whether players may take moves back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TakebackPolicy {
+    #[default]
+    Allowed,
+    Forbidden,
+    // Each player may have this many takebacks accepted
+    Limited(u32),
+}
+
+// Rules chosen when a room is created, e.g. no takebacks in rated games
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSettings {
+    #[serde(default)]
+    pub takebacks: TakebackPolicy,
+    // Chat between the players; clients hide theirs when it is off
+    #[serde(default = "enabled")]
+    pub allow_chat: bool,
+    // Whether anyone outside the room may follow the game
+    #[serde(default = "enabled")]
+    pub allow_spectators: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            takebacks: TakebackPolicy::default(),
+            allow_chat: true,
+            allow_spectators: true,
+        }
+    }
+}
+
+// What a player offered the opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferKind {
+    Draw,
+    Takeback,
+}
+
+// How the clocks of a room run, so clients need not assume a time control:
+// the starting times, the increment added after each move and the remaining
+// time below which a LowTime alert is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClockSettings {
+    pub white_initial_ms: u64,
+    pub black_initial_ms: u64,
+    pub increment_ms: u64,
+    pub low_time_ms: u64,
+}
+
+// What one side may do in the game as it stands, worked out from the rules
+// and the room's settings so clients show the same buttons the server accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SideActions {
+    pub can_offer_draw: bool,
+    pub can_takeback: bool,
+    // The game is drawn as soon as a position repeats three times or fifty
+    // moves pass without a capture or pawn move, so there is never a draw
+    // left to claim; kept for clients that ask
+    pub can_claim_draw: bool,
+    pub can_resign: bool,
+    // Before the game has really begun, as for the first-move deadline
+    pub can_abort: bool,
+}
+
+// Actions open to each side; messages go to the whole room, so both are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AvailableActions {
+    pub white: SideActions,
+    pub black: SideActions,
+}
+
+// A tournament board: the game `board` of `round`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BoardTag {
+    pub tournament_id: Uuid,
+    pub round: u32,
+    pub board: u32,
+}
+
+// Every board of a tournament round, for a wall-of-boards view
+#[derive(Debug, Deserialize)]
+pub struct HallPayload {
+    pub tournament_id: Uuid,
+    pub round: u32,
+}
+
+// A tournament's chat channel, open while the tournament is being played
+#[derive(Debug, Deserialize)]
+pub struct TournamentChatPayload {
+    pub tournament_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TournamentChatLinePayload {
+    pub tournament_id: Uuid,
+    pub player_id: SessionPlayerId,
+    pub text: String,
+}
+
+// An arbiter's notice to everyone in a tournament's chat, signed with the
+// arbiter key the server was started with
+#[derive(Debug, Deserialize)]
+pub struct AnnouncePayload {
+    pub tournament_id: Uuid,
+    pub arbiter_key: String,
+    pub text: String,
+    #[serde(default)]
+    pub kind: AnnouncementKind,
+    // Pinned announcements are shown to everyone joining the channel
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+// Stops a live game's clocks and moves, e.g. while a dispute is settled
+#[derive(Debug, Deserialize)]
+pub struct PauseGamePayload {
+    pub room_id: RoomId,
+    pub arbiter_key: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResumeGamePayload {
+    pub room_id: RoomId,
+    pub arbiter_key: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct JoinRoomPayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
     pub player_name: Option<String>,
+    pub player_rating: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SendMovePayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
     pub move_notation: String,
+    // Number of moves the client has seen played before this one; a retried
+    // move carries the same number and is not applied twice
+    pub seq: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LeaveRoomPayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RequestGameLogPayload {
-    pub room_id: String,
+    pub room_id: RoomId,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct OfferTakebackPayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct AcceptTakebackPayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RejectTakebackPayload {
-    pub room_id: String,
-    pub player_id: String,
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OfferDrawPayload {
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptDrawPayload {
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeclineDrawPayload {
+    pub room_id: RoomId,
+    pub player_id: SessionPlayerId,
+}
+
+// Asks for the clocks as they stand, e.g. after a reconnect. The client's
+// own time is echoed back so it can work out its offset from the server.
+#[derive(Debug, Deserialize)]
+pub struct ClockSyncRequestPayload {
+    pub room_id: RoomId,
+    pub client_time_ms: Option<u64>,
 }
 
 // Server message types
@@ -63,64 +407,203 @@ pub struct RejectTakebackPayload {
 #[serde(tag = "type")]
 pub enum ServerMessage {
     RoomJoined {
-        room_id: String,
-        player_id: String,
+        room_id: RoomId,
+        player_id: SessionPlayerId,
         players: Vec<Player>,
         game_state: Option<GameState>,
+        position: PositionSnapshot,
+        settings: GameSettings,
+        clock: ClockSettings,
+        available_actions: AvailableActions,
     },
     MoveMade {
-        room_id: String,
-        player_id: String,
+        room_id: RoomId,
+        player_id: SessionPlayerId,
         move_notation: String,
         game_state: GameState,
+        position: PositionSnapshot,
+        available_actions: AvailableActions,
+        timing: MoveTiming,
+    },
+    // Cached evaluation of the position after `ply` moves, sent after the
+    // move itself when the API has analysed that position
+    MoveEval {
+        room_id: RoomId,
+        ply: usize,
+        evaluation: CompactEval,
     },
     PlayerLeft {
-        room_id: String,
-        player_id: String,
+        room_id: RoomId,
+        player_id: SessionPlayerId,
     },
     GameLog {
-        room_id: String,
+        room_id: RoomId,
         moves: Vec<MoveRecord>,
     },
     TakebackOffered {
-        room_id: String,
-        requester_id: String,
+        room_id: RoomId,
+        requester_id: SessionPlayerId,
     },
     TakebackAccepted {
-        room_id: String,
+        room_id: RoomId,
         game_state: GameState,
         moves: Vec<MoveRecord>,
+        position: PositionSnapshot,
+        available_actions: AvailableActions,
     },
     TakebackRejected {
-        room_id: String,
-        by_player_id: String,
+        room_id: RoomId,
+        by_player_id: SessionPlayerId,
+    },
+    DrawOffered {
+        room_id: RoomId,
+        player_id: SessionPlayerId,
+    },
+    DrawAccepted {
+        room_id: RoomId,
+        game_state: GameState,
+        available_actions: AvailableActions,
+    },
+    DrawDeclined {
+        room_id: RoomId,
+        by_player_id: SessionPlayerId,
+    },
+    // An offer by `player_id` lapsed unanswered, because it went stale or a
+    // move was played; accepting it is no longer possible
+    OfferExpired {
+        room_id: RoomId,
+        offer: OfferKind,
+        player_id: SessionPlayerId,
+        reason: String,
     },
     Error {
         code: String,
         message: String,
     },
     GameTimeout {
-        room_id: String,
+        room_id: RoomId,
         winner_id: String,
         loser_id: String,
         reason: String,
     },
     MoveRejected {
-        room_id: String,
-        player_id: String,
+        room_id: RoomId,
+        player_id: SessionPlayerId,
         reason: String,
     },
+    // Latest round-trip estimate of a player's connection and the grace
+    // their moves get on the flag-fall check
+    PlayerLag {
+        room_id: RoomId,
+        player_id: SessionPlayerId,
+        rtt_ms: u64,
+        compensation_ms: u64,
+    },
+    // Both clocks at `server_time_ms`; `running` is the side whose clock is
+    // ticking, if any
+    // Sent in place of the messages a client missed by falling behind
+    Resync {
+        room_id: RoomId,
+        missed: u64,
+        players: Vec<Player>,
+        game_state: Option<GameState>,
+        moves: Vec<MoveRecord>,
+        position: PositionSnapshot,
+        clock: ClockSettings,
+        available_actions: AvailableActions,
+    },
+    ClockUpdate {
+        room_id: RoomId,
+        white_remaining_ms: u64,
+        black_remaining_ms: u64,
+        running: Option<PieceColor>,
+        server_time_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        client_time_ms: Option<u64>,
+    },
+    // `color` dropped below `threshold_ms`; sent once each time it does
+    LowTime {
+        room_id: RoomId,
+        color: PieceColor,
+        remaining_ms: u64,
+        threshold_ms: u64,
+    },
+    // Every board of a round as of update `seq`; later updates carry only
+    // what changed since the one before
+    HallSnapshot {
+        tournament_id: Uuid,
+        round: u32,
+        seq: u64,
+        boards: Vec<BoardSummary>,
+    },
+    HallUpdate {
+        tournament_id: Uuid,
+        round: u32,
+        seq: u64,
+        boards: Vec<BoardDelta>,
+    },
+    HallUnwatched {
+        tournament_id: Uuid,
+        round: u32,
+    },
+    // The pinned announcements and latest lines of a tournament's chat, as
+    // of line `last_id`
+    TournamentChatJoined {
+        tournament_id: Uuid,
+        last_id: u64,
+        pinned: Vec<Announcement>,
+        recent: Vec<ChatLine>,
+    },
+    TournamentChatLeft {
+        tournament_id: Uuid,
+    },
+    TournamentChat {
+        tournament_id: Uuid,
+        line: ChatLine,
+    },
+    TournamentAnnouncement {
+        tournament_id: Uuid,
+        announcement: Announcement,
+    },
+    // The tournament is over and its channel is gone
+    TournamentChatClosed {
+        tournament_id: Uuid,
+    },
+    // Clocks stand at the remaining times until the game is resumed
+    GamePaused {
+        room_id: RoomId,
+        reason: String,
+        white_remaining_ms: u64,
+        black_remaining_ms: u64,
+    },
+    GameResumed {
+        room_id: RoomId,
+        game_state: GameState,
+        white_remaining_ms: u64,
+        black_remaining_ms: u64,
+        available_actions: AvailableActions,
+    },
+    // `player_id` let the first-move deadline pass, so the game was called off
+    GameAborted {
+        room_id: RoomId,
+        player_id: Option<SessionPlayerId>,
+        game_state: GameState,
+        reason: String,
+        available_actions: AvailableActions,
+    },
 }
 
 // Game state models
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
-    pub id: String,
+    pub id: SessionPlayerId,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u32>,
     pub color: Option<PieceColor>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PieceColor {
     White,
     Black,
@@ -131,6 +614,9 @@ pub struct GameState {
     pub board: HashMap<String, ChessPiece>,
     pub current_turn: PieceColor,
     pub status: GameStatus,
+    // Side a drawn game is scored as a win for; Black in armageddon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub awarded_to: Option<PieceColor>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -149,7 +635,7 @@ pub enum PieceType {
     King,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameStatus {
     Waiting,
     InProgress,
@@ -157,17 +643,81 @@ pub enum GameStatus {
     Stalemate,
     Draw,
     Timeout,
+    // A side did not make its first move in time; nobody wins or loses
+    Aborted,
+    // Stopped by an arbiter; clocks and moves wait for the game to resume
+    Paused,
+}
+
+// Play a move given in UCI (`e2e4`) or SAN (`e4`), refusing illegal ones
+pub fn play_notation(board: &mut Referee, move_notation: &str) -> Result<(), String> {
+    if board.play_uci(move_notation).is_ok() {
+        return Ok(());
+    }
+    board.play_san(move_notation).map(|_| ()).map_err(|e| e.to_string())
+}
+
+// Status of a game the rules ended
+pub fn status_after(termination: Termination) -> GameStatus {
+    match termination {
+        Termination::Checkmate => GameStatus::Checkmate,
+        Termination::Stalemate => GameStatus::Stalemate,
+        _ => GameStatus::Draw,
+    }
+}
+
+// The position after the moves played so far, so that clients and bots
+// need not replay the move list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub fen: String,
+    pub fullmove_number: u32,
+    pub halfmove_clock: u32,
+}
+
+impl PositionSnapshot {
+    pub fn of(referee: &Referee) -> Self {
+        Self {
+            fen: referee.fen(),
+            fullmove_number: referee.fullmove_number(),
+            halfmove_clock: referee.halfmove_clock(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveRecord {
-    pub player_id: String,
+    pub player_id: SessionPlayerId,
     pub move_notation: String,
     pub timestamp: u64,
+    // Time the mover thought, and the mover's clock after the move
+    #[serde(default)]
+    pub time_spent_ms: u64,
+    #[serde(default)]
+    pub remaining_ms: u64,
+}
+
+// Time a move took, for spectators; `average_ms` is the mover's average
+// per move so far, this one included
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MoveTiming {
+    pub spent_ms: u64,
+    pub remaining_ms: u64,
+    pub average_ms: u64,
+}
+
+// Cached engine evaluation of the position after a move, in pawns as the
+// analysis endpoint reports it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompactEval {
+    pub evaluation: f32,
+    pub depth: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_move: Option<String>,
 }
 
 impl MoveRecord {
-    pub fn new(player_id: String, move_notation: String) -> Self {
+    pub fn new(player_id: SessionPlayerId, move_notation: String, time_spent_ms: u64, remaining_ms: u64) -> Self {
         let timestamp = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_default()
@@ -177,13 +727,17 @@ impl MoveRecord {
             player_id,
             move_notation,
             timestamp,
+            time_spent_ms,
+            remaining_ms,
         }
     }
 }
 
+// Serialized through the impls below, which rebuild `board` on the way in
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Room {
-    pub id: String,
+    pub id: RoomId,
     pub players: Vec<Player>,
     pub game_state: Option<GameState>,
     pub moves: Vec<MoveRecord>,
@@ -192,15 +746,71 @@ pub struct Room {
     pub last_move_at: Option<u64>,
     pub initial_time_ms: u64,
     pub increment_ms: u64,
-    pub pending_takeback: Option<String>,
+    // Black's starting time when it differs from White's
+    #[serde(default)]
+    pub black_initial_time_ms: Option<u64>,
+    // Drawn games go to Black
+    #[serde(default)]
+    pub armageddon: bool,
+    // Tournament board the game is played on, if any
+    #[serde(default)]
+    pub board_tag: Option<BoardTag>,
+    pub pending_takeback: Option<SessionPlayerId>,
+    // Player whose draw offer stands until the opponent moves or answers
+    pub pending_draw: Option<SessionPlayerId>,
+    // When the pending takeback and draw offers were made, to expire them
+    #[serde(default)]
+    pub takeback_offered_at: Option<u64>,
+    #[serde(default)]
+    pub draw_offered_at: Option<u64>,
+    #[serde(default)]
+    pub settings: GameSettings,
+    // Accepted takebacks by the player who asked for them
+    #[serde(default)]
+    pub takebacks_used: HashMap<SessionPlayerId, u32>,
+    // Why the game is paused, kept with the room until it resumes
+    #[serde(default)]
+    pub adjournment: Option<Adjournment>,
+    // Sides already warned that their time is low, until it is back above
+    // the threshold
+    #[serde(default)]
+    pub low_time_alerted: Vec<PieceColor>,
+    // Rules-aware board behind `game_state`; not saved, but replayed from
+    // `moves` when the room is loaded
+    #[serde(skip)]
+    pub board: Referee,
+}
+
+impl Serialize for Room {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Room::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Room {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut room = Room::deserialize(deserializer)?;
+        for record in &room.moves {
+            play_notation(&mut room.board, &record.move_notation).map_err(serde::de::Error::custom)?;
+        }
+        Ok(room)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Adjournment {
+    pub reason: String,
+    pub paused_at_ms: u64,
+    // Whether the side to move's clock was running, to restart it on resume
+    pub clock_running: bool,
 }
 
 // Default time control: 10 minutes (600000ms)
-const DEFAULT_INITIAL_TIME_MS: u64 = 600_000;
-const DEFAULT_INCREMENT_MS: u64 = 0;
+pub const DEFAULT_INITIAL_TIME_MS: u64 = 600_000;
+pub const DEFAULT_INCREMENT_MS: u64 = 0;
 
 impl Room {
-    pub fn new(id: String) -> Self {
+    pub fn new(id: RoomId) -> Self {
         Self {
             id,
             players: Vec::new(),
@@ -211,22 +821,49 @@ impl Room {
             last_move_at: None,
             initial_time_ms: DEFAULT_INITIAL_TIME_MS,
             increment_ms: DEFAULT_INCREMENT_MS,
+            black_initial_time_ms: None,
+            armageddon: false,
+            board_tag: None,
             pending_takeback: None,
+            pending_draw: None,
+            takeback_offered_at: None,
+            draw_offered_at: None,
+            settings: GameSettings::default(),
+            takebacks_used: HashMap::new(),
+            adjournment: None,
+            low_time_alerted: Vec::new(),
+            board: Referee::default(),
         }
     }
 
-    pub fn new_with_time(id: String, initial_time_ms: u64, increment_ms: u64) -> Self {
+    pub fn new_with_time(id: RoomId, initial_time_ms: u64, increment_ms: u64) -> Self {
+        Self::new_with_clocks(id, initial_time_ms, initial_time_ms, increment_ms)
+    }
+
+    // White and Black start with different times, as in armageddon
+    pub fn new_with_clocks(id: RoomId, white_time_ms: u64, black_time_ms: u64, increment_ms: u64) -> Self {
         Self {
             id,
             players: Vec::new(),
             game_state: None,
             moves: Vec::new(),
-            white_remaining_ms: initial_time_ms,
-            black_remaining_ms: initial_time_ms,
+            white_remaining_ms: white_time_ms,
+            black_remaining_ms: black_time_ms,
             last_move_at: None,
-            initial_time_ms,
+            initial_time_ms: white_time_ms,
             increment_ms,
+            black_initial_time_ms: (black_time_ms != white_time_ms).then_some(black_time_ms),
+            armageddon: false,
+            board_tag: None,
             pending_takeback: None,
+            pending_draw: None,
+            takeback_offered_at: None,
+            draw_offered_at: None,
+            settings: GameSettings::default(),
+            takebacks_used: HashMap::new(),
+            adjournment: None,
+            low_time_alerted: Vec::new(),
+            board: Referee::default(),
         }
     }
     
@@ -255,16 +892,236 @@ impl Room {
         Ok(())
     }
     
-    pub fn remove_player(&mut self, player_id: &str) -> bool {
+    // Whether the room's settings let `player_id` ask for a takeback
+    pub fn may_take_back(&self, player_id: &SessionPlayerId) -> Result<(), String> {
+        match self.settings.takebacks {
+            TakebackPolicy::Allowed => Ok(()),
+            TakebackPolicy::Forbidden => Err("Takebacks are not allowed in this game".to_string()),
+            TakebackPolicy::Limited(limit) => {
+                if self.takebacks_used.get(player_id).copied().unwrap_or(0) < limit {
+                    Ok(())
+                } else {
+                    Err(format!("No takebacks left; each player may take back {} in this game", limit))
+                }
+            }
+        }
+    }
+
+    // Whether `viewer`, or an anonymous connection, may follow the game
+    pub fn visible_to(&self, viewer: Option<&SessionPlayerId>) -> bool {
+        self.settings.allow_spectators || viewer.is_some_and(|id| self.players.iter().any(|p| &p.id == id))
+    }
+
+    pub fn remove_player(&mut self, player_id: &SessionPlayerId) -> bool {
         let initial_len = self.players.len();
-        self.players.retain(|p| p.id != player_id);
+        self.players.retain(|p| &p.id != player_id);
         initial_len != self.players.len()
     }
     
-    pub fn add_move(&mut self, player_id: String, move_notation: String) {
-        let move_record = MoveRecord::new(player_id, move_notation);
+    pub fn add_move(&mut self, player_id: SessionPlayerId, move_notation: String, time_spent_ms: u64, remaining_ms: u64) {
+        let move_record = MoveRecord::new(player_id, move_notation, time_spent_ms, remaining_ms);
         self.moves.push(move_record);
     }
+
+    // Timing of the move at `ply`, counted from zero
+    pub fn move_timing(&self, ply: usize) -> Option<MoveTiming> {
+        let record = self.moves.get(ply)?;
+        let (count, total) = self.moves[..=ply]
+            .iter()
+            .filter(|other| other.player_id == record.player_id)
+            .fold((0u64, 0u64), |(count, total), other| (count + 1, total + other.time_spent_ms));
+        Some(MoveTiming {
+            spent_ms: record.time_spent_ms,
+            remaining_ms: record.remaining_ms,
+            average_ms: total / count.max(1),
+        })
+    }
+
+    pub fn position(&self) -> PositionSnapshot {
+        PositionSnapshot::of(&self.board)
+    }
+
+    // Take back the last `plies` moves, unmaking them on the board. Undone on
+    // a copy, so that a board short of history is left as it was
+    pub fn take_back(&mut self, plies: usize) -> Result<(), String> {
+        if self.moves.len() < plies {
+            return Err("Not enough moves to take back".to_string());
+        }
+        let mut board = self.board.clone();
+        for _ in 0..plies {
+            board.take_back().map_err(|e| e.to_string())?;
+        }
+        self.board = board;
+        self.moves.truncate(self.moves.len() - plies);
+        Ok(())
+    }
+
+    pub fn side_to_move(&self) -> PieceColor {
+        if self.board.white_to_move() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        }
+    }
+
+    // Side whose clock is ticking: the side to move once the game started
+    pub fn running_clock(&self) -> Option<PieceColor> {
+        match (&self.game_state, self.last_move_at) {
+            (Some(game_state), Some(_)) if matches!(game_state.status, GameStatus::InProgress) => {
+                Some(game_state.current_turn.clone())
+            }
+            _ => None,
+        }
+    }
+
+    // Side that let `timeout_ms` pass without moving while the game has
+    // not really begun: White before the first move, Black before its reply
+    pub fn first_move_overdue(&self, now_ms: u64, timeout_ms: u64) -> Option<PieceColor> {
+        let game_state = self.game_state.as_ref()?;
+        if !matches!(game_state.status, GameStatus::InProgress) || self.moves.len() >= 2 {
+            return None;
+        }
+        let since = self.last_move_at?;
+        (now_ms.saturating_sub(since) > timeout_ms).then(|| game_state.current_turn.clone())
+    }
+
+    // What each side may do now. Nothing is open to a side nobody sits at,
+    // nor in a game that is waiting, paused or over.
+    pub fn available_actions(&self) -> AvailableActions {
+        AvailableActions {
+            white: self.actions_of(PieceColor::White),
+            black: self.actions_of(PieceColor::Black),
+        }
+    }
+
+    fn actions_of(&self, color: PieceColor) -> SideActions {
+        let in_progress = matches!(self.game_state.as_ref().map(|g| &g.status), Some(GameStatus::InProgress));
+        let player = self.players.iter().find(|p| p.color.as_ref() == Some(&color));
+        let Some(player) = player.filter(|_| in_progress && self.adjournment.is_none()) else {
+            return SideActions::default();
+        };
+        SideActions {
+            can_offer_draw: self.pending_draw.is_none(),
+            can_takeback: self.pending_takeback.is_none()
+                && self.moves.len() >= 2
+                && self.may_take_back(&player.id).is_ok(),
+            can_claim_draw: false,
+            can_resign: true,
+            can_abort: self.moves.len() < 2,
+        }
+    }
+
+    // Remaining time of both sides at `now_ms`, counting the running clock down
+    pub fn clock_update(&self, now_ms: u64, client_time_ms: Option<u64>) -> ServerMessage {
+        let running = self.running_clock();
+        let elapsed_ms = self
+            .last_move_at
+            .map(|last| now_ms.saturating_sub(last))
+            .unwrap_or(0);
+        let (white_remaining_ms, black_remaining_ms) = match running {
+            Some(PieceColor::White) => (self.white_remaining_ms.saturating_sub(elapsed_ms), self.black_remaining_ms),
+            Some(PieceColor::Black) => (self.white_remaining_ms, self.black_remaining_ms.saturating_sub(elapsed_ms)),
+            None => (self.white_remaining_ms, self.black_remaining_ms),
+        };
+
+        ServerMessage::ClockUpdate {
+            room_id: self.id,
+            white_remaining_ms,
+            black_remaining_ms,
+            running,
+            server_time_ms: now_ms,
+            client_time_ms,
+        }
+    }
+
+    // Record an offer of `kind` by `player_id`, made at `now_ms`
+    pub fn make_offer(&mut self, kind: OfferKind, player_id: SessionPlayerId, now_ms: u64) {
+        match kind {
+            OfferKind::Draw => {
+                self.pending_draw = Some(player_id);
+                self.draw_offered_at = Some(now_ms);
+            }
+            OfferKind::Takeback => {
+                self.pending_takeback = Some(player_id);
+                self.takeback_offered_at = Some(now_ms);
+            }
+        }
+    }
+
+    // Withdraw the pending offer of `kind`, if any, returning the message
+    // telling the room it expired
+    pub fn expire_offer(&mut self, kind: OfferKind, reason: &str) -> Option<ServerMessage> {
+        let player_id = match kind {
+            OfferKind::Draw => {
+                self.draw_offered_at = None;
+                self.pending_draw.take()
+            }
+            OfferKind::Takeback => {
+                self.takeback_offered_at = None;
+                self.pending_takeback.take()
+            }
+        }?;
+        Some(ServerMessage::OfferExpired {
+            room_id: self.id,
+            offer: kind,
+            player_id,
+            reason: reason.to_string(),
+        })
+    }
+
+    // Withdraw the offers left unanswered for `ttl_ms`; zero keeps offers
+    // until they are answered or a move is played
+    pub fn expire_stale_offers(&mut self, now_ms: u64, ttl_ms: u64) -> Vec<ServerMessage> {
+        if ttl_ms == 0 {
+            return Vec::new();
+        }
+        let stale = |offered_at: Option<u64>| offered_at.is_some_and(|at| now_ms.saturating_sub(at) >= ttl_ms);
+        let mut expired = Vec::new();
+        if stale(self.draw_offered_at) {
+            expired.extend(self.expire_offer(OfferKind::Draw, "Draw offer went unanswered"));
+        }
+        if stale(self.takeback_offered_at) {
+            expired.extend(self.expire_offer(OfferKind::Takeback, "Takeback request went unanswered"));
+        }
+        expired
+    }
+
+    pub fn clock_settings(&self, low_time_ms: u64) -> ClockSettings {
+        ClockSettings {
+            white_initial_ms: self.initial_time_ms,
+            black_initial_ms: self.black_initial_time_ms.unwrap_or(self.initial_time_ms),
+            increment_ms: self.increment_ms,
+            low_time_ms,
+        }
+    }
+
+    // Alerts for the sides whose time fell below `threshold_ms` since the
+    // last check. A side warned once is warned again only after an increment
+    // or takeback brought it back above the threshold.
+    pub fn low_time_alerts(&mut self, now_ms: u64, threshold_ms: u64) -> Vec<ServerMessage> {
+        let running = self.running_clock();
+        let ServerMessage::ClockUpdate { white_remaining_ms, black_remaining_ms, .. } = self.clock_update(now_ms, None)
+        else {
+            return Vec::new();
+        };
+
+        let mut alerts = Vec::new();
+        for (color, remaining_ms) in [(PieceColor::White, white_remaining_ms), (PieceColor::Black, black_remaining_ms)] {
+            let alerted = self.low_time_alerted.contains(&color);
+            if remaining_ms >= threshold_ms {
+                self.low_time_alerted.retain(|side| side != &color);
+            } else if !alerted && running.as_ref() == Some(&color) {
+                self.low_time_alerted.push(color.clone());
+                alerts.push(ServerMessage::LowTime {
+                    room_id: self.id,
+                    color,
+                    remaining_ms,
+                    threshold_ms,
+                });
+            }
+        }
+        alerts
+    }
 }
 
 impl GameState {
@@ -297,9 +1154,17 @@ impl GameState {
             board,
             current_turn: PieceColor::White,
             status: GameStatus::InProgress,
+            awarded_to: None,
         }
     }
     
+    // Score a drawn ending for Black when the game is an armageddon
+    pub fn settle_draw(&mut self, armageddon: bool) {
+        if armageddon && matches!(self.status, GameStatus::Draw | GameStatus::Stalemate) {
+            self.awarded_to = Some(PieceColor::Black);
+        }
+    }
+
     // Apply a move to the game state
     // This is a simplified implementation that doesn't validate chess rules
     pub fn apply_move(&mut self, move_notation: &str) -> Result<(), String> {
@@ -319,3 +1184,85 @@ impl GameState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_id_must_be_uuid() {
+        let room_id = RoomId::new();
+        assert_eq!(room_id.to_string().parse::<RoomId>(), Ok(room_id));
+        assert!("test-room".parse::<RoomId>().is_err());
+        assert!("".parse::<RoomId>().is_err());
+    }
+
+    #[test]
+    fn test_session_player_id_validation() {
+        assert!("player-1".parse::<SessionPlayerId>().is_ok());
+        assert!("white_player".parse::<SessionPlayerId>().is_ok());
+        assert!("".parse::<SessionPlayerId>().is_err());
+        assert!("player 1".parse::<SessionPlayerId>().is_err());
+        assert!("a".repeat(MAX_PLAYER_ID_LEN + 1).parse::<SessionPlayerId>().is_err());
+    }
+
+    #[test]
+    fn test_payload_ids_are_validated() {
+        let room_id = RoomId::new();
+        let valid = format!(
+            r#"{{"type": "JoinRoom", "payload": {{"room_id": "{}", "player_id": "player-1"}}}}"#,
+            room_id
+        );
+        match serde_json::from_str::<ClientMessage>(&valid).unwrap() {
+            ClientMessage::JoinRoom(payload) => assert_eq!(payload.room_id, room_id),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+
+        let bad_room = r#"{"type": "JoinRoom", "payload": {"room_id": "lobby", "player_id": "player-1"}}"#;
+        assert!(serde_json::from_str::<ClientMessage>(bad_room).is_err());
+
+        let bad_player = format!(
+            r#"{{"type": "LeaveRoom", "payload": {{"room_id": "{}", "player_id": ""}}}}"#,
+            room_id
+        );
+        assert!(serde_json::from_str::<ClientMessage>(&bad_player).is_err());
+    }
+
+    #[test]
+    fn test_loaded_room_replays_its_moves() {
+        let mut room = Room::new(RoomId::new());
+        let player: SessionPlayerId = "player-1".parse().unwrap();
+        for notation in ["e2e4", "e5", "Nf3"] {
+            play_notation(&mut room.board, notation).unwrap();
+            room.add_move(player.clone(), notation.to_string(), 0, 0);
+        }
+
+        let saved = serde_json::to_string(&room).unwrap();
+        let loaded: Room = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.position(), room.position());
+        assert_eq!(loaded.side_to_move(), PieceColor::Black);
+
+        // A move list the rules refuse is not loaded
+        room.add_move(player, "Ke4".to_string(), 0, 0);
+        assert!(serde_json::from_str::<Room>(&serde_json::to_string(&room).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_failed_take_back_leaves_board_and_moves_alone() {
+        let mut room = Room::new(RoomId::new());
+        let player: SessionPlayerId = "player-1".parse().unwrap();
+        // The board only knows the last of the two recorded moves
+        room.add_move(player.clone(), "e2e4".to_string(), 0, 0);
+        play_notation(&mut room.board, "d2d4").unwrap();
+        room.add_move(player, "d2d4".to_string(), 0, 0);
+        let before = room.position();
+
+        assert!(room.take_back(2).is_err());
+        assert_eq!(room.position(), before);
+        assert_eq!(room.moves.len(), 2);
+
+        room.take_back(1).unwrap();
+        assert_eq!(room.side_to_move(), PieceColor::White);
+        assert_eq!(room.moves.len(), 1);
+    }
+}
@@ -0,0 +1,523 @@
+use redis::aio::MultiplexedConnection;
+use redis::{RedisResult, Script};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use crate::game::ServerState;
+use crate::latency::now_ms;
+use crate::lease::{held_lease, lease_key};
+use crate::models::{BoardTag, GameStatus, PieceColor, Room, RoomId};
+
+// Games a list holds unless the client asks for fewer
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 100;
+
+// How long an instance's entries outlive it in Redis
+pub const DEFAULT_MIRROR_TTL_MS: u64 = 30_000;
+
+// Set of the room ids with an entry in Redis
+const MIRROR_INDEX_KEY: &str = "socket:ongoing";
+
+// Writes an entry, or removes it when no JSON is given, unless the room is
+// leased and the lease no longer carries the value the change was made under
+const MIRROR_WRITE_SCRIPT: &str = r#"
+if ARGV[2] ~= '' and redis.call('GET', KEYS[3]) ~= ARGV[2] then
+  return 0
+end
+if ARGV[3] == '' then
+  redis.call('DEL', KEYS[1])
+  redis.call('SREM', KEYS[2], ARGV[1])
+else
+  redis.call('SET', KEYS[1], ARGV[3], 'PX', ARGV[4])
+  redis.call('SADD', KEYS[2], ARGV[1])
+end
+return 1
+"#;
+
+// Time control category, by the expected length of a game of 40 moves each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeControl {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+impl TimeControl {
+    pub fn of(initial_time_ms: u64, increment_ms: u64) -> Self {
+        match (initial_time_ms + 40 * increment_ms) / 1_000 {
+            0..=179 => Self::Bullet,
+            180..=479 => Self::Blitz,
+            480..=1_499 => Self::Rapid,
+            _ => Self::Classical,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bullet" => Some(Self::Bullet),
+            "blitz" => Some(Self::Blitz),
+            "rapid" => Some(Self::Rapid),
+            "classical" => Some(Self::Classical),
+            _ => None,
+        }
+    }
+}
+
+// Rules a game is played by; rooms only play standard chess so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    Standard,
+}
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+        }
+    }
+}
+
+// A side of a listed game; ratings are the ones the players' clients gave
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seat {
+    pub name: String,
+    pub rating: Option<u32>,
+}
+
+// What the watch page shows of one game being played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OngoingGame {
+    pub room_id: RoomId,
+    pub white: Seat,
+    pub black: Seat,
+    pub time_control: TimeControl,
+    pub initial_time_ms: u64,
+    pub increment_ms: u64,
+    pub variant: Variant,
+    pub moves: usize,
+    pub last_move: Option<String>,
+    pub paused: bool,
+    // Tournament board the game is played on, if any
+    pub board: Option<BoardTag>,
+    pub started_at_ms: u64,
+}
+
+impl OngoingGame {
+    // A room's game as listed, or None when it is not one to watch: not
+    // started, over, or closed to spectators
+    pub fn of(room: &Room, started_at_ms: u64) -> Option<Self> {
+        let game_state = room.game_state.as_ref()?;
+        if !matches!(game_state.status, GameStatus::InProgress | GameStatus::Paused) || !room.settings.allow_spectators {
+            return None;
+        }
+        let seat = |color: PieceColor| {
+            room.players
+                .iter()
+                .find(|p| p.color.as_ref() == Some(&color))
+                .map(|p| Seat { name: p.name.clone(), rating: p.rating })
+        };
+
+        Some(OngoingGame {
+            room_id: room.id,
+            white: seat(PieceColor::White)?,
+            black: seat(PieceColor::Black)?,
+            time_control: TimeControl::of(room.initial_time_ms, room.increment_ms),
+            initial_time_ms: room.initial_time_ms,
+            increment_ms: room.increment_ms,
+            variant: Variant::Standard,
+            moves: room.moves.len(),
+            last_move: room.moves.last().map(|m| m.move_notation.clone()),
+            paused: matches!(game_state.status, GameStatus::Paused),
+            board: room.board_tag,
+            started_at_ms,
+        })
+    }
+
+    // Rating of the weaker side, when both sides have one
+    fn min_rating(&self) -> Option<u32> {
+        Some(self.white.rating?.min(self.black.rating?))
+    }
+
+    fn average_rating(&self) -> Option<u32> {
+        Some((self.white.rating? + self.black.rating?) / 2)
+    }
+}
+
+// Which games a list holds, from its query string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OngoingFilter {
+    pub time_control: Option<TimeControl>,
+    // Both sides rated at least this; unrated games never match
+    pub min_rating: Option<u32>,
+    // Variant name; names no room plays match nothing
+    pub variant: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for OngoingFilter {
+    fn default() -> Self {
+        Self { time_control: None, min_rating: None, variant: None, limit: DEFAULT_LIMIT }
+    }
+}
+
+impl OngoingFilter {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for (name, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            if value.is_empty() {
+                continue;
+            }
+            match name {
+                "time_control" => {
+                    let time_control = TimeControl::parse(value).ok_or_else(|| format!("Unknown time control: {}", value))?;
+                    filter.time_control = Some(time_control);
+                }
+                "min_rating" => {
+                    let rating = value.parse().map_err(|_| format!("Invalid min_rating: {}", value))?;
+                    filter.min_rating = Some(rating);
+                }
+                "variant" => filter.variant = Some(value.to_string()),
+                "limit" => {
+                    let limit: usize = value.parse().map_err(|_| format!("Invalid limit: {}", value))?;
+                    filter.limit = limit.clamp(1, MAX_LIMIT);
+                }
+                _ => {}
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, game: &OngoingGame) -> bool {
+        self.time_control.is_none_or(|time_control| game.time_control == time_control)
+            && self.min_rating.is_none_or(|min| game.min_rating().is_some_and(|rating| rating >= min))
+            && self.variant.as_deref().is_none_or(|variant| game.variant.name() == variant)
+    }
+
+    // The matching games, highest rated first, then the most recently
+    // started
+    pub fn apply(&self, games: impl IntoIterator<Item = OngoingGame>) -> Vec<OngoingGame> {
+        let mut games: Vec<OngoingGame> = games.into_iter().filter(|game| self.matches(game)).collect();
+        games.sort_by_key(|game| (Reverse(game.average_rating()), Reverse(game.started_at_ms), game.room_id.to_string()));
+        games.truncate(self.limit);
+        games
+    }
+}
+
+lazy_static::lazy_static! {
+    // Kept apart from the game state so that listing games never waits on
+    // play; taken after the game state lock when both are held
+    static ref ONGOING: RwLock<HashMap<RoomId, OngoingGame>> = RwLock::new(HashMap::new());
+}
+
+// Bring the entries of `rooms` in line with the rooms, adding the games
+// that started and dropping the ones that ended or whose room is gone.
+// Called under the state lock after every change to a room's lifecycle.
+pub fn publish(state: &ServerState, rooms: &[RoomId]) {
+    let mut ongoing = ONGOING.write().unwrap();
+    for room_id in rooms {
+        let started_at_ms = ongoing.get(room_id).map_or_else(now_ms, |game| game.started_at_ms);
+        match state.rooms.get(room_id).and_then(|room| OngoingGame::of(room, started_at_ms)) {
+            Some(game) if ongoing.get(room_id) != Some(&game) => {
+                mirror(Change::Upsert(game.clone()));
+                ongoing.insert(*room_id, game);
+            }
+            Some(_) => {}
+            None => {
+                if ongoing.remove(room_id).is_some() {
+                    mirror(Change::Remove(*room_id));
+                }
+            }
+        }
+    }
+}
+
+// The games of this instance that match
+pub fn local(filter: &OngoingFilter) -> Vec<OngoingGame> {
+    filter.apply(ONGOING.read().unwrap().values().cloned())
+}
+
+// The games of every instance sharing its list in Redis, or of this one
+// when none does or Redis cannot be reached
+pub async fn list(filter: &OngoingFilter) -> Vec<OngoingGame> {
+    if let Some(mirror) = MIRROR.get() {
+        match mirror.read().await {
+            Ok(games) => return filter.apply(games),
+            Err(e) => log::warn!("Listing ongoing games from Redis failed, listing this instance's: {}", e),
+        }
+    }
+    local(filter)
+}
+
+enum Change {
+    Upsert(OngoingGame),
+    Remove(RoomId),
+}
+
+impl Change {
+    fn room_id(&self) -> &RoomId {
+        match self {
+            Change::Upsert(game) => &game.room_id,
+            Change::Remove(room_id) => room_id,
+        }
+    }
+}
+
+// A change with the lease value of its room when it was made, if leased
+type FencedChange = (Change, Option<String>);
+
+pub fn mirror_key(room_id: &RoomId) -> String {
+    format!("{}:{}", MIRROR_INDEX_KEY, room_id)
+}
+
+/// Copies of every instance's entries in Redis, so that any instance lists
+/// the games of all of them. Entries expire after `ttl` unless refreshed, so
+/// the games of an instance that went away drop out of the list.
+#[derive(Clone)]
+pub struct OngoingMirror {
+    conn: MultiplexedConnection,
+    ttl: Duration,
+    changes: mpsc::UnboundedSender<FencedChange>,
+}
+
+static MIRROR: OnceLock<OngoingMirror> = OnceLock::new();
+
+impl OngoingMirror {
+    pub async fn connect(redis_url: &str, ttl: Duration) -> RedisResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+        // Changes are written one at a time, in the order the rooms changed
+        let (changes, mut pending) = mpsc::unbounded_channel();
+        let writer = Self { conn, ttl, changes };
+        let mirror = writer.clone();
+        tokio::spawn(async move {
+            while let Some((change, lease)) = pending.recv().await {
+                match writer.write(&change, lease.as_deref()).await {
+                    Ok(true) => {}
+                    Ok(false) => log::warn!("Not mirroring room {}, whose lease was lost", change.room_id()),
+                    Err(e) => log::warn!("Failed to mirror an ongoing game to Redis: {}", e),
+                }
+            }
+        });
+        Ok(mirror)
+    }
+
+    // `false` when the lease the change was made under is gone, so that an
+    // instance that lost a room cannot overwrite what its new host wrote
+    async fn write(&self, change: &Change, lease: Option<&str>) -> RedisResult<bool> {
+        let room_id = change.room_id();
+        let json = match change {
+            Change::Upsert(game) => serde_json::to_string(game).unwrap_or_default(),
+            Change::Remove(_) => String::new(),
+        };
+        let written: i64 = Script::new(MIRROR_WRITE_SCRIPT)
+            .key(mirror_key(room_id))
+            .key(MIRROR_INDEX_KEY)
+            .key(lease_key(room_id))
+            .arg(room_id.to_string())
+            .arg(lease.unwrap_or_default())
+            .arg(json)
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(written == 1)
+    }
+
+    // Every instance's entries. Ids whose entry expired are dropped from the
+    // index; one refreshed meanwhile is put back by its next refresh.
+    async fn read(&self) -> RedisResult<Vec<OngoingGame>> {
+        let mut conn = self.conn.clone();
+        let ids: Vec<String> = redis::cmd("SMEMBERS").arg(MIRROR_INDEX_KEY).query_async(&mut conn).await?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| format!("{}:{}", MIRROR_INDEX_KEY, id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+
+        let mut games = Vec::new();
+        let mut expired = Vec::new();
+        for (id, value) in ids.iter().zip(values) {
+            match value.and_then(|json| serde_json::from_str(&json).ok()) {
+                Some(game) => games.push(game),
+                None => expired.push(id),
+            }
+        }
+        if !expired.is_empty() {
+            let _: i64 = redis::cmd("SREM").arg(MIRROR_INDEX_KEY).arg(&expired).query_async(&mut conn).await?;
+        }
+        Ok(games)
+    }
+
+    /// Rewrite this instance's entries every third of the TTL, keeping them
+    /// from expiring while their games go on.
+    pub fn spawn_refresh(&self) {
+        let mirror = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(mirror.ttl / 3);
+            loop {
+                ticker.tick().await;
+                // Queued under the lock, so no change to a game can be
+                // overtaken by an older copy of it
+                let ongoing = ONGOING.read().unwrap();
+                for game in ongoing.values() {
+                    mirror.send(Change::Upsert(game.clone()));
+                }
+            }
+        });
+    }
+
+    // Queue a change under the lease its room is held by now
+    fn send(&self, change: Change) {
+        let lease = held_lease(change.room_id());
+        // The writer only stops with the runtime
+        let _ = self.changes.send((change, lease));
+    }
+}
+
+// Share this instance's games in Redis and list those of every instance
+pub fn init_ongoing_mirror(mirror: OngoingMirror) {
+    mirror.spawn_refresh();
+    if MIRROR.set(mirror).is_err() {
+        log::warn!("The ongoing games mirror was already initialized");
+    }
+}
+
+fn mirror(change: Change) {
+    if let Some(mirror) = MIRROR.get() {
+        mirror.send(change);
+    }
+}
+
+// A request for the list, with the query string of its filters
+#[derive(Debug, PartialEq, Eq)]
+pub struct OngoingRequest {
+    pub query: String,
+    // Bytes of the request head, read off the connection before answering
+    pub head_len: usize,
+}
+
+// Answer `GET /v1/games/ongoing` with the matching games as JSON
+pub async fn serve(mut stream: TcpStream, request: OngoingRequest) -> Result<(), Box<dyn std::error::Error>> {
+    let mut head = vec![0; request.head_len];
+    stream.read_exact(&mut head).await?;
+
+    let (status, body) = match OngoingFilter::parse(&request.query) {
+        Ok(filter) => ("200 OK", serde_json::json!({ "games": list(&filter).await })),
+        Err(e) => ("400 Bad Request", serde_json::json!({ "error": e })),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-cache\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{create_room_with_time, join_room_rated, leave_room, remove_room, send_move, GAME_STATE};
+    use crate::models::SessionPlayerId;
+
+    fn player(id: &str) -> SessionPlayerId {
+        id.parse().unwrap()
+    }
+
+    fn game(room_id: RoomId, initial_time_ms: u64, ratings: (Option<u32>, Option<u32>), started_at_ms: u64) -> OngoingGame {
+        OngoingGame {
+            room_id,
+            white: Seat { name: "White".to_string(), rating: ratings.0 },
+            black: Seat { name: "Black".to_string(), rating: ratings.1 },
+            time_control: TimeControl::of(initial_time_ms, 0),
+            initial_time_ms,
+            increment_ms: 0,
+            variant: Variant::Standard,
+            moves: 0,
+            last_move: None,
+            paused: false,
+            board: None,
+            started_at_ms,
+        }
+    }
+
+    #[test]
+    fn test_time_controls_count_forty_increments() {
+        assert_eq!(TimeControl::of(60_000, 0), TimeControl::Bullet);
+        assert_eq!(TimeControl::of(120_000, 1_000), TimeControl::Bullet);
+        assert_eq!(TimeControl::of(300_000, 3_000), TimeControl::Blitz);
+        assert_eq!(TimeControl::of(600_000, 5_000), TimeControl::Rapid);
+        assert_eq!(TimeControl::of(1_800_000, 0), TimeControl::Classical);
+    }
+
+    #[test]
+    fn test_filters_and_ordering() {
+        let (a, b, c, d) = (RoomId::new(), RoomId::new(), RoomId::new(), RoomId::new());
+        let games = vec![
+            game(a, 180_000, (Some(1500), Some(1700)), 1),
+            game(b, 180_000, (Some(2100), Some(2300)), 2),
+            game(c, 600_000, (Some(1900), Some(1800)), 3),
+            game(d, 180_000, (None, Some(2500)), 4),
+        ];
+        let ids = |filter: OngoingFilter| filter.apply(games.clone()).iter().map(|game| game.room_id).collect::<Vec<_>>();
+
+        assert_eq!(ids(OngoingFilter::default()), [b, c, a, d]);
+        assert_eq!(ids(OngoingFilter::parse("time_control=blitz").unwrap()), [b, a, d]);
+        assert_eq!(ids(OngoingFilter::parse("min_rating=1800&time_control=").unwrap()), [b, c]);
+        assert_eq!(ids(OngoingFilter::parse("variant=standard&limit=1").unwrap()), [b]);
+        assert!(ids(OngoingFilter::parse("variant=chess960").unwrap()).is_empty());
+
+        assert!(OngoingFilter::parse("time_control=hyperbullet").is_err());
+        assert!(OngoingFilter::parse("min_rating=high").is_err());
+        assert_eq!(OngoingFilter::parse("limit=1000").unwrap().limit, MAX_LIMIT);
+    }
+
+    #[test]
+    fn test_games_are_listed_while_they_are_played() {
+        let room_id = create_room_with_time(60_000, 0);
+        let listed = |room_id: RoomId| ONGOING.read().unwrap().get(&room_id).cloned();
+        let (white, black) = (player("ongoing-white"), player("ongoing-black"));
+
+        join_room_rated(&room_id, &white, Some("Alice".to_string()), Some(1850)).unwrap();
+        assert!(listed(room_id).is_none());
+        join_room_rated(&room_id, &black, Some("Bob".to_string()), None).unwrap();
+        let started = listed(room_id).unwrap();
+        assert_eq!((started.white.rating, started.black.name.as_str()), (Some(1850), "Bob"));
+        assert_eq!(started.time_control, TimeControl::Bullet);
+
+        send_move(&room_id, &white, "e2e4", None, 0).unwrap();
+        let moved = listed(room_id).unwrap();
+        assert_eq!((moved.moves, moved.last_move.as_deref()), (1, Some("e2e4")));
+        assert_eq!(moved.started_at_ms, started.started_at_ms);
+
+        leave_room(&room_id, &white).unwrap();
+        leave_room(&room_id, &black).unwrap();
+        assert!(!GAME_STATE.lock().unwrap().rooms.contains_key(&room_id));
+        assert!(listed(room_id).is_none());
+    }
+
+    #[test]
+    fn test_private_games_are_not_listed() {
+        let room_id = create_room_with_time(60_000, 0);
+        GAME_STATE.lock().unwrap().rooms.get_mut(&room_id).unwrap().settings.allow_spectators = false;
+        join_room_rated(&room_id, &player("private-white"), None, None).unwrap();
+        join_room_rated(&room_id, &player("private-black"), None, None).unwrap();
+        assert!(!ONGOING.read().unwrap().contains_key(&room_id));
+        remove_room(&room_id);
+    }
+}
@@ -0,0 +1,321 @@
+// Deterministic simulation of the game layer. Scripted games are driven
+// through the functions the connection handlers call, on a manual clock, and
+// the rooms are checked after every step. The script comes from a seed, so a
+// failing game replays exactly and a fuzzer only has to supply seeds.
+
+use std::fmt;
+use std::sync::Arc;
+
+use chess::{ManualTime, TimeSource};
+
+use crate::game::{self, GAME_STATE};
+use crate::latency::{with_time_source, MAX_LAG_COMPENSATION_MS, MIN_LAG_COMPENSATION_MS};
+use crate::models::{GameStatus, PieceColor, Room, RoomId, ServerMessage, SessionPlayerId};
+
+// Steps still taken once a game has ended, to check that it stays over
+const STEPS_AFTER_END: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub initial_time_ms: u64,
+    pub increment_ms: u64,
+    // Half-moves after which the game is left unfinished
+    pub max_plies: usize,
+    // Longest a side thinks before moving; past its clock, it loses on time
+    pub max_think_ms: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            initial_time_ms: 60_000,
+            increment_ms: 1_000,
+            max_plies: 120,
+            max_think_ms: 4_000,
+        }
+    }
+}
+
+// How a simulated game went
+#[derive(Debug, Clone)]
+pub struct SimReport {
+    pub seed: u64,
+    pub steps: usize,
+    pub plies: usize,
+    pub status: GameStatus,
+}
+
+// An invariant the game layer broke, with what it takes to replay it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub seed: u64,
+    pub step: usize,
+    pub action: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {} step {} ({}): {}", self.seed, self.step, self.action, self.message)
+    }
+}
+
+// What a player does in one step of the script
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Move { mover: PieceColor, notation: String, think_ms: u64, lag_ms: u64 },
+    OfferDraw(PieceColor),
+    AcceptDraw(PieceColor),
+    DeclineDraw(PieceColor),
+    OfferTakeback(PieceColor),
+    AcceptTakeback(PieceColor),
+    RejectTakeback(PieceColor),
+    Pause,
+    Resume,
+    Wait(u64),
+}
+
+// SplitMix64: small, seedable and the same on every platform
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        items.get(self.below(items.len() as u64) as usize)
+    }
+}
+
+fn player(color: &PieceColor) -> SessionPlayerId {
+    match color {
+        PieceColor::White => "sim_white".parse().unwrap(),
+        PieceColor::Black => "sim_black".parse().unwrap(),
+    }
+}
+
+fn other(color: &PieceColor) -> PieceColor {
+    match color {
+        PieceColor::White => PieceColor::Black,
+        PieceColor::Black => PieceColor::White,
+    }
+}
+
+fn status(room: &Room) -> GameStatus {
+    room.game_state.as_ref().map_or(GameStatus::Waiting, |g| g.status.clone())
+}
+
+fn is_finished(status: &GameStatus) -> bool {
+    !matches!(status, GameStatus::Waiting | GameStatus::InProgress | GameStatus::Paused)
+}
+
+fn snapshot(room_id: &RoomId) -> Option<Room> {
+    GAME_STATE.lock().unwrap().rooms.get(room_id).cloned()
+}
+
+// The next step, mostly moves: legal ones by the side to move, now and then
+// a move out of turn or one of the other things players and arbiters do
+fn next_action(rng: &mut Rng, room: &Room, config: &SimConfig) -> Action {
+    let to_move = room.side_to_move();
+    let roll = rng.below(100);
+    let legal = room.board.legal_moves();
+    let either = if rng.below(2) == 0 { PieceColor::White } else { PieceColor::Black };
+    match roll {
+        0..=69 => Action::Move {
+            notation: rng.pick(&legal).cloned().unwrap_or_else(|| "e2e4".to_string()),
+            mover: to_move,
+            think_ms: rng.below(config.max_think_ms + 1),
+            lag_ms: MIN_LAG_COMPENSATION_MS + rng.below(MAX_LAG_COMPENSATION_MS - MIN_LAG_COMPENSATION_MS + 1),
+        },
+        70..=73 => Action::Move {
+            notation: rng.pick(&legal).cloned().unwrap_or_else(|| "e7e5".to_string()),
+            mover: other(&to_move),
+            think_ms: rng.below(config.max_think_ms + 1),
+            lag_ms: MIN_LAG_COMPENSATION_MS,
+        },
+        74..=77 => Action::OfferDraw(either),
+        78..=80 => Action::AcceptDraw(either),
+        81..=82 => Action::DeclineDraw(either),
+        83..=86 => Action::OfferTakeback(either),
+        87..=89 => Action::AcceptTakeback(either),
+        90..=91 => Action::RejectTakeback(either),
+        92..=93 => Action::Pause,
+        94..=96 => Action::Resume,
+        _ => Action::Wait(rng.below(3 * config.max_think_ms + 1)),
+    }
+}
+
+fn perform(room_id: &RoomId, action: &Action, time: &ManualTime) -> Result<ServerMessage, String> {
+    match action {
+        Action::Move { mover, notation, think_ms, lag_ms } => {
+            time.advance(*think_ms);
+            game::send_move(room_id, &player(mover), notation, None, *lag_ms)
+        }
+        Action::OfferDraw(color) => game::offer_draw(room_id, &player(color)),
+        Action::AcceptDraw(color) => game::accept_draw(room_id, &player(color)),
+        Action::DeclineDraw(color) => game::decline_draw(room_id, &player(color)),
+        Action::OfferTakeback(color) => game::offer_takeback(room_id, &player(color)),
+        Action::AcceptTakeback(color) => game::accept_takeback(room_id, &player(color)),
+        Action::RejectTakeback(color) => game::reject_takeback(room_id, &player(color)),
+        Action::Pause => game::pause_game(room_id, "Simulated adjournment"),
+        Action::Resume => game::resume_game(room_id),
+        Action::Wait(ms) => {
+            time.advance(*ms);
+            game::clock_sync(room_id, None)
+        }
+    }
+}
+
+// What must hold of `after`, the room once `action` had `outcome` on `before`
+fn check(before: &Room, after: &Room, action: &Action, outcome: &Result<ServerMessage, String>, now_ms: u64) -> Result<(), String> {
+    // Moves alternate, starting with White
+    for (ply, record) in after.moves.iter().enumerate() {
+        let expected = if ply % 2 == 0 { PieceColor::White } else { PieceColor::Black };
+        if record.player_id != player(&expected) {
+            return Err(format!("move {} ({}) was played by {}", ply, record.move_notation, record.player_id));
+        }
+    }
+    if after.board.moves().len() != after.moves.len() {
+        return Err(format!("board has {} moves, the log {}", after.board.moves().len(), after.moves.len()));
+    }
+    if let Some(game_state) = &after.game_state {
+        if game_state.current_turn != after.side_to_move() {
+            return Err(format!("{:?} to move, but the board has {:?}", game_state.current_turn, after.side_to_move()));
+        }
+    }
+
+    // The state machine only moves forward, save for pausing
+    let (from, to) = (status(before), status(after));
+    let legal_transition = match (&from, &to) {
+        (from, to) if from == to => true,
+        (GameStatus::InProgress, _) => true,
+        (GameStatus::Paused, GameStatus::InProgress) => true,
+        _ => false,
+    };
+    if !legal_transition {
+        return Err(format!("status went from {:?} to {:?}", from, to));
+    }
+    if is_finished(&from) && after.moves.len() != before.moves.len() {
+        return Err(format!("moves changed after the game ended in {:?}", from));
+    }
+
+    // Clocks never run below zero: an accepted move was made within the
+    // mover's time and the lag allowance, and it was charged exactly that
+    let expected_plies = match (action, outcome) {
+        (Action::Move { .. }, Ok(_)) => before.moves.len() + 1,
+        (Action::AcceptTakeback(_), Ok(_)) => before.moves.len() - 2,
+        _ => before.moves.len(),
+    };
+    if after.moves.len() != expected_plies {
+        return Err(format!("{} moves after the step, expected {}", after.moves.len(), expected_plies));
+    }
+    if let (Action::Move { mover, lag_ms, .. }, Ok(_)) = (action, outcome) {
+        let elapsed_ms = before.last_move_at.map_or(0, |last| now_ms.saturating_sub(last));
+        let (remaining_ms, charged_ms) = match mover {
+            PieceColor::White => (before.white_remaining_ms, after.white_remaining_ms),
+            PieceColor::Black => (before.black_remaining_ms, after.black_remaining_ms),
+        };
+        if elapsed_ms > remaining_ms + lag_ms {
+            return Err(format!("move accepted {}ms after the flag fell", elapsed_ms - remaining_ms));
+        }
+        let expected_ms = remaining_ms.saturating_sub(elapsed_ms) + after.increment_ms;
+        if charged_ms != expected_ms {
+            return Err(format!("{:?} has {}ms after the move, expected {}ms", mover, charged_ms, expected_ms));
+        }
+    }
+    if let ServerMessage::ClockUpdate { white_remaining_ms, black_remaining_ms, .. } = after.clock_update(now_ms, None) {
+        if white_remaining_ms > after.white_remaining_ms || black_remaining_ms > after.black_remaining_ms {
+            return Err("a running clock counted up".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Play the game scripted by `seed` in a room of its own, checking the room
+// after every step
+pub fn run_game(seed: u64, config: &SimConfig) -> Result<SimReport, Violation> {
+    // Starting at the process's time, simulated rooms look like any other
+    // to the sweeps over every room
+    let time = Arc::new(ManualTime::new(chess::time_source::now_ms()));
+    with_time_source(time.clone(), || {
+        let room_id = game::create_room_with_time(config.initial_time_ms, config.increment_ms);
+        let result = play(seed, config, &room_id, &time);
+        game::remove_room(&room_id);
+        result
+    })
+}
+
+// Play every game of `seeds`, stopping at the first violation
+pub fn run_games(seeds: std::ops::Range<u64>, config: &SimConfig) -> Result<Vec<SimReport>, Violation> {
+    seeds.map(|seed| run_game(seed, config)).collect()
+}
+
+fn play(seed: u64, config: &SimConfig, room_id: &RoomId, time: &ManualTime) -> Result<SimReport, Violation> {
+    let violation = |step: usize, action: String, message: String| Violation { seed, step, action, message };
+    for color in [PieceColor::White, PieceColor::Black] {
+        game::join_room(room_id, &player(&color), None).map_err(|e| violation(0, "join".to_string(), e))?;
+    }
+
+    let mut rng = Rng(seed);
+    // Each step plays at most one move; the rest leave room for the others
+    let max_steps = config.max_plies * 3;
+    let mut steps = 0;
+    // Steps taken after the game ended, which must all be refused
+    let mut steps_after_end = 0;
+    let mut before = snapshot(room_id).ok_or_else(|| violation(0, "join".to_string(), "room vanished".to_string()))?;
+    while steps < max_steps && before.moves.len() < config.max_plies && steps_after_end < STEPS_AFTER_END {
+        if is_finished(&status(&before)) {
+            steps_after_end += 1;
+        }
+        steps += 1;
+        let action = next_action(&mut rng, &before, config);
+        let outcome = perform(room_id, &action, time);
+        let after = snapshot(room_id)
+            .ok_or_else(|| violation(steps, format!("{:?}", action), "room vanished".to_string()))?;
+        check(&before, &after, &action, &outcome, time.now_ms())
+            .map_err(|message| violation(steps, format!("{:?}", action), message))?;
+        before = after;
+    }
+
+    Ok(SimReport { seed, steps, plies: before.moves.len(), status: status(&before) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_games_keep_the_invariants() {
+        let config = SimConfig { max_plies: 60, ..SimConfig::default() };
+        let reports = run_games(0..1_000, &config).unwrap_or_else(|violation| panic!("{}", violation));
+
+        // The scripts reach every way a game ends here
+        let ended = |status: GameStatus| reports.iter().any(|report| report.status == status);
+        assert!(ended(GameStatus::Timeout));
+        assert!(ended(GameStatus::Draw));
+        assert!(ended(GameStatus::InProgress));
+    }
+
+    #[test]
+    fn test_a_seed_replays_the_same_game() {
+        let config = SimConfig { max_plies: 60, ..SimConfig::default() };
+        let first = run_game(42, &config).unwrap();
+        let again = run_game(42, &config).unwrap();
+        assert_eq!((first.steps, first.plies, first.status), (again.steps, again.plies, again.status));
+    }
+
+    #[test]
+    fn test_blitz_games_keep_the_invariants() {
+        let config = SimConfig { initial_time_ms: 3_000, increment_ms: 0, max_plies: 80, max_think_ms: 1_500 };
+        run_games(10_000..11_000, &config).unwrap_or_else(|violation| panic!("{}", violation));
+    }
+}
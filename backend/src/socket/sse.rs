@@ -0,0 +1,519 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::chat::{self, ChatReceiver};
+use crate::game::{self, GAME_STATE};
+use crate::hall::{self, HallReceiver};
+use crate::latency::now_ms;
+use crate::models::{RoomId, ServerMessage};
+use crate::ongoing::OngoingRequest;
+use crate::websocket::RoomReceiver;
+
+// Largest request head looked at before handing a connection over
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+// Time a client has to send its request head
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Events a stream keeps for clients that reconnect with Last-Event-ID
+pub const REPLAY_EVENTS: usize = 200;
+
+// How long a stream nobody reads is kept for clients to resume
+const IDLE_STREAM_MS: u64 = 60_000;
+
+// How often relays look for new messages, as the WebSocket loop does
+const RELAY_INTERVAL: Duration = Duration::from_millis(10);
+
+// Comments sent on quiet streams so that proxies keep them open
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+// What a Server-Sent Events stream follows: one game, or the boards and
+// chat of one tournament
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamTarget {
+    Game(RoomId),
+    Tournament(Uuid),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamRequest {
+    pub target: StreamTarget,
+    // Id of the last event the client got, when it reconnects
+    pub last_event_id: Option<String>,
+    // Bytes of the request head, read off the connection before streaming
+    head_len: usize,
+}
+
+// How a new connection is served, from its request head
+#[derive(Debug, PartialEq, Eq)]
+pub enum Route {
+    WebSocket,
+    Stream(StreamRequest),
+    Ongoing(OngoingRequest),
+    NotFound,
+}
+
+// WebSocket handshakes go to the socket layer; plain GET requests for
+// `/v1/games/{id}/stream` or `/v1/tournaments/{id}/stream` get an event
+// stream and `/v1/games/ongoing` the list of games to watch; anything else
+// is not found
+pub fn route(head: &str) -> Route {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let headers: Vec<(String, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+    let header = |name: &str| headers.iter().find(|(n, _)| n == name).map(|(_, value)| *value);
+
+    if header("upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket")) {
+        return Route::WebSocket;
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(uri)) = (parts.next(), parts.next()) else {
+        return Route::NotFound;
+    };
+    let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if segments == ["v1", "games", "ongoing"] {
+        return Route::Ongoing(OngoingRequest { query: query.to_string(), head_len: head.len() });
+    }
+    let target = match segments.as_slice() {
+        ["v1", "games", id, "stream"] => id.parse().ok().map(StreamTarget::Game),
+        ["v1", "tournaments", id, "stream"] => Uuid::parse_str(id).ok().map(StreamTarget::Tournament),
+        _ => None,
+    };
+    match target {
+        Some(target) => Route::Stream(StreamRequest {
+            target,
+            last_event_id: header("last-event-id").filter(|id| !id.is_empty()).map(str::to_string),
+            head_len: head.len(),
+        }),
+        None => Route::NotFound,
+    }
+}
+
+// Decide how to serve a connection without consuming its request, so that a
+// WebSocket handshake still reads all of it. Heads that cannot be read are
+// left to the handshake to reject.
+pub async fn route_connection(stream: &TcpStream) -> Route {
+    match tokio::time::timeout(HEAD_TIMEOUT, peek_head(stream)).await {
+        Ok(Some(head)) => route(&head),
+        _ => Route::WebSocket,
+    }
+}
+
+async fn peek_head(stream: &TcpStream) -> Option<String> {
+    let mut buf = vec![0; MAX_HEAD_BYTES];
+    loop {
+        let n = stream.peek(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        if let Some(end) = buf[..n].windows(4).position(|window| window == b"\r\n\r\n") {
+            return std::str::from_utf8(&buf[..end + 4]).ok().map(str::to_string);
+        }
+        if n == buf.len() {
+            return None;
+        }
+        // Peeking returns what has arrived so far without waiting for more
+        tokio::time::sleep(RELAY_INTERVAL).await;
+    }
+}
+
+// One stream's events, numbered in the order they were relayed. Ids carry
+// the time the feed started, so ids from an earlier feed of the same target
+// are never mistaken for this one's.
+struct Feed {
+    epoch: u64,
+    last_seq: u64,
+    recent: VecDeque<(u64, ServerMessage)>,
+    sender: broadcast::Sender<(String, ServerMessage)>,
+    // Since when nobody has been reading the stream
+    idle_since_ms: Option<u64>,
+}
+
+impl Feed {
+    fn new(epoch: u64) -> Self {
+        let (sender, _) = broadcast::channel(REPLAY_EVENTS);
+        Self { epoch, last_seq: 0, recent: VecDeque::new(), sender, idle_since_ms: None }
+    }
+
+    fn event_id(&self, seq: u64) -> String {
+        format!("{}-{}", self.epoch, seq)
+    }
+
+    fn record(&mut self, message: ServerMessage) {
+        self.last_seq += 1;
+        self.recent.push_back((self.last_seq, message.clone()));
+        if self.recent.len() > REPLAY_EVENTS {
+            self.recent.pop_front();
+        }
+        // Streams nobody reads right now have no receivers; that is fine
+        let _ = self.sender.send((self.event_id(self.last_seq), message));
+    }
+
+    // The events after `last_event_id`, if the feed still holds all of them
+    fn events_after(&self, last_event_id: &str) -> Option<Vec<(String, ServerMessage)>> {
+        let (epoch, seq) = last_event_id.split_once('-')?;
+        let (epoch, seq): (u64, u64) = (epoch.parse().ok()?, seq.parse().ok()?);
+        let first_seq = self.recent.front().map_or(self.last_seq + 1, |(seq, _)| *seq);
+        if epoch != self.epoch || seq > self.last_seq || seq + 1 < first_seq {
+            return None;
+        }
+        Some(
+            self.recent
+                .iter()
+                .filter(|(event_seq, _)| *event_seq > seq)
+                .map(|(event_seq, message)| (self.event_id(*event_seq), message.clone()))
+                .collect(),
+        )
+    }
+}
+
+lazy_static::lazy_static! {
+    // Taken before the game state lock when both are needed; relays never
+    // hold both
+    static ref FEEDS: Mutex<HashMap<StreamTarget, Feed>> = Mutex::new(HashMap::new());
+}
+
+// What a stream sends first, and its events from then on
+struct Subscription {
+    backlog: Vec<(String, ServerMessage)>,
+    receiver: broadcast::Receiver<(String, ServerMessage)>,
+}
+
+// Follow a target, starting its relay if none runs. A client resuming from
+// an event the feed still holds gets the events after it; any other gets the
+// target as it stands, tagged with the id of the latest event. Events relayed
+// right after a snapshot may already be part of it: room messages carry the
+// state they lead to, hall updates and chat lines their numbers.
+fn subscribe(target: StreamTarget, last_event_id: Option<&str>) -> Option<Subscription> {
+    let mut feeds = FEEDS.lock().unwrap();
+    let feed = match feeds.entry(target) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            if !target_exists(target) {
+                return None;
+            }
+            // Subscribed here, so that nothing sent from now on is missed
+            tokio::spawn(relay(target, Sources::new(target)?));
+            entry.insert(Feed::new(now_ms()))
+        }
+    };
+    feed.idle_since_ms = None;
+    let receiver = feed.sender.subscribe();
+
+    if let Some(events) = last_event_id.and_then(|id| feed.events_after(id)) {
+        return Some(Subscription { backlog: events, receiver });
+    }
+    let id = feed.event_id(feed.last_seq);
+    let state = GAME_STATE.lock().unwrap();
+    let snapshot = match target {
+        StreamTarget::Game(room_id) => game::room_snapshot(&state, &room_id, 0).ok()?,
+        StreamTarget::Tournament(tournament_id) => chat::chat_snapshot(&state, tournament_id)
+            .into_iter()
+            .chain(hall::tournament_snapshots(&state, tournament_id))
+            .collect(),
+    };
+    Some(Subscription {
+        backlog: snapshot.into_iter().map(|message| (id.clone(), message)).collect(),
+        receiver,
+    })
+}
+
+fn target_exists(target: StreamTarget) -> bool {
+    let state = GAME_STATE.lock().unwrap();
+    match target {
+        StreamTarget::Game(room_id) => state.rooms.contains_key(&room_id),
+        StreamTarget::Tournament(tournament_id) => {
+            state.chats.contains_key(&tournament_id) || state.halls.keys().any(|(id, _)| *id == tournament_id)
+        }
+    }
+}
+
+// The socket layer's receivers a relay reads from
+enum Sources {
+    Game(RoomReceiver),
+    Tournament {
+        tournament_id: Uuid,
+        chat: Option<ChatReceiver>,
+        halls: Vec<HallReceiver>,
+    },
+}
+
+impl Sources {
+    fn new(target: StreamTarget) -> Option<Self> {
+        match target {
+            StreamTarget::Game(room_id) => {
+                game::get_room_sender(&room_id).map(|sender| Sources::Game(RoomReceiver::new(room_id, &sender)))
+            }
+            StreamTarget::Tournament(tournament_id) => {
+                Some(Sources::Tournament { tournament_id, chat: None, halls: Vec::new() })
+            }
+        }
+    }
+
+    // Every message waiting. A tournament's chat and rounds are followed
+    // from when they open, starting with their snapshot.
+    fn drain(&mut self) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        match self {
+            Sources::Game(receiver) => drain_into(&mut messages, || receiver.try_next()),
+            Sources::Tournament { tournament_id, chat, halls } => {
+                if chat.is_none() {
+                    if let Ok(snapshot @ ServerMessage::TournamentChatJoined { last_id, .. }) = chat::join_chat(*tournament_id) {
+                        *chat = Some(ChatReceiver::new(*tournament_id, last_id));
+                        messages.push(snapshot);
+                    }
+                }
+                let mut opened: Vec<u32> = GAME_STATE
+                    .lock()
+                    .unwrap()
+                    .halls
+                    .keys()
+                    .filter(|(id, round)| id == tournament_id && !halls.iter().any(|hall| hall.round == *round))
+                    .map(|(_, round)| *round)
+                    .collect();
+                opened.sort_unstable();
+                for round in opened {
+                    let snapshot = hall::watch_hall(*tournament_id, round);
+                    if let ServerMessage::HallSnapshot { seq, .. } = snapshot {
+                        halls.push(HallReceiver::new(*tournament_id, round, seq));
+                    }
+                    messages.push(snapshot);
+                }
+
+                if let Some(chat) = chat {
+                    drain_into(&mut messages, || chat.try_next());
+                }
+                for hall in halls.iter_mut() {
+                    drain_into(&mut messages, || hall.try_next());
+                }
+            }
+        }
+        messages
+    }
+}
+
+fn drain_into(messages: &mut Vec<ServerMessage>, mut next: impl FnMut() -> Vec<ServerMessage>) {
+    loop {
+        let batch = next();
+        if batch.is_empty() {
+            return;
+        }
+        messages.extend(batch);
+    }
+}
+
+// Number the target's messages into its feed until the target is gone or
+// nobody has read the stream for a while
+async fn relay(target: StreamTarget, mut sources: Sources) {
+    let mut ticker = tokio::time::interval(RELAY_INTERVAL);
+    loop {
+        ticker.tick().await;
+        // Checked first, so that what was sent before the target went away
+        // is still relayed
+        let exists = target_exists(target);
+        let messages = sources.drain();
+
+        let mut feeds = FEEDS.lock().unwrap();
+        let Some(feed) = feeds.get_mut(&target) else { return };
+        for message in messages {
+            feed.record(message);
+        }
+        let now = now_ms();
+        if feed.sender.receiver_count() > 0 {
+            feed.idle_since_ms = None;
+        } else {
+            feed.idle_since_ms.get_or_insert(now);
+        }
+        let idle = feed.idle_since_ms.is_some_and(|since| now.saturating_sub(since) >= IDLE_STREAM_MS);
+        if !exists || idle {
+            // Dropping the feed ends the streams still reading it
+            feeds.remove(&target);
+            log::info!("Closed the event stream of {:?}", target);
+            return;
+        }
+    }
+}
+
+fn event(id: &str, message: &ServerMessage) -> Option<String> {
+    serde_json::to_string(message)
+        .ok()
+        .map(|json| format!("id: {}\ndata: {}\n\n", id, json))
+}
+
+// Serve a Server-Sent Events stream of the same messages WebSocket clients
+// get, for clients that cannot hold a WebSocket
+pub async fn handle_stream(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    request: StreamRequest,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut head = vec![0; request.head_len];
+    stream.read_exact(&mut head).await?;
+
+    let Some(Subscription { backlog, mut receiver }) = subscribe(request.target, request.last_event_id.as_deref()) else {
+        return not_found(stream).await;
+    };
+    log::info!("Event stream of {:?} opened by {}", request.target, addr);
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\n\
+              Content-Type: text/event-stream\r\n\
+              Cache-Control: no-cache\r\n\
+              Connection: keep-alive\r\n\
+              Access-Control-Allow-Origin: *\r\n\
+              X-Accel-Buffering: no\r\n\r\n",
+        )
+        .await?;
+    for (id, message) in &backlog {
+        if let Some(event) = event(id, message) {
+            stream.write_all(event.as_bytes()).await?;
+        }
+    }
+
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+    keep_alive.tick().await;
+    loop {
+        tokio::select! {
+            next = receiver.recv() => match next {
+                Ok((id, message)) => {
+                    if let Some(event) = event(&id, &message) {
+                        stream.write_all(event.as_bytes()).await?;
+                    }
+                }
+                // Too far behind: start again from the target as it stands
+                Err(RecvError::Lagged(missed)) => {
+                    log::warn!("An event stream of {:?} missed {} events", request.target, missed);
+                    let Some(resubscribed) = subscribe(request.target, None) else { break };
+                    receiver = resubscribed.receiver;
+                    for (id, message) in &resubscribed.backlog {
+                        if let Some(event) = event(id, message) {
+                            stream.write_all(event.as_bytes()).await?;
+                        }
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = keep_alive.tick() => {
+                stream.write_all(b": keep-alive\n\n").await?;
+            }
+        }
+    }
+
+    log::info!("Event stream of {:?} for {} ended", request.target, addr);
+    Ok(())
+}
+
+pub async fn not_found(mut stream: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    stream
+        .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{create_room_with_time, join_room};
+    use crate::models::SessionPlayerId;
+
+    fn request(path: &str, extra: &str) -> String {
+        format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", path, extra)
+    }
+
+    #[test]
+    fn test_routes_streams_and_handshakes() {
+        let room_id = RoomId::new();
+        let head = request(&format!("/v1/games/{}/stream", room_id), "Last-Event-ID: 17-3\r\n");
+        assert_eq!(
+            route(&head),
+            Route::Stream(StreamRequest {
+                target: StreamTarget::Game(room_id),
+                last_event_id: Some("17-3".to_string()),
+                head_len: head.len(),
+            })
+        );
+
+        let tournament_id = Uuid::new_v4();
+        match route(&request(&format!("/v1/tournaments/{}/stream?x=1", tournament_id), "")) {
+            Route::Stream(request) => {
+                assert_eq!(request.target, StreamTarget::Tournament(tournament_id));
+                assert_eq!(request.last_event_id, None);
+            }
+            other => panic!("expected a stream, got {:?}", other),
+        }
+
+        let upgrade = request(&format!("/v1/games/{}/stream", room_id), "Connection: Upgrade\r\nUpgrade: websocket\r\n");
+        assert_eq!(route(&upgrade), Route::WebSocket);
+        assert_eq!(
+            route(&request("/v1/games/ongoing?time_control=blitz", "")),
+            Route::Ongoing(OngoingRequest {
+                query: "time_control=blitz".to_string(),
+                head_len: request("/v1/games/ongoing?time_control=blitz", "").len(),
+            })
+        );
+        assert_eq!(route(&request("/v1/games/not-a-uuid/stream", "")), Route::NotFound);
+        assert_eq!(route(&request("/", "")), Route::NotFound);
+    }
+
+    #[test]
+    fn test_feed_replays_only_what_it_still_holds() {
+        let mut feed = Feed::new(42);
+        let tournament_id = Uuid::new_v4();
+        for _ in 0..REPLAY_EVENTS + 5 {
+            feed.record(ServerMessage::TournamentChatLeft { tournament_id });
+        }
+
+        let events = feed.events_after(&format!("42-{}", REPLAY_EVENTS + 2)).unwrap();
+        assert_eq!(
+            events.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(),
+            [format!("42-{}", REPLAY_EVENTS + 3), format!("42-{}", REPLAY_EVENTS + 4), format!("42-{}", REPLAY_EVENTS + 5)]
+        );
+        assert!(feed.events_after(&format!("42-{}", REPLAY_EVENTS + 5)).unwrap().is_empty());
+
+        // Dropped from the buffer, from another feed, ahead of this one or garbled
+        assert!(feed.events_after("42-1").is_none());
+        assert!(feed.events_after("41-300").is_none());
+        assert!(feed.events_after(&format!("42-{}", REPLAY_EVENTS + 6)).is_none());
+        assert!(feed.events_after("latest").is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_resumes_after_last_event() {
+        let room_id = create_room_with_time(60_000, 0);
+        let white = SessionPlayerId::try_from("sse-white".to_string()).unwrap();
+        join_room(&room_id, &white, Some("White".to_string())).unwrap();
+
+        let target = StreamTarget::Game(room_id);
+        let first = subscribe(target, None).unwrap();
+        assert!(matches!(first.backlog[0].1, ServerMessage::Resync { .. }));
+        let start_id = first.backlog[0].0.clone();
+
+        let sender = game::get_room_sender(&room_id).unwrap();
+        sender.send(ServerMessage::PlayerLeft { room_id, player_id: white.clone() }).unwrap();
+        // The clock is paused, so this only lets a relay step number the
+        // message into the feed
+        tokio::time::sleep(RELAY_INTERVAL).await;
+
+        let resumed = subscribe(target, Some(&start_id)).unwrap();
+        assert_eq!(resumed.backlog.len(), 1);
+        assert!(matches!(resumed.backlog[0].1, ServerMessage::PlayerLeft { .. }));
+        assert!(subscribe(StreamTarget::Game(RoomId::new()), None).is_none());
+
+        game::remove_room(&room_id);
+    }
+}
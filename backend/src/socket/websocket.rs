@@ -2,10 +2,47 @@ use futures_util::{SinkExt, StreamExt};
 use std::net::SocketAddr;
 use tokio::net::TcpStream;
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
 use tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream};
+use tracing::Instrument;
 
+use crate::chat::ChatReceiver;
+use crate::dispatch::Session;
+use crate::game;
+use crate::hall::HallReceiver;
 use crate::handlers::handle_client_message;
-use crate::models::ServerMessage;
+use crate::latency::{now_ms, ping_payload, rtt_from_pong, LatencyEstimator, PING_INTERVAL};
+use crate::models::{RoomId, ServerMessage, SessionPlayerId};
+
+// One room's broadcasts as seen by a connection. A connection that falls
+// behind gets a resync of the room instead of a gap in its messages.
+pub struct RoomReceiver {
+    room_id: RoomId,
+    receiver: broadcast::Receiver<ServerMessage>,
+}
+
+impl RoomReceiver {
+    pub fn new(room_id: RoomId, sender: &broadcast::Sender<ServerMessage>) -> Self {
+        RoomReceiver { room_id, receiver: sender.subscribe() }
+    }
+
+    // Messages to forward right now, if any
+    pub fn try_next(&mut self) -> Vec<ServerMessage> {
+        match self.receiver.try_recv() {
+            Ok(msg) => vec![msg],
+            Err(TryRecvError::Lagged(missed)) => {
+                match game::resync(&self.room_id, &mut self.receiver, missed) {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        log::warn!("Could not resync room {}: {}", self.room_id, e);
+                        Vec::new()
+                    }
+                }
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+}
 
 // Handle a WebSocket connection
 pub async fn handle_connection(
@@ -20,8 +57,17 @@ pub async fn handle_connection(
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
     // Keep track of room subscriptions
-    let mut room_senders: Vec<(String, broadcast::Sender<ServerMessage>)> = Vec::new();
-    let mut room_receivers = Vec::new();
+    let mut room_senders: Vec<(RoomId, SessionPlayerId, broadcast::Sender<ServerMessage>)> = Vec::new();
+    let mut room_receivers: Vec<RoomReceiver> = Vec::new();
+    let mut hall_receivers: Vec<HallReceiver> = Vec::new();
+    let mut chat_receivers: Vec<ChatReceiver> = Vec::new();
+
+    // The player this connection acts for, fixed by its first message
+    let mut session = Session::new();
+
+    // Round-trip estimate from our pings, used for lag compensation
+    let mut latency = LatencyEstimator::new();
+    let mut ping_timer = tokio::time::interval(PING_INTERVAL);
 
     // Main connection loop
     loop {
@@ -32,7 +78,11 @@ pub async fn handle_connection(
                     Some(Ok(msg)) => {
                         match msg {
                             Message::Text(text) => {
-                                if let Err(e) = handle_client_message(&text, &mut ws_sender, &mut room_senders).await {
+                                // Everything done for the message carries its request id
+                                let request_id = telemetry::new_request_id();
+                                let span = tracing::info_span!("message", %request_id, room_id = tracing::field::Empty);
+                                let handled = handle_client_message(&text, &mut ws_sender, &mut session, &mut room_senders, &mut hall_receivers, &mut chat_receivers, &latency).instrument(span);
+                                if let Err(e) = telemetry::with_request_id(request_id, handled).await {
                                     log::error!("Error handling client message: {}", e);
                                     break;
                                 }
@@ -47,6 +97,20 @@ pub async fn handle_connection(
                                     break;
                                 }
                             }
+                            Message::Pong(data) => {
+                                if let Some(rtt_ms) = rtt_from_pong(&data, now_ms()) {
+                                    latency.record(rtt_ms);
+                                    // Let opponents see how laggy this player is
+                                    for (room_id, player_id, sender) in &room_senders {
+                                        let _ = sender.send(ServerMessage::PlayerLag {
+                                            room_id: *room_id,
+                                            player_id: player_id.clone(),
+                                            rtt_ms: latency.rtt_ms().unwrap_or(rtt_ms),
+                                            compensation_ms: latency.lag_compensation_ms(),
+                                        });
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -61,19 +125,27 @@ pub async fn handle_connection(
                 }
             }
 
+            // Measure the round trip
+            _ = ping_timer.tick() => {
+                if let Err(e) = ws_sender.send(Message::Ping(ping_payload(now_ms()))).await {
+                    log::error!("Error sending ping: {}", e);
+                    break;
+                }
+            }
+
             // Handle room broadcasts
             _ = async {
                // Rebuild receivers when room_senders changes
 if room_receivers.len() != room_senders.len() {
         room_receivers.clear();
-        for (_, sender) in &room_senders {
-            room_receivers.push(sender.subscribe());
+        for (room_id, _, sender) in &room_senders {
+            room_receivers.push(RoomReceiver::new(*room_id, sender));
         }
     }
 
                 // Check for messages from each room
                 for (i, receiver) in room_receivers.iter_mut().enumerate() {
-                    if let Ok(msg) = receiver.try_recv() {
+                    for msg in receiver.try_next() {
                         if let Ok(json) = serde_json::to_string(&msg) {
                             if let Err(e) = ws_sender.send(Message::Text(json)).await {
                                 log::error!("Error forwarding room message: {}", e);
@@ -83,6 +155,30 @@ if room_receivers.len() != room_senders.len() {
                     }
                 }
 
+                // And from each hall being watched
+                for receiver in hall_receivers.iter_mut() {
+                    for msg in receiver.try_next() {
+                        if let Ok(json) = serde_json::to_string(&msg) {
+                            if let Err(e) = ws_sender.send(Message::Text(json)).await {
+                                log::error!("Error forwarding hall message: {}", e);
+                                return;
+                            }
+                        }
+                    }
+                }
+
+                // And from each tournament chat being followed
+                for receiver in chat_receivers.iter_mut() {
+                    for msg in receiver.try_next() {
+                        if let Ok(json) = serde_json::to_string(&msg) {
+                            if let Err(e) = ws_sender.send(Message::Text(json)).await {
+                                log::error!("Error forwarding chat message: {}", e);
+                                return;
+                            }
+                        }
+                    }
+                }
+
                 // Sleep a bit to avoid busy waiting
                 tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
                 